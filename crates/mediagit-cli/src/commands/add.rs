@@ -15,12 +15,15 @@
 //!
 //! The `add` command stages changes to files for inclusion in the next commit.
 
-use super::super::progress::ProgressTracker;
-use super::super::repo::{create_storage_backend, find_repo_root};
+use super::super::progress::{CategoryStats, CompressionStats, ProgressTracker};
+use super::super::repo::{
+    brotli_settings, compression_tier, create_storage_backend, find_repo_root,
+};
 use anyhow::{Context, Result};
 use clap::Parser;
 use mediagit_versioning::{
-    ChunkStrategy, Commit, Index, IndexEntry, ObjectDatabase, ObjectType, Oid, RefDatabase, Tree,
+    ChunkStrategy, Commit, CompressionAttributes, Index, IndexEntry, ObjectDatabase, ObjectType,
+    Oid, RefDatabase, TextAttributes, Tree,
 };
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -104,6 +107,14 @@ pub struct AddCmd {
     pub jobs: Option<usize>,
 }
 
+/// How file content is transformed and stored while staging
+struct StagingOptions {
+    /// Line-ending rules for text assets (.mediagitattributes)
+    attributes: TextAttributes,
+    /// Whether small files may be stored as deltas against similar objects
+    delta_enabled: bool,
+}
+
 /// Result from processing a single file in parallel
 struct FileResult {
     relative_path: PathBuf,
@@ -142,6 +153,9 @@ impl AddCmd {
         let storage_path = repo_root.join(".mediagit");
         let storage = create_storage_backend(&repo_root).await?;
 
+        let config = mediagit_config::Config::load(&repo_root)
+            .await
+            .unwrap_or_default();
         let delta_enabled = !self.no_delta;
 
         let odb = ObjectDatabase::with_optimizations(
@@ -149,7 +163,12 @@ impl AddCmd {
             1000,
             Some(ChunkStrategy::MediaAware),
             delta_enabled,
-        );
+        )
+        .with_min_compress_size(config.compression.min_size as usize)
+        .with_dictionary_compression(config.compression.dictionary)
+        .with_compression_tier(compression_tier(&config))
+        .with_brotli(brotli_settings(&config))
+        .with_compression_attributes(CompressionAttributes::load(&repo_root)?);
 
         if !self.quiet && self.verbose {
             output::info("Auto-chunking enabled for large files");
@@ -158,6 +177,11 @@ impl AddCmd {
         // Load the index
         let mut index = Index::load(&repo_root)?;
 
+        let options = Arc::new(StagingOptions {
+            attributes: TextAttributes::load(&repo_root)?,
+            delta_enabled,
+        });
+
         // Build index lookup for stat-cache change detection (size + mtime)
         let index_files: Arc<HashMap<PathBuf, (u64, Option<u64>)>> = {
             let mut map = HashMap::new();
@@ -173,13 +197,9 @@ impl AddCmd {
             let mut files = HashMap::new();
             if let Ok(head_oid) = refdb.resolve("HEAD").await {
                 if let Ok(commit_data) = odb.read(&head_oid).await {
-                    if let Ok(commit) =
-                        mediagit_versioning::format::deserialize::<Commit>(&commit_data)
-                    {
+                    if let Ok(commit) = Commit::deserialize(&commit_data) {
                         if let Ok(tree_data) = odb.read(&commit.tree).await {
-                            if let Ok(tree) =
-                                mediagit_versioning::format::deserialize::<Tree>(&tree_data)
-                            {
+                            if let Ok(tree) = Tree::deserialize(&tree_data) {
                                 for entry in tree.iter() {
                                     files.insert(PathBuf::from(&entry.name), entry.oid);
                                 }
@@ -250,6 +270,7 @@ impl AddCmd {
                     let progress_files = progress_files.clone();
                     let progress_bar = progress_bar.clone();
                     let skipped = skipped.clone();
+                    let options = options.clone();
 
                     file_tasks.spawn(async move {
                         let _permit = sem
@@ -278,7 +299,7 @@ impl AddCmd {
                             &odb,
                             &head_files,
                             &index_files,
-                            delta_enabled,
+                            &options,
                             on_bytes,
                         )
                         .await;
@@ -361,7 +382,7 @@ impl AddCmd {
                         &odb,
                         &head_files,
                         &index_files,
-                        delta_enabled,
+                        &options,
                         on_bytes_seq.clone(),
                     )
                     .await;
@@ -457,6 +478,14 @@ impl AddCmd {
         // Save the index
         if !self.dry_run {
             index.save(&repo_root).context("Failed to save index")?;
+
+            let metrics = odb.metrics().await;
+            if let Err(e) = CategoryStats::record(&storage_path, &metrics) {
+                tracing::warn!("Failed to save category stats: {}", e);
+            }
+            if let Err(e) = CompressionStats::record(&storage_path, &metrics) {
+                tracing::warn!("Failed to save compression stats: {}", e);
+            }
         }
 
         if !self.quiet {
@@ -495,7 +524,7 @@ impl AddCmd {
         odb: &ObjectDatabase,
         head_files: &HashMap<PathBuf, Oid>,
         index_files: &HashMap<PathBuf, (u64, Option<u64>)>,
-        delta_enabled: bool,
+        options: &StagingOptions,
         on_bytes: Option<Arc<dyn Fn(u64) + Send + Sync>>,
     ) -> Result<(Option<FileResult>, u64)> {
         let metadata = tokio::fs::metadata(file_path).await.context(format!(
@@ -538,13 +567,15 @@ impl AddCmd {
             }
         }
 
-        let filename = file_path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        // Repo-relative, so `.mediagitattributes` compression rules can match
+        // directories; type detection only looks at the extension
+        let filename = relative_path.to_str().unwrap_or("");
 
         // Seed similarity detector from previous version (manifest for chunked, blob for small)
         if let Some(head_oid) = head_files.get(&relative_path) {
             if let Ok(Some(old_manifest)) = odb.get_chunk_manifest(head_oid).await {
                 let _ = odb.seed_similarity_from_manifest(&old_manifest).await;
-            } else if delta_enabled {
+            } else if options.delta_enabled {
                 // Non-chunked file: seed from the previous full blob so write_with_delta
                 // can find a similar base across invocations.  We seed regardless of
                 // whether the current file will ultimately use delta, because we haven't
@@ -553,8 +584,11 @@ impl AddCmd {
             }
         }
 
-        // Choose streaming vs in-memory based on file size
-        let (_content_oid, oid) = if file_size >= STREAMING_THRESHOLD {
+        // Choose streaming vs in-memory based on file size. Text paths always take
+        // the in-memory path so their line endings can be normalized before hashing.
+        let (_content_oid, oid) = if file_size >= STREAMING_THRESHOLD
+            && !options.attributes.may_convert(&relative_path, file_path)?
+        {
             // STREAMING PATH: Files >= 5MB — format-aware chunking via mmap (parallel internally)
             let content_oid = Oid::from_file_async(file_path)
                 .await
@@ -578,10 +612,13 @@ impl AddCmd {
             (content_oid, oid)
         } else {
             // IN-MEMORY PATH: Files < 5MB — read fully into memory, then hash/delta/write
-            let content = tokio::fs::read(file_path)
+            let raw_content = tokio::fs::read(file_path)
                 .await
                 .context(format!("Failed to read file: {}", file_path.display()))?;
 
+            // Normalize CRLF to LF for paths marked as text
+            let content = options.attributes.clean(&relative_path, &raw_content);
+
             let content_oid = Oid::hash(&content);
 
             // Check if unchanged from HEAD
@@ -599,7 +636,7 @@ impl AddCmd {
                 odb.write_chunked_parallel(ObjectType::Blob, &content, filename)
                     .await
                     .context("Failed to write chunked object")?
-            } else if delta_enabled && Self::should_use_delta(filename, &content) {
+            } else if options.delta_enabled && Self::should_use_delta(filename, &content) {
                 odb.write_with_delta(ObjectType::Blob, &content, filename)
                     .await
                     .context("Failed to write object with delta")?
@@ -0,0 +1,400 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Apply unified diff patches to the working tree or the index.
+//!
+//! Every file in the patch is checked before anything is written, so a patch
+//! either applies completely or leaves the repository untouched. With
+//! `--3way`, files whose context no longer matches are merged against the
+//! preimage blob named on the patch's `index` line.
+
+use crate::output;
+use crate::repo::{create_storage_backend, find_repo_root};
+use anyhow::{Context, Result};
+use clap::Parser;
+use mediagit_versioning::{
+    merge_text, parse_patch, resolve_revision, ApplyOutcome, Commit, FilePatch, Index, IndexEntry,
+    ObjectDatabase, ObjectType, Oid, RefDatabase, Tree,
+};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Apply a patch to files
+///
+/// Reads a unified diff (as produced by 'mediagit diff --patch', 'git diff'
+/// or 'diff -u') and applies it to the working tree, or to the index with
+/// --cached. Binary patches are not supported; copy the file instead.
+#[derive(Parser, Debug)]
+#[command(after_help = "EXAMPLES:
+    # Apply a patch to the working tree
+    mediagit apply change.patch
+
+    # Check whether a patch applies without touching any files
+    mediagit apply --check change.patch
+
+    # Stage the patched content without changing the working tree
+    mediagit apply --cached change.patch
+
+    # Merge with local edits when the context has drifted
+    mediagit apply --3way change.patch
+
+    # Read the patch from stdin
+    mediagit diff -p HEAD~1 HEAD | mediagit apply
+
+SEE ALSO:
+    mediagit-diff(1), mediagit-add(1)")]
+pub struct ApplyCmd {
+    /// Patch files to apply (reads stdin when omitted or '-')
+    #[arg(value_name = "PATCH")]
+    pub patches: Vec<PathBuf>,
+
+    /// Apply the patch to the index instead of the working tree
+    #[arg(long)]
+    pub cached: bool,
+
+    /// Only check that the patch applies; do not change anything
+    #[arg(long)]
+    pub check: bool,
+
+    /// Fall back to a three-way merge when a hunk's context does not match
+    #[arg(short = '3', long = "3way")]
+    pub three_way: bool,
+
+    /// Quiet mode
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+/// The result of applying one file's patch, before it is written
+struct PlannedChange {
+    path: String,
+    /// New content, or None to delete the file
+    content: Option<Vec<u8>>,
+    /// Mode named in the patch header, if any
+    mode: Option<u32>,
+    /// Conflict regions left by a three-way merge
+    conflicts: usize,
+}
+
+impl ApplyCmd {
+    pub async fn execute(&self) -> Result<()> {
+        let repo_root = find_repo_root()?;
+
+        let data = self.read_patches()?;
+        let patches = parse_patch(&data).context("Failed to parse patch")?;
+        if patches.is_empty() {
+            anyhow::bail!("No file changes found in patch");
+        }
+        if let Some(patch) = patches.iter().find(|p| p.binary) {
+            anyhow::bail!(
+                "Binary patch for '{}' is not supported; copy the file itself instead",
+                patch.path()
+            );
+        }
+
+        let storage = create_storage_backend(&repo_root).await?;
+        let odb = ObjectDatabase::with_smart_compression(storage, 1000);
+        let mut index = Index::load(&repo_root)?;
+        let head_files = if self.cached {
+            head_files(&repo_root, &odb).await?
+        } else {
+            HashMap::new()
+        };
+
+        // Check every file first so a failing patch leaves nothing half-applied
+        let mut planned = Vec::new();
+        let mut failures = Vec::new();
+        for patch in &patches {
+            let path = patch.path().to_string();
+            let current = if self.cached {
+                staged_content(&odb, &index, &head_files, &path).await?
+            } else {
+                read_optional(&repo_root.join(&path))?
+            };
+
+            match self.plan(patch, current, &odb).await {
+                Ok(content) => planned.push(content),
+                Err(e) => failures.push(format!("{}: {}", path, e)),
+            }
+        }
+
+        if !failures.is_empty() {
+            for failure in &failures {
+                output::error(failure);
+            }
+            anyhow::bail!(
+                "Patch does not apply ({} of {} file(s) failed)",
+                failures.len(),
+                patches.len()
+            );
+        }
+
+        let conflicted: Vec<&str> = planned
+            .iter()
+            .filter(|change| change.conflicts > 0)
+            .map(|change| change.path.as_str())
+            .collect();
+        if self.cached && !conflicted.is_empty() {
+            anyhow::bail!(
+                "Cannot record conflicts in the index: {}",
+                conflicted.join(", ")
+            );
+        }
+
+        if self.check {
+            if !self.quiet {
+                if conflicted.is_empty() {
+                    output::success("Patch applies cleanly");
+                } else {
+                    output::warning(&format!(
+                        "Patch applies with 3-way merge conflicts in: {}",
+                        conflicted.join(", ")
+                    ));
+                }
+            }
+            return Ok(());
+        }
+
+        if self.cached {
+            for change in &planned {
+                stage_change(&odb, &mut index, &head_files, change).await?;
+            }
+            index.save(&repo_root).context("Failed to save index")?;
+        } else {
+            for change in &planned {
+                write_change(&repo_root, change)?;
+            }
+        }
+
+        if !self.quiet {
+            for change in &planned {
+                if change.conflicts > 0 {
+                    output::warning(&format!(
+                        "Applied '{}' with {} conflict(s)",
+                        change.path, change.conflicts
+                    ));
+                } else {
+                    output::success(&format!("Applied patch to '{}'", change.path));
+                }
+            }
+        }
+
+        if !conflicted.is_empty() {
+            anyhow::bail!(
+                "Patch applied with conflicts; resolve the markers in {} and stage the result",
+                conflicted.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Concatenate the patch files, or read stdin when none are given
+    fn read_patches(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        if self.patches.is_empty() || self.patches.iter().any(|p| p == Path::new("-")) {
+            std::io::stdin()
+                .read_to_end(&mut data)
+                .context("Failed to read patch from stdin")?;
+        }
+        for path in self.patches.iter().filter(|p| *p != Path::new("-")) {
+            let patch = std::fs::read(path)
+                .with_context(|| format!("Failed to read patch {}", path.display()))?;
+            data.extend_from_slice(&patch);
+        }
+        Ok(data)
+    }
+
+    /// Work out the new content for one file, or why the patch cannot apply
+    async fn plan(
+        &self,
+        patch: &FilePatch,
+        current: Option<Vec<u8>>,
+        odb: &ObjectDatabase,
+    ) -> Result<PlannedChange> {
+        let path = patch.path().to_string();
+
+        let Some(current) = current else {
+            if !patch.is_creation() {
+                anyhow::bail!(if patch.is_deletion() {
+                    "already deleted (patch already applied?)"
+                } else {
+                    "does not exist"
+                });
+            }
+            let ApplyOutcome::Applied(content) = patch.apply(b"") else {
+                anyhow::bail!("new file patch is malformed");
+            };
+            return Ok(PlannedChange {
+                path,
+                content: Some(content),
+                mode: patch.mode,
+                conflicts: 0,
+            });
+        };
+
+        if patch.is_creation() {
+            if patch.apply(b"") == ApplyOutcome::Applied(current) {
+                anyhow::bail!("patch already applied");
+            }
+            anyhow::bail!("already exists");
+        }
+
+        match patch.apply(&current) {
+            ApplyOutcome::Applied(content) if patch.is_deletion() => {
+                if !content.is_empty() {
+                    anyhow::bail!("file has content the deletion patch does not remove");
+                }
+                Ok(PlannedChange {
+                    path,
+                    content: None,
+                    mode: None,
+                    conflicts: 0,
+                })
+            }
+            ApplyOutcome::Applied(content) => Ok(PlannedChange {
+                path,
+                content: Some(content),
+                mode: patch.mode,
+                conflicts: 0,
+            }),
+            ApplyOutcome::AlreadyApplied => anyhow::bail!("patch already applied"),
+            ApplyOutcome::Conflict { hunk } if self.three_way && !patch.is_deletion() => {
+                let merged = three_way_merge(patch, &current, odb, hunk).await?;
+                Ok(PlannedChange {
+                    path,
+                    content: Some(merged.content),
+                    mode: patch.mode,
+                    conflicts: merged.conflicts,
+                })
+            }
+            ApplyOutcome::Conflict { hunk } => {
+                anyhow::bail!("patch does not apply (hunk #{} does not match)", hunk)
+            }
+        }
+    }
+}
+
+/// Merge the patch into `current` using the preimage it was made against
+async fn three_way_merge(
+    patch: &FilePatch,
+    current: &[u8],
+    odb: &ObjectDatabase,
+    hunk: usize,
+) -> Result<mediagit_versioning::MergedText> {
+    let base_oid = patch.old_oid.with_context(|| {
+        format!(
+            "hunk #{} does not match and the patch names no preimage for --3way",
+            hunk
+        )
+    })?;
+    let base = odb.read(&base_oid).await.with_context(|| {
+        format!(
+            "hunk #{} does not match and preimage {} is not in this repository",
+            hunk,
+            &base_oid.to_hex()[..12]
+        )
+    })?;
+
+    let ApplyOutcome::Applied(theirs) = patch.apply(&base) else {
+        anyhow::bail!("patch does not apply to its own preimage");
+    };
+    Ok(merge_text(&base, current, &theirs, "ours", "patch"))
+}
+
+/// Files in the HEAD commit with their modes, or none before the first commit
+async fn head_files(repo_root: &Path, odb: &ObjectDatabase) -> Result<HashMap<String, (Oid, u32)>> {
+    let refdb = RefDatabase::new(repo_root.join(".mediagit"));
+    let Ok(head_oid) = resolve_revision("HEAD", &refdb, odb).await else {
+        return Ok(HashMap::new());
+    };
+
+    let commit = Commit::deserialize(&odb.read(&head_oid).await?)
+        .context("Failed to deserialize HEAD commit")?;
+    let tree = Tree::deserialize(&odb.read(&commit.tree).await?)
+        .context("Failed to deserialize HEAD tree")?;
+    Ok(tree
+        .iter()
+        .map(|entry| (entry.name.clone(), (entry.oid, entry.mode.as_u32())))
+        .collect())
+}
+
+/// Content of `path` as staged: the index entry, else HEAD, unless staged for deletion
+async fn staged_content(
+    odb: &ObjectDatabase,
+    index: &Index,
+    head_files: &HashMap<String, (Oid, u32)>,
+    path: &str,
+) -> Result<Option<Vec<u8>>> {
+    let index_path = Path::new(path);
+    if index.is_deleted(index_path) {
+        return Ok(None);
+    }
+    let oid = match index.get_entry(index_path) {
+        Some(entry) => entry.oid,
+        None => match head_files.get(path) {
+            Some((oid, _)) => *oid,
+            None => return Ok(None),
+        },
+    };
+    Ok(Some(odb.read(&oid).await.with_context(|| {
+        format!("Failed to read staged content of '{}'", path)
+    })?))
+}
+
+fn read_optional(path: &Path) -> Result<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+fn write_change(repo_root: &Path, change: &PlannedChange) -> Result<()> {
+    let full_path = repo_root.join(&change.path);
+    match &change.content {
+        Some(content) => {
+            if let Some(parent) = full_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&full_path, content)
+                .with_context(|| format!("Failed to write {}", full_path.display()))
+        }
+        None => std::fs::remove_file(&full_path)
+            .with_context(|| format!("Failed to delete {}", full_path.display())),
+    }
+}
+
+/// Stage the patched content, keeping the staged or HEAD mode unless the
+/// patch names a new one
+async fn stage_change(
+    odb: &ObjectDatabase,
+    index: &mut Index,
+    head_files: &HashMap<String, (Oid, u32)>,
+    change: &PlannedChange,
+) -> Result<()> {
+    let path = PathBuf::from(&change.path);
+    match &change.content {
+        Some(content) => {
+            let oid = odb.write(ObjectType::Blob, content).await?;
+            let mode = change
+                .mode
+                .or_else(|| index.get_entry(&path).map(|entry| entry.mode))
+                .or_else(|| head_files.get(&change.path).map(|(_, mode)| *mode))
+                .unwrap_or(0o100644);
+            index.add_entry(IndexEntry::new(path, oid, mode, content.len() as u64, None));
+        }
+        None => index.mark_deleted(path),
+    }
+    Ok(())
+}
@@ -0,0 +1,496 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Benchmark compression on the repository's own files.
+
+use super::super::repo::{brotli_settings, compression_tier, find_repo_root};
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand};
+use console::style;
+use indicatif::HumanBytes;
+use mediagit_compression::{
+    CompressionLevel, CompressionStrategy, Compressor, ObjectType, SmartCompressor,
+};
+use mediagit_versioning::{CompressionAttributes, Index, ATTRIBUTES_FILE};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Strategies tried on every sample, fastest first
+///
+/// Zlib is left out: it is only used for Git-compatible objects.
+const CANDIDATES: [CompressionStrategy; 11] = [
+    CompressionStrategy::Store,
+    CompressionStrategy::Lz4,
+    CompressionStrategy::Zstd(CompressionLevel::Fast),
+    CompressionStrategy::Zstd(CompressionLevel::Default),
+    CompressionStrategy::Zstd(CompressionLevel::Best),
+    CompressionStrategy::Brotli(CompressionLevel::Fast),
+    CompressionStrategy::Brotli(CompressionLevel::Default),
+    CompressionStrategy::Brotli(CompressionLevel::Best),
+    CompressionStrategy::Xz(CompressionLevel::Fast),
+    CompressionStrategy::Xz(CompressionLevel::Default),
+    CompressionStrategy::Xz(CompressionLevel::Best),
+];
+
+/// Below this saving a file type is recommended to be stored as-is
+const MIN_SAVING: f64 = 0.02;
+
+/// Measure how well the repository's files compress
+#[derive(Parser, Debug)]
+#[command(after_help = "EXAMPLES:
+    # Compare every algorithm and level on up to 8 files of each type
+    mediagit benchmark compression
+
+    # Only recommend strategies that compress at 100 MB/s or more, and save them
+    mediagit benchmark compression --min-throughput 100 --apply
+
+SEE ALSO:
+    mediagit-compress-info(1), mediagit-stats(1)")]
+pub struct BenchmarkCmd {
+    #[command(subcommand)]
+    pub command: BenchmarkSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BenchmarkSubcommand {
+    /// Compare compression algorithms and levels on tracked files
+    ///
+    /// Samples tracked files from the working tree, grouped by extension,
+    /// compresses them with each algorithm and level, and prints ratio and
+    /// throughput. For each extension the strategy with the smallest output
+    /// that still reaches `--min-throughput` is recommended.
+    Compression(CompressionBenchmarkArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct CompressionBenchmarkArgs {
+    /// Files sampled per extension; the largest tracked files are used
+    #[arg(long, value_name = "N", default_value_t = 8)]
+    pub files_per_type: usize,
+
+    /// Bytes read from the start of each sampled file
+    #[arg(long, value_name = "BYTES", default_value_t = 1024 * 1024)]
+    pub sample_size: u64,
+
+    /// Slowest compression throughput, in MB/s, a recommendation may have
+    #[arg(long, value_name = "MB/S", default_value_t = 20.0)]
+    pub min_throughput: f64,
+
+    /// Append `compression=` rules for the recommendations that differ from
+    /// the current strategy to .mediagitattributes
+    #[arg(long)]
+    pub apply: bool,
+
+    /// Output format
+    #[arg(long, value_name = "FORMAT", default_value = "text", value_parser = ["text", "json"])]
+    pub format: String,
+}
+
+/// Results for the files sharing one extension
+#[derive(Debug, Serialize)]
+struct GroupReport {
+    /// `.mediagitattributes` pattern matching the group, `None` for files
+    /// without an extension
+    pattern: Option<String>,
+    files: usize,
+    sampled_bytes: u64,
+    /// Strategy `add` currently uses for these files
+    current: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recommended: Option<String>,
+    results: Vec<StrategyResult>,
+}
+
+/// One strategy's totals over a group's samples
+#[derive(Debug, Clone, Serialize)]
+struct StrategyResult {
+    strategy: String,
+    bytes_in: u64,
+    bytes_out: u64,
+    ratio: f64,
+    /// Compression throughput in MB/s
+    compress_mbps: f64,
+    /// Decompression throughput in MB/s
+    decompress_mbps: f64,
+}
+
+impl StrategyResult {
+    /// Share of the input saved; never negative, as expanding strategies
+    /// fall back to store
+    fn saved(&self) -> f64 {
+        (1.0 - self.bytes_out as f64 / self.bytes_in.max(1) as f64).max(0.0)
+    }
+}
+
+/// One sampled file
+struct Sample {
+    data: Vec<u8>,
+    current: CompressionStrategy,
+}
+
+impl BenchmarkCmd {
+    pub async fn execute(&self) -> Result<()> {
+        match &self.command {
+            BenchmarkSubcommand::Compression(args) => args.execute().await,
+        }
+    }
+}
+
+impl CompressionBenchmarkArgs {
+    async fn execute(&self) -> Result<()> {
+        let repo_root = find_repo_root()?;
+        let config = mediagit_config::Config::load(&repo_root)
+            .await
+            .unwrap_or_default();
+        let tier = compression_tier(&config);
+        let attributes = CompressionAttributes::load(&repo_root)?;
+        let index = Index::load(&repo_root)?;
+
+        let mut groups: BTreeMap<Option<String>, Vec<PathBuf>> = BTreeMap::new();
+        let mut entries: Vec<_> = index.entries().collect();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.size));
+        for entry in entries {
+            let files = groups.entry(extension_pattern(&entry.path)).or_default();
+            if files.len() < self.files_per_type {
+                files.push(entry.path.clone());
+            }
+        }
+
+        // Measure with every object compressed, whatever its size
+        let compressor = SmartCompressor::new()
+            .with_min_compress_size(0)
+            .with_brotli(brotli_settings(&config));
+        let mut reports = Vec::new();
+        for (pattern, paths) in groups {
+            let mut samples = Vec::new();
+            for path in &paths {
+                // Tracked files deleted from the working tree are skipped
+                let Ok(file) = std::fs::File::open(repo_root.join(path)) else {
+                    continue;
+                };
+                let size = file.metadata()?.len();
+                let mut data = Vec::new();
+                file.take(self.sample_size)
+                    .read_to_end(&mut data)
+                    .with_context(|| format!("Cannot read {}", path.display()))?;
+
+                let object_type = ObjectType::detect(path, &data);
+                let current = attributes.strategy_for(path).unwrap_or_else(|| {
+                    CompressionStrategy::for_object_type_with_size(object_type, size as usize)
+                        .for_tier(tier)
+                });
+                samples.push(Sample { data, current });
+            }
+            if samples.is_empty() {
+                continue;
+            }
+
+            let results = CANDIDATES
+                .iter()
+                .map(|strategy| benchmark(&compressor, *strategy, &samples))
+                .collect::<Result<Vec<_>>>()?;
+            let current = most_common(samples.iter().map(|s| s.current));
+            let recommended = recommend(&results, self.min_throughput);
+
+            reports.push(GroupReport {
+                pattern,
+                files: samples.len(),
+                sampled_bytes: samples.iter().map(|s| s.data.len() as u64).sum(),
+                current: current.name().to_string(),
+                recommended,
+                results,
+            });
+        }
+
+        let rules: Vec<(String, String)> = reports
+            .iter()
+            .filter_map(|r| match (&r.pattern, &r.recommended) {
+                (Some(pattern), Some(recommended)) if *recommended != r.current => {
+                    Some((pattern.clone(), recommended.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        if self.format == "json" {
+            println!("{}", serde_json::to_string_pretty(&reports)?);
+        } else {
+            self.print_reports(&reports);
+        }
+
+        if self.apply && !rules.is_empty() {
+            append_rules(&repo_root, &rules)?;
+            if self.format != "json" {
+                println!(
+                    "{} Added {} rule(s) to {}",
+                    style("✓").green(),
+                    rules.len(),
+                    ATTRIBUTES_FILE
+                );
+            }
+        } else if self.format != "json" && !rules.is_empty() {
+            println!(
+                "Run with --apply to add these rules to {}:",
+                ATTRIBUTES_FILE
+            );
+            for (pattern, strategy) in &rules {
+                println!("  {} compression={}", pattern, strategy);
+            }
+        }
+        Ok(())
+    }
+
+    fn print_reports(&self, reports: &[GroupReport]) {
+        if reports.is_empty() {
+            println!("No tracked files to benchmark");
+            return;
+        }
+
+        println!(
+            "{} ({} samples, recommending {} MB/s or faster)",
+            style("Compression benchmark").bold(),
+            HumanBytes(self.sample_size),
+            self.min_throughput
+        );
+        for report in reports {
+            println!();
+            println!(
+                "{}  {} file(s), {} sampled, current: {}",
+                style(report.pattern.as_deref().unwrap_or("(no extension)")).bold(),
+                report.files,
+                HumanBytes(report.sampled_bytes),
+                report.current
+            );
+            println!(
+                "    {:<12} {:>8} {:>7} {:>12} {:>12}",
+                "Strategy", "Ratio", "Saved", "Compress", "Decompress"
+            );
+            for result in &report.results {
+                let recommended = report.recommended.as_deref() == Some(result.strategy.as_str());
+                let line = format!(
+                    "{:<12} {:>7.2}x {:>6.1}% {:>7.1} MB/s {:>7.1} MB/s",
+                    result.strategy,
+                    result.ratio,
+                    result.saved() * 100.0,
+                    result.compress_mbps,
+                    result.decompress_mbps
+                );
+                if recommended {
+                    println!("  {} {}", style("*").green(), style(line).green());
+                } else {
+                    println!("    {}", line);
+                }
+            }
+        }
+        println!();
+    }
+}
+
+/// `.mediagitattributes` pattern for files with `path`'s extension
+fn extension_pattern(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| format!("*.{}", e.to_ascii_lowercase()))
+}
+
+/// Compress and decompress every sample with `strategy`
+fn benchmark(
+    compressor: &SmartCompressor,
+    strategy: CompressionStrategy,
+    samples: &[Sample],
+) -> Result<StrategyResult> {
+    let mut bytes_in = 0u64;
+    let mut bytes_out = 0u64;
+    let mut compress_time = Duration::ZERO;
+    let mut decompress_time = Duration::ZERO;
+
+    for sample in samples {
+        let start = Instant::now();
+        let compressed = compressor.compress_with_strategy(&sample.data, strategy)?;
+        compress_time += start.elapsed();
+
+        let start = Instant::now();
+        let restored = compressor.decompress(&compressed)?;
+        decompress_time += start.elapsed();
+        anyhow::ensure!(
+            restored == sample.data,
+            "{} did not round-trip a sample",
+            strategy.name()
+        );
+
+        bytes_in += sample.data.len() as u64;
+        bytes_out += compressed.len() as u64;
+    }
+
+    Ok(StrategyResult {
+        strategy: strategy.name().to_string(),
+        bytes_in,
+        bytes_out,
+        ratio: bytes_in as f64 / bytes_out.max(1) as f64,
+        compress_mbps: throughput(bytes_in, compress_time),
+        decompress_mbps: throughput(bytes_in, decompress_time),
+    })
+}
+
+/// MB/s for `bytes` processed in `elapsed`
+fn throughput(bytes: u64, elapsed: Duration) -> f64 {
+    bytes as f64 / 1_000_000.0 / elapsed.as_secs_f64().max(1e-9)
+}
+
+/// The strategy most samples currently use
+fn most_common(strategies: impl Iterator<Item = CompressionStrategy>) -> CompressionStrategy {
+    let mut counts: Vec<(CompressionStrategy, usize)> = Vec::new();
+    for strategy in strategies {
+        match counts.iter_mut().find(|(s, _)| *s == strategy) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((strategy, 1)),
+        }
+    }
+    // Ties go to the faster strategy
+    counts
+        .into_iter()
+        .max_by_key(|(s, count)| (*count, std::cmp::Reverse(position(*s))))
+        .map(|(s, _)| s)
+        .unwrap_or(CompressionStrategy::Store)
+}
+
+fn position(strategy: CompressionStrategy) -> usize {
+    CANDIDATES
+        .iter()
+        .position(|s| *s == strategy)
+        .unwrap_or(usize::MAX)
+}
+
+/// Strategy with the smallest output among those at least `min_throughput`
+/// MB/s fast; `store` when none saves more than 2%
+///
+/// Store always qualifies, so there is a recommendation whenever `results`
+/// includes it.
+fn recommend(results: &[StrategyResult], min_throughput: f64) -> Option<String> {
+    let best = results
+        .iter()
+        .filter(|r| r.strategy == "store" || r.compress_mbps >= min_throughput)
+        .min_by_key(|r| r.bytes_out)?;
+    if best.saved() < MIN_SAVING {
+        return Some(CompressionStrategy::Store.name().to_string());
+    }
+    Some(best.strategy.clone())
+}
+
+/// Append `compression=` rules to `.mediagitattributes`
+///
+/// Later rules win, so the new ones override any existing rule for the same
+/// pattern.
+fn append_rules(repo_root: &Path, rules: &[(String, String)]) -> Result<()> {
+    let path = repo_root.join(ATTRIBUTES_FILE);
+    let existing = std::fs::read_to_string(&path).unwrap_or_default();
+
+    let mut text = String::new();
+    if !existing.is_empty() {
+        if !existing.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push('\n');
+    }
+    text.push_str("# Recommended by `mediagit benchmark compression`\n");
+    for (pattern, strategy) in rules {
+        text.push_str(&format!("{} compression={}\n", pattern, strategy));
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Cannot open {}", path.display()))?;
+    file.write_all(text.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn result(strategy: &str, bytes_out: u64, compress_mbps: f64) -> StrategyResult {
+        StrategyResult {
+            strategy: strategy.to_string(),
+            bytes_in: 1000,
+            bytes_out,
+            ratio: 1000.0 / bytes_out as f64,
+            compress_mbps,
+            decompress_mbps: 500.0,
+        }
+    }
+
+    #[test]
+    fn test_recommend_smallest_fast_enough() {
+        let results = [
+            result("store", 1001, 5000.0),
+            result("zstd", 400, 300.0),
+            result("zstd-best", 300, 30.0),
+            result("xz-best", 250, 2.0),
+        ];
+        assert_eq!(recommend(&results, 20.0).as_deref(), Some("zstd-best"));
+        assert_eq!(recommend(&results, 100.0).as_deref(), Some("zstd"));
+        assert_eq!(recommend(&results, 1.0).as_deref(), Some("xz-best"));
+    }
+
+    #[test]
+    fn test_recommend_store_for_incompressible() {
+        let results = [result("store", 1001, 5000.0), result("zstd", 990, 300.0)];
+        assert_eq!(recommend(&results, 20.0).as_deref(), Some("store"));
+    }
+
+    #[test]
+    fn test_most_common_prefers_faster_on_tie() {
+        let zstd = CompressionStrategy::Zstd(CompressionLevel::Default);
+        let brotli = CompressionStrategy::Brotli(CompressionLevel::Default);
+        assert_eq!(most_common([brotli, zstd, brotli].into_iter()), brotli);
+        assert_eq!(most_common([brotli, zstd].into_iter()), zstd);
+    }
+
+    #[test]
+    fn test_extension_pattern() {
+        assert_eq!(
+            extension_pattern(Path::new("art/Hero.PSD")).as_deref(),
+            Some("*.psd")
+        );
+        assert_eq!(extension_pattern(Path::new("Makefile")), None);
+    }
+
+    #[test]
+    fn test_append_rules() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join(ATTRIBUTES_FILE), "*.exr compression=store").unwrap();
+
+        append_rules(
+            dir.path(),
+            &[("*.psd".to_string(), "zstd-best".to_string())],
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(dir.path().join(ATTRIBUTES_FILE)).unwrap();
+        let attributes = CompressionAttributes::parse(&content);
+        assert_eq!(
+            attributes.strategy_for(Path::new("a.psd")),
+            Some(CompressionStrategy::Zstd(CompressionLevel::Best))
+        );
+        assert_eq!(
+            attributes.strategy_for(Path::new("b.exr")),
+            Some(CompressionStrategy::Store)
+        );
+    }
+}
@@ -315,7 +315,7 @@ impl BisectCmd {
         let odb = ObjectDatabase::with_smart_compression(storage.clone(), 1000);
         let refdb = RefDatabase::new(&mediagit_dir);
 
-        let checkout_mgr = CheckoutManager::new(&odb, &repo_root);
+        let checkout_mgr = CheckoutManager::new(&odb, &repo_root)?;
         checkout_mgr.checkout_commit(&reset_oid).await?;
 
         // Update HEAD reference
@@ -431,7 +431,7 @@ impl BisectCmd {
         let next_oid = candidates[midpoint];
 
         // Checkout next commit
-        let checkout_mgr = CheckoutManager::new(&odb, repo_root);
+        let checkout_mgr = CheckoutManager::new(&odb, repo_root)?;
         checkout_mgr.checkout_commit(&next_oid).await?;
 
         state.current = Some(next_oid.to_hex());
@@ -11,11 +11,14 @@
 // MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
 // GNU Affero General Public License for more details.
 
-use super::super::repo::{create_storage_backend, find_repo_root};
+use super::super::repo::{create_storage_backend, find_repo_root, tree_limits};
+use super::utils::upstream_ahead_behind;
 use crate::progress::{OperationStats, ProgressTracker};
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use mediagit_versioning::{Oid, Ref, RefDatabase, Reflog, ReflogEntry};
+use mediagit_config::BranchConfig;
+use mediagit_versioning::{ObjectDatabase, Oid, Ref, RefDatabase, Reflog, ReflogEntry};
+use std::sync::Arc;
 use std::time::Instant;
 
 /// Manage branches
@@ -27,8 +30,8 @@ use std::time::Instant;
     # List all local branches
     mediagit branch list
 
-    # List all branches with verbose output
-    mediagit branch list -v
+    # List branches with commits, upstreams and ahead/behind counts
+    mediagit branch list -vv
 
     # Create a new branch
     mediagit branch create feature-branch
@@ -42,6 +45,9 @@ use std::time::Instant;
     # Create and switch to new branch
     mediagit branch switch -c feature-branch
 
+    # Switch even if it discards local changes
+    mediagit branch switch --force main
+
     # Rename current branch
     mediagit branch rename new-name
 
@@ -103,8 +109,8 @@ pub struct ListOpts {
     #[arg(short = 'a', long)]
     pub all: bool,
 
-    /// Show verbose output
-    #[arg(short, long)]
+    /// Show commit ids, upstream branches and ahead/behind counts (-v or -vv)
+    #[arg(short, long, overrides_with = "verbose")]
     pub verbose: bool,
 
     /// Quiet mode
@@ -299,9 +305,18 @@ impl BranchCmd {
 
         let repo_root = find_repo_root()?;
         let storage_path = repo_root.join(".mediagit");
-        let _storage = create_storage_backend(&repo_root).await?;
+        let storage = create_storage_backend(&repo_root).await?;
         let refdb = RefDatabase::new(&storage_path);
 
+        // Upstream tracking is only shown in verbose mode
+        let tracking = if opts.verbose {
+            let config = mediagit_config::Config::load(&repo_root).await?;
+            let odb = Arc::new(ObjectDatabase::with_smart_compression(storage, 100));
+            Some((config, odb))
+        } else {
+            None
+        };
+
         // Get current branch for highlighting
         let head = refdb.read("HEAD").await.ok();
         let current_branch = head.and_then(|h| h.target);
@@ -332,20 +347,36 @@ impl BranchCmd {
                         .unwrap_or(&normalized_branch);
 
                     if opts.verbose {
-                        let branch_ref = refdb.read(&branch_name).await.ok();
-                        let oid_display = branch_ref
-                            .and_then(|r| r.oid)
+                        let branch_oid = refdb.read(&branch_name).await.ok().and_then(|r| r.oid);
+                        let oid_display = branch_oid
                             .map(|o| o.to_string()[..8].to_string())
                             .unwrap_or_else(|| "unknown".to_string());
+                        let tracking_display = match (&tracking, branch_oid) {
+                            (Some((config, odb)), Some(oid)) => {
+                                match config.branches.get(display_name) {
+                                    Some(upstream) => format!(
+                                        " {}",
+                                        tracking_summary(&refdb, odb.clone(), &oid, upstream)
+                                            .await?
+                                    ),
+                                    None => String::new(),
+                                }
+                            }
+                            _ => String::new(),
+                        };
                         if is_current {
                             println!(
-                                "{}{} -> {}",
+                                "{}{} -> {}{}",
                                 style(prefix).green(),
                                 style(display_name).green().bold(),
-                                oid_display
+                                oid_display,
+                                tracking_display
                             );
                         } else {
-                            println!("{}{} -> {}", prefix, display_name, oid_display);
+                            println!(
+                                "{}{} -> {}{}",
+                                prefix, display_name, oid_display, tracking_display
+                            );
                         }
                     } else if is_current {
                         println!(
@@ -508,19 +539,52 @@ impl BranchCmd {
             opts.branch
         ))?;
 
+        let config = mediagit_config::Config::load(&repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 1000)
+            .with_tree_limits(tree_limits(&config));
+        let checkout_mgr = CheckoutManager::new(&odb, &repo_root)?;
+
+        // Refuse to overwrite local modifications unless --force is given
+        if !opts.force {
+            let index = Index::load(&repo_root)?;
+            let overwritten = checkout_mgr
+                .find_overwritten_changes(current_commit_oid.as_ref(), &target_commit_oid, &index)
+                .await
+                .context("Failed to check working directory for local changes")?;
+            if !overwritten.is_empty() {
+                let file_list: Vec<String> = overwritten
+                    .iter()
+                    .map(|p| format!("    {}", p.display()))
+                    .collect();
+                anyhow::bail!(
+                    "Your local changes to the following files would be overwritten by checkout:\n{}\n\
+                     Please commit or stash your changes before switching branches, \
+                     or use --force to discard them.",
+                    file_list.join("\n")
+                );
+            }
+        }
+
         // Update HEAD to point to the branch
         let head = Ref::new_symbolic("HEAD".to_string(), branch_ref_name.clone());
         refdb.write(&head).await?;
 
         // Update working directory to match the target branch's commit
-        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 1000);
-        let checkout_mgr = CheckoutManager::new(&odb, &repo_root);
 
         let checkout_pb = progress.spinner("Updating working directory");
 
         // OPTIMIZATION: Use differential checkout when we have a current commit
         // This only updates files that actually changed between commits
-        let files_updated = if let Some(ref current_oid) = current_commit_oid {
+        let files_updated = if let (true, Some(current_oid)) = (opts.force, &current_commit_oid) {
+            // Forced checkout: also discard local modifications to unchanged paths
+            checkout_mgr
+                .checkout_force(current_oid, &target_commit_oid)
+                .await
+                .context("Failed to update working directory")?
+                .files_changed()
+        } else if let Some(ref current_oid) = current_commit_oid {
             // Differential checkout: only update changed files
             let checkout_stats = checkout_mgr
                 .checkout_diff(current_oid, &target_commit_oid)
@@ -879,3 +943,21 @@ impl BranchCmd {
         anyhow::bail!("Branch merge not yet implemented (use 'mediagit merge' instead)")
     }
 }
+
+/// Upstream name with ahead/behind counts, e.g. "[origin/main: ahead 2, behind 1]"
+async fn tracking_summary(
+    refdb: &RefDatabase,
+    odb: Arc<ObjectDatabase>,
+    local: &Oid,
+    upstream: &BranchConfig,
+) -> Result<String> {
+    let name = upstream.display_name();
+    let summary = match upstream_ahead_behind(refdb, odb, local, upstream).await? {
+        None => format!("[{}: gone]", name),
+        Some((0, 0)) => format!("[{}]", name),
+        Some((ahead, 0)) => format!("[{}: ahead {}]", name, ahead),
+        Some((0, behind)) => format!("[{}: behind {}]", name, behind),
+        Some((ahead, behind)) => format!("[{}: ahead {}, behind {}]", name, ahead, behind),
+    };
+    Ok(summary)
+}
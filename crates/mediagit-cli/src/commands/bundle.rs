@@ -0,0 +1,289 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Move history through files instead of a network.
+//!
+//! The `bundle` command packages refs and their objects into a single
+//! `.mgbundle` file. `clone` and `fetch` accept a bundle path wherever they
+//! accept a remote URL.
+
+use super::super::repo::{create_storage_backend, find_repo_root};
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use console::style;
+use mediagit_versioning::{resolve_revision, Bundle, ObjectDatabase, RefDatabase, RefType};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Package refs and objects into a file for offline transfer
+#[derive(Parser, Debug)]
+#[command(after_help = "EXAMPLES:
+    # Bundle the main branch with its full history
+    mediagit bundle create project.mgbundle main
+
+    # Bundle every branch and tag
+    mediagit bundle create project.mgbundle --all
+
+    # Bundle only what was committed since the last transfer
+    mediagit bundle create --since v1.0 update.mgbundle main
+
+    # Check a bundle before importing it
+    mediagit bundle verify update.mgbundle
+
+    # Clone or fetch from a bundle
+    mediagit clone project.mgbundle project
+    mediagit fetch update.mgbundle
+
+SEE ALSO:
+    mediagit-clone(1), mediagit-fetch(1)")]
+pub struct BundleCmd {
+    #[command(subcommand)]
+    pub command: BundleSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BundleSubcommand {
+    /// Create a bundle from refs and the objects reachable from them
+    Create(CreateOpts),
+
+    /// Check a bundle's checksums and prerequisites
+    Verify(VerifyOpts),
+}
+
+#[derive(Parser, Debug)]
+pub struct CreateOpts {
+    /// Bundle file to write
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Branches, tags or full ref names to include (HEAD for the current branch)
+    #[arg(value_name = "REF")]
+    pub refs: Vec<String>,
+
+    /// Include every branch and tag
+    #[arg(long)]
+    pub all: bool,
+
+    /// Leave out history reachable from this ref or revision, which the
+    /// receiver must already have
+    #[arg(long, visible_alias = "basis", value_name = "REF")]
+    pub since: Vec<String>,
+
+    /// Quiet mode
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct VerifyOpts {
+    /// Bundle file to check
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Quiet mode
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+impl BundleCmd {
+    pub async fn execute(&self) -> Result<()> {
+        match &self.command {
+            BundleSubcommand::Create(opts) => self.create(opts).await,
+            BundleSubcommand::Verify(opts) => self.verify(opts).await,
+        }
+    }
+
+    async fn create(&self, opts: &CreateOpts) -> Result<()> {
+        let repo_root = find_repo_root()?;
+        let storage = create_storage_backend(&repo_root).await?;
+        let odb = ObjectDatabase::with_smart_compression(Arc::clone(&storage), 1000);
+        let refdb = RefDatabase::new(repo_root.join(".mediagit"));
+
+        let names = if opts.all {
+            let mut names = refdb.list_branches().await?;
+            names.extend(refdb.list_tags().await?);
+            names
+        } else if opts.refs.is_empty() {
+            anyhow::bail!("No refs given; name the branches or tags to bundle, or use --all");
+        } else {
+            let mut names = Vec::new();
+            for name in &opts.refs {
+                names.push(full_ref_name(&refdb, name).await?);
+            }
+            names
+        };
+
+        let mut refs = BTreeMap::new();
+        for name in names {
+            let oid = refdb
+                .resolve(&name)
+                .await
+                .with_context(|| format!("Cannot resolve '{}'", name))?;
+            refs.insert(name, oid);
+        }
+
+        // Basis refs are recorded by full name so the receiver can report them
+        let mut basis = BTreeMap::new();
+        for rev in &opts.since {
+            let (name, oid) = match full_ref_name(&refdb, rev).await {
+                Ok(name) => {
+                    let oid = refdb.resolve(&name).await?;
+                    (name, oid)
+                }
+                Err(_) => {
+                    let oid = resolve_revision(rev, &refdb, &odb)
+                        .await
+                        .with_context(|| format!("Cannot resolve basis '{}'", rev))?;
+                    (rev.clone(), oid)
+                }
+            };
+            basis.insert(name, oid);
+        }
+
+        let bundle = Bundle::create(&odb, refs, basis).await?;
+        std::fs::write(&opts.file, bundle.to_bytes())
+            .with_context(|| format!("Failed to write {}", opts.file.display()))?;
+
+        if !opts.quiet {
+            for (name, oid) in bundle.refs() {
+                println!("  {} {}", &oid.to_hex()[..8], name);
+            }
+            println!(
+                "{} Bundled {} ref(s) and {} object(s) into {}",
+                style("✓").green().bold(),
+                bundle.refs().len(),
+                bundle.object_count(),
+                opts.file.display()
+            );
+            for (name, oid) in bundle.prerequisites() {
+                println!(
+                    "  Requires {} ({}) in the receiving repository",
+                    name,
+                    &oid.to_hex()[..8]
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn verify(&self, opts: &VerifyOpts) -> Result<()> {
+        let bundle = read_bundle(&opts.file)?;
+
+        // Prerequisites can only be checked from inside a repository
+        let missing = match find_repo_root() {
+            Ok(repo_root) => {
+                let storage = create_storage_backend(&repo_root).await?;
+                let odb = ObjectDatabase::with_smart_compression(storage, 1000);
+                Some(bundle.missing_prerequisites(&odb).await?)
+            }
+            Err(_) => None,
+        };
+
+        if !opts.quiet {
+            for (name, oid) in bundle.refs() {
+                println!("  {} {}", &oid.to_hex()[..8], name);
+            }
+            for (name, oid) in bundle.prerequisites() {
+                let state = match &missing {
+                    Some(missing) if missing.contains_key(name) => style("missing").red(),
+                    Some(_) => style("present").green(),
+                    None => style("required").dim(),
+                };
+                println!(
+                    "  {} {} {} ({})",
+                    style("-").dim(),
+                    &oid.to_hex()[..8],
+                    name,
+                    state
+                );
+            }
+        }
+
+        if let Some(missing) = missing.filter(|m| !m.is_empty()) {
+            let names: Vec<&str> = missing.keys().map(String::as_str).collect();
+            anyhow::bail!(
+                "{} requires basis the repository does not have: {}",
+                opts.file.display(),
+                names.join(", ")
+            );
+        }
+
+        if !opts.quiet {
+            println!(
+                "{} {} is valid ({} object(s))",
+                style("✓").green().bold(),
+                opts.file.display(),
+                bundle.object_count()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Full ref name for a branch, tag or HEAD given on the command line
+async fn full_ref_name(refdb: &RefDatabase, name: &str) -> Result<String> {
+    if name == "HEAD" {
+        let head = refdb.read("HEAD").await?;
+        return Ok(match (head.ref_type, head.target) {
+            (RefType::Symbolic, Some(target)) => target,
+            _ => "HEAD".to_string(),
+        });
+    }
+    if name.starts_with("refs/") {
+        return Ok(name.to_string());
+    }
+    for candidate in [
+        format!("refs/heads/{}", name),
+        format!("refs/tags/{}", name),
+    ] {
+        if refdb.exists(&candidate).await? {
+            return Ok(candidate);
+        }
+    }
+    anyhow::bail!("'{}' is not a branch or tag", name)
+}
+
+/// The bundle file a remote URL points to, if it is one
+///
+/// Anything with a URL scheme is a network remote; otherwise an existing
+/// file is taken to be a bundle.
+pub(crate) fn bundle_path(url: &str) -> Option<PathBuf> {
+    if url.contains("://") {
+        return None;
+    }
+    let path = PathBuf::from(url);
+    path.is_file().then_some(path)
+}
+
+/// Read and verify a bundle file
+pub(crate) fn read_bundle(path: &Path) -> Result<Bundle> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Bundle::from_bytes(data).with_context(|| format!("Invalid bundle {}", path.display()))
+}
+
+/// A bundle's refs in the form a remote advertises them
+pub(crate) fn advertised_refs(bundle: &Bundle) -> mediagit_protocol::RefsResponse {
+    mediagit_protocol::RefsResponse {
+        refs: bundle
+            .refs()
+            .iter()
+            .map(|(name, oid)| mediagit_protocol::RefInfo {
+                name: name.clone(),
+                oid: oid.to_hex(),
+                target: None,
+            })
+            .collect(),
+        capabilities: Vec::new(),
+    }
+}
@@ -11,7 +11,7 @@
 // MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
 // GNU Affero General Public License for more details.
 
-use super::super::repo::{create_storage_backend, find_repo_root};
+use super::super::repo::{create_storage_backend, find_repo_root, object_format};
 use anyhow::{Context, Result};
 use clap::Parser;
 use console::style;
@@ -81,10 +81,13 @@ impl CherryPickCmd {
     async fn start_cherrypick(&self, repo_root: &PathBuf) -> Result<()> {
         let mediagit_dir = repo_root.join(".mediagit");
         let storage = create_storage_backend(repo_root).await?;
-        let odb = Arc::new(ObjectDatabase::with_smart_compression(
-            storage.clone(),
-            1000,
-        ));
+        let config = mediagit_config::Config::load(repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = Arc::new(
+            ObjectDatabase::with_smart_compression(storage.clone(), 1000)
+                .with_object_format(object_format(&config)),
+        );
         let refdb = RefDatabase::new(&mediagit_dir);
 
         // Get current HEAD
@@ -211,7 +214,7 @@ impl CherryPickCmd {
 
         // Checkout the merged tree if merge was successful
         if let Some(tree_oid) = merge_result.tree_oid {
-            let checkout_mgr = CheckoutManager::new(odb.as_ref(), repo_root);
+            let checkout_mgr = CheckoutManager::new(odb.as_ref(), repo_root)?;
             let commit_to_checkout = Commit {
                 tree: tree_oid,
                 parents: vec![current_oid],
@@ -310,7 +313,11 @@ impl CherryPickCmd {
         // Create commit for current pick
         let mediagit_dir = repo_root.join(".mediagit");
         let storage = create_storage_backend(repo_root).await?;
-        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 1000);
+        let config = mediagit_config::Config::load(repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 1000)
+            .with_object_format(object_format(&config));
         let refdb = RefDatabase::new(&mediagit_dir);
 
         if let Some(current) = &state.current_commit {
@@ -420,7 +427,7 @@ impl CherryPickCmd {
             }
 
             // Restore working directory
-            let checkout_mgr = mediagit_versioning::CheckoutManager::new(&odb, repo_root);
+            let checkout_mgr = mediagit_versioning::CheckoutManager::new(&odb, repo_root)?;
             checkout_mgr.checkout_commit(&original_oid).await?;
         }
 
@@ -0,0 +1,265 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Remove untracked files from the working tree.
+//!
+//! Untracked and ignored files are determined the same way as `status`:
+//! anything in the working tree that is neither in HEAD nor staged. Nothing
+//! is deleted without `--force`; otherwise the command only lists what it
+//! would remove.
+
+use crate::ignore_rules::{scan_working_tree, IgnoreMatcher, WorkingTreeScan};
+use crate::output;
+use crate::repo::{create_storage_backend, find_repo_root};
+use anyhow::{Context, Result};
+use clap::Parser;
+use mediagit_versioning::{Commit, Index, ObjectDatabase, RefDatabase, Tree};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Remove untracked files from the working tree
+///
+/// Lists untracked files, or deletes them with --force. Untracked
+/// directories are only removed with -d, and files matched by
+/// .mediagitignore are only removed with -x.
+#[derive(Parser, Debug)]
+#[command(after_help = "EXAMPLES:
+    # Show what would be removed
+    mediagit clean
+
+    # Remove untracked files
+    mediagit clean -f
+
+    # Also remove untracked directories
+    mediagit clean -fd
+
+    # Also remove ignored files such as renders and build output
+    mediagit clean -fdx
+
+SEE ALSO:
+    mediagit-status(1), mediagit-reset(1)")]
+pub struct CleanCmd {
+    /// Actually delete files (without this, only list them)
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// Only list what would be removed, even with --force
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Remove untracked directories as well as files
+    #[arg(short = 'd')]
+    pub directories: bool,
+
+    /// Also remove files matched by .mediagitignore
+    #[arg(short = 'x')]
+    pub ignored: bool,
+
+    /// Only report errors
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+/// Something `clean` would delete
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Removal {
+    File(PathBuf),
+    Dir(PathBuf),
+}
+
+impl Removal {
+    fn path(&self) -> &Path {
+        match self {
+            Removal::File(path) | Removal::Dir(path) => path,
+        }
+    }
+
+    fn display(&self) -> String {
+        match self {
+            Removal::File(path) => path.display().to_string(),
+            Removal::Dir(path) => format!("{}/", path.display()),
+        }
+    }
+}
+
+/// Untracked content below one untracked top-level directory
+#[derive(Default)]
+struct UntrackedDir {
+    members: Vec<Removal>,
+    /// Holds ignored content that must survive because -x was not given
+    keeps_ignored: bool,
+}
+
+impl CleanCmd {
+    pub async fn execute(&self) -> Result<()> {
+        let repo_root = find_repo_root()?;
+        let storage = create_storage_backend(&repo_root).await?;
+        let odb = ObjectDatabase::with_smart_compression(storage, 1000);
+        let index = Index::load(&repo_root)?;
+
+        let tracked = tracked_paths(&repo_root, &odb, &index).await?;
+        let matcher = IgnoreMatcher::new(&repo_root)?;
+        let scan = scan_working_tree(&repo_root, Some(&matcher))?;
+
+        let removals = self.plan(&tracked, &scan);
+        let dry_run = self.dry_run || !self.force;
+
+        if removals.is_empty() {
+            if !self.quiet {
+                output::info("Nothing to clean");
+            }
+            return Ok(());
+        }
+
+        if dry_run {
+            for removal in &removals {
+                println!("Would remove {}", removal.display());
+            }
+            if !self.force && !self.quiet {
+                output::info("Run with -f to delete these files");
+            }
+            return Ok(());
+        }
+
+        let mut failed = 0;
+        for removal in &removals {
+            let full_path = repo_root.join(removal.path());
+            let result = match removal {
+                Removal::File(_) => std::fs::remove_file(&full_path),
+                Removal::Dir(_) => std::fs::remove_dir_all(&full_path),
+            };
+            match result {
+                Ok(()) => {
+                    if !self.quiet {
+                        println!("Removing {}", removal.display());
+                    }
+                }
+                Err(e) => {
+                    failed += 1;
+                    output::error(&format!("Failed to remove {}: {}", removal.display(), e));
+                }
+            }
+        }
+
+        if failed > 0 {
+            anyhow::bail!("Failed to remove {} path(s)", failed);
+        }
+        Ok(())
+    }
+
+    /// Work out what to delete, mirroring `git clean`
+    ///
+    /// Without -d, files inside untracked directories are left alone. With
+    /// -d, an untracked directory is removed whole unless it holds ignored
+    /// files that -x does not allow removing, in which case only its
+    /// untracked files go.
+    fn plan(&self, tracked: &HashSet<PathBuf>, scan: &WorkingTreeScan) -> Vec<Removal> {
+        let tracked_dirs: HashSet<&Path> = tracked
+            .iter()
+            .flat_map(|path| path.ancestors().skip(1))
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .collect();
+
+        let candidates = scan
+            .files
+            .iter()
+            .map(|path| (Removal::File(path.clone()), false))
+            .chain(
+                scan.ignored_files
+                    .iter()
+                    .map(|path| (Removal::File(path.clone()), true)),
+            )
+            .chain(
+                scan.ignored_dirs
+                    .iter()
+                    .filter(|dir| !tracked_dirs.contains(dir.as_path()))
+                    .map(|dir| (Removal::Dir(dir.clone()), true)),
+            )
+            .filter(|(removal, _)| !tracked.contains(removal.path()));
+
+        let mut removals = BTreeSet::new();
+        let mut untracked_dirs: BTreeMap<PathBuf, UntrackedDir> = BTreeMap::new();
+        for (removal, is_ignored) in candidates {
+            let top = untracked_ancestor(removal.path(), &tracked_dirs);
+            let keep = is_ignored && !self.ignored;
+
+            match top {
+                Some(top) => {
+                    let dir = untracked_dirs.entry(top).or_default();
+                    if keep {
+                        dir.keeps_ignored = true;
+                    } else {
+                        dir.members.push(removal);
+                    }
+                }
+                None if keep => {}
+                None if matches!(removal, Removal::Dir(_)) && !self.directories => {}
+                None => {
+                    removals.insert(removal);
+                }
+            }
+        }
+
+        if self.directories {
+            for (top, dir) in untracked_dirs {
+                if dir.keeps_ignored {
+                    removals.extend(dir.members);
+                } else {
+                    removals.insert(Removal::Dir(top));
+                }
+            }
+        }
+
+        let mut removals: Vec<Removal> = removals.into_iter().collect();
+        removals.sort_by(|a, b| a.path().cmp(b.path()));
+        removals
+    }
+}
+
+/// The outermost directory above `path` that contains no tracked files
+fn untracked_ancestor(path: &Path, tracked_dirs: &HashSet<&Path>) -> Option<PathBuf> {
+    let mut ancestors: Vec<&Path> = path
+        .ancestors()
+        .skip(1)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .collect();
+    ancestors.reverse();
+    ancestors
+        .into_iter()
+        .find(|dir| !tracked_dirs.contains(dir))
+        .map(Path::to_path_buf)
+}
+
+/// Paths tracked at HEAD or staged, excluding staged deletions
+async fn tracked_paths(
+    repo_root: &Path,
+    odb: &ObjectDatabase,
+    index: &Index,
+) -> Result<HashSet<PathBuf>> {
+    let mut tracked = HashSet::new();
+
+    let refdb = RefDatabase::new(repo_root.join(".mediagit"));
+    if let Ok(head_oid) = refdb.resolve("HEAD").await {
+        let commit = Commit::deserialize(&odb.read(&head_oid).await?)
+            .context("Failed to deserialize HEAD commit")?;
+        let tree = Tree::deserialize(&odb.read(&commit.tree).await?)
+            .context("Failed to deserialize HEAD tree")?;
+        tracked.extend(tree.iter().map(|entry| PathBuf::from(&entry.name)));
+    }
+
+    for path in index.deleted_paths() {
+        tracked.remove(path);
+    }
+    tracked.extend(index.entries().map(|entry| entry.path.clone()));
+    Ok(tracked)
+}
@@ -14,12 +14,16 @@
 //! Clone a remote repository.
 //!
 //! The `clone` command creates a copy of an existing remote repository.
+//! With `--local` it clones a repository on this machine, hard-linking its
+//! objects instead of copying them.
 
+use super::bundle::{advertised_refs, bundle_path, read_bundle};
 use crate::progress::{OperationStats, ProgressTracker};
-use crate::repo::create_storage_backend;
+use crate::repo::{create_storage_backend, local_object_store};
 use anyhow::{Context, Result};
 use clap::Parser;
 use console::style;
+use mediagit_storage::{LocalBackend, StorageBackend};
 use mediagit_versioning::{CheckoutManager, ObjectDatabase, RefDatabase};
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -40,10 +44,19 @@ use std::time::Instant;
     # Clone with progress info
     mediagit clone --verbose http://server:3000/my-project
 
+    # Clone and track only main (e.g. for CI)
+    mediagit clone --single-branch --branch main http://server:3000/my-project
+
+    # Clone from a bundle file
+    mediagit clone project.mgbundle
+
+    # Clone a repository on this machine, sharing its objects
+    mediagit clone --local ../my-project my-copy
+
 SEE ALSO:
-    mediagit-init(1), mediagit-pull(1), mediagit-remote(1)")]
+    mediagit-init(1), mediagit-pull(1), mediagit-remote(1), mediagit-bundle(1)")]
 pub struct CloneCmd {
-    /// Remote repository URL
+    /// Remote repository URL or bundle file
     #[arg(value_name = "URL")]
     pub url: String,
 
@@ -55,6 +68,17 @@ pub struct CloneCmd {
     #[arg(short, long, value_name = "BRANCH")]
     pub branch: Option<String>,
 
+    /// Only track the cloned branch; later fetches skip other branches
+    /// unless they are fetched by name
+    #[arg(long)]
+    pub single_branch: bool,
+
+    /// Clone a repository directory on this machine, hard-linking its
+    /// objects (or reflinking them where links aren't possible) instead of
+    /// copying them
+    #[arg(long)]
+    pub local: bool,
+
     /// Quiet mode
     #[arg(short, long)]
     pub quiet: bool,
@@ -70,6 +94,7 @@ impl CloneCmd {
 
         // Determine target directory
         let target_dir = self.get_target_directory()?;
+        let bundle_file = bundle_path(&self.url);
         let branch = self.branch.as_deref().unwrap_or("main");
 
         if !self.quiet {
@@ -85,6 +110,15 @@ impl CloneCmd {
             anyhow::bail!("Destination path '{}' already exists", target_dir.display());
         }
 
+        let local_source = if self.local {
+            if bundle_file.is_some() {
+                anyhow::bail!("--local clones a repository directory, not a bundle");
+            }
+            Some(LocalSource::open(&self.url).await?)
+        } else {
+            None
+        };
+
         // Create progress tracker and stats (matching pull.rs pattern)
         let mut stats = OperationStats::for_operation("clone");
         let progress = ProgressTracker::new(self.quiet);
@@ -107,13 +141,40 @@ impl CloneCmd {
         std::fs::write(storage_path.join("HEAD"), head_content)?;
 
         // Step 3: Configure remote
+        // A bundle is recorded by absolute path so fetch works from the clone
         init_spinner.set_message("Configuring remote...");
-        let config_content = format!(
+        let remote_url = match (&bundle_file, &local_source) {
+            (Some(path), _) => dunce::canonicalize(path)?.to_string_lossy().into_owned(),
+            (None, Some(source)) => source.root.to_string_lossy().into_owned(),
+            (None, None) => self.url.clone(),
+        };
+        let mut config_content = format!(
             r#"[remotes.origin]
 url = "{}"
 "#,
-            self.url
+            remote_url.replace('\\', "\\\\")
         );
+        if self.single_branch {
+            config_content.push_str(&format!(
+                "fetch_refspecs = [\"{}\"]\n",
+                mediagit_config::RemoteConfig::branch_refspec("origin", branch)
+            ));
+        }
+        // A clone of a repository that shares a machine-wide store shares it too
+        if let Some(alternates) = local_source
+            .as_ref()
+            .and_then(|source| source.store.alternates())
+        {
+            config_content.push_str(&format!(
+                r#"
+[storage]
+backend = "filesystem"
+base_path = "./data"
+alternates = "{}"
+"#,
+                alternates.to_string_lossy().replace('\\', "\\\\")
+            ));
+        }
         std::fs::write(storage_path.join("config.toml"), config_content)?;
 
         // Step 4: Initialize storage and fetch
@@ -126,11 +187,20 @@ url = "{}"
         let refdb = RefDatabase::new(&storage_path);
 
         // Initialize protocol client
-        let client = mediagit_protocol::ProtocolClient::new(self.url.clone());
+        // No repository config exists yet, so only the environment applies
+        let client = mediagit_protocol::ProtocolClient::with_proxy(
+            self.url.clone(),
+            &mediagit_storage::ProxySettings::from_env(),
+        )?;
 
         // Step 5: Get remote refs
         init_spinner.set_message("Fetching remote refs...");
-        let remote_refs = client.get_refs().await?;
+        let bundle = bundle_file.as_deref().map(read_bundle).transpose()?;
+        let remote_refs = match (&bundle, &local_source) {
+            (Some(bundle), _) => advertised_refs(bundle),
+            (None, Some(source)) => source.advertised_refs().await?,
+            (None, None) => client.get_refs().await?,
+        };
         init_spinner.finish_with_message("Connected");
         let remote_ref_name = format!("refs/heads/{}", branch);
         let remote_ref = remote_refs
@@ -150,10 +220,27 @@ url = "{}"
         // Step 6: Pull objects using streaming (memory-efficient)
         // Use spinner: total bytes unknown, pull_streaming has no progress callback
         let download_pb = progress.spinner("Receiving objects...");
-        // Use streaming pull to avoid OOM with large files
-        let chunked_oids = client
-            .pull_streaming(&odb, &remote_ref_name, vec![])
-            .await?;
+        let chunked_oids = if let Some(bundle) = &bundle {
+            // A bundle holds every branch's objects, with large files whole
+            stats.objects_received += bundle.unbundle(&odb).await? as u64;
+            Vec::new()
+        } else if let Some(source) = &local_source {
+            // Every branch's objects, linked rather than transferred
+            let target = local_object_store(&target_dir)
+                .await?
+                .context("A local clone needs filesystem storage")?;
+            let (linked, copied) = source.link_objects(&target).await?;
+            stats.objects_received += linked + copied;
+            if self.verbose {
+                println!("  Linked {} objects, copied {}", linked, copied);
+            }
+            Vec::new()
+        } else {
+            // Use streaming pull to avoid OOM with large files
+            client
+                .pull_streaming(&odb, &remote_ref_name, vec![])
+                .await?
+        };
         download_pb.finish_with_message("Received objects");
 
         if self.verbose {
@@ -199,8 +286,12 @@ url = "{}"
         // Step 8b: Create tracking refs for all remote branches (LAZY CLONE)
         // We only download objects for the default branch. Other branches' objects
         // will be fetched on-demand when user runs `pull origin branch` or `branch switch`.
+        // A single-branch clone only tracks the branch it checked out.
         let mut other_branches = Vec::new();
         for ref_info in &remote_refs.refs {
+            if self.single_branch && ref_info.name != remote_ref_name {
+                continue;
+            }
             if ref_info.name.starts_with("refs/heads/") {
                 let branch_name = ref_info
                     .name
@@ -246,7 +337,7 @@ url = "{}"
         // Step 9: Checkout working directory
         // Use spinner: file count only known after checkout finishes
         let checkout_pb = progress.spinner("Checking out files...");
-        let checkout_mgr = CheckoutManager::new(&odb, &target_dir);
+        let checkout_mgr = CheckoutManager::new(&odb, &target_dir)?;
         let files_count = checkout_mgr.checkout_fresh(&remote_oid).await?;
         checkout_pb.finish_with_message(format!("Checked out {} files", files_count));
         stats.files_updated = files_count as u64;
@@ -280,6 +371,15 @@ url = "{}"
             return Ok(PathBuf::from(dir));
         }
 
+        // A bundle names the repository after its file
+        // e.g., transfer/my-project.mgbundle -> my-project
+        if let Some(path) = bundle_path(&self.url) {
+            return path
+                .file_stem()
+                .map(PathBuf::from)
+                .ok_or_else(|| anyhow::anyhow!("Could not determine repository name from bundle"));
+        }
+
         // Extract name from URL
         // e.g., http://localhost:3000/my-project -> my-project
         // Note: URLs always use forward slashes per RFC 3986, regardless of OS,
@@ -297,3 +397,75 @@ url = "{}"
         Ok(PathBuf::from(name))
     }
 }
+
+/// A repository on this machine, cloned with `--local`
+struct LocalSource {
+    /// Canonical path of the repository's working directory
+    root: PathBuf,
+
+    /// Where its objects are stored
+    store: LocalBackend,
+}
+
+impl LocalSource {
+    /// Open the repository at `path`, which must keep its objects on this
+    /// machine under plain keys
+    async fn open(path: &str) -> Result<Self> {
+        let root = dunce::canonicalize(path)
+            .with_context(|| format!("Repository '{}' does not exist", path))?;
+        if !root.join(".mediagit").is_dir() {
+            anyhow::bail!("'{}' is not a MediaGit repository", root.display());
+        }
+
+        let config = mediagit_config::Config::load(&root)
+            .await
+            .unwrap_or_default();
+        if config.security.hash_storage_keys || config.security.encryption_at_rest {
+            anyhow::bail!(
+                "--local can't clone a repository that encrypts its storage or hashes its keys"
+            );
+        }
+        let store = local_object_store(&root).await?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "--local needs a repository whose objects are stored on this machine; \
+                 clone it from its server instead"
+            )
+        })?;
+        Ok(Self { root, store })
+    }
+
+    /// Its branches, as a server would advertise them
+    async fn advertised_refs(&self) -> Result<mediagit_protocol::RefsResponse> {
+        let refdb = RefDatabase::new(self.root.join(".mediagit"));
+        let mut refs = Vec::new();
+        for name in refdb.list_branches().await? {
+            let oid = refdb.resolve(&name).await?;
+            refs.push(mediagit_protocol::RefInfo {
+                name,
+                oid: oid.to_hex(),
+                target: None,
+            });
+        }
+        Ok(mediagit_protocol::RefsResponse {
+            refs,
+            capabilities: Vec::new(),
+        })
+    }
+
+    /// Put every object, pack files included, into `target`, returning how
+    /// many were hard-linked and how many had to be copied
+    async fn link_objects(&self, target: &LocalBackend) -> Result<(u64, u64)> {
+        let mut keys = self.store.list_objects("").await?;
+        keys.extend(self.store.list_objects("packs/").await?);
+
+        let (mut linked, mut copied) = (0, 0);
+        for key in &keys {
+            if target.link_from(&self.store, key).await? {
+                linked += 1;
+            } else {
+                copied += 1;
+            }
+        }
+        Ok((linked, copied))
+    }
+}
@@ -15,8 +15,11 @@
 //!
 //! The `commit` command creates a new commit containing the currently staged changes.
 
-use super::super::repo::{create_storage_backend, find_repo_root};
+use super::super::repo::{
+    brotli_settings, compression_tier, create_storage_backend, find_repo_root, object_format,
+};
 use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
 use clap::Parser;
 use mediagit_versioning::{
     Commit, FileMode, Index, ObjectDatabase, Oid, Ref, RefDatabase, Reflog, ReflogEntry, Signature,
@@ -42,6 +45,9 @@ use mediagit_versioning::{
     # Preview what would be committed
     mediagit commit --dry-run
 
+    # Reproducible commit with a fixed timestamp
+    mediagit commit -m \"Generated assets\" --date 2025-01-01T00:00:00Z
+
 SEE ALSO:
     mediagit-add(1), mediagit-status(1), mediagit-log(1), mediagit-amend(1)")]
 pub struct CommitCmd {
@@ -69,7 +75,7 @@ pub struct CommitCmd {
     #[arg(long, value_name = "NAME <EMAIL>")]
     pub author: Option<String>,
 
-    /// Override the commit date
+    /// Override the author and committer date (RFC 3339 or Unix seconds)
     #[arg(long, value_name = "DATE")]
     pub date: Option<String>,
 
@@ -129,7 +135,15 @@ impl CommitCmd {
         // Initialize storage and databases
         let storage_path = repo_root.join(".mediagit");
         let storage = create_storage_backend(&repo_root).await?;
-        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 1000);
+        let config = mediagit_config::Config::load(&repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 1000)
+            .with_min_compress_size(config.compression.min_size as usize)
+            .with_dictionary_compression(config.compression.dictionary)
+            .with_compression_tier(compression_tier(&config))
+            .with_brotli(brotli_settings(&config))
+            .with_object_format(object_format(&config));
         let refdb = RefDatabase::new(&storage_path);
 
         // Load the index
@@ -155,12 +169,11 @@ impl CommitCmd {
         if let Some(parent_oid_val) = &parent_oid {
             // Read parent commit and its tree
             let parent_commit_data = odb.read(parent_oid_val).await?;
-            let parent_commit: mediagit_versioning::Commit =
-                mediagit_versioning::format::deserialize(&parent_commit_data)
-                    .context("Failed to deserialize parent commit")?;
+            let parent_commit = Commit::deserialize(&parent_commit_data)
+                .context("Failed to deserialize parent commit")?;
 
             let parent_tree_data = odb.read(&parent_commit.tree).await?;
-            let parent_tree: Tree = mediagit_versioning::format::deserialize(&parent_tree_data)
+            let parent_tree: Tree = Tree::deserialize(&parent_tree_data)
                 .context("Failed to deserialize parent tree")?;
 
             // Build a set of deleted paths for fast lookup (normalized for cross-platform)
@@ -195,7 +208,7 @@ impl CommitCmd {
             ));
         }
 
-        let tree_bytes = tree.serialize()?;
+        let tree_bytes = tree.serialize_as(odb.object_format())?;
         let tree_oid = odb
             .write(mediagit_versioning::ObjectType::Tree, &tree_bytes)
             .await
@@ -203,10 +216,6 @@ impl CommitCmd {
 
         // Create commit signature
         // Priority: --author CLI flag > MEDIAGIT_AUTHOR_* env vars > config.toml [author] > $USER > defaults
-        let config = mediagit_config::Config::load(&repo_root)
-            .await
-            .unwrap_or_default();
-
         let (author_name, author_email) = if let Some(author_str) = &self.author {
             // Parse "Name <email>" format from --author flag
             if let (Some(lt), Some(gt)) = (author_str.rfind('<'), author_str.rfind('>')) {
@@ -234,23 +243,45 @@ impl CommitCmd {
             (name, email)
         };
 
-        let signature = Signature::now(author_name.clone(), author_email.clone());
+        // Fixed dates make the commit oid reproducible
+        // Author: --date > MEDIAGIT_AUTHOR_DATE > now
+        // Committer: MEDIAGIT_COMMIT_DATE > --date > now
+        let cli_date = self.date.as_deref().map(parse_commit_date).transpose()?;
+        let author_date = match cli_date {
+            Some(date) => Some(date),
+            None => env_commit_date("MEDIAGIT_AUTHOR_DATE")?,
+        };
+        let committer_date = match env_commit_date("MEDIAGIT_COMMIT_DATE")? {
+            Some(date) => Some(date),
+            None => cli_date,
+        };
+        let now = Utc::now();
+        let author = Signature::new(
+            author_name.clone(),
+            author_email.clone(),
+            author_date.unwrap_or(now),
+        );
+        let committer = Signature::new(
+            author_name.clone(),
+            author_email.clone(),
+            committer_date.unwrap_or(now),
+        );
 
         // Create commit object
         let commit = if let Some(parent) = parent_oid {
             Commit::with_parents(
                 tree_oid,
                 vec![parent],
-                signature.clone(),
-                signature,
+                author,
+                committer,
                 message.to_string(),
             )
         } else {
-            Commit::new(tree_oid, signature.clone(), signature, message.to_string())
+            Commit::new(tree_oid, author, committer, message.to_string())
         };
 
         // Serialize and write commit
-        let commit_bytes = commit.serialize()?;
+        let commit_bytes = commit.serialize_as(odb.object_format())?;
         let commit_oid = odb
             .write(mediagit_versioning::ObjectType::Commit, &commit_bytes)
             .await
@@ -335,3 +366,32 @@ impl CommitCmd {
         Ok(())
     }
 }
+
+/// Parse a commit date given as RFC 3339 or Unix seconds
+fn parse_commit_date(value: &str) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<i64>() {
+        return Utc
+            .timestamp_opt(secs, 0)
+            .single()
+            .ok_or_else(|| anyhow::anyhow!("invalid date '{}': timestamp out of range", value));
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "invalid date '{}': expected RFC 3339 (e.g. 2025-01-01T00:00:00Z) or Unix seconds",
+                value
+            )
+        })
+}
+
+/// Read a fixed commit date from an environment variable, if set
+fn env_commit_date(var: &str) -> Result<Option<DateTime<Utc>>> {
+    match std::env::var(var) {
+        Ok(value) if !value.trim().is_empty() => parse_commit_date(&value)
+            .map(Some)
+            .with_context(|| format!("{} is not a valid date", var)),
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,151 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+use super::super::repo::find_repo_root;
+use anyhow::{Context, Result};
+use clap::Parser;
+use console::style;
+use indicatif::HumanBytes;
+use mediagit_compression::{CompressionStrategy, ObjectType, SmartCompressor};
+use mediagit_versioning::CompressionAttributes;
+use serde::Serialize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Bytes read for type detection and the sample compression
+const SAMPLE_SIZE: u64 = 1024 * 1024;
+
+/// Show the compression strategy MediaGit would choose for a file
+///
+/// Nothing is written to the repository.
+#[derive(Parser, Debug)]
+pub struct CompressInfoCmd {
+    /// File to inspect
+    #[arg(value_name = "PATH")]
+    pub path: PathBuf,
+
+    /// Compress the first MiB of the file and report the ratio achieved
+    #[arg(long)]
+    pub sample: bool,
+
+    /// Output format
+    #[arg(long, value_name = "FORMAT", default_value = "text", value_parser = ["text", "json"])]
+    pub format: String,
+}
+
+/// What compress-info found out about a file
+#[derive(Debug, Serialize)]
+struct CompressInfo {
+    path: String,
+    size: u64,
+    object_type: String,
+    /// Strategy the type table and size rules select
+    default_strategy: String,
+    /// `compression=` set for the path in `.mediagitattributes`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attribute_override: Option<String>,
+    /// Strategy used when the file is added
+    strategy: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample: Option<SampleResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct SampleResult {
+    bytes: u64,
+    compressed_bytes: u64,
+    ratio: f64,
+}
+
+impl CompressInfoCmd {
+    pub async fn execute(&self) -> Result<()> {
+        let info = self.inspect()?;
+
+        if self.format == "json" {
+            println!("{}", serde_json::to_string_pretty(&info)?);
+            return Ok(());
+        }
+
+        println!("{} {}", style("File:").bold(), info.path);
+        println!("  Size:        {}", HumanBytes(info.size));
+        println!("  Type:        {}", info.object_type);
+        println!("  Default:     {}", info.default_strategy);
+        if let Some(name) = &info.attribute_override {
+            println!("  Override:    {} (.mediagitattributes)", name);
+        }
+        println!("  Strategy:    {}", style(&info.strategy).green());
+        if let Some(sample) = &info.sample {
+            println!(
+                "  Sample:      {} -> {} ({:.1}% of original)",
+                HumanBytes(sample.bytes),
+                HumanBytes(sample.compressed_bytes),
+                sample.ratio * 100.0
+            );
+        }
+        Ok(())
+    }
+
+    fn inspect(&self) -> Result<CompressInfo> {
+        let file = std::fs::File::open(&self.path)
+            .with_context(|| format!("Cannot open {}", self.path.display()))?;
+        let size = file.metadata()?.len();
+        let mut head = Vec::new();
+        file.take(SAMPLE_SIZE).read_to_end(&mut head)?;
+
+        // The size rules look at the whole file, detection only at its start
+        let object_type = ObjectType::detect(&self.path, &head);
+        let default_strategy =
+            CompressionStrategy::for_object_type_with_size(object_type, size as usize);
+        let attribute_override = self.attribute_override()?;
+        let strategy = attribute_override.unwrap_or(default_strategy);
+
+        let sample = if self.sample {
+            let compressed = SmartCompressor::new().compress_with_strategy(&head, strategy)?;
+            Some(SampleResult {
+                bytes: head.len() as u64,
+                compressed_bytes: compressed.len() as u64,
+                ratio: compressed.len() as f64 / head.len().max(1) as f64,
+            })
+        } else {
+            None
+        };
+
+        Ok(CompressInfo {
+            path: self.path.display().to_string(),
+            size,
+            object_type: format!("{:?}", object_type),
+            default_strategy: default_strategy.name().to_string(),
+            attribute_override: attribute_override.map(|s| s.name().to_string()),
+            strategy: strategy.name().to_string(),
+            sample,
+        })
+    }
+
+    /// Strategy `.mediagitattributes` sets for the file, when inside a repository
+    fn attribute_override(&self) -> Result<Option<CompressionStrategy>> {
+        let Ok(repo_root) = find_repo_root() else {
+            return Ok(None);
+        };
+        let Some(relative) = relative_to(&repo_root, &self.path) else {
+            return Ok(None);
+        };
+        Ok(CompressionAttributes::load(&repo_root)?.strategy_for(&relative))
+    }
+}
+
+/// `path` relative to the repository root, if it lies inside it
+fn relative_to(repo_root: &Path, path: &Path) -> Option<PathBuf> {
+    let absolute = dunce::canonicalize(path).ok()?;
+    let root = dunce::canonicalize(repo_root).ok()?;
+    absolute.strip_prefix(root).ok().map(Path::to_path_buf)
+}
@@ -16,12 +16,56 @@ use anyhow::{Context, Result};
 use clap::Parser;
 use console::style;
 use mediagit_versioning::{
-    resolve_revision, Commit, Index, ObjectDatabase, Oid, RefDatabase, Tree, TreeDiffer,
+    binary_diff, looks_binary, resolve_revision, unified_diff, Commit, Index, ObjectDatabase, Oid,
+    RefDatabase, RenameOptions, TextAttributes, Tree, TreeDiffer, BINARY_SNIFF_LEN,
+    DEFAULT_CONTEXT_LINES,
 };
 use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+/// Where one side of a patch is read from
+enum PatchSide {
+    /// Blob in the object database
+    Object(Oid),
+    /// File in the working tree
+    WorkingTree(PathBuf),
+    /// File does not exist on this side
+    Missing,
+}
+
+/// A changed file to render as a patch: (path, before, after)
+type PatchEntry = (String, PatchSide, PatchSide);
+
+/// Files larger than this are reported as binary instead of diffed, like
+/// git's `core.bigFileThreshold`
+const MAX_TEXT_DIFF_SIZE: u64 = 512 * 1024 * 1024;
+
+/// One side of a patch, read only as far as needed to render it
+enum PatchContent {
+    /// Text to diff line by line
+    Text(Vec<u8>),
+    /// Binary or oversized content, named by its object ID
+    Binary(Oid),
+}
+
+impl PatchContent {
+    fn id(&self) -> Oid {
+        match self {
+            PatchContent::Text(data) => Oid::hash(data),
+            PatchContent::Binary(oid) => *oid,
+        }
+    }
+
+    fn text(&self) -> Option<&[u8]> {
+        match self {
+            PatchContent::Text(data) => Some(data),
+            PatchContent::Binary(_) => None,
+        }
+    }
+}
+
 /// Show changes between commits
 ///
 /// Display differences between commits, commit and working tree, or between
@@ -46,6 +90,9 @@ use std::sync::Arc;
     # Compare with previous commit
     mediagit diff HEAD~1 HEAD
 
+    # Write a patch that `git apply` or `patch -p1` can consume
+    mediagit diff -U3 HEAD~1 HEAD > change.patch
+
 SEE ALSO:
     mediagit-status(1), mediagit-log(1), mediagit-show(1)")]
 pub struct DiffCmd {
@@ -73,7 +120,11 @@ pub struct DiffCmd {
     #[arg(long)]
     pub summary: bool,
 
-    /// Number of context lines
+    /// Output a unified patch instead of a file summary
+    #[arg(short = 'p', long)]
+    pub patch: bool,
+
+    /// Output a unified patch with NUM lines of context (implies --patch)
     #[arg(short = 'U', long, value_name = "NUM")]
     pub unified: Option<usize>,
 
@@ -120,6 +171,32 @@ impl DiffCmd {
         let to_commit = Commit::deserialize(&to_data)
             .context(format!("Failed to deserialize commit {}", to_oid))?;
 
+        if let Some(context) = self.patch_context() {
+            let differ = TreeDiffer::new(odb.clone());
+            let diff = differ
+                .diff_trees(&from_commit.tree, &to_commit.tree)
+                .await
+                .context("Failed to diff trees")?;
+
+            let mut entries: Vec<PatchEntry> = Vec::new();
+            for entry in diff.added {
+                entries.push((entry.name, PatchSide::Missing, PatchSide::Object(entry.oid)));
+            }
+            for entry in diff.modified {
+                entries.push((
+                    entry.path,
+                    PatchSide::Object(entry.source.oid),
+                    PatchSide::Object(entry.target.oid),
+                ));
+            }
+            for entry in diff.deleted {
+                entries.push((entry.name, PatchSide::Object(entry.oid), PatchSide::Missing));
+            }
+            return self
+                .write_patch(&odb, &TextAttributes::default(), entries, context)
+                .await;
+        }
+
         // Display diff header
         println!("{} Comparing commits:", style("📊").cyan().bold());
         println!(
@@ -141,12 +218,33 @@ impl DiffCmd {
         }
 
         let differ = TreeDiffer::new(odb.clone());
-        let diff = differ
+        let mut diff = differ
             .diff_trees(&from_commit.tree, &to_commit.tree)
             .await
             .context("Failed to diff trees")?;
 
-        let total = diff.added.len() + diff.deleted.len() + diff.modified.len();
+        let diff_config = mediagit_config::Config::load(&repo_root).await?.diff;
+        let renames = differ
+            .detect_renames(
+                &mut diff,
+                &RenameOptions {
+                    limit: diff_config.rename_limit,
+                    min_similarity: diff_config.rename_similarity,
+                },
+            )
+            .await
+            .context("Failed to detect renames")?;
+        if let Some(needed) = renames.limit_needed {
+            eprintln!(
+                "{} Rename detection by similarity skipped: too many files. \
+                 Set diff.rename_limit to at least {} for full detection.",
+                style("⚠").yellow(),
+                needed
+            );
+        }
+
+        let total =
+            diff.added.len() + diff.deleted.len() + diff.modified.len() + renames.renamed.len();
         if total == 0 {
             println!("{}", style("No changes between commits").dim());
             return Ok(());
@@ -158,6 +256,15 @@ impl DiffCmd {
         for entry in &diff.modified {
             println!("  {} {}", style("modified:").yellow(), entry.path);
         }
+        for entry in &renames.renamed {
+            println!(
+                "  {}  {} -> {} ({:.0}%)",
+                style("renamed:").cyan(),
+                entry.source.name,
+                entry.target.name,
+                entry.similarity * 100.0
+            );
+        }
         for entry in &diff.deleted {
             println!("  {}    {}", style("deleted:").red(), entry.name);
         }
@@ -165,11 +272,12 @@ impl DiffCmd {
         println!();
         if self.stat || self.summary {
             println!(
-                "{} {} file(s) changed: {} added, {} modified, {} deleted",
+                "{} {} file(s) changed: {} added, {} modified, {} renamed, {} deleted",
                 style("Summary:").bold(),
                 total,
                 diff.added.len(),
                 diff.modified.len(),
+                renames.renamed.len(),
                 diff.deleted.len()
             );
         }
@@ -205,6 +313,7 @@ impl DiffCmd {
 
         // Scan working directory
         let working_files = self.scan_working_directory(repo_root)?;
+        let attributes = TextAttributes::load(repo_root)?;
 
         // Detect changes
         let mut modified = Vec::new();
@@ -217,9 +326,9 @@ impl DiffCmd {
             if !working_files.contains(path) {
                 deleted.push(path.clone());
             } else {
-                // Hash working tree file and compare
+                // Hash working tree file as `add` would store it and compare
                 let working_oid = if let Ok(content) = std::fs::read(&full_path) {
-                    Oid::hash(&content)
+                    Oid::hash(&attributes.clean(path, &content))
                 } else {
                     continue;
                 };
@@ -236,6 +345,24 @@ impl DiffCmd {
             }
         }
 
+        if let Some(context) = self.patch_context() {
+            let mut entries: Vec<PatchEntry> = Vec::new();
+            for path in modified {
+                let old = PatchSide::Object(head_files[&path]);
+                let new = PatchSide::WorkingTree(repo_root.join(&path));
+                entries.push((path.to_string_lossy().into_owned(), old, new));
+            }
+            for path in added {
+                let new = PatchSide::WorkingTree(repo_root.join(&path));
+                entries.push((path.to_string_lossy().into_owned(), PatchSide::Missing, new));
+            }
+            for path in deleted {
+                let old = PatchSide::Object(head_files[&path]);
+                entries.push((path.to_string_lossy().into_owned(), old, PatchSide::Missing));
+            }
+            return self.write_patch(odb, &attributes, entries, context).await;
+        }
+
         // Display results
         let short_head = &head_oid.to_hex()[..7];
         println!(
@@ -317,6 +444,22 @@ impl DiffCmd {
             return Ok(());
         }
 
+        if let Some(context) = self.patch_context() {
+            let mut entries: Vec<PatchEntry> = Vec::new();
+            for entry in index.entries() {
+                let old = match head_files.get(&entry.path) {
+                    Some(head_oid) if *head_oid == entry.oid => continue,
+                    Some(head_oid) => PatchSide::Object(*head_oid),
+                    None => PatchSide::Missing,
+                };
+                let path = entry.path.to_string_lossy().into_owned();
+                entries.push((path, old, PatchSide::Object(entry.oid)));
+            }
+            return self
+                .write_patch(odb, &TextAttributes::default(), entries, context)
+                .await;
+        }
+
         let short_head = &head_oid.to_hex()[..7];
         println!(
             "{} Diff: index vs HEAD ({})",
@@ -354,6 +497,111 @@ impl DiffCmd {
         Ok(())
     }
 
+    /// Context lines for patch output, or None for the summary view
+    fn patch_context(&self) -> Option<usize> {
+        self.unified.or(self.patch.then_some(DEFAULT_CONTEXT_LINES))
+    }
+
+    /// Render changed files as a unified patch on stdout
+    ///
+    /// Files are emitted in path order and limited to `paths` when given.
+    /// Working-tree sides are cleaned with `attributes`, as `add` would store them.
+    async fn write_patch(
+        &self,
+        odb: &ObjectDatabase,
+        attributes: &TextAttributes,
+        mut entries: Vec<PatchEntry>,
+        context: usize,
+    ) -> Result<()> {
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut stdout = std::io::stdout().lock();
+        for (path, old, new) in entries {
+            if !self.paths.is_empty()
+                && !self.paths.iter().any(|p| {
+                    let p = p.trim_end_matches('/');
+                    path == p || path.starts_with(&format!("{}/", p))
+                })
+            {
+                continue;
+            }
+
+            let old = Self::read_patch_side(odb, attributes, &path, old).await?;
+            let new = Self::read_patch_side(odb, attributes, &path, new).await?;
+            let patch = match (&old, &new) {
+                (Some(PatchContent::Binary(_)), _) | (_, Some(PatchContent::Binary(_))) => {
+                    binary_diff(
+                        &path,
+                        old.as_ref().map(PatchContent::id),
+                        new.as_ref().map(PatchContent::id),
+                    )
+                }
+                _ => unified_diff(
+                    &path,
+                    old.as_ref().and_then(PatchContent::text),
+                    new.as_ref().and_then(PatchContent::text),
+                    context,
+                ),
+            };
+            stdout.write_all(&patch)?;
+        }
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /// Load one side of a patch, stopping at a prefix when it is binary
+    ///
+    /// Chunked objects are checked by their manifest size and first chunk;
+    /// other objects were written from memory and are read whole.
+    async fn read_patch_side(
+        odb: &ObjectDatabase,
+        attributes: &TextAttributes,
+        path: &str,
+        side: PatchSide,
+    ) -> Result<Option<PatchContent>> {
+        match side {
+            PatchSide::Object(oid) => {
+                if let Some(manifest) = odb.get_chunk_manifest(&oid).await? {
+                    let head = match manifest.chunks.first() {
+                        Some(chunk) => odb.get_chunk(&chunk.id).await?,
+                        None => Vec::new(),
+                    };
+                    if manifest.total_size > MAX_TEXT_DIFF_SIZE || looks_binary(&head) {
+                        return Ok(Some(PatchContent::Binary(oid)));
+                    }
+                }
+                let data = odb
+                    .read(&oid)
+                    .await
+                    .with_context(|| format!("Failed to read object {}", oid))?;
+                if looks_binary(&data) {
+                    return Ok(Some(PatchContent::Binary(oid)));
+                }
+                Ok(Some(PatchContent::Text(data)))
+            }
+            PatchSide::WorkingTree(full_path) => {
+                let read_context = || format!("Failed to read {}", full_path.display());
+                let mut file = std::fs::File::open(&full_path).with_context(read_context)?;
+                let size = file.metadata().with_context(read_context)?.len();
+                let mut head = Vec::with_capacity(BINARY_SNIFF_LEN);
+                (&mut file)
+                    .take(BINARY_SNIFF_LEN as u64)
+                    .read_to_end(&mut head)
+                    .with_context(read_context)?;
+                if size > MAX_TEXT_DIFF_SIZE || looks_binary(&head) {
+                    let oid = Oid::from_file(&full_path).with_context(read_context)?;
+                    return Ok(Some(PatchContent::Binary(oid)));
+                }
+
+                let mut data = head;
+                file.read_to_end(&mut data).with_context(read_context)?;
+                let data = attributes.clean(Path::new(path), &data).into_owned();
+                Ok(Some(PatchContent::Text(data)))
+            }
+            PatchSide::Missing => Ok(None),
+        }
+    }
+
     async fn resolve_commits(
         &self,
         refdb: &RefDatabase,
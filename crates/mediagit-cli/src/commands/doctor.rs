@@ -0,0 +1,134 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+use super::super::repo::{create_storage_backend, find_repo_root};
+use anyhow::{Context, Result};
+use clap::Parser;
+use console::style;
+use mediagit_storage::{BackendCapabilities, CheckStatus, HealthReport};
+
+/// Check that the configured storage backend is reachable and usable
+///
+/// Measures the round trip to the backend, then lists, writes, reads back
+/// and deletes a sentinel object, reporting each step separately so missing
+/// permissions are easy to spot.
+#[derive(Parser, Debug)]
+pub struct DoctorCmd {
+    /// Only report problems
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Also show the backend's optional features
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+impl DoctorCmd {
+    pub async fn execute(&self) -> Result<()> {
+        let repo_root = find_repo_root()?;
+        let config = mediagit_config::Config::load(&repo_root)
+            .await
+            .unwrap_or_default();
+        let backend = config.storage.backend_name();
+
+        let storage = create_storage_backend(&repo_root)
+            .await
+            .with_context(|| format!("Failed to open {} storage backend", backend))?;
+        let report = storage.check_health().await;
+
+        if !self.quiet {
+            println!(
+                "{} Checking {} storage backend",
+                style("🩺").cyan().bold(),
+                style(backend).bold()
+            );
+            println!();
+        }
+        self.print_checks(&report);
+        if self.verbose {
+            println!();
+            print_capabilities(&storage.capabilities());
+        }
+
+        if !report.is_healthy() {
+            let denied: Vec<_> = report.denied().map(|check| check.name).collect();
+            if !denied.is_empty() {
+                println!();
+                println!(
+                    "{} The credentials lack permission to {}",
+                    style("hint:").yellow().bold(),
+                    denied.join(", ")
+                );
+            }
+            anyhow::bail!("The {} storage backend is not healthy", backend);
+        }
+
+        if !self.quiet {
+            println!();
+            println!(
+                "{} The {} storage backend is healthy",
+                style("✅").green().bold(),
+                backend
+            );
+        }
+        Ok(())
+    }
+
+    fn print_checks(&self, report: &HealthReport) {
+        for check in &report.checks {
+            if self.quiet && check.passed() {
+                continue;
+            }
+            let status = match check.status {
+                CheckStatus::Passed => style(check.status.to_string()).green(),
+                CheckStatus::Skipped => style(check.status.to_string()).dim(),
+                CheckStatus::Failed | CheckStatus::PermissionDenied => {
+                    style(check.status.to_string()).red().bold()
+                }
+            };
+            if check.status == CheckStatus::Skipped {
+                println!("  {:<8} {}", check.name, status);
+            } else {
+                println!(
+                    "  {:<8} {} ({} ms)",
+                    check.name,
+                    status,
+                    check.elapsed.as_millis()
+                );
+            }
+            if let Some(error) = &check.error {
+                println!("           {}", style(error).dim());
+            }
+        }
+    }
+}
+
+fn print_capabilities(capabilities: &BackendCapabilities) {
+    let features = [
+        ("memory mapping", capabilities.mmap),
+        ("server-side copy", capabilities.server_side_copy),
+        ("conditional writes", capabilities.compare_and_swap),
+        ("expiry", capabilities.ttl),
+        ("range reads", capabilities.range_reads),
+        ("streaming", capabilities.streaming),
+    ];
+    println!("{} Features:", style("📊").cyan().bold());
+    for (name, supported) in features {
+        let mark = if supported {
+            style("yes").green()
+        } else {
+            style("no").dim()
+        };
+        println!("  • {}: {}", name, mark);
+    }
+}
@@ -0,0 +1,121 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Write a commit's files to a plain directory.
+//!
+//! The export carries no repository metadata, so render nodes and other
+//! consumers can read it directly. Rerunning an interrupted export only
+//! writes the files that are still missing or incomplete.
+
+use super::super::repo::{create_storage_backend, find_repo_root, tree_limits};
+use anyhow::{Context, Result};
+use clap::Parser;
+use console::style;
+use mediagit_versioning::{
+    resolve_revision, CheckoutManager, ObjectDatabase, RefDatabase, TextAttributes,
+};
+use std::path::PathBuf;
+
+/// Write a commit's files to a directory without repository metadata
+#[derive(Parser, Debug)]
+#[command(after_help = "EXAMPLES:
+    # Export the current commit for a render farm
+    mediagit export HEAD /mnt/farm/shot_010
+
+    # Export only some directories of a tagged release
+    mediagit export v1.0 /mnt/farm/v1.0 renders/ textures/
+
+    # Resume an interrupted export by running it again
+    mediagit export v1.0 /mnt/farm/v1.0 renders/ textures/
+
+SEE ALSO:
+    mediagit-clone(1), mediagit-bundle(1)")]
+pub struct ExportCmd {
+    /// Commit, branch, tag or revision to export
+    #[arg(value_name = "COMMIT")]
+    pub commit: String,
+
+    /// Directory to write the files to (created if missing)
+    #[arg(value_name = "DIR")]
+    pub directory: PathBuf,
+
+    /// Export only these files and directories
+    #[arg(value_name = "PATHS")]
+    pub paths: Vec<String>,
+
+    /// Quiet mode
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+impl ExportCmd {
+    pub async fn execute(&self) -> Result<()> {
+        let repo_root = find_repo_root()?;
+        let storage = create_storage_backend(&repo_root).await?;
+        let refdb = RefDatabase::new(repo_root.join(".mediagit"));
+        let config = mediagit_config::Config::load(&repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = ObjectDatabase::with_smart_compression(storage, 1000)
+            .with_tree_limits(tree_limits(&config));
+
+        // Tags are what usually gets handed to a farm, so accept them too
+        let commit_oid = match resolve_revision(&self.commit, &refdb, &odb).await {
+            Ok(oid) => oid,
+            Err(e) => refdb
+                .resolve(&format!("refs/tags/{}", self.commit))
+                .await
+                .map_err(|_| e)
+                .with_context(|| format!("Cannot resolve '{}'", self.commit))?,
+        };
+
+        if self.directory.join(".mediagit").exists() {
+            anyhow::bail!(
+                "{} is a repository; export writes to a plain directory",
+                self.directory.display()
+            );
+        }
+        std::fs::create_dir_all(&self.directory)
+            .with_context(|| format!("Failed to create {}", self.directory.display()))?;
+
+        // Line endings follow the source repository's attributes
+        let checkout = CheckoutManager::new(&odb, &self.directory)?
+            .with_attributes(TextAttributes::load(&repo_root)?);
+        let stats = checkout.export_commit(&commit_oid, &self.paths).await?;
+
+        if stats.total_files() == 0 && !self.paths.is_empty() {
+            anyhow::bail!(
+                "No files in {} match {}",
+                self.commit,
+                self.paths.join(", ")
+            );
+        }
+
+        if !self.quiet {
+            println!(
+                "{} Exported {} file(s) from {} to {}",
+                style("✓").green().bold(),
+                stats.files_added + stats.files_modified,
+                &commit_oid.to_hex()[..8],
+                self.directory.display()
+            );
+            if stats.files_unchanged > 0 {
+                println!(
+                    "  {} file(s) were already up to date",
+                    stats.files_unchanged
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -16,7 +16,8 @@
 //! The `fetch` command downloads objects and refs from a remote repository
 //! without integrating them into the local branches.
 
-use super::super::repo::{create_storage_backend, find_repo_root};
+use super::super::repo::{create_storage_backend, find_repo_root, proxy_settings};
+use super::bundle::{advertised_refs, bundle_path, read_bundle};
 use crate::progress::{OperationStats, ProgressTracker};
 use anyhow::Result;
 use clap::Parser;
@@ -38,24 +39,27 @@ use std::time::Instant;
     # Fetch from a specific remote
     mediagit fetch upstream
 
-    # Fetch a specific branch
+    # Fetch a specific branch (also starts tracking it after a single-branch clone)
     mediagit fetch origin main
 
-    # Fetch all branches explicitly
+    # Fetch all branches, ignoring the remote's configured refspecs
     mediagit fetch --all
 
+    # Fetch from a bundle file into refs/remotes/bundle/
+    mediagit fetch update.mgbundle
+
 SEE ALSO:
-    mediagit-pull(1), mediagit-push(1), mediagit-clone(1)")]
+    mediagit-pull(1), mediagit-push(1), mediagit-clone(1), mediagit-bundle(1)")]
 pub struct FetchCmd {
-    /// Remote name (defaults to origin)
+    /// Remote name or bundle file (defaults to origin)
     #[arg(value_name = "REMOTE")]
     pub remote: Option<String>,
 
-    /// Branch to fetch (fetches all branches by default)
+    /// Branch to fetch (defaults to the branches tracked by the remote's refspecs)
     #[arg(value_name = "BRANCH")]
     pub branch: Option<String>,
 
-    /// Fetch all branches from the remote
+    /// Fetch all branches from the remote, including untracked ones
     #[arg(long)]
     pub all: bool,
 
@@ -86,6 +90,21 @@ impl FetchCmd {
         let storage = create_storage_backend(&repo_root).await?;
         let refdb = RefDatabase::new(&storage_path);
 
+        // Load config to get remote URL
+        // A bundle file given in place of a configured remote is tracked as "bundle"
+        let mut config = mediagit_config::Config::load(&repo_root).await?;
+        let (remote, remote_url) = match bundle_path(remote) {
+            Some(path) if !config.remotes.contains_key(remote) => {
+                ("bundle", path.to_string_lossy().into_owned())
+            }
+            _ => (
+                remote,
+                config
+                    .get_remote_url(remote)
+                    .map_err(|e| anyhow::anyhow!("{}", e))?,
+            ),
+        };
+
         if !self.quiet {
             println!(
                 "{} Fetching from {}...",
@@ -94,18 +113,17 @@ impl FetchCmd {
             );
         }
 
-        // Load config to get remote URL
-        let config = mediagit_config::Config::load(&repo_root).await?;
-        let remote_url = config
-            .get_remote_url(remote)
-            .map_err(|e| anyhow::anyhow!("{}", e))?;
-
         if self.verbose {
             println!("  Remote URL: {}", remote_url);
         }
 
         // Initialize protocol client and ODB
-        let client = mediagit_protocol::ProtocolClient::new(remote_url);
+        let bundle = bundle_path(&remote_url)
+            .as_deref()
+            .map(read_bundle)
+            .transpose()?;
+        let client =
+            mediagit_protocol::ProtocolClient::with_proxy(remote_url, &proxy_settings(&config))?;
         let odb = Arc::new(ObjectDatabase::with_smart_compression(
             Arc::clone(&storage),
             1000,
@@ -113,7 +131,10 @@ impl FetchCmd {
 
         // Get remote refs
         let fetch_spinner = progress.spinner("Fetching remote refs...");
-        let remote_refs = client.get_refs().await?;
+        let remote_refs = match &bundle {
+            Some(bundle) => advertised_refs(bundle),
+            None => client.get_refs().await?,
+        };
         fetch_spinner.finish_with_message("Remote refs fetched");
 
         // Filter to branches (refs/heads/*)
@@ -135,9 +156,18 @@ impl FetchCmd {
                 .filter(|r| r.name == full_ref)
                 .copied()
                 .collect()
+        } else if self.all {
+            remote_branches
         } else {
-            // Fetch all branches
+            // Only the branches covered by the remote's refspecs (all, if none are set)
+            let remote_config = config.remotes.get(remote);
             remote_branches
+                .into_iter()
+                .filter(|r| {
+                    let branch_name = r.name.strip_prefix("refs/heads/").unwrap_or(&r.name);
+                    remote_config.is_none_or(|rc| rc.fetches_branch(branch_name))
+                })
+                .collect()
         };
 
         if branches_to_fetch.is_empty() {
@@ -151,6 +181,14 @@ impl FetchCmd {
             }
         }
 
+        // A bundle's objects all arrive at once; its prerequisites must be here
+        if let Some(bundle) = &bundle {
+            let unbundle_pb = progress.spinner("Unpacking bundle...");
+            let written = bundle.unbundle(&odb).await?;
+            unbundle_pb.finish_with_message(format!("Unpacked {} objects", written));
+            stats.objects_received += written as u64;
+        }
+
         // Create refs/remotes/<remote>/ directory if needed
         let remotes_dir = storage_path.join("refs").join("remotes").join(remote);
         std::fs::create_dir_all(&remotes_dir)?;
@@ -194,19 +232,22 @@ impl FetchCmd {
                 .unwrap_or_default();
 
             // Download objects using streaming (memory-efficient, writes directly to ODB)
-            let download_pb = progress.spinner(&format!("Fetching {}...", branch_name));
-            let chunked_oids = client
-                .pull_streaming(&odb, &branch_ref.name, local_have)
-                .await?;
-            download_pb.finish_with_message(format!("Fetched {}", branch_name));
-
-            // Download chunked objects if any
-            if !chunked_oids.is_empty() {
-                let chunks_downloaded = client
-                    .download_chunked_objects(&odb, &chunked_oids, |_, _, _| {})
+            // Bundled objects were all unpacked above
+            if bundle.is_none() {
+                let download_pb = progress.spinner(&format!("Fetching {}...", branch_name));
+                let chunked_oids = client
+                    .pull_streaming(&odb, &branch_ref.name, local_have)
                     .await?;
-                if self.verbose {
-                    println!("    Downloaded {} chunks", chunks_downloaded);
+                download_pb.finish_with_message(format!("Fetched {}", branch_name));
+
+                // Download chunked objects if any
+                if !chunked_oids.is_empty() {
+                    let chunks_downloaded = client
+                        .download_chunked_objects(&odb, &chunked_oids, |_, _, _| {})
+                        .await?;
+                    if self.verbose {
+                        println!("    Downloaded {} chunks", chunks_downloaded);
+                    }
                 }
             }
 
@@ -228,6 +269,26 @@ impl FetchCmd {
             }
         }
 
+        // Fetching a branch by name widens a single-branch remote to include it
+        if let Some(branch) = &self.branch {
+            let branch_name = branch.strip_prefix("refs/heads/").unwrap_or(branch);
+            let widened = config
+                .remotes
+                .get_mut(remote)
+                .is_some_and(|rc| rc.track_branch(remote, branch_name));
+            if widened {
+                config.save(&repo_root)?;
+                if !self.quiet {
+                    println!(
+                        "{} Now tracking {}/{} on fetch",
+                        style("ℹ").blue(),
+                        remote,
+                        branch_name
+                    );
+                }
+            }
+        }
+
         // Prune stale tracking refs if requested
         if self.prune {
             let stale_count = self
@@ -13,11 +13,15 @@
 
 //! File System Check (FSCK) command - Repository integrity verification
 
-use crate::repo::create_storage_backend;
+use crate::repo::{create_storage_backend, tree_limits};
 use anyhow::{Context, Result};
 use clap::Parser;
 use console::style;
-use mediagit_versioning::{FsckChecker, FsckOptions, FsckRepair, IssueSeverity};
+use indicatif::HumanBytes;
+use mediagit_versioning::{
+    CompressionStats, ContentIntegrity, FsckChecker, FsckOptions, FsckRepair, IssueSeverity,
+    RefDatabase,
+};
 
 /// Check repository integrity with comprehensive verification
 ///
@@ -38,6 +42,12 @@ use mediagit_versioning::{FsckChecker, FsckOptions, FsckRepair, IssueSeverity};
     # Quick check (objects and refs only)
     mediagit fsck --quick
 
+    # Fast reachability check without hashing object content
+    mediagit fsck --connectivity-only
+
+    # Verify objects and report where space goes per category
+    mediagit fsck --compression-stats
+
     # Repair mode (fix repairable issues)
     mediagit fsck --repair
 
@@ -65,6 +75,15 @@ pub struct FsckCmd {
     #[arg(long)]
     pub quick: bool,
 
+    /// Only verify every reachable object exists and commits/trees parse
+    /// (skips content hashing)
+    #[arg(long, conflicts_with_all = ["full", "quick"])]
+    pub connectivity_only: bool,
+
+    /// Report compression statistics by object category
+    #[arg(long, conflicts_with = "connectivity_only")]
+    pub compression_stats: bool,
+
     /// Show all objects checked
     #[arg(long)]
     pub all: bool,
@@ -123,7 +142,12 @@ impl FsckCmd {
             .context("Failed to open repository. Is this a MediaGit repository?")?;
 
         // Create FSCK checker
-        let checker = FsckChecker::new(storage.clone());
+        let config = mediagit_config::Config::load(&repo_path)
+            .await
+            .unwrap_or_default();
+        let checker = FsckChecker::new(storage.clone())
+            .with_ref_database(RefDatabase::new(&mediagit_dir))
+            .with_tree_limits(tree_limits(&config));
 
         // Configure options
         let options = self.build_options();
@@ -134,6 +158,7 @@ impl FsckCmd {
             println!("  • Check references: {}", options.check_refs);
             println!("  • Check connectivity: {}", options.check_connectivity);
             println!("  • Check dangling: {}", options.check_dangling);
+            println!("  • Connectivity only: {}", options.connectivity_only);
             if options.max_objects > 0 {
                 println!("  • Max objects: {}", options.max_objects);
             }
@@ -202,7 +227,17 @@ impl FsckCmd {
     }
 
     fn build_options(&self) -> FsckOptions {
-        if self.quick {
+        self.base_options()
+            .with_compression_stats(self.compression_stats)
+    }
+
+    fn base_options(&self) -> FsckOptions {
+        if self.connectivity_only {
+            FsckOptions {
+                verbose: self.verbose,
+                ..FsckOptions::connectivity_only()
+            }
+        } else if self.quick {
             FsckOptions::quick()
         } else if self.full {
             let mut opts = FsckOptions::full();
@@ -230,7 +265,17 @@ impl FsckCmd {
             println!("{} Statistics:", style("📊").cyan().bold());
             println!("  • Objects checked: {}", report.objects_checked);
             println!("  • References checked: {}", report.refs_checked);
+            let content = match report.content_integrity {
+                ContentIntegrity::Verified => style("verified").green(),
+                ContentIntegrity::Partial => style("partially verified").yellow(),
+                ContentIntegrity::NotChecked => style("not checked").yellow(),
+            };
+            println!("  • Content integrity: {}", content);
             println!();
+
+            if let Some(stats) = &report.compression_stats {
+                self.display_compression_stats(stats);
+            }
         }
 
         // Display issues by severity
@@ -325,4 +370,35 @@ impl FsckCmd {
 
         Ok(())
     }
+
+    fn display_compression_stats(&self, stats: &CompressionStats) {
+        println!("{} Compression by category:", style("🗜").cyan().bold());
+
+        let mut categories: Vec<_> = stats.by_category.iter().collect();
+        categories.sort_by_key(|(_, totals)| std::cmp::Reverse(totals.stored_bytes));
+
+        for (category, totals) in categories {
+            let algorithms: Vec<String> = totals
+                .algorithms
+                .iter()
+                .map(|(algorithm, count)| format!("{:?} ×{}", algorithm, count).to_lowercase())
+                .collect();
+            println!(
+                "  • {:<16} {:>5} object(s)  {:>10} → {:>10}  ({:.1}%)  {}",
+                format!("{:?}", category),
+                totals.objects,
+                HumanBytes(totals.logical_bytes).to_string(),
+                HumanBytes(totals.stored_bytes).to_string(),
+                totals.ratio() * 100.0,
+                style(algorithms.join(", ")).dim()
+            );
+        }
+
+        println!(
+            "  • Total: {} → {}",
+            HumanBytes(stats.total_logical_bytes()),
+            HumanBytes(stats.total_stored_bytes())
+        );
+        println!();
+    }
 }
@@ -0,0 +1,94 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+use super::super::repo::find_repo_root;
+use crate::fsmonitor::{self, FsMonitor};
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use console::style;
+
+/// Watch the working tree to speed up status
+///
+/// While the monitor runs, `mediagit status` only re-examines files the
+/// operating system reported as changed since the previous status.
+#[derive(Parser, Debug)]
+#[command(after_help = "EXAMPLES:
+    # Start watching the current repository in the background
+    mediagit fsmonitor start
+
+    # Check whether the monitor is running
+    mediagit fsmonitor status
+
+    # Stop it
+    mediagit fsmonitor stop
+
+SEE ALSO:
+    mediagit-status(1)")]
+pub struct FsmonitorCmd {
+    #[command(subcommand)]
+    pub command: FsmonitorSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum FsmonitorSubcommand {
+    /// Start the monitor in the background
+    Start,
+
+    /// Stop the running monitor
+    Stop,
+
+    /// Show whether the monitor is running
+    Status,
+
+    /// Run the monitor in the foreground (for service managers)
+    Run,
+}
+
+impl FsmonitorCmd {
+    pub async fn execute(&self) -> Result<()> {
+        let repo_root = find_repo_root()?;
+        let repo_root = dunce::canonicalize(&repo_root).unwrap_or(repo_root);
+
+        match self.command {
+            FsmonitorSubcommand::Start => {
+                let started =
+                    tokio::task::spawn_blocking(move || fsmonitor::start(&repo_root)).await??;
+                if started {
+                    println!("{} fsmonitor started", style("✓").green());
+                } else {
+                    println!("{} fsmonitor is already running", style("ℹ").blue());
+                }
+            }
+            FsmonitorSubcommand::Stop => {
+                let stopped =
+                    tokio::task::spawn_blocking(move || fsmonitor::stop(&repo_root)).await??;
+                if stopped {
+                    println!("{} fsmonitor stopped", style("✓").green());
+                } else {
+                    println!("{} fsmonitor is not running", style("ℹ").blue());
+                }
+            }
+            FsmonitorSubcommand::Status => {
+                if FsMonitor::new(&repo_root).is_running() {
+                    println!("fsmonitor is running");
+                } else {
+                    println!("fsmonitor is not running");
+                }
+            }
+            FsmonitorSubcommand::Run => {
+                tokio::task::spawn_blocking(move || fsmonitor::run(&repo_root)).await??;
+            }
+        }
+        Ok(())
+    }
+}
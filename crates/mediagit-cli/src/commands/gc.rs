@@ -12,25 +12,32 @@
 // GNU Affero General Public License for more details.
 
 use crate::progress::ProgressTracker;
-use crate::repo::create_storage_backend;
+use crate::repo::{compression_tier, create_storage_backend, object_format, tree_limits};
 use anyhow::Result;
 use clap::Parser;
 use console::style;
 use dialoguer::Confirm;
+use mediagit_compression::CompressionTier;
+use mediagit_storage::expiry::{ExpiryPolicy, TEMP_PREFIX};
 use mediagit_storage::StorageBackend;
 use mediagit_versioning::{
-    BranchManager, ChunkManifest, Commit, FileMode, Oid, RefDatabase, RefType, Tree,
+    BranchManager, ChunkManifest, Commit, FileMode, GcLock, HistoryRollup, MediaThinner,
+    ObjectDatabase, Oid, RefDatabase, RefRewrite, RefType, Reflog, ReflogEntry, RollupPolicy,
+    ThinRule, Tree,
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
 /// Clean up repository and optimize storage
 #[derive(Parser, Debug)]
 pub struct GcCmd {
     /// Aggressive optimization (includes protected branches)
+    ///
+    /// Also repacks loose objects, compressing them with the archival tier
+    /// (XZ) whatever `compression.tier` says: smallest storage, slowest gc.
     #[arg(long)]
     pub aggressive: bool,
 
@@ -65,6 +72,52 @@ pub struct GcCmd {
     /// Maximum objects per pack file (0 = unlimited)
     #[arg(long, default_value = "0")]
     pub max_pack_size: usize,
+
+    /// Objects to compress concurrently while repacking (overrides pack.threads)
+    #[arg(short = 'j', long = "jobs", value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Memory budget of the delta window in bytes (overrides pack.window_memory)
+    ///
+    /// Objects too large for the budget are packed without a delta. A smaller
+    /// budget gives worse compression but never more memory use.
+    #[arg(long, value_name = "BYTES")]
+    pub window_memory: Option<u64>,
+
+    /// Only prune unreachable data older than this (overrides gc.prune_expire)
+    ///
+    /// A push in progress writes objects before updating refs, so recent
+    /// unreachable data may be about to become reachable. Takes a Git-style
+    /// expiry such as "2.weeks.ago", "1.hour.ago", "now" or "never".
+    #[arg(long, value_name = "EXPIRY")]
+    pub prune: Option<String>,
+
+    /// Retrain the compression dictionary from the repository's small text files
+    ///
+    /// New small text objects are compressed with the new dictionary; objects
+    /// compressed with earlier ones stay readable.
+    #[arg(long)]
+    pub train_dict: bool,
+
+    /// Rewrite history to drop intermediate versions of large files
+    ///
+    /// Follows the `[[gc.thin_media]]` rules in the repository config. Every
+    /// commit is kept, but entries holding a dropped version point at the
+    /// nearest kept one instead, and commits get new IDs. Prints the plan and
+    /// asks for confirmation unless --yes is given; cannot be undone once the
+    /// dropped versions are pruned.
+    #[arg(long)]
+    pub thin_media: bool,
+
+    /// Cut history off below the `[gc.retention]` limits
+    ///
+    /// Commits deeper or older than the configured limits are dropped, and
+    /// the oldest kept commits become roots, like the edge of a shallow
+    /// clone. Kept commits keep their content but get new IDs. Prints the
+    /// plan and asks for confirmation unless --yes is given; cannot be undone
+    /// once the dropped history is pruned.
+    #[arg(long)]
+    pub roll_up: bool,
 }
 
 /// Statistics collected during GC operation
@@ -94,6 +147,12 @@ struct GcStats {
     /// Chunk bytes reclaimed
     chunk_bytes_reclaimed: u64,
 
+    /// Unreachable objects, manifests and chunks kept for the grace period
+    recent_kept: u64,
+
+    /// Temporary objects and leftovers of interrupted uploads removed
+    temp_removed: u64,
+
     /// Time taken for operation
     duration_secs: f64,
 
@@ -174,6 +233,22 @@ impl GcStats {
             );
         }
 
+        if self.recent_kept > 0 {
+            println!(
+                "{:<25} {}",
+                "Kept (grace period):",
+                style(self.recent_kept).yellow()
+            );
+        }
+
+        if self.temp_removed > 0 {
+            println!(
+                "{:<25} {}",
+                "Temporary files removed:",
+                style(self.temp_removed).red()
+            );
+        }
+
         println!("{:<25} {:.2}s", "Time taken:", self.duration_secs);
 
         if !self.errors.is_empty() {
@@ -185,16 +260,25 @@ impl GcStats {
     }
 }
 
+/// Keys deleted per `delete_many` call, matching the S3 DeleteObjects limit
+const DELETE_BATCH_SIZE: usize = 1000;
+
+/// Youngest an upload or temporary object can be and still be swept, however
+/// short the prune grace period, so writes in progress are left alone
+const MIN_ABANDONED_AGE: Duration = Duration::from_secs(60 * 60);
+
 /// Garbage collector for unreferenced objects
 struct GarbageCollector {
     storage: Arc<dyn StorageBackend>,
     odb: mediagit_versioning::ObjectDatabase,
     refdb: RefDatabase,
     branch_mgr: BranchManager,
+    /// Unreachable data younger than this is not pruned
+    prune_grace: Duration,
 }
 
 impl GarbageCollector {
-    fn new(storage: Arc<dyn StorageBackend>, root_path: &Path) -> Self {
+    fn new(storage: Arc<dyn StorageBackend>, root_path: &Path, prune_grace: Duration) -> Self {
         // Create ODB for reading objects (including from pack files)
         let odb =
             mediagit_versioning::ObjectDatabase::with_smart_compression(storage.clone(), 1000);
@@ -204,9 +288,49 @@ impl GarbageCollector {
             odb,
             refdb: RefDatabase::new(root_path),
             branch_mgr: BranchManager::new(root_path),
+            prune_grace,
         }
     }
 
+    /// Drop items whose storage key was written within the grace period
+    ///
+    /// Returns the remaining items and how many were kept back. The time
+    /// comes from [`StorageBackend::head`], which cloud backends answer with
+    /// a HEAD request. An item whose modification time can't be learned is
+    /// kept rather than risk pruning something just written.
+    async fn exclude_recent<T>(&self, items: Vec<T>, key: impl Fn(&T) -> String) -> (Vec<T>, u64) {
+        if self.prune_grace.is_zero() {
+            return (items, 0);
+        }
+
+        let mut expired = Vec::with_capacity(items.len());
+        let mut kept = 0;
+        for item in items {
+            let recent = match self.storage.head(&key(&item)).await {
+                // A modification time in the future also counts as recent
+                Ok(meta) => meta.modified.is_none_or(|modified| {
+                    modified
+                        .elapsed()
+                        .map_or(true, |age| age < self.prune_grace)
+                }),
+                Err(e) => {
+                    debug!("Keeping {}: {}", key(&item), e);
+                    true
+                }
+            };
+            if recent {
+                kept += 1;
+            } else {
+                expired.push(item);
+            }
+        }
+
+        if kept > 0 {
+            debug!("Keeping {} recent items for the grace period", kept);
+        }
+        (expired, kept)
+    }
+
     /// Build reachability graph from all branch refs
     async fn build_reachability_set(&self) -> Result<HashSet<Oid>> {
         info!("Building reachability graph from refs");
@@ -277,7 +401,7 @@ impl GarbageCollector {
             };
 
             // Try to deserialize as commit
-            if let Ok(commit) = mediagit_versioning::format::deserialize::<Commit>(&data) {
+            if let Ok(commit) = Commit::deserialize(&data) {
                 // Traverse tree to mark tree + all blobs as reachable
                 self.traverse_tree(&commit.tree, reachable).await?;
 
@@ -316,7 +440,7 @@ impl GarbageCollector {
             };
 
             // Deserialize tree
-            let tree = match mediagit_versioning::format::deserialize::<Tree>(&data) {
+            let tree = match Tree::deserialize(&data) {
                 Ok(t) => t,
                 Err(e) => {
                     debug!("Failed to deserialize tree {}: {}", tree_oid, e);
@@ -356,8 +480,8 @@ impl GarbageCollector {
                 if hex.len() == 64 {
                     if let Ok(oid) = Oid::from_hex(&hex) {
                         // Get object size
-                        let size = match self.storage.get(&key).await {
-                            Ok(data) => data.len() as u64,
+                        let size = match self.storage.head(&key).await {
+                            Ok(meta) => meta.size,
                             Err(_) => 0,
                         };
                         objects.push((oid, size));
@@ -403,35 +527,38 @@ impl GarbageCollector {
             None
         };
 
-        for (oid, size) in objects {
-            // Use hex OID directly - LocalBackend adds "objects/" and sharding
-            let key = oid.to_hex();
-
+        for batch in objects.chunks(DELETE_BATCH_SIZE) {
             if dry_run {
-                if verbose {
-                    println!("[DRY RUN] Would delete: {} ({} bytes)", oid, size);
+                for (oid, size) in batch {
+                    if verbose {
+                        println!("[DRY RUN] Would delete: {} ({} bytes)", oid, size);
+                    }
+                    stats.objects_deleted += 1;
+                    stats.bytes_reclaimed += size;
                 }
-                stats.objects_deleted += 1;
-                stats.bytes_reclaimed += size;
             } else {
-                match self.storage.delete(&key).await {
-                    Ok(_) => {
-                        if verbose {
-                            println!("Deleted: {} ({} bytes)", oid, size);
-                        }
-                        stats.objects_deleted += 1;
-                        stats.bytes_reclaimed += size;
-                    }
-                    Err(e) => {
-                        let err_msg = format!("Failed to delete {}: {}", oid, e);
+                // Use hex OID directly - LocalBackend adds "objects/" and sharding
+                let keys: Vec<String> = batch.iter().map(|(oid, _)| oid.to_hex()).collect();
+                let failed = self.delete_keys(&keys).await;
+
+                for (oid, size) in batch {
+                    let key = oid.to_hex();
+                    if let Some(error) = failed.get(&key) {
+                        let err_msg = format!("Failed to delete {}: {}", oid, error);
                         warn!("{}", err_msg);
                         stats.errors.push(err_msg);
+                        continue;
+                    }
+                    if verbose {
+                        println!("Deleted: {} ({} bytes)", oid, size);
                     }
+                    stats.objects_deleted += 1;
+                    stats.bytes_reclaimed += size;
                 }
             }
 
             if let Some(ref pb) = progress {
-                pb.inc(1);
+                pb.inc(batch.len() as u64);
             }
         }
 
@@ -474,8 +601,8 @@ impl GarbageCollector {
 
         for key in all_keys {
             if key.starts_with("chunks/") {
-                let size = match self.storage.get(&key).await {
-                    Ok(data) => data.len() as u64,
+                let size = match self.storage.head(&key).await {
+                    Ok(meta) => meta.size,
                     Err(_) => 0,
                 };
                 chunks.push((key, size));
@@ -559,6 +686,21 @@ impl GarbageCollector {
         Ok((orphan_manifest_keys, orphan_chunks))
     }
 
+    /// Delete `keys` in one batch, returning the error for each key that
+    /// couldn't be deleted
+    async fn delete_keys(&self, keys: &[String]) -> HashMap<String, String> {
+        match self.storage.delete_many(keys).await {
+            Ok(failures) => failures
+                .into_iter()
+                .map(|failure| (failure.key, format!("{:#}", failure.error)))
+                .collect(),
+            Err(e) => keys
+                .iter()
+                .map(|key| (key.clone(), e.to_string()))
+                .collect(),
+        }
+    }
+
     /// Delete orphaned manifests and chunks
     async fn delete_chunks_and_manifests(
         &self,
@@ -582,60 +724,67 @@ impl GarbageCollector {
         };
 
         // Delete orphan manifests
-        for key in orphan_manifests {
+        for batch in orphan_manifests.chunks(DELETE_BATCH_SIZE) {
             if dry_run {
-                if verbose {
-                    println!("[DRY RUN] Would delete manifest: {}", key);
+                for key in batch {
+                    if verbose {
+                        println!("[DRY RUN] Would delete manifest: {}", key);
+                    }
+                    stats.manifests_deleted += 1;
                 }
-                stats.manifests_deleted += 1;
             } else {
-                match self.storage.delete(key).await {
-                    Ok(_) => {
-                        if verbose {
-                            println!("Deleted manifest: {}", key);
-                        }
-                        stats.manifests_deleted += 1;
-                    }
-                    Err(e) => {
-                        let err_msg = format!("Failed to delete manifest {}: {}", key, e);
+                let failed = self.delete_keys(batch).await;
+
+                for key in batch {
+                    if let Some(error) = failed.get(key) {
+                        let err_msg = format!("Failed to delete manifest {}: {}", key, error);
                         warn!("{}", err_msg);
                         stats.errors.push(err_msg);
+                        continue;
+                    }
+                    if verbose {
+                        println!("Deleted manifest: {}", key);
                     }
+                    stats.manifests_deleted += 1;
                 }
             }
 
             if let Some(ref pb) = progress {
-                pb.inc(1);
+                pb.inc(batch.len() as u64);
             }
         }
 
         // Delete orphan chunks
-        for (key, size) in orphan_chunks {
+        for batch in orphan_chunks.chunks(DELETE_BATCH_SIZE) {
             if dry_run {
-                if verbose {
-                    println!("[DRY RUN] Would delete chunk: {} ({} bytes)", key, size);
+                for (key, size) in batch {
+                    if verbose {
+                        println!("[DRY RUN] Would delete chunk: {} ({} bytes)", key, size);
+                    }
+                    stats.chunks_deleted += 1;
+                    stats.chunk_bytes_reclaimed += size;
                 }
-                stats.chunks_deleted += 1;
-                stats.chunk_bytes_reclaimed += size;
             } else {
-                match self.storage.delete(key).await {
-                    Ok(_) => {
-                        if verbose {
-                            println!("Deleted chunk: {} ({} bytes)", key, size);
-                        }
-                        stats.chunks_deleted += 1;
-                        stats.chunk_bytes_reclaimed += size;
-                    }
-                    Err(e) => {
-                        let err_msg = format!("Failed to delete chunk {}: {}", key, e);
+                let keys: Vec<String> = batch.iter().map(|(key, _)| key.clone()).collect();
+                let failed = self.delete_keys(&keys).await;
+
+                for (key, size) in batch {
+                    if let Some(error) = failed.get(key) {
+                        let err_msg = format!("Failed to delete chunk {}: {}", key, error);
                         warn!("{}", err_msg);
                         stats.errors.push(err_msg);
+                        continue;
+                    }
+                    if verbose {
+                        println!("Deleted chunk: {} ({} bytes)", key, size);
                     }
+                    stats.chunks_deleted += 1;
+                    stats.chunk_bytes_reclaimed += size;
                 }
             }
 
             if let Some(ref pb) = progress {
-                pb.inc(1);
+                pb.inc(batch.len() as u64);
             }
         }
 
@@ -648,6 +797,248 @@ impl GarbageCollector {
 }
 
 impl GcCmd {
+    /// Print the thinning plan and, once confirmed, rewrite history to follow it
+    ///
+    /// Returns `false` if the user declined.
+    async fn thin_media(
+        &self,
+        storage_path: &Path,
+        storage: Arc<dyn StorageBackend>,
+        config: &mediagit_config::Config,
+    ) -> Result<bool> {
+        let rules: Vec<ThinRule> = config
+            .gc
+            .thin_media
+            .iter()
+            .map(|rule| ThinRule {
+                pattern: rule.pattern.clone(),
+                keep_every: rule.keep_every,
+                keep_last: rule.keep_last,
+            })
+            .collect();
+        if rules.is_empty() {
+            anyhow::bail!(
+                "--thin-media needs at least one [[gc.thin_media]] rule in .mediagit/config.toml"
+            );
+        }
+
+        let odb = ObjectDatabase::with_smart_compression(storage, 10000)
+            .with_tree_limits(tree_limits(config))
+            .with_object_format(object_format(config));
+        let refs = RefDatabase::new(storage_path);
+        let thinner = MediaThinner::new(&odb, &refs, rules);
+
+        println!("{} Planning media thinning...", style("→").cyan());
+        let plan = thinner.plan().await?;
+        for path in &plan.paths {
+            let kept: Vec<String> = path
+                .versions
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| v.keep)
+                .map(|(index, _)| (index + 1).to_string())
+                .collect();
+            println!(
+                "  {}: {} versions, keeping {} ({}), dropping {}",
+                path.path,
+                path.versions.len(),
+                kept.len(),
+                kept.join(", "),
+                path.dropped_count()
+            );
+            if self.verbose {
+                for version in path.versions.iter().filter(|v| !v.keep) {
+                    println!("    drop {}", version.oid);
+                }
+            }
+        }
+        if plan.is_empty() {
+            println!("{} Nothing to thin", style("✓").green());
+            return Ok(true);
+        }
+
+        let summary = format!(
+            "{} versions of {} paths across {} commits",
+            plan.dropped_count(),
+            plan.paths.iter().filter(|p| p.dropped_count() > 0).count(),
+            plan.commit_count()
+        );
+        if self.dry_run {
+            println!("{} Would drop {}", style("ℹ").blue(), summary);
+            return Ok(true);
+        }
+        if !self.yes {
+            let confirmed = Confirm::new()
+                .with_prompt(format!(
+                    "Rewrite history to drop {}? Commits keep their messages but get new IDs.",
+                    summary
+                ))
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                println!("{} GC cancelled by user", style("✗").red());
+                return Ok(false);
+            }
+        }
+
+        let outcome = thinner.apply(&plan).await?;
+        record_rewritten_refs(storage_path, &refs, &outcome.refs, "gc: thin media").await?;
+
+        println!(
+            "{} Rewrote {} commits and moved {} refs",
+            style("✓").green(),
+            outcome.rewritten.len(),
+            outcome.refs.len()
+        );
+        Ok(true)
+    }
+
+    /// Print the rollup plan and, once confirmed, cut history off below it
+    ///
+    /// Returns `false` if the user declined.
+    async fn roll_up(
+        &self,
+        storage_path: &Path,
+        storage: Arc<dyn StorageBackend>,
+        config: &mediagit_config::Config,
+    ) -> Result<bool> {
+        let retention = &config.gc.retention;
+        if !retention.is_enabled() {
+            anyhow::bail!(
+                "--roll-up needs gc.retention.max_depth or gc.retention.max_age in .mediagit/config.toml"
+            );
+        }
+        let cutoff = match retention.max_age()? {
+            Some(age) => Some(chrono::Utc::now() - chrono::Duration::from_std(age)?),
+            None => None,
+        };
+        let policy = RollupPolicy {
+            max_depth: retention.max_depth,
+            cutoff,
+        };
+
+        let odb = ObjectDatabase::with_smart_compression(storage, 10000)
+            .with_tree_limits(tree_limits(config))
+            .with_object_format(object_format(config));
+        let refs = RefDatabase::new(storage_path);
+        let rollup = HistoryRollup::new(&odb, &refs, policy);
+
+        println!("{} Planning history rollup...", style("→").cyan());
+        let plan = rollup.plan().await?;
+        if plan.is_empty() {
+            println!("{} Nothing to roll up", style("✓").green());
+            return Ok(true);
+        }
+        println!(
+            "  Keeping {} commits, rolling up {}",
+            plan.kept_count(),
+            plan.rolled_up
+        );
+        for oid in &plan.boundary {
+            let commit = Commit::read(&odb, oid).await?;
+            println!(
+                "  boundary {} {}",
+                &oid.to_hex()[..8],
+                commit.message.lines().next().unwrap_or("")
+            );
+        }
+        if self.verbose {
+            for blob in &plan.dropped_blobs {
+                println!(
+                    "    drop {} ({})",
+                    blob.oid,
+                    GcStats::format_bytes(blob.size)
+                );
+            }
+        }
+
+        let summary = format!(
+            "{} commits and {} files ({})",
+            plan.rolled_up,
+            plan.dropped_blobs.len(),
+            GcStats::format_bytes(plan.dropped_bytes())
+        );
+        if self.dry_run {
+            println!("{} Would roll up {}", style("ℹ").blue(), summary);
+            return Ok(true);
+        }
+        if !self.yes {
+            let confirmed = Confirm::new()
+                .with_prompt(format!(
+                    "Roll up {}? Kept commits get new IDs and older history is lost.",
+                    summary
+                ))
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                println!("{} GC cancelled by user", style("✗").red());
+                return Ok(false);
+            }
+        }
+
+        let outcome = rollup.apply(&plan).await?;
+        record_rewritten_refs(storage_path, &refs, &outcome.refs, "gc: roll up").await?;
+
+        println!(
+            "{} Rolled up {} commits; rewrote {} and moved {} refs",
+            style("✓").green(),
+            plan.rolled_up,
+            outcome.rewritten.len(),
+            outcome.refs.len()
+        );
+        Ok(true)
+    }
+
+    /// Retrain the repository compression dictionary from `reachable` objects
+    async fn train_dictionary(
+        &self,
+        gc: &GarbageCollector,
+        reachable: &HashSet<Oid>,
+        stats: &mut GcStats,
+    ) {
+        if self.dry_run {
+            if !self.quiet {
+                println!("{} Would train compression dictionary", style("ℹ").blue());
+            }
+            return;
+        }
+        if !self.quiet {
+            println!("{} Training compression dictionary...", style("→").cyan());
+        }
+
+        let mut candidates: Vec<Oid> = reachable.iter().copied().collect();
+        candidates.sort();
+        match gc.odb.train_dictionary(&candidates).await {
+            Ok(Some(dictionary)) => {
+                if !self.quiet {
+                    println!(
+                        "{} Trained compression dictionary {:08x} ({})",
+                        style("✓").green(),
+                        dictionary.id(),
+                        GcStats::format_bytes(dictionary.as_bytes().len() as u64)
+                    );
+                }
+            }
+            Ok(None) => {
+                if !self.quiet {
+                    println!(
+                        "{} Not enough small text files to train a compression dictionary (need {})",
+                        style("ℹ").blue(),
+                        mediagit_versioning::MIN_DICTIONARY_SAMPLES
+                    );
+                }
+            }
+            Err(e) => {
+                if !self.quiet {
+                    println!("{} Dictionary training failed: {}", style("✗").red(), e);
+                }
+                stats
+                    .errors
+                    .push(format!("Dictionary training error: {}", e));
+            }
+        }
+    }
+
     pub async fn execute(&self) -> Result<()> {
         let start = Instant::now();
 
@@ -667,9 +1058,47 @@ impl GcCmd {
 
         // Load storage backend
         let storage_path = repo_root.join(".mediagit");
+
+        // Held until gc returns; a dry run deletes nothing and needs no lock
+        let _lock = if self.dry_run {
+            None
+        } else {
+            Some(GcLock::acquire(&storage_path)?)
+        };
+
+        let config = mediagit_config::Config::load(&repo_root).await?;
+
+        // --prune overrides gc.prune_expire; an expiry of "never" skips pruning
+        let prune_grace = match &self.prune {
+            Some(expiry) => mediagit_config::parse_expiry(expiry)
+                .map_err(|e| anyhow::anyhow!("Invalid --prune: {}", e))?,
+            None => config.gc.prune_grace()?,
+        };
+        let no_prune = self.no_prune || prune_grace.is_none();
+
         let storage = create_storage_backend(&repo_root).await?;
 
-        let gc = GarbageCollector::new(storage.clone(), &storage_path);
+        // History is rewritten first so the dropped data becomes unreachable
+        if self.roll_up
+            && !self
+                .roll_up(&storage_path, storage.clone(), &config)
+                .await?
+        {
+            return Ok(());
+        }
+        if self.thin_media
+            && !self
+                .thin_media(&storage_path, storage.clone(), &config)
+                .await?
+        {
+            return Ok(());
+        }
+
+        let gc = GarbageCollector::new(
+            storage.clone(),
+            &storage_path,
+            prune_grace.unwrap_or_default(),
+        );
         let mut stats = GcStats::default();
 
         // Step 1: Build reachability graph
@@ -695,6 +1124,15 @@ impl GcCmd {
         }
         let unreachable = gc.find_unreachable_objects(&reachable).await?;
         stats.unreachable_objects = unreachable.len() as u64;
+        let (unreachable, recent) = gc
+            .exclude_recent(unreachable, |(oid, _)| oid.to_hex())
+            .await;
+        stats.recent_kept += recent;
+
+        // Train on reachable objects only, before anything is pruned
+        if self.train_dict {
+            self.train_dictionary(&gc, &reachable, &mut stats).await;
+        }
 
         // Even if no unreachable loose objects, still check chunks/manifests
         let has_unreachable_objects = !unreachable.is_empty();
@@ -702,17 +1140,16 @@ impl GcCmd {
         // Calculate total size to reclaim
         let total_size: u64 = unreachable.iter().map(|(_, size)| size).sum();
 
-        // Skip pruning if --no-prune flag is set
-        if self.no_prune {
+        // Skip pruning if --no-prune flag is set (or the expiry is "never")
+        if no_prune {
             if !self.quiet {
                 println!(
-                    "{} Found {} unreachable objects ({}) - skipping prune (--no-prune)",
+                    "{} Found {} unreachable objects ({}) - skipping prune",
                     style("ℹ").blue(),
                     unreachable.len(),
                     GcStats::format_bytes(total_size)
                 );
             }
-            stats.unreachable_objects = unreachable.len() as u64;
             stats.duration_secs = start.elapsed().as_secs_f64();
             stats.print_summary(self.quiet);
             return Ok(());
@@ -784,6 +1221,12 @@ impl GcCmd {
 
         let (orphan_manifests, orphan_chunks) =
             gc.find_orphan_chunks_and_manifests(&reachable).await?;
+        let (orphan_manifests, recent_manifests) =
+            gc.exclude_recent(orphan_manifests, String::clone).await;
+        let (orphan_chunks, recent_chunks) = gc
+            .exclude_recent(orphan_chunks, |(key, _)| key.clone())
+            .await;
+        stats.recent_kept += recent_manifests + recent_chunks;
 
         if orphan_manifests.is_empty() && orphan_chunks.is_empty() {
             if !self.quiet {
@@ -844,6 +1287,35 @@ impl GcCmd {
             }
         }
 
+        // Step 6: Temporary objects and leftovers of interrupted uploads
+        if !self.dry_run {
+            let age = prune_grace.unwrap_or_default().max(MIN_ABANDONED_AGE);
+            let policy = ExpiryPolicy::new()
+                .expire(TEMP_PREFIX, age)
+                .abort_incomplete_uploads_after(age);
+            match storage.sweep_expired(&policy).await {
+                Ok(report) => {
+                    stats.temp_removed = (report.expired.len() + report.incomplete_uploads) as u64;
+                    stats
+                        .errors
+                        .extend(report.failures.iter().map(ToString::to_string));
+                    if self.verbose && stats.temp_removed > 0 {
+                        println!(
+                            "{} Removed {} temporary files",
+                            style("✓").green(),
+                            stats.temp_removed
+                        );
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to sweep temporary files: {:#}", e);
+                    stats
+                        .errors
+                        .push(format!("Failed to sweep temporary files: {:#}", e));
+                }
+            }
+        }
+
         if !has_unreachable_objects && orphan_manifests.is_empty() && orphan_chunks.is_empty() {
             println!(
                 "{} Repository is clean — no unreachable data found.",
@@ -851,17 +1323,45 @@ impl GcCmd {
             );
         }
 
-        // Step 5: Repack loose objects if requested
-        if self.repack {
+        // Step 7: Repack loose objects if requested
+        if self.repack || self.aggressive {
+            // Aggressive gc compresses for long-term storage
+            let tier = if self.aggressive {
+                CompressionTier::Archival
+            } else {
+                compression_tier(&config)
+            };
             if !self.quiet {
-                println!("\n{} Repacking loose objects...", style("→").cyan());
+                match tier {
+                    CompressionTier::Archival => println!(
+                        "\n{} Repacking loose objects with archival compression...",
+                        style("→").cyan()
+                    ),
+                    CompressionTier::Standard => {
+                        println!("\n{} Repacking loose objects...", style("→").cyan())
+                    }
+                }
             }
 
             // Create ODB for repack operation
             use mediagit_versioning::ObjectDatabase;
-            let odb = ObjectDatabase::new(storage.clone(), 1000);
+            use mediagit_versioning::RepackOptions;
+            let odb = match tier {
+                CompressionTier::Standard => ObjectDatabase::new(storage.clone(), 1000),
+                CompressionTier::Archival => {
+                    ObjectDatabase::with_smart_compression(storage.clone(), 1000)
+                        .with_compression_tier(tier)
+                }
+            };
+            let options = RepackOptions {
+                max_objects: self.max_pack_size,
+                remove_loose: !self.dry_run,
+                jobs: self.jobs.unwrap_or(config.pack.threads),
+                window: config.pack.window,
+                window_memory: self.window_memory.unwrap_or(config.pack.window_memory),
+            };
 
-            match odb.repack(self.max_pack_size, !self.dry_run).await {
+            match odb.repack_with(&options).await {
                 Ok(repack_stats) => {
                     if !self.quiet {
                         println!(
@@ -881,6 +1381,12 @@ impl GcCmd {
                                 repack_stats.loose_objects_removed
                             );
                         }
+                        if repack_stats.duplicates_removed > 0 {
+                            println!(
+                                "   Removed {} whole-file copies of chunked objects",
+                                repack_stats.duplicates_removed
+                            );
+                        }
                     }
                 }
                 Err(e) => {
@@ -902,3 +1408,123 @@ impl GcCmd {
         Ok(())
     }
 }
+
+/// Log refs moved by a history rewrite and keep tag metadata in step
+async fn record_rewritten_refs(
+    storage_path: &Path,
+    refs: &RefDatabase,
+    moved: &[RefRewrite],
+    message: &str,
+) -> Result<()> {
+    let reflog = Reflog::new(storage_path);
+    let head_target = refs.read("HEAD").await.ok().and_then(|head| head.target);
+    for moved in moved {
+        let entry = ReflogEntry::now(
+            moved.old_oid,
+            moved.new_oid,
+            "MediaGit",
+            "mediagit@local",
+            message,
+        );
+        reflog.append(&moved.name, &entry).await?;
+        if head_target.as_deref() == Some(moved.name.as_str()) {
+            reflog.append("HEAD", &entry).await?;
+        }
+        if moved.name.starts_with("refs/tags/") {
+            retarget_tag_metadata(storage_path, &moved.name, moved.new_oid)?;
+        }
+    }
+    Ok(())
+}
+
+/// Point an annotated tag's metadata sidecar at the tag's rewritten commit
+fn retarget_tag_metadata(storage_path: &Path, tag_ref: &str, commit: Oid) -> Result<()> {
+    let metadata_path = storage_path.join(format!("{}.meta", tag_ref));
+    let Ok(data) = std::fs::read(&metadata_path) else {
+        return Ok(());
+    };
+    let mut metadata: serde_json::Value = serde_json::from_slice(&data)?;
+    if let Some(fields) = metadata.as_object_mut() {
+        fields.insert(
+            "commit_oid".to_string(),
+            serde_json::Value::String(commit.to_hex()),
+        );
+    }
+    std::fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use mediagit_storage::mock::MockBackend;
+    use mediagit_storage::ObjectMeta;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    /// Reports modification times through `head` only, as cloud backends do
+    #[derive(Debug)]
+    struct HeadOnlyBackend {
+        inner: MockBackend,
+        modified: HashMap<String, SystemTime>,
+    }
+
+    #[async_trait]
+    impl StorageBackend for HeadOnlyBackend {
+        async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+            self.inner.get(key).await
+        }
+
+        async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+            self.inner.put(key, data).await
+        }
+
+        async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+            self.inner.exists(key).await
+        }
+
+        async fn delete(&self, key: &str) -> anyhow::Result<()> {
+            self.inner.delete(key).await
+        }
+
+        async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+            self.inner.list_objects(prefix).await
+        }
+
+        async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+            let size = self.inner.get(key).await?.len() as u64;
+            Ok(ObjectMeta {
+                modified: self.modified.get(key).copied(),
+                ..ObjectMeta::with_size(size)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exclude_recent_uses_head_and_keeps_unknown_ages() {
+        let inner = MockBackend::new();
+        for key in ["old", "new", "undated"] {
+            inner.put(key, b"data").await.unwrap();
+        }
+        let hour = Duration::from_secs(60 * 60);
+        let modified = HashMap::from([
+            ("old".to_string(), SystemTime::now() - 2 * hour),
+            ("new".to_string(), SystemTime::now()),
+        ]);
+        let storage: Arc<dyn StorageBackend> = Arc::new(HeadOnlyBackend { inner, modified });
+        assert_eq!(storage.modified("old").await.unwrap(), None);
+
+        let dir = TempDir::new().unwrap();
+        let gc = GarbageCollector::new(storage, dir.path(), hour);
+        let keys = ["old", "new", "undated", "missing"]
+            .map(String::from)
+            .to_vec();
+        let (expired, kept) = gc.exclude_recent(keys, String::clone).await;
+
+        // Only the key known to be older than the grace period is pruned
+        assert_eq!(expired, ["old"]);
+        assert_eq!(kept, 3);
+    }
+}
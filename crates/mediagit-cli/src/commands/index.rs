@@ -0,0 +1,179 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Maintain the staging index.
+
+use super::super::repo::find_repo_root;
+use crate::fsmonitor::FsMonitor;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use console::style;
+use mediagit_versioning::{Index, Oid, TextAttributes};
+use std::path::{Path, PathBuf};
+
+/// Inspect and maintain the staging index
+#[derive(Parser, Debug)]
+#[command(after_help = "EXAMPLES:
+    # Bring cached file stats up to date after another tool touched the tree
+    mediagit index refresh
+
+SEE ALSO:
+    mediagit-status(1), mediagit-add(1), mediagit-fsck(1)")]
+pub struct IndexCmd {
+    #[command(subcommand)]
+    pub command: IndexSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IndexSubcommand {
+    /// Re-stat the working tree and update cached file stats
+    ///
+    /// Files whose size or modification time changed are re-hashed. When the
+    /// content still matches what is staged, only the cached stats are
+    /// updated; what is staged never changes. Cached working tree hashes kept
+    /// for the filesystem monitor are dropped so the next status examines
+    /// every file again.
+    Refresh {
+        /// Quiet mode
+        #[arg(short, long)]
+        quiet: bool,
+    },
+}
+
+/// What a refresh found
+#[derive(Debug, Default)]
+struct RefreshStats {
+    /// Entries whose stats changed but content did not
+    refreshed: usize,
+    /// Entries whose stats were already current
+    unchanged: usize,
+    /// Files whose content differs from what is staged
+    modified: Vec<PathBuf>,
+    /// Staged files missing from the working tree
+    missing: Vec<PathBuf>,
+}
+
+impl IndexCmd {
+    pub async fn execute(&self) -> Result<()> {
+        match &self.command {
+            IndexSubcommand::Refresh { quiet } => self.refresh(*quiet).await,
+        }
+    }
+
+    async fn refresh(&self, quiet: bool) -> Result<()> {
+        let repo_root = find_repo_root()?;
+        let mut index = Index::load(&repo_root)?;
+        let attributes = TextAttributes::load(&repo_root)?;
+
+        let stats = refresh_index(&repo_root, &mut index, &attributes)?;
+        if stats.refreshed > 0 {
+            index.save(&repo_root)?;
+        }
+        let cache_dropped = FsMonitor::new(&repo_root).discard_status_cache()?;
+
+        if quiet {
+            return Ok(());
+        }
+        println!(
+            "{} Refreshed {} index entr{} ({} already current)",
+            style("✓").green().bold(),
+            stats.refreshed,
+            if stats.refreshed == 1 { "y" } else { "ies" },
+            stats.unchanged
+        );
+        if cache_dropped {
+            println!("  Dropped the fsmonitor status cache");
+        }
+        for path in &stats.modified {
+            println!(
+                "  {} {} changed since it was staged",
+                style("M").yellow(),
+                path.display()
+            );
+        }
+        for path in &stats.missing {
+            println!(
+                "  {} {} is staged but missing",
+                style("D").red(),
+                path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Update the size and mtime of every entry whose content is unchanged
+fn refresh_index(
+    repo_root: &Path,
+    index: &mut Index,
+    attributes: &TextAttributes,
+) -> Result<RefreshStats> {
+    let mut stats = RefreshStats::default();
+    let mut refreshed = Vec::new();
+
+    for entry in index.entries() {
+        let full_path = repo_root.join(&entry.path);
+        let metadata = match std::fs::metadata(&full_path) {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                stats.missing.push(entry.path.clone());
+                continue;
+            }
+        };
+        let size = metadata.len();
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+
+        if entry.size == size && entry.mtime.is_some() && entry.mtime == mtime {
+            stats.unchanged += 1;
+            continue;
+        }
+
+        if working_oid(&full_path, &entry.path, size, attributes)? == entry.oid {
+            let mut updated = entry.clone();
+            updated.size = size;
+            updated.mtime = mtime;
+            refreshed.push(updated);
+        } else {
+            stats.modified.push(entry.path.clone());
+        }
+    }
+
+    stats.refreshed = refreshed.len();
+    for entry in refreshed {
+        index.add_entry(entry);
+    }
+    Ok(stats)
+}
+
+/// Hash a working tree file the way `add` stages it
+fn working_oid(
+    full_path: &Path,
+    path: &Path,
+    size: u64,
+    attributes: &TextAttributes,
+) -> Result<Oid> {
+    // 5MB: matches add.rs STREAMING_THRESHOLD
+    const STREAMING_THRESHOLD: u64 = 5 * 1024 * 1024;
+
+    if size >= STREAMING_THRESHOLD && !attributes.may_convert(path, full_path)? {
+        Oid::from_file(full_path).with_context(|| format!("Failed to hash {}", path.display()))
+    } else {
+        let content = std::fs::read(full_path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Oid::hash(&attributes.clean(path, &content)))
+    }
+}
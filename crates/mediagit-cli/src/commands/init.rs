@@ -173,6 +173,8 @@ impl InitCmd {
                 create_dirs: true,
                 sync: false,
                 file_permissions: "0644".to_string(),
+                max_concurrent_ops: None,
+                alternates: None,
             }),
             ..Config::default()
         };
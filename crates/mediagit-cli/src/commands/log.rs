@@ -355,7 +355,7 @@ impl LogCmd {
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + 'a>> {
         Box::pin(async move {
             let tree_data = odb.read(tree_oid).await?;
-            let tree: Tree = mediagit_versioning::format::deserialize(&tree_data)?;
+            let tree: Tree = Tree::deserialize(&tree_data)?;
 
             for entry in tree.iter() {
                 let entry_path = prefix.join(&entry.name);
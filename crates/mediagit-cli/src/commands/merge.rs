@@ -11,14 +11,17 @@
 // MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
 // GNU Affero General Public License for more details.
 
-use super::super::repo::{create_storage_backend, find_repo_root};
+use super::super::repo::{create_storage_backend, find_repo_root, object_format};
+use super::merge_state::MergeConflicts;
+use super::mergetool::shell_command;
 use anyhow::{Context, Result};
 use clap::Parser;
 use console::style;
 use mediagit_versioning::{
-    CheckoutManager, Commit, MergeEngine, MergeStrategy, ObjectDatabase, ObjectType, Oid, Ref,
-    RefDatabase, Reflog, ReflogEntry, Signature,
+    CheckoutManager, Commit, MergeAttributes, MergeDriver, MergeEngine, MergeStrategy,
+    ObjectDatabase, ObjectType, Oid, Ref, RefDatabase, Reflog, ReflogEntry, Signature,
 };
+use std::path::Path;
 use std::sync::Arc;
 
 /// Merge branches
@@ -115,7 +118,13 @@ impl MergeCmd {
         let storage_path = repo_root.join(".mediagit");
         let storage = create_storage_backend(&repo_root).await?;
         let refdb = RefDatabase::new(&storage_path);
-        let odb = Arc::new(ObjectDatabase::with_smart_compression(storage, 1000));
+        let config = mediagit_config::Config::load(&repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = Arc::new(
+            ObjectDatabase::with_smart_compression(storage, 1000)
+                .with_object_format(object_format(&config)),
+        );
 
         // Resolve branch to OID
         let their_oid = self.resolve_branch(&refdb).await?;
@@ -154,7 +163,8 @@ impl MergeCmd {
         }
 
         // Create merge engine and perform merge
-        let engine = MergeEngine::new(odb.clone());
+        let engine =
+            MergeEngine::new(odb.clone()).with_merge_attributes(MergeAttributes::load(&repo_root)?);
 
         if !self.quiet {
             println!("{} Computing merge...", style("⚙️ ").cyan());
@@ -186,7 +196,7 @@ impl MergeCmd {
                     }
 
                     // Update working directory to match the merged commit (ISS-008 fix)
-                    let checkout_mgr = CheckoutManager::new(&odb, &repo_root);
+                    let checkout_mgr = CheckoutManager::new(&odb, &repo_root)?;
                     checkout_mgr
                         .checkout_commit(&their_oid)
                         .await
@@ -219,11 +229,34 @@ impl MergeCmd {
                     println!("    Type: {:?}", conflict.conflict_type);
                 }
             }
+
+            // Record merge state so mergetool and --continue can pick up from here
+            let message = self
+                .message
+                .clone()
+                .unwrap_or_else(|| format!("Merge branch '{}' into HEAD", self.branch));
+            std::fs::write(storage_path.join("MERGE_HEAD"), their_oid.to_hex())
+                .context("Failed to write MERGE_HEAD")?;
+            std::fs::write(storage_path.join("MERGE_MSG"), message)
+                .context("Failed to write MERGE_MSG")?;
+            MergeConflicts::from_conflicts(&result.conflicts).save(&repo_root)?;
+
             anyhow::bail!(
-                "Automatic merge failed. Fix conflicts and run 'mediagit merge --continue'"
+                "Automatic merge failed. Fix conflicts (or run 'mediagit mergetool') and run 'mediagit merge --continue'"
             );
         }
 
+        if !self.quiet {
+            for (path, driver) in &result.auto_resolved {
+                println!(
+                    "{} Auto-resolved {} (merge={})",
+                    style("✓").green(),
+                    path,
+                    format!("{:?}", driver).to_lowercase()
+                );
+            }
+        }
+
         // No conflicts - create merge commit
         if !self.no_commit {
             let tree_oid = result.tree_oid.context("No merged tree created")?;
@@ -272,7 +305,7 @@ impl MergeCmd {
                 message,
             };
 
-            let commit_data = merge_commit.serialize()?;
+            let commit_data = merge_commit.serialize_as(odb.object_format())?;
             let commit_oid = odb.write(ObjectType::Commit, &commit_data).await?;
 
             // Update HEAD
@@ -285,7 +318,7 @@ impl MergeCmd {
             }
 
             // Update working directory to match the merged commit (ISS-008 fix)
-            let checkout_mgr = CheckoutManager::new(&odb, &repo_root);
+            let checkout_mgr = CheckoutManager::new(&odb, &repo_root)?;
             checkout_mgr
                 .checkout_commit(&commit_oid)
                 .await
@@ -297,6 +330,8 @@ impl MergeCmd {
             let entry = ReflogEntry::now(our_oid, commit_oid, "user", "user@mediagit", &reflog_msg);
             let _ = reflog.append("HEAD", &entry).await;
 
+            self.regenerate(&repo_root, &config, &result.auto_resolved)?;
+
             if !self.quiet {
                 println!(
                     "{} Merge committed: {}",
@@ -311,6 +346,60 @@ impl MergeCmd {
         Ok(())
     }
 
+    /// Run the `[merge] regenerate` command for paths resolved with `merge=regenerate`
+    ///
+    /// Runs once the merged tree is checked out, so the command sees the
+    /// merged working tree; its output is left for the user to review and commit.
+    fn regenerate(
+        &self,
+        repo_root: &Path,
+        config: &mediagit_config::Config,
+        auto_resolved: &[(String, MergeDriver)],
+    ) -> Result<()> {
+        let paths: Vec<&str> = auto_resolved
+            .iter()
+            .filter(|(_, driver)| *driver == MergeDriver::Regenerate)
+            .map(|(path, _)| path.as_str())
+            .collect();
+        if paths.is_empty() {
+            return Ok(());
+        }
+
+        let Some(command) = config.merge.regenerate.as_deref() else {
+            println!(
+                "{} Kept our version of {} file(s) marked merge=regenerate; set [merge] regenerate to rebuild them",
+                style("⚠").yellow(),
+                paths.len()
+            );
+            return Ok(());
+        };
+
+        if !self.quiet {
+            println!(
+                "{} Regenerating {} file(s): {}",
+                style("⚙️ ").cyan(),
+                paths.len(),
+                command
+            );
+        }
+        let status = shell_command(command)
+            .current_dir(repo_root)
+            .env("MEDIAGIT_REGENERATE_PATHS", paths.join("\n"))
+            .status()
+            .with_context(|| format!("Failed to run regenerate command: {}", command))?;
+        if !status.success() {
+            anyhow::bail!("Regenerate command failed ({}): {}", status, command);
+        }
+
+        if !self.quiet {
+            println!(
+                "{} Regenerated files are in the working tree; review and commit them",
+                style("✓").green()
+            );
+        }
+        Ok(())
+    }
+
     async fn resolve_branch(&self, refdb: &RefDatabase) -> Result<Oid> {
         // Try as direct OID
         if let Ok(oid) = Oid::from_hex(&self.branch) {
@@ -367,6 +456,11 @@ impl MergeCmd {
             cleaned += 1;
         }
 
+        if MergeConflicts::state_file(&repo_root).exists() {
+            MergeConflicts::clear(&repo_root)?;
+            cleaned += 1;
+        }
+
         if !self.quiet {
             println!(
                 "{} Merge aborted. Cleaned up {} state file(s).",
@@ -417,7 +511,11 @@ impl MergeCmd {
         }
 
         let storage = create_storage_backend(&repo_root).await?;
-        let odb = mediagit_versioning::ObjectDatabase::with_smart_compression(storage, 1000);
+        let config = mediagit_config::Config::load(&repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = mediagit_versioning::ObjectDatabase::with_smart_compression(storage, 1000)
+            .with_object_format(object_format(&config));
 
         let mut tree = mediagit_versioning::Tree::new();
         for entry in index.entries() {
@@ -476,6 +574,7 @@ impl MergeCmd {
         if merge_mode.exists() {
             std::fs::remove_file(&merge_mode).ok();
         }
+        MergeConflicts::clear(&repo_root).ok();
 
         if !self.quiet {
             println!("{} Merge continued successfully", style("✓").green());
@@ -0,0 +1,159 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Conflict state for an in-progress merge.
+//!
+//! When `merge` stops on conflicts it records, next to `MERGE_HEAD` and
+//! `MERGE_MSG`, the blob of each side of every conflicted path in
+//! `.mediagit/MERGE_CONFLICTS` (JSON). `mergetool` uses it to materialize
+//! the base/ours/theirs versions and drops entries as they are resolved.
+
+use anyhow::{Context, Result};
+use mediagit_versioning::{Conflict, Oid};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// One conflicted path and the blob of each side (None if absent on that side)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConflictEntry {
+    /// Repository-relative path of the conflicted file
+    pub path: String,
+    /// Version in the common ancestor
+    pub base: Option<Oid>,
+    /// Version on the current branch
+    pub ours: Option<Oid>,
+    /// Version on the branch being merged
+    pub theirs: Option<Oid>,
+}
+
+impl From<&Conflict> for ConflictEntry {
+    fn from(conflict: &Conflict) -> Self {
+        Self {
+            path: conflict.path.clone(),
+            base: conflict.base.as_ref().map(|side| side.oid),
+            ours: conflict.ours.as_ref().map(|side| side.oid),
+            theirs: conflict.theirs.as_ref().map(|side| side.oid),
+        }
+    }
+}
+
+/// Unresolved conflicts of the current merge
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeConflicts {
+    /// Conflicted paths still awaiting resolution
+    pub entries: Vec<ConflictEntry>,
+}
+
+impl MergeConflicts {
+    /// Build the conflict state from the conflicts reported by the merge engine.
+    pub fn from_conflicts(conflicts: &[Conflict]) -> Self {
+        Self {
+            entries: conflicts.iter().map(ConflictEntry::from).collect(),
+        }
+    }
+
+    /// Get the path to the conflict state file.
+    pub fn state_file(repo_root: &Path) -> PathBuf {
+        repo_root.join(".mediagit").join("MERGE_CONFLICTS")
+    }
+
+    /// Load the conflict state, returning an empty state if none was recorded.
+    pub fn load(repo_root: &Path) -> Result<Self> {
+        let state_file = Self::state_file(repo_root);
+
+        if !state_file.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&state_file)
+            .context("Failed to read merge conflict state file")?;
+
+        serde_json::from_str(&content).context("Failed to parse merge conflict state file")
+    }
+
+    /// Save the conflict state to disk.
+    pub fn save(&self, repo_root: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)
+            .context("Failed to serialize merge conflict state")?;
+
+        std::fs::write(Self::state_file(repo_root), content)
+            .context("Failed to write merge conflict state file")
+    }
+
+    /// Remove the conflict state file.
+    pub fn clear(repo_root: &Path) -> Result<()> {
+        let state_file = Self::state_file(repo_root);
+
+        if state_file.exists() {
+            std::fs::remove_file(&state_file).context("Failed to remove MERGE_CONFLICTS")?;
+        }
+
+        Ok(())
+    }
+
+    /// Mark a path as resolved, returning true if it was conflicted.
+    pub fn resolve(&mut self, path: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.path != path);
+        self.entries.len() != before
+    }
+
+    /// Check if any conflicts remain.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_merge_conflicts_roundtrip_and_resolve() {
+        let temp = tempdir().unwrap();
+        let repo_root = temp.path();
+        std::fs::create_dir_all(repo_root.join(".mediagit")).unwrap();
+
+        // Nothing recorded yet
+        assert!(MergeConflicts::load(repo_root).unwrap().is_empty());
+
+        let state = MergeConflicts {
+            entries: vec![
+                ConflictEntry {
+                    path: "art/hero.psd".to_string(),
+                    base: Some(Oid::hash(b"base")),
+                    ours: Some(Oid::hash(b"ours")),
+                    theirs: Some(Oid::hash(b"theirs")),
+                },
+                ConflictEntry {
+                    path: "notes.txt".to_string(),
+                    base: None,
+                    ours: Some(Oid::hash(b"ours")),
+                    theirs: Some(Oid::hash(b"theirs")),
+                },
+            ],
+        };
+        state.save(repo_root).unwrap();
+
+        let mut loaded = MergeConflicts::load(repo_root).unwrap();
+        assert_eq!(loaded.entries, state.entries);
+
+        assert!(loaded.resolve("notes.txt"));
+        assert!(!loaded.resolve("notes.txt"));
+        assert_eq!(loaded.entries.len(), 1);
+
+        MergeConflicts::clear(repo_root).unwrap();
+        assert!(!MergeConflicts::state_file(repo_root).exists());
+    }
+}
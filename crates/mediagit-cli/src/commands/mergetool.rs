@@ -0,0 +1,300 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Resolve merge conflicts with an external tool.
+//!
+//! For each conflicted path recorded by `merge`, the base/ours/theirs blobs
+//! are written to a temporary directory and the configured tool is run with
+//! `BASE`, `LOCAL`, `REMOTE` and `MERGED` pointing at them. When the tool
+//! exits successfully the merged file is staged and the path is marked
+//! resolved.
+
+use super::add::AddCmd;
+use super::merge_state::{ConflictEntry, MergeConflicts};
+use crate::output;
+use crate::repo::{create_storage_backend, find_repo_root};
+use anyhow::{Context, Result};
+use clap::Parser;
+use mediagit_compression::ObjectType;
+use mediagit_config::Config;
+use mediagit_versioning::{Index, ObjectDatabase, Oid};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+/// Run a merge tool on conflicted files
+///
+/// Materializes the base, ours and theirs versions of each conflicted file
+/// and runs the configured merge tool on them. Files the tool resolves
+/// (exit status 0) are staged; run 'mediagit merge --continue' afterwards.
+#[derive(Parser, Debug)]
+#[command(after_help = "EXAMPLES:
+    # Resolve all conflicted files with the configured tool
+    mediagit mergetool
+
+    # Resolve a single file
+    mediagit mergetool art/hero.psd
+
+    # Use a one-off tool command
+    mediagit mergetool --tool 'meld \"$LOCAL\" \"$BASE\" \"$REMOTE\" -o \"$MERGED\"'
+
+CONFIGURATION (.mediagit/config.toml):
+    [mergetool]
+    cmd = \"meld \\\"$LOCAL\\\" \\\"$BASE\\\" \\\"$REMOTE\\\" -o \\\"$MERGED\\\"\"
+
+    [mergetool.types]
+    psd = \"psd-merge \\\"$LOCAL\\\" \\\"$REMOTE\\\" \\\"$MERGED\\\"\"
+    text = \"vimdiff \\\"$LOCAL\\\" \\\"$MERGED\\\" \\\"$REMOTE\\\"\"
+
+    Per-type keys are object types (psd, png, json, ...) or categories
+    (image, video, audio, text, ...). The tool runs through the shell with
+    BASE, LOCAL, REMOTE and MERGED set in its environment.
+
+SEE ALSO:
+    mediagit-merge(1), mediagit-add(1)")]
+pub struct MergetoolCmd {
+    /// Conflicted files to resolve (defaults to all)
+    #[arg(value_name = "PATHS")]
+    pub paths: Vec<String>,
+
+    /// Tool command to run instead of the configured one
+    #[arg(short, long, value_name = "COMMAND")]
+    pub tool: Option<String>,
+
+    /// Quiet mode
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+impl MergetoolCmd {
+    pub async fn execute(&self) -> Result<()> {
+        let repo_root = find_repo_root()?;
+        if !repo_root.join(".mediagit").join("MERGE_HEAD").exists() {
+            anyhow::bail!("No merge in progress");
+        }
+
+        let mut conflicts = MergeConflicts::load(&repo_root)?;
+        if conflicts.is_empty() {
+            if !self.quiet {
+                output::info("No files need merging");
+            }
+            return Ok(());
+        }
+
+        let selected = self.select_entries(&conflicts)?;
+        let config = Config::load(&repo_root)
+            .await
+            .context("failed to load config")?;
+        let storage = create_storage_backend(&repo_root).await?;
+        let odb = ObjectDatabase::with_smart_compression(storage, 1000);
+
+        let mut resolved = 0usize;
+        for entry in &selected {
+            let command = self.tool_command(&config, &entry.path)?;
+
+            if !self.quiet {
+                output::progress(&format!("Merging {}", entry.path));
+            }
+
+            let status = run_tool(&odb, &repo_root, entry, &command).await?;
+            if !status.success() {
+                output::warning(&format!(
+                    "Merge tool exited with {} for '{}'; leaving it unresolved",
+                    status, entry.path
+                ));
+                continue;
+            }
+
+            self.stage_resolution(&repo_root, &entry.path).await?;
+            conflicts.resolve(&entry.path);
+            conflicts.save(&repo_root)?;
+            resolved += 1;
+
+            if !self.quiet {
+                output::success(&format!("Resolved {}", entry.path));
+            }
+        }
+
+        if !self.quiet {
+            if conflicts.is_empty() {
+                output::info("All conflicts resolved. Run 'mediagit merge --continue' to commit");
+            } else {
+                output::info(&format!(
+                    "Resolved {} file(s), {} still conflicted",
+                    resolved,
+                    conflicts.entries.len()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pick the conflicts named on the command line, or all of them
+    fn select_entries(&self, conflicts: &MergeConflicts) -> Result<Vec<ConflictEntry>> {
+        if self.paths.is_empty() {
+            return Ok(conflicts.entries.clone());
+        }
+
+        self.paths
+            .iter()
+            .map(|path| {
+                let path = path.replace('\\', "/");
+                let path = path.trim_start_matches("./");
+                conflicts
+                    .entries
+                    .iter()
+                    .find(|entry| entry.path == path)
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("'{}' is not in conflict", path))
+            })
+            .collect()
+    }
+
+    /// Resolve the tool command for a path: --tool, then per-type config, then the default
+    fn tool_command(&self, config: &Config, path: &str) -> Result<String> {
+        if let Some(ref tool) = self.tool {
+            return Ok(tool.clone());
+        }
+
+        let object_type = ObjectType::from_path(path);
+        let type_key = format!("{:?}", object_type).to_lowercase();
+        let category_key = format!("{:?}", object_type.category()).to_lowercase();
+
+        config
+            .mergetool
+            .command_for(&[&type_key, &category_key])
+            .map(str::to_string)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No merge tool configured for '{}'. Set [mergetool] cmd in .mediagit/config.toml or pass --tool",
+                    path
+                )
+            })
+    }
+
+    /// Stage the tool's result, or its deletion if the tool removed the file
+    async fn stage_resolution(&self, repo_root: &Path, path: &str) -> Result<()> {
+        let merged = repo_root.join(path);
+
+        if !merged.exists() {
+            let mut index = Index::load(repo_root)?;
+            index.remove_entry(Path::new(path));
+            index.mark_deleted(PathBuf::from(path));
+            return index.save(repo_root);
+        }
+
+        AddCmd {
+            paths: vec![merged.to_string_lossy().to_string()],
+            all: false,
+            patch: false,
+            dry_run: false,
+            force: true,
+            ignore_removal: false,
+            update: false,
+            quiet: true,
+            verbose: false,
+            no_chunking: false,
+            no_delta: false,
+            no_parallel: true,
+            jobs: None,
+        }
+        .execute()
+        .await
+        .with_context(|| format!("Failed to stage resolved file '{}'", path))
+    }
+}
+
+/// Materialize the three versions of a conflict and run the tool on them
+///
+/// The temporary directory holding BASE/LOCAL/REMOTE is removed when this
+/// returns, whatever the tool's outcome.
+async fn run_tool(
+    odb: &ObjectDatabase,
+    repo_root: &Path,
+    entry: &ConflictEntry,
+    command: &str,
+) -> Result<ExitStatus> {
+    let temp_dir = tempfile::Builder::new()
+        .prefix("mediagit-mergetool-")
+        .tempdir()
+        .context("Failed to create temporary directory for merge tool")?;
+
+    let base = materialize(odb, temp_dir.path(), &entry.path, "BASE", entry.base).await?;
+    let local = materialize(odb, temp_dir.path(), &entry.path, "LOCAL", entry.ours).await?;
+    let remote = materialize(odb, temp_dir.path(), &entry.path, "REMOTE", entry.theirs).await?;
+    let merged = repo_root.join(&entry.path);
+
+    let status = shell_command(command)
+        .current_dir(repo_root)
+        .env("BASE", &base)
+        .env("LOCAL", &local)
+        .env("REMOTE", &remote)
+        .env("MERGED", &merged)
+        .status()
+        .with_context(|| format!("Failed to run merge tool: {}", command))?;
+
+    temp_dir
+        .close()
+        .context("Failed to remove merge tool temporary files")?;
+
+    Ok(status)
+}
+
+/// Write one side of a conflict to `dir` as `<stem>.<LABEL>.<ext>`
+///
+/// A side that does not exist (added or deleted on one branch) is written as
+/// an empty file so tools always receive a valid path.
+async fn materialize(
+    odb: &ObjectDatabase,
+    dir: &Path,
+    path: &str,
+    label: &str,
+    oid: Option<Oid>,
+) -> Result<PathBuf> {
+    let path = Path::new(path);
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let file_name = match path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, label, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, label),
+    };
+    let target = dir.join(file_name);
+
+    match oid {
+        // Streamed chunk by chunk, so large media never sits in memory whole
+        Some(oid) => {
+            odb.read_to_file(&oid, &target).await.with_context(|| {
+                format!("Failed to write {} version of {}", label, path.display())
+            })?;
+        }
+        None => std::fs::write(&target, b"")
+            .with_context(|| format!("Failed to write {}", target.display()))?,
+    }
+
+    Ok(target)
+}
+
+/// Build a command that runs `command` through the platform shell
+pub(crate) fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
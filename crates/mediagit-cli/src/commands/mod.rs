@@ -13,18 +13,31 @@
 
 // Command modules for MediaGit CLI
 pub mod add;
+pub mod apply;
+pub mod benchmark;
 pub mod bisect;
 pub mod branch;
+pub mod bundle;
 pub mod cherrypick;
+pub mod clean;
 pub mod clone;
 pub mod commit;
+pub mod compress_info;
 pub mod diff;
+pub mod doctor;
+pub mod export;
 pub mod fetch;
 pub mod fsck;
+#[cfg(feature = "fsmonitor")]
+pub mod fsmonitor;
 pub mod gc;
+pub mod index;
 pub mod init;
 pub mod log;
 pub mod merge;
+pub mod merge_state;
+pub mod mergetool;
+pub mod mv;
 pub mod pull;
 pub mod push;
 pub mod rebase;
@@ -42,18 +55,30 @@ pub(crate) mod utils;
 pub mod verify;
 
 pub use add::AddCmd;
+pub use apply::ApplyCmd;
+pub use benchmark::BenchmarkCmd;
 pub use bisect::BisectCmd;
 pub use branch::BranchCmd;
+pub use bundle::BundleCmd;
 pub use cherrypick::CherryPickCmd;
+pub use clean::CleanCmd;
 pub use clone::CloneCmd;
 pub use commit::CommitCmd;
+pub use compress_info::CompressInfoCmd;
 pub use diff::DiffCmd;
+pub use doctor::DoctorCmd;
+pub use export::ExportCmd;
 pub use fetch::FetchCmd;
 pub use fsck::FsckCmd;
+#[cfg(feature = "fsmonitor")]
+pub use fsmonitor::FsmonitorCmd;
 pub use gc::GcCmd;
+pub use index::IndexCmd;
 pub use init::InitCmd;
 pub use log::LogCmd;
 pub use merge::MergeCmd;
+pub use mergetool::MergetoolCmd;
+pub use mv::MvCmd;
 pub use pull::PullCmd;
 pub use push::PushCmd;
 pub use rebase::RebaseCmd;
@@ -0,0 +1,341 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Move or rename tracked files.
+//!
+//! The `mv` command moves files in the working tree and stages the move as
+//! an explicit rename in the index, so the next commit carries the same blob
+//! under its new path. Every move is validated before anything changes; if a
+//! filesystem rename or the index update fails, completed moves are undone.
+
+use crate::output;
+use crate::repo::{create_storage_backend, find_repo_root, normalize_path};
+use anyhow::{Context, Result};
+use clap::Parser;
+use mediagit_versioning::{Commit, Index, IndexEntry, ObjectDatabase, RefDatabase, Tree};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Move or rename a file or directory
+///
+/// Moves tracked files in the working tree and stages the move as a rename,
+/// so history records it as one file changing path rather than a deletion
+/// and an unrelated addition.
+#[derive(Parser, Debug)]
+#[command(after_help = "EXAMPLES:
+    # Rename a file
+    mediagit mv hero.psd hero_v2.psd
+
+    # Move files into a directory
+    mediagit mv hero.psd villain.psd characters/
+
+    # Reorganize a whole asset folder
+    mediagit mv textures assets/textures
+
+    # Replace an existing tracked file
+    mediagit mv --force draft.blend final.blend
+
+    # Preview the moves
+    mediagit mv --dry-run textures assets/textures
+
+SEE ALSO:
+    mediagit-status(1), mediagit-add(1), mediagit-commit(1)")]
+pub struct MvCmd {
+    /// Files or directories to move
+    #[arg(value_name = "SOURCE", required = true)]
+    pub sources: Vec<PathBuf>,
+
+    /// New path, or an existing directory to move the sources into
+    #[arg(value_name = "DESTINATION")]
+    pub destination: PathBuf,
+
+    /// Overwrite an existing destination file
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// Show what would be moved
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Quiet mode
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Verbose mode
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+/// One filesystem rename and the tracked files it carries
+struct PlannedMove {
+    source: PathBuf,
+    target: PathBuf,
+    /// (old path, new path) for every tracked file moved
+    files: Vec<(PathBuf, PathBuf)>,
+}
+
+impl MvCmd {
+    pub async fn execute(&self) -> Result<()> {
+        let repo_root = dunce::canonicalize(find_repo_root()?)?;
+        let cwd = std::env::current_dir().context("Failed to get current directory")?;
+
+        let storage = create_storage_backend(&repo_root).await?;
+        let odb = ObjectDatabase::with_smart_compression(storage, 1000);
+        let mut index = Index::load(&repo_root)?;
+        let tracked = tracked_files(&repo_root, &odb, &index).await?;
+
+        let destination = repo_path(&self.destination, &cwd, &repo_root)?;
+        let into_directory = self.sources.len() > 1 || repo_root.join(&destination).is_dir();
+        if self.sources.len() > 1 && !repo_root.join(&destination).is_dir() {
+            anyhow::bail!("Destination '{}' is not a directory", destination.display());
+        }
+
+        // Validate every move before touching the working tree
+        let mut moves = Vec::new();
+        let mut failures = Vec::new();
+        let mut targets = HashSet::new();
+        for source in &self.sources {
+            let planned = repo_path(source, &cwd, &repo_root).and_then(|source| {
+                let target = if into_directory {
+                    let name = source
+                        .file_name()
+                        .with_context(|| format!("Cannot move '{}'", source.display()))?;
+                    destination.join(name)
+                } else {
+                    destination.clone()
+                };
+                self.plan(&repo_root, &tracked, source, target)
+            });
+            match planned {
+                Ok(planned) if !targets.insert(planned.target.clone()) => failures.push(format!(
+                    "{}: multiple sources move to '{}'",
+                    source.display(),
+                    planned.target.display()
+                )),
+                Ok(planned) => moves.push(planned),
+                Err(e) => failures.push(format!("{}: {}", source.display(), e)),
+            }
+        }
+
+        if !failures.is_empty() {
+            for failure in &failures {
+                output::error(failure);
+            }
+            anyhow::bail!("Nothing moved ({} source(s) failed)", failures.len());
+        }
+
+        if self.dry_run {
+            for planned in &moves {
+                for (from, to) in &planned.files {
+                    println!("Would rename {} -> {}", from.display(), to.display());
+                }
+            }
+            return Ok(());
+        }
+
+        let mut done: Vec<&PlannedMove> = Vec::new();
+        for planned in &moves {
+            if let Err(e) = move_path(&repo_root, &planned.source, &planned.target) {
+                undo_moves(&repo_root, &done);
+                return Err(e);
+            }
+            done.push(planned);
+        }
+
+        if let Err(e) = stage_renames(&repo_root, &mut index, &tracked, &moves) {
+            undo_moves(&repo_root, &done);
+            return Err(e);
+        }
+
+        if !self.quiet {
+            let renamed: usize = moves.iter().map(|m| m.files.len()).sum();
+            for planned in &moves {
+                if self.verbose || planned.files.len() == 1 {
+                    for (from, to) in &planned.files {
+                        output::detail(
+                            "renamed",
+                            &format!("{} -> {}", from.display(), to.display()),
+                        );
+                    }
+                } else {
+                    output::detail(
+                        "renamed",
+                        &format!(
+                            "{}/ -> {}/ ({} files)",
+                            planned.source.display(),
+                            planned.target.display(),
+                            planned.files.len()
+                        ),
+                    );
+                }
+            }
+            output::success(&format!("Staged {} rename(s)", renamed));
+        }
+
+        Ok(())
+    }
+
+    /// Check one move and list the tracked files it carries
+    fn plan(
+        &self,
+        repo_root: &Path,
+        tracked: &BTreeMap<PathBuf, IndexEntry>,
+        source: PathBuf,
+        target: PathBuf,
+    ) -> Result<PlannedMove> {
+        let source_full = repo_root.join(&source);
+        let target_full = repo_root.join(&target);
+
+        if source == target {
+            anyhow::bail!("source and destination are the same");
+        }
+        if target.starts_with(&source) {
+            anyhow::bail!("cannot move a directory into itself");
+        }
+
+        let files: Vec<(PathBuf, PathBuf)> = if tracked.contains_key(&source) {
+            if !source_full.is_file() {
+                anyhow::bail!("source does not exist in the working tree");
+            }
+            if target_full.is_dir() {
+                anyhow::bail!("destination '{}' is a directory", target.display());
+            }
+            if target_full.exists() && !self.force {
+                anyhow::bail!(
+                    "destination '{}' exists (use --force to overwrite)",
+                    target.display()
+                );
+            }
+            vec![(source.clone(), target.clone())]
+        } else {
+            let files: Vec<_> = tracked
+                .keys()
+                .filter_map(|path| {
+                    let rest = path.strip_prefix(&source).ok()?;
+                    Some((path.clone(), target.join(rest)))
+                })
+                .collect();
+            if files.is_empty() {
+                anyhow::bail!("not under version control");
+            }
+            if !source_full.is_dir() {
+                anyhow::bail!("source does not exist in the working tree");
+            }
+            if target_full.exists() {
+                anyhow::bail!("destination '{}' already exists", target.display());
+            }
+            files
+        };
+
+        Ok(PlannedMove {
+            source,
+            target,
+            files,
+        })
+    }
+}
+
+/// Files tracked at HEAD plus staged changes, minus staged deletions
+async fn tracked_files(
+    repo_root: &Path,
+    odb: &ObjectDatabase,
+    index: &Index,
+) -> Result<BTreeMap<PathBuf, IndexEntry>> {
+    let mut tracked = BTreeMap::new();
+
+    let refdb = RefDatabase::new(repo_root.join(".mediagit"));
+    if let Ok(head_oid) = refdb.resolve("HEAD").await {
+        let commit = Commit::deserialize(&odb.read(&head_oid).await?)
+            .context("Failed to deserialize HEAD commit")?;
+        let tree = Tree::deserialize(&odb.read(&commit.tree).await?)
+            .context("Failed to deserialize HEAD tree")?;
+        for entry in tree.iter() {
+            let path = PathBuf::from(&entry.name);
+            tracked.insert(
+                path.clone(),
+                IndexEntry::new(path, entry.oid, entry.mode.as_u32(), 0, None),
+            );
+        }
+    }
+
+    for path in index.deleted_paths() {
+        tracked.remove(path);
+    }
+    for entry in index.entries() {
+        tracked.insert(entry.path.clone(), entry.clone());
+    }
+    Ok(tracked)
+}
+
+/// Resolve a command-line path to a repository-relative one
+fn repo_path(path: &Path, cwd: &Path, repo_root: &Path) -> Result<PathBuf> {
+    let relative = normalize_path(&cwd.join(path), repo_root);
+    if relative.is_absolute() || relative.starts_with("..") {
+        anyhow::bail!("'{}' is outside the repository", path.display());
+    }
+    if relative.as_os_str().is_empty() {
+        anyhow::bail!("cannot move the repository root");
+    }
+    if relative.starts_with(".mediagit") {
+        anyhow::bail!("cannot move repository metadata");
+    }
+    Ok(relative)
+}
+
+fn move_path(repo_root: &Path, source: &Path, target: &Path) -> Result<()> {
+    let target_full = repo_root.join(target);
+    if let Some(parent) = target_full.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::rename(repo_root.join(source), &target_full).with_context(|| {
+        format!(
+            "Failed to move {} to {}",
+            source.display(),
+            target.display()
+        )
+    })
+}
+
+/// Put completed moves back, newest first
+fn undo_moves(repo_root: &Path, done: &[&PlannedMove]) {
+    for planned in done.iter().rev() {
+        if let Err(e) = std::fs::rename(
+            repo_root.join(&planned.target),
+            repo_root.join(&planned.source),
+        ) {
+            output::warning(&format!(
+                "Could not restore {}: {}",
+                planned.source.display(),
+                e
+            ));
+        }
+    }
+}
+
+fn stage_renames(
+    repo_root: &Path,
+    index: &mut Index,
+    tracked: &BTreeMap<PathBuf, IndexEntry>,
+    moves: &[PlannedMove],
+) -> Result<()> {
+    for (from, to) in moves.iter().flat_map(|m| &m.files) {
+        let mut entry = tracked[from].clone();
+        entry.path = to.clone();
+        if let Ok(metadata) = std::fs::metadata(repo_root.join(to)) {
+            entry.size = metadata.len();
+        }
+        index.record_rename(from, entry);
+    }
+    index.save(repo_root).context("Failed to save index")
+}
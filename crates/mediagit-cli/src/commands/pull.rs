@@ -11,7 +11,7 @@
 // MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
 // GNU Affero General Public License for more details.
 
-use super::super::repo::{create_storage_backend, find_repo_root};
+use super::super::repo::{create_storage_backend, find_repo_root, object_format, proxy_settings};
 use super::rebase::RebaseCmd;
 use crate::progress::{OperationStats, ProgressTracker};
 use anyhow::{Context, Result};
@@ -46,7 +46,7 @@ use std::time::Instant;
 SEE ALSO:
     mediagit-push(1), mediagit-fetch(1), mediagit-merge(1), mediagit-rebase(1)")]
 pub struct PullCmd {
-    /// Remote name (defaults to origin)
+    /// Remote name (defaults to the branch upstream, then origin)
     #[arg(value_name = "REMOTE")]
     pub remote: Option<String>,
 
@@ -97,14 +97,31 @@ impl PullCmd {
         let mut stats = OperationStats::for_operation("pull");
         let progress = ProgressTracker::new(self.quiet);
 
-        let remote = self.remote.as_deref().unwrap_or("origin");
-
         // Validate repository
         let repo_root = find_repo_root()?;
         let storage_path = repo_root.join(".mediagit");
         let storage = create_storage_backend(&repo_root).await?;
         let refdb = RefDatabase::new(&storage_path);
 
+        // Load config for the remote URL and the current branch's upstream
+        let config = mediagit_config::Config::load(&repo_root).await?;
+
+        // Validate local repository state and read HEAD once
+        let head = refdb.read("HEAD").await.context("Failed to read HEAD")?;
+
+        // Without an explicit remote, pull from the current branch's upstream remote
+        let upstream_remote = head
+            .target
+            .as_deref()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .and_then(|branch| config.get_branch_upstream(branch))
+            .map(|(remote, _)| remote.to_string());
+        let remote = self
+            .remote
+            .as_deref()
+            .or(upstream_remote.as_deref())
+            .unwrap_or("origin");
+
         if self.dry_run && !self.quiet {
             println!("{} Running in dry-run mode", style("ℹ").blue());
         }
@@ -117,9 +134,6 @@ impl PullCmd {
             );
         }
 
-        // Validate local repository state and read HEAD once
-        let head = refdb.read("HEAD").await.context("Failed to read HEAD")?;
-
         if self.verbose {
             println!("  Remote: {}", remote);
             if let Some(branch) = &self.branch {
@@ -132,8 +146,6 @@ impl PullCmd {
             }
         }
 
-        // Load config to get remote URL
-        let config = mediagit_config::Config::load(&repo_root).await?;
         let remote_url = config
             .get_remote_url(remote)
             .map_err(|e| anyhow::anyhow!("{}", e))?;
@@ -143,13 +155,14 @@ impl PullCmd {
         }
 
         // Initialize protocol client
-        let client = mediagit_protocol::ProtocolClient::new(remote_url);
+        let client =
+            mediagit_protocol::ProtocolClient::with_proxy(remote_url, &proxy_settings(&config))?;
 
         // Initialize ODB with smart compression for consistent read/write
-        let odb = Arc::new(mediagit_versioning::ObjectDatabase::with_smart_compression(
-            Arc::clone(&storage),
-            1000,
-        ));
+        let odb = Arc::new(
+            mediagit_versioning::ObjectDatabase::with_smart_compression(Arc::clone(&storage), 1000)
+                .with_object_format(object_format(&config)),
+        );
 
         // Determine remote ref to pull
         // Clone head.target early since we need it later for branch comparison
@@ -452,7 +465,7 @@ impl PullCmd {
                     }
 
                     // Checkout working directory to match new HEAD
-                    let checkout_mgr = CheckoutManager::new(&odb, &repo_root);
+                    let checkout_mgr = CheckoutManager::new(&odb, &repo_root)?;
                     let files_count = checkout_mgr.checkout_commit(&remote_oid_parsed).await?;
                     if self.verbose {
                         println!("  Checked out {} files", files_count);
@@ -519,7 +532,7 @@ impl PullCmd {
                         }
 
                         // Checkout working directory to match merge result
-                        let checkout_mgr = CheckoutManager::new(&odb, &repo_root);
+                        let checkout_mgr = CheckoutManager::new(&odb, &repo_root)?;
                         let files_count = checkout_mgr.checkout_commit(&commit_oid).await?;
                         if self.verbose {
                             println!("  Checked out {} files", files_count);
@@ -556,7 +569,7 @@ impl PullCmd {
                     }
 
                     // Checkout working directory to match new HEAD
-                    let checkout_mgr = CheckoutManager::new(&odb, &repo_root);
+                    let checkout_mgr = CheckoutManager::new(&odb, &repo_root)?;
                     let files_count = checkout_mgr.checkout_commit(&remote_oid_parsed).await?;
                     if self.verbose {
                         println!("  Checked out {} files", files_count);
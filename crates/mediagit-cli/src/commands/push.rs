@@ -11,7 +11,7 @@
 // MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
 // GNU Affero General Public License for more details.
 
-use super::super::repo::{create_storage_backend, find_repo_root};
+use super::super::repo::{create_storage_backend, find_repo_root, proxy_settings, upload_limits};
 use super::utils::validate_ref_name;
 use crate::progress::{OperationStats, ProgressTracker};
 use anyhow::{Context, Result};
@@ -75,7 +75,11 @@ pub struct PushCmd {
     #[arg(short = 'f', long)]
     pub force: bool,
 
-    /// Force with lease (safer)
+    /// Force push, but only over refs that have not moved since last fetch
+    ///
+    /// Each remote branch must still be where its remote-tracking ref says
+    /// it was; the server compares and swaps, so a collaborator's commits
+    /// pushed since are never overwritten.
     #[arg(long)]
     pub force_with_lease: bool,
 
@@ -105,14 +109,35 @@ impl PushCmd {
         let start_time = Instant::now();
         let mut stats = OperationStats::for_operation("push");
 
-        let remote = self.remote.as_deref().unwrap_or("origin");
-
         // Validate repository
         let repo_root = find_repo_root()?;
         let storage_path = repo_root.join(".mediagit");
         let storage = create_storage_backend(&repo_root).await?;
         let refdb = RefDatabase::new(&storage_path);
 
+        // Load config for the remote URL and the current branch's upstream
+        let config = mediagit_config::Config::load(&repo_root).await?;
+
+        // Validate local refs exist and read HEAD once
+        let head = refdb.read("HEAD").await.context("Failed to read HEAD")?;
+
+        if head.oid.is_none() && head.target.is_none() {
+            anyhow::bail!("Nothing to push - no commits yet");
+        }
+
+        // Without an explicit remote, push to the current branch's upstream remote
+        let upstream_remote = head
+            .target
+            .as_deref()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .and_then(|branch| config.get_branch_upstream(branch))
+            .map(|(remote, _)| remote.to_string());
+        let remote = self
+            .remote
+            .as_deref()
+            .or(upstream_remote.as_deref())
+            .unwrap_or("origin");
+
         if self.dry_run && !self.quiet {
             println!("{} Running in dry-run mode", style("ℹ").blue());
         }
@@ -125,13 +150,6 @@ impl PushCmd {
             );
         }
 
-        // Validate local refs exist and read HEAD once
-        let head = refdb.read("HEAD").await.context("Failed to read HEAD")?;
-
-        if head.oid.is_none() && head.target.is_none() {
-            anyhow::bail!("Nothing to push - no commits yet");
-        }
-
         if self.verbose {
             println!("  Remote: {}", remote);
             if !self.refspec.is_empty() {
@@ -142,8 +160,6 @@ impl PushCmd {
             }
         }
 
-        // Load config to get remote URL
-        let config = mediagit_config::Config::load(&repo_root).await?;
         let remote_url = config
             .get_remote_url(remote)
             .map_err(|e| anyhow::anyhow!("{}", e))?;
@@ -153,7 +169,11 @@ impl PushCmd {
         }
 
         // Initialize protocol client
-        let client = mediagit_protocol::ProtocolClient::new(remote_url);
+        let client =
+            mediagit_protocol::ProtocolClient::with_proxy(remote_url, &proxy_settings(&config))?
+                .with_fsck_objects(config.push.fsck_objects)
+                .with_lease(self.force_with_lease)
+                .with_upload_pool(mediagit_storage::shared_upload_pool(upload_limits(&config)));
 
         // Initialize ODB with smart compression for consistent read/write
         let odb =
@@ -199,9 +219,14 @@ impl PushCmd {
                     continue;
                 }
 
+                let old_oid = if self.force_with_lease {
+                    lease_expectation(&refdb, remote, &full_ref, remote_oid).await
+                } else {
+                    remote_oid
+                };
                 updates.push(mediagit_protocol::RefUpdate {
                     name: full_ref,
-                    old_oid: remote_oid,
+                    old_oid,
                     new_oid: String::new(), // ignored for delete
                     delete: true,
                 });
@@ -217,7 +242,9 @@ impl PushCmd {
             // Send delete request directly (no packing/uploading)
             let request = mediagit_protocol::RefUpdateRequest {
                 updates: updates.clone(),
-                force: self.force,
+                force: self.force || self.force_with_lease,
+                lease: self.force_with_lease,
+                capabilities: client.capabilities().to_strings(),
             };
 
             let response = client.update_refs(request).await?;
@@ -348,17 +375,25 @@ impl PushCmd {
                 }
             }
 
+            let old_oid = if self.force_with_lease {
+                lease_expectation(&refdb, remote, ref_to_push, remote_oid).await
+            } else {
+                remote_oid
+            };
             updates.push(mediagit_protocol::RefUpdate {
                 name: ref_to_push.clone(),
-                old_oid: remote_oid,
+                old_oid,
                 new_oid: local_oid_str,
                 delete: false,
             });
         }
 
+        // push.auto_setup_remote makes every push of an untracked branch behave like -u
+        let auto_setup_remote = config.push.auto_setup_remote && !self.no_track;
+
         // BLOCK: Check for new branches without upstream (Git-like behavior)
         // For non-default branches, require explicit -u or --no-track
-        if !self.set_upstream && !self.no_track && self.refspec.is_empty() {
+        if !self.set_upstream && !self.no_track && !auto_setup_remote && self.refspec.is_empty() {
             for update in &updates {
                 // Only check new branches (no old_oid means it doesn't exist on remote)
                 if update.old_oid.is_none() && update.name.starts_with("refs/heads/") {
@@ -378,7 +413,9 @@ impl PushCmd {
                         anyhow::bail!(
                             "The current branch '{}' has no upstream branch.\n\
                             To push the current branch and set the remote as upstream, use:\n\n\
-                            \x20   mediagit push -u {} {}\n",
+                            \x20   mediagit push -u {} {}\n\n\
+                            To have this happen automatically for branches without a tracking\n\
+                            upstream, set 'auto_setup_remote = true' in the [push] config section.",
                             branch_name,
                             remote,
                             branch_name
@@ -388,7 +425,7 @@ impl PushCmd {
             }
         }
 
-        // If all refs are up-to-date, exit early
+        // If all refs are up-to-date, exit early (still recording -u)
         if updates.is_empty() {
             if !self.quiet {
                 println!(
@@ -397,10 +434,23 @@ impl PushCmd {
                     skipped_uptodate
                 );
             }
+            if !self.dry_run {
+                self.record_upstreams(
+                    config,
+                    &repo_root,
+                    remote,
+                    &refs_to_push,
+                    &[],
+                    auto_setup_remote,
+                )?;
+            }
             return Ok(());
         }
 
         if !self.dry_run {
+            // A lease only protects a forced push, so it implies --force
+            let force = self.force || self.force_with_lease;
+
             // Create progress bar for push using ProgressTracker
             let tracker = ProgressTracker::new(self.quiet);
             let pb = if !self.quiet {
@@ -411,7 +461,7 @@ impl PushCmd {
 
             // Push all refs with progress callback
             let (result, push_stats) = client
-                .push_with_progress(&odb, updates.clone(), self.force, |progress| {
+                .push_with_progress(&odb, updates.clone(), force, |progress| {
                     if let Some(ref pb) = pb {
                         let msg = match progress.phase {
                             PushPhase::Collecting => {
@@ -562,51 +612,14 @@ impl PushCmd {
                 }
             }
 
-            // Auto-setup upstream tracking for default branches, or when -u is explicitly used
-            // Non-default branches without -u are blocked before push, so they won't reach here
-            let should_process_upstream = self.set_upstream || !new_branches.is_empty();
-
-            if should_process_upstream && !refs_to_push.is_empty() {
-                let mut config = config; // Make mutable
-                let mut any_upstream_set = false;
-
-                for ref_to_push in &refs_to_push {
-                    // Extract branch name from full ref (e.g., "refs/heads/main" -> "main")
-                    let branch_name = ref_to_push
-                        .strip_prefix("refs/heads/")
-                        .unwrap_or(ref_to_push);
-
-                    // Check if this is a new branch
-                    let is_new_branch = new_branches.contains(&branch_name.to_string());
-                    let has_upstream = config.get_branch_upstream(branch_name).is_some();
-
-                    // Determine if this is a default branch (main or master)
-                    let is_default_branch = branch_name == "main" || branch_name == "master";
-
-                    // Set upstream if:
-                    // 1. -u flag explicitly requested, OR
-                    // 2. New DEFAULT branch (main/master) without existing upstream
-                    if self.set_upstream || (is_new_branch && !has_upstream && is_default_branch) {
-                        config.set_branch_upstream(branch_name, remote, ref_to_push.clone());
-                        any_upstream_set = true;
-
-                        if !self.quiet {
-                            println!(
-                                "{} Branch '{}' set up to track '{}/{}'",
-                                style("ℹ").blue(),
-                                branch_name,
-                                remote,
-                                branch_name
-                            );
-                        }
-                    }
-                }
-
-                // Save the updated config only if we made changes
-                if any_upstream_set {
-                    config.save(&repo_root)?;
-                }
-            }
+            self.record_upstreams(
+                config,
+                &repo_root,
+                remote,
+                &refs_to_push,
+                &new_branches,
+                auto_setup_remote,
+            )?;
         } else if !self.quiet {
             println!("{} Would push {} refs:", style("ℹ").blue(), updates.len());
             for update in &updates {
@@ -641,4 +654,80 @@ impl PushCmd {
 
         Ok(())
     }
+
+    /// Record upstream tracking for pushed branches and save the config
+    ///
+    /// Tracking is set for every branch with -u, for a new default branch
+    /// (main/master) without an upstream, and for any branch without an
+    /// upstream when push.auto_setup_remote is on.
+    fn record_upstreams(
+        &self,
+        mut config: mediagit_config::Config,
+        repo_root: &std::path::Path,
+        remote: &str,
+        refs_to_push: &[String],
+        new_branches: &[String],
+        auto_setup_remote: bool,
+    ) -> Result<()> {
+        let mut any_upstream_set = false;
+
+        for ref_to_push in refs_to_push {
+            // Only branches have upstreams (e.g., "refs/heads/main" -> "main")
+            let Some(branch_name) = ref_to_push.strip_prefix("refs/heads/") else {
+                continue;
+            };
+
+            let is_new_branch = new_branches.iter().any(|b| b == branch_name);
+            let has_upstream = config.get_branch_upstream(branch_name).is_some();
+            let is_default_branch = branch_name == "main" || branch_name == "master";
+
+            let auto_setup =
+                !has_upstream && ((is_new_branch && is_default_branch) || auto_setup_remote);
+            if !self.set_upstream && !auto_setup {
+                continue;
+            }
+
+            config.set_branch_upstream(branch_name, remote, ref_to_push.clone());
+            any_upstream_set = true;
+
+            if !self.quiet {
+                println!(
+                    "{} Branch '{}' set up to track '{}/{}'",
+                    style("ℹ").blue(),
+                    branch_name,
+                    remote,
+                    branch_name
+                );
+            }
+        }
+
+        // Save the updated config only if we made changes
+        if any_upstream_set {
+            config.save(repo_root)?;
+        }
+        Ok(())
+    }
+}
+
+/// Value `ref_name` must still have on the remote for `--force-with-lease`
+///
+/// A branch is expected where its remote-tracking ref says it was at the last
+/// fetch or push, and absent if there is none. Other refs are not tracked, so
+/// the value just read from the remote is used.
+async fn lease_expectation(
+    refdb: &RefDatabase,
+    remote: &str,
+    ref_name: &str,
+    remote_oid: Option<String>,
+) -> Option<String> {
+    let Some(branch) = ref_name.strip_prefix("refs/heads/") else {
+        return remote_oid;
+    };
+    let tracking_ref = format!("refs/remotes/{}/{}", remote, branch);
+    refdb
+        .read(&tracking_ref)
+        .await
+        .ok()
+        .and_then(|r| r.oid)
+        .map(|oid| oid.to_hex())
 }
@@ -20,7 +20,7 @@ use mediagit_versioning::{
 use std::collections::HashSet;
 use std::sync::Arc;
 
-use super::super::repo::{create_storage_backend, find_repo_root};
+use super::super::repo::{create_storage_backend, find_repo_root, object_format};
 use super::rebase_state::RebaseState;
 
 /// Rebase commits
@@ -102,7 +102,13 @@ impl RebaseCmd {
         let storage_path = repo_root.join(".mediagit");
         let storage = create_storage_backend(&repo_root).await?;
         let refdb = RefDatabase::new(&storage_path);
-        let odb = Arc::new(ObjectDatabase::with_smart_compression(storage, 1000));
+        let config = mediagit_config::Config::load(&repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = Arc::new(
+            ObjectDatabase::with_smart_compression(storage, 1000)
+                .with_object_format(object_format(&config)),
+        );
 
         // Resolve upstream branch
         let upstream_oid = self.resolve_branch(&refdb, &self.upstream).await?;
@@ -279,7 +285,7 @@ impl RebaseCmd {
                 message: original_commit.message.clone(),
             };
 
-            let commit_data = new_commit.serialize()?;
+            let commit_data = new_commit.serialize_as(odb.object_format())?;
             let commit_oid = odb.write(ObjectType::Commit, &commit_data).await?;
 
             new_parent = commit_oid;
@@ -418,7 +424,13 @@ impl RebaseCmd {
         let storage_path = repo_root.join(".mediagit");
         let storage = create_storage_backend(repo_root).await?;
         let refdb = RefDatabase::new(&storage_path);
-        let odb = Arc::new(ObjectDatabase::with_smart_compression(storage, 1000));
+        let config = mediagit_config::Config::load(repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = Arc::new(
+            ObjectDatabase::with_smart_compression(storage, 1000)
+                .with_object_format(object_format(&config)),
+        );
 
         // Collect remaining commits to apply
         let remaining_commits = self.load_remaining_commits(&odb, &state).await?;
@@ -11,11 +11,12 @@
 // MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
 // GNU Affero General Public License for more details.
 
-use super::super::repo::find_repo_root;
+use super::super::repo::{find_repo_root, proxy_settings};
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use console::style;
 use mediagit_config::Config;
+use mediagit_storage::ProxySettings;
 
 /// Manage remote repositories
 #[derive(Parser, Debug)]
@@ -282,7 +283,10 @@ impl RemoteCmd {
 
         // Try to fetch remote refs to show HEAD and branches
         let remote_url = remote.url.clone();
-        match self.fetch_remote_info(&remote_url).await {
+        match self
+            .fetch_remote_info(&remote_url, &proxy_settings(&config))
+            .await
+        {
             Ok((head_branch, branches)) => {
                 // Show HEAD branch
                 if let Some(head) = head_branch {
@@ -332,8 +336,12 @@ impl RemoteCmd {
     }
 
     /// Fetch remote info (HEAD branch and list of branches)
-    async fn fetch_remote_info(&self, remote_url: &str) -> Result<(Option<String>, Vec<String>)> {
-        let client = mediagit_protocol::ProtocolClient::new(remote_url.to_string());
+    async fn fetch_remote_info(
+        &self,
+        remote_url: &str,
+        proxy: &ProxySettings,
+    ) -> Result<(Option<String>, Vec<String>)> {
+        let client = mediagit_protocol::ProtocolClient::with_proxy(remote_url, proxy)?;
         let refs = client.get_refs().await?;
 
         // Find HEAD
@@ -26,7 +26,7 @@ use mediagit_versioning::{
 };
 
 use super::super::output;
-use super::super::repo::find_repo_root;
+use super::super::repo::{find_repo_root, tree_limits};
 
 /// Reset current HEAD to the specified state
 #[derive(Parser, Debug)]
@@ -100,7 +100,11 @@ impl ResetCmd {
         mode: ResetMode,
     ) -> Result<()> {
         let storage = create_storage_backend(repo_root).await?;
-        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 10000);
+        let config = mediagit_config::Config::load(repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 10000)
+            .with_tree_limits(tree_limits(&config));
         let refs = RefDatabase::new(storage_path);
         let reflog = Reflog::new(storage_path);
 
@@ -297,7 +301,7 @@ impl ResetCmd {
         odb: &ObjectDatabase,
         commit_oid: &Oid,
     ) -> Result<()> {
-        let checkout_manager = CheckoutManager::new(odb, repo_root);
+        let checkout_manager = CheckoutManager::new(odb, repo_root)?;
         checkout_manager
             .checkout_commit(commit_oid)
             .await
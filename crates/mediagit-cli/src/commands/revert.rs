@@ -26,7 +26,7 @@ use mediagit_versioning::{
 };
 
 use super::super::output;
-use super::super::repo::{create_storage_backend, find_repo_root};
+use super::super::repo::{create_storage_backend, find_repo_root, object_format};
 
 /// Revert commits by creating inverse commits
 #[derive(Parser, Debug)]
@@ -130,7 +130,12 @@ impl RevertCmd {
         }
 
         let storage = create_storage_backend(&repo_root).await?;
-        let odb = Arc::new(ObjectDatabase::new(storage.clone(), 10000));
+        let config = mediagit_config::Config::load(&repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = Arc::new(
+            ObjectDatabase::new(storage.clone(), 10000).with_object_format(object_format(&config)),
+        );
         let refs = RefDatabase::new(&storage_path);
 
         let original_head = refs.resolve("HEAD").await?;
@@ -323,7 +328,12 @@ impl RevertCmd {
         fs::remove_file(&state_file).await?;
 
         let storage = create_storage_backend(repo_root).await?;
-        let odb = Arc::new(ObjectDatabase::new(storage.clone(), 10000));
+        let config = mediagit_config::Config::load(repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = Arc::new(
+            ObjectDatabase::new(storage.clone(), 10000).with_object_format(object_format(&config)),
+        );
         let refs = RefDatabase::new(storage_path);
 
         let index = Index::load(repo_root)?;
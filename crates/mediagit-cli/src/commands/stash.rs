@@ -11,7 +11,7 @@
 // MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
 // GNU Affero General Public License for more details.
 
-use super::super::repo::{create_storage_backend, find_repo_root};
+use super::super::repo::{create_storage_backend, find_repo_root, object_format};
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use console::style;
@@ -162,7 +162,11 @@ impl StashCmd {
         let repo_root = find_repo_root()?;
         let mediagit_dir = repo_root.join(".mediagit");
         let storage = create_storage_backend(&repo_root).await?;
-        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 1000);
+        let config = mediagit_config::Config::load(&repo_root)
+            .await
+            .unwrap_or_default();
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 1000)
+            .with_object_format(object_format(&config));
         let refdb = RefDatabase::new(&mediagit_dir);
 
         // Load index (staged changes)
@@ -299,7 +303,7 @@ impl StashCmd {
         index.save(&repo_root)?;
 
         // Restore to HEAD state
-        let checkout_mgr = CheckoutManager::new(&odb, &repo_root);
+        let checkout_mgr = CheckoutManager::new(&odb, &repo_root)?;
         checkout_mgr.checkout_commit(&current_oid).await?;
 
         if !opts.quiet {
@@ -327,7 +331,7 @@ impl StashCmd {
 
         // Apply stash tree on top of current working directory (overlay, not replace).
         // checkout_commit would wipe files not in the stash tree.
-        let checkout_mgr = CheckoutManager::new(&odb, &repo_root);
+        let checkout_mgr = CheckoutManager::new(&odb, &repo_root)?;
         let files_updated = checkout_mgr.apply_tree_overlay(&stash_oid).await?;
 
         if !opts.quiet {
@@ -11,13 +11,15 @@
 // MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
 // GNU Affero General Public License for more details.
 
+use super::super::progress::{CategoryStats, CompressionStats};
 use super::super::repo::{create_storage_backend, find_repo_root};
 use super::utils::{categorize_extension, format_duration_ago};
 use anyhow::Result;
 use clap::Parser;
 use console::style;
 use indicatif::HumanBytes;
-use mediagit_versioning::{Commit, ObjectDatabase, Oid, RefDatabase, Tree};
+use mediagit_storage::{StorageBackend, StorageUsage};
+use mediagit_versioning::{Commit, CompressionTotals, ObjectDatabase, Oid, RefDatabase, Tree};
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
@@ -48,6 +50,10 @@ pub struct StatsCmd {
     #[arg(long)]
     pub compression: bool,
 
+    /// Show storage reachable only from each branch and tag
+    #[arg(long)]
+    pub by_ref: bool,
+
     /// All statistics
     #[arg(long)]
     pub all: bool,
@@ -87,6 +93,13 @@ struct StorageStats {
     category_stats: std::collections::HashMap<String, (u64, u64)>,
 }
 
+/// What the storage backend reports holding, and the configured quota
+#[derive(Debug, Default)]
+struct BackendUsage {
+    usage: StorageUsage,
+    quota: Option<u64>,
+}
+
 /// Commit history statistics
 #[derive(Debug, Default)]
 struct CommitStats {
@@ -112,6 +125,17 @@ struct FileStats {
     other_files: u64,
 }
 
+/// Storage attributed to one branch or tag
+#[derive(Debug, Default)]
+struct RefStorage {
+    name: String,
+    reachable_objects: u64,
+    reachable_bytes: u64,
+    /// Objects no other ref can reach; deleting the ref frees these
+    exclusive_objects: u64,
+    exclusive_bytes: u64,
+}
+
 impl StatsCmd {
     pub async fn execute(&self) -> Result<()> {
         if self.quiet {
@@ -124,14 +148,29 @@ impl StatsCmd {
         let refdb = RefDatabase::new(&storage_path);
         let odb = ObjectDatabase::with_smart_compression(storage.clone(), 1000);
 
+        // Per-ref breakdown (honours --json itself)
+        if self.by_ref {
+            return self.output_by_ref(&odb, &refdb).await;
+        }
+
         // Handle Prometheus format output
         if self.prometheus {
-            return self.output_prometheus(&storage_path, &odb, &refdb).await;
+            let backend = self
+                .compute_backend_usage(&repo_root, storage.as_ref())
+                .await?;
+            return self
+                .output_prometheus(&storage_path, &backend, &odb, &refdb)
+                .await;
         }
 
         // Handle JSON format output
         if self.json {
-            return self.output_json(&storage_path, &odb, &refdb).await;
+            let backend = self
+                .compute_backend_usage(&repo_root, storage.as_ref())
+                .await?;
+            return self
+                .output_json(&storage_path, &backend, &odb, &refdb)
+                .await;
         }
 
         println!("{} Repository Statistics\n", style("📊").cyan().bold());
@@ -182,6 +221,23 @@ impl StatsCmd {
                 println!("  Storage used: {}", HumanBytes(total_stored));
             }
 
+            let backend = self
+                .compute_backend_usage(&repo_root, storage.as_ref())
+                .await?;
+            println!(
+                "  Backend:       {} in {} objects",
+                HumanBytes(backend.usage.total_bytes),
+                backend.usage.object_count
+            );
+            if let Some(quota) = backend.quota {
+                println!(
+                    "  Quota:         {} of {} ({:.1}% used)",
+                    HumanBytes(backend.usage.total_bytes),
+                    HumanBytes(quota),
+                    backend.usage.total_bytes as f64 / quota as f64 * 100.0
+                );
+            }
+
             if self.verbose {
                 println!(
                     "  Breakdown: loose: {}, packs: {}, chunks: {}, deltas: {}",
@@ -209,6 +265,21 @@ impl StatsCmd {
                     println!("  Cache hit rate: {:.2}%", hit_rate);
                 }
             }
+
+            let category_stats = CategoryStats::load(&storage_path).unwrap_or_default();
+            if !category_stats.by_category.is_empty() {
+                println!("  Deduplication by category:");
+                for (category, counters) in category_stats.sorted() {
+                    println!(
+                        "    {:<16} {:>5} write(s)  {:>10} written  {:>10} stored  ({:.1}% dedup)",
+                        format!("{:?}", category),
+                        counters.total_writes,
+                        HumanBytes(counters.bytes_written).to_string(),
+                        HumanBytes(counters.bytes_stored).to_string(),
+                        counters.dedup_ratio() * 100.0
+                    );
+                }
+            }
             println!();
         }
 
@@ -310,6 +381,24 @@ impl StatsCmd {
         Ok(())
     }
 
+    /// Ask the storage backend what it holds, for comparison with the quota
+    ///
+    /// Unlike `compute_storage_stats` this covers any backend, not just the
+    /// local `.mediagit` directory.
+    async fn compute_backend_usage(
+        &self,
+        repo_root: &Path,
+        storage: &dyn StorageBackend,
+    ) -> Result<BackendUsage> {
+        let config = mediagit_config::Config::load(repo_root)
+            .await
+            .unwrap_or_default();
+        Ok(BackendUsage {
+            usage: storage.usage("").await?,
+            quota: config.quota.max_bytes,
+        })
+    }
+
     /// Compute storage statistics by walking the .mediagit directory
     ///
     /// Chunks, manifests, and deltas are stored inside the sharded `objects/`
@@ -497,7 +586,7 @@ impl StatsCmd {
                 commit_count: count,
             })
             .collect();
-        authors.sort_by(|a, b| b.commit_count.cmp(&a.commit_count));
+        authors.sort_by_key(|a| std::cmp::Reverse(a.commit_count));
 
         Ok(authors)
     }
@@ -588,6 +677,149 @@ impl StatsCmd {
         Ok(())
     }
 
+    /// Attribute reachable objects to every branch and tag
+    ///
+    /// Sizes are logical object sizes; chunks shared between different
+    /// objects are not deduplicated here.
+    async fn compute_ref_storage(
+        &self,
+        odb: &ObjectDatabase,
+        refdb: &RefDatabase,
+    ) -> Result<Vec<RefStorage>> {
+        let mut names = refdb.list("heads").await.unwrap_or_default();
+        names.extend(refdb.list("tags").await.unwrap_or_default());
+
+        let mut reachable: Vec<(String, HashSet<Oid>)> = Vec::new();
+        let mut ref_counts: HashMap<Oid, u64> = HashMap::new();
+        for name in names {
+            let Ok(tip) = refdb.resolve(&name).await else {
+                continue; // Skip dangling refs
+            };
+            let objects = self.collect_reachable(odb, tip).await?;
+            for oid in &objects {
+                *ref_counts.entry(*oid).or_default() += 1;
+            }
+            reachable.push((name, objects));
+        }
+
+        let mut sizes: HashMap<Oid, u64> = HashMap::with_capacity(ref_counts.len());
+        for oid in ref_counts.keys() {
+            let size = match odb.get_chunk_manifest(oid).await? {
+                Some(manifest) => manifest.total_size,
+                None => odb.get_object_size(oid).await? as u64,
+            };
+            sizes.insert(*oid, size);
+        }
+
+        let mut result: Vec<RefStorage> = reachable
+            .into_iter()
+            .map(|(name, objects)| {
+                let mut stats = RefStorage {
+                    name,
+                    ..Default::default()
+                };
+                for oid in &objects {
+                    let size = sizes.get(oid).copied().unwrap_or(0);
+                    stats.reachable_objects += 1;
+                    stats.reachable_bytes += size;
+                    if ref_counts.get(oid) == Some(&1) {
+                        stats.exclusive_objects += 1;
+                        stats.exclusive_bytes += size;
+                    }
+                }
+                stats
+            })
+            .collect();
+        result.sort_by(|a, b| {
+            b.exclusive_bytes
+                .cmp(&a.exclusive_bytes)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Ok(result)
+    }
+
+    /// Collect every commit, tree and blob reachable from a commit
+    async fn collect_reachable(&self, odb: &ObjectDatabase, tip: Oid) -> Result<HashSet<Oid>> {
+        let mut visited = HashSet::new();
+        let mut commits = vec![tip];
+        let mut trees = Vec::new();
+
+        while let Some(oid) = commits.pop() {
+            if !visited.insert(oid) {
+                continue;
+            }
+            let commit = Commit::read(odb, &oid).await?;
+            trees.push(commit.tree);
+            commits.extend(commit.parents.iter().copied());
+        }
+
+        while let Some(oid) = trees.pop() {
+            if !visited.insert(oid) {
+                continue;
+            }
+            let tree = Tree::read(odb, &oid).await?;
+            for entry in tree.iter() {
+                if entry.is_tree() {
+                    trees.push(entry.oid);
+                } else {
+                    visited.insert(entry.oid);
+                }
+            }
+        }
+
+        Ok(visited)
+    }
+
+    /// Print the per-ref storage breakdown
+    async fn output_by_ref(&self, odb: &ObjectDatabase, refdb: &RefDatabase) -> Result<()> {
+        let refs = self.compute_ref_storage(odb, refdb).await?;
+
+        if self.json {
+            let json = serde_json::json!({
+                "refs": refs
+                    .iter()
+                    .map(|r| {
+                        serde_json::json!({
+                            "ref": r.name,
+                            "exclusive_objects": r.exclusive_objects,
+                            "exclusive_bytes": r.exclusive_bytes,
+                            "reachable_objects": r.reachable_objects,
+                            "reachable_bytes": r.reachable_bytes
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+            return Ok(());
+        }
+
+        println!("{} Storage by Ref\n", style("📊").cyan().bold());
+        if refs.is_empty() {
+            println!("  No branches or tags");
+            return Ok(());
+        }
+        for r in &refs {
+            println!("  {}", style(&r.name).bold());
+            println!(
+                "    Exclusive: {} ({} objects)",
+                HumanBytes(r.exclusive_bytes),
+                r.exclusive_objects
+            );
+            println!(
+                "    Reachable: {} ({} objects)",
+                HumanBytes(r.reachable_bytes),
+                r.reachable_objects
+            );
+        }
+        println!(
+            "\n  {}",
+            style("Exclusive storage is freed when the ref is deleted and gc runs").dim()
+        );
+
+        Ok(())
+    }
+
     /// Resolve HEAD to a commit OID
     async fn resolve_head(&self, refdb: &RefDatabase) -> Result<Oid> {
         let head = refdb.read("HEAD").await?;
@@ -656,9 +888,31 @@ impl StatsCmd {
             return Ok(());
         }
 
-        println!("  Algorithm: zstd (type-aware, store for pre-compressed)");
         println!("  Storage used: {}", HumanBytes(total_stored));
 
+        // Bytes in/out per algorithm and category, recorded by `add`
+        let compression_stats = CompressionStats::load(storage_path).unwrap_or_default();
+        let total = compression_stats.total();
+        if total.objects > 0 {
+            println!(
+                "  Compressed: {} object(s), {} in → {} out ({:.2}x, {} saved, {:.1}%)",
+                total.objects,
+                HumanBytes(total.bytes_in),
+                HumanBytes(total.bytes_out),
+                total.ratio(),
+                HumanBytes(total.bytes_saved()),
+                total.saved_ratio() * 100.0,
+            );
+            println!("  By algorithm:");
+            for (algorithm, totals) in compression_stats.sorted_algorithms() {
+                print_compression_totals(&format!("{:?}", algorithm), &totals);
+            }
+            println!("  By category:");
+            for (category, totals) in compression_stats.sorted_categories() {
+                print_compression_totals(&format!("{:?}", category), &totals);
+            }
+        }
+
         // Show chunked file compression ratios from manifest data
         if stats.manifest_count > 0 && stats.original_bytes > 0 {
             let ratio = stats.chunk_bytes as f64 / stats.original_bytes as f64;
@@ -681,7 +935,7 @@ impl StatsCmd {
                 println!("  By file type:");
                 let mut categories: Vec<(&String, &(u64, u64))> =
                     stats.category_stats.iter().collect();
-                categories.sort_by(|a, b| b.1 .0.cmp(&a.1 .0));
+                categories.sort_by_key(|c| std::cmp::Reverse(c.1 .0));
                 for (cat, (orig_bytes, file_count)) in &categories {
                     println!(
                         "    {:8}: {} files, {} original",
@@ -706,6 +960,7 @@ impl StatsCmd {
     async fn output_prometheus(
         &self,
         storage_path: &Path,
+        backend: &BackendUsage,
         odb: &ObjectDatabase,
         refdb: &RefDatabase,
     ) -> Result<()> {
@@ -739,12 +994,105 @@ impl StatsCmd {
         println!("# TYPE mediagit_chunks_total gauge");
         println!("mediagit_chunks_total {}", storage_stats.chunk_count);
 
+        println!("# HELP mediagit_backend_bytes Bytes held by the storage backend");
+        println!("# TYPE mediagit_backend_bytes gauge");
+        println!("mediagit_backend_bytes {}", backend.usage.total_bytes);
+
+        println!("# HELP mediagit_backend_objects Objects held by the storage backend");
+        println!("# TYPE mediagit_backend_objects gauge");
+        println!("mediagit_backend_objects {}", backend.usage.object_count);
+
+        if let Some(quota) = backend.quota {
+            println!("# HELP mediagit_quota_bytes Configured storage quota");
+            println!("# TYPE mediagit_quota_bytes gauge");
+            println!("mediagit_quota_bytes {}", quota);
+        }
+
+        let compression_stats = CompressionStats::load(storage_path).unwrap_or_default();
+        for (label, series) in [
+            (
+                "algorithm",
+                compression_stats
+                    .sorted_algorithms()
+                    .into_iter()
+                    .map(|(a, t)| (format!("{:?}", a), t))
+                    .collect::<Vec<_>>(),
+            ),
+            (
+                "category",
+                compression_stats
+                    .sorted_categories()
+                    .into_iter()
+                    .map(|(c, t)| (format!("{:?}", c), t))
+                    .collect(),
+            ),
+        ] {
+            if series.is_empty() {
+                continue;
+            }
+            println!("# HELP mediagit_compression_{label}_bytes_in_total Bytes before compression by {label}");
+            println!("# TYPE mediagit_compression_{label}_bytes_in_total counter");
+            for (name, totals) in &series {
+                println!(
+                    "mediagit_compression_{label}_bytes_in_total{{{label}=\"{name}\"}} {}",
+                    totals.bytes_in
+                );
+            }
+            println!("# HELP mediagit_compression_{label}_bytes_out_total Bytes after compression by {label}");
+            println!("# TYPE mediagit_compression_{label}_bytes_out_total counter");
+            for (name, totals) in &series {
+                println!(
+                    "mediagit_compression_{label}_bytes_out_total{{{label}=\"{name}\"}} {}",
+                    totals.bytes_out
+                );
+            }
+            println!("# HELP mediagit_compression_{label}_ratio Compression ratio by {label}");
+            println!("# TYPE mediagit_compression_{label}_ratio gauge");
+            for (name, totals) in &series {
+                println!(
+                    "mediagit_compression_{label}_ratio{{{label}=\"{name}\"}} {}",
+                    totals.ratio()
+                );
+            }
+        }
+
+        let category_stats = CategoryStats::load(storage_path).unwrap_or_default();
+        if !category_stats.by_category.is_empty() {
+            let categories = category_stats.sorted();
+            println!("# HELP mediagit_dedup_category_bytes_written_total Bytes written by object category");
+            println!("# TYPE mediagit_dedup_category_bytes_written_total counter");
+            for (category, counters) in &categories {
+                println!(
+                    "mediagit_dedup_category_bytes_written_total{{category=\"{:?}\"}} {}",
+                    category, counters.bytes_written
+                );
+            }
+            println!("# HELP mediagit_dedup_category_bytes_stored_total Bytes stored after deduplication by object category");
+            println!("# TYPE mediagit_dedup_category_bytes_stored_total counter");
+            for (category, counters) in &categories {
+                println!(
+                    "mediagit_dedup_category_bytes_stored_total{{category=\"{:?}\"}} {}",
+                    category, counters.bytes_stored
+                );
+            }
+            println!("# HELP mediagit_dedup_category_ratio Deduplication ratio by object category");
+            println!("# TYPE mediagit_dedup_category_ratio gauge");
+            for (category, counters) in &categories {
+                println!(
+                    "mediagit_dedup_category_ratio{{category=\"{:?}\"}} {}",
+                    category,
+                    counters.dedup_ratio()
+                );
+            }
+        }
+
         Ok(())
     }
 
     async fn output_json(
         &self,
         storage_path: &Path,
+        backend: &BackendUsage,
         odb: &ObjectDatabase,
         refdb: &RefDatabase,
     ) -> Result<()> {
@@ -772,6 +1120,37 @@ impl StatsCmd {
             + storage_stats.chunk_bytes
             + storage_stats.delta_bytes;
 
+        let dedup_by_category: serde_json::Map<String, serde_json::Value> =
+            CategoryStats::load(storage_path)
+                .unwrap_or_default()
+                .sorted()
+                .into_iter()
+                .map(|(category, counters)| {
+                    (
+                        format!("{:?}", category),
+                        serde_json::json!({
+                            "writes": counters.total_writes,
+                            "unique_objects": counters.unique_objects,
+                            "bytes_written": counters.bytes_written,
+                            "bytes_stored": counters.bytes_stored,
+                            "dedup_ratio": counters.dedup_ratio()
+                        }),
+                    )
+                })
+                .collect();
+
+        let compression_stats = CompressionStats::load(storage_path).unwrap_or_default();
+        let by_algorithm: serde_json::Map<String, serde_json::Value> = compression_stats
+            .sorted_algorithms()
+            .into_iter()
+            .map(|(algorithm, totals)| (format!("{:?}", algorithm), compression_json(&totals)))
+            .collect();
+        let by_category: serde_json::Map<String, serde_json::Value> = compression_stats
+            .sorted_categories()
+            .into_iter()
+            .map(|(category, totals)| (format!("{:?}", category), compression_json(&totals)))
+            .collect();
+
         let json = serde_json::json!({
             "storage": {
                 "total_bytes": total_bytes,
@@ -784,7 +1163,18 @@ impl StatsCmd {
                 "pack_files": storage_stats.pack_count,
                 "chunks": storage_stats.chunk_count,
                 "deltas": storage_stats.delta_count,
-                "manifests": storage_stats.manifest_count
+                "manifests": storage_stats.manifest_count,
+                "dedup_by_category": dedup_by_category,
+                "backend": {
+                    "objects": backend.usage.object_count,
+                    "bytes": backend.usage.total_bytes,
+                    "quota_bytes": backend.quota
+                }
+            },
+            "compression": {
+                "total": compression_json(&compression_stats.total()),
+                "by_algorithm": by_algorithm,
+                "by_category": by_category
             },
             "commits": {
                 "total": commit_stats.total_commits,
@@ -814,3 +1204,27 @@ impl StatsCmd {
         Ok(())
     }
 }
+
+/// Print one algorithm's or category's line of `stats --compression`
+fn print_compression_totals(name: &str, totals: &CompressionTotals) {
+    println!(
+        "    {:<16} {:>5} object(s)  {:>10} in  {:>10} out  ({:.2}x, {:.1}% saved)",
+        name,
+        totals.objects,
+        HumanBytes(totals.bytes_in).to_string(),
+        HumanBytes(totals.bytes_out).to_string(),
+        totals.ratio(),
+        totals.saved_ratio() * 100.0
+    );
+}
+
+/// JSON form of compression totals
+fn compression_json(totals: &CompressionTotals) -> serde_json::Value {
+    serde_json::json!({
+        "objects": totals.objects,
+        "bytes_in": totals.bytes_in,
+        "bytes_out": totals.bytes_out,
+        "ratio": totals.ratio(),
+        "bytes_saved": totals.bytes_saved()
+    })
+}
@@ -14,11 +14,15 @@
 use super::super::repo::{create_storage_backend, find_repo_root};
 use anyhow::Result;
 use clap::Parser;
-use mediagit_versioning::{Index, ObjectDatabase, Oid, Ref, RefDatabase};
+use mediagit_config::BranchConfig;
+use mediagit_versioning::{Index, ObjectDatabase, Oid, Ref, RefDatabase, TextAttributes};
 use rayon::prelude::*;
-use std::collections::{HashMap, HashSet};
-use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
 
+use super::utils::upstream_ahead_behind;
+use crate::fsmonitor::WorkingTreeState;
 use crate::ignore_rules::IgnoreMatcher;
 
 /// Show the working tree status
@@ -93,7 +97,7 @@ impl StatusCmd {
         let repo_root = dunce::canonicalize(find_repo_root()?)
             .unwrap_or_else(|_| find_repo_root().expect("repo root"));
 
-        if !self.quiet {
+        if !self.quiet && !self.porcelain {
             output::header("Repository Status");
         }
 
@@ -133,32 +137,46 @@ impl StatusCmd {
             }
         }
 
+        // Report how the current branch compares with its upstream
+        if !self.quiet && !self.porcelain && !self.short {
+            if let Some(branch) = head
+                .as_ref()
+                .and_then(|head| head.target.as_deref())
+                .and_then(|target| target.strip_prefix("refs/heads/"))
+            {
+                let config = mediagit_config::Config::load(&repo_root).await?;
+                if let Some(upstream) = config.branches.get(branch) {
+                    let odb =
+                        Arc::new(ObjectDatabase::with_smart_compression(storage.clone(), 100));
+                    report_upstream(&refdb, odb, branch, upstream).await?;
+                }
+            }
+        }
+
         // Check if we have any commits by trying to resolve HEAD
         let has_commits = refdb.resolve("HEAD").await.is_ok();
 
         // Load index and initialize ODB for file comparison (ISS-005 fix)
         let index = Index::load(&repo_root)?;
         let odb = ObjectDatabase::with_smart_compression(storage.clone(), 1000);
+        // Text assets are compared in their normalized (committed) form
+        let attributes = TextAttributes::load(&repo_root)?;
 
-        // Scan working directory, collecting ignored files separately
-        let mut ignored_files: HashSet<PathBuf> = HashSet::new();
+        // Scan working directory, collecting ignored files separately. With a
+        // running fsmonitor, only paths changed since the last status are
+        // re-examined.
         let matcher = IgnoreMatcher::new(&repo_root).ok();
-        let working_files =
-            self.scan_working_directory(&repo_root, &matcher, &mut ignored_files)?;
+        let working_tree = WorkingTreeState::load(&repo_root, matcher.as_ref())?;
+        let working_files = &working_tree.scan.files;
+        let ignored_files = &working_tree.scan.ignored_files;
 
         // Get HEAD commit tree for comparison (index is cleared after commit)
         let mut head_files: HashMap<PathBuf, Oid> = HashMap::new();
         if let Ok(head_oid) = refdb.resolve("HEAD").await {
             if let Ok(commit_data) = odb.read(&head_oid).await {
-                if let Ok(commit) = mediagit_versioning::format::deserialize::<
-                    mediagit_versioning::Commit,
-                >(&commit_data)
-                {
+                if let Ok(commit) = mediagit_versioning::Commit::deserialize(&commit_data) {
                     if let Ok(tree_data) = odb.read(&commit.tree).await {
-                        if let Ok(tree) = mediagit_versioning::format::deserialize::<
-                            mediagit_versioning::Tree,
-                        >(&tree_data)
-                        {
+                        if let Ok(tree) = mediagit_versioning::Tree::deserialize(&tree_data) {
                             for entry in tree.iter() {
                                 head_files.insert(PathBuf::from(&entry.name), entry.oid);
                             }
@@ -178,9 +196,11 @@ impl StatusCmd {
         // Convert to vector for parallel iteration
         let head_files_vec: Vec<_> = head_files.iter().collect();
 
-        let modified_files: Vec<PathBuf> = head_files_vec
+        // Working tree hash of each unstaged HEAD file, and whether it had to
+        // be computed rather than taken from the fsmonitor cache
+        let working_oids: Vec<(PathBuf, Oid, bool)> = head_files_vec
             .par_iter() // Parallel iterator for multi-core processing
-            .filter_map(|(path, head_oid)| {
+            .filter_map(|(path, _)| {
                 // Skip files not in working directory
                 if !working_files.contains(*path) {
                     return None;
@@ -191,6 +211,10 @@ impl StatusCmd {
                     return None;
                 }
 
+                if let Some(oid) = working_tree.cached_oid(path) {
+                    return Some(((*path).clone(), oid, false));
+                }
+
                 let full_path = repo_root.join(path);
 
                 // OPTIMIZATION 1: Size-based quick check and streaming for large files
@@ -201,7 +225,9 @@ impl StatusCmd {
                     const STREAMING_THRESHOLD: u64 = 5 * 1024 * 1024; // 5MB
 
                     // Compute hash - use streaming for large files
-                    let working_oid = if file_size >= STREAMING_THRESHOLD {
+                    let streaming = file_size >= STREAMING_THRESHOLD
+                        && !attributes.may_convert(path, &full_path).ok()?;
+                    let working_oid = if streaming {
                         // STREAMING: Use constant-memory hash for large files
                         match Oid::from_file(&full_path) {
                             Ok(oid) => oid,
@@ -210,33 +236,62 @@ impl StatusCmd {
                     } else {
                         // IN-MEMORY: Faster for small files
                         if let Ok(content) = std::fs::read(&full_path) {
-                            Oid::hash(&content)
+                            Oid::hash(&attributes.clean(path, &content))
                         } else {
                             return None;
                         }
                     };
 
-                    if working_oid != **head_oid {
-                        // File is modified
-                        return Some((*path).clone());
-                    }
+                    return Some(((*path).clone(), working_oid, true));
                 }
 
                 None
             })
             .collect();
 
+        let modified_files: Vec<PathBuf> = working_oids
+            .iter()
+            .filter(|(path, oid, _)| head_files.get(path) != Some(oid))
+            .map(|(path, _, _)| path.clone())
+            .collect();
+
+        if self.verbose && !self.porcelain {
+            let examined = working_oids.iter().filter(|(_, _, hashed)| *hashed).count();
+            output::info(&format!(
+                "Examined {} of {} tracked files{}",
+                examined,
+                working_oids.len(),
+                if working_tree.is_incremental() {
+                    " (fsmonitor)"
+                } else {
+                    ""
+                }
+            ));
+        }
+
+        // Let the next run reuse this scan and these hashes
+        let oids = working_oids
+            .iter()
+            .map(|(path, oid, _)| (path.clone(), *oid))
+            .collect();
+        if let Err(e) = working_tree.save(&repo_root, oids) {
+            tracing::warn!("Failed to save fsmonitor status cache: {:#}", e);
+        }
+
         // Detect deleted files (in HEAD, not in working dir, not staged for deletion)
         let mut deleted_files = Vec::new();
         for path in head_files.keys() {
-            if !working_files.contains(path) && !index_files.contains_key(path) {
+            if !working_files.contains(path)
+                && !index_files.contains_key(path)
+                && !index.is_deleted(path)
+            {
                 deleted_files.push(path.clone());
             }
         }
 
         // Detect untracked files (in working dir, not in HEAD, not in index, not ignored)
         let mut untracked_files = Vec::new();
-        for path in &working_files {
+        for path in working_files {
             if !head_files.contains_key(path)
                 && !index_files.contains_key(path)
                 && !ignored_files.contains(path)
@@ -249,8 +304,10 @@ impl StatusCmd {
         if self.porcelain {
             // Staged files (new files in index)
             for entry in index.entries() {
-                // Check if it's a new file or modified staged file
-                if head_files.contains_key(&entry.path) {
+                // Check if it's a rename, a new file or a modified staged file
+                if let Some(source) = index.rename_source(&entry.path) {
+                    println!("R  {} -> {}", source.display(), entry.path.display());
+                } else if head_files.contains_key(&entry.path) {
                     println!("M  {}", entry.path.display());
                 } else {
                     println!("A  {}", entry.path.display());
@@ -286,6 +343,16 @@ impl StatusCmd {
             println!();
 
             for entry in index.entries() {
+                if let Some(source) = index.rename_source(&entry.path) {
+                    let status_prefix = if self.short { "R " } else { "  renamed:    " };
+                    output::success(&format!(
+                        "{}{} -> {}",
+                        status_prefix,
+                        source.display(),
+                        entry.path.display()
+                    ));
+                    continue;
+                }
                 let status_prefix = if self.short { "A " } else { "  new file:   " };
                 output::success(&format!("{}{}", status_prefix, entry.path.display()));
             }
@@ -365,64 +432,35 @@ impl StatusCmd {
 
         Ok(())
     }
+}
 
-    // ISS-005 fix: Helper function to scan working directory
-    fn scan_working_directory(
-        &self,
-        repo_root: &Path,
-        matcher: &Option<IgnoreMatcher>,
-        ignored_files: &mut HashSet<PathBuf>,
-    ) -> Result<HashSet<PathBuf>> {
-        let mut files = HashSet::new();
-        self.scan_directory_recursive(repo_root, repo_root, matcher, ignored_files, &mut files)?;
-        Ok(files)
-    }
-
-    #[allow(clippy::only_used_in_recursion)]
-    fn scan_directory_recursive(
-        &self,
-        repo_root: &Path,
-        current_dir: &Path,
-        matcher: &Option<IgnoreMatcher>,
-        ignored_files: &mut HashSet<PathBuf>,
-        files: &mut HashSet<PathBuf>,
-    ) -> Result<()> {
-        for entry in std::fs::read_dir(current_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            // Skip .mediagit directory
-            if path.file_name().and_then(|n| n.to_str()) == Some(".mediagit") {
-                continue;
-            }
-
-            // Check .mediagitignore
-            if let Some(ref m) = matcher {
-                if let Ok(rel) = path.strip_prefix(repo_root) {
-                    let is_dir = path.is_dir();
-                    if m.is_ignored(rel, is_dir) {
-                        if path.is_file() {
-                            // Track ignored files for --ignored output
-                            let normalized =
-                                PathBuf::from(rel.to_string_lossy().replace('\\', "/"));
-                            ignored_files.insert(normalized);
-                        }
-                        // For dirs: prune entire subtree silently (don't enumerate children)
-                        continue;
-                    }
-                }
-            }
-
-            if path.is_file() {
-                // Store as relative path with normalized separators
-                if let Ok(rel_path) = path.strip_prefix(repo_root) {
-                    let normalized = PathBuf::from(rel_path.to_string_lossy().replace('\\', "/"));
-                    files.insert(normalized);
-                }
-            } else if path.is_dir() {
-                self.scan_directory_recursive(repo_root, &path, matcher, ignored_files, files)?;
-            }
-        }
-        Ok(())
-    }
+/// Print whether `branch` is ahead of, behind or level with its upstream
+async fn report_upstream(
+    refdb: &RefDatabase,
+    odb: Arc<ObjectDatabase>,
+    branch: &str,
+    upstream: &BranchConfig,
+) -> Result<()> {
+    use crate::output;
+
+    let Ok(local_oid) = refdb.resolve(&format!("refs/heads/{}", branch)).await else {
+        return Ok(());
+    };
+
+    let name = upstream.display_name();
+    let message = match upstream_ahead_behind(refdb, odb, &local_oid, upstream).await? {
+        None => format!(
+            "Your branch is based on '{}', but the upstream is gone",
+            name
+        ),
+        Some((0, 0)) => format!("Your branch is up to date with '{}'", name),
+        Some((ahead, 0)) => format!("Your branch is ahead of '{}' by {} commit(s)", name, ahead),
+        Some((0, behind)) => format!("Your branch is behind '{}' by {} commit(s)", name, behind),
+        Some((ahead, behind)) => format!(
+            "Your branch and '{}' have diverged, with {} and {} different commit(s) each",
+            name, ahead, behind
+        ),
+    };
+    output::info(&message);
+    Ok(())
 }
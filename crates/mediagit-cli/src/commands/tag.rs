@@ -178,7 +178,7 @@ impl TagCmd {
         // Create tag based on type
         if let Some(ref message) = opts.message {
             // Annotated tag
-            self.create_annotated_tag(&refdb, &opts.name, target_oid, message, opts)
+            self.create_annotated_tag(&mediagit_dir, &refdb, &opts.name, target_oid, message, opts)
                 .await?;
         } else {
             // Lightweight tag
@@ -219,6 +219,7 @@ impl TagCmd {
     /// Create annotated tag (tag object with metadata)
     async fn create_annotated_tag(
         &self,
+        mediagit_dir: &Path,
         refdb: &RefDatabase,
         name: &str,
         commit_oid: Oid,
@@ -242,9 +243,7 @@ impl TagCmd {
             timestamp: chrono::Utc::now().to_rfc3339(),
         };
 
-        // Get metadata path from mediagit dir
-        let mediagit_dir = std::env::current_dir()?.join(".mediagit");
-        let metadata_path = get_ref_path(&mediagit_dir, &format!("{}.meta", tag_ref));
+        let metadata_path = get_ref_path(mediagit_dir, &format!("{}.meta", tag_ref));
         if let Some(parent) = metadata_path.parent() {
             tokio::fs::create_dir_all(parent).await?;
         }
@@ -272,7 +271,7 @@ impl TagCmd {
 
         // Display tags
         if opts.verbose {
-            self.list_verbose(&refdb, tags).await?;
+            self.list_verbose(&mediagit_dir, &refdb, tags).await?;
         } else {
             self.list_simple(tags);
         }
@@ -350,9 +349,12 @@ impl TagCmd {
     }
 
     /// List tags with verbose output
-    async fn list_verbose(&self, refdb: &RefDatabase, tags: Vec<String>) -> Result<()> {
-        let mediagit_dir = std::env::current_dir()?.join(".mediagit");
-
+    async fn list_verbose(
+        &self,
+        mediagit_dir: &Path,
+        refdb: &RefDatabase,
+        tags: Vec<String>,
+    ) -> Result<()> {
         for tag_ref in tags {
             let tag_name = tag_ref.strip_prefix("refs/tags/").unwrap_or(&tag_ref);
 
@@ -361,7 +363,7 @@ impl TagCmd {
             let commit_oid = r.oid.context("Tag has no OID")?;
 
             // Check for metadata (annotated tag)
-            let metadata_path = get_ref_path(&mediagit_dir, &format!("{}.meta", tag_ref));
+            let metadata_path = get_ref_path(mediagit_dir, &format!("{}.meta", tag_ref));
             let is_annotated = tokio::fs::metadata(&metadata_path).await.is_ok();
 
             if is_annotated {
@@ -639,6 +641,9 @@ mod tests {
         let mediagit_dir = repo_path.join(".mediagit");
         let refdb = RefDatabase::new(&mediagit_dir);
         assert!(refdb.exists("refs/tags/v2.0.0").await.unwrap());
+        let metadata_path = get_ref_path(&mediagit_dir, "refs/tags/v2.0.0.meta");
+        let metadata = tokio::fs::read_to_string(metadata_path).await.unwrap();
+        assert!(metadata.contains("Release version 2.0.0"));
     }
 
     #[tokio::test]
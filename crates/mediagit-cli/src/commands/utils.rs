@@ -15,6 +15,9 @@
 
 use anyhow::Result;
 use chrono::Duration;
+use mediagit_config::BranchConfig;
+use mediagit_versioning::{LcaFinder, ObjectDatabase, Oid, RefDatabase};
+use std::sync::Arc;
 
 /// Format a duration as a human-readable "time ago" string.
 pub fn format_duration_ago(duration: Duration) -> String {
@@ -82,3 +85,22 @@ pub fn validate_ref_name(name: &str) -> Result<()> {
 
     Ok(())
 }
+
+/// Count commits `local` is ahead of and behind its upstream.
+///
+/// The upstream is read from its remote-tracking ref, as last updated by
+/// push, pull or fetch. Returns `None` when that ref no longer exists.
+pub async fn upstream_ahead_behind(
+    refdb: &RefDatabase,
+    odb: Arc<ObjectDatabase>,
+    local: &Oid,
+    upstream: &BranchConfig,
+) -> Result<Option<(usize, usize)>> {
+    let Ok(upstream_oid) = refdb.resolve(&upstream.tracking_ref()).await else {
+        return Ok(None);
+    };
+    let counts = LcaFinder::new(odb)
+        .ahead_behind(local, &upstream_oid)
+        .await?;
+    Ok(Some(counts))
+}
@@ -19,11 +19,11 @@ use clap::Parser;
 use console::style;
 use mediagit_storage::StorageBackend;
 use mediagit_versioning::{
-    resolve_revision, Commit, FsckChecker, FsckOptions, IssueSeverity, ObjectDatabase, Oid,
-    RefDatabase,
+    resolve_revision, Commit, FileMode, FsckChecker, FsckOptions, IssueSeverity, ObjectDatabase,
+    Oid, RefDatabase, TextAttributes, Tree,
 };
 use std::collections::HashSet;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 /// Verify repository integrity with quick checks
@@ -45,6 +45,9 @@ use std::sync::Arc;
     # Verify specific commit range
     mediagit verify --start abc123 --end def456
 
+    # Compare working tree files against HEAD
+    mediagit verify --worktree
+
 VERIFY vs FSCK:
     verify  - Fast integrity check (checksums + refs only)
             - Use for quick health checks and CI pipelines
@@ -95,6 +98,10 @@ pub struct VerifyCmd {
     #[arg(short, long)]
     pub verbose: bool,
 
+    /// Compare each tracked file in the working tree against its content in HEAD
+    #[arg(long, conflicts_with_all = ["commit", "start", "end"])]
+    pub worktree: bool,
+
     /// Repository path (defaults to current directory)
     #[arg(long, value_name = "PATH")]
     pub path: Option<String>,
@@ -119,6 +126,10 @@ impl VerifyCmd {
             .await
             .context("Failed to open repository. Is this a MediaGit repository?")?;
 
+        if self.worktree {
+            return self.verify_worktree(&repo_path, storage).await;
+        }
+
         // Handle commit range verification (or single-commit verify via positional arg)
         if self.commit.is_some() || self.start.is_some() || self.end.is_some() {
             return self
@@ -373,6 +384,132 @@ impl VerifyCmd {
         Ok(())
     }
 
+    /// Compare every tracked file in the working tree against the blob committed in HEAD
+    async fn verify_worktree(
+        &self,
+        repo_path: &Path,
+        storage: Arc<dyn StorageBackend>,
+    ) -> Result<()> {
+        let refdb = RefDatabase::new(repo_path.join(".mediagit"));
+        let odb = ObjectDatabase::with_smart_compression(storage, 1000);
+        let attributes = TextAttributes::load(repo_path)?;
+
+        let head_oid = refdb
+            .resolve("HEAD")
+            .await
+            .context("HEAD does not point to a commit")?;
+        let commit = Commit::read(&odb, &head_oid).await?;
+        let files = Self::tracked_files(&odb, commit.tree).await?;
+
+        if !self.quiet {
+            println!(
+                "  Comparing {} tracked file(s) against HEAD ({})",
+                files.len(),
+                style(&head_oid.to_string()[..7]).cyan()
+            );
+        }
+
+        let mut divergent = 0;
+        for (path, oid) in &files {
+            let full_path = repo_path.join(path);
+            let display_path = path.display();
+
+            if !full_path.is_file() {
+                divergent += 1;
+                if !self.quiet {
+                    println!(
+                        "  {} {}: missing from working tree",
+                        style("✗").red(),
+                        display_path
+                    );
+                }
+                continue;
+            }
+
+            let working = attributes
+                .clean(path, &std::fs::read(&full_path)?)
+                .into_owned();
+            if Oid::hash(&working) == *oid {
+                if self.verbose {
+                    println!("  {} {}", style("✓").green(), display_path);
+                }
+                continue;
+            }
+
+            divergent += 1;
+            if !self.quiet {
+                let committed = odb.read(oid).await.with_context(|| {
+                    format!("Failed to read committed blob for {}", display_path)
+                })?;
+                let first_difference = committed
+                    .iter()
+                    .zip(&working)
+                    .position(|(a, b)| a != b)
+                    .unwrap_or_else(|| committed.len().min(working.len()));
+                println!(
+                    "  {} {}: differs from HEAD (committed {} bytes, working {} bytes, first difference at byte {})",
+                    style("✗").red(),
+                    display_path,
+                    committed.len(),
+                    working.len(),
+                    first_difference
+                );
+                if self.detailed {
+                    println!("    Committed: {}", style(oid.to_string()).dim());
+                    println!(
+                        "    Working:   {}",
+                        style(Oid::hash(&working).to_string()).dim()
+                    );
+                }
+            }
+        }
+
+        if !self.quiet {
+            println!();
+            if divergent == 0 {
+                println!(
+                    "{} All {} tracked file(s) match HEAD",
+                    style("✅").green().bold(),
+                    files.len()
+                );
+            } else {
+                println!(
+                    "{} {} of {} tracked file(s) differ from HEAD",
+                    style("❌").red().bold(),
+                    divergent,
+                    files.len()
+                );
+            }
+        }
+
+        if divergent > 0 {
+            anyhow::bail!("{} file(s) differ from HEAD", divergent);
+        }
+
+        Ok(())
+    }
+
+    /// Regular and executable files of a tree, with their paths relative to its root
+    async fn tracked_files(odb: &ObjectDatabase, tree_oid: Oid) -> Result<Vec<(PathBuf, Oid)>> {
+        let mut files = Vec::new();
+        let mut pending = vec![(PathBuf::new(), tree_oid)];
+
+        while let Some((prefix, oid)) = pending.pop() {
+            let tree = Tree::read(odb, &oid).await?;
+            for entry in tree.iter() {
+                let path = prefix.join(&entry.name);
+                match entry.mode {
+                    FileMode::Directory => pending.push((path, entry.oid)),
+                    FileMode::Regular | FileMode::Executable => files.push((path, entry.oid)),
+                    FileMode::Symlink => {}
+                }
+            }
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
     /// Resolve a commit reference to an OID.
     ///
     /// Delegates to `resolve_revision` which handles: full OIDs, abbreviated OIDs,
@@ -0,0 +1,583 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Filesystem monitor for incremental `status`.
+//!
+//! A daemon (`mediagit fsmonitor run`, built with the `fsmonitor` feature)
+//! watches the working tree and appends every changed path to
+//! `.mediagit/fsmonitor/events.log`. The log starts with a header naming the
+//! daemon instance; a [`Token`] is that name plus a byte offset into the log.
+//!
+//! `status` keeps the working tree scan and file hashes of its last run in
+//! `.mediagit/fsmonitor/status-cache.json`, together with the token read
+//! before that run. The next run only re-examines paths logged after the
+//! token. It falls back to a full scan whenever the cache cannot be trusted:
+//! no daemon is running (its lock on `daemon.lock` is free), the log belongs
+//! to another daemon instance, or `.mediagitignore` / `.mediagitattributes`
+//! changed. The daemon starts a new log instance when the OS reports lost
+//! events, which forces the same fallback.
+
+use crate::ignore_rules::{scan_directory, scan_working_tree, IgnoreMatcher, WorkingTreeScan};
+use anyhow::{Context, Result};
+use mediagit_versioning::{Oid, ATTRIBUTES_FILE};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Directory holding the monitor state, inside `.mediagit`
+const FSMONITOR_DIR: &str = "fsmonitor";
+/// Held exclusively by the running daemon
+const LOCK_FILE: &str = "daemon.lock";
+/// Changed paths, one per line after the header
+const LOG_FILE: &str = "events.log";
+/// Scan and hashes from the last `status`
+const CACHE_FILE: &str = "status-cache.json";
+/// First word of the log header
+const LOG_MAGIC: &str = "mediagit-fsmonitor";
+
+/// Position in a daemon's event log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Token {
+    /// Daemon instance that wrote the log
+    pub instance: String,
+    /// Byte offset just past the last line read
+    pub offset: u64,
+}
+
+/// The monitor state of one repository
+pub struct FsMonitor {
+    dir: PathBuf,
+}
+
+impl FsMonitor {
+    pub fn new(repo_root: &Path) -> Self {
+        Self {
+            dir: repo_root.join(".mediagit").join(FSMONITOR_DIR),
+        }
+    }
+
+    /// Whether a daemon currently holds the lock
+    pub fn is_running(&self) -> bool {
+        let Ok(file) = File::open(self.dir.join(LOCK_FILE)) else {
+            return false;
+        };
+        // The lock is released when the daemon exits, however it exits
+        matches!(
+            file.try_lock_shared(),
+            Err(std::fs::TryLockError::WouldBlock)
+        )
+    }
+
+    /// Delete the status cache so the next status scans and hashes everything
+    ///
+    /// Returns whether there was a cache to delete.
+    pub fn discard_status_cache(&self) -> Result<bool> {
+        match std::fs::remove_file(self.dir.join(CACHE_FILE)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e).context("Failed to remove status cache"),
+        }
+    }
+
+    /// Current end of the log, plus the paths logged since `since`
+    ///
+    /// The paths are `None` when they cannot be known: no daemon is running,
+    /// there is no earlier token, or the log was restarted since.
+    fn read_log(&self, since: Option<&Token>) -> Result<Option<(Token, Option<HashSet<PathBuf>>)>> {
+        if !self.is_running() {
+            return Ok(None);
+        }
+
+        let mut file = match File::open(self.dir.join(LOG_FILE)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).context("Failed to open fsmonitor log"),
+        };
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)
+            .context("Failed to read fsmonitor log")?;
+
+        let Some(header_end) = contents.iter().position(|&b| b == b'\n') else {
+            return Ok(None);
+        };
+        let header = String::from_utf8_lossy(&contents[..header_end]);
+        let Some(instance) = header.strip_prefix(LOG_MAGIC).map(str::trim) else {
+            return Ok(None);
+        };
+
+        // Only complete lines count; a partly written one is read next time
+        let end = contents
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |pos| pos + 1);
+        let token = Token {
+            instance: instance.to_string(),
+            offset: end as u64,
+        };
+
+        let changed = since
+            .filter(|since| since.instance == token.instance && since.offset <= token.offset)
+            .map(|since| {
+                let start = (since.offset as usize).max(header_end + 1);
+                String::from_utf8_lossy(&contents[start.min(end)..end])
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(PathBuf::from)
+                    .collect()
+            });
+        Ok(Some((token, changed)))
+    }
+}
+
+/// What `status` saved about the working tree on its last run
+#[derive(Debug, Deserialize)]
+struct StatusCache {
+    token: Option<Token>,
+    scan: WorkingTreeScan,
+    oids: HashMap<PathBuf, Oid>,
+}
+
+/// Borrowed form of [`StatusCache`] for writing
+#[derive(Serialize)]
+struct StatusCacheRef<'a> {
+    token: Option<&'a Token>,
+    scan: &'a WorkingTreeScan,
+    oids: &'a HashMap<PathBuf, Oid>,
+}
+
+/// Working tree scan for `status`, incremental when the monitor allows it
+pub struct WorkingTreeState {
+    pub scan: WorkingTreeScan,
+    /// Hashes still valid from the last run
+    oids: HashMap<PathBuf, Oid>,
+    /// Token to save with this run's results; `None` disables the cache
+    token: Option<Token>,
+    incremental: bool,
+}
+
+impl WorkingTreeState {
+    /// Scan the working tree, reusing the last run's scan where unchanged
+    pub fn load(repo_root: &Path, matcher: Option<&IgnoreMatcher>) -> Result<Self> {
+        let monitor = FsMonitor::new(repo_root);
+        let cache = if monitor.is_running() {
+            load_cache(&monitor.dir)
+        } else {
+            None
+        };
+
+        // Read the log before scanning: anything changing during the scan is
+        // logged past this token and re-examined by the next run
+        let (token, changed) =
+            match monitor.read_log(cache.as_ref().and_then(|c| c.token.as_ref()))? {
+                Some((token, changed)) => (Some(token), changed),
+                None => (None, None),
+            };
+
+        let full_scan_needed = changed.as_ref().is_none_or(|changed| {
+            changed.iter().any(|path| {
+                path.file_name()
+                    .is_some_and(|name| name == ".mediagitignore" || name == ATTRIBUTES_FILE)
+            })
+        });
+
+        match (cache, changed) {
+            (Some(mut cache), Some(changed)) if !full_scan_needed => {
+                debug!(
+                    "fsmonitor: {} paths changed since last status",
+                    changed.len()
+                );
+                apply_changes(repo_root, matcher, &mut cache.scan, &changed)?;
+                cache.oids.retain(|path, _| !changed.contains(path));
+                Ok(Self {
+                    scan: cache.scan,
+                    oids: cache.oids,
+                    token,
+                    incremental: true,
+                })
+            }
+            _ => Ok(Self {
+                scan: scan_working_tree(repo_root, matcher)?,
+                oids: HashMap::new(),
+                token,
+                incremental: false,
+            }),
+        }
+    }
+
+    /// Hash of `path` from the last run, if it has not changed since
+    pub fn cached_oid(&self, path: &Path) -> Option<Oid> {
+        self.oids.get(path).copied()
+    }
+
+    /// Whether the scan was updated from monitor events rather than redone
+    pub fn is_incremental(&self) -> bool {
+        self.incremental
+    }
+
+    /// Save this run's scan and working tree hashes for the next run
+    ///
+    /// Does nothing unless a daemon is running.
+    pub fn save(&self, repo_root: &Path, oids: HashMap<PathBuf, Oid>) -> Result<()> {
+        let Some(token) = &self.token else {
+            return Ok(());
+        };
+        let dir = FsMonitor::new(repo_root).dir;
+        let cache = StatusCacheRef {
+            token: Some(token),
+            scan: &self.scan,
+            oids: &oids,
+        };
+
+        let temp = dir.join(format!("{}.tmp", CACHE_FILE));
+        let data = serde_json::to_vec(&cache).context("Failed to serialize status cache")?;
+        std::fs::write(&temp, data).context("Failed to write status cache")?;
+        std::fs::rename(&temp, dir.join(CACHE_FILE)).context("Failed to write status cache")?;
+        Ok(())
+    }
+}
+
+fn load_cache(dir: &Path) -> Option<StatusCache> {
+    let data = std::fs::read(dir.join(CACHE_FILE)).ok()?;
+    match serde_json::from_slice(&data) {
+        Ok(cache) => Some(cache),
+        Err(e) => {
+            debug!("fsmonitor: ignoring unreadable status cache: {}", e);
+            None
+        }
+    }
+}
+
+/// Bring a cached scan up to date with the paths that changed since
+fn apply_changes(
+    repo_root: &Path,
+    matcher: Option<&IgnoreMatcher>,
+    scan: &mut WorkingTreeScan,
+    changed: &HashSet<PathBuf>,
+) -> Result<()> {
+    let mut changed: Vec<&PathBuf> = changed.iter().collect();
+    changed.sort();
+
+    for path in changed {
+        // Nothing below an ignored directory is tracked
+        if path
+            .ancestors()
+            .skip(1)
+            .any(|dir| scan.ignored_dirs.contains(dir))
+        {
+            continue;
+        }
+
+        let was_file = scan.files.remove(path) | scan.ignored_files.remove(path);
+        let full_path = repo_root.join(path);
+
+        if full_path.is_dir() || !was_file && !full_path.exists() {
+            // A directory appeared, went away or was replaced: redo its subtree
+            scan.ignored_dirs.remove(path);
+            scan.files.retain(|p| !p.starts_with(path));
+            scan.ignored_files.retain(|p| !p.starts_with(path));
+            scan.ignored_dirs.retain(|p| !p.starts_with(path));
+        }
+
+        if full_path.is_dir() {
+            if matcher.is_some_and(|m| m.is_ignored(path, true)) {
+                scan.ignored_dirs.insert(path.clone());
+            } else {
+                scan_directory(repo_root, &full_path, matcher, scan)?;
+            }
+        } else if full_path.is_file() {
+            if matcher.is_some_and(|m| m.is_ignored(path, false)) {
+                scan.ignored_files.insert(path.clone());
+            } else {
+                scan.files.insert(path.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "fsmonitor")]
+pub use daemon::{run, start, stop};
+
+#[cfg(feature = "fsmonitor")]
+mod daemon {
+    use super::*;
+    use notify::event::{AccessKind, AccessMode, EventKind};
+    use notify::{RecursiveMode, Watcher};
+    use std::io::Write;
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    /// Created to ask the daemon to exit
+    const STOP_FILE: &str = "stop";
+
+    /// A log larger than this is restarted, at the cost of one full scan
+    const MAX_LOG_SIZE: u64 = 64 * 1024 * 1024;
+
+    /// How often the daemon checks for a stop request
+    const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+    /// How long `start` and `stop` wait for the daemon
+    const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+    struct EventLog {
+        file: File,
+        size: u64,
+    }
+
+    impl EventLog {
+        /// Start a new log under a fresh instance name
+        ///
+        /// The log is written aside and renamed into place, so readers see
+        /// either the old log or the complete new header.
+        fn create(dir: &Path) -> Result<Self> {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos();
+            let header = format!("{} {}-{}\n", LOG_MAGIC, std::process::id(), nanos);
+
+            let temp = dir.join(format!("{}.tmp", LOG_FILE));
+            std::fs::write(&temp, &header).context("Failed to create fsmonitor log")?;
+            std::fs::rename(&temp, dir.join(LOG_FILE)).context("Failed to create fsmonitor log")?;
+            let file = File::options()
+                .append(true)
+                .open(dir.join(LOG_FILE))
+                .context("Failed to open fsmonitor log")?;
+            debug!("fsmonitor: started log {}", header.trim());
+            Ok(Self {
+                file,
+                size: header.len() as u64,
+            })
+        }
+
+        fn record(&mut self, path: &str) -> Result<()> {
+            // One write per line, so readers never see two lines interleaved
+            let line = format!("{}\n", path);
+            self.file
+                .write_all(line.as_bytes())
+                .context("Failed to write fsmonitor log")?;
+            self.size += line.len() as u64;
+            Ok(())
+        }
+    }
+
+    /// Whether an event can change what `status` reports
+    fn is_change(kind: &EventKind) -> bool {
+        match kind {
+            EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+            EventKind::Access(_) => false,
+            _ => true,
+        }
+    }
+
+    /// Path relative to the repository, `/`-separated, outside `.mediagit`
+    fn relative_path(repo_root: &Path, path: &Path) -> Option<String> {
+        let rel = path.strip_prefix(repo_root).ok()?;
+        if rel.as_os_str().is_empty() || rel.starts_with(".mediagit") {
+            return None;
+        }
+        Some(rel.to_string_lossy().replace('\\', "/"))
+    }
+
+    /// Watch the working tree until asked to stop, logging changed paths
+    pub fn run(repo_root: &Path) -> Result<()> {
+        let repo_root = dunce::canonicalize(repo_root).unwrap_or_else(|_| repo_root.to_path_buf());
+        let dir = FsMonitor::new(&repo_root).dir;
+        std::fs::create_dir_all(&dir).context("Failed to create fsmonitor directory")?;
+
+        let lock = File::create(dir.join(LOCK_FILE)).context("Failed to create fsmonitor lock")?;
+        if let Err(e) = lock.try_lock() {
+            anyhow::bail!("fsmonitor is already running for this repository ({})", e);
+        }
+        let _ = std::fs::remove_file(dir.join(STOP_FILE));
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(tx).context("Failed to create filesystem watcher")?;
+        watcher
+            .watch(&repo_root, RecursiveMode::Recursive)
+            .context("Failed to watch working tree")?;
+
+        // The log exists only once the watch is in place
+        let mut log = EventLog::create(&dir)?;
+
+        loop {
+            match rx.recv_timeout(POLL_INTERVAL) {
+                Ok(Ok(event)) => {
+                    if event.need_rescan() {
+                        debug!("fsmonitor: events were lost, restarting log");
+                        log = EventLog::create(&dir)?;
+                    } else if is_change(&event.kind) {
+                        for path in &event.paths {
+                            match relative_path(&repo_root, path) {
+                                // A newline would split the entry in two
+                                Some(rel) if rel.contains('\n') => log = EventLog::create(&dir)?,
+                                Some(rel) => log.record(&rel)?,
+                                None => {}
+                            }
+                        }
+                        if log.size > MAX_LOG_SIZE {
+                            log = EventLog::create(&dir)?;
+                        }
+                    }
+                }
+                Ok(Err(e)) => return Err(e).context("Filesystem watcher failed"),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    anyhow::bail!("Filesystem watcher stopped unexpectedly")
+                }
+            }
+
+            if dir.join(STOP_FILE).exists() {
+                let _ = std::fs::remove_file(dir.join(STOP_FILE));
+                debug!("fsmonitor: stop requested");
+                return Ok(());
+            }
+        }
+    }
+
+    /// Start a background daemon for the repository
+    ///
+    /// Returns `false` if one was already running.
+    pub fn start(repo_root: &Path) -> Result<bool> {
+        let monitor = FsMonitor::new(repo_root);
+        if monitor.is_running() {
+            return Ok(false);
+        }
+
+        let mut command = std::process::Command::new(
+            std::env::current_exe().context("Failed to locate mediagit executable")?,
+        );
+        command
+            .args(["fsmonitor", "run"])
+            .current_dir(repo_root)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+        // Leave the terminal's process group so Ctrl-C there does not reach it
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let mut child = command.spawn().context("Failed to start fsmonitor")?;
+
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        while !(monitor.is_running() && monitor.dir.join(LOG_FILE).exists()) {
+            if let Some(status) = child.try_wait()? {
+                anyhow::bail!("fsmonitor exited during startup ({})", status);
+            }
+            if Instant::now() >= deadline {
+                anyhow::bail!("fsmonitor did not start within {:?}", STARTUP_TIMEOUT);
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        Ok(true)
+    }
+
+    /// Ask the running daemon to exit and wait for it
+    ///
+    /// Returns `false` if none was running.
+    pub fn stop(repo_root: &Path) -> Result<bool> {
+        let monitor = FsMonitor::new(repo_root);
+        if !monitor.is_running() {
+            return Ok(false);
+        }
+
+        std::fs::write(monitor.dir.join(STOP_FILE), b"")
+            .context("Failed to request fsmonitor stop")?;
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        while monitor.is_running() {
+            if Instant::now() >= deadline {
+                anyhow::bail!("fsmonitor did not stop within {:?}", STARTUP_TIMEOUT);
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_log(dir: &Path, contents: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join(LOG_FILE), contents).unwrap();
+    }
+
+    #[test]
+    fn test_read_log_requires_running_daemon() {
+        let repo = TempDir::new().unwrap();
+        let monitor = FsMonitor::new(repo.path());
+        write_log(&monitor.dir, "mediagit-fsmonitor one\nassets/a.png\n");
+
+        assert!(!monitor.is_running());
+        assert!(monitor.read_log(None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_log_since_token() {
+        let repo = TempDir::new().unwrap();
+        let monitor = FsMonitor::new(repo.path());
+        write_log(&monitor.dir, "mediagit-fsmonitor one\nassets/a.png\n");
+        let lock = File::create(monitor.dir.join(LOCK_FILE)).unwrap();
+        lock.lock().unwrap();
+
+        let (first, changed) = monitor.read_log(None).unwrap().unwrap();
+        assert_eq!(first.instance, "one");
+        assert!(changed.is_none());
+
+        // A partly written line is left for the next read
+        write_log(
+            &monitor.dir,
+            "mediagit-fsmonitor one\nassets/a.png\nscenes/b.blend\nscenes/c",
+        );
+        let (second, changed) = monitor.read_log(Some(&first)).unwrap().unwrap();
+        assert_eq!(
+            changed.unwrap(),
+            HashSet::from([PathBuf::from("scenes/b.blend")])
+        );
+        assert_eq!(
+            second.offset,
+            first.offset + "scenes/b.blend\n".len() as u64
+        );
+
+        // A restarted log cannot be compared with an old token
+        write_log(&monitor.dir, "mediagit-fsmonitor two\n");
+        let (third, changed) = monitor.read_log(Some(&second)).unwrap().unwrap();
+        assert_eq!(third.instance, "two");
+        assert!(changed.is_none());
+    }
+
+    #[test]
+    fn test_apply_changes() {
+        let repo = TempDir::new().unwrap();
+        std::fs::create_dir_all(repo.path().join("scenes/new")).unwrap();
+        std::fs::write(repo.path().join("a.png"), b"a").unwrap();
+        std::fs::write(repo.path().join("scenes/new/c.blend"), b"c").unwrap();
+
+        let mut scan = WorkingTreeScan::default();
+        scan.files.insert(PathBuf::from("a.png"));
+        scan.files.insert(PathBuf::from("old/b.png"));
+
+        let changed = HashSet::from([PathBuf::from("old"), PathBuf::from("scenes")]);
+        apply_changes(repo.path(), None, &mut scan, &changed).unwrap();
+
+        assert_eq!(
+            scan.files,
+            HashSet::from([PathBuf::from("a.png"), PathBuf::from("scenes/new/c.blend")])
+        );
+    }
+}
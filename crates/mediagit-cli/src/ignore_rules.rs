@@ -16,11 +16,13 @@
 //! - `!important.log` — negation: do NOT ignore `important.log`
 //! - `# comment` — line comments
 //!
-//! Used by both `add` and `status` commands.
+//! Used by the `add`, `status` and `clean` commands.
 
 use anyhow::Result;
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 /// Wraps a compiled set of `.mediagitignore` patterns.
 ///
@@ -70,3 +72,68 @@ impl IgnoreMatcher {
         repo_root.join(".mediagitignore").exists()
     }
 }
+
+/// Files found in the working tree, split by `.mediagitignore` status.
+///
+/// Paths are relative to the repository root with `/` separators.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WorkingTreeScan {
+    /// Files not matched by any ignore pattern
+    pub files: HashSet<PathBuf>,
+    /// Files matched directly by an ignore pattern
+    pub ignored_files: HashSet<PathBuf>,
+    /// Ignored directories; their contents are not enumerated
+    pub ignored_dirs: HashSet<PathBuf>,
+}
+
+/// Walk the working tree below `repo_root`, skipping `.mediagit`.
+///
+/// Ignored directories are pruned rather than descended into.
+pub fn scan_working_tree(
+    repo_root: &Path,
+    matcher: Option<&IgnoreMatcher>,
+) -> Result<WorkingTreeScan> {
+    let mut scan = WorkingTreeScan::default();
+    scan_directory(repo_root, repo_root, matcher, &mut scan)?;
+    Ok(scan)
+}
+
+/// Add the contents of `current_dir`, a directory below `repo_root`, to `scan`.
+pub(crate) fn scan_directory(
+    repo_root: &Path,
+    current_dir: &Path,
+    matcher: Option<&IgnoreMatcher>,
+    scan: &mut WorkingTreeScan,
+) -> Result<()> {
+    for entry in std::fs::read_dir(current_dir)? {
+        let path = entry?.path();
+
+        // Skip .mediagit directory
+        if path.file_name().and_then(|n| n.to_str()) == Some(".mediagit") {
+            continue;
+        }
+
+        let Ok(rel) = path.strip_prefix(repo_root) else {
+            continue;
+        };
+        let normalized = PathBuf::from(rel.to_string_lossy().replace('\\', "/"));
+        let is_dir = path.is_dir();
+
+        if matcher.is_some_and(|m| m.is_ignored(rel, is_dir)) {
+            if is_dir {
+                // Prune the entire subtree without enumerating children
+                scan.ignored_dirs.insert(normalized);
+            } else if path.is_file() {
+                scan.ignored_files.insert(normalized);
+            }
+            continue;
+        }
+
+        if path.is_file() {
+            scan.files.insert(normalized);
+        } else if is_dir {
+            scan_directory(repo_root, &path, matcher, scan)?;
+        }
+    }
+    Ok(())
+}
@@ -14,6 +14,7 @@
 #![allow(missing_docs)] // binary crate — documentation is in book/ not rustdoc
 
 mod commands;
+mod fsmonitor;
 mod ignore_rules;
 mod output;
 mod progress;
@@ -23,7 +24,7 @@ use anyhow::{Context, Result};
 use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::{generate, Shell};
 use commands::*;
-use mediagit_observability::{init_tracing, LogFormat};
+use mediagit_observability::{init_tracing_with_config, LogConfig, LogFormat};
 use std::io;
 
 #[derive(Parser)]
@@ -68,6 +69,9 @@ enum Commands {
     /// Stage file contents for commit
     Add(AddCmd),
 
+    /// Move or rename a file or directory
+    Mv(MvCmd),
+
     /// Record changes to the repository
     Commit(CommitCmd),
 
@@ -80,6 +84,12 @@ enum Commands {
     /// Fetch remote changes without merging
     Fetch(FetchCmd),
 
+    /// Package refs and objects into a file for offline transfer
+    Bundle(BundleCmd),
+
+    /// Write a commit's files to a directory without repository metadata
+    Export(ExportCmd),
+
     /// Manage remote repositories
     Remote(RemoteCmd),
 
@@ -92,6 +102,9 @@ enum Commands {
     /// Merge branches
     Merge(MergeCmd),
 
+    /// Run a merge tool on conflicted files
+    Mergetool(MergetoolCmd),
+
     /// Rebase commits
     Rebase(RebaseCmd),
 
@@ -111,15 +124,28 @@ enum Commands {
     /// Show changes between commits
     Diff(DiffCmd),
 
+    /// Apply a patch to files
+    Apply(ApplyCmd),
+
     /// Show object information
     Show(ShowCmd),
 
     /// Show working tree status
     Status(StatusCmd),
 
+    /// Inspect and maintain the staging index
+    Index(IndexCmd),
+
+    /// Remove untracked files from the working tree
+    Clean(CleanCmd),
+
     /// Clean up repository and optimize storage
     Gc(GcCmd),
 
+    /// Watch the working tree to speed up status
+    #[cfg(feature = "fsmonitor")]
+    Fsmonitor(FsmonitorCmd),
+
     /// Check repository integrity
     Fsck(FsckCmd),
 
@@ -129,6 +155,16 @@ enum Commands {
     /// Show repository statistics
     Stats(StatsCmd),
 
+    /// Check that the storage backend is reachable and usable
+    Doctor(DoctorCmd),
+
+    /// Show the compression strategy that would be chosen for a file
+    #[command(name = "compress-info")]
+    CompressInfo(CompressInfoCmd),
+
+    /// Measure compression algorithms on the repository's files
+    Benchmark(BenchmarkCmd),
+
     /// Show reference logs (reflog)
     Reflog(ReflogCmd),
 
@@ -287,17 +323,16 @@ async fn async_main(cli: Cli) -> Result<()> {
     let machine_readable = matches!(
         &cli.command,
         Some(Commands::Stats(cmd)) if cmd.json || cmd.prometheus
+    ) || matches!(
+        &cli.command,
+        Some(Commands::CompressInfo(cmd)) if cmd.format == "json"
+    ) || matches!(
+        &cli.command,
+        Some(Commands::Benchmark(BenchmarkCmd {
+            command: benchmark::BenchmarkSubcommand::Compression(args),
+        })) if args.format == "json"
     );
 
-    // Initialize structured logging
-    if !cli.quiet && !machine_readable {
-        let level = if cli.verbose { "info" } else { "warn" };
-        let format = LogFormat::Pretty; // Pretty format for CLI output
-
-        // Initialize with appropriate log level
-        init_tracing(format, Some(level)).ok(); // Ignore errors if already initialized
-    }
-
     // Handle color output
     match cli.color.as_str() {
         "never" => console::set_colors_enabled(false),
@@ -325,15 +360,47 @@ async fn async_main(cli: Cli) -> Result<()> {
         }
     }
 
+    // Initialize structured logging, after -C so the repository's config is found
+    if !cli.quiet && !machine_readable {
+        let default_level = if cli.verbose { "info" } else { "warn" };
+        let level = std::env::var("RUST_LOG").unwrap_or_else(|_| default_level.to_string());
+
+        // Pretty format for CLI output, unless RUST_LOG_FORMAT asks for e.g. json
+        let format = match std::env::var("RUST_LOG_FORMAT") {
+            Ok(value) if value != "text" => LogFormat::parse(&value).unwrap_or_default(),
+            _ => LogFormat::Pretty,
+        };
+
+        // Per-module levels from [observability.module_levels]
+        let module_levels = match repo::find_repo_root() {
+            Ok(root) => mediagit_config::Config::load(&root)
+                .await
+                .map(|config| config.observability.module_levels)
+                .unwrap_or_default(),
+            Err(_) => Default::default(),
+        };
+        let log_config = module_levels.into_iter().fold(
+            LogConfig::new().with_format(format).with_level(level),
+            |log_config, (module, level)| log_config.with_module_level(module, level),
+        );
+
+        if let Err(e) = init_tracing_with_config(log_config) {
+            eprintln!("warning: logging disabled: {}", e);
+        }
+    }
+
     // Execute command
     match cli.command {
         Some(Commands::Init(cmd)) => cmd.execute().await,
         Some(Commands::Clone(cmd)) => cmd.execute().await,
         Some(Commands::Add(cmd)) => cmd.execute().await,
+        Some(Commands::Mv(cmd)) => cmd.execute().await,
         Some(Commands::Commit(cmd)) => cmd.execute().await,
         Some(Commands::Push(cmd)) => cmd.execute().await,
         Some(Commands::Pull(cmd)) => cmd.execute().await,
         Some(Commands::Fetch(cmd)) => cmd.execute().await,
+        Some(Commands::Bundle(cmd)) => cmd.execute().await,
+        Some(Commands::Export(cmd)) => cmd.execute().await,
         Some(Commands::Remote(cmd)) => cmd.execute().await,
         Some(Commands::Branch(cmd)) => cmd.execute().await,
         Some(Commands::Tag(cmd)) => {
@@ -341,18 +408,27 @@ async fn async_main(cli: Cli) -> Result<()> {
             cmd.execute(repo_path).await
         }
         Some(Commands::Merge(cmd)) => cmd.execute().await,
+        Some(Commands::Mergetool(cmd)) => cmd.execute().await,
         Some(Commands::Rebase(cmd)) => cmd.execute().await,
         Some(Commands::CherryPick(cmd)) => cmd.execute().await,
         Some(Commands::Stash(cmd)) => cmd.execute().await,
         Some(Commands::Bisect(cmd)) => cmd.execute().await,
         Some(Commands::Log(cmd)) => cmd.execute().await,
         Some(Commands::Diff(cmd)) => cmd.execute().await,
+        Some(Commands::Apply(cmd)) => cmd.execute().await,
         Some(Commands::Show(cmd)) => cmd.execute().await,
         Some(Commands::Status(cmd)) => cmd.execute().await,
+        Some(Commands::Index(cmd)) => cmd.execute().await,
+        Some(Commands::Clean(cmd)) => cmd.execute().await,
         Some(Commands::Gc(cmd)) => cmd.execute().await,
+        #[cfg(feature = "fsmonitor")]
+        Some(Commands::Fsmonitor(cmd)) => cmd.execute().await,
         Some(Commands::Fsck(cmd)) => cmd.execute().await,
         Some(Commands::Verify(cmd)) => cmd.execute().await,
         Some(Commands::Stats(cmd)) => cmd.execute().await,
+        Some(Commands::Doctor(cmd)) => cmd.execute().await,
+        Some(Commands::CompressInfo(cmd)) => cmd.execute().await,
+        Some(Commands::Benchmark(cmd)) => cmd.execute().await,
         Some(Commands::Reflog(cmd)) => cmd.execute().await,
         Some(Commands::Reset(cmd)) => cmd.execute().await,
         Some(Commands::Revert(cmd)) => cmd.execute().await,
@@ -373,26 +449,34 @@ async fn async_main(cli: Cli) -> Result<()> {
             println!("  init         Initialize a new MediaGit repository");
             println!("  clone        Clone a repository into a new directory");
             println!("  add          Stage file contents for commit");
+            println!("  mv           Move or rename a file or directory");
             println!("  commit       Record changes to the repository");
             println!("  push         Update remote references");
             println!("  pull         Fetch and integrate remote changes");
             println!("  fetch        Fetch remote changes without merging");
+            println!("  bundle       Package refs and objects into a file");
+            println!("  export       Write a commit's files to a plain directory");
             println!("  remote       Manage remote repositories");
             println!("  branch       Manage branches");
             println!("  tag          Manage tags");
             println!("  merge        Merge branches");
+            println!("  mergetool    Run a merge tool on conflicted files");
             println!("  rebase       Rebase commits");
             println!("  cherry-pick  Apply changes from existing commits");
             println!("  stash        Stash changes in working directory");
             println!("  bisect       Find commit that introduced a bug using binary search");
             println!("  log          Show commit history");
             println!("  diff         Show changes between commits");
+            println!("  apply        Apply a patch to files");
             println!("  show         Show object information");
             println!("  status       Show working tree status");
+            println!("  index        Refresh cached file stats in the index");
+            println!("  clean        Remove untracked files");
             println!("  gc           Clean up repository");
             println!("  fsck         Check repository integrity");
             println!("  verify       Verify commits and signatures");
             println!("  stats        Show repository statistics");
+            println!("  doctor       Check the storage backend");
             println!();
             println!("Run 'mediagit <COMMAND> --help' for command-specific help");
             Ok(())
@@ -16,7 +16,10 @@ use indicatif::{
     HumanBytes, HumanDuration, MultiProgress, ProgressBar, ProgressDrawTarget, ProgressFinish,
     ProgressStyle,
 };
+use mediagit_compression::{CompressionAlgorithm, ObjectCategory};
+use mediagit_versioning::{CategoryMetrics, CompressionTotals, OdbMetrics};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
@@ -274,6 +277,129 @@ impl OperationStats {
     }
 }
 
+/// Per-category write counters accumulated across staging runs
+///
+/// The object database only counts writes for the lifetime of one process;
+/// `add` folds each run's counters into `.mediagit/category_stats.json` so
+/// `stats` can report deduplication by object category for the repository.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CategoryStats {
+    pub by_category: HashMap<ObjectCategory, CategoryMetrics>,
+}
+
+impl CategoryStats {
+    const FILE_NAME: &'static str = "category_stats.json";
+
+    /// Load accumulated counters (empty if nothing was recorded yet)
+    pub fn load(storage_path: &Path) -> anyhow::Result<Self> {
+        let path = storage_path.join(Self::FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Add one session's counters to the stored totals
+    pub fn record(storage_path: &Path, metrics: &OdbMetrics) -> anyhow::Result<()> {
+        if metrics.by_category.is_empty() {
+            return Ok(());
+        }
+
+        let mut stats = Self::load(storage_path).unwrap_or_default();
+        for (category, counters) in &metrics.by_category {
+            stats
+                .by_category
+                .entry(*category)
+                .or_default()
+                .merge(counters);
+        }
+
+        let json = serde_json::to_string_pretty(&stats)?;
+        std::fs::write(storage_path.join(Self::FILE_NAME), json)?;
+        Ok(())
+    }
+
+    /// Categories sorted by bytes written, largest first
+    pub fn sorted(&self) -> Vec<(ObjectCategory, CategoryMetrics)> {
+        let mut categories: Vec<_> = self.by_category.iter().map(|(c, m)| (*c, *m)).collect();
+        categories.sort_by_key(|(_, m)| std::cmp::Reverse(m.bytes_written));
+        categories
+    }
+}
+
+/// Compression totals accumulated across staging runs
+///
+/// Kept in `.mediagit/compression_stats.json` alongside [`CategoryStats`] so
+/// `stats --compression` can show how each algorithm and object category
+/// compresses in this repository.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CompressionStats {
+    pub by_algorithm: HashMap<CompressionAlgorithm, CompressionTotals>,
+    pub by_category: HashMap<ObjectCategory, CompressionTotals>,
+}
+
+impl CompressionStats {
+    const FILE_NAME: &'static str = "compression_stats.json";
+
+    /// Load accumulated totals (empty if nothing was recorded yet)
+    pub fn load(storage_path: &Path) -> anyhow::Result<Self> {
+        let path = storage_path.join(Self::FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Add one session's totals to the stored totals
+    pub fn record(storage_path: &Path, metrics: &OdbMetrics) -> anyhow::Result<()> {
+        if metrics.compression_by_algorithm.is_empty() {
+            return Ok(());
+        }
+
+        let mut stats = Self::load(storage_path).unwrap_or_default();
+        stats.merge(metrics);
+
+        let json = serde_json::to_string_pretty(&stats)?;
+        std::fs::write(storage_path.join(Self::FILE_NAME), json)?;
+        Ok(())
+    }
+
+    fn merge(&mut self, metrics: &OdbMetrics) {
+        for (algorithm, totals) in &metrics.compression_by_algorithm {
+            self.by_algorithm
+                .entry(*algorithm)
+                .or_default()
+                .merge(totals);
+        }
+        for (category, totals) in &metrics.compression_by_category {
+            self.by_category.entry(*category).or_default().merge(totals);
+        }
+    }
+
+    /// Totals across every algorithm
+    pub fn total(&self) -> CompressionTotals {
+        let mut total = CompressionTotals::default();
+        for totals in self.by_algorithm.values() {
+            total.merge(totals);
+        }
+        total
+    }
+
+    /// Algorithms sorted by bytes in, largest first
+    pub fn sorted_algorithms(&self) -> Vec<(CompressionAlgorithm, CompressionTotals)> {
+        let mut algorithms: Vec<_> = self.by_algorithm.iter().map(|(a, t)| (*a, *t)).collect();
+        algorithms.sort_by_key(|(_, t)| std::cmp::Reverse(t.bytes_in));
+        algorithms
+    }
+
+    /// Categories sorted by bytes in, largest first
+    pub fn sorted_categories(&self) -> Vec<(ObjectCategory, CompressionTotals)> {
+        let mut categories: Vec<_> = self.by_category.iter().map(|(c, t)| (*c, *t)).collect();
+        categories.sort_by_key(|(_, t)| std::cmp::Reverse(t.bytes_in));
+        categories
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -324,4 +450,27 @@ mod tests {
         // HumanDuration formats durations in human readable format
         assert!(summary.contains("in "), "Expected 'in ', got: {}", summary);
     }
+
+    #[test]
+    fn test_compression_stats_accumulate_across_runs() {
+        let dir = tempfile::TempDir::new().unwrap();
+
+        let mut session = OdbMetrics::new();
+        session.record_compression(ObjectCategory::Text, CompressionAlgorithm::Zstd, 1000, 200);
+        session.record_compression(ObjectCategory::Image, CompressionAlgorithm::None, 500, 501);
+        CompressionStats::record(dir.path(), &session).unwrap();
+        CompressionStats::record(dir.path(), &session).unwrap();
+
+        let stats = CompressionStats::load(dir.path()).unwrap();
+        let zstd = stats.by_algorithm[&CompressionAlgorithm::Zstd];
+        assert_eq!(zstd.objects, 2);
+        assert_eq!(zstd.bytes_in, 2000);
+        assert_eq!(zstd.bytes_out, 400);
+        assert_eq!(stats.total().bytes_in, 3000);
+        assert_eq!(
+            stats.sorted_categories()[0].0,
+            ObjectCategory::Text,
+            "largest category first"
+        );
+    }
 }
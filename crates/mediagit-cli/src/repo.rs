@@ -131,9 +131,48 @@ pub fn find_repo_root_from(start: &std::path::Path) -> Result<PathBuf> {
     }
 }
 
+/// Proxy and cloud backend timeout settings from the repository config
+pub use mediagit_storage::factory::{proxy_settings, upload_limits};
+
+/// Tree parsing and walking bounds from the `[trees]` config section
+pub fn tree_limits(config: &mediagit_config::Config) -> mediagit_versioning::TreeLimits {
+    mediagit_versioning::TreeLimits {
+        max_depth: config.trees.max_depth,
+        max_entries: config.trees.max_entries,
+    }
+}
+
+/// Commit and tree layout from the `[objects]` config section
+pub fn object_format(config: &mediagit_config::Config) -> mediagit_versioning::ObjectFormat {
+    match config.objects.format {
+        mediagit_config::ObjectFormat::Native => mediagit_versioning::ObjectFormat::Native,
+        mediagit_config::ObjectFormat::Git => mediagit_versioning::ObjectFormat::Git,
+    }
+}
+
+/// Compression tier from `compression.tier` in the repository config
+pub fn compression_tier(config: &mediagit_config::Config) -> mediagit_compression::CompressionTier {
+    match config.compression.tier {
+        mediagit_config::CompressionTier::Standard => {
+            mediagit_compression::CompressionTier::Standard
+        }
+        mediagit_config::CompressionTier::Archival => {
+            mediagit_compression::CompressionTier::Archival
+        }
+    }
+}
+
+/// Brotli settings from `[compression.brotli]` in the repository config
+pub fn brotli_settings(config: &mediagit_config::Config) -> mediagit_compression::BrotliSettings {
+    mediagit_compression::BrotliSettings {
+        window: config.compression.brotli.window,
+        quality: config.compression.brotli.quality,
+    }
+}
+
 /// Create the appropriate storage backend based on repository config.
 ///
-/// Reads `.mediagit/config.toml` to determine backend type (filesystem, S3, Azure, GCS).
+/// Reads `.mediagit/config.toml` to determine backend type (filesystem, S3, Azure, GCS, SFTP).
 /// Falls back to local filesystem if config is missing or uses default storage.
 ///
 /// # Arguments
@@ -142,99 +181,188 @@ pub fn find_repo_root_from(start: &std::path::Path) -> Result<PathBuf> {
 /// # Returns
 /// An `Arc<dyn StorageBackend>` configured per the repository's config.toml
 pub async fn create_storage_backend(repo_root: &Path) -> Result<Arc<dyn StorageBackend>> {
-    let mediagit_dir = repo_root.join(".mediagit");
-
     // Load config (returns default if config.toml doesn't exist)
     let config = mediagit_config::Config::load(repo_root)
         .await
         .unwrap_or_default();
 
+    // The CLI saves configs it loads, so env overrides are applied here only
+    let storage = mediagit_storage::BackendFactory::for_repo(&config, repo_root)
+        .with_env_overrides(true)
+        .open_config(&config.storage)
+        .await?;
+    let storage = with_encryption(storage, &config, repo_root)?;
+    let storage = with_disk_cache(storage, &config, repo_root).await?;
+    let storage = with_hashed_keys(storage, &config, repo_root)?;
+    let storage = with_quota(storage, config.quota.max_bytes);
+
+    // Trace inside the limiter so durations exclude time spent waiting for a permit
+    let storage = Arc::new(mediagit_storage::InstrumentedBackend::new(
+        storage,
+        config.storage.backend_name(),
+    ));
+    let storage = with_operation_limit(storage, config.storage.max_concurrent_ops());
+    // Outside the limiter, so writes waiting for room don't hold a permit
+    Ok(Arc::new(mediagit_storage::PooledUploadBackend::new(
+        storage,
+        mediagit_storage::shared_upload_pool(upload_limits(&config)),
+    )))
+}
+
+/// The local object store of the repository at `repo_root`, or `None` if
+/// its objects are kept elsewhere
+pub async fn local_object_store(
+    repo_root: &Path,
+) -> Result<Option<mediagit_storage::LocalBackend>> {
+    let config = mediagit_config::Config::load(repo_root)
+        .await
+        .unwrap_or_default();
     match &config.storage {
-        mediagit_config::StorageConfig::FileSystem(fs_config) => {
-            let storage_path = if std::path::Path::new(&fs_config.base_path).is_absolute() {
-                PathBuf::from(&fs_config.base_path)
-            } else if fs_config.base_path == "./data" {
-                // Default config value - use .mediagit
-                mediagit_dir.clone()
-            } else {
-                repo_root.join(&fs_config.base_path)
-            };
-            let storage = mediagit_storage::LocalBackend::new(&storage_path)
-                .await
-                .context("Failed to initialize filesystem storage backend")?;
-            Ok(Arc::new(storage))
-        }
-        mediagit_config::StorageConfig::S3(s3_config) => {
-            if let Some(endpoint) = &s3_config.endpoint {
-                // S3-compatible (MinIO, DigitalOcean Spaces, etc.)
-                let storage = mediagit_storage::MinIOBackend::new(
-                    endpoint,
-                    &s3_config.bucket,
-                    s3_config.access_key_id.as_deref().unwrap_or(""),
-                    s3_config.secret_access_key.as_deref().unwrap_or(""),
-                )
-                .await
-                .context("Failed to initialize S3-compatible storage backend")?;
-                Ok(Arc::new(storage))
-            } else {
-                // AWS S3
-                let aws_endpoint = format!("https://s3.{}.amazonaws.com", s3_config.region);
-                let storage = mediagit_storage::MinIOBackend::new(
-                    &aws_endpoint,
-                    &s3_config.bucket,
-                    s3_config.access_key_id.as_deref().unwrap_or(""),
-                    s3_config.secret_access_key.as_deref().unwrap_or(""),
-                )
-                .await
-                .context("Failed to initialize AWS S3 storage backend")?;
-                Ok(Arc::new(storage))
-            }
-        }
-        mediagit_config::StorageConfig::Azure(azure_config) => {
-            let storage = if let Some(conn_str) = &azure_config.connection_string {
-                mediagit_storage::AzureBackend::with_connection_string(
-                    &azure_config.container,
-                    conn_str,
-                )
-                .await
-                .context("Failed to initialize Azure storage backend")?
-            } else if let Some(account_key) = &azure_config.account_key {
-                mediagit_storage::AzureBackend::with_account_key(
-                    &azure_config.account_name,
-                    &azure_config.container,
-                    account_key,
-                )
-                .await
-                .context("Failed to initialize Azure storage backend")?
-            } else {
-                anyhow::bail!("Azure backend requires either connection_string or account_key");
-            };
-            Ok(Arc::new(storage))
-        }
-        mediagit_config::StorageConfig::GCS(gcs_config) => {
-            let credentials_path = gcs_config.credentials_path.as_deref().unwrap_or("");
-
-            let storage = if credentials_path.is_empty() {
-                mediagit_storage::GcsBackend::with_default_credentials(
-                    &gcs_config.project_id,
-                    &gcs_config.bucket,
-                )
-                .await
-                .context("Failed to initialize GCS storage backend")?
-            } else {
-                mediagit_storage::GcsBackend::new(
-                    &gcs_config.project_id,
-                    &gcs_config.bucket,
-                    credentials_path,
-                )
-                .await
-                .context("Failed to initialize GCS storage backend")?
-            };
-            Ok(Arc::new(storage))
-        }
-        mediagit_config::StorageConfig::Multi(_) => {
-            anyhow::bail!("Multi-backend storage is not yet implemented");
-        }
+        mediagit_config::StorageConfig::FileSystem(fs_config) => Ok(Some(
+            mediagit_storage::BackendFactory::for_repo(&config, repo_root)
+                .open_filesystem(fs_config)
+                .await?,
+        )),
+        _ => Ok(None),
+    }
+}
+
+/// Keep objects read from remote storage in `.mediagit/object-cache`, if
+/// `performance.cache.cache_type` is `"disk"`
+///
+/// Filesystem storage is already local, so it is never cached.
+async fn with_disk_cache(
+    storage: Arc<dyn StorageBackend>,
+    config: &mediagit_config::Config,
+    repo_root: &Path,
+) -> Result<Arc<dyn StorageBackend>> {
+    let cache = &config.performance.cache;
+    if !cache.enabled
+        || cache.cache_type != "disk"
+        || matches!(
+            config.storage,
+            mediagit_config::StorageConfig::FileSystem(_)
+        )
+    {
+        return Ok(storage);
+    }
+
+    let disk_cache = mediagit_storage::cache::DiskCache::open(
+        repo_root.join(".mediagit").join("object-cache"),
+        cache.max_size,
+    )
+    .await
+    .context("Failed to open object cache")?
+    .with_ttl(std::time::Duration::from_secs(cache.ttl));
+    Ok(Arc::new(mediagit_storage::CachedBackend::new(
+        storage, disk_cache,
+    )))
+}
+
+/// Encrypt object payloads with the repository key, if
+/// `security.encryption_at_rest` is set
+///
+/// The key file at `security.encryption_key_path` (relative to the
+/// repository root) holds 32 raw bytes or 64 hex digits. Its file stem names
+/// the key in each object's header.
+fn with_encryption(
+    storage: Arc<dyn StorageBackend>,
+    config: &mediagit_config::Config,
+    repo_root: &Path,
+) -> Result<Arc<dyn StorageBackend>> {
+    let security = &config.security;
+    if !security.encryption_at_rest {
+        return Ok(storage);
+    }
+
+    let key_path = security
+        .encryption_key_path
+        .as_deref()
+        .context("security.encryption_at_rest requires security.encryption_key_path")?;
+    let key_path = repo_root.join(key_path);
+    let contents = std::fs::read(&key_path)
+        .with_context(|| format!("Failed to read encryption key: {}", key_path.display()))?;
+    let bytes = match hex::decode(contents.trim_ascii()) {
+        Ok(bytes) if bytes.len() == 32 => bytes,
+        _ => contents,
+    };
+    let key =
+        mediagit_security::encryption::EncryptionKey::from_bytes(bytes).with_context(|| {
+            format!(
+                "Encryption key must be 32 bytes or 64 hex digits: {}",
+                key_path.display()
+            )
+        })?;
+    let key_id = key_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .with_context(|| format!("Encryption key has no usable name: {}", key_path.display()))?;
+
+    Ok(Arc::new(mediagit_storage::EncryptedBackend::new(
+        storage, key_id, key,
+    )?))
+}
+
+/// Store keys hashed with the repository secret, if `security.hash_storage_keys`
+/// is set
+///
+/// The key index used for listing is kept in the same storage, encrypted
+/// along with the objects, so every clone sharing the secret and encryption
+/// key can list it.
+fn with_hashed_keys(
+    storage: Arc<dyn StorageBackend>,
+    config: &mediagit_config::Config,
+    repo_root: &Path,
+) -> Result<Arc<dyn StorageBackend>> {
+    let security = &config.security;
+    if !security.hash_storage_keys {
+        return Ok(storage);
+    }
+    // The index records every key as written
+    if !security.encryption_at_rest {
+        anyhow::bail!("security.hash_storage_keys requires security.encryption_at_rest");
+    }
+
+    let secret_path = security
+        .storage_key_secret_path
+        .as_deref()
+        .context("security.hash_storage_keys requires security.storage_key_secret_path")?;
+    let secret_path = repo_root.join(secret_path);
+    let secret = std::fs::read(&secret_path).with_context(|| {
+        format!(
+            "Failed to read storage key secret: {}",
+            secret_path.display()
+        )
+    })?;
+    let secret = secret.trim_ascii();
+    if secret.is_empty() {
+        anyhow::bail!("Storage key secret is empty: {}", secret_path.display());
+    }
+
+    Ok(Arc::new(mediagit_storage::HashedKeyBackend::new(
+        storage, secret,
+    )))
+}
+
+/// Refuse writes past `quota.max_bytes`, if a quota is configured
+fn with_quota(storage: Arc<dyn StorageBackend>, max_bytes: Option<u64>) -> Arc<dyn StorageBackend> {
+    match max_bytes {
+        Some(max_bytes) => Arc::new(mediagit_storage::QuotaBackend::new(storage, max_bytes)),
+        None => storage,
+    }
+}
+
+/// Share the process-wide operation limit with `storage`, if one is configured
+fn with_operation_limit(
+    storage: Arc<dyn StorageBackend>,
+    max_ops: Option<usize>,
+) -> Arc<dyn StorageBackend> {
+    match max_ops {
+        Some(max_ops) => Arc::new(mediagit_storage::ConcurrencyLimitedBackend::new(
+            storage,
+            mediagit_storage::shared_limiter(max_ops),
+        )),
+        None => storage,
     }
 }
 
@@ -305,4 +433,79 @@ mod tests {
         let result = normalize_path(Path::new(".\\test.ai"), &repo_root);
         assert_eq!(result, PathBuf::from("test.ai"));
     }
+
+    /// A repository on `machine` whose objects live in `bucket`, encrypted
+    /// and under hashed keys
+    fn protected_repo(machine: &Path, bucket: &Path) {
+        let dot = machine.join(".mediagit");
+        std::fs::create_dir_all(dot.join("keys")).unwrap();
+        std::fs::write(dot.join("keys").join("2025-01.key"), "ab".repeat(32)).unwrap();
+        std::fs::write(dot.join("key-secret"), "studio secret\n").unwrap();
+        std::fs::write(
+            dot.join("config.toml"),
+            format!(
+                "[storage]\nbackend = \"filesystem\"\nbase_path = {:?}\n\n\
+                 [security]\nencryption_at_rest = true\n\
+                 encryption_key_path = \".mediagit/keys/2025-01.key\"\n\
+                 hash_storage_keys = true\n\
+                 storage_key_secret_path = \".mediagit/key-secret\"\n",
+                bucket.to_str().unwrap()
+            ),
+        )
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_hashed_keys_are_encrypted_and_listed_from_any_machine() {
+        let temp = TempDir::new().unwrap();
+        let bucket = temp.path().join("bucket");
+        let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+        protected_repo(&a, &bucket);
+        protected_repo(&b, &bucket);
+
+        let storage = create_storage_backend(&a).await.unwrap();
+        storage
+            .put("textures/hero.png", b"hero pixels")
+            .await
+            .unwrap();
+
+        // The provider sees neither the key nor the content
+        for entry in walkdir::WalkDir::new(&bucket) {
+            let entry = entry.unwrap();
+            assert!(!entry.path().to_string_lossy().contains("hero"));
+            if entry.file_type().is_file() {
+                let stored = std::fs::read(entry.path()).unwrap();
+                assert!(!stored.windows(4).any(|w| w == b"hero"));
+            }
+        }
+        assert!(!a.join(".mediagit").join("key-index").exists());
+
+        // Another machine with the same key and secret lists and reads it
+        let storage = create_storage_backend(&b).await.unwrap();
+        assert_eq!(
+            storage.list_objects("textures/").await.unwrap(),
+            ["textures/hero.png"]
+        );
+        assert_eq!(
+            storage.get("textures/hero.png").await.unwrap(),
+            b"hero pixels"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hashed_keys_require_encryption() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path().join("repo");
+        protected_repo(&repo, &temp.path().join("bucket"));
+        let config = repo.join(".mediagit").join("config.toml");
+        let toml = std::fs::read_to_string(&config).unwrap();
+        std::fs::write(
+            &config,
+            toml.replace("encryption_at_rest = true", "encryption_at_rest = false"),
+        )
+        .unwrap();
+
+        let err = create_storage_backend(&repo).await.unwrap_err();
+        assert!(err.to_string().contains("encryption_at_rest"));
+    }
 }
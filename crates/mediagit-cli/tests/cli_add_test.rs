@@ -685,6 +685,114 @@ fn test_add_help() {
         .stdout(predicate::str::contains("Stage"));
 }
 
+// ============================================================================
+// Line Ending Tests
+// ============================================================================
+
+/// Commit `files` (path, content) along with a `.mediagitattributes` file
+fn commit_with_attributes(dir: &Path, attributes: &str, files: &[(&str, &[u8])]) {
+    fs::write(dir.join(".mediagitattributes"), attributes).unwrap();
+    for (name, content) in files {
+        fs::write(dir.join(name), content).unwrap();
+    }
+
+    mediagit()
+        .arg("add")
+        .arg(".")
+        .current_dir(dir)
+        .assert()
+        .success();
+    mediagit()
+        .arg("commit")
+        .arg("-m")
+        .arg("Add text assets")
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+/// Delete `names` from the working tree and restore them from HEAD
+fn restore_from_head(dir: &Path, names: &[&str]) {
+    for name in names {
+        fs::remove_file(dir.join(name)).unwrap();
+    }
+    mediagit()
+        .arg("reset")
+        .arg("--hard")
+        .arg("HEAD")
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_add_text_normalizes_crlf_and_checkout_restores_eol() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+
+    commit_with_attributes(
+        dir,
+        "*.sh text eol=lf\n*.bat text eol=crlf\n*.cfg text\n",
+        &[
+            ("build.sh", b"echo one\r\necho two\r\n"),
+            ("build.bat", b"echo one\r\necho two\r\n"),
+            ("app.cfg", b"key=value\r\nother=1\r\n"),
+        ],
+    );
+
+    // Working copies still have CRLF but match the normalized blobs
+    mediagit()
+        .arg("status")
+        .arg("--porcelain")
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("build.sh").not())
+        .stdout(predicate::str::contains("app.cfg").not());
+
+    restore_from_head(dir, &["build.sh", "build.bat", "app.cfg"]);
+
+    // eol=lf restores the stored content verbatim, proving it was stored as LF
+    assert_eq!(
+        fs::read(dir.join("build.sh")).unwrap(),
+        b"echo one\necho two\n"
+    );
+    assert_eq!(
+        fs::read(dir.join("build.bat")).unwrap(),
+        b"echo one\r\necho two\r\n"
+    );
+
+    // No eol attribute: restored with the platform's native line endings
+    let expected_cfg: &[u8] = if cfg!(windows) {
+        b"key=value\r\nother=1\r\n"
+    } else {
+        b"key=value\nother=1\n"
+    };
+    assert_eq!(fs::read(dir.join("app.cfg")).unwrap(), expected_cfg);
+}
+
+#[test]
+fn test_add_leaves_binary_and_unmarked_files_untouched() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+
+    // Binary-looking content under text=auto, and a file with no attributes
+    let binary: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\r\n";
+    let unmarked: &[u8] = b"line one\r\nline two\r\n";
+    commit_with_attributes(
+        dir,
+        "*.png text=auto\n",
+        &[("image.png", binary), ("notes.md", unmarked)],
+    );
+
+    restore_from_head(dir, &["image.png", "notes.md"]);
+
+    assert_eq!(fs::read(dir.join("image.png")).unwrap(), binary);
+    assert_eq!(fs::read(dir.join("notes.md")).unwrap(), unmarked);
+}
+
 // ============================================================================
 // Performance Benchmark
 // ============================================================================
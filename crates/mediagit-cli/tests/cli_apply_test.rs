@@ -0,0 +1,205 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! CLI Apply Command Tests
+//!
+//! Patches are produced by `mediagit diff --patch` between two commits and
+//! applied back onto the older content.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const ORIGINAL: &str = "intro\nline two\nline three\nline four\nline five\nline six\noutro\n";
+const UPDATED: &str = "intro\nline 2\nline three\nline four\nline five\nline six\noutro\n";
+
+#[allow(deprecated)]
+fn mediagit() -> Command {
+    Command::cargo_bin("mediagit").unwrap()
+}
+
+fn init_repo(dir: &Path) {
+    mediagit()
+        .arg("init")
+        .arg("-q")
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+fn add_and_commit(dir: &Path, name: &str, content: &str, message: &str) {
+    fs::write(dir.join(name), content).unwrap();
+    mediagit()
+        .arg("add")
+        .arg(name)
+        .current_dir(dir)
+        .assert()
+        .success();
+    mediagit()
+        .arg("commit")
+        .arg("-m")
+        .arg(message)
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+/// Commit ORIGINAL then UPDATED, and write the patch between them to change.patch
+fn repo_with_patch() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    add_and_commit(temp_dir.path(), "notes.txt", ORIGINAL, "Initial");
+    add_and_commit(temp_dir.path(), "notes.txt", UPDATED, "Edit notes");
+
+    let output = mediagit()
+        .args(["diff", "--patch", "HEAD~1", "HEAD"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    fs::write(temp_dir.path().join("change.patch"), output.stdout).unwrap();
+
+    temp_dir
+}
+
+fn read_notes(dir: &Path) -> String {
+    fs::read_to_string(dir.join("notes.txt")).unwrap()
+}
+
+#[test]
+fn test_apply_clean_patch() {
+    let temp_dir = repo_with_patch();
+    fs::write(temp_dir.path().join("notes.txt"), ORIGINAL).unwrap();
+
+    mediagit()
+        .args(["apply", "change.patch"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+
+    assert_eq!(read_notes(temp_dir.path()), UPDATED);
+}
+
+#[test]
+fn test_apply_detects_already_applied_patch() {
+    let temp_dir = repo_with_patch();
+
+    mediagit()
+        .args(["apply", "change.patch"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already applied"));
+
+    assert_eq!(read_notes(temp_dir.path()), UPDATED);
+}
+
+#[test]
+fn test_apply_three_way_fallback() {
+    let temp_dir = repo_with_patch();
+    // A local edit inside the hunk's context, away from the patched line
+    let local = ORIGINAL.replace("line four", "line FOUR");
+    fs::write(temp_dir.path().join("notes.txt"), &local).unwrap();
+
+    mediagit()
+        .args(["apply", "change.patch"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("does not match"));
+    assert_eq!(read_notes(temp_dir.path()), local);
+
+    mediagit()
+        .args(["apply", "--3way", "change.patch"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+
+    assert_eq!(
+        read_notes(temp_dir.path()),
+        UPDATED.replace("line four", "line FOUR")
+    );
+}
+
+#[test]
+fn test_apply_check_rejects_conflicting_patch() {
+    let temp_dir = repo_with_patch();
+    let local = ORIGINAL.replace("line two", "line deux");
+    fs::write(temp_dir.path().join("notes.txt"), &local).unwrap();
+
+    mediagit()
+        .args(["apply", "--check", "change.patch"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("hunk #1 does not match"));
+
+    assert_eq!(read_notes(temp_dir.path()), local);
+}
+
+#[test]
+fn test_apply_cached_updates_index_only() {
+    let temp_dir = repo_with_patch();
+    let patch = fs::read(temp_dir.path().join("change.patch")).unwrap();
+    mediagit()
+        .args(["reset", "--hard", "HEAD~1"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    fs::write(temp_dir.path().join("change.patch"), patch).unwrap();
+
+    mediagit()
+        .args(["apply", "--cached", "change.patch"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+
+    assert_eq!(read_notes(temp_dir.path()), ORIGINAL);
+    mediagit()
+        .args(["diff", "--cached", "--patch"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-line two\n+line 2\n"));
+}
+
+#[test]
+fn test_apply_rejects_paths_outside_working_tree() {
+    let temp_dir = repo_with_patch();
+    let repo = temp_dir.path().join("repo");
+    fs::create_dir(&repo).unwrap();
+    init_repo(&repo);
+    add_and_commit(&repo, "notes.txt", ORIGINAL, "Initial");
+
+    let valid = fs::read_to_string(temp_dir.path().join("change.patch")).unwrap();
+    for target in ["../outside.txt", ".mediagit/HEAD"] {
+        let patch = format!(
+            "{valid}diff --git a/{target} b/{target}\nnew file mode 100644\n\
+             --- /dev/null\n+++ b/{target}\n@@ -0,0 +1 @@\n+owned\n"
+        );
+        fs::write(repo.join("evil.patch"), patch).unwrap();
+
+        mediagit()
+            .args(["apply", "evil.patch"])
+            .current_dir(&repo)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Unsafe path"));
+    }
+
+    // Nothing was applied, not even the valid file
+    assert_eq!(read_notes(&repo), ORIGINAL);
+    assert!(!temp_dir.path().join("outside.txt").exists());
+}
@@ -0,0 +1,134 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! `mediagit benchmark compression` tests
+
+use assert_cmd::Command;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[allow(deprecated)]
+fn mediagit() -> Command {
+    Command::cargo_bin("mediagit").unwrap()
+}
+
+/// A repository with a compressible text file and an incompressible one
+fn setup_repo() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let repo = temp.path();
+    mediagit()
+        .args(["init", "-q"])
+        .current_dir(repo)
+        .assert()
+        .success();
+    fs::write(repo.join("notes.txt"), "frame 24 approved\n".repeat(500)).unwrap();
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    let noise: Vec<u8> = (0..16 * 1024)
+        .map(|_| {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 32) as u8
+        })
+        .collect();
+    fs::write(repo.join("noise.bin"), noise).unwrap();
+    mediagit()
+        .args(["add", "notes.txt", "noise.bin"])
+        .current_dir(repo)
+        .assert()
+        .success();
+    temp
+}
+
+fn benchmark(repo: &Path, args: &[&str]) -> Vec<Value> {
+    let output = mediagit()
+        .args(["benchmark", "compression", "--format", "json"])
+        .args(args)
+        .current_dir(repo)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice::<Value>(&output.stdout)
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .clone()
+}
+
+fn group<'a>(reports: &'a [Value], pattern: &str) -> &'a Value {
+    reports
+        .iter()
+        .find(|r| r["pattern"] == pattern)
+        .unwrap_or_else(|| panic!("no report for {}", pattern))
+}
+
+#[test]
+fn test_benchmark_reports_every_strategy_per_extension() {
+    let repo = setup_repo();
+    let reports = benchmark(repo.path(), &[]);
+    assert_eq!(reports.len(), 2);
+
+    let text = group(&reports, "*.txt");
+    assert_eq!(text["files"], 1);
+    assert_eq!(text["current"], "brotli");
+    let results = text["results"].as_array().unwrap();
+    assert!(results.iter().any(|r| r["strategy"] == "lz4"));
+    assert!(results.iter().any(|r| r["strategy"] == "xz-best"));
+    let zstd = results.iter().find(|r| r["strategy"] == "zstd").unwrap();
+    assert!(zstd["ratio"].as_f64().unwrap() > 10.0, "{}", zstd);
+
+    // Random bytes save nothing, whatever the algorithm
+    assert_eq!(group(&reports, "*.bin")["recommended"], "store");
+}
+
+#[test]
+fn test_benchmark_apply_writes_attributes() {
+    let repo = setup_repo();
+    // No throughput floor, so the recommendation for text is the smallest output
+    let reports = benchmark(repo.path(), &["--min-throughput", "0", "--apply"]);
+    let text = group(&reports, "*.txt");
+    let recommended = text["recommended"].as_str().unwrap();
+
+    let attributes =
+        fs::read_to_string(repo.path().join(".mediagitattributes")).unwrap_or_default();
+    if recommended == text["current"] {
+        assert!(!attributes.contains("*.txt"), "{}", attributes);
+    } else {
+        assert!(
+            attributes.contains(&format!("*.txt compression={}", recommended)),
+            "{}",
+            attributes
+        );
+    }
+
+    // The strategy now applied to text files is the recommended one
+    let reports = benchmark(repo.path(), &[]);
+    assert_eq!(group(&reports, "*.txt")["current"], recommended);
+}
+
+#[test]
+fn test_benchmark_outside_repository_fails() {
+    let dir = TempDir::new().unwrap();
+    mediagit()
+        .args(["benchmark", "compression"])
+        .current_dir(dir.path())
+        .assert()
+        .failure();
+}
@@ -192,6 +192,74 @@ fn test_branch_switch_with_media_files() {
     assert!(!temp_dir.path().join("feature_file.txt").exists());
 }
 
+/// Create `feature` with a different version of `shared.txt`, then return to main
+fn setup_diverged_shared_file(dir: &Path) {
+    add_and_commit(dir, "shared.txt", "main version", "Add shared file");
+
+    mediagit()
+        .args(["branch", "switch", "-c", "feature"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    add_and_commit(dir, "shared.txt", "feature version", "Change shared file");
+
+    mediagit()
+        .args(["branch", "switch", "main"])
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_branch_switch_refuses_to_overwrite_local_changes() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    setup_diverged_shared_file(temp_dir.path());
+
+    // Unsaved edit to a file that differs on the target branch
+    fs::write(temp_dir.path().join("shared.txt"), "unsaved edit").unwrap();
+
+    mediagit()
+        .args(["branch", "switch", "feature"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("would be overwritten"))
+        .stderr(predicate::str::contains("shared.txt"));
+
+    // Work is preserved and HEAD did not move
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("shared.txt")).unwrap(),
+        "unsaved edit"
+    );
+    mediagit()
+        .args(["branch", "list"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("* main"));
+}
+
+#[test]
+fn test_branch_switch_force_discards_local_changes() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    setup_diverged_shared_file(temp_dir.path());
+
+    fs::write(temp_dir.path().join("shared.txt"), "unsaved edit").unwrap();
+
+    mediagit()
+        .args(["branch", "switch", "--force", "feature"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("shared.txt")).unwrap(),
+        "feature version"
+    );
+}
+
 // ============================================================================
 // Branch List Tests
 // ============================================================================
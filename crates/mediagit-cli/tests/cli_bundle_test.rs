@@ -0,0 +1,311 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! CLI Bundle Command Tests
+//!
+//! Moves repositories through `.mgbundle` files with `bundle create`,
+//! `clone` and `fetch`, without any server.
+
+use assert_cmd::Command;
+use mediagit_cli::repo::create_storage_backend;
+use mediagit_versioning::{Commit, ObjectDatabase, Oid, RefDatabase, Tree};
+use predicates::prelude::*;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[allow(deprecated)]
+fn mediagit() -> Command {
+    Command::cargo_bin("mediagit").unwrap()
+}
+
+fn init_repo(dir: &Path) {
+    mediagit()
+        .arg("init")
+        .arg("-q")
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+fn add_and_commit(dir: &Path, name: &str, content: &str, message: &str) {
+    fs::write(dir.join(name), content).unwrap();
+    mediagit()
+        .arg("add")
+        .arg(name)
+        .current_dir(dir)
+        .assert()
+        .success();
+    mediagit()
+        .arg("commit")
+        .arg("-m")
+        .arg(message)
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+/// The commit `ref_name` points to in `repo`, with every commit, tree and
+/// blob reachable from it
+fn history(repo: &Path, ref_name: &str) -> (Oid, BTreeSet<Oid>) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let storage = create_storage_backend(repo).await.unwrap();
+        let odb = ObjectDatabase::with_smart_compression(storage, 100);
+        let tip = RefDatabase::new(repo.join(".mediagit"))
+            .resolve(ref_name)
+            .await
+            .unwrap();
+
+        let mut objects = BTreeSet::new();
+        let mut commits = vec![tip];
+        let mut trees = Vec::new();
+        while let Some(oid) = commits.pop() {
+            if !objects.insert(oid) {
+                continue;
+            }
+            let commit = Commit::deserialize(&odb.read(&oid).await.unwrap()).unwrap();
+            trees.push(commit.tree);
+            commits.extend(commit.parents);
+        }
+        while let Some(oid) = trees.pop() {
+            objects.insert(oid);
+            let tree = Tree::deserialize(&odb.read(&oid).await.unwrap()).unwrap();
+            for entry in tree.iter() {
+                if entry.is_tree() {
+                    trees.push(entry.oid);
+                } else {
+                    // Blobs must be present and intact, not just referenced
+                    assert!(odb.verify(&entry.oid).await.unwrap());
+                    objects.insert(entry.oid);
+                }
+            }
+        }
+        (tip, objects)
+    })
+}
+
+/// Source repository with three commits on `main` and one on `feature`
+fn setup_source(dir: &Path) {
+    init_repo(dir);
+    add_and_commit(dir, "scene.json", r#"{"shots": 1}"#, "First shot");
+    add_and_commit(dir, "scene.json", r#"{"shots": 2}"#, "Second shot");
+    fs::create_dir_all(dir.join("renders")).unwrap();
+    add_and_commit(dir, "renders/shot_010.exr", "exr data", "Add render");
+
+    mediagit()
+        .args(["branch", "create", "feature"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    mediagit()
+        .args(["branch", "switch", "feature"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    add_and_commit(dir, "notes.txt", "feature notes", "Feature commit");
+    mediagit()
+        .args(["branch", "switch", "main"])
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_clone_from_bundle_matches_source() {
+    let source = TempDir::new().unwrap();
+    let transfer = TempDir::new().unwrap();
+    setup_source(source.path());
+
+    let bundle = transfer.path().join("project.mgbundle");
+    mediagit()
+        .args(["bundle", "create"])
+        .arg(&bundle)
+        .arg("--all")
+        .current_dir(source.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Bundled 2 ref(s)"));
+
+    mediagit()
+        .args(["bundle", "verify"])
+        .arg(&bundle)
+        .current_dir(transfer.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("is valid"));
+
+    // The clone is named after the bundle
+    mediagit()
+        .args(["clone", "project.mgbundle"])
+        .current_dir(transfer.path())
+        .assert()
+        .success();
+    let clone = transfer.path().join("project");
+
+    assert_eq!(
+        history(&clone, "refs/heads/main"),
+        history(source.path(), "refs/heads/main")
+    );
+    assert_eq!(
+        history(&clone, "refs/remotes/origin/feature"),
+        history(source.path(), "refs/heads/feature")
+    );
+    assert_eq!(
+        fs::read_to_string(clone.join("renders/shot_010.exr")).unwrap(),
+        "exr data"
+    );
+}
+
+#[test]
+fn test_incremental_bundle_fetch() {
+    let source = TempDir::new().unwrap();
+    let transfer = TempDir::new().unwrap();
+    setup_source(source.path());
+
+    let full = transfer.path().join("full.mgbundle");
+    mediagit()
+        .args(["bundle", "create"])
+        .arg(&full)
+        .arg("main")
+        .current_dir(source.path())
+        .assert()
+        .success();
+    mediagit()
+        .args(["clone", "full.mgbundle", "farm"])
+        .current_dir(transfer.path())
+        .assert()
+        .success();
+    let clone = transfer.path().join("farm");
+
+    // Only the new commit travels in the update
+    add_and_commit(source.path(), "scene.json", r#"{"shots": 3}"#, "Third shot");
+    let update = transfer.path().join("update.mgbundle");
+    mediagit()
+        .args(["bundle", "create"])
+        .arg(&update)
+        .args(["main", "--basis", "HEAD~1"])
+        .current_dir(source.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("3 object(s)"));
+
+    // A repository without the basis cannot use it
+    let unrelated = TempDir::new().unwrap();
+    init_repo(unrelated.path());
+    mediagit()
+        .args(["bundle", "verify"])
+        .arg(&update)
+        .current_dir(unrelated.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("requires basis"));
+
+    mediagit()
+        .arg("fetch")
+        .arg(&update)
+        .current_dir(&clone)
+        .assert()
+        .success();
+    assert_eq!(
+        history(&clone, "refs/remotes/bundle/main"),
+        history(source.path(), "refs/heads/main")
+    );
+}
+
+#[test]
+fn test_since_bundle_applies_to_repo_at_basis() {
+    let source = TempDir::new().unwrap();
+    let transfer = TempDir::new().unwrap();
+    setup_source(source.path());
+    mediagit()
+        .args(["tag", "create", "synced"])
+        .current_dir(source.path())
+        .assert()
+        .success();
+
+    // The farm starts from a full bundle taken at the tag
+    let full = transfer.path().join("full.mgbundle");
+    mediagit()
+        .args(["bundle", "create"])
+        .arg(&full)
+        .arg("main")
+        .current_dir(source.path())
+        .assert()
+        .success();
+    mediagit()
+        .args(["clone", "full.mgbundle", "farm"])
+        .current_dir(transfer.path())
+        .assert()
+        .success();
+    let farm = transfer.path().join("farm");
+
+    add_and_commit(source.path(), "scene.json", r#"{"shots": 3}"#, "Third shot");
+    add_and_commit(
+        source.path(),
+        "scene.json",
+        r#"{"shots": 4}"#,
+        "Fourth shot",
+    );
+    let daily = transfer.path().join("daily.mgbundle");
+    mediagit()
+        .args(["bundle", "create", "--since", "synced"])
+        .arg(&daily)
+        .arg("main")
+        .current_dir(source.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Requires refs/tags/synced"));
+
+    // Two commits, two trees and two blobs, against a full bundle of four commits
+    assert!(fs::metadata(&daily).unwrap().len() < fs::metadata(&full).unwrap().len());
+
+    // Applying it without the basis fails before anything is written
+    let unrelated = TempDir::new().unwrap();
+    init_repo(unrelated.path());
+    mediagit()
+        .arg("fetch")
+        .arg(&daily)
+        .current_dir(unrelated.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Bundle requires basis the repository does not have: refs/tags/synced",
+        ));
+    assert!(!unrelated
+        .path()
+        .join(".mediagit/refs/remotes/bundle/main")
+        .exists());
+
+    mediagit()
+        .arg("fetch")
+        .arg(&daily)
+        .current_dir(&farm)
+        .assert()
+        .success();
+    let (tip, objects) = history(&farm, "refs/remotes/bundle/main");
+    assert_eq!(
+        (tip, objects.clone()),
+        history(source.path(), "refs/heads/main")
+    );
+    assert!(objects.len() > history(&farm, "refs/heads/main").1.len());
+
+    mediagit()
+        .args(["log", "refs/remotes/bundle/main"])
+        .current_dir(&farm)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Fourth shot"))
+        .stdout(predicate::str::contains("Third shot"));
+}
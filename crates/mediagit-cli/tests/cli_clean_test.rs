@@ -0,0 +1,188 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! CLI Clean Command Tests
+//!
+//! Tests for removing untracked files, directories and ignored files.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[allow(deprecated)]
+fn mediagit() -> Command {
+    Command::cargo_bin("mediagit").unwrap()
+}
+
+/// Repository with a committed asset, untracked renders and ignored build output
+fn setup_repo() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    mediagit()
+        .args(["init", "-q"])
+        .current_dir(dir)
+        .assert()
+        .success();
+
+    fs::create_dir_all(dir.join("scenes")).unwrap();
+    fs::write(dir.join("scenes/shot01.blend"), "scene").unwrap();
+    fs::write(dir.join(".mediagitignore"), "*.tmp\nbuild/\n").unwrap();
+    mediagit()
+        .args(["add", "scenes/shot01.blend", ".mediagitignore"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    mediagit()
+        .args(["commit", "-m", "Initial"])
+        .current_dir(dir)
+        .assert()
+        .success();
+
+    fs::write(dir.join("scenes/shot01_render.exr"), "render").unwrap();
+    fs::create_dir_all(dir.join("renders/final")).unwrap();
+    fs::write(dir.join("renders/final/frame_0001.png"), "frame").unwrap();
+    fs::write(dir.join("scenes/autosave.tmp"), "tmp").unwrap();
+    fs::create_dir_all(dir.join("build")).unwrap();
+    fs::write(dir.join("build/cache.bin"), "cache").unwrap();
+
+    temp_dir
+}
+
+fn exists(dir: &Path, path: &str) -> bool {
+    dir.join(path).exists()
+}
+
+#[test]
+fn test_clean_without_force_only_lists() {
+    let temp_dir = setup_repo();
+    let dir = temp_dir.path();
+
+    mediagit()
+        .arg("clean")
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Would remove scenes/shot01_render.exr",
+        ))
+        .stdout(predicate::str::contains("autosave.tmp").not())
+        .stdout(predicate::str::contains("renders").not());
+
+    assert!(exists(dir, "scenes/shot01_render.exr"));
+}
+
+#[test]
+fn test_clean_dry_run_removes_nothing() {
+    let temp_dir = setup_repo();
+    let dir = temp_dir.path();
+
+    let output = mediagit()
+        .args(["clean", "-n", "-f", "-d", "-x"])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Would remove build/\n\
+         Would remove renders/\n\
+         Would remove scenes/autosave.tmp\n\
+         Would remove scenes/shot01_render.exr\n"
+    );
+
+    for path in [
+        "scenes/shot01_render.exr",
+        "renders/final/frame_0001.png",
+        "scenes/autosave.tmp",
+        "build/cache.bin",
+    ] {
+        assert!(exists(dir, path), "{} should still exist", path);
+    }
+}
+
+#[test]
+fn test_clean_force_removes_untracked_files() {
+    let temp_dir = setup_repo();
+    let dir = temp_dir.path();
+
+    mediagit()
+        .args(["clean", "-f"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Removing scenes/shot01_render.exr",
+        ));
+
+    assert!(!exists(dir, "scenes/shot01_render.exr"));
+    // Tracked files, untracked directories and ignored files are kept
+    assert!(exists(dir, "scenes/shot01.blend"));
+    assert!(exists(dir, "renders/final/frame_0001.png"));
+    assert!(exists(dir, "scenes/autosave.tmp"));
+    assert!(exists(dir, "build/cache.bin"));
+
+    mediagit()
+        .args(["clean", "-fd"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    assert!(!exists(dir, "renders"));
+    assert!(exists(dir, "scenes/autosave.tmp"));
+    assert!(exists(dir, "build/cache.bin"));
+}
+
+#[test]
+fn test_clean_removes_ignored_only_with_x() {
+    let temp_dir = setup_repo();
+    let dir = temp_dir.path();
+
+    mediagit()
+        .args(["clean", "-fx"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    assert!(!exists(dir, "scenes/autosave.tmp"));
+    // Ignored directories still need -d
+    assert!(exists(dir, "build/cache.bin"));
+
+    mediagit()
+        .args(["clean", "-fdx"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    assert!(!exists(dir, "build"));
+    assert!(!exists(dir, "renders"));
+    assert!(exists(dir, "scenes/shot01.blend"));
+    assert!(exists(dir, ".mediagitignore"));
+}
+
+#[test]
+fn test_clean_keeps_staged_files() {
+    let temp_dir = setup_repo();
+    let dir = temp_dir.path();
+
+    mediagit()
+        .args(["add", "scenes/shot01_render.exr"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    mediagit()
+        .args(["clean", "-f"])
+        .current_dir(dir)
+        .assert()
+        .success();
+
+    assert!(exists(dir, "scenes/shot01_render.exr"));
+}
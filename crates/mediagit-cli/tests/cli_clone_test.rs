@@ -0,0 +1,248 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! CLI Clone Command Tests
+//!
+//! Runs `mediagit clone` and `mediagit fetch` against an in-process server.
+
+mod common;
+
+use assert_cmd::Command;
+use common::start_server;
+use mediagit_storage::LocalBackend;
+use mediagit_versioning::{ObjectDatabase, Oid};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+#[allow(deprecated)]
+fn mediagit() -> Command {
+    Command::cargo_bin("mediagit").unwrap()
+}
+
+fn init_repo(dir: &Path) {
+    mediagit()
+        .arg("init")
+        .arg("-q")
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+fn add_and_commit(dir: &Path, name: &str, content: &str, message: &str) {
+    fs::write(dir.join(name), content).unwrap();
+    mediagit()
+        .arg("add")
+        .arg(name)
+        .current_dir(dir)
+        .assert()
+        .success();
+    mediagit()
+        .arg("commit")
+        .arg("-m")
+        .arg(message)
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+/// Check whether the clone at `repo` has the blob for `content`
+fn has_blob(repo: &Path, content: &str) -> bool {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let storage = LocalBackend::new(repo.join(".mediagit")).await.unwrap();
+        let odb = ObjectDatabase::with_smart_compression(Arc::new(storage), 100);
+        odb.exists(&Oid::hash(content.as_bytes())).await.unwrap()
+    })
+}
+
+/// Server repository with `main` and a diverged `feature` branch
+fn setup_remote_with_two_branches(repos_dir: &Path) {
+    let source = repos_dir.join("project");
+    fs::create_dir_all(&source).unwrap();
+    init_repo(&source);
+    add_and_commit(&source, "main.txt", "main branch content", "Main commit");
+
+    mediagit()
+        .args(["branch", "create", "feature"])
+        .current_dir(&source)
+        .assert()
+        .success();
+    mediagit()
+        .args(["branch", "switch", "feature"])
+        .current_dir(&source)
+        .assert()
+        .success();
+    add_and_commit(
+        &source,
+        "feature.txt",
+        "feature branch content",
+        "Feature commit",
+    );
+    mediagit()
+        .args(["branch", "switch", "main"])
+        .current_dir(&source)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_clone_single_branch_tracks_only_target_branch() {
+    let repos_dir = TempDir::new().unwrap();
+    let clone_parent = TempDir::new().unwrap();
+    setup_remote_with_two_branches(repos_dir.path());
+    let url = format!("{}/project", start_server(repos_dir.path()));
+
+    mediagit()
+        .args(["clone", "--single-branch", "--branch", "main", &url, "ci"])
+        .current_dir(clone_parent.path())
+        .assert()
+        .success();
+
+    let clone = clone_parent.path().join("ci");
+    let remotes = clone.join(".mediagit/refs/remotes/origin");
+    assert!(remotes.join("main").exists());
+    assert!(!remotes.join("feature").exists());
+
+    assert!(has_blob(&clone, "main branch content"));
+    assert!(!has_blob(&clone, "feature branch content"));
+
+    let config = fs::read_to_string(clone.join(".mediagit/config.toml")).unwrap();
+    assert!(config.contains("+refs/heads/main:refs/remotes/origin/main"));
+
+    // A plain fetch honours the recorded refspec
+    mediagit()
+        .arg("fetch")
+        .current_dir(&clone)
+        .assert()
+        .success();
+    assert!(!remotes.join("feature").exists());
+    assert!(!has_blob(&clone, "feature branch content"));
+
+    // Fetching the other branch by name widens the refspecs
+    mediagit()
+        .args(["fetch", "origin", "feature"])
+        .current_dir(&clone)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Now tracking origin/feature"));
+    assert!(remotes.join("feature").exists());
+    assert!(has_blob(&clone, "feature branch content"));
+
+    let config = fs::read_to_string(clone.join(".mediagit/config.toml")).unwrap();
+    assert!(config.contains("+refs/heads/feature:refs/remotes/origin/feature"));
+}
+
+#[test]
+fn test_clone_tracks_all_branches_by_default() {
+    let repos_dir = TempDir::new().unwrap();
+    let clone_parent = TempDir::new().unwrap();
+    setup_remote_with_two_branches(repos_dir.path());
+    let url = format!("{}/project", start_server(repos_dir.path()));
+
+    mediagit()
+        .args(["clone", &url, "full"])
+        .current_dir(clone_parent.path())
+        .assert()
+        .success();
+
+    let clone = clone_parent.path().join("full");
+    let remotes = clone.join(".mediagit/refs/remotes/origin");
+    assert!(remotes.join("main").exists());
+    assert!(remotes.join("feature").exists());
+
+    let config = fs::read_to_string(clone.join(".mediagit/config.toml")).unwrap();
+    assert!(!config.contains("fetch_refspecs"));
+}
+
+#[test]
+fn test_clone_local_links_objects() {
+    let repos_dir = TempDir::new().unwrap();
+    let clone_parent = TempDir::new().unwrap();
+    setup_remote_with_two_branches(repos_dir.path());
+    let source = repos_dir.path().join("project");
+
+    // Objects written from now on are shared with a machine-wide store
+    let shared = repos_dir.path().join("shared-objects");
+    let config_path = source.join(".mediagit/config.toml");
+    let config = fs::read_to_string(&config_path).unwrap();
+    let config = config.replacen(
+        "[storage]\n",
+        &format!(
+            "[storage]\nalternates = {:?}\n",
+            shared.display().to_string()
+        ),
+        1,
+    );
+    fs::write(&config_path, config).unwrap();
+    add_and_commit(&source, "shared.txt", "shared content", "Shared commit");
+    assert!(shared.join("objects").exists());
+
+    mediagit()
+        .args(["clone", "--local", source.to_str().unwrap(), "copy"])
+        .current_dir(clone_parent.path())
+        .assert()
+        .success();
+
+    let clone = clone_parent.path().join("copy");
+    assert_eq!(
+        fs::read_to_string(clone.join("shared.txt")).unwrap(),
+        "shared content"
+    );
+    let remotes = clone.join(".mediagit/refs/remotes/origin");
+    assert!(remotes.join("main").exists());
+    assert!(remotes.join("feature").exists());
+    assert!(has_blob(&clone, "feature branch content"));
+
+    let config = fs::read_to_string(clone.join(".mediagit/config.toml")).unwrap();
+    assert!(config.contains("alternates = "));
+
+    // Every object is a link to the source's file, not a copy
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let mut pending = vec![clone.join(".mediagit/objects")];
+        let mut objects = 0;
+        while let Some(path) = pending.pop() {
+            if path.is_dir() {
+                pending.extend(fs::read_dir(&path).unwrap().map(|e| e.unwrap().path()));
+            } else {
+                assert!(fs::metadata(&path).unwrap().nlink() >= 2, "{:?}", path);
+                objects += 1;
+            }
+        }
+        assert!(objects > 0);
+    }
+
+    mediagit()
+        .arg("fsck")
+        .current_dir(&clone)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_clone_local_rejects_non_repository() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("plain")).unwrap();
+
+    mediagit()
+        .args(["clone", "--local", "plain", "copy"])
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a MediaGit repository"));
+    assert!(!dir.path().join("copy").exists());
+}
@@ -16,6 +16,8 @@
 //! Tests for `mediagit commit` command with all options and edge cases.
 
 use assert_cmd::Command;
+use mediagit_cli::repo::create_storage_backend;
+use mediagit_versioning::{ObjectDatabase, Oid, RefDatabase};
 use predicates::prelude::*;
 use std::fs;
 use std::path::Path;
@@ -401,3 +403,143 @@ fn test_commit_signoff() {
         .assert()
         .success();
 }
+
+// ============================================================================
+// Fixed Date Tests
+// ============================================================================
+
+/// Commit `content` in a fresh repository and return the new commit oid
+fn commit_with_env(content: &str, args: &[&str], env: &[(&str, &str)]) -> String {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    add_file(temp_dir.path(), "asset.bin", content);
+
+    let mut cmd = mediagit();
+    cmd.args(["commit", "-m", "Generated assets"])
+        .args(args)
+        .env("MEDIAGIT_AUTHOR_NAME", "Build Bot")
+        .env("MEDIAGIT_AUTHOR_EMAIL", "build@example.com")
+        .env_remove("MEDIAGIT_AUTHOR_DATE")
+        .env_remove("MEDIAGIT_COMMIT_DATE");
+    for (key, value) in env {
+        cmd.env(key, value);
+    }
+    cmd.current_dir(temp_dir.path()).assert().success();
+
+    fs::read_to_string(temp_dir.path().join(".mediagit/refs/heads/main"))
+        .unwrap()
+        .trim()
+        .to_string()
+}
+
+#[test]
+fn test_commit_fixed_date_is_reproducible() {
+    let first = commit_with_env("frame data", &["--date", "2025-01-01T00:00:00Z"], &[]);
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let second = commit_with_env("frame data", &["--date", "2025-01-01T00:00:00Z"], &[]);
+    assert_eq!(first, second);
+
+    // The same instant as Unix seconds, or through the environment
+    let epoch = commit_with_env("frame data", &["--date", "1735689600"], &[]);
+    assert_eq!(first, epoch);
+    let from_env = commit_with_env(
+        "frame data",
+        &[],
+        &[
+            ("MEDIAGIT_AUTHOR_DATE", "1735689600"),
+            ("MEDIAGIT_COMMIT_DATE", "2025-01-01T01:00:00+01:00"),
+        ],
+    );
+    assert_eq!(first, from_env);
+
+    let other_date = commit_with_env("frame data", &["--date", "1735689601"], &[]);
+    assert_ne!(first, other_date);
+}
+
+#[test]
+fn test_commit_invalid_date_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    add_file(temp_dir.path(), "test.txt", "Content");
+
+    mediagit()
+        .args(["commit", "-m", "Bad date", "--date", "yesterday"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid date 'yesterday'"));
+}
+
+// ============================================================================
+// Object Format Tests
+// ============================================================================
+
+/// Raw stored bytes of `oid` in `repo`
+fn read_object(repo: &Path, oid: &Oid) -> Vec<u8> {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let storage = create_storage_backend(repo).await.unwrap();
+        ObjectDatabase::with_smart_compression(storage, 100)
+            .read(oid)
+            .await
+            .unwrap()
+    })
+}
+
+#[test]
+fn test_commit_git_object_format() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+    let config = fs::read_to_string(dir.join(".mediagit/config.toml")).unwrap();
+    let config = config.replace(
+        "[objects]\nformat = \"native\"",
+        "[objects]\nformat = \"git\"",
+    );
+    assert!(config.contains("format = \"git\""));
+    fs::write(dir.join(".mediagit/config.toml"), config).unwrap();
+
+    for (n, message) in ["First render", "Second render"].iter().enumerate() {
+        add_file(dir, "asset.bin", &format!("frame {}", n));
+        mediagit()
+            .args(["commit", "-m", message, "--date", "1735689600"])
+            .env("MEDIAGIT_AUTHOR_NAME", "Build Bot")
+            .env("MEDIAGIT_AUTHOR_EMAIL", "build@example.com")
+            .current_dir(dir)
+            .assert()
+            .success();
+    }
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let head = runtime
+        .block_on(RefDatabase::new(dir.join(".mediagit")).resolve("HEAD"))
+        .unwrap();
+    let commit = String::from_utf8(read_object(dir, &head)).unwrap();
+    let (headers, message) = commit.split_once("\n\n").unwrap();
+    let headers: Vec<&str> = headers.lines().collect();
+
+    assert_eq!(headers.len(), 4, "{}", commit);
+    let tree = Oid::from_hex(headers[0].strip_prefix("tree ").unwrap()).unwrap();
+    let parent = Oid::from_hex(headers[1].strip_prefix("parent ").unwrap()).unwrap();
+    assert_eq!(
+        headers[2],
+        "author Build Bot <build@example.com> 1735689600 +0000"
+    );
+    assert!(headers[3].starts_with("committer Build Bot <build@example.com> 1735689600 "));
+    assert!(message.starts_with("Second render"));
+
+    let parent_commit = String::from_utf8(read_object(dir, &parent)).unwrap();
+    assert!(parent_commit.contains("\n\nFirst render"));
+
+    let tree = read_object(dir, &tree);
+    assert!(tree.starts_with(b"100644 asset.bin\0"));
+    assert_eq!(tree.len(), "100644 asset.bin\0".len() + 32);
+
+    mediagit()
+        .arg("log")
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("First render"))
+        .stdout(predicate::str::contains("Second render"));
+}
@@ -0,0 +1,104 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! `mediagit compress-info` tests
+
+use assert_cmd::Command;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[allow(deprecated)]
+fn mediagit() -> Command {
+    Command::cargo_bin("mediagit").unwrap()
+}
+
+fn compress_info(dir: &Path, args: &[&str]) -> Value {
+    let output = mediagit()
+        .arg("compress-info")
+        .args(args)
+        .args(["--format", "json"])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn test_compress_info_jpeg_is_stored() {
+    let dir = TempDir::new().unwrap();
+    let mut jpeg = vec![
+        0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F', 0x00,
+    ];
+    jpeg.extend((0..4096u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8));
+    fs::write(dir.path().join("photo.jpg"), &jpeg).unwrap();
+
+    let info = compress_info(dir.path(), &["photo.jpg"]);
+    assert_eq!(info["object_type"], "Jpeg");
+    assert_eq!(info["strategy"], "store");
+    assert_eq!(info["size"], jpeg.len() as u64);
+}
+
+#[test]
+fn test_compress_info_small_text_uses_brotli() {
+    let dir = TempDir::new().unwrap();
+    let text = "fn main() { println!(\"hello\"); }\n".repeat(200);
+    fs::write(dir.path().join("main.rs"), &text).unwrap();
+
+    let info = compress_info(dir.path(), &["main.rs", "--sample"]);
+    assert_eq!(info["object_type"], "Text");
+    assert_eq!(info["strategy"], "brotli");
+    let sample = &info["sample"];
+    assert_eq!(sample["bytes"], text.len() as u64);
+    assert!(sample["ratio"].as_f64().unwrap() < 0.1, "{}", sample);
+}
+
+#[test]
+fn test_compress_info_large_text_falls_back_to_zstd() {
+    let dir = TempDir::new().unwrap();
+    // Sparse, so the test does not write 500 MB
+    let file = fs::File::create(dir.path().join("huge.txt")).unwrap();
+    file.set_len(500 * 1024 * 1024).unwrap();
+
+    let info = compress_info(dir.path(), &["huge.txt"]);
+    assert_eq!(info["object_type"], "Text");
+    assert_eq!(info["default_strategy"], "zstd");
+    assert_eq!(info["strategy"], "zstd");
+}
+
+#[test]
+fn test_compress_info_reports_attribute_override() {
+    let dir = TempDir::new().unwrap();
+    mediagit()
+        .args(["init", "-q"])
+        .current_dir(dir.path())
+        .assert()
+        .success();
+    fs::write(
+        dir.path().join(".mediagitattributes"),
+        "*.rs compression=zstd-best\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+    let info = compress_info(dir.path(), &["main.rs"]);
+    assert_eq!(info["default_strategy"], "brotli");
+    assert_eq!(info["attribute_override"], "zstd-best");
+    assert_eq!(info["strategy"], "zstd-best");
+}
@@ -0,0 +1,229 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! CLI Export Command Tests
+//!
+//! Exports commits to plain directories and compares them with the
+//! committed trees.
+
+use assert_cmd::Command;
+use mediagit_cli::repo::create_storage_backend;
+use mediagit_versioning::{Commit, FileMode, ObjectDatabase, Oid, RefDatabase, Tree};
+use predicates::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[allow(deprecated)]
+fn mediagit() -> Command {
+    Command::cargo_bin("mediagit").unwrap()
+}
+
+/// Every file in `repo`'s HEAD tree as path -> (blob, mode)
+fn head_tree(repo: &Path) -> BTreeMap<String, (Oid, FileMode)> {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let storage = create_storage_backend(repo).await.unwrap();
+        let odb = ObjectDatabase::with_smart_compression(storage, 100);
+        let head = RefDatabase::new(repo.join(".mediagit"))
+            .resolve("HEAD")
+            .await
+            .unwrap();
+        let commit = Commit::deserialize(&odb.read(&head).await.unwrap()).unwrap();
+
+        let mut files = BTreeMap::new();
+        let mut trees = vec![(String::new(), commit.tree)];
+        while let Some((prefix, oid)) = trees.pop() {
+            let tree = Tree::deserialize(&odb.read(&oid).await.unwrap()).unwrap();
+            for entry in tree.iter() {
+                let path = format!("{}{}", prefix, entry.name);
+                if entry.is_tree() {
+                    trees.push((format!("{}/", path), entry.oid));
+                } else {
+                    files.insert(path, (entry.oid, entry.mode));
+                }
+            }
+        }
+        files
+    })
+}
+
+/// Every file under `dir` as path -> (content hash, mode)
+fn exported_files(dir: &Path) -> BTreeMap<String, (Oid, FileMode)> {
+    fn walk(root: &Path, dir: &Path, files: &mut BTreeMap<String, (Oid, FileMode)>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                walk(root, &path, files);
+                continue;
+            }
+            #[cfg(unix)]
+            let mode = {
+                use std::os::unix::fs::PermissionsExt;
+                if fs::metadata(&path).unwrap().permissions().mode() & 0o111 != 0 {
+                    FileMode::Executable
+                } else {
+                    FileMode::Regular
+                }
+            };
+            #[cfg(not(unix))]
+            let mode = FileMode::Regular;
+            let rel = path.strip_prefix(root).unwrap().to_string_lossy();
+            files.insert(
+                rel.replace('\\', "/"),
+                (Oid::hash(&fs::read(&path).unwrap()), mode),
+            );
+        }
+    }
+    let mut files = BTreeMap::new();
+    walk(dir, dir, &mut files);
+    files
+}
+
+/// Repository with files at several depths, one of them executable
+fn setup_repo(dir: &Path) {
+    mediagit()
+        .args(["init", "-q"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    fs::create_dir_all(dir.join("renders/shot_010")).unwrap();
+    fs::create_dir_all(dir.join("scripts")).unwrap();
+    fs::write(dir.join("scene.json"), r#"{"frames": 240}"#).unwrap();
+    fs::write(dir.join("renders/shot_010/beauty.exr"), "exr beauty pass").unwrap();
+    fs::write(dir.join("renders/shot_010/depth.exr"), "exr depth pass").unwrap();
+    fs::write(
+        dir.join("scripts/render.sh"),
+        "#!/bin/sh\nrender scene.json\n",
+    )
+    .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(
+            dir.join("scripts/render.sh"),
+            fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+    }
+
+    mediagit()
+        .args(["add", "--all"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    mediagit()
+        .args(["commit", "-m", "Shot 010 renders"])
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_export_matches_commit_tree() {
+    let repo = TempDir::new().unwrap();
+    let farm = TempDir::new().unwrap();
+    setup_repo(repo.path());
+
+    mediagit()
+        .args(["tag", "create", "delivery"])
+        .current_dir(repo.path())
+        .assert()
+        .success();
+
+    let target = farm.path().join("shot_010");
+    mediagit()
+        .arg("export")
+        .arg("delivery")
+        .arg(&target)
+        .current_dir(repo.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 4 file(s)"));
+
+    let tree = head_tree(repo.path());
+    assert_eq!(tree.len(), 4);
+    #[cfg(unix)]
+    assert_eq!(tree["scripts/render.sh"].1, FileMode::Executable);
+    assert_eq!(exported_files(&target), tree);
+    assert!(!target.join(".mediagit").exists());
+}
+
+#[test]
+fn test_export_selected_paths_and_resume() {
+    let repo = TempDir::new().unwrap();
+    let farm = TempDir::new().unwrap();
+    setup_repo(repo.path());
+    let tree = head_tree(repo.path());
+
+    // Only the render directory
+    let target = farm.path().join("renders_only");
+    mediagit()
+        .arg("export")
+        .arg("main")
+        .arg(&target)
+        .arg("renders/")
+        .current_dir(repo.path())
+        .assert()
+        .success();
+    let exported = exported_files(&target);
+    assert_eq!(
+        exported.keys().collect::<Vec<_>>(),
+        ["renders/shot_010/beauty.exr", "renders/shot_010/depth.exr"]
+    );
+
+    // An interrupted export: one file missing, one cut short
+    let target = farm.path().join("full");
+    mediagit()
+        .arg("export")
+        .arg("HEAD")
+        .arg(&target)
+        .current_dir(repo.path())
+        .assert()
+        .success();
+    fs::remove_file(target.join("scene.json")).unwrap();
+    fs::write(target.join("renders/shot_010/beauty.exr"), "exr be").unwrap();
+
+    mediagit()
+        .arg("export")
+        .arg("HEAD")
+        .arg(&target)
+        .current_dir(repo.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 2 file(s)"))
+        .stdout(predicate::str::contains(
+            "2 file(s) were already up to date",
+        ));
+    assert_eq!(exported_files(&target), tree);
+
+    // Paths outside the tree are an error, as is exporting into a repository
+    mediagit()
+        .arg("export")
+        .arg("HEAD")
+        .arg(farm.path().join("empty"))
+        .arg("audio/")
+        .current_dir(repo.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No files in HEAD match audio/"));
+    mediagit()
+        .arg("export")
+        .arg("HEAD")
+        .arg(repo.path())
+        .current_dir(repo.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is a repository"));
+}
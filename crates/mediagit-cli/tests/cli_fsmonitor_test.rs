@@ -0,0 +1,183 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! CLI tests for `mediagit fsmonitor` and incremental `status`.
+//!
+//! Only built with the `fsmonitor` feature.
+
+#![cfg(feature = "fsmonitor")]
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+#[allow(deprecated)]
+fn mediagit() -> Command {
+    Command::cargo_bin("mediagit").unwrap()
+}
+
+fn setup_repo(dir: &Path) {
+    mediagit()
+        .args(["init", "-q"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        fs::write(dir.join(name), format!("{} content", name)).unwrap();
+    }
+    mediagit()
+        .args(["add", "a.txt", "b.txt", "c.txt"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    mediagit()
+        .args(["commit", "-m", "Initial commit"])
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+/// Wait until the monitor has logged a change to `name`
+fn wait_for_event(dir: &Path, name: &str) {
+    let log = dir.join(".mediagit/fsmonitor/events.log");
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let contents = fs::read_to_string(&log).unwrap_or_default();
+        if contents.lines().any(|line| line == name) {
+            return;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "no event for {} in:\n{}",
+            name,
+            contents
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+struct StopOnDrop<'a>(&'a Path);
+
+impl Drop for StopOnDrop<'_> {
+    fn drop(&mut self) {
+        let _ = mediagit()
+            .args(["fsmonitor", "stop"])
+            .current_dir(self.0)
+            .ok();
+    }
+}
+
+#[test]
+fn test_status_examines_only_changed_files() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_repo(temp_dir.path());
+
+    mediagit()
+        .args(["fsmonitor", "start"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fsmonitor started"));
+    let _stop = StopOnDrop(temp_dir.path());
+
+    // The first status has no earlier state to build on
+    mediagit()
+        .args(["status", "-v"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Examined 3 of 3 tracked files"));
+
+    fs::write(temp_dir.path().join("b.txt"), "changed").unwrap();
+    wait_for_event(temp_dir.path(), "b.txt");
+
+    mediagit()
+        .args(["status", "-v"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Examined 1 of 3 tracked files (fsmonitor)",
+        ))
+        .stdout(predicate::str::contains("modified:   b.txt"));
+
+    // Nothing changed since: nothing is examined, and b.txt is still modified
+    mediagit()
+        .args(["status", "-v"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Examined 0 of 3 tracked files (fsmonitor)",
+        ))
+        .stdout(predicate::str::contains("modified:   b.txt"));
+
+    // New and deleted files are picked up without a full scan
+    fs::write(temp_dir.path().join("d.txt"), "new").unwrap();
+    fs::remove_file(temp_dir.path().join("c.txt")).unwrap();
+    wait_for_event(temp_dir.path(), "c.txt");
+    wait_for_event(temp_dir.path(), "d.txt");
+
+    mediagit()
+        .args(["status", "--porcelain"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(" M b.txt"))
+        .stdout(predicate::str::contains(" D c.txt"))
+        .stdout(predicate::str::contains("?? d.txt"));
+}
+
+#[test]
+fn test_status_falls_back_to_full_scan_when_stopped() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_repo(temp_dir.path());
+
+    mediagit()
+        .args(["fsmonitor", "start"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    mediagit()
+        .args(["status", "-v"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+
+    mediagit()
+        .args(["fsmonitor", "stop"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fsmonitor stopped"));
+    mediagit()
+        .args(["fsmonitor", "status"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("not running"));
+
+    // Changes made while nothing watches are still found
+    fs::write(temp_dir.path().join("a.txt"), "changed").unwrap();
+    mediagit()
+        .args(["status", "-v"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Examined 3 of 3 tracked files"))
+        .stdout(predicate::str::contains("(fsmonitor)").not())
+        .stdout(predicate::str::contains("modified:   a.txt"));
+}
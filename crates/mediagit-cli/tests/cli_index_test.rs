@@ -0,0 +1,128 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! CLI Index Command Tests
+//!
+//! Refreshes cached file stats after the working tree is touched behind
+//! the index's back.
+
+use assert_cmd::Command;
+use mediagit_versioning::Index;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tempfile::TempDir;
+
+#[allow(deprecated)]
+fn mediagit() -> Command {
+    Command::cargo_bin("mediagit").unwrap()
+}
+
+/// Set a file's modification time without changing its content
+fn touch(path: &Path, mtime: SystemTime) {
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(mtime)
+        .unwrap();
+}
+
+#[test]
+fn test_refresh_updates_stats_of_touched_files() {
+    let temp = TempDir::new().unwrap();
+    let repo = temp.path();
+    mediagit()
+        .args(["init", "-q"])
+        .current_dir(repo)
+        .assert()
+        .success();
+    fs::write(repo.join("scene.blend"), "blend v1").unwrap();
+    mediagit()
+        .args(["add", "scene.blend"])
+        .current_dir(repo)
+        .assert()
+        .success();
+    mediagit()
+        .args(["commit", "-m", "Add scene"])
+        .current_dir(repo)
+        .assert()
+        .success();
+
+    fs::write(repo.join("scene.blend"), "blend v2").unwrap();
+    fs::write(repo.join("notes.txt"), "lighting notes").unwrap();
+    mediagit()
+        .args(["add", "scene.blend", "notes.txt"])
+        .current_dir(repo)
+        .assert()
+        .success();
+    let staged = Index::load(repo).unwrap();
+
+    // Another tool rewrites both files with identical content
+    let later = SystemTime::now() + Duration::from_secs(3600);
+    touch(&repo.join("scene.blend"), later);
+    touch(&repo.join("notes.txt"), later);
+
+    mediagit()
+        .args(["index", "refresh"])
+        .current_dir(repo)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Refreshed 2 index entries"))
+        .stdout(predicate::str::contains("changed since it was staged").not());
+
+    let later_secs = later.duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let refreshed = Index::load(repo).unwrap();
+    for entry in refreshed.entries() {
+        assert_eq!(entry.mtime, Some(later_secs));
+        assert_eq!(
+            entry.oid,
+            staged.get_entry(&entry.path).unwrap().oid,
+            "staged content of {} changed",
+            entry.path.display()
+        );
+    }
+
+    // Nothing left to refresh, and status reports only what is staged
+    mediagit()
+        .args(["index", "refresh"])
+        .current_dir(repo)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Refreshed 0 index entries (2 already current)",
+        ));
+    mediagit()
+        .args(["status", "--porcelain"])
+        .current_dir(repo)
+        .assert()
+        .success()
+        .stdout(predicate::eq("A  notes.txt\nM  scene.blend\n"));
+
+    // A real edit is reported and leaves the staged version alone
+    fs::write(repo.join("notes.txt"), "lighting notes, revised").unwrap();
+    mediagit()
+        .args(["index", "refresh"])
+        .current_dir(repo)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "notes.txt changed since it was staged",
+        ));
+    let after_edit = Index::load(repo).unwrap();
+    assert_eq!(
+        after_edit.get_entry(Path::new("notes.txt")).unwrap().oid,
+        staged.get_entry(Path::new("notes.txt")).unwrap().oid
+    );
+}
@@ -13,9 +13,11 @@
 
 //! Comprehensive CLI Maintenance Command Tests
 //!
-//! Tests for `gc`, `fsck`, `verify`, and `stats` commands.
+//! Tests for `gc`, `fsck`, `verify`, `stats` and `doctor` commands.
 
 use assert_cmd::Command;
+use mediagit_cli::repo::create_storage_backend;
+use mediagit_versioning::{Commit, ObjectDatabase, Oid, RefDatabase};
 use predicates::prelude::*;
 use std::fs;
 use std::path::Path;
@@ -180,6 +182,458 @@ fn test_gc_verbose() {
         .success();
 }
 
+#[test]
+fn test_gc_refuses_while_locked() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    add_and_commit(temp_dir.path(), "file.txt", "Content", "Initial commit");
+
+    // A lock held by a live process (this test)
+    let lock_path = temp_dir.path().join(".mediagit/gc.pid");
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    fs::write(&lock_path, format!("{} {}\n", std::process::id(), now)).unwrap();
+
+    mediagit()
+        .args(["gc", "--yes"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("gc is already running"));
+    assert!(lock_path.exists());
+
+    // A dry run deletes nothing and does not need the lock
+    mediagit()
+        .args(["gc", "--dry-run"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_gc_reclaims_stale_lock() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    add_and_commit(temp_dir.path(), "file.txt", "Content", "Initial commit");
+
+    // A lock left behind by a process that has exited
+    let mut child = std::process::Command::new("true").spawn().unwrap();
+    let pid = child.id();
+    child.wait().unwrap();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let lock_path = temp_dir.path().join(".mediagit/gc.pid");
+    fs::write(&lock_path, format!("{} {}\n", pid, now)).unwrap();
+
+    mediagit()
+        .args(["gc", "--yes"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert!(!lock_path.exists());
+}
+
+#[test]
+fn test_gc_keeps_recent_unreachable_objects() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    add_and_commit(temp_dir.path(), "file.txt", "Content", "Initial commit");
+
+    // Staged but uncommitted content is not reachable from any ref
+    fs::write(temp_dir.path().join("pending.txt"), "pending content").unwrap();
+    mediagit()
+        .args(["add", "pending.txt"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+
+    mediagit()
+        .args(["gc", "--yes"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Kept (grace period):"))
+        .stdout(predicate::str::contains("Deleted").not());
+
+    mediagit()
+        .args(["gc", "--yes", "--prune", "now"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted 1 objects"));
+}
+
+#[test]
+fn test_gc_train_dict_keeps_objects_readable() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+
+    let write_sidecars = |range: std::ops::Range<usize>| {
+        for i in range {
+            fs::write(
+                dir.join(format!("shot_{:03}.json", i)),
+                format!(
+                    r#"{{"asset": "shot_{:03}.exr", "colorspace": "ACEScg", "frame_range": [{}, {}], "status": "approved"}}"#,
+                    i,
+                    1001 + i,
+                    1100 + i
+                ),
+            )
+            .unwrap();
+        }
+        mediagit()
+            .args(["add", "."])
+            .current_dir(dir)
+            .assert()
+            .success();
+        mediagit()
+            .args(["commit", "-m", "Add sidecars"])
+            .current_dir(dir)
+            .assert()
+            .success();
+    };
+
+    write_sidecars(0..40);
+    mediagit()
+        .args(["gc", "--yes", "--train-dict"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Trained compression dictionary"));
+
+    // Sidecars written with the first dictionary survive retraining
+    write_sidecars(40..80);
+    mediagit()
+        .args(["gc", "--yes", "--train-dict"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Trained compression dictionary"));
+    write_sidecars(80..90);
+
+    mediagit()
+        .args(["verify", "--worktree"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "All 90 tracked file(s) match HEAD",
+        ));
+    mediagit().arg("fsck").current_dir(dir).assert().success();
+}
+
+/// Backdate every file in the object store by `age`
+fn age_objects(dir: &Path, age: std::time::Duration) {
+    let mtime = std::time::SystemTime::now() - age;
+    let mut pending = vec![dir.join(".mediagit/objects")];
+    while let Some(path) = pending.pop() {
+        if path.is_dir() {
+            pending.extend(fs::read_dir(&path).unwrap().map(|e| e.unwrap().path()));
+        } else {
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap();
+        }
+    }
+}
+
+#[test]
+fn test_gc_prunes_unreachable_objects_after_expiry() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    add_and_commit(temp_dir.path(), "file.txt", "Content", "Initial commit");
+
+    fs::write(temp_dir.path().join("pending.txt"), "pending content").unwrap();
+    mediagit()
+        .args(["add", "pending.txt"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+
+    // Within the default two-week gc.prune_expire
+    age_objects(
+        temp_dir.path(),
+        std::time::Duration::from_secs(13 * 24 * 60 * 60),
+    );
+    mediagit()
+        .args(["gc", "--yes"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Kept (grace period):"))
+        .stdout(predicate::str::contains("Deleted").not());
+
+    // A shorter configured expiry lets it go
+    let config_path = temp_dir.path().join(".mediagit/config.toml");
+    let config_text = fs::read_to_string(&config_path).unwrap();
+    assert!(config_text.contains("prune_expire = \"2.weeks.ago\""));
+    fs::write(
+        &config_path,
+        config_text.replace("2.weeks.ago", "1.week.ago"),
+    )
+    .unwrap();
+    mediagit()
+        .args(["gc", "--yes"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted 1 objects"));
+}
+
+#[test]
+fn test_gc_removes_abandoned_temp_files() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    add_and_commit(temp_dir.path(), "file.txt", "Content", "Initial commit");
+
+    // Left behind by a write that was interrupted two hours ago
+    let shard = temp_dir.path().join(".mediagit/objects/objects/ab/cd");
+    fs::create_dir_all(&shard).unwrap();
+    let abandoned = shard.join("abcd1234.tmp3");
+    fs::write(&abandoned, "partial").unwrap();
+    age_objects(temp_dir.path(), std::time::Duration::from_secs(2 * 60 * 60));
+
+    // Younger than an hour, so possibly still being written
+    let in_progress = shard.join("abcd5678.tmp4");
+    fs::write(&in_progress, "partial").unwrap();
+
+    mediagit()
+        .args(["gc", "--yes", "--prune", "now"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Temporary files removed:"));
+    assert!(!abandoned.exists());
+    assert!(in_progress.exists());
+    mediagit()
+        .arg("fsck")
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_gc_prune_never_keeps_unreachable_objects() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    add_and_commit(temp_dir.path(), "file.txt", "Content", "Initial commit");
+
+    fs::write(temp_dir.path().join("pending.txt"), "pending content").unwrap();
+    mediagit()
+        .args(["add", "pending.txt"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+
+    mediagit()
+        .args(["gc", "--yes", "--prune", "never"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("skipping prune"));
+
+    mediagit()
+        .args(["gc", "--yes", "--prune", "soon"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --prune"));
+}
+
+#[test]
+fn test_gc_thin_media_keeps_designated_versions() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+
+    let config_path = dir.join(".mediagit/config.toml");
+    let mut config = fs::read_to_string(&config_path).unwrap();
+    config.push_str("\n[[gc.thin_media]]\npattern = \"*.exr\"\nkeep_every = 3\nkeep_last = 1\n");
+    fs::write(&config_path, config).unwrap();
+
+    add_and_commit(dir, "notes.txt", "Shot notes", "Add notes");
+    for n in 1..=7 {
+        add_and_commit(
+            dir,
+            "shot.exr",
+            &format!("checkpoint {}", n),
+            &format!("Checkpoint {}", n),
+        );
+    }
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let commits = || {
+        runtime.block_on(async {
+            let storage = create_storage_backend(dir).await.unwrap();
+            let odb = ObjectDatabase::with_smart_compression(storage, 100);
+            let mut next = Some(
+                RefDatabase::new(dir.join(".mediagit"))
+                    .resolve("HEAD")
+                    .await
+                    .unwrap(),
+            );
+            let mut messages = Vec::new();
+            while let Some(oid) = next {
+                let commit = Commit::read(&odb, &oid).await.unwrap();
+                messages.push(commit.message.trim().to_string());
+                next = commit.parents.first().copied();
+            }
+            messages
+        })
+    };
+    let version_exists = |n: usize| {
+        runtime.block_on(async {
+            let storage = create_storage_backend(dir).await.unwrap();
+            let odb = ObjectDatabase::with_smart_compression(storage, 100);
+            odb.exists(&Oid::hash(format!("checkpoint {}", n).as_bytes()))
+                .await
+                .unwrap()
+        })
+    };
+    let before = commits();
+
+    // Versions 3 and 6 by keep_every, 7 by keep_last
+    let plan = "shot.exr: 7 versions, keeping 3 (3, 6, 7), dropping 4";
+    mediagit()
+        .args(["gc", "--thin-media", "--dry-run"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(plan))
+        .stdout(predicate::str::contains("Would drop 4 versions"));
+    assert!((1..=7).all(version_exists));
+
+    mediagit()
+        .args(["gc", "--thin-media", "--yes", "--prune", "now"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(plan))
+        .stdout(predicate::str::contains(
+            "Rewrote 7 commits and moved 1 refs",
+        ));
+
+    for n in 1..=7 {
+        assert_eq!(version_exists(n), [3, 6, 7].contains(&n), "version {}", n);
+    }
+    assert_eq!(commits(), before);
+    assert_eq!(
+        fs::read_to_string(dir.join("shot.exr")).unwrap(),
+        "checkpoint 7"
+    );
+    mediagit()
+        .args(["status", "--porcelain"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn test_gc_roll_up_to_depth() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+
+    let config_path = dir.join(".mediagit/config.toml");
+    let mut config = fs::read_to_string(&config_path).unwrap();
+    config.push_str("\n[gc.retention]\nmax_depth = 3\n");
+    fs::write(&config_path, config).unwrap();
+
+    add_and_commit(dir, "notes.txt", "Shot notes", "Add notes");
+    for n in 1..=6 {
+        add_and_commit(
+            dir,
+            "shot.exr",
+            &format!("take {}", n),
+            &format!("Take {}", n),
+        );
+    }
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let odb = || {
+        runtime.block_on(async {
+            ObjectDatabase::with_smart_compression(create_storage_backend(dir).await.unwrap(), 100)
+        })
+    };
+    let exists = |content: &str| {
+        runtime
+            .block_on(odb().exists(&Oid::hash(content.as_bytes())))
+            .unwrap()
+    };
+    let commits = || {
+        let odb = odb();
+        runtime.block_on(async {
+            let mut next = Some(
+                RefDatabase::new(dir.join(".mediagit"))
+                    .resolve("HEAD")
+                    .await
+                    .unwrap(),
+            );
+            let mut commits = Vec::new();
+            while let Some(oid) = next {
+                let commit = Commit::read(&odb, &oid).await.unwrap();
+                next = commit.parents.first().copied();
+                commits.push((commit.message.trim().to_string(), commit.tree));
+            }
+            commits
+        })
+    };
+    let before = commits();
+
+    mediagit()
+        .args(["gc", "--roll-up", "--dry-run"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Keeping 3 commits, rolling up 4"))
+        .stdout(predicate::str::contains("Take 4"))
+        .stdout(predicate::str::contains(
+            "Would roll up 4 commits and 3 files",
+        ));
+    assert!((1..=6).all(|n| exists(&format!("take {}", n))));
+
+    mediagit()
+        .args(["gc", "--roll-up", "--yes", "--prune", "now"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Rolled up 4 commits; rewrote 3 and moved 1 refs",
+        ));
+
+    // Older takes are gone; the last three commits keep their content
+    for n in 1..=6 {
+        assert_eq!(exists(&format!("take {}", n)), n > 3, "take {}", n);
+    }
+    assert!(exists("Shot notes"));
+    assert_eq!(commits(), before[..3]);
+
+    // The new root is a clean end of history
+    mediagit().arg("fsck").current_dir(dir).assert().success();
+    mediagit()
+        .args(["status", "--porcelain"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+    mediagit()
+        .args(["gc", "--roll-up", "--dry-run"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing to roll up"));
+}
+
 // ============================================================================
 // FSCK Command Tests
 // ============================================================================
@@ -347,6 +801,37 @@ fn test_verify_detailed() {
         .success();
 }
 
+#[test]
+fn test_verify_worktree_reports_modified_files() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+
+    add_and_commit(temp_dir.path(), "scene.txt", "frame one", "Add scene");
+    add_and_commit(temp_dir.path(), "notes.txt", "unchanged", "Add notes");
+
+    mediagit()
+        .arg("verify")
+        .arg("--worktree")
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All 2 tracked file(s) match HEAD"));
+
+    fs::write(temp_dir.path().join("scene.txt"), "frame two!").unwrap();
+
+    mediagit()
+        .arg("verify")
+        .arg("--worktree")
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "scene.txt: differs from HEAD (committed 9 bytes, working 10 bytes, first difference at byte 6)",
+        ))
+        .stdout(predicate::str::contains("notes.txt").not())
+        .stderr(predicate::str::contains("1 file(s) differ from HEAD"));
+}
+
 // ============================================================================
 // Stats Command Tests
 // ============================================================================
@@ -428,6 +913,139 @@ fn test_stats_compression() {
         .success();
 }
 
+#[test]
+fn test_stats_dedup_by_category() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+
+    // The same config staged under two names dedups; two scenes do not
+    add_and_commit(temp_dir.path(), "shot.json", "{\"fps\": 24}", "Add config");
+    add_and_commit(temp_dir.path(), "copy.json", "{\"fps\": 24}", "Copy config");
+    add_and_commit(temp_dir.path(), "a.psd", "layers a", "Add a");
+    add_and_commit(temp_dir.path(), "b.psd", "layers b", "Add b");
+
+    let output = mediagit()
+        .args(["stats", "--json"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let by_category = &json["storage"]["dedup_by_category"];
+
+    assert_eq!(by_category["Text"]["writes"], 2);
+    assert_eq!(by_category["Text"]["unique_objects"], 1);
+    assert_eq!(by_category["Text"]["dedup_ratio"], 0.5);
+    assert_eq!(by_category["CreativeProject"]["writes"], 2);
+    assert_eq!(by_category["CreativeProject"]["dedup_ratio"], 0.0);
+
+    mediagit()
+        .args(["stats", "--storage"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deduplication by category:"));
+}
+
+#[test]
+fn test_stats_reports_backend_usage_and_quota() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+    add_and_commit(dir, "notes.txt", "Shot notes", "Add notes");
+
+    let stats = || {
+        let output = mediagit()
+            .args(["stats", "--json"])
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+    let json = stats();
+    let used = json["storage"]["backend"]["bytes"].as_u64().unwrap();
+    assert!(used > 0);
+    assert!(json["storage"]["backend"]["objects"].as_u64().unwrap() > 0);
+    assert!(json["storage"]["backend"]["quota_bytes"].is_null());
+
+    // A quota already used up refuses the next write
+    let config_path = dir.join(".mediagit/config.toml");
+    let mut config = fs::read_to_string(&config_path).unwrap();
+    config.push_str(&format!("\n[quota]\nmax_bytes = {}\n", used));
+    fs::write(&config_path, config).unwrap();
+
+    assert_eq!(stats()["storage"]["backend"]["quota_bytes"], used);
+    fs::write(dir.join("plate.exr"), "frame data").unwrap();
+    mediagit()
+        .args(["add", "plate.exr"])
+        .current_dir(dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("storage quota exceeded"));
+    mediagit()
+        .args(["stats", "--storage"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Quota:"));
+}
+
+#[test]
+fn test_stats_by_ref_reports_exclusive_storage() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    add_and_commit(temp_dir.path(), "readme.txt", "shared", "Initial commit");
+
+    mediagit()
+        .args(["branch", "create", "experiment"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    mediagit()
+        .args(["branch", "switch", "experiment"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    let large: String = (0..200_000u64)
+        .map(|i| format!("{:08x}", i.wrapping_mul(2_654_435_761)))
+        .collect();
+    add_and_commit(temp_dir.path(), "render.bin", &large, "Add render");
+
+    let output = mediagit()
+        .args(["stats", "--by-ref", "--json"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let refs = json["refs"].as_array().unwrap();
+    let find = |name: &str| {
+        refs.iter()
+            .find(|r| r["ref"] == name)
+            .unwrap_or_else(|| panic!("{} missing from {}", name, json))
+    };
+
+    // The render, its tree and its commit belong to the experiment alone
+    let experiment = find("refs/heads/experiment");
+    let exclusive = experiment["exclusive_bytes"].as_u64().unwrap();
+    assert_eq!(experiment["exclusive_objects"], 3);
+    assert!(exclusive >= large.len() as u64);
+    assert!(exclusive < large.len() as u64 + 4096);
+
+    // Everything on main is also reachable from the experiment
+    let main = find("refs/heads/main");
+    assert_eq!(main["exclusive_bytes"], 0);
+    assert_eq!(main["reachable_objects"], 3);
+
+    mediagit()
+        .args(["stats", "--by-ref"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("refs/heads/experiment"));
+}
+
 #[test]
 fn test_stats_branches() {
     let temp_dir = TempDir::new().unwrap();
@@ -492,6 +1110,57 @@ fn test_stats_files() {
         .success();
 }
 
+// ============================================================================
+// Doctor Command Tests
+// ============================================================================
+
+#[test]
+fn test_doctor_reports_healthy_backend() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+
+    mediagit()
+        .arg("doctor")
+        .arg("--verbose")
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("filesystem"))
+        .stdout(predicate::str::contains("write"))
+        .stdout(predicate::str::contains("delete"))
+        .stdout(predicate::str::contains("is healthy"))
+        .stdout(predicate::str::contains("server-side copy"));
+
+    // The sentinel object is cleaned up
+    let sentinels = temp_dir.path().join(".mediagit/health-check");
+    assert!(!sentinels.exists() || fs::read_dir(&sentinels).unwrap().next().is_none());
+}
+
+#[test]
+fn test_doctor_quiet() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+
+    mediagit()
+        .arg("doctor")
+        .arg("-q")
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn test_doctor_outside_repository() {
+    let temp_dir = TempDir::new().unwrap();
+
+    mediagit()
+        .arg("doctor")
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure();
+}
+
 // ============================================================================
 // Help Tests
 // ============================================================================
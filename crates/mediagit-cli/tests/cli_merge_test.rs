@@ -446,3 +446,145 @@ fn test_merge_verbose() {
         .assert()
         .success();
 }
+
+// ============================================================================
+// Mergetool Tests
+// ============================================================================
+
+/// Create a modify/modify conflict on `shared.txt` and run the failing merge
+fn setup_conflicted_merge(dir: &Path) {
+    add_and_commit(dir, "shared.txt", "base version", "Base commit");
+
+    create_and_switch_branch(dir, "feature");
+    add_and_commit(dir, "shared.txt", "feature version", "Feature edit");
+
+    mediagit()
+        .arg("branch")
+        .arg("switch")
+        .arg("main")
+        .current_dir(dir)
+        .assert()
+        .success();
+    add_and_commit(dir, "shared.txt", "main version", "Main edit");
+
+    mediagit()
+        .arg("merge")
+        .arg("feature")
+        .current_dir(dir)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("shared.txt"));
+}
+
+#[test]
+fn test_mergetool_no_merge_in_progress() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+    add_and_commit(temp_dir.path(), "file.txt", "content", "Initial commit");
+
+    mediagit()
+        .arg("mergetool")
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No merge in progress"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_mergetool_stages_tool_resolution() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+    setup_conflicted_merge(dir);
+
+    // Fake tool: checks the three versions, records where they were, writes a resolution
+    let script = dir.join("fake-merge.sh");
+    fs::write(
+        &script,
+        "#!/bin/sh\n\
+         grep -q 'base version' \"$BASE\" || exit 1\n\
+         grep -q 'main version' \"$LOCAL\" || exit 1\n\
+         grep -q 'feature version' \"$REMOTE\" || exit 1\n\
+         echo \"$BASE\" > \"$MERGED.base-path\"\n\
+         printf 'resolved version' > \"$MERGED\"\n",
+    )
+    .unwrap();
+
+    let mut config = fs::read_to_string(dir.join(".mediagit/config.toml")).unwrap();
+    config.push_str(&format!(
+        "\n[mergetool.types]\ntext = \"sh {}\"\n",
+        script.display()
+    ));
+    fs::write(dir.join(".mediagit/config.toml"), config).unwrap();
+
+    mediagit()
+        .arg("mergetool")
+        .current_dir(dir)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(dir.join("shared.txt")).unwrap(),
+        "resolved version"
+    );
+
+    // Temporary BASE/LOCAL/REMOTE files are cleaned up
+    let base_path = fs::read_to_string(dir.join("shared.txt.base-path")).unwrap();
+    assert!(!Path::new(base_path.trim()).exists());
+
+    // Resolution is staged and the conflict is gone
+    let index = fs::read_to_string(dir.join(".mediagit/index")).unwrap();
+    assert!(index.contains("shared.txt"));
+    let conflicts = fs::read_to_string(dir.join(".mediagit/MERGE_CONFLICTS")).unwrap();
+    assert!(!conflicts.contains("shared.txt"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_mergetool_failed_tool_leaves_conflict() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+    setup_conflicted_merge(dir);
+
+    mediagit()
+        .arg("mergetool")
+        .arg("--tool")
+        .arg("exit 1")
+        .current_dir(dir)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(dir.join("shared.txt")).unwrap(),
+        "main version"
+    );
+    let conflicts = fs::read_to_string(dir.join(".mediagit/MERGE_CONFLICTS")).unwrap();
+    assert!(conflicts.contains("shared.txt"));
+}
+
+#[test]
+fn test_mergetool_broken_config_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+    setup_conflicted_merge(dir);
+
+    let mut config = fs::read_to_string(dir.join(".mediagit/config.toml")).unwrap();
+    config.push_str("\n[mergetool.types\ntext = \"sh\"\n");
+    fs::write(dir.join(".mediagit/config.toml"), config).unwrap();
+
+    // A config that does not parse is an error, not a silent fallback to defaults
+    mediagit()
+        .arg("mergetool")
+        .arg("--tool")
+        .arg("true")
+        .current_dir(dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("failed to load config"));
+
+    let conflicts = fs::read_to_string(dir.join(".mediagit/MERGE_CONFLICTS")).unwrap();
+    assert!(conflicts.contains("shared.txt"));
+}
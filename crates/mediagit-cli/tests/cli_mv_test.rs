@@ -0,0 +1,205 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! CLI Move Command Tests
+//!
+//! Tests for renaming files and moving directories with staged renames.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[allow(deprecated)]
+fn mediagit() -> Command {
+    Command::cargo_bin("mediagit").unwrap()
+}
+
+fn init_repo(dir: &Path) {
+    mediagit()
+        .arg("init")
+        .arg("-q")
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+fn add_and_commit(dir: &Path, files: &[(&str, &str)], message: &str) {
+    for (name, content) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+        mediagit()
+            .arg("add")
+            .arg(name)
+            .current_dir(dir)
+            .assert()
+            .success();
+    }
+    mediagit()
+        .arg("commit")
+        .arg("-m")
+        .arg(message)
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+fn porcelain_status(dir: &Path) -> String {
+    let output = mediagit()
+        .args(["status", "--porcelain"])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_mv_single_file_rename() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+    add_and_commit(dir, &[("hero.psd", "layered art")], "Initial");
+
+    mediagit()
+        .args(["mv", "hero.psd", "hero_v2.psd"])
+        .current_dir(dir)
+        .assert()
+        .success();
+
+    assert!(!dir.join("hero.psd").exists());
+    assert_eq!(
+        fs::read_to_string(dir.join("hero_v2.psd")).unwrap(),
+        "layered art"
+    );
+    assert_eq!(porcelain_status(dir), "R  hero.psd -> hero_v2.psd\n");
+
+    mediagit()
+        .args(["commit", "-m", "Rename hero"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    assert_eq!(porcelain_status(dir), "");
+
+    mediagit()
+        .args(["diff", "HEAD~1", "HEAD"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "renamed:  hero.psd -> hero_v2.psd (100%)",
+        ))
+        .stdout(predicate::str::contains("added:").not())
+        .stdout(predicate::str::contains("deleted:").not())
+        .stdout(predicate::str::contains("modified:").not());
+}
+
+#[test]
+fn test_mv_directory_updates_staged_tree() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+    add_and_commit(
+        dir,
+        &[
+            ("textures/wood.png", "wood"),
+            ("textures/metal/steel.png", "steel"),
+            ("readme.txt", "docs"),
+        ],
+        "Initial",
+    );
+
+    mediagit()
+        .args(["mv", "textures", "assets/textures"])
+        .current_dir(dir)
+        .assert()
+        .success();
+
+    assert!(!dir.join("textures").exists());
+    assert!(dir.join("assets/textures/metal/steel.png").is_file());
+    assert_eq!(
+        porcelain_status(dir),
+        "R  textures/metal/steel.png -> assets/textures/metal/steel.png\n\
+         R  textures/wood.png -> assets/textures/wood.png\n"
+    );
+
+    mediagit()
+        .args(["commit", "-m", "Reorganize textures"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    assert_eq!(porcelain_status(dir), "");
+
+    let output = mediagit()
+        .args(["diff", "--stat", "HEAD~1", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    let summary = String::from_utf8(output.stdout).unwrap();
+    assert!(summary.contains("2 file(s) changed: 0 added, 0 modified, 2 renamed, 0 deleted"));
+    assert!(!summary.contains("readme.txt"));
+}
+
+#[test]
+fn test_mv_refuses_existing_destination_without_force() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+    add_and_commit(
+        dir,
+        &[("draft.blend", "draft"), ("final.blend", "old final")],
+        "Initial",
+    );
+
+    mediagit()
+        .args(["mv", "draft.blend", "final.blend"])
+        .current_dir(dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("use --force"));
+    assert_eq!(
+        fs::read_to_string(dir.join("draft.blend")).unwrap(),
+        "draft"
+    );
+    assert_eq!(porcelain_status(dir), "");
+
+    mediagit()
+        .args(["mv", "--force", "draft.blend", "final.blend"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    assert!(!dir.join("draft.blend").exists());
+    assert_eq!(
+        fs::read_to_string(dir.join("final.blend")).unwrap(),
+        "draft"
+    );
+}
+
+#[test]
+fn test_mv_untracked_file_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    init_repo(dir);
+    add_and_commit(dir, &[("tracked.txt", "t")], "Initial");
+    fs::write(dir.join("scratch.txt"), "s").unwrap();
+
+    mediagit()
+        .args(["mv", "scratch.txt", "notes.txt"])
+        .current_dir(dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not under version control"));
+    assert!(dir.join("scratch.txt").exists());
+}
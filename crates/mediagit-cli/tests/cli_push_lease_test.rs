@@ -0,0 +1,204 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! CLI `push --force-with-lease` Tests
+//!
+//! A rewritten branch is force-pushed to an in-process server only while the
+//! remote branch is still where the local remote-tracking ref says it is.
+
+mod common;
+
+use assert_cmd::Command;
+use common::start_server;
+use predicates::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+#[allow(deprecated)]
+fn mediagit() -> Command {
+    Command::cargo_bin("mediagit").unwrap()
+}
+
+fn add_and_commit(dir: &Path, name: &str, content: &str, message: &str) {
+    fs::write(dir.join(name), content).unwrap();
+    mediagit()
+        .arg("add")
+        .arg(name)
+        .current_dir(dir)
+        .assert()
+        .success();
+    mediagit()
+        .arg("commit")
+        .arg("-m")
+        .arg(message)
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+fn read_ref(repo: &Path, name: &str) -> String {
+    fs::read_to_string(repo.join(".mediagit/refs").join(name))
+        .unwrap()
+        .trim()
+        .to_string()
+}
+
+/// Replace the last commit on `main` with a different one
+fn rewrite_last_commit(repo: &Path, content: &str) {
+    mediagit()
+        .args(["reset", "--hard", "HEAD~1"])
+        .current_dir(repo)
+        .assert()
+        .success();
+    add_and_commit(repo, "scene.blend", content, "Rewritten");
+}
+
+struct Setup {
+    _repos_dir: TempDir,
+    _work_dir: TempDir,
+    served: PathBuf,
+    url: String,
+    local: PathBuf,
+    work_dir: PathBuf,
+}
+
+/// A served repository with two commits on `main`, pushed from `local`
+fn setup() -> Setup {
+    let repos_dir = TempDir::new().unwrap();
+    let served = repos_dir.path().join("project");
+    fs::create_dir_all(&served).unwrap();
+    mediagit()
+        .args(["init", "-q"])
+        .current_dir(&served)
+        .assert()
+        .success();
+    let url = format!("{}/project", start_server(repos_dir.path()));
+
+    let work_dir = TempDir::new().unwrap();
+    let local = work_dir.path().join("local");
+    fs::create_dir_all(&local).unwrap();
+    mediagit()
+        .args(["init", "-q"])
+        .current_dir(&local)
+        .assert()
+        .success();
+    add_and_commit(&local, "scene.blend", "scene v1", "Initial");
+    add_and_commit(&local, "scene.blend", "scene v2", "Second");
+    mediagit()
+        .args(["remote", "add", "origin", &url])
+        .current_dir(&local)
+        .assert()
+        .success();
+    mediagit()
+        .args(["push", "origin", "main"])
+        .current_dir(&local)
+        .assert()
+        .success();
+
+    Setup {
+        served,
+        url,
+        local,
+        work_dir: work_dir.path().to_path_buf(),
+        _repos_dir: repos_dir,
+        _work_dir: work_dir,
+    }
+}
+
+#[test]
+fn test_force_with_lease_succeeds_when_ref_unchanged() {
+    let setup = setup();
+    rewrite_last_commit(&setup.local, "scene v2, take 2");
+
+    // A plain push of the rewritten branch is not what the remote last had
+    mediagit()
+        .args(["push", "--force-with-lease", "origin", "main"])
+        .current_dir(&setup.local)
+        .assert()
+        .success();
+
+    let rewritten = read_ref(&setup.local, "heads/main");
+    assert_eq!(read_ref(&setup.served, "heads/main"), rewritten);
+    assert_eq!(read_ref(&setup.local, "remotes/origin/main"), rewritten);
+}
+
+#[test]
+fn test_force_with_lease_rejected_when_ref_moved() {
+    let setup = setup();
+
+    // A collaborator pushes on top of main after our last push
+    mediagit()
+        .args(["clone", &setup.url, "collaborator"])
+        .current_dir(&setup.work_dir)
+        .assert()
+        .success();
+    let collaborator = setup.work_dir.join("collaborator");
+    add_and_commit(&collaborator, "notes.txt", "lighting notes", "Notes");
+    mediagit()
+        .args(["push", "origin", "main"])
+        .current_dir(&collaborator)
+        .assert()
+        .success();
+    let theirs = read_ref(&collaborator, "heads/main");
+    assert_eq!(read_ref(&setup.served, "heads/main"), theirs);
+
+    // Our stale view of main does not let us overwrite their commit
+    rewrite_last_commit(&setup.local, "scene v2, take 2");
+    mediagit()
+        .args(["push", "--force-with-lease", "origin", "main"])
+        .current_dir(&setup.local)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("moved since last fetch"));
+    assert_eq!(read_ref(&setup.served, "heads/main"), theirs);
+
+    // Once fetched, the lease covers their commit and the push goes through
+    mediagit()
+        .args(["fetch", "origin"])
+        .current_dir(&setup.local)
+        .assert()
+        .success();
+    mediagit()
+        .args(["push", "--force-with-lease", "origin", "main"])
+        .current_dir(&setup.local)
+        .assert()
+        .success();
+    assert_eq!(
+        read_ref(&setup.served, "heads/main"),
+        read_ref(&setup.local, "heads/main")
+    );
+}
+
+#[test]
+fn test_force_with_lease_rejected_when_ref_unreadable() {
+    let setup = setup();
+
+    // The served `feature` ref exists but cannot be parsed
+    let corrupt = setup.served.join(".mediagit/refs/heads/feature");
+    fs::write(&corrupt, b"\xff not a ref").unwrap();
+
+    // We never fetched `feature`, so the lease expects it to be absent
+    mediagit()
+        .args(["branch", "create", "feature"])
+        .current_dir(&setup.local)
+        .assert()
+        .success();
+    mediagit()
+        .args(["push", "--force-with-lease", "origin", "feature"])
+        .current_dir(&setup.local)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot read current value"));
+    assert_eq!(fs::read(&corrupt).unwrap(), b"\xff not a ref");
+}
@@ -0,0 +1,257 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! CLI Upstream Tracking Tests
+//!
+//! Runs `mediagit push -u`, bare `push`/`pull` and `status` against an
+//! in-process server. The remote is named `studio` rather than `origin`, so
+//! bare commands only succeed if they resolve the recorded upstream.
+
+mod common;
+
+use assert_cmd::Command;
+use common::start_server;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[allow(deprecated)]
+fn mediagit() -> Command {
+    Command::cargo_bin("mediagit").unwrap()
+}
+
+fn init_repo(dir: &Path) {
+    mediagit()
+        .arg("init")
+        .arg("-q")
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+fn add_and_commit(dir: &Path, name: &str, content: &str, message: &str) {
+    fs::write(dir.join(name), content).unwrap();
+    mediagit()
+        .arg("add")
+        .arg(name)
+        .current_dir(dir)
+        .assert()
+        .success();
+    mediagit()
+        .arg("commit")
+        .arg("-m")
+        .arg(message)
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+/// Local repository with one commit and a `studio` remote pointing at an empty server repository
+fn setup_repos(repos_dir: &Path, local: &Path) {
+    let served = repos_dir.join("project");
+    fs::create_dir_all(&served).unwrap();
+    init_repo(&served);
+
+    let url = format!("{}/project", start_server(repos_dir));
+    init_repo(local);
+    add_and_commit(local, "scene.blend", "scene v1", "Initial");
+    mediagit()
+        .args(["remote", "add", "studio", &url])
+        .current_dir(local)
+        .assert()
+        .success();
+}
+
+fn read_ref(repo: &Path, name: &str) -> String {
+    fs::read_to_string(repo.join(".mediagit/refs").join(name))
+        .unwrap()
+        .trim()
+        .to_string()
+}
+
+fn config(repo: &Path) -> String {
+    fs::read_to_string(repo.join(".mediagit/config.toml")).unwrap()
+}
+
+#[test]
+fn test_push_set_upstream_records_tracking() {
+    let repos_dir = TempDir::new().unwrap();
+    let local = TempDir::new().unwrap();
+    setup_repos(repos_dir.path(), local.path());
+
+    mediagit()
+        .args(["push", "-u", "studio", "main"])
+        .current_dir(local.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Branch 'main' set up to track 'studio/main'",
+        ));
+
+    let config = config(local.path());
+    assert!(config.contains("[branches.main]"), "{}", config);
+    assert!(config.contains("remote = \"studio\""), "{}", config);
+    assert!(config.contains("merge = \"refs/heads/main\""), "{}", config);
+}
+
+#[test]
+fn test_bare_push_and_pull_use_upstream() {
+    let repos_dir = TempDir::new().unwrap();
+    let local = TempDir::new().unwrap();
+    setup_repos(repos_dir.path(), local.path());
+    let served = repos_dir.path().join("project");
+
+    mediagit()
+        .args(["push", "-u", "studio", "main"])
+        .current_dir(local.path())
+        .assert()
+        .success();
+
+    add_and_commit(local.path(), "scene.blend", "scene v2", "Second");
+    mediagit()
+        .args(["status", "-b"])
+        .current_dir(local.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Your branch is ahead of 'studio/main' by 1 commit(s)",
+        ));
+
+    // No "origin" remote exists, so this only works through the upstream
+    mediagit()
+        .arg("push")
+        .current_dir(local.path())
+        .assert()
+        .success();
+    let pushed = read_ref(local.path(), "heads/main");
+    assert_eq!(read_ref(&served, "heads/main"), pushed);
+    mediagit()
+        .arg("status")
+        .current_dir(local.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Your branch is up to date with 'studio/main'",
+        ));
+
+    mediagit()
+        .args(["reset", "--hard", "HEAD~1"])
+        .current_dir(local.path())
+        .assert()
+        .success();
+    mediagit()
+        .arg("status")
+        .current_dir(local.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Your branch is behind 'studio/main' by 1 commit(s)",
+        ));
+
+    mediagit()
+        .arg("pull")
+        .current_dir(local.path())
+        .assert()
+        .success();
+    assert_eq!(read_ref(local.path(), "heads/main"), pushed);
+    assert_eq!(
+        fs::read_to_string(local.path().join("scene.blend")).unwrap(),
+        "scene v2"
+    );
+}
+
+#[test]
+fn test_auto_setup_remote_tracks_new_branch() {
+    let repos_dir = TempDir::new().unwrap();
+    let local = TempDir::new().unwrap();
+    setup_repos(repos_dir.path(), local.path());
+
+    mediagit()
+        .args(["push", "-u", "studio", "main"])
+        .current_dir(local.path())
+        .assert()
+        .success();
+    mediagit()
+        .args(["branch", "create", "lighting"])
+        .current_dir(local.path())
+        .assert()
+        .success();
+    mediagit()
+        .args(["branch", "switch", "lighting"])
+        .current_dir(local.path())
+        .assert()
+        .success();
+    add_and_commit(local.path(), "lights.blend", "key light", "Lighting");
+
+    // Without the option a new branch needs -u
+    mediagit()
+        .args(["push", "studio"])
+        .current_dir(local.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("auto_setup_remote"));
+
+    let config_path = local.path().join(".mediagit/config.toml");
+    let config_text = fs::read_to_string(&config_path).unwrap();
+    assert!(config_text.contains("auto_setup_remote = false"));
+    fs::write(
+        &config_path,
+        config_text.replace("auto_setup_remote = false", "auto_setup_remote = true"),
+    )
+    .unwrap();
+
+    mediagit()
+        .args(["push", "studio"])
+        .current_dir(local.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Branch 'lighting' set up to track 'studio/lighting'",
+        ));
+
+    let config = config(local.path());
+    assert!(config.contains("[branches.lighting]"), "{}", config);
+    assert_eq!(
+        read_ref(&repos_dir.path().join("project"), "heads/lighting"),
+        read_ref(local.path(), "heads/lighting")
+    );
+}
+
+#[test]
+fn test_branch_list_vv_shows_ahead_behind() {
+    let repos_dir = TempDir::new().unwrap();
+    let local = TempDir::new().unwrap();
+    setup_repos(repos_dir.path(), local.path());
+
+    mediagit()
+        .args(["push", "-u", "studio", "main"])
+        .current_dir(local.path())
+        .assert()
+        .success();
+    mediagit()
+        .args(["branch", "list", "-vv"])
+        .current_dir(local.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[studio/main]"));
+
+    add_and_commit(local.path(), "scene.blend", "scene v2", "Second");
+    add_and_commit(local.path(), "scene.blend", "scene v3", "Third");
+    mediagit()
+        .args(["branch", "list", "-vv"])
+        .current_dir(local.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[studio/main: ahead 2]"));
+}
@@ -404,6 +404,99 @@ fn test_diff_no_changes() {
         .stdout(predicate::str::is_empty().or(predicate::str::contains("")));
 }
 
+#[test]
+fn test_diff_unified_patch_applies_with_git() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+
+    let original = "intro\nline two\nline three\nline four\nline five\nline six\noutro\n";
+    let updated = "intro\nline 2\nline three\nline four\nline five\nline six\noutro\nappendix\n";
+    add_and_commit(temp_dir.path(), "notes.txt", original, "Initial");
+    add_and_commit(temp_dir.path(), "notes.txt", updated, "Edit notes");
+
+    let output = mediagit()
+        .args(["diff", "--unified=3", "HEAD~1", "HEAD"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let patch = String::from_utf8(output.stdout).unwrap();
+    assert!(patch.starts_with("diff --git a/notes.txt b/notes.txt\n"));
+    assert!(patch.contains("--- a/notes.txt\n+++ b/notes.txt\n"));
+    assert!(patch.contains("@@ -1,7 +1,8 @@\n intro\n-line two\n+line 2\n"));
+
+    // Apply the patch to a copy of the old content and expect the new content
+    let target = TempDir::new().unwrap();
+    fs::write(target.path().join("notes.txt"), original).unwrap();
+    let patch_path = target.path().join("change.patch");
+    fs::write(&patch_path, &patch).unwrap();
+
+    let applied = std::process::Command::new("git")
+        .arg("apply")
+        .arg(&patch_path)
+        .current_dir(target.path())
+        .status()
+        .expect("git must be installed to run this test");
+    assert!(applied.success());
+    assert_eq!(
+        fs::read_to_string(target.path().join("notes.txt")).unwrap(),
+        updated
+    );
+}
+
+#[test]
+fn test_diff_patch_binary_files() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+
+    add_and_commit(temp_dir.path(), "blob.bin", "\0\x01\x02", "Initial");
+    fs::write(temp_dir.path().join("blob.bin"), b"\0\x01\x03").unwrap();
+
+    mediagit()
+        .args(["diff", "--patch"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Binary files a/blob.bin and b/blob.bin differ",
+        ));
+}
+
+#[test]
+fn test_diff_patch_normalizes_working_tree_line_endings() {
+    let temp_dir = TempDir::new().unwrap();
+    init_repo(temp_dir.path());
+
+    add_and_commit(
+        temp_dir.path(),
+        ".mediagitattributes",
+        "*.txt text\n",
+        "Attributes",
+    );
+    add_and_commit(
+        temp_dir.path(),
+        "notes.txt",
+        "intro\nline two\noutro\n",
+        "Notes",
+    );
+    fs::write(
+        temp_dir.path().join("notes.txt"),
+        "intro\r\nline 2\r\noutro\r\n",
+    )
+    .unwrap();
+
+    let output = mediagit()
+        .args(["diff", "--patch"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let patch = String::from_utf8(output.stdout).unwrap();
+    assert!(patch.contains("@@ -1,3 +1,3 @@\n intro\n-line two\n+line 2\n outro\n"));
+}
+
 // ============================================================================
 // Show Command Tests
 // ============================================================================
@@ -13,98 +13,29 @@
 
 //! Common test helpers for MediaGit CLI tests.
 //!
-//! This module re-exports utilities from mediagit-test-utils and provides
-//! additional CLI-specific helper functions.
+//! General repository fixtures live in mediagit-test-utils; this module holds
+//! CLI-specific helpers shared by more than one test binary.
 
-pub use mediagit_test_utils::{
-    mediagit, MediagitCommand, TestRepo, TestPaths, TestFixtures,
-    assert_repo_initialized, assert_file_tracked, assert_branch_exists,
-    assert_branch_not_exists, assert_on_branch,
-};
-
-use assert_cmd::Command;
-use std::fs;
 use std::path::Path;
-
-/// Initialize a repository in the given directory (quiet mode).
-pub fn init_repo(dir: &Path) {
-    mediagit()
-        .arg("init")
-        .arg("-q")
-        .current_dir(dir)
-        .assert()
-        .success();
-}
-
-/// Add files and create a commit.
-pub fn add_and_commit(dir: &Path, name: &str, content: &str, message: &str) {
-    fs::write(dir.join(name), content).expect("Failed to write file");
-    mediagit()
-        .arg("add")
-        .arg(name)
-        .current_dir(dir)
-        .assert()
-        .success();
-    mediagit()
-        .arg("commit")
-        .arg("-m")
-        .arg(message)
-        .current_dir(dir)
-        .assert()
-        .success();
-}
-
-/// Create a new branch.
-pub fn create_branch(dir: &Path, name: &str) {
-    mediagit()
-        .arg("branch")
-        .arg("create")
-        .arg(name)
-        .current_dir(dir)
-        .assert()
-        .success();
-}
-
-/// Switch to a branch.
-pub fn switch_branch(dir: &Path, name: &str) {
-    mediagit()
-        .arg("branch")
-        .arg("switch")
-        .arg(name)
-        .current_dir(dir)
-        .assert()
-        .success();
-}
-
-/// Create and switch to a branch in one operation.
-pub fn create_and_switch_branch(dir: &Path, name: &str) {
-    create_branch(dir, name);
-    switch_branch(dir, name);
-}
-
-/// Get the test-files directory path.
-pub fn test_files_dir() -> std::path::PathBuf {
-    TestPaths::test_files_dir()
-}
-
-/// Copy a test file to the repository.
-pub fn copy_test_file(test_file: &str, repo_dir: &Path, dest_name: &str) -> std::path::PathBuf {
-    let source = test_files_dir().join(test_file);
-    let dest = repo_dir.join(dest_name);
-
-    if source.exists() {
-        if let Some(parent) = dest.parent() {
-            fs::create_dir_all(parent).ok();
-        }
-        fs::copy(&source, &dest).ok();
-    }
-
-    dest
-}
-
-/// Get the file size in bytes.
-pub fn file_size_bytes(path: &Path) -> u64 {
-    fs::metadata(path)
-        .map(|m| m.len())
-        .unwrap_or(0)
+use std::sync::Arc;
+
+/// Serve every repository under `repos_dir` on a background thread, returning the base URL
+pub fn start_server(repos_dir: &Path) -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let repos_dir = repos_dir.to_path_buf();
+
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async move {
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            let state = Arc::new(mediagit_server::AppState::new(repos_dir));
+            axum::serve(listener, mediagit_server::create_router(state))
+                .await
+                .unwrap();
+        });
+    });
+
+    base_url
 }
@@ -27,7 +27,7 @@
 
 use crate::{
     BrotliCompressor, CompressionAlgorithm, CompressionLevel, CompressionResult, Compressor,
-    ZlibCompressor, ZstdCompressor,
+    Lz4Compressor, XzCompressor, ZlibCompressor, ZstdCompressor,
 };
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -230,6 +230,39 @@ pub fn calculate_entropy(data: &[u8]) -> f64 {
     entropy
 }
 
+/// Size of each window [`looks_incompressible`] samples
+pub const PROBE_WINDOW_SIZE: usize = 4096;
+
+/// Most windows [`looks_incompressible`] samples from one buffer
+pub const PROBE_WINDOWS: usize = 8;
+
+/// Whether `data` looks too random to be worth compressing
+///
+/// Samples up to [`PROBE_WINDOWS`] windows spread evenly across the buffer
+/// and reports true only if every one has high entropy. A compressible
+/// region a window lands on (a header, an embedded table) is enough to try
+/// compression, so a buffer is never stored raw on the strength of its
+/// first few kilobytes alone; regions smaller than the gap between windows
+/// can be missed. Costs one pass over at most 32 KB, however large the
+/// buffer.
+pub fn looks_incompressible(data: &[u8]) -> bool {
+    if data.is_empty() {
+        return false;
+    }
+
+    let windows = data.len().div_ceil(PROBE_WINDOW_SIZE).min(PROBE_WINDOWS);
+    let last_start = data.len().saturating_sub(PROBE_WINDOW_SIZE);
+    (0..windows).all(|i| {
+        // First window at the start, last at the end, the rest evenly between
+        let start = match windows {
+            1 => 0,
+            n => last_start * i / (n - 1),
+        };
+        let window = &data[start..data.len().min(start + PROBE_WINDOW_SIZE)];
+        EntropyClass::classify(calculate_entropy(window)) == EntropyClass::High
+    })
+}
+
 /// Compression strategy selection
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CompressionStrategy {
@@ -489,6 +522,8 @@ impl Compressor for AdaptiveCompressor {
                 let compressor = BrotliCompressor::new(strategy.level);
                 compressor.compress(data)
             }
+            CompressionAlgorithm::Lz4 => Lz4Compressor::new().compress(data),
+            CompressionAlgorithm::Xz => XzCompressor::new(strategy.level).compress(data),
         };
 
         // Update stats
@@ -512,6 +547,8 @@ impl Compressor for AdaptiveCompressor {
             CompressionAlgorithm::Zlib => self.zlib.decompress(data),
             CompressionAlgorithm::Zstd => self.zstd.decompress(data),
             CompressionAlgorithm::Brotli => self.brotli.decompress(data),
+            CompressionAlgorithm::Lz4 => Lz4Compressor::new().decompress(data),
+            CompressionAlgorithm::Xz => XzCompressor::default_level().decompress(data),
         }
     }
 }
@@ -569,6 +606,32 @@ mod tests {
         assert!(entropy > 3.0 && entropy < 6.0);
     }
 
+    #[test]
+    fn test_looks_incompressible() {
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let random: Vec<u8> = (0..200_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect();
+        assert!(looks_incompressible(&random));
+        assert!(looks_incompressible(&random[..5000]));
+
+        // A compressible region anywhere is sampled
+        let mut tail = random.clone();
+        tail[190_000..].fill(b'a');
+        assert!(!looks_incompressible(&tail));
+        let mut middle = random.clone();
+        middle[80_000..120_000].fill(b'a');
+        assert!(!looks_incompressible(&middle));
+
+        assert!(!looks_incompressible(b""));
+        assert!(!looks_incompressible(&b"some text ".repeat(1000)));
+    }
+
     #[test]
     fn test_pattern_detection_text() {
         let text = b"This is plain text content with newlines\nand tabs\t.";
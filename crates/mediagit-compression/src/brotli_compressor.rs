@@ -17,9 +17,49 @@
 //! Ideal for archival and infrequently accessed data.
 
 use crate::error::{CompressionError, CompressionResult};
+use crate::limit::{self, DEFAULT_MAX_DECOMPRESSED_SIZE};
+use crate::stream::{self, StreamReader, StreamWriter};
 use crate::{CompressionLevel, Compressor};
+use async_trait::async_trait;
 use std::fmt;
 use std::io::Write;
+use tokio::io::AsyncWriteExt;
+
+/// Default Brotli window: 2^22 bytes (4 MiB)
+pub const DEFAULT_BROTLI_WINDOW: u32 = 22;
+
+/// Smallest Brotli window, as a power of two
+pub const MIN_BROTLI_WINDOW: u32 = 10;
+
+/// Largest Brotli window, as a power of two
+pub const MAX_BROTLI_WINDOW: u32 = 24;
+
+/// Highest Brotli quality
+pub const MAX_BROTLI_QUALITY: u32 = 11;
+
+/// Brotli tunables, set from `[compression.brotli]` in the repository config
+///
+/// A larger window finds matches further back, which helps large text
+/// exports, at the cost of memory in both compressor and decompressor: the
+/// decompressor needs a buffer as large as the window the data was written
+/// with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrotliSettings {
+    /// Window size as a power of two ("lgwin"), from
+    /// [`MIN_BROTLI_WINDOW`] to [`MAX_BROTLI_WINDOW`]
+    pub window: u32,
+    /// Quality (0-11) used instead of the one the compression level maps to
+    pub quality: Option<u32>,
+}
+
+impl Default for BrotliSettings {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_BROTLI_WINDOW,
+            quality: None,
+        }
+    }
+}
 
 /// Brotli compressor implementation
 ///
@@ -28,12 +68,18 @@ use std::io::Write;
 #[derive(Clone)]
 pub struct BrotliCompressor {
     level: CompressionLevel,
+    settings: BrotliSettings,
+    max_output_size: u64,
 }
 
 impl BrotliCompressor {
     /// Create a new Brotli compressor with the given compression level
     pub fn new(level: CompressionLevel) -> Self {
-        BrotliCompressor { level }
+        BrotliCompressor {
+            level,
+            settings: BrotliSettings::default(),
+            max_output_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+        }
     }
 
     /// Create a Brotli compressor with fast compression
@@ -50,23 +96,54 @@ impl BrotliCompressor {
     pub fn best() -> Self {
         BrotliCompressor::new(CompressionLevel::Best)
     }
+
+    /// Compress with `settings` instead of the defaults
+    ///
+    /// Out-of-range values are clamped: the window to
+    /// [`MIN_BROTLI_WINDOW`]..=[`MAX_BROTLI_WINDOW`] and the quality to at
+    /// most [`MAX_BROTLI_QUALITY`]. Decompression reads any window.
+    pub fn with_settings(mut self, settings: BrotliSettings) -> Self {
+        self.settings = BrotliSettings {
+            window: settings.window.clamp(MIN_BROTLI_WINDOW, MAX_BROTLI_WINDOW),
+            quality: settings.quality.map(|q| q.min(MAX_BROTLI_QUALITY)),
+        };
+        self
+    }
+
+    /// Fail decompression with
+    /// [`OutputTooLarge`](CompressionError::OutputTooLarge) once output
+    /// passes `max_output_size` bytes
+    ///
+    /// Defaults to [`DEFAULT_MAX_DECOMPRESSED_SIZE`].
+    pub fn with_max_output_size(mut self, max_output_size: u64) -> Self {
+        self.max_output_size = max_output_size;
+        self
+    }
+
+    fn quality(&self) -> u32 {
+        self.settings
+            .quality
+            .unwrap_or_else(|| self.level.to_brotli_level())
+    }
 }
 
 impl fmt::Debug for BrotliCompressor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("BrotliCompressor")
             .field("level", &self.level)
+            .field("settings", &self.settings)
+            .field("max_output_size", &self.max_output_size)
             .finish()
     }
 }
 
+#[async_trait]
 impl Compressor for BrotliCompressor {
     fn compress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
         if data.is_empty() {
             return Ok(Vec::new());
         }
 
-        let level = self.level.to_brotli_level();
         let mut output = Vec::with_capacity(data.len() / 2);
 
         // Add custom marker prefix to identify brotli compressed data
@@ -77,8 +154,8 @@ impl Compressor for BrotliCompressor {
             let mut compressor = brotli::CompressorWriter::new(
                 &mut output,
                 4096, // buffer size
-                level,
-                22, // window size (larger = better compression but more memory)
+                self.quality(),
+                self.settings.window,
             );
 
             if let Err(e) = compressor.write_all(data) {
@@ -107,22 +184,50 @@ impl Compressor for BrotliCompressor {
         // Check for brotli marker
         if data.len() >= 4 && data.starts_with(b"BRT\x01") {
             // Skip the marker prefix
-            let compressed_data = &data[4..];
-            let mut output = Vec::with_capacity(data.len() * 2);
-
-            match brotli::BrotliDecompress(&mut std::io::Cursor::new(compressed_data), &mut output)
-            {
-                Ok(_) => Ok(output),
-                Err(e) => Err(CompressionError::decompression_failed(format!(
-                    "brotli decompression failed: {}",
-                    e
-                ))),
-            }
+            let decoder = brotli::Decompressor::new(&data[4..], 4096);
+            limit::read_limited(decoder, self.max_output_size, "brotli")
         } else {
             // Data is not brotli compressed, return as-is
             Ok(data.to_vec())
         }
     }
+
+    async fn compress_stream(
+        &self,
+        reader: &mut StreamReader<'_>,
+        writer: &mut StreamWriter<'_>,
+    ) -> CompressionResult<u64> {
+        let mut first = [0u8; 4];
+        let len = stream::read_full(reader, &mut first).await?;
+        if len == 0 {
+            return Ok(0);
+        }
+
+        writer.write_all(b"BRT\x01").await?;
+        let compressor =
+            brotli::CompressorWriter::new(Vec::new(), 4096, self.quality(), self.settings.window);
+        let written = stream::pump(Box::new(compressor), &first[..len], reader, writer)
+            .await
+            .map_err(|e| stream::compress_error("brotli", &e))?;
+        Ok(written + 4)
+    }
+
+    async fn decompress_stream(
+        &self,
+        reader: &mut StreamReader<'_>,
+        writer: &mut StreamWriter<'_>,
+    ) -> CompressionResult<u64> {
+        let mut first = [0u8; 4];
+        let len = stream::read_full(reader, &mut first).await?;
+        if len < 4 || first != *b"BRT\x01" {
+            return Ok(stream::pass_through(&first[..len], reader, writer).await?);
+        }
+
+        let decoder = stream::BrotliStreamDecoder::new(self.max_output_size);
+        stream::pump(Box::new(decoder), &[], reader, writer)
+            .await
+            .map_err(|e| stream::decompress_error("brotli", &e))
+    }
 }
 
 #[cfg(test)]
@@ -197,6 +302,60 @@ mod tests {
         assert_eq!(best_dec, data);
     }
 
+    #[test]
+    fn test_brotli_settings() {
+        // Random bytes repeated 4 MiB apart: a 16-bit window cannot see the repeat
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        let block: Vec<u8> = (0..4 * 1024 * 1024)
+            .map(|_| {
+                // xorshift64
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 32) as u8
+            })
+            .collect();
+        let data = [block.as_slice(), block.as_slice()].concat();
+        let settings = |window| BrotliSettings {
+            window,
+            quality: Some(5),
+        };
+
+        let small = BrotliCompressor::fast()
+            .with_settings(settings(16))
+            .compress(&data)
+            .unwrap();
+        let large = BrotliCompressor::fast()
+            .with_settings(settings(24))
+            .compress(&data)
+            .unwrap();
+        assert!(
+            large.len() < small.len() * 2 / 3,
+            "{} vs {}",
+            large.len(),
+            small.len()
+        );
+
+        // Any window decompresses with default settings
+        let decompressor = BrotliCompressor::default_level();
+        assert_eq!(decompressor.decompress(&small).unwrap(), data);
+        assert_eq!(decompressor.decompress(&large).unwrap(), data);
+    }
+
+    #[test]
+    fn test_brotli_settings_clamped() {
+        let compressor = BrotliCompressor::best().with_settings(BrotliSettings {
+            window: 30,
+            quality: Some(20),
+        });
+        assert_eq!(compressor.settings.window, MAX_BROTLI_WINDOW);
+        assert_eq!(compressor.quality(), MAX_BROTLI_QUALITY);
+
+        let data = b"clamped settings still compress. ".repeat(50);
+        let compressed = compressor.compress(&data).unwrap();
+        assert_eq!(compressor.decompress(&compressed).unwrap(), data);
+    }
+
     #[test]
     fn test_brotli_large_data() {
         let compressor = BrotliCompressor::new(CompressionLevel::Default);
@@ -217,4 +376,114 @@ mod tests {
         assert!(debug_str.contains("BrotliCompressor"));
         assert!(debug_str.contains("Default"));
     }
+
+    #[tokio::test]
+    async fn test_brotli_stream_round_trip() {
+        let compressor = BrotliCompressor::default_level();
+        let original = b"streamed brotli data ".repeat(100_000);
+
+        let mut compressed = Vec::new();
+        let written = compressor
+            .compress_stream(&mut &original[..], &mut compressed)
+            .await
+            .unwrap();
+        assert_eq!(written, compressed.len() as u64);
+        assert!(compressed.starts_with(b"BRT\x01"));
+        assert!(compressed.len() < original.len() / 10);
+
+        let mut decompressed = Vec::new();
+        let written = compressor
+            .decompress_stream(&mut &compressed[..], &mut decompressed)
+            .await
+            .unwrap();
+        assert_eq!(written, original.len() as u64);
+        assert_eq!(decompressed, original);
+    }
+
+    #[tokio::test]
+    async fn test_brotli_stream_matches_buffered_format() {
+        let compressor = BrotliCompressor::default_level();
+        let original = b"Hello, World! This is a test of brotli streaming.".repeat(50);
+
+        let mut streamed = Vec::new();
+        compressor
+            .compress_stream(&mut &original[..], &mut streamed)
+            .await
+            .unwrap();
+        assert_eq!(compressor.decompress(&streamed).unwrap(), original);
+
+        let buffered = compressor.compress(&original).unwrap();
+        let mut decompressed = Vec::new();
+        compressor
+            .decompress_stream(&mut &buffered[..], &mut decompressed)
+            .await
+            .unwrap();
+        assert_eq!(decompressed, original);
+    }
+
+    #[tokio::test]
+    async fn test_brotli_stream_empty_and_uncompressed() {
+        let compressor = BrotliCompressor::default_level();
+
+        let mut compressed = Vec::new();
+        let written = compressor
+            .compress_stream(&mut &b""[..], &mut compressed)
+            .await
+            .unwrap();
+        assert_eq!(written, 0);
+        assert!(compressed.is_empty());
+
+        let data = b"not compressed";
+        let mut output = Vec::new();
+        compressor
+            .decompress_stream(&mut &data[..], &mut output)
+            .await
+            .unwrap();
+        assert_eq!(output, data);
+    }
+
+    #[tokio::test]
+    async fn test_brotli_stream_truncated_input_fails() {
+        let compressor = BrotliCompressor::default_level();
+        let original = (0..200_000u32)
+            .map(|i| (i * 7 % 251) as u8)
+            .collect::<Vec<_>>();
+        let compressed = compressor.compress(&original).unwrap();
+        let truncated = &compressed[..compressed.len() / 2];
+
+        let mut output = Vec::new();
+        let result = compressor
+            .decompress_stream(&mut &truncated[..], &mut output)
+            .await;
+        assert!(result.unwrap_err().is_decompression_failed());
+    }
+
+    #[test]
+    fn test_brotli_output_limit() {
+        let compressed = BrotliCompressor::default_level()
+            .compress(&vec![0u8; 1024 * 1024])
+            .unwrap();
+
+        let limited = BrotliCompressor::default_level().with_max_output_size(1024 * 1024);
+        assert_eq!(limited.decompress(&compressed).unwrap().len(), 1024 * 1024);
+
+        let limited = BrotliCompressor::default_level().with_max_output_size(64 * 1024);
+        let err = limited.decompress(&compressed).unwrap_err();
+        assert!(err.is_output_too_large());
+    }
+
+    #[tokio::test]
+    async fn test_brotli_stream_output_limit() {
+        let compressed = BrotliCompressor::default_level()
+            .compress(&vec![0u8; 4 * 1024 * 1024])
+            .unwrap();
+        let limited = BrotliCompressor::default_level().with_max_output_size(64 * 1024);
+
+        let mut output = Vec::new();
+        let result = limited
+            .decompress_stream(&mut &compressed[..], &mut output)
+            .await;
+        assert!(result.unwrap_err().is_output_too_large());
+        assert!(output.len() <= 64 * 1024);
+    }
 }
@@ -25,6 +25,19 @@
 //! varint(result_size)
 //! [zstd-compressed target using base as raw dictionary]
 //! ```
+//!
+//! [`SmartCompressor::compress_delta`](crate::SmartCompressor::compress_delta)
+//! compresses a delta and wraps it in a frame naming its base, so a reader
+//! can fetch the base before applying it:
+//!
+//! ```text
+//! "MGD\x01" magic
+//! u8 base id length
+//! base id (up to 255 bytes, e.g. the base's object id)
+//! [delta in the format above, compressed like any other payload]
+//! ```
+
+use crate::error::{CompressionError, CompressionResult};
 
 /// Magic bytes identifying zstd-dict delta format
 const ZSTD_DICT_MAGIC: [u8; 2] = [0x5A, 0x44]; // "ZD"
@@ -58,9 +71,11 @@ impl Delta {
     }
 
     /// Deserialize delta from bytes
-    pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+    pub fn from_bytes(data: &[u8]) -> CompressionResult<Self> {
         if data.len() < 2 || data[0] != ZSTD_DICT_MAGIC[0] || data[1] != ZSTD_DICT_MAGIC[1] {
-            anyhow::bail!("Invalid delta format: missing ZD magic bytes");
+            return Err(CompressionError::invalid_input(
+                "Invalid delta format: missing ZD magic bytes",
+            ));
         }
 
         let mut pos = 2; // skip magic
@@ -114,7 +129,7 @@ impl DeltaEncoder {
     }
 
     /// Compress target using base as zstd dictionary
-    fn compress_with_dict(base: &[u8], target: &[u8]) -> anyhow::Result<Vec<u8>> {
+    fn compress_with_dict(base: &[u8], target: &[u8]) -> std::io::Result<Vec<u8>> {
         let mut encoder = zstd::bulk::Compressor::with_dictionary(ZSTD_DICT_LEVEL, base)?;
         let compressed = encoder.compress(target)?;
         Ok(compressed)
@@ -126,14 +141,13 @@ pub struct DeltaDecoder;
 
 impl DeltaDecoder {
     /// Apply delta to base object to reconstruct target.
-    pub fn apply(base: &[u8], delta: &Delta) -> anyhow::Result<Vec<u8>> {
+    pub fn apply(base: &[u8], delta: &Delta) -> CompressionResult<Vec<u8>> {
         const MAX_DELTA_RESULT_SIZE: usize = 16 * 1024 * 1024 * 1024; // 16 GB
         if delta.result_size > MAX_DELTA_RESULT_SIZE {
-            anyhow::bail!(
+            return Err(CompressionError::decompression_failed(format!(
                 "Delta result_size {} exceeds maximum {} bytes",
-                delta.result_size,
-                MAX_DELTA_RESULT_SIZE
-            );
+                delta.result_size, MAX_DELTA_RESULT_SIZE
+            )));
         }
 
         if delta.zstd_data.is_empty() {
@@ -143,20 +157,63 @@ impl DeltaDecoder {
         let mut decoder = zstd::bulk::Decompressor::with_dictionary(base)?;
         let result = decoder
             .decompress(&delta.zstd_data, delta.result_size)
-            .map_err(|e| anyhow::anyhow!("Failed to decompress zstd-dict delta: {}", e))?;
+            .map_err(|e| {
+                CompressionError::decompression_failed(format!(
+                    "Failed to decompress zstd-dict delta: {}",
+                    e
+                ))
+            })?;
 
         if result.len() != delta.result_size {
-            anyhow::bail!(
+            return Err(CompressionError::decompression_failed(format!(
                 "Delta reconstruction size mismatch: {} != {}",
                 result.len(),
                 delta.result_size
-            );
+            )));
         }
 
         Ok(result)
     }
 }
 
+/// Magic bytes of a framed delta
+pub const DELTA_FRAME_MAGIC: [u8; 4] = *b"MGD\x01";
+
+/// Identifier of the base a framed delta was encoded against, or `None` if
+/// `data` is not a framed delta
+pub fn base_id_of(data: &[u8]) -> Option<&[u8]> {
+    let rest = data.strip_prefix(&DELTA_FRAME_MAGIC)?;
+    let (&len, rest) = rest.split_first()?;
+    rest.get(..len as usize)
+}
+
+/// Frame a compressed delta `payload` with the identifier of its base
+pub(crate) fn frame(base_id: &[u8], payload: &[u8]) -> CompressionResult<Vec<u8>> {
+    let id_len = u8::try_from(base_id.len()).map_err(|_| {
+        CompressionError::invalid_input(format!(
+            "delta base id is {} bytes, at most 255 are allowed",
+            base_id.len()
+        ))
+    })?;
+    let mut framed =
+        Vec::with_capacity(DELTA_FRAME_MAGIC.len() + 1 + base_id.len() + payload.len());
+    framed.extend_from_slice(&DELTA_FRAME_MAGIC);
+    framed.push(id_len);
+    framed.extend_from_slice(base_id);
+    framed.extend_from_slice(payload);
+    Ok(framed)
+}
+
+/// Split a framed delta into its base id and compressed delta payload
+pub(crate) fn unframe(data: &[u8]) -> CompressionResult<(&[u8], &[u8])> {
+    let base_id =
+        base_id_of(data).ok_or_else(|| CompressionError::invalid_input("Invalid delta frame"))?;
+    Ok((
+        base_id,
+        &data[DELTA_FRAME_MAGIC.len() + 1 + base_id.len()..],
+    ))
+}
+
 /// Helper function to encode variable-length integer
 fn encode_varint(bytes: &mut Vec<u8>, mut value: u32) {
     loop {
@@ -176,13 +233,13 @@ fn encode_varint(bytes: &mut Vec<u8>, mut value: u32) {
 }
 
 /// Helper function to decode variable-length integer
-fn decode_varint(data: &[u8], pos: &mut usize) -> anyhow::Result<u32> {
+fn decode_varint(data: &[u8], pos: &mut usize) -> CompressionResult<u32> {
     let mut result: u32 = 0;
     let mut shift = 0;
 
     loop {
         if *pos >= data.len() {
-            anyhow::bail!("Varint decode overflow");
+            return Err(CompressionError::invalid_input("Varint decode overflow"));
         }
 
         let byte = data[*pos] as u32;
@@ -196,7 +253,7 @@ fn decode_varint(data: &[u8], pos: &mut usize) -> anyhow::Result<u32> {
         }
 
         if shift >= 32 {
-            anyhow::bail!("Varint too large");
+            return Err(CompressionError::invalid_input("Varint too large"));
         }
     }
 
@@ -204,6 +261,7 @@ fn decode_varint(data: &[u8], pos: &mut usize) -> anyhow::Result<u32> {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
@@ -273,6 +331,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_frame_roundtrip() {
+        let base = b"original data here for testing purposes";
+        let target = b"modified data here for testing purposes today";
+        let payload = DeltaEncoder::encode(base, target).to_bytes();
+        let framed = frame(b"base-1", &payload).unwrap();
+
+        assert_eq!(base_id_of(&framed), Some(&b"base-1"[..]));
+        let (base_id, unframed) = unframe(&framed).unwrap();
+        assert_eq!(base_id, b"base-1");
+        let delta = Delta::from_bytes(unframed).unwrap();
+        assert_eq!(DeltaDecoder::apply(base, &delta).unwrap(), target);
+
+        assert_eq!(base_id_of(target), None);
+        assert_eq!(base_id_of(&framed[..6]), None);
+        assert!(frame(&[0u8; 256], &payload).is_err());
+    }
+
     #[test]
     fn test_varint_encode_decode() {
         let mut bytes = Vec::new();
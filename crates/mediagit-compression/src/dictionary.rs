@@ -0,0 +1,226 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Trained zstd dictionaries for small structured objects
+//!
+//! Small files such as JSON sidecars or scene metadata compress poorly on
+//! their own: each one is too short for zstd to learn its repetitions. A
+//! dictionary trained on a sample of similar files supplies that shared
+//! context up front, often shrinking them several times further.
+//!
+//! Dictionary-compressed data is framed as:
+//!
+//! ```text
+//! "DCT\x01" | dictionary id (u32, little-endian) | zstd frame
+//! ```
+//!
+//! so decompression can pick the right dictionary even after newer ones have
+//! been trained.
+//!
+//! # Examples
+//!
+//! ```
+//! use mediagit_compression::CompressionDictionary;
+//!
+//! let samples: Vec<Vec<u8>> = (0..200)
+//!     .map(|i| format!(r#"{{"shot": {}, "camera": "A", "lens": "50mm"}}"#, i).into_bytes())
+//!     .collect();
+//! let dict = CompressionDictionary::train(&samples, 4096).unwrap();
+//!
+//! let compressed = dict.compress(&samples[7]).unwrap();
+//! assert_eq!(CompressionDictionary::id_of(&compressed), Some(dict.id()));
+//! assert_eq!(dict.decompress(&compressed).unwrap(), samples[7]);
+//! ```
+
+use crate::error::{CompressionError, CompressionResult};
+use crate::limit::{self, DEFAULT_MAX_DECOMPRESSED_SIZE};
+use crate::CompressionLevel;
+use std::fmt;
+use std::sync::Arc;
+
+/// Marker at the start of dictionary-compressed data
+pub const DICTIONARY_MAGIC: &[u8; 4] = b"DCT\x01";
+
+/// Length of the header preceding the zstd frame
+const HEADER_LEN: usize = DICTIONARY_MAGIC.len() + 4;
+
+/// A trained zstd dictionary, identified by the id zstd assigns when training
+#[derive(Clone, PartialEq, Eq)]
+pub struct CompressionDictionary {
+    id: u32,
+    data: Arc<Vec<u8>>,
+}
+
+impl CompressionDictionary {
+    /// Train a dictionary of at most `max_size` bytes from `samples`
+    ///
+    /// # Errors
+    ///
+    /// Fails if zstd cannot build a dictionary from the samples, typically
+    /// because there are too few of them or they share too little.
+    pub fn train<S: AsRef<[u8]>>(samples: &[S], max_size: usize) -> CompressionResult<Self> {
+        let data = zstd::dict::from_samples(samples, max_size).map_err(|e| {
+            CompressionError::compression_failed(format!("dictionary training failed: {}", e))
+        })?;
+        Self::from_bytes(data)
+    }
+
+    /// Load a dictionary previously produced by [`train`](Self::train)
+    ///
+    /// # Errors
+    ///
+    /// Fails if `data` is not a zstd dictionary with an id.
+    pub fn from_bytes(data: Vec<u8>) -> CompressionResult<Self> {
+        let id = zstd::zstd_safe::get_dict_id_from_dict(&data)
+            .ok_or_else(|| CompressionError::invalid_input("not a zstd dictionary"))?
+            .get();
+        Ok(Self {
+            id,
+            data: Arc::new(data),
+        })
+    }
+
+    /// Dictionary id recorded in the header of everything it compresses
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Raw dictionary bytes, as stored
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Dictionary id in the header of `data`, if it is dictionary-compressed
+    pub fn id_of(data: &[u8]) -> Option<u32> {
+        if data.len() < HEADER_LEN || !data.starts_with(DICTIONARY_MAGIC) {
+            return None;
+        }
+        let id = data[DICTIONARY_MAGIC.len()..HEADER_LEN].try_into().ok()?;
+        Some(u32::from_le_bytes(id))
+    }
+
+    /// Compress `data` with this dictionary at the default level
+    pub fn compress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
+        self.compress_with_level(data, CompressionLevel::Default)
+    }
+
+    /// Compress `data` with this dictionary at `level`
+    pub fn compress_with_level(
+        &self,
+        data: &[u8],
+        level: CompressionLevel,
+    ) -> CompressionResult<Vec<u8>> {
+        let mut compressor =
+            zstd::bulk::Compressor::with_dictionary(level.to_zstd_level(), &self.data)
+                .map_err(|e| CompressionError::zstd_error(e.to_string()))?;
+        let frame = compressor
+            .compress(data)
+            .map_err(|e| CompressionError::zstd_error(e.to_string()))?;
+
+        let mut result = Vec::with_capacity(HEADER_LEN + frame.len());
+        result.extend_from_slice(DICTIONARY_MAGIC);
+        result.extend_from_slice(&self.id.to_le_bytes());
+        result.extend_from_slice(&frame);
+        Ok(result)
+    }
+
+    /// Decompress data produced by [`compress`](Self::compress)
+    ///
+    /// # Errors
+    ///
+    /// Fails if `data` was compressed with a different dictionary or is corrupt.
+    pub fn decompress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
+        self.decompress_with_limit(data, DEFAULT_MAX_DECOMPRESSED_SIZE)
+    }
+
+    /// Decompress like [`decompress`](Self::decompress), failing with
+    /// [`OutputTooLarge`](CompressionError::OutputTooLarge) once output passes
+    /// `max_output_size` bytes
+    pub fn decompress_with_limit(
+        &self,
+        data: &[u8],
+        max_output_size: u64,
+    ) -> CompressionResult<Vec<u8>> {
+        match Self::id_of(data) {
+            Some(id) if id == self.id => {}
+            Some(id) => {
+                return Err(CompressionError::decompression_failed(format!(
+                    "data needs dictionary {}, not {}",
+                    id, self.id
+                )))
+            }
+            None => {
+                return Err(CompressionError::invalid_input(
+                    "data is not dictionary-compressed",
+                ))
+            }
+        }
+
+        let decoder = zstd::stream::read::Decoder::with_dictionary(&data[HEADER_LEN..], &self.data)
+            .map_err(|e| CompressionError::zstd_error(e.to_string()))?;
+        limit::read_limited(decoder, max_output_size, &format!("dictionary {}", self.id))
+    }
+}
+
+impl fmt::Debug for CompressionDictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompressionDictionary")
+            .field("id", &self.id)
+            .field("size", &self.data.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn sidecars(seed: usize) -> Vec<Vec<u8>> {
+        (0..300)
+            .map(|i| {
+                format!(
+                    r#"{{"asset": "shot_{:04}.exr", "colorspace": "ACEScg", "frame": {}, "artist": "render-farm-{}", "status": "approved"}}"#,
+                    i + seed,
+                    i * 7,
+                    i % 5
+                )
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dictionary_roundtrip() {
+        let samples = sidecars(0);
+        let dict = CompressionDictionary::train(&samples, 8192).unwrap();
+        let restored = CompressionDictionary::from_bytes(dict.as_bytes().to_vec()).unwrap();
+        assert_eq!(restored, dict);
+
+        let compressed = dict.compress(&samples[42]).unwrap();
+        assert!(compressed.starts_with(DICTIONARY_MAGIC));
+        assert_eq!(CompressionDictionary::id_of(&compressed), Some(dict.id()));
+        assert_eq!(restored.decompress(&compressed).unwrap(), samples[42]);
+    }
+
+    #[test]
+    fn test_wrong_dictionary_is_rejected() {
+        let first = CompressionDictionary::train(&sidecars(0), 8192).unwrap();
+        let second = CompressionDictionary::train(&sidecars(1000), 4096).unwrap();
+        assert_ne!(first.id(), second.id());
+
+        let compressed = first.compress(&sidecars(0)[3]).unwrap();
+        assert!(second.decompress(&compressed).is_err());
+        assert!(CompressionDictionary::from_bytes(b"not a dictionary".to_vec()).is_err());
+    }
+}
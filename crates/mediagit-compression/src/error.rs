@@ -37,6 +37,13 @@ pub enum CompressionError {
     #[error("unsupported algorithm: {0}")]
     UnsupportedAlgorithm(String),
 
+    /// Decompressed output would exceed the configured maximum size
+    #[error("decompressed output exceeds the {limit} byte limit")]
+    OutputTooLarge {
+        /// Maximum decompressed size in bytes
+        limit: u64,
+    },
+
     /// I/O error occurred
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
@@ -75,6 +82,11 @@ impl CompressionError {
         CompressionError::UnsupportedAlgorithm(algorithm.into())
     }
 
+    /// Create an output too large error for a maximum of `limit` bytes
+    pub fn output_too_large(limit: u64) -> Self {
+        CompressionError::OutputTooLarge { limit }
+    }
+
     /// Create a zstd-specific error
     pub fn zstd_error<S: Into<String>>(msg: S) -> Self {
         CompressionError::ZstdError(msg.into())
@@ -95,6 +107,11 @@ impl CompressionError {
         matches!(self, CompressionError::DecompressionFailed(_))
     }
 
+    /// Check if this is an output too large error
+    pub fn is_output_too_large(&self) -> bool {
+        matches!(self, CompressionError::OutputTooLarge { .. })
+    }
+
     /// Check if this is an I/O error
     pub fn is_io(&self) -> bool {
         matches!(self, CompressionError::Io(_))
@@ -131,6 +148,17 @@ mod tests {
         assert_eq!(err.to_string(), "unsupported algorithm: gzip");
     }
 
+    #[test]
+    fn test_output_too_large_error() {
+        let err = CompressionError::output_too_large(1024);
+        assert!(err.is_output_too_large());
+        assert!(!err.is_decompression_failed());
+        assert_eq!(
+            err.to_string(),
+            "decompressed output exceeds the 1024 byte limit"
+        );
+    }
+
     #[test]
     fn test_io_error_conversion() {
         let io_err = std::io::Error::other("read failed");
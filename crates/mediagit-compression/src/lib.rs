@@ -79,6 +79,28 @@
 //! - Good for archival and infrequently accessed data
 //! - Higher memory usage during compression
 //!
+//! ## LZ4
+//! - Fastest compression and decompression, modest ratios
+//! - Standard LZ4 frames, identified by the frame magic
+//! - Used for ML checkpoints and local cache entries, where read speed
+//!   matters more than size
+//!
+//! ## XZ (LZMA2)
+//! - Highest compression ratios, much slower compression
+//! - Standard `.xz` streams, identified by the XZ magic
+//! - Used by the archival tier for repositories where storage cost dominates
+//!
+//! ## Trained dictionaries
+//! - Zstd with a dictionary trained on similar small files
+//! - Large gains for small structured files (JSON sidecars, metadata)
+//! - Compressed data names its dictionary, so older dictionaries stay usable
+//!
+//! # Streaming
+//!
+//! `Compressor::compress_stream` and `Compressor::decompress_stream` move data
+//! between an `AsyncRead` and an `AsyncWrite`. Zstd and Brotli stream in
+//! bounded memory; other compressors buffer the input.
+//!
 //! # Per-Object Type Strategies
 //!
 //! `SmartCompressor` automatically selects optimal compression:
@@ -91,27 +113,44 @@
 
 pub mod adaptive;
 pub mod brotli_compressor;
+pub mod delta;
+pub mod dictionary;
 pub mod error;
+pub mod limit;
+pub mod lz4_compressor;
 pub mod metrics;
 pub mod per_type_compressor;
 pub mod smart_compressor;
+pub mod sniff;
+pub mod stream;
+pub mod xz_compressor;
 pub mod zlib_compressor;
 pub mod zstd_compressor;
 
+use async_trait::async_trait;
 use std::fmt::Debug;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 pub use adaptive::{
-    calculate_entropy, AdaptiveCompressor, CompressionStrategy as AdaptiveStrategy, EntropyClass,
-    FileProfile, PatternClass, PerformanceStats, SizeClass,
+    calculate_entropy, looks_incompressible, AdaptiveCompressor,
+    CompressionStrategy as AdaptiveStrategy, EntropyClass, FileProfile, PatternClass,
+    PerformanceStats, SizeClass, PROBE_WINDOWS, PROBE_WINDOW_SIZE,
 };
-pub use brotli_compressor::BrotliCompressor;
+pub use brotli_compressor::{BrotliCompressor, BrotliSettings};
+pub use delta::{Delta, DeltaDecoder, DeltaEncoder};
+pub use dictionary::CompressionDictionary;
 pub use error::{CompressionError, CompressionResult};
+pub use limit::DEFAULT_MAX_DECOMPRESSED_SIZE;
+pub use lz4_compressor::Lz4Compressor;
 pub use metrics::{AggregatedStats, CompressionMetrics, MetricsAggregator};
 pub use per_type_compressor::{CompressionProfile, PerObjectTypeCompressor, PerTypeStats};
 pub use smart_compressor::{
-    ChunkCodecHint, CompressionStrategy, ObjectCategory, ObjectType, SmartCompressor,
-    TypeAwareCompressor,
+    ChunkCodecHint, CompressionStrategy, CompressionTier, DeltaBase, ObjectCategory, ObjectType,
+    SmartCompressor, TypeAwareCompressor, DEFAULT_MIN_COMPRESS_SIZE, MAX_DICTIONARY_OBJECT_SIZE,
 };
+pub use sniff::{sniff, Confidence, Sniffed};
+pub use stream::{StreamReader, StreamWriter, STREAM_BUFFER_SIZE};
+pub use xz_compressor::XzCompressor;
 pub use zlib_compressor::ZlibCompressor;
 pub use zstd_compressor::ZstdCompressor;
 
@@ -120,11 +159,11 @@ pub use zstd_compressor::ZstdCompressor;
 /// Balances compression speed vs compression ratio.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompressionLevel {
-    /// Fast compression, larger output (level 1 for zstd, 4 for brotli)
+    /// Fast compression, larger output (level 1 for zstd, 4 for brotli, 1 for xz)
     Fast,
-    /// Default balance (level 3 for zstd, 9 for brotli)
+    /// Default balance (level 3 for zstd, 9 for brotli, 6 for xz)
     Default,
-    /// Best compression, slower (level 22 for zstd, 11 for brotli)
+    /// Best compression, slower (level 22 for zstd, 11 for brotli, 9 for xz)
     Best,
 }
 
@@ -146,10 +185,21 @@ impl CompressionLevel {
             CompressionLevel::Best => 11,
         }
     }
+
+    /// Convert to xz preset (0-9)
+    pub fn to_xz_level(self) -> u32 {
+        match self {
+            CompressionLevel::Fast => 1,
+            CompressionLevel::Default => 6,
+            CompressionLevel::Best => 9,
+        }
+    }
 }
 
 /// Compression algorithm identifier
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
 #[repr(u8)]
 pub enum CompressionAlgorithm {
     /// No compression (raw data)
@@ -160,6 +210,10 @@ pub enum CompressionAlgorithm {
     Zstd = 2,
     /// Brotli compression
     Brotli = 3,
+    /// LZ4 compression (frame format)
+    Lz4 = 4,
+    /// XZ compression (LZMA2)
+    Xz = 5,
 }
 
 impl CompressionAlgorithm {
@@ -170,6 +224,8 @@ impl CompressionAlgorithm {
             CompressionAlgorithm::Zlib => b"\x78", // Zlib header
             CompressionAlgorithm::Zstd => b"\x28\xb5\x2f\xfd", // Zstd frame magic
             CompressionAlgorithm::Brotli => b"BRT\x01", // Custom marker for brotli
+            CompressionAlgorithm::Lz4 => lz4_compressor::LZ4_MAGIC, // LZ4 frame magic
+            CompressionAlgorithm::Xz => xz_compressor::XZ_MAGIC, // XZ stream magic
         }
     }
 
@@ -189,6 +245,15 @@ impl CompressionAlgorithm {
             if data.starts_with(b"BRT\x01") {
                 return CompressionAlgorithm::Brotli;
             }
+            // LZ4 frame magic: 0x184D2204 (little-endian)
+            if data.starts_with(lz4_compressor::LZ4_MAGIC) {
+                return CompressionAlgorithm::Lz4;
+            }
+        }
+
+        // XZ stream magic: 0xFD "7zXZ" 0x00
+        if data.starts_with(xz_compressor::XZ_MAGIC) {
+            return CompressionAlgorithm::Xz;
         }
 
         // Check zlib (Git compatibility) - requires proper header validation
@@ -211,7 +276,10 @@ impl CompressionAlgorithm {
 /// Compressor trait for pluggable compression implementations
 ///
 /// Implementations must support transparent compression and decompression
-/// with configurable levels.
+/// with configurable levels. Large objects can be processed without
+/// buffering them through [`compress_stream`](Compressor::compress_stream)
+/// and [`decompress_stream`](Compressor::decompress_stream).
+#[async_trait]
 pub trait Compressor: Send + Sync + Debug {
     /// Compress data
     ///
@@ -244,6 +312,57 @@ pub trait Compressor: Send + Sync + Debug {
     ///
     /// Returns `CompressionError` if decompression fails
     fn decompress(&self, data: &[u8]) -> CompressionResult<Vec<u8>>;
+
+    /// Compress everything read from `reader` into `writer`
+    ///
+    /// The default implementation buffers the whole input and calls
+    /// [`compress`](Self::compress); streaming implementations keep memory
+    /// bounded regardless of input size.
+    ///
+    /// # Returns
+    ///
+    /// Number of bytes written
+    ///
+    /// # Errors
+    ///
+    /// Returns `CompressionError` if reading, compressing, or writing fails
+    async fn compress_stream(
+        &self,
+        reader: &mut StreamReader<'_>,
+        writer: &mut StreamWriter<'_>,
+    ) -> CompressionResult<u64> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+        let compressed = self.compress(&data)?;
+        writer.write_all(&compressed).await?;
+        writer.flush().await?;
+        Ok(compressed.len() as u64)
+    }
+
+    /// Decompress everything read from `reader` into `writer`
+    ///
+    /// The default implementation buffers the whole input and calls
+    /// [`decompress`](Self::decompress).
+    ///
+    /// # Returns
+    ///
+    /// Number of bytes written
+    ///
+    /// # Errors
+    ///
+    /// Returns `CompressionError` if reading, decompressing, or writing fails
+    async fn decompress_stream(
+        &self,
+        reader: &mut StreamReader<'_>,
+        writer: &mut StreamWriter<'_>,
+    ) -> CompressionResult<u64> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+        let decompressed = self.decompress(&data)?;
+        writer.write_all(&decompressed).await?;
+        writer.flush().await?;
+        Ok(decompressed.len() as u64)
+    }
 }
 
 #[cfg(test)]
@@ -259,6 +378,10 @@ mod tests {
         assert_eq!(CompressionLevel::Fast.to_brotli_level(), 4);
         assert_eq!(CompressionLevel::Default.to_brotli_level(), 9);
         assert_eq!(CompressionLevel::Best.to_brotli_level(), 11);
+
+        assert_eq!(CompressionLevel::Fast.to_xz_level(), 1);
+        assert_eq!(CompressionLevel::Default.to_xz_level(), 6);
+        assert_eq!(CompressionLevel::Best.to_xz_level(), 9);
     }
 
     #[test]
@@ -284,6 +407,20 @@ mod tests {
             CompressionAlgorithm::Brotli
         );
 
+        // LZ4 frame magic
+        let lz4_data = b"\x04\x22\x4d\x18\x64\x40\xa7";
+        assert_eq!(
+            CompressionAlgorithm::detect(lz4_data),
+            CompressionAlgorithm::Lz4
+        );
+
+        // XZ stream magic
+        let xz_data = b"\xfd7zXZ\x00\x00\x04";
+        assert_eq!(
+            CompressionAlgorithm::detect(xz_data),
+            CompressionAlgorithm::Xz
+        );
+
         // Uncompressed
         let raw_data = b"Hello, World!";
         assert_eq!(
@@ -0,0 +1,130 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Bounds on decompressed output
+//!
+//! A few kilobytes of crafted compressed data can expand to gigabytes. Every
+//! decompressor stops once its output passes a maximum size and fails with
+//! [`CompressionError::OutputTooLarge`], having allocated no more than that
+//! maximum, so untrusted data such as pushed objects cannot exhaust memory.
+
+use crate::error::{CompressionError, CompressionResult};
+use std::io::{self, Read, Write};
+
+/// Largest output a decompressor produces unless configured otherwise (16 GB)
+///
+/// This is the largest object the object database stores, so reading a
+/// repository's own objects never fails on the limit. Readers of untrusted
+/// data, such as a server checking a push, set a lower one.
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: u64 = 16 * 1024 * 1024 * 1024;
+
+/// Read everything `decoder` produces, failing once it passes `limit` bytes
+///
+/// `algorithm` names the format in decoding errors.
+pub(crate) fn read_limited<R: Read>(
+    decoder: R,
+    limit: u64,
+    algorithm: &str,
+) -> CompressionResult<Vec<u8>> {
+    let mut output = Vec::new();
+    decoder
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut output)
+        .map_err(|e| {
+            CompressionError::decompression_failed(format!(
+                "{} decompression failed: {}",
+                algorithm, e
+            ))
+        })?;
+    if output.len() as u64 > limit {
+        return Err(CompressionError::output_too_large(limit));
+    }
+    Ok(output)
+}
+
+/// Output buffer for streaming decoders that refuses to grow past a limit
+///
+/// The limit counts every byte written, including bytes already taken out
+/// of [`data`](Self::data). Writes past it fail with an I/O error wrapping
+/// [`CompressionError::OutputTooLarge`].
+pub(crate) struct LimitedBuffer {
+    pub(crate) data: Vec<u8>,
+    written: u64,
+    limit: u64,
+}
+
+impl LimitedBuffer {
+    pub(crate) fn new(limit: u64) -> Self {
+        Self {
+            data: Vec::new(),
+            written: 0,
+            limit,
+        }
+    }
+}
+
+impl Write for LimitedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.written.saturating_add(buf.len() as u64);
+        if written > self.limit {
+            return Err(io::Error::other(CompressionError::output_too_large(
+                self.limit,
+            )));
+        }
+        self.written = written;
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The [`CompressionError::OutputTooLarge`] a [`LimitedBuffer`] wrapped in `e`
+pub(crate) fn output_too_large(e: &io::Error) -> Option<CompressionError> {
+    match e.get_ref()?.downcast_ref::<CompressionError>()? {
+        CompressionError::OutputTooLarge { limit } => {
+            Some(CompressionError::output_too_large(*limit))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_limited() {
+        let data = vec![7u8; 100];
+        assert_eq!(read_limited(&data[..], 100, "raw").unwrap(), data);
+
+        let err = read_limited(&data[..], 99, "raw").unwrap_err();
+        assert!(err.is_output_too_large());
+    }
+
+    #[test]
+    fn test_limited_buffer() {
+        let mut buffer = LimitedBuffer::new(10);
+        buffer.write_all(b"123456").unwrap();
+        buffer.data.clear();
+        buffer.write_all(b"7890").unwrap();
+
+        // Bytes already taken still count towards the limit
+        let err = buffer.write_all(b"x").unwrap_err();
+        assert!(output_too_large(&err).unwrap().is_output_too_large());
+        assert!(output_too_large(&io::Error::other("other")).is_none());
+    }
+}
@@ -0,0 +1,155 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! LZ4 compression implementation
+//!
+//! Very fast compression and decompression with modest ratios.
+//! Ideal for large, frequently rewritten data such as ML checkpoints and
+//! local cache entries, where read speed matters more than size.
+
+use crate::error::{CompressionError, CompressionResult};
+use crate::limit::{self, DEFAULT_MAX_DECOMPRESSED_SIZE};
+use crate::Compressor;
+use lz4_flex::frame::{FrameDecoder, FrameEncoder};
+use std::fmt;
+use std::io::Write;
+
+/// LZ4 frame magic number (0x184D2204, little-endian)
+pub const LZ4_MAGIC: &[u8; 4] = b"\x04\x22\x4d\x18";
+
+/// LZ4 compressor implementation
+///
+/// Writes standard LZ4 frames, so compressed data starts with the LZ4 frame
+/// magic and can be read by any LZ4 tool. LZ4 has a single speed setting,
+/// so unlike the other compressors it takes no level.
+#[derive(Clone)]
+pub struct Lz4Compressor {
+    max_output_size: u64,
+}
+
+impl Lz4Compressor {
+    /// Create a new LZ4 compressor
+    pub fn new() -> Self {
+        Lz4Compressor {
+            max_output_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+        }
+    }
+
+    /// Fail decompression with
+    /// [`OutputTooLarge`](CompressionError::OutputTooLarge) once output
+    /// passes `max_output_size` bytes
+    ///
+    /// Defaults to [`DEFAULT_MAX_DECOMPRESSED_SIZE`].
+    pub fn with_max_output_size(mut self, max_output_size: u64) -> Self {
+        self.max_output_size = max_output_size;
+        self
+    }
+}
+
+impl Default for Lz4Compressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Lz4Compressor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lz4Compressor")
+            .field("max_output_size", &self.max_output_size)
+            .finish()
+    }
+}
+
+impl Compressor for Lz4Compressor {
+    fn compress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut encoder = FrameEncoder::new(Vec::with_capacity(data.len() / 2));
+        encoder.write_all(data).map_err(|e| {
+            CompressionError::compression_failed(format!("lz4 compression failed: {}", e))
+        })?;
+        encoder.finish().map_err(|e| {
+            CompressionError::compression_failed(format!("lz4 compression failed: {}", e))
+        })
+    }
+
+    fn decompress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if data.starts_with(LZ4_MAGIC) {
+            limit::read_limited(FrameDecoder::new(data), self.max_output_size, "lz4")
+        } else {
+            // Data is not lz4 compressed, return as-is
+            Ok(data.to_vec())
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lz4_compress_decompress() {
+        let compressor = Lz4Compressor::new();
+        let original = b"Hello, World! This is a test of lz4 compression.".repeat(20);
+
+        let compressed = compressor.compress(&original).unwrap();
+        assert!(compressed.starts_with(LZ4_MAGIC));
+        assert!(compressed.len() < original.len());
+
+        let decompressed = compressor.decompress(&compressed).unwrap();
+        assert_eq!(decompressed, original);
+    }
+
+    #[test]
+    fn test_lz4_empty() {
+        let compressor = Lz4Compressor::new();
+        assert!(compressor.compress(b"").unwrap().is_empty());
+        assert!(compressor.decompress(b"").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lz4_decompress_uncompressed_data() {
+        let compressor = Lz4Compressor::new();
+        let data = b"This is not compressed";
+        assert_eq!(compressor.decompress(data).unwrap(), data);
+    }
+
+    #[test]
+    fn test_lz4_large_data() {
+        let compressor = Lz4Compressor::new();
+        let original = (0..4 * 1024 * 1024u32)
+            .map(|i| (i / 1024) as u8)
+            .collect::<Vec<_>>();
+
+        let compressed = compressor.compress(&original).unwrap();
+        assert!(compressed.len() < original.len() / 10);
+        assert_eq!(compressor.decompress(&compressed).unwrap(), original);
+    }
+
+    #[test]
+    fn test_lz4_truncated_frame_fails() {
+        let compressor = Lz4Compressor::new();
+        let original = (0..100_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let compressed = compressor.compress(&original).unwrap();
+
+        let result = compressor.decompress(&compressed[..compressed.len() / 2]);
+        assert!(result.unwrap_err().is_decompression_failed());
+    }
+}
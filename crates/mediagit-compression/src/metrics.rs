@@ -30,6 +30,10 @@ pub enum CompressionAlgorithm {
     Zstd = 2,
     /// Brotli compression
     Brotli = 3,
+    /// LZ4 compression
+    Lz4 = 4,
+    /// XZ compression
+    Xz = 5,
 }
 
 /// Compression level configuration
@@ -23,7 +23,10 @@ use crate::metrics::{
 use crate::smart_compressor::{
     CompressionStrategy, ObjectCategory, ObjectType, TypeAwareCompressor,
 };
-use crate::{BrotliCompressor, CompressionResult, Compressor, ZlibCompressor, ZstdCompressor};
+use crate::{
+    BrotliCompressor, CompressionError, CompressionResult, Compressor, Lz4Compressor, XzCompressor,
+    ZlibCompressor, ZstdCompressor,
+};
 use crate::{CompressionAlgorithm, CompressionLevel};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -229,10 +232,11 @@ impl PerObjectTypeCompressor {
                 };
                 compressor.compress(data)
             }
-            CompressionStrategy::Delta => {
-                // Delta requires base - fall back to Zstd
-                self.zstd_default.compress(data)
-            }
+            CompressionStrategy::Lz4 => Lz4Compressor::new().compress(data),
+            CompressionStrategy::Xz(level) => XzCompressor::new(level).compress(data),
+            CompressionStrategy::Delta => Err(CompressionError::invalid_input(
+                "the delta strategy needs a base object; compress with SmartCompressor::compress_delta",
+            )),
         }
     }
 
@@ -323,13 +327,16 @@ impl TypeAwareCompressor for PerObjectTypeCompressor {
             CompressionStrategy::Zlib(_) => MetricsAlgorithm::Zlib,
             CompressionStrategy::Zstd(_) => MetricsAlgorithm::Zstd,
             CompressionStrategy::Brotli(_) => MetricsAlgorithm::Brotli,
+            CompressionStrategy::Lz4 => MetricsAlgorithm::Lz4,
+            CompressionStrategy::Xz(_) => MetricsAlgorithm::Xz,
             CompressionStrategy::Delta => MetricsAlgorithm::Zstd,
         };
         let level = match strategy {
-            CompressionStrategy::Store => MetricsLevel::Fast,
+            CompressionStrategy::Store | CompressionStrategy::Lz4 => MetricsLevel::Fast,
             CompressionStrategy::Zlib(l)
             | CompressionStrategy::Zstd(l)
-            | CompressionStrategy::Brotli(l) => match l {
+            | CompressionStrategy::Brotli(l)
+            | CompressionStrategy::Xz(l) => match l {
                 CompressionLevel::Fast => MetricsLevel::Fast,
                 CompressionLevel::Default => MetricsLevel::Default,
                 CompressionLevel::Best => MetricsLevel::Best,
@@ -358,6 +365,8 @@ impl TypeAwareCompressor for PerObjectTypeCompressor {
             CompressionAlgorithm::Zlib => self.zlib.decompress(data),
             CompressionAlgorithm::Zstd => self.zstd_default.decompress(data),
             CompressionAlgorithm::Brotli => self.brotli_default.decompress(data),
+            CompressionAlgorithm::Lz4 => Lz4Compressor::new().decompress(data),
+            CompressionAlgorithm::Xz => XzCompressor::default_level().decompress(data),
         }
     }
 
@@ -382,13 +391,16 @@ impl TypeAwareCompressor for PerObjectTypeCompressor {
             CompressionStrategy::Zlib(_) => MetricsAlgorithm::Zlib,
             CompressionStrategy::Zstd(_) => MetricsAlgorithm::Zstd,
             CompressionStrategy::Brotli(_) => MetricsAlgorithm::Brotli,
+            CompressionStrategy::Lz4 => MetricsAlgorithm::Lz4,
+            CompressionStrategy::Xz(_) => MetricsAlgorithm::Xz,
             CompressionStrategy::Delta => MetricsAlgorithm::Zstd,
         };
         let level = match strategy {
-            CompressionStrategy::Store => MetricsLevel::Fast,
+            CompressionStrategy::Store | CompressionStrategy::Lz4 => MetricsLevel::Fast,
             CompressionStrategy::Zlib(l)
             | CompressionStrategy::Zstd(l)
-            | CompressionStrategy::Brotli(l) => match l {
+            | CompressionStrategy::Brotli(l)
+            | CompressionStrategy::Xz(l) => match l {
                 CompressionLevel::Fast => MetricsLevel::Fast,
                 CompressionLevel::Default => MetricsLevel::Default,
                 CompressionLevel::Best => MetricsLevel::Best,
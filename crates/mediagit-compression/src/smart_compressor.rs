@@ -15,10 +15,19 @@
 //!
 //! Automatically selects optimal compression based on file type and content.
 
-use crate::error::CompressionResult;
-use crate::{BrotliCompressor, CompressionLevel, Compressor, ZlibCompressor, ZstdCompressor};
+use crate::delta::{self, DeltaDecoder, DeltaEncoder};
+use crate::dictionary::CompressionDictionary;
+use crate::error::{CompressionError, CompressionResult};
+use crate::limit::DEFAULT_MAX_DECOMPRESSED_SIZE;
+use crate::sniff::{sniff, Confidence};
+use crate::{
+    BrotliCompressor, BrotliSettings, CompressionLevel, Compressor, Lz4Compressor, XzCompressor,
+    ZlibCompressor, ZstdCompressor,
+};
+use std::collections::HashMap;
 use std::fmt;
 use std::path::Path;
+use std::sync::{Arc, RwLock};
 
 /// Object/File type classification for compression strategy selection
 #[allow(missing_docs)]
@@ -341,131 +350,28 @@ impl ObjectType {
     }
 
     /// Detect object type from magic bytes
+    ///
+    /// Returns the best guess whatever the match's confidence; see
+    /// [`crate::sniff`] for the signatures recognised.
     pub fn from_magic_bytes(data: &[u8]) -> Self {
-        if data.len() < 4 {
-            return ObjectType::Unknown;
-        }
-
-        // JPEG: FF D8 FF
-        if data.len() >= 3 && data[0] == 0xFF && data[1] == 0xD8 && data[2] == 0xFF {
-            return ObjectType::Jpeg;
-        }
-
-        // PNG: 89 50 4E 47
-        if data.starts_with(&[0x89, 0x50, 0x4E, 0x47]) {
-            return ObjectType::Png;
-        }
-
-        // GIF: 47 49 46 38
-        if data.starts_with(b"GIF8") {
-            return ObjectType::Gif;
-        }
-
-        // RIFF container: dispatch by subtype at bytes 8-11
-        if data.len() >= 12 && data.starts_with(b"RIFF") {
-            return match &data[8..12] {
-                b"WEBP" => ObjectType::Webp,
-                b"WAVE" => ObjectType::Wav,
-                b"AVI " => ObjectType::Avi,
-                _ => ObjectType::Unknown,
-            };
-        }
-
-        // TIFF: 49 49 2A 00 (little-endian) or 4D 4D 00 2A (big-endian)
-        if data.starts_with(&[0x49, 0x49, 0x2A, 0x00])
-            || data.starts_with(&[0x4D, 0x4D, 0x00, 0x2A])
-        {
-            return ObjectType::Tiff;
-        }
-
-        // BMP: 42 4D
-        if data.starts_with(&[0x42, 0x4D]) {
-            return ObjectType::Bmp;
-        }
-
-        // PDF: 25 50 44 46
-        if data.starts_with(b"%PDF") {
-            return ObjectType::Pdf;
-        }
-
-        // MP4: ftyp at offset 4
-        if data.len() >= 12 && &data[4..8] == b"ftyp" {
-            return ObjectType::Mp4;
-        }
-
-        // ZIP: 50 4B 03 04 or 50 4B 05 06
-        if data.starts_with(&[0x50, 0x4B, 0x03, 0x04])
-            || data.starts_with(&[0x50, 0x4B, 0x05, 0x06])
-        {
-            return ObjectType::Zip;
-        }
-
-        // GZIP: 1F 8B
-        if data.starts_with(&[0x1F, 0x8B]) {
-            return ObjectType::Gz;
-        }
-
-        // MKV/WebM: EBML header (Matroska container)
-        if data.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
-            return ObjectType::Mkv;
-        }
-
-        // FLAC: "fLaC"
-        if data.starts_with(b"fLaC") {
-            return ObjectType::Flac;
-        }
-
-        // EXR: OpenEXR magic
-        if data.starts_with(&[0x76, 0x2F, 0x31, 0x01]) {
-            return ObjectType::Exr;
-        }
-
-        // PSD/PSB: "8BPS"
-        if data.starts_with(&[0x38, 0x42, 0x50, 0x53]) {
-            return ObjectType::AdobePhotoshop;
-        }
-
-        // 7-Zip: 37 7A BC AF 27 1C
-        if data.len() >= 6 && data.starts_with(&[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C]) {
-            return ObjectType::SevenZ;
-        }
-
-        // RAR5: "Rar!\x1A\x07"
-        if data.len() >= 6 && data.starts_with(&[0x52, 0x61, 0x72, 0x21, 0x1A, 0x07]) {
-            return ObjectType::Rar;
-        }
-
-        // XZ: FD 37 7A 58 5A 00
-        if data.len() >= 6 && data.starts_with(&[0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00]) {
-            return ObjectType::Gz;
-        }
-
-        // Bzip2: "BZh"
-        if data.starts_with(&[0x42, 0x5A, 0x68]) {
-            return ObjectType::Gz;
-        }
-
-        // Zstd frame: 28 B5 2F FD
-        if data.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
-            return ObjectType::Gz;
-        }
-
-        // LZ4 frame: 04 22 4D 18
-        if data.starts_with(&[0x04, 0x22, 0x4D, 0x18]) {
-            return ObjectType::Gz;
-        }
-
-        // MP3: ID3 tag at start
-        if data.starts_with(b"ID3") {
-            return ObjectType::Mp3;
-        }
+        sniff(data)
+            .map(|sniffed| sniffed.object_type)
+            .unwrap_or(ObjectType::Unknown)
+    }
 
-        // MP3: sync word (conservative: require >32 bytes + valid sync bits)
-        if data.len() > 32 && data[0] == 0xFF && (data[1] & 0xE0) == 0xE0 {
-            return ObjectType::Mp3;
+    /// Detect object type from both file path and content
+    ///
+    /// A signature that identifies one format overrides the extension, so
+    /// mis-named files are classified by what they contain. Signatures
+    /// shared by several formats (ZIP, PDF, TIFF, ...) defer to a known
+    /// extension, which is more specific.
+    pub fn detect<P: AsRef<Path>>(path: P, data: &[u8]) -> Self {
+        let by_extension = Self::from_path(path);
+        match sniff(data) {
+            Some(sniffed) if sniffed.confidence == Confidence::Strong => sniffed.object_type,
+            Some(sniffed) if by_extension == ObjectType::Unknown => sniffed.object_type,
+            _ => by_extension,
         }
-
-        ObjectType::Unknown
     }
 
     /// Check if this type is already compressed
@@ -617,7 +523,7 @@ impl ObjectType {
 
 /// Object category for high-level classification
 #[allow(missing_docs)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum ObjectCategory {
     Image,
     Video,
@@ -653,7 +559,16 @@ pub enum CompressionStrategy {
     /// Brotli compression (best ratio, slower)
     Brotli(CompressionLevel),
 
+    /// LZ4 compression (fastest, modest ratio)
+    Lz4,
+
+    /// XZ compression (highest ratio, slowest; for archival)
+    Xz(CompressionLevel),
+
     /// Delta compression (for similar files)
+    ///
+    /// Needs a base object, so only [`SmartCompressor::compress_delta`]
+    /// produces it; compressing with it directly fails.
     Delta,
 }
 
@@ -732,8 +647,9 @@ impl CompressionStrategy {
                 CompressionStrategy::Zstd(CompressionLevel::Fast)
             }
 
-            // ML training checkpoints: Zstd fast (huge files, created frequently)
-            ObjectType::MlCheckpoint => CompressionStrategy::Zstd(CompressionLevel::Fast),
+            // ML training checkpoints: LZ4 (huge files, written and reloaded
+            // often, so decompression speed matters more than ratio)
+            ObjectType::MlCheckpoint => CompressionStrategy::Lz4,
 
             // ML inference models: Zstd default (better compression for archival)
             ObjectType::MlInference | ObjectType::MlDeployment => {
@@ -811,6 +727,95 @@ impl CompressionStrategy {
 
         base_strategy
     }
+
+    /// Name of the strategy as written in `.mediagitattributes`
+    ///
+    /// The inverse of [`parse`](Self::parse); `Delta`, which cannot be
+    /// configured, is named `delta`.
+    pub fn name(self) -> &'static str {
+        use CompressionLevel::{Best, Default, Fast};
+        match self {
+            CompressionStrategy::Store => "store",
+            CompressionStrategy::Zlib(Fast) => "zlib-fast",
+            CompressionStrategy::Zlib(Default) => "zlib",
+            CompressionStrategy::Zlib(Best) => "zlib-best",
+            CompressionStrategy::Zstd(Fast) => "zstd-fast",
+            CompressionStrategy::Zstd(Default) => "zstd",
+            CompressionStrategy::Zstd(Best) => "zstd-best",
+            CompressionStrategy::Brotli(Fast) => "brotli-fast",
+            CompressionStrategy::Brotli(Default) => "brotli",
+            CompressionStrategy::Brotli(Best) => "brotli-best",
+            CompressionStrategy::Lz4 => "lz4",
+            CompressionStrategy::Xz(Fast) => "xz-fast",
+            CompressionStrategy::Xz(Default) => "xz",
+            CompressionStrategy::Xz(Best) => "xz-best",
+            CompressionStrategy::Delta => "delta",
+        }
+    }
+
+    /// Parse a strategy name as written in `.mediagitattributes`
+    ///
+    /// Accepts `store`, `lz4`, and `zstd`, `brotli`, `zlib` or `xz` with an
+    /// optional `-fast` / `-best` suffix. Returns `None` for anything else.
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        match name.as_str() {
+            "store" => return Some(CompressionStrategy::Store),
+            "lz4" => return Some(CompressionStrategy::Lz4),
+            _ => {}
+        }
+
+        let (algorithm, level) = match name.split_once('-') {
+            Some((algorithm, "fast")) => (algorithm, CompressionLevel::Fast),
+            Some((algorithm, "default")) => (algorithm, CompressionLevel::Default),
+            Some((algorithm, "best")) => (algorithm, CompressionLevel::Best),
+            Some(_) => return None,
+            None => (name.as_str(), CompressionLevel::Default),
+        };
+        match algorithm {
+            "zstd" => Some(CompressionStrategy::Zstd(level)),
+            "brotli" => Some(CompressionStrategy::Brotli(level)),
+            "zlib" => Some(CompressionStrategy::Zlib(level)),
+            "xz" => Some(CompressionStrategy::Xz(level)),
+            _ => None,
+        }
+    }
+}
+
+/// How far a repository trades compression speed for size
+///
+/// Selected with `compression.tier` in the repository config; `mediagit gc
+/// --aggressive` repacks with [`Archival`](CompressionTier::Archival)
+/// whatever the configured tier.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionTier {
+    /// Strategies picked per object type, balancing speed and size
+    #[default]
+    Standard,
+    /// XZ Best for everything compressible, for repositories where storage
+    /// cost dominates, such as long-term project archives
+    Archival,
+}
+
+impl CompressionStrategy {
+    /// Adjust this strategy for `tier`
+    ///
+    /// The archival tier replaces every compressing strategy with XZ Best.
+    /// Store is kept, as the data is already compressed, and so is Zlib,
+    /// which Git objects use for compatibility.
+    pub fn for_tier(self, tier: CompressionTier) -> Self {
+        match (tier, self) {
+            (CompressionTier::Standard, strategy) => strategy,
+            (
+                CompressionTier::Archival,
+                strategy @ (CompressionStrategy::Store | CompressionStrategy::Zlib(_)),
+            ) => strategy,
+            (CompressionTier::Archival, _) => CompressionStrategy::Xz(CompressionLevel::Best),
+        }
+    }
 }
 
 /// Codec-level compression strategy for individual chunks inside video containers.
@@ -876,6 +881,12 @@ impl CompressionStrategy {
     }
 }
 
+/// Objects smaller than this many bytes are stored without compression by default
+///
+/// Below this size the compressor's frame header and the CPU spent outweigh
+/// any saving (pointer files, small metadata blobs, most trees).
+pub const DEFAULT_MIN_COMPRESS_SIZE: usize = 64;
+
 /// Type-aware compressor trait
 pub trait TypeAwareCompressor: Send + Sync {
     /// Compress with automatic strategy selection
@@ -903,6 +914,31 @@ pub trait TypeAwareCompressor: Send + Sync {
     ) -> CompressionStrategy;
 }
 
+/// Largest object [`SmartCompressor::compress_with_dictionary`] compresses
+/// with a dictionary; bigger objects carry enough context of their own
+pub const MAX_DICTIONARY_OBJECT_SIZE: usize = 128 * 1024;
+
+/// Strategy [`SmartCompressor::compress_delta`] uses when it stores no delta
+const NO_DELTA_STRATEGY: CompressionStrategy = CompressionStrategy::Zstd(CompressionLevel::Default);
+
+/// Base object [`SmartCompressor::compress_delta`] encodes against
+#[derive(Debug, Clone, Copy)]
+pub struct DeltaBase<'a> {
+    /// Identifier passed to the fetch callback of
+    /// [`SmartCompressor::decompress_with_base`], such as the base's object
+    /// id; at most 255 bytes
+    pub id: &'a [u8],
+    /// Uncompressed content of the base
+    pub data: &'a [u8],
+}
+
+/// Dictionaries known to a compressor, and the one new objects use
+#[derive(Default)]
+struct DictionarySet {
+    loaded: HashMap<u32, CompressionDictionary>,
+    active: Option<u32>,
+}
+
 /// Smart compressor with automatic type-based strategy selection
 #[derive(Clone)]
 pub struct SmartCompressor {
@@ -911,6 +947,14 @@ pub struct SmartCompressor {
     zstd_default: ZstdCompressor,
     zstd_best: ZstdCompressor,
     brotli_best: BrotliCompressor,
+    lz4: Lz4Compressor,
+    xz_best: XzCompressor,
+    tier: CompressionTier,
+    brotli: BrotliSettings,
+    min_compress_size: usize,
+    max_decompressed_size: u64,
+    /// Shared between clones, so dictionaries loaded later reach every copy
+    dictionaries: Arc<RwLock<DictionarySet>>,
 }
 
 impl SmartCompressor {
@@ -922,7 +966,289 @@ impl SmartCompressor {
             zstd_default: ZstdCompressor::new(CompressionLevel::Default),
             zstd_best: ZstdCompressor::new(CompressionLevel::Best),
             brotli_best: BrotliCompressor::new(CompressionLevel::Best),
+            lz4: Lz4Compressor::new(),
+            xz_best: XzCompressor::best(),
+            tier: CompressionTier::Standard,
+            brotli: BrotliSettings::default(),
+            min_compress_size: DEFAULT_MIN_COMPRESS_SIZE,
+            max_decompressed_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+            dictionaries: Arc::default(),
+        }
+    }
+
+    /// Store objects smaller than `min_compress_size` bytes without compressing them
+    ///
+    /// Such objects get the Store marker regardless of their type, so reads
+    /// need no special handling. A threshold of 0 compresses everything.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mediagit_compression::{ObjectType, SmartCompressor, TypeAwareCompressor};
+    ///
+    /// let compressor = SmartCompressor::new().with_min_compress_size(128);
+    /// let stored = compressor.compress_typed(b"version 1", ObjectType::Text).unwrap();
+    /// assert_eq!(&stored[1..], b"version 1");
+    /// ```
+    pub fn with_min_compress_size(mut self, min_compress_size: usize) -> Self {
+        self.min_compress_size = min_compress_size;
+        self
+    }
+
+    /// Size below which objects are stored without compression
+    pub fn min_compress_size(&self) -> usize {
+        self.min_compress_size
+    }
+
+    /// Adjust the strategies picked from object types and codecs for `tier`
+    ///
+    /// Explicit strategies given to
+    /// [`compress_with_strategy`](Self::compress_with_strategy) are used as
+    /// they are. See [`CompressionStrategy::for_tier`].
+    pub fn with_tier(mut self, tier: CompressionTier) -> Self {
+        self.tier = tier;
+        self
+    }
+
+    /// Tier the strategies picked from object types are adjusted for
+    pub fn tier(&self) -> CompressionTier {
+        self.tier
+    }
+
+    /// Compress Brotli strategies with `settings`
+    ///
+    /// See [`BrotliCompressor::with_settings`].
+    pub fn with_brotli(mut self, settings: BrotliSettings) -> Self {
+        self.brotli_best = self.brotli_best.with_settings(settings);
+        self.brotli = settings;
+        self
+    }
+
+    /// Settings Brotli strategies compress with
+    pub fn brotli(&self) -> BrotliSettings {
+        self.brotli
+    }
+
+    /// Fail decompression with
+    /// [`OutputTooLarge`](CompressionError::OutputTooLarge) once an object
+    /// passes `max_decompressed_size` bytes
+    ///
+    /// Protects readers of untrusted data from small objects that claim to
+    /// expand to gigabytes. Defaults to [`DEFAULT_MAX_DECOMPRESSED_SIZE`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mediagit_compression::{ObjectType, SmartCompressor, TypeAwareCompressor};
+    ///
+    /// let compressed = SmartCompressor::new()
+    ///     .compress_typed(&[0u8; 4096], ObjectType::Unknown)
+    ///     .unwrap();
+    /// let limited = SmartCompressor::new().with_max_decompressed_size(1024);
+    /// assert!(limited.decompress_typed(&compressed).unwrap_err().is_output_too_large());
+    /// ```
+    pub fn with_max_decompressed_size(mut self, max_decompressed_size: u64) -> Self {
+        self.max_decompressed_size = max_decompressed_size;
+        self.zlib = self.zlib.with_max_output_size(max_decompressed_size);
+        self.zstd_fast = self.zstd_fast.with_max_output_size(max_decompressed_size);
+        self.zstd_default = self
+            .zstd_default
+            .with_max_output_size(max_decompressed_size);
+        self.zstd_best = self.zstd_best.with_max_output_size(max_decompressed_size);
+        self.brotli_best = self.brotli_best.with_max_output_size(max_decompressed_size);
+        self.lz4 = self.lz4.with_max_output_size(max_decompressed_size);
+        self.xz_best = self.xz_best.with_max_output_size(max_decompressed_size);
+        self
+    }
+
+    /// Largest object decompression produces
+    pub fn max_decompressed_size(&self) -> u64 {
+        self.max_decompressed_size
+    }
+
+    /// Make `dictionary` available for decompression
+    ///
+    /// With `activate`, it also becomes the dictionary
+    /// [`compress_with_dictionary`](Self::compress_with_dictionary) uses.
+    /// Earlier dictionaries stay loaded so the objects they compressed remain
+    /// readable.
+    pub fn add_dictionary(&self, dictionary: CompressionDictionary, activate: bool) {
+        let mut set = self
+            .dictionaries
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if activate {
+            set.active = Some(dictionary.id());
+        }
+        set.loaded.insert(dictionary.id(), dictionary);
+    }
+
+    /// Whether the dictionary with `id` is loaded
+    pub fn has_dictionary(&self, id: u32) -> bool {
+        self.dictionaries
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .loaded
+            .contains_key(&id)
+    }
+
+    /// The dictionary new small objects are compressed with, if any
+    pub fn active_dictionary(&self) -> Option<CompressionDictionary> {
+        let set = self
+            .dictionaries
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        set.active.and_then(|id| set.loaded.get(&id).cloned())
+    }
+
+    fn dictionary(&self, id: u32) -> Option<CompressionDictionary> {
+        self.dictionaries
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .loaded
+            .get(&id)
+            .cloned()
+    }
+
+    /// Compress like [`compress_typed_with_size`], using the active dictionary
+    /// for small text objects when that comes out smaller
+    ///
+    /// Only text-category objects between the minimum compression size and
+    /// [`MAX_DICTIONARY_OBJECT_SIZE`] are considered; everything else, and
+    /// every object when no dictionary is active, is compressed as usual.
+    ///
+    /// [`compress_typed_with_size`]: TypeAwareCompressor::compress_typed_with_size
+    pub fn compress_with_dictionary(
+        &self,
+        data: &[u8],
+        obj_type: ObjectType,
+    ) -> CompressionResult<Vec<u8>> {
+        let compressed = self.compress_typed_with_size(data, obj_type)?;
+
+        let eligible = obj_type.category() == ObjectCategory::Text
+            && data.len() >= self.min_compress_size
+            && data.len() <= MAX_DICTIONARY_OBJECT_SIZE;
+        let Some(dictionary) = self.active_dictionary().filter(|_| eligible) else {
+            return Ok(compressed);
+        };
+
+        let with_dictionary = dictionary.compress(data)?;
+        if with_dictionary.len() < compressed.len() {
+            tracing::debug!(
+                dictionary = dictionary.id(),
+                original_size = data.len(),
+                compressed_size = with_dictionary.len(),
+                without_dictionary = compressed.len(),
+                "Compressed with dictionary"
+            );
+            Ok(with_dictionary)
+        } else {
+            Ok(compressed)
+        }
+    }
+
+    /// Compress `data` as a delta against `base`
+    ///
+    /// The result is a compressed delta framed with the base's id, which
+    /// [`decompress_with_base`](Self::decompress_with_base) reads. Without a
+    /// base, or when the framed delta is no smaller than `data` compressed
+    /// with Zstd, that compressed `data` is returned instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mediagit_compression::{DeltaBase, SmartCompressor};
+    ///
+    /// let compressor = SmartCompressor::new();
+    /// let v1 = "scene: hero walks left\n".repeat(100);
+    /// let v2 = v1.replace("left", "right");
+    /// let base = DeltaBase { id: b"v1", data: v1.as_bytes() };
+    ///
+    /// let delta = compressor.compress_delta(v2.as_bytes(), Some(base)).unwrap();
+    /// let restored = compressor
+    ///     .decompress_with_base(&delta, |id| {
+    ///         assert_eq!(id, b"v1");
+    ///         Ok(v1.as_bytes().to_vec())
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(restored, v2.as_bytes());
+    /// ```
+    pub fn compress_delta(
+        &self,
+        data: &[u8],
+        base: Option<DeltaBase<'_>>,
+    ) -> CompressionResult<Vec<u8>> {
+        // The delta format records sizes as 32-bit integers
+        let eligible = |base: &DeltaBase<'_>| {
+            data.len() >= self.min_compress_size
+                && u32::try_from(data.len()).is_ok()
+                && u32::try_from(base.data.len()).is_ok()
+        };
+        let Some(base) = base.filter(eligible) else {
+            return self.compress_with_strategy(data, NO_DELTA_STRATEGY);
+        };
+
+        let delta = DeltaEncoder::encode(base.data, data).to_bytes();
+        let payload = self.compress_with_strategy(&delta, NO_DELTA_STRATEGY)?;
+        let framed = delta::frame(base.id, &payload)?;
+        let full = self.compress_with_strategy(data, NO_DELTA_STRATEGY)?;
+        if framed.len() >= full.len() {
+            return Ok(full);
+        }
+        tracing::debug!(
+            original_size = data.len(),
+            base_size = base.data.len(),
+            delta_size = framed.len(),
+            compressed_size = full.len(),
+            "Compressed as delta"
+        );
+        Ok(framed)
+    }
+
+    /// Decompress like [`decompress_typed`], also reading framed deltas
+    ///
+    /// For a framed delta, `fetch_base` is called with the base's id and must
+    /// return the base's uncompressed content; it is not called for anything
+    /// else.
+    ///
+    /// [`decompress_typed`]: TypeAwareCompressor::decompress_typed
+    pub fn decompress_with_base<F>(&self, data: &[u8], fetch_base: F) -> CompressionResult<Vec<u8>>
+    where
+        F: FnOnce(&[u8]) -> CompressionResult<Vec<u8>>,
+    {
+        if delta::base_id_of(data).is_none() {
+            return self.decompress_typed(data);
+        }
+
+        let (base_id, payload) = delta::unframe(data)?;
+        let delta = delta::Delta::from_bytes(&self.decompress_typed(payload)?)?;
+        if delta.result_size as u64 > self.max_decompressed_size {
+            return Err(CompressionError::output_too_large(
+                self.max_decompressed_size,
+            ));
         }
+        let base = fetch_base(base_id)?;
+        if base.len() != delta.base_size {
+            return Err(CompressionError::decompression_failed(format!(
+                "delta base is {} bytes, the delta was encoded against {} bytes",
+                base.len(),
+                delta.base_size
+            )));
+        }
+        DeltaDecoder::apply(&base, &delta)
+    }
+
+    /// Whether `data` of unknown type should be stored without trying to
+    /// compress it, logging the decision
+    fn probe_incompressible(&self, data: &[u8]) -> bool {
+        if data.len() < self.min_compress_size || !crate::looks_incompressible(data) {
+            return false;
+        }
+        tracing::debug!(
+            size = data.len(),
+            "Sampled windows are high-entropy, storing without compression"
+        );
+        true
     }
 
     /// Compress a demuxed chunk using codec-aware strategy.
@@ -934,7 +1260,7 @@ impl SmartCompressor {
         data: &[u8],
         codec_hint: ChunkCodecHint,
     ) -> Option<CompressionResult<Vec<u8>>> {
-        let strategy = CompressionStrategy::for_codec_hint(codec_hint)?;
+        let strategy = CompressionStrategy::for_codec_hint(codec_hint)?.for_tier(self.tier);
         Some(self.compress_with_strategy(data, strategy))
     }
 
@@ -942,13 +1268,23 @@ impl SmartCompressor {
     ///
     /// If compression would EXPAND the data (common for already-compressed content
     /// like embedded JPEGs in AI/PSD files), automatically falls back to Store mode.
-    fn compress_with_strategy(
+    /// Objects below the minimum compression size are stored without trying.
+    ///
+    /// [`CompressionStrategy::Delta`] is refused, as a delta needs a base;
+    /// use [`compress_delta`](Self::compress_delta).
+    pub fn compress_with_strategy(
         &self,
         data: &[u8],
         strategy: CompressionStrategy,
     ) -> CompressionResult<Vec<u8>> {
+        if matches!(strategy, CompressionStrategy::Delta) {
+            return Err(CompressionError::invalid_input(
+                "the delta strategy needs a base object; compress with compress_delta",
+            ));
+        }
+
         // Store mode: prefix with 0x00 magic byte
-        if matches!(strategy, CompressionStrategy::Store) {
+        if matches!(strategy, CompressionStrategy::Store) || data.len() < self.min_compress_size {
             let mut result = Vec::with_capacity(data.len() + 1);
             result.push(0x00); // Store magic byte
             result.extend_from_slice(data);
@@ -956,7 +1292,7 @@ impl SmartCompressor {
         }
 
         let compressed = match strategy {
-            CompressionStrategy::Store => unreachable!(), // Handled above
+            CompressionStrategy::Store | CompressionStrategy::Delta => unreachable!(), // Handled above
 
             CompressionStrategy::Zlib(level) => {
                 let compressor = ZlibCompressor::new(level);
@@ -973,15 +1309,15 @@ impl SmartCompressor {
             }
 
             CompressionStrategy::Brotli(level) => {
-                let compressor = BrotliCompressor::new(level);
+                let compressor = BrotliCompressor::new(level).with_settings(self.brotli);
                 compressor.compress(data)?
             }
 
-            CompressionStrategy::Delta => {
-                // Delta compression requires a base - not implemented in simple compress
-                // Fall back to Zstd
-                self.zstd_default.compress(data)?
-            }
+            CompressionStrategy::Lz4 => self.lz4.compress(data)?,
+
+            CompressionStrategy::Xz(CompressionLevel::Best) => self.xz_best.compress(data)?,
+
+            CompressionStrategy::Xz(level) => XzCompressor::new(level).compress(data)?,
         };
 
         // CRITICAL FIX: If compression expanded the data (happens with already-compressed
@@ -1012,14 +1348,25 @@ impl Default for SmartCompressor {
 impl fmt::Debug for SmartCompressor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("SmartCompressor")
-            .field("strategies", &"Zlib|Zstd|Brotli|Delta")
+            .field("strategies", &"Zlib|Zstd|Brotli|Lz4|Xz|Delta")
+            .field("tier", &self.tier)
+            .field("min_compress_size", &self.min_compress_size)
+            .field("max_decompressed_size", &self.max_decompressed_size)
+            .field(
+                "active_dictionary",
+                &self.active_dictionary().map(|dictionary| dictionary.id()),
+            )
             .finish()
     }
 }
 
 impl TypeAwareCompressor for SmartCompressor {
     fn compress_typed(&self, data: &[u8], obj_type: ObjectType) -> CompressionResult<Vec<u8>> {
-        let strategy = self.strategy_for_type(obj_type);
+        let strategy = if obj_type == ObjectType::Unknown && self.probe_incompressible(data) {
+            CompressionStrategy::Store
+        } else {
+            self.strategy_for_type(obj_type).for_tier(self.tier)
+        };
         self.compress_with_strategy(data, strategy)
     }
 
@@ -1030,22 +1377,29 @@ impl TypeAwareCompressor for SmartCompressor {
     ) -> CompressionResult<Vec<u8>> {
         let strategy = if obj_type == ObjectType::Unknown {
             // For Unknown types, use entropy analysis to pick a smarter strategy.
-            // Sample at most 64KB to bound CPU cost on large files.
-            let sample = &data[..data.len().min(65_536)];
-            let entropy = crate::calculate_entropy(sample);
-            let entropy_class = crate::EntropyClass::classify(entropy);
-
-            match entropy_class {
-                crate::EntropyClass::High => CompressionStrategy::Store,
-                crate::EntropyClass::VeryLow | crate::EntropyClass::Low => {
-                    CompressionStrategy::Brotli(CompressionLevel::Best)
+            // Windows sampled across the buffer rule out random data before
+            // any CPU is spent compressing it.
+            if self.probe_incompressible(data) {
+                CompressionStrategy::Store
+            } else {
+                // Sample at most 64KB to bound CPU cost on large files.
+                let sample = &data[..data.len().min(65_536)];
+                let entropy = crate::calculate_entropy(sample);
+
+                match crate::EntropyClass::classify(entropy) {
+                    crate::EntropyClass::VeryLow | crate::EntropyClass::Low => {
+                        CompressionStrategy::Brotli(CompressionLevel::Best)
+                    }
+                    // A random start with compressible data further in
+                    crate::EntropyClass::Medium | crate::EntropyClass::High => {
+                        CompressionStrategy::Zstd(CompressionLevel::Default)
+                    }
                 }
-                crate::EntropyClass::Medium => CompressionStrategy::Zstd(CompressionLevel::Default),
             }
         } else {
             self.strategy_for_type_with_size(obj_type, data.len())
         };
-        self.compress_with_strategy(data, strategy)
+        self.compress_with_strategy(data, strategy.for_tier(self.tier))
     }
 
     fn decompress_typed(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
@@ -1064,6 +1418,27 @@ impl TypeAwareCompressor for SmartCompressor {
             }
         }
 
+        // Framed deltas name their base, which only decompress_with_base
+        // can fetch
+        if delta::base_id_of(data).is_some() {
+            return Err(CompressionError::decompression_failed(
+                "data is a delta; read it with decompress_with_base to fetch its base",
+            ));
+        }
+
+        // Dictionary-compressed objects name their dictionary; unlike the
+        // other formats there is no raw-data fallback, as smart-compressed
+        // raw data always carries the Store prefix
+        if let Some(id) = CompressionDictionary::id_of(data) {
+            let dictionary = self.dictionary(id).ok_or_else(|| {
+                CompressionError::decompression_failed(format!(
+                    "compression dictionary {} is not loaded",
+                    id
+                ))
+            })?;
+            return dictionary.decompress_with_limit(data, self.max_decompressed_size);
+        }
+
         let algo = CompressionAlgorithm::detect(data);
 
         match algo {
@@ -1071,23 +1446,27 @@ impl TypeAwareCompressor for SmartCompressor {
             CompressionAlgorithm::Zlib => {
                 // False positive possible: raw data starting with 0x78 + valid checksum byte
                 // can be misdetected as zlib. Fall back to raw data if decompression fails.
-                Ok(self.zlib.decompress(data).unwrap_or_else(|_| data.to_vec()))
+                or_raw(self.zlib.decompress(data), data)
             }
             CompressionAlgorithm::Zstd => {
                 // False positive rare but possible: raw data starting with zstd magic bytes
                 // (0x28 0xB5 0x2F 0xFD). Fall back to raw data if decompression fails.
-                Ok(self
-                    .zstd_default
-                    .decompress(data)
-                    .unwrap_or_else(|_| data.to_vec()))
+                or_raw(self.zstd_default.decompress(data), data)
             }
             CompressionAlgorithm::Brotli => {
                 // False positive rare but possible: raw data starting with "BRT\x01".
                 // Fall back to raw data if decompression fails.
-                Ok(self
-                    .brotli_best
-                    .decompress(data)
-                    .unwrap_or_else(|_| data.to_vec()))
+                or_raw(self.brotli_best.decompress(data), data)
+            }
+            CompressionAlgorithm::Lz4 => {
+                // False positive rare but possible: raw data starting with the
+                // LZ4 frame magic. Fall back to raw data if decompression fails.
+                or_raw(self.lz4.decompress(data), data)
+            }
+            CompressionAlgorithm::Xz => {
+                // False positive rare but possible: raw data starting with the
+                // XZ magic. Fall back to raw data if decompression fails.
+                or_raw(self.xz_best.decompress(data), data)
             }
         }
     }
@@ -1105,6 +1484,19 @@ impl TypeAwareCompressor for SmartCompressor {
     }
 }
 
+/// Fall back to `data` itself when decompressing it failed, as raw data can be
+/// misdetected as compressed
+///
+/// Output past the size limit is still an error: it means `data` really is
+/// compressed, with more content than the reader allows.
+fn or_raw(decompressed: CompressionResult<Vec<u8>>, data: &[u8]) -> CompressionResult<Vec<u8>> {
+    match decompressed {
+        Err(e) if e.is_output_too_large() => Err(e),
+        Err(_) => Ok(data.to_vec()),
+        ok => ok,
+    }
+}
+
 impl Compressor for SmartCompressor {
     fn compress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
         // Default to Zstd when no type information available
@@ -1334,6 +1726,52 @@ mod tests {
         assert_eq!(decompressed, medium_entropy);
     }
 
+    /// Deterministic pseudo-random bytes (xorshift)
+    fn noise(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_unknown_random_body_is_stored_without_compressing() {
+        let compressor = SmartCompressor::new();
+
+        // A low-entropy header no longer decides for the whole buffer
+        let mut data = vec![0u8; 1024];
+        data.extend(noise(1024 * 1024));
+        let compressed = compressor
+            .compress_typed_with_size(&data, ObjectType::Unknown)
+            .unwrap();
+        assert_eq!(compressed[0], 0x00);
+        assert_eq!(&compressed[1..], &data[..]);
+
+        let compressed = compressor
+            .compress_typed(&noise(256 * 1024), ObjectType::Unknown)
+            .unwrap();
+        assert_eq!(compressed[0], 0x00);
+    }
+
+    #[test]
+    fn test_unknown_random_header_still_compresses() {
+        let compressor = SmartCompressor::new();
+
+        // A random start followed by compressible data is compressed
+        let mut data = noise(8 * 1024);
+        data.extend(b"vertex 0.0 1.0 0.5\n".repeat(20_000));
+        let compressed = compressor
+            .compress_typed_with_size(&data, ObjectType::Unknown)
+            .unwrap();
+        assert!(compressed.len() < data.len() / 4);
+        assert_eq!(compressor.decompress_typed(&compressed).unwrap(), data);
+    }
+
     #[test]
     fn test_from_magic_bytes_riff_dispatcher() {
         // WebP (existing behavior preserved)
@@ -1522,6 +1960,8 @@ mod tests {
                     | CompressionStrategy::Zlib(_)
                     | CompressionStrategy::Zstd(_)
                     | CompressionStrategy::Brotli(_)
+                    | CompressionStrategy::Lz4
+                    | CompressionStrategy::Xz(_)
                     | CompressionStrategy::Delta
             ));
         }
@@ -1598,6 +2038,39 @@ mod tests {
         assert_eq!(decompressed, empty);
     }
 
+    #[test]
+    fn test_min_compress_size_stores_small_objects() {
+        let compressor = SmartCompressor::new();
+        assert_eq!(compressor.min_compress_size(), DEFAULT_MIN_COMPRESS_SIZE);
+
+        // Below the threshold: Store marker followed by the raw bytes
+        let pointer = b"oid sha256:4d7a2146 size 30";
+        let stored = compressor
+            .compress_typed(pointer, ObjectType::Text)
+            .unwrap();
+        assert_eq!(stored[0], 0x00);
+        assert_eq!(&stored[1..], pointer);
+        assert_eq!(compressor.decompress_typed(&stored).unwrap(), pointer);
+
+        // Above the threshold: still compressed
+        let text = "metadata line\n".repeat(20);
+        let compressed = compressor
+            .compress_typed(text.as_bytes(), ObjectType::Text)
+            .unwrap();
+        assert!(compressed.len() < text.len());
+        assert_eq!(
+            compressor.decompress_typed(&compressed).unwrap(),
+            text.as_bytes()
+        );
+
+        // A zero threshold compresses even tiny objects
+        let eager = SmartCompressor::new().with_min_compress_size(0);
+        let tiny = b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+        let compressed = eager.compress_typed(tiny, ObjectType::Text).unwrap();
+        assert_ne!(compressed[0], 0x00);
+        assert_eq!(eager.decompress_typed(&compressed).unwrap(), tiny);
+    }
+
     #[test]
     fn test_debug_format() {
         let compressor = SmartCompressor::new();
@@ -1819,10 +2292,10 @@ mod tests {
 
     #[test]
     fn test_ml_specialized_compression_strategy() {
-        // Training checkpoints use Fast (for speed with large files)
+        // Training checkpoints use LZ4 (for read speed with large files)
         assert_eq!(
             CompressionStrategy::for_object_type(ObjectType::MlCheckpoint),
-            CompressionStrategy::Zstd(CompressionLevel::Fast)
+            CompressionStrategy::Lz4
         );
         // Inference models use Default (better compression for archival)
         assert_eq!(
@@ -1977,7 +2450,7 @@ mod tests {
 
     #[test]
     fn test_integration_ml_specialized_roundtrip() {
-        // Test ML checkpoint (Zstd Fast) vs inference model (Zstd Default)
+        // Test ML checkpoint (LZ4) vs inference model (Zstd Default)
         let compressor = SmartCompressor::new();
 
         // Simulate model weights (numeric data)
@@ -1992,6 +2465,10 @@ mod tests {
             model_data, checkpoint_decompressed,
             "Checkpoint roundtrip failed"
         );
+        assert_eq!(
+            crate::CompressionAlgorithm::detect(&checkpoint_compressed),
+            crate::CompressionAlgorithm::Lz4
+        );
 
         // Test inference model compression
         let inference_compressed = compressor
@@ -2049,7 +2526,7 @@ mod tests {
             ObjectType::Text,           // Brotli
             ObjectType::Json,           // Brotli
             ObjectType::AdobePhotoshop, // Zstd Default
-            ObjectType::MlCheckpoint,   // Zstd Fast
+            ObjectType::MlCheckpoint,   // LZ4
             ObjectType::WordDocument,   // Zstd Default
             ObjectType::Tiff,           // Zstd Best
         ];
@@ -2140,7 +2617,7 @@ mod tests {
             ObjectType::Text,           // Brotli
             ObjectType::Json,           // Brotli
             ObjectType::AdobePhotoshop, // Zstd Default
-            ObjectType::MlCheckpoint,   // Zstd Fast
+            ObjectType::MlCheckpoint,   // LZ4
             ObjectType::Tiff,           // Zstd Best
             ObjectType::Jpeg,           // Store
         ];
@@ -2240,6 +2717,8 @@ mod tests {
                         | CompressionStrategy::Zlib(_)
                         | CompressionStrategy::Zstd(_)
                         | CompressionStrategy::Brotli(_)
+                        | CompressionStrategy::Lz4
+                        | CompressionStrategy::Xz(_)
                         | CompressionStrategy::Delta
                 ),
                 "Type {:?} has invalid strategy: {:?}",
@@ -2268,4 +2747,323 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_parse_strategy_names() {
+        assert_eq!(
+            CompressionStrategy::parse("store"),
+            Some(CompressionStrategy::Store)
+        );
+        assert_eq!(
+            CompressionStrategy::parse("zstd"),
+            Some(CompressionStrategy::Zstd(CompressionLevel::Default))
+        );
+        assert_eq!(
+            CompressionStrategy::parse("Brotli-Best"),
+            Some(CompressionStrategy::Brotli(CompressionLevel::Best))
+        );
+        assert_eq!(
+            CompressionStrategy::parse("zlib-fast"),
+            Some(CompressionStrategy::Zlib(CompressionLevel::Fast))
+        );
+        assert_eq!(CompressionStrategy::parse("zstd-max"), None);
+        assert_eq!(
+            CompressionStrategy::parse("LZ4"),
+            Some(CompressionStrategy::Lz4)
+        );
+        assert_eq!(CompressionStrategy::parse("lz4-best"), None);
+        assert_eq!(CompressionStrategy::parse("delta"), None);
+    }
+
+    #[test]
+    fn test_archival_tier_strategies() {
+        let archival = CompressionTier::Archival;
+        assert_eq!(
+            CompressionStrategy::Zstd(CompressionLevel::Fast).for_tier(archival),
+            CompressionStrategy::Xz(CompressionLevel::Best)
+        );
+        assert_eq!(
+            CompressionStrategy::Lz4.for_tier(archival),
+            CompressionStrategy::Xz(CompressionLevel::Best)
+        );
+        assert_eq!(
+            CompressionStrategy::Store.for_tier(archival),
+            CompressionStrategy::Store
+        );
+        assert_eq!(
+            CompressionStrategy::Zlib(CompressionLevel::Default).for_tier(archival),
+            CompressionStrategy::Zlib(CompressionLevel::Default)
+        );
+        assert_eq!(
+            CompressionStrategy::Lz4.for_tier(CompressionTier::Standard),
+            CompressionStrategy::Lz4
+        );
+    }
+
+    #[test]
+    fn test_archival_tier_compresses_with_xz() {
+        let compressor = SmartCompressor::new().with_tier(CompressionTier::Archival);
+        assert_eq!(compressor.tier(), CompressionTier::Archival);
+        let data = b"archived scene description, take 1\n".repeat(200);
+
+        let compressed = compressor
+            .compress_typed_with_size(&data, ObjectType::Json)
+            .unwrap();
+        assert_eq!(
+            crate::CompressionAlgorithm::detect(&compressed),
+            crate::CompressionAlgorithm::Xz
+        );
+        assert_eq!(compressor.decompress_typed(&compressed).unwrap(), data);
+
+        // Already-compressed media is still stored, and standard-tier
+        // compressors read archival objects
+        let jpeg = compressor.compress_typed(&data, ObjectType::Jpeg).unwrap();
+        assert_eq!(jpeg[0], 0x00);
+        assert_eq!(
+            SmartCompressor::new()
+                .decompress_typed(&compressed)
+                .unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn test_strategy_names_roundtrip() {
+        for level in [
+            CompressionLevel::Fast,
+            CompressionLevel::Default,
+            CompressionLevel::Best,
+        ] {
+            for strategy in [
+                CompressionStrategy::Zlib(level),
+                CompressionStrategy::Zstd(level),
+                CompressionStrategy::Brotli(level),
+                CompressionStrategy::Xz(level),
+            ] {
+                assert_eq!(CompressionStrategy::parse(strategy.name()), Some(strategy));
+            }
+        }
+        assert_eq!(CompressionStrategy::Store.name(), "store");
+        assert_eq!(
+            CompressionStrategy::parse(CompressionStrategy::Lz4.name()),
+            Some(CompressionStrategy::Lz4)
+        );
+        assert_eq!(CompressionStrategy::Delta.name(), "delta");
+    }
+
+    #[test]
+    fn test_compress_with_dictionary() {
+        let sidecars: Vec<Vec<u8>> = (0..300)
+            .map(|i| {
+                format!(
+                    r#"{{"asset": "plate_{:04}.dpx", "colorspace": "ACEScct", "frame_in": {}, "approved": true}}"#,
+                    i,
+                    i * 24
+                )
+                .into_bytes()
+            })
+            .collect();
+        let compressor = SmartCompressor::new();
+        let plain = compressor
+            .compress_with_dictionary(&sidecars[5], ObjectType::Json)
+            .unwrap();
+
+        let dictionary = CompressionDictionary::train(&sidecars, 8192).unwrap();
+        compressor.add_dictionary(dictionary.clone(), true);
+        let trained = compressor
+            .compress_with_dictionary(&sidecars[5], ObjectType::Json)
+            .unwrap();
+
+        assert!(trained.len() < plain.len());
+        assert_eq!(
+            CompressionDictionary::id_of(&trained),
+            Some(dictionary.id())
+        );
+        assert_eq!(compressor.decompress_typed(&trained).unwrap(), sidecars[5]);
+
+        // Binary objects never use the dictionary
+        let binary = compressor
+            .compress_with_dictionary(&sidecars[5], ObjectType::Tiff)
+            .unwrap();
+        assert_eq!(CompressionDictionary::id_of(&binary), None);
+
+        // Without the dictionary loaded the object cannot be read
+        assert!(SmartCompressor::new().decompress_typed(&trained).is_err());
+    }
+
+    #[test]
+    fn test_max_decompressed_size() {
+        let original = b"frame 0001 exposure 1.5\n".repeat(4096);
+        let strategies = [
+            CompressionStrategy::Zlib(CompressionLevel::Default),
+            CompressionStrategy::Zstd(CompressionLevel::Default),
+            CompressionStrategy::Brotli(CompressionLevel::Default),
+            CompressionStrategy::Lz4,
+            CompressionStrategy::Xz(CompressionLevel::Fast),
+        ];
+        let limited = SmartCompressor::new().with_max_decompressed_size(original.len() as u64 - 1);
+        assert_eq!(limited.max_decompressed_size(), original.len() as u64 - 1);
+
+        for strategy in strategies {
+            let compressed = SmartCompressor::new()
+                .compress_with_strategy(&original, strategy)
+                .unwrap();
+            // Too much output is an error, not raw data misdetected as compressed
+            let err = limited.decompress_typed(&compressed).unwrap_err();
+            assert!(err.is_output_too_large(), "{}", strategy.name());
+        }
+
+        // Stored objects are as large as their input, so the limit has no say
+        let stored = limited
+            .compress_with_strategy(&original, CompressionStrategy::Store)
+            .unwrap();
+        assert_eq!(limited.decompress_typed(&stored).unwrap(), original);
+    }
+
+    #[test]
+    fn test_brotli_settings() {
+        let settings = BrotliSettings {
+            window: 16,
+            quality: Some(5),
+        };
+        let tuned = SmartCompressor::new().with_brotli(settings);
+        assert_eq!(tuned.brotli(), settings);
+
+        let original = b"shot 042 take 3: approved for comp\n".repeat(2048);
+        for level in [CompressionLevel::Fast, CompressionLevel::Best] {
+            let compressed = tuned
+                .compress_with_strategy(&original, CompressionStrategy::Brotli(level))
+                .unwrap();
+            // Output matches a Brotli compressor with the same settings
+            let expected = BrotliCompressor::new(level)
+                .with_settings(settings)
+                .compress(&original)
+                .unwrap();
+            assert_eq!(compressed, expected);
+            // Untuned readers decompress it
+            assert_eq!(
+                SmartCompressor::new()
+                    .decompress_typed(&compressed)
+                    .unwrap(),
+                original
+            );
+        }
+    }
+
+    #[test]
+    fn test_compress_delta_roundtrip() {
+        let compressor = SmartCompressor::new();
+        let base = noise(64 * 1024);
+        let mut target = base.clone();
+        target[1000..1010].copy_from_slice(b"0123456789");
+
+        let delta = compressor
+            .compress_delta(
+                &target,
+                Some(DeltaBase {
+                    id: b"base",
+                    data: &base,
+                }),
+            )
+            .unwrap();
+        assert!(delta.starts_with(&crate::delta::DELTA_FRAME_MAGIC));
+        assert!(delta.len() < 1024, "delta is {} bytes", delta.len());
+
+        // A delta that beats the raw object but not the compressed one is
+        // not used
+        let text = b"frame 0001 exposure 1.5\n".repeat(2000);
+        let unrelated = noise(text.len());
+        let stored = compressor
+            .compress_delta(
+                &text,
+                Some(DeltaBase {
+                    id: b"noise",
+                    data: &unrelated,
+                }),
+            )
+            .unwrap();
+        assert_eq!(
+            crate::CompressionAlgorithm::detect(&stored),
+            crate::CompressionAlgorithm::Zstd
+        );
+
+        let mut fetched = None;
+        let restored = compressor
+            .decompress_with_base(&delta, |id| {
+                fetched = Some(id.to_vec());
+                Ok(base.clone())
+            })
+            .unwrap();
+        assert_eq!(restored, target);
+        assert_eq!(fetched.as_deref(), Some(&b"base"[..]));
+
+        // Without its base the delta cannot be read
+        assert!(compressor.decompress_typed(&delta).is_err());
+        let wrong_base = compressor.decompress_with_base(&delta, |_| Ok(vec![0u8; 10]));
+        assert!(wrong_base.is_err());
+    }
+
+    #[test]
+    fn test_compress_delta_without_base_uses_zstd() {
+        let compressor = SmartCompressor::new();
+        let data = b"frame 0001 exposure 1.5\n".repeat(100);
+
+        let compressed = compressor.compress_delta(&data, None).unwrap();
+        assert_eq!(
+            crate::CompressionAlgorithm::detect(&compressed),
+            crate::CompressionAlgorithm::Zstd
+        );
+        let restored = compressor
+            .decompress_with_base(&compressed, |_| panic!("no base to fetch"))
+            .unwrap();
+        assert_eq!(restored, data);
+
+        // Below the minimum compression size nothing is delta-encoded
+        let small = compressor
+            .compress_delta(
+                b"v2",
+                Some(DeltaBase {
+                    id: b"v1",
+                    data: b"v1",
+                }),
+            )
+            .unwrap();
+        assert_eq!(small, b"\x00v2");
+    }
+
+    #[test]
+    fn test_delta_strategy_needs_a_base() {
+        let compressor = SmartCompressor::new();
+        let data = b"frame 0001 exposure 1.5\n".repeat(100);
+
+        let err = compressor
+            .compress_with_strategy(&data, CompressionStrategy::Delta)
+            .unwrap_err();
+        assert!(matches!(err, CompressionError::InvalidInput(_)));
+        assert!(compressor
+            .compress_with_strategy(b"tiny", CompressionStrategy::Delta)
+            .is_err());
+    }
+
+    #[test]
+    fn test_decompress_delta_output_limit() {
+        let base = noise(64 * 1024);
+        let mut target = base.clone();
+        target.push(7);
+        let delta = SmartCompressor::new()
+            .compress_delta(
+                &target,
+                Some(DeltaBase {
+                    id: b"b",
+                    data: &base,
+                }),
+            )
+            .unwrap();
+
+        let limited = SmartCompressor::new().with_max_decompressed_size(64 * 1024);
+        let err = limited
+            .decompress_with_base(&delta, |_| panic!("checked before fetching"))
+            .unwrap_err();
+        assert!(err.is_output_too_large());
+    }
 }
@@ -0,0 +1,631 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Content sniffing for extension-less and mis-named files
+//!
+//! [`sniff`] identifies an [`ObjectType`] from a file's leading bytes, in the
+//! spirit of the `infer` crate's matcher table but mapped onto the types the
+//! compressor distinguishes. Every match carries a [`Confidence`]:
+//!
+//! - [`Confidence::Strong`] signatures identify one format, such as PNG's
+//!   8-byte magic, an ISO-BMFF `ftyp` brand like `heic`, or a Canon CR2
+//!   header. They override the extension.
+//! - [`Confidence::Weak`] signatures identify a container that many formats
+//!   share: ZIP (Office documents, PyTorch checkpoints), PDF (Illustrator),
+//!   plain TIFF (most camera RAW), gzip (`.svgz`). A known extension is more
+//!   specific, so it wins; the sniffed type is used only when the extension
+//!   says nothing.
+//!
+//! [`ObjectType::detect`] applies that rule.
+
+use crate::smart_compressor::ObjectType;
+
+/// How much a signature match says about the format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    /// The signature is shared by several formats; defer to a known extension
+    Weak,
+    /// The signature identifies this format
+    Strong,
+}
+
+/// A sniffed object type and how certain the match is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sniffed {
+    /// The detected type
+    pub object_type: ObjectType,
+    /// How far the detection can be trusted over the file extension
+    pub confidence: Confidence,
+}
+
+impl Sniffed {
+    fn strong(object_type: ObjectType) -> Option<Self> {
+        Some(Self {
+            object_type,
+            confidence: Confidence::Strong,
+        })
+    }
+
+    fn weak(object_type: ObjectType) -> Option<Self> {
+        Some(Self {
+            object_type,
+            confidence: Confidence::Weak,
+        })
+    }
+}
+
+/// Bytes of a TIFF file searched for camera maker notes
+const MAKERNOTE_SCAN_LEN: usize = 64 * 1024;
+
+/// Maker note headers written by cameras into their TIFF-based RAW files
+const MAKERNOTE_SIGNATURES: &[&[u8]] = &[
+    b"Nikon\x00\x02",    // NEF
+    b"SONY DSC \x00",    // ARW
+    b"PENTAX \x00",      // PEF
+    b"OLYMPUS\x00",      // ORF (newer bodies)
+    b"Panasonic\x00",    // RW2 embedded in TIFF
+    b"FUJIFILM\x0C\x00", // RAF embedded JPEG/TIFF
+];
+
+/// TIFF tag holding the DNG version
+const DNG_VERSION_TAG: u16 = 0xC612;
+
+/// Identify a file's type from its leading bytes
+///
+/// Returns `None` when no signature matches.
+pub fn sniff(data: &[u8]) -> Option<Sniffed> {
+    if data.len() < 4 {
+        return None;
+    }
+
+    // Images
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Sniffed::strong(ObjectType::Jpeg);
+    }
+    if data.starts_with(&[0x89, b'P', b'N', b'G']) {
+        return Sniffed::strong(ObjectType::Png);
+    }
+    if data.starts_with(b"GIF8") {
+        return Sniffed::strong(ObjectType::Gif);
+    }
+    if data.starts_with(&[0x76, 0x2F, 0x31, 0x01]) {
+        return Sniffed::strong(ObjectType::Exr);
+    }
+    if data.starts_with(b"8BPS") {
+        return Sniffed::strong(ObjectType::AdobePhotoshop);
+    }
+    if data.starts_with(b"SDPX") || data.starts_with(b"XPDS") {
+        return Sniffed::strong(ObjectType::Dpx);
+    }
+    if data.starts_with(b"#?RADIANCE") || data.starts_with(b"#?RGBE") {
+        return Sniffed::strong(ObjectType::Hdr);
+    }
+    if data.starts_with(b"DDS ")
+        || data.starts_with(b"\xABKTX 11\xBB")
+        || data.starts_with(b"\xABKTX 20\xBB")
+    {
+        return Sniffed::strong(ObjectType::GpuTexture);
+    }
+    if data.starts_with(b"FUJIFILMCCD-RAW") {
+        return Sniffed::strong(ObjectType::Raw);
+    }
+    if let Some(sniffed) = sniff_tiff(data) {
+        return Some(sniffed);
+    }
+    if data.starts_with(b"BM") {
+        return Sniffed::weak(ObjectType::Bmp);
+    }
+
+    // Containers dispatched on a subtype
+    if data.len() >= 12 && &data[4..8] == b"ftyp" {
+        return sniff_ftyp(data);
+    }
+    if data.len() >= 12 && data.starts_with(b"RIFF") {
+        return match &data[8..12] {
+            b"WEBP" => Sniffed::strong(ObjectType::Webp),
+            b"WAVE" => Sniffed::strong(ObjectType::Wav),
+            b"AVI " => Sniffed::strong(ObjectType::Avi),
+            _ => None,
+        };
+    }
+    if data.len() >= 12 && data.starts_with(b"FORM") {
+        return match &data[8..12] {
+            b"AIFF" | b"AIFC" => Sniffed::strong(ObjectType::Aiff),
+            _ => None,
+        };
+    }
+    if data.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        return sniff_ebml(data);
+    }
+    if data.starts_with(b"OggS") {
+        return sniff_ogg(data);
+    }
+
+    // Audio and video
+    if data.starts_with(b"fLaC") {
+        return Sniffed::strong(ObjectType::Flac);
+    }
+    if data.starts_with(&[
+        0x06, 0x0E, 0x2B, 0x34, 0x02, 0x05, 0x01, 0x01, 0x0D, 0x01, 0x02,
+    ]) {
+        return Sniffed::strong(ObjectType::Mxf);
+    }
+    if data.starts_with(&[
+        0x30, 0x26, 0xB2, 0x75, 0x8E, 0x66, 0xCF, 0x11, 0xA6, 0xD9, 0x00, 0xAA, 0x00, 0x62, 0xCE,
+        0x6C,
+    ]) {
+        return Sniffed::strong(ObjectType::Wmv);
+    }
+    if data.starts_with(b"FLV\x01") {
+        return Sniffed::strong(ObjectType::Flv);
+    }
+    if data.starts_with(&[0x00, 0x00, 0x01, 0xBA]) || data.starts_with(&[0x00, 0x00, 0x01, 0xB3]) {
+        return Sniffed::strong(ObjectType::Mpg);
+    }
+    if data.starts_with(b"ID3") {
+        return Sniffed::weak(ObjectType::Mp3);
+    }
+    // ADTS AAC: 12-bit sync word with layer bits 00 (MPEG audio uses non-zero layers)
+    if data.len() > 32 && data[0] == 0xFF && (data[1] & 0xF6) == 0xF0 {
+        return Sniffed::weak(ObjectType::Aac);
+    }
+    // MPEG audio frame sync; short buffers match too easily
+    if data.len() > 32 && data[0] == 0xFF && (data[1] & 0xE0) == 0xE0 {
+        return Sniffed::weak(ObjectType::Mp3);
+    }
+
+    // Documents and data
+    if data.starts_with(b"%PDF") {
+        return Sniffed::weak(ObjectType::Pdf);
+    }
+    if data.starts_with(b"BLENDER") {
+        return Sniffed::strong(ObjectType::Blender);
+    }
+    if data.starts_with(b"glTF") {
+        return Sniffed::strong(ObjectType::Model3D);
+    }
+    if data.starts_with(b"SQLite format 3\x00") {
+        return Sniffed::strong(ObjectType::SqliteDatabase);
+    }
+    if data.starts_with(b"PAR1") {
+        return Sniffed::strong(ObjectType::Parquet);
+    }
+    if data.starts_with(b"\x93NUMPY") {
+        return Sniffed::strong(ObjectType::MlData);
+    }
+    // HDF5 also stores Keras weights and netCDF-4
+    if data.starts_with(b"\x89HDF\r\n\x1A\n") {
+        return Sniffed::weak(ObjectType::MlData);
+    }
+    if data.starts_with(b"GGUF") {
+        return Sniffed::strong(ObjectType::MlInference);
+    }
+
+    // Archives and compressed streams
+    if data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06") {
+        return sniff_zip(data);
+    }
+    if data.starts_with(&[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C]) {
+        return Sniffed::strong(ObjectType::SevenZ);
+    }
+    if data.starts_with(b"Rar!\x1A\x07") {
+        return Sniffed::strong(ObjectType::Rar);
+    }
+    if data.starts_with(&[0x1F, 0x8B])
+        || data.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00])
+        || data.starts_with(b"BZh")
+        || data.starts_with(&[0x28, 0xB5, 0x2F, 0xFD])
+        || data.starts_with(&[0x04, 0x22, 0x4D, 0x18])
+    {
+        return Sniffed::weak(ObjectType::Gz);
+    }
+
+    None
+}
+
+/// ISO base media files (MP4, MOV, HEIF, AVIF, CR3, ...), told apart by brand
+fn sniff_ftyp(data: &[u8]) -> Option<Sniffed> {
+    let box_len = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let box_end = box_len.clamp(12, data.len());
+    let major = &data[8..12];
+    // Compatible brands follow the 4-byte minor version
+    let compatible: Vec<&[u8]> = data
+        .get(16..box_end)
+        .unwrap_or_default()
+        .chunks_exact(4)
+        .collect();
+    let has = |brand: &[u8]| major == brand || compatible.contains(&brand);
+
+    // AVIF is often labelled with the generic HEIF brand mif1
+    if has(b"avif") || has(b"avis") {
+        return Sniffed::strong(ObjectType::Avif);
+    }
+    match major {
+        b"heic" | b"heix" | b"hevc" | b"hevx" | b"heim" | b"heis" => {
+            Sniffed::strong(ObjectType::Heic)
+        }
+        b"mif1" | b"msf1" => Sniffed::weak(ObjectType::Heic),
+        b"crx " => Sniffed::strong(ObjectType::Raw),
+        b"qt  " => Sniffed::strong(ObjectType::Mov),
+        b"M4A " | b"M4B " | b"M4P " => Sniffed::strong(ObjectType::Aac),
+        b"M4V " | b"M4VH" | b"M4VP" => Sniffed::strong(ObjectType::Mp4),
+        _ if major.starts_with(b"3g") => Sniffed::strong(ObjectType::Mp4),
+        _ => Sniffed::weak(ObjectType::Mp4),
+    }
+}
+
+/// Matroska and WebM share the EBML header; the DocType tells them apart
+fn sniff_ebml(data: &[u8]) -> Option<Sniffed> {
+    let header = &data[..data.len().min(64)];
+    if contains(header, b"webm") {
+        Sniffed::strong(ObjectType::Webm)
+    } else if contains(header, b"matroska") {
+        Sniffed::strong(ObjectType::Mkv)
+    } else {
+        Sniffed::weak(ObjectType::Mkv)
+    }
+}
+
+/// Ogg pages carry Vorbis, Opus or FLAC; the first packet names the codec
+fn sniff_ogg(data: &[u8]) -> Option<Sniffed> {
+    // 27-byte page header, then one lacing byte per segment
+    let packet = data
+        .get(26)
+        .and_then(|&segments| data.get(27 + segments as usize..))
+        .unwrap_or_default();
+    if packet.starts_with(b"OpusHead") {
+        Sniffed::strong(ObjectType::Opus)
+    } else if packet.starts_with(b"\x01vorbis") {
+        Sniffed::strong(ObjectType::Ogg)
+    } else if packet.starts_with(b"\x7FFLAC") {
+        Sniffed::strong(ObjectType::Flac)
+    } else {
+        Sniffed::weak(ObjectType::Ogg)
+    }
+}
+
+/// ZIP archives, recognising OpenDocument by its leading `mimetype` entry
+fn sniff_zip(data: &[u8]) -> Option<Sniffed> {
+    // The first local file header's name starts at byte 30
+    let entry = data.get(30..).unwrap_or_default();
+    if entry.starts_with(b"mimetypeapplication/vnd.oasis.opendocument") {
+        Sniffed::strong(ObjectType::OpenDocument)
+    } else {
+        Sniffed::weak(ObjectType::Zip)
+    }
+}
+
+/// TIFF and the camera RAW formats built on it
+fn sniff_tiff(data: &[u8]) -> Option<Sniffed> {
+    if data.len() >= 10 && data.starts_with(b"II*\x00") && &data[8..10] == b"CR" {
+        return Sniffed::strong(ObjectType::Raw); // Canon CR2
+    }
+    if data.starts_with(b"IIRO") || data.starts_with(b"IIRS") || data.starts_with(b"MMOR") {
+        return Sniffed::strong(ObjectType::Raw); // Olympus ORF
+    }
+    if data.starts_with(b"IIU\x00") {
+        return Sniffed::strong(ObjectType::Raw); // Panasonic RW2
+    }
+
+    let little_endian = if data.starts_with(b"II*\x00") {
+        true
+    } else if data.starts_with(b"MM\x00*") {
+        false
+    } else {
+        return None;
+    };
+
+    let head = &data[..data.len().min(MAKERNOTE_SCAN_LEN)];
+    let is_raw = has_dng_version(data, little_endian)
+        || MAKERNOTE_SIGNATURES
+            .iter()
+            .any(|signature| contains(head, signature));
+    if is_raw {
+        Sniffed::strong(ObjectType::Raw)
+    } else {
+        Sniffed::weak(ObjectType::Tiff)
+    }
+}
+
+/// Whether the first IFD of a TIFF carries the DNGVersion tag
+fn has_dng_version(data: &[u8], little_endian: bool) -> bool {
+    let read_u16 = |at: usize| {
+        data.get(at..at + 2).map(|b| {
+            let b = [b[0], b[1]];
+            if little_endian {
+                u16::from_le_bytes(b)
+            } else {
+                u16::from_be_bytes(b)
+            }
+        })
+    };
+    let read_u32 = |at: usize| {
+        data.get(at..at + 4).map(|b| {
+            let b = [b[0], b[1], b[2], b[3]];
+            if little_endian {
+                u32::from_le_bytes(b)
+            } else {
+                u32::from_be_bytes(b)
+            }
+        })
+    };
+
+    let Some(ifd) = read_u32(4).map(|offset| offset as usize) else {
+        return false;
+    };
+    let Some(count) = read_u16(ifd) else {
+        return false;
+    };
+    (0..count as usize)
+        .map_while(|i| read_u16(ifd + 2 + i * 12))
+        .any(|tag| tag == DNG_VERSION_TAG)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strong(object_type: ObjectType) -> Option<Sniffed> {
+        Sniffed::strong(object_type)
+    }
+
+    fn weak(object_type: ObjectType) -> Option<Sniffed> {
+        Sniffed::weak(object_type)
+    }
+
+    /// A little-endian TIFF header whose first IFD holds the given tags
+    fn tiff_with_tags(tags: &[u16]) -> Vec<u8> {
+        let mut data = b"II*\x00\x08\x00\x00\x00".to_vec();
+        data.extend((tags.len() as u16).to_le_bytes());
+        for tag in tags {
+            data.extend(tag.to_le_bytes());
+            data.extend([0x01, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01, 0x04, 0x00, 0x00]);
+        }
+        data.extend([0x00; 4]);
+        data
+    }
+
+    #[test]
+    fn test_heif_family_brands() {
+        // iPhone HEIC: ftyp heic, compatible mif1 heic
+        let heic = b"\x00\x00\x00\x18ftypheic\x00\x00\x00\x00mif1heic";
+        assert_eq!(sniff(heic), strong(ObjectType::Heic));
+
+        // libavif output: ftyp avif, compatible avif mif1 miaf MA1B
+        let avif = b"\x00\x00\x00\x20ftypavif\x00\x00\x00\x00avifmif1miafMA1B";
+        assert_eq!(sniff(avif), strong(ObjectType::Avif));
+
+        // AVIF under the generic HEIF major brand
+        let avif_mif1 = b"\x00\x00\x00\x1cftypmif1\x00\x00\x00\x00mif1avifmiaf";
+        assert_eq!(sniff(avif_mif1), strong(ObjectType::Avif));
+
+        // Generic HEIF image without a codec-specific brand
+        let heif = b"\x00\x00\x00\x18ftypmif1\x00\x00\x00\x00mif1miaf";
+        assert_eq!(sniff(heif), weak(ObjectType::Heic));
+    }
+
+    #[test]
+    fn test_iso_media_brands() {
+        let mov = b"\x00\x00\x00\x14ftypqt  \x20\x05\x03\x00qt  ";
+        assert_eq!(sniff(mov), strong(ObjectType::Mov));
+
+        let m4a = b"\x00\x00\x00\x20ftypM4A \x00\x00\x00\x00M4A mp42isom\x00\x00\x00\x00";
+        assert_eq!(sniff(m4a), strong(ObjectType::Aac));
+
+        let three_gp = b"\x00\x00\x00\x14ftyp3gp4\x00\x00\x02\x003gp4";
+        assert_eq!(sniff(three_gp), strong(ObjectType::Mp4));
+
+        // isom is shared by MP4, M4V and many more
+        let mp4 = b"\x00\x00\x00\x20ftypisom\x00\x00\x02\x00isomiso2avc1mp41";
+        assert_eq!(sniff(mp4), weak(ObjectType::Mp4));
+
+        // Canon CR3
+        let cr3 = b"\x00\x00\x00\x18ftypcrx \x00\x00\x00\x01crx isom";
+        assert_eq!(sniff(cr3), strong(ObjectType::Raw));
+    }
+
+    #[test]
+    fn test_exr_and_hdr_images() {
+        // OpenEXR magic, then version 2 with no flags
+        let exr = [0x76, 0x2F, 0x31, 0x01, 0x02, 0x00, 0x00, 0x00];
+        assert_eq!(sniff(&exr), strong(ObjectType::Exr));
+
+        assert_eq!(
+            sniff(b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n"),
+            strong(ObjectType::Hdr)
+        );
+        // DPX, big- and little-endian
+        assert_eq!(sniff(b"SDPX\x00\x00\x08\x00"), strong(ObjectType::Dpx));
+        assert_eq!(sniff(b"XPDS\x00\x08\x00\x00"), strong(ObjectType::Dpx));
+    }
+
+    #[test]
+    fn test_gpu_textures() {
+        assert_eq!(
+            sniff(b"DDS \x7C\x00\x00\x00\x07\x10\x08\x00"),
+            strong(ObjectType::GpuTexture)
+        );
+        assert_eq!(
+            sniff(b"\xABKTX 20\xBB\r\n\x1A\n"),
+            strong(ObjectType::GpuTexture)
+        );
+    }
+
+    #[test]
+    fn test_lossless_and_ogg_audio() {
+        // FLAC: magic, then a STREAMINFO metadata block header
+        assert_eq!(
+            sniff(b"fLaC\x00\x00\x00\x22\x10\x00\x10\x00"),
+            strong(ObjectType::Flac)
+        );
+
+        // First Ogg page: 27-byte header, 1 segment of 30 bytes, then the packet
+        let page = |packet: &[u8]| {
+            let mut data = b"OggS\x00\x02".to_vec();
+            data.extend([0x00; 20]);
+            data.extend([0x01, 0x1E]);
+            data.extend(packet);
+            data
+        };
+        assert_eq!(sniff(&page(b"\x01vorbis\x00\x00")), strong(ObjectType::Ogg));
+        assert_eq!(sniff(&page(b"OpusHead\x01\x02")), strong(ObjectType::Opus));
+        assert_eq!(sniff(&page(b"\x7FFLAC\x01\x00")), strong(ObjectType::Flac));
+        assert_eq!(sniff(&page(b"Speex   ")), weak(ObjectType::Ogg));
+
+        assert_eq!(
+            sniff(b"FORM\x00\x01\x2A\x4CAIFFCOMM"),
+            strong(ObjectType::Aiff)
+        );
+    }
+
+    #[test]
+    fn test_matroska_doctype() {
+        let webm = b"\x1A\x45\xDF\xA3\x9F\x42\x86\x81\x01\x42\xF7\x81\x01\x42\xF2\x81\x04\x42\xF3\x81\x08\x42\x82\x84webm";
+        assert_eq!(sniff(webm), strong(ObjectType::Webm));
+        let mkv = b"\x1A\x45\xDF\xA3\xA3\x42\x86\x81\x01\x42\xF7\x81\x01\x42\xF2\x81\x04\x42\xF3\x81\x08\x42\x82\x88matroska";
+        assert_eq!(sniff(mkv), strong(ObjectType::Mkv));
+    }
+
+    #[test]
+    fn test_broadcast_video() {
+        // MXF header partition pack key
+        let mxf = [
+            0x06, 0x0E, 0x2B, 0x34, 0x02, 0x05, 0x01, 0x01, 0x0D, 0x01, 0x02, 0x01, 0x01, 0x02,
+        ];
+        assert_eq!(sniff(&mxf), strong(ObjectType::Mxf));
+        // ASF header object GUID
+        let asf = [
+            0x30, 0x26, 0xB2, 0x75, 0x8E, 0x66, 0xCF, 0x11, 0xA6, 0xD9, 0x00, 0xAA, 0x00, 0x62,
+            0xCE, 0x6C,
+        ];
+        assert_eq!(sniff(&asf), strong(ObjectType::Wmv));
+        assert_eq!(
+            sniff(b"FLV\x01\x05\x00\x00\x00\x09"),
+            strong(ObjectType::Flv)
+        );
+        assert_eq!(
+            sniff(&[0x00, 0x00, 0x01, 0xBA, 0x44]),
+            strong(ObjectType::Mpg)
+        );
+    }
+
+    #[test]
+    fn test_camera_raw() {
+        // Canon CR2: TIFF header, then "CR" and major version 2 at byte 8
+        let cr2 = b"II*\x00\x10\x00\x00\x00CR\x02\x00\x00\x00\x00\x00";
+        assert_eq!(sniff(cr2), strong(ObjectType::Raw));
+        assert_eq!(sniff(b"IIRO\x08\x00\x00\x00"), strong(ObjectType::Raw));
+        assert_eq!(sniff(b"IIU\x00\x18\x00\x00\x00"), strong(ObjectType::Raw));
+        assert_eq!(
+            sniff(b"FUJIFILMCCD-RAW 0201FF383501"),
+            strong(ObjectType::Raw)
+        );
+
+        // DNG: an ordinary TIFF whose first IFD has DNGVersion
+        let dng = tiff_with_tags(&[0x00FE, 0x0100, DNG_VERSION_TAG]);
+        assert_eq!(sniff(&dng), strong(ObjectType::Raw));
+
+        // NEF and ARW: ordinary TIFFs with a maker note
+        let mut nef = tiff_with_tags(&[0x0100, 0x927C]);
+        nef.extend(b"Nikon\x00\x02\x10\x00\x00MM\x00*");
+        assert_eq!(sniff(&nef), strong(ObjectType::Raw));
+        let mut arw = tiff_with_tags(&[0x0100, 0x927C]);
+        arw.extend(b"SONY DSC \x00\x00\x00");
+        assert_eq!(sniff(&arw), strong(ObjectType::Raw));
+
+        // Without either, it is just a TIFF
+        let tiff = tiff_with_tags(&[0x0100, 0x0101]);
+        assert_eq!(sniff(&tiff), weak(ObjectType::Tiff));
+        assert_eq!(sniff(b"MM\x00*\x00\x00\x00\x08"), weak(ObjectType::Tiff));
+    }
+
+    #[test]
+    fn test_project_and_data_files() {
+        assert_eq!(sniff(b"BLENDER-v402REND"), strong(ObjectType::Blender));
+        assert_eq!(
+            sniff(b"glTF\x02\x00\x00\x00\x9C\x05\x00\x00"),
+            strong(ObjectType::Model3D)
+        );
+        assert_eq!(
+            sniff(b"SQLite format 3\x00\x10\x00\x01\x01"),
+            strong(ObjectType::SqliteDatabase)
+        );
+        assert_eq!(sniff(b"PAR1\x15\x04\x15\x10"), strong(ObjectType::Parquet));
+        assert_eq!(
+            sniff(b"\x93NUMPY\x01\x00v\x00{'descr': '<f4'"),
+            strong(ObjectType::MlData)
+        );
+        assert_eq!(
+            sniff(b"\x89HDF\r\n\x1A\n\x00\x00\x00\x00"),
+            weak(ObjectType::MlData)
+        );
+        assert_eq!(
+            sniff(b"GGUF\x03\x00\x00\x00"),
+            strong(ObjectType::MlInference)
+        );
+    }
+
+    #[test]
+    fn test_zip_based_formats() {
+        let mut odt = b"PK\x03\x04\x14\x00\x00\x08\x00\x00".to_vec();
+        odt.extend([0x00; 16]);
+        odt.extend([0x08, 0x00, 0x00, 0x00]);
+        odt.extend(b"mimetypeapplication/vnd.oasis.opendocument.text");
+        assert_eq!(sniff(&odt), strong(ObjectType::OpenDocument));
+
+        let mut docx = b"PK\x03\x04\x14\x00\x06\x00\x08\x00".to_vec();
+        docx.extend([0x00; 16]);
+        docx.extend([0x13, 0x00, 0x00, 0x00]);
+        docx.extend(b"[Content_Types].xml");
+        assert_eq!(sniff(&docx), weak(ObjectType::Zip));
+    }
+
+    #[test]
+    fn test_adts_aac_is_not_mp3() {
+        let mut adts = vec![0xFF, 0xF1, 0x50, 0x80];
+        adts.extend([0x00; 40]);
+        assert_eq!(sniff(&adts), weak(ObjectType::Aac));
+    }
+
+    #[test]
+    fn test_detect_prefers_extension_over_weak_matches() {
+        // Strong signatures correct a wrong or missing extension
+        let flac = b"fLaC\x00\x00\x00\x22";
+        assert_eq!(ObjectType::detect("take_03", flac), ObjectType::Flac);
+        assert_eq!(ObjectType::detect("take_03.mp3", flac), ObjectType::Flac);
+
+        // A PyTorch checkpoint is a ZIP, but the extension is more specific
+        let zip = b"PK\x03\x04\x14\x00\x00\x00";
+        assert_eq!(
+            ObjectType::detect("model.pt", zip),
+            ObjectType::MlCheckpoint
+        );
+        assert_eq!(ObjectType::detect("bundle", zip), ObjectType::Zip);
+
+        // Illustrator files are PDFs underneath
+        assert_eq!(
+            ObjectType::detect("logo.ai", b"%PDF-1.6"),
+            ObjectType::AdobeIllustrator
+        );
+
+        // Nothing recognisable: the extension decides
+        assert_eq!(ObjectType::detect("notes.txt", b"hello"), ObjectType::Text);
+        assert_eq!(ObjectType::detect("blob", b"hello"), ObjectType::Unknown);
+    }
+}
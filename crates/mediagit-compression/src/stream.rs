@@ -0,0 +1,445 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Streaming compression between async readers and writers
+//!
+//! [`Compressor::compress_stream`](crate::Compressor::compress_stream) and
+//! [`Compressor::decompress_stream`](crate::Compressor::decompress_stream)
+//! move data from an `AsyncRead` to an `AsyncWrite` without holding all of
+//! it. The input is read [`STREAM_BUFFER_SIZE`] bytes at a time and pushed
+//! through a [`StreamCodec`] in steps, with its output written out after
+//! each step. Decoders end a step once about [`STREAM_BUFFER_SIZE`] bytes
+//! of output are waiting, however little input that took, so memory stays
+//! bounded by the buffer and the codec's own window even for input that
+//! expands a thousandfold.
+//!
+//! Streamed output uses the same format as the buffered `compress`, so data
+//! compressed one way can be decompressed the other.
+//!
+//! Decompression stops with
+//! [`OutputTooLarge`](crate::CompressionError::OutputTooLarge) once the
+//! output written passes the compressor's maximum decompressed size.
+
+use crate::error::CompressionError;
+use crate::limit::{self, LimitedBuffer};
+use brotli::{BrotliDecompressStream, BrotliResult, BrotliState, HeapAlloc, HuffmanCode};
+use std::io::{self, Write};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use zstd::stream::raw::Operation;
+
+/// Bytes read from the input at a time (1 MB)
+pub const STREAM_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// The async reader streaming compression reads from
+pub type StreamReader<'a> = dyn AsyncRead + Unpin + Send + 'a;
+
+/// The async writer streaming compression writes to
+pub type StreamWriter<'a> = dyn AsyncWrite + Unpin + Send + 'a;
+
+/// A synchronous encoder or decoder that writes its output into a buffer
+///
+/// A `write` may take only part of its input, and decoders return early
+/// once [`STREAM_BUFFER_SIZE`] bytes of output are waiting so the caller can
+/// drain them. Output the codec still holds is produced by later writes,
+/// even empty ones, while [`pending`](Self::pending) is true.
+pub(crate) trait StreamCodec: Write + Send {
+    /// Output produced so far and not yet taken
+    fn output(&mut self) -> &mut Vec<u8>;
+
+    /// Whether the codec holds output it had no room to produce yet
+    fn pending(&self) -> bool {
+        false
+    }
+
+    /// End the stream, returning the output still buffered
+    fn finish(self: Box<Self>) -> io::Result<Vec<u8>>;
+}
+
+impl StreamCodec for zstd::stream::write::Encoder<'static, Vec<u8>> {
+    fn output(&mut self) -> &mut Vec<u8> {
+        self.get_mut()
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<Vec<u8>> {
+        (*self).finish()
+    }
+}
+
+/// Zstd decoder that refuses input ending partway through a frame, and
+/// output past `limit` bytes
+pub(crate) struct ZstdStreamDecoder {
+    raw: zstd::stream::raw::Decoder<'static>,
+    scratch: Vec<u8>,
+    output: LimitedBuffer,
+    in_frame: bool,
+    pending: bool,
+}
+
+impl ZstdStreamDecoder {
+    pub(crate) fn new(limit: u64) -> io::Result<Self> {
+        Ok(Self {
+            raw: zstd::stream::raw::Decoder::new()?,
+            scratch: vec![0u8; zstd::zstd_safe::DCtx::out_size()],
+            output: LimitedBuffer::new(limit),
+            in_frame: false,
+            pending: false,
+        })
+    }
+}
+
+impl Write for ZstdStreamDecoder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut read = 0;
+        loop {
+            let status = self.raw.run_on_buffers(&buf[read..], &mut self.scratch)?;
+            self.output
+                .write_all(&self.scratch[..status.bytes_written])?;
+            read += status.bytes_read;
+            // An empty step past the end of a frame hints at the next one
+            if status.bytes_read > 0 || status.bytes_written > 0 {
+                self.in_frame = status.remaining != 0;
+            }
+            // A full scratch buffer means the decoder may still hold output
+            self.pending = status.bytes_written == self.scratch.len();
+            if (read == buf.len() && !self.pending) || self.output.data.len() >= STREAM_BUFFER_SIZE
+            {
+                return Ok(read);
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl StreamCodec for ZstdStreamDecoder {
+    fn output(&mut self) -> &mut Vec<u8> {
+        &mut self.output.data
+    }
+
+    fn pending(&self) -> bool {
+        self.pending
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<Vec<u8>> {
+        if self.in_frame {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "zstd stream is truncated",
+            ));
+        }
+        Ok(self.output.data)
+    }
+}
+
+impl StreamCodec for brotli::CompressorWriter<Vec<u8>> {
+    fn output(&mut self) -> &mut Vec<u8> {
+        self.get_mut()
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<Vec<u8>> {
+        Ok(self.into_inner())
+    }
+}
+
+/// Brotli decoder that refuses input ending partway through the stream or
+/// running on past its end, and output past `limit` bytes
+pub(crate) struct BrotliStreamDecoder {
+    state: BrotliState<HeapAlloc<u8>, HeapAlloc<u32>, HeapAlloc<HuffmanCode>>,
+    scratch: Vec<u8>,
+    output: LimitedBuffer,
+    total_out: usize,
+    pending: bool,
+    finished: bool,
+}
+
+impl BrotliStreamDecoder {
+    pub(crate) fn new(limit: u64) -> Self {
+        Self {
+            state: BrotliState::new(
+                HeapAlloc::default(),
+                HeapAlloc::default(),
+                HeapAlloc::default(),
+            ),
+            scratch: vec![0u8; 64 * 1024],
+            output: LimitedBuffer::new(limit),
+            total_out: 0,
+            pending: false,
+            finished: false,
+        }
+    }
+}
+
+impl Write for BrotliStreamDecoder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.finished {
+            if buf.is_empty() {
+                return Ok(0);
+            }
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "data after the end of the brotli stream",
+            ));
+        }
+
+        let mut read = 0;
+        loop {
+            let mut available_in = buf.len() - read;
+            let mut available_out = self.scratch.len();
+            let mut produced = 0;
+            let result = BrotliDecompressStream(
+                &mut available_in,
+                &mut read,
+                buf,
+                &mut available_out,
+                &mut produced,
+                &mut self.scratch,
+                &mut self.total_out,
+                &mut self.state,
+            );
+            self.output.write_all(&self.scratch[..produced])?;
+            self.pending = matches!(result, BrotliResult::NeedsMoreOutput);
+            match result {
+                BrotliResult::NeedsMoreInput => return Ok(read),
+                BrotliResult::NeedsMoreOutput => {
+                    if self.output.data.len() >= STREAM_BUFFER_SIZE {
+                        return Ok(read);
+                    }
+                }
+                BrotliResult::ResultSuccess => {
+                    self.finished = true;
+                    if read < buf.len() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "data after the end of the brotli stream",
+                        ));
+                    }
+                    return Ok(read);
+                }
+                BrotliResult::ResultFailure => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "invalid brotli data",
+                    ));
+                }
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl StreamCodec for BrotliStreamDecoder {
+    fn output(&mut self) -> &mut Vec<u8> {
+        &mut self.output.data
+    }
+
+    fn pending(&self) -> bool {
+        self.pending
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<Vec<u8>> {
+        if !self.finished {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "brotli stream is truncated",
+            ));
+        }
+        Ok(self.output.data)
+    }
+}
+
+/// Read up to `buf.len()` bytes, stopping early only at the end of input
+pub(crate) async fn read_full(reader: &mut StreamReader<'_>, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let read = reader.read(&mut buf[filled..]).await?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    Ok(filled)
+}
+
+/// Feed `first`, then the rest of `reader`, through `codec`, writing its
+/// output to `writer` after every step
+///
+/// Returns the number of bytes written.
+pub(crate) async fn pump(
+    mut codec: Box<dyn StreamCodec>,
+    first: &[u8],
+    reader: &mut StreamReader<'_>,
+    writer: &mut StreamWriter<'_>,
+) -> io::Result<u64> {
+    let mut written = 0u64;
+    let mut buf = vec![0u8; STREAM_BUFFER_SIZE];
+    let mut input = first.len();
+    buf[..input].copy_from_slice(first);
+
+    loop {
+        let mut taken = 0;
+        while taken < input || codec.pending() {
+            let read = codec.write(&buf[taken..input])?;
+            taken += read;
+            let output = codec.output();
+            let produced = output.len();
+            if produced > 0 {
+                writer.write_all(output).await?;
+                written += produced as u64;
+                output.clear();
+            }
+            if read == 0 && produced == 0 && taken < input && !codec.pending() {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "codec stopped taking input",
+                ));
+            }
+        }
+        input = reader.read(&mut buf).await?;
+        if input == 0 {
+            break;
+        }
+    }
+
+    let rest = codec.finish()?;
+    writer.write_all(&rest).await?;
+    writer.flush().await?;
+    Ok(written + rest.len() as u64)
+}
+
+/// Write `first` and the rest of `reader` to `writer` unchanged
+///
+/// Returns the number of bytes written.
+pub(crate) async fn pass_through(
+    first: &[u8],
+    reader: &mut StreamReader<'_>,
+    writer: &mut StreamWriter<'_>,
+) -> io::Result<u64> {
+    writer.write_all(first).await?;
+    let copied = tokio::io::copy(reader, writer).await?;
+    writer.flush().await?;
+    Ok(first.len() as u64 + copied)
+}
+
+/// Map an I/O error from compressing with `algorithm`
+pub(crate) fn compress_error(algorithm: &str, e: &io::Error) -> CompressionError {
+    CompressionError::compression_failed(format!("{} stream compression failed: {}", algorithm, e))
+}
+
+/// Map an I/O error from decompressing `algorithm` data
+///
+/// Output past the decoder's limit keeps its
+/// [`OutputTooLarge`](CompressionError::OutputTooLarge) error.
+pub(crate) fn decompress_error(algorithm: &str, e: &io::Error) -> CompressionError {
+    if let Some(too_large) = limit::output_too_large(e) {
+        return too_large;
+    }
+    CompressionError::decompression_failed(format!(
+        "{} stream decompression failed: {}",
+        algorithm, e
+    ))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{BrotliCompressor, CompressionLevel, Compressor, ZstdCompressor};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// Writer that keeps only the size of the largest write it was given
+    #[derive(Default)]
+    struct PeakWriter {
+        total: u64,
+        peak: usize,
+    }
+
+    impl AsyncWrite for PeakWriter {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.total += buf.len() as u64;
+            self.peak = self.peak.max(buf.len());
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    const EXPANDED_SIZE: usize = 64 * 1024 * 1024;
+
+    #[tokio::test]
+    async fn test_zstd_decoder_output_is_drained_in_steps() {
+        let compressor = ZstdCompressor::new(CompressionLevel::Default);
+        let compressed = compressor.compress(&vec![0u8; EXPANDED_SIZE]).unwrap();
+        // The whole object arrives in a single read
+        assert!(compressed.len() < STREAM_BUFFER_SIZE);
+
+        let mut writer = PeakWriter::default();
+        compressor
+            .decompress_stream(&mut &compressed[..], &mut writer)
+            .await
+            .unwrap();
+        assert_eq!(writer.total, EXPANDED_SIZE as u64);
+        assert!(
+            writer.peak <= 2 * STREAM_BUFFER_SIZE,
+            "peak {}",
+            writer.peak
+        );
+    }
+
+    #[tokio::test]
+    async fn test_brotli_decoder_output_is_drained_in_steps() {
+        let compressor = BrotliCompressor::new(CompressionLevel::Fast);
+        let compressed = compressor.compress(&vec![0u8; EXPANDED_SIZE]).unwrap();
+        assert!(compressed.len() < STREAM_BUFFER_SIZE);
+
+        let mut writer = PeakWriter::default();
+        compressor
+            .decompress_stream(&mut &compressed[..], &mut writer)
+            .await
+            .unwrap();
+        assert_eq!(writer.total, EXPANDED_SIZE as u64);
+        assert!(
+            writer.peak <= 2 * STREAM_BUFFER_SIZE,
+            "peak {}",
+            writer.peak
+        );
+    }
+
+    #[tokio::test]
+    async fn test_brotli_decoder_refuses_trailing_data() {
+        let compressor = BrotliCompressor::default_level();
+        let mut compressed = compressor.compress(&b"brotli data ".repeat(1000)).unwrap();
+        compressed.extend_from_slice(b"trailing");
+
+        let mut output = Vec::new();
+        let result = compressor
+            .decompress_stream(&mut &compressed[..], &mut output)
+            .await;
+        assert!(result.unwrap_err().is_decompression_failed());
+    }
+}
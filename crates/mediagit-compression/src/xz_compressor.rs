@@ -0,0 +1,171 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! XZ (LZMA2) compression implementation
+//!
+//! The highest compression ratios, at a much slower compression speed.
+//! Ideal for deep archival, where storage cost dominates and objects are
+//! rarely read back.
+
+use crate::error::{CompressionError, CompressionResult};
+use crate::limit::{self, DEFAULT_MAX_DECOMPRESSED_SIZE};
+use crate::{CompressionLevel, Compressor};
+use std::fmt;
+use std::io::Write;
+use xz2::read::XzDecoder;
+use xz2::write::XzEncoder;
+
+/// XZ stream magic bytes
+pub const XZ_MAGIC: &[u8; 6] = b"\xfd7zXZ\x00";
+
+/// XZ compressor implementation
+///
+/// Writes standard `.xz` streams, so compressed data starts with the XZ
+/// magic and can be read by any XZ tool.
+#[derive(Clone)]
+pub struct XzCompressor {
+    level: CompressionLevel,
+    max_output_size: u64,
+}
+
+impl XzCompressor {
+    /// Create a new XZ compressor with the given compression level
+    pub fn new(level: CompressionLevel) -> Self {
+        XzCompressor {
+            level,
+            max_output_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+        }
+    }
+
+    /// Create an XZ compressor with fast compression
+    pub fn fast() -> Self {
+        XzCompressor::new(CompressionLevel::Fast)
+    }
+
+    /// Create an XZ compressor with default compression
+    pub fn default_level() -> Self {
+        XzCompressor::new(CompressionLevel::Default)
+    }
+
+    /// Create an XZ compressor with best compression
+    pub fn best() -> Self {
+        XzCompressor::new(CompressionLevel::Best)
+    }
+
+    /// Fail decompression with
+    /// [`OutputTooLarge`](CompressionError::OutputTooLarge) once output
+    /// passes `max_output_size` bytes
+    ///
+    /// Defaults to [`DEFAULT_MAX_DECOMPRESSED_SIZE`].
+    pub fn with_max_output_size(mut self, max_output_size: u64) -> Self {
+        self.max_output_size = max_output_size;
+        self
+    }
+}
+
+impl fmt::Debug for XzCompressor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("XzCompressor")
+            .field("level", &self.level)
+            .field("max_output_size", &self.max_output_size)
+            .finish()
+    }
+}
+
+impl Compressor for XzCompressor {
+    fn compress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut encoder =
+            XzEncoder::new(Vec::with_capacity(data.len() / 4), self.level.to_xz_level());
+        encoder.write_all(data).map_err(|e| {
+            CompressionError::compression_failed(format!("xz compression failed: {}", e))
+        })?;
+        encoder.finish().map_err(|e| {
+            CompressionError::compression_failed(format!("xz compression failed: {}", e))
+        })
+    }
+
+    fn decompress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if data.starts_with(XZ_MAGIC) {
+            limit::read_limited(XzDecoder::new(data), self.max_output_size, "xz")
+        } else {
+            // Data is not xz compressed, return as-is
+            Ok(data.to_vec())
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xz_compress_decompress() {
+        let compressor = XzCompressor::default_level();
+        let original = b"Hello, World! This is a test of xz compression.".repeat(20);
+
+        let compressed = compressor.compress(&original).unwrap();
+        assert!(compressed.starts_with(XZ_MAGIC));
+        assert!(compressed.len() < original.len());
+
+        let decompressed = compressor.decompress(&compressed).unwrap();
+        assert_eq!(decompressed, original);
+    }
+
+    #[test]
+    fn test_xz_empty() {
+        let compressor = XzCompressor::default_level();
+        assert!(compressor.compress(b"").unwrap().is_empty());
+        assert!(compressor.decompress(b"").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_xz_decompress_uncompressed_data() {
+        let compressor = XzCompressor::default_level();
+        let data = b"This is not compressed";
+        assert_eq!(compressor.decompress(data).unwrap(), data);
+    }
+
+    #[test]
+    fn test_xz_beats_zstd_on_text() {
+        let original = (0..20_000)
+            .map(|i| format!("frame {:05} exposure {} shot {}\n", i, i % 7, i % 13))
+            .collect::<String>()
+            .into_bytes();
+
+        let xz = XzCompressor::best().compress(&original).unwrap();
+        let zstd = crate::ZstdCompressor::default_level()
+            .compress(&original)
+            .unwrap();
+        assert!(xz.len() < zstd.len());
+        assert_eq!(XzCompressor::fast().decompress(&xz).unwrap(), original);
+    }
+
+    #[test]
+    fn test_xz_truncated_stream_fails() {
+        let compressor = XzCompressor::default_level();
+        let original = (0..100_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let compressed = compressor.compress(&original).unwrap();
+
+        let result = compressor.decompress(&compressed[..compressed.len() / 2]);
+        assert!(result.unwrap_err().is_decompression_failed());
+    }
+}
@@ -17,6 +17,7 @@
 //! This implementation provides Git-compatible compression and decompression.
 
 use crate::error::{CompressionError, CompressionResult};
+use crate::limit::{self, DEFAULT_MAX_DECOMPRESSED_SIZE};
 use crate::{CompressionLevel, Compressor};
 use flate2::read::{ZlibDecoder, ZlibEncoder};
 use flate2::Compression;
@@ -30,12 +31,16 @@ use std::io::Read;
 #[derive(Clone)]
 pub struct ZlibCompressor {
     level: CompressionLevel,
+    max_output_size: u64,
 }
 
 impl ZlibCompressor {
     /// Create a new Zlib compressor with the given compression level
     pub fn new(level: CompressionLevel) -> Self {
-        ZlibCompressor { level }
+        ZlibCompressor {
+            level,
+            max_output_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+        }
     }
 
     /// Create a Zlib compressor with fast compression
@@ -53,6 +58,16 @@ impl ZlibCompressor {
         ZlibCompressor::new(CompressionLevel::Best)
     }
 
+    /// Fail decompression with
+    /// [`OutputTooLarge`](CompressionError::OutputTooLarge) once output
+    /// passes `max_output_size` bytes
+    ///
+    /// Defaults to [`DEFAULT_MAX_DECOMPRESSED_SIZE`].
+    pub fn with_max_output_size(mut self, max_output_size: u64) -> Self {
+        self.max_output_size = max_output_size;
+        self
+    }
+
     /// Get the flate2 compression level
     fn get_compression(&self) -> Compression {
         match self.level {
@@ -67,6 +82,7 @@ impl fmt::Debug for ZlibCompressor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ZlibCompressor")
             .field("level", &self.level)
+            .field("max_output_size", &self.max_output_size)
             .finish()
     }
 }
@@ -102,14 +118,7 @@ impl Compressor for ZlibCompressor {
         };
 
         if is_zlib {
-            let mut decoder = ZlibDecoder::new(data);
-            let mut decompressed = Vec::new();
-
-            decoder.read_to_end(&mut decompressed).map_err(|e| {
-                CompressionError::decompression_failed(format!("zlib decompression failed: {}", e))
-            })?;
-
-            Ok(decompressed)
+            limit::read_limited(ZlibDecoder::new(data), self.max_output_size, "zlib")
         } else {
             // Data is not zlib compressed, return as-is
             // This handles backward compatibility with uncompressed data
@@ -17,7 +17,10 @@
 //! Ideal for frequently accessed data in MediaGit.
 
 use crate::error::{CompressionError, CompressionResult};
+use crate::limit::{self, DEFAULT_MAX_DECOMPRESSED_SIZE};
+use crate::stream::{self, StreamReader, StreamWriter};
 use crate::{CompressionLevel, Compressor};
+use async_trait::async_trait;
 use std::fmt;
 
 /// Zstd compressor implementation
@@ -27,12 +30,16 @@ use std::fmt;
 #[derive(Clone)]
 pub struct ZstdCompressor {
     level: CompressionLevel,
+    max_output_size: u64,
 }
 
 impl ZstdCompressor {
     /// Create a new Zstd compressor with the given compression level
     pub fn new(level: CompressionLevel) -> Self {
-        ZstdCompressor { level }
+        ZstdCompressor {
+            level,
+            max_output_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+        }
     }
 
     /// Create a Zstd compressor with fast compression
@@ -49,16 +56,28 @@ impl ZstdCompressor {
     pub fn best() -> Self {
         ZstdCompressor::new(CompressionLevel::Best)
     }
+
+    /// Fail decompression with
+    /// [`OutputTooLarge`](CompressionError::OutputTooLarge) once output
+    /// passes `max_output_size` bytes
+    ///
+    /// Defaults to [`DEFAULT_MAX_DECOMPRESSED_SIZE`].
+    pub fn with_max_output_size(mut self, max_output_size: u64) -> Self {
+        self.max_output_size = max_output_size;
+        self
+    }
 }
 
 impl fmt::Debug for ZstdCompressor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ZstdCompressor")
             .field("level", &self.level)
+            .field("max_output_size", &self.max_output_size)
             .finish()
     }
 }
 
+#[async_trait]
 impl Compressor for ZstdCompressor {
     fn compress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
         let level = self.level.to_zstd_level();
@@ -87,19 +106,51 @@ impl Compressor for ZstdCompressor {
 
         // Check if this looks like zstd compressed data (has zstd magic bytes)
         if data.len() >= 4 && data.starts_with(b"\x28\xb5\x2f\xfd") {
-            match zstd::decode_all(data) {
-                Ok(decompressed) => Ok(decompressed),
-                Err(e) => Err(CompressionError::decompression_failed(format!(
-                    "zstd decompression failed: {}",
-                    e
-                ))),
-            }
+            let decoder = zstd::stream::read::Decoder::new(data)
+                .map_err(|e| CompressionError::zstd_error(format!("zstd decoder failed: {}", e)))?;
+            limit::read_limited(decoder, self.max_output_size, "zstd")
         } else {
             // Data is not zstd compressed, return as-is
             // This handles the case where data was never compressed
             Ok(data.to_vec())
         }
     }
+
+    async fn compress_stream(
+        &self,
+        reader: &mut StreamReader<'_>,
+        writer: &mut StreamWriter<'_>,
+    ) -> CompressionResult<u64> {
+        let mut first = [0u8; 4];
+        let len = stream::read_full(reader, &mut first).await?;
+        if len == 0 {
+            return Ok(0);
+        }
+
+        let encoder = zstd::stream::write::Encoder::new(Vec::new(), self.level.to_zstd_level())
+            .map_err(|e| CompressionError::zstd_error(format!("zstd encoder failed: {}", e)))?;
+        stream::pump(Box::new(encoder), &first[..len], reader, writer)
+            .await
+            .map_err(|e| stream::compress_error("zstd", &e))
+    }
+
+    async fn decompress_stream(
+        &self,
+        reader: &mut StreamReader<'_>,
+        writer: &mut StreamWriter<'_>,
+    ) -> CompressionResult<u64> {
+        let mut first = [0u8; 4];
+        let len = stream::read_full(reader, &mut first).await?;
+        if len < 4 || first != *b"\x28\xb5\x2f\xfd" {
+            return Ok(stream::pass_through(&first[..len], reader, writer).await?);
+        }
+
+        let decoder = stream::ZstdStreamDecoder::new(self.max_output_size)
+            .map_err(|e| CompressionError::zstd_error(format!("zstd decoder failed: {}", e)))?;
+        stream::pump(Box::new(decoder), &first, reader, writer)
+            .await
+            .map_err(|e| stream::decompress_error("zstd", &e))
+    }
 }
 
 #[cfg(test)]
@@ -199,4 +250,114 @@ mod tests {
         assert!(debug_str.contains("ZstdCompressor"));
         assert!(debug_str.contains("Default"));
     }
+
+    #[tokio::test]
+    async fn test_zstd_stream_round_trip() {
+        let compressor = ZstdCompressor::default_level();
+        let original = b"streamed zstd data ".repeat(100_000);
+
+        let mut compressed = Vec::new();
+        let written = compressor
+            .compress_stream(&mut &original[..], &mut compressed)
+            .await
+            .unwrap();
+        assert_eq!(written, compressed.len() as u64);
+        assert!(compressed.starts_with(b"\x28\xb5\x2f\xfd"));
+        assert!(compressed.len() < original.len() / 10);
+
+        let mut decompressed = Vec::new();
+        let written = compressor
+            .decompress_stream(&mut &compressed[..], &mut decompressed)
+            .await
+            .unwrap();
+        assert_eq!(written, original.len() as u64);
+        assert_eq!(decompressed, original);
+    }
+
+    #[tokio::test]
+    async fn test_zstd_stream_matches_buffered_format() {
+        let compressor = ZstdCompressor::default_level();
+        let original = b"Hello, World! This is a test of zstd streaming.".repeat(50);
+
+        let mut streamed = Vec::new();
+        compressor
+            .compress_stream(&mut &original[..], &mut streamed)
+            .await
+            .unwrap();
+        assert_eq!(compressor.decompress(&streamed).unwrap(), original);
+
+        let buffered = compressor.compress(&original).unwrap();
+        let mut decompressed = Vec::new();
+        compressor
+            .decompress_stream(&mut &buffered[..], &mut decompressed)
+            .await
+            .unwrap();
+        assert_eq!(decompressed, original);
+    }
+
+    #[tokio::test]
+    async fn test_zstd_stream_empty_and_uncompressed() {
+        let compressor = ZstdCompressor::default_level();
+
+        let mut compressed = Vec::new();
+        let written = compressor
+            .compress_stream(&mut &b""[..], &mut compressed)
+            .await
+            .unwrap();
+        assert_eq!(written, 0);
+        assert!(compressed.is_empty());
+
+        let data = b"not compressed";
+        let mut output = Vec::new();
+        compressor
+            .decompress_stream(&mut &data[..], &mut output)
+            .await
+            .unwrap();
+        assert_eq!(output, data);
+    }
+
+    #[tokio::test]
+    async fn test_zstd_stream_truncated_input_fails() {
+        let compressor = ZstdCompressor::default_level();
+        let original = (0..200_000u32)
+            .map(|i| (i * 7 % 251) as u8)
+            .collect::<Vec<_>>();
+        let compressed = compressor.compress(&original).unwrap();
+        let truncated = &compressed[..compressed.len() / 2];
+
+        let mut output = Vec::new();
+        let result = compressor
+            .decompress_stream(&mut &truncated[..], &mut output)
+            .await;
+        assert!(result.unwrap_err().is_decompression_failed());
+    }
+
+    #[test]
+    fn test_zstd_output_limit() {
+        let compressed = ZstdCompressor::default_level()
+            .compress(&vec![0u8; 1024 * 1024])
+            .unwrap();
+
+        let limited = ZstdCompressor::default_level().with_max_output_size(1024 * 1024);
+        assert_eq!(limited.decompress(&compressed).unwrap().len(), 1024 * 1024);
+
+        let limited = ZstdCompressor::default_level().with_max_output_size(64 * 1024);
+        let err = limited.decompress(&compressed).unwrap_err();
+        assert!(err.is_output_too_large());
+    }
+
+    #[tokio::test]
+    async fn test_zstd_stream_output_limit() {
+        let compressed = ZstdCompressor::default_level()
+            .compress(&vec![0u8; 4 * 1024 * 1024])
+            .unwrap();
+        let limited = ZstdCompressor::default_level().with_max_output_size(64 * 1024);
+
+        let mut output = Vec::new();
+        let result = limited
+            .decompress_stream(&mut &compressed[..], &mut output)
+            .await;
+        assert!(result.unwrap_err().is_output_too_large());
+        assert!(output.len() <= 64 * 1024);
+    }
 }
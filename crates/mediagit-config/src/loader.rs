@@ -12,7 +12,7 @@
 // GNU Affero General Public License for more details.
 
 use crate::error::{ConfigError, ConfigResult};
-use crate::schema::Config;
+use crate::schema::{Config, StorageConfig};
 use crate::validation::Validator;
 use std::path::Path;
 use tokio::fs;
@@ -238,6 +238,27 @@ impl ConfigLoader {
             config.security.auth_enabled = parse_bool(&value)?;
         }
 
+        self.apply_storage_env_overrides(&mut config.storage)
+    }
+
+    /// Apply environment variable overrides to the storage settings alone
+    ///
+    /// For callers that build a backend from a config they may save again,
+    /// and so shouldn't apply the other overrides to it.
+    pub fn apply_storage_env_overrides(&self, storage: &mut StorageConfig) -> ConfigResult<()> {
+        // S3-compatible endpoint TLS and encryption settings
+        if let StorageConfig::S3(s3) = storage {
+            if let Ok(value) = std::env::var("MEDIAGIT_S3_CA_BUNDLE") {
+                s3.ca_bundle = Some(value);
+            }
+            if let Ok(value) = std::env::var("MEDIAGIT_S3_INSECURE_SKIP_VERIFY") {
+                s3.insecure_skip_verify = parse_bool(&value)?;
+            }
+            if let Ok(value) = std::env::var("MEDIAGIT_S3_SSE_CUSTOMER_KEY") {
+                s3.sse_customer_key = Some(value);
+            }
+        }
+
         Ok(())
     }
 
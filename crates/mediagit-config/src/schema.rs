@@ -13,6 +13,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// Author identity configuration (used when creating commits)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
@@ -64,6 +65,46 @@ pub struct Config {
     #[serde(default)]
     pub protected_branches: HashMap<String, BranchProtection>,
 
+    /// External merge tool used by `mediagit mergetool`
+    #[serde(default)]
+    pub mergetool: MergeToolConfig,
+
+    /// Automatic conflict resolution for `mediagit merge`
+    #[serde(default)]
+    pub merge: MergeConfig,
+
+    /// Outbound HTTP proxy for cloud backends and remotes
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+
+    /// Defaults for `mediagit push`
+    #[serde(default)]
+    pub push: PushConfig,
+
+    /// Garbage collection settings for `mediagit gc`
+    #[serde(default)]
+    pub gc: GcConfig,
+
+    /// Resource limits for `mediagit gc --repack`
+    #[serde(default)]
+    pub pack: PackConfig,
+
+    /// Rename detection settings for `mediagit diff`
+    #[serde(default)]
+    pub diff: DiffConfig,
+
+    /// Bounds on the trees checkout and fsck will parse and walk
+    #[serde(default)]
+    pub trees: TreeLimitsConfig,
+
+    /// Layout of commit and tree objects
+    #[serde(default)]
+    pub objects: ObjectsConfig,
+
+    /// Cap on the space the repository may use in its storage backend
+    #[serde(default, skip_serializing_if = "QuotaConfig::is_unset")]
+    pub quota: QuotaConfig,
+
     /// Custom user-defined settings
     #[serde(default)]
     pub custom: HashMap<String, serde_json::Value>,
@@ -226,11 +267,41 @@ pub enum StorageConfig {
     #[serde(rename = "gcs")]
     GCS(GCSStorage),
 
+    /// SFTP server, such as an SSH-accessible NAS
+    #[serde(rename = "sftp")]
+    Sftp(SftpStorage),
+
     /// Multi-backend configuration
     #[serde(rename = "multi")]
     Multi(MultiBackendStorage),
 }
 
+impl StorageConfig {
+    /// The configured cap on concurrent backend operations, if any
+    pub fn max_concurrent_ops(&self) -> Option<usize> {
+        match self {
+            StorageConfig::FileSystem(fs) => fs.max_concurrent_ops,
+            StorageConfig::S3(s3) => s3.max_concurrent_ops,
+            StorageConfig::Azure(azure) => azure.max_concurrent_ops,
+            StorageConfig::GCS(gcs) => gcs.max_concurrent_ops,
+            StorageConfig::Sftp(sftp) => sftp.max_concurrent_ops,
+            StorageConfig::Multi(_) => None,
+        }
+    }
+
+    /// Short backend name, as written in the `backend` key
+    pub fn backend_name(&self) -> &'static str {
+        match self {
+            StorageConfig::FileSystem(_) => "filesystem",
+            StorageConfig::S3(_) => "s3",
+            StorageConfig::Azure(_) => "azure",
+            StorageConfig::GCS(_) => "gcs",
+            StorageConfig::Sftp(_) => "sftp",
+            StorageConfig::Multi(_) => "multi",
+        }
+    }
+}
+
 /// Filesystem storage configuration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct FileSystemStorage {
@@ -248,6 +319,21 @@ pub struct FileSystemStorage {
     /// File permissions (octal string like "0755")
     #[serde(default = "default_file_permissions")]
     pub file_permissions: String,
+
+    /// Cap on concurrent operations against this backend, shared by every
+    /// command and task in the process (unset means no cap)
+    #[serde(
+        default,
+        alias = "maxConcurrentOps",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_concurrent_ops: Option<usize>,
+
+    /// Machine-wide object store that identical objects are hard-linked
+    /// from and shared with, so repositories holding the same media don't
+    /// store it twice (unset means no sharing)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alternates: Option<String>,
 }
 
 /// AWS S3 storage configuration
@@ -271,6 +357,30 @@ pub struct S3Storage {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub endpoint: Option<String>,
 
+    /// Region requests are signed for, when the service expects a different
+    /// one than `region` (some S3-compatible providers only accept
+    /// `us-east-1`)
+    #[serde(
+        default,
+        alias = "signingRegion",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub signing_region: Option<String>,
+
+    /// PEM file of CA certificates to trust for `endpoint`, in addition to
+    /// the system roots (for services behind a private CA)
+    #[serde(default, alias = "caBundle", skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>,
+
+    /// Accept any certificate from `endpoint` without verifying it; for
+    /// development against self-signed servers only
+    #[serde(
+        default,
+        alias = "insecureSkipVerify",
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub insecure_skip_verify: bool,
+
     /// Object prefix
     #[serde(default)]
     pub prefix: String,
@@ -279,9 +389,53 @@ pub struct S3Storage {
     #[serde(default)]
     pub encryption: bool,
 
-    /// Encryption algorithm (AES256, aws:kms)
+    /// Encryption algorithm: `AES256` (SSE-S3), `aws:kms` or
+    /// `aws:kms:<key-id>` (SSE-KMS), or `SSE-C` with `sse_customer_key`
     #[serde(default = "default_encryption_algorithm")]
     pub encryption_algorithm: String,
+
+    /// Base64 256-bit key for `SSE-C` (can be overridden via env)
+    #[serde(
+        default,
+        alias = "sseCustomerKey",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub sse_customer_key: Option<String>,
+
+    /// S3 storage class objects are written in, e.g. `STANDARD_IA` (unset
+    /// uses the bucket's default)
+    #[serde(
+        default,
+        alias = "storageClass",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub storage_class: Option<String>,
+
+    /// Storage class for the pack files `gc` writes, e.g. `GLACIER_IR`, so
+    /// rarely read history moves to a colder class (unset uses
+    /// `storage_class`)
+    #[serde(
+        default,
+        alias = "packStorageClass",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub pack_storage_class: Option<String>,
+
+    /// Cap on concurrent operations against this backend, shared by every
+    /// command and task in the process (unset means no cap)
+    #[serde(
+        default,
+        alias = "maxConcurrentOps",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_concurrent_ops: Option<usize>,
+}
+
+impl S3Storage {
+    /// Region to sign requests for: `signing_region` if set, otherwise `region`
+    pub fn signing_region(&self) -> &str {
+        self.signing_region.as_deref().unwrap_or(&self.region)
+    }
 }
 
 /// Azure Blob Storage configuration
@@ -304,6 +458,33 @@ pub struct AzureStorage {
     /// Connection string (alternative to account_name/account_key)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub connection_string: Option<String>,
+
+    /// Objects larger than this many bytes are uploaded as staged blocks
+    /// (unset uses the backend default of 4MB)
+    #[serde(
+        default,
+        alias = "blockUploadThreshold",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub block_upload_threshold: Option<usize>,
+
+    /// Maximum number of blocks uploaded at once for a staged upload (unset
+    /// uses the backend default of 8)
+    #[serde(
+        default,
+        alias = "maxConcurrentBlocks",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_concurrent_blocks: Option<usize>,
+
+    /// Cap on concurrent operations against this backend, shared by every
+    /// command and task in the process (unset means no cap)
+    #[serde(
+        default,
+        alias = "maxConcurrentOps",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_concurrent_ops: Option<usize>,
 }
 
 /// Google Cloud Storage configuration
@@ -322,6 +503,105 @@ pub struct GCSStorage {
     /// Object prefix
     #[serde(default)]
     pub prefix: String,
+
+    /// Cap on concurrent operations against this backend, shared by every
+    /// command and task in the process (unset means no cap)
+    #[serde(
+        default,
+        alias = "maxConcurrentOps",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_concurrent_ops: Option<usize>,
+}
+
+/// SFTP storage configuration
+///
+/// Set either `password` or `private_key_path`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SftpStorage {
+    /// SSH server host name or address
+    pub host: String,
+
+    /// SSH server port
+    #[serde(default = "default_sftp_port")]
+    pub port: u16,
+
+    /// User to log in as
+    pub username: String,
+
+    /// Directory objects are stored under; relative paths are resolved
+    /// against the user's login directory
+    #[serde(default)]
+    pub base_path: String,
+
+    /// Password for password authentication
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
+    /// Private key file for public key authentication
+    #[serde(
+        default,
+        alias = "privateKeyPath",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub private_key_path: Option<String>,
+
+    /// Passphrase the private key is encrypted with
+    #[serde(
+        default,
+        alias = "privateKeyPassphrase",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub private_key_passphrase: Option<String>,
+
+    /// How the server's host key is checked
+    #[serde(default, alias = "hostKeyChecking")]
+    pub host_key_checking: HostKeyChecking,
+
+    /// known_hosts file to check against (default: `~/.ssh/known_hosts`)
+    #[serde(
+        default,
+        alias = "knownHostsPath",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub known_hosts_path: Option<String>,
+
+    /// SHA256 host key fingerprint (`SHA256:...`), required when
+    /// `host_key_checking` is `fingerprint`
+    #[serde(
+        default,
+        alias = "hostKeyFingerprint",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub host_key_fingerprint: Option<String>,
+
+    /// Number of SSH connections requests are spread over
+    #[serde(default = "default_sftp_connections", alias = "maxConnections")]
+    pub max_connections: usize,
+
+    /// Cap on concurrent operations against this backend, shared by every
+    /// command and task in the process (unset means no cap)
+    #[serde(
+        default,
+        alias = "maxConcurrentOps",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_concurrent_ops: Option<usize>,
+}
+
+/// SSH host key verification mode
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum HostKeyChecking {
+    /// Only connect to hosts already in known_hosts
+    #[default]
+    Strict,
+    /// Record unknown hosts in known_hosts, reject changed keys
+    AcceptNew,
+    /// Only accept the key matching `host_key_fingerprint`
+    Fingerprint,
+    /// Accept any host key (testing only)
+    Off,
 }
 
 /// Multi-backend storage configuration
@@ -336,6 +616,55 @@ pub struct MultiBackendStorage {
 
     /// Individual backend configurations
     pub backends: HashMap<String, serde_json::Value>,
+
+    /// How many backends must accept a write for it to succeed
+    #[serde(default, alias = "writePolicy")]
+    pub write_policy: ReplicaWritePolicy,
+
+    /// Backend names by key prefix; keys under a prefix are kept only in
+    /// that backend, and other keys go to the primary and replicas
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub routes: HashMap<String, String>,
+}
+
+impl MultiBackendStorage {
+    /// Parse the configuration of the backend called `name`
+    pub fn backend(&self, name: &str) -> crate::ConfigResult<StorageConfig> {
+        let value = self.backends.get(name).ok_or_else(|| {
+            crate::ConfigError::invalid_value(
+                "storage.backends",
+                format!("backend '{}' is not configured", name),
+            )
+        })?;
+        serde_json::from_value(value.clone()).map_err(|e| {
+            crate::ConfigError::invalid_value(format!("storage.backends.{}", name), e.to_string())
+        })
+    }
+
+    /// Backend names in read order: the primary, then the replicas
+    pub fn backend_names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.primary.as_str()).chain(self.replicas.iter().map(String::as_str))
+    }
+
+    /// Names of the backends routes point to, each once
+    pub fn routed_backend_names(&self) -> impl Iterator<Item = &str> {
+        let names: std::collections::BTreeSet<&str> =
+            self.routes.values().map(String::as_str).collect();
+        names.into_iter()
+    }
+}
+
+/// Write acknowledgement required from multi-backend storage
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReplicaWritePolicy {
+    /// Every backend
+    #[default]
+    All,
+    /// More than half of the backends
+    Majority,
+    /// Any one backend; failures on the rest are only logged
+    BestEffort,
 }
 
 /// Compression configuration
@@ -353,10 +682,27 @@ pub struct CompressionConfig {
     #[serde(default = "default_level")]
     pub level: u32,
 
-    /// Minimum file size for compression (in bytes)
-    #[serde(default = "default_min_size")]
+    /// Objects smaller than this many bytes are stored uncompressed
+    #[serde(default = "default_min_size", alias = "min_compress_size")]
     pub min_size: u64,
 
+    /// Compress small text objects with the repository's trained dictionary
+    ///
+    /// Has no effect until `mediagit gc --train-dict` has trained one.
+    #[serde(default = "default_true")]
+    pub dictionary: bool,
+
+    /// How far compression trades speed for size
+    ///
+    /// `archival` compresses everything compressible with XZ, for
+    /// repositories where storage cost dominates.
+    #[serde(default)]
+    pub tier: CompressionTier,
+
+    /// Brotli window size and quality
+    #[serde(default)]
+    pub brotli: BrotliConfig,
+
     /// Algorithm-specific settings
     #[serde(default)]
     pub algorithms: HashMap<String, AlgorithmConfig>,
@@ -371,6 +717,33 @@ pub enum CompressionAlgorithm {
     None,
 }
 
+/// Compression tiers
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionTier {
+    /// Algorithm picked per file type
+    #[default]
+    Standard,
+    /// XZ for everything compressible; smallest, slowest to write
+    Archival,
+}
+
+/// Brotli tunables
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BrotliConfig {
+    /// Window size as a power of two (10-24)
+    ///
+    /// 24 (16 MiB) finds repeats across large text exports; smaller windows
+    /// use less memory when compressing and when reading objects back.
+    #[serde(default = "default_brotli_window")]
+    pub window: u32,
+
+    /// Quality (0-11) used for every Brotli object instead of the one picked
+    /// per file type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<u32>,
+}
+
 /// Algorithm-specific configuration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct AlgorithmConfig {
@@ -402,6 +775,10 @@ pub struct PerformanceConfig {
 
     /// Timeout settings (in seconds)
     pub timeouts: TimeoutConfig,
+
+    /// Limits shared by all uploads of a process
+    #[serde(default)]
+    pub uploads: UploadConfig,
 }
 
 /// Cache configuration
@@ -455,7 +832,8 @@ pub struct TimeoutConfig {
     #[serde(default = "default_request_timeout")]
     pub request: u64,
 
-    /// Read timeout (in seconds)
+    /// Read timeout (in seconds): longest a cloud storage download may go
+    /// without receiving data
     #[serde(default = "default_read_timeout")]
     pub read: u64,
 
@@ -463,11 +841,23 @@ pub struct TimeoutConfig {
     #[serde(default = "default_write_timeout")]
     pub write: u64,
 
-    /// Connection timeout (in seconds)
+    /// Connection timeout (in seconds) for cloud storage backends
     #[serde(default = "default_connection_timeout")]
     pub connection: u64,
 }
 
+/// Upload pool configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UploadConfig {
+    /// Uploads in progress at once
+    #[serde(default = "default_max_concurrent_uploads", alias = "maxConcurrent")]
+    pub max_concurrent: usize,
+
+    /// Total size of the uploads in progress (in bytes)
+    #[serde(default = "default_max_in_flight_bytes", alias = "maxInFlightBytes")]
+    pub max_in_flight_bytes: u64,
+}
+
 /// Observability configuration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ObservabilityConfig {
@@ -487,6 +877,11 @@ pub struct ObservabilityConfig {
     #[serde(default = "default_sample_rate")]
     pub sample_rate: f64,
 
+    /// Per-module log levels on top of `log_level`, e.g.
+    /// `mediagit_storage = "debug"`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub module_levels: HashMap<String, String>,
+
     /// Metrics configuration
     pub metrics: MetricsConfig,
 }
@@ -546,7 +941,21 @@ pub struct SecurityConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub encryption_key_path: Option<String>,
 
+    /// Store objects under keyed hashes of their names, so the storage
+    /// provider cannot see the repository's key layout
+    #[serde(default, alias = "hashStorageKeys")]
+    pub hash_storage_keys: bool,
+
+    /// File holding the secret storage keys are hashed with
+    #[serde(
+        default,
+        alias = "storageKeySecretPath",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub storage_key_secret_path: Option<String>,
+
     /// Rate limiting configuration
+    #[serde(default)]
     pub rate_limiting: RateLimitConfig,
 }
 
@@ -567,6 +976,11 @@ pub struct RemoteConfig {
     /// Default fetch flag
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default_fetch: Option<bool>,
+
+    /// Branches fetched by a plain `fetch`, as refspecs
+    /// (e.g. "+refs/heads/main:refs/remotes/origin/main"). Empty fetches all branches.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fetch_refspecs: Vec<String>,
 }
 
 impl RemoteConfig {
@@ -577,7 +991,47 @@ impl RemoteConfig {
             fetch: None,
             push: None,
             default_fetch: Some(true),
+            fetch_refspecs: Vec::new(),
+        }
+    }
+
+    /// Build the refspec that tracks a single branch of a remote
+    pub fn branch_refspec(remote_name: &str, branch: &str) -> String {
+        format!(
+            "+refs/heads/{}:refs/remotes/{}/{}",
+            branch, remote_name, branch
+        )
+    }
+
+    /// Check whether a plain fetch should include `branch`
+    ///
+    /// Refspec sources may end in `*` to match a branch prefix.
+    pub fn fetches_branch(&self, branch: &str) -> bool {
+        if self.fetch_refspecs.is_empty() {
+            return true;
+        }
+
+        let ref_name = format!("refs/heads/{}", branch);
+        self.fetch_refspecs.iter().any(|refspec| {
+            let source = refspec.trim_start_matches('+');
+            let source = source.split(':').next().unwrap_or(source);
+            match source.strip_suffix('*') {
+                Some(prefix) => ref_name.starts_with(prefix),
+                None => ref_name == source,
+            }
+        })
+    }
+
+    /// Start tracking `branch` on plain fetches (no-op if already fetched)
+    ///
+    /// Returns true if a refspec was added.
+    pub fn track_branch(&mut self, remote_name: &str, branch: &str) -> bool {
+        if self.fetches_branch(branch) {
+            return false;
         }
+        self.fetch_refspecs
+            .push(Self::branch_refspec(remote_name, branch));
+        true
     }
 
     /// Get the effective fetch URL
@@ -625,6 +1079,23 @@ impl BranchConfig {
             merge: merge.into(),
         }
     }
+
+    /// Upstream branch name without the `refs/heads/` prefix (e.g. "main")
+    pub fn merge_branch(&self) -> &str {
+        self.merge
+            .strip_prefix("refs/heads/")
+            .unwrap_or(&self.merge)
+    }
+
+    /// Local remote-tracking ref for the upstream (e.g. "refs/remotes/origin/main")
+    pub fn tracking_ref(&self) -> String {
+        format!("refs/remotes/{}/{}", self.remote, self.merge_branch())
+    }
+
+    /// Short display name for the upstream (e.g. "origin/main")
+    pub fn display_name(&self) -> String {
+        format!("{}/{}", self.remote, self.merge_branch())
+    }
 }
 
 /// Branch protection rules
@@ -669,6 +1140,452 @@ impl BranchProtection {
     }
 }
 
+/// External merge tool configuration
+///
+/// Commands are run through the shell with `BASE`, `LOCAL`, `REMOTE` and
+/// `MERGED` set in the environment to the paths of the common ancestor, our
+/// version, their version and the working-tree file to write the result to:
+///
+/// ```toml
+/// [mergetool]
+/// cmd = "meld \"$LOCAL\" \"$BASE\" \"$REMOTE\" --output \"$MERGED\""
+///
+/// [mergetool.types]
+/// psd = "photoshop-merge \"$LOCAL\" \"$REMOTE\" \"$MERGED\""
+/// text = "vimdiff \"$LOCAL\" \"$MERGED\" \"$REMOTE\""
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct MergeToolConfig {
+    /// Default tool command, used when no per-type command matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cmd: Option<String>,
+
+    /// Per-type tool commands, keyed by object type (e.g. "psd", "png") or
+    /// category (e.g. "image", "text")
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub types: HashMap<String, String>,
+}
+
+impl MergeToolConfig {
+    /// Get the tool command for a file, trying `keys` in order before the default
+    ///
+    /// Keys are matched case-insensitively, so callers can pass the most specific
+    /// type first followed by broader categories.
+    pub fn command_for(&self, keys: &[&str]) -> Option<&str> {
+        keys.iter()
+            .find_map(|key| {
+                self.types
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                    .map(|(_, cmd)| cmd.as_str())
+            })
+            .or(self.cmd.as_deref())
+    }
+}
+
+/// Automatic conflict resolution for `mediagit merge`
+///
+/// Paths marked `merge=regenerate` in `.mediagitattributes` keep our version
+/// during the merge and are rebuilt afterwards by `regenerate`, run through
+/// the shell from the repository root:
+///
+/// ```toml
+/// [merge]
+/// regenerate = "npm install --package-lock-only"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct MergeConfig {
+    /// Command that rebuilds `merge=regenerate` paths after a merge; the
+    /// paths are passed one per line in `MEDIAGIT_REGENERATE_PATHS`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regenerate: Option<String>,
+}
+
+/// Outbound HTTP proxy configuration
+///
+/// Applies to the S3-compatible storage backends and to remote transfers.
+/// Settings left empty fall back to `HTTPS_PROXY` / `NO_PROXY`:
+///
+/// ```toml
+/// [proxy]
+/// url = "http://proxy.corp.example:3128"
+/// username = "build-bot"
+/// password = "s3cret"
+/// no_proxy = ["localhost", "minio.internal", ".corp.example"]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ProxyConfig {
+    /// Proxy URL; unset means use the environment (or connect directly)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Username for proxy basic authentication
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// Password for proxy basic authentication
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
+    /// Hosts that bypass the proxy, in addition to `NO_PROXY`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+}
+
+/// Defaults for `mediagit push`
+///
+/// ```toml
+/// [push]
+/// auto_setup_remote = true
+/// fsck_objects = true
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct PushConfig {
+    /// Push a branch with no upstream as if `-u` had been given, so new
+    /// branches start tracking their remote counterpart automatically
+    #[serde(default, alias = "autoSetupRemote")]
+    pub auto_setup_remote: bool,
+
+    /// Verify every outgoing object (content and connectivity) before
+    /// uploading, so local corruption is never pushed
+    #[serde(default, alias = "fsckObjects")]
+    pub fsck_objects: bool,
+}
+
+/// Garbage collection settings
+///
+/// ```toml
+/// [gc]
+/// prune_expire = "2.weeks.ago"
+///
+/// [[gc.thin_media]]
+/// pattern = "renders/*.exr"
+/// keep_every = 10
+/// keep_last = 5
+///
+/// [gc.retention]
+/// max_depth = 50
+/// max_age = "1.year.ago"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GcConfig {
+    /// Unreachable data written more recently than this is not pruned
+    ///
+    /// A Git-style expiry: `"2.weeks.ago"`, `"3.days"`, `"now"` (no grace
+    /// period) or `"never"` (never prune).
+    #[serde(default = "default_prune_expire", alias = "pruneExpire")]
+    pub prune_expire: String,
+
+    /// Rules for `gc --thin-media`, which drops intermediate versions of
+    /// large files from history
+    #[serde(default, alias = "thinMedia", skip_serializing_if = "Vec::is_empty")]
+    pub thin_media: Vec<ThinMediaRule>,
+
+    /// How much history `gc --roll-up` keeps
+    #[serde(default, skip_serializing_if = "RetentionConfig::is_unset")]
+    pub retention: RetentionConfig,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self {
+            prune_expire: default_prune_expire(),
+            thin_media: Vec::new(),
+            retention: RetentionConfig::default(),
+        }
+    }
+}
+
+/// History `gc --roll-up` keeps; everything older is cut off and pruned
+///
+/// Commits at a branch or tag are always kept. With both limits set, a
+/// commit must satisfy both to be kept.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RetentionConfig {
+    /// Keep commits fewer than this many parent links below a branch or tag
+    #[serde(default, alias = "maxDepth", skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+
+    /// Keep commits made within this Git-style expiry, e.g. `"1.year.ago"`
+    #[serde(default, alias = "maxAge", skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<String>,
+}
+
+impl RetentionConfig {
+    /// Whether neither limit is configured, so the section is left out of new configs
+    fn is_unset(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Whether any limit is set
+    pub fn is_enabled(&self) -> bool {
+        self.max_depth.is_some() || self.max_age().ok().flatten().is_some()
+    }
+
+    /// How old a kept commit may be, or `None` for no age limit
+    pub fn max_age(&self) -> crate::ConfigResult<Option<Duration>> {
+        match &self.max_age {
+            Some(expiry) => parse_expiry(expiry).map_err(|reason| {
+                crate::ConfigError::invalid_value("gc.retention.max_age", reason)
+            }),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Which historical versions of matching files `gc --thin-media` keeps
+///
+/// Versions of a path are numbered from 1 in the order they first appear in
+/// history. A version is kept if its number is a multiple of `keep_every`,
+/// if it is one of the last `keep_last`, or if a branch or tag still points
+/// at it; every other version is dropped.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ThinMediaRule {
+    /// `.mediagitattributes`-style glob selecting the files the rule covers
+    pub pattern: String,
+
+    /// Keep every Nth version (0 keeps none on this basis)
+    #[serde(default, alias = "keepEvery")]
+    pub keep_every: usize,
+
+    /// Keep the most recent N versions
+    #[serde(default, alias = "keepLast")]
+    pub keep_last: usize,
+}
+
+impl GcConfig {
+    /// The prune grace period, or `None` if unreachable data is never pruned
+    pub fn prune_grace(&self) -> crate::ConfigResult<Option<Duration>> {
+        parse_expiry(&self.prune_expire)
+            .map_err(|reason| crate::ConfigError::invalid_value("gc.prune_expire", reason))
+    }
+}
+
+/// Parse a Git-style expiry into the age it names
+///
+/// Accepts `now`, `never` (returned as `None`) and `<count>.<unit>` with an
+/// optional `.ago` suffix, where units run from seconds to years and `.` may
+/// also be a space: `"2.weeks.ago"`, `"90 minutes"`, `"1.day"`.
+pub fn parse_expiry(expiry: &str) -> Result<Option<Duration>, String> {
+    let expiry = expiry.trim().to_ascii_lowercase();
+    match expiry.as_str() {
+        "now" => return Ok(Some(Duration::ZERO)),
+        "never" => return Ok(None),
+        _ => {}
+    }
+
+    let invalid = || {
+        format!(
+            "invalid expiry '{}' (expected e.g. \"2.weeks.ago\", \"now\" or \"never\")",
+            expiry
+        )
+    };
+
+    let mut parts = expiry
+        .split(|c: char| c == '.' || c.is_whitespace())
+        .filter(|part| !part.is_empty());
+    let count: u64 = parts
+        .next()
+        .and_then(|count| count.parse().ok())
+        .ok_or_else(invalid)?;
+    let unit_secs = match parts.next().ok_or_else(invalid)?.trim_end_matches('s') {
+        "second" | "sec" => 1,
+        "minute" | "min" => 60,
+        "hour" => 60 * 60,
+        "day" => 24 * 60 * 60,
+        "week" => 7 * 24 * 60 * 60,
+        "month" => 30 * 24 * 60 * 60,
+        "year" => 365 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    match (parts.next(), parts.next()) {
+        (None, None) | (Some("ago"), None) => {}
+        _ => return Err(invalid()),
+    }
+
+    Ok(Some(Duration::from_secs(count.saturating_mul(unit_secs))))
+}
+
+fn default_prune_expire() -> String {
+    "2.weeks.ago".to_string()
+}
+
+/// Resource limits for repacking
+///
+/// Bound the CPU and memory a repack may use, so a maintenance run on a
+/// large repository does not starve a server. The counterparts of Git's
+/// `pack.threads`, `pack.window` and `pack.windowMemory`.
+///
+/// ```toml
+/// [pack]
+/// threads = 2
+/// window = 10
+/// window_memory = 67108864
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackConfig {
+    /// Objects read and compressed concurrently (0 = one per CPU)
+    #[serde(default)]
+    pub threads: usize,
+
+    /// Recently packed objects tried as delta bases (0 = no deltas)
+    #[serde(default = "default_pack_window")]
+    pub window: usize,
+
+    /// Bytes the delta window may hold (0 = unlimited)
+    #[serde(default = "default_pack_window_memory", alias = "windowMemory")]
+    pub window_memory: u64,
+}
+
+impl Default for PackConfig {
+    fn default() -> Self {
+        Self {
+            threads: 0,
+            window: default_pack_window(),
+            window_memory: default_pack_window_memory(),
+        }
+    }
+}
+
+fn default_pack_window() -> usize {
+    10
+}
+
+fn default_pack_window_memory() -> u64 {
+    256 * 1024 * 1024
+}
+
+/// Diff settings
+///
+/// ```toml
+/// [diff]
+/// rename_limit = 1000
+/// rename_similarity = 0.5
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DiffConfig {
+    /// Most deleted or added files compared when detecting renames by similarity
+    ///
+    /// Larger changesets only get exact-content rename detection. 0 means
+    /// no limit.
+    #[serde(default = "default_rename_limit", alias = "renameLimit")]
+    pub rename_limit: usize,
+
+    /// Minimum content similarity (0.0-1.0) for a changed file to count as renamed
+    #[serde(default = "default_rename_similarity", alias = "renameSimilarity")]
+    pub rename_similarity: f64,
+}
+
+impl Default for DiffConfig {
+    fn default() -> Self {
+        Self {
+            rename_limit: default_rename_limit(),
+            rename_similarity: default_rename_similarity(),
+        }
+    }
+}
+
+fn default_rename_limit() -> usize {
+    1000
+}
+
+fn default_rename_similarity() -> f64 {
+    0.5
+}
+
+/// Tree limits
+///
+/// Guards against corrupt or hostile trees, for example from a pushed pack.
+///
+/// ```toml
+/// [trees]
+/// max_depth = 1024
+/// max_entries = 1000000
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TreeLimitsConfig {
+    /// Deepest directory nesting checkout and fsck descend into
+    #[serde(default = "default_max_tree_depth", alias = "maxDepth")]
+    pub max_depth: usize,
+
+    /// Most entries a single directory's tree may hold
+    #[serde(default = "default_max_tree_entries", alias = "maxEntries")]
+    pub max_entries: usize,
+}
+
+impl Default for TreeLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: default_max_tree_depth(),
+            max_entries: default_max_tree_entries(),
+        }
+    }
+}
+
+/// Object layout
+///
+/// ```toml
+/// [objects]
+/// format = "git"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ObjectsConfig {
+    /// Layout used when writing new commits and trees
+    #[serde(default)]
+    pub format: ObjectFormat,
+}
+
+/// Storage quota
+///
+/// Writes that would take the repository's storage past `max_bytes` fail
+/// with a quota error. Usage is counted once per command, before its first
+/// write.
+///
+/// ```toml
+/// [quota]
+/// max_bytes = 107374182400
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct QuotaConfig {
+    /// Most bytes the repository may store (unset means no quota)
+    #[serde(default, alias = "maxBytes", skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+}
+
+impl QuotaConfig {
+    /// Whether no quota is configured, so the section is left out of new configs
+    fn is_unset(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Layout of commit and tree objects
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectFormat {
+    /// Compact binary encoding
+    #[default]
+    Native,
+    /// Git's commit and tree layout, readable by Git object parsers
+    Git,
+}
+
+fn default_sftp_port() -> u16 {
+    22
+}
+
+fn default_sftp_connections() -> usize {
+    4
+}
+
+fn default_max_tree_depth() -> usize {
+    1024
+}
+
+fn default_max_tree_entries() -> usize {
+    1_000_000
+}
+
 fn default_min_approvals() -> u32 {
     1
 }
@@ -707,7 +1624,11 @@ fn default_level() -> u32 {
 }
 
 fn default_min_size() -> u64 {
-    1024 // 1KB
+    64
+}
+
+fn default_brotli_window() -> u32 {
+    22
 }
 
 fn default_file_permissions() -> String {
@@ -730,6 +1651,14 @@ fn default_cache_type() -> String {
     "memory".to_string()
 }
 
+fn default_max_concurrent_uploads() -> usize {
+    16
+}
+
+fn default_max_in_flight_bytes() -> u64 {
+    268435456 // 256MB
+}
+
 fn default_cache_size() -> u64 {
     536870912 // 512MB
 }
@@ -811,6 +1740,16 @@ impl Default for Config {
             remotes: HashMap::new(),
             branches: HashMap::new(),
             protected_branches: HashMap::new(),
+            mergetool: MergeToolConfig::default(),
+            merge: MergeConfig::default(),
+            proxy: ProxyConfig::default(),
+            push: PushConfig::default(),
+            gc: GcConfig::default(),
+            pack: PackConfig::default(),
+            diff: DiffConfig::default(),
+            trees: TreeLimitsConfig::default(),
+            objects: ObjectsConfig::default(),
+            quota: QuotaConfig::default(),
             custom: HashMap::new(),
         }
     }
@@ -836,6 +1775,8 @@ impl Default for FileSystemStorage {
             create_dirs: true,
             sync: false,
             file_permissions: "0644".to_string(),
+            max_concurrent_ops: None,
+            alternates: None,
         }
     }
 }
@@ -846,12 +1787,24 @@ impl Default for CompressionConfig {
             enabled: true,
             algorithm: CompressionAlgorithm::Zstd,
             level: 3,
-            min_size: 1024,
+            min_size: default_min_size(),
+            dictionary: true,
+            tier: CompressionTier::Standard,
+            brotli: BrotliConfig::default(),
             algorithms: HashMap::new(),
         }
     }
 }
 
+impl Default for BrotliConfig {
+    fn default() -> Self {
+        BrotliConfig {
+            window: default_brotli_window(),
+            quality: None,
+        }
+    }
+}
+
 impl Default for PerformanceConfig {
     fn default() -> Self {
         PerformanceConfig {
@@ -860,6 +1813,7 @@ impl Default for PerformanceConfig {
             cache: CacheConfig::default(),
             connection_pool: ConnectionPoolConfig::default(),
             timeouts: TimeoutConfig::default(),
+            uploads: UploadConfig::default(),
         }
     }
 }
@@ -898,6 +1852,15 @@ impl Default for TimeoutConfig {
     }
 }
 
+impl Default for UploadConfig {
+    fn default() -> Self {
+        UploadConfig {
+            max_concurrent: default_max_concurrent_uploads(),
+            max_in_flight_bytes: default_max_in_flight_bytes(),
+        }
+    }
+}
+
 impl Default for ObservabilityConfig {
     fn default() -> Self {
         ObservabilityConfig {
@@ -905,6 +1868,7 @@ impl Default for ObservabilityConfig {
             log_format: "json".to_string(),
             tracing_enabled: true,
             sample_rate: 0.1,
+            module_levels: HashMap::new(),
             metrics: MetricsConfig::default(),
         }
     }
@@ -932,6 +1896,8 @@ impl Default for SecurityConfig {
             cors_origins: vec!["http://localhost:3000".to_string()],
             encryption_at_rest: false,
             encryption_key_path: None,
+            hash_storage_keys: false,
+            storage_key_secret_path: None,
             rate_limiting: RateLimitConfig::default(),
         }
     }
@@ -967,4 +1933,533 @@ mod tests {
         let deserialized: Config = serde_json::from_str(&json).unwrap();
         assert_eq!(config, deserialized);
     }
+
+    #[test]
+    fn test_proxy_config() {
+        let config: Config = toml::from_str(
+            r#"
+[proxy]
+url = "http://proxy:3128"
+username = "bot"
+no_proxy = ["localhost", ".internal"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.proxy.url.as_deref(), Some("http://proxy:3128"));
+        assert_eq!(config.proxy.username.as_deref(), Some("bot"));
+        assert!(config.proxy.password.is_none());
+        assert_eq!(config.proxy.no_proxy, vec!["localhost", ".internal"]);
+        assert_eq!(Config::default().proxy, ProxyConfig::default());
+    }
+
+    #[test]
+    fn test_branch_config_tracking_ref() {
+        let upstream = BranchConfig::new("origin", "refs/heads/feature/lighting");
+        assert_eq!(upstream.merge_branch(), "feature/lighting");
+        assert_eq!(
+            upstream.tracking_ref(),
+            "refs/remotes/origin/feature/lighting"
+        );
+        assert_eq!(upstream.display_name(), "origin/feature/lighting");
+    }
+
+    #[test]
+    fn test_push_config() {
+        assert!(!Config::default().push.auto_setup_remote);
+
+        for toml in [
+            "[push]\nauto_setup_remote = true\n",
+            "[push]\nautoSetupRemote = true\n",
+        ] {
+            let config: Config = toml::from_str(toml).unwrap();
+            assert!(config.push.auto_setup_remote);
+        }
+
+        assert!(!Config::default().push.fsck_objects);
+        let config: Config = toml::from_str("[push]\nfsckObjects = true\n").unwrap();
+        assert!(config.push.fsck_objects);
+    }
+
+    #[test]
+    fn test_pack_config() {
+        let defaults = Config::default().pack;
+        assert_eq!(defaults.threads, 0);
+        assert_eq!(defaults.window, 10);
+        assert_eq!(defaults.window_memory, 256 * 1024 * 1024);
+
+        let config: Config = toml::from_str(
+            "[pack]
+threads = 2
+windowMemory = 1048576
+",
+        )
+        .unwrap();
+        assert_eq!(config.pack.threads, 2);
+        assert_eq!(config.pack.window, 10);
+        assert_eq!(config.pack.window_memory, 1024 * 1024);
+    }
+
+    #[test]
+    fn test_storage_max_concurrent_ops() {
+        assert_eq!(Config::default().storage.max_concurrent_ops(), None);
+
+        let config: Config = toml::from_str(
+            r#"
+[storage]
+backend = "s3"
+bucket = "assets"
+region = "us-east-1"
+maxConcurrentOps = 16
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.storage.max_concurrent_ops(), Some(16));
+    }
+
+    #[test]
+    fn test_s3_signing_region() {
+        use crate::Validator;
+
+        let parse = |extra: &str| -> S3Storage {
+            let toml = format!(
+                "[storage]\nbackend = \"s3\"\nbucket = \"assets\"\nregion = \"eu-central-2\"\n{}",
+                extra
+            );
+            match toml::from_str::<Config>(&toml).unwrap().storage {
+                StorageConfig::S3(s3) => s3,
+                other => panic!("expected S3 storage, got {:?}", other),
+            }
+        };
+
+        assert_eq!(parse("").signing_region(), "eu-central-2");
+        let s3 = parse("signingRegion = \"us-east-1\"\n");
+        assert_eq!(s3.signing_region(), "us-east-1");
+        assert!(s3.validate().is_ok());
+        assert!(parse("signing_region = \"\"\n").validate().is_err());
+    }
+
+    #[test]
+    fn test_s3_tls_options() {
+        use crate::Validator;
+
+        let parse = |extra: &str| -> S3Storage {
+            let toml = format!(
+                "[storage]\nbackend = \"s3\"\nbucket = \"assets\"\nregion = \"us-east-1\"\n{}",
+                extra
+            );
+            match toml::from_str::<Config>(&toml).unwrap().storage {
+                StorageConfig::S3(s3) => s3,
+                other => panic!("expected S3 storage, got {:?}", other),
+            }
+        };
+
+        let s3 = parse("");
+        assert_eq!(s3.ca_bundle, None);
+        assert!(!s3.insecure_skip_verify);
+
+        let s3 = parse("caBundle = \"/etc/ssl/minio-ca.pem\"\ninsecureSkipVerify = true\n");
+        assert_eq!(s3.ca_bundle.as_deref(), Some("/etc/ssl/minio-ca.pem"));
+        assert!(s3.insecure_skip_verify);
+        assert!(s3.validate().is_ok());
+        assert!(parse("ca_bundle = \"\"\n").validate().is_err());
+    }
+
+    #[test]
+    fn test_s3_storage_classes() {
+        use crate::Validator;
+
+        let parse = |extra: &str| -> S3Storage {
+            let toml = format!(
+                "[storage]\nbackend = \"s3\"\nbucket = \"assets\"\nregion = \"us-east-1\"\n{}",
+                extra
+            );
+            match toml::from_str::<Config>(&toml).unwrap().storage {
+                StorageConfig::S3(s3) => s3,
+                other => panic!("expected S3 storage, got {:?}", other),
+            }
+        };
+
+        let s3 =
+            parse("storageClass = \"intelligent_tiering\"\npackStorageClass = \"GLACIER_IR\"\n");
+        assert_eq!(s3.storage_class.as_deref(), Some("intelligent_tiering"));
+        assert_eq!(s3.pack_storage_class.as_deref(), Some("GLACIER_IR"));
+        assert!(s3.validate().is_ok());
+        assert!(parse("storage_class = \"COLD\"\n").validate().is_err());
+        assert!(parse("pack_storage_class = \"\"\n").validate().is_err());
+    }
+
+    #[test]
+    fn test_s3_server_side_encryption() {
+        use crate::Validator;
+
+        let parse = |extra: &str| -> S3Storage {
+            let toml = format!(
+                "[storage]\nbackend = \"s3\"\nbucket = \"assets\"\nregion = \"us-east-1\"\n{}",
+                extra
+            );
+            match toml::from_str::<Config>(&toml).unwrap().storage {
+                StorageConfig::S3(s3) => s3,
+                other => panic!("expected S3 storage, got {:?}", other),
+            }
+        };
+
+        for algorithm in ["AES256", "aws:kms", "aws:kms:alias/mediagit", "SSE-C"] {
+            let s3 = parse(&format!(
+                "encryption = true\nencryption_algorithm = \"{}\"\n",
+                algorithm
+            ));
+            assert!(s3.validate().is_ok(), "{}", algorithm);
+        }
+        let s3 = parse("encryption_algorithm = \"SSE-C\"\nsseCustomerKey = \"a2V5\"\n");
+        assert_eq!(s3.sse_customer_key.as_deref(), Some("a2V5"));
+        assert!(parse("encryption_algorithm = \"DES\"\n")
+            .validate()
+            .is_err());
+        assert!(parse("sse_customer_key = \"\"\n").validate().is_err());
+    }
+
+    #[test]
+    fn test_azure_block_uploads() {
+        use crate::Validator;
+
+        let parse = |extra: &str| -> AzureStorage {
+            let toml = format!(
+                "[storage]\nbackend = \"azure\"\naccount_name = \"studio\"\ncontainer = \"media\"\naccount_key = \"key\"\n{}",
+                extra
+            );
+            match toml::from_str::<Config>(&toml).unwrap().storage {
+                StorageConfig::Azure(azure) => azure,
+                other => panic!("expected Azure storage, got {:?}", other),
+            }
+        };
+
+        let azure = parse("");
+        assert_eq!(azure.block_upload_threshold, None);
+        assert_eq!(azure.max_concurrent_blocks, None);
+
+        let azure = parse("blockUploadThreshold = 67108864\nmax_concurrent_blocks = 16\n");
+        assert_eq!(azure.block_upload_threshold, Some(64 * 1024 * 1024));
+        assert_eq!(azure.max_concurrent_blocks, Some(16));
+        assert!(azure.validate().is_ok());
+
+        assert!(parse("block_upload_threshold = 0\n").validate().is_err());
+        assert!(parse("maxConcurrentBlocks = 0\n").validate().is_err());
+    }
+
+    #[test]
+    fn test_sftp_storage() {
+        use crate::Validator;
+
+        let parse = |extra: &str| -> SftpStorage {
+            let toml = format!(
+                "[storage]\nbackend = \"sftp\"\nhost = \"nas.local\"\nusername = \"mediagit\"\nbase_path = \"/volume1/archive\"\n{}",
+                extra
+            );
+            match toml::from_str::<Config>(&toml).unwrap().storage {
+                StorageConfig::Sftp(sftp) => sftp,
+                other => panic!("expected SFTP storage, got {:?}", other),
+            }
+        };
+
+        let sftp = parse("privateKeyPath = \"/home/me/.ssh/id_ed25519\"\n");
+        assert_eq!(sftp.port, 22);
+        assert_eq!(sftp.max_connections, 4);
+        assert_eq!(sftp.host_key_checking, HostKeyChecking::Strict);
+        assert!(sftp.validate().is_ok());
+
+        // Credentials are required, and only one kind
+        assert!(parse("").validate().is_err());
+        assert!(
+            parse("password = \"pw\"\nprivate_key_path = \"id_ed25519\"\n")
+                .validate()
+                .is_err()
+        );
+
+        let pinned = parse("password = \"pw\"\nhost_key_checking = \"fingerprint\"\n");
+        assert!(pinned.validate().is_err());
+        let pinned = parse(
+            "password = \"pw\"\nhost_key_checking = \"fingerprint\"\nhost_key_fingerprint = \"SHA256:abc\"\n",
+        );
+        assert!(pinned.validate().is_ok());
+        assert_eq!(
+            parse("password = \"pw\"\nhostKeyChecking = \"accept-new\"\n").host_key_checking,
+            HostKeyChecking::AcceptNew
+        );
+    }
+
+    #[test]
+    fn test_multi_backend_storage() {
+        use crate::Validator;
+
+        let parse = |extra: &str| -> MultiBackendStorage {
+            let toml = format!(
+                "[storage]\nbackend = \"multi\"\nprimary = \"s3\"\nreplicas = [\"nas\"]\n{}\n\
+                 [storage.backends.s3]\nbackend = \"s3\"\nbucket = \"media\"\nregion = \"us-east-1\"\n\
+                 [storage.backends.nas]\nbackend = \"filesystem\"\nbase_path = \"/mnt/nas\"\n",
+                extra
+            );
+            match toml::from_str::<Config>(&toml).unwrap().storage {
+                StorageConfig::Multi(multi) => multi,
+                other => panic!("expected multi-backend storage, got {:?}", other),
+            }
+        };
+
+        let multi = parse("");
+        assert_eq!(multi.write_policy, ReplicaWritePolicy::All);
+        assert_eq!(multi.backend_names().collect::<Vec<_>>(), ["s3", "nas"]);
+        assert!(matches!(multi.backend("s3").unwrap(), StorageConfig::S3(_)));
+        assert!(matches!(
+            multi.backend("nas").unwrap(),
+            StorageConfig::FileSystem(_)
+        ));
+        assert!(multi.backend("gcs").is_err());
+        assert!(multi.validate().is_ok());
+        assert_eq!(
+            parse("writePolicy = \"best-effort\"").write_policy,
+            ReplicaWritePolicy::BestEffort
+        );
+
+        let mut nested = parse("write_policy = \"majority\"");
+        nested.backends.insert(
+            "nas".to_string(),
+            serde_json::json!({"backend": "multi", "primary": "s3", "backends": {}}),
+        );
+        assert!(nested.validate().is_err());
+
+        let mut invalid = parse("");
+        invalid.backends.insert(
+            "nas".to_string(),
+            serde_json::json!({"backend": "filesystem"}),
+        );
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_multi_backend_routes() {
+        use crate::Validator;
+
+        let toml = "[storage]\nbackend = \"multi\"\nprimary = \"local\"\n\
+                    [storage.routes]\n\"packs/\" = \"s3\"\n\"previews/\" = \"cdn\"\n\
+                    [storage.backends.local]\nbackend = \"filesystem\"\nbase_path = \"./data\"\n\
+                    [storage.backends.s3]\nbackend = \"s3\"\nbucket = \"packs\"\nregion = \"us-east-1\"\n\
+                    [storage.backends.cdn]\nbackend = \"s3\"\nbucket = \"previews\"\nregion = \"us-east-1\"\n";
+        let multi = match toml::from_str::<Config>(toml).unwrap().storage {
+            StorageConfig::Multi(multi) => multi,
+            other => panic!("expected multi-backend storage, got {:?}", other),
+        };
+        assert_eq!(multi.routes["packs/"], "s3");
+        assert_eq!(
+            multi.routed_backend_names().collect::<Vec<_>>(),
+            ["cdn", "s3"]
+        );
+        assert!(multi.validate().is_ok());
+
+        let mut unknown = multi.clone();
+        unknown
+            .routes
+            .insert("thumbnails/".to_string(), "gcs".to_string());
+        assert!(unknown.validate().is_err());
+
+        let mut everything = multi.clone();
+        everything.routes.insert(String::new(), "s3".to_string());
+        assert!(everything.validate().is_err());
+    }
+
+    #[test]
+    fn test_storage_key_hashing_config() {
+        use crate::Validator;
+
+        let config: Config = toml::from_str(
+            "[security]\nhashStorageKeys = true\nstorageKeySecretPath = \".mediagit/key-secret\"\n",
+        )
+        .unwrap();
+        assert!(config.security.hash_storage_keys);
+        assert_eq!(
+            config.security.storage_key_secret_path.as_deref(),
+            Some(".mediagit/key-secret")
+        );
+        assert!(config.security.validate().is_ok());
+
+        let config: Config = toml::from_str("[security]\nhash_storage_keys = true\n").unwrap();
+        assert!(config.security.validate().is_err());
+    }
+
+    #[test]
+    fn test_gc_config() {
+        use crate::Validator;
+
+        let config = Config::default();
+        assert_eq!(config.gc.prune_expire, "2.weeks.ago");
+        assert_eq!(
+            config.gc.prune_grace().unwrap(),
+            Some(Duration::from_secs(14 * 24 * 60 * 60))
+        );
+
+        let config: Config = toml::from_str("[gc]\npruneExpire = \"now\"\n").unwrap();
+        assert_eq!(config.gc.prune_grace().unwrap(), Some(Duration::ZERO));
+
+        let config: Config = toml::from_str("[gc]\nprune_expire = \"soon\"\n").unwrap();
+        assert!(config.gc.prune_grace().is_err());
+
+        let config: Config = toml::from_str(
+            "[[gc.thin_media]]\npattern = \"*.exr\"\nkeepEvery = 10\nkeep_last = 5\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.gc.thin_media,
+            vec![ThinMediaRule {
+                pattern: "*.exr".to_string(),
+                keep_every: 10,
+                keep_last: 5,
+            }]
+        );
+        assert!(config.validate().is_ok());
+
+        let config: Config = toml::from_str("[[gc.thin_media]]\npattern = \"*.exr\"\n").unwrap();
+        assert!(config.validate().is_err());
+
+        assert!(!Config::default().gc.retention.is_enabled());
+        let config: Config =
+            toml::from_str("[gc.retention]\nmaxDepth = 50\nmax_age = \"1.year.ago\"\n").unwrap();
+        assert_eq!(config.gc.retention.max_depth, Some(50));
+        assert_eq!(
+            config.gc.retention.max_age().unwrap(),
+            Some(Duration::from_secs(365 * 24 * 60 * 60))
+        );
+        assert!(config.gc.retention.is_enabled());
+        assert!(config.validate().is_ok());
+
+        let config: Config = toml::from_str("[gc.retention]\nmax_depth = 0\n").unwrap();
+        assert!(config.validate().is_err());
+        let config: Config = toml::from_str("[gc.retention]\nmax_age = \"soon\"\n").unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_diff_config() {
+        let config = Config::default();
+        assert_eq!(config.diff.rename_limit, 1000);
+        assert_eq!(config.diff.rename_similarity, 0.5);
+
+        let config: Config =
+            toml::from_str("[diff]\nrenameLimit = 50\nrename_similarity = 0.8\n").unwrap();
+        assert_eq!(config.diff.rename_limit, 50);
+        assert_eq!(config.diff.rename_similarity, 0.8);
+    }
+
+    #[test]
+    fn test_objects_config() {
+        let config = Config::default();
+        assert_eq!(config.objects.format, ObjectFormat::Native);
+
+        let config: Config = toml::from_str("[objects]\nformat = \"git\"\n").unwrap();
+        assert_eq!(config.objects.format, ObjectFormat::Git);
+
+        assert!(toml::from_str::<Config>("[objects]\nformat = \"svn\"\n").is_err());
+    }
+
+    #[test]
+    fn test_quota_config() {
+        assert_eq!(Config::default().quota.max_bytes, None);
+
+        let config: Config = toml::from_str("[quota]\nmaxBytes = 1073741824\n").unwrap();
+        assert_eq!(config.quota.max_bytes, Some(1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_parse_expiry() {
+        let hour = Duration::from_secs(60 * 60);
+        assert_eq!(parse_expiry("never").unwrap(), None);
+        assert_eq!(parse_expiry("1.hour.ago").unwrap(), Some(hour));
+        assert_eq!(parse_expiry("90 minutes").unwrap(), Some(hour * 3 / 2));
+        assert_eq!(parse_expiry("3.days").unwrap(), Some(hour * 72));
+        assert_eq!(parse_expiry("1.month.ago").unwrap(), Some(hour * 720));
+
+        for invalid in ["", "2", "weeks.ago", "2.fortnights", "2.weeks.ago.now"] {
+            assert!(parse_expiry(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_compression_min_size() {
+        assert_eq!(Config::default().compression.min_size, 64);
+
+        let config: Config = toml::from_str("[compression]\nmin_compress_size = 256\n").unwrap();
+        assert_eq!(config.compression.min_size, 256);
+        assert!(config.compression.dictionary);
+
+        let config: Config = toml::from_str("[compression]\ndictionary = false\n").unwrap();
+        assert!(!config.compression.dictionary);
+    }
+
+    #[test]
+    fn test_compression_tier() {
+        assert_eq!(
+            Config::default().compression.tier,
+            CompressionTier::Standard
+        );
+
+        let config: Config = toml::from_str("[compression]\ntier = \"archival\"\n").unwrap();
+        assert_eq!(config.compression.tier, CompressionTier::Archival);
+
+        assert!(toml::from_str::<Config>("[compression]\ntier = \"deep\"\n").is_err());
+    }
+
+    #[test]
+    fn test_compression_brotli() {
+        let brotli = Config::default().compression.brotli;
+        assert_eq!(brotli.window, 22);
+        assert_eq!(brotli.quality, None);
+
+        let config: Config =
+            toml::from_str("[compression.brotli]\nwindow = 24\nquality = 9\n").unwrap();
+        assert_eq!(config.compression.brotli.window, 24);
+        assert_eq!(config.compression.brotli.quality, Some(9));
+
+        let config: Config = toml::from_str("[compression.brotli]\nquality = 5\n").unwrap();
+        assert_eq!(config.compression.brotli.window, 22);
+    }
+
+    #[test]
+    fn test_mergetool_command_lookup() {
+        let config: Config = toml::from_str(
+            r#"
+[mergetool]
+cmd = "default-tool"
+
+[mergetool.types]
+PSD = "psd-tool"
+text = "text-tool"
+"#,
+        )
+        .unwrap();
+
+        let tools = &config.mergetool;
+        assert_eq!(
+            tools.command_for(&["psd", "creativeproject"]),
+            Some("psd-tool")
+        );
+        assert_eq!(tools.command_for(&["json", "text"]), Some("text-tool"));
+        assert_eq!(tools.command_for(&["png", "image"]), Some("default-tool"));
+        assert_eq!(MergeToolConfig::default().command_for(&["text"]), None);
+    }
+
+    #[test]
+    fn test_remote_fetch_refspecs() {
+        let mut remote = RemoteConfig::new("http://localhost:3000/repo");
+        assert!(remote.fetches_branch("anything"));
+
+        remote.fetch_refspecs = vec![RemoteConfig::branch_refspec("origin", "main")];
+        assert!(remote.fetches_branch("main"));
+        assert!(!remote.fetches_branch("feature"));
+
+        assert!(remote.track_branch("origin", "feature"));
+        assert!(!remote.track_branch("origin", "feature"));
+        assert!(remote.fetches_branch("feature"));
+
+        remote.fetch_refspecs = vec!["+refs/heads/release/*:refs/remotes/origin/release/*".into()];
+        assert!(remote.fetches_branch("release/1.0"));
+        assert!(!remote.fetches_branch("main"));
+    }
 }
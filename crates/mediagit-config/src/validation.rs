@@ -15,6 +15,18 @@ use crate::error::{ConfigError, ConfigResult};
 use crate::schema::*;
 use std::path::Path;
 
+/// S3 storage class names accepted for `storage_class` and
+/// `pack_storage_class`
+const S3_STORAGE_CLASSES: &[&str] = &[
+    "STANDARD",
+    "STANDARD_IA",
+    "ONEZONE_IA",
+    "INTELLIGENT_TIERING",
+    "GLACIER_IR",
+    "GLACIER",
+    "DEEP_ARCHIVE",
+];
+
 /// Validator for configuration settings
 pub trait Validator {
     fn validate(&self) -> ConfigResult<()>;
@@ -28,6 +40,10 @@ impl Validator for Config {
         self.performance.validate()?;
         self.observability.validate()?;
         self.security.validate()?;
+        self.gc.validate()?;
+        self.diff.validate()?;
+        self.trees.validate()?;
+        self.quota.validate()?;
         Ok(())
     }
 }
@@ -63,11 +79,19 @@ impl Validator for AppConfig {
 
 impl Validator for StorageConfig {
     fn validate(&self) -> ConfigResult<()> {
+        if self.max_concurrent_ops() == Some(0) {
+            return Err(ConfigError::invalid_value(
+                "storage.max_concurrent_ops",
+                "must be greater than 0",
+            ));
+        }
+
         match self {
             StorageConfig::FileSystem(fs) => fs.validate(),
             StorageConfig::S3(s3) => s3.validate(),
             StorageConfig::Azure(azure) => azure.validate(),
             StorageConfig::GCS(gcs) => gcs.validate(),
+            StorageConfig::Sftp(sftp) => sftp.validate(),
             StorageConfig::Multi(multi) => multi.validate(),
         }
     }
@@ -89,6 +113,13 @@ impl Validator for FileSystemStorage {
             ));
         }
 
+        if self.alternates.as_deref().is_some_and(str::is_empty) {
+            return Err(ConfigError::invalid_value(
+                "storage.alternates",
+                "must be a directory path",
+            ));
+        }
+
         Ok(())
     }
 }
@@ -103,6 +134,20 @@ impl Validator for S3Storage {
             return Err(ConfigError::MissingRequired("storage.region".to_string()));
         }
 
+        if self.signing_region.as_deref() == Some("") {
+            return Err(ConfigError::invalid_value(
+                "storage.signing_region",
+                "signing region cannot be empty",
+            ));
+        }
+
+        if self.ca_bundle.as_deref() == Some("") {
+            return Err(ConfigError::invalid_value(
+                "storage.ca_bundle",
+                "CA bundle path cannot be empty",
+            ));
+        }
+
         // S3 bucket names must be 3-63 characters long
         if self.bucket.len() < 3 || self.bucket.len() > 63 {
             return Err(ConfigError::invalid_value(
@@ -127,6 +172,7 @@ impl Validator for S3Storage {
         if self.encryption_algorithm != "AES256"
             && self.encryption_algorithm != "aws:kms"
             && !self.encryption_algorithm.starts_with("aws:kms:")
+            && self.encryption_algorithm != "SSE-C"
         {
             return Err(ConfigError::invalid_value(
                 "storage.encryption_algorithm",
@@ -134,6 +180,32 @@ impl Validator for S3Storage {
             ));
         }
 
+        if self.sse_customer_key.as_deref() == Some("") {
+            return Err(ConfigError::invalid_value(
+                "storage.sse_customer_key",
+                "SSE-C key cannot be empty",
+            ));
+        }
+
+        for (field, class) in [
+            ("storage.storage_class", &self.storage_class),
+            ("storage.pack_storage_class", &self.pack_storage_class),
+        ] {
+            if let Some(class) = class {
+                let name = class.trim().to_ascii_uppercase().replace('-', "_");
+                if !S3_STORAGE_CLASSES.contains(&name.as_str()) {
+                    return Err(ConfigError::invalid_value(
+                        field,
+                        format!(
+                            "unknown storage class '{}' (expected one of: {})",
+                            class,
+                            S3_STORAGE_CLASSES.join(", ")
+                        ),
+                    ));
+                }
+            }
+        }
+
         Ok(())
     }
 }
@@ -167,6 +239,20 @@ impl Validator for AzureStorage {
             ));
         }
 
+        if self.block_upload_threshold == Some(0) {
+            return Err(ConfigError::invalid_value(
+                "storage.block_upload_threshold",
+                "must be greater than 0",
+            ));
+        }
+
+        if self.max_concurrent_blocks == Some(0) {
+            return Err(ConfigError::invalid_value(
+                "storage.max_concurrent_blocks",
+                "must be greater than 0",
+            ));
+        }
+
         Ok(())
     }
 }
@@ -187,6 +273,66 @@ impl Validator for GCSStorage {
     }
 }
 
+impl Validator for SftpStorage {
+    fn validate(&self) -> ConfigResult<()> {
+        if self.host.is_empty() {
+            return Err(ConfigError::MissingRequired("storage.host".to_string()));
+        }
+
+        if self.username.is_empty() {
+            return Err(ConfigError::MissingRequired("storage.username".to_string()));
+        }
+
+        if self.port == 0 {
+            return Err(ConfigError::invalid_value(
+                "storage.port",
+                "must be greater than 0",
+            ));
+        }
+
+        if self.max_connections == 0 {
+            return Err(ConfigError::invalid_value(
+                "storage.max_connections",
+                "must be greater than 0",
+            ));
+        }
+
+        // Exactly one way to authenticate
+        match (&self.password, &self.private_key_path) {
+            (None, None) => {
+                return Err(ConfigError::ValidationError(
+                    "SFTP storage requires either password or private_key_path".to_string(),
+                ));
+            }
+            (Some(_), Some(_)) => {
+                return Err(ConfigError::ValidationError(
+                    "SFTP storage takes password or private_key_path, not both".to_string(),
+                ));
+            }
+            _ => {}
+        }
+
+        match (self.host_key_checking, &self.host_key_fingerprint) {
+            (HostKeyChecking::Fingerprint, None) => {
+                return Err(ConfigError::MissingRequired(
+                    "storage.host_key_fingerprint".to_string(),
+                ));
+            }
+            (HostKeyChecking::Fingerprint, Some(fingerprint))
+                if !fingerprint.starts_with("SHA256:") =>
+            {
+                return Err(ConfigError::invalid_value(
+                    "storage.host_key_fingerprint",
+                    "must be a SHA256 fingerprint (SHA256:...)",
+                ));
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
 impl Validator for MultiBackendStorage {
     fn validate(&self) -> ConfigResult<()> {
         if self.primary.is_empty() {
@@ -213,6 +359,33 @@ impl Validator for MultiBackendStorage {
             }
         }
 
+        for (prefix, name) in &self.routes {
+            if prefix.is_empty() {
+                return Err(ConfigError::invalid_value(
+                    "storage.routes",
+                    "route prefixes must not be empty; use the primary for other keys",
+                ));
+            }
+            if !self.backends.contains_key(name) {
+                return Err(ConfigError::invalid_value(
+                    format!("storage.routes.{}", prefix),
+                    format!("routed backend '{}' not found in backends", name),
+                ));
+            }
+        }
+
+        for name in self.backend_names().chain(self.routed_backend_names()) {
+            match self.backend(name)? {
+                StorageConfig::Multi(_) => {
+                    return Err(ConfigError::invalid_value(
+                        format!("storage.backends.{}", name),
+                        "multi-backend storage cannot be nested",
+                    ));
+                }
+                backend => backend.validate()?,
+            }
+        }
+
         Ok(())
     }
 }
@@ -244,6 +417,19 @@ impl Validator for CompressionConfig {
             }
         }
 
+        if !(10..=24).contains(&self.brotli.window) {
+            return Err(ConfigError::invalid_value(
+                "compression.brotli.window",
+                "brotli window must be between 10 and 24",
+            ));
+        }
+        if self.brotli.quality.is_some_and(|quality| quality > 11) {
+            return Err(ConfigError::invalid_value(
+                "compression.brotli.quality",
+                "brotli quality must be between 0 and 11",
+            ));
+        }
+
         // Validate algorithm configs
         for (algo_name, algo_config) in &self.algorithms {
             if let Some(level) = algo_config.level {
@@ -297,6 +483,27 @@ impl Validator for PerformanceConfig {
         self.cache.validate()?;
         self.connection_pool.validate()?;
         self.timeouts.validate()?;
+        self.uploads.validate()?;
+
+        Ok(())
+    }
+}
+
+impl Validator for UploadConfig {
+    fn validate(&self) -> ConfigResult<()> {
+        if self.max_concurrent == 0 {
+            return Err(ConfigError::invalid_value(
+                "performance.uploads.max_concurrent",
+                "must be greater than 0",
+            ));
+        }
+
+        if self.max_in_flight_bytes == 0 {
+            return Err(ConfigError::invalid_value(
+                "performance.uploads.max_in_flight_bytes",
+                "must be greater than 0",
+            ));
+        }
 
         Ok(())
     }
@@ -390,6 +597,15 @@ impl Validator for ObservabilityConfig {
             ));
         }
 
+        for (module, level) in &self.module_levels {
+            if module.is_empty() || !(valid_levels.contains(&level.as_str()) || level == "off") {
+                return Err(ConfigError::invalid_value(
+                    format!("observability.module_levels.{}", module),
+                    format!("must be one of: off, {}", valid_levels.join(", ")),
+                ));
+            }
+        }
+
         let valid_formats = ["json", "text"];
         if !valid_formats.contains(&self.log_format.as_str()) {
             return Err(ConfigError::invalid_value(
@@ -473,18 +689,18 @@ impl Validator for SecurityConfig {
             }
         }
 
-        if self.encryption_at_rest {
-            if self.encryption_key_path.is_none() {
-                return Err(ConfigError::MissingRequired(
-                    "security.encryption_key_path".to_string(),
-                ));
-            }
+        // The key and secret paths may be relative to the repository, so they
+        // are only read (and checked) when the storage backend is opened
+        if self.encryption_at_rest && self.encryption_key_path.is_none() {
+            return Err(ConfigError::MissingRequired(
+                "security.encryption_key_path".to_string(),
+            ));
+        }
 
-            if let Some(key_path) = &self.encryption_key_path {
-                if !Path::new(key_path).exists() {
-                    return Err(ConfigError::FileNotFound(key_path.clone().into()));
-                }
-            }
+        if self.hash_storage_keys && self.storage_key_secret_path.is_none() {
+            return Err(ConfigError::MissingRequired(
+                "security.storage_key_secret_path".to_string(),
+            ));
         }
 
         self.rate_limiting.validate()?;
@@ -514,6 +730,84 @@ impl Validator for RateLimitConfig {
     }
 }
 
+impl Validator for GcConfig {
+    fn validate(&self) -> ConfigResult<()> {
+        self.prune_grace()?;
+        for rule in &self.thin_media {
+            if rule.pattern.is_empty() {
+                return Err(ConfigError::invalid_value(
+                    "gc.thin_media.pattern",
+                    "cannot be empty",
+                ));
+            }
+            // A rule keeping nothing would drop every version not at a ref
+            if rule.keep_every == 0 && rule.keep_last == 0 {
+                return Err(ConfigError::invalid_value(
+                    "gc.thin_media",
+                    format!(
+                        "rule for '{}' must set keep_every or keep_last",
+                        rule.pattern
+                    ),
+                ));
+            }
+        }
+        // Depth counts from the ref's own commit, which is always kept
+        if self.retention.max_depth == Some(0) {
+            return Err(ConfigError::invalid_value(
+                "gc.retention.max_depth",
+                "must be at least 1",
+            ));
+        }
+        self.retention.max_age()?;
+        Ok(())
+    }
+}
+
+impl Validator for TreeLimitsConfig {
+    fn validate(&self) -> ConfigResult<()> {
+        if self.max_depth == 0 {
+            return Err(ConfigError::invalid_value(
+                "trees.max_depth",
+                "must be at least 1",
+            ));
+        }
+        if self.max_entries == 0 {
+            return Err(ConfigError::invalid_value(
+                "trees.max_entries",
+                "must be at least 1",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Validator for QuotaConfig {
+    fn validate(&self) -> ConfigResult<()> {
+        if self.max_bytes == Some(0) {
+            return Err(ConfigError::invalid_value(
+                "quota.max_bytes",
+                "must be at least 1",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Validator for DiffConfig {
+    fn validate(&self) -> ConfigResult<()> {
+        if !(0.0..=1.0).contains(&self.rename_similarity) {
+            return Err(ConfigError::invalid_value(
+                "diff.rename_similarity",
+                format!(
+                    "must be between 0.0 and 1.0, got {}",
+                    self.rename_similarity
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Helper function to validate octal string format
 fn is_valid_octal(s: &str) -> bool {
     if s.starts_with('0') && s.len() == 4 {
@@ -563,6 +857,40 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_brotli_validation() {
+        let mut config = Config::default();
+        config.compression.brotli.window = 9;
+        assert!(config.validate().is_err());
+        config.compression.brotli.window = 24;
+        assert!(config.validate().is_ok());
+        config.compression.brotli.quality = Some(12);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_rename_similarity_validation() {
+        let mut config = Config::default();
+        config.diff.rename_similarity = 1.5;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_tree_limits_validation() {
+        let mut config = Config::default();
+        config.trees.max_entries = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_quota_validation() {
+        let mut config = Config::default();
+        config.quota.max_bytes = Some(0);
+        assert!(config.validate().is_err());
+        config.quota.max_bytes = Some(1);
+        assert!(config.validate().is_ok());
+    }
+
     #[test]
     fn test_cache_validation() {
         let mut config = Config::default();
@@ -570,10 +898,37 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_upload_limits_validation() {
+        let mut config = Config::default();
+        config.performance.uploads.max_concurrent = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.performance.uploads.max_in_flight_bytes = 0;
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_log_level_validation() {
         let mut config = Config::default();
         config.observability.log_level = "invalid".to_string();
         assert!(config.validate().is_err());
     }
+
+    #[test]
+    fn test_module_level_validation() {
+        let mut config = Config::default();
+        config
+            .observability
+            .module_levels
+            .insert("mediagit_storage".to_string(), "debug".to_string());
+        assert!(config.validate().is_ok());
+
+        config
+            .observability
+            .module_levels
+            .insert("mediagit_protocol".to_string(), "loud".to_string());
+        assert!(config.validate().is_err());
+    }
 }
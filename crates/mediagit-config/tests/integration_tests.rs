@@ -334,9 +334,16 @@ fn test_validation_s3_bucket_name_too_short() {
             access_key_id: None,
             secret_access_key: None,
             endpoint: None,
+            signing_region: None,
+            ca_bundle: None,
+            insecure_skip_verify: false,
             prefix: String::new(),
             encryption: false,
             encryption_algorithm: "AES256".to_string(),
+            sse_customer_key: None,
+            storage_class: None,
+            pack_storage_class: None,
+            max_concurrent_ops: None,
         }),
         ..Default::default()
     };
@@ -54,6 +54,43 @@ pub const FILTER_DRIVER_NAME: &str = "mediagit";
 /// Files smaller than this will be stored in Git normally
 pub const MIN_FILE_SIZE_THRESHOLD: u64 = 1024 * 1024; // 1 MB
 
+/// Git config entries under `filter.<driver>` that register the driver
+const DRIVER_CONFIG: &[(&str, &str)] = &[
+    ("clean", "mediagit filter-clean %f"),
+    ("smudge", "mediagit filter-smudge %f"),
+    // Git aborts instead of committing unfiltered content if the filter fails
+    ("required", "true"),
+];
+
+/// A Git config entry that installing the filter driver sets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigChange {
+    /// Config key (e.g., `filter.mediagit.clean`)
+    pub key: String,
+
+    /// Value the driver needs
+    pub value: String,
+
+    /// Value currently set, if any
+    pub current: Option<String>,
+}
+
+/// Whether the filter driver is installed in a repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallStatus {
+    /// Every driver entry is set to the expected value
+    Installed,
+
+    /// No driver entries are set
+    NotInstalled,
+
+    /// Some driver entries are set correctly; the listed keys are missing
+    Partial(Vec<String>),
+
+    /// Driver entries set to values other than MediaGit's
+    Conflicting(Vec<ConfigChange>),
+}
+
 /// Configuration for the filter driver
 #[derive(Debug, Clone)]
 pub struct FilterConfig {
@@ -117,30 +154,90 @@ impl FilterDriver {
             repo_path
         );
 
-        let repo = Repository::open(repo_path)
-            .map_err(|e| GitError::RepositoryNotFound(format!("{}: {}", repo_path.display(), e)))?;
-
+        let repo = open_repository(repo_path)?;
         let mut config = repo.config()?;
 
-        // Configure clean filter
-        config.set_str(
-            &format!("filter.{}.clean", FILTER_DRIVER_NAME),
-            "mediagit filter-clean %f",
-        )?;
-
-        // Configure smudge filter
-        config.set_str(
-            &format!("filter.{}.smudge", FILTER_DRIVER_NAME),
-            "mediagit filter-smudge %f",
-        )?;
-
-        // Mark as required (Git will abort if filter fails)
-        config.set_bool(&format!("filter.{}.required", FILTER_DRIVER_NAME), true)?;
+        for change in Self::plan(&config)? {
+            debug!("Setting {} = {}", change.key, change.value);
+            config.set_str(&change.key, &change.value)?;
+        }
 
         info!("Filter driver installed successfully");
         Ok(())
     }
 
+    /// Lists the Git config entries [`install`](Self::install) would add or
+    /// modify, without writing anything
+    ///
+    /// Entries already set to the expected value are left out, so an empty
+    /// plan means the driver is fully installed. Installing never touches
+    /// `.gitattributes`; patterns are added separately with
+    /// [`track_pattern`](Self::track_pattern).
+    ///
+    /// # Arguments
+    ///
+    /// * `repo_path` - Path to the Git repository
+    pub fn install_plan(&self, repo_path: &Path) -> GitResult<Vec<ConfigChange>> {
+        let repo = open_repository(repo_path)?;
+        Self::plan(&repo.config()?)
+    }
+
+    /// Reports whether the filter driver is correctly installed
+    ///
+    /// Reads the repository's effective Git config, so entries set globally
+    /// count as installed.
+    ///
+    /// # Arguments
+    ///
+    /// * `repo_path` - Path to the Git repository
+    pub fn check_install(&self, repo_path: &Path) -> GitResult<InstallStatus> {
+        let plan = self.install_plan(repo_path)?;
+        let conflicting: Vec<ConfigChange> = plan
+            .iter()
+            .filter(|change| change.current.is_some())
+            .cloned()
+            .collect();
+
+        Ok(if plan.is_empty() {
+            InstallStatus::Installed
+        } else if !conflicting.is_empty() {
+            InstallStatus::Conflicting(conflicting)
+        } else if plan.len() == DRIVER_CONFIG.len() {
+            InstallStatus::NotInstalled
+        } else {
+            InstallStatus::Partial(plan.into_iter().map(|change| change.key).collect())
+        })
+    }
+
+    /// Driver entries in `config` that are missing or set to another value
+    fn plan(config: &git2::Config) -> GitResult<Vec<ConfigChange>> {
+        let mut changes = Vec::new();
+        for (name, value) in DRIVER_CONFIG {
+            let key = format!("filter.{}.{}", FILTER_DRIVER_NAME, name);
+            let current = match config.get_string(&key) {
+                Ok(current) => Some(current),
+                Err(e) if e.code() == git2::ErrorCode::NotFound => None,
+                Err(e) => return Err(e.into()),
+            };
+            let up_to_date = match &current {
+                // `required` is a boolean, which Git also accepts as yes/on/1
+                Some(current) if *name == "required" => {
+                    git2::Config::parse_bool(current.as_str()).unwrap_or(false)
+                }
+                Some(current) => current == value,
+                None => false,
+            };
+            if !up_to_date {
+                changes.push(ConfigChange {
+                    key,
+                    value: value.to_string(),
+                    current,
+                });
+            }
+        }
+        Ok(changes)
+    }
+
     /// Configures .gitattributes to track a file pattern
     ///
     /// # Arguments
@@ -489,6 +586,12 @@ impl FilterDriver {
     }
 }
 
+/// Opens the Git repository at `repo_path`
+fn open_repository(repo_path: &Path) -> GitResult<Repository> {
+    Repository::open(repo_path)
+        .map_err(|e| GitError::RepositoryNotFound(format!("{}: {}", repo_path.display(), e)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -58,5 +58,5 @@ pub mod filter;
 pub mod pointer;
 
 pub use error::{GitError, GitResult};
-pub use filter::{FilterConfig, FilterDriver};
+pub use filter::{ConfigChange, FilterConfig, FilterDriver, InstallStatus};
 pub use pointer::PointerFile;
@@ -13,7 +13,7 @@
 
 //! Integration tests for Git filter driver
 
-use mediagit_git::{FilterConfig, FilterDriver, PointerFile};
+use mediagit_git::{FilterConfig, FilterDriver, InstallStatus, PointerFile};
 use std::fs;
 use std::process::Command;
 use tempfile::TempDir;
@@ -101,6 +101,86 @@ fn test_filter_driver_install() {
     assert!(config_value.contains("mediagit filter-clean"));
 }
 
+#[test]
+fn test_filter_driver_install_dry_run_and_check() {
+    let temp_dir = init_git_repo();
+    let driver =
+        FilterDriver::new(FilterConfig::default()).expect("Failed to create filter driver");
+    let git_config = temp_dir.path().join(".git").join("config");
+    let before = fs::read_to_string(&git_config).expect("Failed to read git config");
+
+    // Dry run lists every entry and writes nothing
+    let plan = driver
+        .install_plan(temp_dir.path())
+        .expect("Failed to plan install");
+    let keys: Vec<&str> = plan.iter().map(|change| change.key.as_str()).collect();
+    assert_eq!(
+        keys,
+        [
+            "filter.mediagit.clean",
+            "filter.mediagit.smudge",
+            "filter.mediagit.required"
+        ]
+    );
+    assert!(plan.iter().all(|change| change.current.is_none()));
+    assert_eq!(fs::read_to_string(&git_config).unwrap(), before);
+    assert!(!temp_dir.path().join(".gitattributes").exists());
+
+    assert_eq!(
+        driver.check_install(temp_dir.path()).unwrap(),
+        InstallStatus::NotInstalled
+    );
+
+    driver
+        .install(temp_dir.path())
+        .expect("Failed to install filter driver");
+    assert_eq!(
+        driver.check_install(temp_dir.path()).unwrap(),
+        InstallStatus::Installed
+    );
+    assert!(driver.install_plan(temp_dir.path()).unwrap().is_empty());
+}
+
+#[test]
+fn test_filter_driver_check_partial_and_conflicting() {
+    let temp_dir = init_git_repo();
+    let driver =
+        FilterDriver::new(FilterConfig::default()).expect("Failed to create filter driver");
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(temp_dir.path())
+            .output()
+            .expect("Failed to run git");
+        assert!(output.status.success());
+    };
+
+    git(&[
+        "config",
+        "filter.mediagit.clean",
+        "mediagit filter-clean %f",
+    ]);
+    assert_eq!(
+        driver.check_install(temp_dir.path()).unwrap(),
+        InstallStatus::Partial(vec![
+            "filter.mediagit.smudge".to_string(),
+            "filter.mediagit.required".to_string(),
+        ])
+    );
+
+    git(&["config", "filter.mediagit.smudge", "git-lfs smudge -- %f"]);
+    git(&["config", "filter.mediagit.required", "yes"]);
+    match driver.check_install(temp_dir.path()).unwrap() {
+        InstallStatus::Conflicting(changes) => {
+            assert_eq!(changes.len(), 1);
+            assert_eq!(changes[0].key, "filter.mediagit.smudge");
+            assert_eq!(changes[0].current.as_deref(), Some("git-lfs smudge -- %f"));
+            assert_eq!(changes[0].value, "mediagit filter-smudge %f");
+        }
+        other => panic!("expected conflicting install, got {:?}", other),
+    }
+}
+
 #[test]
 fn test_track_pattern() {
     let temp_dir = init_git_repo();
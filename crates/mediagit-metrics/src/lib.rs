@@ -25,11 +25,11 @@
 //!
 //! # Key Metrics
 //!
-//! - Deduplication ratio (from object database)
+//! - Deduplication ratio (from object database), overall and per object category
 //! - Compression ratios by algorithm
 //! - Operation timing (store/retrieve)
 //! - Cache hit/miss rates
-//! - Storage backend performance
+//! - Storage backend performance, per backend and operation
 //!
 //! # Example
 //!
@@ -54,6 +54,20 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! # Storage Backends
+//!
+//! `MetricsRegistry` implements [`mediagit_storage::MetricsSink`], so an
+//! `InstrumentedBackend` can report every operation to it:
+//!
+//! ```ignore
+//! let storage = InstrumentedBackend::new(backend, "s3").with_metrics(Arc::new(registry.clone()));
+//! ```
+//!
+//! Operations are counted in `mediagit_storage_operations_total`, data in
+//! `mediagit_storage_bytes_total` and latency in
+//! `mediagit_storage_operation_duration_seconds`, all labelled by `backend`
+//! and `operation`, so a dashboard can chart S3 and local latencies apart.
 
 pub mod collector;
 pub mod registry;
@@ -63,7 +77,7 @@ pub mod types;
 pub use collector::MediaGitCollector;
 pub use registry::MetricsRegistry;
 pub use server::MetricsServer;
-pub use types::{CompressionAlgorithm, MetricsConfig, StorageBackend};
+pub use types::{CompressionAlgorithm, MetricsConfig, ObjectCategory, StorageBackend};
 
 // Re-export prometheus types for convenience
 pub use prometheus::{Encoder, TextEncoder};
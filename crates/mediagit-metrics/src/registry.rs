@@ -19,7 +19,7 @@ use prometheus::{
 use std::sync::Arc;
 use tracing::warn;
 
-use crate::types::{CompressionAlgorithm, OperationType, StorageBackend};
+use crate::types::{CompressionAlgorithm, ObjectCategory, OperationType, StorageBackend};
 
 /// Central metrics registry for MediaGit operations
 ///
@@ -42,6 +42,12 @@ struct MetricsRegistryInner {
     dedup_writes_avoided: Counter,
     /// Current deduplication ratio (0.0-1.0)
     dedup_ratio: Gauge,
+    /// Bytes written by object category
+    dedup_category_bytes_written: CounterVec,
+    /// Bytes stored by object category
+    dedup_category_bytes_stored: CounterVec,
+    /// Deduplication ratio by object category
+    dedup_category_ratio: GaugeVec,
 
     // Compression metrics
     /// Compression ratio by algorithm
@@ -74,6 +80,14 @@ struct MetricsRegistryInner {
     backend_latency: HistogramVec,
     /// Backend throughput (bytes/second)
     backend_throughput: GaugeVec,
+
+    // Per-backend storage operation metrics
+    /// Storage operations by backend, operation and outcome
+    storage_operations: CounterVec,
+    /// Bytes read or written by backend and operation
+    storage_bytes: CounterVec,
+    /// Storage operation latency by backend and operation
+    storage_duration: HistogramVec,
 }
 
 impl MetricsRegistry {
@@ -106,6 +120,33 @@ impl MetricsRegistry {
         ))?;
         registry.register(Box::new(dedup_ratio.clone()))?;
 
+        let dedup_category_bytes_written = CounterVec::new(
+            Opts::new(
+                "mediagit_dedup_category_bytes_written_total",
+                "Bytes written including duplicates, by object category",
+            ),
+            &["category"],
+        )?;
+        registry.register(Box::new(dedup_category_bytes_written.clone()))?;
+
+        let dedup_category_bytes_stored = CounterVec::new(
+            Opts::new(
+                "mediagit_dedup_category_bytes_stored_total",
+                "Bytes stored after deduplication, by object category",
+            ),
+            &["category"],
+        )?;
+        registry.register(Box::new(dedup_category_bytes_stored.clone()))?;
+
+        let dedup_category_ratio = GaugeVec::new(
+            Opts::new(
+                "mediagit_dedup_category_ratio",
+                "Deduplication ratio (bytes saved / bytes written) by object category",
+            ),
+            &["category"],
+        )?;
+        registry.register(Box::new(dedup_category_ratio.clone()))?;
+
         // Compression metrics
         let compression_ratio = GaugeVec::new(
             Opts::new(
@@ -212,6 +253,37 @@ impl MetricsRegistry {
         )?;
         registry.register(Box::new(backend_throughput.clone()))?;
 
+        // Per-backend storage operation metrics
+        let storage_operations = CounterVec::new(
+            Opts::new(
+                "mediagit_storage_operations_total",
+                "Storage operations by backend, operation and outcome",
+            ),
+            &["backend", "operation", "outcome"],
+        )?;
+        registry.register(Box::new(storage_operations.clone()))?;
+
+        let storage_bytes = CounterVec::new(
+            Opts::new(
+                "mediagit_storage_bytes_total",
+                "Bytes read or written by storage operations",
+            ),
+            &["backend", "operation"],
+        )?;
+        registry.register(Box::new(storage_bytes.clone()))?;
+
+        let storage_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "mediagit_storage_operation_duration_seconds",
+                "Storage operation latency in seconds",
+            )
+            .buckets(vec![
+                0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+            ]),
+            &["backend", "operation"],
+        )?;
+        registry.register(Box::new(storage_duration.clone()))?;
+
         Ok(Self {
             inner: Arc::new(MetricsRegistryInner {
                 registry,
@@ -219,6 +291,9 @@ impl MetricsRegistry {
                 dedup_bytes_stored,
                 dedup_writes_avoided,
                 dedup_ratio,
+                dedup_category_bytes_written,
+                dedup_category_bytes_stored,
+                dedup_category_ratio,
                 compression_ratio,
                 compression_bytes_saved,
                 compression_original_bytes,
@@ -231,6 +306,9 @@ impl MetricsRegistry {
                 operation_errors,
                 backend_latency,
                 backend_throughput,
+                storage_operations,
+                storage_bytes,
+                storage_duration,
             }),
         })
     }
@@ -259,6 +337,36 @@ impl MetricsRegistry {
         self.update_dedup_ratio();
     }
 
+    /// Record a write for deduplication tracking, attributed to a category
+    ///
+    /// Updates both the overall and the per-category counters.
+    pub fn record_category_dedup_write(&self, category: ObjectCategory, bytes: u64, is_new: bool) {
+        self.record_dedup_write(bytes, is_new);
+
+        let label = category.as_label();
+        let written = self
+            .inner
+            .dedup_category_bytes_written
+            .with_label_values(&[label]);
+        let stored = self
+            .inner
+            .dedup_category_bytes_stored
+            .with_label_values(&[label]);
+
+        written.inc_by(bytes as f64);
+        if is_new {
+            stored.inc_by(bytes as f64);
+        }
+
+        let written = written.get();
+        if written > 0.0 {
+            self.inner
+                .dedup_category_ratio
+                .with_label_values(&[label])
+                .set((written - stored.get()) / written);
+        }
+    }
+
     /// Update deduplication ratio gauge
     fn update_dedup_ratio(&self) {
         let written = self.inner.dedup_bytes_written.get();
@@ -400,6 +508,47 @@ impl MetricsRegistry {
             .with_label_values(&[backend.as_label(), operation.as_label()])
             .set(bytes_per_second);
     }
+
+    // Per-backend storage operation metrics
+
+    /// Record a finished storage backend operation
+    ///
+    /// `backend` and `operation` are the names storage reports, such as
+    /// `s3` and `put`, so each backend gets its own latency series.
+    pub fn record_storage_operation(
+        &self,
+        backend: &str,
+        operation: &str,
+        bytes: u64,
+        duration_secs: f64,
+        success: bool,
+    ) {
+        let outcome = if success { "ok" } else { "error" };
+        self.inner
+            .storage_operations
+            .with_label_values(&[backend, operation, outcome])
+            .inc();
+        self.inner
+            .storage_bytes
+            .with_label_values(&[backend, operation])
+            .inc_by(bytes as f64);
+        self.inner
+            .storage_duration
+            .with_label_values(&[backend, operation])
+            .observe(duration_secs);
+    }
+}
+
+impl mediagit_storage::MetricsSink for MetricsRegistry {
+    fn record(&self, record: &mediagit_storage::OperationRecord<'_>) {
+        self.record_storage_operation(
+            record.backend,
+            record.operation,
+            record.bytes,
+            record.duration.as_secs_f64(),
+            record.success,
+        );
+    }
 }
 
 impl Default for MetricsRegistry {
@@ -414,6 +563,21 @@ impl Default for MetricsRegistry {
                     dedup_bytes_stored: Counter::new("fallback", "fallback").unwrap(),
                     dedup_writes_avoided: Counter::new("fallback", "fallback").unwrap(),
                     dedup_ratio: Gauge::new("fallback", "fallback").unwrap(),
+                    dedup_category_bytes_written: CounterVec::new(
+                        Opts::new("fallback", "fallback"),
+                        &["category"],
+                    )
+                    .unwrap(),
+                    dedup_category_bytes_stored: CounterVec::new(
+                        Opts::new("fallback", "fallback"),
+                        &["category"],
+                    )
+                    .unwrap(),
+                    dedup_category_ratio: GaugeVec::new(
+                        Opts::new("fallback", "fallback"),
+                        &["category"],
+                    )
+                    .unwrap(),
                     compression_ratio: GaugeVec::new(
                         Opts::new("fallback", "fallback"),
                         &["algorithm"],
@@ -462,6 +626,21 @@ impl Default for MetricsRegistry {
                         &["backend", "operation"],
                     )
                     .unwrap(),
+                    storage_operations: CounterVec::new(
+                        Opts::new("fallback", "fallback"),
+                        &["backend", "operation", "outcome"],
+                    )
+                    .unwrap(),
+                    storage_bytes: CounterVec::new(
+                        Opts::new("fallback", "fallback"),
+                        &["backend", "operation"],
+                    )
+                    .unwrap(),
+                    storage_duration: HistogramVec::new(
+                        HistogramOpts::new("fallback", "fallback"),
+                        &["backend", "operation"],
+                    )
+                    .unwrap(),
                 }),
             }
         })
@@ -497,6 +676,39 @@ mod tests {
         assert_eq!(registry.inner.dedup_ratio.get(), 0.5);
     }
 
+    #[test]
+    fn test_category_dedup_metrics() {
+        let registry = MetricsRegistry::new().unwrap();
+
+        registry.record_category_dedup_write(ObjectCategory::CreativeProject, 1000, true);
+        registry.record_category_dedup_write(ObjectCategory::CreativeProject, 1000, true);
+        registry.record_category_dedup_write(ObjectCategory::Text, 100, true);
+        registry.record_category_dedup_write(ObjectCategory::Text, 100, false);
+        registry.record_category_dedup_write(ObjectCategory::Text, 100, false);
+        registry.record_category_dedup_write(ObjectCategory::Text, 100, false);
+
+        let ratio = |label: &str| {
+            registry
+                .inner
+                .dedup_category_ratio
+                .with_label_values(&[label])
+                .get()
+        };
+        assert_eq!(ratio("creative_project"), 0.0);
+        assert_eq!(ratio("text"), 0.75);
+
+        let stored = registry
+            .inner
+            .dedup_category_bytes_stored
+            .with_label_values(&["text"])
+            .get();
+        assert_eq!(stored, 100.0);
+
+        // Overall counters include every category
+        assert_eq!(registry.inner.dedup_bytes_written.get(), 2400.0);
+        assert_eq!(registry.inner.dedup_writes_avoided.get(), 3.0);
+    }
+
     #[test]
     fn test_compression_metrics() {
         let registry = MetricsRegistry::new().unwrap();
@@ -561,4 +773,43 @@ mod tests {
             .get();
         assert_eq!(count, 1.0);
     }
+
+    #[tokio::test]
+    async fn test_storage_metrics_per_backend() {
+        use mediagit_storage::{mock::MockBackend, InstrumentedBackend, StorageBackend};
+
+        let registry = MetricsRegistry::new().unwrap();
+        let sink = Arc::new(registry.clone());
+        let s3 =
+            InstrumentedBackend::new(Arc::new(MockBackend::new()), "s3").with_metrics(sink.clone());
+        let local =
+            InstrumentedBackend::new(Arc::new(MockBackend::new()), "filesystem").with_metrics(sink);
+
+        s3.put("objects/ab", b"frame data").await.unwrap();
+        s3.get("objects/ab").await.unwrap();
+        local.put("objects/ab", b"data").await.unwrap();
+        assert!(local.get("objects/missing").await.is_err());
+
+        let operations = |labels: &[&str]| {
+            registry
+                .inner
+                .storage_operations
+                .with_label_values(labels)
+                .get()
+        };
+        assert_eq!(operations(&["s3", "put", "ok"]), 1.0);
+        assert_eq!(operations(&["s3", "get", "ok"]), 1.0);
+        assert_eq!(operations(&["filesystem", "get", "error"]), 1.0);
+
+        let bytes = |labels: &[&str]| registry.inner.storage_bytes.with_label_values(labels).get();
+        assert_eq!(bytes(&["s3", "put"]), 10.0);
+        assert_eq!(bytes(&["filesystem", "put"]), 4.0);
+
+        let samples = registry
+            .inner
+            .storage_duration
+            .with_label_values(&["s3", "put"])
+            .get_sample_count();
+        assert_eq!(samples, 1);
+    }
 }
@@ -110,6 +110,58 @@ impl CompressionAlgorithm {
     }
 }
 
+/// Object categories for labeling per-category deduplication metrics
+///
+/// Mirrors the high-level categories used for compression so the number of
+/// label values stays fixed regardless of how many file types are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ObjectCategory {
+    /// Raster images and textures
+    Image,
+    /// Video files
+    Video,
+    /// Audio files
+    Audio,
+    /// PDF and similar documents
+    Document,
+    /// Source code, JSON and other text
+    Text,
+    /// Archives
+    Archive,
+    /// Creative application projects (PSD, Blender, DAW sessions)
+    CreativeProject,
+    /// Office documents
+    Office,
+    /// ML models and checkpoints
+    MlSpecialized,
+    /// Database files
+    Database,
+    /// Trees and commits
+    GitObject,
+    /// Anything unrecognised
+    Unknown,
+}
+
+impl ObjectCategory {
+    /// Get string label for Prometheus
+    pub fn as_label(&self) -> &'static str {
+        match self {
+            ObjectCategory::Image => "image",
+            ObjectCategory::Video => "video",
+            ObjectCategory::Audio => "audio",
+            ObjectCategory::Document => "document",
+            ObjectCategory::Text => "text",
+            ObjectCategory::Archive => "archive",
+            ObjectCategory::CreativeProject => "creative_project",
+            ObjectCategory::Office => "office",
+            ObjectCategory::MlSpecialized => "ml",
+            ObjectCategory::Database => "database",
+            ObjectCategory::GitObject => "git_object",
+            ObjectCategory::Unknown => "unknown",
+        }
+    }
+}
+
 /// Operation types for timing metrics
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum OperationType {
@@ -169,6 +221,16 @@ mod tests {
         assert_eq!(CompressionAlgorithm::Brotli.as_label(), "brotli");
     }
 
+    #[test]
+    fn test_object_category_labels() {
+        assert_eq!(ObjectCategory::Image.as_label(), "image");
+        assert_eq!(
+            ObjectCategory::CreativeProject.as_label(),
+            "creative_project"
+        );
+        assert_eq!(ObjectCategory::GitObject.as_label(), "git_object");
+    }
+
     #[test]
     fn test_operation_type_labels() {
         assert_eq!(OperationType::Store.as_label(), "store");
@@ -0,0 +1,155 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+//! Copying objects between backends
+//!
+//! Objects are copied through an [`UploadPool`], which caps how many copies
+//! run at once and how many bytes they hold in memory. An object is only
+//! read from the source once the pool has room for its size, so a fast
+//! source can't get ahead of a slow target.
+
+use crate::state::MigrationState;
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt};
+use mediagit_storage::upload::UploadPool;
+use mediagit_storage::StorageBackend;
+use std::sync::Arc;
+
+/// Copies objects from a source backend to a target backend
+pub struct ObjectCopier {
+    source: Arc<dyn StorageBackend>,
+    target: Arc<dyn StorageBackend>,
+    pool: UploadPool,
+}
+
+impl ObjectCopier {
+    /// Create a copier that uploads to `target` through `pool`
+    pub fn new(
+        source: Arc<dyn StorageBackend>,
+        target: Arc<dyn StorageBackend>,
+        pool: UploadPool,
+    ) -> Self {
+        Self {
+            source,
+            target,
+            pool,
+        }
+    }
+
+    /// Copy a single object
+    pub async fn copy_object(&self, key: &str) -> Result<()> {
+        let size = self
+            .source
+            .head(key)
+            .await
+            .with_context(|| format!("Failed to stat source object: {}", key))?
+            .size;
+        self.pool
+            .run(size, async {
+                let data = self
+                    .source
+                    .get(key)
+                    .await
+                    .with_context(|| format!("Failed to get source object: {}", key))?;
+                self.target
+                    .put(key, &data)
+                    .await
+                    .with_context(|| format!("Failed to put target object: {}", key))
+            })
+            .await
+    }
+
+    /// Copy every key in `keys` that `state` doesn't already list as migrated
+    ///
+    /// Each object is marked migrated or failed in `state` as its copy
+    /// finishes; a failed object doesn't stop the others. Returns the
+    /// number of objects copied.
+    pub async fn copy_all(&self, keys: &[String], state: &mut MigrationState) -> Result<usize> {
+        let pending: Vec<&String> = keys.iter().filter(|key| !state.is_migrated(key)).collect();
+        let mut copies = stream::iter(pending)
+            .map(|key| async move { (key, self.copy_object(key).await) })
+            .buffer_unordered(self.pool.limits().max_concurrent);
+
+        let mut copied = 0;
+        while let Some((key, result)) = copies.next().await {
+            match result {
+                Ok(()) => {
+                    state.mark_migrated(key.clone());
+                    copied += 1;
+                }
+                Err(e) => {
+                    tracing::warn!(key = %key, "Failed to migrate object: {:#}", e);
+                    state.mark_failed(key.clone(), format!("{:#}", e));
+                }
+            }
+        }
+
+        Ok(copied)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mediagit_storage::mock::MockBackend;
+    use mediagit_storage::upload::UploadLimits;
+
+    #[tokio::test]
+    async fn test_copy_all_skips_migrated_objects() {
+        let source = Arc::new(MockBackend::new());
+        let target = Arc::new(MockBackend::new());
+        for i in 0..10 {
+            source
+                .put(&format!("obj{}", i), format!("data {}", i).as_bytes())
+                .await
+                .unwrap();
+        }
+        let keys: Vec<String> = (0..10).map(|i| format!("obj{}", i)).collect();
+
+        let mut state = MigrationState::new(
+            "local".to_string(),
+            "s3".to_string(),
+            keys.len(),
+            serde_json::json!({}),
+        );
+        state.mark_migrated("obj0".to_string());
+
+        let pool = UploadPool::new(UploadLimits::new(3, 16));
+        let copier = ObjectCopier::new(source, target.clone(), pool);
+        let copied = copier.copy_all(&keys, &mut state).await.unwrap();
+
+        assert_eq!(copied, 9);
+        assert_eq!(state.progress(), 1.0);
+        assert!(!target.exists("obj0").await.unwrap());
+        assert_eq!(target.get("obj9").await.unwrap(), b"data 9");
+    }
+
+    #[tokio::test]
+    async fn test_copy_all_records_failures() {
+        let source = Arc::new(MockBackend::new());
+        source.put("present", b"data").await.unwrap();
+        let keys = vec!["present".to_string(), "missing".to_string()];
+
+        let mut state = MigrationState::new(
+            "local".to_string(),
+            "s3".to_string(),
+            keys.len(),
+            serde_json::json!({}),
+        );
+        let copier = ObjectCopier::new(
+            source,
+            Arc::new(MockBackend::new()),
+            UploadPool::new(UploadLimits::default()),
+        );
+        let copied = copier.copy_all(&keys, &mut state).await.unwrap();
+
+        assert_eq!(copied, 1);
+        assert_eq!(state.failed_objects.len(), 1);
+        assert_eq!(state.failed_objects[0].0, "missing");
+    }
+}
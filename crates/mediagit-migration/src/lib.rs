@@ -14,8 +14,10 @@
 #![allow(missing_docs)]
 //! Storage backend migration tool for MediaGit
 
+pub mod copy;
 pub mod state;
 pub mod verify;
 
+pub use copy::ObjectCopier;
 pub use state::MigrationState;
 pub use verify::IntegrityVerifier;
@@ -18,6 +18,8 @@
 //! filter configurations.
 
 use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Errors that can occur during logging configuration
@@ -72,6 +74,10 @@ pub struct LogConfig {
     /// If None, will be determined from RUST_LOG environment variable
     pub level: Option<String>,
 
+    /// Per-module levels applied on top of `level`, e.g.
+    /// `("mediagit_storage", "debug")`
+    pub module_levels: Vec<(String, String)>,
+
     /// Whether to use colored output (only for Pretty format)
     pub use_color: bool,
 
@@ -86,16 +92,74 @@ pub struct LogConfig {
 
     /// Output destination (stderr by default)
     pub output: LogOutput,
+
+    /// Rotation and retention for [`LogOutput::File`]
+    pub rotation: LogRotation,
 }
 
 /// Log output destination
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LogOutput {
     /// Write to standard error
     Stderr,
 
     /// Write to standard output
     Stdout,
+
+    /// Append to a file, rotated according to [`LogConfig::rotation`]
+    File(PathBuf),
+}
+
+/// When a log file is rotated and how many rotated files are kept
+///
+/// The active file keeps its configured name; rotated files get a numeric
+/// suffix, `.1` being the most recent. With no limits set the file is never
+/// rotated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogRotation {
+    /// Rotate once the active file would grow past this many bytes
+    pub max_size: Option<u64>,
+
+    /// Rotate once the active file has been written to for this long
+    pub max_interval: Option<Duration>,
+
+    /// Keep at most this many rotated files
+    pub max_files: Option<usize>,
+
+    /// Delete rotated files last written longer ago than this
+    pub max_age: Option<Duration>,
+
+    /// Gzip rotated files
+    pub compress: bool,
+}
+
+impl LogRotation {
+    /// Rotate by size, keeping at most `max_files` rotated files
+    pub fn by_size(max_size: u64, max_files: usize) -> Self {
+        Self {
+            max_size: Some(max_size),
+            max_files: Some(max_files),
+            ..Self::default()
+        }
+    }
+
+    /// Also rotate once the active file has been written to for `interval`
+    pub fn with_max_interval(mut self, interval: Duration) -> Self {
+        self.max_interval = Some(interval);
+        self
+    }
+
+    /// Delete rotated files older than `max_age`
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Enable or disable gzip compression of rotated files
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
 }
 
 impl Default for LogConfig {
@@ -103,11 +167,13 @@ impl Default for LogConfig {
         LogConfig {
             format: LogFormat::Pretty,
             level: None,
+            module_levels: Vec::new(),
             use_color: true,
             use_timestamps: true,
             include_thread_ids: false,
             include_targets: true,
             output: LogOutput::Stderr,
+            rotation: LogRotation::default(),
         }
     }
 }
@@ -130,6 +196,16 @@ impl LogConfig {
         self
     }
 
+    /// Log `module` and its submodules at `level`, whatever the base level
+    pub fn with_module_level(
+        mut self,
+        module: impl Into<String>,
+        level: impl Into<String>,
+    ) -> Self {
+        self.module_levels.push((module.into(), level.into()));
+        self
+    }
+
     /// Enable or disable color output
     pub fn with_color(mut self, use_color: bool) -> Self {
         self.use_color = use_color;
@@ -160,6 +236,12 @@ impl LogConfig {
         self
     }
 
+    /// Set rotation and retention for file output
+    pub fn with_rotation(mut self, rotation: LogRotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
     /// Get the effective log level from config or environment
     pub fn get_effective_level(&self) -> String {
         self.level
@@ -16,9 +16,13 @@
 //! This module provides functions to initialize the tracing system with
 //! different configurations and output formats.
 
-use crate::config::{LogConfig, LogError, LogFormat, LogOutput};
+use crate::config::{LogConfig, LogError, LogFormat, LogOutput, LogRotation};
+use crate::rotation::RotatingFile;
 use std::io;
+use std::sync::Mutex;
+use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{fmt, EnvFilter, Registry};
@@ -76,18 +80,20 @@ pub fn init_tracing(format: LogFormat, level: Option<&str>) -> Result<(), LogErr
 pub fn init_tracing_with_config(config: LogConfig) -> Result<(), LogError> {
     let env_filter = build_env_filter(&config)?;
     let registry = Registry::default().with(env_filter);
+    // No escape codes in log files
+    let use_color = config.use_color && !matches!(config.output, LogOutput::File(_));
 
     match config.format {
         LogFormat::Pretty => {
             let layer = fmt::layer()
-                .with_writer(get_writer(&config.output))
+                .with_writer(get_writer(&config.output, &config.rotation)?)
                 .with_target(config.include_targets)
                 .with_thread_ids(config.include_thread_ids)
                 .with_thread_names(true)
                 .with_span_events(FmtSpan::ACTIVE)
                 .pretty();
 
-            if config.use_timestamps && config.use_color {
+            if config.use_timestamps && use_color {
                 registry
                     .with(layer.with_timer(fmt::time::SystemTime).with_ansi(true))
                     .init();
@@ -95,7 +101,7 @@ pub fn init_tracing_with_config(config: LogConfig) -> Result<(), LogError> {
                 registry
                     .with(layer.with_timer(fmt::time::SystemTime).with_ansi(false))
                     .init();
-            } else if config.use_color {
+            } else if use_color {
                 registry.with(layer.without_time().with_ansi(true)).init();
             } else {
                 registry.with(layer.without_time().with_ansi(false)).init();
@@ -103,14 +109,14 @@ pub fn init_tracing_with_config(config: LogConfig) -> Result<(), LogError> {
         }
         LogFormat::Compact => {
             let layer = fmt::layer()
-                .with_writer(get_writer(&config.output))
+                .with_writer(get_writer(&config.output, &config.rotation)?)
                 .with_target(config.include_targets)
                 .with_thread_ids(config.include_thread_ids)
                 .with_thread_names(false)
                 .with_span_events(FmtSpan::CLOSE)
                 .compact();
 
-            if config.use_timestamps && config.use_color {
+            if config.use_timestamps && use_color {
                 registry
                     .with(layer.with_timer(fmt::time::SystemTime).with_ansi(true))
                     .init();
@@ -118,7 +124,7 @@ pub fn init_tracing_with_config(config: LogConfig) -> Result<(), LogError> {
                 registry
                     .with(layer.with_timer(fmt::time::SystemTime).with_ansi(false))
                     .init();
-            } else if config.use_color {
+            } else if use_color {
                 registry.with(layer.without_time().with_ansi(true)).init();
             } else {
                 registry.with(layer.without_time().with_ansi(false)).init();
@@ -126,7 +132,7 @@ pub fn init_tracing_with_config(config: LogConfig) -> Result<(), LogError> {
         }
         LogFormat::Json => {
             let layer = fmt::layer()
-                .with_writer(get_writer(&config.output))
+                .with_writer(get_writer(&config.output, &config.rotation)?)
                 .json()
                 .with_target(config.include_targets)
                 .with_thread_ids(config.include_thread_ids)
@@ -147,22 +153,76 @@ pub fn init_tracing_with_config(config: LogConfig) -> Result<(), LogError> {
 }
 
 /// Get the writer for the specified output
-fn get_writer(output: &LogOutput) -> fn() -> Box<dyn io::Write + Send> {
-    match output {
-        LogOutput::Stderr => || Box::new(io::stderr()),
-        LogOutput::Stdout => || Box::new(io::stdout()),
-    }
+fn get_writer(output: &LogOutput, rotation: &LogRotation) -> Result<BoxMakeWriter, LogError> {
+    Ok(match output {
+        LogOutput::Stderr => BoxMakeWriter::new(io::stderr),
+        LogOutput::Stdout => BoxMakeWriter::new(io::stdout),
+        LogOutput::File(path) => {
+            let file = RotatingFile::open(path, rotation.clone())?;
+            BoxMakeWriter::new(Mutex::new(file))
+        }
+    })
 }
 
 /// Build an environment filter for the given configuration
+///
+/// From lowest to highest precedence, the filter combines:
+///
+/// 1. the base level: `level`, else `RUST_LOG`, else `info`
+/// 2. the configured per-module levels
+/// 3. per-module directives in `RUST_LOG` (`mediagit_storage=trace`), so a
+///    one-off run can still change a module the configuration pins
 fn build_env_filter(config: &LogConfig) -> Result<EnvFilter, LogError> {
-    let level_str = config.get_effective_level();
+    let filter = filter_directives(config, std::env::var("RUST_LOG").ok().as_deref())?;
 
-    EnvFilter::try_new(&level_str).map_err(|e| {
-        LogError::ConfigError(format!("Failed to parse log filter '{}': {}", level_str, e))
+    EnvFilter::try_new(&filter).map_err(|e| {
+        LogError::ConfigError(format!("Failed to parse log filter '{}': {}", filter, e))
     })
 }
 
+/// The directive string for `config`, given the value of `RUST_LOG`
+fn filter_directives(config: &LogConfig, rust_log: Option<&str>) -> Result<String, LogError> {
+    let mut directives = vec![config
+        .level
+        .as_deref()
+        .or(rust_log)
+        .unwrap_or("info")
+        .to_string()];
+
+    for (module, level) in &config.module_levels {
+        let valid_module = !module.is_empty()
+            && module
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':' || c == '-');
+        if !valid_module {
+            return Err(LogError::ConfigError(format!(
+                "Invalid module name '{}' in log level overrides",
+                module
+            )));
+        }
+        if level.parse::<LevelFilter>().is_err() {
+            return Err(LogError::InvalidLogLevel(format!(
+                "{} for module {} (expected one of: off, error, warn, info, debug, trace)",
+                level, module
+            )));
+        }
+        directives.push(format!("{}={}", module, level));
+    }
+
+    // Later directives for the same module replace earlier ones
+    if let Some(rust_log) = rust_log {
+        directives.extend(
+            rust_log
+                .split(',')
+                .map(str::trim)
+                .filter(|directive| directive.contains('='))
+                .map(String::from),
+        );
+    }
+
+    Ok(directives.join(","))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,4 +243,81 @@ mod tests {
         let result = build_env_filter(&LogConfig::new().with_level("trace"));
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_module_level_overrides() {
+        use std::sync::{Arc, Mutex};
+        use tracing::{Event, Level, Subscriber};
+        use tracing_subscriber::layer::{Context, Layer};
+
+        /// Records the target and level of every event that gets through
+        struct Recorder(Arc<Mutex<Vec<(String, Level)>>>);
+
+        impl<S: Subscriber> Layer<S> for Recorder {
+            fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+                let meta = event.metadata();
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push((meta.target().to_string(), *meta.level()));
+            }
+        }
+
+        let config = LogConfig::new()
+            .with_level("info")
+            .with_module_level("mediagit_storage", "debug")
+            .with_module_level("mediagit_protocol", "trace")
+            .with_module_level("mediagit_server", "error");
+        let filter = EnvFilter::try_new(filter_directives(&config, None).unwrap()).unwrap();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = Registry::default()
+            .with(filter)
+            .with(Recorder(seen.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(target: "mediagit_storage::s3", "admitted");
+            tracing::trace!(target: "mediagit_storage::s3", "denied");
+            tracing::trace!(target: "mediagit_protocol", "admitted");
+            tracing::warn!(target: "mediagit_server::handlers", "denied");
+            tracing::error!(target: "mediagit_server::handlers", "admitted");
+            tracing::info!(target: "mediagit_versioning", "admitted");
+            tracing::debug!(target: "mediagit_versioning", "denied");
+        });
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                ("mediagit_storage::s3".to_string(), Level::DEBUG),
+                ("mediagit_protocol".to_string(), Level::TRACE),
+                ("mediagit_server::handlers".to_string(), Level::ERROR),
+                ("mediagit_versioning".to_string(), Level::INFO),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_module_level_precedence_and_errors() {
+        let config = LogConfig::new().with_module_level("mediagit_storage", "debug");
+
+        // Module directives in RUST_LOG win over the configured ones
+        assert_eq!(
+            filter_directives(&config, Some("warn,mediagit_storage=trace")).unwrap(),
+            "warn,mediagit_storage=trace,mediagit_storage=debug,mediagit_storage=trace"
+        );
+        assert_eq!(
+            filter_directives(&config.clone().with_level("error"), Some("warn")).unwrap(),
+            "error,mediagit_storage=debug"
+        );
+
+        let bad_level = LogConfig::new().with_module_level("mediagit_storage", "loud");
+        assert!(matches!(
+            build_env_filter(&bad_level),
+            Err(LogError::InvalidLogLevel(_))
+        ));
+        let bad_module = LogConfig::new().with_module_level("mediagit storage", "debug");
+        assert!(matches!(
+            build_env_filter(&bad_module),
+            Err(LogError::ConfigError(_))
+        ));
+    }
 }
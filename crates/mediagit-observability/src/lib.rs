@@ -22,6 +22,7 @@
 //! - **Environment-based Filtering**: Dynamic log level control via `RUST_LOG`
 //! - **Async Context Propagation**: Proper span context in async/tokio runtime
 //! - **Structured Logging**: JSON output for machine-readable logs
+//! - **Log Rotation**: File output rotated by size or age, with bounded retention
 //!
 //! # Example
 //!
@@ -41,9 +42,11 @@
 pub mod config;
 pub mod initialization;
 pub mod macros;
+pub mod rotation;
 
-pub use config::{LogConfig, LogFormat, LogOutput};
+pub use config::{LogConfig, LogFormat, LogOutput, LogRotation};
 pub use initialization::{init_tracing, init_tracing_with_config};
+pub use rotation::RotatingFile;
 
 /// Tracing re-exports for convenience
 pub use tracing::{debug, error, info, span, trace, warn, Level};
@@ -0,0 +1,268 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Size- and time-based rotation of log files.
+//!
+//! [`RotatingFile`] appends to a log file and, when the file would outgrow
+//! [`LogRotation::max_size`] or has been open longer than
+//! [`LogRotation::max_interval`], renames it aside and starts a new one:
+//!
+//! ```text
+//! server.log      <- being written
+//! server.log.1    <- most recent rotation (server.log.1.gz when compressed)
+//! server.log.2
+//! ```
+//!
+//! Rotated files beyond [`LogRotation::max_files`] or older than
+//! [`LogRotation::max_age`] are deleted at each rotation, so a long-running
+//! server's logs stay within a bounded amount of disk.
+
+use crate::config::LogRotation;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+/// Log file writer that rotates and prunes according to a [`LogRotation`]
+#[derive(Debug)]
+pub struct RotatingFile {
+    path: PathBuf,
+    rotation: LogRotation,
+    file: File,
+    size: u64,
+    opened: Instant,
+}
+
+impl RotatingFile {
+    /// Open `path` for appending, creating it and its directory if needed
+    pub fn open(path: impl Into<PathBuf>, rotation: LogRotation) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            rotation,
+            file,
+            size,
+            opened: Instant::now(),
+        })
+    }
+
+    /// Path of the file currently being written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Move the current file aside as `.1` and start a new one
+    pub fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        // Shift older files up first so no rename overwrites another file
+        let mut rotated = self.rotated_files()?;
+        rotated.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+        for (index, path) in rotated {
+            fs::rename(&path, self.rotated_path(index + 1, is_gzip(&path)))?;
+        }
+
+        let newest = self.rotated_path(1, false);
+        fs::rename(&self.path, &newest)?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        self.opened = Instant::now();
+
+        if self.rotation.compress {
+            gzip(&newest, &self.rotated_path(1, true))?;
+        }
+        self.prune()
+    }
+
+    /// Delete rotated files beyond the retention limits
+    fn prune(&self) -> io::Result<()> {
+        let now = SystemTime::now();
+        for (index, path) in self.rotated_files()? {
+            let too_many = self.rotation.max_files.is_some_and(|max| index > max);
+            let too_old = match self.rotation.max_age {
+                Some(max_age) => fs::metadata(&path)?
+                    .modified()?
+                    .checked_add(max_age)
+                    .is_some_and(|expires| expires <= now),
+                None => false,
+            };
+            if too_many || too_old {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rotated files next to the active one, with their index
+    fn rotated_files(&self) -> io::Result<Vec<(usize, PathBuf)>> {
+        let Some(name) = self.path.file_name().and_then(|n| n.to_str()) else {
+            return Ok(Vec::new());
+        };
+        let prefix = format!("{}.", name);
+        let mut files = Vec::new();
+        for entry in fs::read_dir(self.directory())? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let Some(suffix) = file_name.to_str().and_then(|n| n.strip_prefix(&prefix)) else {
+                continue;
+            };
+            let suffix = suffix.strip_suffix(".gz").unwrap_or(suffix);
+            if let Ok(index) = suffix.parse::<usize>() {
+                files.push((index, entry.path()));
+            }
+        }
+        Ok(files)
+    }
+
+    fn rotated_path(&self, index: usize, gzipped: bool) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        if gzipped {
+            name.push(".gz");
+        }
+        PathBuf::from(name)
+    }
+
+    fn directory(&self) -> &Path {
+        match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        }
+    }
+
+    fn needs_rotation(&self, incoming: usize) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+        let too_big = self
+            .rotation
+            .max_size
+            .is_some_and(|max| self.size + incoming as u64 > max);
+        let too_long = self
+            .rotation
+            .max_interval
+            .is_some_and(|interval| self.opened.elapsed() >= interval);
+        too_big || too_long
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.needs_rotation(buf.len()) {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+/// Compress `source` into `target` and remove `source`
+fn gzip(source: &Path, target: &Path) -> io::Result<()> {
+    let mut input = File::open(source)?;
+    let mut encoder = GzEncoder::new(File::create(target)?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    fs::remove_file(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn log_line(n: usize) -> String {
+        format!("{{\"level\":\"INFO\",\"message\":\"request {:04}\"}}\n", n)
+    }
+
+    #[test]
+    fn test_rotates_past_size_and_keeps_max_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("logs/server.log");
+        let line_len = log_line(0).len() as u64;
+
+        // Room for four lines per file, two rotated files kept
+        let mut log = RotatingFile::open(&path, LogRotation::by_size(4 * line_len, 2)).unwrap();
+        for n in 0..20 {
+            log.write_all(log_line(n).as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+
+        let mut names: Vec<String> = fs::read_dir(dir.path().join("logs"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["server.log", "server.log.1", "server.log.2"]);
+
+        // Lines 16-19 are current, 12-15 and 8-11 were rotated, older ones deleted
+        let current = fs::read_to_string(&path).unwrap();
+        assert_eq!(current, (16..20).map(log_line).collect::<String>());
+        let newest = fs::read_to_string(dir.path().join("logs/server.log.1")).unwrap();
+        assert_eq!(newest, (12..16).map(log_line).collect::<String>());
+        let oldest = fs::read_to_string(dir.path().join("logs/server.log.2")).unwrap();
+        assert_eq!(oldest, (8..12).map(log_line).collect::<String>());
+    }
+
+    #[test]
+    fn test_compressed_rotation_and_max_age() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("server.log");
+        let rotation = LogRotation::by_size(1024, 5)
+            .with_max_interval(Duration::ZERO)
+            .with_compression(true);
+
+        let mut log = RotatingFile::open(&path, rotation.clone()).unwrap();
+        log.write_all(log_line(1).as_bytes()).unwrap();
+        log.write_all(log_line(2).as_bytes()).unwrap();
+        log.flush().unwrap();
+
+        // An interval of zero rotates before every write to a non-empty file
+        assert!(!dir.path().join("server.log.1").exists());
+        let mut unpacked = String::new();
+        GzDecoder::new(File::open(dir.path().join("server.log.1.gz")).unwrap())
+            .read_to_string(&mut unpacked)
+            .unwrap();
+        assert_eq!(unpacked, log_line(1));
+        assert_eq!(fs::read_to_string(&path).unwrap(), log_line(2));
+
+        // Already-expired rotations are removed at the next rotation
+        let mut log = RotatingFile::open(&path, rotation.with_max_age(Duration::ZERO)).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        log.write_all(log_line(3).as_bytes()).unwrap();
+        assert!(!dir.path().join("server.log.1.gz").exists());
+        assert!(!dir.path().join("server.log.2.gz").exists());
+        assert_eq!(fs::read_to_string(&path).unwrap(), log_line(3));
+    }
+}
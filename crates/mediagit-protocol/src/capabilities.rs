@@ -0,0 +1,205 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Protocol capability advertisement and negotiation
+//!
+//! Modelled on Git's capability strings. The server lists what it supports in
+//! the `capabilities` of its `info/refs` response; each entry is a name, or
+//! `name=value` for a capability that takes a parameter. The client keeps the
+//! entries it also supports and sends that selection back with its want and
+//! ref-update requests, so both sides know which features the session uses.
+//!
+//! Unknown capabilities are ignored on both sides, which is what lets a new
+//! client talk to an old server and vice versa: each only uses what the other
+//! has advertised. A server that predates capabilities altogether advertises
+//! nothing, and the client falls back to the baseline protocol.
+//!
+//! # Examples
+//!
+//! ```
+//! use mediagit_protocol::capabilities::{self, Capabilities};
+//!
+//! let advertised = Capabilities::parse(["pack-v1", "bitmap-index", "filter=blob:none"]);
+//! let selected = Capabilities::supported().negotiate(&advertised);
+//!
+//! assert!(selected.contains(capabilities::PACK_V1));
+//! assert!(!selected.contains("bitmap-index"));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Objects are exchanged as MediaGit pack files
+pub const PACK_V1: &str = "pack-v1";
+
+/// Large files are transferred as chunks and manifests
+pub const CHUNKED_OBJECTS: &str = "chunked-objects";
+
+/// Objects uploaded under an `X-Push-ID` are quarantined until the ref update
+pub const PUSH_QUARANTINE: &str = "push-quarantine";
+
+/// Ref updates may delete refs
+pub const DELETE_REFS: &str = "delete-refs";
+
+/// Ref updates may be leases, compared against the ref even when forced
+pub const REF_LEASE: &str = "ref-lease";
+
+/// A set of protocol capabilities, each with an optional parameter
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    entries: BTreeMap<String, Option<String>>,
+}
+
+impl Capabilities {
+    /// An empty set, as advertised by servers that predate capabilities
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything this version of MediaGit implements
+    pub fn supported() -> Self {
+        Self::parse([
+            PACK_V1,
+            CHUNKED_OBJECTS,
+            PUSH_QUARANTINE,
+            DELETE_REFS,
+            REF_LEASE,
+        ])
+    }
+
+    /// Parse capability strings of the form `name` or `name=value`
+    ///
+    /// Blank entries are skipped; if a name repeats, the last entry wins.
+    pub fn parse<I, S>(list: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut capabilities = Self::new();
+        for entry in list {
+            let entry = entry.as_ref().trim();
+            if entry.is_empty() {
+                continue;
+            }
+            match entry.split_once('=') {
+                Some((name, value)) => capabilities.insert(name.trim(), Some(value.trim())),
+                None => capabilities.insert(entry, None),
+            }
+        }
+        capabilities
+    }
+
+    /// Add a capability, replacing any previous entry with the same name
+    pub fn insert(&mut self, name: &str, value: Option<&str>) {
+        self.entries
+            .insert(name.to_string(), value.map(str::to_string));
+    }
+
+    /// Whether the capability `name` is in the set
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// The parameter of capability `name`, if it is present and has one
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.entries.get(name)?.as_deref()
+    }
+
+    /// Whether the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of capabilities in the set
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The capabilities both this set and `other` contain
+    ///
+    /// Capabilities only one side knows are dropped. When only one side gives
+    /// a parameter it is kept; when both do and they differ, the capability
+    /// is dropped, as the two sides disagree on how to use it.
+    pub fn negotiate(&self, other: &Capabilities) -> Capabilities {
+        let entries = self
+            .entries
+            .iter()
+            .filter_map(|(name, ours)| {
+                let theirs = other.entries.get(name)?;
+                let value = match (ours, theirs) {
+                    (Some(ours), Some(theirs)) if ours != theirs => return None,
+                    (Some(value), _) | (None, Some(value)) => Some(value.clone()),
+                    (None, None) => None,
+                };
+                Some((name.clone(), value))
+            })
+            .collect();
+        Capabilities { entries }
+    }
+
+    /// The set as capability strings, sorted by name
+    pub fn to_strings(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|(name, value)| match value {
+                Some(value) => format!("{}={}", name, value),
+                None => name.clone(),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_strings().join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        let capabilities = Capabilities::parse(["pack-v1", " filter = blob:none ", "", "pack-v1"]);
+
+        assert_eq!(capabilities.len(), 2);
+        assert!(capabilities.contains("pack-v1"));
+        assert_eq!(capabilities.value("pack-v1"), None);
+        assert_eq!(capabilities.value("filter"), Some("blob:none"));
+        assert_eq!(
+            capabilities.to_strings(),
+            vec!["filter=blob:none", "pack-v1"]
+        );
+        assert_eq!(capabilities.to_string(), "filter=blob:none pack-v1");
+    }
+
+    #[test]
+    fn test_negotiate_parameters() {
+        let client = Capabilities::parse(["compression=zstd", "resume", "thin-pack=v1"]);
+        let server = Capabilities::parse(["compression=zstd", "resume=64k", "thin-pack=v2"]);
+
+        let selected = client.negotiate(&server);
+
+        assert_eq!(selected.value("compression"), Some("zstd"));
+        assert_eq!(selected.value("resume"), Some("64k"));
+        // Both sides parameterise thin-pack, differently
+        assert!(!selected.contains("thin-pack"));
+    }
+
+    #[test]
+    fn test_negotiate_with_server_without_capabilities() {
+        let selected = Capabilities::supported().negotiate(&Capabilities::new());
+        assert!(selected.is_empty());
+    }
+}
@@ -12,11 +12,15 @@
 // GNU Affero General Public License for more details.
 
 use anyhow::{Context, Result};
+use mediagit_storage::{ProxySettings, UploadLimits, UploadPool};
+use mediagit_versioning::fsck::{FsckChecker, IssueSeverity};
 use mediagit_versioning::{
     chunking::ChunkManifest, Commit, FileMode, ObjectDatabase, ObjectType, Oid, PackWriter, Tree,
 };
 use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::capabilities::{self, Capabilities};
 use crate::types::{
     RefUpdate, RefUpdateRequest, RefUpdateResponse, RefsResponse, WantRequest, WantResponse,
 };
@@ -60,10 +64,64 @@ pub struct PushProgress {
     pub message: String,
 }
 
+/// Shared reqwest settings for protocol connections
+fn http_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        // Cap idle connections per host to match max concurrent downloads.
+        // On Windows, each socket registers with IOCP; unbounded idle
+        // connections exhaust kernel handles (OS error 1450).
+        .pool_max_idle_per_host(if cfg!(target_os = "windows") { 4 } else { 8 })
+        .tcp_keepalive(std::time::Duration::from_secs(30))
+        .http2_adaptive_window(true)
+        .http2_initial_stream_window_size(2 * 1024 * 1024)
+        .http2_initial_connection_window_size(8 * 1024 * 1024)
+}
+
+/// Header naming the push that a request belongs to
+///
+/// Servers stage objects uploaded under one push ID in a quarantine and only
+/// move them into the repository when the push's ref update succeeds.
+const PUSH_ID_HEADER: &str = "X-Push-ID";
+
+/// A new ID for one push, unique across pushes from this and other processes
+fn generate_push_id() -> String {
+    static PUSH_COUNTER: AtomicU64 = AtomicU64::new(0);
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!(
+        "{:x}-{:x}-{:x}",
+        timestamp,
+        std::process::id(),
+        PUSH_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Tag `request` with `push_id`, if any
+fn with_push_id(
+    request: reqwest::RequestBuilder,
+    push_id: Option<&str>,
+) -> reqwest::RequestBuilder {
+    match push_id {
+        Some(push_id) => request.header(PUSH_ID_HEADER, push_id),
+        None => request,
+    }
+}
+
 /// HTTP client for the MediaGit protocol
 pub struct ProtocolClient {
     base_url: String,
     client: reqwest::Client,
+    /// Capabilities selected from the server's advertisement; empty until
+    /// [`ProtocolClient::get_refs`] has run
+    capabilities: std::sync::Mutex<Capabilities>,
+    /// Verify outgoing objects before uploading them
+    fsck_objects: bool,
+    /// Send ref updates as leases
+    lease: bool,
+    /// Bounds the chunk uploads in flight
+    upload_pool: UploadPool,
 }
 
 impl ProtocolClient {
@@ -74,20 +132,87 @@ impl ProtocolClient {
     pub fn new(base_url: impl Into<String>) -> Self {
         Self {
             base_url: base_url.into(),
-            client: reqwest::Client::builder()
-                // Cap idle connections per host to match max concurrent downloads.
-                // On Windows, each socket registers with IOCP; unbounded idle
-                // connections exhaust kernel handles (OS error 1450).
-                .pool_max_idle_per_host(if cfg!(target_os = "windows") { 4 } else { 8 })
-                .tcp_keepalive(std::time::Duration::from_secs(30))
-                .http2_adaptive_window(true)
-                .http2_initial_stream_window_size(2 * 1024 * 1024)
-                .http2_initial_connection_window_size(8 * 1024 * 1024)
+            client: http_client_builder()
                 .build()
                 .unwrap_or_else(|_| reqwest::Client::new()),
+            capabilities: Default::default(),
+            fsck_objects: false,
+            lease: false,
+            upload_pool: UploadPool::new(UploadLimits::default()),
         }
     }
 
+    /// Create a protocol client that connects through an HTTP proxy
+    ///
+    /// Anything left unset in `proxy` falls back to `HTTPS_PROXY`/`NO_PROXY`;
+    /// hosts on the bypass list are contacted directly.
+    ///
+    /// # Arguments
+    /// * `base_url` - Base URL of the MediaGit server
+    /// * `proxy` - Proxy settings (URL, credentials and bypass list)
+    pub fn with_proxy(base_url: impl Into<String>, proxy: &ProxySettings) -> Result<Self> {
+        let proxy = proxy.clone().or_env();
+
+        // Disable reqwest's own environment lookup; `proxy` already includes it
+        let mut builder = http_client_builder().no_proxy();
+        if let Some(url) = &proxy.url {
+            let mut route = reqwest::Proxy::all(url.as_str())
+                .with_context(|| format!("Invalid proxy URL '{}'", url))?
+                .no_proxy(reqwest::NoProxy::from_string(&proxy.no_proxy_rules()));
+            if let Some(username) = &proxy.username {
+                route = route.basic_auth(username, proxy.password.as_deref().unwrap_or(""));
+            }
+            builder = builder.proxy(route);
+        }
+
+        Ok(Self {
+            base_url: base_url.into(),
+            client: builder.build().context("Failed to build HTTP client")?,
+            capabilities: Default::default(),
+            fsck_objects: false,
+            lease: false,
+            upload_pool: UploadPool::new(UploadLimits::default()),
+        })
+    }
+
+    /// Verify the objects of every push before uploading them
+    ///
+    /// Each outgoing object is read and checked, and everything it references
+    /// must exist locally; a push with a corrupt object fails before anything
+    /// is sent. Git's `push.fsckObjects`.
+    pub fn with_fsck_objects(mut self, enabled: bool) -> Self {
+        self.fsck_objects = enabled;
+        self
+    }
+
+    /// Send the ref updates of every push as leases
+    ///
+    /// Each update's `old_oid` is then the value the remote ref must still
+    /// have (`None`: the ref must not exist), checked by the server even for
+    /// a forced push; Git's `--force-with-lease`. A push fails before
+    /// uploading anything if the server does not support leases.
+    pub fn with_lease(mut self, enabled: bool) -> Self {
+        self.lease = enabled;
+        self
+    }
+
+    /// Run chunk uploads in `pool` instead of a pool of the client's own
+    ///
+    /// Pass the pool the local object database writes through to keep a
+    /// push's uploads within one set of limits.
+    pub fn with_upload_pool(mut self, pool: UploadPool) -> Self {
+        self.upload_pool = pool;
+        self
+    }
+
+    /// Refuse a leased update to a server that would ignore the lease
+    fn check_lease_supported(&self, lease: bool) -> Result<()> {
+        if lease && !self.capabilities().contains(capabilities::REF_LEASE) {
+            anyhow::bail!("the remote does not support --force-with-lease; nothing was pushed");
+        }
+        Ok(())
+    }
+
     /// Get all refs from the remote repository
     pub async fn get_refs(&self) -> Result<RefsResponse> {
         let url = format!("{}/info/refs", self.base_url);
@@ -104,10 +229,32 @@ impl ProtocolClient {
             anyhow::bail!("GET /info/refs failed with status: {}", response.status());
         }
 
-        response
+        let refs = response
             .json::<RefsResponse>()
             .await
-            .context("Failed to parse refs response")
+            .context("Failed to parse refs response")?;
+
+        let selected =
+            Capabilities::supported().negotiate(&Capabilities::parse(&refs.capabilities));
+        tracing::debug!(advertised = ?refs.capabilities, selected = %selected, "Negotiated capabilities");
+        *self
+            .capabilities
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = selected;
+
+        Ok(refs)
+    }
+
+    /// Capabilities negotiated with the server
+    ///
+    /// The ones both this client and the server support, as selected by the
+    /// last [`get_refs`](Self::get_refs); empty before that, or when the
+    /// server advertises none.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
     }
 
     /// Push local objects and update remote refs
@@ -124,7 +271,9 @@ impl ProtocolClient {
         updates: Vec<RefUpdate>,
         force: bool,
     ) -> Result<(RefUpdateResponse, PushStats)> {
+        self.check_lease_supported(self.lease)?;
         let mut stats = PushStats::default();
+        let push_id = generate_push_id();
 
         // Collect commit OIDs from ref updates (what we want to push)
         let mut commit_oids = Vec::new();
@@ -146,9 +295,13 @@ impl ProtocolClient {
 
         // Collect only NEW objects (not reachable from remote's current state)
         if !commit_oids.is_empty() {
+            let tips = commit_oids.clone();
             let objects = self
                 .collect_reachable_objects(odb, commit_oids, have_oids)
                 .await?;
+            if self.fsck_objects {
+                self.fsck_outgoing(odb, &objects, &tips).await?;
+            }
 
             stats.objects_count = objects.len();
             stats.commits_count = objects
@@ -177,11 +330,11 @@ impl ProtocolClient {
                 // Generate and upload pack file with new objects only
                 let (pack_data, chunked_oids) = self.generate_pack(odb, objects).await?;
                 stats.bytes_uploaded = pack_data.len();
-                self.upload_pack(&pack_data).await?;
+                self.upload_pack(&pack_data, &push_id).await?;
 
                 // Upload chunked objects (large files) if any
                 if !chunked_oids.is_empty() {
-                    self.upload_chunked_objects(odb, &chunked_oids, |_, _, _| {})
+                    self.upload_chunks(odb, &chunked_oids, Some(&push_id), |_, _, _| {})
                         .await?;
                 }
             } else {
@@ -189,9 +342,14 @@ impl ProtocolClient {
             }
         }
 
-        // Update refs
-        let request = RefUpdateRequest { updates, force };
-        let response = self.update_refs(request).await?;
+        // Update refs; the server moves the pushed objects in only if this succeeds
+        let request = RefUpdateRequest {
+            updates,
+            force,
+            lease: self.lease,
+            capabilities: self.capabilities().to_strings(),
+        };
+        let response = self.send_ref_update(request, Some(&push_id)).await?;
         Ok((response, stats))
     }
 
@@ -214,7 +372,9 @@ impl ProtocolClient {
     where
         F: Fn(PushProgress),
     {
+        self.check_lease_supported(self.lease)?;
         let mut stats = PushStats::default();
+        let push_id = generate_push_id();
 
         // Collect commit OIDs from ref updates (what we want to push)
         let mut commit_oids = Vec::new();
@@ -240,12 +400,16 @@ impl ProtocolClient {
             message: "Collecting objects...".to_string(),
         });
 
+        let tips = commit_oids.clone();
         let objects = if !commit_oids.is_empty() {
             self.collect_reachable_objects(odb, commit_oids, have_oids)
                 .await?
         } else {
             Vec::new()
         };
+        if self.fsck_objects {
+            self.fsck_outgoing(odb, &objects, &tips).await?;
+        }
 
         stats.objects_count = objects.len();
         stats.commits_count = objects
@@ -300,7 +464,7 @@ impl ProtocolClient {
                 message: "Uploading pack...".to_string(),
             });
 
-            self.upload_pack(&pack_data).await?;
+            self.upload_pack(&pack_data, &push_id).await?;
 
             on_progress(PushProgress {
                 phase: PushPhase::Uploading,
@@ -319,7 +483,7 @@ impl ProtocolClient {
                 });
 
                 let chunks_uploaded = self
-                    .upload_chunked_objects(odb, &chunked_oids, |current, total, msg| {
+                    .upload_chunks(odb, &chunked_oids, Some(&push_id), |current, total, msg| {
                         tracing::info!("Chunked upload: {}/{} - {}", current, total, msg);
                     })
                     .await?;
@@ -339,9 +503,14 @@ impl ProtocolClient {
             tracing::info!("No new objects to push");
         }
 
-        // Update refs
-        let request = RefUpdateRequest { updates, force };
-        let response = self.update_refs(request).await?;
+        // Update refs; the server moves the pushed objects in only if this succeeds
+        let request = RefUpdateRequest {
+            updates,
+            force,
+            lease: self.lease,
+            capabilities: self.capabilities().to_strings(),
+        };
+        let response = self.send_ref_update(request, Some(&push_id)).await?;
         Ok((response, stats))
     }
 
@@ -394,8 +563,8 @@ impl ProtocolClient {
         self.pull_with_have(odb, remote_ref, Vec::new()).await
     }
 
-    /// Upload a pack file to the server
-    async fn upload_pack(&self, pack_data: &[u8]) -> Result<()> {
+    /// Upload a pack file to the server as part of push `push_id`
+    async fn upload_pack(&self, pack_data: &[u8], push_id: &str) -> Result<()> {
         let url = format!("{}/objects/pack", self.base_url);
         tracing::debug!("POST {} ({} bytes)", url, pack_data.len());
 
@@ -403,15 +572,22 @@ impl ProtocolClient {
             .client
             .post(&url)
             .header("Content-Type", "application/octet-stream")
+            .header(PUSH_ID_HEADER, push_id)
             .body(pack_data.to_vec())
             .send()
             .await
             .context("Failed to upload pack file")?;
 
         if !response.status().is_success() {
+            let status = response.status();
+            let message = response.text().await.unwrap_or_default();
+            if message.is_empty() {
+                anyhow::bail!("POST /objects/pack failed with status: {}", status);
+            }
             anyhow::bail!(
-                "POST /objects/pack failed with status: {}",
-                response.status()
+                "POST /objects/pack failed with status: {}: {}",
+                status,
+                message
             );
         }
 
@@ -431,7 +607,11 @@ impl ProtocolClient {
         let want_url = format!("{}/objects/want", self.base_url);
         tracing::debug!("POST {}", want_url);
 
-        let want_req = WantRequest { want, have };
+        let want_req = WantRequest {
+            want,
+            have,
+            capabilities: self.capabilities().to_strings(),
+        };
 
         let response = self
             .client
@@ -518,7 +698,11 @@ impl ProtocolClient {
         let want_url = format!("{}/objects/want", self.base_url);
         tracing::debug!("POST {} (streaming)", want_url);
 
-        let want_req = WantRequest { want, have };
+        let want_req = WantRequest {
+            want,
+            have,
+            capabilities: self.capabilities().to_strings(),
+        };
 
         let response = self
             .client
@@ -650,12 +834,20 @@ impl ProtocolClient {
 
     /// Update remote refs
     pub async fn update_refs(&self, request: RefUpdateRequest) -> Result<RefUpdateResponse> {
+        self.send_ref_update(request, None).await
+    }
+
+    /// Update remote refs, completing push `push_id` if given
+    async fn send_ref_update(
+        &self,
+        request: RefUpdateRequest,
+        push_id: Option<&str>,
+    ) -> Result<RefUpdateResponse> {
+        self.check_lease_supported(request.lease)?;
         let url = format!("{}/refs/update", self.base_url);
         tracing::debug!("POST {}", url);
 
-        let response = self
-            .client
-            .post(&url)
+        let response = with_push_id(self.client.post(&url), push_id)
             .json(&request)
             .send()
             .await
@@ -698,11 +890,9 @@ impl ProtocolClient {
                     // Detect actual object type by reading and inspecting the object
                     let obj_type = if let Ok(obj_data) = odb.read(&oid).await {
                         // Try to deserialize as each type to detect the actual type
-                        if mediagit_versioning::format::deserialize::<Commit>(&obj_data).is_ok() {
+                        if Commit::deserialize(&obj_data).is_ok() {
                             ObjectType::Commit
-                        } else if mediagit_versioning::format::deserialize::<Tree>(&obj_data)
-                            .is_ok()
-                        {
+                        } else if Tree::deserialize(&obj_data).is_ok() {
                             ObjectType::Tree
                         } else {
                             ObjectType::Blob
@@ -729,9 +919,7 @@ impl ProtocolClient {
                 if let Ok(obj_data) = odb.read(&oid).await {
                     match obj_type {
                         ObjectType::Commit => {
-                            if let Ok(commit) =
-                                mediagit_versioning::format::deserialize::<Commit>(&obj_data)
-                            {
+                            if let Ok(commit) = Commit::deserialize(&obj_data) {
                                 if visited.insert(commit.tree) {
                                     have_queue.push_back((commit.tree, ObjectType::Tree));
                                 }
@@ -743,9 +931,7 @@ impl ProtocolClient {
                             }
                         }
                         ObjectType::Tree => {
-                            if let Ok(tree) =
-                                mediagit_versioning::format::deserialize::<Tree>(&obj_data)
-                            {
+                            if let Ok(tree) = Tree::deserialize(&obj_data) {
                                 for entry in tree.entries.values() {
                                     if visited.insert(entry.oid) {
                                         let entry_type = match entry.mode {
@@ -787,7 +973,7 @@ impl ProtocolClient {
                         .context(format!("Failed to read commit {}", oid))?;
 
                     // Deserialize commit to extract tree and parent refs
-                    let commit: Commit = mediagit_versioning::format::deserialize(&obj_data)
+                    let commit: Commit = Commit::deserialize(&obj_data)
                         .context(format!("Failed to deserialize commit {}", oid))?;
 
                     // Add tree OID
@@ -809,7 +995,7 @@ impl ProtocolClient {
                         .context(format!("Failed to read tree {}", oid))?;
 
                     // Deserialize tree to extract blob/subtree refs
-                    let tree: Tree = mediagit_versioning::format::deserialize(&obj_data)
+                    let tree: Tree = Tree::deserialize(&obj_data)
                         .context(format!("Failed to deserialize tree {}", oid))?;
 
                     for entry in tree.entries.values() {
@@ -833,6 +1019,30 @@ impl ProtocolClient {
         Ok(result)
     }
 
+    /// Fail if any object about to be pushed is corrupt or references a
+    /// missing object
+    async fn fsck_outgoing(
+        &self,
+        odb: &ObjectDatabase,
+        objects: &[(Oid, ObjectType)],
+        tips: &[Oid],
+    ) -> Result<()> {
+        let oids: Vec<Oid> = objects.iter().map(|(oid, _)| *oid).collect();
+        let report = FsckChecker::new(odb.storage().clone())
+            .check_transfer(&oids, tips)
+            .await?;
+        let errors = report.issues_by_severity(IssueSeverity::Error);
+        if let Some(issue) = errors.first() {
+            anyhow::bail!(
+                "fsck found {} problem(s) in outgoing objects, nothing was pushed: {}",
+                errors.len(),
+                issue.message
+            );
+        }
+        tracing::debug!("Verified {} outgoing objects", oids.len());
+        Ok(())
+    }
+
     /// Generate a pack file containing specified objects with their types
     ///
     /// Uses incremental pack generation to minimize memory usage.
@@ -924,12 +1134,10 @@ impl ProtocolClient {
     }
 
     /// Upload a manifest to the remote server
-    async fn upload_manifest(&self, oid: &Oid, data: &[u8]) -> Result<()> {
+    async fn upload_manifest(&self, oid: &Oid, data: &[u8], push_id: Option<&str>) -> Result<()> {
         let url = format!("{}/manifests/{}", self.base_url, oid.to_hex());
 
-        let response = self
-            .client
-            .put(&url)
+        let response = with_push_id(self.client.put(&url), push_id)
             .body(data.to_vec())
             .send()
             .await
@@ -953,6 +1161,22 @@ impl ProtocolClient {
         &self,
         odb: &ObjectDatabase,
         chunked_oids: &[Oid],
+        on_progress: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(usize, usize, &str),
+    {
+        self.upload_chunks(odb, chunked_oids, None, on_progress)
+            .await
+    }
+
+    /// [`upload_chunked_objects`](Self::upload_chunked_objects) as part of
+    /// push `push_id`, if given
+    async fn upload_chunks<F>(
+        &self,
+        odb: &ObjectDatabase,
+        chunked_oids: &[Oid],
+        push_id: Option<&str>,
         mut on_progress: F,
     ) -> Result<usize>
     where
@@ -1012,26 +1236,27 @@ impl ProtocolClient {
                 // Upload missing chunks with bounded concurrency.
                 // buffer_unordered keeps at most `concurrent_uploads` futures
                 // active at once, preventing Windows IOCP handle exhaustion
-                // that occurs when all tasks are spawned upfront.
+                // that occurs when all tasks are spawned upfront. Each chunk
+                // is only read once the upload pool has room for it, which
+                // bounds the chunk data held in memory.
                 let missing_set: std::collections::HashSet<String> =
                     missing_chunks.into_iter().collect();
-                let chunks_to_upload: Vec<Oid> = manifest
+                let chunks_to_upload: Vec<(Oid, usize)> = manifest
                     .chunks
                     .iter()
                     .filter(|c| missing_set.contains(&c.id.to_hex()))
-                    .map(|c| c.id)
+                    .map(|c| (c.id, c.size))
                     .collect();
 
                 let results: Vec<anyhow::Result<()>> = futures::stream::iter(chunks_to_upload)
-                    .map(|chunk_id| {
+                    .map(|(chunk_id, size)| {
                         let client = self.client.clone();
                         let base_url = self.base_url.clone();
                         let odb = odb.clone();
-                        async move {
+                        self.upload_pool.run(size as u64, async move {
                             let chunk_data = odb.get_compressed_chunk(&chunk_id).await?;
                             let url = format!("{}/chunks/{}", base_url, chunk_id.to_hex());
-                            client
-                                .put(&url)
+                            with_push_id(client.put(&url), push_id)
                                 .body(chunk_data)
                                 .send()
                                 .await
@@ -1039,7 +1264,7 @@ impl ProtocolClient {
                                 .map_err(|e| {
                                     anyhow::anyhow!("Failed to upload chunk {}: {}", chunk_id, e)
                                 })
-                        }
+                        })
                     })
                     .buffer_unordered(concurrent_uploads)
                     .collect()
@@ -1054,7 +1279,7 @@ impl ProtocolClient {
             // Upload manifest last (ensures all chunks exist first)
             let manifest_data = mediagit_versioning::format::serialize(&manifest)
                 .context("Failed to serialize manifest")?;
-            self.upload_manifest(oid, &manifest_data).await?;
+            self.upload_manifest(oid, &manifest_data, push_id).await?;
 
             tracing::debug!(oid = %oid, "Manifest uploaded");
         }
@@ -1214,7 +1439,7 @@ impl ProtocolClient {
                 // buffer_unordered keeps at most `concurrent_downloads` futures
                 // active at once, preventing Windows IOCP handle exhaustion
                 // that occurs when all tasks are spawned upfront.
-                let mut stream = futures::stream::iter(missing_chunks.into_iter())
+                let mut stream = futures::stream::iter(missing_chunks)
                     .map(|chunk_id| {
                         let client = self.client.clone();
                         let base_url = self.base_url.clone();
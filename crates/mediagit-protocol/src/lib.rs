@@ -18,11 +18,13 @@
 //! network protocol, enabling push/pull operations between repositories.
 
 pub mod adaptive_config;
+pub mod capabilities;
 pub mod client;
 pub mod streaming;
 pub mod types;
 
 // Re-export commonly used types
+pub use capabilities::Capabilities;
 pub use client::{ProtocolClient, PushPhase, PushProgress, PushStats};
 pub use streaming::{
     DownloadConfig, DownloadHandle, StreamingDownloader, StreamingUploader, TransferProgress,
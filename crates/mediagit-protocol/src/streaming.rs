@@ -53,6 +53,7 @@
 //! ```
 
 use anyhow::{Context, Result};
+use mediagit_storage::{UploadLimits, UploadPool};
 use reqwest::{Client, StatusCode};
 // use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -162,18 +163,33 @@ pub struct StreamingUploader {
     base_url: String,
     client: Client,
     config: UploadConfig,
+    pool: UploadPool,
 }
 
 impl StreamingUploader {
     /// Create a new streaming uploader
+    ///
+    /// Up to `parallel_transfers` chunks are read and uploaded at once.
     pub fn new(base_url: impl Into<String>, config: UploadConfig) -> Self {
+        let pool = UploadPool::new(UploadLimits::new(
+            config.parallel_transfers,
+            (config.parallel_transfers * config.chunk_size) as u64,
+        ));
         Self {
             base_url: base_url.into(),
             client: Client::new(),
             config,
+            pool,
         }
     }
 
+    /// Upload chunks in `pool`, shared with other uploads, instead of
+    /// `parallel_transfers` at a time
+    pub fn with_upload_pool(mut self, pool: UploadPool) -> Self {
+        self.pool = pool;
+        self
+    }
+
     /// Upload a file with progress tracking
     pub async fn upload_file(
         &self,
@@ -304,6 +320,7 @@ impl Clone for StreamingUploader {
             base_url: self.base_url.clone(),
             client: self.client.clone(),
             config: self.config.clone(),
+            pool: self.pool.clone(),
         }
     }
 }
@@ -332,13 +349,20 @@ impl UploadHandle {
         let mut file = File::open(&self.local_path).await?;
         let total_chunks = (self.file_size as usize).div_ceil(self.uploader.config.chunk_size);
 
-        let semaphore = Arc::new(Semaphore::new(self.uploader.config.parallel_transfers));
         let mut tasks = Vec::new();
 
         let start_time = std::time::Instant::now();
         let mut bytes_transferred = 0u64;
 
         for chunk_index in 0..total_chunks {
+            // Wait for room before reading, so chunks aren't read faster
+            // than they are uploaded
+            let permit = self
+                .uploader
+                .pool
+                .acquire(self.uploader.config.chunk_size as u64)
+                .await?;
+
             let offset = (chunk_index * self.uploader.config.chunk_size) as u64;
             file.seek(tokio::io::SeekFrom::Start(offset)).await?;
 
@@ -348,7 +372,6 @@ impl UploadHandle {
 
             let uploader = self.uploader.clone();
             let remote_path = self.remote_path.clone();
-            let semaphore = semaphore.clone();
             let progress_callback = self.progress_callback.clone();
 
             bytes_transferred += bytes_read as u64;
@@ -372,7 +395,7 @@ impl UploadHandle {
             }
 
             let task = tokio::spawn(async move {
-                let _permit = semaphore.acquire().await?;
+                let _permit = permit;
                 uploader
                     .upload_chunk(&remote_path, chunk_index, chunk_data, total_chunks)
                     .await
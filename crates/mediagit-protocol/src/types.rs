@@ -29,7 +29,9 @@ pub struct RefInfo {
 pub struct RefsResponse {
     /// List of all references in the repository
     pub refs: Vec<RefInfo>,
-    /// Protocol capabilities supported by the server
+    /// Protocol capabilities supported by the server, as `name` or
+    /// `name=value` strings (see [`crate::capabilities`])
+    #[serde(default)]
     pub capabilities: Vec<String>,
 }
 
@@ -40,6 +42,9 @@ pub struct WantRequest {
     pub want: Vec<String>,
     /// Object IDs the client already has (for delta compression)
     pub have: Vec<String>,
+    /// Capabilities the client selected from the server's advertisement
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub capabilities: Vec<String>,
 }
 
 /// Response from POST /objects/want
@@ -71,6 +76,13 @@ pub struct RefUpdateRequest {
     pub updates: Vec<RefUpdate>,
     /// Force update even if not fast-forward
     pub force: bool,
+    /// Treat each update's `old_oid` as a lease: the ref must have exactly
+    /// that value (`None`: must not exist), even when `force` is set
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub lease: bool,
+    /// Capabilities the client selected from the server's advertisement
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub capabilities: Vec<String>,
 }
 
 /// Result of a single ref update operation
@@ -160,12 +172,18 @@ mod tests {
         let request = WantRequest {
             want: vec!["abc123".to_string()],
             have: vec!["def456".to_string()],
+            capabilities: vec!["pack-v1".to_string()],
         };
 
         let json = serde_json::to_string(&request).unwrap();
         let deserialized: WantRequest = serde_json::from_str(&json).unwrap();
         assert_eq!(request.want, deserialized.want);
         assert_eq!(request.have, deserialized.have);
+        assert_eq!(request.capabilities, deserialized.capabilities);
+
+        // Requests from clients that predate capabilities still parse
+        let legacy: WantRequest = serde_json::from_str(r#"{"want":[],"have":[]}"#).unwrap();
+        assert!(legacy.capabilities.is_empty());
     }
 
     #[test]
@@ -178,6 +196,8 @@ mod tests {
                 delete: false,
             }],
             force: false,
+            lease: false,
+            capabilities: Vec::new(),
         };
 
         let json = serde_json::to_string(&request).unwrap();
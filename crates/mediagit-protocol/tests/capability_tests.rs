@@ -0,0 +1,124 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Capability negotiation tests for ProtocolClient.
+//! A fake HTTP server advertises a given refs response and records the body
+//! of every want request, which it then rejects.
+
+use mediagit_protocol::capabilities::{self, Capabilities};
+use mediagit_protocol::{ProtocolClient, WantRequest};
+use mediagit_storage::mock::MockBackend;
+use mediagit_versioning::ObjectDatabase;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+/// Start a fake server answering `info/refs` with `refs_body`, returning its
+/// URL and the want requests it received
+fn spawn_server(refs_body: &'static str) -> (String, Arc<Mutex<Vec<WantRequest>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let wants = Arc::new(Mutex::new(Vec::new()));
+
+    let recorded = Arc::clone(&wants);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            let mut content_length = 0usize;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+                if request_line.is_empty() {
+                    request_line = line.clone();
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+            let mut body = vec![0u8; content_length];
+            let _ = reader.read_exact(&mut body);
+
+            if request_line.starts_with("GET") {
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    refs_body.len(),
+                    refs_body
+                );
+            } else {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(serde_json::from_slice(&body).unwrap());
+                let _ = stream.write_all(
+                    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                );
+            }
+        }
+    });
+
+    (format!("http://{}/repo", addr), wants)
+}
+
+#[tokio::test]
+async fn test_client_and_server_negotiate_shared_capabilities() {
+    // Overlaps with this client on two capabilities; the rest are unknown to it
+    let (url, wants) = spawn_server(
+        r#"{"refs":[],"capabilities":["pack-v1","chunked-objects","bitmap-index","filter=blob:none","resumable-transfer=v2"]}"#,
+    );
+    let client = ProtocolClient::new(url);
+    assert!(client.capabilities().is_empty());
+
+    let refs = client.get_refs().await.unwrap();
+    assert_eq!(refs.capabilities.len(), 5);
+
+    let selected = client.capabilities();
+    assert_eq!(
+        selected,
+        Capabilities::parse([capabilities::PACK_V1, capabilities::CHUNKED_OBJECTS])
+    );
+    assert!(!selected.contains(capabilities::PUSH_QUARANTINE));
+    assert!(!selected.contains("bitmap-index"));
+
+    // The selection accompanies later requests
+    let odb = ObjectDatabase::with_smart_compression(Arc::new(MockBackend::new()), 10);
+    assert!(client
+        .download_pack_streaming(&odb, vec!["ab".repeat(32)], Vec::new())
+        .await
+        .is_err());
+    let wants = wants.lock().unwrap();
+    assert_eq!(wants.len(), 1);
+    assert_eq!(wants[0].capabilities, vec!["chunked-objects", "pack-v1"]);
+}
+
+#[tokio::test]
+async fn test_server_without_capabilities_selects_none() {
+    let (url, wants) = spawn_server(r#"{"refs":[]}"#);
+    let client = ProtocolClient::new(url);
+
+    let refs = client.get_refs().await.unwrap();
+    assert!(refs.capabilities.is_empty());
+    assert!(client.capabilities().is_empty());
+
+    // Nothing selected, so requests look like those of older clients
+    let odb = ObjectDatabase::with_smart_compression(Arc::new(MockBackend::new()), 10);
+    let _ = client
+        .download_pack_streaming(&odb, vec!["ab".repeat(32)], Vec::new())
+        .await;
+    assert!(wants.lock().unwrap()[0].capabilities.is_empty());
+}
@@ -107,6 +107,8 @@ fn test_ref_update_request() {
             delete: false,
         }],
         force: false,
+        lease: false,
+        capabilities: Vec::new(),
     };
 
     let json = serde_json::to_string(&request).expect("Failed to serialize");
@@ -166,6 +168,7 @@ fn test_want_request() {
     let request = WantRequest {
         want: vec!["abc123".to_string(), "def456".to_string()],
         have: vec!["ghi789".to_string()],
+        capabilities: Vec::new(),
     };
 
     let json = serde_json::to_string(&request).expect("Failed to serialize");
@@ -202,6 +205,8 @@ fn test_multiple_ref_updates() {
             },
         ],
         force: false,
+        lease: false,
+        capabilities: Vec::new(),
     };
 
     let json = serde_json::to_string(&request).expect("Failed to serialize");
@@ -223,6 +228,8 @@ fn test_force_push() {
             delete: false,
         }],
         force: true,
+        lease: false,
+        capabilities: Vec::new(),
     };
 
     let json = serde_json::to_string(&request).expect("Failed to serialize");
@@ -0,0 +1,97 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Proxy routing tests for ProtocolClient.
+//! A fake HTTP server records request heads and serves an empty refs listing.
+
+use mediagit_protocol::ProtocolClient;
+use mediagit_storage::ProxySettings;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+/// Start a fake server and return its address and the request heads it saw
+fn spawn_refs_server() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let recorded = Arc::clone(&seen);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            recorded.lock().unwrap().push(head);
+
+            let body = r#"{"refs":[],"capabilities":["proxy-test"]}"#;
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+
+    (addr, seen)
+}
+
+#[tokio::test]
+async fn test_client_routes_through_proxy() {
+    let (proxy_addr, proxy_seen) = spawn_refs_server();
+
+    let proxy = ProxySettings::new(format!("http://{}", proxy_addr)).with_auth("bot", "s3cret");
+    let client = ProtocolClient::with_proxy("http://remote.invalid/repo", &proxy).unwrap();
+
+    let refs = client
+        .get_refs()
+        .await
+        .expect("request should go via proxy");
+    assert_eq!(refs.capabilities, vec!["proxy-test"]);
+
+    let seen = proxy_seen.lock().unwrap();
+    assert_eq!(seen.len(), 1);
+    assert!(
+        seen[0].starts_with("GET http://remote.invalid/repo/info/refs HTTP/1.1"),
+        "expected absolute-form request, got: {}",
+        seen[0]
+    );
+    // "bot:s3cret" in base64
+    assert!(seen[0].contains("Ym90OnMzY3JldA=="));
+}
+
+#[tokio::test]
+async fn test_client_bypasses_proxy_for_no_proxy_hosts() {
+    let (proxy_addr, proxy_seen) = spawn_refs_server();
+    let (origin_addr, origin_seen) = spawn_refs_server();
+
+    let proxy = ProxySettings::new(format!("http://{}", proxy_addr)).with_no_proxy(["127.0.0.1"]);
+    let client =
+        ProtocolClient::with_proxy(format!("http://{}/repo", origin_addr), &proxy).unwrap();
+
+    client.get_refs().await.expect("request should go direct");
+
+    assert!(proxy_seen.lock().unwrap().is_empty());
+    let seen = origin_seen.lock().unwrap();
+    assert_eq!(seen.len(), 1);
+    assert!(seen[0].starts_with("GET /repo/info/refs HTTP/1.1"));
+}
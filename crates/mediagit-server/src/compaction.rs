@@ -0,0 +1,283 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Background compaction of hosted repositories.
+//!
+//! Pushes leave every object loose. The [`Compactor`] periodically checks each
+//! repository under `repos_dir` and repacks those that have accumulated
+//! [`CompactionConfig::loose_object_threshold`] loose objects, removing the
+//! packed loose copies. It runs on its own task so serving is never blocked,
+//! only inside the configured maintenance window, and never twice at once for
+//! the same repository. A repository whose gc lock is held is skipped until
+//! the next pass. Each compaction also packs the repository's refs into its
+//! `packed-refs` file (see [`RefDatabase::pack_refs`]), holding the
+//! repository's ref lock so no push moves a ref meanwhile.
+//!
+//! An interrupted run is safe: loose objects are only deleted after the pack
+//! holding them has been written, and loose refs only after the packed refs
+//! holding their values, so every object and ref stays readable.
+
+use crate::config::CompactionConfig;
+use crate::handlers::create_storage_backend;
+use crate::state::RefLocks;
+use anyhow::{Context, Result};
+use mediagit_versioning::{GcLock, ObjectDatabase, RefDatabase, RepackOptions};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Compaction history of one repository, reported by `GET /compaction`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RepoCompactionStats {
+    /// Unix time (seconds) the last compaction finished
+    pub last_compaction: Option<u64>,
+    /// Loose objects removed across all compactions
+    pub objects_reclaimed: u64,
+    /// Number of compactions run
+    pub runs: u64,
+}
+
+/// What happened to one repository during a compaction pass
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompactionOutcome {
+    /// Loose objects were repacked and refs packed
+    Compacted {
+        /// Loose objects removed after packing
+        objects_reclaimed: usize,
+        /// Loose refs moved into `packed-refs`
+        refs_packed: usize,
+    },
+    /// Below the loose-object threshold
+    Skipped {
+        /// Loose objects currently in the repository
+        loose_objects: usize,
+    },
+    /// Another compaction or a gc of this repository is still running
+    Busy,
+}
+
+/// Repacks hosted repositories in the background
+pub struct Compactor {
+    repos_dir: PathBuf,
+    config: CompactionConfig,
+    /// Repositories currently being compacted
+    running: Mutex<HashSet<String>>,
+    stats: RwLock<HashMap<String, RepoCompactionStats>>,
+    /// Held while refs are packed, shared with ref updates
+    ref_locks: Arc<RefLocks>,
+}
+
+/// Marks a repository as busy until dropped
+struct RunningGuard<'a> {
+    running: &'a Mutex<HashSet<String>>,
+    repo: String,
+}
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut running) = self.running.lock() {
+            running.remove(&self.repo);
+        }
+    }
+}
+
+impl Compactor {
+    pub fn new(repos_dir: PathBuf, config: CompactionConfig) -> Self {
+        Self {
+            repos_dir,
+            config,
+            running: Mutex::new(HashSet::new()),
+            stats: RwLock::new(HashMap::new()),
+            ref_locks: Arc::default(),
+        }
+    }
+
+    /// Pack refs under `ref_locks`, the locks ref updates take
+    ///
+    /// Without them a push could move a ref while it is being packed.
+    pub fn with_ref_locks(mut self, ref_locks: Arc<RefLocks>) -> Self {
+        self.ref_locks = ref_locks;
+        self
+    }
+
+    /// Compaction history per repository name
+    pub fn stats(&self) -> HashMap<String, RepoCompactionStats> {
+        self.stats
+            .read()
+            .map(|stats| stats.clone())
+            .unwrap_or_default()
+    }
+
+    /// Check every repository once, compacting those over the threshold
+    ///
+    /// Failures are logged and do not stop the pass.
+    pub async fn run_once(&self) -> Vec<(String, CompactionOutcome)> {
+        let repos = match self.list_repos() {
+            Ok(repos) => repos,
+            Err(e) => {
+                tracing::warn!("Compaction could not list repositories: {:#}", e);
+                return Vec::new();
+            }
+        };
+
+        let mut outcomes = Vec::new();
+        for repo in repos {
+            match self.compact_repo(&repo).await {
+                Ok(outcome) => outcomes.push((repo, outcome)),
+                Err(e) => tracing::warn!(repo = %repo, "Compaction failed: {:#}", e),
+            }
+        }
+        outcomes
+    }
+
+    /// Repack one repository and pack its refs if it has enough loose
+    /// objects
+    pub async fn compact_repo(&self, repo: &str) -> Result<CompactionOutcome> {
+        let Some(_guard) = self.try_start(repo) else {
+            tracing::debug!(repo, "Compaction already running, skipping");
+            return Ok(CompactionOutcome::Busy);
+        };
+
+        let repo_path = self.repos_dir.join(repo);
+        let storage = create_storage_backend(&repo_path)
+            .await
+            .map_err(|status| anyhow::anyhow!("Failed to open storage ({})", status))?;
+        let odb = ObjectDatabase::with_smart_compression(storage, 100);
+
+        let loose_objects = odb.count_loose_objects().await?;
+        if loose_objects < self.config.loose_object_threshold {
+            tracing::debug!(repo, loose_objects, "Below compaction threshold");
+            return Ok(CompactionOutcome::Skipped { loose_objects });
+        }
+
+        // Repacking deletes loose objects, so it takes the gc lock
+        let _gc_lock = match GcLock::acquire(&repo_path.join(".mediagit")) {
+            Ok(lock) => lock,
+            Err(e) => {
+                tracing::debug!(repo, "Skipping compaction: {:#}", e);
+                return Ok(CompactionOutcome::Busy);
+            }
+        };
+
+        tracing::info!(repo, loose_objects, "Compacting repository");
+        let repack = odb
+            .repack_with(&RepackOptions {
+                max_objects: self.config.max_pack_objects,
+                jobs: self.config.jobs,
+                window_memory: self.config.window_memory,
+                ..Default::default()
+            })
+            .await
+            .context("Repack failed")?;
+
+        let refs_packed = {
+            let ref_lock = self.ref_locks.get(repo).await;
+            let _ref_guard = ref_lock.lock().await;
+            RefDatabase::new(repo_path.join(".mediagit"))
+                .pack_refs()
+                .await
+                .context("Packing refs failed")?
+        };
+
+        let reclaimed = repack.loose_objects_removed + repack.duplicates_removed;
+        if let Ok(mut stats) = self.stats.write() {
+            let entry = stats.entry(repo.to_string()).or_default();
+            entry.last_compaction = Some(unix_now());
+            entry.objects_reclaimed += reclaimed as u64;
+            entry.runs += 1;
+        }
+        tracing::info!(
+            repo,
+            packed = repack.objects_packed,
+            reclaimed,
+            refs_packed,
+            "Compaction complete"
+        );
+
+        Ok(CompactionOutcome::Compacted {
+            objects_reclaimed: reclaimed,
+            refs_packed,
+        })
+    }
+
+    /// Run a compaction pass every `interval_secs` until `shutdown` is cancelled
+    ///
+    /// Passes outside the maintenance window are skipped. Cancelling stops
+    /// the task promptly, abandoning any pass in progress.
+    pub fn spawn(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = interval.tick() => {}
+                }
+
+                let hour = ((unix_now() / 3600) % 24) as u32;
+                if !self.config.in_window(hour) {
+                    continue;
+                }
+
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = self.run_once() => {}
+                }
+            }
+            tracing::info!("Compaction task stopped");
+        })
+    }
+
+    fn try_start(&self, repo: &str) -> Option<RunningGuard<'_>> {
+        let mut running = self.running.lock().ok()?;
+        if !running.insert(repo.to_string()) {
+            return None;
+        }
+        Some(RunningGuard {
+            running: &self.running,
+            repo: repo.to_string(),
+        })
+    }
+
+    /// Directories under `repos_dir` that hold a repository
+    fn list_repos(&self) -> Result<Vec<String>> {
+        let mut repos = Vec::new();
+        for entry in std::fs::read_dir(&self.repos_dir)
+            .with_context(|| format!("Failed to read {}", self.repos_dir.display()))?
+        {
+            let entry = entry?;
+            if !entry.path().join(".mediagit").is_dir() {
+                continue;
+            }
+            if let Some(name) = entry.file_name().to_str() {
+                repos.push(name.to_string());
+            }
+        }
+        repos.sort();
+        Ok(repos)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -69,6 +69,157 @@ pub struct ServerConfig {
     /// Rate limiting: burst size
     #[serde(default = "default_rate_limit_burst")]
     pub rate_limit_burst: u32,
+
+    /// Background compaction of hosted repositories
+    #[serde(default)]
+    pub compaction: CompactionConfig,
+
+    /// Stage pushed objects in a quarantine until the push's ref update
+    /// succeeds, so a failed push leaves no objects behind
+    #[serde(default = "default_push_quarantine")]
+    pub push_quarantine: bool,
+
+    /// Verify the objects of each quarantined push (content and
+    /// connectivity) before its refs are updated, rejecting the push if any
+    /// is corrupt. Git's `receive.fsckObjects`.
+    #[serde(default)]
+    pub receive_fsck_objects: bool,
+
+    /// Largest size, in bytes, an object received in a push may decompress
+    /// to while `receive_fsck_objects` checks it. A larger one rejects the
+    /// push instead of allocating whatever its compressed frame claims.
+    /// Objects already in the repository are read without this limit.
+    /// Defaults to [`default_max_decompressed_size`].
+    #[serde(default = "default_max_decompressed_size")]
+    pub max_decompressed_size: u64,
+}
+
+/// Background compaction settings (`[compaction]` section)
+///
+/// ```toml
+/// [compaction]
+/// enabled = true
+/// interval_secs = 3600
+/// loose_object_threshold = 1000
+/// window_start_hour = 2   # UTC
+/// window_end_hour = 5
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompactionConfig {
+    /// Run the compaction task
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Seconds between checks of every repository
+    #[serde(default = "default_compaction_interval_secs")]
+    pub interval_secs: u64,
+
+    /// Repack a repository once it has at least this many loose objects
+    #[serde(default = "default_loose_object_threshold")]
+    pub loose_object_threshold: usize,
+
+    /// Maximum objects per pack (0 = unlimited)
+    #[serde(default)]
+    pub max_pack_objects: usize,
+
+    /// Objects compressed concurrently while repacking (0 = one per CPU)
+    #[serde(default = "default_compaction_jobs")]
+    pub jobs: usize,
+
+    /// Bytes the repack delta window may hold (0 = unlimited)
+    #[serde(default = "default_compaction_window_memory")]
+    pub window_memory: u64,
+
+    /// First hour (UTC, 0-23) of the maintenance window
+    pub window_start_hour: Option<u32>,
+
+    /// Hour (UTC, 0-23) at which the maintenance window ends
+    pub window_end_hour: Option<u32>,
+}
+
+fn default_compaction_interval_secs() -> u64 {
+    3600
+}
+
+fn default_loose_object_threshold() -> usize {
+    1000
+}
+
+fn default_compaction_jobs() -> usize {
+    1
+}
+
+fn default_compaction_window_memory() -> u64 {
+    mediagit_versioning::DEFAULT_REPACK_WINDOW_MEMORY
+}
+
+impl Default for CompactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_compaction_interval_secs(),
+            loose_object_threshold: default_loose_object_threshold(),
+            max_pack_objects: 0,
+            jobs: default_compaction_jobs(),
+            window_memory: default_compaction_window_memory(),
+            window_start_hour: None,
+            window_end_hour: None,
+        }
+    }
+}
+
+impl CompactionConfig {
+    /// Whether compaction may run during the given UTC hour
+    ///
+    /// Without a window every hour is allowed. A window may wrap midnight,
+    /// e.g. 22 to 4.
+    pub fn in_window(&self, hour: u32) -> bool {
+        match (self.window_start_hour, self.window_end_hour) {
+            (Some(start), Some(end)) if start <= end => (start..end).contains(&hour),
+            (Some(start), Some(end)) => hour >= start || hour < end,
+            _ => true,
+        }
+    }
+}
+
+fn default_push_quarantine() -> bool {
+    true
+}
+
+/// Decompression limit used when `max_decompressed_size` is not configured
+///
+/// A sixteenth of physical memory, so that many pushes can each be checked
+/// at once. See
+/// [`max_decompressed_size_for_memory`] for the bounds.
+pub fn default_max_decompressed_size() -> u64 {
+    max_decompressed_size_for_memory(total_memory())
+}
+
+/// Decompression limit for a server with `total_memory` bytes of memory
+///
+/// A sixteenth of `total_memory`. It is never below 64 MB, so the largest
+/// chunks (32 MB) still read, and never above the library default
+/// ([`DEFAULT_MAX_DECOMPRESSED_SIZE`](mediagit_compression::DEFAULT_MAX_DECOMPRESSED_SIZE)).
+/// When memory is unknown the limit is 256 MB.
+pub fn max_decompressed_size_for_memory(total_memory: Option<u64>) -> u64 {
+    const MIN: u64 = 64 * 1024 * 1024;
+    const UNKNOWN: u64 = 256 * 1024 * 1024;
+    let max = mediagit_compression::DEFAULT_MAX_DECOMPRESSED_SIZE;
+    total_memory.map_or(UNKNOWN, |memory| (memory / 16).clamp(MIN, max))
+}
+
+/// Physical memory in bytes, read from `/proc/meminfo` where there is one
+fn total_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let kib = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
 }
 
 fn default_port() -> u16 {
@@ -111,6 +262,10 @@ impl Default for ServerConfig {
             enable_rate_limiting: false,
             rate_limit_rps: default_rate_limit_rps(),
             rate_limit_burst: default_rate_limit_burst(),
+            compaction: CompactionConfig::default(),
+            push_quarantine: default_push_quarantine(),
+            receive_fsck_objects: false,
+            max_decompressed_size: default_max_decompressed_size(),
         }
     }
 }
@@ -19,20 +19,27 @@ use axum::{
 };
 use bytes::Bytes;
 use mediagit_protocol::{
-    RefInfo, RefUpdateRequest, RefUpdateResponse, RefUpdateResult, RefsResponse, WantRequest,
-    WantResponse,
+    Capabilities, RefInfo, RefUpdate, RefUpdateRequest, RefUpdateResponse, RefUpdateResult,
+    RefsResponse, WantRequest, WantResponse,
 };
 use mediagit_security::auth::AuthUser;
-use mediagit_storage::{AzureBackend, GcsBackend, LocalBackend, MinIOBackend, StorageBackend};
+use mediagit_storage::{
+    shared_limiter, BackendFactory, ConcurrencyLimitedBackend, InstrumentedBackend, StorageBackend,
+};
+use mediagit_versioning::fsck::IssueSeverity;
 use mediagit_versioning::{
-    resolve_revision, Commit, ObjectDatabase, ObjectType, Oid, Ref, RefDatabase,
+    resolve_revision, Commit, GcLock, ObjectDatabase, ObjectType, Oid, Ref, RefDatabase,
     StreamingPackWriter, Tree,
 };
+use std::collections::HashMap;
 use std::path::Path as StdPath;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::duplex;
 use tokio_util::io::ReaderStream;
 
+use crate::compaction::RepoCompactionStats;
+use crate::quarantine::{self, Quarantine, PUSH_ID_HEADER};
 use crate::state::AppState;
 
 /// Helper function to check if user has required permission
@@ -63,7 +70,7 @@ fn check_permission(
 }
 
 /// Helper function to create storage backend based on repository configuration
-async fn create_storage_backend(
+pub(crate) async fn create_storage_backend(
     repo_path: &StdPath,
 ) -> Result<Arc<dyn StorageBackend>, StatusCode> {
     // Load repository configuration
@@ -74,155 +81,89 @@ async fn create_storage_backend(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    // Create storage backend based on configuration
-    let storage: Arc<dyn StorageBackend> = match &config.storage {
-        mediagit_config::StorageConfig::FileSystem(fs_config) => {
-            // Use configured base_path - it can be absolute or relative to repo
-            let storage_path = if std::path::Path::new(&fs_config.base_path).is_absolute() {
-                std::path::PathBuf::from(&fs_config.base_path)
-            } else if fs_config.base_path == "./data" {
-                // Default config value - use .mediagit instead
-                repo_path.join(".mediagit")
-            } else {
-                repo_path.join(&fs_config.base_path)
-            };
-            tracing::debug!(
-                "Using filesystem storage backend: {}",
-                storage_path.display()
-            );
-            let storage = LocalBackend::new(&storage_path).await.map_err(|e| {
-                tracing::error!("Failed to initialize filesystem backend: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
-            Arc::new(storage)
-        }
-        mediagit_config::StorageConfig::S3(s3_config) => {
-            // Use MinIOBackend for custom endpoints (MinIO, DigitalOcean Spaces, etc.)
-            // Use S3Backend for AWS S3
-            if let Some(endpoint) = &s3_config.endpoint {
-                tracing::info!(
-                    "Using MinIO/S3-compatible backend: bucket={}, endpoint={}",
-                    s3_config.bucket,
-                    endpoint
-                );
-
-                let storage = MinIOBackend::new(
-                    endpoint,
-                    &s3_config.bucket,
-                    s3_config.access_key_id.as_deref().unwrap_or(""),
-                    s3_config.secret_access_key.as_deref().unwrap_or(""),
-                )
-                .await
-                .map_err(|e| {
-                    tracing::error!("Failed to initialize MinIO backend: {}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
-                Arc::new(storage)
-            } else {
-                tracing::info!("Using AWS S3 storage backend: bucket={}", s3_config.bucket);
-
-                // For AWS S3 without custom endpoint, we use MinIOBackend with the AWS S3 endpoint
-                // This avoids setting global environment variables which could cause race conditions
-                // in concurrent requests.
-                let aws_endpoint = format!("https://s3.{}.amazonaws.com", s3_config.region);
-
-                let storage = MinIOBackend::new(
-                    &aws_endpoint,
-                    &s3_config.bucket,
-                    s3_config.access_key_id.as_deref().unwrap_or(""),
-                    s3_config.secret_access_key.as_deref().unwrap_or(""),
-                )
-                .await
-                .map_err(|e| {
-                    tracing::error!("Failed to initialize S3 backend: {}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
-                Arc::new(storage)
-            }
-        }
-        mediagit_config::StorageConfig::Azure(azure_config) => {
-            tracing::info!(
-                "Using Azure storage backend: container={}",
-                azure_config.container
-            );
-
-            // Use connection string if provided, otherwise use account key
-            let storage = if let Some(conn_str) = &azure_config.connection_string {
-                AzureBackend::with_connection_string(&azure_config.container, conn_str)
-                    .await
-                    .map_err(|e| {
-                        tracing::error!(
-                            "Failed to initialize Azure backend with connection string: {}",
-                            e
-                        );
-                        StatusCode::INTERNAL_SERVER_ERROR
-                    })?
-            } else if let Some(account_key) = &azure_config.account_key {
-                AzureBackend::with_account_key(
-                    &azure_config.account_name,
-                    &azure_config.container,
-                    account_key,
-                )
-                .await
-                .map_err(|e| {
-                    tracing::error!("Failed to initialize Azure backend with account key: {}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?
-            } else {
-                tracing::error!("Azure backend requires either connection_string or account_key");
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            };
-            Arc::new(storage)
-        }
-        mediagit_config::StorageConfig::GCS(gcs_config) => {
-            tracing::info!(
-                "Using GCS storage backend: bucket={}, project={}",
-                gcs_config.bucket,
-                gcs_config.project_id
-            );
+    tracing::debug!(
+        "Using {} storage backend for {}",
+        config.storage.backend_name(),
+        repo_path.display()
+    );
+    let storage = BackendFactory::for_repo(&config, repo_path)
+        .open_config(&config.storage)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to initialize storage backend: {:#}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let storage = Arc::new(InstrumentedBackend::new(
+        storage,
+        config.storage.backend_name(),
+    ));
+
+    // All repositories served by this process draw from one pool of permits
+    match config.storage.max_concurrent_ops() {
+        Some(max_ops) => Ok(Arc::new(ConcurrencyLimitedBackend::new(
+            storage,
+            shared_limiter(max_ops),
+        ))),
+        None => Ok(storage),
+    }
+}
 
-            // Determine credentials path - from config or default application credentials
-            let credentials_path = gcs_config
-                .credentials_path
-                .as_deref()
-                .or_else(|| {
-                    std::env::var("GOOGLE_APPLICATION_CREDENTIALS")
-                        .ok()
-                        .as_deref()
-                        .map(|_| "")
-                })
-                .unwrap_or("");
+/// How long a request waits for a running gc before going ahead
+///
+/// Going ahead is safe: gc leaves recently written objects alone, and only
+/// prunes objects no ref reaches.
+const GC_LOCK_WAIT: Duration = Duration::from_secs(5);
 
-            let storage = if credentials_path.is_empty() {
-                // Use default credentials (ADC) - for GKE, Cloud Run, etc.
-                GcsBackend::with_default_credentials(&gcs_config.project_id, &gcs_config.bucket)
-                    .await
-                    .map_err(|e| {
-                        tracing::error!(
-                            "Failed to initialize GCS backend with default credentials: {}",
-                            e
-                        );
-                        StatusCode::INTERNAL_SERVER_ERROR
-                    })?
-            } else {
-                // Use service account JSON file
-                GcsBackend::new(&gcs_config.project_id, &gcs_config.bucket, credentials_path)
-                    .await
-                    .map_err(|e| {
-                        tracing::error!("Failed to initialize GCS backend: {}", e);
-                        StatusCode::INTERNAL_SERVER_ERROR
-                    })?
-            };
+/// The push ID of a request whose uploads should be quarantined
+///
+/// `None` when the client sent no `X-Push-ID` header or the quarantine is
+/// disabled on this server.
+fn quarantine_push_id<'a>(state: &AppState, headers: &'a HeaderMap) -> Option<&'a str> {
+    if !state.push_quarantine {
+        return None;
+    }
+    headers.get(PUSH_ID_HEADER).and_then(|v| v.to_str().ok())
+}
 
-            Arc::new(storage)
-        }
-        mediagit_config::StorageConfig::Multi(_) => {
-            tracing::error!("Multi-backend storage is not yet implemented");
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
+/// Storage that a push upload writes to: the push's quarantine when it has
+/// one, otherwise the repository itself
+async fn push_storage(
+    state: &AppState,
+    repo_path: &StdPath,
+    headers: &HeaderMap,
+) -> Result<Arc<dyn StorageBackend>, StatusCode> {
+    let Some(push_id) = quarantine_push_id(state, headers) else {
+        return create_storage_backend(repo_path).await;
     };
+    if !quarantine::is_valid_push_id(push_id) {
+        tracing::warn!("Invalid {} header: {:?}", PUSH_ID_HEADER, push_id);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let quarantine = Quarantine::open(repo_path, push_id).await.map_err(|e| {
+        tracing::error!("Failed to open push quarantine: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(quarantine.storage())
+}
 
-    Ok(storage)
+/// Wait briefly for a gc running in the repository to finish
+async fn wait_for_gc(repo_path: &StdPath) {
+    let storage_path = repo_path.join(".mediagit");
+    if !GcLock::wait_until_released(&storage_path, GC_LOCK_WAIT).await {
+        tracing::warn!(
+            repo = %repo_path.display(),
+            "gc still running after {:?}, proceeding",
+            GC_LOCK_WAIT
+        );
+    }
+}
+
+/// Capabilities a request's client selected that this server supports
+///
+/// Clients that predate capabilities select none; anything this server does
+/// not know is ignored.
+fn session_capabilities(selected: &[String]) -> Capabilities {
+    Capabilities::supported().negotiate(&Capabilities::parse(selected))
 }
 
 /// GET /:repo/info/refs - List all refs in the repository
@@ -252,8 +193,6 @@ pub async fn get_refs(
     let _storage = create_storage_backend(&repo_path).await?;
     let refdb = RefDatabase::new(repo_path.join(".mediagit"));
 
-    // List all refs by scanning refs directory
-    let refs_dir = repo_path.join(".mediagit/refs");
     let mut ref_infos = Vec::new();
 
     // Read HEAD
@@ -265,71 +204,57 @@ pub async fn get_refs(
         });
     }
 
-    // Recursively read all refs in refs/heads, refs/tags, refs/remotes, etc.
-    if refs_dir.exists() {
-        // Use walkdir pattern to recursively traverse all ref directories
-        let mut dirs_to_visit = vec![refs_dir.clone()];
-
-        while let Some(current_dir) = dirs_to_visit.pop() {
-            if let Ok(entries) = std::fs::read_dir(&current_dir) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.is_dir() {
-                        // Add subdirectory to visit (for nested refs like feature/branch or remotes/origin/main)
-                        dirs_to_visit.push(path);
-                    } else if path.is_file() {
-                        // Skip .meta sidecar files (annotated tag metadata)
-                        if path.extension().and_then(|e| e.to_str()) == Some("meta") {
-                            continue;
-                        }
-                        // Construct ref name relative to refs_dir
-                        // e.g., refs/heads/main, refs/heads/feature/branch, refs/remotes/origin/main
-                        if let Ok(relative_path) = path.strip_prefix(&refs_dir) {
-                            let ref_name = format!(
-                                "refs/{}",
-                                relative_path.to_string_lossy().replace('\\', "/")
-                            );
-
-                            if let Ok(r) = refdb.read(&ref_name).await {
-                                ref_infos.push(RefInfo {
-                                    name: ref_name,
-                                    oid: r.oid.map(|o| o.to_hex()).unwrap_or_default(),
-                                    target: r.target,
-                                });
-                            }
-                        }
-                    }
-                }
-            }
+    // Every ref under refs/, loose or packed
+    let ref_names = refdb.list("").await.map_err(|e| {
+        tracing::error!("Failed to list refs of {}: {:#}", repo, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    for ref_name in ref_names {
+        if let Ok(r) = refdb.read(&ref_name).await {
+            ref_infos.push(RefInfo {
+                name: ref_name,
+                oid: r.oid.map(|o| o.to_hex()).unwrap_or_default(),
+                target: r.target,
+            });
         }
     }
 
     Ok(Json(RefsResponse {
         refs: ref_infos,
-        capabilities: vec!["pack-v1".to_string()],
+        capabilities: Capabilities::supported().to_strings(),
     }))
 }
 
 /// POST /:repo/objects/pack - Upload a pack file (streaming)
+///
+/// The pack is spooled to a temporary file and its checksum and index are
+/// verified before any object reaches the ODB, so a pack corrupted in transit
+/// is rejected with `400 Bad Request` and leaves the repository untouched.
+/// With an `X-Push-ID` header the objects go to that push's quarantine.
 pub async fn upload_pack(
     Path(repo): Path<String>,
     State(state): State<Arc<AppState>>,
     auth_user: Option<Extension<AuthUser>>,
+    headers: HeaderMap,
     body: axum::body::Body,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, axum::response::Response> {
     tracing::info!("POST /{}/objects/pack (streaming)", repo);
 
     // Check permission: repo:write required
-    check_permission(auth_user.as_deref(), "repo:write", state.is_auth_enabled())?;
+    check_permission(auth_user.as_deref(), "repo:write", state.is_auth_enabled())
+        .map_err(IntoResponse::into_response)?;
 
     let repo_path = state.repos_dir.join(&repo);
     if !repo_path.exists() {
         tracing::warn!("Repository not found: {}", repo);
-        return Err(StatusCode::NOT_FOUND);
+        return Err(StatusCode::NOT_FOUND.into_response());
     }
+    wait_for_gc(&repo_path).await;
 
     // Initialize storage and ODB for proper compression and storage
-    let storage = create_storage_backend(&repo_path).await?;
+    let storage = push_storage(&state, &repo_path, &headers)
+        .await
+        .map_err(IntoResponse::into_response)?;
     let odb = ObjectDatabase::with_smart_compression(storage, 1000);
 
     // Convert body to AsyncRead stream
@@ -338,14 +263,37 @@ pub async fn upload_pack(
 
     let stream = body.into_data_stream().map_err(std::io::Error::other);
 
-    let stream_reader = StreamReader::new(stream);
+    let mut stream_reader = StreamReader::new(stream);
+
+    // Spool the pack to disk so it can be verified before anything is stored
+    let spool = spool_pack(&repo_path, &mut stream_reader)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to receive pack: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+
+    let verified = verify_pack(spool.path()).await.map_err(|e| {
+        tracing::warn!("Rejected corrupt pack for {}: {}", repo, e);
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Rejected corrupt pack: {}", e),
+        )
+            .into_response()
+    })?;
+    tracing::info!("Verified pack with {} objects", verified);
+
+    let pack_file = tokio::fs::File::open(spool.path()).await.map_err(|e| {
+        tracing::error!("Failed to reopen received pack: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
 
     // Create streaming pack reader
-    let mut reader = mediagit_versioning::StreamingPackReader::new(stream_reader)
+    let mut reader = mediagit_versioning::StreamingPackReader::new(pack_file)
         .await
         .map_err(|e| {
             tracing::error!("Failed to create streaming pack reader: {}", e);
-            StatusCode::BAD_REQUEST
+            StatusCode::BAD_REQUEST.into_response()
         })?;
 
     tracing::info!("Processing streaming pack upload");
@@ -355,13 +303,13 @@ pub async fn upload_pack(
     while let Some(result) = reader.next_object().await {
         let (oid, obj_type, data) = result.map_err(|e| {
             tracing::error!("Failed to read object from pack stream: {}", e);
-            StatusCode::BAD_REQUEST
+            StatusCode::BAD_REQUEST.into_response()
         })?;
 
         // Write through ODB which handles compression and correct storage paths
         let stored_oid = odb.write(obj_type, &data).await.map_err(|e| {
             tracing::error!("Failed to write object {} to ODB: {}", oid, e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
         })?;
 
         if stored_oid != oid {
@@ -385,6 +333,39 @@ pub async fn upload_pack(
     Ok(StatusCode::OK)
 }
 
+/// Copy an uploaded pack into a temporary file inside the repository
+///
+/// The file is removed when the returned handle is dropped.
+async fn spool_pack<R: tokio::io::AsyncRead + Unpin>(
+    repo_path: &StdPath,
+    body: &mut R,
+) -> std::io::Result<tempfile::NamedTempFile> {
+    use tokio::io::AsyncWriteExt;
+
+    let spool = tempfile::Builder::new()
+        .prefix("incoming-")
+        .suffix(".pack")
+        .tempfile_in(repo_path)?;
+    let mut file = tokio::fs::File::from_std(spool.reopen()?);
+    tokio::io::copy(body, &mut file).await?;
+    file.flush().await?;
+    Ok(spool)
+}
+
+/// Read a spooled pack end to end, checking every object's OID against the
+/// pack index and the trailing checksum
+///
+/// Returns the number of objects in the pack.
+async fn verify_pack(path: &StdPath) -> std::io::Result<u32> {
+    let file = tokio::fs::File::open(path).await?;
+    let mut reader = mediagit_versioning::StreamingPackReader::new(file).await?;
+    while let Some(object) = reader.next_object().await {
+        object?;
+    }
+    reader.verify_checksum().await?;
+    Ok(reader.objects_processed())
+}
+
 /// GET /:repo/objects/pack - Download a pack file (after POST to /objects/want)
 /// Requires X-Request-ID header with the request_id from POST /objects/want response.
 pub async fn download_pack(
@@ -437,6 +418,7 @@ pub async fn download_pack(
     if !repo_path.exists() {
         return Err(StatusCode::NOT_FOUND);
     }
+    wait_for_gc(&repo_path).await;
 
     // Initialize storage and odb
     let storage = create_storage_backend(&repo_path).await?;
@@ -514,8 +496,7 @@ pub async fn download_pack(
                 .map_err(|e| anyhow::anyhow!("Failed to create streaming pack writer: {}", e))?;
 
             for oid in objects_to_stream {
-                let obj_data = odb_clone.read(&oid).await?;
-                let obj_type = detect_object_type(&obj_data).unwrap_or(ObjectType::Blob);
+                let (obj_type, obj_data) = odb_clone.read_typed(&oid).await?;
                 pack_writer
                     .write_object(oid, obj_type, &obj_data)
                     .await
@@ -582,8 +563,8 @@ async fn collect_objects_recursive(
     }
 
     // Try to read the object - only for non-chunked objects
-    let obj_data = match odb.read(&oid).await {
-        Ok(data) => data,
+    let (obj_type, obj_data) = match odb.read_typed(&oid).await {
+        Ok(typed) => typed,
         Err(e) => {
             tracing::warn!("Object {} not found: {}", oid, e);
             return Ok(()); // Skip missing objects
@@ -593,9 +574,7 @@ async fn collect_objects_recursive(
     // Add this object to collection
     collected.push(oid);
 
-    // Determine type and recursively collect children
-    let obj_type = detect_object_type(&obj_data).unwrap_or(ObjectType::Blob);
-
+    // Recursively collect children
     match obj_type {
         ObjectType::Commit => {
             // Parse commit to get tree OID and parent commits using Commit's own deserializer
@@ -650,6 +629,11 @@ pub async fn request_objects(
         want_req.have.len()
     );
 
+    tracing::debug!(
+        "Client capabilities: {}",
+        session_capabilities(&want_req.capabilities)
+    );
+
     // Check permission: repo:read required
     check_permission(auth_user.as_deref(), "repo:read", state.is_auth_enabled())?;
 
@@ -665,14 +649,117 @@ pub async fn request_objects(
     Ok(Json(WantResponse { request_id }))
 }
 
+/// Why `update` cannot be applied to the refs as they are now, if it cannot
+///
+/// With `lease`, the ref must have exactly the update's `old_oid`, forced or
+/// not.
+async fn ref_update_error(
+    refdb: &RefDatabase,
+    update: &RefUpdate,
+    force: bool,
+    lease: bool,
+) -> Option<String> {
+    if lease {
+        // Only a missing ref counts as "nothing"; a ref that cannot be read
+        // must not let a lease expecting nothing overwrite it
+        let current = match refdb.read_optional(&update.name).await {
+            Ok(current_ref) => current_ref.and_then(|r| r.oid).map(|oid| oid.to_hex()),
+            Err(e) => {
+                tracing::error!("Cannot check lease on '{}': {:#}", update.name, e);
+                return Some(format!("cannot read current value of the ref: {:#}", e));
+            }
+        };
+        if current != update.old_oid {
+            let describe = |oid: &Option<String>| match oid {
+                Some(oid) => oid[..8.min(oid.len())].to_string(),
+                None => "nothing".to_string(),
+            };
+            tracing::warn!(
+                "Lease on '{}' broken: expected {:?}, found {:?}",
+                update.name,
+                update.old_oid,
+                current
+            );
+            return Some(format!(
+                "stale info: the ref moved since last fetch (expected {}, found {}); fetch and retry",
+                describe(&update.old_oid),
+                describe(&current)
+            ));
+        }
+    }
+
+    if update.delete {
+        // HEAD protection: prevent deleting the currently active branch
+        if let Ok(head) = refdb.read("HEAD").await {
+            if head.target.as_deref() == Some(&update.name) {
+                tracing::warn!(
+                    "Refusing to delete '{}': it is the current HEAD",
+                    update.name
+                );
+                return Some(format!(
+                    "refusing to delete the current branch: '{}'",
+                    update.name
+                ));
+            }
+        }
+    }
+
+    // Safety check: verify old_oid matches (if provided)
+    if let Some(expected_old) = &update.old_oid {
+        if let Ok(current_ref) = refdb.read(&update.name).await {
+            if let Some(current_oid) = &current_ref.oid {
+                let current_oid_str = current_oid.to_hex();
+                if &current_oid_str != expected_old && !force {
+                    tracing::warn!(
+                        "Ref {} rejected for '{}': expected {}, got {}",
+                        if update.delete { "delete" } else { "update" },
+                        update.name,
+                        expected_old,
+                        current_oid_str
+                    );
+                    return Some(if update.delete {
+                        "ref changed since last fetch".to_string()
+                    } else {
+                        "not fast-forward".to_string()
+                    });
+                }
+            }
+        }
+    }
+
+    // Verify ref exists before deleting
+    if update.delete && refdb.read(&update.name).await.is_err() {
+        tracing::warn!("Ref '{}' does not exist, cannot delete", update.name);
+        return Some(format!("ref '{}' does not exist", update.name));
+    }
+
+    None
+}
+
 /// POST /:repo/refs/update - Update repository refs
+///
+/// With an `X-Push-ID` header whose push uploaded objects, the update is
+/// atomic: every ref is checked first, and the push's quarantined objects are
+/// migrated into the repository only if all of them can be applied. If any is
+/// rejected, no ref is changed and the quarantine is deleted. With
+/// `receive_fsck_objects` the quarantined objects must also pass
+/// [`Quarantine::fsck`].
+///
+/// A request with `lease` set is a compare-and-swap: each ref must still have
+/// the update's `old_oid`. Ref updates to one repository are serialized, so
+/// nothing moves a ref between the check and the write.
 pub async fn update_refs(
     Path(repo): Path<String>,
     State(state): State<Arc<AppState>>,
     auth_user: Option<Extension<AuthUser>>,
+    headers: HeaderMap,
     Json(req): Json<RefUpdateRequest>,
 ) -> Result<Json<RefUpdateResponse>, StatusCode> {
     tracing::info!("POST /{}/refs/update ({} updates)", repo, req.updates.len());
+    tracing::debug!(
+        "Client capabilities: {}",
+        session_capabilities(&req.capabilities)
+    );
 
     // Check permission: repo:write required
     check_permission(auth_user.as_deref(), "repo:write", state.is_auth_enabled())?;
@@ -682,76 +769,116 @@ pub async fn update_refs(
         return Err(StatusCode::NOT_FOUND);
     }
 
+    // Checking and writing refs happen under the lock, so leases hold
+    let ref_lock = state.ref_lock(&repo).await;
+    let _ref_guard = ref_lock.lock().await;
+
     // Initialize storage and refdb
-    let _storage = create_storage_backend(&repo_path).await?;
+    let storage = create_storage_backend(&repo_path).await?;
     let refdb = RefDatabase::new(repo_path.join(".mediagit"));
 
-    let mut results = Vec::new();
-    let mut all_success = true;
+    let quarantine = match quarantine_push_id(&state, &headers) {
+        Some(push_id) => Quarantine::existing(&repo_path, push_id)
+            .await
+            .map_err(|e| {
+                tracing::warn!("Invalid push quarantine: {}", e);
+                StatusCode::BAD_REQUEST
+            })?,
+        None => None,
+    };
 
-    for update in req.updates {
-        // Handle ref deletion
-        if update.delete {
-            // HEAD protection: prevent deleting the currently active branch
-            if let Ok(head) = refdb.read("HEAD").await {
-                if head.target.as_deref() == Some(&update.name) {
-                    tracing::warn!(
-                        "Refusing to delete '{}': it is the current HEAD",
-                        update.name
-                    );
-                    results.push(RefUpdateResult {
-                        ref_name: update.name.clone(),
-                        success: false,
-                        error: Some(format!(
-                            "refusing to delete the current branch: '{}'",
-                            update.name
-                        )),
-                    });
-                    all_success = false;
-                    continue;
-                }
+    if let Some(quarantine) = quarantine {
+        let mut rejected = HashMap::new();
+        for update in &req.updates {
+            let error = if !update.delete && Oid::from_hex(&update.new_oid).is_err() {
+                Some(format!("invalid object id '{}'", update.new_oid))
+            } else {
+                ref_update_error(&refdb, update, req.force, req.lease).await
+            };
+            if let Some(error) = error {
+                rejected.insert(update.name.clone(), error);
             }
+        }
 
-            // Safety check: verify old_oid matches (if provided)
-            if let Some(expected_old) = &update.old_oid {
-                if let Ok(current_ref) = refdb.read(&update.name).await {
-                    if let Some(current_oid) = &current_ref.oid {
-                        let current_oid_str = current_oid.to_hex();
-                        if &current_oid_str != expected_old && !req.force {
-                            tracing::warn!(
-                                "Ref delete rejected for '{}': expected {}, got {}",
-                                update.name,
-                                expected_old,
-                                current_oid_str
-                            );
-                            results.push(RefUpdateResult {
-                                ref_name: update.name.clone(),
-                                success: false,
-                                error: Some("ref changed since last fetch".to_string()),
-                            });
-                            all_success = false;
-                            continue;
-                        }
-                    }
+        if rejected.is_empty() && state.receive_fsck_objects {
+            let tips: Vec<Oid> = req
+                .updates
+                .iter()
+                .filter(|update| !update.delete)
+                .filter_map(|update| Oid::from_hex(&update.new_oid).ok())
+                .collect();
+            let report = quarantine
+                .fsck(storage.clone(), &tips, state.max_decompressed_size)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to check pushed objects: {:#}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            let errors = report.issues_by_severity(IssueSeverity::Error);
+            if let Some(issue) = errors.first() {
+                tracing::warn!(
+                    "Rejecting push: {} problem(s) in pushed objects, first: {}",
+                    errors.len(),
+                    issue.message
+                );
+                let error = format!("fsck failed: {}", issue.message);
+                for update in &req.updates {
+                    rejected.insert(update.name.clone(), error.clone());
                 }
             }
+        }
 
-            // Verify ref exists before deleting
-            match refdb.read(&update.name).await {
-                Ok(_) => {}
-                Err(_) => {
-                    tracing::warn!("Ref '{}' does not exist, cannot delete", update.name);
-                    results.push(RefUpdateResult {
-                        ref_name: update.name.clone(),
-                        success: false,
-                        error: Some(format!("ref '{}' does not exist", update.name)),
-                    });
-                    all_success = false;
-                    continue;
-                }
+        if !rejected.is_empty() {
+            tracing::warn!(
+                "Rejecting push: {} of {} ref updates failed, discarding quarantined objects",
+                rejected.len(),
+                req.updates.len()
+            );
+            if let Err(e) = quarantine.discard().await {
+                tracing::error!("{}", e);
             }
+            let results = req
+                .updates
+                .into_iter()
+                .map(|update| {
+                    let error = rejected
+                        .remove(&update.name)
+                        .unwrap_or_else(|| "atomic push failed".to_string());
+                    RefUpdateResult {
+                        ref_name: update.name,
+                        success: false,
+                        error: Some(error),
+                    }
+                })
+                .collect();
+            return Ok(Json(RefUpdateResponse {
+                success: false,
+                results,
+            }));
+        }
+
+        quarantine.migrate(storage.as_ref()).await.map_err(|e| {
+            tracing::error!("Failed to migrate quarantined objects: {:#}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
 
-            // Delete the ref
+    let mut results = Vec::new();
+    let mut all_success = true;
+
+    for update in req.updates {
+        if let Some(error) = ref_update_error(&refdb, &update, req.force, req.lease).await {
+            results.push(RefUpdateResult {
+                ref_name: update.name,
+                success: false,
+                error: Some(error),
+            });
+            all_success = false;
+            continue;
+        }
+
+        // Handle ref deletion
+        if update.delete {
             match refdb.delete(&update.name).await {
                 Ok(_) => {
                     tracing::info!("Deleted ref '{}'", update.name);
@@ -774,29 +901,6 @@ pub async fn update_refs(
             continue;
         }
 
-        // Check if old_oid matches (if provided)
-        if let Some(expected_old) = &update.old_oid {
-            if let Ok(current_ref) = refdb.read(&update.name).await {
-                if let Some(current_oid) = &current_ref.oid {
-                    let current_oid_str = current_oid.to_hex();
-                    if &current_oid_str != expected_old && !req.force {
-                        tracing::warn!(
-                            "Ref update rejected: expected {}, got {}",
-                            expected_old,
-                            current_oid_str
-                        );
-                        results.push(RefUpdateResult {
-                            ref_name: update.name.clone(),
-                            success: false,
-                            error: Some("not fast-forward".to_string()),
-                        });
-                        all_success = false;
-                        continue;
-                    }
-                }
-            }
-        }
-
         // Update the ref
         let new_oid = Oid::from_hex(&update.new_oid).map_err(|_| StatusCode::BAD_REQUEST)?;
         let ref_update = Ref::new_direct(update.name.clone(), new_oid);
@@ -828,24 +932,6 @@ pub async fn update_refs(
     }))
 }
 
-/// Helper function to detect object type from raw object data
-/// MediaGit stores objects with bincode serialization, so we try to deserialize
-/// as Commit or Tree. If neither works, it's a Blob.
-fn detect_object_type(data: &[u8]) -> Option<ObjectType> {
-    // Try to deserialize as Commit first using its own deserializer
-    if Commit::deserialize(data).is_ok() {
-        return Some(ObjectType::Commit);
-    }
-
-    // Try to deserialize as Tree using its own deserializer
-    if Tree::deserialize(data).is_ok() {
-        return Some(ObjectType::Tree);
-    }
-
-    // If neither, it's a Blob (or at minimum treat it as one)
-    Some(ObjectType::Blob)
-}
-
 // ============================================================================
 // Chunk Transfer Endpoints - For efficient large file push
 // ============================================================================
@@ -909,6 +995,7 @@ pub async fn upload_chunk(
     Path((repo, chunk_id)): Path<(String, String)>,
     State(state): State<Arc<AppState>>,
     auth_user: Option<Extension<AuthUser>>,
+    headers: HeaderMap,
     body: Bytes,
 ) -> Result<StatusCode, StatusCode> {
     // Check write permission
@@ -928,8 +1015,8 @@ pub async fn upload_chunk(
         return Err(StatusCode::NOT_FOUND);
     }
 
-    // Create storage backend
-    let storage = create_storage_backend(&repo_path).await?;
+    // Create storage backend (or the push's quarantine)
+    let storage = push_storage(&state, &repo_path, &headers).await?;
 
     // Store chunk directly (already compressed)
     let chunk_key = format!("chunks/{}", chunk_id);
@@ -949,6 +1036,7 @@ pub async fn upload_manifest(
     Path((repo, oid)): Path<(String, String)>,
     State(state): State<Arc<AppState>>,
     auth_user: Option<Extension<AuthUser>>,
+    headers: HeaderMap,
     body: Bytes,
 ) -> Result<StatusCode, StatusCode> {
     // Check write permission
@@ -968,8 +1056,8 @@ pub async fn upload_manifest(
         return Err(StatusCode::NOT_FOUND);
     }
 
-    // Create storage backend
-    let storage = create_storage_backend(&repo_path).await?;
+    // Create storage backend (or the push's quarantine)
+    let storage = push_storage(&state, &repo_path, &headers).await?;
 
     // Store manifest
     let manifest_key = format!("manifests/{}", oid);
@@ -1005,6 +1093,7 @@ pub async fn download_chunk(
         tracing::warn!(repo = %repo, "Repository not found");
         return Err(StatusCode::NOT_FOUND);
     }
+    wait_for_gc(&repo_path).await;
 
     // Create storage backend
     let storage = create_storage_backend(&repo_path).await?;
@@ -1043,6 +1132,7 @@ pub async fn download_manifest(
         tracing::warn!(repo = %repo, "Repository not found");
         return Err(StatusCode::NOT_FOUND);
     }
+    wait_for_gc(&repo_path).await;
 
     // Create storage backend
     let storage = create_storage_backend(&repo_path).await?;
@@ -1221,6 +1311,7 @@ pub async fn download_file_by_path(
     if !repo_path.exists() {
         return Err(StatusCode::NOT_FOUND);
     }
+    wait_for_gc(&repo_path).await;
 
     let storage = create_storage_backend(&repo_path).await?;
     let odb = Arc::new(ObjectDatabase::with_smart_compression(storage, 1000));
@@ -1376,3 +1467,14 @@ pub async fn list_tree_root(
     tracing::info!("GET /{}/tree ref={}", repo, params.ref_name);
     list_tree_impl(repo, String::new(), state, auth_user, params.ref_name).await
 }
+
+/// GET /compaction — Background compaction history per repository
+pub async fn compaction_stats(
+    State(state): State<Arc<AppState>>,
+    auth_user: Option<Extension<AuthUser>>,
+) -> Result<Json<HashMap<String, RepoCompactionStats>>, StatusCode> {
+    check_permission(auth_user.as_deref(), "repo:admin", state.is_auth_enabled())?;
+
+    let compactor = state.compactor.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(compactor.stats()))
+}
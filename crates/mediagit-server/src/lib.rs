@@ -40,16 +40,20 @@
 // This allows integration tests to use server components
 
 pub mod auth_routes;
+pub mod compaction;
 pub mod config;
 pub mod handlers;
+pub mod quarantine;
 pub mod security;
 pub mod state;
 
 pub use auth_routes::create_auth_router;
-pub use config::ServerConfig;
+pub use compaction::{CompactionOutcome, Compactor, RepoCompactionStats};
+pub use config::{CompactionConfig, ServerConfig};
+pub use quarantine::Quarantine;
 pub use security::validate_repo_name;
 pub use security::RateLimitConfig;
-pub use state::AppState;
+pub use state::{AppState, RefLocks};
 
 use axum::{
     extract::DefaultBodyLimit,
@@ -107,6 +111,7 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         )
         .route("/{repo}/tree/{*path}", get(handlers::list_tree))
         .route("/{repo}/tree", get(handlers::list_tree_root))
+        .route("/compaction", get(handlers::compaction_stats))
         .with_state(Arc::clone(&state));
 
     // Apply authentication middleware to Git routes if enabled
@@ -238,6 +243,7 @@ pub fn create_router_with_rate_limit(
         )
         .route("/{repo}/tree/{*path}", get(handlers::list_tree))
         .route("/{repo}/tree", get(handlers::list_tree_root))
+        .route("/compaction", get(handlers::compaction_stats))
         .with_state(Arc::clone(&state));
 
     // Apply middleware layers
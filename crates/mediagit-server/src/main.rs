@@ -18,8 +18,10 @@ use std::sync::Arc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use mediagit_server::{
-    create_router, create_router_with_rate_limit, AppState, RateLimitConfig, ServerConfig,
+    create_router, create_router_with_rate_limit, AppState, Compactor, RateLimitConfig,
+    ServerConfig,
 };
+use tokio_util::sync::CancellationToken;
 
 /// MediaGit Server - HTTP(S) server for MediaGit repositories
 #[derive(Parser, Debug)]
@@ -84,21 +86,63 @@ async fn main() -> Result<()> {
     std::fs::create_dir_all(&config.repos_dir)?;
     tracing::info!("Repositories directory: {:?}", config.repos_dir);
 
+    // Cancelled on Ctrl+C to stop serving and background tasks
+    let shutdown = CancellationToken::new();
+    {
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                tracing::info!("Shutting down");
+            }
+            shutdown.cancel();
+        });
+    }
+
     // Setup shared state with optional authentication
-    let state = if config.enable_auth {
+    let mut state = if config.enable_auth {
         let jwt_secret = config.jwt_secret.as_deref().ok_or_else(|| {
             anyhow::anyhow!("JWT secret is required when authentication is enabled")
         })?;
         tracing::info!("Authentication is ENABLED");
-        Arc::new(AppState::new_with_full_auth(
-            config.repos_dir.clone(),
-            jwt_secret,
-        ))
+        AppState::new_with_full_auth(config.repos_dir.clone(), jwt_secret)
     } else {
         tracing::warn!("Authentication is DISABLED - not suitable for production!");
-        Arc::new(AppState::new(config.repos_dir.clone()))
+        AppState::new(config.repos_dir.clone())
     };
 
+    // Background compaction of hosted repositories
+    let compaction_task = if config.compaction.enabled {
+        tracing::info!(
+            "Compaction ENABLED: every {}s, threshold {} loose objects",
+            config.compaction.interval_secs,
+            config.compaction.loose_object_threshold
+        );
+        let compactor = Arc::new(
+            Compactor::new(config.repos_dir.clone(), config.compaction.clone())
+                .with_ref_locks(state.ref_locks()),
+        );
+        state = state.with_compactor(Arc::clone(&compactor));
+        Some(compactor.spawn(shutdown.clone()))
+    } else {
+        None
+    };
+    if !config.push_quarantine {
+        tracing::info!("Push quarantine DISABLED: pushed objects are stored immediately");
+    }
+    if config.receive_fsck_objects {
+        if config.push_quarantine {
+            tracing::info!("Receive fsck ENABLED: pushed objects are verified before refs update");
+        } else {
+            tracing::warn!("receive_fsck_objects has no effect while push_quarantine is disabled");
+        }
+    }
+    let state = Arc::new(
+        state
+            .with_push_quarantine(config.push_quarantine)
+            .with_receive_fsck_objects(config.receive_fsck_objects)
+            .with_max_decompressed_size(config.max_decompressed_size),
+    );
+
     // Build router with optional rate limiting
     let (app, _cleanup_task) = if config.enable_rate_limiting {
         tracing::info!(
@@ -173,6 +217,7 @@ async fn main() -> Result<()> {
             result = https_server => {
                 result??;
             }
+            _ = shutdown.cancelled() => {}
         }
     } else {
         // HTTP only mode
@@ -180,7 +225,15 @@ async fn main() -> Result<()> {
         tracing::info!("Press Ctrl+C to stop");
 
         let listener = tokio::net::TcpListener::bind(&http_bind_addr).await?;
-        axum::serve(listener, app).await?;
+        axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+            .await?;
+    }
+
+    // Stop background tasks before exiting
+    shutdown.cancel();
+    if let Some(task) = compaction_task {
+        task.await.ok();
     }
 
     Ok(())
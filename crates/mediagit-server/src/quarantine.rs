@@ -0,0 +1,310 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Push quarantine
+//!
+//! Objects, chunks and manifests uploaded with an `X-Push-ID` header are
+//! written to `.mediagit/quarantine/<push id>/` instead of the repository's
+//! storage. `POST /refs/update` with the same ID migrates them into the
+//! repository once every ref update in the request can be applied, and deletes
+//! them if any is rejected, so either all objects and refs of a push land or
+//! none do. This is Git's `GIT_QUARANTINE_PATH` model.
+//!
+//! With `receive_fsck_objects` the quarantined objects are checked before
+//! they are migrated; see [`Quarantine::fsck`].
+//!
+//! Quarantines left behind by clients that never updated refs are removed
+//! once they are older than [`STALE_QUARANTINE_AGE`].
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use mediagit_storage::{LocalBackend, StorageBackend};
+use mediagit_versioning::fsck::{FsckChecker, FsckReport};
+use mediagit_versioning::Oid;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Header carrying the push ID shared by every request of one push
+pub const PUSH_ID_HEADER: &str = "X-Push-ID";
+
+/// Age after which an abandoned quarantine is deleted
+pub const STALE_QUARANTINE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Longest accepted push ID
+const MAX_PUSH_ID_LEN: usize = 64;
+
+/// Objects received by one push, held apart from the repository
+pub struct Quarantine {
+    dir: PathBuf,
+    storage: Arc<LocalBackend>,
+}
+
+impl Quarantine {
+    /// Open the quarantine for `push_id`, creating it if needed
+    ///
+    /// # Errors
+    ///
+    /// Fails if `push_id` is not valid (see [`is_valid_push_id`]).
+    pub async fn open(repo_path: &Path, push_id: &str) -> Result<Self> {
+        let dir = quarantine_dir(repo_path, push_id)?;
+        prune_stale(repo_path, STALE_QUARANTINE_AGE).await;
+
+        let storage = LocalBackend::new(&dir)
+            .await
+            .with_context(|| format!("Failed to create quarantine {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            storage: Arc::new(storage),
+        })
+    }
+
+    /// Open the quarantine for `push_id` if that push uploaded anything
+    pub async fn existing(repo_path: &Path, push_id: &str) -> Result<Option<Self>> {
+        let dir = quarantine_dir(repo_path, push_id)?;
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+        Self::open(repo_path, push_id).await.map(Some)
+    }
+
+    /// Storage that writes into the quarantine
+    pub fn storage(&self) -> Arc<dyn StorageBackend> {
+        self.storage.clone()
+    }
+
+    /// Check the quarantined objects before they are migrated into `repo`
+    ///
+    /// Every object the push uploaded is read and verified, and everything
+    /// they reference must exist in the quarantine or the repository, as must
+    /// each commit in `tips`. Chunks are checked through the objects whose
+    /// manifests list them.
+    ///
+    /// The objects were sent by the client, so none may decompress to more
+    /// than `max_decompressed_size` bytes; a larger one is reported as
+    /// unreadable.
+    pub async fn fsck(
+        &self,
+        repo: Arc<dyn StorageBackend>,
+        tips: &[Oid],
+        max_decompressed_size: u64,
+    ) -> Result<FsckReport> {
+        let mut oids: Vec<Oid> = self
+            .storage
+            .list_objects("")
+            .await?
+            .iter()
+            .filter_map(|key| {
+                let hex = key.strip_prefix("manifests/").unwrap_or(key);
+                (hex.len() == 64).then(|| Oid::from_hex(hex).ok()).flatten()
+            })
+            .collect();
+        oids.sort();
+        oids.dedup();
+
+        let view = Arc::new(QuarantineView {
+            quarantine: self.storage.clone(),
+            repo,
+        });
+        FsckChecker::new(view)
+            .with_max_decompressed_size(max_decompressed_size)
+            .check_transfer(&oids, tips)
+            .await
+    }
+
+    /// Move every quarantined object into `target`, then delete the quarantine
+    ///
+    /// Returns the number of objects moved. Chunks are moved before the
+    /// manifests that list them.
+    pub async fn migrate(self, target: &dyn StorageBackend) -> Result<usize> {
+        let mut keys = self.storage.list_objects("").await?;
+        keys.sort_by_key(|key| !key.starts_with("chunks/"));
+
+        for key in &keys {
+            let data = self
+                .storage
+                .get(key)
+                .await
+                .with_context(|| format!("Failed to read quarantined object {}", key))?;
+            target
+                .put(key, &data)
+                .await
+                .with_context(|| format!("Failed to migrate quarantined object {}", key))?;
+        }
+
+        tracing::info!(
+            objects = keys.len(),
+            quarantine = %self.dir.display(),
+            "Migrated quarantined objects"
+        );
+        self.discard().await?;
+        Ok(keys.len())
+    }
+
+    /// Delete the quarantine and everything in it
+    pub async fn discard(self) -> Result<()> {
+        match tokio::fs::remove_dir_all(&self.dir).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e)
+                .with_context(|| format!("Failed to remove quarantine {}", self.dir.display())),
+        }
+    }
+}
+
+/// A repository as it would be after migration: quarantined objects shadow
+/// the repository's, and writes are not expected
+#[derive(Debug)]
+struct QuarantineView {
+    quarantine: Arc<LocalBackend>,
+    repo: Arc<dyn StorageBackend>,
+}
+
+#[async_trait]
+impl StorageBackend for QuarantineView {
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        if self.quarantine.exists(key).await? {
+            return self.quarantine.get(key).await;
+        }
+        self.repo.get(key).await
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        self.quarantine.put(key, data).await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        Ok(self.quarantine.exists(key).await? || self.repo.exists(key).await?)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.quarantine.delete(key).await
+    }
+
+    async fn list_objects(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = self.quarantine.list_objects(prefix).await?;
+        keys.extend(self.repo.list_objects(prefix).await?);
+        keys.sort();
+        keys.dedup();
+        Ok(keys)
+    }
+}
+
+/// `.mediagit/quarantine/` of the repository at `repo_path`
+fn quarantine_root(repo_path: &Path) -> PathBuf {
+    repo_path.join(".mediagit").join("quarantine")
+}
+
+/// Whether `push_id` is 1-64 ASCII letters, digits, `-` or `_`
+pub fn is_valid_push_id(push_id: &str) -> bool {
+    !push_id.is_empty()
+        && push_id.len() <= MAX_PUSH_ID_LEN
+        && push_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn quarantine_dir(repo_path: &Path, push_id: &str) -> Result<PathBuf> {
+    if !is_valid_push_id(push_id) {
+        anyhow::bail!("Invalid push ID '{}'", push_id);
+    }
+    Ok(quarantine_root(repo_path).join(push_id))
+}
+
+/// Delete quarantines older than `max_age`, returning how many were removed
+pub async fn prune_stale(repo_path: &Path, max_age: Duration) -> usize {
+    let Ok(mut entries) = tokio::fs::read_dir(quarantine_root(repo_path)).await else {
+        return 0;
+    };
+
+    let mut removed = 0;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let stale = entry
+            .metadata()
+            .await
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > max_age);
+        if stale && tokio::fs::remove_dir_all(entry.path()).await.is_ok() {
+            tracing::info!(quarantine = %entry.path().display(), "Removed stale quarantine");
+            removed += 1;
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mediagit_storage::mock::MockBackend;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_migrate_moves_everything() {
+        let repo = TempDir::new().unwrap();
+        let quarantine = Quarantine::open(repo.path(), "push-1").await.unwrap();
+        let storage = quarantine.storage();
+        storage.put("abcdef0123", b"object").await.unwrap();
+        storage.put("chunks/0123abcd", b"chunk").await.unwrap();
+        storage
+            .put("manifests/abcdef0123", b"manifest")
+            .await
+            .unwrap();
+
+        let target = MockBackend::new();
+        assert_eq!(quarantine.migrate(&target).await.unwrap(), 3);
+
+        assert_eq!(target.get("chunks/0123abcd").await.unwrap(), b"chunk");
+        assert_eq!(
+            target.get("manifests/abcdef0123").await.unwrap(),
+            b"manifest"
+        );
+        assert!(!quarantine_root(repo.path()).join("push-1").exists());
+    }
+
+    #[tokio::test]
+    async fn test_discard_and_existing() {
+        let repo = TempDir::new().unwrap();
+        assert!(Quarantine::existing(repo.path(), "push-2")
+            .await
+            .unwrap()
+            .is_none());
+
+        let quarantine = Quarantine::open(repo.path(), "push-2").await.unwrap();
+        quarantine.storage().put("abcdef", b"data").await.unwrap();
+        let quarantine = Quarantine::existing(repo.path(), "push-2")
+            .await
+            .unwrap()
+            .unwrap();
+        quarantine.discard().await.unwrap();
+        assert!(!quarantine_root(repo.path()).join("push-2").exists());
+    }
+
+    #[tokio::test]
+    async fn test_push_id_is_validated() {
+        let repo = TempDir::new().unwrap();
+        for id in ["", "../escape", "a/b", &"x".repeat(65)] {
+            assert!(Quarantine::open(repo.path(), id).await.is_err(), "{}", id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prune_stale() {
+        let repo = TempDir::new().unwrap();
+        Quarantine::open(repo.path(), "old").await.unwrap();
+        assert_eq!(prune_stale(repo.path(), STALE_QUARANTINE_AGE).await, 0);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(prune_stale(repo.path(), Duration::ZERO).await, 1);
+    }
+}
@@ -18,6 +18,7 @@ use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::Mutex;
 
+use crate::compaction::Compactor;
 use mediagit_security::auth::{ApiKeyAuth, AuthLayer, AuthService, JwtAuth};
 
 /// Unique request ID generator
@@ -126,6 +127,40 @@ pub struct AppState {
 
     /// Authentication service with user management (optional)
     pub auth_service: Option<Arc<AuthService>>,
+
+    /// Background compaction task, when enabled
+    pub compactor: Option<Arc<Compactor>>,
+
+    /// Stage objects from pushes that send `X-Push-ID` in a quarantine
+    pub push_quarantine: bool,
+
+    /// Check quarantined objects before accepting a push
+    pub receive_fsck_objects: bool,
+
+    /// Largest size an object received in a push may decompress to
+    pub max_decompressed_size: u64,
+
+    /// Per-repository locks held while refs are checked and updated, so a
+    /// lease is compared and swapped atomically
+    ref_locks: Arc<RefLocks>,
+}
+
+/// Per-repository locks serializing every change to a repository's refs
+///
+/// Shared by ref updates and the [`Compactor`], which packs refs.
+#[derive(Default)]
+pub struct RefLocks(Mutex<HashMap<String, Arc<Mutex<()>>>>);
+
+impl RefLocks {
+    /// Lock serializing ref changes in `repo`
+    pub async fn get(&self, repo: &str) -> Arc<Mutex<()>> {
+        self.0
+            .lock()
+            .await
+            .entry(repo.to_string())
+            .or_default()
+            .clone()
+    }
 }
 
 impl AppState {
@@ -136,6 +171,11 @@ impl AppState {
             want_cache: Mutex::new(WantCache::new()),
             auth_layer: None,
             auth_service: None,
+            compactor: None,
+            push_quarantine: true,
+            receive_fsck_objects: false,
+            max_decompressed_size: crate::config::default_max_decompressed_size(),
+            ref_locks: Arc::default(),
         }
     }
 
@@ -157,6 +197,11 @@ impl AppState {
             want_cache: Mutex::new(WantCache::new()),
             auth_layer: Some(auth_layer),
             auth_service: Some(auth_service),
+            compactor: None,
+            push_quarantine: true,
+            receive_fsck_objects: false,
+            max_decompressed_size: crate::config::default_max_decompressed_size(),
+            ref_locks: Arc::default(),
         }
     }
 
@@ -174,9 +219,55 @@ impl AppState {
             want_cache: Mutex::new(WantCache::new()),
             auth_layer: Some(auth_layer),
             auth_service: Some(auth_service),
+            compactor: None,
+            push_quarantine: true,
+            receive_fsck_objects: false,
+            max_decompressed_size: crate::config::default_max_decompressed_size(),
+            ref_locks: Arc::default(),
         }
     }
 
+    /// Report compaction statistics from this compactor
+    pub fn with_compactor(mut self, compactor: Arc<Compactor>) -> Self {
+        self.compactor = Some(compactor);
+        self
+    }
+
+    /// Enable or disable the push quarantine (enabled by default)
+    pub fn with_push_quarantine(mut self, enabled: bool) -> Self {
+        self.push_quarantine = enabled;
+        self
+    }
+
+    /// Enable or disable checking pushed objects (disabled by default)
+    ///
+    /// Only pushes staged in a quarantine are checked.
+    pub fn with_receive_fsck_objects(mut self, enabled: bool) -> Self {
+        self.receive_fsck_objects = enabled;
+        self
+    }
+
+    /// Reject pushes with objects that decompress to more than
+    /// `max_decompressed_size` bytes (a share of physical memory by default,
+    /// see [`default_max_decompressed_size`](crate::config::default_max_decompressed_size))
+    ///
+    /// Only applies while `receive_fsck_objects` checks pushed objects.
+    /// Objects already stored are served whatever their size.
+    pub fn with_max_decompressed_size(mut self, max_decompressed_size: u64) -> Self {
+        self.max_decompressed_size = max_decompressed_size;
+        self
+    }
+
+    /// Lock serializing ref updates to `repo`
+    pub async fn ref_lock(&self, repo: &str) -> Arc<Mutex<()>> {
+        self.ref_locks.get(repo).await
+    }
+
+    /// The ref locks, to share with a [`Compactor`]
+    pub fn ref_locks(&self) -> Arc<RefLocks> {
+        Arc::clone(&self.ref_locks)
+    }
+
     /// Check if authentication is enabled
     pub fn is_auth_enabled(&self) -> bool {
         self.auth_layer.is_some()
@@ -0,0 +1,217 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Background compaction tests
+//!
+//! Checks that repositories over the loose-object threshold are repacked,
+//! those under it are left alone, and objects and refs stay readable
+//! afterwards.
+
+use axum::{body::Body, http::Request};
+use mediagit_protocol::RefsResponse;
+use mediagit_server::{create_router, AppState, CompactionConfig, CompactionOutcome, Compactor};
+use mediagit_storage::LocalBackend;
+use mediagit_versioning::{GcLock, ObjectDatabase, ObjectType, Oid, Ref, RefDatabase};
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tower::util::ServiceExt;
+
+async fn open_odb(repo: &Path) -> ObjectDatabase {
+    let storage = LocalBackend::new(repo.join(".mediagit")).await.unwrap();
+    ObjectDatabase::with_smart_compression(Arc::new(storage), 100)
+}
+
+/// Create a repository under `repos_dir` holding `count` loose blobs
+async fn create_repo(repos_dir: &Path, name: &str, count: usize) -> Vec<Oid> {
+    let repo = repos_dir.join(name);
+    std::fs::create_dir_all(repo.join(".mediagit")).unwrap();
+    let odb = open_odb(&repo).await;
+
+    let mut oids = Vec::new();
+    for i in 0..count {
+        let data = format!("{} frame {:04} {}", name, i, "x".repeat(200));
+        oids.push(odb.write(ObjectType::Blob, data.as_bytes()).await.unwrap());
+    }
+    oids
+}
+
+fn config(threshold: usize) -> CompactionConfig {
+    CompactionConfig {
+        enabled: true,
+        loose_object_threshold: threshold,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_compacts_only_repos_over_threshold() {
+    let repos_dir = TempDir::new().unwrap();
+    let busy_oids = create_repo(repos_dir.path(), "busy", 12).await;
+    create_repo(repos_dir.path(), "quiet", 3).await;
+    // Directories without a repository are ignored
+    std::fs::create_dir_all(repos_dir.path().join("not-a-repo")).unwrap();
+
+    let compactor = Compactor::new(repos_dir.path().to_path_buf(), config(10));
+    let outcomes = compactor.run_once().await;
+
+    assert_eq!(
+        outcomes,
+        vec![
+            (
+                "busy".to_string(),
+                CompactionOutcome::Compacted {
+                    objects_reclaimed: 12,
+                    refs_packed: 0,
+                }
+            ),
+            (
+                "quiet".to_string(),
+                CompactionOutcome::Skipped { loose_objects: 3 }
+            ),
+        ]
+    );
+
+    let odb = open_odb(&repos_dir.path().join("busy")).await;
+    assert_eq!(odb.count_loose_objects().await.unwrap(), 0);
+    for oid in &busy_oids {
+        assert!(
+            odb.read(oid).await.is_ok(),
+            "{} unreadable after compaction",
+            oid
+        );
+    }
+
+    let stats = compactor.stats();
+    let busy = &stats["busy"];
+    assert_eq!(busy.runs, 1);
+    assert_eq!(busy.objects_reclaimed, 12);
+    assert!(busy.last_compaction.is_some());
+    assert!(!stats.contains_key("quiet"));
+}
+
+#[tokio::test]
+async fn test_compacted_repo_is_skipped_next_pass() {
+    let repos_dir = TempDir::new().unwrap();
+    create_repo(repos_dir.path(), "project", 5).await;
+
+    let compactor = Compactor::new(repos_dir.path().to_path_buf(), config(5));
+    assert!(matches!(
+        compactor.compact_repo("project").await.unwrap(),
+        CompactionOutcome::Compacted { .. }
+    ));
+    assert_eq!(
+        compactor.compact_repo("project").await.unwrap(),
+        CompactionOutcome::Skipped { loose_objects: 0 }
+    );
+    assert_eq!(compactor.stats()["project"].runs, 1);
+}
+
+#[tokio::test]
+async fn test_repo_under_gc_is_skipped() {
+    let repos_dir = TempDir::new().unwrap();
+    create_repo(repos_dir.path(), "project", 5).await;
+    let compactor = Compactor::new(repos_dir.path().to_path_buf(), config(5));
+
+    let lock = GcLock::acquire(&repos_dir.path().join("project/.mediagit")).unwrap();
+    assert_eq!(
+        compactor.compact_repo("project").await.unwrap(),
+        CompactionOutcome::Busy
+    );
+
+    drop(lock);
+    assert!(matches!(
+        compactor.compact_repo("project").await.unwrap(),
+        CompactionOutcome::Compacted { .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_compaction_packs_refs() {
+    let repos_dir = TempDir::new().unwrap();
+    let oids = create_repo(repos_dir.path(), "project", 5).await;
+    let mediagit_dir = repos_dir.path().join("project/.mediagit");
+    let refdb = RefDatabase::new(&mediagit_dir);
+    refdb
+        .write(&Ref::new_direct("refs/heads/main".to_string(), oids[0]))
+        .await
+        .unwrap();
+    refdb
+        .write(&Ref::new_direct("refs/tags/v1.0".to_string(), oids[1]))
+        .await
+        .unwrap();
+    refdb
+        .write(&Ref::new_symbolic(
+            "HEAD".to_string(),
+            "refs/heads/main".to_string(),
+        ))
+        .await
+        .unwrap();
+
+    let compactor = Compactor::new(repos_dir.path().to_path_buf(), config(5));
+    assert_eq!(
+        compactor.compact_repo("project").await.unwrap(),
+        CompactionOutcome::Compacted {
+            objects_reclaimed: 5,
+            refs_packed: 2,
+        }
+    );
+    assert!(!mediagit_dir.join("refs/heads/main").exists());
+    assert_eq!(refdb.resolve("HEAD").await.unwrap(), oids[0]);
+
+    // The server still advertises the packed refs
+    let state = Arc::new(AppState::new(repos_dir.path().to_path_buf()));
+    let request = Request::builder()
+        .uri("/project/info/refs")
+        .body(Body::empty())
+        .unwrap();
+    let response = create_router(state).oneshot(request).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let refs: RefsResponse = serde_json::from_slice(&body).unwrap();
+    let advertised: Vec<(&str, &str)> = refs
+        .refs
+        .iter()
+        .map(|r| (r.name.as_str(), r.oid.as_str()))
+        .collect();
+    assert_eq!(
+        advertised,
+        vec![
+            ("HEAD", ""),
+            ("refs/heads/main", oids[0].to_hex().as_str()),
+            ("refs/tags/v1.0", oids[1].to_hex().as_str()),
+        ]
+    );
+}
+
+#[test]
+fn test_maintenance_window() {
+    let mut config = CompactionConfig::default();
+    assert!(config.in_window(13));
+
+    config.window_start_hour = Some(2);
+    config.window_end_hour = Some(5);
+    assert!(config.in_window(2));
+    assert!(config.in_window(4));
+    assert!(!config.in_window(5));
+    assert!(!config.in_window(13));
+
+    // Wrapping midnight
+    config.window_start_hour = Some(22);
+    config.window_end_hour = Some(4);
+    assert!(config.in_window(23));
+    assert!(config.in_window(0));
+    assert!(!config.in_window(4));
+    assert!(!config.in_window(12));
+}
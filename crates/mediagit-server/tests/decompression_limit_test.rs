@@ -0,0 +1,190 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Decompression limit tests
+//!
+//! `max_decompressed_size` bounds the objects a push may send: while
+//! `receive_fsck_objects` checks them, one that decompresses past the limit
+//! rejects the push. Objects already in the repository are served whatever
+//! their size.
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use mediagit_protocol::{RefUpdate, RefUpdateRequest, RefUpdateResponse, WantResponse};
+use mediagit_server::{create_router, AppState};
+use mediagit_storage::LocalBackend;
+use mediagit_versioning::{
+    Commit, FileMode, ObjectDatabase, ObjectType, Oid, PackReader, PackWriter, Signature, Tree,
+    TreeEntry,
+};
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tower::util::ServiceExt;
+
+/// Store a highly compressible 512 KB blob in a new repository
+async fn create_repo(repos_dir: &Path) -> Oid {
+    let repo = repos_dir.join("test-repo");
+    std::fs::create_dir_all(repo.join(".mediagit")).unwrap();
+    let storage = LocalBackend::new(repo.join(".mediagit")).await.unwrap();
+    let odb = ObjectDatabase::with_smart_compression(Arc::new(storage), 100);
+    odb.write(ObjectType::Blob, &vec![0u8; 512 * 1024])
+        .await
+        .unwrap()
+}
+
+/// Objects in the pack served for a fetch of `oid`
+async fn download(state: Arc<AppState>, oid: Oid) -> Vec<Oid> {
+    let want = serde_json::json!({ "want": [oid.to_hex()], "have": [] });
+    let request = Request::builder()
+        .uri("/test-repo/objects/want")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .body(Body::from(want.to_string()))
+        .unwrap();
+    let response = create_router(Arc::clone(&state))
+        .oneshot(request)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let want: WantResponse = serde_json::from_slice(&body).unwrap();
+
+    let request = Request::builder()
+        .uri("/test-repo/objects/pack")
+        .header("X-Request-ID", want.request_id)
+        .body(Body::empty())
+        .unwrap();
+    let response = create_router(state).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let pack = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    PackReader::new(pack.to_vec()).unwrap().list_objects()
+}
+
+/// Push a commit holding a highly compressible 512 KB blob to `main`
+async fn push(state: Arc<AppState>) -> RefUpdateResponse {
+    let signature = Signature::now("Test User".to_string(), "test@example.com".to_string());
+    let blob = vec![1u8; 512 * 1024];
+    let blob_oid = Oid::hash(&blob);
+    let mut tree = Tree::new();
+    tree.add_entry(TreeEntry::new(
+        "texture.raw".to_string(),
+        FileMode::Regular,
+        blob_oid,
+    ));
+    let tree = tree.serialize().unwrap();
+    let commit = Commit::new(
+        Oid::hash(&tree),
+        signature.clone(),
+        signature,
+        "Add texture".to_string(),
+    )
+    .serialize()
+    .unwrap();
+    let commit_oid = Oid::hash(&commit);
+
+    let mut pack = PackWriter::new();
+    pack.add_object(blob_oid, ObjectType::Blob, &blob);
+    pack.add_object(Oid::hash(&tree), ObjectType::Tree, &tree);
+    pack.add_object(commit_oid, ObjectType::Commit, &commit);
+    let request = Request::builder()
+        .uri("/test-repo/objects/pack")
+        .method("POST")
+        .header("X-Push-ID", "limit-test")
+        .body(Body::from(pack.finalize()))
+        .unwrap();
+    let response = create_router(Arc::clone(&state))
+        .oneshot(request)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let update = RefUpdateRequest {
+        updates: vec![RefUpdate {
+            name: "refs/heads/main".to_string(),
+            old_oid: None,
+            new_oid: commit_oid.to_hex(),
+            delete: false,
+        }],
+        force: false,
+        lease: false,
+        capabilities: Vec::new(),
+    };
+    let request = Request::builder()
+        .uri("/test-repo/refs/update")
+        .method("POST")
+        .header("Content-Type", "application/json")
+        .header("X-Push-ID", "limit-test")
+        .body(Body::from(serde_json::to_vec(&update).unwrap()))
+        .unwrap();
+    let response = create_router(state).oneshot(request).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_stored_object_is_served_whatever_the_limit() {
+    let repos_dir = TempDir::new().unwrap();
+    let oid = create_repo(repos_dir.path()).await;
+    let state = AppState::new(repos_dir.path().to_path_buf()).with_max_decompressed_size(64 * 1024);
+
+    assert_eq!(download(Arc::new(state), oid).await, vec![oid]);
+}
+
+#[tokio::test]
+async fn test_pushed_object_within_limit_is_accepted() {
+    let repos_dir = TempDir::new().unwrap();
+    create_repo(repos_dir.path()).await;
+    let state = AppState::new(repos_dir.path().to_path_buf())
+        .with_receive_fsck_objects(true)
+        .with_max_decompressed_size(512 * 1024);
+
+    let response = push(Arc::new(state)).await;
+    assert!(response.success, "{:?}", response.results);
+}
+
+#[tokio::test]
+async fn test_pushed_object_over_limit_is_rejected() {
+    let repos_dir = TempDir::new().unwrap();
+    create_repo(repos_dir.path()).await;
+    let state = AppState::new(repos_dir.path().to_path_buf())
+        .with_receive_fsck_objects(true)
+        .with_max_decompressed_size(64 * 1024);
+
+    let response = push(Arc::new(state)).await;
+    assert!(!response.success);
+    let error = response.results[0].error.as_deref().unwrap();
+    assert!(error.starts_with("fsck failed"), "{}", error);
+}
+
+#[test]
+fn test_default_limit_follows_server_memory() {
+    use mediagit_server::config::max_decompressed_size_for_memory;
+    const MB: u64 = 1024 * 1024;
+
+    assert_eq!(max_decompressed_size_for_memory(Some(4096 * MB)), 256 * MB);
+    assert_eq!(max_decompressed_size_for_memory(Some(512 * MB)), 64 * MB);
+    assert_eq!(
+        max_decompressed_size_for_memory(Some(1024 * 1024 * MB)),
+        16 * 1024 * MB
+    );
+    assert_eq!(max_decompressed_size_for_memory(None), 256 * MB);
+}
@@ -362,6 +362,7 @@ async fn test_local_backend_complete_flow() {
     let want_request = WantRequest {
         want: vec![oid.to_hex()],
         have: vec![],
+        capabilities: Vec::new(),
     };
 
     let resp = client
@@ -394,6 +395,8 @@ async fn test_local_backend_complete_flow() {
             delete: false,
         }],
         force: false,
+        lease: false,
+        capabilities: Vec::new(),
     };
 
     let resp = client
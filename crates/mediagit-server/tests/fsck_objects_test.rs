@@ -0,0 +1,234 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Transfer fsck tests
+//!
+//! With `push.fsck_objects` the client refuses to upload a push containing a
+//! corrupt object; with `receive_fsck_objects` the server rejects one before
+//! any ref moves or object reaches the repository.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+
+use mediagit_protocol::{ProtocolClient, RefUpdate, RefUpdateRequest, RefUpdateResponse};
+use mediagit_storage::{LocalBackend, StorageBackend};
+use mediagit_versioning::{
+    Commit, FileMode, ObjectDatabase, ObjectType, Oid, PackWriter, Ref, RefDatabase, Signature,
+    Tree, TreeEntry,
+};
+
+async fn start_test_server(repos_dir: PathBuf) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let state = mediagit_server::AppState::new(repos_dir).with_receive_fsck_objects(true);
+    let app = mediagit_server::create_router(Arc::new(state));
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    base_url
+}
+
+async fn open_storage(repo: &Path) -> Arc<dyn StorageBackend> {
+    Arc::new(LocalBackend::new(repo.join(".mediagit")).await.unwrap())
+}
+
+fn signature() -> Signature {
+    Signature::now("Test User".to_string(), "test@example.com".to_string())
+}
+
+/// Write a commit holding one file, returning the commit, tree and blob OIDs
+async fn write_commit(odb: &ObjectDatabase, content: &[u8], parent: Option<Oid>) -> [Oid; 3] {
+    let blob = odb.write(ObjectType::Blob, content).await.unwrap();
+    let mut tree = Tree::new();
+    tree.add_entry(TreeEntry::new(
+        "scene.blend".to_string(),
+        FileMode::Regular,
+        blob,
+    ));
+    let tree = tree.write(odb).await.unwrap();
+    let mut commit = Commit::new(tree, signature(), signature(), "Update scene".to_string());
+    commit.parents.extend(parent);
+    [commit.write(odb).await.unwrap(), tree, blob]
+}
+
+struct Fixture {
+    _server_dir: TempDir,
+    _client_dir: TempDir,
+    server_repo: PathBuf,
+    client_storage: Arc<dyn StorageBackend>,
+    base_url: String,
+    base: Oid,
+}
+
+/// A server and a client that both have the `main` commit
+async fn setup() -> Fixture {
+    let client_dir = TempDir::new().unwrap();
+    let client_storage = open_storage(client_dir.path()).await;
+    let client_odb = ObjectDatabase::new(client_storage.clone(), 1000);
+    let [base, ..] = write_commit(&client_odb, b"initial scene", None).await;
+
+    let server_dir = TempDir::new().unwrap();
+    let server_repo = server_dir.path().join("test-repo");
+    let server_storage = open_storage(&server_repo).await;
+    for key in client_storage.list_objects("").await.unwrap() {
+        let data = client_storage.get(&key).await.unwrap();
+        server_storage.put(&key, &data).await.unwrap();
+    }
+    let refdb = RefDatabase::new(server_repo.join(".mediagit"));
+    refdb
+        .write(&Ref::new_direct("refs/heads/main".to_string(), base))
+        .await
+        .unwrap();
+    refdb
+        .write(&Ref::new_symbolic(
+            "HEAD".to_string(),
+            "refs/heads/main".to_string(),
+        ))
+        .await
+        .unwrap();
+
+    let base_url = start_test_server(server_dir.path().to_path_buf()).await;
+    Fixture {
+        server_repo,
+        client_storage,
+        base_url: format!("{}/test-repo", base_url),
+        base,
+        _server_dir: server_dir,
+        _client_dir: client_dir,
+    }
+}
+
+fn update_main(fixture: &Fixture, new_oid: Oid) -> RefUpdate {
+    RefUpdate {
+        name: "refs/heads/main".to_string(),
+        old_oid: Some(fixture.base.to_hex()),
+        new_oid: new_oid.to_hex(),
+        delete: false,
+    }
+}
+
+async fn main_oid(fixture: &Fixture) -> Option<Oid> {
+    RefDatabase::new(fixture.server_repo.join(".mediagit"))
+        .read("refs/heads/main")
+        .await
+        .unwrap()
+        .oid
+}
+
+fn quarantine_is_empty(repo: &Path) -> bool {
+    match std::fs::read_dir(repo.join(".mediagit/quarantine")) {
+        Ok(mut entries) => entries.next().is_none(),
+        Err(_) => true,
+    }
+}
+
+#[tokio::test]
+async fn test_client_refuses_to_push_corrupt_object() {
+    let fixture = setup().await;
+    let client_odb = ObjectDatabase::new(fixture.client_storage.clone(), 1000);
+    let [commit, _, blob] = write_commit(&client_odb, b"edited scene", Some(fixture.base)).await;
+
+    // Bit rot in the local copy of the blob
+    fixture
+        .client_storage
+        .put(&blob.to_hex(), b"not the scene")
+        .await
+        .unwrap();
+    let client_odb = ObjectDatabase::new(fixture.client_storage.clone(), 1000);
+
+    let client = ProtocolClient::new(fixture.base_url.clone()).with_fsck_objects(true);
+    let error = client
+        .push(&client_odb, vec![update_main(&fixture, commit)], false)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("fsck"), "{:#}", error);
+
+    // Nothing was uploaded
+    assert!(quarantine_is_empty(&fixture.server_repo));
+    let server_odb = ObjectDatabase::new(open_storage(&fixture.server_repo).await, 1000);
+    assert!(!server_odb.exists(&commit).await.unwrap());
+    assert_eq!(main_oid(&fixture).await, Some(fixture.base));
+}
+
+#[tokio::test]
+async fn test_server_rejects_push_with_corrupt_object() {
+    let fixture = setup().await;
+
+    // A commit whose tree was never sent, and a "commit" that is not one
+    let missing_tree = Oid::hash(b"tree that was lost");
+    let mut commit = Commit::new(missing_tree, signature(), signature(), "Broken".to_string());
+    commit.parents.push(fixture.base);
+    let dangling = commit.serialize().unwrap();
+    let garbage: &[u8] = b"definitely not a commit";
+
+    for (data, oid) in [
+        (dangling.as_slice(), Oid::hash(&dangling)),
+        (garbage, Oid::hash(garbage)),
+    ] {
+        let mut pack = PackWriter::new();
+        pack.add_object(oid, ObjectType::Commit, data);
+
+        let http = reqwest::Client::new();
+        let push_id = format!("fsck-{}", &oid.to_hex()[..8]);
+        let status = http
+            .post(format!("{}/objects/pack", fixture.base_url))
+            .header("X-Push-ID", &push_id)
+            .body(pack.finalize())
+            .send()
+            .await
+            .unwrap()
+            .status();
+        assert!(status.is_success());
+
+        let response: RefUpdateResponse = http
+            .post(format!("{}/refs/update", fixture.base_url))
+            .header("X-Push-ID", &push_id)
+            .json(&RefUpdateRequest {
+                updates: vec![update_main(&fixture, oid)],
+                force: false,
+                lease: false,
+                capabilities: Vec::new(),
+            })
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+        assert!(!response.success);
+        let error = response.results[0].error.as_deref().unwrap();
+        assert!(error.starts_with("fsck failed"), "{}", error);
+        assert_eq!(main_oid(&fixture).await, Some(fixture.base));
+        let server_odb = ObjectDatabase::new(open_storage(&fixture.server_repo).await, 1000);
+        assert!(!server_odb.exists(&oid).await.unwrap());
+        assert!(quarantine_is_empty(&fixture.server_repo));
+    }
+}
+
+#[tokio::test]
+async fn test_server_accepts_valid_push_with_fsck() {
+    let fixture = setup().await;
+    let client_odb = ObjectDatabase::new(fixture.client_storage.clone(), 1000);
+    let [commit, ..] = write_commit(&client_odb, b"edited scene", Some(fixture.base)).await;
+
+    let client = ProtocolClient::new(fixture.base_url.clone()).with_fsck_objects(true);
+    let (response, _) = client
+        .push(&client_odb, vec![update_main(&fixture, commit)], false)
+        .await
+        .unwrap();
+    assert!(response.success, "{:?}", response.results);
+    assert_eq!(main_oid(&fixture).await, Some(commit));
+}
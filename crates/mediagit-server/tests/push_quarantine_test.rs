@@ -0,0 +1,185 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Push quarantine tests
+//!
+//! Objects from a push are held in a quarantine until its ref update
+//! succeeds: a rejected push leaves nothing behind, an accepted one moves
+//! every object into the repository.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+
+use mediagit_protocol::{ProtocolClient, RefUpdate};
+use mediagit_storage::{LocalBackend, StorageBackend};
+use mediagit_versioning::{
+    Commit, FileMode, ObjectDatabase, ObjectType, Oid, Ref, RefDatabase, Signature, Tree, TreeEntry,
+};
+
+async fn start_test_server(repos_dir: PathBuf) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let app = mediagit_server::create_router(Arc::new(mediagit_server::AppState::new(repos_dir)));
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    base_url
+}
+
+async fn open_odb(repo: &Path) -> ObjectDatabase {
+    let storage: Arc<dyn StorageBackend> =
+        Arc::new(LocalBackend::new(repo.join(".mediagit")).await.unwrap());
+    ObjectDatabase::new(storage, 1000)
+}
+
+/// Write a commit holding one file, returning the commit, tree and blob OIDs
+async fn write_commit(odb: &ObjectDatabase, content: &[u8], parent: Option<Oid>) -> [Oid; 3] {
+    let blob = odb.write(ObjectType::Blob, content).await.unwrap();
+    let mut tree = Tree::new();
+    tree.add_entry(TreeEntry::new(
+        "scene.blend".to_string(),
+        FileMode::Regular,
+        blob,
+    ));
+    let tree = tree.write(odb).await.unwrap();
+    let author = Signature::now("Test User".to_string(), "test@example.com".to_string());
+    let mut commit = Commit::new(tree, author.clone(), author, "Update scene".to_string());
+    commit.parents.extend(parent);
+    [commit.write(odb).await.unwrap(), tree, blob]
+}
+
+struct Fixture {
+    _server_dir: TempDir,
+    _client_dir: TempDir,
+    server_repo: PathBuf,
+    client_odb: ObjectDatabase,
+    client: ProtocolClient,
+    base: Oid,
+    pushed: [Oid; 3],
+}
+
+async fn setup() -> Fixture {
+    let server_dir = TempDir::new().unwrap();
+    let server_repo = server_dir.path().join("test-repo");
+    let server_odb = open_odb(&server_repo).await;
+    let [base, ..] = write_commit(&server_odb, b"initial scene", None).await;
+    let [stable, ..] = write_commit(&server_odb, b"stable scene", Some(base)).await;
+    let refdb = RefDatabase::new(server_repo.join(".mediagit"));
+    refdb
+        .write(&Ref::new_direct("refs/heads/main".to_string(), base))
+        .await
+        .unwrap();
+    refdb
+        .write(&Ref::new_direct("refs/heads/stable".to_string(), stable))
+        .await
+        .unwrap();
+    refdb
+        .write(&Ref::new_symbolic(
+            "HEAD".to_string(),
+            "refs/heads/main".to_string(),
+        ))
+        .await
+        .unwrap();
+
+    let client_dir = TempDir::new().unwrap();
+    let client_odb = open_odb(client_dir.path()).await;
+    let pushed = write_commit(&client_odb, b"edited scene", Some(base)).await;
+
+    let base_url = start_test_server(server_dir.path().to_path_buf()).await;
+    Fixture {
+        server_repo,
+        client_odb,
+        client: ProtocolClient::new(format!("{}/test-repo", base_url)),
+        base,
+        pushed,
+        _server_dir: server_dir,
+        _client_dir: client_dir,
+    }
+}
+
+fn update_main(fixture: &Fixture) -> RefUpdate {
+    RefUpdate {
+        name: "refs/heads/main".to_string(),
+        old_oid: Some(fixture.base.to_hex()),
+        new_oid: fixture.pushed[0].to_hex(),
+        delete: false,
+    }
+}
+
+fn quarantine_is_empty(repo: &Path) -> bool {
+    match std::fs::read_dir(repo.join(".mediagit/quarantine")) {
+        Ok(mut entries) => entries.next().is_none(),
+        Err(_) => true,
+    }
+}
+
+#[tokio::test]
+async fn test_failed_ref_update_leaves_no_objects() {
+    let fixture = setup().await;
+
+    // `stable` has moved on since the client last fetched, so that update is
+    // rejected, and with it the whole push
+    let stale_update = RefUpdate {
+        name: "refs/heads/stable".to_string(),
+        ..update_main(&fixture)
+    };
+    let (response, stats) = fixture
+        .client
+        .push(
+            &fixture.client_odb,
+            vec![update_main(&fixture), stale_update],
+            false,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(stats.objects_count, 3);
+    assert!(!response.success);
+    assert!(response.results.iter().all(|result| !result.success));
+
+    let server_odb = open_odb(&fixture.server_repo).await;
+    for oid in &fixture.pushed {
+        assert!(!server_odb.exists(oid).await.unwrap(), "{} was stored", oid);
+    }
+    let main = RefDatabase::new(fixture.server_repo.join(".mediagit"))
+        .read("refs/heads/main")
+        .await
+        .unwrap();
+    assert_eq!(main.oid, Some(fixture.base));
+    assert!(quarantine_is_empty(&fixture.server_repo));
+}
+
+#[tokio::test]
+async fn test_successful_push_migrates_all_objects() {
+    let fixture = setup().await;
+
+    let (response, _) = fixture
+        .client
+        .push(&fixture.client_odb, vec![update_main(&fixture)], false)
+        .await
+        .unwrap();
+    assert!(response.success);
+
+    let server_odb = open_odb(&fixture.server_repo).await;
+    for oid in &fixture.pushed {
+        assert!(server_odb.exists(oid).await.unwrap(), "{} is missing", oid);
+    }
+    let main = RefDatabase::new(fixture.server_repo.join(".mediagit"))
+        .read("refs/heads/main")
+        .await
+        .unwrap();
+    assert_eq!(main.oid, Some(fixture.pushed[0]));
+    assert!(quarantine_is_empty(&fixture.server_repo));
+}
@@ -0,0 +1,129 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Pack upload verification tests
+//!
+//! A pushed pack must pass its checksum and index checks before any object
+//! is stored; a corrupt one is rejected and leaves the repository unchanged.
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use mediagit_server::{create_router, AppState};
+use mediagit_storage::LocalBackend;
+use mediagit_versioning::{ObjectDatabase, ObjectType, Oid, PackWriter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tempfile::TempDir;
+use tower::util::ServiceExt;
+
+const OBJECTS: [&[u8]; 2] = [b"frame 0001 pixels", b"frame 0002 pixels"];
+
+fn create_repo(repos_dir: &Path) -> PathBuf {
+    let repo = repos_dir.join("test-repo");
+    std::fs::create_dir_all(repo.join(".mediagit")).unwrap();
+    repo
+}
+
+fn test_pack() -> Vec<u8> {
+    let mut writer = PackWriter::new();
+    for data in OBJECTS {
+        writer.add_object(Oid::hash(data), ObjectType::Blob, data);
+    }
+    writer.finalize()
+}
+
+/// Every file under `dir`, relative to it
+fn snapshot(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = walkdir(dir)
+        .into_iter()
+        .map(|path| path.strip_prefix(dir).unwrap().to_path_buf())
+        .collect();
+    files.sort();
+    files
+}
+
+fn walkdir(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(walkdir(&path));
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+async fn upload(state: Arc<AppState>, pack: Vec<u8>) -> (StatusCode, String) {
+    let request = Request::builder()
+        .uri("/test-repo/objects/pack")
+        .method("POST")
+        .body(Body::from(pack))
+        .unwrap();
+    let response = create_router(state).oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, String::from_utf8_lossy(&body).into_owned())
+}
+
+async fn open_odb(repo: &Path) -> ObjectDatabase {
+    let storage = LocalBackend::new(repo.join(".mediagit")).await.unwrap();
+    ObjectDatabase::with_smart_compression(Arc::new(storage), 100)
+}
+
+#[tokio::test]
+async fn test_valid_pack_is_stored() {
+    let repos_dir = TempDir::new().unwrap();
+    let repo = create_repo(repos_dir.path());
+    let state = Arc::new(AppState::new(repos_dir.path().to_path_buf()));
+
+    let (status, _) = upload(state, test_pack()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let odb = open_odb(&repo).await;
+    for data in OBJECTS {
+        assert_eq!(odb.read(&Oid::hash(data)).await.unwrap(), data);
+    }
+    // The spooled pack is cleaned up
+    assert!(snapshot(&repo)
+        .iter()
+        .all(|path| !path.to_string_lossy().ends_with(".pack")));
+}
+
+#[tokio::test]
+async fn test_corrupt_pack_is_rejected() {
+    let repos_dir = TempDir::new().unwrap();
+    let repo = create_repo(repos_dir.path());
+    let state = Arc::new(AppState::new(repos_dir.path().to_path_buf()));
+    let before = snapshot(&repo);
+
+    // Flip one bit inside the second object's data, as a transit error would
+    let mut pack = test_pack();
+    let pos = 12 + 5 + OBJECTS[0].len() + 5 + 3;
+    pack[pos] ^= 0x01;
+
+    let (status, message) = upload(state, pack).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(message.contains("Rejected corrupt pack"), "{}", message);
+
+    assert_eq!(snapshot(&repo), before);
+    let odb = open_odb(&repo).await;
+    for data in OBJECTS {
+        assert!(!odb.exists(&Oid::hash(data)).await.unwrap());
+    }
+}
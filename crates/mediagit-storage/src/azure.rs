@@ -140,14 +140,23 @@
 //! export AZURE_STORAGE_CONNECTION_STRING="DefaultEndpointsProtocol=http;AccountName=devstoreaccount1;AccountKey=Eby8vdM02xNOcqFlqUwJPLlmEtlCDXJ1OUzFT50uSRZ6IFsuFq2UVErCz4I6tq/K1SZFPTOtr/KBHBeksoGMGw==;BlobEndpoint=http://127.0.0.1:10000/devstoreaccount1;"
 //! ```
 
-use crate::StorageBackend;
+use crate::retry::RetryPolicy;
+use crate::stream::{self, KeyStream, ObjectStream, PartReader};
+use crate::{check_delimiter, BackendCapabilities, ObjectMeta, StorageBackend, StorageUsage};
+use anyhow::Context;
 use async_trait::async_trait;
+use azure_core::prelude::NextMarker;
+use azure_core::RetryOptions;
 use azure_storage::prelude::*;
 use azure_storage::CloudLocation;
+use azure_storage_blobs::blob::CopyStatus;
 use azure_storage_blobs::prelude::*;
-use futures::TryStreamExt;
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
 use std::fmt;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Chunk size for multipart uploads (4 MB)
 /// This provides a good balance between memory usage and upload efficiency
@@ -156,6 +165,70 @@ const CHUNK_SIZE: usize = 4 * 1024 * 1024; // 4 MB
 /// Block size for Azure block blob operations
 const AZURE_BLOCK_SIZE: usize = 4 * 1024 * 1024; // 4 MB, Azure maximum is 4GB
 
+/// Most blocks a single block blob can be committed from
+const MAX_BLOCKS: usize = 50_000;
+
+/// How often to check on a Copy Blob the service hasn't finished yet
+const COPY_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Settings for staged block uploads (Put Block / Put Block List)
+///
+/// Objects above `threshold` are split into blocks that are uploaded
+/// concurrently, each retried on its own, then committed in one Put Block
+/// List call. Smaller objects go up in a single Put Blob request.
+///
+/// The retry settings apply to every request the backend makes, not just
+/// block uploads; the SDK's own retries are turned off so requests aren't
+/// retried twice.
+#[derive(Clone, Debug)]
+pub struct BlockUploadSettings {
+    /// Objects larger than this many bytes are staged as blocks (default: 4MB)
+    pub threshold: usize,
+
+    /// Size of each staged block in bytes (default: 4MB); grown as needed to
+    /// stay within Azure's 50,000 block limit
+    pub block_size: usize,
+
+    /// Maximum number of blocks uploaded at once (default: 8)
+    pub max_concurrent_blocks: usize,
+
+    /// Maximum attempts for each request, including each block and the
+    /// final commit (default: 3)
+    pub max_retries: u32,
+
+    /// Initial retry delay in milliseconds (default: 100ms)
+    pub initial_retry_delay_ms: u64,
+}
+
+impl Default for BlockUploadSettings {
+    fn default() -> Self {
+        BlockUploadSettings {
+            threshold: AZURE_BLOCK_SIZE,
+            block_size: CHUNK_SIZE,
+            max_concurrent_blocks: 8,
+            max_retries: 3,
+            initial_retry_delay_ms: 100,
+        }
+    }
+}
+
+impl BlockUploadSettings {
+    /// Block size for an object of `len` bytes, never below `block_size` and
+    /// large enough that the object fits in [`MAX_BLOCKS`] blocks
+    fn block_size_for(&self, len: usize) -> usize {
+        self.block_size.max(len.div_ceil(MAX_BLOCKS)).max(1)
+    }
+
+    /// How requests are retried, from `max_retries` and
+    /// `initial_retry_delay_ms`
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::new(
+            self.max_retries,
+            Duration::from_millis(self.initial_retry_delay_ms),
+        )
+    }
+}
+
 /// Azure Blob Storage backend
 ///
 /// Thread-safe implementation of `StorageBackend` using Azure Blob Storage.
@@ -171,6 +244,8 @@ pub struct AzureBackend {
     container_name: String,
     /// The actual Azure SDK client for blob operations
     client: Arc<ContainerClient>,
+    /// How objects above the block threshold are staged
+    blocks: BlockUploadSettings,
 }
 
 impl fmt::Debug for AzureBackend {
@@ -178,6 +253,7 @@ impl fmt::Debug for AzureBackend {
         f.debug_struct("AzureBackend")
             .field("account_name", &self.account_name)
             .field("container_name", &self.container_name)
+            .field("blocks", &self.blocks)
             .finish()
     }
 }
@@ -239,6 +315,7 @@ impl AzureBackend {
         // Create the container client with SAS token
         let storage_credentials = StorageCredentials::sas_token(sas_token)?;
         let container_client = ClientBuilder::new(account_name.clone(), storage_credentials)
+            .retry(RetryOptions::none())
             .container_client(container_name.clone());
 
         tracing::info!(
@@ -251,6 +328,7 @@ impl AzureBackend {
             account_name: account_name.clone(),
             container_name: container_name.clone(),
             client: Arc::new(container_client),
+            blocks: BlockUploadSettings::default(),
         };
 
         // Ensure container exists
@@ -315,6 +393,7 @@ impl AzureBackend {
         // Create the container client with account key
         let storage_credentials = StorageCredentials::access_key(account_name.clone(), account_key);
         let container_client = ClientBuilder::new(account_name.clone(), storage_credentials)
+            .retry(RetryOptions::none())
             .container_client(container_name.clone());
 
         tracing::info!(
@@ -327,6 +406,7 @@ impl AzureBackend {
             account_name: account_name.clone(),
             container_name: container_name.clone(),
             client: Arc::new(container_client),
+            blocks: BlockUploadSettings::default(),
         };
 
         // Ensure container exists
@@ -425,10 +505,12 @@ impl AzureBackend {
 
             tracing::debug!("Using custom blob endpoint: {}:{}", host, port);
             ClientBuilder::with_location(cloud_location, storage_credentials)
+                .retry(RetryOptions::none())
                 .container_client(container_name.clone())
         } else {
             // Use default public cloud
             ClientBuilder::new(account_name.clone(), storage_credentials)
+                .retry(RetryOptions::none())
                 .container_client(container_name.clone())
         };
 
@@ -442,6 +524,7 @@ impl AzureBackend {
             account_name: account_name.clone(),
             container_name: container_name.clone(),
             client: Arc::new(container_client),
+            blocks: BlockUploadSettings::default(),
         };
 
         // Ensure container exists
@@ -450,6 +533,30 @@ impl AzureBackend {
         Ok(backend)
     }
 
+    /// Replace the block upload settings used for large objects
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use mediagit_storage::azure::{AzureBackend, BlockUploadSettings};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = AzureBackend::with_connection_string("mycontainer", "DefaultEndpointsProtocol=https;...")
+    ///     .await?
+    ///     .with_block_uploads(BlockUploadSettings {
+    ///         threshold: 64 * 1024 * 1024,
+    ///         max_concurrent_blocks: 16,
+    ///         ..BlockUploadSettings::default()
+    ///     });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_block_uploads(mut self, blocks: BlockUploadSettings) -> Self {
+        self.blocks = blocks;
+        self
+    }
+
     /// Check if a key is valid (non-empty)
     fn validate_key(key: &str) -> anyhow::Result<()> {
         if key.is_empty() {
@@ -552,18 +659,174 @@ impl StorageBackend for AzureBackend {
 
         let blob_client = self.client.blob_client(key);
 
-        match blob_client.get_content().await {
-            Ok(data) => {
-                tracing::debug!("Successfully retrieved {} ({} bytes)", key, data.len());
-                Ok(data)
-            }
-            Err(e) => {
-                let azure_error = e.to_string();
-                if azure_error.contains("404") || azure_error.contains("BlobNotFound") {
-                    return Err(anyhow::anyhow!("object not found: {}", key));
+        self.retry("Azure get", || async {
+            match blob_client.get_content().await {
+                Ok(data) => {
+                    tracing::debug!("Successfully retrieved {} ({} bytes)", key, data.len());
+                    Ok(data)
                 }
-                Err(Self::map_error(e, key))
+                Err(e) => {
+                    let azure_error = e.to_string();
+                    if azure_error.contains("404") || azure_error.contains("BlobNotFound") {
+                        return Err(anyhow::anyhow!("object not found: {}", key));
+                    }
+                    Err(Self::map_error(e, key))
+                }
+            }
+        })
+        .await
+    }
+
+    /// Retrieve part of a blob with a ranged Get Blob request
+    ///
+    /// Azure rejects a range starting past the end of the blob, which is
+    /// returned as empty rather than as an error.
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        Self::validate_key(key)?;
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+
+        tracing::debug!(
+            "Getting {} bytes at {} of object from Azure Blob Storage: {}/{}",
+            len,
+            offset,
+            self.container_name,
+            key
+        );
+
+        let blob_client = self.client.blob_client(key);
+
+        self.retry("Azure ranged get", || async {
+            let mut pages = blob_client
+                .get()
+                .range(offset..offset.saturating_add(len))
+                .into_stream();
+
+            let mut data = Vec::new();
+            while let Some(page) = pages.next().await {
+                let page = match page {
+                    Ok(page) => page,
+                    Err(e)
+                        if e.as_http_error().is_some_and(|http| {
+                            http.status() == azure_core::StatusCode::RequestedRangeNotSatisfiable
+                        }) =>
+                    {
+                        return Ok(Vec::new());
+                    }
+                    Err(e) => {
+                        let azure_error = e.to_string();
+                        if azure_error.contains("404") || azure_error.contains("BlobNotFound") {
+                            return Err(anyhow::anyhow!("object not found: {}", key));
+                        }
+                        return Err(Self::map_error(e, key));
+                    }
+                };
+                let chunk = page
+                    .data
+                    .collect()
+                    .await
+                    .map_err(|e| Self::map_error(e, key))?;
+                data.extend_from_slice(&chunk);
             }
+            Ok(data)
+        })
+        .await
+    }
+
+    /// Fetch a blob's properties without downloading it
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        Self::validate_key(key)?;
+
+        tracing::debug!(
+            "Getting properties of object in Azure Blob Storage: {}/{}",
+            self.container_name,
+            key
+        );
+
+        let blob_client = self.client.blob_client(key);
+        let blob = self
+            .retry("Azure head", || async {
+                blob_client
+                    .get_properties()
+                    .await
+                    .map_err(|e| Self::map_error(e, key))
+            })
+            .await?
+            .blob;
+        let properties = blob.properties;
+        Ok(ObjectMeta {
+            size: properties.content_length,
+            modified: Some(properties.last_modified.into()),
+            etag: Some(properties.etag.to_string().trim_matches('"').to_string()),
+            content_type: Some(properties.content_type),
+            metadata: blob.metadata.unwrap_or_default(),
+        })
+    }
+
+    /// Copy a blob within the account with Copy Blob
+    ///
+    /// Copies within an account usually finish at once; otherwise the
+    /// destination's properties are polled until the service reports the
+    /// outcome.
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        Self::validate_key(src_key)?;
+        Self::validate_key(dst_key)?;
+
+        tracing::debug!(
+            "Copying object in Azure Blob Storage: {} to {}",
+            src_key,
+            dst_key
+        );
+
+        let source = self
+            .client
+            .blob_client(src_key)
+            .url()
+            .with_context(|| format!("failed to build URL for {}", src_key))?;
+        let destination = self.client.blob_client(dst_key);
+        let mut status = self
+            .retry("Azure copy", || async {
+                destination
+                    .copy(source.clone())
+                    .await
+                    .map_err(|e| Self::map_error(e, src_key))
+            })
+            .await?
+            .copy_status;
+        let mut description = None;
+
+        while status == CopyStatus::Pending {
+            tokio::time::sleep(COPY_POLL_INTERVAL).await;
+            let properties = destination
+                .get_properties()
+                .await
+                .map_err(|e| Self::map_error(e, dst_key))?
+                .blob
+                .properties;
+            status = properties.copy_status.unwrap_or(CopyStatus::Success);
+            description = properties.copy_status_description;
+        }
+
+        match status {
+            CopyStatus::Success => Ok(()),
+            _ => Err(anyhow::anyhow!(
+                "copy of {} to {} {}: {}",
+                src_key,
+                dst_key,
+                <&str>::from(status),
+                description.unwrap_or_default()
+            )),
+        }
+    }
+
+    /// Ranged reads and copies within the account are supported; nothing
+    /// else is
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            server_side_copy: true,
+            range_reads: true,
+            ..BackendCapabilities::default()
         }
     }
 
@@ -577,9 +840,11 @@ impl StorageBackend for AzureBackend {
         );
 
         // For small files, use direct upload
-        // For large files, use chunked/block upload
-        if data.len() > AZURE_BLOCK_SIZE {
-            self.put_chunked(key, data).await?;
+        // For large files, stage blocks and commit them as one blob
+        if data.len() > self.blocks.threshold {
+            let block_size = self.blocks.block_size_for(data.len());
+            let blocks = PartReader::new(stream::once(Bytes::copy_from_slice(data)), block_size);
+            self.put_blocks(key, Vec::new(), blocks).await?;
         } else {
             self.put_direct(key, data).await?;
         }
@@ -588,6 +853,26 @@ impl StorageBackend for AzureBackend {
         Ok(())
     }
 
+    /// Store an object from a stream, staging blocks once it grows past the
+    /// block threshold so only the blocks in flight are buffered
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> anyhow::Result<()> {
+        Self::validate_key(key)?;
+
+        let mut blocks = PartReader::new(data, self.blocks.block_size.max(1));
+        let mut head = Vec::new();
+        let mut buffered = 0;
+        while buffered <= self.blocks.threshold {
+            match blocks.next_part().await? {
+                Some(block) => {
+                    buffered += block.len();
+                    head.push(block);
+                }
+                None => return self.put_direct(key, &head.concat()).await,
+            }
+        }
+        self.put_blocks(key, head, blocks).await
+    }
+
     async fn exists(&self, key: &str) -> anyhow::Result<bool> {
         Self::validate_key(key)?;
 
@@ -598,27 +883,30 @@ impl StorageBackend for AzureBackend {
 
         let blob_client = self.client.blob_client(key);
 
-        match blob_client.exists().await {
-            Ok(exists) => {
-                tracing::debug!("Blob {} exists: {}", key, exists);
-                Ok(exists)
-            }
-            Err(e) => {
-                let error_msg = e.to_string().to_lowercase();
-                // Check for various "not found" patterns from real and emulated Azure services
-                if error_msg.contains("404")
-                    || error_msg.contains("not found")
-                    || error_msg.contains("notfound")
-                    || error_msg.contains("blobnotfound")
-                    || error_msg.contains("does not exist")
-                    || error_msg.contains("containernotfound")
-                {
-                    Ok(false)
-                } else {
-                    Err(Self::map_error(e, key))
+        self.retry("Azure exists", || async {
+            match blob_client.exists().await {
+                Ok(exists) => {
+                    tracing::debug!("Blob {} exists: {}", key, exists);
+                    Ok(exists)
+                }
+                Err(e) => {
+                    let error_msg = e.to_string().to_lowercase();
+                    // Check for various "not found" patterns from real and emulated Azure services
+                    if error_msg.contains("404")
+                        || error_msg.contains("not found")
+                        || error_msg.contains("notfound")
+                        || error_msg.contains("blobnotfound")
+                        || error_msg.contains("does not exist")
+                        || error_msg.contains("containernotfound")
+                    {
+                        Ok(false)
+                    } else {
+                        Err(Self::map_error(e, key))
+                    }
                 }
             }
-        }
+        })
+        .await
     }
 
     async fn delete(&self, key: &str) -> anyhow::Result<()> {
@@ -629,22 +917,25 @@ impl StorageBackend for AzureBackend {
         let blob_client = self.client.blob_client(key);
 
         // Azure delete is idempotent - non-existent blobs return success
-        match blob_client.delete().await {
-            Ok(_) => {
-                tracing::debug!("Successfully deleted {}", key);
-                Ok(())
-            }
-            Err(e) => {
-                let error_msg = e.to_string();
-                // Ignore 404 errors since delete is idempotent
-                if error_msg.contains("404") {
-                    tracing::debug!("Blob {} doesn't exist, delete is idempotent", key);
+        self.retry("Azure delete", || async {
+            match blob_client.delete().await {
+                Ok(_) => {
+                    tracing::debug!("Successfully deleted {}", key);
                     Ok(())
-                } else {
-                    Err(Self::map_error(e, key))
+                }
+                Err(e) => {
+                    let error_msg = e.to_string();
+                    // Ignore 404 errors since delete is idempotent
+                    if error_msg.contains("404") {
+                        tracing::debug!("Blob {} doesn't exist, delete is idempotent", key);
+                        Ok(())
+                    } else {
+                        Err(Self::map_error(e, key))
+                    }
                 }
             }
-        }
+        })
+        .await
     }
 
     async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
@@ -653,25 +944,7 @@ impl StorageBackend for AzureBackend {
             prefix
         );
 
-        // Use streaming API to fetch all blobs
-        let prefix_owned = prefix.to_string(); // Clone prefix to satisfy 'static lifetime requirement
-        let mut stream = if prefix_owned.is_empty() {
-            self.client.list_blobs().into_stream()
-        } else {
-            self.client.list_blobs().prefix(prefix_owned).into_stream()
-        };
-
-        let mut results = Vec::new();
-        while let Some(blob_list) = stream
-            .try_next()
-            .await
-            .map_err(|e| Self::map_error(e, &format!("listing with prefix '{}'", prefix)))?
-        {
-            // Extract blob names from the response
-            for blob in blob_list.blobs.blobs() {
-                results.push(blob.name.clone());
-            }
-        }
+        let mut results: Vec<String> = self.list_objects_stream(prefix).try_collect().await?;
 
         // Sort results for consistency
         results.sort();
@@ -685,6 +958,122 @@ impl StorageBackend for AzureBackend {
 
         Ok(results)
     }
+
+    /// Stream blob names a List Blobs page at a time, each page retried on
+    /// its own from its marker
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        let prefix = prefix.to_string();
+        // `None` once the last page has been fetched
+        let first_page: Option<Option<NextMarker>> = Some(None);
+
+        stream::pages(first_page, move |marker| {
+            let prefix = prefix.clone();
+            async move {
+                let Some(marker) = marker else {
+                    return Ok(None);
+                };
+                let page = self
+                    .retry("Azure list", || async {
+                        let mut request = self.client.list_blobs();
+                        if !prefix.is_empty() {
+                            request = request.prefix(prefix.clone());
+                        }
+                        if let Some(marker) = marker.clone() {
+                            request = request.marker(marker);
+                        }
+                        request.into_stream().next().await.transpose().map_err(|e| {
+                            Self::map_error(e, &format!("listing with prefix '{}'", prefix))
+                        })
+                    })
+                    .await?;
+
+                Ok(page.map(|page| {
+                    // Extract blob names from the response
+                    let names = page.blobs.blobs().map(|blob| blob.name.clone()).collect();
+                    (names, page.next_marker.map(Some))
+                }))
+            }
+        })
+    }
+
+    /// Collect the blob prefixes Azure groups keys into when listing with a
+    /// delimiter
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        check_delimiter(delimiter)?;
+        let first_page: Option<Option<NextMarker>> = Some(None);
+
+        let pages = stream::pages(first_page, |marker| async move {
+            let Some(marker) = marker else {
+                return Ok(None);
+            };
+            let page = self
+                .retry("Azure list", || async {
+                    let mut request = self.client.list_blobs().delimiter(delimiter.to_string());
+                    if !prefix.is_empty() {
+                        request = request.prefix(prefix.to_string());
+                    }
+                    if let Some(marker) = marker.clone() {
+                        request = request.marker(marker);
+                    }
+                    request.into_stream().next().await.transpose().map_err(|e| {
+                        Self::map_error(e, &format!("listing prefixes under '{}'", prefix))
+                    })
+                })
+                .await?;
+
+            Ok(page.map(|page| {
+                let names = page.blobs.prefixes().map(|p| p.name.clone()).collect();
+                (names, page.next_marker.map(Some))
+            }))
+        });
+
+        let mut prefixes: Vec<String> = pages.try_collect().await?;
+        prefixes.sort();
+        prefixes.dedup();
+        Ok(prefixes)
+    }
+
+    /// Sum the blob sizes in the listing, without reading each blob's
+    /// properties
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        let first_page: Option<Option<NextMarker>> = Some(None);
+
+        let sizes = stream::pages(first_page, |marker| async move {
+            let Some(marker) = marker else {
+                return Ok(None);
+            };
+            let page = self
+                .retry("Azure list", || async {
+                    let mut request = self.client.list_blobs();
+                    if !prefix.is_empty() {
+                        request = request.prefix(prefix.to_string());
+                    }
+                    if let Some(marker) = marker.clone() {
+                        request = request.marker(marker);
+                    }
+                    request.into_stream().next().await.transpose().map_err(|e| {
+                        Self::map_error(e, &format!("measuring usage under '{}'", prefix))
+                    })
+                })
+                .await?;
+
+            Ok(page.map(|page| {
+                let sizes = page
+                    .blobs
+                    .blobs()
+                    .map(|blob| blob.properties.content_length)
+                    .collect();
+                (sizes, page.next_marker.map(Some))
+            }))
+        });
+
+        sizes
+            .try_fold(StorageUsage::default(), |mut usage, size| async move {
+                usage.add(size);
+                Ok(usage)
+            })
+            .await
+    }
 }
 
 impl AzureBackend {
@@ -698,75 +1087,135 @@ impl AzureBackend {
         );
 
         let blob_client = self.client.blob_client(key);
-        let data_vec = data.to_vec(); // Clone data to satisfy 'static lifetime requirement
 
-        blob_client
-            .put_block_blob(data_vec)
-            .await
-            .map_err(|e| Self::map_error(e, key))?;
+        self.retry("Azure put", || async {
+            let data_vec = data.to_vec(); // Clone data to satisfy 'static lifetime requirement
+            blob_client
+                .put_block_blob(data_vec)
+                .await
+                .map(|_| ())
+                .map_err(|e| Self::map_error(e, key))
+        })
+        .await
+    }
 
-        Ok(())
+    /// Run `operation` under the configured retry policy
+    async fn retry<T, F, Fut>(&self, what: &str, operation: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        self.blocks.retry_policy().run(what, operation).await
     }
 
-    /// Internal method for chunked uploads of large files
+    /// Internal method for staged block uploads of large files
     ///
-    /// Uploads large files as block blobs with multiple blocks.
-    /// This is more efficient than uploading the entire file at once
-    /// and allows for better handling of network interruptions.
-    async fn put_chunked(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
-        let chunk_count = data.len().div_ceil(CHUNK_SIZE);
-        tracing::debug!(
-            "Uploading {} bytes in {} chunks to {} in container {}",
-            data.len(),
-            chunk_count,
-            key,
-            self.container_name
-        );
-
+    /// Uploads `head` and then the rest of `blocks` with Put Block, at most
+    /// `max_concurrent_blocks` at a time and each retried on its own, then
+    /// commits them in order with Put Block List. Nothing is visible under
+    /// `key` until the commit succeeds, and a failed block only costs that
+    /// block's retry rather than the whole object.
+    async fn put_blocks(
+        &self,
+        key: &str,
+        head: Vec<Bytes>,
+        mut blocks: PartReader,
+    ) -> anyhow::Result<()> {
         let blob_client = self.client.blob_client(key);
+        let mut uploads = tokio::task::JoinSet::new();
+        let mut head = head.into_iter();
         let mut block_ids = Vec::new();
+        let mut total = 0usize;
+
+        loop {
+            let block = match head.next() {
+                Some(block) => block,
+                None => match blocks.next_part().await? {
+                    Some(block) => block,
+                    None => break,
+                },
+            };
+            if block_ids.len() >= MAX_BLOCKS {
+                anyhow::bail!(
+                    "{} exceeds {} blocks of {} bytes; raise the block size",
+                    key,
+                    MAX_BLOCKS,
+                    block.len()
+                );
+            }
+            total += block.len();
 
-        // Upload each chunk as a separate block
-        for (i, chunk) in data.chunks(CHUNK_SIZE).enumerate() {
-            let block_id = format!("{:08}", i).into_bytes();
-            let block_id_b64 = azure_core::base64::encode(&block_id);
+            while uploads.len() >= self.blocks.max_concurrent_blocks.max(1) {
+                if let Some(done) = uploads.join_next().await {
+                    done??;
+                }
+            }
+
+            let index = block_ids.len();
+            let block_id = azure_core::base64::encode(format!("{:08}", index).into_bytes());
+            block_ids.push(block_id.clone());
 
             tracing::trace!(
-                "Uploading chunk {}/{} ({} bytes) with block ID {}",
-                i + 1,
-                chunk_count,
-                chunk.len(),
-                block_id_b64
+                "Uploading block {} ({} bytes) with block ID {}",
+                index + 1,
+                block.len(),
+                block_id
             );
 
-            let chunk_vec = chunk.to_vec(); // Clone chunk to satisfy 'static lifetime requirement
-            blob_client
-                .put_block(block_id_b64.clone(), chunk_vec)
-                .await
-                .map_err(|e| {
-                    Self::map_error(e, &format!("uploading chunk {} of {}", i + 1, chunk_count))
-                })?;
-
-            block_ids.push(block_id_b64);
+            let client = blob_client.clone();
+            let settings = self.blocks.clone();
+            uploads.spawn(async move {
+                settings
+                    .retry_policy()
+                    .run(&format!("uploading block {}", index + 1), || {
+                        let request = client.put_block(block_id.clone(), block.clone());
+                        async move {
+                            request
+                                .await
+                                .map(|_| ())
+                                .map_err(|e| Self::map_error(e, &format!("block {}", index + 1)))
+                        }
+                    })
+                    .await
+            });
+        }
+        while let Some(done) = uploads.join_next().await {
+            done??;
         }
 
-        // Commit all blocks to create the final blob
-        let block_list = BlockList {
-            blocks: block_ids
-                .into_iter()
-                .map(BlobBlockType::new_uncommitted)
-                .collect(),
-        };
+        let block_count = block_ids.len();
+        tracing::debug!(
+            "Committing {} blocks ({} bytes) to {} in container {}",
+            block_count,
+            total,
+            key,
+            self.container_name
+        );
 
-        blob_client
-            .put_block_list(block_list)
-            .await
-            .map_err(|e| Self::map_error(e, &format!("committing {} blocks", chunk_count)))?;
+        // Commit all blocks to create the final blob
+        self.blocks
+            .retry_policy()
+            .run(&format!("committing {} blocks", block_count), || {
+                let block_list = BlockList {
+                    blocks: block_ids
+                        .iter()
+                        .cloned()
+                        .map(BlobBlockType::new_uncommitted)
+                        .collect(),
+                };
+                let request = blob_client.put_block_list(block_list);
+                async move {
+                    request.await.map(|_| ()).map_err(|e| {
+                        Self::map_error(e, &format!("committing {} blocks", block_count))
+                    })
+                }
+            })
+            .await?;
 
         tracing::debug!(
-            "Successfully uploaded {} bytes in {} chunks to {}",
-            data.len(),
-            chunk_count,
+            "Successfully uploaded {} bytes in {} blocks to {}",
+            total,
+            block_count,
             key
         );
 
@@ -888,4 +1337,74 @@ mod tests {
         const { assert!(CHUNK_SIZE >= 1024 * 1024) };
         const { assert!(CHUNK_SIZE <= 100 * 1024 * 1024) };
     }
+
+    #[test]
+    fn test_block_upload_defaults() {
+        let settings = BlockUploadSettings::default();
+        assert_eq!(settings.threshold, AZURE_BLOCK_SIZE);
+        assert_eq!(settings.block_size, CHUNK_SIZE);
+        assert_eq!(settings.max_concurrent_blocks, 8);
+        assert_eq!(settings.max_retries, 3);
+        assert_eq!(settings.initial_retry_delay_ms, 100);
+    }
+
+    #[test]
+    fn test_block_size_stays_within_block_limit() {
+        let settings = BlockUploadSettings::default();
+        assert_eq!(settings.block_size_for(10 * 1024 * 1024), CHUNK_SIZE);
+
+        // 400GB would need 100,000 blocks at 4MB, so blocks grow to 8MB
+        let len = 400 * 1024 * 1024 * 1024;
+        let block_size = settings.block_size_for(len);
+        assert!(block_size >= 2 * CHUNK_SIZE);
+        assert!(len.div_ceil(block_size) <= MAX_BLOCKS);
+    }
+
+    #[tokio::test]
+    async fn test_retry_recovers_from_transient_failures() {
+        let settings = BlockUploadSettings {
+            initial_retry_delay_ms: 1,
+            ..BlockUploadSettings::default()
+        };
+        let mut attempts = 0;
+        let result = settings
+            .retry_policy()
+            .run("uploading block 1", || {
+                attempts += 1;
+                let attempt = attempts;
+                async move {
+                    if attempt < 3 {
+                        anyhow::bail!("connection reset");
+                    }
+                    Ok(attempt)
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_retries() {
+        let settings = BlockUploadSettings {
+            max_retries: 2,
+            initial_retry_delay_ms: 1,
+            ..BlockUploadSettings::default()
+        };
+        let mut attempts = 0;
+        let result: anyhow::Result<()> = settings
+            .retry_policy()
+            .run("uploading block 7", || {
+                attempts += 1;
+                async { anyhow::bail!("connection reset") }
+            })
+            .await;
+        assert_eq!(attempts, 2);
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(
+            err.contains("uploading block 7 failed after 2 attempts"),
+            "{}",
+            err
+        );
+        assert!(err.contains("connection reset"), "{}", err);
+    }
 }
@@ -27,6 +27,7 @@
 //! - Custom endpoint configuration
 //! - Automatic credential handling
 //! - SSL/TLS support for secure connections
+//! - Multipart and streaming uploads, range reads and paginated listing
 //!
 //! # Provider Comparison
 //!
@@ -179,8 +180,13 @@
 //! ```
 
 use crate::s3::{S3Backend, S3Config};
-use crate::StorageBackend;
+use crate::storage_class::StorageClassPolicy;
+use crate::{
+    BackendCapabilities, DeleteFailure, ExpiryPolicy, KeyStream, ObjectMeta, ObjectStream,
+    StorageBackend, StorageUsage, SweepReport,
+};
 use async_trait::async_trait;
+use futures::TryStreamExt;
 use std::fmt;
 use std::sync::Arc;
 
@@ -327,6 +333,28 @@ impl B2SpacesBackend {
         bucket: &str,
         access_key: &str,
         secret_key: &str,
+    ) -> anyhow::Result<Self> {
+        Self::with_storage_class(
+            provider,
+            bucket,
+            access_key,
+            secret_key,
+            StorageClassPolicy::default(),
+        )
+        .await
+    }
+
+    /// Create a new B2/Spaces backend that writes objects in the storage
+    /// classes `storage_class` picks
+    ///
+    /// Same as [`B2SpacesBackend::new`] otherwise. Class names are sent
+    /// as-is; which ones are accepted is up to the provider.
+    pub async fn with_storage_class(
+        provider: Provider,
+        bucket: &str,
+        access_key: &str,
+        secret_key: &str,
+        storage_class: StorageClassPolicy,
     ) -> anyhow::Result<Self> {
         // Validate provider configuration
         provider.validate()?;
@@ -378,6 +406,7 @@ impl B2SpacesBackend {
         let s3_config = S3Config {
             bucket: bucket.to_string(),
             endpoint: Some(provider.endpoint()),
+            storage_class,
             ..Default::default()
         };
 
@@ -529,6 +558,91 @@ impl StorageBackend for B2SpacesBackend {
             .map_err(|e| anyhow::anyhow!("Failed to put object to {}: {}", self.provider.name(), e))
     }
 
+    /// Store an object in B2/Spaces with `If-None-Match: *`
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        self.inner
+            .put_if_absent(key, data)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to put object to {}: {}", self.provider.name(), e))
+    }
+
+    /// Replace an object in B2/Spaces with an `If-Match` upload
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        self.inner
+            .put_if_match(key, data, etag)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to put object to {}: {}", self.provider.name(), e))
+    }
+
+    /// Copy an object within the bucket with CopyObject
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        tracing::trace!(
+            provider = self.provider.name(),
+            bucket = self.bucket,
+            src_key = src_key,
+            dst_key = dst_key,
+            "Copying object"
+        );
+        self.inner.copy(src_key, dst_key).await.map_err(|e| {
+            anyhow::anyhow!("Failed to copy object in {}: {}", self.provider.name(), e)
+        })
+    }
+
+    /// Retrieve part of an object from B2/Spaces with a `Range` request
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        tracing::trace!(
+            provider = self.provider.name(),
+            bucket = self.bucket,
+            key = key,
+            offset = offset,
+            len = len,
+            "Getting object range from B2/Spaces"
+        );
+
+        self.inner.get_range(key, offset, len).await.map_err(|e| {
+            anyhow::anyhow!("Failed to get object from {}: {}", self.provider.name(), e)
+        })
+    }
+
+    /// Stream an object from B2/Spaces
+    ///
+    /// Errors opening the object name the provider; errors while reading
+    /// come from the stream itself.
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        tracing::trace!(
+            provider = self.provider.name(),
+            bucket = self.bucket,
+            key = key,
+            "Streaming object from B2/Spaces"
+        );
+
+        self.inner.get_stream(key).await.map_err(|e| {
+            anyhow::anyhow!("Failed to get object from {}: {}", self.provider.name(), e)
+        })
+    }
+
+    /// Store an object in B2/Spaces from a stream
+    ///
+    /// Streams longer than one part are sent as a multipart upload.
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> anyhow::Result<()> {
+        tracing::trace!(
+            provider = self.provider.name(),
+            bucket = self.bucket,
+            key = key,
+            "Streaming object to B2/Spaces"
+        );
+
+        self.inner
+            .put_stream(key, data)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to put object to {}: {}", self.provider.name(), e))
+    }
+
+    /// Same as the S3 backend: streaming and range reads
+    fn capabilities(&self) -> BackendCapabilities {
+        self.inner.capabilities()
+    }
+
     /// Check if an object exists in B2/Spaces
     async fn exists(&self, key: &str) -> anyhow::Result<bool> {
         tracing::trace!(
@@ -547,6 +661,18 @@ impl StorageBackend for B2SpacesBackend {
         })
     }
 
+    /// Fetch an object's metadata from B2/Spaces with a HEAD request
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        tracing::trace!(
+            provider = self.provider.name(),
+            bucket = self.bucket,
+            key = key,
+            "Fetching object metadata from B2/Spaces"
+        );
+
+        self.inner.head(key).await
+    }
+
     /// Delete an object from B2/Spaces
     ///
     /// This operation is idempotent: deleting a non-existent object succeeds.
@@ -567,6 +693,24 @@ impl StorageBackend for B2SpacesBackend {
         })
     }
 
+    /// Delete objects from B2/Spaces in batches of up to 1000 keys
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        tracing::trace!(
+            provider = self.provider.name(),
+            bucket = self.bucket,
+            count = keys.len(),
+            "Deleting objects from B2/Spaces"
+        );
+
+        self.inner.delete_many(keys).await.map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to delete objects from {}: {}",
+                self.provider.name(),
+                e
+            )
+        })
+    }
+
     /// List objects in B2/Spaces with a given prefix
     ///
     /// Returns a sorted list of all keys that start with the given prefix.
@@ -582,6 +726,82 @@ impl StorageBackend for B2SpacesBackend {
             anyhow::anyhow!("Failed to list objects in {}: {}", self.provider.name(), e)
         })
     }
+
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        tracing::trace!(
+            provider = self.provider.name(),
+            bucket = self.bucket,
+            prefix = prefix,
+            "Streaming object listing from B2/Spaces"
+        );
+
+        Box::pin(self.inner.list_objects_stream(prefix).map_err(|e| {
+            anyhow::anyhow!("Failed to list objects in {}: {}", self.provider.name(), e)
+        }))
+    }
+
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        tracing::trace!(
+            provider = self.provider.name(),
+            bucket = self.bucket,
+            prefix = prefix,
+            delimiter = delimiter,
+            "Listing prefixes in B2/Spaces"
+        );
+
+        self.inner
+            .list_prefixes(prefix, delimiter)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!("Failed to list prefixes in {}: {}", self.provider.name(), e)
+            })
+    }
+
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        tracing::trace!(
+            provider = self.provider.name(),
+            bucket = self.bucket,
+            prefix = prefix,
+            "Measuring usage in B2/Spaces"
+        );
+
+        self.inner.usage(prefix).await.map_err(|e| {
+            anyhow::anyhow!("Failed to measure usage in {}: {}", self.provider.name(), e)
+        })
+    }
+
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> anyhow::Result<bool> {
+        tracing::trace!(
+            provider = self.provider.name(),
+            bucket = self.bucket,
+            rules = policy.rules.len(),
+            "Applying lifecycle rules in B2/Spaces"
+        );
+
+        self.inner.apply_expiry(policy).await.map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to apply lifecycle rules in {}: {}",
+                self.provider.name(),
+                e
+            )
+        })
+    }
+
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport> {
+        tracing::trace!(
+            provider = self.provider.name(),
+            bucket = self.bucket,
+            "Sweeping expired objects in B2/Spaces"
+        );
+
+        self.inner.sweep_expired(policy).await.map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to sweep expired objects in {}: {}",
+                self.provider.name(),
+                e
+            )
+        })
+    }
 }
 
 #[cfg(test)]
@@ -1271,6 +1491,21 @@ mod tests {
         let retrieved = backend.get(&test_key).await.expect("Failed to get object");
         assert_eq!(retrieved, test_data, "Retrieved data should match original");
 
+        // Test get_range and get_stream
+        let range = backend
+            .get_range(&test_key, 7, 8)
+            .await
+            .expect("Failed to get object range");
+        assert_eq!(range, b"MediaGit");
+        let stream = backend
+            .get_stream(&test_key)
+            .await
+            .expect("Failed to stream object");
+        let streamed = crate::stream::collect(stream)
+            .await
+            .expect("Failed to read object stream");
+        assert_eq!(streamed, test_data);
+
         // Test list_objects
         let objects = backend
             .list_objects("mediagit-test/")
@@ -11,14 +11,19 @@
 // MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
 // GNU Affero General Public License for more details.
 
-//! LRU cache implementation for object database
+//! LRU caches for object data
 //!
-//! Provides thread-safe, async-compatible LRU caching with:
+//! [`LruCache`] is an in-memory cache for the object database, with:
 //! - Size-based eviction (configurable max bytes)
 //! - Count-based eviction (configurable max entries)
 //! - O(1) get/put operations
 //! - Concurrent access via tokio RwLock
 //!
+//! [`DiskCache`] keeps objects on local disk across runs, evicting by byte
+//! budget and age. Wrapped around a remote backend with [`CachedBackend`], it
+//! makes repeated pulls and checkouts of the same objects read them from disk
+//! instead of downloading them again.
+//!
 //! # Examples
 //!
 //! ```
@@ -33,10 +38,47 @@
 //!     assert_eq!(value, Some(vec![1, 2, 3]));
 //! }
 //! ```
+//!
+//! Caching a remote backend on disk:
+//!
+//! ```
+//! use mediagit_storage::cache::{CachedBackend, DiskCache};
+//! use mediagit_storage::{mock::MockBackend, StorageBackend};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! # let dir = tempfile::TempDir::new()?;
+//! let remote = Arc::new(MockBackend::new());
+//! remote.put("abc123", b"data").await?;
+//!
+//! let cache = DiskCache::open(dir.path(), 512 * 1024 * 1024)
+//!     .await?
+//!     .with_ttl(Duration::from_secs(7 * 24 * 3600));
+//! let storage = CachedBackend::new(remote, cache);
+//!
+//! storage.get("abc123").await?; // downloaded and cached
+//! storage.get("abc123").await?; // read from disk
+//! assert_eq!(storage.cache().stats().hits, 1);
+//! # Ok(())
+//! # }
+//! ```
 
-use std::collections::{HashMap, VecDeque};
+use crate::{
+    BackendCapabilities, DeleteFailure, ExpiryPolicy, KeyStream, ObjectMeta, StorageBackend,
+    StorageUsage, SweepReport,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use tokio::sync::RwLock;
+use tracing::{debug, warn};
 
 /// Entry in the LRU cache with metadata
 #[derive(Debug, Clone)]
@@ -285,9 +327,472 @@ pub struct CacheStats {
     pub hit_rate: f64,
 }
 
+/// Persistent LRU cache of objects on local disk
+///
+/// Each object is stored in its own file named after a hash of its key, so
+/// the cache survives restarts: [`open`](Self::open) rebuilds the index from
+/// the files already there, treating the most recently written as the most
+/// recently used. Entries older than the TTL count as misses and are removed
+/// when next looked up.
+#[derive(Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    ttl: Option<Duration>,
+    state: std::sync::Mutex<DiskCacheState>,
+}
+
+#[derive(Debug, Default)]
+struct DiskCacheState {
+    /// Entries by file name
+    entries: HashMap<String, DiskEntry>,
+    /// File names by access order, least recent first
+    order: BTreeMap<u64, String>,
+    total_bytes: u64,
+    access_counter: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    expirations: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DiskEntry {
+    size: u64,
+    stored: SystemTime,
+    access_order: u64,
+}
+
+impl DiskCacheState {
+    fn touch(&mut self, name: &str) {
+        self.access_counter += 1;
+        let access_order = self.access_counter;
+        if let Some(entry) = self.entries.get_mut(name) {
+            self.order.remove(&entry.access_order);
+            entry.access_order = access_order;
+            self.order.insert(access_order, name.to_string());
+        }
+    }
+
+    fn insert(&mut self, name: String, size: u64, stored: SystemTime) {
+        self.remove(&name);
+        self.access_counter += 1;
+        let access_order = self.access_counter;
+        self.order.insert(access_order, name.clone());
+        self.entries.insert(
+            name,
+            DiskEntry {
+                size,
+                stored,
+                access_order,
+            },
+        );
+        self.total_bytes += size;
+    }
+
+    fn remove(&mut self, name: &str) -> bool {
+        match self.entries.remove(name) {
+            Some(entry) => {
+                self.order.remove(&entry.access_order);
+                self.total_bytes -= entry.size;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop least recently used entries until `max_bytes` fit, returning
+    /// their file names
+    fn evict(&mut self, max_bytes: u64) -> Vec<String> {
+        let mut victims = Vec::new();
+        while self.total_bytes > max_bytes {
+            let Some((_, name)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&name) {
+                self.total_bytes -= entry.size;
+                self.evictions += 1;
+            }
+            victims.push(name);
+        }
+        victims
+    }
+}
+
+impl DiskCache {
+    /// Open the cache in `dir`, creating it if needed, holding at most
+    /// `max_bytes` of objects
+    pub async fn open(dir: impl Into<PathBuf>, max_bytes: u64) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+
+        let mut found = Vec::new();
+        let mut listing = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = listing.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let metadata = entry.metadata().await?;
+            if name.ends_with(".tmp") {
+                // Left behind by an interrupted write
+                let _ = tokio::fs::remove_file(entry.path()).await;
+                continue;
+            }
+            if metadata.is_file() {
+                let stored = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                found.push((stored, name, metadata.len()));
+            }
+        }
+        found.sort();
+
+        let mut state = DiskCacheState::default();
+        for (stored, name, size) in found {
+            state.insert(name, size, stored);
+        }
+        let cache = Self {
+            dir,
+            max_bytes,
+            ttl: None,
+            state: std::sync::Mutex::new(state),
+        };
+        // The budget may have shrunk since the cache was last used
+        let victims = cache.lock().evict(max_bytes);
+        cache.remove_files(victims).await;
+        Ok(cache)
+    }
+
+    /// Treat objects cached longer than `ttl` ago as missing
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// The directory objects are cached in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The cached object, if present and not expired
+    pub async fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let name = file_name(key);
+        if !self.lookup(&name).await {
+            return None;
+        }
+        match tokio::fs::read(self.dir.join(&name)).await {
+            Ok(data) => Some(data),
+            Err(e) => {
+                // Removed behind our back; forget it and fetch again
+                debug!("Cached object {} is unreadable: {}", key, e);
+                let mut state = self.lock();
+                state.remove(&name);
+                state.hits -= 1;
+                state.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// `len` bytes of the cached object from `offset`, if present and not
+    /// expired
+    pub async fn get_range(&self, key: &str, offset: u64, len: u64) -> Option<Vec<u8>> {
+        use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+        let name = file_name(key);
+        if !self.lookup(&name).await {
+            return None;
+        }
+        let read = async {
+            let mut file = tokio::fs::File::open(self.dir.join(&name)).await?;
+            file.seek(std::io::SeekFrom::Start(offset)).await?;
+            let mut data = Vec::new();
+            file.take(len).read_to_end(&mut data).await?;
+            std::io::Result::Ok(data)
+        };
+        read.await.ok()
+    }
+
+    /// Whether `key` is cached and not expired, without counting a hit or miss
+    pub fn contains(&self, key: &str) -> bool {
+        let state = self.lock();
+        state
+            .entries
+            .get(&file_name(key))
+            .is_some_and(|entry| !self.expired(entry))
+    }
+
+    /// Cache `data` under `key`, evicting least recently used objects to
+    /// stay within the byte budget
+    ///
+    /// Objects larger than the whole budget are not cached.
+    pub async fn insert(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        let size = data.len() as u64;
+        if size > self.max_bytes {
+            return Ok(());
+        }
+
+        let name = file_name(key);
+        let temp = self.dir.join(format!(
+            "{}.{}.tmp",
+            name,
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&temp, data).await?;
+        tokio::fs::rename(&temp, self.dir.join(&name)).await?;
+
+        let victims = {
+            let mut state = self.lock();
+            state.insert(name, size, SystemTime::now());
+            state.evict(self.max_bytes)
+        };
+        self.remove_files(victims).await;
+        Ok(())
+    }
+
+    /// Drop `key` from the cache
+    pub async fn remove(&self, key: &str) {
+        let name = file_name(key);
+        if self.lock().remove(&name) {
+            self.remove_files(vec![name]).await;
+        }
+    }
+
+    /// Current cache statistics
+    pub fn stats(&self) -> DiskCacheStats {
+        let state = self.lock();
+        let total_accesses = state.hits + state.misses;
+        DiskCacheStats {
+            entry_count: state.entries.len(),
+            total_bytes: state.total_bytes,
+            max_bytes: self.max_bytes,
+            hits: state.hits,
+            misses: state.misses,
+            evictions: state.evictions,
+            expirations: state.expirations,
+            hit_rate: if total_accesses > 0 {
+                state.hits as f64 / total_accesses as f64
+            } else {
+                0.0
+            },
+        }
+    }
+
+    /// Record a hit or miss for `name`, removing it if it has expired
+    async fn lookup(&self, name: &str) -> bool {
+        let expired = {
+            let mut state = self.lock();
+            match state.entries.get(name).copied() {
+                Some(entry) if !self.expired(&entry) => {
+                    state.hits += 1;
+                    state.touch(name);
+                    return true;
+                }
+                Some(_) => {
+                    state.remove(name);
+                    state.expirations += 1;
+                    state.misses += 1;
+                    true
+                }
+                None => {
+                    state.misses += 1;
+                    false
+                }
+            }
+        };
+        if expired {
+            self.remove_files(vec![name.to_string()]).await;
+        }
+        false
+    }
+
+    fn expired(&self, entry: &DiskEntry) -> bool {
+        self.ttl
+            .is_some_and(|ttl| entry.stored.elapsed().is_ok_and(|age| age > ttl))
+    }
+
+    async fn remove_files(&self, names: Vec<String>) {
+        for name in names {
+            if let Err(e) = tokio::fs::remove_file(self.dir.join(&name)).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to remove cached object {}: {}", name, e);
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, DiskCacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Distinguishes concurrent writes of the same object
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Cache file name for `key`; hashing keeps it flat and filesystem-safe
+fn file_name(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Disk cache statistics
+#[derive(Debug, Clone, Copy)]
+pub struct DiskCacheStats {
+    /// Current number of cached objects
+    pub entry_count: usize,
+    /// Current total size in bytes
+    pub total_bytes: u64,
+    /// Byte budget
+    pub max_bytes: u64,
+    /// Lookups served from disk
+    pub hits: u64,
+    /// Lookups that went to the backend, including expired entries
+    pub misses: u64,
+    /// Objects evicted to stay within the byte budget
+    pub evictions: u64,
+    /// Objects dropped for outliving the TTL
+    pub expirations: u64,
+    /// Hit rate (0.0 to 1.0)
+    pub hit_rate: f64,
+}
+
+/// Storage backend wrapper that reads through a [`DiskCache`]
+///
+/// Objects fetched from the wrapped backend are kept on disk, so reading
+/// them again doesn't download them. Writes and deletes go to the backend
+/// and drop any cached copy.
+#[derive(Debug, Clone)]
+pub struct CachedBackend {
+    inner: Arc<dyn StorageBackend>,
+    cache: Arc<DiskCache>,
+}
+
+impl CachedBackend {
+    /// Cache reads from `inner` in `cache`
+    pub fn new(inner: Arc<dyn StorageBackend>, cache: DiskCache) -> Self {
+        Self {
+            inner,
+            cache: Arc::new(cache),
+        }
+    }
+
+    /// The disk cache
+    pub fn cache(&self) -> &DiskCache {
+        &self.cache
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Arc<dyn StorageBackend> {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl StorageBackend for CachedBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        if let Some(data) = self.cache.get(key).await {
+            return Ok(data);
+        }
+        let data = self.inner.get(key).await?;
+        if let Err(e) = self.cache.insert(key, &data).await {
+            warn!("Failed to cache object {}: {}", key, e);
+        }
+        Ok(data)
+    }
+
+    /// Reads are served from the cache, so memory mapping and streaming
+    /// don't carry over
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            mmap: false,
+            streaming: false,
+            ..self.inner.capabilities()
+        }
+    }
+
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<SystemTime>> {
+        self.inner.modified(key).await
+    }
+
+    /// Metadata always comes from the backend, since the cache keeps none
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        self.inner.head(key).await
+    }
+
+    /// Served from the cache when the object is there; otherwise only the
+    /// range is fetched and nothing is cached
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        if let Some(data) = self.cache.get_range(key, offset, len).await {
+            return Ok(data);
+        }
+        self.inner.get_range(key, offset, len).await
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        self.cache.remove(key).await;
+        self.inner.put(key, data).await
+    }
+
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        self.inner.put_if_absent(key, data).await
+    }
+
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        self.cache.remove(key).await;
+        self.inner.put_if_match(key, data, etag).await
+    }
+
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        self.cache.remove(dst_key).await;
+        self.inner.copy(src_key, dst_key).await
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        if self.cache.contains(key) {
+            return Ok(true);
+        }
+        self.inner.exists(key).await
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.cache.remove(key).await;
+        self.inner.delete(key).await
+    }
+
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        for key in keys {
+            self.cache.remove(key).await;
+        }
+        self.inner.delete_many(keys).await
+    }
+
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        self.inner.list_objects(prefix).await
+    }
+
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        self.inner.list_objects_stream(prefix)
+    }
+
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        self.inner.list_prefixes(prefix, delimiter).await
+    }
+
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        self.inner.usage(prefix).await
+    }
+
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> anyhow::Result<bool> {
+        self.inner.apply_expiry(policy).await
+    }
+
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport> {
+        self.inner.sweep_expired(policy).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mock::MockBackend;
+    use tempfile::TempDir;
 
     #[tokio::test]
     async fn test_basic_get_put() {
@@ -529,4 +1034,93 @@ mod tests {
         let stats = cache.stats().await;
         assert_eq!(stats.max_object_size, 50 * 1024 * 1024);
     }
+
+    #[tokio::test]
+    async fn test_disk_cache_reads_through() {
+        let remote = Arc::new(MockBackend::new());
+        let dir = TempDir::new().unwrap();
+        remote.put("objects/a", b"alpha").await.unwrap();
+
+        let cache = DiskCache::open(dir.path(), 1024).await.unwrap();
+        let storage = CachedBackend::new(remote.clone(), cache);
+
+        assert_eq!(storage.get("objects/a").await.unwrap(), b"alpha");
+        remote.delete("objects/a").await.unwrap();
+        assert_eq!(storage.get("objects/a").await.unwrap(), b"alpha");
+        assert_eq!(storage.get_range("objects/a", 1, 3).await.unwrap(), b"lph");
+        assert!(storage.exists("objects/a").await.unwrap());
+
+        let stats = storage.cache().stats();
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        assert_eq!(stats.total_bytes, 5);
+
+        // Writes and deletes drop the cached copy
+        storage.put("objects/a", b"changed").await.unwrap();
+        assert_eq!(storage.get("objects/a").await.unwrap(), b"changed");
+        storage.delete("objects/a").await.unwrap();
+        assert!(storage.get("objects/a").await.is_err());
+        assert_eq!(storage.cache().stats().entry_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_evicts_least_recently_used() {
+        let dir = TempDir::new().unwrap();
+        let cache = DiskCache::open(dir.path(), 10).await.unwrap();
+
+        cache.insert("a", &[1; 4]).await.unwrap();
+        cache.insert("b", &[2; 4]).await.unwrap();
+        assert!(cache.get("a").await.is_some());
+        cache.insert("c", &[3; 4]).await.unwrap();
+
+        assert!(cache.contains("a"));
+        assert!(!cache.contains("b"));
+        assert!(cache.contains("c"));
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+
+        // Larger than the whole budget
+        cache.insert("d", &[4; 11]).await.unwrap();
+        assert!(!cache.contains("d"));
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_ttl() {
+        let dir = TempDir::new().unwrap();
+        let cache = DiskCache::open(dir.path(), 1024)
+            .await
+            .unwrap()
+            .with_ttl(Duration::from_millis(50));
+
+        cache.insert("a", b"alpha").await.unwrap();
+        assert!(cache.get("a").await.is_some());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!cache.contains("a"));
+        assert!(cache.get("a").await.is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.expirations, 1);
+        assert_eq!(stats.entry_count, 0);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_persists_across_opens() {
+        let dir = TempDir::new().unwrap();
+        {
+            let cache = DiskCache::open(dir.path(), 1024).await.unwrap();
+            cache.insert("a", b"alpha").await.unwrap();
+            cache.insert("b", b"bravo").await.unwrap();
+        }
+        std::fs::write(dir.path().join("leftover.0.tmp"), b"partial").unwrap();
+
+        let cache = DiskCache::open(dir.path(), 1024).await.unwrap();
+        assert_eq!(cache.get("a").await.unwrap(), b"alpha");
+        assert_eq!(cache.stats().total_bytes, 10);
+        assert!(!dir.path().join("leftover.0.tmp").exists());
+
+        // A smaller budget evicts on open
+        drop(cache);
+        let cache = DiskCache::open(dir.path(), 5).await.unwrap();
+        assert_eq!(cache.stats().entry_count, 1);
+    }
 }
@@ -0,0 +1,58 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Optional features a storage backend supports
+//!
+//! Higher layers check [`StorageBackend::capabilities`](crate::StorageBackend::capabilities)
+//! to choose a code path up front instead of trying an operation and falling
+//! back when it fails.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::{mock::MockBackend, BackendCapabilities, StorageBackend};
+//!
+//! let storage = MockBackend::new();
+//! if storage.capabilities().mmap {
+//!     // read through get_mapped
+//! }
+//! assert_eq!(storage.capabilities(), BackendCapabilities::default());
+//! ```
+
+/// Optional features of a storage backend
+///
+/// A flag is only set when the backend itself implements the feature, not
+/// merely when the underlying service could offer it. The default has every
+/// feature off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackendCapabilities {
+    /// Large objects are memory-mapped by `get_mapped` instead of copied
+    pub mmap: bool,
+
+    /// Objects can be copied within the backend without downloading them
+    pub server_side_copy: bool,
+
+    /// Writes can be made conditional on the currently stored value
+    pub compare_and_swap: bool,
+
+    /// Expiry policies are enforced by the service itself once
+    /// [`apply_expiry`](crate::StorageBackend::apply_expiry) installs them
+    pub ttl: bool,
+
+    /// Part of an object can be read without fetching all of it
+    pub range_reads: bool,
+
+    /// `get_stream` and `put_stream` move data chunk by chunk instead of
+    /// buffering whole objects
+    pub streaming: bool,
+}
@@ -0,0 +1,216 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! End-to-end object checksums
+//!
+//! Backends configured with a [`ChecksumAlgorithm`] compute a checksum of
+//! each object they upload and send it along, so the service rejects an
+//! upload that was corrupted on the way. Reads are checked against the
+//! checksum the service stored, and data that doesn't match is reported as
+//! [`StorageError::ChecksumMismatch`] instead of being returned.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::checksum::{Checksum, ChecksumAlgorithm};
+//!
+//! let checksum = ChecksumAlgorithm::Crc32c.checksum(b"123456789");
+//! assert_eq!(checksum.to_string(), "crc32c:e3069283");
+//! assert_eq!(checksum.to_base64(), "4waSgw==");
+//!
+//! let stored = Checksum::from_base64(ChecksumAlgorithm::Crc32c, "4waSgw==").unwrap();
+//! assert!(stored.verify("objects/ab", b"123456789").is_ok());
+//! assert!(stored.verify("objects/ab", b"12345678").is_err());
+//! ```
+
+use crate::StorageError;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+/// CRC-32 with the Castagnoli polynomial, as S3 and GCS compute it
+const CRC32C: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISCSI);
+
+/// Algorithm used to checksum objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    /// SHA-256: slower, but collision resistant
+    Sha256,
+
+    /// CRC32C: fast, and the checksum GCS keeps for every object
+    Crc32c,
+}
+
+impl ChecksumAlgorithm {
+    /// Lowercase name, as accepted by [`FromStr`]
+    pub fn name(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Crc32c => "crc32c",
+        }
+    }
+
+    /// Checksum `data`
+    pub fn checksum(self, data: &[u8]) -> Checksum {
+        let digest = match self {
+            ChecksumAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            ChecksumAlgorithm::Crc32c => CRC32C.checksum(data).to_be_bytes().to_vec(),
+        };
+        Checksum {
+            algorithm: self,
+            digest,
+        }
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ChecksumAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().replace('-', "").as_str() {
+            "sha256" => Ok(ChecksumAlgorithm::Sha256),
+            "crc32c" => Ok(ChecksumAlgorithm::Crc32c),
+            _ => anyhow::bail!(
+                "unknown checksum algorithm '{}' (expected sha256 or crc32c)",
+                s
+            ),
+        }
+    }
+}
+
+/// A checksum of an object's content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    algorithm: ChecksumAlgorithm,
+    digest: Vec<u8>,
+}
+
+impl Checksum {
+    /// Parse the base64 form services send checksums in, returning `None`
+    /// if it isn't valid base64 or has the wrong length for `algorithm`
+    ///
+    /// Checksums of multipart uploads, which end in `-<parts>`, aren't
+    /// checksums of the whole object and are rejected too.
+    pub fn from_base64(algorithm: ChecksumAlgorithm, encoded: &str) -> Option<Self> {
+        let digest = BASE64.decode(encoded.trim()).ok()?;
+        let expected_len = match algorithm {
+            ChecksumAlgorithm::Sha256 => 32,
+            ChecksumAlgorithm::Crc32c => 4,
+        };
+        (digest.len() == expected_len).then_some(Checksum { algorithm, digest })
+    }
+
+    /// Algorithm the checksum was computed with
+    pub fn algorithm(&self) -> ChecksumAlgorithm {
+        self.algorithm
+    }
+
+    /// Base64 of the checksum bytes, as S3 and GCS expect it
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(&self.digest)
+    }
+
+    /// Check that `data`, read from `key`, has this checksum
+    pub fn verify(&self, key: &str, data: &[u8]) -> Result<(), StorageError> {
+        let actual = self.algorithm.checksum(data);
+        if actual == *self {
+            Ok(())
+        } else {
+            Err(StorageError::checksum_mismatch(
+                key,
+                self.to_string(),
+                actual.to_string(),
+            ))
+        }
+    }
+}
+
+impl fmt::Display for Checksum {
+    /// `<algorithm>:<hex digest>`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm, hex::encode(&self.digest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_checksums() {
+        assert_eq!(
+            ChecksumAlgorithm::Sha256.checksum(b"abc").to_string(),
+            "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            ChecksumAlgorithm::Crc32c.checksum(b"").to_string(),
+            "crc32c:00000000"
+        );
+        assert_eq!(
+            ChecksumAlgorithm::Crc32c.checksum(b"123456789").to_string(),
+            "crc32c:e3069283"
+        );
+    }
+
+    #[test]
+    fn test_base64_round_trip() {
+        for algorithm in [ChecksumAlgorithm::Sha256, ChecksumAlgorithm::Crc32c] {
+            let checksum = algorithm.checksum(b"frame data");
+            let parsed = Checksum::from_base64(algorithm, &checksum.to_base64());
+            assert_eq!(parsed, Some(checksum));
+        }
+    }
+
+    #[test]
+    fn test_from_base64_rejects_other_lengths() {
+        let crc = ChecksumAlgorithm::Crc32c.checksum(b"data").to_base64();
+        assert!(Checksum::from_base64(ChecksumAlgorithm::Sha256, &crc).is_none());
+        assert!(Checksum::from_base64(ChecksumAlgorithm::Crc32c, "not base64!").is_none());
+        // Multipart upload checksum
+        let composite = format!("{}-3", crc);
+        assert!(Checksum::from_base64(ChecksumAlgorithm::Crc32c, &composite).is_none());
+    }
+
+    #[test]
+    fn test_verify_reports_mismatch() {
+        let checksum = ChecksumAlgorithm::Sha256.checksum(b"original");
+        assert!(checksum.verify("clip.mov", b"original").is_ok());
+
+        let err = checksum.verify("clip.mov", b"corrupted").unwrap_err();
+        assert!(err.is_checksum_mismatch());
+        assert!(err
+            .to_string()
+            .starts_with("checksum mismatch for clip.mov"));
+    }
+
+    #[test]
+    fn test_parse_algorithm() {
+        assert_eq!(
+            "SHA-256".parse::<ChecksumAlgorithm>().unwrap(),
+            ChecksumAlgorithm::Sha256
+        );
+        assert_eq!(
+            "crc32c".parse::<ChecksumAlgorithm>().unwrap(),
+            ChecksumAlgorithm::Crc32c
+        );
+        assert!("md5".parse::<ChecksumAlgorithm>().is_err());
+    }
+}
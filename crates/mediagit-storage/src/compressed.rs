@@ -0,0 +1,356 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Transparent compression of object payloads
+//!
+//! [`CompressedBackend`] runs every payload through
+//! [`SmartCompressor`] before handing it to the wrapped backend and
+//! decompresses it again on reads. Code that talks to a backend directly,
+//! such as the migration tool, then gets the same savings as objects written
+//! through the object database.
+//!
+//! The strategy is chosen from the key's extension and the payload's magic
+//! bytes, so text compresses well while JPEGs and videos are stored as they
+//! are. Objects must be written through the wrapper to be read through it.
+//!
+//! Each stored payload starts with a header recording the uncompressed size,
+//! so [`head`](StorageBackend::head) can report it without decompressing:
+//!
+//! ```text
+//! [magic:4 "MGCZ"][size:8 LE][compressed payload]
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::{mock::MockBackend, CompressedBackend, StorageBackend};
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let bucket = Arc::new(MockBackend::new());
+//! let storage = CompressedBackend::new(bucket.clone());
+//!
+//! let text = "All work and no play makes Jack a dull boy.\n".repeat(100);
+//! storage.put("notes.txt", text.as_bytes()).await?;
+//! assert!(bucket.get("notes.txt").await?.len() < text.len());
+//! assert_eq!(storage.get("notes.txt").await?, text.as_bytes());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    BackendCapabilities, DeleteFailure, ExpiryPolicy, KeyStream, ObjectMeta, StorageBackend,
+    StorageUsage, SweepReport,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use mediagit_compression::{ObjectType, SmartCompressor, TypeAwareCompressor};
+use std::sync::Arc;
+use tracing::trace;
+
+/// Marks an object written by [`CompressedBackend`]
+const MAGIC: &[u8; 4] = b"MGCZ";
+
+/// Length of the header in front of each payload
+const HEADER_LEN: usize = MAGIC.len() + 8;
+
+/// Storage backend wrapper that compresses object payloads
+#[derive(Debug, Clone)]
+pub struct CompressedBackend {
+    inner: Arc<dyn StorageBackend>,
+    compressor: Arc<SmartCompressor>,
+}
+
+impl CompressedBackend {
+    /// Wrap `inner` with a default [`SmartCompressor`]
+    pub fn new(inner: Arc<dyn StorageBackend>) -> Self {
+        Self {
+            inner,
+            compressor: Arc::new(SmartCompressor::new()),
+        }
+    }
+
+    /// Compress with `compressor`, for example one with a minimum size or
+    /// dictionaries loaded
+    pub fn with_compressor(mut self, compressor: SmartCompressor) -> Self {
+        self.compressor = Arc::new(compressor);
+        self
+    }
+
+    /// The compressor payloads go through
+    pub fn compressor(&self) -> &SmartCompressor {
+        &self.compressor
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Arc<dyn StorageBackend> {
+        &self.inner
+    }
+
+    fn compress(&self, key: &str, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let object_type = ObjectType::detect(key, data);
+        let compressed = self
+            .compressor
+            .compress_typed_with_size(data, object_type)
+            .with_context(|| format!("failed to compress object {}", key))?;
+        let mut stored = Vec::with_capacity(HEADER_LEN + compressed.len());
+        stored.extend_from_slice(MAGIC);
+        stored.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        stored.extend_from_slice(&compressed);
+        trace!(
+            key = key,
+            object_type = ?object_type,
+            size = data.len(),
+            stored = stored.len(),
+            "Compressed object"
+        );
+        Ok(stored)
+    }
+}
+
+/// Split a stored object, or the start of one, into its uncompressed size
+/// and compressed payload
+fn parse_header(stored: &[u8]) -> Option<(u64, &[u8])> {
+    let rest = stored.strip_prefix(MAGIC)?;
+    if rest.len() < 8 {
+        return None;
+    }
+    let (size, payload) = rest.split_at(8);
+    Some((u64::from_le_bytes(size.try_into().ok()?), payload))
+}
+
+#[async_trait]
+impl StorageBackend for CompressedBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        let stored = self.inner.get(key).await?;
+        let (size, payload) = parse_header(&stored)
+            .with_context(|| format!("object {} was not written compressed", key))?;
+        let data = self
+            .compressor
+            .decompress_typed(payload)
+            .with_context(|| format!("failed to decompress object {}", key))?;
+        if data.len() as u64 != size {
+            anyhow::bail!(
+                "object {} decompressed to {} bytes, its header says {}",
+                key,
+                data.len(),
+                size
+            );
+        }
+        Ok(data)
+    }
+
+    /// Range reads and streaming need whole objects to decompress, while
+    /// copies and conditional writes work on compressed payloads as they
+    /// are, so only those and expiry carry over
+    fn capabilities(&self) -> BackendCapabilities {
+        let inner = self.inner.capabilities();
+        BackendCapabilities {
+            server_side_copy: inner.server_side_copy,
+            compare_and_swap: inner.compare_and_swap,
+            ttl: inner.ttl,
+            ..BackendCapabilities::default()
+        }
+    }
+
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<std::time::SystemTime>> {
+        self.inner.modified(key).await
+    }
+
+    /// Keeps the stored payload's etag, which
+    /// [`put_if_match`](StorageBackend::put_if_match) expects, and reports
+    /// the uncompressed size from the header, read with a range request
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        let meta = self.inner.head(key).await?;
+        let header = self.inner.get_range(key, 0, HEADER_LEN as u64).await?;
+        let (size, _) = parse_header(&header)
+            .with_context(|| format!("object {} was not written compressed", key))?;
+        Ok(ObjectMeta { size, ..meta })
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        let compressed = self.compress(key, data)?;
+        self.inner.put(key, &compressed).await
+    }
+
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        let compressed = self.compress(key, data)?;
+        self.inner.put_if_absent(key, &compressed).await
+    }
+
+    /// `etag` is the stored payload's, as [`head`](StorageBackend::head)
+    /// reports it
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        let compressed = self.compress(key, data)?;
+        self.inner.put_if_match(key, &compressed, etag).await
+    }
+
+    /// Copies the compressed payload as it is
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        self.inner.copy(src_key, dst_key).await
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        self.inner.exists(key).await
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.inner.delete(key).await
+    }
+
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        self.inner.delete_many(keys).await
+    }
+
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        self.inner.list_objects(prefix).await
+    }
+
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        self.inner.list_objects_stream(prefix)
+    }
+
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        self.inner.list_prefixes(prefix, delimiter).await
+    }
+
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        self.inner.usage(prefix).await
+    }
+
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> anyhow::Result<bool> {
+        self.inner.apply_expiry(policy).await
+    }
+
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport> {
+        self.inner.sweep_expired(policy).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{CasBackend, MockBackend};
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let bucket = Arc::new(MockBackend::new());
+        let storage = CompressedBackend::new(bucket.clone());
+
+        let text = "frame,x,y\n1,0.5,0.25\n".repeat(1000);
+        storage
+            .put("shots/track.csv", text.as_bytes())
+            .await
+            .unwrap();
+        assert!(bucket.get("shots/track.csv").await.unwrap().len() < text.len() / 10);
+        assert_eq!(
+            storage.get("shots/track.csv").await.unwrap(),
+            text.as_bytes()
+        );
+        assert_eq!(
+            storage.get_range("shots/track.csv", 10, 11).await.unwrap(),
+            &text.as_bytes()[10..21]
+        );
+
+        storage.put("empty", b"").await.unwrap();
+        assert!(storage.get("empty").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_compressed_media_is_stored_as_is() {
+        let bucket = Arc::new(MockBackend::new());
+        let storage = CompressedBackend::new(bucket.clone());
+
+        // JPEG magic followed by incompressible bytes
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0];
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        jpeg.extend((0..64 * 1024).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        }));
+
+        storage.put("plates/bg.jpg", &jpeg).await.unwrap();
+        let stored = bucket.get("plates/bg.jpg").await.unwrap();
+        assert!(stored.len() <= HEADER_LEN + jpeg.len() + 1);
+        assert_eq!(storage.get("plates/bg.jpg").await.unwrap(), jpeg);
+    }
+
+    #[tokio::test]
+    async fn test_custom_compressor() {
+        let bucket = Arc::new(MockBackend::new());
+        let storage = CompressedBackend::new(bucket.clone())
+            .with_compressor(SmartCompressor::new().with_min_compress_size(1024));
+        assert_eq!(storage.compressor().min_compress_size(), 1024);
+
+        let small = "tiny tiny tiny tiny tiny tiny";
+        storage.put("notes.txt", small.as_bytes()).await.unwrap();
+        assert_eq!(
+            bucket.get("notes.txt").await.unwrap()[HEADER_LEN + 1..],
+            *small.as_bytes()
+        );
+        assert_eq!(storage.get("notes.txt").await.unwrap(), small.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_head_reads_size_from_header() {
+        let bucket = Arc::new(MockBackend::new());
+        let storage = CompressedBackend::new(bucket.clone());
+
+        // The payload is never decompressed, so it need not be valid
+        let mut stored = MAGIC.to_vec();
+        stored.extend_from_slice(&42u64.to_le_bytes());
+        stored.extend_from_slice(b"\x28\xb5\x2f\xfd not zstd");
+        bucket.put("renders/big.exr", &stored).await.unwrap();
+        assert_eq!(storage.head("renders/big.exr").await.unwrap().size, 42);
+        assert!(storage.get("renders/big.exr").await.is_err());
+
+        bucket.put("raw.txt", b"raw").await.unwrap();
+        assert!(storage.head("raw.txt").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_conditional_replace_uses_head_etag() {
+        let bucket = Arc::new(CasBackend::default());
+        let storage = CompressedBackend::new(bucket.clone());
+        assert!(storage.capabilities().compare_and_swap);
+
+        let text = "frame,x,y\n1,0.5,0.25\n".repeat(1000);
+        storage
+            .put("shots/track.csv", text.as_bytes())
+            .await
+            .unwrap();
+        let meta = storage.head("shots/track.csv").await.unwrap();
+        assert_eq!(meta.size, text.len() as u64);
+        assert_eq!(
+            meta.etag,
+            bucket.head("shots/track.csv").await.unwrap().etag
+        );
+        let etag = meta.etag.unwrap();
+
+        assert!(storage
+            .put_if_match("shots/track.csv", b"frame,x,y\n", &etag)
+            .await
+            .unwrap());
+        assert!(!storage
+            .put_if_match("shots/track.csv", b"stale", &etag)
+            .await
+            .unwrap());
+        assert_eq!(
+            storage.get("shots/track.csv").await.unwrap(),
+            b"frame,x,y\n"
+        );
+    }
+}
@@ -0,0 +1,388 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Client-side encryption of object payloads
+//!
+//! [`EncryptedBackend`] encrypts every payload with AES-256-GCM from
+//! [`mediagit_security::encryption`] before handing it to the wrapped
+//! backend, so the storage provider only ever sees ciphertext. Keys and
+//! listings pass through unchanged.
+//!
+//! Each stored object starts with a header naming the key it was encrypted
+//! with and the plaintext's size:
+//!
+//! ```text
+//! [magic:4 "MGEK"][key_id_len:1][key_id:N][size:8 LE][version:1][nonce:12][ciphertext][tag]
+//! ```
+//!
+//! New objects are written with the current key. Older keys can be added
+//! with [`EncryptedBackend::with_key`] so objects written before a key
+//! rotation stay readable.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_security::encryption::EncryptionKey;
+//! use mediagit_storage::{mock::MockBackend, EncryptedBackend, StorageBackend};
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let bucket = Arc::new(MockBackend::new());
+//! let storage = EncryptedBackend::new(bucket.clone(), "2025-01", EncryptionKey::generate()?)?;
+//!
+//! storage.put("abc123", b"data").await?;
+//! assert_ne!(bucket.get("abc123").await?, b"data");
+//! assert_eq!(storage.get("abc123").await?, b"data");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    BackendCapabilities, DeleteFailure, ExpiryPolicy, KeyStream, ObjectMeta, StorageBackend,
+    StorageUsage, SweepReport,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use mediagit_security::encryption::{self, EncryptionKey};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Marks an object written by [`EncryptedBackend`]
+const MAGIC: &[u8; 4] = b"MGEK";
+
+/// Longest key ID the header can hold
+const MAX_KEY_ID_LEN: usize = u8::MAX as usize;
+
+/// Longest header, read on its own by [`head`](StorageBackend::head)
+const MAX_HEADER_LEN: usize = MAGIC.len() + 1 + MAX_KEY_ID_LEN + 8;
+
+/// Storage backend wrapper that encrypts object payloads
+#[derive(Debug, Clone)]
+pub struct EncryptedBackend {
+    inner: Arc<dyn StorageBackend>,
+    key_id: String,
+    keys: Arc<HashMap<String, EncryptionKey>>,
+}
+
+impl EncryptedBackend {
+    /// Wrap `inner`, encrypting new objects with `key` under the name `key_id`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `key_id` is empty or longer than 255 bytes.
+    pub fn new(
+        inner: Arc<dyn StorageBackend>,
+        key_id: impl Into<String>,
+        key: EncryptionKey,
+    ) -> anyhow::Result<Self> {
+        let key_id = key_id.into();
+        validate_key_id(&key_id)?;
+        let keys = HashMap::from([(key_id.clone(), key)]);
+        Ok(Self {
+            inner,
+            key_id,
+            keys: Arc::new(keys),
+        })
+    }
+
+    /// Also decrypt objects written with `key` under the name `key_id`
+    ///
+    /// New objects are still encrypted with the key given to
+    /// [`new`](Self::new).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `key_id` is invalid or already names another key.
+    pub fn with_key(
+        mut self,
+        key_id: impl Into<String>,
+        key: EncryptionKey,
+    ) -> anyhow::Result<Self> {
+        let key_id = key_id.into();
+        validate_key_id(&key_id)?;
+        if self.keys.contains_key(&key_id) {
+            anyhow::bail!("encryption key '{}' is already configured", key_id);
+        }
+        Arc::make_mut(&mut self.keys).insert(key_id, key);
+        Ok(self)
+    }
+
+    /// The ID of the key new objects are encrypted with
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Arc<dyn StorageBackend> {
+        &self.inner
+    }
+
+    fn seal(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let ciphertext = encryption::encrypt(&self.keys[&self.key_id], data)?;
+        let mut sealed =
+            Vec::with_capacity(MAGIC.len() + 1 + self.key_id.len() + 8 + ciphertext.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.push(self.key_id.len() as u8);
+        sealed.extend_from_slice(self.key_id.as_bytes());
+        sealed.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    fn open(&self, key: &str, sealed: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let (key_id, size, ciphertext) =
+            parse_header(sealed).with_context(|| format!("object {} is not encrypted", key))?;
+        let encryption_key = self.keys.get(key_id).with_context(|| {
+            format!("object {} is encrypted with unknown key '{}'", key, key_id)
+        })?;
+        let data = encryption::decrypt(encryption_key, ciphertext)
+            .with_context(|| format!("failed to decrypt object {}", key))?;
+        if data.len() as u64 != size {
+            anyhow::bail!(
+                "object {} decrypted to {} bytes, its header says {}",
+                key,
+                data.len(),
+                size
+            );
+        }
+        Ok(data)
+    }
+}
+
+fn validate_key_id(key_id: &str) -> anyhow::Result<()> {
+    if key_id.is_empty() {
+        anyhow::bail!("encryption key ID must not be empty");
+    }
+    if key_id.len() > MAX_KEY_ID_LEN {
+        anyhow::bail!(
+            "encryption key ID is {} bytes, at most {} allowed",
+            key_id.len(),
+            MAX_KEY_ID_LEN
+        );
+    }
+    Ok(())
+}
+
+/// Split a stored object, or the start of one, into its key ID, plaintext
+/// size and ciphertext
+fn parse_header(sealed: &[u8]) -> Option<(&str, u64, &[u8])> {
+    let rest = sealed.strip_prefix(MAGIC)?;
+    let (&len, rest) = rest.split_first()?;
+    if rest.len() < len as usize + 8 {
+        return None;
+    }
+    let (key_id, rest) = rest.split_at(len as usize);
+    let (size, ciphertext) = rest.split_at(8);
+    let size = u64::from_le_bytes(size.try_into().ok()?);
+    Some((std::str::from_utf8(key_id).ok()?, size, ciphertext))
+}
+
+#[async_trait]
+impl StorageBackend for EncryptedBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        let sealed = self.inner.get(key).await?;
+        self.open(key, &sealed)
+    }
+
+    /// Range reads and streaming need whole objects to decrypt, while copies
+    /// and conditional writes of ciphertext work as they are, so only those
+    /// and expiry carry over
+    fn capabilities(&self) -> BackendCapabilities {
+        let inner = self.inner.capabilities();
+        BackendCapabilities {
+            server_side_copy: inner.server_side_copy,
+            compare_and_swap: inner.compare_and_swap,
+            ttl: inner.ttl,
+            ..BackendCapabilities::default()
+        }
+    }
+
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<std::time::SystemTime>> {
+        self.inner.modified(key).await
+    }
+
+    /// Keeps the stored payload's etag, which
+    /// [`put_if_match`](StorageBackend::put_if_match) expects, and reports
+    /// the plaintext size from the header, read with a range request
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        let meta = self.inner.head(key).await?;
+        let header = self.inner.get_range(key, 0, MAX_HEADER_LEN as u64).await?;
+        let (_, size, _) =
+            parse_header(&header).with_context(|| format!("object {} is not encrypted", key))?;
+        Ok(ObjectMeta { size, ..meta })
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        let sealed = self.seal(data)?;
+        self.inner.put(key, &sealed).await
+    }
+
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        let sealed = self.seal(data)?;
+        self.inner.put_if_absent(key, &sealed).await
+    }
+
+    /// `etag` is the stored ciphertext's, as [`head`](StorageBackend::head)
+    /// reports it
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        let sealed = self.seal(data)?;
+        self.inner.put_if_match(key, &sealed, etag).await
+    }
+
+    /// Copies the ciphertext as it is
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        self.inner.copy(src_key, dst_key).await
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        self.inner.exists(key).await
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.inner.delete(key).await
+    }
+
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        self.inner.delete_many(keys).await
+    }
+
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        self.inner.list_objects(prefix).await
+    }
+
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        self.inner.list_objects_stream(prefix)
+    }
+
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        self.inner.list_prefixes(prefix, delimiter).await
+    }
+
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        self.inner.usage(prefix).await
+    }
+
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> anyhow::Result<bool> {
+        self.inner.apply_expiry(policy).await
+    }
+
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport> {
+        self.inner.sweep_expired(policy).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{CasBackend, MockBackend};
+
+    fn encrypted(bucket: &Arc<MockBackend>, key_id: &str, key: &EncryptionKey) -> EncryptedBackend {
+        EncryptedBackend::new(bucket.clone(), key_id, key.clone()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_payloads_are_encrypted_with_key_id_header() {
+        let bucket = Arc::new(MockBackend::new());
+        let key = EncryptionKey::generate().unwrap();
+        let storage = encrypted(&bucket, "k1", &key);
+
+        let data = vec![7u8; 200 * 1024];
+        storage.put("objects/a", &data).await.unwrap();
+
+        let stored = bucket.get("objects/a").await.unwrap();
+        assert_eq!(parse_header(&stored).unwrap().0, "k1");
+        assert!(!stored.windows(64).any(|w| w == &data[..64]));
+        assert_eq!(storage.get("objects/a").await.unwrap(), data);
+        assert_eq!(
+            storage.get_range("objects/a", 10, 5).await.unwrap(),
+            &data[10..15]
+        );
+        assert_eq!(storage.list_objects("").await.unwrap(), vec!["objects/a"]);
+    }
+
+    #[tokio::test]
+    async fn test_rotated_keys_stay_readable() {
+        let bucket = Arc::new(MockBackend::new());
+        let old = EncryptionKey::generate().unwrap();
+        let new = EncryptionKey::generate().unwrap();
+
+        encrypted(&bucket, "old", &old)
+            .put("objects/a", b"alpha")
+            .await
+            .unwrap();
+
+        let rotated = encrypted(&bucket, "new", &new);
+        let err = rotated.get("objects/a").await.unwrap_err();
+        assert!(err.to_string().contains("unknown key 'old'"), "{}", err);
+        // The size comes from the header, without decrypting
+        assert_eq!(rotated.head("objects/a").await.unwrap().size, 5);
+
+        let rotated = rotated.with_key("old", old).unwrap();
+        assert_eq!(rotated.get("objects/a").await.unwrap(), b"alpha");
+
+        rotated.put("objects/b", b"bravo").await.unwrap();
+        let stored = bucket.get("objects/b").await.unwrap();
+        assert_eq!(parse_header(&stored).unwrap().0, "new");
+        assert!(rotated.clone().with_key("new", new).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_plaintext_and_tampered_objects_are_rejected() {
+        let bucket = Arc::new(MockBackend::new());
+        let storage = encrypted(&bucket, "k1", &EncryptionKey::generate().unwrap());
+
+        bucket.put("objects/plain", b"not encrypted").await.unwrap();
+        let err = storage.get("objects/plain").await.unwrap_err();
+        assert!(err.to_string().contains("not encrypted"), "{}", err);
+
+        storage.put("objects/a", b"alpha").await.unwrap();
+        let mut stored = bucket.get("objects/a").await.unwrap();
+        *stored.last_mut().unwrap() ^= 1;
+        bucket.put("objects/a", &stored).await.unwrap();
+        assert!(storage.get("objects/a").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_conditional_replace_uses_head_etag() {
+        let bucket = Arc::new(CasBackend::default());
+        let storage =
+            EncryptedBackend::new(bucket.clone(), "k1", EncryptionKey::generate().unwrap())
+                .unwrap();
+        assert!(storage.capabilities().compare_and_swap);
+
+        storage.put("refs/heads/main", b"alpha").await.unwrap();
+        let meta = storage.head("refs/heads/main").await.unwrap();
+        assert_eq!(meta.size, 5);
+        let etag = meta.etag.unwrap();
+
+        assert!(storage
+            .put_if_match("refs/heads/main", b"bravo", &etag)
+            .await
+            .unwrap());
+        assert!(!storage
+            .put_if_match("refs/heads/main", b"charlie", &etag)
+            .await
+            .unwrap());
+        assert_eq!(storage.get("refs/heads/main").await.unwrap(), b"bravo");
+    }
+
+    #[test]
+    fn test_key_id_validation() {
+        let bucket: Arc<dyn StorageBackend> = Arc::new(MockBackend::new());
+        let key = EncryptionKey::generate().unwrap();
+        assert!(EncryptedBackend::new(bucket.clone(), "", key.clone()).is_err());
+        assert!(EncryptedBackend::new(bucket.clone(), "k".repeat(256), key.clone()).is_err());
+        assert!(EncryptedBackend::new(bucket, "k".repeat(255), key).is_ok());
+    }
+}
@@ -13,6 +13,7 @@
 
 //! Storage error types and utilities
 
+use std::fmt;
 use std::io;
 use thiserror::Error;
 
@@ -46,6 +47,32 @@ pub enum StorageError {
     #[error("operation timed out: {0}")]
     Timeout(String),
 
+    /// Fewer bytes were received than the object holds (interrupted
+    /// download, file truncated while being read)
+    #[error("truncated object: expected {expected} bytes, got {got}")]
+    Truncated { expected: u64, got: u64 },
+
+    /// Data read back doesn't match the checksum it was stored with
+    #[error("checksum mismatch for {key}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        key: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// A write would take the repository past its storage quota
+    #[error("storage quota exceeded: writing {requested} bytes with {used} of {limit} bytes used")]
+    QuotaExceeded {
+        limit: u64,
+        used: u64,
+        requested: u64,
+    },
+
+    /// Two keys differ only by case and would be stored in the same file on
+    /// a case-insensitive filesystem
+    #[error("key {key} collides with stored key {existing} on this case-insensitive filesystem")]
+    CaseCollision { key: String, existing: String },
+
     /// Transparent error delegation for wrapped error types
     ///
     /// This variant allows wrapping other error types (like anyhow::Error)
@@ -81,6 +108,42 @@ impl StorageError {
         StorageError::Timeout(msg.into())
     }
 
+    /// Create a Truncated error for a read of `got` out of `expected` bytes
+    pub fn truncated(expected: u64, got: u64) -> Self {
+        StorageError::Truncated { expected, got }
+    }
+
+    /// Create a ChecksumMismatch error for `key`
+    pub fn checksum_mismatch<K, E, A>(key: K, expected: E, actual: A) -> Self
+    where
+        K: Into<String>,
+        E: Into<String>,
+        A: Into<String>,
+    {
+        StorageError::ChecksumMismatch {
+            key: key.into(),
+            expected: expected.into(),
+            actual: actual.into(),
+        }
+    }
+
+    /// Create a QuotaExceeded error for a write of `requested` bytes
+    pub fn quota_exceeded(limit: u64, used: u64, requested: u64) -> Self {
+        StorageError::QuotaExceeded {
+            limit,
+            used,
+            requested,
+        }
+    }
+
+    /// Create a CaseCollision error for a write of `key` over `existing`
+    pub fn case_collision<K: Into<String>, E: Into<String>>(key: K, existing: E) -> Self {
+        StorageError::CaseCollision {
+            key: key.into(),
+            existing: existing.into(),
+        }
+    }
+
     /// Create a generic error from any error type that can convert to anyhow::Error
     pub fn other<E: Into<anyhow::Error>>(error: E) -> Self {
         StorageError::Other(error.into())
@@ -100,6 +163,42 @@ impl StorageError {
     pub fn is_invalid_key(&self) -> bool {
         matches!(self, StorageError::InvalidKey(_))
     }
+
+    /// Check if this is a Truncated error
+    pub fn is_truncated(&self) -> bool {
+        matches!(self, StorageError::Truncated { .. })
+    }
+
+    /// Check if this is a ChecksumMismatch error
+    pub fn is_checksum_mismatch(&self) -> bool {
+        matches!(self, StorageError::ChecksumMismatch { .. })
+    }
+
+    /// Check if this is a QuotaExceeded error
+    pub fn is_quota_exceeded(&self) -> bool {
+        matches!(self, StorageError::QuotaExceeded { .. })
+    }
+
+    /// Check if this is a CaseCollision error
+    pub fn is_case_collision(&self) -> bool {
+        matches!(self, StorageError::CaseCollision { .. })
+    }
+}
+
+/// A key [`delete_many`](crate::StorageBackend::delete_many) could not delete
+#[derive(Debug)]
+pub struct DeleteFailure {
+    /// The key, which may still be stored
+    pub key: String,
+
+    /// Why the delete failed
+    pub error: anyhow::Error,
+}
+
+impl fmt::Display for DeleteFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:#}", self.key, self.error)
+    }
 }
 
 #[cfg(test)]
@@ -125,6 +224,47 @@ mod tests {
         assert!(err.is_invalid_key());
     }
 
+    #[test]
+    fn test_truncated_error() {
+        let err = StorageError::truncated(1000, 600);
+        assert!(err.is_truncated());
+        assert_eq!(
+            err.to_string(),
+            "truncated object: expected 1000 bytes, got 600"
+        );
+    }
+
+    #[test]
+    fn test_checksum_mismatch_error() {
+        let err =
+            StorageError::checksum_mismatch("objects/ab", "crc32c:e3069283", "crc32c:00000000");
+        assert!(err.is_checksum_mismatch());
+        assert_eq!(
+            err.to_string(),
+            "checksum mismatch for objects/ab: expected crc32c:e3069283, got crc32c:00000000"
+        );
+    }
+
+    #[test]
+    fn test_quota_exceeded_error() {
+        let err = StorageError::quota_exceeded(1000, 900, 200);
+        assert!(err.is_quota_exceeded());
+        assert_eq!(
+            err.to_string(),
+            "storage quota exceeded: writing 200 bytes with 900 of 1000 bytes used"
+        );
+    }
+
+    #[test]
+    fn test_case_collision_error() {
+        let err = StorageError::case_collision("textures/Hero.png", "textures/hero.png");
+        assert!(err.is_case_collision());
+        assert_eq!(
+            err.to_string(),
+            "key textures/Hero.png collides with stored key textures/hero.png on this case-insensitive filesystem"
+        );
+    }
+
     #[test]
     fn test_io_error_conversion() {
         let io_err = io::Error::other("read failed");
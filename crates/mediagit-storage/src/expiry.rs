@@ -0,0 +1,275 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Expiry of temporary and orphaned objects
+//!
+//! Some of what a backend holds is only meant to live for a while: scratch
+//! objects written under [`TEMP_PREFIX`], multipart uploads abandoned half
+//! way, temporary files left by an interrupted write. An [`ExpiryPolicy`]
+//! gives each a time to live.
+//!
+//! - [`apply_expiry`](crate::StorageBackend::apply_expiry) installs the
+//!   policy as native lifecycle rules where the service has them (S3, MinIO
+//!   and B2/Spaces), so expired objects go away without a client running.
+//! - [`sweep_expired`](crate::StorageBackend::sweep_expired) deletes what has
+//!   outlived the policy, judging age by when each object was last written.
+//! - [`spawn_sweeper`] sweeps periodically, for backends without native
+//!   rules such as Local.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mediagit_storage::expiry::{ExpiryPolicy, TEMP_PREFIX};
+//! use mediagit_storage::{LocalBackend, StorageBackend};
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+//! let storage = LocalBackend::new("/tmp/mediagit").await?;
+//!
+//! let policy = ExpiryPolicy::new()
+//!     .expire(TEMP_PREFIX, DAY)
+//!     .abort_incomplete_uploads_after(DAY);
+//! let report = storage.sweep_expired(&policy).await?;
+//! println!("{} expired objects removed", report.expired.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{DeleteFailure, ObjectMeta, StorageBackend};
+use futures::TryStreamExt;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+/// Prefix for objects that are only needed for a while, such as chunks
+/// staged before the manifest that references them is written
+pub const TEMP_PREFIX: &str = "tmp/";
+
+/// Objects under `prefix` expire `ttl` after they were last written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpiryRule {
+    /// Key prefix the rule covers
+    pub prefix: String,
+
+    /// How long an object may live after it was last written
+    pub ttl: Duration,
+}
+
+/// Time to live for temporary objects and abandoned uploads
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExpiryPolicy {
+    /// Prefix rules; where several cover a key, the longest prefix wins
+    pub rules: Vec<ExpiryRule>,
+
+    /// How long an upload may stay unfinished before its parts or temporary
+    /// files are removed (`None` keeps them)
+    pub incomplete_uploads: Option<Duration>,
+
+    /// Key prefix the policy is confined to, set by
+    /// [`NamespacedBackend`](crate::NamespacedBackend) to its namespace.
+    /// Lifecycle rules are named after it, so repositories sharing a bucket
+    /// replace only their own rules, and only uploads under it are aborted.
+    pub scope: String,
+}
+
+impl ExpiryPolicy {
+    /// A policy that expires nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expire objects under `prefix` once they are `ttl` old
+    pub fn expire(mut self, prefix: impl Into<String>, ttl: Duration) -> Self {
+        self.rules.push(ExpiryRule {
+            prefix: prefix.into(),
+            ttl,
+        });
+        self
+    }
+
+    /// Remove uploads left unfinished for `ttl`
+    pub fn abort_incomplete_uploads_after(mut self, ttl: Duration) -> Self {
+        self.incomplete_uploads = Some(ttl);
+        self
+    }
+
+    /// Whether the policy expires nothing
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.incomplete_uploads.is_none()
+    }
+
+    /// The rule governing `key`: the one with the longest matching prefix
+    pub fn rule_for(&self, key: &str) -> Option<&ExpiryRule> {
+        self.rules
+            .iter()
+            .filter(|rule| key.starts_with(&rule.prefix))
+            .fold(None, |best: Option<&ExpiryRule>, rule| match best {
+                Some(best) if best.prefix.len() >= rule.prefix.len() => Some(best),
+                _ => Some(rule),
+            })
+    }
+}
+
+/// What a [`sweep_expired`](crate::StorageBackend::sweep_expired) removed
+#[derive(Debug, Default)]
+pub struct SweepReport {
+    /// Keys deleted for outliving their rule
+    pub expired: Vec<String>,
+
+    /// Abandoned uploads aborted and temporary files removed
+    pub incomplete_uploads: usize,
+
+    /// Expired keys that could not be deleted
+    pub failures: Vec<DeleteFailure>,
+}
+
+impl SweepReport {
+    /// Whether nothing was removed or failed
+    pub fn is_empty(&self) -> bool {
+        self.expired.is_empty() && self.incomplete_uploads == 0 && self.failures.is_empty()
+    }
+}
+
+/// Whether something written at `written` has outlived `ttl` at `now`
+pub fn is_expired(written: SystemTime, ttl: Duration, now: SystemTime) -> bool {
+    now.duration_since(written).is_ok_and(|age| age >= ttl)
+}
+
+/// Delete the keys under `policy`'s rules that have outlived them
+///
+/// Ages come from the modification time [`head`](crate::StorageBackend::head)
+/// reports; keys whose age is unknown are kept. This is the default
+/// [`sweep_expired`](crate::StorageBackend::sweep_expired), which backends
+/// run before removing their own kind of abandoned uploads.
+pub async fn sweep_rules<B>(storage: &B, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport>
+where
+    B: StorageBackend + ?Sized,
+{
+    let now = SystemTime::now();
+    let mut expired = Vec::new();
+    for rule in &policy.rules {
+        let mut keys = storage.list_objects_stream(&rule.prefix);
+        while let Some(key) = keys.try_next().await? {
+            // A longer prefix may give the key another TTL
+            if !policy.rule_for(&key).is_some_and(|r| std::ptr::eq(r, rule)) {
+                continue;
+            }
+            let Ok(ObjectMeta {
+                modified: Some(written),
+                ..
+            }) = storage.head(&key).await
+            else {
+                continue;
+            };
+            if is_expired(written, rule.ttl, now) {
+                expired.push(key);
+            }
+        }
+    }
+
+    let failures = if expired.is_empty() {
+        Vec::new()
+    } else {
+        storage.delete_many(&expired).await?
+    };
+    let failed: HashSet<&str> = failures.iter().map(|f| f.key.as_str()).collect();
+    let expired = expired
+        .iter()
+        .filter(|key| !failed.contains(key.as_str()))
+        .cloned()
+        .collect();
+    Ok(SweepReport {
+        expired,
+        incomplete_uploads: 0,
+        failures,
+    })
+}
+
+/// Sweep `storage` under `policy` every `every` until the task is aborted
+///
+/// Each pass is logged; a failed pass is logged and retried on the next
+/// tick.
+pub fn spawn_sweeper(
+    storage: Arc<dyn StorageBackend>,
+    policy: ExpiryPolicy,
+    every: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every.max(Duration::from_secs(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match storage.sweep_expired(&policy).await {
+                Ok(report) if report.is_empty() => {}
+                Ok(report) => tracing::info!(
+                    expired = report.expired.len(),
+                    incomplete_uploads = report.incomplete_uploads,
+                    failures = report.failures.len(),
+                    "Swept expired objects"
+                ),
+                Err(e) => tracing::warn!("Expiry sweep failed: {:#}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LocalBackend;
+    use tempfile::TempDir;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let policy = ExpiryPolicy::new()
+            .expire("tmp/", DAY)
+            .expire("tmp/staged/", DAY * 7);
+        assert_eq!(policy.rule_for("tmp/a").unwrap().ttl, DAY);
+        assert_eq!(policy.rule_for("tmp/staged/a").unwrap().ttl, DAY * 7);
+        assert!(policy.rule_for("objects/a").is_none());
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let now = SystemTime::now();
+        assert!(is_expired(now - DAY * 2, DAY, now));
+        assert!(!is_expired(now - DAY / 2, DAY, now));
+        // Written "in the future" by a skewed clock
+        assert!(!is_expired(now + DAY, DAY, now));
+    }
+
+    #[tokio::test]
+    async fn test_sweep_only_touches_expired_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = LocalBackend::new(temp_dir.path()).await.unwrap();
+        storage.put("tmp/scratch", b"1").await.unwrap();
+        storage.put("tmp/staged/chunk", b"2").await.unwrap();
+        storage.put("objects/abc", b"3").await.unwrap();
+
+        let policy = ExpiryPolicy::new()
+            .expire("tmp/", Duration::ZERO)
+            .expire("tmp/staged/", DAY);
+        let report = storage.sweep_expired(&policy).await.unwrap();
+
+        assert_eq!(report.expired, ["tmp/scratch"]);
+        assert!(report.failures.is_empty());
+        assert!(!storage.exists("tmp/scratch").await.unwrap());
+        assert!(storage.exists("tmp/staged/chunk").await.unwrap());
+        assert!(storage.exists("objects/abc").await.unwrap());
+    }
+}
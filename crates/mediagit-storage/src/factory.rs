@@ -0,0 +1,925 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Backends built from configuration or URIs
+//!
+//! [`BackendFactory`] is the one place that turns a `[storage]` section or a
+//! URI such as `s3://bucket/repos/game?region=eu-west-1` into a
+//! [`StorageBackend`]. It applies everything the configuration says about
+//! the backend itself (key prefix, proxy, timeouts, TLS, storage classes,
+//! encryption, replication and routing), while wrappers that depend on the
+//! repository, such as caching and quotas, are left to the caller.
+//!
+//! Built-in URI schemes map onto the matching storage configuration, with
+//! query parameters filling in its remaining keys:
+//!
+//! | URI | Backend |
+//! |-----|---------|
+//! | `file:///srv/media` | Local filesystem |
+//! | `s3://bucket/prefix?region=us-east-1&endpoint=http://minio:9000` | S3 or S3-compatible |
+//! | `gs://bucket/prefix?project_id=studio` | Google Cloud Storage |
+//! | `az://container/prefix?account_name=studio` | Azure Blob Storage |
+//! | `sftp://user@host:22/srv/media?private_key_path=...` | SFTP |
+//! | `memory://` | In-memory, for tests |
+//!
+//! Other crates add schemes of their own by registering a
+//! [`BackendProvider`], which replaces the built-in handling of a scheme
+//! with the same name.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::factory::BackendFactory;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let factory = BackendFactory::new();
+//! let storage = factory.open_uri("memory://").await?;
+//! storage.put("abc123", b"data").await?;
+//!
+//! let uri = "s3://studio-media/repos/game?region=eu-west-1".parse()?;
+//! let config = BackendFactory::storage_config(&uri)?;
+//! assert_eq!(config.backend_name(), "s3");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::mock::MockBackend;
+use crate::sse::{CustomerKey, ServerSideEncryption};
+use crate::{
+    LocalBackend, NamespacedBackend, ProxySettings, ReplicatedBackend, RoutingBackend,
+    StorageBackend, StorageClassPolicy, TimeoutSettings, TlsSettings, UploadLimits,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use mediagit_config::{
+    Config, ConfigLoader, FileSystemStorage, MultiBackendStorage, ReplicaWritePolicy, S3Storage,
+    StorageConfig,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// URI schemes the factory handles itself
+pub const BUILTIN_SCHEMES: &[&str] = &["file", "s3", "gs", "az", "sftp", "memory"];
+
+/// A storage URI split into its parts
+///
+/// `scheme://authority/path?key=value&...`, where the authority is the
+/// bucket, container or host. Values are taken literally; there is no
+/// percent-decoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendUri {
+    /// Scheme, lowercased
+    pub scheme: String,
+
+    /// Everything between `://` and the next `/` or `?`
+    pub authority: String,
+
+    /// The path, with its leading `/` (empty if none)
+    pub path: String,
+
+    /// Query parameters
+    pub params: BTreeMap<String, String>,
+}
+
+impl BackendUri {
+    /// The path without its leading `/`, as a key prefix
+    pub fn prefix(&self) -> &str {
+        self.path.trim_start_matches('/')
+    }
+}
+
+impl FromStr for BackendUri {
+    type Err = anyhow::Error;
+
+    fn from_str(uri: &str) -> Result<Self> {
+        let (scheme, rest) = uri
+            .split_once("://")
+            .with_context(|| format!("'{}' is not a storage URI (expected scheme://...)", uri))?;
+        anyhow::ensure!(
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')),
+            "invalid scheme in storage URI '{}'",
+            uri
+        );
+
+        let (location, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (authority, path) = match location.find('/') {
+            Some(slash) => location.split_at(slash),
+            None => (location, ""),
+        };
+
+        let mut params = BTreeMap::new();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            params.insert(key.to_string(), value.to_string());
+        }
+
+        Ok(BackendUri {
+            scheme: scheme.to_ascii_lowercase(),
+            authority: authority.to_string(),
+            path: path.to_string(),
+            params,
+        })
+    }
+}
+
+/// Builds backends for a URI scheme the factory doesn't know
+#[async_trait]
+pub trait BackendProvider: Send + Sync {
+    /// Open the backend `uri` names
+    ///
+    /// `factory` carries the caller's base directory, proxy and timeouts,
+    /// and can open other backends for providers that wrap them.
+    async fn open(
+        &self,
+        uri: &BackendUri,
+        factory: &BackendFactory,
+    ) -> Result<Arc<dyn StorageBackend>>;
+}
+
+/// Builds storage backends from configuration sections and URIs
+#[derive(Clone, Default)]
+pub struct BackendFactory {
+    base_dir: PathBuf,
+    proxy: ProxySettings,
+    timeouts: TimeoutSettings,
+    env_overrides: bool,
+    providers: HashMap<String, Arc<dyn BackendProvider>>,
+}
+
+impl fmt::Debug for BackendFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut schemes: Vec<&str> = self.providers.keys().map(String::as_str).collect();
+        schemes.sort();
+        f.debug_struct("BackendFactory")
+            .field("base_dir", &self.base_dir)
+            .field("proxy", &self.proxy)
+            .field("timeouts", &self.timeouts)
+            .field("env_overrides", &self.env_overrides)
+            .field("providers", &schemes)
+            .finish()
+    }
+}
+
+impl BackendFactory {
+    /// Factory with default proxy and timeout settings, resolving relative
+    /// filesystem paths against the current directory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Factory for the repository at `repo_root`, with the proxy and
+    /// timeouts from its `config`
+    pub fn for_repo(config: &Config, repo_root: impl Into<PathBuf>) -> Self {
+        Self::new()
+            .with_base_dir(repo_root)
+            .with_proxy(proxy_settings(config))
+            .with_timeouts(timeout_settings(config))
+    }
+
+    /// Resolve relative filesystem paths against `dir`, the repository root
+    ///
+    /// The default `./data` path resolves to `dir/.mediagit`, and GCS keeps
+    /// its resumable upload sessions under `dir/.mediagit/gcs-uploads`.
+    pub fn with_base_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.base_dir = dir.into();
+        self
+    }
+
+    /// Route cloud backends' requests through `proxy`
+    pub fn with_proxy(mut self, proxy: ProxySettings) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Set the cloud backends' connect and read timeouts
+    pub fn with_timeouts(mut self, timeouts: TimeoutSettings) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Apply the `MEDIAGIT_S3_*` environment overrides to each storage
+    /// section before opening it
+    pub fn with_env_overrides(mut self, enabled: bool) -> Self {
+        self.env_overrides = enabled;
+        self
+    }
+
+    /// Open URIs with `scheme` through `provider`
+    pub fn with_provider(
+        mut self,
+        scheme: impl AsRef<str>,
+        provider: Arc<dyn BackendProvider>,
+    ) -> Self {
+        self.providers
+            .insert(scheme.as_ref().to_ascii_lowercase(), provider);
+        self
+    }
+
+    /// Every scheme [`open_uri`](Self::open_uri) accepts, sorted
+    pub fn schemes(&self) -> Vec<&str> {
+        let mut schemes: Vec<&str> = BUILTIN_SCHEMES
+            .iter()
+            .copied()
+            .chain(self.providers.keys().map(String::as_str))
+            .collect();
+        schemes.sort();
+        schemes.dedup();
+        schemes
+    }
+
+    /// Open the backend `uri` names
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URI doesn't parse, its scheme is unknown, or
+    /// the backend can't be opened.
+    pub async fn open_uri(&self, uri: &str) -> Result<Arc<dyn StorageBackend>> {
+        let uri: BackendUri = uri.parse()?;
+        if let Some(provider) = self.providers.get(&uri.scheme) {
+            return provider.open(&uri, self).await;
+        }
+        if uri.scheme == "memory" {
+            return Ok(Arc::new(MockBackend::new()));
+        }
+        self.open_config(&Self::storage_config(&uri)?).await
+    }
+
+    /// The storage configuration a built-in URI stands for
+    ///
+    /// Query parameters set configuration keys by name; `true`, `false` and
+    /// whole numbers are passed as such, everything else as strings.
+    pub fn storage_config(uri: &BackendUri) -> Result<StorageConfig> {
+        let mut section = serde_json::Map::new();
+        let mut set = |key: &str, value: serde_json::Value| {
+            section.insert(key.to_string(), value);
+        };
+        match uri.scheme.as_str() {
+            "file" => {
+                set("backend", "filesystem".into());
+                set("base_path", format!("{}{}", uri.authority, uri.path).into());
+            }
+            "s3" => {
+                set("backend", "s3".into());
+                set("bucket", uri.authority.as_str().into());
+                set("region", "us-east-1".into());
+                set("prefix", uri.prefix().into());
+            }
+            "gs" => {
+                set("backend", "gcs".into());
+                set("bucket", uri.authority.as_str().into());
+                set("prefix", uri.prefix().into());
+            }
+            "az" => {
+                set("backend", "azure".into());
+                set("container", uri.authority.as_str().into());
+                set("prefix", uri.prefix().into());
+            }
+            "sftp" => {
+                let (username, host) = uri
+                    .authority
+                    .split_once('@')
+                    .context("sftp URIs need a user name: sftp://user@host/path")?;
+                set("backend", "sftp".into());
+                set("username", username.into());
+                match host.rsplit_once(':') {
+                    Some((host, port)) => {
+                        let port: u16 = port
+                            .parse()
+                            .with_context(|| format!("invalid port in sftp URI: {}", port))?;
+                        set("host", host.into());
+                        set("port", port.into());
+                    }
+                    None => set("host", host.into()),
+                }
+                set("base_path", uri.path.as_str().into());
+            }
+            "memory" => anyhow::bail!("memory:// storage has no configuration section"),
+            scheme => anyhow::bail!(
+                "unknown storage scheme '{}' (expected one of: {})",
+                scheme,
+                BUILTIN_SCHEMES.join(", ")
+            ),
+        }
+
+        for (key, value) in &uri.params {
+            let value = match value.as_str() {
+                "true" => true.into(),
+                "false" => false.into(),
+                _ => match value.parse::<u64>() {
+                    Ok(number) => number.into(),
+                    Err(_) => value.as_str().into(),
+                },
+            };
+            set(key, value);
+        }
+        // Query parameters may hold credentials, so the URI isn't repeated
+        serde_json::from_value(serde_json::Value::Object(section))
+            .with_context(|| format!("invalid {}:// storage URI", uri.scheme))
+    }
+
+    /// Open the backend a storage configuration section describes
+    pub async fn open_config(&self, storage: &StorageConfig) -> Result<Arc<dyn StorageBackend>> {
+        let mut storage = storage.clone();
+        if self.env_overrides {
+            ConfigLoader::new().apply_storage_env_overrides(&mut storage)?;
+        }
+        let storage: Arc<dyn StorageBackend> = match &storage {
+            StorageConfig::FileSystem(fs_config) => {
+                Arc::new(self.open_filesystem(fs_config).await?)
+            }
+            StorageConfig::S3(s3_config) => {
+                let endpoint = match &s3_config.endpoint {
+                    // S3-compatible (MinIO, DigitalOcean Spaces, etc.)
+                    Some(endpoint) => endpoint.clone(),
+                    None => format!("https://s3.{}.amazonaws.com", s3_config.region),
+                };
+                let storage = self
+                    .s3_compatible_backend(s3_config, &endpoint)
+                    .await
+                    .context("Failed to initialize S3 storage backend")?;
+                with_key_prefix(Arc::new(storage), &s3_config.prefix)
+            }
+            StorageConfig::Azure(azure_config) => {
+                self.check_env_only_proxy("Azure")?;
+                self.open_azure(azure_config).await?
+            }
+            StorageConfig::GCS(gcs_config) => {
+                self.check_env_only_proxy("GCS")?;
+                self.open_gcs(gcs_config).await?
+            }
+            StorageConfig::Sftp(sftp_config) => self.open_sftp(sftp_config).await?,
+            StorageConfig::Multi(multi) => {
+                let storage = self.replicated_backend(multi).await?;
+                self.routed_backend(multi, storage).await?
+            }
+        };
+        Ok(storage)
+    }
+
+    /// Refuse explicit proxy settings for a backend whose HTTP client only
+    /// reads the proxy environment variables, rather than connecting around
+    /// the configured proxy
+    fn check_env_only_proxy(&self, backend: &str) -> Result<()> {
+        if self.proxy != ProxySettings::default() {
+            anyhow::bail!(
+                "The [proxy] section does not apply to {} storage, which only honors the \
+                 HTTPS_PROXY and NO_PROXY environment variables; set those instead",
+                backend
+            );
+        }
+        Ok(())
+    }
+
+    /// Open the local store `fs_config` describes
+    pub async fn open_filesystem(&self, fs_config: &FileSystemStorage) -> Result<LocalBackend> {
+        let storage_path = if Path::new(&fs_config.base_path).is_absolute() {
+            PathBuf::from(&fs_config.base_path)
+        } else if fs_config.base_path == "./data" {
+            // Default config value - use .mediagit
+            self.base_dir.join(".mediagit")
+        } else {
+            self.base_dir.join(&fs_config.base_path)
+        };
+        let mut storage = LocalBackend::new(&storage_path)
+            .await
+            .context("Failed to initialize filesystem storage backend")?;
+        if let Some(alternates) = &fs_config.alternates {
+            storage = storage.with_alternates(self.base_dir.join(alternates));
+        }
+        Ok(storage)
+    }
+
+    /// S3-compatible backend for `bucket` at `endpoint`, with the proxy,
+    /// timeout, signing region, TLS, storage class and encryption settings
+    async fn s3_compatible_backend(
+        &self,
+        s3_config: &S3Storage,
+        endpoint: &str,
+    ) -> Result<crate::MinIOBackend> {
+        let minio_config = crate::minio::MinIOConfig {
+            proxy: self.proxy.clone(),
+            timeouts: self.timeouts,
+            signing_region: s3_config.signing_region().to_string(),
+            tls: TlsSettings {
+                ca_bundle: s3_config.ca_bundle.as_ref().map(PathBuf::from),
+                insecure_skip_verify: s3_config.insecure_skip_verify,
+            },
+            storage_class: storage_class_policy(s3_config)?,
+            encryption: server_side_encryption(s3_config)?,
+            ..crate::minio::MinIOConfig::new(
+                endpoint,
+                &s3_config.bucket,
+                s3_config.access_key_id.as_deref().unwrap_or(""),
+                s3_config.secret_access_key.as_deref().unwrap_or(""),
+            )?
+        };
+        crate::MinIOBackend::with_config(minio_config).await
+    }
+
+    #[cfg(feature = "azure")]
+    async fn open_azure(
+        &self,
+        azure_config: &mediagit_config::AzureStorage,
+    ) -> Result<Arc<dyn StorageBackend>> {
+        use crate::azure::BlockUploadSettings;
+
+        let storage = if let Some(conn_str) = &azure_config.connection_string {
+            crate::AzureBackend::with_connection_string(&azure_config.container, conn_str)
+                .await
+                .context("Failed to initialize Azure storage backend")?
+        } else if let Some(account_key) = &azure_config.account_key {
+            crate::AzureBackend::with_account_key(
+                &azure_config.account_name,
+                &azure_config.container,
+                account_key,
+            )
+            .await
+            .context("Failed to initialize Azure storage backend")?
+        } else {
+            anyhow::bail!("Azure backend requires either connection_string or account_key");
+        };
+
+        let defaults = BlockUploadSettings::default();
+        let storage = storage.with_block_uploads(BlockUploadSettings {
+            threshold: azure_config
+                .block_upload_threshold
+                .unwrap_or(defaults.threshold),
+            max_concurrent_blocks: azure_config
+                .max_concurrent_blocks
+                .unwrap_or(defaults.max_concurrent_blocks),
+            ..defaults
+        });
+        Ok(with_key_prefix(Arc::new(storage), &azure_config.prefix))
+    }
+
+    #[cfg(not(feature = "azure"))]
+    async fn open_azure(
+        &self,
+        _azure_config: &mediagit_config::AzureStorage,
+    ) -> Result<Arc<dyn StorageBackend>> {
+        anyhow::bail!("Azure storage needs mediagit-storage built with the \"azure\" feature")
+    }
+
+    #[cfg(feature = "gcs")]
+    async fn open_gcs(
+        &self,
+        gcs_config: &mediagit_config::GCSStorage,
+    ) -> Result<Arc<dyn StorageBackend>> {
+        let storage = match gcs_config.credentials_path.as_deref().unwrap_or("") {
+            "" => {
+                crate::GcsBackend::with_default_credentials(
+                    &gcs_config.project_id,
+                    &gcs_config.bucket,
+                )
+                .await
+            }
+            credentials_path => {
+                crate::GcsBackend::new(&gcs_config.project_id, &gcs_config.bucket, credentials_path)
+                    .await
+            }
+        }
+        .context("Failed to initialize GCS storage backend")?;
+        // Keep resumable upload sessions so an interrupted push resumes
+        let storage = storage.with_session_dir(self.base_dir.join(".mediagit").join("gcs-uploads"));
+        Ok(with_key_prefix(Arc::new(storage), &gcs_config.prefix))
+    }
+
+    #[cfg(not(feature = "gcs"))]
+    async fn open_gcs(
+        &self,
+        _gcs_config: &mediagit_config::GCSStorage,
+    ) -> Result<Arc<dyn StorageBackend>> {
+        anyhow::bail!("GCS storage needs mediagit-storage built with the \"gcs\" feature")
+    }
+
+    #[cfg(feature = "sftp")]
+    async fn open_sftp(
+        &self,
+        sftp_config: &mediagit_config::SftpStorage,
+    ) -> Result<Arc<dyn StorageBackend>> {
+        use crate::sftp::{HostKeyPolicy, SftpAuth, SftpConfig};
+        use mediagit_config::HostKeyChecking;
+
+        let auth = match (&sftp_config.password, &sftp_config.private_key_path) {
+            (Some(password), _) => SftpAuth::Password(password.clone()),
+            (None, Some(path)) => SftpAuth::Key {
+                path: PathBuf::from(path),
+                passphrase: sftp_config.private_key_passphrase.clone(),
+            },
+            (None, None) => {
+                anyhow::bail!("SFTP backend requires either password or private_key_path")
+            }
+        };
+        let known_hosts = sftp_config.known_hosts_path.as_ref().map(PathBuf::from);
+        let host_key = match sftp_config.host_key_checking {
+            HostKeyChecking::Strict => HostKeyPolicy::Strict { known_hosts },
+            HostKeyChecking::AcceptNew => HostKeyPolicy::AcceptNew { known_hosts },
+            HostKeyChecking::Fingerprint => HostKeyPolicy::Fingerprint(
+                sftp_config
+                    .host_key_fingerprint
+                    .clone()
+                    .context("host_key_checking = \"fingerprint\" requires host_key_fingerprint")?,
+            ),
+            HostKeyChecking::Off => HostKeyPolicy::Insecure,
+        };
+
+        let sftp = SftpConfig {
+            port: sftp_config.port,
+            host_key,
+            connections: sftp_config.max_connections,
+            timeouts: self.timeouts,
+            ..SftpConfig::new(
+                &sftp_config.host,
+                &sftp_config.username,
+                &sftp_config.base_path,
+                auth,
+            )?
+        };
+        let storage = crate::SftpBackend::new(sftp)
+            .await
+            .context("Failed to initialize SFTP storage backend")?;
+        Ok(Arc::new(storage))
+    }
+
+    #[cfg(not(feature = "sftp"))]
+    async fn open_sftp(
+        &self,
+        _sftp_config: &mediagit_config::SftpStorage,
+    ) -> Result<Arc<dyn StorageBackend>> {
+        anyhow::bail!("SFTP storage needs mediagit-storage built with the \"sftp\" feature")
+    }
+
+    /// Replicate across the backends of a multi-backend configuration,
+    /// reading from the primary first
+    async fn replicated_backend(
+        &self,
+        multi: &MultiBackendStorage,
+    ) -> Result<Arc<dyn StorageBackend>> {
+        let mut replicas = Vec::new();
+        for name in multi.backend_names() {
+            replicas.push(self.open_named(multi, name).await?);
+        }
+
+        let policy = match multi.write_policy {
+            ReplicaWritePolicy::All => crate::replicated::WritePolicy::All,
+            ReplicaWritePolicy::Majority => {
+                crate::replicated::WritePolicy::majority(replicas.len())
+            }
+            ReplicaWritePolicy::BestEffort => crate::replicated::WritePolicy::BestEffort,
+        };
+        Ok(Arc::new(ReplicatedBackend::new(replicas, policy)?))
+    }
+
+    /// Send keys under the routes of a multi-backend configuration to their
+    /// own backends, and every other key to `default`
+    async fn routed_backend(
+        &self,
+        multi: &MultiBackendStorage,
+        default: Arc<dyn StorageBackend>,
+    ) -> Result<Arc<dyn StorageBackend>> {
+        if multi.routes.is_empty() {
+            return Ok(default);
+        }
+
+        // Routes naming the same backend share one connection to it
+        let mut backends = HashMap::new();
+        for name in multi.routed_backend_names() {
+            backends.insert(name, self.open_named(multi, name).await?);
+        }
+
+        let mut storage = RoutingBackend::new(default);
+        for (prefix, name) in &multi.routes {
+            storage = storage.with_route(prefix, backends[name.as_str()].clone());
+        }
+        Ok(Arc::new(storage))
+    }
+
+    /// Open the backend called `name` in a multi-backend configuration
+    async fn open_named(
+        &self,
+        multi: &MultiBackendStorage,
+        name: &str,
+    ) -> Result<Arc<dyn StorageBackend>> {
+        let storage = multi.backend(name)?;
+        Box::pin(self.open_config(&storage))
+            .await
+            .with_context(|| format!("Failed to initialize storage backend '{}'", name))
+    }
+}
+
+/// Proxy settings from the `[proxy]` config section
+///
+/// Environment variables are merged in later by the consumers, so an empty
+/// section still honors `HTTPS_PROXY` / `NO_PROXY`.
+pub fn proxy_settings(config: &Config) -> ProxySettings {
+    let proxy = &config.proxy;
+    ProxySettings {
+        url: proxy.url.clone(),
+        username: proxy.username.clone(),
+        password: proxy.password.clone(),
+        no_proxy: Vec::new(),
+    }
+    .with_no_proxy(proxy.no_proxy.iter().cloned())
+}
+
+/// Cloud backend timeouts from the `[performance.timeouts]` config section
+pub fn timeout_settings(config: &Config) -> TimeoutSettings {
+    let timeouts = &config.performance.timeouts;
+    TimeoutSettings::new(
+        std::time::Duration::from_secs(timeouts.connection),
+        std::time::Duration::from_secs(timeouts.read),
+    )
+}
+
+/// Upload pool limits from the `[performance.uploads]` config section
+pub fn upload_limits(config: &Config) -> UploadLimits {
+    let uploads = &config.performance.uploads;
+    UploadLimits::new(uploads.max_concurrent, uploads.max_in_flight_bytes)
+}
+
+/// Storage classes from the `[storage]` section: `pack_storage_class` for
+/// the pack files `gc` writes, `storage_class` for everything else
+///
+/// The backend sees keys with the repository's `prefix` in front, so the
+/// pack rule includes it.
+fn storage_class_policy(s3_config: &S3Storage) -> Result<StorageClassPolicy> {
+    let mut policy = StorageClassPolicy::new();
+    if let Some(class) = &s3_config.storage_class {
+        policy = policy.with_default(class.parse().context("Invalid storage.storage_class")?);
+    }
+    if let Some(class) = &s3_config.pack_storage_class {
+        let packs = format!(
+            "{}packs/",
+            crate::namespace::normalize_prefix(&s3_config.prefix)
+        );
+        policy = policy.with_rule(
+            packs,
+            class
+                .parse()
+                .context("Invalid storage.pack_storage_class")?,
+        );
+    }
+    Ok(policy)
+}
+
+/// Server-side encryption from `encryption` and `encryption_algorithm` in
+/// the `[storage]` section, or None if it's off
+fn server_side_encryption(s3_config: &S3Storage) -> Result<Option<ServerSideEncryption>> {
+    if !s3_config.encryption {
+        return Ok(None);
+    }
+    let algorithm = s3_config.encryption_algorithm.as_str();
+    let encryption = match algorithm {
+        "AES256" => ServerSideEncryption::S3,
+        "aws:kms" => ServerSideEncryption::Kms { key_id: None },
+        "SSE-C" => {
+            let key = s3_config
+                .sse_customer_key
+                .as_deref()
+                .context("SSE-C needs storage.sse_customer_key or MEDIAGIT_S3_SSE_CUSTOMER_KEY")?;
+            ServerSideEncryption::Customer(CustomerKey::from_base64(key)?)
+        }
+        _ => match algorithm.strip_prefix("aws:kms:") {
+            Some(key_id) => ServerSideEncryption::kms(key_id),
+            None => anyhow::bail!("Unsupported storage.encryption_algorithm: {}", algorithm),
+        },
+    };
+    Ok(Some(encryption))
+}
+
+/// Confine a cloud backend to the configured key prefix, if any
+fn with_key_prefix(storage: Arc<dyn StorageBackend>, prefix: &str) -> Arc<dyn StorageBackend> {
+    if crate::namespace::normalize_prefix(prefix).is_empty() {
+        storage
+    } else {
+        Arc::new(NamespacedBackend::new(storage, prefix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn s3_storage(section: serde_json::Value) -> S3Storage {
+        match serde_json::from_value(section).unwrap() {
+            StorageConfig::S3(s3) => s3,
+            other => panic!("expected S3 storage, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_uri() {
+        let uri: BackendUri = "S3://media/repos/game?region=eu-west-1&encryption=true"
+            .parse()
+            .unwrap();
+        assert_eq!(uri.scheme, "s3");
+        assert_eq!(uri.authority, "media");
+        assert_eq!(uri.prefix(), "repos/game");
+        assert_eq!(uri.params["region"], "eu-west-1");
+
+        let uri: BackendUri = "file:///srv/media".parse().unwrap();
+        assert_eq!(
+            (uri.authority.as_str(), uri.path.as_str()),
+            ("", "/srv/media")
+        );
+
+        assert!("/srv/media".parse::<BackendUri>().is_err());
+        assert!("://media".parse::<BackendUri>().is_err());
+    }
+
+    #[test]
+    fn test_storage_config_from_uri() {
+        let config = |uri: &str| BackendFactory::storage_config(&uri.parse().unwrap());
+
+        let s3 = match config(
+            "s3://media/repos/game?endpoint=http://minio:9000&encryption=true&max_concurrent_ops=8",
+        )
+        .unwrap()
+        {
+            StorageConfig::S3(s3) => s3,
+            other => panic!("expected S3 storage, got {:?}", other),
+        };
+        assert_eq!(s3.bucket, "media");
+        assert_eq!(s3.region, "us-east-1");
+        assert_eq!(s3.prefix, "repos/game");
+        assert_eq!(s3.endpoint.as_deref(), Some("http://minio:9000"));
+        assert!(s3.encryption);
+        assert_eq!(s3.max_concurrent_ops, Some(8));
+
+        match config("sftp://backup@nas.lan:2222/srv/media").unwrap() {
+            StorageConfig::Sftp(sftp) => {
+                assert_eq!(sftp.username, "backup");
+                assert_eq!(sftp.host, "nas.lan");
+                assert_eq!(sftp.port, 2222);
+                assert_eq!(sftp.base_path, "/srv/media");
+            }
+            other => panic!("expected SFTP storage, got {:?}", other),
+        }
+        assert!(matches!(
+            config("file:///srv/media").unwrap(),
+            StorageConfig::FileSystem(fs) if fs.base_path == "/srv/media"
+        ));
+
+        // GCS needs a project, SFTP a user, and unknown schemes fail
+        assert!(config("gs://media").is_err());
+        assert!(config("gs://media?project_id=studio").is_ok());
+        assert!(config("sftp://nas.lan/srv").is_err());
+        let err = config("ftp://nas.lan/srv").unwrap_err();
+        assert!(err.to_string().contains("s3"));
+    }
+
+    #[derive(Debug)]
+    struct Shared(Arc<MockBackend>);
+
+    #[async_trait]
+    impl BackendProvider for Shared {
+        async fn open(
+            &self,
+            uri: &BackendUri,
+            _factory: &BackendFactory,
+        ) -> Result<Arc<dyn StorageBackend>> {
+            Ok(with_key_prefix(self.0.clone(), uri.prefix()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_schemes() {
+        let bucket = Arc::new(MockBackend::new());
+        let factory =
+            BackendFactory::new().with_provider("Shared", Arc::new(Shared(bucket.clone())));
+        assert!(factory.schemes().contains(&"shared"));
+
+        let storage = factory.open_uri("shared://any/repos/a").await.unwrap();
+        storage.put("abc123", b"data").await.unwrap();
+        assert!(bucket.exists("repos/a/abc123").await.unwrap());
+
+        assert!(factory.open_uri("memory://").await.is_ok());
+        assert!(factory.open_uri("ftp://nas.lan").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_explicit_proxy_is_refused_for_azure_and_gcs() {
+        let factory = BackendFactory::new().with_proxy(ProxySettings::new("http://proxy:3128"));
+        for uri in [
+            "az://media?account_name=studio",
+            "gs://media?project_id=studio",
+        ] {
+            let config = BackendFactory::storage_config(&uri.parse().unwrap()).unwrap();
+            let err = factory.open_config(&config).await.unwrap_err();
+            assert!(
+                format!("{:#}", err).contains("[proxy] section"),
+                "{:#}",
+                err
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_open_routed_filesystem_config() {
+        let dir = TempDir::new().unwrap();
+        let storage: StorageConfig = serde_json::from_value(serde_json::json!({
+            "backend": "multi",
+            "primary": "loose",
+            "routes": {"packs/": "packs"},
+            "backends": {
+                "loose": {"backend": "filesystem", "base_path": "loose"},
+                "packs": {"backend": "filesystem", "base_path": "packs"},
+            },
+        }))
+        .unwrap();
+
+        let factory = BackendFactory::new().with_base_dir(dir.path());
+        let backend = factory.open_config(&storage).await.unwrap();
+        backend.put("packs/pack-1.pack", b"pack").await.unwrap();
+        backend.put("abc123", b"loose").await.unwrap();
+
+        let packs = LocalBackend::new(dir.path().join("packs")).await.unwrap();
+        let loose = LocalBackend::new(dir.path().join("loose")).await.unwrap();
+        assert!(packs.exists("packs/pack-1.pack").await.unwrap());
+        assert!(loose.exists("abc123").await.unwrap());
+        assert!(!loose.exists("packs/pack-1.pack").await.unwrap());
+    }
+
+    #[test]
+    fn test_storage_class_policy() {
+        use crate::StorageClass;
+
+        let s3_config = s3_storage(serde_json::json!({
+            "backend": "s3",
+            "bucket": "assets",
+            "region": "us-east-1",
+            "prefix": "/repos/game/",
+            "storage_class": "standard-ia",
+            "pack_storage_class": "GLACIER_IR",
+        }));
+
+        let policy = storage_class_policy(&s3_config).unwrap();
+        assert_eq!(
+            policy.class_for("repos/game/packs/pack-1.pack"),
+            Some(StorageClass::GlacierIr)
+        );
+        assert_eq!(
+            policy.class_for("repos/game/ab12"),
+            Some(StorageClass::StandardIa)
+        );
+
+        let s3_config = S3Storage {
+            storage_class: Some("COLD".to_string()),
+            ..s3_config
+        };
+        assert!(storage_class_policy(&s3_config).is_err());
+    }
+
+    #[test]
+    fn test_server_side_encryption() {
+        let s3_config = s3_storage(serde_json::json!({
+            "backend": "s3",
+            "bucket": "assets",
+            "region": "us-east-1",
+            "encryption_algorithm": "aws:kms:alias/mediagit",
+        }));
+        // Off unless `encryption` is set
+        assert_eq!(server_side_encryption(&s3_config).unwrap(), None);
+
+        let s3_config = S3Storage {
+            encryption: true,
+            ..s3_config
+        };
+        assert_eq!(
+            server_side_encryption(&s3_config).unwrap(),
+            Some(ServerSideEncryption::kms("alias/mediagit"))
+        );
+
+        let s3_config = S3Storage {
+            encryption_algorithm: "SSE-C".to_string(),
+            ..s3_config
+        };
+        assert!(server_side_encryption(&s3_config).is_err());
+        let s3_config = S3Storage {
+            sse_customer_key: Some("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=".to_string()),
+            ..s3_config
+        };
+        assert!(matches!(
+            server_side_encryption(&s3_config).unwrap(),
+            Some(ServerSideEncryption::Customer(_))
+        ));
+    }
+}
@@ -15,7 +15,8 @@
 //!
 //! Implements the `StorageBackend` trait using Google Cloud Storage (GCS) with:
 //! - Async/await support via `tokio`
-//! - Service account authentication from JSON files
+//! - Service account keys or Application Default Credentials, including
+//!   GKE Workload Identity and workload identity federation
 //! - Resumable uploads for large files (>5MB)
 //! - Transparent retry logic with exponential backoff
 //! - Proper error handling and GCS-specific error mapping
@@ -33,7 +34,9 @@
 //! The GCS backend requires:
 //! 1. A Google Cloud Project with GCS enabled
 //! 2. A service account with appropriate roles (Storage Admin or Editor)
-//! 3. The service account JSON key file
+//! 3. Either the service account JSON key file, or an environment where
+//!    [`GcsBackend::with_default_credentials`] can find credentials, such as
+//!    a GKE pod with Workload Identity or a Compute Engine instance
 //!
 //! # Examples
 //!
@@ -75,26 +78,54 @@
 //! # Resumable Uploads
 //!
 //! For files larger than 5MB, the backend automatically uses resumable uploads:
-//! - Uploads are broken into 256KB chunks (configurable)
-//! - Each chunk is uploaded independently
-//! - If a chunk fails, only that chunk is retried
-//! - Progress can be tracked via resumable session URLs
+//! - A resumable session is started and its URI remembered
+//! - Data is sent in 256KB-aligned chunks (configurable)
+//! - If a chunk fails, the session is asked how much it already holds and
+//!   the upload continues from there
+//! - If the whole `put` fails, retrying it with the same data resumes the
+//!   same session instead of starting over
 //!
-//! This provides better reliability for large files and allows recovery
-//! from transient network failures.
-
-use crate::StorageBackend;
+//! Session URIs are kept in memory, and also on disk when
+//! [`GcsConfig::with_session_dir`] is set, so an upload interrupted by a
+//! crash or restart resumes too. GCS keeps sessions for about a week; an
+//! expired session is dropped and the upload starts fresh.
+//!
+//! # Checksums
+//!
+//! With [`GcsConfig::with_checksum`], uploads carry a checksum of the data.
+//! A CRC32C is sent as the object's `crc32c`, which GCS checks before
+//! accepting the upload. GCS has no SHA-256 of its own, so a SHA-256 is kept
+//! in the object's `mediagit-sha256` metadata instead. Either way, `get`
+//! checks the downloaded data against the stored checksum and fails with
+//! [`StorageError::ChecksumMismatch`](crate::StorageError::ChecksumMismatch)
+//! if they differ.
+
+use crate::checksum::{Checksum, ChecksumAlgorithm};
+use crate::retry::{is_transient, RetryPolicy};
+use crate::stream::{self, KeyStream};
+use crate::{
+    check_delimiter, BackendCapabilities, ExpiryPolicy, ObjectMeta, StorageBackend, StorageUsage,
+};
 use async_trait::async_trait;
+use bytes::Bytes;
+use futures::TryStreamExt;
 use google_cloud_auth::credentials::CredentialsFile;
 use google_cloud_storage::client::{Client as GcsClient, ClientConfig};
 use google_cloud_storage::http::objects::delete::DeleteObjectRequest;
 use google_cloud_storage::http::objects::download::Range;
 use google_cloud_storage::http::objects::get::GetObjectRequest;
 use google_cloud_storage::http::objects::list::ListObjectsRequest;
+use google_cloud_storage::http::objects::rewrite::RewriteObjectRequest;
 use google_cloud_storage::http::objects::upload::{Media, UploadObjectRequest, UploadType};
 use google_cloud_storage::http::objects::Object;
+use google_cloud_storage::http::resumable_upload_client::{ChunkSize, UploadStatus};
+use google_cloud_storage::http::Error as HttpError;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fmt;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tracing::{debug, warn};
 
 /// Configuration for the GCS backend
@@ -114,8 +145,18 @@ pub struct GcsConfig {
     /// Maximum number of retries for transient failures
     /// Default: 3
     pub max_retries: u32,
+    /// Directory where resumable upload session URIs are saved, so an upload
+    /// interrupted by a restart can resume
+    /// Default: None (sessions are only remembered in memory)
+    pub session_dir: Option<PathBuf>,
+    /// Checksum sent with each upload and checked on `get`
+    /// Default: None
+    pub checksum: Option<ChecksumAlgorithm>,
 }
 
+/// Resumable upload chunks must be a multiple of this size, except the last
+const RESUMABLE_CHUNK_ALIGNMENT: usize = 256 * 1024;
+
 impl Default for GcsConfig {
     fn default() -> Self {
         GcsConfig {
@@ -124,6 +165,8 @@ impl Default for GcsConfig {
             chunk_size: 256 * 1024,               // 256KB
             resumable_threshold: 5 * 1024 * 1024, // 5MB
             max_retries: 3,
+            session_dir: None,
+            checksum: None,
         }
     }
 }
@@ -155,6 +198,81 @@ impl GcsConfig {
         self.max_retries = retries;
         self
     }
+
+    /// Save resumable upload sessions under `dir`
+    pub fn with_session_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.session_dir = Some(dir.into());
+        self
+    }
+
+    /// Checksum uploads with `algorithm` and verify downloads against it
+    pub fn with_checksum(mut self, algorithm: ChecksumAlgorithm) -> Self {
+        self.checksum = Some(algorithm);
+        self
+    }
+
+    /// Resumable chunk size, rounded up to the 256KB multiple GCS requires
+    fn resumable_chunk_size(&self) -> usize {
+        self.chunk_size.div_ceil(RESUMABLE_CHUNK_ALIGNMENT).max(1) * RESUMABLE_CHUNK_ALIGNMENT
+    }
+}
+
+/// Resumable upload session URIs, keyed by bucket, object key and content
+///
+/// A `put` that fails partway leaves its session here, so retrying it with
+/// the same data continues the upload rather than restarting it.
+#[derive(Debug, Default)]
+struct UploadSessions {
+    memory: Mutex<HashMap<String, String>>,
+}
+
+impl UploadSessions {
+    /// Identifies an upload of `data` to `key` in `bucket`
+    fn id(bucket: &str, key: &str, data: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(bucket.as_bytes());
+        hasher.update([0]);
+        hasher.update(key.as_bytes());
+        hasher.update([0]);
+        hasher.update(Sha256::digest(data));
+        hex::encode(hasher.finalize())
+    }
+
+    /// The session URI saved for `id`, from memory or else from `dir`
+    async fn load(&self, dir: Option<&Path>, id: &str) -> Option<String> {
+        if let Some(url) = self.memory.lock().unwrap().get(id) {
+            return Some(url.clone());
+        }
+        let url = tokio::fs::read_to_string(dir?.join(id)).await.ok()?;
+        let url = url.trim();
+        (!url.is_empty()).then(|| url.to_string())
+    }
+
+    /// Remember `url` for `id`; failing to write it to `dir` only costs the
+    /// ability to resume after a restart
+    async fn save(&self, dir: Option<&Path>, id: &str, url: &str) {
+        self.memory
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), url.to_string());
+        if let Some(dir) = dir {
+            let written = match tokio::fs::create_dir_all(dir).await {
+                Ok(()) => tokio::fs::write(dir.join(id), url).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = written {
+                warn!(dir = %dir.display(), error = %e, "Failed to save GCS upload session");
+            }
+        }
+    }
+
+    /// Forget the session for `id`
+    async fn remove(&self, dir: Option<&Path>, id: &str) {
+        self.memory.lock().unwrap().remove(id);
+        if let Some(dir) = dir {
+            let _ = tokio::fs::remove_file(dir.join(id)).await;
+        }
+    }
 }
 
 /// Google Cloud Storage backend implementation
@@ -165,6 +283,7 @@ impl GcsConfig {
 pub struct GcsBackend {
     client: Arc<GcsClient>,
     config: GcsConfig,
+    sessions: Arc<UploadSessions>,
 }
 
 impl GcsBackend {
@@ -246,6 +365,7 @@ impl GcsBackend {
         Ok(GcsBackend {
             client: Arc::new(client),
             config: GcsConfig::new(project_id, bucket_name),
+            sessions: Arc::default(),
         })
     }
 
@@ -317,6 +437,7 @@ impl GcsBackend {
         Ok(GcsBackend {
             client: Arc::new(client),
             config,
+            sessions: Arc::default(),
         })
     }
 
@@ -325,12 +446,28 @@ impl GcsBackend {
         &self.config
     }
 
+    /// Save resumable upload sessions under `dir`, so uploads interrupted by
+    /// a restart resume instead of starting over
+    pub fn with_session_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.session_dir = Some(dir.into());
+        self
+    }
+
+    /// Checksum uploads with `algorithm` and verify downloads against it
+    pub fn with_checksum(mut self, algorithm: ChecksumAlgorithm) -> Self {
+        self.config.checksum = Some(algorithm);
+        self
+    }
+
     /// Create a new GCS backend with environment variable authentication
     ///
     /// Looks for:
-    /// - `GOOGLE_APPLICATION_CREDENTIALS` - Path to service account JSON
-    /// - `GCS_PROJECT_ID` - GCS project ID
     /// - `GCS_BUCKET_NAME` - GCS bucket name
+    /// - `GCS_PROJECT_ID` or `GOOGLE_CLOUD_PROJECT` - GCS project ID, taken
+    ///   from the credentials when neither is set
+    /// - `GOOGLE_APPLICATION_CREDENTIALS` - Path to a credentials file; when
+    ///   unset, the other [Application Default Credentials](Self::with_default_credentials)
+    ///   sources are tried
     ///
     /// # Examples
     ///
@@ -351,35 +488,35 @@ impl GcsBackend {
     pub async fn from_env() -> anyhow::Result<Self> {
         let project_id = std::env::var("GCS_PROJECT_ID")
             .or_else(|_| std::env::var("GOOGLE_CLOUD_PROJECT"))
-            .map_err(|_| {
-                anyhow::anyhow!(
-                    "GCS_PROJECT_ID or GOOGLE_CLOUD_PROJECT environment variable not set"
-                )
-            })?;
+            .unwrap_or_default();
 
         let bucket_name = std::env::var("GCS_BUCKET_NAME")
             .map_err(|_| anyhow::anyhow!("GCS_BUCKET_NAME environment variable not set"))?;
 
-        let service_account_path =
-            std::env::var("GOOGLE_APPLICATION_CREDENTIALS").map_err(|_| {
-                anyhow::anyhow!("GOOGLE_APPLICATION_CREDENTIALS environment variable not set")
-            })?;
-
-        Self::new(project_id, bucket_name, service_account_path).await
+        Self::with_default_credentials(project_id, bucket_name).await
     }
 
     /// Create a new GCS backend using Application Default Credentials (ADC)
     ///
-    /// This method uses Google Cloud's Application Default Credentials which
-    /// automatically detect credentials from the environment:
-    /// - On GKE: Uses the node's service account
-    /// - On Cloud Run/Functions: Uses the service's identity
-    /// - On Compute Engine: Uses the instance's service account
-    /// - Locally: Uses `GOOGLE_APPLICATION_CREDENTIALS` env var if set
+    /// No key material has to be configured. Credentials are looked up in
+    /// the order Google's client libraries use:
+    /// - The file named by `GOOGLE_APPLICATION_CREDENTIALS`: a service
+    ///   account key, `gcloud` user credentials, or an `external_account`
+    ///   configuration for workload identity federation (AWS, Azure, OIDC)
+    /// - The `gcloud auth application-default login` credentials
+    /// - The metadata server on Compute Engine, Cloud Run and Cloud
+    ///   Functions, and on GKE with Workload Identity, which issues tokens
+    ///   for the service account bound to the instance or pod
+    ///
+    /// Access tokens are cached and fetched again once they expire, so a
+    /// long-running server or push keeps working past the token lifetime.
+    ///
+    /// An empty `project_id` is taken from the credentials or the metadata
+    /// server.
     ///
     /// # Arguments
     ///
-    /// * `project_id` - Google Cloud Project ID
+    /// * `project_id` - Google Cloud Project ID, or empty to discover it
     /// * `bucket_name` - GCS bucket name
     ///
     /// # Returns
@@ -406,77 +543,90 @@ impl GcsBackend {
         project_id: impl Into<String>,
         bucket_name: impl Into<String>,
     ) -> anyhow::Result<Self> {
-        let project_id = project_id.into();
-        let bucket_name = bucket_name.into();
+        Self::with_config_default_credentials(GcsConfig::new(project_id, bucket_name)).await
+    }
 
-        if project_id.is_empty() {
-            return Err(anyhow::anyhow!("project_id cannot be empty"));
-        }
-        if bucket_name.is_empty() {
+    /// Create a new GCS backend with custom configuration, authenticated
+    /// with Application Default Credentials
+    ///
+    /// See [`with_default_credentials`](Self::with_default_credentials) for
+    /// where credentials are looked up.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use mediagit_storage::gcs::{GcsBackend, GcsConfig};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let config = GcsConfig::new("my-project", "my-bucket").with_max_retries(5);
+    /// let storage = GcsBackend::with_config_default_credentials(config).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn with_config_default_credentials(mut config: GcsConfig) -> anyhow::Result<Self> {
+        if config.bucket_name.is_empty() {
             return Err(anyhow::anyhow!("bucket_name cannot be empty"));
         }
 
-        // Check if GOOGLE_APPLICATION_CREDENTIALS is set - use that file
-        if let Ok(creds_path) = std::env::var("GOOGLE_APPLICATION_CREDENTIALS") {
-            debug!(
-                project_id = %project_id,
-                bucket_name = %bucket_name,
-                credentials_path = %creds_path,
-                "Using GOOGLE_APPLICATION_CREDENTIALS for GCS"
-            );
-            return Self::new(&project_id, &bucket_name, &creds_path).await;
-        }
-
-        // Otherwise use default client config (ADC)
-        debug!(
-            project_id = %project_id,
-            bucket_name = %bucket_name,
-            "Using Application Default Credentials for GCS"
-        );
-
         let client_config = ClientConfig::default()
             .with_auth()
             .await
             .map_err(|e| anyhow::anyhow!("failed to create GCS client with ADC: {}", e))?;
 
-        let client = GcsClient::new(client_config);
+        if config.project_id.is_empty() {
+            config.project_id = discovered_project_id(client_config.project_id.as_deref())?;
+        }
+
+        debug!(
+            project_id = %config.project_id,
+            bucket_name = %config.bucket_name,
+            credentials_path = ?std::env::var("GOOGLE_APPLICATION_CREDENTIALS").ok(),
+            "Using Application Default Credentials for GCS"
+        );
 
         Ok(GcsBackend {
-            client: Arc::new(client),
-            config: GcsConfig::new(project_id, bucket_name),
+            client: Arc::new(GcsClient::new(client_config)),
+            config,
+            sessions: Arc::default(),
         })
     }
 
-    /// Retry logic with exponential backoff for transient failures
-    async fn retry<F, Fut, T>(&self, mut f: F) -> anyhow::Result<T>
+    /// Run `f` with exponential backoff, retrying transient failures up to
+    /// `max_retries` attempts in total
+    async fn retry<F, Fut, T>(&self, f: F) -> anyhow::Result<T>
     where
         F: FnMut() -> Fut,
         Fut: std::future::Future<Output = anyhow::Result<T>>,
     {
-        let mut retry_count = 0;
-        let mut delay_ms = 100u64;
+        self.retry_policy().run("GCS request", f).await
+    }
 
-        loop {
-            match f().await {
-                Ok(result) => return Ok(result),
-                Err(e) => {
-                    retry_count += 1;
-                    if retry_count >= self.config.max_retries {
-                        return Err(e);
-                    }
+    /// Backoff from 100ms up to 32s, as Google recommends
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::new(self.config.max_retries, Duration::from_millis(100))
+            .with_max_delay(Duration::from_secs(32))
+    }
+}
 
-                    warn!(
-                        retry_count,
-                        delay_ms,
-                        error = %e,
-                        "Retrying failed GCS operation"
-                    );
+/// The project ID Application Default Credentials found, if any
+fn discovered_project_id(project_id: Option<&str>) -> anyhow::Result<String> {
+    match project_id {
+        Some(project_id) if !project_id.is_empty() => Ok(project_id.to_string()),
+        _ => Err(anyhow::anyhow!(
+            "project_id cannot be empty: the credentials don't name a project, set GCS_PROJECT_ID"
+        )),
+    }
+}
 
-                    tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-                    delay_ms = std::cmp::min(delay_ms * 2, 32000); // Cap at 32s
-                }
-            }
+/// An error for a failed request that keeps the HTTP status, so
+/// [`RetryPolicy`] can tell throttling and outages from permanent failures
+fn request_error(what: &str, error: HttpError) -> anyhow::Error {
+    match &error {
+        HttpError::Response(response) => {
+            anyhow::anyhow!("{} (HTTP {}): {}", what, response.code, error)
         }
+        _ => anyhow::anyhow!("{}: {}", what, error),
     }
 }
 
@@ -488,6 +638,7 @@ impl fmt::Debug for GcsBackend {
             .field("chunk_size", &self.config.chunk_size)
             .field("resumable_threshold", &self.config.resumable_threshold)
             .field("max_retries", &self.config.max_retries)
+            .field("session_dir", &self.config.session_dir)
             .finish()
     }
 }
@@ -512,6 +663,8 @@ impl StorageBackend for GcsBackend {
     /// - Downloads the object from the configured bucket
     /// - Returns an error with "object not found" message if the object doesn't exist
     /// - Retries transient failures automatically
+    /// - With a checksum configured, reads the object's metadata first and
+    ///   verifies the download against its checksum
     async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
         if key.is_empty() {
             return Err(anyhow::anyhow!("key cannot be empty"));
@@ -520,6 +673,7 @@ impl StorageBackend for GcsBackend {
         let bucket = self.config.bucket_name.clone();
         let key = key.to_string();
         let client = self.client.clone();
+        let checksum = self.config.checksum;
 
         self.retry(|| {
             let bucket = bucket.clone();
@@ -527,20 +681,90 @@ impl StorageBackend for GcsBackend {
             let client = client.clone();
 
             async move {
-                let req = GetObjectRequest {
+                let mut req = GetObjectRequest {
                     bucket: bucket.clone(),
                     object: key.clone(),
                     ..Default::default()
                 };
 
+                let mut stored = None;
+                if let Some(algorithm) = checksum {
+                    let object = match client.get_object(&req).await {
+                        Ok(object) => object,
+                        Err(e) => {
+                            let err_string = e.to_string();
+                            if err_string.contains("404") || err_string.contains("Not Found") {
+                                return Err(anyhow::anyhow!("object not found: {}", key));
+                            }
+                            return Err(request_error("GCS error", e));
+                        }
+                    };
+                    stored = stored_checksum(algorithm, &object);
+                    // Download the version whose checksum was read, even if
+                    // the object is replaced in between
+                    req.generation = Some(object.generation);
+                }
+
                 match client.download_object(&req, &Range::default()).await {
+                    Ok(bytes) => {
+                        if let Some(stored) = stored {
+                            stored.verify(&key, &bytes)?;
+                        }
+                        Ok(bytes)
+                    }
+                    Err(e) => {
+                        let err_string = e.to_string();
+                        if err_string.contains("404") || err_string.contains("Not Found") {
+                            Err(anyhow::anyhow!("object not found: {}", key))
+                        } else {
+                            Err(request_error("GCS error", e))
+                        }
+                    }
+                }
+            }
+        })
+        .await
+    }
+
+    /// Retrieve part of an object from GCS with a ranged download
+    ///
+    /// GCS rejects a range starting past the end of the object, which is
+    /// returned as empty rather than as an error.
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+
+        let bucket = self.config.bucket_name.clone();
+        let key = key.to_string();
+        let client = self.client.clone();
+        let last = offset.saturating_add(len - 1);
+
+        self.retry(|| {
+            let bucket = bucket.clone();
+            let key = key.clone();
+            let client = client.clone();
+
+            async move {
+                let req = GetObjectRequest {
+                    bucket: bucket.clone(),
+                    object: key.clone(),
+                    ..Default::default()
+                };
+
+                let range = Range(Some(offset), Some(last));
+                match client.download_object(&req, &range).await {
                     Ok(bytes) => Ok(bytes),
+                    Err(HttpError::Response(response)) if response.code == 416 => Ok(Vec::new()),
                     Err(e) => {
                         let err_string = e.to_string();
                         if err_string.contains("404") || err_string.contains("Not Found") {
                             Err(anyhow::anyhow!("object not found: {}", key))
                         } else {
-                            Err(anyhow::anyhow!("GCS error: {}", e))
+                            Err(request_error("GCS error", e))
                         }
                     }
                 }
@@ -549,6 +773,16 @@ impl StorageBackend for GcsBackend {
         .await
     }
 
+    /// Ranged downloads and copies within the bucket are supported; nothing
+    /// else is
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            server_side_copy: true,
+            range_reads: true,
+            ..BackendCapabilities::default()
+        }
+    }
+
     /// Store an object in GCS
     ///
     /// Uses resumable uploads for files larger than the configured threshold (default 5MB).
@@ -571,10 +805,10 @@ impl StorageBackend for GcsBackend {
     ///
     /// For large files (>5MB):
     /// - Uses resumable upload protocol
-    /// - Breaks file into 256KB chunks
-    /// - Each chunk is uploaded and verified
-    /// - Implements automatic retry on chunk failure
-    /// - Supports pausing and resuming uploads via session ID
+    /// - Sends the file in 256KB-aligned chunks
+    /// - After a failed chunk, resumes from the offset the session reports
+    /// - Keeps the session after a failed `put`, so a retry with the same
+    ///   data resumes it
     async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
         if key.is_empty() {
             return Err(anyhow::anyhow!("key cannot be empty"));
@@ -631,14 +865,14 @@ impl StorageBackend for GcsBackend {
                     ..Default::default()
                 };
 
-                match client.download_object(&req, &Range::default()).await {
+                match client.get_object(&req).await {
                     Ok(_) => Ok(true),
                     Err(e) => {
                         let err_string = e.to_string();
                         if err_string.contains("404") || err_string.contains("Not Found") {
                             Ok(false)
                         } else {
-                            Err(anyhow::anyhow!("GCS error: {}", e))
+                            Err(request_error("GCS error", e))
                         }
                     }
                 }
@@ -647,6 +881,111 @@ impl StorageBackend for GcsBackend {
         .await
     }
 
+    /// Copy an object within the bucket with rewrite requests
+    ///
+    /// Large objects may take several requests, each resuming from the
+    /// token the previous one returned.
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        if src_key.is_empty() || dst_key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        let mut request = RewriteObjectRequest {
+            source_bucket: self.config.bucket_name.clone(),
+            source_object: src_key.to_string(),
+            destination_bucket: self.config.bucket_name.clone(),
+            destination_object: dst_key.to_string(),
+            ..Default::default()
+        };
+
+        loop {
+            let client = self.client.clone();
+            let response = self
+                .retry(|| {
+                    let client = client.clone();
+                    let request = request.clone();
+
+                    async move {
+                        match client.rewrite_object(&request).await {
+                            Ok(response) => Ok(Some(response)),
+                            Err(e) => {
+                                let err_string = e.to_string();
+                                // Not worth retrying
+                                if err_string.contains("404") || err_string.contains("Not Found") {
+                                    Ok(None)
+                                } else {
+                                    Err(request_error("GCS error", e))
+                                }
+                            }
+                        }
+                    }
+                })
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("object not found: {}", src_key))?;
+
+            if response.done {
+                debug!("Copied {} to {}", src_key, dst_key);
+                return Ok(());
+            }
+            debug!(
+                "Copying {} to {}: {} of {} bytes",
+                src_key, dst_key, response.total_bytes_rewritten, response.object_size
+            );
+            request.rewrite_token = response.rewrite_token;
+        }
+    }
+
+    /// Fetch an object's metadata from GCS
+    ///
+    /// Reads the object resource without downloading its content.
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        let bucket = self.config.bucket_name.clone();
+        let key = key.to_string();
+        let client = self.client.clone();
+
+        let object = self
+            .retry(|| {
+                let bucket = bucket.clone();
+                let key = key.clone();
+                let client = client.clone();
+
+                async move {
+                    let req = GetObjectRequest {
+                        bucket: bucket.clone(),
+                        object: key.clone(),
+                        ..Default::default()
+                    };
+
+                    match client.get_object(&req).await {
+                        Ok(object) => Ok(Some(object)),
+                        Err(e) => {
+                            let err_string = e.to_string();
+                            // Not worth retrying
+                            if err_string.contains("404") || err_string.contains("Not Found") {
+                                Ok(None)
+                            } else {
+                                Err(request_error("GCS error", e))
+                            }
+                        }
+                    }
+                }
+            })
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("object not found: {}", key))?;
+
+        Ok(ObjectMeta {
+            size: u64::try_from(object.size).unwrap_or(0),
+            modified: object.updated.map(std::time::SystemTime::from),
+            etag: Some(object.etag),
+            content_type: object.content_type,
+            metadata: object.metadata.unwrap_or_default(),
+        })
+    }
+
     /// Delete an object from GCS
     ///
     /// # Arguments
@@ -695,7 +1034,7 @@ impl StorageBackend for GcsBackend {
                             debug!(key = %key, "Object not found during delete (idempotent)");
                             Ok(())
                         } else {
-                            Err(anyhow::anyhow!("GCS delete error: {}", e))
+                            Err(request_error("GCS delete error", e))
                         }
                     }
                 }
@@ -723,63 +1062,188 @@ impl StorageBackend for GcsBackend {
     /// - Automatically handles pagination for large result sets
     /// - Returns empty vec (not error) if no objects match
     async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
-        let bucket = self.config.bucket_name.clone();
-        let prefix = prefix.to_string();
-        let client = self.client.clone();
-
         debug!(
             bucket = %self.config.bucket_name,
             prefix = %prefix,
             "Listing objects from GCS"
         );
 
-        self.retry(|| {
-            let bucket = bucket.clone();
+        let mut results: Vec<String> = self.list_objects_stream(prefix).try_collect().await?;
+        results.sort();
+        debug!(count = results.len(), "Listed objects from GCS");
+        Ok(results)
+    }
+
+    /// Stream keys a page at a time, each page retried on its own
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        let prefix = (!prefix.is_empty()).then(|| prefix.to_string());
+        // `None` once the last page has been fetched
+        let first_page: Option<Option<String>> = Some(None);
+
+        stream::pages(first_page, move |page_token| {
             let prefix = prefix.clone();
-            let client = client.clone();
+            async move {
+                let Some(page_token) = page_token else {
+                    return Ok(None);
+                };
+                let req = ListObjectsRequest {
+                    bucket: self.config.bucket_name.clone(),
+                    prefix,
+                    page_token,
+                    ..Default::default()
+                };
+
+                let response = self
+                    .retry(|| async {
+                        self.client
+                            .list_objects(&req)
+                            .await
+                            .map_err(|e| request_error("GCS list error", e))
+                    })
+                    .await?;
+                let keys = response
+                    .items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|obj| obj.name)
+                    .collect();
+                Ok(Some((keys, response.next_page_token.map(Some))))
+            }
+        })
+    }
 
+    /// Collect the prefixes GCS groups keys into when listing with a delimiter
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        check_delimiter(delimiter)?;
+        let prefix = (!prefix.is_empty()).then(|| prefix.to_string());
+        let first_page: Option<Option<String>> = Some(None);
+
+        let pages = stream::pages(first_page, |page_token| {
+            let prefix = prefix.clone();
             async move {
-                let mut results = Vec::new();
-                let mut page_token: Option<String> = None;
+                let Some(page_token) = page_token else {
+                    return Ok(None);
+                };
+                let req = ListObjectsRequest {
+                    bucket: self.config.bucket_name.clone(),
+                    prefix,
+                    delimiter: Some(delimiter.to_string()),
+                    page_token,
+                    ..Default::default()
+                };
 
-                loop {
-                    let req = ListObjectsRequest {
-                        bucket: bucket.clone(),
-                        prefix: if prefix.is_empty() {
-                            None
-                        } else {
-                            Some(prefix.clone())
-                        },
-                        page_token: page_token.clone(),
-                        ..Default::default()
-                    };
+                let response = self
+                    .retry(|| async {
+                        self.client
+                            .list_objects(&req)
+                            .await
+                            .map_err(|e| request_error("GCS list error", e))
+                    })
+                    .await?;
+                let prefixes = response.prefixes.unwrap_or_default();
+                Ok(Some((prefixes, response.next_page_token.map(Some))))
+            }
+        });
 
-                    match client.list_objects(&req).await {
-                        Ok(response) => {
-                            if let Some(objects) = response.items {
-                                for obj in objects {
-                                    results.push(obj.name);
-                                }
-                            }
+        let mut prefixes: Vec<String> = pages.try_collect().await?;
+        prefixes.sort();
+        prefixes.dedup();
+        Ok(prefixes)
+    }
 
-                            page_token = response.next_page_token;
-                            if page_token.is_none() {
-                                break;
-                            }
-                        }
-                        Err(e) => return Err(anyhow::anyhow!("GCS list error: {}", e)),
-                    }
-                }
+    /// Sum the object sizes in the listing, without reading each object
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        let prefix = (!prefix.is_empty()).then(|| prefix.to_string());
+        let first_page: Option<Option<String>> = Some(None);
 
-                results.sort();
-                debug!(count = results.len(), "Listed objects from GCS");
-                Ok(results)
+        let sizes = stream::pages(first_page, |page_token| {
+            let prefix = prefix.clone();
+            async move {
+                let Some(page_token) = page_token else {
+                    return Ok(None);
+                };
+                let req = ListObjectsRequest {
+                    bucket: self.config.bucket_name.clone(),
+                    prefix,
+                    page_token,
+                    ..Default::default()
+                };
+
+                let response = self
+                    .retry(|| async {
+                        self.client
+                            .list_objects(&req)
+                            .await
+                            .map_err(|e| request_error("GCS list error", e))
+                    })
+                    .await?;
+                let sizes = response
+                    .items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|obj| obj.size.max(0) as u64)
+                    .collect();
+                Ok(Some((sizes, response.next_page_token.map(Some))))
             }
-        })
-        .await
+        });
+
+        sizes
+            .try_fold(StorageUsage::default(), |mut usage, size| async move {
+                usage.add(size);
+                Ok(usage)
+            })
+            .await
+    }
+
+    /// GCS lifecycle rules are not installed: they carry no id, so rules
+    /// written for one repository could not be told from those of another
+    /// sharing the bucket, or from the owner's own. Returns `false`, and
+    /// [`sweep_expired`](StorageBackend::sweep_expired) enforces the policy.
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> anyhow::Result<bool> {
+        let _ = policy;
+        Ok(false)
     }
 }
 
+/// Custom metadata key holding an object's SHA-256, which GCS doesn't
+/// compute itself
+const SHA256_METADATA_KEY: &str = "mediagit-sha256";
+
+/// Upload metadata for `key` carrying `checksum`: a CRC32C as the
+/// `crc32c` GCS verifies the upload against, a SHA-256 as custom metadata
+fn checksummed_object(key: &str, checksum: Checksum) -> Object {
+    let mut object = Object {
+        name: key.to_string(),
+        ..Default::default()
+    };
+    match checksum.algorithm() {
+        ChecksumAlgorithm::Crc32c => object.crc32c = Some(checksum.to_base64()),
+        ChecksumAlgorithm::Sha256 => {
+            object.metadata = Some(HashMap::from([(
+                SHA256_METADATA_KEY.to_string(),
+                checksum.to_base64(),
+            )]))
+        }
+    }
+    object
+}
+
+/// The `algorithm` checksum stored with `object`, if it has one
+///
+/// Every GCS object has a CRC32C; only objects uploaded with SHA-256
+/// checksums enabled have a SHA-256.
+fn stored_checksum(algorithm: ChecksumAlgorithm, object: &Object) -> Option<Checksum> {
+    let encoded = match algorithm {
+        ChecksumAlgorithm::Crc32c => object.crc32c.as_deref(),
+        ChecksumAlgorithm::Sha256 => object
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get(SHA256_METADATA_KEY))
+            .map(String::as_str),
+    }?;
+    Checksum::from_base64(algorithm, encoded)
+}
+
 // Helper methods for GcsBackend (not part of StorageBackend trait)
 impl GcsBackend {
     /// Simple upload for small files
@@ -787,6 +1251,12 @@ impl GcsBackend {
         let bucket = self.config.bucket_name.clone();
         let key = key.to_string();
         let client = self.client.clone();
+        let upload_type = match self.config.checksum {
+            Some(algorithm) => {
+                UploadType::Multipart(Box::new(checksummed_object(&key, algorithm.checksum(data))))
+            }
+            None => UploadType::Simple(Media::new(key.clone())),
+        };
         let data = data.to_vec();
 
         self.retry(|| {
@@ -794,23 +1264,20 @@ impl GcsBackend {
             let key = key.clone();
             let client = client.clone();
             let data = data.clone();
+            let upload_type = upload_type.clone();
 
             async move {
-                let media = Media::new(key.clone());
                 let req = UploadObjectRequest {
                     bucket: bucket.clone(),
                     ..Default::default()
                 };
 
-                match client
-                    .upload_object(&req, data, &UploadType::Simple(media))
-                    .await
-                {
+                match client.upload_object(&req, data, &upload_type).await {
                     Ok(_) => {
                         debug!(key = %key, "Successfully uploaded object to GCS");
                         Ok(())
                     }
-                    Err(e) => Err(anyhow::anyhow!("GCS upload error: {}", e)),
+                    Err(e) => Err(request_error("GCS upload error", e)),
                 }
             }
         })
@@ -818,70 +1285,121 @@ impl GcsBackend {
     }
 
     /// Resumable upload for large files with retry capability
+    ///
+    /// Resumes the saved session for this key and data if there is one,
+    /// otherwise starts a new session and saves its URI. The session is
+    /// forgotten once the upload completes; on failure it is kept so the
+    /// next attempt can pick up where this one stopped.
     async fn upload_resumable(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
-        let bucket = self.config.bucket_name.clone();
-        let key = key.to_string();
-        let client = self.client.clone();
-        let chunk_size = self.config.chunk_size;
-        let data = data.to_vec();
-
-        debug!(
-            key = %key,
-            total_size = data.len(),
-            chunk_size = chunk_size,
-            "Starting resumable upload"
-        );
-
-        let mut offset = 0;
-        let total_size = data.len();
-
-        while offset < total_size {
-            let chunk_end = std::cmp::min(offset + chunk_size, total_size);
-            let chunk = &data[offset..chunk_end];
-
-            let bucket = bucket.clone();
-            let key = key.clone();
-            let client = client.clone();
-
-            self.retry(|| {
-                let bucket = bucket.clone();
-                let key = key.clone();
-                let client = client.clone();
-                let chunk = chunk.to_vec();
+        let dir = self.config.session_dir.as_deref();
+        let id = UploadSessions::id(&self.config.bucket_name, key, data);
+        let total = data.len() as u64;
+
+        let mut resumed = None;
+        if let Some(url) = self.sessions.load(dir, &id).await {
+            let uploader = self.client.get_resumable_upload(url);
+            match uploader.status(Some(total)).await {
+                Ok(UploadStatus::Ok(_)) => {
+                    debug!(key = %key, "Resumable upload had already completed");
+                    self.sessions.remove(dir, &id).await;
+                    return Ok(());
+                }
+                Ok(UploadStatus::ResumeIncomplete(range)) => {
+                    resumed = Some((uploader, range.last_byte + 1));
+                }
+                Ok(UploadStatus::NotStarted) => resumed = Some((uploader, 0)),
+                Err(e) => {
+                    // Expired or cancelled; start a new session below
+                    debug!(key = %key, error = %e, "Discarding stale resumable upload session");
+                    self.sessions.remove(dir, &id).await;
+                }
+            }
+        }
 
-                async move {
-                    let object = Object {
-                        name: key.clone(),
-                        ..Default::default()
-                    };
-                    let req = UploadObjectRequest {
-                        bucket: bucket.clone(),
+        let (uploader, mut offset) = match resumed {
+            Some((uploader, offset)) => {
+                debug!(key = %key, offset, total_size = total, "Resuming resumable upload");
+                (uploader, offset)
+            }
+            None => {
+                let req = UploadObjectRequest {
+                    bucket: self.config.bucket_name.clone(),
+                    ..Default::default()
+                };
+                let object = match self.config.checksum {
+                    Some(algorithm) => checksummed_object(key, algorithm.checksum(data)),
+                    None => Object {
+                        name: key.to_string(),
                         ..Default::default()
-                    };
+                    },
+                };
+                let upload_type = UploadType::Multipart(Box::new(object));
+                let uploader = self
+                    .retry(|| async {
+                        self.client
+                            .prepare_resumable_upload(&req, &upload_type)
+                            .await
+                            .map_err(|e| request_error("GCS resumable session error", e))
+                    })
+                    .await?;
+                self.sessions.save(dir, &id, uploader.url()).await;
+                debug!(key = %key, total_size = total, "Started resumable upload");
+                (uploader, 0)
+            }
+        };
 
-                    let chunk_len = chunk.len(); // Store length before move
-                    match client
-                        .upload_object(&req, chunk, &UploadType::Multipart(Box::new(object)))
-                        .await
-                    {
-                        Ok(_) => {
-                            debug!(
-                                key = %key,
-                                uploaded = offset + chunk_len,
-                                total = total_size,
-                                "Uploaded chunk to GCS"
-                            );
-                            Ok(())
-                        }
-                        Err(e) => Err(anyhow::anyhow!("GCS chunk upload error: {}", e)),
-                    }
-                }
-            })
-            .await?;
+        let chunk_size = self.config.resumable_chunk_size() as u64;
+        let data = Bytes::copy_from_slice(data);
+        let policy = self.retry_policy();
+        let mut failures = 0;
 
-            offset = chunk_end;
+        loop {
+            let end = (offset + chunk_size).min(total);
+            let range = ChunkSize::new(offset, end - 1, Some(total));
+            let chunk = data.slice(offset as usize..end as usize);
+
+            let error = match uploader.upload_multiple_chunk(chunk, &range).await {
+                Ok(UploadStatus::Ok(_)) => break,
+                Ok(UploadStatus::ResumeIncomplete(uploaded)) => {
+                    debug!(key = %key, uploaded = uploaded.last_byte + 1, total = total, "Uploaded chunk to GCS");
+                    offset = uploaded.last_byte + 1;
+                    failures = 0;
+                    continue;
+                }
+                Ok(UploadStatus::NotStarted) => {
+                    offset = 0;
+                    continue;
+                }
+                Err(e) => request_error("GCS chunk upload error", e),
+            };
+
+            failures += 1;
+            if failures >= policy.max_attempts || !is_transient(&error) {
+                return Err(error.context(format!(
+                    "Resumable upload stopped at byte {} of {} (retrying the put resumes from here)",
+                    offset, total
+                )));
+            }
+            let delay = policy.backoff(failures);
+            warn!(
+                retry_count = failures,
+                delay_ms = delay.as_millis() as u64,
+                offset,
+                error = %error,
+                "Retrying failed GCS chunk upload"
+            );
+            tokio::time::sleep(delay).await;
+
+            // The failed request may still have landed, in part or in full
+            match uploader.status(Some(total)).await {
+                Ok(UploadStatus::Ok(_)) => break,
+                Ok(UploadStatus::ResumeIncomplete(uploaded)) => offset = uploaded.last_byte + 1,
+                Ok(UploadStatus::NotStarted) => offset = 0,
+                Err(e) => debug!(error = %e, "GCS upload status check failed"),
+            }
         }
 
+        self.sessions.remove(dir, &id).await;
         debug!(key = %key, "Completed resumable upload");
         Ok(())
     }
@@ -918,6 +1436,87 @@ mod tests {
         assert_eq!(config.max_retries, 5);
     }
 
+    #[test]
+    fn test_checksummed_object() {
+        let crc = ChecksumAlgorithm::Crc32c.checksum(b"123456789");
+        let object = checksummed_object("objects/ab", crc.clone());
+        assert_eq!(object.name, "objects/ab");
+        assert_eq!(object.crc32c.as_deref(), Some("4waSgw=="));
+        assert_eq!(
+            stored_checksum(ChecksumAlgorithm::Crc32c, &object),
+            Some(crc)
+        );
+        assert_eq!(stored_checksum(ChecksumAlgorithm::Sha256, &object), None);
+
+        let sha = ChecksumAlgorithm::Sha256.checksum(b"frame data");
+        let object = checksummed_object("objects/ab", sha.clone());
+        assert_eq!(object.crc32c, None);
+        assert_eq!(
+            stored_checksum(ChecksumAlgorithm::Sha256, &object),
+            Some(sha)
+        );
+    }
+
+    #[test]
+    fn test_resumable_chunk_size_alignment() {
+        let config = GcsConfig::default();
+        assert_eq!(config.session_dir, None);
+        assert_eq!(config.resumable_chunk_size(), 256 * 1024);
+        assert_eq!(
+            config
+                .clone()
+                .with_chunk_size(300 * 1024)
+                .resumable_chunk_size(),
+            512 * 1024
+        );
+        assert_eq!(config.with_chunk_size(0).resumable_chunk_size(), 256 * 1024);
+    }
+
+    #[test]
+    fn test_upload_session_id() {
+        let id = UploadSessions::id("bucket", "objects/ab/cd", b"frame data");
+        assert_eq!(
+            id,
+            UploadSessions::id("bucket", "objects/ab/cd", b"frame data")
+        );
+        assert_ne!(
+            id,
+            UploadSessions::id("bucket", "objects/ab/cd", b"other data")
+        );
+        assert_ne!(
+            id,
+            UploadSessions::id("bucket", "objects/ab/ce", b"frame data")
+        );
+        assert_ne!(
+            id,
+            UploadSessions::id("other", "objects/ab/cd", b"frame data")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_sessions_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let session_dir = dir.path().join("gcs-uploads");
+        let id = UploadSessions::id("bucket", "render.mov", b"data");
+        let url = "https://storage.googleapis.com/upload/storage/v1/b/bucket/o?upload_id=abc";
+
+        let sessions = UploadSessions::default();
+        assert_eq!(sessions.load(Some(&session_dir), &id).await, None);
+        sessions.save(Some(&session_dir), &id, url).await;
+        assert_eq!(sessions.load(None, &id).await.as_deref(), Some(url));
+
+        // A fresh process only has what was written to disk
+        let restarted = UploadSessions::default();
+        assert_eq!(restarted.load(None, &id).await, None);
+        assert_eq!(
+            restarted.load(Some(&session_dir), &id).await.as_deref(),
+            Some(url)
+        );
+
+        restarted.remove(Some(&session_dir), &id).await;
+        assert_eq!(restarted.load(Some(&session_dir), &id).await, None);
+    }
+
     #[tokio::test]
     async fn test_gcs_backend_new_empty_project() {
         let result = GcsBackend::new("", "bucket", "dummy.json").await;
@@ -930,6 +1529,19 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_gcs_backend_default_credentials_empty_bucket() {
+        let result = GcsBackend::with_default_credentials("project", "").await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_discovered_project_id() {
+        assert_eq!(discovered_project_id(Some("studio")).unwrap(), "studio");
+        assert!(discovered_project_id(Some("")).is_err());
+        assert!(discovered_project_id(None).is_err());
+    }
+
     #[tokio::test]
     #[ignore = "requires GCS credentials"]
     async fn test_gcs_backend_new_valid() {
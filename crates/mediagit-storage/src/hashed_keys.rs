@@ -0,0 +1,473 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Key hashing for untrusted storage
+//!
+//! [`HashedKeyBackend`] replaces every key with the hex HMAC-SHA256 of the key
+//! under a repository secret before handing it to the wrapped backend. The
+//! provider then sees a flat space of random-looking names: no directory
+//! layout, no object types, no oids. Hashing is deterministic, so the same
+//! key always maps to the same stored name, and repositories with different
+//! secrets never collide.
+//!
+//! Hashed names cannot be turned back into keys, so each write also records
+//! the key in an index kept in the wrapped backend itself, under
+//! [`INDEX_PREFIX`] and the same hashed name. Any machine holding the secret
+//! can list the repository from it. The index entries hold the keys as
+//! written, so wrap the provider in an
+//! [`EncryptedBackend`](crate::EncryptedBackend) first to keep them, and the
+//! objects, unreadable to it. Reads, existence checks and deletes never
+//! consult the index.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_security::encryption::EncryptionKey;
+//! use mediagit_storage::{mock::MockBackend, EncryptedBackend, HashedKeyBackend, StorageBackend};
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let bucket = Arc::new(MockBackend::new());
+//! let key = EncryptionKey::generate()?;
+//! let encrypted = Arc::new(EncryptedBackend::new(bucket.clone(), "2025-01", key)?);
+//! let repo = HashedKeyBackend::new(encrypted, b"repo secret");
+//!
+//! repo.put("objects/abc123", b"data").await?;
+//! assert!(!bucket.exists("objects/abc123").await?);
+//! assert!(bucket.exists(&repo.hashed_key("objects/abc123")).await?);
+//! assert_eq!(repo.list_objects("objects/").await?, vec!["objects/abc123"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    BackendCapabilities, DeleteFailure, MmapOrVec, ObjectMeta, ObjectStream, StorageBackend,
+};
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Prefix of the key index in the wrapped backend
+///
+/// Hashed names are hex and never contain `/`, so no object is stored here.
+pub const INDEX_PREFIX: &str = "key-index/";
+
+/// Index entries fetched at once while listing
+const INDEX_FETCH_CONCURRENCY: usize = 16;
+
+/// Storage backend wrapper that stores every key under its HMAC
+#[derive(Clone)]
+pub struct HashedKeyBackend {
+    inner: Arc<dyn StorageBackend>,
+    mac: Hmac<Sha256>,
+    /// Keys of index entries already read, by hashed name
+    known: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl std::fmt::Debug for HashedKeyBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HashedKeyBackend")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HashedKeyBackend {
+    /// Wrap `inner` so keys are stored hashed with `secret`
+    ///
+    /// The key index is kept in `inner` too; see the [module docs](self).
+    pub fn new(inner: Arc<dyn StorageBackend>, secret: &[u8]) -> Self {
+        Self {
+            inner,
+            mac: Hmac::new_from_slice(secret).expect("HMAC accepts keys of any length"),
+            known: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Name `key` is stored under in the wrapped backend
+    pub fn hashed_key(&self, key: &str) -> String {
+        let mut mac = self.mac.clone();
+        mac.update(key.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Arc<dyn StorageBackend> {
+        &self.inner
+    }
+
+    /// Record `key`, stored as `hashed`, in the index
+    async fn index(&self, key: &str, hashed: &str) -> anyhow::Result<()> {
+        self.inner
+            .put(&format!("{}{}", INDEX_PREFIX, hashed), key.as_bytes())
+            .await?;
+        self.remember(hashed, key);
+        Ok(())
+    }
+
+    fn remember(&self, hashed: &str, key: &str) {
+        self.known
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(hashed.to_string(), key.to_string());
+    }
+
+    fn forget(&self, hashed: &str) {
+        self.known
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(hashed);
+    }
+
+    /// The key an index entry records, fetching it unless already known
+    async fn indexed_key(&self, hashed: &str) -> anyhow::Result<String> {
+        let known = self
+            .known
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(hashed)
+            .cloned();
+        if let Some(key) = known {
+            return Ok(key);
+        }
+
+        let data = self
+            .inner
+            .get(&format!("{}{}", INDEX_PREFIX, hashed))
+            .await?;
+        let key = String::from_utf8(data)
+            .map_err(|_| anyhow::anyhow!("key index entry {} is not a key", hashed))?;
+        // An entry must name the key that hashes to it
+        if self.hashed_key(&key) != hashed {
+            anyhow::bail!("key index entry {} does not match its key", hashed);
+        }
+        self.remember(hashed, &key);
+        Ok(key)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for HashedKeyBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.inner.get(&self.hashed_key(key)).await
+    }
+
+    async fn get_mapped(&self, key: &str) -> anyhow::Result<MmapOrVec> {
+        self.inner.get_mapped(&self.hashed_key(key)).await
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<std::time::SystemTime>> {
+        self.inner.modified(&self.hashed_key(key)).await
+    }
+
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        self.inner.head(&self.hashed_key(key)).await
+    }
+
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        self.inner
+            .get_range(&self.hashed_key(key), offset, len)
+            .await
+    }
+
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        self.inner.get_stream(&self.hashed_key(key)).await
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        let hashed = self.hashed_key(key);
+        self.inner.put(&hashed, data).await?;
+        self.index(key, &hashed).await
+    }
+
+    /// Records the key in the index even if the object was already stored,
+    /// so it lists again after the index is lost
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        let hashed = self.hashed_key(key);
+        let written = self.inner.put_if_absent(&hashed, data).await?;
+        self.index(key, &hashed).await?;
+        Ok(written)
+    }
+
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        self.inner
+            .put_if_match(&self.hashed_key(key), data, etag)
+            .await
+    }
+
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        let hashed = self.hashed_key(dst_key);
+        self.inner.copy(&self.hashed_key(src_key), &hashed).await?;
+        self.index(dst_key, &hashed).await
+    }
+
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> anyhow::Result<()> {
+        let hashed = self.hashed_key(key);
+        self.inner.put_stream(&hashed, data).await?;
+        self.index(key, &hashed).await
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        self.inner.exists(&self.hashed_key(key)).await
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        let hashed = self.hashed_key(key);
+        self.inner.delete(&hashed).await?;
+        self.forget(&hashed);
+        self.inner
+            .delete(&format!("{}{}", INDEX_PREFIX, hashed))
+            .await
+    }
+
+    /// Keys whose object couldn't be deleted stay in the index
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        let mut by_hash: HashMap<String, &String> =
+            keys.iter().map(|key| (self.hashed_key(key), key)).collect();
+        let hashed: Vec<String> = by_hash.keys().cloned().collect();
+
+        let mut failures = self.inner.delete_many(&hashed).await?;
+        for failure in &mut failures {
+            if let Some(key) = by_hash.remove(&failure.key) {
+                failure.key = key.clone();
+            }
+        }
+
+        let entries: HashMap<String, &String> = by_hash
+            .into_iter()
+            .map(|(hashed, key)| {
+                self.forget(&hashed);
+                (format!("{}{}", INDEX_PREFIX, hashed), key)
+            })
+            .collect();
+        let names: Vec<String> = entries.keys().cloned().collect();
+        for mut failure in self.inner.delete_many(&names).await? {
+            if let Some(key) = entries.get(&failure.key) {
+                failure.key = (*key).clone();
+            }
+            failures.push(failure);
+        }
+        Ok(failures)
+    }
+
+    /// Answered from the key index; entries not yet read in this process are
+    /// fetched from the wrapped backend
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let names = self.inner.list_objects(INDEX_PREFIX).await?;
+        let mut keys: Vec<String> = futures::stream::iter(names)
+            .map(|name| async move {
+                let hashed = name.strip_prefix(INDEX_PREFIX).unwrap_or(&name);
+                self.indexed_key(hashed).await
+            })
+            .buffer_unordered(INDEX_FETCH_CONCURRENCY)
+            .try_filter(|key| std::future::ready(key.starts_with(prefix)))
+            .try_collect()
+            .await?;
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockBackend;
+    use crate::EncryptedBackend;
+    use mediagit_security::encryption::EncryptionKey;
+
+    fn hashed(bucket: &Arc<MockBackend>, secret: &[u8]) -> HashedKeyBackend {
+        HashedKeyBackend::new(bucket.clone(), secret)
+    }
+
+    /// Objects in the bucket, leaving out key index entries
+    async fn stored_objects(bucket: &MockBackend) -> usize {
+        let keys = bucket.list_objects("").await.unwrap();
+        keys.iter().filter(|k| !k.starts_with(INDEX_PREFIX)).count()
+    }
+
+    #[test]
+    fn test_hashing_is_stable() {
+        let bucket = Arc::new(MockBackend::new());
+        let a = hashed(&bucket, b"secret");
+        let b = hashed(&bucket, b"secret");
+
+        let key = a.hashed_key("objects/ab/cdef");
+        assert_eq!(key, a.hashed_key("objects/ab/cdef"));
+        assert_eq!(key, b.hashed_key("objects/ab/cdef"));
+        assert_eq!(key.len(), 64);
+        assert!(!key.contains('/'));
+        assert_ne!(key, a.hashed_key("objects/ab/cdeg"));
+    }
+
+    #[tokio::test]
+    async fn test_different_secrets_produce_disjoint_key_spaces() {
+        let bucket = Arc::new(MockBackend::new());
+        let a = hashed(&bucket, b"studio a");
+        let b = hashed(&bucket, b"studio b");
+
+        let keys: Vec<String> = (0..32).map(|i| format!("objects/{:064x}", i)).collect();
+        for key in &keys {
+            a.put(key, b"a").await.unwrap();
+            b.put(key, b"b").await.unwrap();
+        }
+
+        assert_eq!(stored_objects(&bucket).await, keys.len() * 2);
+        for key in &keys {
+            assert_ne!(a.hashed_key(key), b.hashed_key(key));
+            assert_eq!(a.get(key).await.unwrap(), b"a");
+            assert_eq!(b.get(key).await.unwrap(), b"b");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_operations_use_hashed_keys_and_index() {
+        let bucket = Arc::new(MockBackend::new());
+        let repo = hashed(&bucket, b"secret");
+        repo.put("objects/1", b"one").await.unwrap();
+        repo.put("refs/heads/main", b"oid").await.unwrap();
+
+        assert!(!bucket.exists("objects/1").await.unwrap());
+        assert!(repo.exists("objects/1").await.unwrap());
+        assert_eq!(repo.get("objects/1").await.unwrap(), b"one");
+        assert_eq!(
+            repo.list_objects("objects/").await.unwrap(),
+            vec!["objects/1"]
+        );
+
+        repo.delete("objects/1").await.unwrap();
+        assert!(!repo.exists("objects/1").await.unwrap());
+        assert!(repo.list_objects("objects/").await.unwrap().is_empty());
+        assert_eq!(stored_objects(&bucket).await, 1);
+        assert_eq!(bucket.list_objects(INDEX_PREFIX).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_delete_many_updates_index() {
+        let bucket = Arc::new(MockBackend::new());
+        let repo = hashed(&bucket, b"secret");
+        for key in ["objects/1", "objects/2", "objects/3"] {
+            repo.put(key, b"data").await.unwrap();
+        }
+
+        let keys = vec!["objects/1".to_string(), "objects/3".to_string()];
+        assert!(repo.delete_many(&keys).await.unwrap().is_empty());
+        assert_eq!(
+            repo.list_objects("objects/").await.unwrap(),
+            vec!["objects/2"]
+        );
+        assert_eq!(bucket.list_objects("").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_another_machine_lists_from_the_shared_index() {
+        let bucket = Arc::new(MockBackend::new());
+        let writer = hashed(&bucket, b"secret");
+        writer.put("objects/1", b"one").await.unwrap();
+        writer.put("refs/heads/main", b"oid").await.unwrap();
+
+        // Nothing is shared but the bucket and the secret
+        let reader = hashed(&bucket, b"secret");
+        assert_eq!(
+            reader.list_objects("").await.unwrap(),
+            vec!["objects/1", "refs/heads/main"]
+        );
+        assert_eq!(
+            reader.list_prefixes("", "/").await.unwrap(),
+            vec!["objects/", "refs/"]
+        );
+
+        // A different secret finds nothing it can use
+        assert!(hashed(&bucket, b"other").list_objects("").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_put_if_absent_restores_index_entries() {
+        let bucket = Arc::new(MockBackend::new());
+        let repo = hashed(&bucket, b"secret");
+        assert!(repo.put_if_absent("objects/1", b"one").await.unwrap());
+
+        // A lost entry is recorded again without a second upload
+        let entry = format!("{}{}", INDEX_PREFIX, repo.hashed_key("objects/1"));
+        bucket.delete(&entry).await.unwrap();
+        let repo = hashed(&bucket, b"secret");
+        assert!(!repo.put_if_absent("objects/1", b"one").await.unwrap());
+        assert_eq!(
+            repo.list_objects("objects/").await.unwrap(),
+            vec!["objects/1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copy_updates_index() {
+        let bucket = Arc::new(MockBackend::new());
+        let repo = hashed(&bucket, b"secret");
+        repo.put("loose/1", b"one").await.unwrap();
+
+        repo.copy("loose/1", "objects/1").await.unwrap();
+        assert_eq!(repo.get("objects/1").await.unwrap(), b"one");
+        assert_eq!(
+            repo.list_objects("").await.unwrap(),
+            vec!["loose/1", "objects/1"]
+        );
+        assert_eq!(stored_objects(&bucket).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_index_is_encrypted_with_the_objects() {
+        let bucket = Arc::new(MockBackend::new());
+        let encrypted = EncryptedBackend::new(
+            bucket.clone(),
+            "2025-01",
+            EncryptionKey::generate().unwrap(),
+        )
+        .unwrap();
+        let repo = HashedKeyBackend::new(Arc::new(encrypted), b"secret");
+        repo.put("textures/hero.png", b"pixels").await.unwrap();
+
+        for name in bucket.list_objects("").await.unwrap() {
+            let stored = bucket.get(&name).await.unwrap();
+            assert!(!stored.windows(6).any(|w| w == b"pixels"));
+            assert!(!stored.windows(4).any(|w| w == b"hero"));
+            assert!(!name.contains("hero"));
+        }
+        assert_eq!(
+            repo.list_objects("textures/").await.unwrap(),
+            vec!["textures/hero.png"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_index_entry_for_another_key_is_rejected() {
+        let bucket = Arc::new(MockBackend::new());
+        let repo = hashed(&bucket, b"secret");
+        repo.put("objects/1", b"one").await.unwrap();
+
+        // The provider swaps in an entry naming a different key
+        let entry = format!("{}{}", INDEX_PREFIX, repo.hashed_key("objects/1"));
+        bucket.put(&entry, b"objects/2").await.unwrap();
+
+        let err = hashed(&bucket, b"secret")
+            .list_objects("")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("does not match"));
+    }
+}
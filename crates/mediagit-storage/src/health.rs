@@ -0,0 +1,267 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Health reports returned by [`StorageBackend::check_health`](crate::StorageBackend::check_health)
+//!
+//! A health check measures the round trip to the backend, then lists, writes,
+//! reads back and deletes a sentinel object. Each step is reported on its
+//! own, so credentials that can read but not write show up as a failed write
+//! rather than a generic error.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::{mock::MockBackend, StorageBackend};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let storage = MockBackend::new();
+//! let report = storage.check_health().await;
+//!
+//! assert!(report.is_healthy());
+//! assert!(report.latency().is_some());
+//! # }
+//! ```
+
+use crate::StorageError;
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Prefix of the sentinel objects health checks write and delete
+pub const HEALTH_CHECK_PREFIX: &str = "health-check/";
+
+/// Outcome of one step of a health check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// The step succeeded
+    Passed,
+    /// The step failed
+    Failed,
+    /// The backend refused the step for lack of permission
+    PermissionDenied,
+    /// The step wasn't attempted because an earlier one failed
+    Skipped,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckStatus::Passed => "ok",
+            CheckStatus::Failed => "failed",
+            CheckStatus::PermissionDenied => "permission denied",
+            CheckStatus::Skipped => "skipped",
+        })
+    }
+}
+
+/// One step of a health check
+#[derive(Debug, Clone)]
+pub struct HealthCheck {
+    /// What was checked: `latency`, `list`, `write`, `read` or `delete`
+    pub name: &'static str,
+
+    /// How it went
+    pub status: CheckStatus,
+
+    /// How long the step took
+    pub elapsed: Duration,
+
+    /// The error, if the step failed
+    pub error: Option<String>,
+}
+
+impl HealthCheck {
+    /// Time `step`, recording whether it succeeded
+    pub async fn run<T>(name: &'static str, step: impl Future<Output = anyhow::Result<T>>) -> Self {
+        let start = Instant::now();
+        let result = step.await;
+        let elapsed = start.elapsed();
+        match result {
+            Ok(_) => Self {
+                name,
+                status: CheckStatus::Passed,
+                elapsed,
+                error: None,
+            },
+            Err(e) => Self {
+                name,
+                status: if is_permission_error(&e) {
+                    CheckStatus::PermissionDenied
+                } else {
+                    CheckStatus::Failed
+                },
+                elapsed,
+                error: Some(format!("{:#}", e)),
+            },
+        }
+    }
+
+    /// A step that wasn't attempted
+    pub fn skipped(name: &'static str) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Skipped,
+            elapsed: Duration::ZERO,
+            error: None,
+        }
+    }
+
+    /// Whether the step succeeded
+    pub fn passed(&self) -> bool {
+        self.status == CheckStatus::Passed
+    }
+}
+
+/// The steps of a health check, in the order they ran
+#[derive(Debug, Clone, Default)]
+pub struct HealthReport {
+    /// Each step's outcome
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    /// Whether every step succeeded
+    pub fn is_healthy(&self) -> bool {
+        !self.checks.is_empty() && self.checks.iter().all(HealthCheck::passed)
+    }
+
+    /// Round trip time of a single request, if the backend answered
+    pub fn latency(&self) -> Option<Duration> {
+        self.check("latency")
+            .filter(|check| check.passed())
+            .map(|check| check.elapsed)
+    }
+
+    /// The step named `name`
+    pub fn check(&self, name: &str) -> Option<&HealthCheck> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+
+    /// Steps the backend refused for lack of permission
+    pub fn denied(&self) -> impl Iterator<Item = &HealthCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::PermissionDenied)
+    }
+}
+
+/// A fresh key under [`HEALTH_CHECK_PREFIX`], so concurrent checks don't
+/// touch each other's sentinels
+pub(crate) fn sentinel_key() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{}{:x}-{}", HEALTH_CHECK_PREFIX, nanos, std::process::id())
+}
+
+/// Whether `error` says the credentials lack permission
+///
+/// Backends report this differently: a [`StorageError`], an I/O error, or
+/// the service's own message, so the message is checked as a last resort.
+fn is_permission_error(error: &anyhow::Error) -> bool {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<StorageError>() {
+            if e.is_permission_denied() {
+                return true;
+            }
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            if e.kind() == std::io::ErrorKind::PermissionDenied {
+                return true;
+            }
+        }
+    }
+    let message = format!("{:#}", error).to_lowercase();
+    message.contains("permission denied")
+        || message.contains("accessdenied")
+        || message.contains("access denied")
+        || message.contains("forbidden")
+        || message.contains("authorizationfailure")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockBackend;
+    use crate::StorageBackend;
+    use async_trait::async_trait;
+
+    /// Reads succeed; writes are refused
+    #[derive(Debug)]
+    struct ReadOnly(MockBackend);
+
+    #[async_trait]
+    impl StorageBackend for ReadOnly {
+        async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+            self.0.get(key).await
+        }
+
+        async fn put(&self, key: &str, _data: &[u8]) -> anyhow::Result<()> {
+            Err(StorageError::permission_denied(key).into())
+        }
+
+        async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+            self.0.exists(key).await
+        }
+
+        async fn delete(&self, key: &str) -> anyhow::Result<()> {
+            Err(StorageError::permission_denied(key).into())
+        }
+
+        async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+            self.0.list_objects(prefix).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_healthy_backend_leaves_nothing_behind() {
+        let storage = MockBackend::new();
+        let report = storage.check_health().await;
+
+        assert!(report.is_healthy());
+        let names: Vec<_> = report.checks.iter().map(|check| check.name).collect();
+        assert_eq!(names, ["latency", "list", "write", "read", "delete"]);
+        assert!(storage
+            .list_objects(HEALTH_CHECK_PREFIX)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_backend_reports_denied_write() {
+        let report = ReadOnly(MockBackend::new()).check_health().await;
+
+        assert!(!report.is_healthy());
+        assert!(report.latency().is_some());
+        assert!(report.check("list").unwrap().passed());
+        assert_eq!(
+            report.check("write").unwrap().status,
+            CheckStatus::PermissionDenied
+        );
+        assert_eq!(report.check("read").unwrap().status, CheckStatus::Skipped);
+        assert_eq!(report.denied().count(), 1);
+    }
+
+    #[test]
+    fn test_permission_errors_are_recognized() {
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(is_permission_error(&anyhow::Error::from(denied)));
+        assert!(is_permission_error(&anyhow::anyhow!(
+            "Failed to put object: service error: AccessDenied"
+        )));
+        assert!(!is_permission_error(&anyhow::anyhow!("connection reset")));
+    }
+}
@@ -0,0 +1,423 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Structured tracing of backend operations
+//!
+//! [`InstrumentedBackend`] runs every operation on the wrapped backend inside
+//! a `storage_op` span at debug level. The span carries the same fields
+//! whichever backend is underneath, so JSON logs can be filtered with queries
+//! like `backend=s3 operation=put duration_ms>1000`:
+//!
+//! | Field | Type | Meaning |
+//! |-------|------|---------|
+//! | `backend` | string | `filesystem`, `s3`, `azure`, `gcs`, ... |
+//! | `operation` | string | `get`, `get_mapped`, `get_range`, `get_stream`, `put`, `put_stream`, `exists`, `delete`, `list_objects`, `list_prefixes`, `usage`, `apply_expiry`, `sweep_expired`, `modified` |
+//! | `key` | string | Object key, or the prefix for `list_objects`, `list_prefixes` and `usage` |
+//! | `bytes` | integer | Bytes read or written; `0` for operations that move no data |
+//! | `duration_ms` | integer | Wall-clock time of the operation in milliseconds |
+//! | `outcome` | string | `ok` or `error` |
+//!
+//! `bytes`, `duration_ms` and `outcome` are recorded when the operation
+//! finishes, so they appear on the span's close event. These names are part
+//! of the log format and should not be renamed. The data of `get_stream` and
+//! `put_stream` is not counted, so they record `0` bytes, and a `get_stream`
+//! span ends once the stream is open rather than when it is drained.
+//! `list_objects_stream` is passed through without a span.
+//!
+//! # Metrics
+//!
+//! With [`InstrumentedBackend::with_metrics`], every traced operation is
+//! also reported to a [`MetricsSink`] as an [`OperationRecord`] carrying the
+//! same backend, operation, byte count, duration and outcome as the span.
+//! `mediagit_metrics::MetricsRegistry` implements the trait, exporting the
+//! records as Prometheus counters and latency histograms labelled by
+//! backend and operation.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::{mock::MockBackend, InstrumentedBackend, StorageBackend};
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let storage = InstrumentedBackend::new(Arc::new(MockBackend::new()), "mock");
+//!
+//! storage.put("abc123", b"data").await?;
+//! assert_eq!(storage.backend(), "mock");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    BackendCapabilities, DeleteFailure, ExpiryPolicy, KeyStream, MmapOrVec, ObjectMeta,
+    ObjectStream, StorageBackend, StorageUsage, SweepReport,
+};
+use async_trait::async_trait;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{field, Instrument};
+
+/// One finished backend operation, as reported to a [`MetricsSink`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperationRecord<'a> {
+    /// Name of the backend, as passed to [`InstrumentedBackend::new`]
+    pub backend: &'a str,
+    /// Operation name, the same as the span's `operation` field
+    pub operation: &'a str,
+    /// Bytes read or written; `0` for failed operations and those that
+    /// move no data
+    pub bytes: u64,
+    /// Wall-clock time of the operation
+    pub duration: Duration,
+    /// Whether the operation succeeded
+    pub success: bool,
+}
+
+/// Receives a record of every operation an [`InstrumentedBackend`] runs
+///
+/// Called on the task that ran the operation, so implementations should
+/// only update counters and return.
+pub trait MetricsSink: Send + Sync {
+    /// Record a finished operation
+    fn record(&self, record: &OperationRecord<'_>);
+}
+
+/// Storage backend wrapper that traces each operation with standard fields
+#[derive(Clone)]
+pub struct InstrumentedBackend {
+    inner: Arc<dyn StorageBackend>,
+    backend: &'static str,
+    metrics: Option<Arc<dyn MetricsSink>>,
+}
+
+impl fmt::Debug for InstrumentedBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InstrumentedBackend")
+            .field("inner", &self.inner)
+            .field("backend", &self.backend)
+            .field("metrics", &self.metrics.is_some())
+            .finish()
+    }
+}
+
+impl InstrumentedBackend {
+    /// Wrap `inner`, reporting its operations under the name `backend`
+    pub fn new(inner: Arc<dyn StorageBackend>, backend: &'static str) -> Self {
+        Self {
+            inner,
+            backend,
+            metrics: None,
+        }
+    }
+
+    /// Also report every operation to `sink`
+    pub fn with_metrics(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = Some(sink);
+        self
+    }
+
+    /// The name reported in the `backend` field
+    pub fn backend(&self) -> &'static str {
+        self.backend
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Arc<dyn StorageBackend> {
+        &self.inner
+    }
+
+    /// Run `op` inside a `storage_op` span, recording how it went
+    async fn traced<T>(
+        &self,
+        operation: &'static str,
+        key: &str,
+        bytes: impl FnOnce(&T) -> usize,
+        op: impl Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        let span = tracing::debug_span!(
+            "storage_op",
+            backend = self.backend,
+            operation,
+            key,
+            bytes = field::Empty,
+            duration_ms = field::Empty,
+            outcome = field::Empty,
+        );
+        let started = Instant::now();
+        let result = op.instrument(span.clone()).await;
+
+        let duration = started.elapsed();
+        let bytes = result.as_ref().map_or(0, |value| bytes(value) as u64);
+        span.record("duration_ms", duration.as_millis() as u64);
+        span.record("bytes", bytes);
+        span.record("outcome", if result.is_ok() { "ok" } else { "error" });
+
+        if let Some(metrics) = &self.metrics {
+            metrics.record(&OperationRecord {
+                backend: self.backend,
+                operation,
+                bytes,
+                duration,
+                success: result.is_ok(),
+            });
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl StorageBackend for InstrumentedBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.traced("get", key, Vec::len, self.inner.get(key)).await
+    }
+
+    async fn get_mapped(&self, key: &str) -> anyhow::Result<MmapOrVec> {
+        self.traced(
+            "get_mapped",
+            key,
+            |data: &MmapOrVec| data.as_ref().len(),
+            self.inner.get_mapped(key),
+        )
+        .await
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<std::time::SystemTime>> {
+        self.traced("modified", key, |_| 0, self.inner.modified(key))
+            .await
+    }
+
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        self.traced("head", key, |_| 0, self.inner.head(key)).await
+    }
+
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        self.traced(
+            "get_range",
+            key,
+            Vec::len,
+            self.inner.get_range(key, offset, len),
+        )
+        .await
+    }
+
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        self.traced("get_stream", key, |_| 0, self.inner.get_stream(key))
+            .await
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        self.traced("put", key, |_| data.len(), self.inner.put(key, data))
+            .await
+    }
+
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        self.traced(
+            "put_if_absent",
+            key,
+            |&written| if written { data.len() } else { 0 },
+            self.inner.put_if_absent(key, data),
+        )
+        .await
+    }
+
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        self.traced(
+            "put_if_match",
+            key,
+            |&written| if written { data.len() } else { 0 },
+            self.inner.put_if_match(key, data, etag),
+        )
+        .await
+    }
+
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        let summary = format!("{} -> {}", src_key, dst_key);
+        self.traced("copy", &summary, |_| 0, self.inner.copy(src_key, dst_key))
+            .await
+    }
+
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> anyhow::Result<()> {
+        self.traced("put_stream", key, |_| 0, self.inner.put_stream(key, data))
+            .await
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        self.traced("exists", key, |_| 0, self.inner.exists(key))
+            .await
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.traced("delete", key, |_| 0, self.inner.delete(key))
+            .await
+    }
+
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        let summary = format!("{} keys", keys.len());
+        self.traced("delete_many", &summary, |_| 0, self.inner.delete_many(keys))
+            .await
+    }
+
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        self.traced(
+            "list_objects",
+            prefix,
+            |_| 0,
+            self.inner.list_objects(prefix),
+        )
+        .await
+    }
+
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        self.inner.list_objects_stream(prefix)
+    }
+
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        self.traced(
+            "list_prefixes",
+            prefix,
+            |_| 0,
+            self.inner.list_prefixes(prefix, delimiter),
+        )
+        .await
+    }
+
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        self.traced("usage", prefix, |_| 0, self.inner.usage(prefix))
+            .await
+    }
+
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> anyhow::Result<bool> {
+        self.traced("apply_expiry", "", |_| 0, self.inner.apply_expiry(policy))
+            .await
+    }
+
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport> {
+        self.traced("sweep_expired", "", |_| 0, self.inner.sweep_expired(policy))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockBackend;
+    use std::io::Write;
+    use std::sync::Mutex;
+    use tracing_subscriber::fmt::format::FmtSpan;
+
+    /// Log writer that collects everything written to it
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_log_fields() {
+        let logs = Captured::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let storage = InstrumentedBackend::new(Arc::new(MockBackend::new()), "s3");
+        storage.put("objects/ab/cdef", b"frame data").await.unwrap();
+        storage.get("objects/ab/cdef").await.unwrap();
+        assert!(storage.get("objects/missing").await.is_err());
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let spans: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .filter(|line| line["span"]["name"] == "storage_op")
+            .map(|line| line["span"].clone())
+            .collect();
+        assert_eq!(spans.len(), 3);
+
+        for span in &spans {
+            assert_eq!(span["backend"], "s3");
+            assert!(span["key"].is_string());
+            assert!(span["bytes"].is_u64());
+            assert!(span["duration_ms"].is_u64());
+        }
+        assert_eq!(spans[0]["operation"], "put");
+        assert_eq!(spans[0]["bytes"], 10);
+        assert_eq!(spans[0]["outcome"], "ok");
+        assert_eq!(spans[1]["operation"], "get");
+        assert_eq!(spans[1]["key"], "objects/ab/cdef");
+        assert_eq!(spans[1]["bytes"], 10);
+        assert_eq!(spans[2]["outcome"], "error");
+        assert_eq!(spans[2]["bytes"], 0);
+    }
+
+    /// Sink that keeps `(backend, operation, bytes, success)` of each record
+    #[derive(Default)]
+    struct Recorded(Mutex<Vec<(String, String, u64, bool)>>);
+
+    impl MetricsSink for Recorded {
+        fn record(&self, record: &OperationRecord<'_>) {
+            self.0.lock().unwrap().push((
+                record.backend.to_string(),
+                record.operation.to_string(),
+                record.bytes,
+                record.success,
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics_sink_receives_each_operation() {
+        let sink = Arc::new(Recorded::default());
+        let storage = InstrumentedBackend::new(Arc::new(MockBackend::new()), "gcs")
+            .with_metrics(sink.clone());
+
+        storage.put("objects/ab/cdef", b"frame data").await.unwrap();
+        storage.get("objects/ab/cdef").await.unwrap();
+        assert!(storage.get("objects/missing").await.is_err());
+        storage.list_objects("objects/").await.unwrap();
+
+        let records = sink.0.lock().unwrap();
+        let expected = [
+            ("gcs", "put", 10, true),
+            ("gcs", "get", 10, true),
+            ("gcs", "get", 0, false),
+            ("gcs", "list_objects", 0, true),
+        ];
+        assert_eq!(records.len(), expected.len());
+        for (record, (backend, operation, bytes, success)) in records.iter().zip(expected) {
+            assert_eq!(record.0, backend);
+            assert_eq!(record.1, operation);
+            assert_eq!(record.2, bytes);
+            assert_eq!(record.3, success);
+        }
+    }
+}
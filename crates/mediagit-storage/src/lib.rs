@@ -21,6 +21,7 @@
 //! - Google Cloud Storage
 //! - MinIO / S3-compatible
 //! - Backblaze B2 / DigitalOcean Spaces
+//! - SFTP servers, such as SSH-accessible NAS boxes
 //!
 //! # Architecture
 //!
@@ -107,26 +108,87 @@
 pub mod azure;
 pub mod b2_spaces;
 pub mod cache;
+pub mod capabilities;
+pub mod checksum;
+pub mod compressed;
+pub mod encrypted;
 pub mod error;
+pub mod expiry;
+pub mod factory;
 #[cfg(feature = "gcs")]
 pub mod gcs;
+pub mod hashed_keys;
+pub mod health;
+pub mod instrument;
+pub mod limit;
 pub mod local;
+pub mod meta;
 pub mod minio;
 pub mod mock;
+pub mod namespace;
+pub mod proxy;
+pub mod quota;
+pub mod replicated;
+pub mod retry;
+pub mod routing;
 pub mod s3;
+#[cfg(feature = "sftp")]
+pub mod sftp;
+pub mod sse;
+pub mod storage_class;
+pub mod stream;
+pub mod tiered;
+pub mod timeouts;
+pub mod tls;
+pub mod upload;
+pub mod usage;
 
 use async_trait::async_trait;
+use std::collections::BTreeSet;
 use std::fmt::Debug;
 
 #[cfg(feature = "azure")]
 pub use azure::AzureBackend;
 pub use b2_spaces::B2SpacesBackend;
-pub use error::{StorageError, StorageResult};
+pub use cache::CachedBackend;
+pub use capabilities::BackendCapabilities;
+pub use checksum::{Checksum, ChecksumAlgorithm};
+pub use compressed::CompressedBackend;
+pub use encrypted::EncryptedBackend;
+pub use error::{DeleteFailure, StorageError, StorageResult};
+pub use expiry::{ExpiryPolicy, ExpiryRule, SweepReport};
+pub use factory::{BackendFactory, BackendProvider};
 #[cfg(feature = "gcs")]
 pub use gcs::GcsBackend;
-pub use local::LocalBackend;
+pub use hashed_keys::HashedKeyBackend;
+pub use health::{CheckStatus, HealthCheck, HealthReport};
+pub use instrument::{InstrumentedBackend, MetricsSink, OperationRecord};
+pub use limit::{shared_limiter, ConcurrencyLimitedBackend};
+pub use local::{LocalBackend, MmapOrVec};
+pub use meta::ObjectMeta;
 pub use minio::MinIOBackend;
+pub use namespace::NamespacedBackend;
+pub use proxy::ProxySettings;
+pub use quota::QuotaBackend;
+pub use replicated::ReplicatedBackend;
+pub use retry::RetryPolicy;
+pub use routing::RoutingBackend;
 pub use s3::S3Backend;
+#[cfg(feature = "sftp")]
+pub use sftp::SftpBackend;
+pub use storage_class::{StorageClass, StorageClassPolicy};
+pub use stream::{KeyStream, ObjectStream};
+pub use tiered::TieredBackend;
+pub use timeouts::TimeoutSettings;
+pub use tls::TlsSettings;
+pub use upload::{shared_upload_pool, PooledUploadBackend, UploadLimits, UploadPool};
+pub use usage::StorageUsage;
+
+/// Deletes the default [`StorageBackend::delete_many`] runs at once
+const DELETE_CONCURRENCY: usize = 32;
+
+/// HEAD requests the default [`StorageBackend::usage`] runs at once
+const HEAD_CONCURRENCY: usize = 32;
 
 /// Storage backend trait for object storage operations
 ///
@@ -367,6 +429,573 @@ pub trait StorageBackend: Send + Sync + Debug {
     /// # }
     /// ```
     async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>>;
+
+    /// Stream the keys that start with `prefix`
+    ///
+    /// [`list_objects`](Self::list_objects) gathers and sorts every key before
+    /// returning, which takes a lot of memory for millions of objects. This
+    /// yields keys as the backend lists them instead: a page at a time on
+    /// cloud backends, a directory at a time on local disk and SFTP. Keys
+    /// arrive in no particular order, and an error ends the stream.
+    ///
+    /// The default lists everything with [`list_objects`](Self::list_objects)
+    /// and yields the keys from memory.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, mock::MockBackend};
+    /// # use futures::TryStreamExt;
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = MockBackend::new();
+    /// storage.put("images/photo1.jpg", b"data").await?;
+    ///
+    /// let mut keys = storage.list_objects_stream("images/");
+    /// while let Some(key) = keys.try_next().await? {
+    ///     println!("{}", key);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        use futures::{StreamExt, TryStreamExt};
+
+        let prefix = prefix.to_string();
+        futures::stream::once(async move { self.list_objects(&prefix).await })
+            .map_ok(|keys| futures::stream::iter(keys.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed()
+    }
+
+    /// List the "directories" under `prefix`
+    ///
+    /// Returns each distinct prefix that keys under `prefix` share up to and
+    /// including the first `delimiter` after `prefix`, sorted, like S3's
+    /// CommonPrefixes. With `delimiter` "/", listing "" finds `packs/` and
+    /// `manifests/` without listing every object beneath them. Keys with no
+    /// delimiter after `prefix` contribute nothing.
+    ///
+    /// S3-compatible backends, Azure and GCS have the service group keys,
+    /// and Local only walks the directories that can hold matching keys. The
+    /// default groups the keys from
+    /// [`list_objects_stream`](Self::list_objects_stream), holding only the
+    /// prefixes in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `delimiter` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, mock::MockBackend};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = MockBackend::new();
+    /// storage.put("packs/pack-1.pack", b"data").await?;
+    /// storage.put("manifests/abc", b"data").await?;
+    /// storage.put("abc123", b"data").await?;
+    ///
+    /// let dirs = storage.list_prefixes("", "/").await?;
+    /// assert_eq!(dirs, ["manifests/", "packs/"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        use futures::TryStreamExt;
+
+        check_delimiter(delimiter)?;
+        let mut prefixes = BTreeSet::new();
+        let mut keys = self.list_objects_stream(prefix);
+        while let Some(key) = keys.try_next().await? {
+            if let Some(common) = common_prefix(&key, prefix, delimiter) {
+                if !prefixes.contains(common) {
+                    prefixes.insert(common.to_string());
+                }
+            }
+        }
+        Ok(prefixes.into_iter().collect())
+    }
+
+    /// Count the objects under `prefix` and the bytes they take up
+    ///
+    /// Sizes are as stored. S3-compatible backends, Azure and GCS read them
+    /// from the listing itself, and Local from the files. The default runs
+    /// up to 32 [`head`](Self::head) calls at a time over
+    /// [`list_objects_stream`](Self::list_objects_stream).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, mock::MockBackend};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = MockBackend::new();
+    /// storage.put("packs/pack-1.pack", b"pack data").await?;
+    ///
+    /// let usage = storage.usage("packs/").await?;
+    /// println!("{} packs, {} bytes", usage.object_count, usage.total_bytes);
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        use futures::TryStreamExt;
+
+        self.list_objects_stream(prefix)
+            .map_ok(|key| async move { self.head(&key).await })
+            .try_buffer_unordered(HEAD_CONCURRENCY)
+            .try_fold(StorageUsage::default(), |mut usage, meta| async move {
+                usage.add(meta.size);
+                Ok(usage)
+            })
+            .await
+    }
+
+    /// Install `policy` as the service's own lifecycle rules
+    ///
+    /// Returns `true` when the service will now expire objects and abort
+    /// abandoned uploads itself, and `false` when it has no such rules and
+    /// [`sweep_expired`](Self::sweep_expired) has to be run instead. S3,
+    /// MinIO and B2/Spaces replace the lifecycle rules a previous call
+    /// installed for the same [`scope`](ExpiryPolicy::scope) and keep the
+    /// bucket's other rules. GCS rules carry no id to tell repositories'
+    /// rules apart, so GCS, like the default, installs nothing.
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> anyhow::Result<bool> {
+        let _ = policy;
+        Ok(false)
+    }
+
+    /// Delete what has outlived `policy`
+    ///
+    /// Objects under a rule's prefix are deleted once they are older than its
+    /// TTL, by the modification time [`head`](Self::head) reports. With
+    /// [`incomplete_uploads`](ExpiryPolicy::incomplete_uploads) set, S3,
+    /// MinIO and B2/Spaces also abort multipart uploads started longer ago,
+    /// and Local removes temporary files left by interrupted writes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{ExpiryPolicy, StorageBackend, mock::MockBackend};
+    /// # use std::time::Duration;
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = MockBackend::new();
+    /// let policy = ExpiryPolicy::new().expire("tmp/", Duration::from_secs(3600));
+    ///
+    /// let report = storage.sweep_expired(&policy).await?;
+    /// println!("{} expired objects removed", report.expired.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport> {
+        expiry::sweep_rules(self, policy).await
+    }
+
+    /// Delete many objects, returning the keys that could not be deleted
+    ///
+    /// As with [`delete`](Self::delete), keys that don't exist count as
+    /// deleted. A key that fails doesn't stop the others; each failure is
+    /// reported with its own error, in no particular order. `Err` is kept
+    /// for problems with the request as a whole, such as an invalid key.
+    ///
+    /// S3-compatible backends send DeleteObjects requests of up to 1000 keys
+    /// each. The default runs up to 32 [`delete`](Self::delete) calls at a
+    /// time.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, mock::MockBackend};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = MockBackend::new();
+    /// storage.put("a", b"1").await?;
+    /// storage.put("b", b"2").await?;
+    ///
+    /// let failures = storage.delete_many(&["a".into(), "b".into()]).await?;
+    /// for failure in &failures {
+    ///     eprintln!("could not delete {}", failure);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        use futures::StreamExt;
+
+        let deletes: Vec<_> = keys
+            .iter()
+            .map(|key| async move { (key, self.delete(key).await) })
+            .collect();
+        let results: Vec<_> = futures::stream::iter(deletes)
+            .buffer_unordered(DELETE_CONCURRENCY)
+            .collect()
+            .await;
+        Ok(results
+            .into_iter()
+            .filter_map(|(key, result)| {
+                result.err().map(|error| DeleteFailure {
+                    key: key.clone(),
+                    error,
+                })
+            })
+            .collect())
+    }
+
+    /// Retrieve an object, memory-mapping it where the backend supports it
+    ///
+    /// Backends on a local filesystem map large objects instead of copying
+    /// them onto the heap. The default reads the object with [`get`](Self::get),
+    /// so callers can use this unconditionally.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, mock::MockBackend};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = MockBackend::new();
+    /// storage.put("video.mp4", b"content").await?;
+    ///
+    /// let data = storage.get_mapped("video.mp4").await?;
+    /// assert_eq!(data.as_ref(), b"content");
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn get_mapped(&self, key: &str) -> anyhow::Result<MmapOrVec> {
+        Ok(MmapOrVec::Vec(self.get(key).await?))
+    }
+
+    /// Retrieve `len` bytes of an object, starting `offset` bytes in
+    ///
+    /// A range running past the end of the object is cut short, so the
+    /// result may hold fewer than `len` bytes, and none if `offset` is at or
+    /// past the end. Backends that report [`BackendCapabilities::range_reads`]
+    /// fetch only the requested bytes; the default reads the whole object
+    /// with [`get`](Self::get) and slices it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, mock::MockBackend};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = MockBackend::new();
+    /// storage.put("video.mp4", b"ftypmoov").await?;
+    ///
+    /// assert_eq!(storage.get_range("video.mp4", 4, 4).await?, b"moov");
+    /// assert_eq!(storage.get_range("video.mp4", 6, 100).await?, b"ov");
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        let mut data = self.get(key).await?;
+        let (start, end) = range_bounds(data.len() as u64, offset, len);
+        data.truncate(end);
+        data.drain(..start);
+        Ok(data)
+    }
+
+    /// Retrieve an object as a stream of chunks
+    ///
+    /// Lets callers process objects far larger than memory. Backends that
+    /// report [`BackendCapabilities::streaming`] read from disk or the
+    /// network as the stream is polled; the default reads the whole object
+    /// with [`get`](Self::get) and yields it as one chunk.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, LocalBackend};
+    /// # use futures::StreamExt;
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = LocalBackend::new("/tmp/mediagit").await?;
+    /// let mut stream = storage.get_stream("video.mp4").await?;
+    /// while let Some(chunk) = stream.next().await {
+    ///     println!("read {} bytes", chunk?.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        Ok(stream::once(self.get(key).await?))
+    }
+
+    /// Store an object read from a stream of chunks
+    ///
+    /// The object only becomes visible once the stream ends without error.
+    /// Backends that report [`BackendCapabilities::streaming`] write chunks
+    /// as they arrive; the default buffers the whole stream and calls
+    /// [`put`](Self::put).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{stream, StorageBackend, LocalBackend};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = LocalBackend::new("/tmp/mediagit").await?;
+    /// let file = tokio::fs::File::open("render.mov").await?;
+    /// storage.put_stream("render.mov", stream::from_reader(file)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> anyhow::Result<()> {
+        let data = stream::collect(data).await?;
+        self.put(key, &data).await
+    }
+
+    /// Store an object unless one already exists under `key`
+    ///
+    /// Returns `true` if `data` was written and `false` if the key was
+    /// already taken, in which case the stored object is left alone. Since
+    /// objects are content-addressed, an existing object already holds the
+    /// same data and need not be uploaded again.
+    ///
+    /// S3-compatible backends send the write with `If-None-Match: *` and
+    /// LocalBackend refuses to replace an existing file, so concurrent
+    /// writers cannot both win. The default checks [`exists`](Self::exists)
+    /// before writing, which can race with another writer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, mock::MockBackend};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = MockBackend::new();
+    ///
+    /// assert!(storage.put_if_absent("abc123", b"content").await?);
+    /// assert!(!storage.put_if_absent("abc123", b"content").await?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        if self.exists(key).await? {
+            return Ok(false);
+        }
+        self.put(key, data).await?;
+        Ok(true)
+    }
+
+    /// Replace an object only if it is still the version tagged `etag`
+    ///
+    /// `etag` comes from [`head`](Self::head). Returns `true` if `data` was
+    /// written and `false` if the object has changed or been deleted since,
+    /// which lets refs and other mutable objects be updated without losing
+    /// a concurrent write.
+    ///
+    /// Only backends reporting
+    /// [`compare_and_swap`](BackendCapabilities::compare_and_swap) support
+    /// this; the default returns an error.
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        let _ = (data, etag);
+        Err(anyhow::anyhow!(
+            "conditional writes are not supported by this backend: {}",
+            key
+        ))
+    }
+
+    /// Copy the object at `src_key` to `dst_key`, replacing any object there
+    ///
+    /// Backends reporting
+    /// [`server_side_copy`](BackendCapabilities::server_side_copy) copy
+    /// within the service (S3 CopyObject, Azure Copy Blob, GCS rewrite), so
+    /// repacking and migration can relocate objects without downloading and
+    /// uploading them again. The default reads the object and writes it back.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, mock::MockBackend};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = MockBackend::new();
+    /// storage.put("loose/abc123", b"content").await?;
+    ///
+    /// storage.copy("loose/abc123", "objects/abc123").await?;
+    /// assert_eq!(storage.get("objects/abc123").await?, b"content");
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        let data = self.get(src_key).await?;
+        self.put(dst_key, &data).await
+    }
+
+    /// When an object was last written, if the backend tracks it
+    ///
+    /// Garbage collection uses this to leave recently written objects alone,
+    /// since a concurrent push may not have updated its refs yet. The default
+    /// returns `None`, meaning the time is unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, LocalBackend};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = LocalBackend::new("/tmp/mediagit").await?;
+    /// storage.put("video.mp4", b"content").await?;
+    ///
+    /// assert!(storage.modified("video.mp4").await?.is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<std::time::SystemTime>> {
+        let _ = key;
+        Ok(None)
+    }
+
+    /// Size and other metadata of an object, without its content
+    ///
+    /// Cloud backends send a HEAD request and local ones read file metadata.
+    /// The default reads the whole object with [`get`](Self::get) to learn
+    /// its size and asks [`modified`](Self::modified) when it was written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the object doesn't exist (with "object not found"
+    /// in the message) or an I/O error occurs.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, LocalBackend};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = LocalBackend::new("/tmp/mediagit").await?;
+    /// storage.put("video.mp4", b"content").await?;
+    ///
+    /// let meta = storage.head("video.mp4").await?;
+    /// assert_eq!(meta.size, 7);
+    /// assert!(meta.modified.is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        let size = self.get(key).await?.len() as u64;
+        Ok(ObjectMeta {
+            modified: self.modified(key).await?,
+            ..ObjectMeta::with_size(size)
+        })
+    }
+
+    /// Optional features this backend supports
+    ///
+    /// Lets callers pick a code path without trying an operation first.
+    /// The default reports none.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, LocalBackend};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = LocalBackend::new("/tmp/mediagit").await?;
+    /// assert!(storage.capabilities().mmap);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities::default()
+    }
+
+    /// Probe whether the backend is reachable and usable
+    ///
+    /// Times a single existence check as a latency probe, lists the
+    /// sentinel prefix, then writes, reads back and deletes a sentinel
+    /// object under [`HEALTH_CHECK_PREFIX`](health::HEALTH_CHECK_PREFIX).
+    /// Failures are recorded in the report rather than returned, and steps
+    /// refused for lack of permission are told apart from other failures.
+    /// Once the write fails, the read and delete are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use mediagit_storage::{StorageBackend, LocalBackend};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = LocalBackend::new("/tmp/mediagit").await?;
+    /// let report = storage.check_health().await;
+    ///
+    /// for check in &report.checks {
+    ///     println!("{}: {} ({:?})", check.name, check.status, check.elapsed);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn check_health(&self) -> HealthReport {
+        let key = health::sentinel_key();
+        let data = key.as_bytes().to_vec();
+        let mut report = HealthReport::default();
+
+        report
+            .checks
+            .push(HealthCheck::run("latency", self.exists(&key)).await);
+        report
+            .checks
+            .push(HealthCheck::run("list", self.list_objects(health::HEALTH_CHECK_PREFIX)).await);
+
+        let write = HealthCheck::run("write", self.put(&key, &data)).await;
+        let written = write.passed();
+        report.checks.push(write);
+        if !written {
+            report.checks.push(HealthCheck::skipped("read"));
+            report.checks.push(HealthCheck::skipped("delete"));
+            return report;
+        }
+
+        let read = async {
+            let read = self.get(&key).await?;
+            anyhow::ensure!(
+                read == data,
+                "read back {} bytes that differ from the {} written",
+                read.len(),
+                data.len()
+            );
+            Ok(())
+        };
+        report.checks.push(HealthCheck::run("read", read).await);
+
+        let delete = async {
+            self.delete(&key).await?;
+            anyhow::ensure!(
+                !self.exists(&key).await?,
+                "object still exists after delete"
+            );
+            Ok(())
+        };
+        report.checks.push(HealthCheck::run("delete", delete).await);
+        report
+    }
+}
+
+/// Byte positions `[start, end)` of a range within an object of `size` bytes
+///
+/// Clamps the range to the object, so both are at most `size`.
+pub(crate) fn range_bounds(size: u64, offset: u64, len: u64) -> (usize, usize) {
+    let start = offset.min(size);
+    let end = offset.saturating_add(len).min(size);
+    (start as usize, end as usize)
+}
+
+/// Reject the empty delimiter, which would make every key its own prefix
+pub(crate) fn check_delimiter(delimiter: &str) -> anyhow::Result<()> {
+    if delimiter.is_empty() {
+        anyhow::bail!("delimiter cannot be empty");
+    }
+    Ok(())
+}
+
+/// The part of `key` up to and including the first `delimiter` after
+/// `prefix`, if `key` is under `prefix` and has one
+pub(crate) fn common_prefix<'k>(key: &'k str, prefix: &str, delimiter: &str) -> Option<&'k str> {
+    let rest = key.strip_prefix(prefix)?;
+    let end = rest.find(delimiter)? + delimiter.len();
+    Some(&key[..prefix.len() + end])
 }
 
 #[cfg(test)]
@@ -384,4 +1013,53 @@ mod tests {
         // Verify the trait can be used as a trait object
         fn _check_object_safe(_: &dyn StorageBackend) {}
     }
+
+    #[test]
+    fn range_bounds_clamp_to_object() {
+        assert_eq!(range_bounds(10, 2, 3), (2, 5));
+        assert_eq!(range_bounds(10, 8, 5), (8, 10));
+        assert_eq!(range_bounds(10, 12, 5), (10, 10));
+        assert_eq!(range_bounds(10, 4, u64::MAX), (4, 10));
+    }
+
+    #[test]
+    fn common_prefix_stops_at_first_delimiter() {
+        assert_eq!(common_prefix("packs/a.pack", "", "/"), Some("packs/"));
+        assert_eq!(
+            common_prefix("refs/heads/main", "refs/", "/"),
+            Some("refs/heads/")
+        );
+        assert_eq!(common_prefix("abc123", "", "/"), None);
+        assert_eq!(common_prefix("refs/heads/main", "packs/", "/"), None);
+        assert_eq!(common_prefix("a::b::c", "a::", "::"), Some("a::b::"));
+    }
+
+    #[tokio::test]
+    async fn list_prefixes_groups_keys() {
+        let storage = mock::MockBackend::new();
+        for key in [
+            "packs/1.pack",
+            "packs/2.pack",
+            "refs/heads/main",
+            "refs/tags/v1",
+            "abc",
+        ] {
+            storage.put(key, b"data").await.unwrap();
+        }
+
+        assert_eq!(
+            storage.list_prefixes("", "/").await.unwrap(),
+            ["packs/", "refs/"]
+        );
+        assert_eq!(
+            storage.list_prefixes("refs/", "/").await.unwrap(),
+            ["refs/heads/", "refs/tags/"]
+        );
+        assert!(storage
+            .list_prefixes("packs/", "/")
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(storage.list_prefixes("", "").await.is_err());
+    }
 }
@@ -0,0 +1,325 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Process-wide limit on concurrent backend operations
+//!
+//! [`ConcurrencyLimitedBackend`] holds a permit from a shared
+//! [`Semaphore`] for the duration of every operation on the wrapped backend.
+//! Backends wrapped with the same semaphore — typically the one from
+//! [`shared_limiter`] — draw from one pool, so a push, a gc and a background
+//! compaction running at once still keep the total load on S3 or MinIO under
+//! the configured limit instead of each adding their own concurrency.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::{mock::MockBackend, ConcurrencyLimitedBackend, StorageBackend};
+//! use std::sync::Arc;
+//! use tokio::sync::Semaphore;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let permits = Arc::new(Semaphore::new(8));
+//! let bucket = Arc::new(MockBackend::new());
+//! let storage = ConcurrencyLimitedBackend::new(bucket, permits.clone());
+//!
+//! storage.put("abc123", b"data").await?;
+//! assert_eq!(permits.available_permits(), 8);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    BackendCapabilities, DeleteFailure, ExpiryPolicy, KeyStream, MmapOrVec, ObjectMeta,
+    ObjectStream, StorageBackend, StorageUsage, SweepReport,
+};
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::debug;
+
+static SHARED_LIMITER: OnceLock<(usize, Arc<Semaphore>)> = OnceLock::new();
+
+/// The process-wide operation limiter, created with `max_ops` permits
+///
+/// The first call decides the size; later calls return the same semaphore
+/// whatever `max_ops` they pass, so every backend in the process shares one
+/// pool of permits.
+pub fn shared_limiter(max_ops: usize) -> Arc<Semaphore> {
+    let (size, permits) =
+        SHARED_LIMITER.get_or_init(|| (max_ops, Arc::new(Semaphore::new(max_ops))));
+    if *size != max_ops {
+        debug!(
+            "Storage operation limit already set to {}, ignoring {}",
+            size, max_ops
+        );
+    }
+    permits.clone()
+}
+
+/// Storage backend wrapper that runs each operation under a shared permit
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimitedBackend {
+    inner: Arc<dyn StorageBackend>,
+    permits: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimitedBackend {
+    /// Wrap `inner` so each operation holds one of `permits` while it runs
+    pub fn new(inner: Arc<dyn StorageBackend>, permits: Arc<Semaphore>) -> Self {
+        Self { inner, permits }
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Arc<dyn StorageBackend> {
+        &self.inner
+    }
+
+    async fn permit(&self) -> anyhow::Result<SemaphorePermit<'_>> {
+        self.permits
+            .acquire()
+            .await
+            .map_err(|_| anyhow::anyhow!("storage operation limiter was closed"))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for ConcurrencyLimitedBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        let _permit = self.permit().await?;
+        self.inner.get(key).await
+    }
+
+    async fn get_mapped(&self, key: &str) -> anyhow::Result<MmapOrVec> {
+        let _permit = self.permit().await?;
+        self.inner.get_mapped(key).await
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<std::time::SystemTime>> {
+        let _permit = self.permit().await?;
+        self.inner.modified(key).await
+    }
+
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        let _permit = self.permit().await?;
+        self.inner.head(key).await
+    }
+
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        let _permit = self.permit().await?;
+        self.inner.get_range(key, offset, len).await
+    }
+
+    /// Holds the permit until the stream is dropped, since it keeps a
+    /// connection or file open while it is read
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| anyhow::anyhow!("storage operation limiter was closed"))?;
+        let stream = self.inner.get_stream(key).await?;
+        Ok(Box::pin(stream.map(move |chunk| {
+            let _held = &permit;
+            chunk
+        })))
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        let _permit = self.permit().await?;
+        self.inner.put(key, data).await
+    }
+
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        let _permit = self.permit().await?;
+        self.inner.put_if_absent(key, data).await
+    }
+
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        let _permit = self.permit().await?;
+        self.inner.put_if_match(key, data, etag).await
+    }
+
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        let _permit = self.permit().await?;
+        self.inner.copy(src_key, dst_key).await
+    }
+
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> anyhow::Result<()> {
+        let _permit = self.permit().await?;
+        self.inner.put_stream(key, data).await
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        let _permit = self.permit().await?;
+        self.inner.exists(key).await
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        let _permit = self.permit().await?;
+        self.inner.delete(key).await
+    }
+
+    /// Counts as one operation, so backends that batch deletes keep doing so
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        let _permit = self.permit().await?;
+        self.inner.delete_many(keys).await
+    }
+
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let _permit = self.permit().await?;
+        self.inner.list_objects(prefix).await
+    }
+
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        let _permit = self.permit().await?;
+        self.inner.list_prefixes(prefix, delimiter).await
+    }
+
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        let _permit = self.permit().await?;
+        self.inner.usage(prefix).await
+    }
+
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> anyhow::Result<bool> {
+        let _permit = self.permit().await?;
+        self.inner.apply_expiry(policy).await
+    }
+
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport> {
+        let _permit = self.permit().await?;
+        self.inner.sweep_expired(policy).await
+    }
+
+    /// Holds a permit until the stream is dropped, like `get_stream`
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        let keys = self.inner.list_objects_stream(prefix);
+        futures::stream::once(async move {
+            let permit = self.permit().await?;
+            anyhow::Ok(keys.map(move |key| {
+                let _held = &permit;
+                key
+            }))
+        })
+        .try_flatten()
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockBackend;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Backend whose operations take a while and record peak concurrency
+    #[derive(Debug, Default)]
+    struct SlowBackend {
+        inner: MockBackend,
+        running: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl SlowBackend {
+        async fn track<T>(&self, op: impl std::future::Future<Output = T>) -> T {
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let result = op.await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            result
+        }
+    }
+
+    #[async_trait]
+    impl StorageBackend for SlowBackend {
+        async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+            self.track(self.inner.get(key)).await
+        }
+
+        async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+            self.track(self.inner.put(key, data)).await
+        }
+
+        async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+            self.track(self.inner.exists(key)).await
+        }
+
+        async fn delete(&self, key: &str) -> anyhow::Result<()> {
+            self.track(self.inner.delete(key)).await
+        }
+
+        async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+            self.track(self.inner.list_objects(prefix)).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_operations_are_capped() {
+        let slow = Arc::new(SlowBackend::default());
+        let permits = Arc::new(Semaphore::new(4));
+
+        // Two wrappers, as two subsystems would hold, sharing one semaphore
+        let push = Arc::new(ConcurrencyLimitedBackend::new(
+            slow.clone(),
+            permits.clone(),
+        ));
+        let gc = Arc::new(ConcurrencyLimitedBackend::new(
+            slow.clone(),
+            permits.clone(),
+        ));
+
+        let mut tasks = Vec::new();
+        for i in 0..40 {
+            let storage = if i % 2 == 0 { push.clone() } else { gc.clone() };
+            tasks.push(tokio::spawn(async move {
+                let key = format!("objects/{}", i);
+                storage.put(&key, b"data").await.unwrap();
+                storage.get(&key).await.unwrap();
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(slow.peak.load(Ordering::SeqCst), 4);
+        assert_eq!(permits.available_permits(), 4);
+        assert_eq!(slow.inner.list_objects("").await.unwrap().len(), 40);
+    }
+
+    #[tokio::test]
+    async fn test_open_stream_holds_permit() {
+        let permits = Arc::new(Semaphore::new(2));
+        let storage = ConcurrencyLimitedBackend::new(Arc::new(MockBackend::new()), permits.clone());
+        storage.put("objects/a", b"data").await.unwrap();
+
+        let stream = storage.get_stream("objects/a").await.unwrap();
+        assert_eq!(permits.available_permits(), 1);
+        assert_eq!(crate::stream::collect(stream).await.unwrap(), b"data");
+        assert_eq!(permits.available_permits(), 2);
+    }
+
+    #[test]
+    fn test_shared_limiter_is_process_wide() {
+        let first = shared_limiter(16);
+        let second = shared_limiter(4);
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+}
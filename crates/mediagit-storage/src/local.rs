@@ -16,6 +16,7 @@
 //! Implements the `StorageBackend` trait using the local filesystem with:
 //! - Sharded directory structure to prevent too many files in one directory
 //! - Atomic writes using temp files and atomic rename operations
+//! - Resumable, checksummed writes for large objects (see [`LocalBackend::with_resumable_writes`])
 //! - Proper file permissions (0644 for files, 0755 for directories)
 //! - Async I/O using tokio::fs
 //!
@@ -35,6 +36,27 @@
 //!
 //! This prevents too many files in a single directory, improving filesystem performance.
 //!
+//! # Windows Long Paths
+//!
+//! A deep repository root plus the shard directories and a long key easily
+//! exceeds the 260-character `MAX_PATH` limit of the Win32 API. On Windows
+//! the backend therefore reaches its files through the extended-length form
+//! of the root, `\\?\C:\...` for drive paths and `\\?\UNC\server\share\...`
+//! for network shares, which allows paths of up to 32,767 characters.
+//! [`LocalBackend::root`] still returns the root as it was given.
+//!
+//! # Case-Insensitive Filesystems
+//!
+//! On macOS and Windows, keys that differ only by case, like
+//! `textures/Hero.png` and `textures/hero.png`, map to the same file. The
+//! backend checks whether its root is case-insensitive when it is created,
+//! and if so refuses to read or overwrite an object stored under a key of
+//! different case with [`StorageError::CaseCollision`], and reports such a
+//! key as missing from `exists`. Content-addressed keys are lowercase hex and never
+//! collide; wrap the backend in a
+//! [`HashedKeyBackend`](crate::hashed_keys::HashedKeyBackend) to store
+//! other keys under hashed file names instead.
+//!
 //! # Examples
 //!
 //! ```rust,no_run
@@ -69,18 +91,50 @@
 //! }
 //! ```
 
-use crate::StorageBackend;
+use crate::expiry::{self, ExpiryPolicy, SweepReport};
+use crate::stream::{KeyStream, ObjectStream, STREAM_CHUNK_SIZE};
+use crate::{
+    check_delimiter, common_prefix, BackendCapabilities, ObjectMeta, StorageBackend, StorageError,
+    StorageUsage,
+};
 use async_trait::async_trait;
+use bytes::BytesMut;
+use futures::{StreamExt, TryStreamExt};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
 use std::fmt;
+use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
 /// Monotonic counter for unique temp file names.
 /// Prevents temp-file collisions when multiple async tasks write the same key concurrently.
 static TEMP_WRITE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
+/// Objects at least this large are written with resumable chunked writes (64MB)
+pub const DEFAULT_RESUMABLE_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// Chunk size for resumable writes (8MB)
+pub const DEFAULT_RESUMABLE_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Suffix of the partially written object kept between attempts
+const PART_SUFFIX: &str = ".mgpart";
+
+/// Suffix of the chunk checksum manifest that accompanies a `.mgpart` file
+const PART_SUMS_SUFFIX: &str = ".mgpart.sums";
+
+/// Suffix of the lock file held for the whole of a resumable write
+const PART_LOCK_SUFFIX: &str = ".mgpart.lock";
+
+/// A resumable write's lock not touched for this long belongs to a writer
+/// that was killed, and is taken over
+const PART_LOCK_STALE_AFTER: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Manifest format version, written as the first token of the header line
+const PART_MANIFEST_VERSION: &str = "mgpart-v1";
+
 /// Returns true if this OS error code is a transient Windows error worth retrying.
 ///
 /// - `os error 2`  = `ERROR_FILE_NOT_FOUND` — directory not yet visible after creation
@@ -125,6 +179,13 @@ impl AsRef<[u8]> for MmapOrVec {
 #[derive(Clone)]
 pub struct LocalBackend {
     root: PathBuf,
+    /// `root` in the form files are accessed through: extended-length on
+    /// Windows, `root` itself elsewhere
+    fs_root: PathBuf,
+    resumable_threshold: u64,
+    resumable_chunk_size: usize,
+    alternates: Option<PathBuf>,
+    case_insensitive: bool,
 }
 
 impl LocalBackend {
@@ -166,7 +227,14 @@ impl LocalBackend {
             ));
         }
 
-        Ok(LocalBackend { root })
+        Ok(LocalBackend {
+            case_insensitive: is_case_insensitive(&root),
+            fs_root: extended_path(&root),
+            root,
+            resumable_threshold: DEFAULT_RESUMABLE_THRESHOLD,
+            resumable_chunk_size: DEFAULT_RESUMABLE_CHUNK_SIZE,
+            alternates: None,
+        })
     }
 
     /// Create a new local filesystem backend synchronously
@@ -199,7 +267,14 @@ impl LocalBackend {
             ));
         }
 
-        Ok(LocalBackend { root })
+        Ok(LocalBackend {
+            case_insensitive: is_case_insensitive(&root),
+            fs_root: extended_path(&root),
+            root,
+            resumable_threshold: DEFAULT_RESUMABLE_THRESHOLD,
+            resumable_chunk_size: DEFAULT_RESUMABLE_CHUNK_SIZE,
+            alternates: None,
+        })
     }
 
     /// Get the root path for this backend
@@ -207,6 +282,156 @@ impl LocalBackend {
         &self.root
     }
 
+    /// Whether the filesystem under the root ignores case in file names
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    /// Configure resumable writes for large objects
+    ///
+    /// Objects of at least `threshold` bytes are written in `chunk_size` pieces
+    /// to a `.mgpart` file next to the final location, with a SHA-256 per chunk
+    /// recorded in a `.mgpart.sums` manifest. If the write is interrupted (a
+    /// dropped NFS/SMB mount, a killed process), the next `put` of the same key
+    /// keeps every chunk whose checksum still matches and continues from there.
+    /// The whole file is hashed again before the final atomic rename.
+    ///
+    /// A `.mgpart.lock` file is held for the whole write. A concurrent `put`
+    /// of the same key finds it taken and writes through its own temporary
+    /// file instead. A killed writer's lock is taken over once it has not
+    /// been touched for five minutes.
+    ///
+    /// Defaults to [`DEFAULT_RESUMABLE_THRESHOLD`] and [`DEFAULT_RESUMABLE_CHUNK_SIZE`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use mediagit_storage::local::LocalBackend;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// // Chunk everything over 16MB into 4MB pieces
+    /// let storage = LocalBackend::new("/mnt/studio-nas/.mediagit")
+    ///     .await?
+    ///     .with_resumable_writes(16 * 1024 * 1024, 4 * 1024 * 1024);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_resumable_writes(mut self, threshold: u64, chunk_size: usize) -> Self {
+        self.resumable_threshold = threshold;
+        self.resumable_chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Share objects with a machine-wide store in `dir`
+    ///
+    /// The store has the same layout as a backend root. When `put` is given
+    /// exactly the bytes the store already holds for the key, the stored file
+    /// is hard-linked into place instead of written again; otherwise the new
+    /// file is written as usual and then hard-linked into the store for the
+    /// next repository. Files are only ever replaced by renaming, never
+    /// modified in place, so a shared file can't change under another
+    /// repository. Linking needs the store on the same filesystem; where it
+    /// isn't, objects are simply written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use mediagit_storage::local::LocalBackend;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let storage = LocalBackend::new("project/.mediagit")
+    ///     .await?
+    ///     .with_alternates("/srv/mediagit/shared-objects");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_alternates(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.alternates = Some(dir.into());
+        self
+    }
+
+    /// The shared store objects are linked from, if any
+    pub fn alternates(&self) -> Option<&Path> {
+        self.alternates.as_deref()
+    }
+
+    /// Put `source`'s object `key` in place here, sharing its data
+    ///
+    /// The file is hard-linked. Where that isn't possible, such as across
+    /// filesystems, it is copied, which still shares the data blocks on
+    /// filesystems that support it (reflinks on Btrfs and XFS, clones on
+    /// APFS). Returns `true` if it was linked rather than copied. The object is also shared with this backend's
+    /// [alternates](Self::with_alternates).
+    pub async fn link_from(&self, source: &LocalBackend, key: &str) -> anyhow::Result<bool> {
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        let src = source.object_path(key);
+        let dst = self.object_path(key);
+        self.ensure_parent_dir(&dst).await?;
+        let linked = match place_file(&src, &dst, true).await {
+            Ok(linked) => linked,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !fs::try_exists(&src).await? => {
+                return Err(anyhow::anyhow!("object not found: {}", key));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        self.share(key, &dst).await;
+        Ok(linked)
+    }
+
+    /// Where the alternates store keeps `key`
+    fn alternate_path(&self, key: &str) -> Option<PathBuf> {
+        let alternates = extended_path(self.alternates.as_ref()?);
+        let relative = self.object_path(key);
+        Some(alternates.join(relative.strip_prefix(&self.fs_root).ok()?))
+    }
+
+    /// Hard-link the alternates store's copy of `key` to `path` if it holds
+    /// exactly `data`, returning whether it did
+    async fn link_shared(&self, key: &str, path: &Path, data: &[u8]) -> bool {
+        let Some(shared) = self.alternate_path(key) else {
+            return false;
+        };
+        let same = match fs::metadata(&shared).await {
+            Ok(metadata) if metadata.len() == data.len() as u64 => {
+                fs::read(&shared).await.is_ok_and(|stored| stored == data)
+            }
+            _ => false,
+        };
+        if !same || self.ensure_parent_dir(path).await.is_err() {
+            return false;
+        }
+        place_file(&shared, path, false).await.unwrap_or(false)
+    }
+
+    /// Hard-link the file at `path` into the alternates store, unless the
+    /// store already has `key`
+    ///
+    /// Sharing is an optimization, so failures are only logged.
+    async fn share(&self, key: &str, path: &Path) {
+        let Some(shared) = self.alternate_path(key) else {
+            return;
+        };
+        if fs::try_exists(&shared).await.unwrap_or(true) {
+            return;
+        }
+        if let Some(parent) = shared.parent() {
+            if let Err(e) = fs::create_dir_all(parent).await {
+                tracing::debug!(key = %key, "Failed to create alternates directory: {}", e);
+                return;
+            }
+        }
+        match fs::hard_link(path, &shared).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => tracing::debug!(key = %key, "Failed to share object with alternates: {}", e),
+        }
+    }
+
     /// Get the path for a given key with sharding
     ///
     /// Sharding layout for objects: `root/objects/AB/CD/key` where:
@@ -243,7 +468,7 @@ impl LocalBackend {
         // Special case: pack files should not be sharded
         // They are stored directly under root/packs/
         if key.starts_with("packs/") {
-            return self.root.join(key);
+            return join_key(&self.fs_root, key);
         }
 
         // Encode "/" as "__" to allow keys with "/" in filenames
@@ -254,18 +479,46 @@ impl LocalBackend {
             // For keys with 4+ chars: use shard1/shard2/key layout
             let shard1 = &key[0..2];
             let shard2 = &key[2..4];
-            self.root
-                .join("objects")
-                .join(shard1)
-                .join(shard2)
-                .join(&encoded_key)
+            let shards = join_key(&join_key(&self.fs_root.join("objects"), shard1), shard2);
+            shards.join(&encoded_key)
         } else if key.len() >= 2 {
             // For keys with 2-3 chars: use shard1/key layout
             let shard1 = &key[0..2];
-            self.root.join("objects").join(shard1).join(&encoded_key)
+            join_key(&self.fs_root.join("objects"), shard1).join(&encoded_key)
         } else {
             // For single-char keys: just store directly under objects
-            self.root.join("objects").join(&encoded_key)
+            self.fs_root.join("objects").join(&encoded_key)
+        }
+    }
+
+    /// The key stored at `path` under a name that differs from `path`'s
+    /// only by case, if the filesystem ignores case and there is one
+    async fn case_collision(&self, key: &str, path: &Path) -> anyhow::Result<Option<String>> {
+        if !self.case_insensitive || !fs::try_exists(path).await? {
+            return Ok(None);
+        }
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Ok(None);
+        };
+        let name = name.to_string_lossy();
+
+        let mut entries = fs::read_dir(parent).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let stored = entry.file_name().to_string_lossy().into_owned();
+            if stored != name && stored.to_lowercase() == name.to_lowercase() {
+                return Ok(Some(stored_key(key, &name, &stored)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fail with [`StorageError::CaseCollision`] if `path` holds a key of
+    /// different case than `key`, so reading or writing `key` there would
+    /// reach the other key's object
+    async fn check_case_collision(&self, key: &str, path: &Path) -> anyhow::Result<()> {
+        match self.case_collision(key, path).await? {
+            Some(existing) => Err(StorageError::case_collision(key, existing).into()),
+            None => Ok(()),
         }
     }
 
@@ -376,69 +629,24 @@ impl LocalBackend {
             tracing::debug!(key = %key, size = size, "Using mmap for large file");
             Ok(MmapOrVec::Mmap(self.get_mmap(key)?))
         } else {
-            Ok(MmapOrVec::Vec(fs::read(self.object_path(key)).await?))
+            Ok(MmapOrVec::Vec(read_whole(&self.object_path(key)).await?))
         }
     }
-}
 
-impl fmt::Debug for LocalBackend {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("LocalBackend")
-            .field("root", &self.root)
-            .finish()
-    }
-}
-
-#[async_trait]
-impl StorageBackend for LocalBackend {
-    /// Retrieve an object by its key
-    ///
-    /// Reads the file from the sharded directory structure.
-    ///
-    /// # Arguments
-    ///
-    /// * `key` - The object identifier
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(Vec<u8>)` - The object data
-    /// * `Err` - If the key doesn't exist or an I/O error occurs
-    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
-        if key.is_empty() {
-            return Err(anyhow::anyhow!("key cannot be empty"));
-        }
-
-        let path = self.object_path(key);
-
-        match fs::read(&path).await {
-            Ok(data) => Ok(data),
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                Err(anyhow::anyhow!("object not found: {}", key))
+    /// Write `data` to `path` atomically, through a temporary file renamed
+    /// into place, or as a resumable write for large objects
+    async fn write_file(&self, path: &Path, data: &[u8]) -> anyhow::Result<()> {
+        if data.len() as u64 >= self.resumable_threshold {
+            self.ensure_parent_dir(path).await?;
+            if let Some(mut write) =
+                ResumableWrite::begin(path, data, self.resumable_chunk_size).await?
+            {
+                while write.write_next_chunk().await? {}
+                return write.finish().await;
             }
-            Err(e) => Err(e.into()),
+            // Another writer of the same key holds the resumable files, so
+            // write through a unique temporary file instead
         }
-    }
-
-    /// Store an object with the given key
-    ///
-    /// Uses atomic writes: writes to a temporary file first, then atomically
-    /// renames it to the final location. This ensures no partial writes are visible.
-    ///
-    /// # Arguments
-    ///
-    /// * `key` - The object identifier
-    /// * `data` - The object content
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(())` - The operation succeeded
-    /// * `Err` - If an I/O error occurs or permission is denied
-    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
-        if key.is_empty() {
-            return Err(anyhow::anyhow!("key cannot be empty"));
-        }
-
-        let path = self.object_path(key);
 
         // Windows-specific transient errors require retry with backoff:
         //
@@ -461,9 +669,9 @@ impl StorageBackend for LocalBackend {
                 let delay_ms = 10 * (3u64.pow(attempt - 1));
                 tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
                 // Re-ensure parent dir — may have been transiently invisible
-                let _ = self.ensure_parent_dir(&path).await;
+                let _ = self.ensure_parent_dir(path).await;
             } else {
-                self.ensure_parent_dir(&path).await?;
+                self.ensure_parent_dir(path).await?;
             }
 
             // Unique temp path per write avoids collisions between concurrent writers
@@ -495,7 +703,7 @@ impl StorageBackend for LocalBackend {
             drop(file);
 
             // Atomically rename temp file to final location
-            match fs::rename(&temp_path, &path).await {
+            match fs::rename(&temp_path, path).await {
                 Ok(()) => return Ok(()),
 
                 Err(e) if attempt < MAX_RETRIES && is_transient_windows_error(&e) => {
@@ -516,7 +724,7 @@ impl StorageBackend for LocalBackend {
                     return Ok(());
                 }
 
-                Err(e) if is_transient_windows_error(&e) && fs::metadata(&path).await.is_ok() => {
+                Err(e) if is_transient_windows_error(&e) && fs::metadata(path).await.is_ok() => {
                     // ACCESS_DENIED on rename but destination exists: concurrent winner
                     // already wrote the correct object. Safe to treat as success.
                     let _ = fs::remove_file(&temp_path).await;
@@ -537,6 +745,505 @@ impl StorageBackend for LocalBackend {
         ))
     }
 
+    /// Delete the temporary files of writes that were last touched more than
+    /// `ttl` ago, returning how many were removed
+    ///
+    /// These are the `<name>.tmpN` files of atomic writes and the `.mgpart`
+    /// files of resumable ones; a write that is still going touches them
+    /// continually, so anything older was abandoned.
+    async fn remove_stale_temp_files(&self, ttl: std::time::Duration) -> anyhow::Result<usize> {
+        let now = std::time::SystemTime::now();
+        let mut pending = vec![self.fs_root.join("objects"), self.fs_root.join("packs")];
+        let mut removed = 0;
+        while let Some(dir) = pending.pop() {
+            let Ok(mut entries) = fs::read_dir(&dir).await else {
+                continue;
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let metadata = entry.metadata().await?;
+                if metadata.is_dir() {
+                    pending.push(entry.path());
+                    continue;
+                }
+                if !entry.file_name().to_str().is_some_and(is_temp_file) {
+                    continue;
+                }
+                if !expiry::is_expired(metadata.modified()?, ttl, now) {
+                    continue;
+                }
+                match fs::remove_file(entry.path()).await {
+                    Ok(()) => removed += 1,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Put the file at `src` in place at `dst` by hard-linking it, or with `copy`
+/// set and linking impossible, by copying it, which shares the data blocks
+/// where the filesystem supports it
+///
+/// Goes through a temporary file renamed into place so readers never see a
+/// partial copy. The modification time is set to now where permitted, so the
+/// object counts as freshly written for the gc grace period. Returns `true` if linked,
+/// `false` if copied or, without `copy`, if nothing was done.
+async fn place_file(src: &Path, dst: &Path, copy: bool) -> std::io::Result<bool> {
+    let write_id = TEMP_WRITE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let temp_path = dst.with_extension(format!("tmp{}", write_id));
+    let linked = match fs::hard_link(src, &temp_path).await {
+        Ok(()) => true,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(e),
+        Err(_) if copy => false,
+        Err(_) => return Ok(false),
+    };
+    let placed = async {
+        if !linked {
+            fs::copy(src, &temp_path).await?;
+        }
+        // A store owned by another user may not allow it; that only makes gc
+        // judge the object by the store's write time
+        if let Ok(file) = fs::File::options().write(true).open(&temp_path).await {
+            let _ = file
+                .into_std()
+                .await
+                .set_modified(std::time::SystemTime::now());
+        }
+        fs::rename(&temp_path, dst).await
+    }
+    .await;
+    if let Err(e) = placed {
+        let _ = fs::remove_file(&temp_path).await;
+        return Err(e);
+    }
+    Ok(linked)
+}
+
+/// `base` joined with the `/`-separated `key`, one component at a time
+///
+/// Extended-length Windows paths take `/` literally rather than as a
+/// separator, so keys can't be joined whole.
+fn join_key(base: &Path, key: &str) -> PathBuf {
+    let mut path = base.to_path_buf();
+    path.extend(key.split('/').filter(|part| !part.is_empty()));
+    path
+}
+
+/// `path` in the form the backend accesses files through
+///
+/// On Windows, the extended-length (`\\?\`) form of the absolute path, so
+/// object paths aren't limited to `MAX_PATH`. Elsewhere `path` itself.
+#[cfg(windows)]
+fn extended_path(path: &Path) -> PathBuf {
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    match absolute.to_str().and_then(windows_extended) {
+        Some(extended) => PathBuf::from(extended),
+        None => absolute,
+    }
+}
+
+#[cfg(not(windows))]
+fn extended_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// The extended-length form of the absolute Windows path `path`
+///
+/// `C:\media` becomes `\\?\C:\media` and `\\server\share\media` becomes
+/// `\\?\UNC\server\share\media`. Returns `None` for relative paths and
+/// paths that are already extended-length or device paths. `path` must be
+/// normalized, since `.` and `..` are taken literally in the result.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn windows_extended(path: &str) -> Option<String> {
+    if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return None;
+    }
+    let path = path.replace('/', "\\");
+    if let Some(unc) = path.strip_prefix(r"\\") {
+        return Some(format!(r"\\?\UNC\{}", unc));
+    }
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\' {
+        return Some(format!(r"\\?\{}", path));
+    }
+    None
+}
+
+/// Whether file names under `root` ignore case
+///
+/// Creates an uppercase probe file and looks for it under its lowercase
+/// name. If the probe can't be written, falls back to the platform default:
+/// case-insensitive on macOS and Windows.
+pub fn is_case_insensitive(root: &Path) -> bool {
+    let write_id = TEMP_WRITE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let name = format!("MGCASE{}.TMP{}", std::process::id(), write_id);
+    let probe = root.join(&name);
+    if std::fs::write(&probe, b"").is_err() {
+        return cfg!(any(target_os = "macos", target_os = "windows"));
+    }
+    let insensitive = root.join(name.to_lowercase()).exists();
+    let _ = std::fs::remove_file(&probe);
+    insensitive
+}
+
+/// The key of the file `stored` whose name differs only by case from
+/// `name`, the file name `key` maps to
+fn stored_key(key: &str, name: &str, stored: &str) -> String {
+    if key.starts_with("packs/") {
+        // Pack keys keep their directories, and the file name is the last part
+        format!("{}{}", &key[..key.len() - name.len()], stored)
+    } else {
+        stored.replace("__", "/")
+    }
+}
+
+/// Whether `name` is the temporary file of a write rather than an object:
+/// `<name>.tmpN` for atomic writes, `.mgpart`, `.mgpart.sums` and
+/// `.mgpart.lock` for resumable ones
+fn is_temp_file(name: &str) -> bool {
+    if [PART_SUFFIX, PART_SUMS_SUFFIX, PART_LOCK_SUFFIX]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+    {
+        return true;
+    }
+    name.rsplit_once('.')
+        .and_then(|(_, extension)| extension.strip_prefix("tmp"))
+        .is_some_and(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Read all of `path`, failing with [`StorageError::Truncated`] if the file
+/// shrinks while it is being read
+async fn read_whole(path: &Path) -> anyhow::Result<Vec<u8>> {
+    let mut file = fs::File::open(path).await?;
+    let expected = file.metadata().await?.len();
+    let mut data = Vec::with_capacity(expected as usize);
+    file.read_to_end(&mut data).await?;
+
+    let got = data.len() as u64;
+    if got < expected {
+        return Err(StorageError::truncated(expected, got).into());
+    }
+    Ok(data)
+}
+
+/// Stream `file` in chunks, failing with [`StorageError::Truncated`] if it
+/// ends before `expected` bytes
+fn file_stream(file: fs::File, expected: u64) -> ObjectStream {
+    Box::pin(futures::stream::try_unfold(
+        (file, 0u64),
+        move |(mut file, got)| async move {
+            let mut chunk = BytesMut::with_capacity(STREAM_CHUNK_SIZE);
+            let read = file.read_buf(&mut chunk).await? as u64;
+            if read == 0 {
+                if got < expected {
+                    return Err(StorageError::truncated(expected, got).into());
+                }
+                return Ok(None);
+            }
+            Ok(Some((chunk.freeze(), (file, got + read))))
+        },
+    ))
+}
+
+impl fmt::Debug for LocalBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalBackend")
+            .field("root", &self.root)
+            .field("resumable_threshold", &self.resumable_threshold)
+            .field("alternates", &self.alternates)
+            .field("case_insensitive", &self.case_insensitive)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalBackend {
+    /// Retrieve an object by its key
+    ///
+    /// Reads the file from the sharded directory structure.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The object identifier
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<u8>)` - The object data
+    /// * `Err` - If the key doesn't exist or an I/O error occurs
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        let path = self.object_path(key);
+        self.check_case_collision(key, &path).await?;
+
+        match read_whole(&path).await {
+            Ok(data) => Ok(data),
+            Err(e)
+                if e.downcast_ref::<std::io::Error>()
+                    .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound) =>
+            {
+                Err(anyhow::anyhow!("object not found: {}", key))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Large objects are memory-mapped, files are streamed or read in part,
+    /// and copies stay on disk
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            mmap: true,
+            server_side_copy: true,
+            range_reads: true,
+            streaming: true,
+            ..BackendCapabilities::default()
+        }
+    }
+
+    /// Retrieve an object, memory-mapping it if larger than 10MB
+    ///
+    /// See [`get_adaptive`](LocalBackend::get_adaptive).
+    async fn get_mapped(&self, key: &str) -> anyhow::Result<MmapOrVec> {
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        self.check_case_collision(key, &self.object_path(key))
+            .await?;
+
+        match self.get_adaptive(key).await {
+            Ok(data) => Ok(data),
+            Err(e)
+                if e.downcast_ref::<std::io::Error>()
+                    .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound) =>
+            {
+                Err(anyhow::anyhow!("object not found: {}", key))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Seek to `offset` in the object file and read up to `len` bytes
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        let path = self.object_path(key);
+        self.check_case_collision(key, &path).await?;
+
+        let mut file = match fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(anyhow::anyhow!("object not found: {}", key));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let size = file.metadata().await?.len();
+        let (start, end) = crate::range_bounds(size, offset, len);
+
+        file.seek(SeekFrom::Start(start as u64)).await?;
+        let mut data = Vec::with_capacity(end - start);
+        file.take((end - start) as u64)
+            .read_to_end(&mut data)
+            .await?;
+        if data.len() < end - start {
+            return Err(StorageError::truncated((end - start) as u64, data.len() as u64).into());
+        }
+        Ok(data)
+    }
+
+    /// Stream the object file in 1MB chunks
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        let path = self.object_path(key);
+        self.check_case_collision(key, &path).await?;
+
+        let file = match fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(anyhow::anyhow!("object not found: {}", key));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let expected = file.metadata().await?.len();
+        Ok(file_stream(file, expected))
+    }
+
+    /// Write the stream to a temporary file, then rename it into place
+    ///
+    /// Like [`put`](Self::put), readers never see a partial object; if the
+    /// stream fails the temporary file is removed.
+    async fn put_stream(&self, key: &str, mut data: ObjectStream) -> anyhow::Result<()> {
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        let path = self.object_path(key);
+        self.check_case_collision(key, &path).await?;
+        self.ensure_parent_dir(&path).await?;
+        let write_id = TEMP_WRITE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let temp_path = path.with_extension(format!("tmp{}", write_id));
+
+        let written: anyhow::Result<()> = async {
+            let mut file = fs::File::create(&temp_path).await?;
+            while let Some(chunk) = data.next().await {
+                file.write_all(&chunk?).await?;
+            }
+            file.sync_all().await?;
+            drop(file);
+            fs::rename(&temp_path, &path).await?;
+            Ok(())
+        }
+        .await;
+        if written.is_err() {
+            let _ = fs::remove_file(&temp_path).await;
+        }
+        written
+    }
+
+    /// Modification time of the object file
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<std::time::SystemTime>> {
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        match fs::metadata(self.object_path(key)).await {
+            Ok(metadata) => Ok(Some(metadata.modified()?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(anyhow::anyhow!("object not found: {}", key))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Size and modification time of the object file
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        match fs::metadata(self.object_path(key)).await {
+            Ok(metadata) => Ok(ObjectMeta {
+                modified: Some(metadata.modified()?),
+                ..ObjectMeta::with_size(metadata.len())
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(anyhow::anyhow!("object not found: {}", key))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store an object unless the key is taken
+    ///
+    /// The data is written to a temporary file that is then hard-linked into
+    /// place. Like opening with `O_EXCL`, linking fails if the target exists,
+    /// so of two concurrent writers exactly one wins, and unlike `O_EXCL` no
+    /// reader sees a partly written object.
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        let path = self.object_path(key);
+        self.check_case_collision(key, &path).await?;
+        if fs::try_exists(&path).await? {
+            return Ok(false);
+        }
+        self.ensure_parent_dir(&path).await?;
+
+        let write_id = TEMP_WRITE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let temp_path = path.with_extension(format!("tmp{}", write_id));
+        let written = async {
+            let mut file = fs::File::create(&temp_path).await?;
+            file.write_all(data).await?;
+            file.sync_all().await?;
+            drop(file);
+            fs::hard_link(&temp_path, &path).await
+        }
+        .await;
+        let _ = fs::remove_file(&temp_path).await;
+
+        match written {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Copy an object without reading it into memory
+    ///
+    /// The file is copied to a temporary file that is then renamed into
+    /// place, so readers never see a partial copy. Filesystems that support
+    /// it share the data blocks instead of copying them.
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        if src_key.is_empty() || dst_key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        let src = self.object_path(src_key);
+        let dst = self.object_path(dst_key);
+        self.check_case_collision(dst_key, &dst).await?;
+        self.ensure_parent_dir(&dst).await?;
+
+        let write_id = TEMP_WRITE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let temp_path = dst.with_extension(format!("tmp{}", write_id));
+        if let Err(e) = fs::copy(&src, &temp_path).await {
+            let _ = fs::remove_file(&temp_path).await;
+            if e.kind() == std::io::ErrorKind::NotFound && !fs::try_exists(&src).await? {
+                return Err(anyhow::anyhow!("object not found: {}", src_key));
+            }
+            return Err(e.into());
+        }
+        if let Err(e) = fs::rename(&temp_path, &dst).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Store an object with the given key
+    ///
+    /// Uses atomic writes: writes to a temporary file first, then atomically
+    /// renames it to the final location. This ensures no partial writes are visible.
+    /// With [alternates](LocalBackend::with_alternates), identical objects are
+    /// hard-linked from the shared store instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The object identifier
+    /// * `data` - The object content
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The operation succeeded
+    /// * `Err` - If an I/O error occurs or permission is denied
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        if key.is_empty() {
+            return Err(anyhow::anyhow!("key cannot be empty"));
+        }
+
+        let path = self.object_path(key);
+        self.check_case_collision(key, &path).await?;
+        if self.link_shared(key, &path, data).await {
+            return Ok(());
+        }
+        self.write_file(&path, data).await?;
+        self.share(key, &path).await;
+        Ok(())
+    }
+
     /// Check if an object exists
     ///
     /// # Arguments
@@ -555,6 +1262,8 @@ impl StorageBackend for LocalBackend {
 
         let path = self.object_path(key);
         match fs::try_exists(&path).await {
+            // A key of different case is stored in the same file
+            Ok(true) => Ok(self.case_collision(key, &path).await?.is_none()),
             Ok(exists) => Ok(exists),
             Err(e) => Err(e.into()),
         }
@@ -605,144 +1314,445 @@ impl StorageBackend for LocalBackend {
     /// * `Ok(Vec<String>)` - Sorted list of matching keys
     /// * `Err` - If an I/O error occurs or permission is denied
     async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
-        let mut results = Vec::new();
+        let mut results: Vec<String> = self.list_objects_stream(prefix).try_collect().await?;
+        results.sort();
+        Ok(results)
+    }
+
+    /// Stream keys while walking the directory tree, reading one directory
+    /// entry at a time
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        let walk = KeyWalk::new(&self.fs_root, prefix);
+        Box::pin(futures::stream::try_unfold(walk, |mut walk| async move {
+            Ok(walk.next_key().await?.map(|key| (key, walk)))
+        }))
+    }
+
+    /// Group keys by `delimiter`, walking only the shards that can hold keys
+    /// under `prefix`
+    ///
+    /// Pack files sit outside the sharded tree, so whether they add a prefix
+    /// usually takes reading a single entry of root/packs/.
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        check_delimiter(delimiter)?;
+        let mut prefixes = BTreeSet::new();
+
+        if !prefix.starts_with("packs/") && "packs/".starts_with(prefix) {
+            let mut packs = KeyWalk::new(&self.fs_root, "packs/");
+            while let Some(key) = packs.next_key().await? {
+                if let Some(common) = common_prefix(&key, prefix, delimiter) {
+                    // Ends within "packs/", so every other pack shares it
+                    let shared = common.len() <= "packs/".len();
+                    prefixes.insert(common.to_string());
+                    if shared {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let mut keys = self.list_objects_stream(prefix);
+        while let Some(key) = keys.try_next().await? {
+            if let Some(common) = common_prefix(&key, prefix, delimiter) {
+                if !prefixes.contains(common) {
+                    prefixes.insert(common.to_string());
+                }
+            }
+        }
+        Ok(prefixes.into_iter().collect())
+    }
+
+    /// Sum the sizes of the files holding keys under `prefix`
+    ///
+    /// Unlike `list_objects`, an empty prefix also counts the pack files.
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        let mut walks = vec![KeyWalk::new(&self.fs_root, prefix)];
+        if !prefix.starts_with("packs/") && "packs/".starts_with(prefix) {
+            walks.push(KeyWalk::new(&self.fs_root, "packs/"));
+        }
+
+        let mut usage = StorageUsage::default();
+        for mut walk in walks {
+            while let Some(key) = walk.next_key().await? {
+                usage.add(fs::metadata(self.object_path(&key)).await?.len());
+            }
+        }
+        Ok(usage)
+    }
 
-        // Special case: pack files are stored directly under root/packs/
-        if prefix.starts_with("packs/") {
-            let packs_dir = self.root.join("packs");
+    /// Also removes the temporary files of abandoned writes
+    ///
+    /// With `incomplete_uploads` set, `.tmpN` and `.mgpart` files under
+    /// objects/ and packs/ that haven't been written to for that long are
+    /// deleted and counted in the report.
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport> {
+        let mut report = expiry::sweep_rules(self, policy).await?;
+        if let Some(ttl) = policy.incomplete_uploads {
+            report.incomplete_uploads = self.remove_stale_temp_files(ttl).await?;
+        }
+        Ok(report)
+    }
+}
+
+/// An incremental walk of the directories holding objects
+///
+/// - For "packs/" prefixes: lists root/packs/ directly, without descending
+/// - For other prefixes: walks the sharded root/objects/ tree, starting at
+///   the shard directories the prefix determines
+///
+/// Directories are read one entry at a time, with a work queue of the
+/// directories still to visit rather than recursion.
+struct KeyWalk {
+    /// Directory keys are reconstructed relative to
+    base: PathBuf,
+    /// Whether `base` is the flat packs/ directory
+    flat: bool,
+    prefix: String,
+    pending: Vec<PathBuf>,
+    entries: Option<fs::ReadDir>,
+}
 
-            // If packs directory doesn't exist, return empty list
-            if !packs_dir.exists() {
-                return Ok(Vec::new());
+impl KeyWalk {
+    fn new(root: &Path, prefix: &str) -> Self {
+        // Special case: pack files are stored directly under root/packs/
+        let flat = prefix.starts_with("packs/");
+        let base = root.join(if flat { "packs" } else { "objects" });
+
+        // Keys under a prefix of 2 or more characters share its shard
+        // directories, so the rest of the tree can be skipped
+        let mut start = base.clone();
+        if !flat {
+            let shards = [prefix.get(0..2), prefix.get(2..4)];
+            for shard in shards.into_iter().map_while(|shard| shard) {
+                if !shard.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    break;
+                }
+                start.push(shard);
             }
+        }
 
-            // List files directly in packs/ directory
-            Self::walk_dir_flat(&packs_dir, prefix, &mut results).await?;
-        } else {
-            // Regular objects: search in sharded objects/ directory
-            let objects_dir = self.root.join("objects");
+        Self {
+            pending: vec![start],
+            base,
+            flat,
+            prefix: prefix.to_string(),
+            entries: None,
+        }
+    }
+
+    /// The next key matching the prefix, or `None` once the walk is done
+    async fn next_key(&mut self) -> anyhow::Result<Option<String>> {
+        loop {
+            let Some(entries) = self.entries.as_mut() else {
+                let Some(dir) = self.pending.pop() else {
+                    return Ok(None);
+                };
+                // Skip directories that don't exist or can't be read
+                self.entries = fs::read_dir(&dir).await.ok();
+                continue;
+            };
+            let Some(entry) = entries.next_entry().await? else {
+                self.entries = None;
+                continue;
+            };
 
-            // If objects directory doesn't exist, return empty list
-            if !objects_dir.exists() {
-                return Ok(Vec::new());
+            let path = entry.path();
+            if path.is_dir() {
+                if !self.flat {
+                    self.pending.push(path);
+                }
+                continue;
             }
+            if let Some(key) = self.key_for(&path) {
+                if key.starts_with(&self.prefix) {
+                    return Ok(Some(key));
+                }
+            }
+        }
+    }
 
-            Self::walk_dir_iterative(&objects_dir, &objects_dir, prefix, &mut results).await?;
+    /// Reconstruct the key stored at `path`
+    fn key_for(&self, path: &Path) -> Option<String> {
+        if self.flat {
+            // For packs directory: reconstruct key as "packs/filename"
+            return Some(format!("packs/{}", path.file_name()?.to_str()?));
         }
 
-        results.sort();
-        Ok(results)
+        // Reconstruct the key by removing shard directories
+        // Path structures:
+        // - 1-char key: objects/X -> key is "X"
+        // - 2-3 char key: objects/AB/encoded_key -> key is the filename
+        // - 4+ char key: objects/AB/CD/encoded_key -> key is the filename
+        // The shard dirs are just organizational, so the key is always the
+        // last component (the filename)
+        let relative_path = path.strip_prefix(&self.base).ok()?;
+        let key = relative_path
+            .components()
+            .next_back()?
+            .as_os_str()
+            .to_string_lossy()
+            .to_string();
+
+        // In-progress writes are not objects yet
+        if is_temp_file(&key) {
+            return None;
+        }
+
+        // Decode "__" back to "/"
+        Some(key.replace("__", "/"))
     }
 }
 
-// Helper function for iterative directory traversal
-impl LocalBackend {
-    /// Walk a flat directory (like packs/) and collect matching keys
-    ///
-    /// For directories that don't use sharding (like packs/), list files directly
-    /// and reconstruct keys by prepending the directory name.
-    ///
-    /// # Arguments
+/// An in-progress resumable write of one object
+///
+/// Layout next to the final object path:
+/// - `<name>.mgpart` holds the bytes written so far
+/// - `<name>.mgpart.sums` holds a header line (`mgpart-v1 <chunk size> <length>
+///   <sha256>`) followed by one SHA-256 per chunk that has been synced to disk
+/// - `<name>.mgpart.lock` is created exclusively when the write begins and
+///   removed when it ends, so only one writer uses the other two files
+///
+/// A chunk's checksum is appended only after its data is synced, so every
+/// listed chunk was fully written at some point; resuming re-hashes them to
+/// catch anything the network filesystem lost or corrupted since.
+struct ResumableWrite<'a> {
+    path: PathBuf,
+    part_path: PathBuf,
+    sums_path: PathBuf,
+    data: &'a [u8],
+    chunk_size: usize,
+    digest: String,
+    part: fs::File,
+    sums: fs::File,
+    next_chunk: usize,
+    resumed_chunks: usize,
+    lock: PartLock,
+}
+
+impl<'a> ResumableWrite<'a> {
+    /// Open (or resume) the resumable write of `data` to `path`
     ///
-    /// * `dir` - The directory to walk (e.g., root/packs/)
-    /// * `prefix` - The key prefix to filter by (e.g., "packs/")
-    /// * `results` - Vector to collect matching keys
-    async fn walk_dir_flat(
-        dir: &Path,
-        prefix: &str,
-        results: &mut Vec<String>,
-    ) -> anyhow::Result<()> {
-        let mut entries = match fs::read_dir(dir).await {
-            Ok(entries) => entries,
-            Err(_) => return Ok(()), // Directory doesn't exist or can't be read
+    /// Returns None if another write to `path` holds the lock.
+    async fn begin(path: &Path, data: &'a [u8], chunk_size: usize) -> anyhow::Result<Option<Self>> {
+        let Some(lock) = PartLock::acquire(sidecar_path(path, PART_LOCK_SUFFIX))? else {
+            tracing::debug!("{} is being written by another writer", path.display());
+            return Ok(None);
         };
+        let part_path = sidecar_path(path, PART_SUFFIX);
+        let sums_path = sidecar_path(path, PART_SUMS_SUFFIX);
+        let digest = hex::encode(Sha256::digest(data));
+        let header = format!(
+            "{} {} {} {}",
+            PART_MANIFEST_VERSION,
+            chunk_size,
+            data.len(),
+            digest
+        );
 
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
+        let mut part = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&part_path)
+            .await?;
+
+        let trusted = verified_chunks(&mut part, &sums_path, &header, data, chunk_size).await;
+        if trusted > 0 {
+            tracing::debug!(
+                "Resuming write of {} from chunk {} of {}",
+                path.display(),
+                trusted,
+                data.len().div_ceil(chunk_size)
+            );
+        }
 
-            if path.is_file() {
-                // For packs directory: reconstruct key as "packs/filename"
-                if let Some(filename) = path.file_name() {
-                    if let Some(filename_str) = filename.to_str() {
-                        let key = format!("packs/{}", filename_str);
-                        if key.starts_with(prefix) {
-                            results.push(key);
-                        }
-                    }
-                }
+        // Drop anything past the verified prefix and rewrite the manifest to match
+        let offset = trusted * chunk_size;
+        part.set_len(offset as u64).await?;
+        part.seek(SeekFrom::Start(offset as u64)).await?;
+
+        let mut manifest = format!("{}\n", header);
+        for chunk in data.chunks(chunk_size).take(trusted) {
+            manifest.push_str(&hex::encode(Sha256::digest(chunk)));
+            manifest.push('\n');
+        }
+        fs::write(&sums_path, manifest).await?;
+        let sums = fs::OpenOptions::new().append(true).open(&sums_path).await?;
+
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            part_path,
+            sums_path,
+            data,
+            chunk_size,
+            digest,
+            part,
+            sums,
+            next_chunk: trusted,
+            resumed_chunks: trusted,
+            lock,
+        }))
+    }
+
+    /// Write and checksum the next chunk; returns false once all chunks are written
+    async fn write_next_chunk(&mut self) -> anyhow::Result<bool> {
+        let Some(chunk) = self.data.chunks(self.chunk_size).nth(self.next_chunk) else {
+            return Ok(false);
+        };
+
+        self.part.write_all(chunk).await?;
+        self.part.sync_data().await?;
+
+        let line = format!("{}\n", hex::encode(Sha256::digest(chunk)));
+        self.sums.write_all(line.as_bytes()).await?;
+        self.sums.sync_data().await?;
+        self.lock.touch();
+
+        self.next_chunk += 1;
+        Ok(true)
+    }
+
+    /// Verify the complete file against the object hash and move it into place
+    async fn finish(mut self) -> anyhow::Result<()> {
+        self.part.flush().await?;
+        self.part.seek(SeekFrom::Start(0)).await?;
+
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; self.chunk_size.min(DEFAULT_RESUMABLE_CHUNK_SIZE)];
+        loop {
+            let n = self.part.read(&mut buf).await?;
+            if n == 0 {
+                break;
             }
+            hasher.update(&buf[..n]);
+        }
+        drop(self.part);
+        drop(self.sums);
+
+        let written = hex::encode(hasher.finalize());
+        if written != self.digest {
+            // The prefix can no longer be trusted; start over on the next attempt
+            let _ = fs::remove_file(&self.part_path).await;
+            let _ = fs::remove_file(&self.sums_path).await;
+            return Err(anyhow::anyhow!(
+                "resumable write of {} failed verification (expected sha256 {}, found {})",
+                self.path.display(),
+                self.digest,
+                written
+            ));
         }
 
+        fs::rename(&self.part_path, &self.path).await?;
+        let _ = fs::remove_file(&self.sums_path).await;
+        tracing::debug!(
+            "Wrote {} ({} chunks reused from an earlier attempt)",
+            self.path.display(),
+            self.resumed_chunks
+        );
         Ok(())
     }
+}
 
-    /// Iteratively walk directory tree and collect matching keys
-    /// Uses a work queue to avoid recursive async function issues
-    ///
-    /// Reconstructs keys by removing the shard directories (first 2-4 path components)
-    /// that were added during storage.
-    async fn walk_dir_iterative(
-        objects_dir: &Path,
-        objects_base: &Path,
-        prefix: &str,
-        results: &mut Vec<String>,
-    ) -> anyhow::Result<()> {
-        let mut work_queue = vec![objects_dir.to_path_buf()];
-
-        while let Some(current_path) = work_queue.pop() {
-            let mut entries = match fs::read_dir(&current_path).await {
-                Ok(entries) => entries,
-                Err(_) => continue, // Skip directories we can't read
-            };
+/// Exclusive hold on a resumable write's files, released when dropped
+struct PartLock {
+    path: PathBuf,
+    file: std::fs::File,
+}
 
-            while let Some(entry) = entries.next_entry().await? {
-                let path = entry.path();
-
-                if path.is_dir() {
-                    // Add to work queue for processing
-                    work_queue.push(path);
-                } else {
-                    // Reconstruct the key by removing shard directories
-                    // Path structure: objects/AB/CD/key or objects/AB/key or objects/key
-                    // The key is the last component (or components after the shard dirs)
-
-                    if let Ok(relative_path) = path.strip_prefix(objects_base) {
-                        let components: Vec<_> = relative_path
-                            .components()
-                            .map(|c| c.as_os_str().to_string_lossy().to_string())
-                            .collect();
-
-                        // Reconstruct the original key by analyzing the path structure
-                        // Path structures:
-                        // - 1-char key: objects/X -> key is "X"
-                        // - 2-3 char key: objects/AB/encoded_key -> key is the filename
-                        // - 4+ char key: objects/AB/CD/encoded_key -> key is the filename
-                        // Filenames have "/" encoded as "::" which needs to be decoded
-                        let mut key = if components.is_empty() {
-                            continue;
-                        } else if components.len() == 1 {
-                            // Single-char key stored at objects/X
-                            components[0].clone()
-                        } else {
-                            // For 2+ components, the key is always in the last component (the filename)
-                            // The shard dirs (1st and maybe 2nd component) are just organizational
-                            components.last().unwrap().clone()
-                        };
-
-                        // Decode "__" back to "/"
-                        key = key.replace("__", "/");
-
-                        // Filter by prefix
-                        if key.starts_with(prefix) {
-                            results.push(key);
-                        }
-                    }
+impl PartLock {
+    /// Create the lock file, or return None if a live writer holds it
+    fn acquire(path: PathBuf) -> anyhow::Result<Option<Self>> {
+        let create = || {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+        };
+        let file = match create() {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let stale = std::fs::metadata(&path)
+                    .and_then(|metadata| metadata.modified())
+                    .is_ok_and(|modified| {
+                        modified
+                            .elapsed()
+                            .is_ok_and(|age| age > PART_LOCK_STALE_AFTER)
+                    });
+                if !stale {
+                    return Ok(None);
+                }
+                let _ = std::fs::remove_file(&path);
+                match create() {
+                    Ok(file) => file,
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return Ok(None),
+                    Err(e) => return Err(e.into()),
                 }
             }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(Self { path, file }))
+    }
+
+    /// Mark the write as still going, so stale-file cleanup leaves the lock alone
+    fn touch(&self) {
+        let _ = self.file.set_modified(std::time::SystemTime::now());
+    }
+}
+
+impl Drop for PartLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Append `suffix` to the file name of `path`
+fn sidecar_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Count the leading chunks of an existing `.mgpart` file that can be trusted
+///
+/// A chunk is trusted when the manifest was written for the same object
+/// (matching header), its recorded checksum matches the new data, and the bytes
+/// on disk still hash to that checksum. Counting stops at the first failure.
+async fn verified_chunks(
+    part: &mut fs::File,
+    sums_path: &Path,
+    header: &str,
+    data: &[u8],
+    chunk_size: usize,
+) -> usize {
+    let Ok(manifest) = fs::read_to_string(sums_path).await else {
+        return 0;
+    };
+    let mut lines = manifest.lines();
+    if lines.next() != Some(header) {
+        return 0;
+    }
+
+    let mut trusted = 0;
+    let mut buf = vec![0u8; chunk_size];
+    for (recorded, chunk) in lines.zip(data.chunks(chunk_size)) {
+        let expected = hex::encode(Sha256::digest(chunk));
+        if recorded != expected {
+            break;
         }
 
-        Ok(())
+        let on_disk = &mut buf[..chunk.len()];
+        let read = async {
+            part.seek(SeekFrom::Start((trusted * chunk_size) as u64))
+                .await?;
+            part.read_exact(on_disk).await
+        };
+        if read.await.is_err() || hex::encode(Sha256::digest(&*on_disk)) != expected {
+            break;
+        }
+        trusted += 1;
     }
+    trusted
 }
 
 #[cfg(test)]
@@ -762,6 +1772,181 @@ mod tests {
         assert_eq!(backend.root(), &path);
     }
 
+    #[tokio::test]
+    async fn test_case_probe_leaves_no_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path()).await.unwrap();
+
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+        // Linux filesystems are case-sensitive
+        if cfg!(target_os = "linux") {
+            assert!(!backend.is_case_insensitive());
+        }
+    }
+
+    /// Make `storage` see `textures/hero.png` under `Textures/Hero.png`, as a
+    /// case-insensitive filesystem would
+    #[cfg(unix)]
+    async fn fold_case(storage: &mut LocalBackend) {
+        storage.put("textures/hero.png", b"hero v1").await.unwrap();
+        let objects = storage.root().join("objects");
+        std::os::unix::fs::symlink(objects.join("te"), objects.join("Te")).unwrap();
+        fs::hard_link(
+            objects.join("te/xt/textures__hero.png"),
+            objects.join("te/xt/Textures__Hero.png"),
+        )
+        .unwrap();
+        storage.case_insensitive = true;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_case_collision_is_refused() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = LocalBackend::new(temp_dir.path()).await.unwrap();
+        fold_case(&mut storage).await;
+
+        let err = storage
+            .put("Textures/Hero.png", b"hero v2")
+            .await
+            .unwrap_err();
+        match err.downcast_ref::<StorageError>() {
+            Some(StorageError::CaseCollision { key, existing }) => {
+                assert_eq!(key, "Textures/Hero.png");
+                assert_eq!(existing, "textures/hero.png");
+            }
+            other => panic!("expected a case collision, got {:?}", other),
+        }
+        assert!(storage
+            .put_if_absent("Textures/Hero.png", b"hero v2")
+            .await
+            .is_err());
+        assert!(storage
+            .copy("textures/hero.png", "Textures/Hero.png")
+            .await
+            .is_err());
+
+        assert!(!storage.exists("Textures/Hero.png").await.unwrap());
+
+        // Reads do not hand back the other key's bytes
+        let is_collision = |err: anyhow::Error| {
+            err.downcast_ref::<StorageError>()
+                .is_some_and(StorageError::is_case_collision)
+        };
+        assert!(is_collision(
+            storage.get("Textures/Hero.png").await.unwrap_err()
+        ));
+        assert!(is_collision(
+            storage
+                .get_range("Textures/Hero.png", 0, 4)
+                .await
+                .unwrap_err()
+        ));
+        assert!(is_collision(
+            storage.get_mapped("Textures/Hero.png").await.unwrap_err()
+        ));
+        assert!(is_collision(
+            storage.get_stream("Textures/Hero.png").await.err().unwrap()
+        ));
+
+        // Without the second name the directory lists, the stored key itself
+        // is found and can be rewritten
+        fs::remove_file(storage.object_path("Textures/Hero.png")).unwrap();
+        assert_eq!(storage.get("textures/hero.png").await.unwrap(), b"hero v1");
+        assert!(storage.exists("textures/hero.png").await.unwrap());
+        storage.put("textures/hero.png", b"hero v3").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_object_paths_longer_than_max_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut root = temp_dir.path().to_path_buf();
+        for depth in 0..6 {
+            root.push(format!(
+                "{}-{}",
+                depth,
+                "nested-project-directory".repeat(2)
+            ));
+        }
+        let storage = LocalBackend::new(&root).await.unwrap();
+
+        let key = format!("renders/{}/final.exr", "shot-0420-".repeat(16));
+        let path = storage.object_path(&key);
+        assert!(path.as_os_str().len() > 260);
+
+        storage.put(&key, b"frame").await.unwrap();
+        assert_eq!(storage.get(&key).await.unwrap(), b"frame");
+        assert!(storage.exists(&key).await.unwrap());
+        assert_eq!(
+            storage.list_objects("renders/").await.unwrap(),
+            vec![key.clone()]
+        );
+        storage.delete(&key).await.unwrap();
+        assert!(!storage.exists(&key).await.unwrap());
+
+        let pack = format!("packs/{}.pack", "pack-".repeat(40));
+        storage.put(&pack, b"pack").await.unwrap();
+        assert_eq!(storage.list_objects("packs/").await.unwrap(), vec![pack]);
+    }
+
+    #[test]
+    fn test_windows_extended_paths() {
+        assert_eq!(
+            windows_extended(r"C:\Users\artist\project\.mediagit").as_deref(),
+            Some(r"\\?\C:\Users\artist\project\.mediagit")
+        );
+        assert_eq!(
+            windows_extended(r"\\nas\media\show\.mediagit").as_deref(),
+            Some(r"\\?\UNC\nas\media\show\.mediagit")
+        );
+        assert_eq!(
+            windows_extended("D:/studio/repo").as_deref(),
+            Some(r"\\?\D:\studio\repo")
+        );
+        // Already extended, device paths and relative paths are left alone
+        assert_eq!(windows_extended(r"\\?\C:\repo"), None);
+        assert_eq!(windows_extended(r"\\?\UNC\nas\media"), None);
+        assert_eq!(windows_extended(r"\\.\pipe\mediagit"), None);
+        assert_eq!(windows_extended(r"repo\.mediagit"), None);
+    }
+
+    #[test]
+    fn test_join_key() {
+        let base = Path::new("root");
+        assert_eq!(
+            join_key(base, "packs/pack-1.pack"),
+            base.join("packs").join("pack-1.pack")
+        );
+        assert_eq!(join_key(base, "a/"), base.join("a"));
+    }
+
+    #[cfg(windows)]
+    #[tokio::test]
+    async fn test_extended_root_on_windows() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = LocalBackend::new(temp_dir.path()).await.unwrap();
+
+        assert_eq!(storage.root(), temp_dir.path());
+        let path = storage.object_path("abcd1234");
+        assert!(path.to_string_lossy().starts_with(r"\\?\"));
+    }
+
+    #[test]
+    fn test_stored_key() {
+        assert_eq!(
+            stored_key(
+                "Textures/Hero.png",
+                "Textures__Hero.png",
+                "textures__hero.png"
+            ),
+            "textures/hero.png"
+        );
+        assert_eq!(
+            stored_key("packs/Pack-1.pack", "Pack-1.pack", "pack-1.pack"),
+            "packs/pack-1.pack"
+        );
+    }
+
     #[tokio::test]
     async fn test_new_with_existing_directory() {
         let temp_dir = TempDir::new().unwrap();
@@ -893,6 +2078,83 @@ mod tests {
         assert_eq!(empty.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_list_objects_stream() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path()).await.unwrap();
+
+        backend.put("images/photo1.jpg", b"data1").await.unwrap();
+        backend.put("images/photo2.jpg", b"data2").await.unwrap();
+        backend.put("videos/video1.mp4", b"data3").await.unwrap();
+        backend.put("packs/pack-1.pack", b"pack").await.unwrap();
+
+        let mut images: Vec<String> = backend
+            .list_objects_stream("images/")
+            .try_collect()
+            .await
+            .unwrap();
+        images.sort();
+        assert_eq!(images, ["images/photo1.jpg", "images/photo2.jpg"]);
+
+        let packs: Vec<String> = backend
+            .list_objects_stream("packs/")
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(packs, ["packs/pack-1.pack"]);
+
+        let mut empty = backend.list_objects_stream("nonexistent/");
+        assert!(empty.try_next().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_prefixes() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path()).await.unwrap();
+
+        backend.put("packs/pack-1.pack", b"pack").await.unwrap();
+        backend.put("packs/pack-1.idx", b"idx").await.unwrap();
+        backend.put("manifests/abc", b"m").await.unwrap();
+        backend.put("refs/heads/main", b"r").await.unwrap();
+        backend.put("refs/tags/v1", b"r").await.unwrap();
+        backend.put("abc123", b"loose").await.unwrap();
+
+        assert_eq!(
+            backend.list_prefixes("", "/").await.unwrap(),
+            ["manifests/", "packs/", "refs/"]
+        );
+        assert_eq!(
+            backend.list_prefixes("refs/", "/").await.unwrap(),
+            ["refs/heads/", "refs/tags/"]
+        );
+        assert_eq!(
+            backend.list_prefixes("packs/", ".").await.unwrap(),
+            ["packs/pack-1."]
+        );
+        assert!(backend
+            .list_prefixes("videos/", "/")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_usage_counts_packs() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path()).await.unwrap();
+
+        backend.put("packs/pack-1.pack", b"pack").await.unwrap();
+        backend.put("abc123", b"loose").await.unwrap();
+        backend.put("abd456", b"other").await.unwrap();
+
+        let usage = backend.usage("").await.unwrap();
+        assert_eq!((usage.object_count, usage.total_bytes), (3, 14));
+        let usage = backend.usage("abc").await.unwrap();
+        assert_eq!((usage.object_count, usage.total_bytes), (1, 5));
+        let usage = backend.usage("packs/").await.unwrap();
+        assert_eq!((usage.object_count, usage.total_bytes), (1, 4));
+    }
+
     #[tokio::test]
     async fn test_list_objects_sorted() {
         let temp_dir = TempDir::new().unwrap();
@@ -1033,6 +2295,76 @@ mod tests {
         assert_eq!(retrieved, large_data);
     }
 
+    #[tokio::test]
+    async fn test_stream_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path()).await.unwrap();
+
+        let data: Vec<u8> = (0..3 * STREAM_CHUNK_SIZE + 100)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let source = crate::stream::from_reader(std::io::Cursor::new(data.clone()));
+        backend.put_stream("video.mp4", source).await.unwrap();
+        assert_eq!(backend.get("video.mp4").await.unwrap(), data);
+
+        let chunks: Vec<_> = backend
+            .get_stream("video.mp4")
+            .await
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.len() <= STREAM_CHUNK_SIZE));
+        assert_eq!(chunks.concat(), data);
+
+        let missing = backend.get_stream("missing.mp4").await.err().unwrap();
+        assert!(missing.to_string().contains("object not found"));
+    }
+
+    #[tokio::test]
+    async fn test_get_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path()).await.unwrap();
+        backend.put("video.mp4", b"ftypisommoovmdat").await.unwrap();
+
+        assert_eq!(backend.get_range("video.mp4", 8, 4).await.unwrap(), b"moov");
+        assert_eq!(
+            backend.get_range("video.mp4", 12, 100).await.unwrap(),
+            b"mdat"
+        );
+        assert!(backend
+            .get_range("video.mp4", 4, 0)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(backend
+            .get_range("video.mp4", 40, 4)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let missing = backend.get_range("missing.mp4", 0, 4).await.err().unwrap();
+        assert!(missing.to_string().contains("object not found"));
+    }
+
+    #[tokio::test]
+    async fn test_put_stream_failure_leaves_no_object() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path()).await.unwrap();
+
+        let source: ObjectStream = Box::pin(futures::stream::iter(vec![
+            Ok(bytes::Bytes::from_static(b"partial")),
+            Err(anyhow::anyhow!("connection reset")),
+        ]));
+        assert!(backend.put_stream("video.mp4", source).await.is_err());
+        assert!(!backend.exists("video.mp4").await.unwrap());
+
+        let shard = backend.object_path("video.mp4");
+        let leftovers = fs::read_dir(shard.parent().unwrap()).unwrap().count();
+        assert_eq!(leftovers, 0);
+    }
+
     #[tokio::test]
     async fn test_debug_impl() {
         let temp_dir = TempDir::new().unwrap();
@@ -1135,6 +2467,127 @@ mod tests {
         assert_eq!(size, 12345);
     }
 
+    #[tokio::test]
+    async fn test_head() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path()).await.unwrap();
+
+        backend.put("head_test", &[7u8; 4096]).await.unwrap();
+        let meta = backend.head("head_test").await.unwrap();
+        assert_eq!(meta.size, 4096);
+        assert!(meta.modified.is_some());
+
+        let err = backend.head("missing").await.unwrap_err();
+        assert!(err.to_string().contains("object not found"));
+    }
+
+    #[tokio::test]
+    async fn test_put_if_absent() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = std::sync::Arc::new(LocalBackend::new(temp_dir.path()).await.unwrap());
+
+        assert!(backend.put_if_absent("abc123", b"first").await.unwrap());
+        assert!(!backend.put_if_absent("abc123", b"second").await.unwrap());
+        assert_eq!(backend.get("abc123").await.unwrap(), b"first");
+
+        // Exactly one of several concurrent writers wins
+        let writers: Vec<_> = (0..8u8)
+            .map(|i| {
+                let backend = backend.clone();
+                tokio::spawn(async move { backend.put_if_absent("raced", &[i; 64]).await })
+            })
+            .collect();
+        let mut won = 0;
+        for writer in writers {
+            won += writer.await.unwrap().unwrap() as usize;
+        }
+        assert_eq!(won, 1);
+        assert_eq!(backend.get("raced").await.unwrap().len(), 64);
+        assert_eq!(backend.list_objects("").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path()).await.unwrap();
+
+        backend.put("loose/abc123", b"content").await.unwrap();
+        backend.put("objects/abc123", b"stale").await.unwrap();
+        backend
+            .copy("loose/abc123", "objects/abc123")
+            .await
+            .unwrap();
+        backend
+            .copy("loose/abc123", "nested/dir/abc123")
+            .await
+            .unwrap();
+
+        assert_eq!(backend.get("objects/abc123").await.unwrap(), b"content");
+        assert_eq!(backend.get("nested/dir/abc123").await.unwrap(), b"content");
+        assert_eq!(backend.get("loose/abc123").await.unwrap(), b"content");
+
+        let missing = backend.copy("missing", "elsewhere").await.unwrap_err();
+        assert!(missing.to_string().contains("object not found"));
+        assert!(!backend.exists("elsewhere").await.unwrap());
+        assert_eq!(backend.list_objects("").await.unwrap().len(), 3);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_alternates_share_identical_objects() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let shared = temp_dir.path().join("shared");
+        let first = LocalBackend::new(temp_dir.path().join("first"))
+            .await
+            .unwrap()
+            .with_alternates(&shared);
+        let second = LocalBackend::new(temp_dir.path().join("second"))
+            .await
+            .unwrap()
+            .with_alternates(&shared);
+        let inode = |backend: &LocalBackend, key: &str| {
+            fs::metadata(backend.object_path(key)).unwrap().ino()
+        };
+
+        first.put("abcd1234", b"shared blob").await.unwrap();
+        second.put("abcd1234", b"shared blob").await.unwrap();
+        assert_eq!(inode(&first, "abcd1234"), inode(&second, "abcd1234"));
+
+        // Different bytes under the same key are written separately, and
+        // replacing a file leaves the other repository's copy alone
+        second.put("abcd1234", b"other bytes").await.unwrap();
+        assert_ne!(inode(&first, "abcd1234"), inode(&second, "abcd1234"));
+        assert_eq!(first.get("abcd1234").await.unwrap(), b"shared blob");
+        assert_eq!(second.get("abcd1234").await.unwrap(), b"other bytes");
+    }
+
+    #[tokio::test]
+    async fn test_link_from() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = LocalBackend::new(temp_dir.path().join("source"))
+            .await
+            .unwrap();
+        let target = LocalBackend::new(temp_dir.path().join("target"))
+            .await
+            .unwrap();
+        source.put("abcd1234", b"blob").await.unwrap();
+        source.put("packs/pack-1.pack", b"pack").await.unwrap();
+
+        assert!(target.link_from(&source, "abcd1234").await.unwrap());
+        assert!(target
+            .link_from(&source, "packs/pack-1.pack")
+            .await
+            .unwrap());
+        assert_eq!(target.get("abcd1234").await.unwrap(), b"blob");
+        assert_eq!(target.get("packs/pack-1.pack").await.unwrap(), b"pack");
+
+        let missing = target.link_from(&source, "missing0").await.unwrap_err();
+        assert!(missing.to_string().contains("object not found"));
+        assert_eq!(target.list_objects("").await.unwrap(), ["abcd1234"]);
+    }
+
     #[tokio::test]
     async fn test_adaptive_loading_small() {
         let temp_dir = TempDir::new().unwrap();
@@ -1169,6 +2622,60 @@ mod tests {
         assert_eq!(result.as_ref().len(), large_data.len());
     }
 
+    #[tokio::test]
+    async fn test_capabilities_report_mmap() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path()).await.unwrap();
+
+        let caps = backend.capabilities();
+        assert!(caps.mmap);
+        assert!(caps.server_side_copy);
+        assert!(!caps.compare_and_swap);
+        assert!(!caps.ttl);
+        assert!(caps.range_reads);
+        assert!(caps.streaming);
+    }
+
+    #[tokio::test]
+    async fn test_modified_time() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path()).await.unwrap();
+
+        let before = std::time::SystemTime::now() - std::time::Duration::from_secs(5);
+        backend.put("chunks/abcd1234", b"chunk").await.unwrap();
+
+        let modified = backend.modified("chunks/abcd1234").await.unwrap().unwrap();
+        assert!(modified >= before);
+        assert!(backend.modified("chunks/missing0").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sweep_removes_abandoned_temp_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path()).await.unwrap();
+        backend.put("abcd1234", b"object").await.unwrap();
+
+        // Left behind by writes that never finished
+        let path = backend.object_path("abcd5678");
+        backend.ensure_parent_dir(&path).await.unwrap();
+        fs::write(path.with_extension("tmp7"), b"partial").unwrap();
+        fs::write(sidecar_path(&path, PART_SUFFIX), b"partial").unwrap();
+        assert_eq!(backend.list_objects("").await.unwrap(), ["abcd1234"]);
+
+        // Too recent to be abandoned
+        let policy = ExpiryPolicy::new()
+            .abort_incomplete_uploads_after(std::time::Duration::from_secs(3600));
+        let report = backend.sweep_expired(&policy).await.unwrap();
+        assert_eq!(report.incomplete_uploads, 0);
+
+        let policy = ExpiryPolicy::new().abort_incomplete_uploads_after(std::time::Duration::ZERO);
+        let report = backend.sweep_expired(&policy).await.unwrap();
+        assert_eq!(report.incomplete_uploads, 2);
+        assert!(report.expired.is_empty());
+        assert!(!path.with_extension("tmp7").exists());
+        assert!(backend.exists("abcd1234").await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_mmap_or_vec_as_ref() {
         // Test that MmapOrVec::as_ref works correctly for both variants
@@ -1183,4 +2690,145 @@ mod tests {
         let slice: &[u8] = result.as_ref();
         assert_eq!(slice, data);
     }
+
+    #[tokio::test]
+    async fn test_resumable_write_resumes_after_interruption() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path())
+            .await
+            .unwrap()
+            .with_resumable_writes(1024, 256);
+
+        let data: Vec<u8> = (0..2000u32).map(|i| (i * 31 % 251) as u8).collect();
+        let path = backend.object_path("resumable_key");
+        let part_path = sidecar_path(&path, PART_SUFFIX);
+        backend.ensure_parent_dir(&path).await.unwrap();
+
+        // Write three chunks, then tear the fourth halfway through and "crash"
+        {
+            let mut write = ResumableWrite::begin(&path, &data, 256)
+                .await
+                .unwrap()
+                .unwrap();
+            for _ in 0..3 {
+                assert!(write.write_next_chunk().await.unwrap());
+            }
+            write.part.write_all(&data[768..900]).await.unwrap();
+            write.part.flush().await.unwrap();
+        }
+        assert!(!path.exists());
+        assert_eq!(fs::metadata(&part_path).unwrap().len(), 900);
+
+        // The verified prefix is kept and the torn tail is discarded
+        let write = ResumableWrite::begin(&path, &data, 256)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(write.resumed_chunks, 3);
+        assert_eq!(fs::metadata(&part_path).unwrap().len(), 768);
+        drop(write);
+
+        backend.put("resumable_key", &data).await.unwrap();
+        assert_eq!(backend.get("resumable_key").await.unwrap(), data);
+        assert!(!part_path.exists());
+        assert!(!sidecar_path(&path, PART_SUMS_SUFFIX).exists());
+        assert_eq!(
+            backend.list_objects("").await.unwrap(),
+            vec!["resumable_key"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resumable_write_rejects_corrupted_prefix() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp_dir.path())
+            .await
+            .unwrap()
+            .with_resumable_writes(1024, 256);
+
+        let data = vec![0xA5u8; 1500];
+        let path = backend.object_path("corrupt_key");
+        let part_path = sidecar_path(&path, PART_SUFFIX);
+        backend.ensure_parent_dir(&path).await.unwrap();
+
+        {
+            let mut write = ResumableWrite::begin(&path, &data, 256)
+                .await
+                .unwrap()
+                .unwrap();
+            for _ in 0..4 {
+                write.write_next_chunk().await.unwrap();
+            }
+        }
+
+        // Flip a byte inside the second chunk, as a flaky mount might
+        let mut bytes = fs::read(&part_path).unwrap();
+        bytes[300] ^= 0xFF;
+        fs::write(&part_path, bytes).unwrap();
+
+        let write = ResumableWrite::begin(&path, &data, 256)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(write.resumed_chunks, 1);
+        drop(write);
+
+        backend.put("corrupt_key", &data).await.unwrap();
+        assert_eq!(backend.get("corrupt_key").await.unwrap(), data);
+    }
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_resumable_writes_of_one_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = std::sync::Arc::new(
+            LocalBackend::new(temp_dir.path())
+                .await
+                .unwrap()
+                .with_resumable_writes(1024, 256),
+        );
+        let data: Vec<u8> = (0..4000u32).map(|i| (i * 7 % 253) as u8).collect();
+
+        let writes: Vec<_> = (0..8)
+            .map(|_| {
+                let backend = backend.clone();
+                let data = data.clone();
+                tokio::spawn(async move { backend.put("shared_key", &data).await })
+            })
+            .collect();
+        for write in writes {
+            write.await.unwrap().unwrap();
+        }
+        assert_eq!(backend.get("shared_key").await.unwrap(), data);
+
+        // Neither part files nor locks are left behind
+        let path = backend.object_path("shared_key");
+        let files: Vec<_> = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files, vec![path.clone()]);
+
+        // While one write holds the lock, another put goes through a temporary
+        // file and leaves the first write's part file alone
+        let mut held = ResumableWrite::begin(&path, &data, 256)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(ResumableWrite::begin(&path, &data, 256)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(held.write_next_chunk().await.unwrap());
+        backend.put("shared_key", &data).await.unwrap();
+        assert_eq!(
+            fs::metadata(sidecar_path(&path, PART_SUFFIX))
+                .unwrap()
+                .len(),
+            256
+        );
+
+        while held.write_next_chunk().await.unwrap() {}
+        held.finish().await.unwrap();
+        assert_eq!(backend.get("shared_key").await.unwrap(), data);
+        assert!(!sidecar_path(&path, PART_LOCK_SUFFIX).exists());
+    }
 }
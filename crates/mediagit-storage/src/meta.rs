@@ -0,0 +1,70 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Object metadata returned by [`StorageBackend::head`](crate::StorageBackend::head)
+//!
+//! Cloud backends answer with a HEAD request and local ones with a `stat`,
+//! so callers can learn an object's size or age without downloading it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::{mock::MockBackend, StorageBackend};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let storage = MockBackend::new();
+//! storage.put("video.mp4", b"content").await?;
+//!
+//! assert_eq!(storage.head("video.mp4").await?.size, 7);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// What a backend knows about a stored object, short of its content
+///
+/// Only `size` is always known; the other fields are filled in where the
+/// backend tracks them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjectMeta {
+    /// Size in bytes
+    pub size: u64,
+
+    /// When the object was last written
+    pub modified: Option<SystemTime>,
+
+    /// Entity tag or checksum identifying this version of the object
+    ///
+    /// Its format is backend-specific; only compare tags from the same
+    /// backend.
+    pub etag: Option<String>,
+
+    /// MIME type recorded with the object
+    pub content_type: Option<String>,
+
+    /// User metadata recorded with the object
+    pub metadata: HashMap<String, String>,
+}
+
+impl ObjectMeta {
+    /// Metadata holding only a size
+    pub fn with_size(size: u64) -> Self {
+        Self {
+            size,
+            ..Self::default()
+        }
+    }
+}
@@ -24,6 +24,9 @@
 //! - MinIO authentication (Access Key ID + Secret Access Key)
 //! - SSL/TLS support for secure connections
 //! - Automatic credential handling
+//! - Streaming reads and writes for objects larger than memory
+//! - Storage classes by key prefix, for services that support them
+//! - Server-side encryption with SSE-S3, SSE-KMS or a customer-provided key
 //!
 //! # Configuration
 //!
@@ -77,14 +80,34 @@
 //! - Use MinIO's distributed mode for high availability
 //! - Enable encryption at rest for sensitive data
 
-use crate::StorageBackend;
+use crate::checksum::ChecksumAlgorithm;
+use crate::expiry::{self, ExpiryPolicy, SweepReport};
+use crate::proxy::ProxySettings;
+use crate::retry::RetryPolicy;
+use crate::s3::{
+    abort_stale_uploads, apply_lifecycle_rules, copy_object, delete_objects, head_object_meta,
+    is_invalid_range, is_not_found, is_precondition_failed, list_common_prefixes, list_pages,
+    measure_objects, put_multipart_stream, request_error, sdk_checksum_algorithm, stored_checksum,
+    ChecksumHeaders, UploadOptions, WriteCondition, DELETE_BATCH_SIZE,
+};
+use crate::sse::{ServerSideEncryption, SseHeaders};
+use crate::storage_class::{StorageClass, StorageClassPolicy};
+use crate::stream::{KeyStream, PartReader};
+use crate::timeouts::{self, TimeoutSettings};
+use crate::tls::TlsSettings;
+use crate::{
+    BackendCapabilities, DeleteFailure, ObjectMeta, ObjectStream, StorageBackend, StorageUsage,
+};
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
+use aws_sdk_s3::types::ChecksumMode;
 use aws_sdk_s3::Client;
 use bytes::Bytes;
+use futures::TryStreamExt;
 use std::fmt;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{debug, warn};
 
 /// Configuration for the MinIO backend
@@ -111,11 +134,41 @@ pub struct MinIOConfig {
     /// Maximum number of concurrent parts to upload (default: 8)
     pub max_concurrent_parts: usize,
 
-    /// Maximum number of retries for failed operations (default: 3)
+    /// Maximum attempts for each request; only transient failures such as
+    /// throttling, 5xx responses and timeouts are retried (default: 3)
     pub max_retries: u32,
 
-    /// Initial retry delay in milliseconds (default: 100ms)
+    /// Delay before the first retry in milliseconds, doubled for each one
+    /// after (default: 100ms)
     pub initial_retry_delay_ms: u64,
+
+    /// Outbound proxy; unset fields fall back to `HTTPS_PROXY`/`NO_PROXY`
+    pub proxy: ProxySettings,
+
+    /// Connect and read timeouts for requests to the endpoint
+    pub timeouts: TimeoutSettings,
+
+    /// CA bundle and certificate verification for the endpoint; an unset
+    /// CA bundle falls back to `AWS_CA_BUNDLE`
+    pub tls: TlsSettings,
+
+    /// Region requests are signed for (default: `us-east-1`, which MinIO
+    /// and most S3-compatible services accept)
+    pub signing_region: String,
+
+    /// Checksum sent with each upload for the server to verify, and checked
+    /// again on `get` (default: none)
+    pub checksum: Option<ChecksumAlgorithm>,
+
+    /// Storage class each object is written in, by key prefix (default: the
+    /// bucket's default class). MinIO itself only knows `STANDARD` and
+    /// whatever tiers the server has configured.
+    pub storage_class: StorageClassPolicy,
+
+    /// Server-side encryption objects are written with, and for SSE-C read
+    /// with (default: the bucket's default encryption). MinIO needs a KMS
+    /// configured for SSE-S3 and SSE-KMS, and TLS for SSE-C.
+    pub encryption: Option<ServerSideEncryption>,
 }
 
 impl Default for MinIOConfig {
@@ -130,10 +183,85 @@ impl Default for MinIOConfig {
             max_concurrent_parts: 8,
             max_retries: 3,
             initial_retry_delay_ms: 100,
+            proxy: ProxySettings::default(),
+            timeouts: TimeoutSettings::default(),
+            tls: TlsSettings::default(),
+            signing_region: "us-east-1".to_string(),
+            checksum: None,
+            storage_class: StorageClassPolicy::default(),
+            encryption: None,
         }
     }
 }
 
+impl MinIOConfig {
+    /// How requests are retried, from `max_retries` and `initial_retry_delay_ms`
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::new(
+            self.max_retries,
+            Duration::from_millis(self.initial_retry_delay_ms),
+        )
+    }
+
+    /// Validated configuration for `bucket` at `endpoint`, with defaults for
+    /// everything else
+    ///
+    /// # Errors
+    ///
+    /// Fails if the endpoint is not an `http(s)://` URL, the bucket name breaks
+    /// S3 naming rules, or either credential is empty.
+    pub fn new(endpoint: &str, bucket: &str, access_key: &str, secret_key: &str) -> Result<Self> {
+        // Validate endpoint format
+        if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+            return Err(anyhow!(
+                "Invalid endpoint: must start with http:// or https://"
+            ));
+        }
+
+        // Remove trailing slash for consistency
+        let endpoint = endpoint.trim_end_matches('/').to_string();
+
+        // Validate bucket name (S3 bucket naming rules)
+        if bucket.is_empty() {
+            return Err(anyhow!("bucket name cannot be empty"));
+        }
+
+        if bucket.len() > 63 {
+            return Err(anyhow!("bucket name must be 63 characters or less"));
+        }
+
+        if !bucket
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err(anyhow!(
+                "bucket name must contain only lowercase letters, numbers, and hyphens"
+            ));
+        }
+
+        if bucket.starts_with('-') || bucket.ends_with('-') {
+            return Err(anyhow!("bucket name cannot start or end with a hyphen"));
+        }
+
+        // Validate credentials
+        if access_key.is_empty() {
+            return Err(anyhow!("access key cannot be empty"));
+        }
+
+        if secret_key.is_empty() {
+            return Err(anyhow!("secret key cannot be empty"));
+        }
+
+        Ok(MinIOConfig {
+            endpoint,
+            bucket: bucket.to_string(),
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+            ..Default::default()
+        })
+    }
+}
+
 /// Internal statistics for the MinIO backend
 #[derive(Debug)]
 struct MinIOStats {
@@ -210,55 +338,30 @@ impl MinIOBackend {
         access_key: &str,
         secret_key: &str,
     ) -> anyhow::Result<Self> {
-        // Validate endpoint format
-        if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
-            return Err(anyhow::anyhow!(
-                "Invalid endpoint: must start with http:// or https://"
-            ));
-        }
-
-        // Remove trailing slash for consistency
-        let endpoint = endpoint.trim_end_matches('/').to_string();
-
-        // Validate bucket name (S3 bucket naming rules)
-        if bucket.is_empty() {
-            return Err(anyhow::anyhow!("bucket name cannot be empty"));
-        }
-
-        if bucket.len() > 63 {
-            return Err(anyhow::anyhow!("bucket name must be 63 characters or less"));
-        }
-
-        if !bucket
-            .chars()
-            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
-        {
-            return Err(anyhow::anyhow!(
-                "bucket name must contain only lowercase letters, numbers, and hyphens"
-            ));
-        }
-
-        if bucket.starts_with('-') || bucket.ends_with('-') {
-            return Err(anyhow::anyhow!(
-                "bucket name cannot start or end with a hyphen"
-            ));
-        }
-
-        // Validate credentials
-        if access_key.is_empty() {
-            return Err(anyhow::anyhow!("access key cannot be empty"));
-        }
-
-        if secret_key.is_empty() {
-            return Err(anyhow::anyhow!("secret key cannot be empty"));
-        }
+        Self::with_proxy(
+            endpoint,
+            bucket,
+            access_key,
+            secret_key,
+            ProxySettings::default(),
+        )
+        .await
+    }
 
+    /// Create a new MinIO backend that connects through an HTTP proxy
+    ///
+    /// Same as [`MinIOBackend::new`], with explicit proxy settings. Anything
+    /// left unset in `proxy` is still read from the environment.
+    pub async fn with_proxy(
+        endpoint: &str,
+        bucket: &str,
+        access_key: &str,
+        secret_key: &str,
+        proxy: ProxySettings,
+    ) -> anyhow::Result<Self> {
         let config = MinIOConfig {
-            endpoint: endpoint.clone(),
-            bucket: bucket.to_string(),
-            access_key: access_key.to_string(),
-            secret_key: secret_key.to_string(),
-            ..Default::default()
+            proxy,
+            ..MinIOConfig::new(endpoint, bucket, access_key, secret_key)?
         };
 
         Self::with_config(config).await
@@ -276,31 +379,11 @@ impl MinIOBackend {
     /// * `Err` - If AWS SDK initialization or bucket access fails
     pub async fn with_config(config: MinIOConfig) -> Result<Self> {
         debug!(
-            "Initializing MinIO backend: endpoint={}, bucket={}, path_style={}",
-            config.endpoint, config.bucket, config.path_style
-        );
-
-        // Create credentials
-        let credentials = aws_sdk_s3::config::Credentials::new(
-            config.access_key.clone(),
-            config.secret_key.clone(),
-            None,
-            None,
-            "MinIOBackend",
+            "Initializing MinIO backend: endpoint={}, bucket={}, path_style={}, signing_region={}",
+            config.endpoint, config.bucket, config.path_style, config.signing_region
         );
 
-        // Build S3 configuration directly for MinIO/S3-compatible endpoints.
-        // We skip aws_config::defaults().load() to avoid IMDS region discovery
-        // which causes 2x 1-second timeouts in non-AWS environments.
-        let s3_config = aws_sdk_s3::config::Builder::new()
-            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
-            .endpoint_url(&config.endpoint)
-            .credentials_provider(credentials)
-            .force_path_style(config.path_style)
-            .region(aws_sdk_s3::config::Region::new("us-east-1"))
-            .build();
-
-        let client = Client::from_conf(s3_config);
+        let client = Client::from_conf(Self::sdk_config(&config)?);
 
         // Ensure bucket exists; use create_bucket and treat "already exists" as success.
         // This avoids relying on head_bucket which can return unreliable error codes
@@ -341,6 +424,40 @@ impl MinIOBackend {
         })
     }
 
+    /// SDK client configuration for `config`
+    fn sdk_config(config: &MinIOConfig) -> Result<aws_sdk_s3::Config> {
+        let credentials = aws_sdk_s3::config::Credentials::new(
+            config.access_key.clone(),
+            config.secret_key.clone(),
+            None,
+            None,
+            "MinIOBackend",
+        );
+
+        // Build S3 configuration directly for MinIO/S3-compatible endpoints.
+        // We skip aws_config::defaults().load() to avoid IMDS region discovery
+        // which causes 2x 1-second timeouts in non-AWS environments.
+        let mut s3_config = aws_sdk_s3::config::Builder::new()
+            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
+            .endpoint_url(&config.endpoint)
+            .credentials_provider(credentials)
+            .force_path_style(config.path_style)
+            .region(aws_sdk_s3::config::Region::new(
+                config.signing_region.clone(),
+            ))
+            .timeout_config(config.timeouts.s3_timeout_config());
+        if let Some(http_client) = config
+            .proxy
+            .clone()
+            .or_env()
+            .s3_http_client(&config.tls.clone().or_env())?
+        {
+            debug!("Using custom HTTP client for MinIO (proxy or TLS settings)");
+            s3_config = s3_config.http_client(http_client);
+        }
+        Ok(s3_config.build())
+    }
+
     /// Get current statistics
     pub fn stats(&self) -> (u64, u64, u64) {
         (
@@ -361,36 +478,16 @@ impl MinIOBackend {
         Ok(())
     }
 
-    /// Perform operation with exponential backoff retry logic
-    async fn with_retry<F, T>(&self, mut operation: F) -> Result<T>
+    /// Run `operation` under the configured [`RetryPolicy`], retrying
+    /// transient failures with exponential backoff
+    async fn with_retry<F, T>(&self, operation: F) -> Result<T>
     where
         F: FnMut() -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<T>> + Send>>,
     {
-        let mut retry_count = 0;
-        let mut delay_ms = self.config.initial_retry_delay_ms;
-
-        loop {
-            match operation().await {
-                Ok(result) => return Ok(result),
-                Err(e) => {
-                    retry_count += 1;
-                    if retry_count >= self.config.max_retries {
-                        return Err(e)
-                            .context(format!("Failed after {} retries", self.config.max_retries));
-                    }
-
-                    warn!(
-                        "Operation failed (attempt {}/{}), retrying in {}ms: {}",
-                        retry_count, self.config.max_retries, delay_ms, e
-                    );
-
-                    tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-
-                    // Exponential backoff with jitter
-                    delay_ms = (delay_ms * 2).min(10000); // Cap at 10 seconds
-                }
-            }
-        }
+        self.config
+            .retry_policy()
+            .run("MinIO request", operation)
+            .await
     }
 
     /// Create a new MinIO backend from environment variables
@@ -443,13 +540,36 @@ impl MinIOBackend {
         &self.bucket
     }
 
+    /// Upload with `put_object` or as a multipart upload depending on size,
+    /// returning `false` if `condition` didn't hold
+    async fn write(
+        &self,
+        key: &str,
+        data: &[u8],
+        condition: Option<WriteCondition>,
+    ) -> Result<bool> {
+        if data.len() as u64 <= self.config.part_size {
+            self.put_simple(key, data, condition).await
+        } else {
+            self.put_multipart(key, data, condition).await
+        }
+    }
+
     /// Store a small object directly using put_object
-    async fn put_simple(&self, key: &str, data: &[u8]) -> Result<()> {
+    async fn put_simple(
+        &self,
+        key: &str,
+        data: &[u8],
+        condition: Option<WriteCondition>,
+    ) -> Result<bool> {
         let client = self.client.clone();
         let bucket = self.config.bucket.clone();
         let key_clone = key.to_string();
         let stats = self.stats.clone();
         let body = Bytes::copy_from_slice(data);
+        let checksum = ChecksumHeaders::new(self.config.checksum, data);
+        let storage_class = self.config.storage_class.class_for(key);
+        let sse = SseHeaders::write(self.config.encryption.as_ref());
 
         self.with_retry(|| {
             let client = client.clone();
@@ -457,6 +577,9 @@ impl MinIOBackend {
             let key = key_clone.clone();
             let stats = stats.clone();
             let body = body.clone();
+            let condition = condition.clone();
+            let checksum = checksum.clone();
+            let sse = sse.clone();
 
             Box::pin(async move {
                 debug!(
@@ -465,27 +588,50 @@ impl MinIOBackend {
                     body.len()
                 );
 
-                client
+                let result = client
                     .put_object()
                     .bucket(&bucket)
                     .key(&key)
+                    .set_if_none_match(condition.as_ref().and_then(WriteCondition::if_none_match))
+                    .set_if_match(condition.as_ref().and_then(WriteCondition::if_match))
+                    .set_checksum_sha256(checksum.sha256)
+                    .set_checksum_crc32_c(checksum.crc32c)
+                    .set_storage_class(storage_class.map(StorageClass::to_sdk))
+                    .set_server_side_encryption(sse.algorithm)
+                    .set_ssekms_key_id(sse.kms_key_id)
+                    .set_sse_customer_algorithm(sse.customer_algorithm)
+                    .set_sse_customer_key(sse.customer_key)
+                    .set_sse_customer_key_md5(sse.customer_key_md5)
                     .body(body.clone().into())
                     .send()
-                    .await
-                    .map_err(|e| anyhow!("Failed to put object: {}", e))?;
+                    .await;
+                match result {
+                    Ok(_) => {}
+                    // Not worth retrying
+                    Err(e) if is_precondition_failed(&e) => {
+                        debug!("Precondition failed putting {}: {:?}", key, condition);
+                        return Ok(false);
+                    }
+                    Err(e) => return Err(request_error("Failed to put object", e)),
+                }
 
                 stats
                     .total_bytes_uploaded
                     .fetch_add(body.len() as u64, Ordering::Relaxed);
 
-                Ok(())
+                Ok(true)
             })
         })
         .await
     }
 
     /// Store a large object using multipart upload
-    async fn put_multipart(&self, key: &str, data: &[u8]) -> Result<()> {
+    async fn put_multipart(
+        &self,
+        key: &str,
+        data: &[u8],
+        condition: Option<WriteCondition>,
+    ) -> Result<bool> {
         debug!(
             "Putting large object to MinIO (multipart): {} ({} bytes)",
             key,
@@ -497,13 +643,27 @@ impl MinIOBackend {
         let key_clone = key.to_string();
 
         // Initiate multipart upload
+        let checksum = self.config.checksum;
+        let sse = SseHeaders::write(self.config.encryption.as_ref());
         let multipart = client
             .create_multipart_upload()
             .bucket(&bucket)
             .key(&key_clone)
+            .set_checksum_algorithm(checksum.map(sdk_checksum_algorithm))
+            .set_storage_class(
+                self.config
+                    .storage_class
+                    .class_for(key)
+                    .map(StorageClass::to_sdk),
+            )
+            .set_server_side_encryption(sse.algorithm.clone())
+            .set_ssekms_key_id(sse.kms_key_id.clone())
+            .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+            .set_sse_customer_key(sse.customer_key.clone())
+            .set_sse_customer_key_md5(sse.customer_key_md5.clone())
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to initiate multipart upload: {}", e))?;
+            .map_err(|e| request_error("Failed to initiate multipart upload", e))?;
 
         let upload_id = multipart
             .upload_id()
@@ -518,9 +678,8 @@ impl MinIOBackend {
         // Upload parts concurrently
         let mut part_handles = vec![];
         let part_size = self.config.part_size as usize;
-        let mut part_number = 1;
 
-        for chunk in data.chunks(part_size) {
+        for (part_number, chunk) in (1..).zip(data.chunks(part_size)) {
             let client = client.clone();
             let bucket = bucket.clone();
             let key = key_clone.clone();
@@ -528,6 +687,7 @@ impl MinIOBackend {
             let stats = self.stats.clone();
             let chunk_data = chunk.to_vec();
             let part_num = part_number;
+            let sse = sse.clone();
 
             let handle = tokio::spawn(async move {
                 debug!(
@@ -537,16 +697,24 @@ impl MinIOBackend {
                     key
                 );
 
+                let part_checksum = ChecksumHeaders::new(checksum, &chunk_data);
                 let response = client
                     .upload_part()
                     .bucket(&bucket)
                     .key(&key)
                     .upload_id(&upload_id)
                     .part_number(part_num)
+                    .set_checksum_sha256(part_checksum.sha256.clone())
+                    .set_checksum_crc32_c(part_checksum.crc32c.clone())
+                    .set_sse_customer_algorithm(sse.customer_algorithm)
+                    .set_sse_customer_key(sse.customer_key)
+                    .set_sse_customer_key_md5(sse.customer_key_md5)
                     .body(Bytes::from(chunk_data.clone()).into())
                     .send()
                     .await
-                    .map_err(|e| anyhow!("Failed to upload part {}: {}", part_num, e))?;
+                    .map_err(|e| {
+                        request_error(&format!("Failed to upload part {}", part_num), e)
+                    })?;
 
                 let etag = response
                     .e_tag()
@@ -557,7 +725,7 @@ impl MinIOBackend {
                     .total_bytes_uploaded
                     .fetch_add(chunk_data.len() as u64, Ordering::Relaxed);
 
-                Ok::<_, anyhow::Error>((part_num, etag))
+                Ok::<_, anyhow::Error>((part_num, etag, part_checksum))
             });
 
             part_handles.push(handle);
@@ -569,15 +737,12 @@ impl MinIOBackend {
                     let _ = handle.await??;
                 }
             }
-
-            part_number += 1;
         }
 
         // Wait for all remaining parts to complete
         let mut parts = vec![];
         for handle in part_handles {
-            let (part_num, etag) = handle.await??;
-            parts.push((part_num, etag));
+            parts.push(handle.await??);
         }
 
         // Sort parts by part number
@@ -586,30 +751,55 @@ impl MinIOBackend {
         // Complete multipart upload
         let part_list: Vec<_> = parts
             .into_iter()
-            .map(|(part_num, etag)| {
+            .map(|(part_num, etag, checksum)| {
                 aws_sdk_s3::types::CompletedPart::builder()
                     .part_number(part_num)
                     .e_tag(etag)
+                    .set_checksum_sha256(checksum.sha256)
+                    .set_checksum_crc32_c(checksum.crc32c)
                     .build()
             })
             .collect();
 
-        client
+        let result = client
             .complete_multipart_upload()
             .bucket(&bucket)
             .key(&key_clone)
             .upload_id(&upload_id)
+            .set_if_none_match(condition.as_ref().and_then(WriteCondition::if_none_match))
+            .set_if_match(condition.as_ref().and_then(WriteCondition::if_match))
+            .set_sse_customer_algorithm(sse.customer_algorithm)
+            .set_sse_customer_key(sse.customer_key)
+            .set_sse_customer_key_md5(sse.customer_key_md5)
             .multipart_upload(
                 aws_sdk_s3::types::CompletedMultipartUpload::builder()
                     .set_parts(Some(part_list))
                     .build(),
             )
             .send()
-            .await
-            .map_err(|e| anyhow!("Failed to complete multipart upload: {}", e))?;
+            .await;
+        match result {
+            Ok(_) => {}
+            Err(e) if is_precondition_failed(&e) => {
+                debug!("Precondition failed putting {}: {:?}", key_clone, condition);
+                // Don't leave the uploaded parts behind
+                if let Err(e) = client
+                    .abort_multipart_upload()
+                    .bucket(&bucket)
+                    .key(&key_clone)
+                    .upload_id(&upload_id)
+                    .send()
+                    .await
+                {
+                    warn!("Failed to abort multipart upload for {}: {}", key_clone, e);
+                }
+                return Ok(false);
+            }
+            Err(e) => return Err(request_error("Failed to complete multipart upload", e)),
+        }
 
         debug!("Successfully completed multipart upload for {}", key_clone);
-        Ok(())
+        Ok(true)
     }
 }
 
@@ -634,31 +824,105 @@ impl StorageBackend for MinIOBackend {
         let bucket = self.config.bucket.clone();
         let key_clone = key.to_string();
         let stats = self.stats.clone();
+        let read_timeout = self.config.timeouts.read;
+        let checksum = self.config.checksum;
+        let sse = SseHeaders::read(self.config.encryption.as_ref());
 
         self.with_retry(|| {
             let client = client.clone();
             let bucket = bucket.clone();
             let key = key_clone.clone();
             let stats = stats.clone();
+            let sse = sse.clone();
 
             Box::pin(async move {
                 debug!("Getting object from MinIO: {}", key);
 
-                let response = client
-                    .get_object()
-                    .bucket(&bucket)
-                    .key(&key)
-                    .send()
-                    .await
-                    .map_err(|e| anyhow!("Failed to get object: {}", e))?;
+                let response = timeouts::within(
+                    read_timeout,
+                    client
+                        .get_object()
+                        .bucket(&bucket)
+                        .key(&key)
+                        .set_checksum_mode(checksum.map(|_| ChecksumMode::Enabled))
+                        .set_sse_customer_algorithm(sse.customer_algorithm)
+                        .set_sse_customer_key(sse.customer_key)
+                        .set_sse_customer_key_md5(sse.customer_key_md5)
+                        .send(),
+                )
+                .await?
+                .map_err(|e| request_error("Failed to get object", e))?;
+
+                let stored = checksum.and_then(|algorithm| stored_checksum(algorithm, &response));
+                let expected = response
+                    .content_length()
+                    .and_then(|len| u64::try_from(len).ok());
+                let data = timeouts::collect_body(response.body, read_timeout, expected).await?;
+                stats
+                    .total_bytes_downloaded
+                    .fetch_add(data.len() as u64, Ordering::Relaxed);
 
-                let body = response
-                    .body
-                    .collect()
-                    .await
-                    .map_err(|e| anyhow!("Failed to read object body: {}", e))?;
+                if let Some(stored) = stored {
+                    stored.verify(&key, &data)?;
+                }
+                Ok(data)
+            })
+        })
+        .await
+    }
+
+    /// Retrieve part of an object from MinIO with a `Range` request
+    ///
+    /// MinIO rejects a range starting past the end of the object, which is
+    /// returned as empty rather than as an error.
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        Self::validate_key(key)?;
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+
+        let client = self.client.clone();
+        let bucket = self.config.bucket.clone();
+        let key_clone = key.to_string();
+        let stats = self.stats.clone();
+        let read_timeout = self.config.timeouts.read;
+        let range = format!("bytes={}-{}", offset, offset.saturating_add(len - 1));
+        let sse = SseHeaders::read(self.config.encryption.as_ref());
+
+        self.with_retry(|| {
+            let client = client.clone();
+            let bucket = bucket.clone();
+            let key = key_clone.clone();
+            let stats = stats.clone();
+            let range = range.clone();
+            let sse = sse.clone();
 
-                let data = body.into_bytes().to_vec();
+            Box::pin(async move {
+                debug!("Getting {} of object from MinIO: {}", range, key);
+
+                let response = match timeouts::within(
+                    read_timeout,
+                    client
+                        .get_object()
+                        .bucket(&bucket)
+                        .key(&key)
+                        .range(&range)
+                        .set_sse_customer_algorithm(sse.customer_algorithm)
+                        .set_sse_customer_key(sse.customer_key)
+                        .set_sse_customer_key_md5(sse.customer_key_md5)
+                        .send(),
+                )
+                .await?
+                {
+                    Ok(response) => response,
+                    Err(e) if is_invalid_range(&e) => return Ok(Vec::new()),
+                    Err(e) => return Err(request_error("Failed to get object", e)),
+                };
+
+                let expected = response
+                    .content_length()
+                    .and_then(|len| u64::try_from(len).ok());
+                let data = timeouts::collect_body(response.body, read_timeout, expected).await?;
                 stats
                     .total_bytes_downloaded
                     .fetch_add(data.len() as u64, Ordering::Relaxed);
@@ -669,17 +933,160 @@ impl StorageBackend for MinIOBackend {
         .await
     }
 
+    /// Stream an object from MinIO
+    ///
+    /// Opening the object is retried; once data is flowing, a stalled or
+    /// truncated body fails the stream.
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        Self::validate_key(key)?;
+
+        let client = self.client.clone();
+        let bucket = self.config.bucket.clone();
+        let key_clone = key.to_string();
+        let read_timeout = self.config.timeouts.read;
+        let sse = SseHeaders::read(self.config.encryption.as_ref());
+
+        let response = self
+            .with_retry(|| {
+                let client = client.clone();
+                let bucket = bucket.clone();
+                let key = key_clone.clone();
+                let sse = sse.clone();
+
+                Box::pin(async move {
+                    debug!("Streaming object from MinIO: {}", key);
+
+                    timeouts::within(
+                        read_timeout,
+                        client
+                            .get_object()
+                            .bucket(&bucket)
+                            .key(&key)
+                            .set_sse_customer_algorithm(sse.customer_algorithm)
+                            .set_sse_customer_key(sse.customer_key)
+                            .set_sse_customer_key_md5(sse.customer_key_md5)
+                            .send(),
+                    )
+                    .await?
+                    .map_err(|e| request_error("Failed to get object", e))
+                })
+            })
+            .await?;
+
+        let expected = response
+            .content_length()
+            .and_then(|len| u64::try_from(len).ok());
+        let stats = self.stats.clone();
+        Ok(Box::pin(
+            timeouts::body_stream(response.body, read_timeout, expected).inspect_ok(move |chunk| {
+                stats
+                    .total_bytes_downloaded
+                    .fetch_add(chunk.len() as u64, Ordering::Relaxed);
+            }),
+        ))
+    }
+
+    /// Store an object in MinIO from a stream, one part at a time
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> anyhow::Result<()> {
+        Self::validate_key(key)?;
+
+        let mut parts = PartReader::new(data, self.config.part_size as usize);
+        let Some(first) = parts.next_part().await? else {
+            return self.put_simple(key, &[], None).await.map(|_| ());
+        };
+        let Some(second) = parts.next_part().await? else {
+            return self.put_simple(key, &first, None).await.map(|_| ());
+        };
+
+        let uploaded = put_multipart_stream(
+            &self.client,
+            &self.config.bucket,
+            key,
+            vec![first, second],
+            parts,
+            self.config.max_concurrent_parts,
+            UploadOptions {
+                checksum: self.config.checksum,
+                storage_class: self.config.storage_class.class_for(key),
+                encryption: SseHeaders::write(self.config.encryption.as_ref()),
+            },
+        )
+        .await?;
+        self.stats
+            .total_bytes_uploaded
+            .fetch_add(uploaded, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Streams in both directions, reads ranges, and copies and writes
+    /// conditionally within the service; objects can't be given an expiry
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            server_side_copy: true,
+            compare_and_swap: true,
+            ttl: true,
+            range_reads: true,
+            streaming: true,
+            ..BackendCapabilities::default()
+        }
+    }
+
     /// Store an object in MinIO
     async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
         Self::validate_key(key)?;
 
-        // For small objects, use simple put_object
-        if data.len() as u64 <= self.config.part_size {
-            return self.put_simple(key, data).await;
+        self.write(key, data, None).await.map(|_| ())
+    }
+
+    /// Store an object in MinIO unless the key is taken
+    ///
+    /// A HEAD request first skips uploading objects that are already
+    /// there; the upload itself carries `If-None-Match: *` so a concurrent
+    /// writer can't be overwritten either.
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> Result<bool> {
+        if self.exists(key).await? {
+            return Ok(false);
         }
+        self.write(key, data, Some(WriteCondition::Absent)).await
+    }
+
+    /// Replace an object in MinIO with an `If-Match` upload
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> Result<bool> {
+        Self::validate_key(key)?;
+        self.write(key, data, Some(WriteCondition::Matches(etag.to_string())))
+            .await
+    }
+
+    /// Copy an object within the bucket with CopyObject
+    async fn copy(&self, src_key: &str, dst_key: &str) -> Result<()> {
+        Self::validate_key(src_key)?;
+        Self::validate_key(dst_key)?;
+
+        let client = self.client.clone();
+        let bucket = self.config.bucket.clone();
+        let src = src_key.to_string();
+        let dst = dst_key.to_string();
+        let storage_class = self.config.storage_class.class_for(dst_key);
+        let encryption = self.config.encryption.clone();
+
+        let copied = self
+            .with_retry(|| {
+                let client = client.clone();
+                let bucket = bucket.clone();
+                let src = src.clone();
+                let dst = dst.clone();
+                let encryption = encryption.clone();
+                Box::pin(async move {
+                    let encryption = encryption.as_ref();
+                    copy_object(&client, &bucket, &src, &dst, storage_class, encryption).await
+                })
+            })
+            .await?;
 
-        // For large objects, use multipart upload
-        self.put_multipart(key, data).await
+        if !copied {
+            return Err(anyhow!("object not found: {}", src_key));
+        }
+        Ok(())
     }
 
     /// Check if an object exists in MinIO
@@ -689,16 +1096,27 @@ impl StorageBackend for MinIOBackend {
         let client = self.client.clone();
         let bucket = self.config.bucket.clone();
         let key_clone = key.to_string();
+        let sse = SseHeaders::read(self.config.encryption.as_ref());
 
         self.with_retry(|| {
             let client = client.clone();
             let bucket = bucket.clone();
             let key = key_clone.clone();
+            let sse = sse.clone();
 
             Box::pin(async move {
                 debug!("Checking if object exists in MinIO: {}", key);
 
-                match client.head_object().bucket(&bucket).key(&key).send().await {
+                let result = client
+                    .head_object()
+                    .bucket(&bucket)
+                    .key(&key)
+                    .set_sse_customer_algorithm(sse.customer_algorithm)
+                    .set_sse_customer_key(sse.customer_key)
+                    .set_sse_customer_key_md5(sse.customer_key_md5)
+                    .send()
+                    .await;
+                match result {
                     Ok(_) => {
                         debug!("Object exists: {}", key);
                         Ok(true)
@@ -718,7 +1136,7 @@ impl StorageBackend for MinIOBackend {
                             debug!("Object does not exist: {}", key);
                             Ok(false)
                         } else {
-                            Err(anyhow!("Failed to check object existence: {}", e))
+                            Err(request_error("Failed to check object existence", e))
                         }
                     }
                 }
@@ -727,6 +1145,47 @@ impl StorageBackend for MinIOBackend {
         .await
     }
 
+    /// Fetch an object's metadata with a HEAD request
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        Self::validate_key(key)?;
+
+        let client = self.client.clone();
+        let bucket = self.config.bucket.clone();
+        let key_clone = key.to_string();
+        let sse = SseHeaders::read(self.config.encryption.as_ref());
+
+        let meta = self
+            .with_retry(|| {
+                let client = client.clone();
+                let bucket = bucket.clone();
+                let key = key_clone.clone();
+                let sse = sse.clone();
+
+                Box::pin(async move {
+                    debug!("Fetching object metadata from MinIO: {}", key);
+
+                    let result = client
+                        .head_object()
+                        .bucket(&bucket)
+                        .key(&key)
+                        .set_sse_customer_algorithm(sse.customer_algorithm)
+                        .set_sse_customer_key(sse.customer_key)
+                        .set_sse_customer_key_md5(sse.customer_key_md5)
+                        .send()
+                        .await;
+                    match result {
+                        Ok(output) => Ok(Some(head_object_meta(&output))),
+                        // Not worth retrying
+                        Err(e) if is_not_found(&e) => Ok(None),
+                        Err(e) => Err(request_error("Failed to fetch object metadata", e)),
+                    }
+                })
+            })
+            .await?;
+
+        meta.ok_or_else(|| anyhow!("object not found: {}", key))
+    }
+
     /// Delete an object from MinIO
     async fn delete(&self, key: &str) -> anyhow::Result<()> {
         Self::validate_key(key)?;
@@ -751,7 +1210,7 @@ impl StorageBackend for MinIOBackend {
                     .key(&key)
                     .send()
                     .await
-                    .map_err(|e| anyhow!("Failed to delete object: {}", e))?;
+                    .map_err(|e| request_error("Failed to delete object", e))?;
 
                 stats.total_objects_deleted.fetch_add(1, Ordering::Relaxed);
 
@@ -761,63 +1220,122 @@ impl StorageBackend for MinIOBackend {
         .await
     }
 
-    /// List objects in MinIO with a given prefix
-    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
-        let client = self.client.clone();
-        let bucket = self.config.bucket.clone();
-        let prefix_clone = prefix.to_string();
-
-        self.with_retry(|| {
-            let client = client.clone();
-            let bucket = bucket.clone();
-            let prefix = prefix_clone.clone();
-
-            Box::pin(async move {
-                debug!("Listing objects in MinIO with prefix: '{}'", prefix);
+    /// Delete objects from MinIO with DeleteObjects requests of up to 1000 keys
+    ///
+    /// A request that fails outright, after retries, is reported as a
+    /// failure for each of its keys.
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        for key in keys {
+            Self::validate_key(key)?;
+        }
 
-                let mut result = vec![];
-                let mut continuation_token: Option<String> = None;
+        let mut failures = Vec::new();
+        for batch in keys.chunks(DELETE_BATCH_SIZE) {
+            let client = self.client.clone();
+            let bucket = self.config.bucket.clone();
+            let batch_keys = batch.to_vec();
+
+            let result = self
+                .with_retry(|| {
+                    let client = client.clone();
+                    let bucket = bucket.clone();
+                    let keys = batch_keys.clone();
+                    Box::pin(async move { delete_objects(&client, &bucket, &keys).await })
+                })
+                .await;
+
+            match result {
+                Ok(batch_failures) => {
+                    self.stats.total_objects_deleted.fetch_add(
+                        (batch.len() - batch_failures.len()) as u64,
+                        Ordering::Relaxed,
+                    );
+                    failures.extend(batch_failures);
+                }
+                Err(e) => {
+                    warn!("Failed to delete {} objects: {}", batch.len(), e);
+                    failures.extend(batch.iter().map(|key| DeleteFailure {
+                        key: key.clone(),
+                        error: anyhow!("{:#}", e),
+                    }));
+                }
+            }
+        }
+        Ok(failures)
+    }
 
-                loop {
-                    let mut request = client.list_objects_v2().bucket(&bucket);
+    /// List objects in MinIO with a given prefix
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        debug!("Listing objects in MinIO with prefix: '{}'", prefix);
 
-                    if !prefix.is_empty() {
-                        request = request.prefix(&prefix);
-                    }
+        let mut result: Vec<String> = self.list_objects_stream(prefix).try_collect().await?;
+        // Sort for consistency
+        result.sort();
 
-                    if let Some(token) = continuation_token {
-                        request = request.continuation_token(token);
-                    }
+        debug!("Found {} objects with prefix: '{}'", result.len(), prefix);
+        Ok(result)
+    }
 
-                    let response = request
-                        .send()
-                        .await
-                        .map_err(|e| anyhow!("Failed to list objects: {}", e))?;
+    /// Stream keys a ListObjectsV2 page at a time, each page retried on its own
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        list_pages(
+            &self.client,
+            &self.config.bucket,
+            prefix.to_string(),
+            self.config.retry_policy(),
+        )
+    }
 
-                    // Collect keys from this page
-                    for obj in response.contents() {
-                        if let Some(key) = obj.key() {
-                            result.push(key.to_string());
-                        }
-                    }
+    /// List CommonPrefixes with a delimited ListObjectsV2 request
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        list_common_prefixes(
+            &self.client,
+            &self.config.bucket,
+            prefix,
+            delimiter,
+            self.config.retry_policy(),
+        )
+        .await
+    }
 
-                    // Check if there are more results
-                    if response.is_truncated() == Some(true) {
-                        continuation_token =
-                            response.next_continuation_token().map(|t| t.to_string());
-                    } else {
-                        break;
-                    }
-                }
+    /// Sum the object sizes ListObjectsV2 reports, without a HEAD per object
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        measure_objects(
+            &self.client,
+            &self.config.bucket,
+            prefix,
+            self.config.retry_policy(),
+        )
+        .await
+    }
 
-                // Sort for consistency
-                result.sort();
+    /// Install the policy as bucket lifecycle rules, as for S3
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> anyhow::Result<bool> {
+        apply_lifecycle_rules(
+            &self.client,
+            &self.config.bucket,
+            policy,
+            self.config.retry_policy(),
+        )
+        .await?;
+        Ok(true)
+    }
 
-                debug!("Found {} objects with prefix: '{}'", result.len(), prefix);
-                Ok(result)
-            })
-        })
-        .await
+    /// Also aborts multipart uploads started longer ago than
+    /// `incomplete_uploads`
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport> {
+        let mut report = expiry::sweep_rules(self, policy).await?;
+        if let Some(ttl) = policy.incomplete_uploads {
+            report.incomplete_uploads = abort_stale_uploads(
+                &self.client,
+                &self.config.bucket,
+                &policy.scope,
+                ttl,
+                self.config.retry_policy(),
+            )
+            .await?;
+        }
+        Ok(report)
     }
 }
 
@@ -1119,4 +1637,43 @@ mod tests {
             std::env::remove_var("MINIO_SECRET_KEY");
         }
     }
+
+    #[test]
+    fn test_signing_region_applied_to_client_config() {
+        let config = MinIOConfig::new(
+            "https://s3.eu-central-2.wasabisys.com",
+            "mediagit-test",
+            "access",
+            "secret",
+        )
+        .unwrap();
+        let sdk_config = MinIOBackend::sdk_config(&config).unwrap();
+        assert_eq!(sdk_config.region().unwrap().as_ref(), "us-east-1");
+
+        let config = MinIOConfig {
+            signing_region: "eu-central-2".to_string(),
+            ..config
+        };
+        let sdk_config = MinIOBackend::sdk_config(&config).unwrap();
+        assert_eq!(sdk_config.region().unwrap().as_ref(), "eu-central-2");
+    }
+
+    #[test]
+    fn test_tls_settings_applied_to_client_config() {
+        let config =
+            MinIOConfig::new("https://minio.internal:9000", "mediagit-test", "a", "s").unwrap();
+
+        let config = MinIOConfig {
+            tls: TlsSettings::with_ca_bundle("/nonexistent/minio-ca.pem"),
+            ..config
+        };
+        let err = MinIOBackend::sdk_config(&config).unwrap_err();
+        assert!(format!("{:#}", err).contains("/nonexistent/minio-ca.pem"));
+
+        let config = MinIOConfig {
+            tls: TlsSettings::default().insecure(),
+            ..config
+        };
+        assert!(MinIOBackend::sdk_config(&config).is_ok());
+    }
 }
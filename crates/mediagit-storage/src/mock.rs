@@ -47,7 +47,7 @@
 //! }
 //! ```
 
-use crate::StorageBackend;
+use crate::{BackendCapabilities, StorageBackend};
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::fmt;
@@ -240,6 +240,79 @@ impl StorageBackend for MockBackend {
         results.sort();
         Ok(results)
     }
+
+    /// An in-memory map has none of the optional features
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities::default()
+    }
+}
+
+/// [`MockBackend`] that tags objects with etags and supports conditional
+/// replaces, for testing wrappers that forward them
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct CasBackend {
+    inner: MockBackend,
+    writes: tokio::sync::Mutex<()>,
+}
+
+#[cfg(test)]
+impl CasBackend {
+    fn etag(data: &[u8]) -> String {
+        use sha2::{Digest, Sha256};
+        hex::encode(Sha256::digest(data))
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl StorageBackend for CasBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.inner.get(key).await
+    }
+
+    async fn head(&self, key: &str) -> anyhow::Result<crate::ObjectMeta> {
+        let data = self.inner.get(key).await?;
+        Ok(crate::ObjectMeta {
+            etag: Some(Self::etag(&data)),
+            ..crate::ObjectMeta::with_size(data.len() as u64)
+        })
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        let _writes = self.writes.lock().await;
+        self.inner.put(key, data).await
+    }
+
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        let _writes = self.writes.lock().await;
+        match self.inner.get(key).await {
+            Ok(current) if Self::etag(&current) == etag => {
+                self.inner.put(key, data).await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        self.inner.exists(key).await
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.inner.delete(key).await
+    }
+
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        self.inner.list_objects(prefix).await
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            compare_and_swap: true,
+            ..BackendCapabilities::default()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -273,6 +346,21 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_get_range_slices_whole_object() {
+        let backend = MockBackend::new();
+        backend.put("video.mp4", b"0123456789").await.unwrap();
+
+        assert_eq!(backend.get_range("video.mp4", 2, 3).await.unwrap(), b"234");
+        assert_eq!(backend.get_range("video.mp4", 7, 10).await.unwrap(), b"789");
+        assert!(backend
+            .get_range("video.mp4", 12, 1)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(backend.get_range("missing.mp4", 0, 1).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_empty_key_operations() {
         let backend = MockBackend::new();
@@ -311,6 +399,23 @@ mod tests {
         backend.delete("nonexistent").await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_delete_many_reports_each_failure() {
+        let backend = MockBackend::new();
+        for i in 0..100 {
+            backend.put(&format!("key{}", i), b"data").await.unwrap();
+        }
+
+        let mut keys: Vec<String> = (0..100).map(|i| format!("key{}", i)).collect();
+        keys.push(String::new());
+        keys.push("nonexistent".to_string());
+
+        let failures = backend.delete_many(&keys).await.unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].key, "");
+        assert!(backend.is_empty().await);
+    }
+
     #[tokio::test]
     async fn test_list_objects() {
         let backend = MockBackend::new();
@@ -436,6 +541,14 @@ mod tests {
         assert!(keys.contains(&"cherry".to_string()));
     }
 
+    #[test]
+    fn test_capabilities() {
+        let caps = MockBackend::new().capabilities();
+        assert_eq!(caps, BackendCapabilities::default());
+        assert!(!caps.mmap);
+        assert!(!caps.compare_and_swap);
+    }
+
     #[tokio::test]
     async fn test_debug_impl() {
         let backend = MockBackend::new();
@@ -0,0 +1,280 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Key namespacing for shared buckets
+//!
+//! [`NamespacedBackend`] prepends a fixed prefix to every key before handing
+//! it to the wrapped backend, and strips it again from listed keys. Several
+//! repositories can then share one bucket (`repos/gameassets/`,
+//! `repos/website/`, ...) without seeing each other's objects, and MediaGit
+//! data stays separate from anything else stored there.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::{mock::MockBackend, NamespacedBackend, StorageBackend};
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let bucket = Arc::new(MockBackend::new());
+//! let repo = NamespacedBackend::new(bucket.clone(), "repos/gameassets");
+//!
+//! repo.put("abc123", b"data").await?;
+//! assert!(bucket.exists("repos/gameassets/abc123").await?);
+//! assert_eq!(repo.list_objects("").await?, vec!["abc123"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    BackendCapabilities, DeleteFailure, ExpiryPolicy, KeyStream, MmapOrVec, ObjectMeta,
+    ObjectStream, StorageBackend, StorageUsage, SweepReport,
+};
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use std::sync::Arc;
+
+/// Storage backend wrapper that confines all keys to a prefix
+#[derive(Debug, Clone)]
+pub struct NamespacedBackend {
+    inner: Arc<dyn StorageBackend>,
+    prefix: String,
+}
+
+impl NamespacedBackend {
+    /// Wrap `inner` so every key lives under `prefix`
+    ///
+    /// Leading slashes are dropped and a trailing `/` is added, so
+    /// `"/repos/gameassets"` and `"repos/gameassets/"` name the same
+    /// namespace. An empty prefix passes keys through unchanged.
+    pub fn new(inner: Arc<dyn StorageBackend>, prefix: impl AsRef<str>) -> Self {
+        Self {
+            inner,
+            prefix: normalize_prefix(prefix.as_ref()),
+        }
+    }
+
+    /// The normalized prefix, including its trailing `/` (empty if none)
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Arc<dyn StorageBackend> {
+        &self.inner
+    }
+
+    fn full_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    fn full_policy(&self, policy: &ExpiryPolicy) -> ExpiryPolicy {
+        let mut policy = policy.clone();
+        for rule in &mut policy.rules {
+            rule.prefix = self.full_key(&rule.prefix);
+        }
+        policy.scope = self.full_key(&policy.scope);
+        policy
+    }
+}
+
+/// Normalize a namespace to `segment/segment/` form
+pub fn normalize_prefix(prefix: &str) -> String {
+    let trimmed = prefix.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", trimmed)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for NamespacedBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.inner.get(&self.full_key(key)).await
+    }
+
+    async fn get_mapped(&self, key: &str) -> anyhow::Result<MmapOrVec> {
+        self.inner.get_mapped(&self.full_key(key)).await
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<std::time::SystemTime>> {
+        self.inner.modified(&self.full_key(key)).await
+    }
+
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        self.inner.head(&self.full_key(key)).await
+    }
+
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        self.inner.get_range(&self.full_key(key), offset, len).await
+    }
+
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        self.inner.get_stream(&self.full_key(key)).await
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        self.inner.put(&self.full_key(key), data).await
+    }
+
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        self.inner.put_if_absent(&self.full_key(key), data).await
+    }
+
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        self.inner
+            .put_if_match(&self.full_key(key), data, etag)
+            .await
+    }
+
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        self.inner
+            .copy(&self.full_key(src_key), &self.full_key(dst_key))
+            .await
+    }
+
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> anyhow::Result<()> {
+        self.inner.put_stream(&self.full_key(key), data).await
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        self.inner.exists(&self.full_key(key)).await
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.inner.delete(&self.full_key(key)).await
+    }
+
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        let full_keys: Vec<String> = keys.iter().map(|key| self.full_key(key)).collect();
+        let mut failures = self.inner.delete_many(&full_keys).await?;
+        for failure in &mut failures {
+            if let Some(key) = failure.key.strip_prefix(&self.prefix) {
+                failure.key = key.to_string();
+            }
+        }
+        Ok(failures)
+    }
+
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let keys = self.inner.list_objects(&self.full_key(prefix)).await?;
+        Ok(keys
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_string))
+            .collect())
+    }
+
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        self.inner
+            .list_objects_stream(&self.full_key(prefix))
+            .try_filter_map(|key| {
+                let key = key.strip_prefix(&self.prefix).map(str::to_string);
+                async move { Ok(key) }
+            })
+            .boxed()
+    }
+
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        let prefixes = self
+            .inner
+            .list_prefixes(&self.full_key(prefix), delimiter)
+            .await?;
+        Ok(prefixes
+            .into_iter()
+            .filter_map(|common| common.strip_prefix(&self.prefix).map(str::to_string))
+            .collect())
+    }
+
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        self.inner.usage(&self.full_key(prefix)).await
+    }
+
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> anyhow::Result<bool> {
+        self.inner.apply_expiry(&self.full_policy(policy)).await
+    }
+
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport> {
+        let mut report = self.inner.sweep_expired(&self.full_policy(policy)).await?;
+        for key in &mut report.expired {
+            if let Some(stripped) = key.strip_prefix(&self.prefix) {
+                *key = stripped.to_string();
+            }
+        }
+        for failure in &mut report.failures {
+            if let Some(key) = failure.key.strip_prefix(&self.prefix) {
+                failure.key = key.to_string();
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockBackend;
+
+    #[test]
+    fn test_normalize_prefix() {
+        assert_eq!(normalize_prefix("repos/gameassets"), "repos/gameassets/");
+        assert_eq!(normalize_prefix("/repos/gameassets/"), "repos/gameassets/");
+        assert_eq!(normalize_prefix(""), "");
+        assert_eq!(normalize_prefix("/"), "");
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_prefixed_and_listing_is_scoped() {
+        let bucket = Arc::new(MockBackend::new());
+        bucket.put("unrelated/report.csv", b"other").await.unwrap();
+
+        let repo = NamespacedBackend::new(bucket.clone(), "repos/a");
+        repo.put("objects/1", b"one").await.unwrap();
+
+        assert!(bucket.exists("repos/a/objects/1").await.unwrap());
+        assert_eq!(repo.get("objects/1").await.unwrap(), b"one");
+        assert!(!repo.exists("unrelated/report.csv").await.unwrap());
+        assert_eq!(repo.list_objects("").await.unwrap(), vec!["objects/1"]);
+        assert_eq!(repo.list_objects("obj").await.unwrap(), vec!["objects/1"]);
+        assert_eq!(repo.list_prefixes("", "/").await.unwrap(), vec!["objects/"]);
+
+        repo.delete("objects/1").await.unwrap();
+        assert!(!bucket.exists("repos/a/objects/1").await.unwrap());
+        assert!(bucket.exists("unrelated/report.csv").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_come_from_inner_backend() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let local = Arc::new(crate::LocalBackend::new(temp_dir.path()).await.unwrap());
+        assert!(NamespacedBackend::new(local, "repos/a").capabilities().mmap);
+
+        let mock = Arc::new(MockBackend::new());
+        assert!(!NamespacedBackend::new(mock, "repos/a").capabilities().mmap);
+    }
+
+    #[test]
+    fn test_expiry_policy_is_scoped_to_namespace() {
+        let repo = NamespacedBackend::new(Arc::new(MockBackend::new()), "repos/a");
+        let policy = ExpiryPolicy::new().expire("tmp/", std::time::Duration::from_secs(60));
+
+        let scoped = repo.full_policy(&policy);
+        assert_eq!(scoped.rules[0].prefix, "repos/a/tmp/");
+        assert_eq!(scoped.scope, "repos/a/");
+    }
+}
@@ -0,0 +1,220 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Outbound HTTP proxy settings
+//!
+//! S3-compatible backends and the protocol client route their requests
+//! through an HTTP proxy when one is configured. Settings come from explicit
+//! configuration, falling back to the conventional environment variables
+//! (the only source Azure and GCS read, so the factory refuses explicit
+//! settings for them):
+//!
+//! - `HTTPS_PROXY` / `https_proxy`, then `ALL_PROXY` / `all_proxy`, then
+//!   `HTTP_PROXY` / `http_proxy` for the proxy URL
+//! - `NO_PROXY` / `no_proxy` for hosts that connect directly
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::proxy::ProxySettings;
+//!
+//! let proxy = ProxySettings::new("http://proxy.corp.example:3128")
+//!     .with_auth("build-bot", "s3cret")
+//!     .with_no_proxy(["minio.internal", ".corp.example"]);
+//!
+//! assert!(proxy.is_enabled());
+//! assert_eq!(proxy.no_proxy_rules(), "minio.internal,.corp.example");
+//! ```
+
+use crate::tls::TlsSettings;
+use anyhow::Result;
+use aws_sdk_s3::config::SharedHttpClient;
+use aws_smithy_http_client::proxy::ProxyConfig;
+use aws_smithy_http_client::tls::{self, rustls_provider::CryptoMode};
+use aws_smithy_http_client::{Builder, Connector};
+
+/// Environment variables consulted for the proxy URL, in order of preference
+const PROXY_ENV_VARS: &[&str] = &[
+    "HTTPS_PROXY",
+    "https_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+];
+
+/// Environment variables consulted for the bypass list
+const NO_PROXY_ENV_VARS: &[&str] = &["NO_PROXY", "no_proxy"];
+
+/// Proxy configuration for outbound HTTP(S) connections
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxySettings {
+    /// Proxy URL (e.g. `http://proxy.corp.example:3128`); None connects directly
+    pub url: Option<String>,
+
+    /// Username for proxy basic authentication
+    pub username: Option<String>,
+
+    /// Password for proxy basic authentication
+    pub password: Option<String>,
+
+    /// Hosts that bypass the proxy, in `NO_PROXY` syntax
+    /// (`host`, `.domain`, `*.domain`, `10.0.0.0/8`, or `*` for everything)
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxySettings {
+    /// Route requests through the proxy at `url`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: Some(url.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Read proxy settings from the environment only
+    pub fn from_env() -> Self {
+        Self::default().or_env()
+    }
+
+    /// Authenticate to the proxy with basic auth
+    pub fn with_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Add hosts that connect directly instead of through the proxy
+    pub fn with_no_proxy<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for host in hosts {
+            let host = host.into();
+            if !host.is_empty() && !self.no_proxy.contains(&host) {
+                self.no_proxy.push(host);
+            }
+        }
+        self
+    }
+
+    /// Fill in anything not set explicitly from the environment
+    ///
+    /// An explicit URL wins over the environment; `NO_PROXY` entries are
+    /// always added to the bypass list.
+    pub fn or_env(mut self) -> Self {
+        if self.url.is_none() {
+            self.url = first_env(PROXY_ENV_VARS);
+        }
+        if let Some(hosts) = first_env(NO_PROXY_ENV_VARS) {
+            self = self.with_no_proxy(hosts.split(',').map(str::trim));
+        }
+        self
+    }
+
+    /// Whether requests should go through a proxy at all
+    pub fn is_enabled(&self) -> bool {
+        self.url.is_some()
+    }
+
+    /// The bypass list joined in `NO_PROXY` format
+    pub fn no_proxy_rules(&self) -> String {
+        self.no_proxy.join(",")
+    }
+
+    /// Build an AWS SDK HTTP client that routes through this proxy and
+    /// connects with the given TLS settings
+    ///
+    /// Returns None when neither a proxy nor custom TLS is configured,
+    /// leaving the SDK's default client in place.
+    pub(crate) fn s3_http_client(&self, tls: &TlsSettings) -> Result<Option<SharedHttpClient>> {
+        if tls.insecure_skip_verify {
+            return crate::tls::insecure_http_client(self).map(Some);
+        }
+        let tls_context = tls.tls_context()?;
+        if self.url.is_none() && tls_context.is_none() {
+            return Ok(None);
+        }
+
+        let proxy = match &self.url {
+            Some(url) => {
+                let mut proxy = ProxyConfig::all(url.as_str())
+                    .map_err(|e| anyhow::anyhow!("Invalid proxy URL '{}': {}", url, e))?;
+                if let Some(username) = &self.username {
+                    proxy = proxy.with_basic_auth(username, self.password.as_deref().unwrap_or(""));
+                }
+                if !self.no_proxy.is_empty() {
+                    proxy = proxy.no_proxy(self.no_proxy_rules());
+                }
+                Some(proxy)
+            }
+            None => None,
+        };
+
+        let client = Builder::new().build_with_connector_fn(move |settings, components| {
+            let mut builder = Connector::builder();
+            builder.set_proxy_config(proxy.clone());
+            if let Some(settings) = settings {
+                builder = builder.connector_settings(settings.clone());
+            }
+            if let Some(sleep) = components.and_then(|c| c.sleep_impl()) {
+                builder = builder.sleep_impl(sleep);
+            }
+            let mut builder = builder.tls_provider(tls::Provider::Rustls(CryptoMode::Ring));
+            if let Some(context) = &tls_context {
+                builder = builder.tls_context(context.clone());
+            }
+            builder.build()
+        });
+
+        Ok(Some(client))
+    }
+}
+
+fn first_env(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .map(|value| value.trim().to_string())
+        .find(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_settings_builder() {
+        let proxy = ProxySettings::new("http://proxy:3128")
+            .with_auth("user", "pass")
+            .with_no_proxy(["minio.internal", "", "minio.internal", "10.0.0.0/8"]);
+
+        assert!(proxy.is_enabled());
+        assert_eq!(proxy.username.as_deref(), Some("user"));
+        assert_eq!(proxy.no_proxy_rules(), "minio.internal,10.0.0.0/8");
+        assert!(!ProxySettings::default().is_enabled());
+    }
+
+    #[test]
+    fn test_invalid_proxy_url_is_rejected() {
+        let tls = TlsSettings::default();
+        assert!(ProxySettings::new("not a url")
+            .s3_http_client(&tls)
+            .is_err());
+        assert!(ProxySettings::default()
+            .s3_http_client(&tls)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,336 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Per-repository storage quota
+//!
+//! [`QuotaBackend`] refuses writes that would take the wrapped backend past a
+//! byte limit, failing them with [`StorageError::QuotaExceeded`] before any
+//! data is sent. Usage is counted with
+//! [`usage`](crate::StorageBackend::usage) before the first write and kept
+//! up to date as objects are written. Deletes make it count again before the
+//! next write, since the size of a deleted object isn't known without
+//! asking.
+//!
+//! An object that is overwritten counts in full until usage is counted again,
+//! so the quota errs on the side of refusing writes.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::{mock::MockBackend, QuotaBackend, StorageBackend, StorageError};
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let storage = QuotaBackend::new(Arc::new(MockBackend::new()), 8);
+//! storage.put("a", b"12345").await?;
+//!
+//! let err = storage.put("b", b"6789").await.unwrap_err();
+//! assert!(err.downcast_ref::<StorageError>().unwrap().is_quota_exceeded());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    BackendCapabilities, DeleteFailure, ExpiryPolicy, KeyStream, MmapOrVec, ObjectMeta,
+    ObjectStream, StorageBackend, StorageError, StorageUsage, SweepReport,
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Bytes counted against a quota
+#[derive(Debug)]
+struct Ledger {
+    limit: u64,
+    /// Stored bytes plus writes in flight; `None` until counted
+    used: Mutex<Option<u64>>,
+}
+
+impl Ledger {
+    /// Reserve `bytes`, or return `false` if usage has to be counted first
+    fn try_reserve(&self, bytes: u64) -> anyhow::Result<bool> {
+        let mut used = self.used.lock().unwrap();
+        let Some(current) = *used else {
+            return Ok(false);
+        };
+        if current.saturating_add(bytes) > self.limit {
+            return Err(StorageError::quota_exceeded(self.limit, current, bytes).into());
+        }
+        *used = Some(current + bytes);
+        Ok(true)
+    }
+
+    /// Give back a reservation for a write that didn't happen
+    fn release(&self, bytes: u64) {
+        if let Some(used) = self.used.lock().unwrap().as_mut() {
+            *used = used.saturating_sub(bytes);
+        }
+    }
+
+    /// Record counted usage, unless another write already did
+    fn counted(&self, bytes: u64) {
+        self.used.lock().unwrap().get_or_insert(bytes);
+    }
+
+    /// Count usage again before the next write
+    fn forget(&self) {
+        *self.used.lock().unwrap() = None;
+    }
+}
+
+/// Storage backend wrapper that caps the bytes stored in the wrapped backend
+#[derive(Debug, Clone)]
+pub struct QuotaBackend {
+    inner: Arc<dyn StorageBackend>,
+    ledger: Arc<Ledger>,
+}
+
+impl QuotaBackend {
+    /// Wrap `inner` so writes fail once it would hold more than `limit` bytes
+    pub fn new(inner: Arc<dyn StorageBackend>, limit: u64) -> Self {
+        Self {
+            inner,
+            ledger: Arc::new(Ledger {
+                limit,
+                used: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Arc<dyn StorageBackend> {
+        &self.inner
+    }
+
+    /// The quota in bytes
+    pub fn limit(&self) -> u64 {
+        self.ledger.limit
+    }
+
+    /// Reserve room for a write of `bytes`, counting usage first if needed
+    async fn reserve(&self, bytes: u64) -> anyhow::Result<()> {
+        while !self.ledger.try_reserve(bytes)? {
+            let StorageUsage { total_bytes, .. } = self.inner.usage("").await?;
+            self.ledger.counted(total_bytes);
+        }
+        Ok(())
+    }
+
+    /// Run a write that was reserved `bytes`, giving them back unless it
+    /// stored something
+    async fn reserved<F>(&self, bytes: u64, write: F) -> anyhow::Result<bool>
+    where
+        F: std::future::Future<Output = anyhow::Result<bool>>,
+    {
+        self.reserve(bytes).await?;
+        let result = write.await;
+        if !matches!(result, Ok(true)) {
+            self.ledger.release(bytes);
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl StorageBackend for QuotaBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.inner.get(key).await
+    }
+
+    async fn get_mapped(&self, key: &str) -> anyhow::Result<MmapOrVec> {
+        self.inner.get_mapped(key).await
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<std::time::SystemTime>> {
+        self.inner.modified(key).await
+    }
+
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        self.inner.head(key).await
+    }
+
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        self.inner.get_range(key, offset, len).await
+    }
+
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        self.inner.get_stream(key).await
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        self.reserved(data.len() as u64, async {
+            self.inner.put(key, data).await.map(|()| true)
+        })
+        .await
+        .map(drop)
+    }
+
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        self.reserved(data.len() as u64, self.inner.put_if_absent(key, data))
+            .await
+    }
+
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        self.reserved(data.len() as u64, self.inner.put_if_match(key, data, etag))
+            .await
+    }
+
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        let size = self.inner.head(src_key).await?.size;
+        self.reserved(size, async {
+            self.inner.copy(src_key, dst_key).await.map(|()| true)
+        })
+        .await
+        .map(drop)
+    }
+
+    /// Reserves each chunk as it passes, failing the upload at the first
+    /// chunk over the quota
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> anyhow::Result<()> {
+        // Count usage now, so chunks can be reserved without waiting
+        self.reserve(0).await?;
+
+        let streamed = Arc::new(AtomicU64::new(0));
+        let ledger = self.ledger.clone();
+        let counter = streamed.clone();
+        let data = data.map(move |chunk| {
+            let chunk = chunk?;
+            let len = chunk.len() as u64;
+            if ledger.try_reserve(len)? {
+                counter.fetch_add(len, Ordering::Relaxed);
+            }
+            Ok(chunk)
+        });
+
+        let result = self.inner.put_stream(key, Box::pin(data)).await;
+        if result.is_err() {
+            self.ledger.release(streamed.load(Ordering::Relaxed));
+        }
+        result
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        self.inner.exists(key).await
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        let result = self.inner.delete(key).await;
+        self.ledger.forget();
+        result
+    }
+
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        let result = self.inner.delete_many(keys).await;
+        self.ledger.forget();
+        result
+    }
+
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        self.inner.list_objects(prefix).await
+    }
+
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        self.inner.list_objects_stream(prefix)
+    }
+
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        self.inner.list_prefixes(prefix, delimiter).await
+    }
+
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        self.inner.usage(prefix).await
+    }
+
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> anyhow::Result<bool> {
+        self.inner.apply_expiry(policy).await
+    }
+
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport> {
+        let result = self.inner.sweep_expired(policy).await;
+        self.ledger.forget();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockBackend;
+    use bytes::Bytes;
+
+    fn is_quota_exceeded(err: &anyhow::Error) -> bool {
+        err.downcast_ref::<StorageError>()
+            .is_some_and(StorageError::is_quota_exceeded)
+    }
+
+    #[tokio::test]
+    async fn test_counts_existing_objects() {
+        let inner = Arc::new(MockBackend::new());
+        inner.put("old", b"123456").await.unwrap();
+        let storage = QuotaBackend::new(inner, 10);
+
+        storage.put("a", b"1234").await.unwrap();
+        let err = storage.put("b", b"1").await.unwrap_err();
+        assert!(is_quota_exceeded(&err));
+        assert_eq!(
+            err.to_string(),
+            "storage quota exceeded: writing 1 bytes with 10 of 10 bytes used"
+        );
+        assert!(!storage.exists("b").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_delete_frees_space() {
+        let storage = QuotaBackend::new(Arc::new(MockBackend::new()), 10);
+        storage.put("a", b"12345678").await.unwrap();
+        assert!(storage.put("b", b"123").await.is_err());
+
+        storage.delete("a").await.unwrap();
+        storage.put("b", b"123").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refused_writes_release_their_reservation() {
+        let storage = QuotaBackend::new(Arc::new(MockBackend::new()), 10);
+        storage.put("a", b"12345").await.unwrap();
+        assert!(!storage.put_if_absent("a", b"12345").await.unwrap());
+
+        // The skipped write didn't use up the remaining space
+        storage.put("b", b"12345").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_put_stream_stops_at_quota() {
+        let storage = QuotaBackend::new(Arc::new(MockBackend::new()), 10);
+        let chunks: Vec<anyhow::Result<Bytes>> = vec![
+            Ok(Bytes::from_static(b"123456")),
+            Ok(Bytes::from_static(b"789012")),
+        ];
+        let err = storage
+            .put_stream("video.mp4", Box::pin(futures::stream::iter(chunks)))
+            .await
+            .unwrap_err();
+        assert!(err.chain().any(|cause| cause
+            .downcast_ref::<StorageError>()
+            .is_some_and(StorageError::is_quota_exceeded)));
+
+        // The partial upload's reservation was given back
+        storage.put("a", b"1234567890").await.unwrap();
+    }
+}
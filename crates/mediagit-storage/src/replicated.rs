@@ -0,0 +1,543 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Writes fanned out to several backends
+//!
+//! [`ReplicatedBackend`] keeps the same objects in several backends, such as
+//! S3 and an on-premises MinIO. Every `put` and `delete` goes to all of them
+//! at once; the [`WritePolicy`] decides how many must succeed. Reads go to
+//! the first replica that answers, trying replicas whose last write or
+//! listing failed only after the others.
+//!
+//! A write that some replicas missed is logged as divergence, and
+//! [`ReplicatedBackend::divergence`] compares the replicas' listings to find
+//! every object a replica lacks.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::replicated::WritePolicy;
+//! use mediagit_storage::{mock::MockBackend, ReplicatedBackend, StorageBackend};
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let s3 = Arc::new(MockBackend::new());
+//! let nas = Arc::new(MockBackend::new());
+//! let storage = ReplicatedBackend::new(vec![s3.clone(), nas.clone()], WritePolicy::All)?;
+//!
+//! storage.put("abc123", b"data").await?;
+//! assert!(s3.exists("abc123").await? && nas.exists("abc123").await?);
+//! assert!(storage.divergence("").await?.is_consistent());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{BackendCapabilities, MmapOrVec, ObjectMeta, ObjectStream, StorageBackend};
+use async_trait::async_trait;
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// How many replicas must accept a write for it to succeed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WritePolicy {
+    /// Every replica
+    #[default]
+    All,
+    /// At least this many replicas
+    Quorum(usize),
+    /// Any one replica; failures on the rest are only logged
+    BestEffort,
+}
+
+impl WritePolicy {
+    /// A quorum of more than half of `replicas`
+    pub fn majority(replicas: usize) -> Self {
+        WritePolicy::Quorum(replicas / 2 + 1)
+    }
+
+    fn required(&self, replicas: usize) -> usize {
+        match self {
+            WritePolicy::All => replicas,
+            WritePolicy::Quorum(count) => *count,
+            WritePolicy::BestEffort => 1,
+        }
+    }
+}
+
+/// Objects missing from each replica, from [`ReplicatedBackend::divergence`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DivergenceReport {
+    /// For each replica, in configured order, the keys some other replica
+    /// holds but it doesn't
+    pub missing: Vec<Vec<String>>,
+    /// Replicas that couldn't be listed, and so weren't compared
+    pub unreachable: Vec<usize>,
+}
+
+impl DivergenceReport {
+    /// Every replica was listed and they all hold the same keys
+    pub fn is_consistent(&self) -> bool {
+        self.unreachable.is_empty() && self.missing.iter().all(Vec::is_empty)
+    }
+}
+
+/// Storage backend that writes to several replicas and reads from the first
+/// that answers
+#[derive(Debug, Clone)]
+pub struct ReplicatedBackend {
+    replicas: Vec<Arc<dyn StorageBackend>>,
+    policy: WritePolicy,
+    /// Cleared when an operation on the replica fails, set when one succeeds
+    healthy: Arc<Vec<AtomicBool>>,
+}
+
+impl ReplicatedBackend {
+    /// Replicate across `replicas`, reading from them in this order
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `replicas` is empty or the policy's quorum is zero
+    /// or larger than the number of replicas.
+    pub fn new(
+        replicas: Vec<Arc<dyn StorageBackend>>,
+        policy: WritePolicy,
+    ) -> anyhow::Result<Self> {
+        if replicas.is_empty() {
+            anyhow::bail!("replicated storage needs at least one backend");
+        }
+        if let WritePolicy::Quorum(count) = policy {
+            if count == 0 || count > replicas.len() {
+                anyhow::bail!(
+                    "write quorum of {} is impossible with {} replicas",
+                    count,
+                    replicas.len()
+                );
+            }
+        }
+
+        let healthy = replicas.iter().map(|_| AtomicBool::new(true)).collect();
+        Ok(Self {
+            replicas,
+            policy,
+            healthy: Arc::new(healthy),
+        })
+    }
+
+    /// The replicas, in read order
+    pub fn replicas(&self) -> &[Arc<dyn StorageBackend>] {
+        &self.replicas
+    }
+
+    /// The write policy
+    pub fn policy(&self) -> WritePolicy {
+        self.policy
+    }
+
+    /// Whether the last write or listing on replica `index` succeeded
+    pub fn is_healthy(&self, index: usize) -> bool {
+        self.healthy[index].load(Ordering::Relaxed)
+    }
+
+    /// Compare the replicas' listings under `prefix`
+    ///
+    /// Lists every replica, so it is as slow as the slowest one; run it from
+    /// maintenance commands rather than on every operation.
+    pub async fn divergence(&self, prefix: &str) -> anyhow::Result<DivergenceReport> {
+        let listings =
+            futures::future::join_all(self.replicas.iter().map(|r| r.list_objects(prefix))).await;
+
+        let mut report = DivergenceReport::default();
+        let mut sets = Vec::new();
+        for (index, listing) in listings.into_iter().enumerate() {
+            self.record(index, listing.is_ok());
+            match listing {
+                Ok(keys) => sets.push(Some(keys.into_iter().collect::<BTreeSet<_>>())),
+                Err(e) => {
+                    warn!("Replica {} could not be listed: {:#}", index, e);
+                    report.unreachable.push(index);
+                    sets.push(None);
+                }
+            }
+        }
+        if report.unreachable.len() == self.replicas.len() {
+            anyhow::bail!("no replica could be listed");
+        }
+
+        let all: BTreeSet<&String> = sets.iter().flatten().flatten().collect();
+        report.missing = sets
+            .iter()
+            .map(|keys| match keys {
+                Some(keys) => all
+                    .iter()
+                    .filter(|key| !keys.contains(**key))
+                    .map(|key| key.to_string())
+                    .collect(),
+                None => Vec::new(),
+            })
+            .collect();
+        Ok(report)
+    }
+
+    fn record(&self, index: usize, ok: bool) {
+        let was = self.healthy[index].swap(ok, Ordering::Relaxed);
+        if was && !ok {
+            warn!("Replica {} marked unhealthy", index);
+        } else if !was && ok {
+            debug!("Replica {} is healthy again", index);
+        }
+    }
+
+    /// Replica indices with healthy replicas first, each group in order
+    fn read_order(&self) -> Vec<usize> {
+        let (mut order, unhealthy): (Vec<usize>, Vec<usize>) =
+            (0..self.replicas.len()).partition(|&index| self.is_healthy(index));
+        order.extend(unhealthy);
+        order
+    }
+
+    /// Run a write on every replica at once and apply the write policy
+    async fn fan_out<F, Fut>(&self, op: &str, key: &str, write: F) -> anyhow::Result<()>
+    where
+        F: Fn(Arc<dyn StorageBackend>) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>>,
+    {
+        let results =
+            futures::future::join_all(self.replicas.iter().map(|r| write(r.clone()))).await;
+
+        let mut failures = Vec::new();
+        for (index, result) in results.into_iter().enumerate() {
+            self.record(index, result.is_ok());
+            if let Err(e) = result {
+                failures.push(format!("replica {}: {:#}", index, e));
+            }
+        }
+
+        let succeeded = self.replicas.len() - failures.len();
+        let required = self.policy.required(self.replicas.len());
+        if succeeded < required {
+            anyhow::bail!(
+                "{} {} succeeded on {} of {} replicas, {} required ({})",
+                op,
+                key,
+                succeeded,
+                self.replicas.len(),
+                required,
+                failures.join("; ")
+            );
+        }
+        for failure in failures {
+            warn!("Replicas diverged: {} {} failed on {}", op, key, failure);
+        }
+        Ok(())
+    }
+
+    /// Run a read on each replica in read order until one succeeds
+    async fn read_first<T, F, Fut>(&self, read: F) -> anyhow::Result<T>
+    where
+        F: Fn(Arc<dyn StorageBackend>) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let mut last_error = None;
+        for index in self.read_order() {
+            match read(self.replicas[index].clone()).await {
+                Ok(value) => return Ok(value),
+                Err(e) => {
+                    debug!("Read from replica {} failed: {:#}", index, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.expect("replicated storage has at least one backend"))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for ReplicatedBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.read_first(|r| async move { r.get(key).await }).await
+    }
+
+    async fn get_mapped(&self, key: &str) -> anyhow::Result<MmapOrVec> {
+        self.read_first(|r| async move { r.get_mapped(key).await })
+            .await
+    }
+
+    /// Only what every replica supports, since a read may land on any of them
+    fn capabilities(&self) -> BackendCapabilities {
+        let all = |flag: fn(&BackendCapabilities) -> bool| {
+            self.replicas.iter().all(|r| flag(&r.capabilities()))
+        };
+        BackendCapabilities {
+            mmap: all(|c| c.mmap),
+            range_reads: all(|c| c.range_reads),
+            streaming: all(|c| c.streaming),
+            ..BackendCapabilities::default()
+        }
+    }
+
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<std::time::SystemTime>> {
+        self.read_first(|r| async move { r.modified(key).await })
+            .await
+    }
+
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        self.read_first(|r| async move { r.head(key).await }).await
+    }
+
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        self.read_first(|r| async move { r.get_range(key, offset, len).await })
+            .await
+    }
+
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        self.read_first(|r| async move { r.get_stream(key).await })
+            .await
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        self.fan_out("put", key, |r| async move { r.put(key, data).await })
+            .await
+    }
+
+    /// True if any replica has the object, so a write one replica missed is
+    /// still found
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        let mut last_error = None;
+        let mut answered = false;
+        for index in self.read_order() {
+            let result = self.replicas[index].exists(key).await;
+            self.record(index, result.is_ok());
+            match result {
+                Ok(true) => return Ok(true),
+                Ok(false) => answered = true,
+                Err(e) => last_error = Some(e),
+            }
+        }
+        match last_error {
+            Some(e) if !answered => Err(e),
+            _ => Ok(false),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.fan_out("delete", key, |r| async move { r.delete(key).await })
+            .await
+    }
+
+    /// Keys on any replica that could be listed
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let listings =
+            futures::future::join_all(self.replicas.iter().map(|r| r.list_objects(prefix))).await;
+
+        let mut keys = BTreeSet::new();
+        let mut last_error = None;
+        let mut answered = false;
+        for (index, listing) in listings.into_iter().enumerate() {
+            self.record(index, listing.is_ok());
+            match listing {
+                Ok(listed) => {
+                    answered = true;
+                    keys.extend(listed);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        match last_error {
+            Some(e) if !answered => Err(e),
+            _ => Ok(keys.into_iter().collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockBackend;
+
+    /// Replica that can be taken offline
+    #[derive(Debug, Default)]
+    struct FlakyBackend {
+        inner: MockBackend,
+        offline: AtomicBool,
+    }
+
+    impl FlakyBackend {
+        fn check(&self) -> anyhow::Result<()> {
+            if self.offline.load(Ordering::SeqCst) {
+                anyhow::bail!("connection refused");
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl StorageBackend for FlakyBackend {
+        async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+            self.check()?;
+            self.inner.get(key).await
+        }
+
+        async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+            self.check()?;
+            self.inner.put(key, data).await
+        }
+
+        async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+            self.check()?;
+            self.inner.exists(key).await
+        }
+
+        async fn delete(&self, key: &str) -> anyhow::Result<()> {
+            self.check()?;
+            self.inner.delete(key).await
+        }
+
+        async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+            self.check()?;
+            self.inner.list_objects(prefix).await
+        }
+    }
+
+    fn replicas(count: usize) -> Vec<Arc<FlakyBackend>> {
+        (0..count)
+            .map(|_| Arc::new(FlakyBackend::default()))
+            .collect()
+    }
+
+    fn replicated(replicas: &[Arc<FlakyBackend>], policy: WritePolicy) -> ReplicatedBackend {
+        let backends = replicas
+            .iter()
+            .map(|r| r.clone() as Arc<dyn StorageBackend>)
+            .collect();
+        ReplicatedBackend::new(backends, policy).unwrap()
+    }
+
+    #[test]
+    fn test_invalid_quorum_is_rejected() {
+        let backends = || -> Vec<Arc<dyn StorageBackend>> {
+            vec![Arc::new(MockBackend::new()), Arc::new(MockBackend::new())]
+        };
+        assert!(ReplicatedBackend::new(Vec::new(), WritePolicy::All).is_err());
+        assert!(ReplicatedBackend::new(backends(), WritePolicy::Quorum(0)).is_err());
+        assert!(ReplicatedBackend::new(backends(), WritePolicy::Quorum(3)).is_err());
+        assert!(ReplicatedBackend::new(backends(), WritePolicy::majority(2)).is_ok());
+        assert_eq!(WritePolicy::majority(3), WritePolicy::Quorum(2));
+    }
+
+    #[tokio::test]
+    async fn test_writes_reach_every_replica() {
+        let nodes = replicas(3);
+        let storage = replicated(&nodes, WritePolicy::All);
+
+        storage.put("objects/a", b"alpha").await.unwrap();
+        for node in &nodes {
+            assert_eq!(node.inner.get("objects/a").await.unwrap(), b"alpha");
+        }
+
+        storage.delete("objects/a").await.unwrap();
+        for node in &nodes {
+            assert!(!node.inner.exists("objects/a").await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_policies() {
+        let nodes = replicas(3);
+        nodes[2].offline.store(true, Ordering::SeqCst);
+
+        let err = replicated(&nodes, WritePolicy::All)
+            .put("objects/a", b"alpha")
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("2 of 3 replicas, 3 required"),
+            "{}",
+            err
+        );
+
+        let storage = replicated(&nodes, WritePolicy::majority(3));
+        storage.put("objects/b", b"bravo").await.unwrap();
+        assert!(!storage.is_healthy(2));
+
+        nodes[1].offline.store(true, Ordering::SeqCst);
+        assert!(storage.put("objects/c", b"charlie").await.is_err());
+        replicated(&nodes, WritePolicy::BestEffort)
+            .put("objects/c", b"charlie")
+            .await
+            .unwrap();
+        assert!(nodes[0].inner.exists("objects/c").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_reads_fall_back_to_other_replicas() {
+        let nodes = replicas(2);
+        nodes[1].inner.put("objects/a", b"alpha").await.unwrap();
+        let storage = replicated(&nodes, WritePolicy::All);
+
+        // Missing from the first replica, found on the second
+        assert_eq!(storage.get("objects/a").await.unwrap(), b"alpha");
+        assert!(storage.exists("objects/a").await.unwrap());
+
+        nodes[1].offline.store(true, Ordering::SeqCst);
+        assert!(storage.get("objects/a").await.is_err());
+        assert!(!storage.exists("objects/a").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_replicas_are_read_last() {
+        let nodes = replicas(2);
+        let storage = replicated(&nodes, WritePolicy::BestEffort);
+        nodes[0].offline.store(true, Ordering::SeqCst);
+        storage.put("objects/a", b"alpha").await.unwrap();
+        assert_eq!(storage.read_order(), vec![1, 0]);
+
+        nodes[0].offline.store(false, Ordering::SeqCst);
+        storage.put("objects/b", b"bravo").await.unwrap();
+        assert_eq!(storage.read_order(), vec![0, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_divergence_report() {
+        let nodes = replicas(3);
+        let storage = replicated(&nodes, WritePolicy::BestEffort);
+        storage.put("objects/a", b"alpha").await.unwrap();
+
+        nodes[1].offline.store(true, Ordering::SeqCst);
+        storage.put("objects/b", b"bravo").await.unwrap();
+        nodes[1].offline.store(false, Ordering::SeqCst);
+        nodes[2].inner.delete("objects/a").await.unwrap();
+
+        let report = storage.divergence("objects/").await.unwrap();
+        assert!(!report.is_consistent());
+        assert_eq!(
+            report.missing,
+            vec![
+                Vec::<String>::new(),
+                vec!["objects/b".to_string()],
+                vec!["objects/a".to_string()],
+            ]
+        );
+        assert_eq!(
+            storage.list_objects("").await.unwrap(),
+            vec!["objects/a", "objects/b"]
+        );
+
+        nodes[0].offline.store(true, Ordering::SeqCst);
+        let report = storage.divergence("").await.unwrap();
+        assert_eq!(report.unreachable, vec![0]);
+    }
+}
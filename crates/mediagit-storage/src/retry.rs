@@ -0,0 +1,349 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Retries with exponential backoff for transient storage failures
+//!
+//! The cloud backends run each request through a [`RetryPolicy`]. Only
+//! failures [`is_transient`] recognizes are retried: throttling, 5xx
+//! responses, timeouts and dropped connections. A missing object, a refused
+//! credential or a malformed request fails at once, since trying again
+//! can't help.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::retry::RetryPolicy;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let policy = RetryPolicy::new(5, Duration::from_millis(1));
+//!
+//! let mut attempts = 0;
+//! let value = policy
+//!     .run("fetching manifest", || {
+//!         attempts += 1;
+//!         let attempt = attempts;
+//!         async move {
+//!             if attempt < 3 {
+//!                 anyhow::bail!("HTTP 503: service unavailable");
+//!             }
+//!             Ok(attempt)
+//!         }
+//!     })
+//!     .await?;
+//! assert_eq!(value, 3);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::StorageError;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tracing::warn;
+
+/// How often and how patiently to retry a failed request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first (default: 3)
+    pub max_attempts: u32,
+
+    /// Delay before the first retry, doubled for each one after (default: 100ms)
+    pub initial_delay: Duration,
+
+    /// Longest delay between attempts (default: 10s)
+    pub max_delay: Duration,
+
+    /// Randomize each delay between half and all of its length, so clients
+    /// throttled together don't retry together (default: on)
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Make up to `max_attempts` attempts, waiting `initial_delay` before
+    /// the first retry
+    pub fn new(max_attempts: u32, initial_delay: Duration) -> Self {
+        RetryPolicy {
+            max_attempts,
+            initial_delay,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Make a single attempt
+    pub fn never() -> Self {
+        RetryPolicy::new(1, Duration::ZERO)
+    }
+
+    /// Cap the delay between attempts at `max_delay`
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Turn delay randomization on or off
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// How long to wait before retry number `retry`, counting from 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        let delay = self
+            .initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay);
+        if self.jitter {
+            delay / 2 + delay.mul_f64(random_fraction() / 2.0)
+        } else {
+            delay
+        }
+    }
+
+    /// Run `operation` until it succeeds, fails with an error that isn't
+    /// transient, or has been attempted `max_attempts` times
+    ///
+    /// `what` names the operation in logs and in the error returned once
+    /// the attempts run out.
+    pub async fn run<T, F, Fut>(&self, what: &str, mut operation: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let max_attempts = self.max_attempts.max(1);
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            match operation().await {
+                Ok(result) => return Ok(result),
+                Err(e) if !is_transient(&e) => return Err(e),
+                Err(e) if attempt >= max_attempts => {
+                    let attempts = if attempt == 1 { "attempt" } else { "attempts" };
+                    return Err(
+                        e.context(format!("{} failed after {} {}", what, attempt, attempts))
+                    );
+                }
+                Err(e) => {
+                    let delay = self.backoff(attempt);
+                    warn!(
+                        "{} failed (attempt {}/{}), retrying in {}ms: {:#}",
+                        what,
+                        attempt,
+                        max_attempts,
+                        delay.as_millis(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+}
+
+/// Fragments of error messages that mark a failure as worth retrying
+///
+/// Services report throttling and outages in their own words, and SDKs
+/// often only keep the message, so these are matched against it in lower
+/// case.
+const TRANSIENT_MESSAGES: &[&str] = &[
+    // Throttling
+    "too many requests",
+    "slowdown",
+    "slow down",
+    "throttl",
+    "requestlimitexceeded",
+    "serverbusy",
+    "rate limit",
+    // Server errors
+    "internal server error",
+    "internalerror",
+    "bad gateway",
+    "service unavailable",
+    "serviceunavailable",
+    "gateway timeout",
+    // Timeouts and dropped connections
+    "timed out",
+    "timeout",
+    "connection reset",
+    "connection closed",
+    "connection refused",
+    "broken pipe",
+    "dispatch failure",
+    "error sending request",
+];
+
+/// HTTP statuses worth retrying: throttling and server errors
+const TRANSIENT_STATUSES: &[u16] = &[408, 429, 500, 502, 503, 504];
+
+/// Whether `error` is a failure that may succeed if tried again
+///
+/// Typed errors are checked first: timeouts, truncated downloads and
+/// dropped connections are transient, while missing objects, refused
+/// permissions and invalid keys are not. Otherwise the message is searched
+/// for a retryable HTTP status or one of the ways services describe
+/// throttling and outages.
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<StorageError>() {
+            match e {
+                StorageError::Timeout(_) | StorageError::Truncated { .. } => return true,
+                StorageError::NotFound(_)
+                | StorageError::PermissionDenied(_)
+                | StorageError::InvalidKey(_)
+                | StorageError::ChecksumMismatch { .. } => return false,
+                _ => {}
+            }
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            use std::io::ErrorKind;
+            if matches!(
+                e.kind(),
+                ErrorKind::TimedOut
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::ConnectionRefused
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::UnexpectedEof
+                    | ErrorKind::Interrupted
+            ) {
+                return true;
+            }
+        }
+    }
+
+    let message = format!("{:#}", error).to_lowercase();
+    TRANSIENT_MESSAGES
+        .iter()
+        .any(|fragment| message.contains(fragment))
+        || TRANSIENT_STATUSES.iter().any(|status| {
+            ["http ", "status: ", "status "]
+                .iter()
+                .any(|prefix| message.contains(&format!("{}{}", prefix, status)))
+        })
+}
+
+/// A number in `[0, 1)` that differs from call to call
+///
+/// Every `RandomState` is seeded differently, which is random enough to
+/// spread retries out without pulling in a random number generator.
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy::new(10, Duration::from_millis(100))
+            .with_max_delay(Duration::from_millis(500))
+            .with_jitter(false);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_jitter_stays_within_half_the_delay() {
+        let policy = RetryPolicy::new(3, Duration::from_millis(100));
+        for _ in 0..100 {
+            let delay = policy.backoff(2);
+            assert!(delay >= Duration::from_millis(100), "{:?}", delay);
+            assert!(delay <= Duration::from_millis(200), "{:?}", delay);
+        }
+    }
+
+    #[test]
+    fn test_transient_errors_are_recognized() {
+        for message in [
+            "Failed to get object (HTTP 503): service error",
+            "Failed to put object (HTTP 400): service error: unhandled error (SlowDown)",
+            "GCS error (HTTP 429): rate exceeded",
+            "HttpError\n\tStatus: 500\n\tError Code: InternalError",
+            "Failed to list objects: dispatch failure: io error: connection reset",
+        ] {
+            assert!(is_transient(&anyhow::anyhow!("{}", message)), "{}", message);
+        }
+        assert!(is_transient(&StorageError::timeout("get").into()));
+        assert!(is_transient(&anyhow::Error::from(std::io::Error::from(
+            std::io::ErrorKind::ConnectionReset
+        ))));
+    }
+
+    #[test]
+    fn test_permanent_errors_are_recognized() {
+        for message in [
+            "object not found: abc123",
+            "Failed to get object (HTTP 403): service error: AccessDenied",
+            "GCS error (HTTP 404): No such object",
+            "key cannot be empty",
+        ] {
+            assert!(
+                !is_transient(&anyhow::anyhow!("{}", message)),
+                "{}",
+                message
+            );
+        }
+        // A typed error wins over its message
+        let err = anyhow::Error::from(StorageError::not_found("timeout.mp4"));
+        assert!(!is_transient(&err));
+        let err = StorageError::checksum_mismatch("timeout.mp4", "sha256:00", "sha256:01");
+        assert!(!is_transient(&err.into()));
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_at_permanent_errors() {
+        let policy = RetryPolicy::new(5, Duration::from_millis(1));
+        let mut attempts = 0;
+        let result: anyhow::Result<()> = policy
+            .run("getting abc123", || {
+                attempts += 1;
+                async { Err(StorageError::not_found("abc123").into()) }
+            })
+            .await;
+        assert_eq!(attempts, 1);
+        assert_eq!(result.unwrap_err().to_string(), "object not found: abc123");
+    }
+
+    #[tokio::test]
+    async fn test_never_makes_one_attempt() {
+        let mut attempts = 0;
+        let result: anyhow::Result<()> = RetryPolicy::never()
+            .run("getting abc123", || {
+                attempts += 1;
+                async { anyhow::bail!("connection reset") }
+            })
+            .await;
+        assert_eq!(attempts, 1);
+        assert!(format!("{:#}", result.unwrap_err()).contains("failed after 1 attempt"));
+    }
+}
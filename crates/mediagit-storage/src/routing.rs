@@ -0,0 +1,342 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Keys split across backends by prefix
+//!
+//! [`RoutingBackend`] sends each key to the backend of the longest route
+//! prefix it starts with, and every other key to a default backend. A
+//! repository can keep its pack files in S3, its loose objects on local
+//! disk and its previews in a CDN bucket, while callers still see a single
+//! store. Keys are passed through unchanged, so a routed backend holds
+//! `packs/pack-1.pack` under that same key.
+//!
+//! Listings merge the backends that can hold keys under the prefix, and
+//! only include keys each backend is actually routed, so objects left behind
+//! in the wrong backend stay invisible.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::{mock::MockBackend, RoutingBackend, StorageBackend};
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let local = Arc::new(MockBackend::new());
+//! let s3 = Arc::new(MockBackend::new());
+//! let storage = RoutingBackend::new(local.clone()).with_route("packs/", s3.clone());
+//!
+//! storage.put("packs/pack-1.pack", b"pack").await?;
+//! storage.put("abc123", b"loose").await?;
+//! assert!(s3.exists("packs/pack-1.pack").await?);
+//! assert!(local.exists("abc123").await?);
+//! assert_eq!(storage.list_objects("").await?, vec!["abc123", "packs/pack-1.pack"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    BackendCapabilities, DeleteFailure, MmapOrVec, ObjectMeta, ObjectStream, StorageBackend,
+};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Storage backend that sends each key to a backend chosen by its prefix
+#[derive(Debug, Clone)]
+pub struct RoutingBackend {
+    default: Arc<dyn StorageBackend>,
+    routes: Vec<(String, Arc<dyn StorageBackend>)>,
+}
+
+impl RoutingBackend {
+    /// Send every key to `default` until routes are added
+    pub fn new(default: Arc<dyn StorageBackend>) -> Self {
+        Self {
+            default,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Send keys starting with `prefix` to `backend`
+    ///
+    /// The prefix is matched literally, so include the trailing `/` for a
+    /// directory. Adding a prefix again replaces its backend.
+    pub fn with_route(
+        mut self,
+        prefix: impl Into<String>,
+        backend: Arc<dyn StorageBackend>,
+    ) -> Self {
+        let prefix = prefix.into();
+        self.routes.retain(|(existing, _)| *existing != prefix);
+        self.routes.push((prefix, backend));
+        self
+    }
+
+    /// The backend for keys no route matches
+    pub fn default_backend(&self) -> &Arc<dyn StorageBackend> {
+        &self.default
+    }
+
+    /// The route prefixes and their backends, in the order they were added
+    pub fn routes(&self) -> impl Iterator<Item = (&str, &Arc<dyn StorageBackend>)> {
+        self.routes
+            .iter()
+            .map(|(prefix, backend)| (prefix.as_str(), backend))
+    }
+
+    /// The backend `key` is routed to: the longest matching route's, else
+    /// the default
+    pub fn backend_for(&self, key: &str) -> &Arc<dyn StorageBackend> {
+        self.routes
+            .iter()
+            .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(&self.default, |(_, backend)| backend)
+    }
+
+    /// Every distinct backend some key under `prefix` is routed to
+    ///
+    /// That is the backend `prefix` itself is routed to, plus those of
+    /// routes nested below it.
+    fn backends_under(&self, prefix: &str) -> Vec<&Arc<dyn StorageBackend>> {
+        let mut backends = vec![self.backend_for(prefix)];
+        for (route, backend) in &self.routes {
+            if route.starts_with(prefix) && !backends.iter().any(|b| Arc::ptr_eq(b, backend)) {
+                backends.push(backend);
+            }
+        }
+        backends
+    }
+
+    fn routes_to(&self, key: &str, backend: &Arc<dyn StorageBackend>) -> bool {
+        Arc::ptr_eq(self.backend_for(key), backend)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for RoutingBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.backend_for(key).get(key).await
+    }
+
+    async fn get_mapped(&self, key: &str) -> anyhow::Result<MmapOrVec> {
+        self.backend_for(key).get_mapped(key).await
+    }
+
+    /// Features every backend supports, since any key may land on any of
+    /// them; copies between backends always download
+    fn capabilities(&self) -> BackendCapabilities {
+        let all = |flag: fn(&BackendCapabilities) -> bool| {
+            std::iter::once(&self.default)
+                .chain(self.routes.iter().map(|(_, backend)| backend))
+                .all(|backend| flag(&backend.capabilities()))
+        };
+        BackendCapabilities {
+            mmap: all(|c| c.mmap),
+            compare_and_swap: all(|c| c.compare_and_swap),
+            range_reads: all(|c| c.range_reads),
+            streaming: all(|c| c.streaming),
+            ..BackendCapabilities::default()
+        }
+    }
+
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<std::time::SystemTime>> {
+        self.backend_for(key).modified(key).await
+    }
+
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        self.backend_for(key).head(key).await
+    }
+
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        self.backend_for(key).get_range(key, offset, len).await
+    }
+
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        self.backend_for(key).get_stream(key).await
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        self.backend_for(key).put(key, data).await
+    }
+
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        self.backend_for(key).put_if_absent(key, data).await
+    }
+
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        self.backend_for(key).put_if_match(key, data, etag).await
+    }
+
+    /// Copies within the backend when both keys route to it, and streams
+    /// the object across otherwise
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        let src = self.backend_for(src_key);
+        let dst = self.backend_for(dst_key);
+        if Arc::ptr_eq(src, dst) {
+            return src.copy(src_key, dst_key).await;
+        }
+        dst.put_stream(dst_key, src.get_stream(src_key).await?)
+            .await
+    }
+
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> anyhow::Result<()> {
+        self.backend_for(key).put_stream(key, data).await
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        self.backend_for(key).exists(key).await
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.backend_for(key).delete(key).await
+    }
+
+    /// Deletes each backend's share of `keys` in one batch
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        let mut batches: Vec<(&Arc<dyn StorageBackend>, Vec<String>)> = Vec::new();
+        for key in keys {
+            let backend = self.backend_for(key);
+            match batches.iter_mut().find(|(b, _)| Arc::ptr_eq(b, backend)) {
+                Some((_, batch)) => batch.push(key.clone()),
+                None => batches.push((backend, vec![key.clone()])),
+            }
+        }
+
+        let mut failures = Vec::new();
+        for (backend, batch) in batches {
+            failures.extend(backend.delete_many(&batch).await?);
+        }
+        Ok(failures)
+    }
+
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let mut keys = Vec::new();
+        for backend in self.backends_under(prefix) {
+            let listed = backend.list_objects(prefix).await?;
+            keys.extend(
+                listed
+                    .into_iter()
+                    .filter(|key| self.routes_to(key, backend)),
+            );
+        }
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockBackend;
+
+    fn routed() -> (
+        RoutingBackend,
+        Arc<MockBackend>,
+        Arc<MockBackend>,
+        Arc<MockBackend>,
+    ) {
+        let local = Arc::new(MockBackend::new());
+        let s3 = Arc::new(MockBackend::new());
+        let cdn = Arc::new(MockBackend::new());
+        let storage = RoutingBackend::new(local.clone())
+            .with_route("packs/", s3.clone())
+            .with_route("previews/", cdn.clone());
+        (storage, local, s3, cdn)
+    }
+
+    #[tokio::test]
+    async fn test_keys_go_to_their_route() {
+        let (storage, local, s3, cdn) = routed();
+
+        storage.put("abc123", b"loose").await.unwrap();
+        storage.put("packs/pack-1.pack", b"pack").await.unwrap();
+        storage.put("previews/abc123.jpg", b"jpeg").await.unwrap();
+
+        assert!(local.exists("abc123").await.unwrap());
+        assert!(s3.exists("packs/pack-1.pack").await.unwrap());
+        assert!(cdn.exists("previews/abc123.jpg").await.unwrap());
+        assert!(!local.exists("packs/pack-1.pack").await.unwrap());
+        assert_eq!(storage.get("packs/pack-1.pack").await.unwrap(), b"pack");
+
+        storage.delete("previews/abc123.jpg").await.unwrap();
+        assert!(!cdn.exists("previews/abc123.jpg").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_longest_route_wins() {
+        let local = Arc::new(MockBackend::new());
+        let warm = Arc::new(MockBackend::new());
+        let cold = Arc::new(MockBackend::new());
+        let storage = RoutingBackend::new(local.clone())
+            .with_route("packs/", warm.clone())
+            .with_route("packs/archive/", cold.clone());
+
+        storage.put("packs/pack-1.pack", b"warm").await.unwrap();
+        storage
+            .put("packs/archive/pack-0.pack", b"cold")
+            .await
+            .unwrap();
+        assert!(warm.exists("packs/pack-1.pack").await.unwrap());
+        assert!(cold.exists("packs/archive/pack-0.pack").await.unwrap());
+
+        assert_eq!(
+            storage.list_objects("packs/").await.unwrap(),
+            vec!["packs/archive/pack-0.pack", "packs/pack-1.pack"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_listing_merges_backends_and_hides_strays() {
+        let (storage, local, s3, _cdn) = routed();
+        storage.put("abc123", b"loose").await.unwrap();
+        storage.put("packs/pack-1.pack", b"pack").await.unwrap();
+        // Written before the route existed, so no longer reachable
+        local.put("packs/old.pack", b"stray").await.unwrap();
+
+        assert_eq!(
+            storage.list_objects("").await.unwrap(),
+            vec!["abc123", "packs/pack-1.pack"]
+        );
+        assert_eq!(
+            storage.list_objects("packs/").await.unwrap(),
+            vec!["packs/pack-1.pack"]
+        );
+        assert_eq!(
+            storage.list_prefixes("", "/").await.unwrap(),
+            vec!["packs/"]
+        );
+        assert!(!storage.exists("packs/old.pack").await.unwrap());
+        assert!(s3.exists("packs/pack-1.pack").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_copy_and_delete_many_across_backends() {
+        let (storage, local, s3, _cdn) = routed();
+        storage.put("abc123", b"loose").await.unwrap();
+
+        storage.copy("abc123", "packs/abc123").await.unwrap();
+        assert_eq!(s3.get("packs/abc123").await.unwrap(), b"loose");
+        storage.copy("abc123", "def456").await.unwrap();
+        assert_eq!(local.get("def456").await.unwrap(), b"loose");
+
+        let keys = vec![
+            "abc123".to_string(),
+            "def456".to_string(),
+            "packs/abc123".to_string(),
+        ];
+        assert!(storage.delete_many(&keys).await.unwrap().is_empty());
+        assert!(local.list_objects("").await.unwrap().is_empty());
+        assert!(s3.list_objects("").await.unwrap().is_empty());
+    }
+}
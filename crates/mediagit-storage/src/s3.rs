@@ -18,6 +18,7 @@
 //! - Automatic region detection
 //! - Multipart upload for large files (>100MB)
 //! - Concurrent part uploads for performance
+//! - Streaming reads and writes for objects larger than memory
 //! - Exponential backoff retry logic
 //! - Comprehensive error handling
 //!
@@ -27,6 +28,12 @@
 //! - **Region detection**: Uses environment variables or AWS metadata service
 //! - **Multipart uploads**: Automatically handles files >100MB with concurrent uploads
 //! - **Retry logic**: Exponential backoff with configurable max retries
+//! - **Checksums**: With [`S3Config::checksum`] set, uploads carry a SHA-256 or CRC32C
+//!   checksum that S3 verifies, and `get` checks the data it returns against it
+//! - **Storage classes**: [`S3Config::storage_class`] writes objects in a colder
+//!   class by key prefix, e.g. pack files in `GLACIER_IR`
+//! - **Server-side encryption**: [`S3Config::encryption`] has S3 encrypt objects
+//!   with SSE-S3, SSE-KMS or a customer-provided key (SSE-C)
 //! - **Performance**: Optimized for >100MB/s throughput on high-speed connections
 //! - **Thread-safe**: Full `Send + Sync` support for concurrent access
 //!
@@ -87,14 +94,30 @@
 //! All AWS errors are mapped to `anyhow::Error` with descriptive messages.
 //! Use [`StorageError`](crate::StorageError) for more structured error information.
 
-use crate::StorageBackend;
+use crate::checksum::{Checksum, ChecksumAlgorithm};
+use crate::expiry::{self, is_expired, ExpiryPolicy, SweepReport};
+use crate::proxy::ProxySettings;
+use crate::retry::RetryPolicy;
+use crate::sse::{ServerSideEncryption, SseHeaders};
+use crate::storage_class::{StorageClass, StorageClassPolicy};
+use crate::stream::{self, KeyStream, PartReader};
+use crate::timeouts::{self, TimeoutSettings};
+use crate::tls::TlsSettings;
+use crate::{
+    check_delimiter, BackendCapabilities, DeleteFailure, ObjectMeta, ObjectStream, StorageBackend,
+    StorageUsage,
+};
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
+use aws_sdk_s3::operation::get_object::GetObjectOutput;
+use aws_sdk_s3::types::{ChecksumMode, Delete, ObjectIdentifier};
 use aws_sdk_s3::Client;
 use bytes::Bytes;
+use futures::TryStreamExt;
 use std::fmt;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use tracing::{debug, warn};
 
 /// Configuration for the S3 backend
@@ -121,11 +144,39 @@ pub struct S3Config {
     /// Maximum number of concurrent parts to upload (default: 8)
     pub max_concurrent_parts: usize,
 
-    /// Maximum number of retries for failed operations (default: 3)
+    /// Maximum attempts for each request; only transient failures such as
+    /// throttling, 5xx responses and timeouts are retried (default: 3)
     pub max_retries: u32,
 
-    /// Initial retry delay in milliseconds (default: 100ms)
+    /// Delay before the first retry in milliseconds, doubled for each one
+    /// after (default: 100ms)
     pub initial_retry_delay_ms: u64,
+
+    /// Outbound proxy; unset fields fall back to `HTTPS_PROXY`/`NO_PROXY`
+    pub proxy: ProxySettings,
+
+    /// Connect and read timeouts for requests to the endpoint
+    pub timeouts: TimeoutSettings,
+
+    /// CA bundle and certificate verification for the endpoint; an unset
+    /// CA bundle falls back to `AWS_CA_BUNDLE`
+    pub tls: TlsSettings,
+
+    /// Region requests are signed for, when the service expects a different
+    /// one than `region`
+    pub signing_region: Option<String>,
+
+    /// Checksum sent with each upload for S3 to verify, and checked again on
+    /// `get` (default: none)
+    pub checksum: Option<ChecksumAlgorithm>,
+
+    /// Storage class each object is written in, by key prefix (default: the
+    /// bucket's default class)
+    pub storage_class: StorageClassPolicy,
+
+    /// Server-side encryption objects are written with, and for SSE-C read
+    /// with (default: the bucket's default encryption)
+    pub encryption: Option<ServerSideEncryption>,
 }
 
 impl Default for S3Config {
@@ -140,10 +191,27 @@ impl Default for S3Config {
             max_concurrent_parts: 8,
             max_retries: 3,
             initial_retry_delay_ms: 100,
+            proxy: ProxySettings::default(),
+            timeouts: TimeoutSettings::default(),
+            tls: TlsSettings::default(),
+            signing_region: None,
+            checksum: None,
+            storage_class: StorageClassPolicy::default(),
+            encryption: None,
         }
     }
 }
 
+impl S3Config {
+    /// How requests are retried, from `max_retries` and `initial_retry_delay_ms`
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::new(
+            self.max_retries,
+            Duration::from_millis(self.initial_retry_delay_ms),
+        )
+    }
+}
+
 /// AWS S3 storage backend
 ///
 /// Implements the `StorageBackend` trait using AWS S3.
@@ -241,32 +309,28 @@ impl S3Backend {
     /// # }
     /// ```
     pub async fn with_config(config: S3Config) -> Result<Self> {
+        let http_client = config
+            .proxy
+            .clone()
+            .or_env()
+            .s3_http_client(&config.tls.clone().or_env())?;
+
         // Override endpoint if provided (for S3-compatible services like MinIO)
         // Skip aws_config::defaults().load() for custom endpoints to avoid IMDS timeouts
         let client = if let Some(endpoint) = &config.endpoint {
             debug!("Using custom S3 endpoint: {}", endpoint);
-            let mut builder = aws_sdk_s3::config::Builder::new()
-                .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
-                .endpoint_url(endpoint.clone())
-                .force_path_style(true);
-            if let Some(region) = &config.region {
-                builder = builder.region(aws_sdk_s3::config::Region::new(region.clone()));
-            } else {
-                builder = builder.region(aws_sdk_s3::config::Region::new("us-east-1"));
-            }
-            if let (Some(key_id), Some(secret)) = (&config.access_key_id, &config.secret_access_key)
-            {
-                let credentials =
-                    aws_sdk_s3::config::Credentials::new(key_id, secret, None, None, "S3Backend");
-                builder = builder.credentials_provider(credentials);
-            }
-            Client::from_conf(builder.build())
+            Client::from_conf(Self::endpoint_sdk_config(&config, endpoint, http_client))
         } else {
             // Real AWS S3 - use standard config loading (IMDS is expected)
-            let sdk_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
-                .load()
-                .await;
-            Client::new(&sdk_config)
+            let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+                .timeout_config(config.timeouts.s3_timeout_config());
+            if let Some(region) = &config.signing_region {
+                loader = loader.region(aws_sdk_s3::config::Region::new(region.clone()));
+            }
+            if let Some(http_client) = http_client {
+                loader = loader.http_client(http_client);
+            }
+            Client::new(&loader.load().await)
         };
 
         // Ensure bucket exists; use create_bucket and treat "already exists" as success.
@@ -313,7 +377,8 @@ impl S3Backend {
     /// * `config` - Custom S3 configuration with endpoint
     /// * `access_key` - Access Key ID
     /// * `secret_key` - Secret Access Key
-    /// * `region` - Region string (e.g., "us-west-002" for B2)
+    /// * `region` - Region string (e.g., "us-west-002" for B2); `config.signing_region`
+    ///   takes precedence when set
     ///
     /// # Returns
     ///
@@ -360,16 +425,27 @@ impl S3Backend {
         );
 
         // Build S3 config with explicit credentials and endpoint
+        let region = config.signing_region.as_deref().unwrap_or(region);
         let mut s3_config_builder = aws_sdk_s3::config::Builder::new()
             .credentials_provider(credentials)
             .region(Region::new(region.to_string()))
-            .force_path_style(true); // Required for most S3-compatible services
+            .force_path_style(true) // Required for most S3-compatible services
+            .timeout_config(config.timeouts.s3_timeout_config());
 
         if let Some(endpoint) = &config.endpoint {
             debug!("Using custom S3 endpoint with credentials: {}", endpoint);
             s3_config_builder = s3_config_builder.endpoint_url(endpoint.clone());
         }
 
+        if let Some(http_client) = config
+            .proxy
+            .clone()
+            .or_env()
+            .s3_http_client(&config.tls.clone().or_env())?
+        {
+            s3_config_builder = s3_config_builder.http_client(http_client);
+        }
+
         let client = Client::from_conf(s3_config_builder.build());
 
         // Ensure bucket exists; use create_bucket and ignore "already exists" errors.
@@ -421,6 +497,36 @@ impl S3Backend {
         )
     }
 
+    /// SDK client configuration for an S3-compatible `endpoint`
+    ///
+    /// Requests are signed for `signing_region`, else `region`, else `us-east-1`.
+    fn endpoint_sdk_config(
+        config: &S3Config,
+        endpoint: &str,
+        http_client: Option<aws_sdk_s3::config::SharedHttpClient>,
+    ) -> aws_sdk_s3::Config {
+        let region = config
+            .signing_region
+            .as_deref()
+            .or(config.region.as_deref())
+            .unwrap_or("us-east-1");
+        let mut builder = aws_sdk_s3::config::Builder::new()
+            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
+            .endpoint_url(endpoint)
+            .force_path_style(true)
+            .region(aws_sdk_s3::config::Region::new(region.to_string()))
+            .timeout_config(config.timeouts.s3_timeout_config());
+        if let (Some(key_id), Some(secret)) = (&config.access_key_id, &config.secret_access_key) {
+            let credentials =
+                aws_sdk_s3::config::Credentials::new(key_id, secret, None, None, "S3Backend");
+            builder = builder.credentials_provider(credentials);
+        }
+        if let Some(http_client) = http_client {
+            builder = builder.http_client(http_client);
+        }
+        builder.build()
+    }
+
     /// Validate a key for correctness
     fn validate_key(key: &str) -> Result<()> {
         if key.is_empty() {
@@ -432,36 +538,16 @@ impl S3Backend {
         Ok(())
     }
 
-    /// Perform operation with exponential backoff retry logic
-    async fn with_retry<F, T>(&self, mut operation: F) -> Result<T>
+    /// Run `operation` under the configured [`RetryPolicy`], retrying
+    /// transient failures with exponential backoff
+    async fn with_retry<F, T>(&self, operation: F) -> Result<T>
     where
         F: FnMut() -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<T>> + Send>>,
     {
-        let mut retry_count = 0;
-        let mut delay_ms = self.config.initial_retry_delay_ms;
-
-        loop {
-            match operation().await {
-                Ok(result) => return Ok(result),
-                Err(e) => {
-                    retry_count += 1;
-                    if retry_count >= self.config.max_retries {
-                        return Err(e)
-                            .context(format!("Failed after {} retries", self.config.max_retries));
-                    }
-
-                    warn!(
-                        "Operation failed (attempt {}/{}), retrying in {}ms: {}",
-                        retry_count, self.config.max_retries, delay_ms, e
-                    );
-
-                    tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-
-                    // Exponential backoff with jitter
-                    delay_ms = (delay_ms * 2).min(10000); // Cap at 10 seconds
-                }
-            }
-        }
+        self.config
+            .retry_policy()
+            .run("S3 request", operation)
+            .await
     }
 }
 
@@ -495,31 +581,105 @@ impl StorageBackend for S3Backend {
         let bucket = self.config.bucket.clone();
         let key_clone = key.to_string();
         let stats = self.stats.clone();
+        let read_timeout = self.config.timeouts.read;
+        let checksum = self.config.checksum;
+        let sse = SseHeaders::read(self.config.encryption.as_ref());
 
         self.with_retry(|| {
             let client = client.clone();
             let bucket = bucket.clone();
             let key = key_clone.clone();
             let stats = stats.clone();
+            let sse = sse.clone();
 
             Box::pin(async move {
                 debug!("Getting object from S3: {}", key);
 
-                let response = client
-                    .get_object()
-                    .bucket(&bucket)
-                    .key(&key)
-                    .send()
-                    .await
-                    .map_err(|e| anyhow!("Failed to get object: {}", e))?;
+                let response = timeouts::within(
+                    read_timeout,
+                    client
+                        .get_object()
+                        .bucket(&bucket)
+                        .key(&key)
+                        .set_checksum_mode(checksum.map(|_| ChecksumMode::Enabled))
+                        .set_sse_customer_algorithm(sse.customer_algorithm)
+                        .set_sse_customer_key(sse.customer_key)
+                        .set_sse_customer_key_md5(sse.customer_key_md5)
+                        .send(),
+                )
+                .await?
+                .map_err(|e| request_error("Failed to get object", e))?;
+
+                let stored = checksum.and_then(|algorithm| stored_checksum(algorithm, &response));
+                let expected = response
+                    .content_length()
+                    .and_then(|len| u64::try_from(len).ok());
+                let data = timeouts::collect_body(response.body, read_timeout, expected).await?;
+                stats
+                    .total_bytes_downloaded
+                    .fetch_add(data.len() as u64, Ordering::Relaxed);
 
-                let body = response
-                    .body
-                    .collect()
-                    .await
-                    .map_err(|e| anyhow!("Failed to read object body: {}", e))?;
+                if let Some(stored) = stored {
+                    stored.verify(&key, &data)?;
+                }
+                Ok(data)
+            })
+        })
+        .await
+    }
 
-                let data = body.into_bytes().to_vec();
+    /// Retrieve part of an object from S3 with a `Range` request
+    ///
+    /// S3 rejects a range starting past the end of the object, which is
+    /// returned as empty rather than as an error.
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> Result<Vec<u8>> {
+        Self::validate_key(key)?;
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+
+        let client = self.client.clone();
+        let bucket = self.config.bucket.clone();
+        let key_clone = key.to_string();
+        let stats = self.stats.clone();
+        let read_timeout = self.config.timeouts.read;
+        let range = format!("bytes={}-{}", offset, offset.saturating_add(len - 1));
+        let sse = SseHeaders::read(self.config.encryption.as_ref());
+
+        self.with_retry(|| {
+            let client = client.clone();
+            let bucket = bucket.clone();
+            let key = key_clone.clone();
+            let stats = stats.clone();
+            let range = range.clone();
+            let sse = sse.clone();
+
+            Box::pin(async move {
+                debug!("Getting {} of object from S3: {}", range, key);
+
+                let response = match timeouts::within(
+                    read_timeout,
+                    client
+                        .get_object()
+                        .bucket(&bucket)
+                        .key(&key)
+                        .range(&range)
+                        .set_sse_customer_algorithm(sse.customer_algorithm)
+                        .set_sse_customer_key(sse.customer_key)
+                        .set_sse_customer_key_md5(sse.customer_key_md5)
+                        .send(),
+                )
+                .await?
+                {
+                    Ok(response) => response,
+                    Err(e) if is_invalid_range(&e) => return Ok(Vec::new()),
+                    Err(e) => return Err(request_error("Failed to get object", e)),
+                };
+
+                let expected = response
+                    .content_length()
+                    .and_then(|len| u64::try_from(len).ok());
+                let data = timeouts::collect_body(response.body, read_timeout, expected).await?;
                 stats
                     .total_bytes_downloaded
                     .fetch_add(data.len() as u64, Ordering::Relaxed);
@@ -530,6 +690,108 @@ impl StorageBackend for S3Backend {
         .await
     }
 
+    /// Stream an object from S3
+    ///
+    /// Opening the object is retried; once data is flowing, a stalled or
+    /// truncated body fails the stream.
+    async fn get_stream(&self, key: &str) -> Result<ObjectStream> {
+        Self::validate_key(key)?;
+
+        let client = self.client.clone();
+        let bucket = self.config.bucket.clone();
+        let key_clone = key.to_string();
+        let read_timeout = self.config.timeouts.read;
+        let sse = SseHeaders::read(self.config.encryption.as_ref());
+
+        let response = self
+            .with_retry(|| {
+                let client = client.clone();
+                let bucket = bucket.clone();
+                let key = key_clone.clone();
+                let sse = sse.clone();
+
+                Box::pin(async move {
+                    debug!("Streaming object from S3: {}", key);
+
+                    timeouts::within(
+                        read_timeout,
+                        client
+                            .get_object()
+                            .bucket(&bucket)
+                            .key(&key)
+                            .set_sse_customer_algorithm(sse.customer_algorithm)
+                            .set_sse_customer_key(sse.customer_key)
+                            .set_sse_customer_key_md5(sse.customer_key_md5)
+                            .send(),
+                    )
+                    .await?
+                    .map_err(|e| request_error("Failed to get object", e))
+                })
+            })
+            .await?;
+
+        let expected = response
+            .content_length()
+            .and_then(|len| u64::try_from(len).ok());
+        let stats = self.stats.clone();
+        Ok(Box::pin(
+            timeouts::body_stream(response.body, read_timeout, expected).inspect_ok(move |chunk| {
+                stats
+                    .total_bytes_downloaded
+                    .fetch_add(chunk.len() as u64, Ordering::Relaxed);
+            }),
+        ))
+    }
+
+    /// Store an object in S3 from a stream
+    ///
+    /// A stream that fits in one part is sent with put_object; anything
+    /// longer is uploaded part by part, holding at most
+    /// `max_concurrent_parts` parts in memory.
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> Result<()> {
+        Self::validate_key(key)?;
+
+        let mut parts = PartReader::new(data, self.config.part_size as usize);
+        let Some(first) = parts.next_part().await? else {
+            return self.put_simple(key, &[], None).await.map(|_| ());
+        };
+        let Some(second) = parts.next_part().await? else {
+            return self.put_simple(key, &first, None).await.map(|_| ());
+        };
+
+        let uploaded = put_multipart_stream(
+            &self.client,
+            &self.config.bucket,
+            key,
+            vec![first, second],
+            parts,
+            self.config.max_concurrent_parts,
+            UploadOptions {
+                checksum: self.config.checksum,
+                storage_class: self.config.storage_class.class_for(key),
+                encryption: SseHeaders::write(self.config.encryption.as_ref()),
+            },
+        )
+        .await?;
+        self.stats
+            .total_bytes_uploaded
+            .fetch_add(uploaded, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Streams in both directions, reads ranges, and copies and writes
+    /// conditionally within the service; objects can't be given an expiry
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            server_side_copy: true,
+            compare_and_swap: true,
+            ttl: true,
+            range_reads: true,
+            streaming: true,
+            ..BackendCapabilities::default()
+        }
+    }
+
     /// Store an object in S3
     ///
     /// For objects smaller than the configured part size, uses direct put_object.
@@ -547,13 +809,58 @@ impl StorageBackend for S3Backend {
     async fn put(&self, key: &str, data: &[u8]) -> Result<()> {
         Self::validate_key(key)?;
 
-        // For small objects, use simple put_object
-        if data.len() as u64 <= self.config.part_size {
-            return self.put_simple(key, data).await;
+        self.write(key, data, None).await.map(|_| ())
+    }
+
+    /// Store an object in S3 unless the key is taken
+    ///
+    /// A HEAD request first skips uploading objects that are already
+    /// there; the upload itself carries `If-None-Match: *` so a concurrent
+    /// writer can't be overwritten either.
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> Result<bool> {
+        if self.exists(key).await? {
+            return Ok(false);
         }
+        self.write(key, data, Some(WriteCondition::Absent)).await
+    }
 
-        // For large objects, use multipart upload
-        self.put_multipart(key, data).await
+    /// Replace an object in S3 with an `If-Match` upload
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> Result<bool> {
+        Self::validate_key(key)?;
+        self.write(key, data, Some(WriteCondition::Matches(etag.to_string())))
+            .await
+    }
+
+    /// Copy an object within the bucket with CopyObject
+    async fn copy(&self, src_key: &str, dst_key: &str) -> Result<()> {
+        Self::validate_key(src_key)?;
+        Self::validate_key(dst_key)?;
+
+        let client = self.client.clone();
+        let bucket = self.config.bucket.clone();
+        let src = src_key.to_string();
+        let dst = dst_key.to_string();
+        let storage_class = self.config.storage_class.class_for(dst_key);
+        let encryption = self.config.encryption.clone();
+
+        let copied = self
+            .with_retry(|| {
+                let client = client.clone();
+                let bucket = bucket.clone();
+                let src = src.clone();
+                let dst = dst.clone();
+                let encryption = encryption.clone();
+                Box::pin(async move {
+                    let encryption = encryption.as_ref();
+                    copy_object(&client, &bucket, &src, &dst, storage_class, encryption).await
+                })
+            })
+            .await?;
+
+        if !copied {
+            return Err(anyhow!("object not found: {}", src_key));
+        }
+        Ok(())
     }
 
     /// Check if an object exists in S3
@@ -573,16 +880,27 @@ impl StorageBackend for S3Backend {
         let client = self.client.clone();
         let bucket = self.config.bucket.clone();
         let key_clone = key.to_string();
+        let sse = SseHeaders::read(self.config.encryption.as_ref());
 
         self.with_retry(|| {
             let client = client.clone();
             let bucket = bucket.clone();
             let key = key_clone.clone();
+            let sse = sse.clone();
 
             Box::pin(async move {
                 debug!("Checking if object exists in S3: {}", key);
 
-                match client.head_object().bucket(&bucket).key(&key).send().await {
+                let result = client
+                    .head_object()
+                    .bucket(&bucket)
+                    .key(&key)
+                    .set_sse_customer_algorithm(sse.customer_algorithm)
+                    .set_sse_customer_key(sse.customer_key)
+                    .set_sse_customer_key_md5(sse.customer_key_md5)
+                    .send()
+                    .await;
+                match result {
                     Ok(_) => {
                         debug!("Object exists: {}", key);
                         Ok(true)
@@ -602,7 +920,7 @@ impl StorageBackend for S3Backend {
                             debug!("Object does not exist: {}", key);
                             Ok(false)
                         } else {
-                            Err(anyhow!("Failed to check object existence: {}", e))
+                            Err(request_error("Failed to check object existence", e))
                         }
                     }
                 }
@@ -611,6 +929,47 @@ impl StorageBackend for S3Backend {
         .await
     }
 
+    /// Fetch an object's metadata with a HEAD request
+    async fn head(&self, key: &str) -> Result<ObjectMeta> {
+        Self::validate_key(key)?;
+
+        let client = self.client.clone();
+        let bucket = self.config.bucket.clone();
+        let key_clone = key.to_string();
+        let sse = SseHeaders::read(self.config.encryption.as_ref());
+
+        let meta = self
+            .with_retry(|| {
+                let client = client.clone();
+                let bucket = bucket.clone();
+                let key = key_clone.clone();
+                let sse = sse.clone();
+
+                Box::pin(async move {
+                    debug!("Fetching object metadata from S3: {}", key);
+
+                    let result = client
+                        .head_object()
+                        .bucket(&bucket)
+                        .key(&key)
+                        .set_sse_customer_algorithm(sse.customer_algorithm)
+                        .set_sse_customer_key(sse.customer_key)
+                        .set_sse_customer_key_md5(sse.customer_key_md5)
+                        .send()
+                        .await;
+                    match result {
+                        Ok(output) => Ok(Some(head_object_meta(&output))),
+                        // Not worth retrying
+                        Err(e) if is_not_found(&e) => Ok(None),
+                        Err(e) => Err(request_error("Failed to fetch object metadata", e)),
+                    }
+                })
+            })
+            .await?;
+
+        meta.ok_or_else(|| anyhow!("object not found: {}", key))
+    }
+
     /// Delete an object from S3
     ///
     /// This operation is idempotent: deleting a non-existent object succeeds.
@@ -646,7 +1005,7 @@ impl StorageBackend for S3Backend {
                     .key(&key)
                     .send()
                     .await
-                    .map_err(|e| anyhow!("Failed to delete object: {}", e))?;
+                    .map_err(|e| request_error("Failed to delete object", e))?;
 
                 stats.total_objects_deleted.fetch_add(1, Ordering::Relaxed);
 
@@ -656,6 +1015,50 @@ impl StorageBackend for S3Backend {
         .await
     }
 
+    /// Delete objects from S3 with DeleteObjects requests of up to 1000 keys
+    ///
+    /// A request that fails outright, after retries, is reported as a
+    /// failure for each of its keys.
+    async fn delete_many(&self, keys: &[String]) -> Result<Vec<DeleteFailure>> {
+        for key in keys {
+            Self::validate_key(key)?;
+        }
+
+        let mut failures = Vec::new();
+        for batch in keys.chunks(DELETE_BATCH_SIZE) {
+            let client = self.client.clone();
+            let bucket = self.config.bucket.clone();
+            let batch_keys = batch.to_vec();
+
+            let result = self
+                .with_retry(|| {
+                    let client = client.clone();
+                    let bucket = bucket.clone();
+                    let keys = batch_keys.clone();
+                    Box::pin(async move { delete_objects(&client, &bucket, &keys).await })
+                })
+                .await;
+
+            match result {
+                Ok(batch_failures) => {
+                    self.stats.total_objects_deleted.fetch_add(
+                        (batch.len() - batch_failures.len()) as u64,
+                        Ordering::Relaxed,
+                    );
+                    failures.extend(batch_failures);
+                }
+                Err(e) => {
+                    warn!("Failed to delete {} objects: {}", batch.len(), e);
+                    failures.extend(batch.iter().map(|key| DeleteFailure {
+                        key: key.clone(),
+                        error: anyhow!("{:#}", e),
+                    }));
+                }
+            }
+        }
+        Ok(failures)
+    }
+
     /// List objects in S3 with a given prefix
     ///
     /// Returns a sorted list of all keys that start with the given prefix.
@@ -669,68 +1072,106 @@ impl StorageBackend for S3Backend {
     /// * `Ok(Vec<String>)` - Sorted list of matching keys
     /// * `Err` - If an error occurs
     async fn list_objects(&self, prefix: &str) -> Result<Vec<String>> {
-        let client = self.client.clone();
-        let bucket = self.config.bucket.clone();
-        let prefix_clone = prefix.to_string();
-
-        self.with_retry(|| {
-            let client = client.clone();
-            let bucket = bucket.clone();
-            let prefix = prefix_clone.clone();
+        debug!("Listing objects in S3 with prefix: '{}'", prefix);
 
-            Box::pin(async move {
-                debug!("Listing objects in S3 with prefix: '{}'", prefix);
-
-                let mut result = vec![];
-                let mut continuation_token: Option<String> = None;
-
-                loop {
-                    let mut request = client.list_objects_v2().bucket(&bucket);
-
-                    if !prefix.is_empty() {
-                        request = request.prefix(&prefix);
-                    }
+        let mut result: Vec<String> = self.list_objects_stream(prefix).try_collect().await?;
+        // Sort for consistency
+        result.sort();
 
-                    if let Some(token) = continuation_token {
-                        request = request.continuation_token(token);
-                    }
+        debug!("Found {} objects with prefix: '{}'", result.len(), prefix);
+        Ok(result)
+    }
 
-                    let response = request
-                        .send()
-                        .await
-                        .map_err(|e| anyhow!("Failed to list objects: {}", e))?;
+    /// Stream keys a ListObjectsV2 page at a time, each page retried on its own
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        list_pages(
+            &self.client,
+            &self.config.bucket,
+            prefix.to_string(),
+            self.config.retry_policy(),
+        )
+    }
 
-                    // Collect keys from this page
-                    for obj in response.contents() {
-                        if let Some(key) = obj.key() {
-                            result.push(key.to_string());
-                        }
-                    }
+    /// List CommonPrefixes with a delimited ListObjectsV2 request
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> Result<Vec<String>> {
+        list_common_prefixes(
+            &self.client,
+            &self.config.bucket,
+            prefix,
+            delimiter,
+            self.config.retry_policy(),
+        )
+        .await
+    }
 
-                    // Check if there are more results
-                    if response.is_truncated() == Some(true) {
-                        continuation_token =
-                            response.next_continuation_token().map(|t| t.to_string());
-                    } else {
-                        break;
-                    }
-                }
+    /// Sum the object sizes ListObjectsV2 reports, without a HEAD per object
+    async fn usage(&self, prefix: &str) -> Result<StorageUsage> {
+        measure_objects(
+            &self.client,
+            &self.config.bucket,
+            prefix,
+            self.config.retry_policy(),
+        )
+        .await
+    }
 
-                // Sort for consistency
-                result.sort();
+    /// Install the policy as bucket lifecycle rules, which S3 enforces itself
+    ///
+    /// Rules are given whole days, rounded up. Rules whose id starts with
+    /// `mediagit-` are replaced; the bucket's other rules are kept.
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> Result<bool> {
+        apply_lifecycle_rules(
+            &self.client,
+            &self.config.bucket,
+            policy,
+            self.config.retry_policy(),
+        )
+        .await?;
+        Ok(true)
+    }
 
-                debug!("Found {} objects with prefix: '{}'", result.len(), prefix);
-                Ok(result)
-            })
-        })
-        .await
+    /// Also aborts multipart uploads started longer ago than
+    /// `incomplete_uploads`
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> Result<SweepReport> {
+        let mut report = expiry::sweep_rules(self, policy).await?;
+        if let Some(ttl) = policy.incomplete_uploads {
+            report.incomplete_uploads = abort_stale_uploads(
+                &self.client,
+                &self.config.bucket,
+                &policy.scope,
+                ttl,
+                self.config.retry_policy(),
+            )
+            .await?;
+        }
+        Ok(report)
     }
 }
 
 // Helper methods for S3Backend (not part of StorageBackend trait)
 impl S3Backend {
+    /// Upload with `put_object` or as a multipart upload depending on size,
+    /// returning `false` if `condition` didn't hold
+    async fn write(
+        &self,
+        key: &str,
+        data: &[u8],
+        condition: Option<WriteCondition>,
+    ) -> Result<bool> {
+        if data.len() as u64 <= self.config.part_size {
+            self.put_simple(key, data, condition).await
+        } else {
+            self.put_multipart(key, data, condition).await
+        }
+    }
+
     /// Upload small objects using direct put_object
-    async fn put_simple(&self, key: &str, data: &[u8]) -> Result<()> {
+    async fn put_simple(
+        &self,
+        key: &str,
+        data: &[u8],
+        condition: Option<WriteCondition>,
+    ) -> Result<bool> {
         debug!("Putting small object to S3: {} ({} bytes)", key, data.len());
 
         let client = self.client.clone();
@@ -738,6 +1179,9 @@ impl S3Backend {
         let key_clone = key.to_string();
         let data_vec = data.to_vec();
         let stats = self.stats.clone();
+        let checksum = ChecksumHeaders::new(self.config.checksum, data);
+        let storage_class = self.config.storage_class.class_for(key);
+        let sse = SseHeaders::write(self.config.encryption.as_ref());
 
         self.with_retry(|| {
             let client = client.clone();
@@ -745,30 +1189,56 @@ impl S3Backend {
             let key = key_clone.clone();
             let data = data_vec.clone();
             let stats = stats.clone();
+            let condition = condition.clone();
+            let checksum = checksum.clone();
+            let sse = sse.clone();
 
             Box::pin(async move {
-                client
+                let result = client
                     .put_object()
                     .bucket(&bucket)
                     .key(&key)
+                    .set_if_none_match(condition.as_ref().and_then(WriteCondition::if_none_match))
+                    .set_if_match(condition.as_ref().and_then(WriteCondition::if_match))
+                    .set_checksum_sha256(checksum.sha256)
+                    .set_checksum_crc32_c(checksum.crc32c)
+                    .set_storage_class(storage_class.map(StorageClass::to_sdk))
+                    .set_server_side_encryption(sse.algorithm)
+                    .set_ssekms_key_id(sse.kms_key_id)
+                    .set_sse_customer_algorithm(sse.customer_algorithm)
+                    .set_sse_customer_key(sse.customer_key)
+                    .set_sse_customer_key_md5(sse.customer_key_md5)
                     .body(Bytes::from(data.clone()).into())
                     .send()
-                    .await
-                    .map_err(|e| anyhow!("Failed to put object: {}", e))?;
+                    .await;
+                match result {
+                    Ok(_) => {}
+                    // Not worth retrying
+                    Err(e) if is_precondition_failed(&e) => {
+                        debug!("Precondition failed putting {}: {:?}", key, condition);
+                        return Ok(false);
+                    }
+                    Err(e) => return Err(request_error("Failed to put object", e)),
+                }
 
                 stats
                     .total_bytes_uploaded
                     .fetch_add(data.len() as u64, Ordering::Relaxed);
 
                 debug!("Successfully put object to S3: {}", key);
-                Ok(())
+                Ok(true)
             })
         })
         .await
     }
 
     /// Upload large objects using multipart upload
-    async fn put_multipart(&self, key: &str, data: &[u8]) -> Result<()> {
+    async fn put_multipart(
+        &self,
+        key: &str,
+        data: &[u8],
+        condition: Option<WriteCondition>,
+    ) -> Result<bool> {
         debug!(
             "Putting large object to S3 (multipart): {} ({} bytes)",
             key,
@@ -780,13 +1250,27 @@ impl S3Backend {
         let key_clone = key.to_string();
 
         // Initiate multipart upload
+        let checksum = self.config.checksum;
+        let sse = SseHeaders::write(self.config.encryption.as_ref());
         let multipart = client
             .create_multipart_upload()
             .bucket(&bucket)
             .key(&key_clone)
+            .set_checksum_algorithm(checksum.map(sdk_checksum_algorithm))
+            .set_storage_class(
+                self.config
+                    .storage_class
+                    .class_for(key)
+                    .map(StorageClass::to_sdk),
+            )
+            .set_server_side_encryption(sse.algorithm.clone())
+            .set_ssekms_key_id(sse.kms_key_id.clone())
+            .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+            .set_sse_customer_key(sse.customer_key.clone())
+            .set_sse_customer_key_md5(sse.customer_key_md5.clone())
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to initiate multipart upload: {}", e))?;
+            .map_err(|e| request_error("Failed to initiate multipart upload", e))?;
 
         let upload_id = multipart
             .upload_id()
@@ -801,9 +1285,8 @@ impl S3Backend {
         // Upload parts concurrently
         let mut part_handles = vec![];
         let part_size = self.config.part_size as usize;
-        let mut part_number = 1;
 
-        for chunk in data.chunks(part_size) {
+        for (part_number, chunk) in (1..).zip(data.chunks(part_size)) {
             let client = client.clone();
             let bucket = bucket.clone();
             let key = key_clone.clone();
@@ -811,6 +1294,7 @@ impl S3Backend {
             let stats = self.stats.clone();
             let chunk_data = chunk.to_vec();
             let part_num = part_number;
+            let sse = sse.clone();
 
             let handle = tokio::spawn(async move {
                 debug!(
@@ -820,16 +1304,24 @@ impl S3Backend {
                     key
                 );
 
+                let part_checksum = ChecksumHeaders::new(checksum, &chunk_data);
                 let response = client
                     .upload_part()
                     .bucket(&bucket)
                     .key(&key)
                     .upload_id(&upload_id)
                     .part_number(part_num)
+                    .set_checksum_sha256(part_checksum.sha256.clone())
+                    .set_checksum_crc32_c(part_checksum.crc32c.clone())
+                    .set_sse_customer_algorithm(sse.customer_algorithm)
+                    .set_sse_customer_key(sse.customer_key)
+                    .set_sse_customer_key_md5(sse.customer_key_md5)
                     .body(Bytes::from(chunk_data.clone()).into())
                     .send()
                     .await
-                    .map_err(|e| anyhow!("Failed to upload part {}: {}", part_num, e))?;
+                    .map_err(|e| {
+                        request_error(&format!("Failed to upload part {}", part_num), e)
+                    })?;
 
                 let etag = response
                     .e_tag()
@@ -840,7 +1332,7 @@ impl S3Backend {
                     .total_bytes_uploaded
                     .fetch_add(chunk_data.len() as u64, Ordering::Relaxed);
 
-                Ok::<_, anyhow::Error>((part_num, etag))
+                Ok::<_, anyhow::Error>((part_num, etag, part_checksum))
             });
 
             part_handles.push(handle);
@@ -852,15 +1344,12 @@ impl S3Backend {
                     let _ = handle.await??;
                 }
             }
-
-            part_number += 1;
         }
 
         // Wait for all remaining parts to complete
         let mut parts = vec![];
         for handle in part_handles {
-            let (part_num, etag) = handle.await??;
-            parts.push((part_num, etag));
+            parts.push(handle.await??);
         }
 
         // Sort parts by part number
@@ -869,33 +1358,951 @@ impl S3Backend {
         // Complete multipart upload
         let part_list: Vec<_> = parts
             .into_iter()
-            .map(|(part_num, etag)| {
+            .map(|(part_num, etag, checksum)| {
                 aws_sdk_s3::types::CompletedPart::builder()
                     .part_number(part_num)
                     .e_tag(etag)
+                    .set_checksum_sha256(checksum.sha256)
+                    .set_checksum_crc32_c(checksum.crc32c)
                     .build()
             })
             .collect();
 
-        client
+        let result = client
             .complete_multipart_upload()
             .bucket(&bucket)
             .key(&key_clone)
             .upload_id(&upload_id)
+            .set_if_none_match(condition.as_ref().and_then(WriteCondition::if_none_match))
+            .set_if_match(condition.as_ref().and_then(WriteCondition::if_match))
+            .set_sse_customer_algorithm(sse.customer_algorithm)
+            .set_sse_customer_key(sse.customer_key)
+            .set_sse_customer_key_md5(sse.customer_key_md5)
             .multipart_upload(
                 aws_sdk_s3::types::CompletedMultipartUpload::builder()
                     .set_parts(Some(part_list))
                     .build(),
             )
             .send()
-            .await
-            .map_err(|e| anyhow!("Failed to complete multipart upload: {}", e))?;
+            .await;
+        match result {
+            Ok(_) => {}
+            Err(e) if is_precondition_failed(&e) => {
+                debug!("Precondition failed putting {}: {:?}", key_clone, condition);
+                // Don't leave the uploaded parts behind
+                if let Err(e) = client
+                    .abort_multipart_upload()
+                    .bucket(&bucket)
+                    .key(&key_clone)
+                    .upload_id(&upload_id)
+                    .send()
+                    .await
+                {
+                    warn!("Failed to abort multipart upload for {}: {}", key_clone, e);
+                }
+                return Ok(false);
+            }
+            Err(e) => return Err(request_error("Failed to complete multipart upload", e)),
+        }
 
         debug!("Successfully completed multipart upload for {}", key_clone);
-        Ok(())
+        Ok(true)
     }
 }
 
+/// Most keys a DeleteObjects request may name. Shared with the MinIO backend.
+pub(crate) const DELETE_BATCH_SIZE: usize = 1000;
+
+/// Delete up to [`DELETE_BATCH_SIZE`] keys with one DeleteObjects request,
+/// returning the keys the service reported errors for. Shared with the
+/// MinIO backend.
+pub(crate) async fn delete_objects(
+    client: &Client,
+    bucket: &str,
+    keys: &[String],
+) -> Result<Vec<DeleteFailure>> {
+    let objects = keys
+        .iter()
+        .map(|key| ObjectIdentifier::builder().key(key).build())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let delete = Delete::builder()
+        .set_objects(Some(objects))
+        // Only report the keys that failed
+        .quiet(true)
+        .build()?;
+
+    debug!("Deleting {} objects from {}", keys.len(), bucket);
+    let output = client
+        .delete_objects()
+        .bucket(bucket)
+        .delete(delete)
+        .send()
+        .await
+        .map_err(|e| request_error("Failed to delete objects", e))?;
+
+    Ok(output
+        .errors()
+        .iter()
+        .map(|error| DeleteFailure {
+            key: error.key().unwrap_or_default().to_string(),
+            error: anyhow!(
+                "{}: {}",
+                error.code().unwrap_or("UnknownError"),
+                error.message().unwrap_or_default()
+            ),
+        })
+        .collect())
+}
+
+/// Largest object a single CopyObject request may copy
+const MAX_COPY_OBJECT_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Part size when copying objects too large for CopyObject
+const COPY_PART_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Copy `src_key` to `dst_key` within `bucket` without downloading it,
+/// returning `false` if the source doesn't exist
+///
+/// Objects over 5 GiB are copied as a multipart upload of ranged
+/// UploadPartCopy requests, carrying over their content type and metadata
+/// as CopyObject does. The copy is written in `storage_class`, or the
+/// bucket's default class, whatever the source's. Both objects are taken to
+/// use `encryption`; with SSE-C, the source is read with the same key.
+/// Shared with the MinIO backend.
+pub(crate) async fn copy_object(
+    client: &Client,
+    bucket: &str,
+    src_key: &str,
+    dst_key: &str,
+    storage_class: Option<StorageClass>,
+    encryption: Option<&ServerSideEncryption>,
+) -> Result<bool> {
+    let sse = SseHeaders::write(encryption);
+    let source = match client
+        .head_object()
+        .bucket(bucket)
+        .key(src_key)
+        .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+        .set_sse_customer_key(sse.customer_key.clone())
+        .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+        .send()
+        .await
+    {
+        Ok(output) => output,
+        Err(e) if is_not_found(&e) => return Ok(false),
+        Err(e) => return Err(request_error("Failed to fetch object metadata", e)),
+    };
+    let size = head_object_meta(&source).size;
+    let copy_source = copy_source(bucket, src_key);
+
+    if size <= MAX_COPY_OBJECT_SIZE {
+        debug!("Copying {} to {} in {}", src_key, dst_key, bucket);
+        client
+            .copy_object()
+            .bucket(bucket)
+            .key(dst_key)
+            .copy_source(&copy_source)
+            .set_storage_class(storage_class.map(StorageClass::to_sdk))
+            .set_server_side_encryption(sse.algorithm.clone())
+            .set_ssekms_key_id(sse.kms_key_id.clone())
+            .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+            .set_sse_customer_key(sse.customer_key.clone())
+            .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+            .set_copy_source_sse_customer_algorithm(sse.customer_algorithm.clone())
+            .set_copy_source_sse_customer_key(sse.customer_key.clone())
+            .set_copy_source_sse_customer_key_md5(sse.customer_key_md5.clone())
+            .send()
+            .await
+            .map_err(|e| request_error("Failed to copy object", e))?;
+        return Ok(true);
+    }
+
+    debug!(
+        "Copying {} to {} in {} as a multipart upload of {} bytes",
+        src_key, dst_key, bucket, size
+    );
+    let multipart = client
+        .create_multipart_upload()
+        .bucket(bucket)
+        .key(dst_key)
+        .set_content_type(source.content_type().map(str::to_string))
+        .set_metadata(source.metadata().cloned())
+        .set_storage_class(storage_class.map(StorageClass::to_sdk))
+        .set_server_side_encryption(sse.algorithm.clone())
+        .set_ssekms_key_id(sse.kms_key_id.clone())
+        .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+        .set_sse_customer_key(sse.customer_key.clone())
+        .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+        .send()
+        .await
+        .map_err(|e| request_error("Failed to initiate multipart copy", e))?;
+    let upload_id = multipart
+        .upload_id()
+        .ok_or_else(|| anyhow!("No upload ID returned for multipart copy"))?
+        .to_string();
+
+    let copied = async {
+        let mut completed = Vec::new();
+        let mut offset = 0;
+        for part_number in 1.. {
+            if offset >= size {
+                break;
+            }
+            let end = (offset + COPY_PART_SIZE).min(size) - 1;
+            let response = client
+                .upload_part_copy()
+                .bucket(bucket)
+                .key(dst_key)
+                .upload_id(&upload_id)
+                .part_number(part_number)
+                .copy_source(&copy_source)
+                .copy_source_range(format!("bytes={}-{}", offset, end))
+                .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+                .set_sse_customer_key(sse.customer_key.clone())
+                .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+                .set_copy_source_sse_customer_algorithm(sse.customer_algorithm.clone())
+                .set_copy_source_sse_customer_key(sse.customer_key.clone())
+                .set_copy_source_sse_customer_key_md5(sse.customer_key_md5.clone())
+                .send()
+                .await
+                .map_err(|e| request_error(&format!("Failed to copy part {}", part_number), e))?;
+            let etag = response
+                .copy_part_result()
+                .and_then(|result| result.e_tag())
+                .ok_or_else(|| anyhow!("No ETag returned for part {}", part_number))?;
+            completed.push(
+                aws_sdk_s3::types::CompletedPart::builder()
+                    .part_number(part_number)
+                    .e_tag(etag)
+                    .build(),
+            );
+            offset = end + 1;
+        }
+
+        client
+            .complete_multipart_upload()
+            .bucket(bucket)
+            .key(dst_key)
+            .upload_id(&upload_id)
+            .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+            .set_sse_customer_key(sse.customer_key.clone())
+            .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+            .multipart_upload(
+                aws_sdk_s3::types::CompletedMultipartUpload::builder()
+                    .set_parts(Some(completed))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| request_error("Failed to complete multipart copy", e))?;
+        Ok::<_, anyhow::Error>(())
+    };
+
+    if let Err(e) = copied.await {
+        if let Err(abort) = client
+            .abort_multipart_upload()
+            .bucket(bucket)
+            .key(dst_key)
+            .upload_id(&upload_id)
+            .send()
+            .await
+        {
+            warn!("Failed to abort multipart copy for {}: {}", dst_key, abort);
+        }
+        return Err(e);
+    }
+    Ok(true)
+}
+
+/// The `x-amz-copy-source` value naming `key` in `bucket`, with everything
+/// but unreserved characters and path separators percent-encoded
+fn copy_source(bucket: &str, key: &str) -> String {
+    let mut source = format!("{}/", bucket);
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                source.push(byte as char)
+            }
+            _ => source.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    source
+}
+
+/// An error for a failed request that keeps the HTTP status and the
+/// service's error code, so [`RetryPolicy`] can tell throttling and outages
+/// from permanent failures. Shared with the MinIO backend.
+pub(crate) fn request_error<E>(
+    what: &str,
+    error: aws_sdk_s3::error::SdkError<E, aws_sdk_s3::config::http::HttpResponse>,
+) -> anyhow::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let context = aws_sdk_s3::error::DisplayErrorContext(&error);
+    match error.raw_response() {
+        Some(response) => anyhow!(
+            "{} (HTTP {}): {}",
+            what,
+            response.status().as_u16(),
+            context
+        ),
+        None => anyhow!("{}: {}", what, context),
+    }
+}
+
+/// Keys under `prefix`, a ListObjectsV2 page at a time, each page retried
+/// under `policy`. Shared with the MinIO backend.
+pub(crate) fn list_pages<'a>(
+    client: &'a Client,
+    bucket: &'a str,
+    prefix: String,
+    policy: RetryPolicy,
+) -> KeyStream<'a> {
+    // `None` once the last page has been fetched
+    let first_page: Option<Option<String>> = Some(None);
+    stream::pages(first_page, move |token| {
+        let prefix = prefix.clone();
+        async move {
+            let Some(token) = token else {
+                return Ok(None);
+            };
+            let response = policy
+                .run("listing objects", || {
+                    let mut request = client.list_objects_v2().bucket(bucket);
+                    if !prefix.is_empty() {
+                        request = request.prefix(&prefix);
+                    }
+                    if let Some(token) = &token {
+                        request = request.continuation_token(token);
+                    }
+                    async move {
+                        request
+                            .send()
+                            .await
+                            .map_err(|e| request_error("Failed to list objects", e))
+                    }
+                })
+                .await?;
+
+            let keys = response
+                .contents()
+                .iter()
+                .filter_map(|obj| obj.key())
+                .map(str::to_string)
+                .collect();
+            let next = match response.is_truncated() {
+                Some(true) => response
+                    .next_continuation_token()
+                    .map(|token| Some(token.to_string())),
+                _ => None,
+            };
+            Ok(Some((keys, next)))
+        }
+    })
+}
+
+/// The CommonPrefixes of the keys under `prefix` grouped by `delimiter`, each
+/// ListObjectsV2 page retried under `policy`. Shared with the MinIO backend.
+pub(crate) async fn list_common_prefixes(
+    client: &Client,
+    bucket: &str,
+    prefix: &str,
+    delimiter: &str,
+    policy: RetryPolicy,
+) -> Result<Vec<String>> {
+    check_delimiter(delimiter)?;
+
+    let mut prefixes = vec![];
+    let mut continuation_token: Option<String> = None;
+    loop {
+        let response = policy
+            .run("listing prefixes", || {
+                let mut request = client.list_objects_v2().bucket(bucket).delimiter(delimiter);
+                if !prefix.is_empty() {
+                    request = request.prefix(prefix);
+                }
+                if let Some(token) = &continuation_token {
+                    request = request.continuation_token(token);
+                }
+                async move {
+                    request
+                        .send()
+                        .await
+                        .map_err(|e| request_error("Failed to list prefixes", e))
+                }
+            })
+            .await?;
+
+        prefixes.extend(
+            response
+                .common_prefixes()
+                .iter()
+                .filter_map(|common| common.prefix())
+                .map(str::to_string),
+        );
+
+        continuation_token = match response.is_truncated() {
+            Some(true) => response.next_continuation_token().map(str::to_string),
+            _ => None,
+        };
+        if continuation_token.is_none() {
+            break;
+        }
+    }
+
+    prefixes.sort();
+    prefixes.dedup();
+    Ok(prefixes)
+}
+
+/// Count the objects under `prefix` and their sizes from ListObjectsV2
+/// pages, each retried under `policy`. Shared with the MinIO backend.
+pub(crate) async fn measure_objects(
+    client: &Client,
+    bucket: &str,
+    prefix: &str,
+    policy: RetryPolicy,
+) -> Result<StorageUsage> {
+    let mut usage = StorageUsage::default();
+    let mut continuation_token: Option<String> = None;
+    loop {
+        let response = policy
+            .run("measuring usage", || {
+                let mut request = client.list_objects_v2().bucket(bucket);
+                if !prefix.is_empty() {
+                    request = request.prefix(prefix);
+                }
+                if let Some(token) = &continuation_token {
+                    request = request.continuation_token(token);
+                }
+                async move {
+                    request
+                        .send()
+                        .await
+                        .map_err(|e| request_error("Failed to list objects", e))
+                }
+            })
+            .await?;
+
+        for object in response.contents() {
+            usage.add(object.size().unwrap_or(0).max(0) as u64);
+        }
+
+        continuation_token = match response.is_truncated() {
+            Some(true) => response.next_continuation_token().map(str::to_string),
+            _ => None,
+        };
+        if continuation_token.is_none() {
+            break;
+        }
+    }
+    Ok(usage)
+}
+
+/// Prefix of the ids of the lifecycle rules [`apply_lifecycle_rules`]
+/// manages, so they can be told from rules set up by other means
+const LIFECYCLE_RULE_PREFIX: &str = "mediagit-";
+
+/// Id of the managed lifecycle rule `name` for policies confined to `scope`
+fn lifecycle_rule_id(scope: &str, name: &str) -> String {
+    format!("{}{}{}", LIFECYCLE_RULE_PREFIX, scope, name)
+}
+
+/// Whether the lifecycle rule `id` was installed for a policy confined to
+/// `scope`, and not for another scope sharing the bucket
+fn is_scope_lifecycle_rule(id: &str, scope: &str) -> bool {
+    let Some(name) = id
+        .strip_prefix(LIFECYCLE_RULE_PREFIX)
+        .and_then(|id| id.strip_prefix(scope))
+    else {
+        return false;
+    };
+    name == "abort-incomplete-uploads"
+        || name
+            .strip_prefix("expire-")
+            .is_some_and(|i| !i.is_empty() && i.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Whole days for a lifecycle rule, rounded up: S3 counts rules in days and
+/// needs at least one
+fn lifecycle_days(ttl: Duration) -> i32 {
+    const DAY: u64 = 24 * 60 * 60;
+    i32::try_from(ttl.as_secs().div_ceil(DAY).max(1)).unwrap_or(i32::MAX)
+}
+
+/// Replace the bucket's mediagit lifecycle rules for `policy`'s scope with
+/// ones enforcing it, keeping any other rules, including those of other
+/// scopes, each request retried under `retry`. Shared with the MinIO backend.
+pub(crate) async fn apply_lifecycle_rules(
+    client: &Client,
+    bucket: &str,
+    policy: &ExpiryPolicy,
+    retry: RetryPolicy,
+) -> Result<()> {
+    use aws_sdk_s3::types::{
+        AbortIncompleteMultipartUpload, BucketLifecycleConfiguration, ExpirationStatus,
+        LifecycleExpiration, LifecycleRule, LifecycleRuleFilter,
+    };
+
+    // PutBucketLifecycleConfiguration replaces every rule, so start from the
+    // current ones; a bucket without any answers 404
+    let current = retry
+        .run("reading lifecycle rules", || async {
+            match client
+                .get_bucket_lifecycle_configuration()
+                .bucket(bucket)
+                .send()
+                .await
+            {
+                Ok(output) => Ok(output.rules().to_vec()),
+                Err(e) if is_not_found(&e) => Ok(vec![]),
+                Err(e) => Err(request_error("Failed to read lifecycle rules", e)),
+            }
+        })
+        .await?;
+    let mut rules: Vec<LifecycleRule> = current
+        .into_iter()
+        .filter(|rule| {
+            !rule
+                .id()
+                .is_some_and(|id| is_scope_lifecycle_rule(id, &policy.scope))
+        })
+        .collect();
+
+    for (i, rule) in policy.rules.iter().enumerate() {
+        rules.push(
+            LifecycleRule::builder()
+                .id(lifecycle_rule_id(&policy.scope, &format!("expire-{}", i)))
+                .filter(LifecycleRuleFilter::builder().prefix(&rule.prefix).build())
+                .status(ExpirationStatus::Enabled)
+                .expiration(
+                    LifecycleExpiration::builder()
+                        .days(lifecycle_days(rule.ttl))
+                        .build(),
+                )
+                .build()
+                .context("Failed to build lifecycle rule")?,
+        );
+    }
+    if let Some(ttl) = policy.incomplete_uploads {
+        rules.push(
+            LifecycleRule::builder()
+                .id(lifecycle_rule_id(&policy.scope, "abort-incomplete-uploads"))
+                .filter(LifecycleRuleFilter::builder().prefix(&policy.scope).build())
+                .status(ExpirationStatus::Enabled)
+                .abort_incomplete_multipart_upload(
+                    AbortIncompleteMultipartUpload::builder()
+                        .days_after_initiation(lifecycle_days(ttl))
+                        .build(),
+                )
+                .build()
+                .context("Failed to build lifecycle rule")?,
+        );
+    }
+
+    if rules.is_empty() {
+        // An empty configuration is rejected; remove it instead
+        return retry
+            .run("removing lifecycle rules", || async {
+                client
+                    .delete_bucket_lifecycle()
+                    .bucket(bucket)
+                    .send()
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| request_error("Failed to remove lifecycle rules", e))
+            })
+            .await;
+    }
+
+    let configuration = BucketLifecycleConfiguration::builder()
+        .set_rules(Some(rules))
+        .build()
+        .context("Failed to build lifecycle configuration")?;
+    retry
+        .run("writing lifecycle rules", || {
+            let request = client
+                .put_bucket_lifecycle_configuration()
+                .bucket(bucket)
+                .lifecycle_configuration(configuration.clone());
+            async move {
+                request
+                    .send()
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| request_error("Failed to write lifecycle rules", e))
+            }
+        })
+        .await
+}
+
+/// Abort the multipart uploads of keys under `prefix` started more than
+/// `ttl` ago, returning how many were aborted, each request retried under
+/// `retry`. Shared with the MinIO backend.
+pub(crate) async fn abort_stale_uploads(
+    client: &Client,
+    bucket: &str,
+    prefix: &str,
+    ttl: Duration,
+    retry: RetryPolicy,
+) -> Result<usize> {
+    let now = SystemTime::now();
+    let mut stale = vec![];
+    let mut markers: Option<(String, String)> = None;
+    loop {
+        let response = retry
+            .run("listing multipart uploads", || {
+                let mut request = client.list_multipart_uploads().bucket(bucket);
+                if !prefix.is_empty() {
+                    request = request.prefix(prefix);
+                }
+                if let Some((key, upload_id)) = &markers {
+                    request = request.key_marker(key).upload_id_marker(upload_id);
+                }
+                async move {
+                    request
+                        .send()
+                        .await
+                        .map_err(|e| request_error("Failed to list multipart uploads", e))
+                }
+            })
+            .await?;
+
+        for upload in response.uploads() {
+            let (Some(key), Some(upload_id), Some(initiated)) =
+                (upload.key(), upload.upload_id(), upload.initiated())
+            else {
+                continue;
+            };
+            let Ok(initiated) = SystemTime::try_from(*initiated) else {
+                continue;
+            };
+            if is_expired(initiated, ttl, now) {
+                stale.push((key.to_string(), upload_id.to_string()));
+            }
+        }
+
+        markers = match response.is_truncated() {
+            Some(true) => response
+                .next_key_marker()
+                .zip(response.next_upload_id_marker())
+                .map(|(key, upload_id)| (key.to_string(), upload_id.to_string())),
+            _ => None,
+        };
+        if markers.is_none() {
+            break;
+        }
+    }
+
+    for (key, upload_id) in &stale {
+        retry
+            .run("aborting multipart upload", || async {
+                match client
+                    .abort_multipart_upload()
+                    .bucket(bucket)
+                    .key(key)
+                    .upload_id(upload_id)
+                    .send()
+                    .await
+                {
+                    // Completed or aborted since it was listed
+                    Err(e) if is_not_found(&e) => Ok(()),
+                    result => result
+                        .map(|_| ())
+                        .map_err(|e| request_error("Failed to abort multipart upload", e)),
+                }
+            })
+            .await?;
+        debug!(key = %key, upload_id = %upload_id, "Aborted stale multipart upload");
+    }
+    Ok(stale.len())
+}
+
+/// Precondition on a conditional write. Shared with the MinIO backend.
+#[derive(Debug, Clone)]
+pub(crate) enum WriteCondition {
+    /// No object exists under the key (`If-None-Match: *`)
+    Absent,
+    /// The stored object has this ETag (`If-Match`)
+    Matches(String),
+}
+
+impl WriteCondition {
+    pub(crate) fn if_none_match(&self) -> Option<String> {
+        matches!(self, Self::Absent).then(|| "*".to_string())
+    }
+
+    pub(crate) fn if_match(&self) -> Option<String> {
+        match self {
+            // ETags are quoted on the wire but `head` returns them bare
+            Self::Matches(etag) => Some(format!("\"{}\"", etag.trim_matches('"'))),
+            Self::Absent => None,
+        }
+    }
+}
+
+/// Whether a conditional write was refused because its precondition
+/// didn't hold (HTTP 412). Shared with the MinIO backend.
+pub(crate) fn is_precondition_failed<E>(
+    error: &aws_sdk_s3::error::SdkError<E, aws_sdk_s3::config::http::HttpResponse>,
+) -> bool {
+    error
+        .raw_response()
+        .is_some_and(|response| response.status().as_u16() == 412)
+}
+
+/// Whether a request failed because the object doesn't exist (HTTP 404).
+/// Shared with the MinIO backend.
+pub(crate) fn is_not_found<E>(
+    error: &aws_sdk_s3::error::SdkError<E, aws_sdk_s3::config::http::HttpResponse>,
+) -> bool {
+    error
+        .raw_response()
+        .is_some_and(|response| response.status().as_u16() == 404)
+}
+
+/// Object metadata from a HEAD response. Shared with the MinIO backend.
+pub(crate) fn head_object_meta(
+    output: &aws_sdk_s3::operation::head_object::HeadObjectOutput,
+) -> ObjectMeta {
+    ObjectMeta {
+        size: output
+            .content_length()
+            .and_then(|len| u64::try_from(len).ok())
+            .unwrap_or(0),
+        modified: output
+            .last_modified()
+            .and_then(|time| SystemTime::try_from(*time).ok()),
+        etag: output.e_tag().map(|tag| tag.trim_matches('"').to_string()),
+        content_type: output.content_type().map(str::to_string),
+        metadata: output.metadata().cloned().unwrap_or_default(),
+    }
+}
+
+/// How a streamed upload is stored. Shared with the MinIO backend.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct UploadOptions {
+    /// Algorithm each part is checksummed with
+    pub(crate) checksum: Option<ChecksumAlgorithm>,
+
+    /// Storage class the object is written in
+    pub(crate) storage_class: Option<StorageClass>,
+
+    /// Server-side encryption headers for the upload
+    pub(crate) encryption: SseHeaders,
+}
+
+/// Checksum header values for an upload, at most one of them set. Shared
+/// with the MinIO backend.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ChecksumHeaders {
+    pub(crate) sha256: Option<String>,
+    pub(crate) crc32c: Option<String>,
+}
+
+impl ChecksumHeaders {
+    /// Headers carrying the `algorithm` checksum of `data`, or none
+    pub(crate) fn new(algorithm: Option<ChecksumAlgorithm>, data: &[u8]) -> Self {
+        let mut headers = ChecksumHeaders::default();
+        if let Some(algorithm) = algorithm {
+            let encoded = Some(algorithm.checksum(data).to_base64());
+            match algorithm {
+                ChecksumAlgorithm::Sha256 => headers.sha256 = encoded,
+                ChecksumAlgorithm::Crc32c => headers.crc32c = encoded,
+            }
+        }
+        headers
+    }
+}
+
+/// The SDK's name for `algorithm`. Shared with the MinIO backend.
+pub(crate) fn sdk_checksum_algorithm(
+    algorithm: ChecksumAlgorithm,
+) -> aws_sdk_s3::types::ChecksumAlgorithm {
+    match algorithm {
+        ChecksumAlgorithm::Sha256 => aws_sdk_s3::types::ChecksumAlgorithm::Sha256,
+        ChecksumAlgorithm::Crc32c => aws_sdk_s3::types::ChecksumAlgorithm::Crc32C,
+    }
+}
+
+/// The whole-object `algorithm` checksum S3 stored with a downloaded
+/// object, if it has one
+///
+/// Objects uploaded without that checksum have none, and multipart uploads
+/// only have a checksum of their part checksums, which can't be checked
+/// against the data. Shared with the MinIO backend.
+pub(crate) fn stored_checksum(
+    algorithm: ChecksumAlgorithm,
+    output: &GetObjectOutput,
+) -> Option<Checksum> {
+    let encoded = match algorithm {
+        ChecksumAlgorithm::Sha256 => output.checksum_sha256(),
+        ChecksumAlgorithm::Crc32c => output.checksum_crc32_c(),
+    }?;
+    Checksum::from_base64(algorithm, encoded)
+}
+
+/// Whether a ranged get failed because the range starts past the end of
+/// the object (HTTP 416). Shared with the MinIO backend.
+pub(crate) fn is_invalid_range<E>(
+    error: &aws_sdk_s3::error::SdkError<E, aws_sdk_s3::config::http::HttpResponse>,
+) -> bool {
+    error
+        .raw_response()
+        .is_some_and(|response| response.status().as_u16() == 416)
+}
+
+/// Upload `head` followed by the rest of `parts` as one multipart object,
+/// returning the bytes uploaded
+///
+/// At most `max_concurrent` parts are in flight, and so in memory, at once.
+/// If the stream or any part fails the upload is aborted, so the bucket is
+/// not left holding orphaned parts. With a checksum algorithm in `options`,
+/// each part is sent with its checksum. Shared with the MinIO backend.
+pub(crate) async fn put_multipart_stream(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    head: Vec<Bytes>,
+    parts: PartReader,
+    max_concurrent: usize,
+    options: UploadOptions,
+) -> Result<u64> {
+    debug!("Streaming multipart upload: {}", key);
+
+    let checksum = options.checksum;
+    let sse = &options.encryption;
+    let multipart = client
+        .create_multipart_upload()
+        .bucket(bucket)
+        .key(key)
+        .set_checksum_algorithm(checksum.map(sdk_checksum_algorithm))
+        .set_storage_class(options.storage_class.map(StorageClass::to_sdk))
+        .set_server_side_encryption(sse.algorithm.clone())
+        .set_ssekms_key_id(sse.kms_key_id.clone())
+        .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+        .set_sse_customer_key(sse.customer_key.clone())
+        .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+        .send()
+        .await
+        .map_err(|e| request_error("Failed to initiate multipart upload", e))?;
+    let upload_id = multipart
+        .upload_id()
+        .ok_or_else(|| anyhow!("No upload ID returned for multipart upload"))?
+        .to_string();
+
+    let part_request = || {
+        client
+            .upload_part()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(&upload_id)
+            .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+            .set_sse_customer_key(sse.customer_key.clone())
+            .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+    };
+    let uploaded = upload_parts(part_request, checksum, head, parts, max_concurrent).await;
+    let (total, completed) = match uploaded {
+        Ok(uploaded) => uploaded,
+        Err(e) => {
+            if let Err(abort) = client
+                .abort_multipart_upload()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .send()
+                .await
+            {
+                warn!("Failed to abort multipart upload for {}: {}", key, abort);
+            }
+            return Err(e);
+        }
+    };
+
+    client
+        .complete_multipart_upload()
+        .bucket(bucket)
+        .key(key)
+        .upload_id(&upload_id)
+        .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+        .set_sse_customer_key(sse.customer_key.clone())
+        .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+        .multipart_upload(
+            aws_sdk_s3::types::CompletedMultipartUpload::builder()
+                .set_parts(Some(completed))
+                .build(),
+        )
+        .send()
+        .await
+        .map_err(|e| request_error("Failed to complete multipart upload", e))?;
+
+    debug!("Completed streaming multipart upload for {}", key);
+    Ok(total)
+}
+
+/// Upload every part of a multipart upload with requests from
+/// `part_request`, returning the bytes sent and the completed parts in order
+async fn upload_parts(
+    part_request: impl Fn() -> aws_sdk_s3::operation::upload_part::builders::UploadPartFluentBuilder,
+    checksum: Option<ChecksumAlgorithm>,
+    head: Vec<Bytes>,
+    mut parts: PartReader,
+    max_concurrent: usize,
+) -> Result<(u64, Vec<aws_sdk_s3::types::CompletedPart>)> {
+    let mut uploads = tokio::task::JoinSet::new();
+    let mut completed = Vec::new();
+    let mut head = head.into_iter();
+    let mut total = 0u64;
+
+    for part_number in 1.. {
+        let part = match head.next() {
+            Some(part) => part,
+            None => match parts.next_part().await? {
+                Some(part) => part,
+                None => break,
+            },
+        };
+        total += part.len() as u64;
+
+        while uploads.len() >= max_concurrent.max(1) {
+            if let Some(done) = uploads.join_next().await {
+                completed.push(done??);
+            }
+        }
+
+        let part_checksum = ChecksumHeaders::new(checksum, &part);
+        let request = part_request()
+            .part_number(part_number)
+            .set_checksum_sha256(part_checksum.sha256.clone())
+            .set_checksum_crc32_c(part_checksum.crc32c.clone())
+            .body(part.into());
+        uploads.spawn(async move {
+            let response = request
+                .send()
+                .await
+                .map_err(|e| request_error(&format!("Failed to upload part {}", part_number), e))?;
+            let etag = response
+                .e_tag()
+                .ok_or_else(|| anyhow!("No ETag returned for part {}", part_number))?;
+            Ok::<_, anyhow::Error>(
+                aws_sdk_s3::types::CompletedPart::builder()
+                    .part_number(part_number)
+                    .e_tag(etag)
+                    .set_checksum_sha256(part_checksum.sha256)
+                    .set_checksum_crc32_c(part_checksum.crc32c)
+                    .build(),
+            )
+        });
+    }
+    while let Some(done) = uploads.join_next().await {
+        completed.push(done??);
+    }
+
+    completed.sort_by_key(|part| part.part_number());
+    Ok((total, completed))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -925,4 +2332,88 @@ mod tests {
         };
         let _ = format!("{:?}", config);
     }
+
+    #[test]
+    fn test_signing_region_applied_to_client_config() {
+        let endpoint = "https://s3.eu-central-2.wasabisys.com";
+        let mut config = S3Config {
+            bucket: "test-bucket".to_string(),
+            endpoint: Some(endpoint.to_string()),
+            ..Default::default()
+        };
+        let region = |config: &S3Config| {
+            let sdk_config = S3Backend::endpoint_sdk_config(config, endpoint, None);
+            sdk_config.region().unwrap().to_string()
+        };
+        assert_eq!(region(&config), "us-east-1");
+
+        config.region = Some("eu-central-2".to_string());
+        assert_eq!(region(&config), "eu-central-2");
+
+        config.signing_region = Some("us-east-1".to_string());
+        assert_eq!(region(&config), "us-east-1");
+    }
+
+    #[test]
+    fn test_copy_source_encoding() {
+        assert_eq!(
+            copy_source("bucket", "objects/ab/cd"),
+            "bucket/objects/ab/cd"
+        );
+        assert_eq!(
+            copy_source("bucket", "media/my clip+1.mp4"),
+            "bucket/media/my%20clip%2B1.mp4"
+        );
+        assert_eq!(copy_source("bucket", "café"), "bucket/caf%C3%A9");
+    }
+
+    #[test]
+    fn test_checksum_headers() {
+        let none = ChecksumHeaders::new(None, b"data");
+        assert_eq!((none.sha256, none.crc32c), (None, None));
+
+        let crc = ChecksumHeaders::new(Some(ChecksumAlgorithm::Crc32c), b"123456789");
+        assert_eq!(crc.crc32c.as_deref(), Some("4waSgw=="));
+        assert_eq!(crc.sha256, None);
+
+        let sha = ChecksumHeaders::new(Some(ChecksumAlgorithm::Sha256), b"data");
+        assert!(sha.sha256.is_some());
+        assert_eq!(sha.crc32c, None);
+    }
+
+    #[test]
+    fn test_stored_checksum() {
+        let output = GetObjectOutput::builder()
+            .checksum_crc32_c("4waSgw==")
+            .build();
+        let stored = stored_checksum(ChecksumAlgorithm::Crc32c, &output).unwrap();
+        assert!(stored.verify("key", b"123456789").is_ok());
+        assert!(stored_checksum(ChecksumAlgorithm::Sha256, &output).is_none());
+
+        // A multipart upload's checksum of part checksums isn't checked
+        let multipart = GetObjectOutput::builder()
+            .checksum_crc32_c("4waSgw==-2")
+            .build();
+        assert!(stored_checksum(ChecksumAlgorithm::Crc32c, &multipart).is_none());
+    }
+
+    #[test]
+    fn test_lifecycle_rules_are_scoped() {
+        let games = "repos/games/";
+        let expire = lifecycle_rule_id(games, "expire-0");
+        let abort = lifecycle_rule_id(games, "abort-incomplete-uploads");
+        assert_eq!(expire, "mediagit-repos/games/expire-0");
+        assert!(is_scope_lifecycle_rule(&expire, games));
+        assert!(is_scope_lifecycle_rule(&abort, games));
+
+        // Neither the unscoped repository nor a nested one owns these rules
+        assert!(!is_scope_lifecycle_rule(&expire, ""));
+        assert!(!is_scope_lifecycle_rule(&expire, "repos/"));
+        assert!(!is_scope_lifecycle_rule(
+            &lifecycle_rule_id("repos/games/v2/", "expire-0"),
+            games
+        ));
+        assert!(is_scope_lifecycle_rule("mediagit-expire-3", ""));
+        assert!(!is_scope_lifecycle_rule("archive-after-30-days", ""));
+    }
 }
@@ -0,0 +1,236 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Server-side encryption for S3-compatible backends
+//!
+//! S3 can encrypt objects at rest with keys it manages (SSE-S3), with a KMS
+//! key (SSE-KMS), or with a key the client sends along with every request
+//! (SSE-C). Unlike [`EncryptedBackend`](crate::EncryptedBackend), the
+//! service sees the plaintext; what it adds is encryption the provider's
+//! tooling, audits and key policies understand.
+//!
+//! SSE-S3 and SSE-KMS only change how objects are written. SSE-C objects
+//! can't even be read or inspected without the key, so reads, HEAD requests
+//! and copies send it too.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::sse::{CustomerKey, ServerSideEncryption};
+//!
+//! let kms = ServerSideEncryption::kms("alias/mediagit");
+//! assert_eq!(kms.to_string(), "SSE-KMS (alias/mediagit)");
+//!
+//! let key = CustomerKey::from_base64("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=").unwrap();
+//! let sse_c = ServerSideEncryption::Customer(key);
+//! assert_eq!(sse_c.to_string(), "SSE-C");
+//! ```
+
+use anyhow::Result;
+use aws_sdk_s3::types::ServerSideEncryption as SdkServerSideEncryption;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use md5::{Digest, Md5};
+use std::fmt;
+
+/// Algorithm name S3 expects for customer-provided keys
+const CUSTOMER_KEY_ALGORITHM: &str = "AES256";
+
+/// How S3 encrypts objects at rest
+#[derive(Clone, PartialEq, Eq)]
+pub enum ServerSideEncryption {
+    /// SSE-S3: AES-256 with keys S3 manages
+    S3,
+
+    /// SSE-KMS: a KMS key, or the account's `aws/s3` key if `key_id` is None
+    Kms {
+        /// Key ID, ARN or alias of the KMS key
+        key_id: Option<String>,
+    },
+
+    /// SSE-C: AES-256 with a key the client provides on every request
+    Customer(CustomerKey),
+}
+
+impl ServerSideEncryption {
+    /// SSE-KMS with the KMS key `key_id`
+    pub fn kms(key_id: impl Into<String>) -> Self {
+        ServerSideEncryption::Kms {
+            key_id: Some(key_id.into()),
+        }
+    }
+
+    /// Headers for requests that write an object
+    pub(crate) fn write_headers(&self) -> SseHeaders {
+        match self {
+            ServerSideEncryption::S3 => SseHeaders {
+                algorithm: Some(SdkServerSideEncryption::Aes256),
+                ..SseHeaders::default()
+            },
+            ServerSideEncryption::Kms { key_id } => SseHeaders {
+                algorithm: Some(SdkServerSideEncryption::AwsKms),
+                kms_key_id: key_id.clone(),
+                ..SseHeaders::default()
+            },
+            ServerSideEncryption::Customer(key) => key.headers(),
+        }
+    }
+
+    /// Headers for requests that read an object or its metadata, which only
+    /// SSE-C needs
+    pub(crate) fn read_headers(&self) -> SseHeaders {
+        match self {
+            ServerSideEncryption::Customer(key) => key.headers(),
+            _ => SseHeaders::default(),
+        }
+    }
+}
+
+impl fmt::Display for ServerSideEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerSideEncryption::S3 => f.write_str("SSE-S3"),
+            ServerSideEncryption::Kms { key_id: None } => f.write_str("SSE-KMS"),
+            ServerSideEncryption::Kms {
+                key_id: Some(key_id),
+            } => write!(f, "SSE-KMS ({})", key_id),
+            ServerSideEncryption::Customer(_) => f.write_str("SSE-C"),
+        }
+    }
+}
+
+impl fmt::Debug for ServerSideEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// A 256-bit SSE-C key
+///
+/// `Debug` doesn't print the key, so configs holding one can be logged.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CustomerKey([u8; 32]);
+
+impl CustomerKey {
+    /// Use the raw 32-byte `key`
+    pub fn new(key: [u8; 32]) -> Self {
+        CustomerKey(key)
+    }
+
+    /// Parse a base64-encoded 32-byte key, as S3 tools print them
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| anyhow::anyhow!("SSE-C key is not valid base64: {}", e))?;
+        let key: [u8; 32] = bytes.try_into().map_err(|bytes: Vec<u8>| {
+            anyhow::anyhow!("SSE-C key must be 32 bytes, got {}", bytes.len())
+        })?;
+        Ok(CustomerKey(key))
+    }
+
+    /// The key and its MD5 in the base64 form S3 expects
+    fn headers(&self) -> SseHeaders {
+        SseHeaders {
+            customer_algorithm: Some(CUSTOMER_KEY_ALGORITHM.to_string()),
+            customer_key: Some(BASE64.encode(self.0)),
+            customer_key_md5: Some(BASE64.encode(Md5::digest(self.0))),
+            ..SseHeaders::default()
+        }
+    }
+}
+
+impl fmt::Debug for CustomerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CustomerKey(<redacted>)")
+    }
+}
+
+/// Encryption header values for one request, unset where they don't apply.
+/// Shared by the S3 and MinIO backends.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SseHeaders {
+    pub(crate) algorithm: Option<SdkServerSideEncryption>,
+    pub(crate) kms_key_id: Option<String>,
+    pub(crate) customer_algorithm: Option<String>,
+    pub(crate) customer_key: Option<String>,
+    pub(crate) customer_key_md5: Option<String>,
+}
+
+impl SseHeaders {
+    /// Headers for writing with `encryption`, or none
+    pub(crate) fn write(encryption: Option<&ServerSideEncryption>) -> Self {
+        encryption
+            .map(ServerSideEncryption::write_headers)
+            .unwrap_or_default()
+    }
+
+    /// Headers for reading with `encryption`, or none
+    pub(crate) fn read(encryption: Option<&ServerSideEncryption>) -> Self {
+        encryption
+            .map(ServerSideEncryption::read_headers)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_headers() {
+        let headers = ServerSideEncryption::S3.write_headers();
+        assert_eq!(headers.algorithm, Some(SdkServerSideEncryption::Aes256));
+        assert!(headers.customer_key.is_none());
+
+        let headers = ServerSideEncryption::kms("alias/mediagit").write_headers();
+        assert_eq!(headers.algorithm, Some(SdkServerSideEncryption::AwsKms));
+        assert_eq!(headers.kms_key_id.as_deref(), Some("alias/mediagit"));
+
+        // Reads of SSE-S3 and SSE-KMS objects need no headers
+        assert!(ServerSideEncryption::S3.read_headers().algorithm.is_none());
+        assert!(SseHeaders::read(None).customer_key.is_none());
+    }
+
+    #[test]
+    fn test_customer_key_headers() {
+        let key = CustomerKey::new([7; 32]);
+        let sse = ServerSideEncryption::Customer(key);
+        for headers in [sse.write_headers(), sse.read_headers()] {
+            assert_eq!(headers.algorithm, None);
+            assert_eq!(headers.customer_algorithm.as_deref(), Some("AES256"));
+            assert_eq!(
+                headers.customer_key.as_deref(),
+                Some("BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc=")
+            );
+            assert_eq!(
+                headers.customer_key_md5.as_deref(),
+                Some(BASE64.encode(Md5::digest([7; 32])).as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn test_customer_key_parsing() {
+        let encoded = BASE64.encode([1; 32]);
+        assert_eq!(
+            CustomerKey::from_base64(&encoded).unwrap(),
+            CustomerKey::new([1; 32])
+        );
+        assert!(CustomerKey::from_base64(&BASE64.encode([1; 16])).is_err());
+        assert!(CustomerKey::from_base64("not base64!").is_err());
+        assert_eq!(
+            format!("{:?}", CustomerKey::new([1; 32])),
+            "CustomerKey(<redacted>)"
+        );
+    }
+}
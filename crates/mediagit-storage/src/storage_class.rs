@@ -0,0 +1,221 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! S3 storage classes for uploaded objects
+//!
+//! Pack files written by `gc` are rarely read once the objects in them are
+//! old, so they can live in a cheaper, colder class than the loose objects
+//! still in use. A [`StorageClassPolicy`] picks the class for each upload
+//! from its key: the longest matching prefix rule wins, and keys no rule
+//! matches get the default class, or the bucket's default if there is none.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::storage_class::{StorageClass, StorageClassPolicy};
+//!
+//! let policy = StorageClassPolicy::new()
+//!     .with_default(StorageClass::IntelligentTiering)
+//!     .with_rule("packs/", StorageClass::GlacierIr);
+//!
+//! assert_eq!(policy.class_for("packs/pack-1.pack"), Some(StorageClass::GlacierIr));
+//! assert_eq!(policy.class_for("ab12cd"), Some(StorageClass::IntelligentTiering));
+//! assert_eq!(StorageClassPolicy::new().class_for("ab12cd"), None);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+/// S3 storage class an object is written with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StorageClass {
+    /// Frequently accessed data (the S3 default)
+    Standard,
+
+    /// Infrequently accessed data, with a per-GB retrieval fee
+    StandardIa,
+
+    /// Infrequently accessed data kept in a single availability zone
+    OnezoneIa,
+
+    /// Moved between access tiers by S3 as access patterns change
+    IntelligentTiering,
+
+    /// Archive with millisecond retrieval
+    GlacierIr,
+
+    /// Archive that must be restored before it can be read
+    Glacier,
+
+    /// Cheapest archive, restored within hours
+    DeepArchive,
+}
+
+impl StorageClass {
+    /// Every storage class, coldest last
+    pub const ALL: [StorageClass; 7] = [
+        StorageClass::Standard,
+        StorageClass::StandardIa,
+        StorageClass::OnezoneIa,
+        StorageClass::IntelligentTiering,
+        StorageClass::GlacierIr,
+        StorageClass::Glacier,
+        StorageClass::DeepArchive,
+    ];
+
+    /// Name S3 uses for the class, e.g. `GLACIER_IR`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StorageClass::Standard => "STANDARD",
+            StorageClass::StandardIa => "STANDARD_IA",
+            StorageClass::OnezoneIa => "ONEZONE_IA",
+            StorageClass::IntelligentTiering => "INTELLIGENT_TIERING",
+            StorageClass::GlacierIr => "GLACIER_IR",
+            StorageClass::Glacier => "GLACIER",
+            StorageClass::DeepArchive => "DEEP_ARCHIVE",
+        }
+    }
+
+    /// Whether objects in this class must be restored before `get` can
+    /// read them
+    pub fn needs_restore(self) -> bool {
+        matches!(self, StorageClass::Glacier | StorageClass::DeepArchive)
+    }
+
+    /// The class as the AWS SDK expects it
+    pub(crate) fn to_sdk(self) -> aws_sdk_s3::types::StorageClass {
+        aws_sdk_s3::types::StorageClass::from(self.as_str())
+    }
+}
+
+impl fmt::Display for StorageClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for StorageClass {
+    type Err = anyhow::Error;
+
+    /// Parse an S3 class name, ignoring case and accepting `-` for `_`
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let name = s.trim().to_ascii_uppercase().replace('-', "_");
+        StorageClass::ALL
+            .into_iter()
+            .find(|class| class.as_str() == name)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unknown storage class '{}' (expected one of: {})",
+                    s,
+                    StorageClass::ALL.map(StorageClass::as_str).join(", ")
+                )
+            })
+    }
+}
+
+/// Which storage class each uploaded object gets, by key prefix
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageClassPolicy {
+    /// Class for keys no rule matches (`None` leaves it to the bucket)
+    pub default: Option<StorageClass>,
+
+    /// Key prefixes and the class for keys under each
+    pub rules: Vec<(String, StorageClass)>,
+}
+
+impl StorageClassPolicy {
+    /// Policy that leaves every object in the bucket's default class
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Policy that writes every object in `class`
+    pub fn uniform(class: StorageClass) -> Self {
+        Self::new().with_default(class)
+    }
+
+    /// Write keys no rule matches in `class`
+    pub fn with_default(mut self, class: StorageClass) -> Self {
+        self.default = Some(class);
+        self
+    }
+
+    /// Write keys under `prefix` in `class`
+    pub fn with_rule(mut self, prefix: impl Into<String>, class: StorageClass) -> Self {
+        self.rules.push((prefix.into(), class));
+        self
+    }
+
+    /// Whether the policy sets a class for any key
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.rules.is_empty()
+    }
+
+    /// Class to write `key` in: the longest matching rule's, else the default
+    pub fn class_for(&self, key: &str) -> Option<StorageClass> {
+        self.rules
+            .iter()
+            .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, class)| *class)
+            .or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_storage_class() {
+        for class in StorageClass::ALL {
+            assert_eq!(class.as_str().parse::<StorageClass>().unwrap(), class);
+        }
+        assert_eq!(
+            "glacier-ir".parse::<StorageClass>().unwrap(),
+            StorageClass::GlacierIr
+        );
+        let err = "COLD".parse::<StorageClass>().unwrap_err();
+        assert!(err.to_string().contains("DEEP_ARCHIVE"));
+    }
+
+    #[test]
+    fn test_longest_rule_wins() {
+        let policy = StorageClassPolicy::new()
+            .with_rule("repos/", StorageClass::StandardIa)
+            .with_rule("repos/game/packs/", StorageClass::GlacierIr);
+
+        assert_eq!(
+            policy.class_for("repos/game/packs/pack-1.pack"),
+            Some(StorageClass::GlacierIr)
+        );
+        assert_eq!(
+            policy.class_for("repos/game/ab12"),
+            Some(StorageClass::StandardIa)
+        );
+        assert_eq!(policy.class_for("other/ab12"), None);
+        assert!(!policy.is_empty());
+        assert!(StorageClassPolicy::new().is_empty());
+    }
+
+    #[test]
+    fn test_archive_classes_need_restore() {
+        assert!(StorageClass::DeepArchive.needs_restore());
+        assert!(StorageClass::Glacier.needs_restore());
+        assert!(!StorageClass::GlacierIr.needs_restore());
+        assert_eq!(
+            StorageClass::GlacierIr.to_sdk(),
+            aws_sdk_s3::types::StorageClass::GlacierIr
+        );
+    }
+}
@@ -0,0 +1,207 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Streaming object data
+//!
+//! [`StorageBackend::get`](crate::StorageBackend::get) and
+//! [`put`](crate::StorageBackend::put) hold a whole object in memory, which
+//! is fine for chunks and small files but not for a 20GB video.
+//! [`get_stream`](crate::StorageBackend::get_stream) and
+//! [`put_stream`](crate::StorageBackend::put_stream) move the same data as an
+//! [`ObjectStream`] of [`Bytes`] chunks instead, so memory use stays bounded
+//! by the chunk size. The helpers here convert between streams, buffers and
+//! `tokio` readers.
+//!
+//! Listings work the same way:
+//! [`list_objects_stream`](crate::StorageBackend::list_objects_stream)
+//! yields a [`KeyStream`] that fetches keys a page at a time, so walking a
+//! bucket with millions of objects never holds them all at once.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::{mock::MockBackend, stream, StorageBackend};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let storage = MockBackend::new();
+//! let file: &[u8] = b"frame data";
+//! storage.put_stream("video.mp4", stream::from_reader(file)).await?;
+//!
+//! let data = stream::collect(storage.get_stream("video.mp4").await?).await?;
+//! assert_eq!(data, b"frame data");
+//! # Ok(())
+//! # }
+//! ```
+
+use bytes::{Bytes, BytesMut};
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt, TryStreamExt};
+use std::future::Future;
+use std::pin::Pin;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::io::StreamReader;
+
+/// Size of the chunks read from files and readers: 1MB
+pub const STREAM_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Object data as a stream of chunks
+pub type ObjectStream = Pin<Box<dyn Stream<Item = anyhow::Result<Bytes>> + Send>>;
+
+/// Object keys as they are listed
+pub type KeyStream<'a> = BoxStream<'a, anyhow::Result<String>>;
+
+/// A stream yielding `data` as a single chunk
+pub fn once(data: impl Into<Bytes>) -> ObjectStream {
+    let data = data.into();
+    Box::pin(futures::stream::once(async move { Ok(data) }))
+}
+
+/// Read `reader` to the end as a stream of chunks
+pub fn from_reader(reader: impl AsyncRead + Send + Unpin + 'static) -> ObjectStream {
+    Box::pin(futures::stream::try_unfold(
+        reader,
+        |mut reader| async move {
+            let mut chunk = BytesMut::with_capacity(STREAM_CHUNK_SIZE);
+            while chunk.len() < STREAM_CHUNK_SIZE {
+                if reader.read_buf(&mut chunk).await? == 0 {
+                    break;
+                }
+            }
+            if chunk.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some((chunk.freeze(), reader)))
+        },
+    ))
+}
+
+/// Read `stream` through the `tokio` [`AsyncRead`] interface
+pub fn into_reader(stream: ObjectStream) -> impl AsyncRead + Send + Unpin {
+    StreamReader::new(stream.map_err(std::io::Error::other))
+}
+
+/// Buffer all of `stream` into memory
+pub async fn collect(mut stream: ObjectStream) -> anyhow::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        data.extend_from_slice(&chunk?);
+    }
+    Ok(data)
+}
+
+/// Keys, or other listing entries, fetched a page at a time
+///
+/// `next_page` turns a cursor into the next page of entries and the cursor
+/// for the page after it, or `None` once the listing is done. Pages are only
+/// fetched as the stream is polled.
+pub fn pages<'a, T, C, F, Fut>(cursor: C, next_page: F) -> BoxStream<'a, anyhow::Result<T>>
+where
+    T: Send + 'a,
+    C: Send + 'a,
+    F: FnMut(C) -> Fut + Send + 'a,
+    Fut: Future<Output = anyhow::Result<Option<(Vec<T>, C)>>> + Send + 'a,
+{
+    Box::pin(
+        futures::stream::try_unfold(cursor, next_page)
+            .map_ok(|entries| futures::stream::iter(entries.into_iter().map(Ok)))
+            .try_flatten(),
+    )
+}
+
+/// Pulls fixed-size parts out of a stream for multipart uploads
+///
+/// Chunks are joined or split as needed, so at most one part is buffered.
+pub(crate) struct PartReader {
+    stream: ObjectStream,
+    pending: BytesMut,
+    part_size: usize,
+    done: bool,
+}
+
+impl PartReader {
+    pub(crate) fn new(stream: ObjectStream, part_size: usize) -> Self {
+        Self {
+            stream,
+            pending: BytesMut::new(),
+            part_size,
+            done: false,
+        }
+    }
+
+    /// The next `part_size` bytes, fewer at the end, or `None` once drained
+    pub(crate) async fn next_part(&mut self) -> anyhow::Result<Option<Bytes>> {
+        while !self.done && self.pending.len() < self.part_size {
+            match self.stream.next().await {
+                Some(chunk) => self.pending.extend_from_slice(&chunk?),
+                None => self.done = true,
+            }
+        }
+        if self.pending.is_empty() {
+            return Ok(None);
+        }
+        let len = self.pending.len().min(self.part_size);
+        Ok(Some(self.pending.split_to(len).freeze()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunks(parts: &[&'static [u8]]) -> ObjectStream {
+        let parts: Vec<anyhow::Result<Bytes>> =
+            parts.iter().map(|p| Ok(Bytes::from_static(p))).collect();
+        Box::pin(futures::stream::iter(parts))
+    }
+
+    #[tokio::test]
+    async fn test_reader_round_trip() {
+        let data: Vec<u8> = (0..3 * STREAM_CHUNK_SIZE + 17)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let stream = from_reader(std::io::Cursor::new(data.clone()));
+
+        let mut reader = into_reader(stream);
+        let mut read = Vec::new();
+        reader.read_to_end(&mut read).await.unwrap();
+        assert_eq!(read, data);
+    }
+
+    #[tokio::test]
+    async fn test_part_reader_rechunks() {
+        let mut parts = PartReader::new(chunks(&[b"ab", b"cdefg", b"", b"h"]), 3);
+        let mut read = Vec::new();
+        while let Some(part) = parts.next_part().await.unwrap() {
+            read.push(part);
+        }
+        assert_eq!(read, vec!["abc", "def", "gh"]);
+
+        let mut empty = PartReader::new(chunks(&[]), 3);
+        assert!(empty.next_part().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pages_flatten_in_order() {
+        let listing: Vec<Vec<&str>> = vec![vec!["a", "b"], vec![], vec!["c"]];
+        let keys = pages(0, |page| {
+            let keys = listing.get(page).map(|keys| {
+                let keys = keys.iter().map(|key| key.to_string()).collect();
+                (keys, page + 1)
+            });
+            async move { Ok(keys) }
+        });
+        let keys: Vec<String> = keys.try_collect().await.unwrap();
+        assert_eq!(keys, ["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,596 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Local disk in front of a cloud backend
+//!
+//! [`TieredBackend`] keeps a working set of objects on a [`LocalBackend`]
+//! (the hot tier) and the whole repository on a remote backend (the cold
+//! tier). Reads are served from local disk when possible, and objects fetched
+//! from the cold tier are copied down, so checking out the same files again
+//! doesn't download them again. Writes go to both tiers, either before `put`
+//! returns ([`WriteMode::WriteThrough`]) or to disk first with the upload
+//! finishing in the background ([`WriteMode::WriteBack`]).
+//!
+//! The hot tier only holds copies. [`TieredBackend::evict`] trims it by age
+//! and total size, least recently used first, and never removes an object the
+//! cold tier doesn't have yet.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::tiered::EvictionPolicy;
+//! use mediagit_storage::{mock::MockBackend, LocalBackend, StorageBackend, TieredBackend};
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! # let dir = tempfile::tempdir()?;
+//! let hot = Arc::new(LocalBackend::new(dir.path()).await?);
+//! let cloud = Arc::new(MockBackend::new());
+//! let storage = TieredBackend::new(hot, cloud.clone()).with_eviction(EvictionPolicy {
+//!     max_bytes: Some(50 * 1024 * 1024 * 1024),
+//!     max_age: None,
+//! });
+//!
+//! storage.put("abc123", b"data").await?;
+//! assert!(cloud.exists("abc123").await?);
+//!
+//! let report = storage.evict().await?;
+//! assert_eq!(report.evicted, 0);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    BackendCapabilities, LocalBackend, MmapOrVec, ObjectMeta, ObjectStream, StorageBackend,
+};
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+
+/// When writes reach the cold tier
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WriteMode {
+    /// `put` returns once both tiers hold the object
+    #[default]
+    WriteThrough,
+    /// `put` returns once the hot tier holds the object; the upload runs in
+    /// the background until [`TieredBackend::flush`] waits for it
+    WriteBack,
+}
+
+/// Limits on what the hot tier keeps, applied by [`TieredBackend::evict`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvictionPolicy {
+    /// Evict objects that haven't been read or written for this long
+    pub max_age: Option<Duration>,
+
+    /// Evict least recently used objects until the hot tier holds at most
+    /// this many bytes
+    pub max_bytes: Option<u64>,
+}
+
+/// What one [`TieredBackend::evict`] pass did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvictionReport {
+    /// Objects removed from the hot tier
+    pub evicted: usize,
+    /// Bytes those objects took up
+    pub freed_bytes: u64,
+    /// Objects the policy would have removed but that the cold tier doesn't
+    /// hold yet
+    pub kept_unsynced: usize,
+    /// Bytes left in the hot tier
+    pub retained_bytes: u64,
+}
+
+/// Storage backend that serves reads from local disk and keeps everything
+/// in a cold backend
+///
+/// Clones share pending uploads and access times. In
+/// [`WriteMode::WriteBack`], call [`flush`](Self::flush) before the last
+/// clone is dropped; uploads still running at that point are abandoned and
+/// the objects stay on the hot tier only.
+#[derive(Debug, Clone)]
+pub struct TieredBackend {
+    hot: Arc<LocalBackend>,
+    cold: Arc<dyn StorageBackend>,
+    write_mode: WriteMode,
+    policy: EvictionPolicy,
+    state: Arc<TierState>,
+}
+
+#[derive(Debug, Default)]
+struct TierState {
+    /// Last read or write through this backend, which is more recent than
+    /// the file's modification time when an object is only being read
+    accessed: Mutex<HashMap<String, SystemTime>>,
+    /// Keys on the hot tier whose upload to the cold tier hasn't succeeded
+    pending: Mutex<HashSet<String>>,
+    /// Background uploads in flight
+    uploads: Mutex<JoinSet<()>>,
+}
+
+impl TierState {
+    fn touch(&self, key: &str) {
+        self.accessed
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), SystemTime::now());
+    }
+
+    fn is_pending(&self, key: &str) -> bool {
+        self.pending.lock().unwrap().contains(key)
+    }
+}
+
+impl TieredBackend {
+    /// Serve `cold` through `hot`, writing through and never evicting
+    pub fn new(hot: Arc<LocalBackend>, cold: Arc<dyn StorageBackend>) -> Self {
+        Self {
+            hot,
+            cold,
+            write_mode: WriteMode::default(),
+            policy: EvictionPolicy::default(),
+            state: Arc::default(),
+        }
+    }
+
+    /// Set when writes reach the cold tier
+    pub fn with_write_mode(mut self, write_mode: WriteMode) -> Self {
+        self.write_mode = write_mode;
+        self
+    }
+
+    /// Set the limits [`evict`](Self::evict) applies to the hot tier
+    pub fn with_eviction(mut self, policy: EvictionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The local backend serving reads
+    pub fn hot(&self) -> &Arc<LocalBackend> {
+        &self.hot
+    }
+
+    /// The backend holding every object
+    pub fn cold(&self) -> &Arc<dyn StorageBackend> {
+        &self.cold
+    }
+
+    /// Keys written to the hot tier that haven't reached the cold tier yet
+    pub fn pending_uploads(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.state.pending.lock().unwrap().iter().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    /// Wait for background uploads, then retry any that failed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming how many objects still aren't on the cold
+    /// tier; they stay pending and are retried by the next flush.
+    pub async fn flush(&self) -> anyhow::Result<()> {
+        let mut uploads = std::mem::take(&mut *self.state.uploads.lock().unwrap());
+        while uploads.join_next().await.is_some() {}
+
+        let mut failed = 0;
+        for key in self.pending_uploads() {
+            match upload_from_hot(&self.hot, &self.cold, &key).await {
+                Ok(()) => {
+                    self.state.pending.lock().unwrap().remove(&key);
+                }
+                Err(e) => {
+                    warn!("Upload of {} to the cold tier failed again: {:#}", key, e);
+                    failed += 1;
+                }
+            }
+        }
+        if failed > 0 {
+            anyhow::bail!("{} objects could not be uploaded to the cold tier", failed);
+        }
+        Ok(())
+    }
+
+    /// Remove objects from the hot tier according to the eviction policy
+    ///
+    /// Objects past `max_age` go first, then the least recently used until
+    /// the tier fits in `max_bytes`. Evicted objects remain readable; the
+    /// next read fetches them from the cold tier again. Objects the cold
+    /// tier doesn't hold are kept whatever the policy says.
+    pub async fn evict(&self) -> anyhow::Result<EvictionReport> {
+        let now = SystemTime::now();
+        let mut entries = Vec::new();
+        let mut total = 0u64;
+
+        let mut keys = self.hot.list_objects("").await?;
+        keys.extend(self.hot.list_objects("packs/").await?);
+        for key in keys {
+            // Objects deleted while we walk the tier simply drop out
+            let Ok(size) = self.hot.get_size(&key).await else {
+                continue;
+            };
+            let written = self.hot.modified(&key).await.ok().flatten();
+            let read = self.state.accessed.lock().unwrap().get(&key).copied();
+            let used = written.max(read).unwrap_or(now);
+            total += size;
+            entries.push((used, key, size));
+        }
+        entries.sort();
+
+        let mut report = EvictionReport::default();
+        for (used, key, size) in entries {
+            let too_old = self
+                .policy
+                .max_age
+                .is_some_and(|max_age| now.duration_since(used).unwrap_or_default() > max_age);
+            let over_budget = self.policy.max_bytes.is_some_and(|max| total > max);
+            if !too_old && !over_budget {
+                // Entries are oldest first, so nothing later qualifies either
+                break;
+            }
+
+            if self.state.is_pending(&key) || !self.cold.exists(&key).await? {
+                report.kept_unsynced += 1;
+                continue;
+            }
+            self.hot.delete(&key).await?;
+            self.state.accessed.lock().unwrap().remove(&key);
+            total -= size;
+            report.evicted += 1;
+            report.freed_bytes += size;
+        }
+        report.retained_bytes = total;
+
+        debug!(
+            "Evicted {} objects ({} bytes) from the hot tier, {} bytes remain",
+            report.evicted, report.freed_bytes, report.retained_bytes
+        );
+        Ok(report)
+    }
+
+    /// Copy `key` down from the cold tier unless the hot tier has it
+    async fn fill(&self, key: &str) -> anyhow::Result<()> {
+        if !self.hot.exists(key).await? {
+            let stream = self.cold.get_stream(key).await?;
+            self.hot.put_stream(key, stream).await?;
+            debug!("Copied {} to the hot tier", key);
+        }
+        self.state.touch(key);
+        Ok(())
+    }
+
+    /// Upload `key` from the hot tier in the background
+    fn upload_later(&self, key: &str) {
+        self.state.pending.lock().unwrap().insert(key.to_string());
+
+        let hot = self.hot.clone();
+        let cold = self.cold.clone();
+        let state = self.state.clone();
+        let key = key.to_string();
+        self.state.uploads.lock().unwrap().spawn(async move {
+            match upload_from_hot(&hot, &cold, &key).await {
+                Ok(()) => {
+                    state.pending.lock().unwrap().remove(&key);
+                }
+                Err(e) => warn!(
+                    "Background upload of {} to the cold tier failed: {:#}",
+                    key, e
+                ),
+            }
+        });
+    }
+}
+
+/// Copy `key` from the hot tier to the cold tier without buffering it
+async fn upload_from_hot(
+    hot: &LocalBackend,
+    cold: &Arc<dyn StorageBackend>,
+    key: &str,
+) -> anyhow::Result<()> {
+    cold.put_stream(key, hot.get_stream(key).await?).await
+}
+
+#[async_trait]
+impl StorageBackend for TieredBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        if self.hot.exists(key).await? {
+            if let Ok(data) = self.hot.get(key).await {
+                self.state.touch(key);
+                return Ok(data);
+            }
+        }
+
+        let data = self.cold.get(key).await?;
+        // A full disk shouldn't fail the read; the copy is only a cache
+        if let Err(e) = self.hot.put(key, &data).await {
+            warn!("Failed to copy {} to the hot tier: {:#}", key, e);
+        }
+        self.state.touch(key);
+        Ok(data)
+    }
+
+    async fn get_mapped(&self, key: &str) -> anyhow::Result<MmapOrVec> {
+        self.fill(key).await?;
+        self.hot.get_mapped(key).await
+    }
+
+    /// Always maps from local disk; streams and ranges are as good as the
+    /// cold tier's, since misses are read from it
+    fn capabilities(&self) -> BackendCapabilities {
+        let cold = self.cold.capabilities();
+        BackendCapabilities {
+            mmap: true,
+            range_reads: cold.range_reads,
+            streaming: cold.streaming,
+            ..BackendCapabilities::default()
+        }
+    }
+
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<SystemTime>> {
+        if self.hot.exists(key).await? {
+            return self.hot.modified(key).await;
+        }
+        self.cold.modified(key).await
+    }
+
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        if self.hot.exists(key).await? {
+            return self.hot.head(key).await;
+        }
+        self.cold.head(key).await
+    }
+
+    /// Reads the range from whichever tier has the object, without copying
+    /// a whole object down for part of it
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        if self.hot.exists(key).await? {
+            self.state.touch(key);
+            return self.hot.get_range(key, offset, len).await;
+        }
+        self.cold.get_range(key, offset, len).await
+    }
+
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        self.fill(key).await?;
+        self.hot.get_stream(key).await
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        match self.write_mode {
+            WriteMode::WriteThrough => {
+                tokio::try_join!(self.hot.put(key, data), self.cold.put(key, data))?;
+            }
+            WriteMode::WriteBack => {
+                self.hot.put(key, data).await?;
+                self.upload_later(key);
+            }
+        }
+        self.state.touch(key);
+        Ok(())
+    }
+
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> anyhow::Result<()> {
+        self.hot.put_stream(key, data).await?;
+        match self.write_mode {
+            WriteMode::WriteThrough => upload_from_hot(&self.hot, &self.cold, key).await?,
+            WriteMode::WriteBack => self.upload_later(key),
+        }
+        self.state.touch(key);
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        Ok(self.hot.exists(key).await? || self.cold.exists(key).await?)
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.state.pending.lock().unwrap().remove(key);
+        self.state.accessed.lock().unwrap().remove(key);
+        tokio::try_join!(self.hot.delete(key), self.cold.delete(key))?;
+        Ok(())
+    }
+
+    /// Keys in either tier, so objects still uploading are included
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let (hot, cold) = tokio::try_join!(
+            self.hot.list_objects(prefix),
+            self.cold.list_objects(prefix)
+        )?;
+        let mut keys: Vec<String> = hot.into_iter().chain(cold).collect();
+        keys.sort();
+        keys.dedup();
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockBackend;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tempfile::TempDir;
+
+    /// Cold tier that can be taken offline
+    #[derive(Debug, Default)]
+    struct FlakyBackend {
+        inner: MockBackend,
+        offline: AtomicBool,
+    }
+
+    impl FlakyBackend {
+        fn check(&self) -> anyhow::Result<()> {
+            if self.offline.load(Ordering::SeqCst) {
+                anyhow::bail!("connection refused");
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl StorageBackend for FlakyBackend {
+        async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+            self.check()?;
+            self.inner.get(key).await
+        }
+
+        async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+            self.check()?;
+            self.inner.put(key, data).await
+        }
+
+        async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+            self.check()?;
+            self.inner.exists(key).await
+        }
+
+        async fn delete(&self, key: &str) -> anyhow::Result<()> {
+            self.check()?;
+            self.inner.delete(key).await
+        }
+
+        async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+            self.check()?;
+            self.inner.list_objects(prefix).await
+        }
+    }
+
+    async fn tiers() -> (TempDir, Arc<LocalBackend>, Arc<FlakyBackend>) {
+        let dir = TempDir::new().unwrap();
+        let hot = Arc::new(LocalBackend::new(dir.path()).await.unwrap());
+        (dir, hot, Arc::new(FlakyBackend::default()))
+    }
+
+    #[tokio::test]
+    async fn test_write_through_reaches_both_tiers() {
+        let (_dir, hot, cold) = tiers().await;
+        let storage = TieredBackend::new(hot.clone(), cold.clone());
+
+        storage.put("objects/a", b"alpha").await.unwrap();
+        assert_eq!(hot.get("objects/a").await.unwrap(), b"alpha");
+        assert_eq!(cold.inner.get("objects/a").await.unwrap(), b"alpha");
+
+        // Served from disk even with the cloud unreachable
+        cold.offline.store(true, Ordering::SeqCst);
+        assert_eq!(storage.get("objects/a").await.unwrap(), b"alpha");
+    }
+
+    #[tokio::test]
+    async fn test_reads_copy_misses_to_hot_tier() {
+        let (_dir, hot, cold) = tiers().await;
+        cold.inner.put("objects/a", b"alpha").await.unwrap();
+        cold.inner.put("objects/b", b"bravo").await.unwrap();
+        let storage = TieredBackend::new(hot.clone(), cold.clone());
+
+        assert_eq!(storage.get("objects/a").await.unwrap(), b"alpha");
+        assert!(hot.exists("objects/a").await.unwrap());
+
+        let stream = storage.get_stream("objects/b").await.unwrap();
+        assert_eq!(crate::stream::collect(stream).await.unwrap(), b"bravo");
+        assert!(hot.exists("objects/b").await.unwrap());
+
+        assert!(storage.get("objects/missing").await.is_err());
+        assert!(!hot.exists("objects/missing").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_write_back_uploads_on_flush() {
+        let (_dir, hot, cold) = tiers().await;
+        let storage =
+            TieredBackend::new(hot.clone(), cold.clone()).with_write_mode(WriteMode::WriteBack);
+
+        cold.offline.store(true, Ordering::SeqCst);
+        storage.put("objects/a", b"alpha").await.unwrap();
+        assert!(storage.flush().await.is_err());
+        assert_eq!(storage.pending_uploads(), vec!["objects/a"]);
+
+        // Not on the cold tier yet, so eviction must keep it
+        let eager = storage.clone().with_eviction(EvictionPolicy {
+            max_bytes: Some(0),
+            max_age: None,
+        });
+        let report = eager.evict().await.unwrap();
+        assert_eq!(report.evicted, 0);
+        assert_eq!(report.kept_unsynced, 1);
+
+        cold.offline.store(false, Ordering::SeqCst);
+        storage.flush().await.unwrap();
+        assert!(storage.pending_uploads().is_empty());
+        assert_eq!(cold.inner.get("objects/a").await.unwrap(), b"alpha");
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used_to_fit_budget() {
+        let (_dir, hot, cold) = tiers().await;
+        let storage = TieredBackend::new(hot.clone(), cold.clone()).with_eviction(EvictionPolicy {
+            max_bytes: Some(10),
+            max_age: None,
+        });
+
+        storage.put("objects/a", b"aaaaa").await.unwrap();
+        storage.put("objects/b", b"bbbbb").await.unwrap();
+        storage.put("objects/c", b"ccccc").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        storage.get("objects/a").await.unwrap();
+
+        let report = storage.evict().await.unwrap();
+        assert_eq!(report.evicted, 1);
+        assert_eq!(report.freed_bytes, 5);
+        assert_eq!(report.retained_bytes, 10);
+        assert!(hot.exists("objects/a").await.unwrap());
+        assert!(!hot.exists("objects/b").await.unwrap());
+        assert!(hot.exists("objects/c").await.unwrap());
+
+        // Still readable, and listed once
+        assert_eq!(storage.get("objects/b").await.unwrap(), b"bbbbb");
+        assert_eq!(
+            storage.list_objects("").await.unwrap(),
+            vec!["objects/a", "objects/b", "objects/c"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_evicts_by_age() {
+        let (_dir, hot, cold) = tiers().await;
+        let storage = TieredBackend::new(hot.clone(), cold.clone()).with_eviction(EvictionPolicy {
+            max_bytes: None,
+            max_age: Some(Duration::from_millis(10)),
+        });
+
+        storage.put("objects/old", b"old").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        storage.put("objects/new", b"new").await.unwrap();
+
+        let report = storage.evict().await.unwrap();
+        assert_eq!(report.evicted, 1);
+        assert!(!hot.exists("objects/old").await.unwrap());
+        assert!(hot.exists("objects/new").await.unwrap());
+        assert!(storage.exists("objects/old").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_delete_removes_from_both_tiers() {
+        let (_dir, hot, cold) = tiers().await;
+        let storage = TieredBackend::new(hot.clone(), cold.clone());
+
+        storage.put("objects/a", b"alpha").await.unwrap();
+        storage.delete("objects/a").await.unwrap();
+        assert!(!hot.exists("objects/a").await.unwrap());
+        assert!(!cold.inner.exists("objects/a").await.unwrap());
+        assert!(!storage.exists("objects/a").await.unwrap());
+    }
+}
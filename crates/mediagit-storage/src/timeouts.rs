@@ -0,0 +1,139 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Network timeouts for the cloud backends
+//!
+//! Establishing a connection should take seconds, while downloading a
+//! multi-gigabyte object can legitimately take hours, so no single overall
+//! timeout fits both. The S3-compatible backends take two instead:
+//!
+//! - the **connect timeout** bounds TCP/TLS connection setup and is applied
+//!   by the HTTP client
+//! - the **read timeout** bounds how long a download may go without receiving
+//!   any data. A stalled transfer fails once it expires, handing over to the
+//!   backend's retry logic, while a slow one that keeps making progress runs
+//!   to completion.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::TimeoutSettings;
+//! use std::time::Duration;
+//!
+//! let timeouts = TimeoutSettings::new(Duration::from_secs(5), Duration::from_secs(120));
+//! assert_eq!(timeouts.read, Duration::from_secs(120));
+//! ```
+
+use crate::{ObjectStream, StorageError};
+use anyhow::{anyhow, Result};
+use aws_sdk_s3::config::timeout::TimeoutConfig;
+use aws_sdk_s3::primitives::ByteStream;
+use std::future::Future;
+use std::time::Duration;
+
+/// Connect and read timeouts for outbound storage requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutSettings {
+    /// Longest time to wait for a connection to be established
+    pub connect: Duration,
+
+    /// Longest time a transfer may go without receiving any data
+    pub read: Duration,
+}
+
+impl Default for TimeoutSettings {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(30),
+            read: Duration::from_secs(30),
+        }
+    }
+}
+
+impl TimeoutSettings {
+    /// Timeouts with the given connect and read limits
+    pub fn new(connect: Duration, read: Duration) -> Self {
+        Self { connect, read }
+    }
+
+    /// AWS SDK timeout configuration carrying the connect timeout
+    ///
+    /// The SDK's own read timeout only covers the wait for response headers,
+    /// which for an upload includes sending the whole body, so it is left
+    /// unset; downloads apply [`TimeoutSettings::read`] themselves.
+    pub(crate) fn s3_timeout_config(&self) -> TimeoutConfig {
+        TimeoutConfig::builder()
+            .connect_timeout(self.connect)
+            .build()
+    }
+}
+
+/// Await `future`, failing if it does not complete within `read_timeout`
+pub(crate) async fn within<T>(
+    read_timeout: Duration,
+    future: impl Future<Output = T>,
+) -> Result<T> {
+    tokio::time::timeout(read_timeout, future)
+        .await
+        .map_err(|_| anyhow!("Transfer stalled: no data received for {:?}", read_timeout))
+}
+
+/// Read a response body, failing if any gap between chunks exceeds `read_timeout`
+///
+/// With the `expected` length from the response's `Content-Length`, a body
+/// that ends early, whether the stream fails or simply stops, is reported as
+/// [`StorageError::Truncated`] rather than handed back as partial data.
+pub(crate) async fn collect_body(
+    body: ByteStream,
+    read_timeout: Duration,
+    expected: Option<u64>,
+) -> Result<Vec<u8>> {
+    crate::stream::collect(body_stream(body, read_timeout, expected)).await
+}
+
+/// Stream a response body with the same stall and length checks as
+/// [`collect_body`], failing on the chunk where a check does
+pub(crate) fn body_stream(
+    body: ByteStream,
+    read_timeout: Duration,
+    expected: Option<u64>,
+) -> ObjectStream {
+    Box::pin(futures::stream::try_unfold(
+        (body, 0u64),
+        move |(mut body, got)| async move {
+            match within(read_timeout, body.next()).await? {
+                Some(Ok(chunk)) => {
+                    let got = got + chunk.len() as u64;
+                    Ok(Some((chunk, (body, got))))
+                }
+                Some(Err(e)) => Err(match expected {
+                    Some(expected) if got < expected => {
+                        StorageError::truncated(expected, got).into()
+                    }
+                    _ => anyhow!("Failed to read object body: {}", e),
+                }),
+                None => match expected {
+                    Some(expected) if got < expected => {
+                        Err(StorageError::truncated(expected, got).into())
+                    }
+                    Some(expected) if got > expected => Err(anyhow!(
+                        "Object body is {} bytes, but Content-Length is {}",
+                        got,
+                        expected
+                    )),
+                    _ => Ok(None),
+                },
+            }
+        },
+    ))
+}
@@ -0,0 +1,274 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! TLS settings for S3-compatible endpoints
+//!
+//! Self-hosted MinIO often serves a certificate signed by a private CA. A CA
+//! bundle adds that CA to the trusted roots; the system roots stay trusted.
+//! For development against a self-signed endpoint, certificate verification
+//! can be turned off entirely.
+//!
+//! Settings not given explicitly fall back to the environment:
+//!
+//! - `AWS_CA_BUNDLE` for the CA bundle, as the AWS CLI reads it
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::tls::TlsSettings;
+//!
+//! let tls = TlsSettings::with_ca_bundle("/etc/ssl/minio-ca.pem");
+//! assert!(tls.is_customized());
+//! assert!(!TlsSettings::default().is_customized());
+//! ```
+
+use crate::proxy::ProxySettings;
+use anyhow::{Context, Result};
+use aws_sdk_s3::config::http::{HttpRequest, HttpResponse};
+use aws_sdk_s3::config::SharedHttpClient;
+use aws_smithy_http_client::tls::{TlsContext, TrustStore};
+use aws_smithy_runtime_api::client::http::{
+    http_client_fn, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_types::body::SdkBody;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::CertificateDer;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+
+/// Environment variable consulted for the CA bundle
+const CA_BUNDLE_ENV_VAR: &str = "AWS_CA_BUNDLE";
+
+/// TLS configuration for connections to S3-compatible endpoints
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsSettings {
+    /// PEM file of CA certificates to trust in addition to the system roots
+    pub ca_bundle: Option<PathBuf>,
+
+    /// Accept any server certificate without verifying it. Only for
+    /// development: anyone on the network path can read and alter traffic.
+    pub insecure_skip_verify: bool,
+}
+
+impl TlsSettings {
+    /// Trust the CA certificates in the PEM file at `path`
+    pub fn with_ca_bundle(path: impl Into<PathBuf>) -> Self {
+        Self {
+            ca_bundle: Some(path.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Read TLS settings from the environment only
+    pub fn from_env() -> Self {
+        Self::default().or_env()
+    }
+
+    /// Turn certificate verification off (development only)
+    pub fn insecure(mut self) -> Self {
+        self.insecure_skip_verify = true;
+        self
+    }
+
+    /// Fill in anything not set explicitly from the environment
+    pub fn or_env(mut self) -> Self {
+        if self.ca_bundle.is_none() {
+            self.ca_bundle = std::env::var(CA_BUNDLE_ENV_VAR)
+                .ok()
+                .map(|path| path.trim().to_string())
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from);
+        }
+        self
+    }
+
+    /// Whether connections need anything other than the default TLS setup
+    pub fn is_customized(&self) -> bool {
+        self.ca_bundle.is_some() || self.insecure_skip_verify
+    }
+
+    /// TLS context trusting the system roots plus the CA bundle, if one is set
+    pub(crate) fn tls_context(&self) -> Result<Option<TlsContext>> {
+        let Some(path) = &self.ca_bundle else {
+            return Ok(None);
+        };
+        let pem = std::fs::read(path)
+            .with_context(|| format!("Failed to read CA bundle '{}'", path.display()))?;
+        check_ca_bundle(&pem).with_context(|| format!("Invalid CA bundle '{}'", path.display()))?;
+        let trust_store = TrustStore::default().with_pem_certificate(pem);
+        let context = TlsContext::builder()
+            .with_trust_store(trust_store)
+            .build()
+            .map_err(|e| anyhow::anyhow!("Invalid CA bundle '{}': {}", path.display(), e))?;
+        Ok(Some(context))
+    }
+}
+
+/// Check that `pem` holds at least one certificate and that every one of
+/// them can be used as a root; the SDK only parses the bundle once it
+/// connects, and panics if it can't
+fn check_ca_bundle(pem: &[u8]) -> Result<()> {
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in CertificateDer::pem_slice_iter(pem) {
+        roots.add(cert?)?;
+    }
+    anyhow::ensure!(!roots.is_empty(), "no certificates found");
+    Ok(())
+}
+
+/// Connect and read timeouts a connector was built for
+type Timeouts = (Option<Duration>, Option<Duration>);
+
+/// AWS SDK HTTP client that doesn't verify server certificates, routed
+/// through `proxy` if it is enabled
+///
+/// The SDK's own client can't turn verification off, so requests go through
+/// reqwest instead. One reqwest client, and so one connection pool, is kept
+/// for each combination of timeouts the SDK asks for.
+pub(crate) fn insecure_http_client(proxy: &ProxySettings) -> Result<SharedHttpClient> {
+    warn!("TLS certificate verification is disabled for S3 connections");
+
+    let proxy = match &proxy.url {
+        Some(url) => {
+            let mut rule = reqwest::Proxy::all(url.as_str())
+                .map_err(|e| anyhow::anyhow!("Invalid proxy URL '{}': {}", url, e))?;
+            if let Some(username) = &proxy.username {
+                rule = rule.basic_auth(username, proxy.password.as_deref().unwrap_or(""));
+            }
+            Some(rule.no_proxy(reqwest::NoProxy::from_string(&proxy.no_proxy_rules())))
+        }
+        None => None,
+    };
+
+    let clients: Mutex<HashMap<Timeouts, SharedHttpConnector>> = Mutex::default();
+    Ok(http_client_fn(move |settings, _components| {
+        let timeouts = (settings.connect_timeout(), settings.read_timeout());
+        clients
+            .lock()
+            .unwrap()
+            .entry(timeouts)
+            .or_insert_with(|| {
+                SharedHttpConnector::new(InsecureConnector {
+                    client: insecure_reqwest_client(proxy.clone(), settings)
+                        .map_err(|e| e.to_string()),
+                })
+            })
+            .clone()
+    }))
+}
+
+/// reqwest client that accepts any certificate, with the SDK's timeouts
+fn insecure_reqwest_client(
+    proxy: Option<reqwest::Proxy>,
+    settings: &HttpConnectorSettings,
+) -> reqwest::Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().danger_accept_invalid_certs(true);
+    builder = match proxy {
+        Some(proxy) => builder.proxy(proxy),
+        None => builder.no_proxy(),
+    };
+    if let Some(timeout) = settings.connect_timeout() {
+        builder = builder.connect_timeout(timeout);
+    }
+    if let Some(timeout) = settings.read_timeout() {
+        builder = builder.read_timeout(timeout);
+    }
+    builder.build()
+}
+
+/// Sends the SDK's requests with a reqwest client
+#[derive(Debug)]
+struct InsecureConnector {
+    /// The client, or why it couldn't be built
+    client: std::result::Result<reqwest::Client, String>,
+}
+
+impl HttpConnector for InsecureConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let client = self.client.clone();
+        HttpConnectorFuture::new(async move {
+            let client = client.map_err(|e| ConnectorError::other(e.into(), None))?;
+            let request = request
+                .try_into_http1x()
+                .map_err(|e| ConnectorError::other(e.into(), None))?
+                .map(reqwest::Body::wrap);
+            let request = reqwest::Request::try_from(request)
+                .map_err(|e| ConnectorError::other(e.into(), None))?;
+
+            let response = client.execute(request).await.map_err(|e| {
+                if e.is_timeout() {
+                    ConnectorError::timeout(e.into())
+                } else if e.is_connect() || e.is_request() || e.is_body() {
+                    ConnectorError::io(e.into())
+                } else {
+                    ConnectorError::other(e.into(), None)
+                }
+            })?;
+            let response = http::Response::from(response).map(SdkBody::from_body_1_x);
+            HttpResponse::try_from(response).map_err(|e| ConnectorError::other(e.into(), None))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tls_settings_builder() {
+        let tls = TlsSettings::with_ca_bundle("/etc/ssl/minio-ca.pem");
+        assert_eq!(tls.ca_bundle, Some(PathBuf::from("/etc/ssl/minio-ca.pem")));
+        assert!(!tls.insecure_skip_verify);
+        assert!(tls.is_customized());
+
+        let tls = TlsSettings::default().insecure();
+        assert!(tls.insecure_skip_verify);
+        assert!(tls.is_customized());
+        assert!(!TlsSettings::default().is_customized());
+    }
+
+    #[test]
+    fn test_ca_bundle_is_loaded() {
+        assert!(TlsSettings::default().tls_context().unwrap().is_none());
+
+        let missing = TlsSettings::with_ca_bundle("/nonexistent/ca.pem");
+        let err = missing.tls_context().unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/ca.pem"));
+
+        // Rejected up front rather than when the first request connects
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("ca.pem");
+        std::fs::write(&path, "not a certificate\n").unwrap();
+        let err = TlsSettings::with_ca_bundle(&path)
+            .tls_context()
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("no certificates found"));
+
+        std::fs::write(
+            &path,
+            "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n",
+        )
+        .unwrap();
+        assert!(TlsSettings::with_ca_bundle(&path).tls_context().is_err());
+    }
+
+    #[test]
+    fn test_insecure_client_rejects_invalid_proxy() {
+        assert!(insecure_http_client(&ProxySettings::default()).is_ok());
+        assert!(insecure_http_client(&ProxySettings::new("not a url")).is_err());
+    }
+}
@@ -0,0 +1,442 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Bounded pool for uploads
+//!
+//! Pushing a large file produces thousands of chunk objects, and starting a
+//! put for each of them at once opens as many connections and keeps every
+//! chunk in memory until its put completes. An [`UploadPool`] bounds both:
+//! an upload needs one of `max_concurrent` slots and a share of
+//! `max_in_flight_bytes` for its size before it starts, and callers that
+//! can't get them wait. Producers that read or compress data only once
+//! they hold a permit therefore slow down to the pace of the uploads.
+//!
+//! The ODB writes through a [`PooledUploadBackend`], the protocol client
+//! runs its chunk uploads in the pool, and the migration tool copies objects
+//! through it. Clones of a pool, and every caller of [`shared_upload_pool`],
+//! share the same limits.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::upload::{UploadLimits, UploadPool};
+//! use mediagit_storage::{mock::MockBackend, StorageBackend};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let pool = UploadPool::new(UploadLimits::new(4, 64 * 1024 * 1024));
+//! let bucket = MockBackend::new();
+//!
+//! pool.put(&bucket, "chunks/ab12", b"data").await?;
+//! let uploaded = pool
+//!     .run(4, async { bucket.put("chunks/cd34", b"more").await })
+//!     .await;
+//! assert!(uploaded.is_ok());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    BackendCapabilities, DeleteFailure, ExpiryPolicy, KeyStream, MmapOrVec, ObjectMeta,
+    ObjectStream, StorageBackend, StorageUsage, SweepReport,
+};
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::debug;
+
+/// Bytes per permit of the in-flight byte budget
+const BYTES_PER_UNIT: u64 = 1024;
+
+static SHARED_POOL: OnceLock<UploadPool> = OnceLock::new();
+
+/// The process-wide upload pool, created with `limits`
+///
+/// The first call decides the limits; later calls return the same pool
+/// whatever they pass, so every upload in the process shares it.
+pub fn shared_upload_pool(limits: UploadLimits) -> UploadPool {
+    let pool = SHARED_POOL.get_or_init(|| UploadPool::new(limits));
+    if pool.limits() != limits {
+        debug!(
+            "Upload pool already set to {:?}, ignoring {:?}",
+            pool.limits(),
+            limits
+        );
+    }
+    pool.clone()
+}
+
+/// How much an [`UploadPool`] lets run at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadLimits {
+    /// Uploads in progress at once
+    pub max_concurrent: usize,
+
+    /// Total size of the data of the uploads in progress
+    pub max_in_flight_bytes: u64,
+}
+
+impl UploadLimits {
+    /// Allow `max_concurrent` uploads of at most `max_in_flight_bytes` in all
+    ///
+    /// Zero for either limit is treated as one.
+    pub fn new(max_concurrent: usize, max_in_flight_bytes: u64) -> Self {
+        Self {
+            max_concurrent: max_concurrent.max(1),
+            max_in_flight_bytes: max_in_flight_bytes.max(1),
+        }
+    }
+}
+
+impl Default for UploadLimits {
+    /// 16 uploads and 256 MB in flight
+    fn default() -> Self {
+        Self::new(16, 256 * 1024 * 1024)
+    }
+}
+
+/// Shared slots and byte budget for uploads
+#[derive(Debug, Clone)]
+pub struct UploadPool {
+    limits: UploadLimits,
+    slots: Arc<Semaphore>,
+    bytes: Arc<Semaphore>,
+    /// Size of `bytes` in permits
+    byte_units: u32,
+}
+
+impl UploadPool {
+    /// New pool enforcing `limits`
+    pub fn new(limits: UploadLimits) -> Self {
+        let limits = UploadLimits::new(limits.max_concurrent, limits.max_in_flight_bytes);
+        let byte_units = limits
+            .max_in_flight_bytes
+            .div_ceil(BYTES_PER_UNIT)
+            .min(u64::from(u32::MAX)) as u32;
+        Self {
+            limits,
+            slots: Arc::new(Semaphore::new(limits.max_concurrent)),
+            bytes: Arc::new(Semaphore::new(byte_units as usize)),
+            byte_units,
+        }
+    }
+
+    /// The limits the pool enforces
+    pub fn limits(&self) -> UploadLimits {
+        self.limits
+    }
+
+    /// Uploads currently holding a permit
+    pub fn in_flight(&self) -> usize {
+        self.limits.max_concurrent - self.slots.available_permits()
+    }
+
+    /// Wait for a slot and `bytes` of the byte budget
+    ///
+    /// An upload larger than the whole budget takes all of it, so it runs
+    /// once everything before it has finished rather than never. Both are
+    /// returned when the permit is dropped.
+    pub async fn acquire(&self, bytes: u64) -> anyhow::Result<UploadPermit> {
+        let units = bytes
+            .div_ceil(BYTES_PER_UNIT)
+            .min(u64::from(self.byte_units)) as u32;
+        let bytes = self
+            .bytes
+            .clone()
+            .acquire_many_owned(units)
+            .await
+            .map_err(|_| anyhow::anyhow!("upload pool was closed"))?;
+        let slot = self
+            .slots
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| anyhow::anyhow!("upload pool was closed"))?;
+        Ok(UploadPermit {
+            _slot: slot,
+            _bytes: bytes,
+        })
+    }
+
+    /// Run `upload`, which sends `bytes` of data, once the pool has room
+    pub async fn run<T, F>(&self, bytes: u64, upload: F) -> anyhow::Result<T>
+    where
+        F: Future<Output = anyhow::Result<T>>,
+    {
+        let _permit = self.acquire(bytes).await?;
+        upload.await
+    }
+
+    /// Put `data` under `key` in `storage` once the pool has room
+    pub async fn put(
+        &self,
+        storage: &dyn StorageBackend,
+        key: &str,
+        data: &[u8],
+    ) -> anyhow::Result<()> {
+        self.run(data.len() as u64, storage.put(key, data)).await
+    }
+}
+
+/// A slot and share of the byte budget of an [`UploadPool`], returned on drop
+#[derive(Debug)]
+pub struct UploadPermit {
+    _slot: OwnedSemaphorePermit,
+    _bytes: OwnedSemaphorePermit,
+}
+
+/// Storage backend wrapper that runs every write in an [`UploadPool`]
+///
+/// `put_stream` doesn't know its size up front, so it only takes a slot.
+/// Reads, deletes and listings pass straight through.
+#[derive(Debug, Clone)]
+pub struct PooledUploadBackend {
+    inner: Arc<dyn StorageBackend>,
+    pool: UploadPool,
+}
+
+impl PooledUploadBackend {
+    /// Wrap `inner` so its writes go through `pool`
+    pub fn new(inner: Arc<dyn StorageBackend>, pool: UploadPool) -> Self {
+        Self { inner, pool }
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Arc<dyn StorageBackend> {
+        &self.inner
+    }
+
+    /// The pool writes run in
+    pub fn pool(&self) -> &UploadPool {
+        &self.pool
+    }
+}
+
+#[async_trait]
+impl StorageBackend for PooledUploadBackend {
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.inner.get(key).await
+    }
+
+    async fn get_mapped(&self, key: &str) -> anyhow::Result<MmapOrVec> {
+        self.inner.get_mapped(key).await
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn modified(&self, key: &str) -> anyhow::Result<Option<std::time::SystemTime>> {
+        self.inner.modified(key).await
+    }
+
+    async fn head(&self, key: &str) -> anyhow::Result<ObjectMeta> {
+        self.inner.head(key).await
+    }
+
+    async fn get_range(&self, key: &str, offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        self.inner.get_range(key, offset, len).await
+    }
+
+    async fn get_stream(&self, key: &str) -> anyhow::Result<ObjectStream> {
+        self.inner.get_stream(key).await
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        self.pool.put(self.inner.as_ref(), key, data).await
+    }
+
+    async fn put_if_absent(&self, key: &str, data: &[u8]) -> anyhow::Result<bool> {
+        self.pool
+            .run(data.len() as u64, self.inner.put_if_absent(key, data))
+            .await
+    }
+
+    async fn put_if_match(&self, key: &str, data: &[u8], etag: &str) -> anyhow::Result<bool> {
+        self.pool
+            .run(data.len() as u64, self.inner.put_if_match(key, data, etag))
+            .await
+    }
+
+    async fn copy(&self, src_key: &str, dst_key: &str) -> anyhow::Result<()> {
+        self.inner.copy(src_key, dst_key).await
+    }
+
+    async fn put_stream(&self, key: &str, data: ObjectStream) -> anyhow::Result<()> {
+        self.pool.run(0, self.inner.put_stream(key, data)).await
+    }
+
+    async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+        self.inner.exists(key).await
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.inner.delete(key).await
+    }
+
+    async fn delete_many(&self, keys: &[String]) -> anyhow::Result<Vec<DeleteFailure>> {
+        self.inner.delete_many(keys).await
+    }
+
+    async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        self.inner.list_objects(prefix).await
+    }
+
+    async fn list_prefixes(&self, prefix: &str, delimiter: &str) -> anyhow::Result<Vec<String>> {
+        self.inner.list_prefixes(prefix, delimiter).await
+    }
+
+    async fn usage(&self, prefix: &str) -> anyhow::Result<StorageUsage> {
+        self.inner.usage(prefix).await
+    }
+
+    async fn apply_expiry(&self, policy: &ExpiryPolicy) -> anyhow::Result<bool> {
+        self.inner.apply_expiry(policy).await
+    }
+
+    async fn sweep_expired(&self, policy: &ExpiryPolicy) -> anyhow::Result<SweepReport> {
+        self.inner.sweep_expired(policy).await
+    }
+
+    fn list_objects_stream<'a>(&'a self, prefix: &str) -> KeyStream<'a> {
+        self.inner.list_objects_stream(prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockBackend;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Backend whose puts take a while and record peak concurrency and bytes
+    #[derive(Debug, Default)]
+    struct SlowBackend {
+        inner: MockBackend,
+        running: AtomicUsize,
+        peak: AtomicUsize,
+        bytes: AtomicU64,
+        peak_bytes: AtomicU64,
+    }
+
+    #[async_trait]
+    impl StorageBackend for SlowBackend {
+        async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+            self.inner.get(key).await
+        }
+
+        async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+            let size = data.len() as u64;
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            let bytes = self.bytes.fetch_add(size, Ordering::SeqCst) + size;
+            self.peak_bytes.fetch_max(bytes, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let result = self.inner.put(key, data).await;
+            self.bytes.fetch_sub(size, Ordering::SeqCst);
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            result
+        }
+
+        async fn exists(&self, key: &str) -> anyhow::Result<bool> {
+            self.inner.exists(key).await
+        }
+
+        async fn delete(&self, key: &str) -> anyhow::Result<()> {
+            self.inner.delete(key).await
+        }
+
+        async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+            self.inner.list_objects(prefix).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_uploads_are_capped() {
+        let slow = Arc::new(SlowBackend::default());
+        let pool = UploadPool::new(UploadLimits::new(4, 1024 * 1024));
+        let storage = Arc::new(PooledUploadBackend::new(slow.clone(), pool.clone()));
+
+        let mut tasks = Vec::new();
+        for i in 0..40 {
+            let storage = storage.clone();
+            tasks.push(tokio::spawn(async move {
+                storage.put(&format!("chunks/{}", i), b"data").await
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert_eq!(slow.peak.load(Ordering::SeqCst), 4);
+        assert_eq!(pool.in_flight(), 0);
+        assert_eq!(slow.inner.list_objects("chunks/").await.unwrap().len(), 40);
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_bytes_are_capped() {
+        let slow = Arc::new(SlowBackend::default());
+        // Room for 16 uploads, but only for three 64 KB chunks
+        let pool = UploadPool::new(UploadLimits::new(16, 3 * 64 * 1024));
+
+        let mut tasks = Vec::new();
+        for i in 0..12 {
+            let slow = slow.clone();
+            let pool = pool.clone();
+            tasks.push(tokio::spawn(async move {
+                let chunk = vec![i as u8; 64 * 1024];
+                pool.put(slow.as_ref(), &format!("chunks/{}", i), &chunk)
+                    .await
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert_eq!(slow.peak.load(Ordering::SeqCst), 3);
+        assert_eq!(slow.peak_bytes.load(Ordering::SeqCst), 3 * 64 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_upload_runs_alone() {
+        let pool = UploadPool::new(UploadLimits::new(4, 1024));
+        let storage = PooledUploadBackend::new(Arc::new(MockBackend::new()), pool.clone());
+
+        // Larger than the whole budget: takes all of it instead of waiting forever
+        storage.put("packs/big", &vec![0; 10_000]).await.unwrap();
+
+        let permit = pool.acquire(10_000).await.unwrap();
+        assert_eq!(pool.in_flight(), 1);
+        let blocked = tokio::time::timeout(Duration::from_millis(20), pool.acquire(1)).await;
+        assert!(blocked.is_err());
+        drop(permit);
+        assert!(pool.acquire(1).await.is_ok());
+    }
+
+    #[test]
+    fn test_shared_upload_pool_is_process_wide() {
+        let first = shared_upload_pool(UploadLimits::default());
+        let second = shared_upload_pool(UploadLimits::new(2, 1024));
+        assert!(Arc::ptr_eq(&first.slots, &second.slots));
+        assert_eq!(second.limits(), first.limits());
+    }
+
+    #[test]
+    fn test_zero_limits_are_raised_to_one() {
+        let limits = UploadLimits::new(0, 0);
+        assert_eq!(limits.max_concurrent, 1);
+        assert_eq!(limits.max_in_flight_bytes, 1);
+    }
+}
@@ -0,0 +1,73 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Space used under a prefix, as returned by
+//! [`StorageBackend::usage`](crate::StorageBackend::usage)
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_storage::{mock::MockBackend, StorageBackend};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let storage = MockBackend::new();
+//! storage.put("objects/a", b"12345").await?;
+//! storage.put("objects/b", b"678").await?;
+//!
+//! let usage = storage.usage("objects/").await?;
+//! assert_eq!(usage.object_count, 2);
+//! assert_eq!(usage.total_bytes, 8);
+//! # Ok(())
+//! # }
+//! ```
+
+/// How many objects are stored under a prefix and how many bytes they take
+///
+/// Sizes are as stored, so compressed or encrypted objects count at their
+/// stored size rather than their original one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// Number of objects
+    pub object_count: u64,
+
+    /// Combined size of the objects in bytes
+    pub total_bytes: u64,
+}
+
+impl StorageUsage {
+    /// Count one more object of `size` bytes
+    pub fn add(&mut self, size: u64) {
+        self.object_count += 1;
+        self.total_bytes += size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_counts_objects_and_bytes() {
+        let mut usage = StorageUsage::default();
+        usage.add(10);
+        usage.add(0);
+        assert_eq!(
+            usage,
+            StorageUsage {
+                object_count: 2,
+                total_bytes: 10
+            }
+        );
+    }
+}
@@ -34,7 +34,10 @@
 
 #[cfg(test)]
 mod azure_azurite_tests {
-    use mediagit_storage::{azure::AzureBackend, StorageBackend};
+    use mediagit_storage::{
+        azure::{AzureBackend, BlockUploadSettings},
+        StorageBackend,
+    };
 
     /// Azurite default connection string
     const AZURITE_CONNECTION_STRING: &str = "DefaultEndpointsProtocol=http;\
@@ -207,6 +210,32 @@ mod azure_azurite_tests {
         backend.delete(key).await.unwrap();
     }
 
+    /// Test staged block uploads with small blocks, from a slice and a stream
+    #[tokio::test]
+    #[ignore] // Requires Azurite
+    async fn test_azurite_staged_block_upload() {
+        let backend = create_test_backend()
+            .await
+            .with_block_uploads(BlockUploadSettings {
+                threshold: 64 * 1024,
+                block_size: 64 * 1024,
+                max_concurrent_blocks: 4,
+                ..BlockUploadSettings::default()
+            });
+
+        let data: Vec<u8> = (0..1024 * 1024 + 123).map(|i| (i % 251) as u8).collect();
+        let key = "test/staged_blocks.bin";
+
+        backend.put(key, &data).await.unwrap();
+        assert_eq!(backend.get(key).await.unwrap(), data);
+
+        let stream = mediagit_storage::stream::from_reader(std::io::Cursor::new(data.clone()));
+        backend.put_stream(key, stream).await.unwrap();
+        assert_eq!(backend.get(key).await.unwrap(), data);
+
+        backend.delete(key).await.unwrap();
+    }
+
     /// Test concurrent PUT operations
     #[tokio::test]
     #[ignore] // Requires Azurite
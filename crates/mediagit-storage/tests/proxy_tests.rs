@@ -0,0 +1,107 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Proxy routing tests for the S3-compatible backend
+//!
+//! A plain TCP listener stands in for both the proxy and the storage
+//! endpoint, recording the request heads it receives and answering every
+//! request with an empty 200 so bucket initialization succeeds.
+
+use mediagit_storage::{MinIOBackend, ProxySettings};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+/// Start a fake HTTP server and return its address and the request heads it saw
+fn spawn_recorder() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let recorded = Arc::clone(&seen);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut head = String::new();
+            let mut content_length = 0usize;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+                head.push_str(&line);
+            }
+            let mut body = vec![0u8; content_length];
+            let _ = reader.read_exact(&mut body);
+
+            recorded.lock().unwrap().push(head);
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        }
+    });
+
+    (addr, seen)
+}
+
+#[tokio::test]
+async fn test_minio_requests_route_through_proxy() {
+    let (proxy_addr, proxy_seen) = spawn_recorder();
+
+    let proxy = ProxySettings::new(format!("http://{}", proxy_addr)).with_auth("bot", "s3cret");
+    MinIOBackend::with_proxy(
+        "http://minio.invalid:9000",
+        "media-bucket",
+        "access",
+        "secret",
+        proxy,
+    )
+    .await
+    .expect("bucket setup should succeed via the proxy");
+
+    let seen = proxy_seen.lock().unwrap();
+    let request = seen.first().expect("proxy received no request");
+    assert!(
+        request.starts_with("PUT http://minio.invalid:9000/media-bucket"),
+        "expected absolute-form request through the proxy, got: {}",
+        request
+    );
+    // "bot:s3cret" in base64
+    assert!(request.contains("Ym90OnMzY3JldA=="));
+}
+
+#[tokio::test]
+async fn test_minio_no_proxy_host_connects_directly() {
+    let (proxy_addr, proxy_seen) = spawn_recorder();
+    let (origin_addr, origin_seen) = spawn_recorder();
+
+    let proxy = ProxySettings::new(format!("http://{}", proxy_addr)).with_no_proxy(["127.0.0.1"]);
+    MinIOBackend::with_proxy(
+        &format!("http://{}", origin_addr),
+        "media-bucket",
+        "access",
+        "secret",
+        proxy,
+    )
+    .await
+    .expect("bucket setup should succeed directly");
+
+    assert!(proxy_seen.lock().unwrap().is_empty());
+    let seen = origin_seen.lock().unwrap();
+    let request = seen.first().expect("origin received no request");
+    assert!(request.starts_with("PUT /media-bucket"), "got: {}", request);
+}
@@ -0,0 +1,131 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Read timeout tests for the S3-compatible backend
+//!
+//! A plain TCP listener stands in for the storage endpoint. It answers bucket
+//! creation with an empty 200 and serves every GET as a 1000-byte body sent
+//! in ten pieces, either pausing briefly between pieces (slow but progressing)
+//! or stopping after the first one (stalled).
+
+use mediagit_storage::minio::MinIOConfig;
+use mediagit_storage::{MinIOBackend, StorageBackend, TimeoutSettings};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+const READ_TIMEOUT: Duration = Duration::from_millis(500);
+const PIECE: [u8; 100] = [7u8; 100];
+
+/// Start a fake endpoint whose downloads pause `gap` between pieces, or
+/// never send more than the first piece if `gap` is None
+fn spawn_endpoint(gap: Option<Duration>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { break };
+            std::thread::spawn(move || serve(stream, gap));
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+fn serve(mut stream: TcpStream, gap: Option<Duration>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    let mut content_length = 0usize;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+            break;
+        }
+        if request_line.is_empty() {
+            request_line = line.clone();
+        }
+        if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut body = vec![0u8; content_length];
+    let _ = reader.read_exact(&mut body);
+
+    if !request_line.starts_with("GET") {
+        let _ =
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        return;
+    }
+
+    let _ = stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: 1000\r\nConnection: close\r\n\r\n",
+    );
+    let _ = stream.write_all(&PIECE);
+    let _ = stream.flush();
+    match gap {
+        Some(gap) => {
+            for _ in 1..10 {
+                std::thread::sleep(gap);
+                let _ = stream.write_all(&PIECE);
+                let _ = stream.flush();
+            }
+        }
+        // Hold the connection open without sending anything more
+        None => std::thread::sleep(Duration::from_secs(30)),
+    }
+}
+
+async fn backend(endpoint: &str) -> MinIOBackend {
+    let config = MinIOConfig {
+        timeouts: TimeoutSettings::new(Duration::from_secs(5), READ_TIMEOUT),
+        max_retries: 1,
+        ..MinIOConfig::new(endpoint, "media-bucket", "access", "secret").unwrap()
+    };
+    MinIOBackend::with_config(config)
+        .await
+        .expect("bucket setup should succeed")
+}
+
+#[tokio::test]
+async fn test_stalled_download_aborts_at_read_timeout() {
+    let storage = backend(&spawn_endpoint(None)).await;
+
+    let start = Instant::now();
+    let err = storage.get("renders/shot_010.exr").await.unwrap_err();
+    let elapsed = start.elapsed();
+
+    assert!(
+        format!("{:#}", err).contains("Transfer stalled"),
+        "unexpected error: {:#}",
+        err
+    );
+    assert!(elapsed >= READ_TIMEOUT, "aborted early after {:?}", elapsed);
+    assert!(
+        elapsed < Duration::from_secs(5),
+        "took {:?} to abort",
+        elapsed
+    );
+}
+
+#[tokio::test]
+async fn test_slow_but_progressing_download_completes() {
+    // Each gap is under the read timeout, the whole transfer well over it
+    let storage = backend(&spawn_endpoint(Some(Duration::from_millis(200)))).await;
+
+    let start = Instant::now();
+    let data = storage.get("renders/shot_010.exr").await.unwrap();
+
+    assert_eq!(data, [7u8; 1000]);
+    assert!(start.elapsed() > READ_TIMEOUT);
+}
@@ -0,0 +1,129 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Truncated download tests for the S3-compatible backend
+//!
+//! A plain TCP listener stands in for the storage endpoint. It answers bucket
+//! creation with an empty 200 and serves GETs as a 1000-byte object, cutting
+//! the connection after 600 bytes for the first few requests.
+
+use mediagit_storage::minio::MinIOConfig;
+use mediagit_storage::{MinIOBackend, StorageBackend, StorageError};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const OBJECT: [u8; 1000] = [42u8; 1000];
+const TRUNCATED_AT: usize = 600;
+
+/// Start a fake endpoint whose first `truncated` GETs end early, returning
+/// its URL and the number of GETs served
+fn spawn_endpoint(truncated: usize) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let gets = Arc::new(AtomicUsize::new(0));
+
+    let served = Arc::clone(&gets);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { break };
+            serve(stream, &served, truncated);
+        }
+    });
+
+    (format!("http://{}", addr), gets)
+}
+
+fn serve(mut stream: TcpStream, gets: &AtomicUsize, truncated: usize) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    let mut content_length = 0usize;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+            break;
+        }
+        if request_line.is_empty() {
+            request_line = line.clone();
+        }
+        if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut body = vec![0u8; content_length];
+    let _ = reader.read_exact(&mut body);
+
+    if !request_line.starts_with("GET") {
+        let _ =
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        return;
+    }
+
+    let sent = if gets.fetch_add(1, Ordering::SeqCst) < truncated {
+        TRUNCATED_AT
+    } else {
+        OBJECT.len()
+    };
+    let _ = stream.write_all(
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            OBJECT.len()
+        )
+        .as_bytes(),
+    );
+    let _ = stream.write_all(&OBJECT[..sent]);
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+}
+
+async fn backend(endpoint: &str, max_retries: u32) -> MinIOBackend {
+    let config = MinIOConfig {
+        max_retries,
+        ..MinIOConfig::new(endpoint, "media-bucket", "access", "secret").unwrap()
+    };
+    MinIOBackend::with_config(config)
+        .await
+        .expect("bucket setup should succeed")
+}
+
+#[tokio::test]
+async fn test_truncated_download_is_retried() {
+    let (endpoint, gets) = spawn_endpoint(1);
+    let storage = backend(&endpoint, 3).await;
+
+    let data = storage.get("renders/shot_010.exr").await.unwrap();
+
+    assert_eq!(data, OBJECT);
+    assert_eq!(gets.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_persistent_truncation_is_reported() {
+    let (endpoint, gets) = spawn_endpoint(usize::MAX);
+    let storage = backend(&endpoint, 2).await;
+
+    let err = storage.get("renders/shot_010.exr").await.unwrap_err();
+
+    assert!(
+        matches!(
+            err.downcast_ref::<StorageError>(),
+            Some(StorageError::Truncated {
+                expected: 1000,
+                got: 600
+            })
+        ),
+        "unexpected error: {:#}",
+        err
+    );
+    assert_eq!(gets.load(Ordering::SeqCst), 2);
+}
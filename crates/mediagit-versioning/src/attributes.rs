@@ -0,0 +1,647 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! `.mediagitattributes` parsing and line-ending normalization.
+//!
+//! Mirrors Git's `text` / `eol` attributes so text assets (scripts, configs,
+//! SVG) are stored with LF line endings and restored with the platform's (or
+//! the path's configured) line endings on checkout. Binary media is never
+//! touched: only paths explicitly marked as text — or marked `text=auto` and
+//! detected as text — are converted.
+//!
+//! ```text
+//! # .mediagitattributes
+//! *.sh      text eol=lf
+//! *.bat     text eol=crlf
+//! *.svg     text
+//! *.json    text=auto
+//! *.psd     binary
+//! ```
+//!
+//! The `compression` attribute overrides the compression the object database
+//! would pick from a file's type, for paths the type table cannot describe:
+//!
+//! ```text
+//! logs/**                 compression=brotli-best
+//! incompressible_cache/** compression=store
+//! ```
+//!
+//! The `merge` attribute resolves conflicts on paths whose resolution is
+//! always the same, so merges stop asking about them:
+//!
+//! ```text
+//! package-lock.json  merge=regenerate
+//! *.blend1           merge=ours
+//! CHANGELOG.md       merge=union
+//! ```
+//!
+//! Patterns use `.gitattributes` glob syntax, matched with the same
+//! [`ignore`] globs as `.mediagitignore`: a pattern without `/` matches the
+//! file name at any depth, `*` does not cross directories and `**` does.
+//! When several lines match a path, the last one wins per attribute.
+
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use mediagit_compression::CompressionStrategy;
+use std::borrow::Cow;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Name of the attributes file at the repository root
+pub const ATTRIBUTES_FILE: &str = ".mediagitattributes";
+
+/// Bytes inspected by `text=auto` when deciding whether content is text
+pub const BINARY_SNIFF_LEN: usize = 8000;
+
+/// Line-ending style written to the working tree on checkout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EolStyle {
+    /// Unix line endings (`\n`)
+    Lf,
+    /// Windows line endings (`\r\n`)
+    Crlf,
+}
+
+impl EolStyle {
+    /// Line-ending style native to the current platform
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            EolStyle::Crlf
+        } else {
+            EolStyle::Lf
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "lf" => Some(EolStyle::Lf),
+            "crlf" => Some(EolStyle::Crlf),
+            _ => None,
+        }
+    }
+}
+
+/// Value of the `text` attribute for a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextSetting {
+    /// `text`: always normalize line endings
+    Text,
+    /// `-text` / `binary`: never touch content
+    Binary,
+    /// `text=auto`: normalize only if the content looks like text
+    Auto,
+}
+
+/// A compiled `.gitattributes`-style path pattern
+#[derive(Debug, Clone)]
+pub(crate) struct AttributePattern {
+    matcher: Gitignore,
+}
+
+impl AttributePattern {
+    /// Compile `pattern`; one that is not a valid glob matches nothing
+    pub(crate) fn new(pattern: &str) -> Self {
+        let mut builder = GitignoreBuilder::new(".");
+        let matcher = match builder.add_line(None, pattern) {
+            Ok(builder) => builder.build(),
+            Err(e) => Err(e),
+        };
+        let matcher = matcher.unwrap_or_else(|e| {
+            tracing::warn!(pattern, "Invalid pattern in {}: {}", ATTRIBUTES_FILE, e);
+            Gitignore::empty()
+        });
+        Self { matcher }
+    }
+
+    /// Returns true if the pattern applies to a `/`-separated path relative
+    /// to the repository root
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        self.matcher.matched(path, false).is_ignore()
+    }
+}
+
+/// A single `pattern attr...` line
+#[derive(Debug, Clone)]
+struct AttributeRule {
+    pattern: AttributePattern,
+    text: Option<TextSetting>,
+    eol: Option<EolStyle>,
+}
+
+/// Compiled `.mediagitattributes` rules for line-ending conversion
+#[derive(Debug, Clone)]
+pub struct TextAttributes {
+    rules: Vec<AttributeRule>,
+    default_eol: EolStyle,
+}
+
+impl Default for TextAttributes {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            default_eol: EolStyle::native(),
+        }
+    }
+}
+
+impl TextAttributes {
+    /// Load `.mediagitattributes` from the repository root
+    ///
+    /// Returns an empty rule set (no conversion) if the file does not exist.
+    pub fn load(repo_root: &Path) -> Result<Self> {
+        Ok(read_attributes_file(repo_root)?
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default())
+    }
+
+    /// Parse attribute rules from the contents of an attributes file
+    ///
+    /// Unknown attributes are ignored so the file can carry attributes for
+    /// other tools.
+    pub fn parse(content: &str) -> Self {
+        let mut rules = Vec::new();
+
+        for (pattern, parts) in attribute_lines(content) {
+            let mut rule = AttributeRule {
+                pattern: AttributePattern::new(&pattern),
+                text: None,
+                eol: None,
+            };
+
+            for attr in parts {
+                match attr {
+                    "text" => rule.text = Some(TextSetting::Text),
+                    "-text" | "binary" => rule.text = Some(TextSetting::Binary),
+                    "text=auto" => rule.text = Some(TextSetting::Auto),
+                    _ => {
+                        if let Some(value) = attr.strip_prefix("eol=") {
+                            rule.eol = EolStyle::parse(value);
+                        }
+                    }
+                }
+            }
+
+            if rule.text.is_some() || rule.eol.is_some() {
+                rules.push(rule);
+            }
+        }
+
+        Self {
+            rules,
+            default_eol: EolStyle::native(),
+        }
+    }
+
+    /// Override the line-ending style used for text paths without an `eol` attribute
+    pub fn with_default_eol(mut self, eol: EolStyle) -> Self {
+        self.default_eol = eol;
+        self
+    }
+
+    /// Returns true if no rule could ever convert content
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns true if the working-tree file at `full_path` may have its line endings converted
+    ///
+    /// `text=auto` paths are decided from the file's first [`BINARY_SNIFF_LEN`]
+    /// bytes, and no other path reads the file at all. Callers use this to
+    /// route convertible files through an in-memory path where content can be
+    /// rewritten, while binary media keeps its streaming path.
+    pub fn may_convert(&self, path: &Path, full_path: &Path) -> Result<bool> {
+        if self.resolve(path).0 != Some(TextSetting::Auto) {
+            return Ok(self.should_convert(path, &[]));
+        }
+        let mut head = Vec::with_capacity(BINARY_SNIFF_LEN);
+        fs::File::open(full_path)
+            .and_then(|file| file.take(BINARY_SNIFF_LEN as u64).read_to_end(&mut head))
+            .with_context(|| format!("Failed to read {}", full_path.display()))?;
+        Ok(!looks_binary(&head))
+    }
+
+    /// Returns true if line endings of `data` at `path` should be converted
+    pub fn should_convert(&self, path: &Path, data: &[u8]) -> bool {
+        let (text, eol) = self.resolve(path);
+        match text {
+            Some(TextSetting::Text) => true,
+            Some(TextSetting::Binary) => false,
+            Some(TextSetting::Auto) => !looks_binary(data),
+            // Setting `eol` without `text` implies text, as in Git
+            None => eol.is_some(),
+        }
+    }
+
+    /// Clean filter: normalize CRLF to LF for text paths before storing
+    pub fn clean<'d>(&self, path: &Path, data: &'d [u8]) -> Cow<'d, [u8]> {
+        if self.should_convert(path, data) {
+            normalize_to_lf(data)
+        } else {
+            Cow::Borrowed(data)
+        }
+    }
+
+    /// Line-ending style to write `data` at `path` with, or `None` to write it verbatim
+    pub fn checkout_eol(&self, path: &Path, data: &[u8]) -> Option<EolStyle> {
+        if !self.should_convert(path, data) {
+            return None;
+        }
+        let (_, eol) = self.resolve(path);
+        Some(eol.unwrap_or(self.default_eol))
+    }
+
+    /// Smudge filter: restore the configured line endings for text paths on checkout
+    pub fn smudge(&self, path: &Path, data: Vec<u8>) -> Vec<u8> {
+        match self.checkout_eol(path, &data) {
+            Some(EolStyle::Crlf) => convert_lf_to_crlf(&data),
+            _ => data,
+        }
+    }
+
+    /// Resolve the effective `text` and `eol` attributes for a path
+    fn resolve(&self, path: &Path) -> (Option<TextSetting>, Option<EolStyle>) {
+        let path_str = path.to_string_lossy().replace('\\', "/");
+
+        let mut text = None;
+        let mut eol = None;
+
+        for rule in &self.rules {
+            if rule.pattern.matches(&path_str) {
+                if rule.text.is_some() {
+                    text = rule.text;
+                }
+                if rule.eol.is_some() {
+                    eol = rule.eol;
+                }
+            }
+        }
+
+        (text, eol)
+    }
+}
+
+/// Compiled `compression=<strategy>` rules from `.mediagitattributes`
+///
+/// Strategy names are those accepted by [`CompressionStrategy::parse`];
+/// lines with an unknown name are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct CompressionAttributes {
+    rules: Vec<(AttributePattern, CompressionStrategy)>,
+}
+
+impl CompressionAttributes {
+    /// Load `.mediagitattributes` from the repository root
+    ///
+    /// Returns an empty rule set (no overrides) if the file does not exist.
+    pub fn load(repo_root: &Path) -> Result<Self> {
+        Ok(read_attributes_file(repo_root)?
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default())
+    }
+
+    /// Parse compression rules from the contents of an attributes file
+    pub fn parse(content: &str) -> Self {
+        let mut rules = Vec::new();
+
+        for (pattern, parts) in attribute_lines(content) {
+            let strategy = parts
+                .filter_map(|attr| attr.strip_prefix("compression="))
+                .next_back();
+            match strategy.map(|name| (name, CompressionStrategy::parse(name))) {
+                Some((_, Some(strategy))) => {
+                    rules.push((AttributePattern::new(&pattern), strategy))
+                }
+                Some((name, None)) => {
+                    tracing::warn!(pattern = %pattern, "Unknown compression '{}' in {}", name, ATTRIBUTES_FILE)
+                }
+                None => {}
+            }
+        }
+
+        Self { rules }
+    }
+
+    /// Returns true if no path has a compression override
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Compression strategy configured for `path`, relative to the repository root
+    pub fn strategy_for(&self, path: &Path) -> Option<CompressionStrategy> {
+        let path_str = path.to_string_lossy().replace('\\', "/");
+        self.rules
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches(&path_str))
+            .map(|(_, strategy)| *strategy)
+    }
+}
+
+/// How conflicts on a path are resolved automatically (`merge=<driver>`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeDriver {
+    /// `merge=ours`: keep our version
+    Ours,
+    /// `merge=union`: keep the lines both sides added, without conflict markers
+    Union,
+    /// `merge=regenerate`: keep our version for now and rebuild the file with
+    /// the configured command once the merge is done
+    Regenerate,
+}
+
+impl MergeDriver {
+    /// Parse a driver name as written in `.mediagitattributes`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ours" => Some(MergeDriver::Ours),
+            "union" => Some(MergeDriver::Union),
+            "regenerate" => Some(MergeDriver::Regenerate),
+            _ => None,
+        }
+    }
+}
+
+/// Compiled `merge=<driver>` rules from `.mediagitattributes`
+///
+/// Lines with an unknown driver are skipped, so those paths conflict as usual.
+#[derive(Debug, Clone, Default)]
+pub struct MergeAttributes {
+    rules: Vec<(AttributePattern, MergeDriver)>,
+}
+
+impl MergeAttributes {
+    /// Load `.mediagitattributes` from the repository root
+    ///
+    /// Returns an empty rule set (no automatic resolution) if the file does not exist.
+    pub fn load(repo_root: &Path) -> Result<Self> {
+        Ok(read_attributes_file(repo_root)?
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default())
+    }
+
+    /// Parse merge rules from the contents of an attributes file
+    pub fn parse(content: &str) -> Self {
+        let mut rules = Vec::new();
+
+        for (pattern, parts) in attribute_lines(content) {
+            let driver = parts
+                .filter_map(|attr| attr.strip_prefix("merge="))
+                .next_back();
+            match driver.map(|name| (name, MergeDriver::parse(name))) {
+                Some((_, Some(driver))) => rules.push((AttributePattern::new(&pattern), driver)),
+                Some((name, None)) => {
+                    tracing::warn!(pattern = %pattern, "Unknown merge driver '{}' in {}", name, ATTRIBUTES_FILE)
+                }
+                None => {}
+            }
+        }
+
+        Self { rules }
+    }
+
+    /// Returns true if no path resolves conflicts automatically
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Merge driver configured for `path`, relative to the repository root
+    pub fn driver_for(&self, path: &str) -> Option<MergeDriver> {
+        let path = path.replace('\\', "/");
+        self.rules
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches(&path))
+            .map(|(_, driver)| *driver)
+    }
+}
+
+/// Contents of the repository's attributes file, if it has one
+fn read_attributes_file(repo_root: &Path) -> Result<Option<String>> {
+    let path = repo_root.join(ATTRIBUTES_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    fs::read_to_string(&path)
+        .map(Some)
+        .with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// Non-comment lines of an attributes file as `(pattern, attributes)`
+fn attribute_lines(content: &str) -> impl Iterator<Item = (String, std::str::SplitWhitespace<'_>)> {
+    content.lines().filter_map(|line| {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let mut parts = line.split_whitespace();
+        let pattern = parts.next()?.trim_start_matches('/').to_string();
+        Some((pattern, parts))
+    })
+}
+
+/// Returns true if content looks binary (contains a NUL byte near the start)
+pub fn looks_binary(data: &[u8]) -> bool {
+    data[..data.len().min(BINARY_SNIFF_LEN)].contains(&0)
+}
+
+/// Convert CRLF line endings to LF, borrowing if there is nothing to convert
+pub fn normalize_to_lf(data: &[u8]) -> Cow<'_, [u8]> {
+    if !data.windows(2).any(|w| w == b"\r\n") {
+        return Cow::Borrowed(data);
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] == b'\r' && data.get(i + 1) == Some(&b'\n') {
+            i += 1;
+            continue;
+        }
+        out.push(data[i]);
+        i += 1;
+    }
+    Cow::Owned(out)
+}
+
+/// Convert LF line endings to CRLF, leaving existing CRLF pairs intact
+pub fn convert_lf_to_crlf(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 32);
+    for (i, &byte) in data.iter().enumerate() {
+        if byte == b'\n' && (i == 0 || data[i - 1] != b'\r') {
+            out.push(b'\r');
+        }
+        out.push(byte);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mediagit_compression::CompressionLevel;
+
+    #[test]
+    fn test_pattern_matches() {
+        let matches = |pattern: &str, path: &str| AttributePattern::new(pattern).matches(path);
+        assert!(matches("*.sh", "build.sh"));
+        assert!(matches("*.sh", "scripts/build.sh"));
+        assert!(matches("scripts/*.sh", "scripts/build.sh"));
+        assert!(!matches("scripts/*.sh", "scripts/ci/build.sh"));
+        assert!(!matches("scripts/*.sh", "tools/scripts/build.sh"));
+        assert!(matches("assets/**/*.svg", "assets/icons/ui/logo.svg"));
+        assert!(matches("**/*.svg", "logo.svg"));
+        assert!(matches("file?.txt", "file1.txt"));
+        assert!(!matches("file?.txt", "file12.txt"));
+        assert!(matches("*.[ch]", "src/main.c"));
+        assert!(!matches("*.[ch]", "src/main.o"));
+    }
+
+    #[test]
+    fn test_parse_and_resolve() {
+        let attrs = TextAttributes::parse(
+            "# comment\n*.txt text\n*.bat text eol=crlf\n*.psd binary\n*.json text=auto\nnotes.txt -text\n",
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let full_path = dir.path().join("content");
+        fs::write(&full_path, b"{}\n").unwrap();
+        let may_convert = |path: &str| attrs.may_convert(Path::new(path), &full_path).unwrap();
+        assert!(may_convert("docs/readme.txt"));
+        assert!(!may_convert("docs/notes.txt"));
+        assert!(!may_convert("art/cover.psd"));
+        assert!(may_convert("data.json"));
+        assert!(!may_convert("unlisted.bin"));
+
+        assert_eq!(
+            attrs.checkout_eol(Path::new("run.bat"), b"echo\n"),
+            Some(EolStyle::Crlf)
+        );
+        assert_eq!(attrs.checkout_eol(Path::new("art/cover.psd"), b"x\n"), None);
+        assert_eq!(attrs.checkout_eol(Path::new("unlisted.bin"), b"x\n"), None);
+    }
+
+    #[test]
+    fn test_text_auto_skips_binary_content() {
+        let attrs = TextAttributes::parse("* text=auto\n");
+        assert!(attrs.should_convert(Path::new("a.cfg"), b"key=value\r\n"));
+        assert!(!attrs.should_convert(Path::new("a.bin"), b"\x89PNG\r\n\x00\x00"));
+    }
+
+    #[test]
+    fn test_text_auto_keeps_large_binary_on_streaming_path() {
+        let attrs = TextAttributes::parse("* text=auto\n");
+        let dir = tempfile::tempdir().unwrap();
+
+        // Larger than the 5MB streaming threshold; only the sniffed prefix decides
+        let video = dir.path().join("clip.mp4");
+        let mut content = b"\x00\x00\x00\x18ftypmp42".to_vec();
+        content.resize(6 * 1024 * 1024, b'\n');
+        fs::write(&video, &content).unwrap();
+        assert!(!attrs.may_convert(Path::new("clip.mp4"), &video).unwrap());
+
+        let log = dir.path().join("build.log");
+        fs::write(&log, "line\r\n".repeat(1024 * 1024)).unwrap();
+        assert!(attrs.may_convert(Path::new("build.log"), &log).unwrap());
+    }
+
+    #[test]
+    fn test_clean_normalizes_crlf() {
+        let attrs = TextAttributes::parse("*.cfg text\n");
+        let cleaned = attrs.clean(Path::new("app.cfg"), b"a\r\nb\r\nc\n");
+        assert_eq!(cleaned.as_ref(), b"a\nb\nc\n");
+
+        // Binary paths are left untouched, including lone CR bytes
+        let raw = b"a\r\nb\r";
+        assert_eq!(attrs.clean(Path::new("image.png"), raw).as_ref(), raw);
+    }
+
+    #[test]
+    fn test_smudge_respects_eol() {
+        let attrs = TextAttributes::parse("*.cfg text\n*.bat text eol=crlf\n")
+            .with_default_eol(EolStyle::Lf);
+        assert_eq!(
+            attrs.smudge(Path::new("run.bat"), b"a\nb\n".to_vec()),
+            b"a\r\nb\r\n"
+        );
+        assert_eq!(
+            attrs.smudge(Path::new("app.cfg"), b"a\nb\n".to_vec()),
+            b"a\nb\n"
+        );
+
+        let crlf_default = attrs.with_default_eol(EolStyle::Crlf);
+        assert_eq!(
+            crlf_default.smudge(Path::new("app.cfg"), b"a\nb\n".to_vec()),
+            b"a\r\nb\r\n"
+        );
+    }
+
+    #[test]
+    fn test_compression_overrides() {
+        let attrs = CompressionAttributes::parse(
+            "*.txt text\nlogs/** compression=brotli-best\ncache/** compression=store\n\
+             cache/keep/*.json compression=zstd\n*.tmp compression=lz4\n",
+        );
+
+        assert_eq!(
+            attrs.strategy_for(Path::new("logs/2025/render.log")),
+            Some(CompressionStrategy::Brotli(CompressionLevel::Best))
+        );
+        assert_eq!(
+            attrs.strategy_for(Path::new("cache/frames/0001.exr")),
+            Some(CompressionStrategy::Store)
+        );
+        // A later line wins over an earlier one
+        assert_eq!(
+            attrs.strategy_for(Path::new("cache/keep/index.json")),
+            Some(CompressionStrategy::Zstd(CompressionLevel::Default))
+        );
+        assert_eq!(attrs.strategy_for(Path::new("notes.txt")), None);
+        assert_eq!(
+            attrs.strategy_for(Path::new("scratch.tmp")),
+            Some(CompressionStrategy::Lz4)
+        );
+        assert_eq!(attrs.strategy_for(Path::new("art/logs/a.log")), None);
+    }
+
+    #[test]
+    fn test_merge_drivers() {
+        let attrs = MergeAttributes::parse(
+            "*.json text\npackage-lock.json merge=regenerate\n*.blend1 merge=ours\n\
+             CHANGELOG.md merge=union\nnotes/* merge=theirs\n",
+        );
+
+        assert_eq!(
+            attrs.driver_for("web/package-lock.json"),
+            Some(MergeDriver::Regenerate)
+        );
+        assert_eq!(
+            attrs.driver_for("scenes/shot01.blend1"),
+            Some(MergeDriver::Ours)
+        );
+        assert_eq!(attrs.driver_for("CHANGELOG.md"), Some(MergeDriver::Union));
+        assert_eq!(attrs.driver_for("scenes/shot01.blend"), None);
+        assert_eq!(attrs.driver_for("notes/todo.txt"), None);
+    }
+
+    #[test]
+    fn test_crlf_roundtrip_is_stable() {
+        let original = b"line1\r\nline2\nline3\r\n";
+        let stored = normalize_to_lf(original);
+        assert_eq!(stored.as_ref(), b"line1\nline2\nline3\n");
+        let restored = convert_lf_to_crlf(&stored);
+        assert_eq!(restored, b"line1\r\nline2\r\nline3\r\n");
+        assert_eq!(convert_lf_to_crlf(&restored), restored);
+    }
+}
@@ -0,0 +1,411 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Bundle files for moving history without a network connection
+//!
+//! A bundle packages a set of refs and every object reachable from them into
+//! one file that can be carried across an air gap and cloned or fetched from.
+//! An incremental bundle leaves out everything reachable from a basis the
+//! receiving repository already has, and lists the basis refs as
+//! prerequisites so a receiver without them is refused before anything is
+//! written.
+//!
+//! # Format
+//!
+//! ```text
+//! # mediagit bundle v1\n
+//! -<oid> <basis>\n      one line per prerequisite, named as it was given
+//! <oid> <refname>\n     one line per ref
+//! \n
+//! [Pack: PackWriter output, with its own checksum]
+//! [Checksum: 32 bytes]
+//!   - SHA-256 of everything above
+//! ```
+//!
+//! Chunked objects are stored reassembled, so the bundle is independent of
+//! how either repository chunks large files.
+
+use crate::pack::{PackReader, PackWriter};
+use crate::{Commit, ObjectDatabase, ObjectType, Oid, Tree};
+use sha2::Digest;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use tracing::{debug, info};
+
+/// First line of every bundle
+const BUNDLE_SIGNATURE: &str = "# mediagit bundle v1";
+
+const CHECKSUM_SIZE: usize = 32;
+
+/// File extension used for bundles
+pub const BUNDLE_EXTENSION: &str = "mgbundle";
+
+/// Refs and objects packaged for offline transfer
+pub struct Bundle {
+    refs: BTreeMap<String, Oid>,
+    prerequisites: BTreeMap<String, Oid>,
+    pack: PackReader,
+}
+
+impl Bundle {
+    /// Bundle `refs` and the objects reachable from them
+    ///
+    /// Objects reachable from any commit in `basis` are left out, and the
+    /// basis refs become the bundle's prerequisites.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `refs` is empty or an object cannot be read.
+    pub async fn create(
+        odb: &ObjectDatabase,
+        refs: BTreeMap<String, Oid>,
+        basis: BTreeMap<String, Oid>,
+    ) -> anyhow::Result<Self> {
+        if refs.is_empty() {
+            anyhow::bail!("Refusing to create an empty bundle");
+        }
+        for name in refs.keys().chain(basis.keys()) {
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                anyhow::bail!("Invalid ref name for bundle: '{}'", name);
+            }
+        }
+
+        // Everything the receiver already has
+        let mut seen = HashSet::new();
+        let basis_tips: Vec<Oid> = basis.values().copied().collect();
+        walk(odb, &basis_tips, &mut seen, false, |_, _, _| {}).await?;
+
+        let mut writer = PackWriter::new();
+        let tips: Vec<Oid> = refs.values().copied().collect();
+        let mut count = 0usize;
+        walk(odb, &tips, &mut seen, true, |oid, obj_type, data| {
+            writer.add_object(oid, obj_type, &data);
+            count += 1;
+        })
+        .await?;
+
+        info!(
+            refs = refs.len(),
+            objects = count,
+            prerequisites = basis.len(),
+            "Bundle created"
+        );
+
+        Ok(Self {
+            refs,
+            prerequisites: basis,
+            pack: PackReader::new(writer.finalize())
+                .map_err(|e| anyhow::anyhow!("Failed to build bundle pack: {}", e))?,
+        })
+    }
+
+    /// Parse a bundle, verifying its checksums
+    ///
+    /// # Errors
+    ///
+    /// Fails if the data is not a bundle or is corrupt.
+    pub fn from_bytes(mut data: Vec<u8>) -> anyhow::Result<Self> {
+        if data.len() < BUNDLE_SIGNATURE.len() + CHECKSUM_SIZE
+            || !data.starts_with(BUNDLE_SIGNATURE.as_bytes())
+        {
+            anyhow::bail!("Not a mediagit bundle");
+        }
+
+        let checksum_offset = data.len() - CHECKSUM_SIZE;
+        let actual = sha2::Sha256::digest(&data[..checksum_offset]);
+        if actual[..] != data[checksum_offset..] {
+            anyhow::bail!("Bundle checksum verification failed");
+        }
+        data.truncate(checksum_offset);
+
+        let header_end = data
+            .windows(2)
+            .position(|w| w == b"\n\n")
+            .ok_or_else(|| anyhow::anyhow!("Bundle header is not terminated"))?;
+        let header = std::str::from_utf8(&data[..header_end])
+            .map_err(|_| anyhow::anyhow!("Bundle header is not valid UTF-8"))?;
+
+        let mut refs = BTreeMap::new();
+        let mut prerequisites = BTreeMap::new();
+        for line in header.lines().skip(1) {
+            if let Some(prerequisite) = line.strip_prefix('-') {
+                let (oid, name) = prerequisite.split_once(' ').unwrap_or((prerequisite, ""));
+                let oid = Oid::from_hex(oid)?;
+                let name = if name.is_empty() {
+                    oid.to_hex()
+                } else {
+                    name.to_string()
+                };
+                prerequisites.insert(name, oid);
+            } else {
+                let (oid, name) = line
+                    .split_once(' ')
+                    .ok_or_else(|| anyhow::anyhow!("Malformed bundle ref line: '{}'", line))?;
+                refs.insert(name.to_string(), Oid::from_hex(oid)?);
+            }
+        }
+
+        let pack = PackReader::new(data.split_off(header_end + 2))
+            .map_err(|e| anyhow::anyhow!("Invalid bundle pack: {}", e))?;
+
+        Ok(Self {
+            refs,
+            prerequisites,
+            pack,
+        })
+    }
+
+    /// Serialize the bundle, appending its checksum
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = format!("{}\n", BUNDLE_SIGNATURE).into_bytes();
+        for (name, oid) in &self.prerequisites {
+            data.extend_from_slice(format!("-{} {}\n", oid, name).as_bytes());
+        }
+        for (name, oid) in &self.refs {
+            data.extend_from_slice(format!("{} {}\n", oid, name).as_bytes());
+        }
+        data.push(b'\n');
+        data.extend_from_slice(self.pack.as_bytes());
+
+        let checksum = sha2::Sha256::digest(&data);
+        data.extend_from_slice(&checksum);
+        data
+    }
+
+    /// Refs in the bundle, by full name
+    pub fn refs(&self) -> &BTreeMap<String, Oid> {
+        &self.refs
+    }
+
+    /// Basis refs the receiving repository must already have, by the name
+    /// they were given when the bundle was created
+    pub fn prerequisites(&self) -> &BTreeMap<String, Oid> {
+        &self.prerequisites
+    }
+
+    /// Number of objects in the bundle
+    pub fn object_count(&self) -> usize {
+        self.pack.index().len()
+    }
+
+    /// Prerequisites missing from `odb`
+    pub async fn missing_prerequisites(
+        &self,
+        odb: &ObjectDatabase,
+    ) -> anyhow::Result<BTreeMap<String, Oid>> {
+        let mut missing = BTreeMap::new();
+        for (name, oid) in &self.prerequisites {
+            if !odb.exists(oid).await? {
+                missing.insert(name.clone(), *oid);
+            }
+        }
+        Ok(missing)
+    }
+
+    /// Write the bundle's objects into `odb`, returning how many were new
+    ///
+    /// # Errors
+    ///
+    /// Fails if a prerequisite is missing or an object does not match its id.
+    pub async fn unbundle(&self, odb: &ObjectDatabase) -> anyhow::Result<usize> {
+        let missing = self.missing_prerequisites(odb).await?;
+        if !missing.is_empty() {
+            let listed: Vec<String> = missing
+                .iter()
+                .map(|(name, oid)| format!("{} ({})", name, &oid.to_hex()[..8]))
+                .collect();
+            anyhow::bail!(
+                "Bundle requires basis the repository does not have: {}",
+                listed.join(", ")
+            );
+        }
+
+        let mut written = 0;
+        for oid in self.pack.list_objects() {
+            if odb.exists(&oid).await? {
+                continue;
+            }
+            let (obj_type, data) = self
+                .pack
+                .get_object_with_type(&oid)
+                .map_err(|e| anyhow::anyhow!("Failed to read {} from bundle: {}", oid, e))?;
+            if Oid::hash(&data) != oid {
+                anyhow::bail!("Bundle object {} does not match its id", oid);
+            }
+            odb.write(obj_type, &data).await?;
+            written += 1;
+        }
+
+        debug!(written, "Unbundled objects");
+        Ok(written)
+    }
+}
+
+/// Breadth-first walk over commits, trees and blobs reachable from `tips`
+///
+/// Objects already in `seen` are skipped along with everything below them.
+/// Blobs are only read, and passed to `visit`, when `read_blobs` is set.
+async fn walk<F>(
+    odb: &ObjectDatabase,
+    tips: &[Oid],
+    seen: &mut HashSet<Oid>,
+    read_blobs: bool,
+    mut visit: F,
+) -> anyhow::Result<()>
+where
+    F: FnMut(Oid, ObjectType, Vec<u8>),
+{
+    let mut queue: VecDeque<(Oid, ObjectType)> = tips
+        .iter()
+        .filter(|oid| seen.insert(**oid))
+        .map(|oid| (*oid, ObjectType::Commit))
+        .collect();
+
+    while let Some((oid, obj_type)) = queue.pop_front() {
+        if obj_type == ObjectType::Blob && !read_blobs {
+            continue;
+        }
+
+        let data = odb.read(&oid).await?;
+        match obj_type {
+            ObjectType::Commit => {
+                let commit = Commit::deserialize(&data)?;
+                if seen.insert(commit.tree) {
+                    queue.push_back((commit.tree, ObjectType::Tree));
+                }
+                for parent in commit.parents {
+                    if seen.insert(parent) {
+                        queue.push_back((parent, ObjectType::Commit));
+                    }
+                }
+            }
+            ObjectType::Tree => {
+                let tree = Tree::deserialize(&data)?;
+                for entry in tree.iter() {
+                    if seen.insert(entry.oid) {
+                        queue.push_back((entry.oid, entry.mode.object_type()));
+                    }
+                }
+            }
+            ObjectType::Blob => {}
+        }
+        visit(oid, obj_type, data);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{FileMode, Signature, TreeEntry};
+    use mediagit_storage::mock::MockBackend;
+    use std::sync::Arc;
+
+    fn odb() -> ObjectDatabase {
+        ObjectDatabase::new(Arc::new(MockBackend::new()), 100)
+    }
+
+    /// Commit a single file on top of `parent`
+    async fn commit(odb: &ObjectDatabase, content: &[u8], parent: Option<Oid>) -> Oid {
+        let blob = odb.write(ObjectType::Blob, content).await.unwrap();
+        let mut tree = Tree::new();
+        tree.add_entry(TreeEntry::new(
+            "shot.json".to_string(),
+            FileMode::Regular,
+            blob,
+        ));
+        let tree = tree.write(odb).await.unwrap();
+        let sig = Signature::now("Test".to_string(), "test@example.com".to_string());
+        let commit = Commit::with_parents(
+            tree,
+            parent.into_iter().collect(),
+            sig.clone(),
+            sig,
+            "update".to_string(),
+        );
+        commit.write(odb).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_bundle_roundtrip() {
+        let source = odb();
+        let first = commit(&source, b"take 1", None).await;
+        let second = commit(&source, b"take 2", Some(first)).await;
+        let refs = BTreeMap::from([("refs/heads/main".to_string(), second)]);
+
+        let bundle = Bundle::create(&source, refs.clone(), BTreeMap::new())
+            .await
+            .unwrap();
+        assert_eq!(bundle.object_count(), 6);
+
+        let parsed = Bundle::from_bytes(bundle.to_bytes()).unwrap();
+        assert_eq!(parsed.refs(), &refs);
+        assert!(parsed.prerequisites().is_empty());
+
+        let target = odb();
+        assert_eq!(parsed.unbundle(&target).await.unwrap(), 6);
+        let head = Commit::deserialize(&target.read(&second).await.unwrap()).unwrap();
+        assert_eq!(head.parents, vec![first]);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_bundle_needs_basis() {
+        let source = odb();
+        let first = commit(&source, b"take 1", None).await;
+        let second = commit(&source, b"take 2", Some(first)).await;
+        let refs = BTreeMap::from([("refs/heads/main".to_string(), second)]);
+
+        let basis = BTreeMap::from([("refs/tags/v1".to_string(), first)]);
+        let bundle = Bundle::create(&source, refs, basis.clone()).await.unwrap();
+        // Only the new commit, its tree and its blob
+        assert_eq!(bundle.object_count(), 3);
+
+        // The basis survives serialization by name
+        let bundle = Bundle::from_bytes(bundle.to_bytes()).unwrap();
+        assert_eq!(bundle.prerequisites(), &basis);
+
+        let empty = odb();
+        assert_eq!(bundle.missing_prerequisites(&empty).await.unwrap(), basis);
+        let err = bundle.unbundle(&empty).await.unwrap_err().to_string();
+        assert!(err.contains("refs/tags/v1"), "unexpected error: {}", err);
+        assert_eq!(empty.count_loose_objects().await.unwrap(), 0);
+
+        // A repository cloned from an earlier full bundle has the basis
+        let target = odb();
+        let earlier = BTreeMap::from([("refs/heads/main".to_string(), first)]);
+        Bundle::create(&source, earlier, BTreeMap::new())
+            .await
+            .unwrap()
+            .unbundle(&target)
+            .await
+            .unwrap();
+        assert_eq!(bundle.unbundle(&target).await.unwrap(), 3);
+        assert!(target.exists(&second).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_bundle_is_rejected() {
+        let source = odb();
+        let head = commit(&source, b"take 1", None).await;
+        let refs = BTreeMap::from([("refs/heads/main".to_string(), head)]);
+        let mut data = Bundle::create(&source, refs, BTreeMap::new())
+            .await
+            .unwrap()
+            .to_bytes();
+
+        let middle = data.len() / 2;
+        data[middle] ^= 0xff;
+        assert!(Bundle::from_bytes(data).is_err());
+        assert!(Bundle::from_bytes(b"PACK".to_vec()).is_err());
+    }
+}
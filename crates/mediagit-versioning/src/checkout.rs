@@ -16,26 +16,52 @@
 //! This module provides functionality to update the working directory
 //! to match a specific commit's tree structure.
 
-use crate::{Commit, FileMode, ObjectDatabase, Oid, Tree};
+use crate::{
+    convert_lf_to_crlf, Commit, EolStyle, FileMode, Index, ObjectDatabase, Oid, Prefetched,
+    TextAttributes, Tree,
+};
 use anyhow::{Context, Result};
-use std::collections::{HashMap, HashSet};
+use mediagit_storage::StorageError;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use tracing::{debug, info};
 
+/// Objects fetched at once while materializing a checkout
+const PREFETCH_CONCURRENCY: usize = 16;
+
 /// Checkout manager for working directory operations
 pub struct CheckoutManager<'a> {
     odb: &'a ObjectDatabase,
     repo_root: PathBuf,
+    attributes: TextAttributes,
+    /// Whether the working tree ignores case in file names, probed on first use
+    case_insensitive: OnceLock<bool>,
 }
 
 impl<'a> CheckoutManager<'a> {
     /// Create a new checkout manager
-    pub fn new(odb: &'a ObjectDatabase, repo_root: impl Into<PathBuf>) -> Self {
-        Self {
+    ///
+    /// Line-ending rules are loaded from the repository's `.mediagitattributes`
+    /// (if any), so text assets are restored with their configured line endings.
+    /// Fails if the attributes file exists but cannot be read, as `add` does,
+    /// rather than writing text files without their line-ending rules.
+    pub fn new(odb: &'a ObjectDatabase, repo_root: impl Into<PathBuf>) -> Result<Self> {
+        let repo_root = repo_root.into();
+        let attributes = TextAttributes::load(&repo_root)?;
+        Ok(Self {
             odb,
-            repo_root: repo_root.into(),
-        }
+            repo_root,
+            attributes,
+            case_insensitive: OnceLock::new(),
+        })
+    }
+
+    /// Replace the line-ending rules applied when writing files
+    pub fn with_attributes(mut self, attributes: TextAttributes) -> Self {
+        self.attributes = attributes;
+        self
     }
 
     /// Checkout a commit, updating the working directory to match its tree
@@ -60,20 +86,100 @@ impl<'a> CheckoutManager<'a> {
 
         debug!("Commit tree: {}", commit.tree);
 
-        // Optimized: Single-pass checkout that collects files and writes them
-        // This eliminates the redundant tree traversal
-        let (target_files, files_updated) = self
-            .checkout_tree_optimized(&commit.tree, Path::new(""))
+        let target_files = self
+            .get_tree_files_with_oid(&commit.tree, Path::new(""))
             .await?;
         debug!("Target files: {} entries", target_files.len());
+        self.check_case_collisions(target_files.keys())?;
+
+        // Differential checkout: files already matching the target are not fetched
+        let mut needed = Vec::new();
+        for (path, (oid, mode)) in &target_files {
+            if *mode != FileMode::Symlink && self.working_file_oid(path)?.as_ref() == Some(oid) {
+                debug!("Skipped unchanged file: {}", path.display());
+                continue;
+            }
+            needed.push((path.clone(), *oid, *mode));
+        }
+        let files_updated = self.materialize(&PrefetchManifest::new(needed)).await?;
 
         // Clean working directory (remove files not in target)
-        self.clean_working_directory(&target_files)?;
+        let target_paths: HashSet<PathBuf> = target_files.into_keys().collect();
+        self.clean_working_directory(&target_paths)?;
 
         info!("Checked out {} files", files_updated);
         Ok(files_updated)
     }
 
+    /// The blobs a checkout of `commit_oid` would fetch, in fetch order
+    ///
+    /// Files whose working-tree content already matches the commit are left
+    /// out, as [`checkout_commit`](Self::checkout_commit) does not rewrite them.
+    pub async fn prefetch_manifest(&self, commit_oid: &Oid) -> Result<PrefetchManifest> {
+        let commit = Commit::read(self.odb, commit_oid).await?;
+        let target_files = self
+            .get_tree_files_with_oid(&commit.tree, Path::new(""))
+            .await?;
+
+        let mut needed = Vec::new();
+        for (path, (oid, mode)) in target_files {
+            if mode == FileMode::Symlink || self.working_file_oid(&path)? != Some(oid) {
+                needed.push((path, oid, mode));
+            }
+        }
+        Ok(PrefetchManifest::new(needed))
+    }
+
+    /// Write every file in `manifest`, fetching its blobs in one batch
+    ///
+    /// All blobs are handed to [`ObjectDatabase::prefetch`] up front so remote
+    /// backends fetch them concurrently, and each file is written as soon as
+    /// its blob arrives, overlapping network and disk I/O. Returns the number
+    /// of files written.
+    async fn materialize(&self, manifest: &PrefetchManifest) -> Result<usize> {
+        if manifest.is_empty() {
+            return Ok(0);
+        }
+
+        let entries: HashMap<Oid, &PrefetchEntry> =
+            manifest.entries().iter().map(|e| (e.oid, e)).collect();
+        let mut arrivals = self
+            .odb
+            .prefetch(manifest.oids(), PREFETCH_CONCURRENCY)
+            .await;
+
+        let mut received = 0;
+        let mut files_written = 0;
+        while let Some((oid, fetched)) = arrivals.recv().await {
+            let fetched = fetched.with_context(|| format!("Failed to read blob: {}", oid))?;
+            let Some(entry) = entries.get(&oid) else {
+                continue;
+            };
+            for (path, mode) in &entry.paths {
+                let full_path = self.repo_root.join(path);
+                match &fetched {
+                    Prefetched::Data(data) => {
+                        self.write_file(&full_path, *mode, data.clone()).await
+                    }
+                    Prefetched::Chunked => self.stream_file(&full_path, &oid, *mode).await,
+                }
+                .with_context(|| format!("Failed to checkout file: {}", full_path.display()))?;
+                debug!("Checked out file: {}", path.display());
+                files_written += 1;
+            }
+            received += 1;
+        }
+
+        if received != entries.len() {
+            anyhow::bail!(
+                "Prefetch ended after {} of {} objects",
+                received,
+                entries.len()
+            );
+        }
+        Ok(files_written)
+    }
+
     /// Get all file paths from a tree recursively
     #[allow(dead_code)]
     fn get_tree_files<'b>(
@@ -82,6 +188,10 @@ impl<'a> CheckoutManager<'a> {
         prefix: &'b Path,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<HashSet<PathBuf>>> + 'b>> {
         Box::pin(async move {
+            self.odb
+                .tree_limits()
+                .check_depth(prefix.components().count())
+                .with_context(|| format!("Cannot walk {}", prefix.display()))?;
             let tree = Tree::read(self.odb, tree_oid).await?;
 
             let mut files = HashSet::new();
@@ -289,6 +399,10 @@ impl<'a> CheckoutManager<'a> {
         prefix: &'b Path,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<usize>> + 'b>> {
         Box::pin(async move {
+            self.odb
+                .tree_limits()
+                .check_depth(prefix.components().count())
+                .with_context(|| format!("Cannot walk {}", prefix.display()))?;
             let tree = Tree::read(self.odb, tree_oid).await?;
 
             let mut files_updated = 0;
@@ -308,8 +422,7 @@ impl<'a> CheckoutManager<'a> {
 
                         // Use streaming write for checkout (constant memory)
                         // This method handles both chunked and non-chunked objects
-                        self.odb
-                            .read_to_file(&entry.oid, &full_path)
+                        self.write_blob(&entry_path, &full_path, &entry.oid)
                             .await
                             .with_context(|| {
                                 format!("Failed to checkout file: {}", full_path.display())
@@ -372,141 +485,6 @@ impl<'a> CheckoutManager<'a> {
         })
     }
 
-    /// Optimized checkout: Single-pass tree traversal that both collects files and writes them
-    ///
-    /// This eliminates the redundant tree traversal (get_tree_files + checkout_tree).
-    /// Returns (file_paths, files_updated) for cleanup and counting.
-    #[allow(clippy::type_complexity)]
-    fn checkout_tree_optimized<'b>(
-        &'b self,
-        tree_oid: &'b Oid,
-        prefix: &'b Path,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<(HashSet<PathBuf>, usize)>> + 'b>>
-    {
-        Box::pin(async move {
-            let tree = Tree::read(self.odb, tree_oid).await?;
-
-            let mut file_paths = HashSet::new();
-            let mut files_updated = 0;
-
-            for entry in tree.iter() {
-                let entry_path = prefix.join(&entry.name);
-                let full_path = self.repo_root.join(&entry_path);
-
-                match entry.mode {
-                    FileMode::Regular | FileMode::Executable => {
-                        // Collect path for cleanup
-                        file_paths.insert(entry_path.clone());
-
-                        // OPTIMIZATION: Differential checkout - skip unchanged files
-                        // Check if file exists and matches the expected OID
-                        let mut skip_write = false;
-                        if full_path.exists() {
-                            // Quick check: Compare file size first (cheap operation)
-                            if let Ok(metadata) = fs::metadata(&full_path) {
-                                if let Ok(expected_size) =
-                                    self.odb.get_object_size(&entry.oid).await
-                                {
-                                    if metadata.len() == expected_size as u64 {
-                                        // Size matches - perform full hash comparison
-                                        if let Ok(file_data) = fs::read(&full_path) {
-                                            let file_oid = Oid::hash(&file_data);
-                                            if file_oid == entry.oid {
-                                                // File is unchanged - skip write operation
-                                                skip_write = true;
-                                                debug!(
-                                                    "Skipped unchanged file: {}",
-                                                    entry_path.display()
-                                                );
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-
-                        if !skip_write {
-                            // Ensure parent directory exists
-                            if let Some(parent) = full_path.parent() {
-                                fs::create_dir_all(parent).with_context(|| {
-                                    format!("Failed to create directory: {}", parent.display())
-                                })?;
-                            }
-
-                            // Use streaming write for checkout (constant memory)
-                            self.odb
-                                .read_to_file(&entry.oid, &full_path)
-                                .await
-                                .with_context(|| {
-                                    format!("Failed to checkout file: {}", full_path.display())
-                                })?;
-
-                            // Set executable permission if needed
-                            #[cfg(unix)]
-                            if entry.mode == FileMode::Executable {
-                                use std::os::unix::fs::PermissionsExt;
-                                let mut perms = fs::metadata(&full_path)?.permissions();
-                                perms.set_mode(0o755);
-                                fs::set_permissions(&full_path, perms)?;
-                            }
-
-                            debug!("Checked out file: {}", entry_path.display());
-                            files_updated += 1;
-                        }
-                    }
-                    FileMode::Symlink => {
-                        // Collect path for cleanup
-                        file_paths.insert(entry_path.clone());
-
-                        // Read symlink target
-                        let target_data = self.odb.read(&entry.oid).await?;
-                        let target = String::from_utf8(target_data)
-                            .context("Symlink target is not valid UTF-8")?;
-
-                        // Ensure parent directory exists
-                        if let Some(parent) = full_path.parent() {
-                            fs::create_dir_all(parent)?;
-                        }
-
-                        // Create symlink
-                        #[cfg(unix)]
-                        {
-                            use std::os::unix::fs::symlink;
-                            // Remove existing file/link if present
-                            if full_path.exists() || full_path.symlink_metadata().is_ok() {
-                                let _ = fs::remove_file(&full_path);
-                            }
-                            symlink(&target, &full_path).with_context(|| {
-                                format!("Failed to create symlink: {}", full_path.display())
-                            })?;
-                        }
-
-                        #[cfg(not(unix))]
-                        {
-                            // On non-Unix, write symlink target as a regular file
-                            fs::write(&full_path, target.as_bytes()).with_context(|| {
-                                format!("Failed to write symlink file: {}", full_path.display())
-                            })?;
-                        }
-
-                        debug!("Checked out symlink: {}", entry_path.display());
-                        files_updated += 1;
-                    }
-                    FileMode::Directory => {
-                        // Recursively checkout subdirectory
-                        let (subdir_paths, subdir_count) = self
-                            .checkout_tree_optimized(&entry.oid, &entry_path)
-                            .await?;
-                        file_paths.extend(subdir_paths);
-                        files_updated += subdir_count;
-                    }
-                }
-            }
-
-            Ok((file_paths, files_updated))
-        })
-    }
-
     /// Apply a commit's tree on top of the current working directory without cleaning.
     ///
     /// Unlike `checkout_commit`, this does NOT remove files that aren't in the target tree.
@@ -526,8 +504,83 @@ impl<'a> CheckoutManager<'a> {
         // Read the commit
         let commit = Commit::read(self.odb, commit_oid).await?;
 
-        // Checkout tree without cleaning (assume empty directory)
-        self.checkout_tree(&commit.tree, Path::new("")).await
+        // Write every file without cleaning (assume empty directory)
+        let files = self
+            .get_tree_files_with_oid(&commit.tree, Path::new(""))
+            .await?;
+        self.check_case_collisions(files.keys())?;
+        let needed = files
+            .into_iter()
+            .map(|(path, (oid, mode))| (path, oid, mode))
+            .collect();
+        self.materialize(&PrefetchManifest::new(needed)).await
+    }
+
+    /// Write a commit's files into a directory that is not a repository
+    ///
+    /// The manager's root is the export directory. Only files at or under one
+    /// of `paths` are written; an empty list exports the whole tree. Files
+    /// already present with the commit's content are kept, so an interrupted
+    /// export resumes where it stopped. Nothing outside the tree is removed.
+    ///
+    /// Reports newly written files as added, overwritten ones as modified and
+    /// kept ones as unchanged.
+    pub async fn export_commit(&self, commit_oid: &Oid, paths: &[String]) -> Result<CheckoutStats> {
+        let start = std::time::Instant::now();
+        info!(
+            "Exporting commit {} to {}",
+            commit_oid,
+            self.repo_root.display()
+        );
+
+        let commit = Commit::read(self.odb, commit_oid).await?;
+        let files = self
+            .get_tree_files_with_oid(&commit.tree, Path::new(""))
+            .await?;
+
+        let prefixes: Vec<&str> = paths.iter().map(|p| p.trim_matches('/')).collect();
+        let selected = |path: &Path| {
+            let path = path.to_string_lossy().replace('\\', "/");
+            prefixes.is_empty()
+                || prefixes
+                    .iter()
+                    .any(|p| path == *p || path.starts_with(&format!("{}/", p)))
+        };
+
+        let files: Vec<_> = files
+            .into_iter()
+            .filter(|(path, _)| selected(path))
+            .collect();
+        self.check_case_collisions(files.iter().map(|(path, _)| path))?;
+
+        let mut stats = CheckoutStats::default();
+        let mut needed = Vec::new();
+        for (path, (oid, mode)) in files {
+            let existing = if mode == FileMode::Symlink {
+                None
+            } else {
+                self.working_file_oid(&path)?
+            };
+            match existing {
+                Some(existing) if existing == oid => {
+                    // The content survived an earlier run; its mode may not have
+                    Self::set_executable(&self.repo_root.join(&path), mode)?;
+                    stats.files_unchanged += 1;
+                }
+                Some(_) => {
+                    stats.files_modified += 1;
+                    needed.push((path, oid, mode));
+                }
+                None => {
+                    stats.files_added += 1;
+                    needed.push((path, oid, mode));
+                }
+            }
+        }
+
+        self.materialize(&PrefetchManifest::new(needed)).await?;
+        stats.elapsed_ms = start.elapsed().as_millis() as u64;
+        Ok(stats)
     }
 
     /// Differential checkout - only update changed files
@@ -597,6 +650,7 @@ impl<'a> CheckoutManager<'a> {
         let to_files = self
             .get_tree_files_with_oid(&to_commit.tree, Path::new(""))
             .await?;
+        self.check_case_collisions(to_files.keys())?;
 
         let mut stats = CheckoutStats {
             files_added: 0,
@@ -607,9 +661,8 @@ impl<'a> CheckoutManager<'a> {
         };
 
         // Process files in target tree
+        let mut to_write = Vec::new();
         for (path, (to_oid, mode)) in &to_files {
-            let full_path = self.repo_root.join(path);
-
             match from_files.get(path) {
                 Some((from_oid, _)) if from_oid == to_oid => {
                     // File unchanged - skip
@@ -618,18 +671,19 @@ impl<'a> CheckoutManager<'a> {
                 }
                 Some(_) => {
                     // File modified - update it
-                    self.checkout_single_file(&full_path, to_oid, *mode).await?;
+                    to_write.push((path.clone(), *to_oid, *mode));
                     stats.files_modified += 1;
                     debug!("Modified: {}", path.display());
                 }
                 None => {
                     // File added - create it
-                    self.checkout_single_file(&full_path, to_oid, *mode).await?;
+                    to_write.push((path.clone(), *to_oid, *mode));
                     stats.files_added += 1;
                     debug!("Added: {}", path.display());
                 }
             }
         }
+        self.materialize(&PrefetchManifest::new(to_write)).await?;
 
         // Delete files not in target tree
         for path in from_files.keys() {
@@ -661,6 +715,192 @@ impl<'a> CheckoutManager<'a> {
         Ok(stats)
     }
 
+    /// Find working-tree files whose local changes would be lost by a checkout
+    ///
+    /// Only paths that differ between the current and target trees are
+    /// considered, since checkout leaves every other path untouched. A path is
+    /// reported when:
+    /// - it is tracked in the current tree and its working-tree content or its
+    ///   staged index entry no longer matches the committed content, or
+    /// - it is untracked but exists on disk with content that differs from the
+    ///   file the target tree would write there.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_commit_oid` - The commit currently checked out (`None` for an unborn branch)
+    /// * `to_commit_oid` - The commit about to be checked out
+    /// * `index` - The staging area, whose entries count as local changes
+    ///
+    /// # Returns
+    ///
+    /// Sorted list of paths (relative to the repository root) that would be overwritten
+    pub async fn find_overwritten_changes(
+        &self,
+        from_commit_oid: Option<&Oid>,
+        to_commit_oid: &Oid,
+        index: &Index,
+    ) -> Result<Vec<PathBuf>> {
+        let from_files = match from_commit_oid {
+            Some(oid) => {
+                let commit = Commit::read(self.odb, oid).await?;
+                self.get_tree_files_with_oid(&commit.tree, Path::new(""))
+                    .await?
+            }
+            None => HashMap::new(),
+        };
+        let to_commit = Commit::read(self.odb, to_commit_oid).await?;
+        let to_files = self
+            .get_tree_files_with_oid(&to_commit.tree, Path::new(""))
+            .await?;
+
+        let mut conflicts = Vec::new();
+
+        for (path, (from_oid, _)) in &from_files {
+            // Paths identical in both trees are not touched by checkout
+            if to_files.get(path).map(|(oid, _)| oid) == Some(from_oid) {
+                continue;
+            }
+
+            let staged_change = index
+                .get_entry(path)
+                .is_some_and(|entry| entry.oid != *from_oid);
+            let worktree_change = match self.working_file_oid(path)? {
+                Some(oid) => oid != *from_oid,
+                // Locally deleted: nothing left on disk to lose
+                None => false,
+            };
+
+            if staged_change || worktree_change {
+                conflicts.push(path.clone());
+            }
+        }
+
+        for (path, (to_oid, _)) in &to_files {
+            if from_files.contains_key(path) {
+                continue;
+            }
+
+            // Untracked file in the way of a file the target would create
+            if let Some(oid) = self.working_file_oid(path)? {
+                if oid != *to_oid {
+                    conflicts.push(path.clone());
+                }
+            }
+        }
+
+        conflicts.sort();
+        debug!(
+            "Checkout would overwrite {} local change(s)",
+            conflicts.len()
+        );
+        Ok(conflicts)
+    }
+
+    /// Forced checkout - materialize the target commit exactly
+    ///
+    /// Performs a differential checkout and then restores every target file
+    /// whose working-tree content still differs from the commit, discarding
+    /// local modifications. Untracked files outside the target tree are kept.
+    pub async fn checkout_force(
+        &self,
+        from_commit_oid: &Oid,
+        to_commit_oid: &Oid,
+    ) -> Result<CheckoutStats> {
+        let mut stats = self.checkout_diff(from_commit_oid, to_commit_oid).await?;
+
+        let to_commit = Commit::read(self.odb, to_commit_oid).await?;
+        let to_files = self
+            .get_tree_files_with_oid(&to_commit.tree, Path::new(""))
+            .await?;
+
+        for (path, (oid, mode)) in &to_files {
+            if *mode == FileMode::Symlink {
+                continue;
+            }
+            if self.working_file_oid(path)?.as_ref() != Some(oid) {
+                let full_path = self.repo_root.join(path);
+                self.checkout_single_file(&full_path, oid, *mode).await?;
+                stats.files_modified += 1;
+                stats.files_unchanged = stats.files_unchanged.saturating_sub(1);
+                debug!("Discarded local changes: {}", path.display());
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Fail with [`StorageError::CaseCollision`] if two of `paths` differ
+    /// only by case and the working tree ignores case
+    ///
+    /// Such files would be written to the same file on disk, each one
+    /// silently replacing the one before. Checked before anything is written.
+    fn check_case_collisions<'p>(
+        &self,
+        paths: impl IntoIterator<Item = &'p PathBuf>,
+    ) -> Result<()> {
+        let case_insensitive = *self
+            .case_insensitive
+            .get_or_init(|| mediagit_storage::local::is_case_insensitive(&self.repo_root));
+        if !case_insensitive {
+            return Ok(());
+        }
+
+        // Sorted so the same pair is reported whichever order the map yields
+        let mut paths: Vec<&PathBuf> = paths.into_iter().collect();
+        paths.sort();
+        let mut seen: HashMap<String, &PathBuf> = HashMap::new();
+        for path in paths {
+            if let Some(existing) = seen.insert(path.to_string_lossy().to_lowercase(), path) {
+                return Err(StorageError::case_collision(
+                    path.to_string_lossy().replace('\\', "/"),
+                    existing.to_string_lossy().replace('\\', "/"),
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Hash a working-tree file, returning `None` if it does not exist
+    ///
+    /// Text paths are hashed after line-ending normalization so a file checked
+    /// out with CRLF endings still matches its LF-normalized blob.
+    fn working_file_oid(&self, path: &Path) -> Result<Option<Oid>> {
+        let full_path = self.repo_root.join(path);
+        if !full_path.is_file() {
+            return Ok(None);
+        }
+        let oid = if self.attributes.may_convert(path, &full_path)? {
+            let data = fs::read(&full_path)
+                .with_context(|| format!("Failed to read file: {}", full_path.display()))?;
+            Oid::hash(&self.attributes.clean(path, &data))
+        } else {
+            Oid::from_file(&full_path)
+                .with_context(|| format!("Failed to hash file: {}", full_path.display()))?
+        };
+        Ok(Some(oid))
+    }
+
+    /// Write a regular file's blob to the working tree
+    ///
+    /// Content is streamed straight to disk; text paths covered by
+    /// `.mediagitattributes` are then read back so their line endings can be
+    /// converted, while binary media is only sniffed.
+    async fn write_blob(&self, rel_path: &Path, full_path: &Path, oid: &Oid) -> Result<()> {
+        self.odb.read_to_file(oid, full_path).await?;
+        if !self.attributes.may_convert(rel_path, full_path)? {
+            return Ok(());
+        }
+
+        let data = fs::read(full_path)
+            .with_context(|| format!("Failed to read file: {}", full_path.display()))?;
+        if self.attributes.checkout_eol(rel_path, &data) != Some(EolStyle::Crlf) {
+            return Ok(());
+        }
+        fs::write(full_path, convert_lf_to_crlf(&data))
+            .with_context(|| format!("Failed to write file: {}", full_path.display()))
+    }
+
     /// Get all files from a tree with their OIDs and modes
     ///
     /// Returns a map of path -> (OID, FileMode) for all files in the tree.
@@ -673,6 +913,10 @@ impl<'a> CheckoutManager<'a> {
         Box<dyn std::future::Future<Output = Result<HashMap<PathBuf, (Oid, FileMode)>>> + 'b>,
     > {
         Box::pin(async move {
+            self.odb
+                .tree_limits()
+                .check_depth(prefix.components().count())
+                .with_context(|| format!("Cannot walk {}", prefix.display()))?;
             let tree = Tree::read(self.odb, tree_oid).await?;
             let mut files = HashMap::new();
 
@@ -710,7 +954,11 @@ impl<'a> CheckoutManager<'a> {
             .read(oid)
             .await
             .with_context(|| format!("Failed to read blob: {}", oid))?;
+        self.write_file(full_path, mode, blob_data).await
+    }
 
+    /// Write a fetched blob to `full_path` as a file of the given mode
+    async fn write_file(&self, full_path: &Path, mode: FileMode, blob_data: Vec<u8>) -> Result<()> {
         // Ensure parent directory exists
         if let Some(parent) = full_path.parent() {
             fs::create_dir_all(parent)
@@ -719,18 +967,14 @@ impl<'a> CheckoutManager<'a> {
 
         match mode {
             FileMode::Regular | FileMode::Executable => {
+                // Restore configured line endings for text paths
+                let rel_path = full_path.strip_prefix(&self.repo_root).unwrap_or(full_path);
+                let blob_data = self.attributes.smudge(rel_path, blob_data);
+
                 // Write file
                 fs::write(full_path, &blob_data)
                     .with_context(|| format!("Failed to write file: {}", full_path.display()))?;
-
-                // Set executable permission if needed
-                #[cfg(unix)]
-                if mode == FileMode::Executable {
-                    use std::os::unix::fs::PermissionsExt;
-                    let mut perms = fs::metadata(full_path)?.permissions();
-                    perms.set_mode(0o755);
-                    fs::set_permissions(full_path, perms)?;
-                }
+                Self::set_executable(full_path, mode)?;
             }
             FileMode::Symlink => {
                 let target =
@@ -758,6 +1002,103 @@ impl<'a> CheckoutManager<'a> {
 
         Ok(())
     }
+
+    /// Stream a chunked blob to `full_path` without holding it in memory
+    async fn stream_file(&self, full_path: &Path, oid: &Oid, mode: FileMode) -> Result<()> {
+        if mode == FileMode::Symlink {
+            return self.checkout_single_file(full_path, oid, mode).await;
+        }
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let rel_path = full_path.strip_prefix(&self.repo_root).unwrap_or(full_path);
+        self.write_blob(rel_path, full_path, oid).await?;
+        Self::set_executable(full_path, mode)
+    }
+
+    /// Mark `full_path` executable if `mode` says so
+    #[allow(unused_variables)]
+    fn set_executable(full_path: &Path, mode: FileMode) -> Result<()> {
+        #[cfg(unix)]
+        if mode == FileMode::Executable {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(full_path)?.permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(full_path, perms)?;
+        }
+        Ok(())
+    }
+}
+
+/// Blobs a checkout needs, ordered for fetching
+///
+/// Each blob appears once with every path it is written to. Entries are
+/// ordered by directory, then by path within it, so files that are used
+/// together arrive together. Blob sizes are not known until they are
+/// fetched; large blobs are stored chunked and streamed to disk as soon as
+/// their turn comes, without waiting in memory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrefetchManifest {
+    entries: Vec<PrefetchEntry>,
+}
+
+/// One blob of a [`PrefetchManifest`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefetchEntry {
+    /// The blob to fetch
+    pub oid: Oid,
+    /// Working-tree paths (relative to the repository root) it is written to
+    pub paths: Vec<(PathBuf, FileMode)>,
+}
+
+impl PrefetchManifest {
+    /// Build a manifest from the files to write, as `(path, blob, mode)`
+    pub fn new(files: Vec<(PathBuf, Oid, FileMode)>) -> Self {
+        // Group paths by blob, placing each blob at its first path in
+        // directory order
+        let mut by_dir: BTreeMap<(PathBuf, PathBuf), Oid> = BTreeMap::new();
+        let mut paths: HashMap<Oid, Vec<(PathBuf, FileMode)>> = HashMap::new();
+        let mut files = files;
+        files.sort_by(|a, b| (a.0.parent(), &a.0).cmp(&(b.0.parent(), &b.0)));
+        for (path, oid, mode) in files {
+            let blob_paths = paths.entry(oid).or_default();
+            if blob_paths.is_empty() {
+                let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+                by_dir.insert((dir, path.clone()), oid);
+            }
+            blob_paths.push((path, mode));
+        }
+
+        let entries = by_dir
+            .into_values()
+            .map(|oid| PrefetchEntry {
+                oid,
+                paths: paths.remove(&oid).unwrap_or_default(),
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// The blobs in fetch order
+    pub fn oids(&self) -> Vec<Oid> {
+        self.entries.iter().map(|e| e.oid).collect()
+    }
+
+    /// The entries in fetch order
+    pub fn entries(&self) -> &[PrefetchEntry] {
+        &self.entries
+    }
+
+    /// Number of distinct blobs
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there is nothing to fetch
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
 }
 
 /// Statistics from a differential checkout operation
@@ -828,7 +1169,7 @@ mod tests {
         let commit_oid = commit.write(&odb).await?;
 
         // Checkout the commit
-        let checkout_mgr = CheckoutManager::new(&odb, repo_root);
+        let checkout_mgr = CheckoutManager::new(&odb, repo_root)?;
         let files_updated = checkout_mgr.checkout_commit(&commit_oid).await?;
 
         assert_eq!(files_updated, 1);
@@ -843,6 +1184,78 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_unreadable_attributes_fail_checkout() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let repo_root = temp_dir.path();
+        let storage_path = repo_root.join(".mediagit");
+        fs::create_dir_all(&storage_path)?;
+        // Present but not a readable file
+        fs::create_dir_all(repo_root.join(".mediagitattributes"))?;
+
+        let storage = Arc::new(LocalBackend::new(&storage_path).await?);
+        let odb = ObjectDatabase::new(storage, 100);
+
+        let err = CheckoutManager::new(&odb, repo_root)
+            .err()
+            .expect("attributes that cannot be read are an error");
+        assert!(err.to_string().contains(".mediagitattributes"), "{}", err);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_checkout_case_collision() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let repo_root = temp_dir.path();
+        let storage_path = repo_root.join(".mediagit");
+        fs::create_dir_all(&storage_path)?;
+
+        let storage = Arc::new(LocalBackend::new(&storage_path).await?);
+        let odb = ObjectDatabase::new(storage, 100);
+
+        // Two files whose names differ only by case
+        let mut tree = Tree::new();
+        for (name, data) in [("Hero.png", b"hero v1"), ("hero.png", b"hero v2")] {
+            let blob_oid = odb.write(ObjectType::Blob, data).await?;
+            tree.add_entry(TreeEntry::new(
+                name.to_string(),
+                FileMode::Regular,
+                blob_oid,
+            ));
+        }
+        let tree_oid = tree.write(&odb).await?;
+        let commit = Commit::new(
+            tree_oid,
+            Signature::now("Test".to_string(), "test@example.com".to_string()),
+            Signature::now("Test".to_string(), "test@example.com".to_string()),
+            "Add hero".to_string(),
+        );
+        let commit_oid = commit.write(&odb).await?;
+
+        // A case-insensitive working tree refuses the checkout up front
+        let checkout_mgr = CheckoutManager::new(&odb, repo_root)?;
+        checkout_mgr.case_insensitive.set(true).unwrap();
+        let err = checkout_mgr.checkout_commit(&commit_oid).await.unwrap_err();
+        match err.downcast_ref::<StorageError>() {
+            Some(StorageError::CaseCollision { key, existing }) => {
+                assert_eq!(key, "hero.png");
+                assert_eq!(existing, "Hero.png");
+            }
+            other => panic!("expected a case collision, got {:?}", other),
+        }
+        assert!(!repo_root.join("Hero.png").exists());
+        assert!(checkout_mgr.checkout_fresh(&commit_oid).await.is_err());
+
+        // A case-sensitive one gets both files
+        let checkout_mgr = CheckoutManager::new(&odb, repo_root)?;
+        checkout_mgr.case_insensitive.set(false).unwrap();
+        assert_eq!(checkout_mgr.checkout_commit(&commit_oid).await?, 2);
+        assert_eq!(fs::read(repo_root.join("hero.png"))?, b"hero v2");
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_differential_checkout_same_commit() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -871,7 +1284,7 @@ mod tests {
         );
         let commit_oid = commit.write(&odb).await?;
 
-        let checkout_mgr = CheckoutManager::new(&odb, repo_root);
+        let checkout_mgr = CheckoutManager::new(&odb, repo_root)?;
 
         // Differential checkout same commit should complete instantly
         let stats = checkout_mgr.checkout_diff(&commit_oid, &commit_oid).await?;
@@ -943,7 +1356,7 @@ mod tests {
         let commit2_oid = commit2.write(&odb).await?;
 
         // First checkout commit1
-        let checkout_mgr = CheckoutManager::new(&odb, repo_root);
+        let checkout_mgr = CheckoutManager::new(&odb, repo_root)?;
         checkout_mgr.checkout_commit(&commit1_oid).await?;
 
         // Differential checkout to commit2
@@ -1014,7 +1427,7 @@ mod tests {
         let commit2_oid = commit2.write(&odb).await?;
 
         // First checkout commit1
-        let checkout_mgr = CheckoutManager::new(&odb, repo_root);
+        let checkout_mgr = CheckoutManager::new(&odb, repo_root)?;
         checkout_mgr.checkout_commit(&commit1_oid).await?;
         assert!(repo_root.join("a.txt").exists());
 
@@ -1036,6 +1449,199 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_checkout_prefetches_needed_blobs_in_one_batch() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let repo_root = temp_dir.path();
+        let storage = Arc::new(LocalBackend::new(repo_root.join(".mediagit")).await?);
+        let odb = ObjectDatabase::with_optimizations(
+            storage,
+            100,
+            Some(crate::ChunkStrategy::Fixed { size: 256 * 1024 }),
+            false,
+        );
+
+        let wood = odb.write(ObjectType::Blob, b"wood grain").await?;
+        let scene = odb.write(ObjectType::Blob, b"scene graph").await?;
+        let readme = odb.write(ObjectType::Blob, b"read me").await?;
+        let video_data: Vec<u8> = (0..2 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+        let video = odb
+            .write_chunked(ObjectType::Blob, &video_data, "intro.mp4")
+            .await?;
+
+        let mut textures = Tree::new();
+        for name in ["wood.png", "wood_copy.png"] {
+            textures.add_entry(TreeEntry::new(name.to_string(), FileMode::Regular, wood));
+        }
+        let mut tree = Tree::new();
+        tree.add_entry(TreeEntry::new(
+            "textures".to_string(),
+            FileMode::Directory,
+            textures.write(&odb).await?,
+        ));
+        tree.add_entry(TreeEntry::new(
+            "scene.blend".to_string(),
+            FileMode::Regular,
+            scene,
+        ));
+        tree.add_entry(TreeEntry::new(
+            "README.md".to_string(),
+            FileMode::Regular,
+            readme,
+        ));
+        tree.add_entry(TreeEntry::new(
+            "intro.mp4".to_string(),
+            FileMode::Regular,
+            video,
+        ));
+        let author = Signature::now("Test".to_string(), "test@example.com".to_string());
+        let commit = Commit::new(
+            tree.write(&odb).await?,
+            author.clone(),
+            author,
+            "Assets".to_string(),
+        );
+        let commit_oid = commit.write(&odb).await?;
+
+        // README.md is already up to date, so its blob is not needed
+        fs::write(repo_root.join("README.md"), b"read me")?;
+
+        let checkout_mgr = CheckoutManager::new(&odb, repo_root)?;
+        let manifest = checkout_mgr.prefetch_manifest(&commit_oid).await?;
+        let needed: HashSet<Oid> = manifest.oids().into_iter().collect();
+        assert_eq!(needed, HashSet::from([wood, scene, video]));
+        assert_eq!(manifest.len(), 3);
+        // Top-level files come before those in textures/
+        assert_eq!(manifest.entries()[2].oid, wood);
+        assert_eq!(manifest.entries()[2].paths.len(), 2);
+
+        let before = odb.metrics().await;
+        let files_updated = checkout_mgr.checkout_commit(&commit_oid).await?;
+        let after = odb.metrics().await;
+
+        assert_eq!(after.prefetch_batches - before.prefetch_batches, 1);
+        assert_eq!(after.objects_prefetched - before.objects_prefetched, 3);
+        assert_eq!(files_updated, 4);
+        assert_eq!(
+            fs::read(repo_root.join("textures/wood.png"))?,
+            b"wood grain"
+        );
+        assert_eq!(
+            fs::read(repo_root.join("textures/wood_copy.png"))?,
+            b"wood grain"
+        );
+        assert_eq!(fs::read(repo_root.join("scene.blend"))?, b"scene graph");
+        assert_eq!(fs::read(repo_root.join("intro.mp4"))?, video_data);
+        Ok(())
+    }
+
+    /// Write a commit containing a single `shared.txt` file with the given content
+    async fn commit_single_file(odb: &ObjectDatabase, content: &[u8]) -> Result<Oid> {
+        let blob_oid = odb.write(ObjectType::Blob, content).await?;
+        let mut tree = Tree::new();
+        tree.add_entry(TreeEntry::new(
+            "shared.txt".to_string(),
+            FileMode::Regular,
+            blob_oid,
+        ));
+        let tree_oid = tree.write(odb).await?;
+
+        let commit = Commit::new(
+            tree_oid,
+            Signature::now("Test".to_string(), "test@example.com".to_string()),
+            Signature::now("Test".to_string(), "test@example.com".to_string()),
+            "commit".to_string(),
+        );
+        commit.write(odb).await
+    }
+
+    #[tokio::test]
+    async fn test_overwritten_changes_detects_dirty_overlapping_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let repo_root = temp_dir.path();
+        let storage_path = repo_root.join(".mediagit");
+        fs::create_dir_all(&storage_path)?;
+
+        let storage = Arc::new(LocalBackend::new(&storage_path).await?);
+        let odb = ObjectDatabase::new(storage, 100);
+
+        let commit1_oid = commit_single_file(&odb, b"version 1").await?;
+        let commit2_oid = commit_single_file(&odb, b"version 2").await?;
+
+        let checkout_mgr = CheckoutManager::new(&odb, repo_root)?;
+        checkout_mgr.checkout_commit(&commit1_oid).await?;
+
+        // Clean working tree: nothing would be lost
+        let clean = checkout_mgr
+            .find_overwritten_changes(Some(&commit1_oid), &commit2_oid, &Index::new())
+            .await?;
+        assert!(clean.is_empty());
+
+        // Unsaved edit to a file that differs between the commits
+        fs::write(repo_root.join("shared.txt"), b"local edit")?;
+        let dirty = checkout_mgr
+            .find_overwritten_changes(Some(&commit1_oid), &commit2_oid, &Index::new())
+            .await?;
+        assert_eq!(dirty, vec![PathBuf::from("shared.txt")]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_overwritten_changes_detects_untracked_file_in_the_way() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let repo_root = temp_dir.path();
+        let storage_path = repo_root.join(".mediagit");
+        fs::create_dir_all(&storage_path)?;
+
+        let storage = Arc::new(LocalBackend::new(&storage_path).await?);
+        let odb = ObjectDatabase::new(storage, 100);
+
+        let target_oid = commit_single_file(&odb, b"tracked on target").await?;
+        fs::write(repo_root.join("shared.txt"), b"untracked local file")?;
+
+        let checkout_mgr = CheckoutManager::new(&odb, repo_root)?;
+        let dirty = checkout_mgr
+            .find_overwritten_changes(None, &target_oid, &Index::new())
+            .await?;
+        assert_eq!(dirty, vec![PathBuf::from("shared.txt")]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_checkout_force_discards_local_changes() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let repo_root = temp_dir.path();
+        let storage_path = repo_root.join(".mediagit");
+        fs::create_dir_all(&storage_path)?;
+
+        let storage = Arc::new(LocalBackend::new(&storage_path).await?);
+        let odb = ObjectDatabase::new(storage, 100);
+
+        let commit1_oid = commit_single_file(&odb, b"version 1").await?;
+        let commit2_oid = commit_single_file(&odb, b"version 2").await?;
+
+        let checkout_mgr = CheckoutManager::new(&odb, repo_root)?;
+        checkout_mgr.checkout_commit(&commit1_oid).await?;
+        fs::write(repo_root.join("shared.txt"), b"local edit")?;
+
+        checkout_mgr
+            .checkout_force(&commit1_oid, &commit2_oid)
+            .await?;
+        assert_eq!(fs::read(repo_root.join("shared.txt"))?, b"version 2");
+
+        // Forcing onto the same commit still restores committed content
+        fs::write(repo_root.join("shared.txt"), b"another edit")?;
+        let stats = checkout_mgr
+            .checkout_force(&commit2_oid, &commit2_oid)
+            .await?;
+        assert_eq!(stats.files_modified, 1);
+        assert_eq!(fs::read(repo_root.join("shared.txt"))?, b"version 2");
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_differential_checkout_stats() -> Result<()> {
         let stats = CheckoutStats {
@@ -16,6 +16,7 @@
 //! A Commit object captures a moment in time with metadata about changes,
 //! references to the tree snapshot, and parent commits for history tracking.
 
+use crate::format::ObjectFormat;
 use crate::{ObjectType, Oid};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -240,18 +241,32 @@ impl Commit {
 
     /// Serialize commit to bytes
     pub fn serialize(&self) -> anyhow::Result<Vec<u8>> {
-        crate::format::serialize(self)
-            .map_err(|e| anyhow::anyhow!("Commit serialization failed: {}", e))
+        self.serialize_as(ObjectFormat::Native)
     }
 
-    /// Deserialize commit from bytes
+    /// Serialize commit to bytes in the given object format
+    pub fn serialize_as(&self, format: ObjectFormat) -> anyhow::Result<Vec<u8>> {
+        match format {
+            ObjectFormat::Native => crate::format::serialize(self),
+            ObjectFormat::Git => crate::git_format::encode_commit(self),
+        }
+        .map_err(|e| anyhow::anyhow!("Commit serialization failed: {}", e))
+    }
+
+    /// Deserialize commit from bytes in either object format
     pub fn deserialize(data: &[u8]) -> anyhow::Result<Self> {
-        crate::format::deserialize(data)
-            .map_err(|e| anyhow::anyhow!("Commit deserialization failed: {}", e))
+        if crate::git_format::is_commit(data) {
+            crate::git_format::decode_commit(data)
+        } else {
+            crate::format::deserialize_exact(data)
+        }
+        .map_err(|e| anyhow::anyhow!("Commit deserialization failed: {}", e))
     }
 
     /// Write commit to object database and return its OID
     ///
+    /// The commit is encoded in the database's [`ObjectFormat`].
+    ///
     /// # Arguments
     ///
     /// * `odb` - Object database instance
@@ -260,7 +275,7 @@ impl Commit {
     ///
     /// The OID of the written commit
     pub async fn write(&self, odb: &crate::ObjectDatabase) -> anyhow::Result<Oid> {
-        let data = self.serialize()?;
+        let data = self.serialize_as(odb.object_format())?;
         odb.write(ObjectType::Commit, &data).await
     }
 
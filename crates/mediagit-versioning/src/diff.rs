@@ -26,11 +26,20 @@
 //! - **Added**: File exists in target but not in source
 //! - **Deleted**: File exists in source but not in target
 //! - **Modified**: File exists in both but with different content
+//! - **Renamed**: A deleted file reappears under another path, with the same
+//!   or similar content (see [`TreeDiffer::detect_renames`])
 
-use crate::{ObjectDatabase, Oid, Tree, TreeEntry};
+use crate::similarity::{ObjectMetadata, SimilarityDetector};
+use crate::{FileMode, ObjectDatabase, ObjectType, Oid, Tree, TreeEntry};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
+
+/// Default limit on rename candidates (`diff.renameLimit`)
+pub const DEFAULT_RENAME_LIMIT: usize = 1000;
+
+/// Default minimum similarity for a rename of changed content
+pub const DEFAULT_RENAME_SIMILARITY: f64 = 0.5;
 
 /// Two-way tree diff result
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -58,6 +67,54 @@ pub struct ModifiedEntry {
     pub target: TreeEntry,
 }
 
+/// File moved from one path to another
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenamedEntry {
+    /// Entry at the old path
+    pub source: TreeEntry,
+
+    /// Entry at the new path
+    pub target: TreeEntry,
+
+    /// Content similarity (1.0 = unchanged content)
+    pub similarity: f64,
+}
+
+/// Rename detection settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenameOptions {
+    /// Largest number of deleted or added files compared by similarity
+    ///
+    /// Similarity detection compares every deleted file with every added one,
+    /// so it is skipped when `deleted × added` exceeds `limit²`; exact renames
+    /// are still found. 0 means no limit.
+    pub limit: usize,
+
+    /// Minimum similarity (0.0-1.0) for changed content to count as a rename
+    pub min_similarity: f64,
+}
+
+impl Default for RenameOptions {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_RENAME_LIMIT,
+            min_similarity: DEFAULT_RENAME_SIMILARITY,
+        }
+    }
+}
+
+/// Renames found by [`TreeDiffer::detect_renames`]
+#[derive(Debug, Clone, Default)]
+pub struct RenameDetection {
+    /// Detected renames, exact ones first
+    pub renamed: Vec<RenamedEntry>,
+
+    /// Set when similarity detection was skipped because of the limit
+    ///
+    /// Holds the `diff.renameLimit` needed to run it.
+    pub limit_needed: Option<usize>,
+}
+
 /// Three-way diff result for merge operations
 #[derive(Debug, Clone)]
 pub struct ThreeWayDiff {
@@ -347,6 +404,119 @@ impl TreeDiffer {
         })
     }
 
+    /// Pair deleted and added files of `diff` into renames
+    ///
+    /// Files with identical content are always paired. The remaining ones are
+    /// compared by content similarity unless there are more candidates than
+    /// `options.limit` allows, in which case only exact renames are reported
+    /// and [`RenameDetection::limit_needed`] says how far to raise the limit.
+    /// Paired entries are removed from `diff.deleted` and `diff.added`.
+    pub async fn detect_renames(
+        &self,
+        diff: &mut TreeDiff,
+        options: &RenameOptions,
+    ) -> anyhow::Result<RenameDetection> {
+        let mut detection = RenameDetection::default();
+
+        // Exact renames: same content under a new path
+        let mut deleted_by_oid: HashMap<Oid, Vec<usize>> = HashMap::new();
+        for (i, entry) in diff.deleted.iter().enumerate() {
+            if entry.mode != FileMode::Directory {
+                deleted_by_oid.entry(entry.oid).or_default().push(i);
+            }
+        }
+        let mut paired_deleted = vec![false; diff.deleted.len()];
+        let mut paired_added = vec![false; diff.added.len()];
+        for (i, entry) in diff.added.iter().enumerate() {
+            if entry.mode == FileMode::Directory {
+                continue;
+            }
+            if let Some(source) = deleted_by_oid.get_mut(&entry.oid).and_then(Vec::pop) {
+                paired_deleted[source] = true;
+                paired_added[i] = true;
+                detection.renamed.push(RenamedEntry {
+                    source: diff.deleted[source].clone(),
+                    target: entry.clone(),
+                    similarity: 1.0,
+                });
+            }
+        }
+
+        // Similarity renames: every remaining deleted file against every added one
+        let sources: Vec<usize> = (0..diff.deleted.len())
+            .filter(|&i| !paired_deleted[i] && diff.deleted[i].mode != FileMode::Directory)
+            .collect();
+        let targets: Vec<usize> = (0..diff.added.len())
+            .filter(|&i| !paired_added[i] && diff.added[i].mode != FileMode::Directory)
+            .collect();
+        let candidates = sources.len().max(targets.len());
+        if options.limit > 0
+            && sources.len().saturating_mul(targets.len())
+                > options.limit.saturating_mul(options.limit)
+        {
+            warn!(
+                candidates,
+                limit = options.limit,
+                "Too many files for similarity rename detection, only exact renames detected"
+            );
+            detection.limit_needed = Some(candidates);
+        } else if !sources.is_empty() && !targets.is_empty() {
+            let mut remaining = Vec::with_capacity(sources.len());
+            for &i in &sources {
+                remaining.push((i, self.metadata(&diff.deleted[i]).await?));
+            }
+
+            for &i in &targets {
+                let target = self.metadata(&diff.added[i]).await?;
+                let mut detector = SimilarityDetector::new(remaining.len());
+                for (_, metadata) in &remaining {
+                    detector.add_object(metadata.clone());
+                }
+                let Some((source_oid, score)) =
+                    detector.find_similar_with_size_ratio(&target, options.min_similarity, 0.0)
+                else {
+                    continue;
+                };
+                let Some(pos) = remaining.iter().position(|(_, m)| m.oid == source_oid) else {
+                    continue;
+                };
+                let (source, _) = remaining.swap_remove(pos);
+                paired_deleted[source] = true;
+                paired_added[i] = true;
+                detection.renamed.push(RenamedEntry {
+                    source: diff.deleted[source].clone(),
+                    target: diff.added[i].clone(),
+                    similarity: score.score,
+                });
+            }
+        }
+
+        let mut paired = paired_deleted.into_iter();
+        diff.deleted.retain(|_| !paired.next().unwrap_or(false));
+        let mut paired = paired_added.into_iter();
+        diff.added.retain(|_| !paired.next().unwrap_or(false));
+
+        debug!(
+            renamed = detection.renamed.len(),
+            limited = detection.limit_needed.is_some(),
+            "Rename detection complete"
+        );
+        Ok(detection)
+    }
+
+    /// Sampled content of a file for similarity comparison
+    async fn metadata(&self, entry: &TreeEntry) -> anyhow::Result<ObjectMetadata> {
+        let data = self.odb.read(&entry.oid).await?;
+        let mut metadata = ObjectMetadata::new(
+            entry.oid,
+            data.len(),
+            ObjectType::Blob,
+            Some(entry.name.clone()),
+        );
+        metadata.generate_samples(&data);
+        Ok(metadata)
+    }
+
     /// Check if two trees are identical
     pub async fn are_trees_equal(&self, oid1: &Oid, oid2: &Oid) -> anyhow::Result<bool> {
         if oid1 == oid2 {
@@ -374,6 +544,124 @@ mod tests {
         tree.write(odb).await.unwrap()
     }
 
+    /// Like `create_tree`, but also stores the file contents
+    async fn write_tree(odb: &Arc<ObjectDatabase>, entries: Vec<(&str, &[u8])>) -> Oid {
+        for (_, content) in &entries {
+            odb.write(ObjectType::Blob, content).await.unwrap();
+        }
+        create_tree(odb, entries).await
+    }
+
+    /// 16KB of content with an edit near the end
+    fn texture(seed: u8, edit: bool) -> Vec<u8> {
+        let mut data: Vec<u8> = (0..16 * 1024u32)
+            .map(|i| (i.wrapping_mul(31) as u8) ^ seed)
+            .collect();
+        if edit {
+            data[15_000] ^= 0xff;
+        }
+        data
+    }
+
+    #[tokio::test]
+    async fn test_detect_renames() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = Arc::new(ObjectDatabase::new(storage, 100));
+        let differ = TreeDiffer::new(odb.clone());
+
+        let (wood, wood_edited) = (texture(1, false), texture(1, true));
+        let source = write_tree(
+            &odb,
+            vec![
+                ("hero.psd", b"layered art"),
+                ("wood.png", &wood),
+                ("notes.txt", b"todo"),
+            ],
+        )
+        .await;
+        let target = write_tree(
+            &odb,
+            vec![
+                ("art/hero.psd", b"layered art"),
+                ("textures/wood.png", &wood_edited),
+                ("readme.txt", b"unrelated"),
+            ],
+        )
+        .await;
+
+        let mut diff = differ.diff_trees(&source, &target).await.unwrap();
+        let renames = differ
+            .detect_renames(&mut diff, &RenameOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(renames.limit_needed, None);
+        assert_eq!(renames.renamed.len(), 2);
+        assert_eq!(renames.renamed[0].source.name, "hero.psd");
+        assert_eq!(renames.renamed[0].target.name, "art/hero.psd");
+        assert_eq!(renames.renamed[0].similarity, 1.0);
+        assert_eq!(renames.renamed[1].source.name, "wood.png");
+        assert_eq!(renames.renamed[1].target.name, "textures/wood.png");
+        assert!(renames.renamed[1].similarity < 1.0);
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].name, "readme.txt");
+        assert_eq!(diff.deleted.len(), 1);
+        assert_eq!(diff.deleted[0].name, "notes.txt");
+    }
+
+    #[tokio::test]
+    async fn test_rename_limit_falls_back_to_exact_renames() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = Arc::new(ObjectDatabase::new(storage, 100));
+        let differ = TreeDiffer::new(odb.clone());
+
+        let (wood, metal) = (texture(1, false), texture(2, false));
+        let (wood_edited, metal_edited) = (texture(1, true), texture(2, true));
+        let source = write_tree(
+            &odb,
+            vec![
+                ("hero.psd", b"layered art"),
+                ("wood.png", &wood),
+                ("metal.png", &metal),
+            ],
+        )
+        .await;
+        let target = write_tree(
+            &odb,
+            vec![
+                ("art/hero.psd", b"layered art"),
+                ("textures/wood.png", &wood_edited),
+                ("textures/metal.png", &metal_edited),
+            ],
+        )
+        .await;
+        let limited = RenameOptions {
+            limit: 1,
+            ..RenameOptions::default()
+        };
+
+        // Two deleted × two added files exceeds a limit of 1
+        let mut diff = differ.diff_trees(&source, &target).await.unwrap();
+        let renames = differ.detect_renames(&mut diff, &limited).await.unwrap();
+        assert_eq!(renames.limit_needed, Some(2));
+        assert_eq!(renames.renamed.len(), 1);
+        assert_eq!(renames.renamed[0].target.name, "art/hero.psd");
+        assert_eq!(diff.added.len(), 2);
+        assert_eq!(diff.deleted.len(), 2);
+
+        // Raising the limit as suggested finds the rest
+        let needed = RenameOptions {
+            limit: 2,
+            ..RenameOptions::default()
+        };
+        let mut diff = differ.diff_trees(&source, &target).await.unwrap();
+        let renames = differ.detect_renames(&mut diff, &needed).await.unwrap();
+        assert_eq!(renames.limit_needed, None);
+        assert_eq!(renames.renamed.len(), 3);
+        assert!(diff.added.is_empty() && diff.deleted.is_empty());
+    }
+
     #[tokio::test]
     async fn test_diff_identical_trees() {
         let storage = Arc::new(MockBackend::new());
@@ -25,3 +25,37 @@ pub fn serialize<T: serde::Serialize>(value: &T) -> anyhow::Result<Vec<u8>> {
 pub fn deserialize<T: for<'de> serde::Deserialize<'de>>(data: &[u8]) -> anyhow::Result<T> {
     postcard::from_bytes(data).map_err(|e| anyhow::anyhow!("Deserialization error: {}", e))
 }
+
+/// Deserialize a value from postcard bytes, rejecting trailing bytes
+///
+/// Used where the bytes must be exactly one encoded value, such as commit
+/// and tree objects, so arbitrary data that merely starts with a valid
+/// encoding is not accepted.
+pub fn deserialize_exact<T: for<'de> serde::Deserialize<'de>>(data: &[u8]) -> anyhow::Result<T> {
+    let (value, rest) = postcard::take_from_bytes(data)
+        .map_err(|e| anyhow::anyhow!("Deserialization error: {}", e))?;
+    if !rest.is_empty() {
+        anyhow::bail!("Deserialization error: {} trailing bytes", rest.len());
+    }
+    Ok(value)
+}
+
+/// Byte layout used when writing commit and tree objects
+///
+/// Reading accepts either layout, so the setting can change at any time and a
+/// repository may hold objects in both. Blobs are unaffected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ObjectFormat {
+    /// Compact postcard encoding
+    #[default]
+    Native,
+    /// Git's commit and tree layout, readable by Git object parsers
+    ///
+    /// Object IDs stay SHA-256 of the object bytes alone (no Git
+    /// `"<type> <size>\0"` header), written as 64 hex digits like a Git
+    /// repository using the sha256 object format. Tree entries point at
+    /// MediaGit blobs, which may be chunked, delta-encoded or compressed, so
+    /// Git can walk the history but not read file contents. Signature
+    /// timestamps are kept to whole seconds.
+    Git,
+}
@@ -18,6 +18,9 @@
 //! - **Reference validation**: Ensure all refs point to valid commits
 //! - **Missing object detection**: Find referenced but missing objects
 //! - **Commit graph validation**: Verify parent and tree relationships
+//! - **Connectivity-only mode**: Walk the reachability graph without hashing blob content
+//! - **Transfer checks**: Verify only the objects sent or received by a push
+//! - **Compression statistics**: Optionally report stored vs logical size per object category
 //! - **Repair mode**: Automatically fix common corruption issues
 //!
 //! # Examples
@@ -47,10 +50,12 @@
 //! ```
 
 use crate::odb::ObjectDatabase;
-use crate::{Commit, Oid, Ref, RefType};
+use crate::{Commit, Oid, Ref, RefDatabase, RefType, Tree, TreeLimits};
+use futures::TryStreamExt;
+use mediagit_compression::{CompressionAlgorithm, ObjectCategory, ObjectType};
 use mediagit_storage::StorageBackend;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use tracing::{debug, info, warn};
 
@@ -82,6 +87,97 @@ pub enum IssueCategory {
     InvalidFormat,
     /// Orphaned reference
     OrphanedRef,
+    /// Recorded object type disagrees with its content or how it is referenced
+    TypeMismatch,
+}
+
+/// Whether object content was hashed during a check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContentIntegrity {
+    /// Every object's content was hashed and compared to its OID
+    Verified,
+    /// Only the first `max_objects` objects were hashed
+    Partial,
+    /// Content was not hashed (e.g. connectivity-only mode)
+    NotChecked,
+}
+
+/// Compression details of a single object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectCompressionInfo {
+    /// Object ID
+    pub oid: Oid,
+
+    /// Category detected from the object's content
+    pub category: ObjectCategory,
+
+    /// Algorithm the object is stored with
+    pub algorithm: CompressionAlgorithm,
+
+    /// Size in storage (compressed)
+    pub stored_size: u64,
+
+    /// Size after decompression
+    pub logical_size: u64,
+}
+
+/// Compression totals for one object category
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CategoryCompressionStats {
+    /// Number of objects in this category
+    pub objects: u64,
+
+    /// Total size in storage
+    pub stored_bytes: u64,
+
+    /// Total size after decompression
+    pub logical_bytes: u64,
+
+    /// Number of objects stored with each algorithm
+    pub algorithms: BTreeMap<CompressionAlgorithm, u64>,
+}
+
+impl CategoryCompressionStats {
+    /// Stored size as a fraction of logical size (1.0 = no savings)
+    pub fn ratio(&self) -> f64 {
+        if self.logical_bytes == 0 {
+            1.0
+        } else {
+            self.stored_bytes as f64 / self.logical_bytes as f64
+        }
+    }
+}
+
+/// Compression statistics gathered while checking objects
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompressionStats {
+    /// Per-object details
+    pub objects: Vec<ObjectCompressionInfo>,
+
+    /// Totals aggregated by object category
+    pub by_category: HashMap<ObjectCategory, CategoryCompressionStats>,
+}
+
+impl CompressionStats {
+    /// Record one object and add it to its category totals
+    pub fn record(&mut self, info: ObjectCompressionInfo) {
+        let totals = self.by_category.entry(info.category).or_default();
+        totals.objects += 1;
+        totals.stored_bytes += info.stored_size;
+        totals.logical_bytes += info.logical_size;
+        *totals.algorithms.entry(info.algorithm).or_insert(0) += 1;
+        self.objects.push(info);
+    }
+
+    /// Total size in storage across all categories
+    pub fn total_stored_bytes(&self) -> u64 {
+        self.by_category.values().map(|c| c.stored_bytes).sum()
+    }
+
+    /// Total size after decompression across all categories
+    pub fn total_logical_bytes(&self) -> u64 {
+        self.by_category.values().map(|c| c.logical_bytes).sum()
+    }
 }
 
 /// An issue detected during FSCK
@@ -161,6 +257,12 @@ pub struct FsckReport {
 
     /// Dangling objects (unreferenced)
     pub dangling_objects: u64,
+
+    /// Whether object content integrity was verified
+    pub content_integrity: ContentIntegrity,
+
+    /// Compression statistics (only when requested in the options)
+    pub compression_stats: Option<CompressionStats>,
 }
 
 impl FsckReport {
@@ -174,6 +276,8 @@ impl FsckReport {
             broken_refs: 0,
             missing_objects: 0,
             dangling_objects: 0,
+            content_integrity: ContentIntegrity::NotChecked,
+            compression_stats: None,
         }
     }
 
@@ -247,6 +351,13 @@ pub struct FsckOptions {
     /// Maximum objects to check (0 = unlimited)
     pub max_objects: u64,
 
+    /// Walk commits, trees and blobs checking only that every reachable
+    /// object exists and commits/trees parse; blob content is not hashed
+    pub connectivity_only: bool,
+
+    /// Collect per-object compression statistics while checking objects
+    pub compression_stats: bool,
+
     /// Verbose output
     pub verbose: bool,
 }
@@ -259,6 +370,8 @@ impl Default for FsckOptions {
             check_connectivity: true,
             check_dangling: false, // Expensive operation
             max_objects: 0,
+            connectivity_only: false,
+            compression_stats: false,
             verbose: false,
         }
     }
@@ -273,6 +386,8 @@ impl FsckOptions {
             check_connectivity: true,
             check_dangling: true,
             max_objects: 0,
+            connectivity_only: false,
+            compression_stats: false,
             verbose: true,
         }
     }
@@ -285,9 +400,37 @@ impl FsckOptions {
             check_connectivity: false,
             check_dangling: false,
             max_objects: 0,
+            connectivity_only: false,
+            compression_stats: false,
             verbose: false,
         }
     }
+
+    /// Create options for a connectivity-only check
+    ///
+    /// Verifies that the reachability graph is intact without hashing object
+    /// content, so it is much faster than a full check on large repositories.
+    /// A corrupted but present blob is not detected in this mode.
+    pub fn connectivity_only() -> Self {
+        Self {
+            check_objects: false,
+            check_refs: true,
+            check_connectivity: true,
+            check_dangling: false,
+            max_objects: 0,
+            connectivity_only: true,
+            compression_stats: false,
+            verbose: false,
+        }
+    }
+
+    /// Also gather compression statistics from the objects being verified
+    ///
+    /// Only takes effect when objects are checked (not in connectivity-only mode).
+    pub fn with_compression_stats(mut self, enabled: bool) -> Self {
+        self.compression_stats = enabled;
+        self
+    }
 }
 
 /// FSCK integrity checker
@@ -296,6 +439,8 @@ pub struct FsckChecker {
     storage: Arc<dyn StorageBackend>,
     /// Object database for reading and verifying objects
     odb: Arc<ObjectDatabase>,
+    /// On-disk reference database; refs are read from storage when unset
+    refdb: Option<RefDatabase>,
 }
 
 impl FsckChecker {
@@ -306,9 +451,38 @@ impl FsckChecker {
         Self {
             storage,
             odb: Arc::new(odb),
+            refdb: None,
         }
     }
 
+    /// Bound the trees the check parses and walks; see [`TreeLimits`]
+    ///
+    /// Trees beyond the limits are reported as invalid instead of read.
+    pub fn with_tree_limits(mut self, limits: TreeLimits) -> Self {
+        self.odb = Arc::new((*self.odb).clone().with_tree_limits(limits));
+        self
+    }
+
+    /// Bound how large an object may decompress to while it is checked
+    ///
+    /// Objects past the limit are reported as unreadable; see
+    /// [`ObjectDatabase::with_max_decompressed_size`].
+    pub fn with_max_decompressed_size(mut self, max_decompressed_size: u64) -> Self {
+        self.odb = Arc::new(
+            (*self.odb)
+                .clone()
+                .with_max_decompressed_size(max_decompressed_size),
+        );
+        self
+    }
+
+    /// Read references from an on-disk reference database (e.g. `.mediagit`)
+    /// instead of the storage backend
+    pub fn with_ref_database(mut self, refdb: RefDatabase) -> Self {
+        self.refdb = Some(refdb);
+        self
+    }
+
     /// Run comprehensive integrity check
     ///
     /// # Arguments
@@ -339,8 +513,8 @@ impl FsckChecker {
         info!("Starting FSCK integrity check");
         let mut report = FsckReport::new();
 
-        // Step 1: Check object integrity
-        if options.check_objects {
+        // Step 1: Check object integrity (never in connectivity-only mode)
+        if options.check_objects && !options.connectivity_only {
             info!("Checking object integrity...");
             self.check_objects(&mut report, &options).await?;
         }
@@ -352,7 +526,10 @@ impl FsckChecker {
         }
 
         // Step 3: Check commit graph connectivity
-        if options.check_connectivity {
+        if options.connectivity_only {
+            info!("Checking reachability graph (connectivity only)...");
+            self.check_reachability(&mut report).await?;
+        } else if options.check_connectivity {
             info!("Checking commit graph connectivity...");
             self.check_connectivity(&mut report).await?;
         }
@@ -373,6 +550,138 @@ impl FsckChecker {
         Ok(report)
     }
 
+    /// Check only the objects of a transfer (Git's `transfer.fsckObjects`)
+    ///
+    /// Every object in `oids` is read and verified as [`check`](Self::check)
+    /// would, and everything a transferred commit or tree references must
+    /// exist, either in the transfer or already in storage, as must the
+    /// commits in `tips` that refs will be updated to. Objects outside the
+    /// transfer are only checked for existence and that they parse as what
+    /// they are referenced as.
+    pub async fn check_transfer(&self, oids: &[Oid], tips: &[Oid]) -> anyhow::Result<FsckReport> {
+        let mut report = FsckReport::new();
+
+        for tip in tips {
+            if !self.odb.exists(tip).await? {
+                report.add_issue(
+                    FsckIssue::new(
+                        IssueSeverity::Error,
+                        IssueCategory::MissingObject,
+                        format!("Commit {} is missing", tip),
+                    )
+                    .with_oid(*tip),
+                );
+            } else {
+                self.check_referenced_type(
+                    tip,
+                    crate::ObjectType::Commit,
+                    "Ref update",
+                    &mut report,
+                )
+                .await;
+            }
+        }
+
+        for oid in oids {
+            let Some(data) = self.verify_object(oid, &mut report).await? else {
+                continue;
+            };
+            report.objects_checked += 1;
+
+            match self.odb.object_type(oid).await? {
+                crate::ObjectType::Commit => {
+                    // A commit that doesn't parse was reported by verify_object
+                    let Ok(commit) = Commit::deserialize(&data) else {
+                        continue;
+                    };
+                    let owner = format!("Commit {}", oid);
+                    if !self.odb.exists(&commit.tree).await? {
+                        report.add_issue(
+                            FsckIssue::new(
+                                IssueSeverity::Error,
+                                IssueCategory::MissingObject,
+                                format!("Commit {} references missing tree {}", oid, commit.tree),
+                            )
+                            .with_oid(*oid),
+                        );
+                    } else {
+                        self.check_referenced_type(
+                            &commit.tree,
+                            crate::ObjectType::Tree,
+                            &owner,
+                            &mut report,
+                        )
+                        .await;
+                    }
+                    for parent in &commit.parents {
+                        if !self.odb.exists(parent).await? {
+                            report.add_issue(
+                                FsckIssue::new(
+                                    IssueSeverity::Error,
+                                    IssueCategory::MissingObject,
+                                    format!("Commit {} references missing parent {}", oid, parent),
+                                )
+                                .with_oid(*oid),
+                            );
+                        } else {
+                            self.check_referenced_type(
+                                parent,
+                                crate::ObjectType::Commit,
+                                &owner,
+                                &mut report,
+                            )
+                            .await;
+                        }
+                    }
+                }
+                crate::ObjectType::Tree => {
+                    let Ok(tree) = Tree::deserialize_with_limits(&data, self.odb.tree_limits())
+                    else {
+                        continue;
+                    };
+                    let owner = format!("Tree {}", oid);
+                    for entry in tree.iter() {
+                        let exists = if entry.is_tree() {
+                            self.odb.exists(&entry.oid).await?
+                        } else {
+                            self.blob_exists(&entry.oid).await?
+                        };
+                        if !exists {
+                            report.add_issue(
+                                FsckIssue::new(
+                                    IssueSeverity::Error,
+                                    IssueCategory::MissingObject,
+                                    format!(
+                                        "Tree {} references missing object {} ({})",
+                                        oid, entry.oid, entry.name
+                                    ),
+                                )
+                                .with_oid(*oid),
+                            );
+                        } else {
+                            let expected = if entry.is_tree() {
+                                crate::ObjectType::Tree
+                            } else {
+                                crate::ObjectType::Blob
+                            };
+                            self.check_referenced_type(&entry.oid, expected, &owner, &mut report)
+                                .await;
+                        }
+                    }
+                }
+                crate::ObjectType::Blob => {}
+            }
+        }
+
+        report.content_integrity = ContentIntegrity::Verified;
+        info!(
+            objects_checked = report.objects_checked,
+            issues = report.total_issues(),
+            "Transfer check complete"
+        );
+        Ok(report)
+    }
+
     /// Check integrity of all objects in storage
     async fn check_objects(
         &self,
@@ -391,29 +700,50 @@ impl FsckChecker {
             objects.len()
         };
 
+        if options.compression_stats {
+            report.compression_stats = Some(CompressionStats::default());
+        }
+
         for (idx, oid) in objects.iter().take(max_check).enumerate() {
             if options.verbose && (idx + 1) % 100 == 0 {
                 debug!("Checked {}/{} objects", idx + 1, max_check);
             }
 
-            self.verify_object(oid, report).await?;
+            let data = self.verify_object(oid, report).await?;
             report.objects_checked += 1;
+
+            if let (Some(data), Some(stats)) = (data, report.compression_stats.as_mut()) {
+                if let Some(info) = self.compression_info(oid, &data).await {
+                    stats.record(info);
+                }
+            }
         }
 
+        report.content_integrity = if max_check < objects.len() {
+            ContentIntegrity::Partial
+        } else {
+            ContentIntegrity::Verified
+        };
+
         Ok(())
     }
 
-    /// Verify a single object's integrity
-    async fn verify_object(&self, oid: &Oid, report: &mut FsckReport) -> anyhow::Result<()> {
+    /// Verify a single object's integrity, returning its content if it verified
+    async fn verify_object(
+        &self,
+        oid: &Oid,
+        report: &mut FsckReport,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
         // Use ObjectDatabase's read method, which handles:
         // - Decompression (smart, zlib, or uncompressed)
         // - Checksum verification (returns error if checksum doesn't match)
         // - Chunk reconstruction if needed
         match self.odb.read(oid).await {
-            Ok(_data) => {
+            Ok(data) => {
                 // Object read successfully, checksum verified by ODB
                 debug!(oid = %oid, "Object verified successfully");
-                Ok(())
+                self.check_recorded_type(oid, &data, report).await;
+                Ok(Some(data))
             }
             Err(e) => {
                 let error_msg = e.to_string();
@@ -451,11 +781,124 @@ impl FsckChecker {
                         .with_oid(*oid),
                     );
                 }
-                Ok(())
+                Ok(None)
             }
         }
     }
 
+    /// Check that an object's recorded type agrees with its content
+    ///
+    /// Commits and trees must decode as what they are recorded as. Blobs are
+    /// arbitrary bytes, so a blob record always agrees.
+    async fn check_recorded_type(&self, oid: &Oid, data: &[u8], report: &mut FsckReport) {
+        let recorded = match self.odb.recorded_type(oid).await {
+            Ok(Some(recorded)) => recorded,
+            Ok(None) => return,
+            Err(e) => {
+                report.add_issue(
+                    FsckIssue::new(
+                        IssueSeverity::Error,
+                        IssueCategory::TypeMismatch,
+                        e.to_string(),
+                    )
+                    .with_oid(*oid),
+                );
+                return;
+            }
+        };
+
+        if !self.odb.content_matches(recorded, data) {
+            report.add_issue(
+                FsckIssue::new(
+                    IssueSeverity::Error,
+                    IssueCategory::TypeMismatch,
+                    format!(
+                        "Object {} is recorded as a {} but its content is not one",
+                        oid, recorded
+                    ),
+                )
+                .with_oid(*oid),
+            );
+        }
+    }
+
+    /// Check that a referenced object is the type it is referenced as
+    ///
+    /// `owner` describes what referenced the object. The recorded type is
+    /// compared, without reading the object. Objects written before types
+    /// were recorded must decode as the commit or tree they are referenced
+    /// as, and are not checked when referenced as blobs, since a blob's bytes
+    /// may happen to decode as either. Returns false after reporting a
+    /// mismatch, so the caller can skip parsing the object again.
+    async fn check_referenced_type(
+        &self,
+        oid: &Oid,
+        expected: crate::ObjectType,
+        owner: &str,
+        report: &mut FsckReport,
+    ) -> bool {
+        let matches = match self.odb.recorded_type(oid).await {
+            Ok(Some(recorded)) => recorded == expected,
+            // Unreadable records are reported when the object is verified
+            Err(_) => return true,
+            Ok(None) if expected == crate::ObjectType::Blob => return true,
+            // Unreadable objects are reported when they are parsed
+            Ok(None) => match self.odb.read(oid).await {
+                Ok(data) => self.odb.content_matches(expected, &data),
+                Err(_) => return true,
+            },
+        };
+        if !matches {
+            report.add_issue(
+                FsckIssue::new(
+                    IssueSeverity::Error,
+                    IssueCategory::TypeMismatch,
+                    format!(
+                        "{} references {} as a {} but it is not one",
+                        owner, oid, expected
+                    ),
+                )
+                .with_oid(*oid),
+            );
+        }
+        matches
+    }
+
+    /// Describe how a verified object is stored
+    ///
+    /// The algorithm is detected from the stored bytes and the category from
+    /// the decompressed content. Returns None if the stored form can't be read.
+    async fn compression_info(&self, oid: &Oid, data: &[u8]) -> Option<ObjectCompressionInfo> {
+        let stored = self.storage.get(&oid.to_hex()).await.ok()?;
+        let (_, stored) = crate::odb::split_type_header(&stored);
+
+        // Incompressible objects are stored raw behind a 0x00 marker
+        let algorithm = match stored.split_first() {
+            Some((0x00, rest))
+                if CompressionAlgorithm::detect(rest) == CompressionAlgorithm::None =>
+            {
+                CompressionAlgorithm::None
+            }
+            _ => CompressionAlgorithm::detect(stored),
+        };
+
+        // Magic bytes cover media formats; plain text has none
+        let category = match ObjectType::from_magic_bytes(data).category() {
+            ObjectCategory::Unknown if !crate::attributes::looks_binary(data) => {
+                ObjectCategory::Text
+            }
+            category => category,
+        };
+
+        Some(ObjectCompressionInfo {
+            oid: *oid,
+            category,
+            algorithm,
+            stored_size: stored.len() as u64,
+            logical_size: data.len() as u64,
+        })
+    }
+
     /// Validate all references
     async fn check_references(&self, report: &mut FsckReport) -> anyhow::Result<()> {
         debug!("Checking references");
@@ -571,26 +1014,27 @@ impl FsckChecker {
             visited.insert(*oid);
             referenced_objects.insert(*oid);
 
-            // Try to read commit
-            // Use oid.to_hex() - LocalBackend handles "objects/" prefix and sharding
-            let key = oid.to_hex();
-            let data = match self.storage.get(&key).await {
-                Ok(d) => d,
-                Err(_) => {
-                    report.add_issue(
-                        FsckIssue::new(
-                            IssueSeverity::Error,
-                            IssueCategory::MissingObject,
-                            format!("Commit {} is missing", oid),
-                        )
-                        .with_oid(*oid),
-                    );
-                    return Ok(());
-                }
-            };
+            if !self.odb.exists(oid).await? {
+                report.add_issue(
+                    FsckIssue::new(
+                        IssueSeverity::Error,
+                        IssueCategory::MissingObject,
+                        format!("Commit {} is missing", oid),
+                    )
+                    .with_oid(*oid),
+                );
+                return Ok(());
+            }
 
-            // Deserialize commit
-            let commit: Commit = match crate::format::deserialize(&data) {
+            if !self
+                .check_referenced_type(oid, crate::ObjectType::Commit, "Commit graph", report)
+                .await
+            {
+                return Ok(());
+            }
+
+            // Read through the ODB so compressed commits are decoded
+            let commit = match Commit::read(&self.odb, oid).await {
                 Ok(c) => c,
                 Err(e) => {
                     report.add_issue(
@@ -616,6 +1060,14 @@ impl FsckChecker {
                     )
                     .with_oid(*oid),
                 );
+            } else {
+                self.check_referenced_type(
+                    &commit.tree,
+                    crate::ObjectType::Tree,
+                    &format!("Commit {}", oid),
+                    report,
+                )
+                .await;
             }
 
             // Traverse parent commits
@@ -629,6 +1081,155 @@ impl FsckChecker {
         })
     }
 
+    /// Walk everything reachable from the refs without hashing blob content
+    ///
+    /// Commits and trees are read and parsed so their edges can be followed;
+    /// blobs are only checked for existence.
+    async fn check_reachability(&self, report: &mut FsckReport) -> anyhow::Result<()> {
+        debug!("Walking reachability graph");
+
+        let mut commits: Vec<Oid> = self
+            .list_all_refs()
+            .await?
+            .into_iter()
+            .filter_map(|r| r.oid)
+            .collect();
+        let mut trees: Vec<(Oid, Oid, usize)> = Vec::new();
+        let mut visited = HashSet::new();
+
+        while let Some(oid) = commits.pop() {
+            if !visited.insert(oid) {
+                continue;
+            }
+            if !self.odb.exists(&oid).await? {
+                report.add_issue(
+                    FsckIssue::new(
+                        IssueSeverity::Error,
+                        IssueCategory::MissingObject,
+                        format!("Commit {} is missing", oid),
+                    )
+                    .with_oid(oid),
+                );
+                continue;
+            }
+
+            if !self
+                .check_referenced_type(&oid, crate::ObjectType::Commit, "Commit graph", report)
+                .await
+            {
+                continue;
+            }
+
+            let commit = match Commit::read(&self.odb, &oid).await {
+                Ok(c) => c,
+                Err(e) => {
+                    report.add_issue(
+                        FsckIssue::new(
+                            IssueSeverity::Error,
+                            IssueCategory::InvalidFormat,
+                            format!("Failed to parse commit {}: {}", oid, e),
+                        )
+                        .with_oid(oid),
+                    );
+                    continue;
+                }
+            };
+            report.objects_checked += 1;
+
+            trees.push((commit.tree, oid, 0));
+            commits.extend(commit.parents);
+        }
+
+        // Trees are followed recursively; `owner` is the object that referenced
+        // each one and `depth` its nesting below a commit's root tree
+        while let Some((oid, owner, depth)) = trees.pop() {
+            if !visited.insert(oid) {
+                continue;
+            }
+            if let Err(e) = self.odb.tree_limits().check_depth(depth) {
+                report.add_issue(
+                    FsckIssue::new(
+                        IssueSeverity::Error,
+                        IssueCategory::InvalidFormat,
+                        format!("Tree {} (in {}): {}", oid, owner, e),
+                    )
+                    .with_oid(oid),
+                );
+                continue;
+            }
+            if !self.odb.exists(&oid).await? {
+                report.add_issue(
+                    FsckIssue::new(
+                        IssueSeverity::Error,
+                        IssueCategory::MissingObject,
+                        format!("{} references missing tree {}", owner, oid),
+                    )
+                    .with_oid(oid),
+                );
+                continue;
+            }
+
+            if !self
+                .check_referenced_type(&oid, crate::ObjectType::Tree, &owner.to_string(), report)
+                .await
+            {
+                continue;
+            }
+
+            let tree = match Tree::read(&self.odb, &oid).await {
+                Ok(t) => t,
+                Err(e) => {
+                    report.add_issue(
+                        FsckIssue::new(
+                            IssueSeverity::Error,
+                            IssueCategory::InvalidFormat,
+                            format!("Failed to parse tree {}: {}", oid, e),
+                        )
+                        .with_oid(oid),
+                    );
+                    continue;
+                }
+            };
+            report.objects_checked += 1;
+
+            for entry in tree.iter() {
+                if entry.is_tree() {
+                    trees.push((entry.oid, oid, depth + 1));
+                } else if visited.insert(entry.oid) {
+                    if self.blob_exists(&entry.oid).await? {
+                        report.objects_checked += 1;
+                        self.check_referenced_type(
+                            &entry.oid,
+                            crate::ObjectType::Blob,
+                            &format!("Tree {} ({})", oid, entry.name),
+                            report,
+                        )
+                        .await;
+                    } else {
+                        report.add_issue(
+                            FsckIssue::new(
+                                IssueSeverity::Error,
+                                IssueCategory::MissingObject,
+                                format!(
+                                    "Tree {} references missing blob {} ({})",
+                                    oid, entry.oid, entry.name
+                                ),
+                            )
+                            .with_oid(entry.oid),
+                        );
+                    }
+                }
+            }
+        }
+
+        info!(
+            "Reachability check complete, {} objects reachable",
+            visited.len()
+        );
+
+        Ok(())
+    }
+
     /// Detect dangling (unreferenced) objects
     async fn check_dangling(&self, report: &mut FsckReport) -> anyhow::Result<()> {
         debug!("Detecting dangling objects");
@@ -680,15 +1281,11 @@ impl FsckChecker {
             visited.insert(*oid);
             referenced.insert(*oid);
 
-            // Use oid.to_hex() - LocalBackend handles "objects/" prefix and sharding
-            let key = oid.to_hex();
-            if let Ok(data) = self.storage.get(&key).await {
-                if let Ok(commit) = crate::format::deserialize::<Commit>(&data) {
-                    referenced.insert(commit.tree);
-                    for parent in commit.parents {
-                        self.collect_referenced_objects(&parent, visited, referenced)
-                            .await?;
-                    }
+            if let Ok(commit) = Commit::read(&self.odb, oid).await {
+                referenced.insert(commit.tree);
+                for parent in commit.parents {
+                    self.collect_referenced_objects(&parent, visited, referenced)
+                        .await?;
                 }
             }
 
@@ -704,9 +1301,9 @@ impl FsckChecker {
         // This assumes the storage backend provides a way to list objects
         // For now, we'll scan the objects directory structure
         // List all objects (LocalBackend already operates within objects/ directory)
-        let object_keys = self.storage.list_objects("").await?;
+        let mut object_keys = self.storage.list_objects_stream("");
 
-        for key in object_keys {
+        while let Some(key) = object_keys.try_next().await? {
             // LocalBackend returns hex OIDs directly (no "objects/" prefix)
             // The key is already the hex string
             if key.len() == 64 {
@@ -723,6 +1320,18 @@ impl FsckChecker {
     async fn list_all_refs(&self) -> anyhow::Result<Vec<Ref>> {
         let mut refs = Vec::new();
 
+        if let Some(refdb) = &self.refdb {
+            for name in refdb.list("").await? {
+                if let Ok(r) = refdb.read(&name).await {
+                    refs.push(r);
+                }
+            }
+            if let Ok(head) = refdb.read("HEAD").await {
+                refs.push(head);
+            }
+            return Ok(refs);
+        }
+
         // List all ref files
         let ref_keys = self.storage.list_objects("refs/").await?;
 
@@ -751,8 +1360,35 @@ impl FsckChecker {
         self.storage.exists(&key).await
     }
 
+    /// Check if a blob exists in any of its stored forms (loose, chunked or delta)
+    ///
+    /// A chunked blob only counts when every chunk its manifest lists is
+    /// stored too, and an unreadable manifest counts as missing.
+    async fn blob_exists(&self, oid: &Oid) -> anyhow::Result<bool> {
+        match self.odb.get_chunk_manifest(oid).await {
+            Ok(Some(manifest)) => {
+                for chunk in &manifest.chunks {
+                    if !self.odb.chunk_exists(&chunk.id).await? {
+                        return Ok(false);
+                    }
+                }
+                return Ok(true);
+            }
+            Ok(None) => {}
+            Err(_) => return Ok(false),
+        }
+        if self.odb.exists(oid).await? {
+            return Ok(true);
+        }
+        let delta_meta_key = format!("deltas/{}.meta", oid.to_hex());
+        self.storage.exists(&delta_meta_key).await
+    }
+
     /// Check if a reference exists
     async fn ref_exists(&self, ref_name: &str) -> anyhow::Result<bool> {
+        if let Some(refdb) = &self.refdb {
+            return refdb.exists(ref_name).await;
+        }
         self.storage.exists(ref_name).await
     }
 }
@@ -943,5 +1579,17 @@ mod tests {
         let quick = FsckOptions::quick();
         assert!(!quick.check_dangling);
         assert!(!quick.check_connectivity);
+
+        let connectivity = FsckOptions::connectivity_only();
+        assert!(connectivity.connectivity_only);
+        assert!(!connectivity.check_objects);
+        assert!(!connectivity.check_dangling);
+
+        assert!(!FsckOptions::default().compression_stats);
+        assert!(
+            FsckOptions::default()
+                .with_compression_stats(true)
+                .compression_stats
+        );
     }
 }
@@ -0,0 +1,422 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Repository-level garbage collection lock
+//!
+//! Garbage collection holds `gc.pid` in the repository directory while it
+//! runs, recording its process id and start time. A second gc refuses to
+//! start, and serving operations can wait for the lock to be released before
+//! reading objects.
+//!
+//! The lock file is written in full before it is linked into place, so it is
+//! never seen half-written. A lock is stale, and is reclaimed by the next gc,
+//! when its process no longer exists or it is older than [`STALE_LOCK_AGE`].
+//! A lock that cannot be parsed is judged by its modification time instead.
+//! Process liveness can only be checked on Unix; elsewhere only the age is
+//! used.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mediagit_versioning::GcLock;
+//! use std::path::Path;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let repo = Path::new(".mediagit");
+//! let lock = GcLock::acquire(repo)?;
+//! assert!(GcLock::holder(repo).is_some());
+//! drop(lock);
+//! assert!(GcLock::holder(repo).is_none());
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// Name of the lock file inside the repository directory
+pub const GC_LOCK_FILE: &str = "gc.pid";
+
+/// Age after which a lock is considered abandoned
+pub const STALE_LOCK_AGE: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Age after which a lock that cannot be parsed is considered abandoned
+const UNREADABLE_LOCK_AGE: Duration = Duration::from_secs(60);
+
+/// Process holding a gc lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GcLockHolder {
+    /// Process id of the gc
+    pub pid: u32,
+    /// Unix time (seconds) the lock was taken
+    pub started: u64,
+}
+
+impl GcLockHolder {
+    fn parse(contents: &str) -> Option<Self> {
+        let mut fields = contents.split_whitespace();
+        let pid = fields.next()?.parse().ok()?;
+        let started = fields.next()?.parse().ok()?;
+        Some(Self { pid, started })
+    }
+
+    /// Whether the holding process is gone or the lock has outlived [`STALE_LOCK_AGE`]
+    pub fn is_stale(&self) -> bool {
+        if unix_now().saturating_sub(self.started) > STALE_LOCK_AGE.as_secs() {
+            return true;
+        }
+        process_alive(self.pid) == Some(false)
+    }
+}
+
+/// Exclusive gc lock, released when dropped
+#[derive(Debug)]
+pub struct GcLock {
+    path: PathBuf,
+}
+
+impl GcLock {
+    /// Take the gc lock of the repository at `repo_dir` (the `.mediagit` directory)
+    ///
+    /// Fails if another live gc holds it. A stale lock is removed and taken over.
+    pub fn acquire(repo_dir: &Path) -> Result<Self> {
+        let path = repo_dir.join(GC_LOCK_FILE);
+
+        // Two attempts: the second follows removal of a stale lock
+        for _ in 0..2 {
+            match create_lock(&path) {
+                Ok(()) => {
+                    debug!("Acquired gc lock {}", path.display());
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let (contents, age) = match read_lock(&path) {
+                        Ok(lock) => lock,
+                        // Released since the attempt
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                        Err(e) => {
+                            return Err(e)
+                                .with_context(|| format!("Failed to read {}", path.display()))
+                        }
+                    };
+                    let holder = std::str::from_utf8(&contents)
+                        .ok()
+                        .and_then(GcLockHolder::parse);
+                    match holder {
+                        Some(holder) if !holder.is_stale() => {
+                            anyhow::bail!(
+                                "gc is already running in this repository (pid {}); \
+                                 if it is not, remove {}",
+                                holder.pid,
+                                path.display()
+                            );
+                        }
+                        None if age < UNREADABLE_LOCK_AGE => {
+                            anyhow::bail!(
+                                "gc lock {} is unreadable and recent; \
+                                 if no gc is running, remove it",
+                                path.display()
+                            );
+                        }
+                        holder => {
+                            warn!("Removing stale gc lock {} ({:?})", path.display(), holder);
+                            reclaim(&path, &contents)?;
+                        }
+                    }
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create {}", path.display()))
+                }
+            }
+        }
+
+        anyhow::bail!("could not acquire gc lock {}", path.display())
+    }
+
+    /// The live gc holding the lock of `repo_dir`, if any
+    ///
+    /// Stale locks are reported as not held.
+    pub fn holder(repo_dir: &Path) -> Option<GcLockHolder> {
+        let contents = std::fs::read_to_string(repo_dir.join(GC_LOCK_FILE)).ok()?;
+        GcLockHolder::parse(&contents).filter(|holder| !holder.is_stale())
+    }
+
+    /// Wait up to `timeout` for a running gc to finish
+    ///
+    /// Returns `true` if no live gc holds the lock when this returns.
+    pub async fn wait_until_released(repo_dir: &Path, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if Self::holder(repo_dir).is_none() {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+impl Drop for GcLock {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            warn!("Failed to release gc lock {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Create the lock file at `path` holding this process's id and start time
+///
+/// The record is written to a private file and hard-linked into place, so the
+/// lock never exists half-written and linking fails with `AlreadyExists` if
+/// another gc holds it.
+fn create_lock(path: &Path) -> std::io::Result<()> {
+    let temp = private_path(path, "new");
+    let linked = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp)?;
+        writeln!(file, "{} {}", std::process::id(), unix_now())?;
+        file.sync_all()?;
+        std::fs::hard_link(&temp, path)
+    })();
+    let _ = std::fs::remove_file(&temp);
+    linked
+}
+
+/// Read a lock file's contents and age
+///
+/// A modification time that can't be read counts as brand new.
+fn read_lock(path: &Path) -> std::io::Result<(Vec<u8>, Duration)> {
+    let contents = std::fs::read(path)?;
+    let age = std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .unwrap_or_default();
+    Ok((contents, age))
+}
+
+/// Remove the stale lock at `path`, whose contents were read as `judged`
+///
+/// The lock is renamed aside before its contents are compared again, so a
+/// lock another gc created after `judged` was read is never deleted: it is put
+/// back and the takeover is refused.
+fn reclaim(path: &Path, judged: &[u8]) -> Result<()> {
+    let aside = private_path(path, "stale");
+    match std::fs::rename(path, &aside) {
+        Ok(()) => {}
+        // Another gc reclaimed it first
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to remove stale {}", path.display()))
+        }
+    }
+
+    let contents = std::fs::read(&aside).unwrap_or_default();
+    if contents == judged {
+        let _ = std::fs::remove_file(&aside);
+        return Ok(());
+    }
+
+    // Replaced by a live lock in the meantime
+    let restored = std::fs::hard_link(&aside, path);
+    let _ = std::fs::remove_file(&aside);
+    if let Err(e) = restored {
+        warn!("Failed to restore gc lock {}: {}", path.display(), e);
+    }
+    anyhow::bail!(
+        "gc is already running in this repository; if it is not, remove {}",
+        path.display()
+    )
+}
+
+/// A path next to `path` that no other process or call uses
+fn private_path(path: &Path, purpose: &str) -> PathBuf {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(
+        ".{}.{}.{}",
+        purpose,
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    PathBuf::from(name)
+}
+
+/// Whether process `pid` exists, or `None` if this platform cannot tell
+#[cfg(unix)]
+fn process_alive(pid: u32) -> Option<bool> {
+    // 0 and values past pid_t's range would address process groups
+    let pid = match libc::pid_t::try_from(pid) {
+        Ok(pid) if pid > 0 => pid,
+        _ => return Some(false),
+    };
+
+    // Signal 0 checks that the process exists without signalling it
+    // SAFETY: kill has no memory-safety preconditions
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return Some(true);
+    }
+    match std::io::Error::last_os_error().raw_os_error() {
+        Some(libc::ESRCH) => Some(false),
+        // Exists, but belongs to another user
+        Some(libc::EPERM) => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg(not(unix))]
+fn process_alive(pid: u32) -> Option<bool> {
+    (pid == std::process::id()).then_some(true)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_second_acquire_refuses() {
+        let dir = TempDir::new().unwrap();
+        let lock = GcLock::acquire(dir.path()).unwrap();
+
+        let err = GcLock::acquire(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("gc is already running"));
+        assert_eq!(
+            GcLock::holder(dir.path()).map(|h| h.pid),
+            Some(std::process::id())
+        );
+
+        drop(lock);
+        assert!(!dir.path().join(GC_LOCK_FILE).exists());
+        assert!(GcLock::acquire(dir.path()).is_ok());
+    }
+
+    #[test]
+    fn test_old_lock_is_reclaimed() {
+        let dir = TempDir::new().unwrap();
+        let started = unix_now() - STALE_LOCK_AGE.as_secs() - 60;
+        std::fs::write(
+            dir.path().join(GC_LOCK_FILE),
+            format!("{} {}\n", std::process::id(), started),
+        )
+        .unwrap();
+
+        assert!(GcLock::holder(dir.path()).is_none());
+        let _lock = GcLock::acquire(dir.path()).unwrap();
+        let holder = GcLock::holder(dir.path()).unwrap();
+        assert!(holder.started > started);
+    }
+
+    #[test]
+    fn test_unreadable_lock_is_judged_by_age() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(GC_LOCK_FILE);
+        std::fs::write(&path, "not a lock").unwrap();
+
+        // Recent: it may be another gc's lock being written
+        let err = GcLock::acquire(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("unreadable"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"not a lock");
+
+        let old = SystemTime::now() - UNREADABLE_LOCK_AGE - Duration::from_secs(60);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let _lock = GcLock::acquire(dir.path()).unwrap();
+        assert_eq!(
+            GcLock::holder(dir.path()).map(|h| h.pid),
+            Some(std::process::id())
+        );
+    }
+
+    #[test]
+    fn test_reclaim_keeps_a_lock_replaced_since_it_was_judged() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(GC_LOCK_FILE);
+        let live = format!("{} {}\n", std::process::id(), unix_now());
+        std::fs::write(&path, &live).unwrap();
+
+        // Judged stale from contents that have since been replaced
+        let err = reclaim(&path, b"1 0\n").unwrap_err();
+        assert!(err.to_string().contains("gc is already running"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), live);
+
+        // Nothing is left behind besides the lock
+        let entries = std::fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(entries, 1);
+    }
+
+    #[test]
+    fn test_acquire_leaves_only_the_lock() {
+        let dir = TempDir::new().unwrap();
+        let _lock = GcLock::acquire(dir.path()).unwrap();
+        let names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, [GC_LOCK_FILE]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dead_process_lock_is_reclaimed() {
+        let dir = TempDir::new().unwrap();
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        std::fs::write(
+            dir.path().join(GC_LOCK_FILE),
+            format!("{} {}\n", pid, unix_now()),
+        )
+        .unwrap();
+
+        assert!(GcLock::holder(dir.path()).is_none());
+        assert!(GcLock::acquire(dir.path()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_released() {
+        let dir = TempDir::new().unwrap();
+        assert!(GcLock::wait_until_released(dir.path(), Duration::ZERO).await);
+
+        let lock = GcLock::acquire(dir.path()).unwrap();
+        assert!(!GcLock::wait_until_released(dir.path(), Duration::from_millis(150)).await);
+
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            drop(lock);
+        });
+        assert!(GcLock::wait_until_released(dir.path(), Duration::from_secs(5)).await);
+        release.await.unwrap();
+    }
+}
@@ -0,0 +1,361 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Git-compatible encoding for commits and trees
+//!
+//! Used when a repository writes [`ObjectFormat::Git`](crate::format::ObjectFormat::Git)
+//! objects. A commit is Git's text layout:
+//!
+//! ```text
+//! tree <64 hex digits>
+//! parent <64 hex digits>
+//! author <name> <<email>> <unix seconds> +0000
+//! committer <name> <<email>> <unix seconds> +0000
+//!
+//! <message>
+//! ```
+//!
+//! and a tree is a run of `<octal mode> <name>\0<32-byte oid>` entries in
+//! Git's order, where a directory sorts as if its name ended in `/`.
+//!
+//! Both layouts are recognised by their first bytes, which a postcard
+//! encoding of the same object cannot plausibly produce.
+
+use crate::{Commit, FileMode, Oid, Signature, Tree, TreeEntry};
+use anyhow::{Context, Result};
+use chrono::DateTime;
+
+/// Length of `"tree " + 64 hex digits`
+const TREE_HEADER_LEN: usize = 5 + 64;
+
+/// Returns true if `data` is a Git-layout commit
+pub(crate) fn is_commit(data: &[u8]) -> bool {
+    data.len() > TREE_HEADER_LEN
+        && data.starts_with(b"tree ")
+        && data[5..TREE_HEADER_LEN].iter().all(u8::is_ascii_hexdigit)
+        && data[TREE_HEADER_LEN] == b'\n'
+}
+
+/// Returns true if `data` is a Git-layout tree
+///
+/// An empty tree is zero bytes in Git's layout; the postcard encoding is a
+/// single zero count byte.
+pub(crate) fn is_tree(data: &[u8]) -> bool {
+    data.is_empty()
+        || [&b"100644 "[..], b"100755 ", b"120000 ", b"40000 "]
+            .iter()
+            .any(|mode| data.starts_with(mode))
+}
+
+/// Encode a commit in Git's layout
+pub(crate) fn encode_commit(commit: &Commit) -> Result<Vec<u8>> {
+    let mut out = format!("tree {}\n", commit.tree.to_hex());
+    for parent in &commit.parents {
+        out.push_str(&format!("parent {}\n", parent.to_hex()));
+    }
+    out.push_str(&format!("author {}\n", encode_signature(&commit.author)?));
+    out.push_str(&format!(
+        "committer {}\n",
+        encode_signature(&commit.committer)?
+    ));
+    out.push('\n');
+    out.push_str(&commit.message);
+    Ok(out.into_bytes())
+}
+
+/// Decode a Git-layout commit
+///
+/// Headers MediaGit does not use, such as `gpgsig` or `encoding`, and their
+/// continuation lines are skipped.
+pub(crate) fn decode_commit(data: &[u8]) -> Result<Commit> {
+    let text = std::str::from_utf8(data).context("Commit is not valid UTF-8")?;
+    let (headers, message) = text
+        .split_once("\n\n")
+        .context("Commit has no blank line before its message")?;
+
+    let mut tree = None;
+    let mut parents = Vec::new();
+    let mut author = None;
+    let mut committer = None;
+    for line in headers.lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            anyhow::bail!("Malformed commit header: {:?}", line);
+        };
+        match key {
+            "tree" => tree = Some(Oid::from_hex(value)?),
+            "parent" => parents.push(Oid::from_hex(value)?),
+            "author" => author = Some(decode_signature(value)?),
+            "committer" => committer = Some(decode_signature(value)?),
+            _ => {}
+        }
+    }
+
+    Ok(Commit {
+        tree: tree.context("Commit has no tree header")?,
+        parents,
+        author: author.context("Commit has no author header")?,
+        committer: committer.context("Commit has no committer header")?,
+        message: message.to_string(),
+    })
+}
+
+/// Encode a tree in Git's layout
+pub(crate) fn encode_tree(tree: &Tree) -> Vec<u8> {
+    let mut entries: Vec<&TreeEntry> = tree.iter().collect();
+    entries.sort_by_cached_key(|entry| {
+        let mut key = entry.name.as_bytes().to_vec();
+        if entry.is_tree() {
+            key.push(b'/');
+        }
+        key
+    });
+
+    let mut out = Vec::new();
+    for entry in entries {
+        out.extend_from_slice(format!("{:o} {}\0", entry.mode.as_u32(), entry.name).as_bytes());
+        out.extend_from_slice(entry.oid.as_bytes());
+    }
+    out
+}
+
+/// Decode a Git-layout tree
+pub(crate) fn decode_tree(data: &[u8]) -> Result<Tree> {
+    let mut tree = Tree::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let space = rest
+            .iter()
+            .position(|&b| b == b' ')
+            .context("Tree entry has no mode")?;
+        let mode = std::str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|mode| u32::from_str_radix(mode, 8).ok())
+            .context("Tree entry mode is not octal")?;
+        let mode = FileMode::from_u32(mode)?;
+
+        let name_len = rest[space + 1..]
+            .iter()
+            .position(|&b| b == 0)
+            .context("Tree entry name is not terminated")?;
+        let name = std::str::from_utf8(&rest[space + 1..space + 1 + name_len])
+            .context("Tree entry name is not valid UTF-8")?;
+
+        let oid_start = space + 2 + name_len;
+        let oid: [u8; 32] = rest
+            .get(oid_start..oid_start + 32)
+            .context("Tree entry OID is truncated")?
+            .try_into()?;
+
+        tree.add_entry(TreeEntry::new(name.to_string(), mode, Oid::from_bytes(oid)));
+        rest = &rest[oid_start + 32..];
+    }
+    Ok(tree)
+}
+
+/// Number of entries in a Git-layout tree, counted without decoding them
+pub(crate) fn tree_entry_count(data: &[u8]) -> u64 {
+    let mut count = 0;
+    let mut pos = 0;
+    while let Some(nul) = data[pos..].iter().position(|&b| b == 0) {
+        count += 1;
+        pos += nul + 1 + 32;
+        if pos >= data.len() {
+            break;
+        }
+    }
+    count
+}
+
+fn encode_signature(sig: &Signature) -> Result<String> {
+    for field in [&sig.name, &sig.email] {
+        if field.contains(['<', '>', '\n']) {
+            anyhow::bail!(
+                "{:?} cannot be written in Git object format: it contains '<', '>' or a newline",
+                field
+            );
+        }
+    }
+    Ok(format!(
+        "{} <{}> {} +0000",
+        sig.name,
+        sig.email,
+        sig.timestamp.timestamp()
+    ))
+}
+
+fn decode_signature(value: &str) -> Result<Signature> {
+    let malformed = || format!("Malformed signature: {:?}", value);
+    let (ident, when) = value.rsplit_once("> ").with_context(malformed)?;
+    let (name, email) = ident.split_once('<').with_context(malformed)?;
+    let seconds = when
+        .split_whitespace()
+        .next()
+        .and_then(|seconds| seconds.parse::<i64>().ok())
+        .with_context(malformed)?;
+    let timestamp = DateTime::from_timestamp(seconds, 0).with_context(malformed)?;
+    Ok(Signature::new(
+        name.trim_end().to_string(),
+        email.to_string(),
+        timestamp,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sig(name: &str, seconds: i64) -> Signature {
+        Signature::new(
+            name.to_string(),
+            format!("{}@example.com", name.to_lowercase()),
+            chrono::Utc.timestamp_opt(seconds, 0).unwrap(),
+        )
+    }
+
+    fn sample_commit() -> Commit {
+        Commit {
+            tree: Oid::hash(b"tree"),
+            parents: vec![Oid::hash(b"first"), Oid::hash(b"second")],
+            author: sig("Alice", 1_700_000_000),
+            committer: sig("Bob", 1_700_000_100),
+            message: "Merge renders\n\nKeeps both exports.\n".to_string(),
+        }
+    }
+
+    /// Minimal commit reader written from Git's object format description,
+    /// independent of `decode_commit`
+    fn reference_decode(data: &[u8]) -> (Vec<(String, String)>, String) {
+        let text = String::from_utf8(data.to_vec()).unwrap();
+        let end = text.find("\n\n").unwrap();
+        let headers = text[..end]
+            .split('\n')
+            .map(|line| {
+                let (key, value) = line.split_once(' ').unwrap();
+                (key.to_string(), value.to_string())
+            })
+            .collect();
+        (headers, text[end + 2..].to_string())
+    }
+
+    #[test]
+    fn test_commit_fields_match_reference_decoder() {
+        let commit = sample_commit();
+        let data = encode_commit(&commit).unwrap();
+        let (headers, message) = reference_decode(&data);
+
+        assert_eq!(
+            headers,
+            vec![
+                ("tree".to_string(), commit.tree.to_hex()),
+                ("parent".to_string(), commit.parents[0].to_hex()),
+                ("parent".to_string(), commit.parents[1].to_hex()),
+                (
+                    "author".to_string(),
+                    "Alice <alice@example.com> 1700000000 +0000".to_string()
+                ),
+                (
+                    "committer".to_string(),
+                    "Bob <bob@example.com> 1700000100 +0000".to_string()
+                ),
+            ]
+        );
+        assert_eq!(message, commit.message);
+    }
+
+    #[test]
+    fn test_commit_roundtrip() {
+        let commit = sample_commit();
+        let data = encode_commit(&commit).unwrap();
+        assert!(is_commit(&data));
+        assert_eq!(decode_commit(&data).unwrap(), commit);
+    }
+
+    #[test]
+    fn test_commit_skips_unknown_headers() {
+        let tree = Oid::hash(b"tree");
+        let data = format!(
+            "tree {}\nauthor A <a@x> 1 +0100\ncommitter C <c@x> 2 -0500\n\
+             gpgsig -----BEGIN PGP SIGNATURE-----\n \n -----END PGP SIGNATURE-----\n\nmsg",
+            tree.to_hex()
+        );
+        let commit = decode_commit(data.as_bytes()).unwrap();
+        assert_eq!(commit.tree, tree);
+        assert_eq!(commit.author.name, "A");
+        assert_eq!(commit.committer.timestamp.timestamp(), 2);
+        assert_eq!(commit.message, "msg");
+    }
+
+    #[test]
+    fn test_commit_rejects_unrepresentable_signature() {
+        let mut commit = sample_commit();
+        commit.author.name = "Alice <admin>".to_string();
+        assert!(encode_commit(&commit).is_err());
+    }
+
+    #[test]
+    fn test_tree_layout_and_roundtrip() {
+        let mut tree = Tree::new();
+        let blob = Oid::hash(b"blob");
+        let dir = Oid::hash(b"dir");
+        tree.add_entry(TreeEntry::new("a.psd".to_string(), FileMode::Regular, blob));
+        tree.add_entry(TreeEntry::new("a".to_string(), FileMode::Directory, dir));
+        tree.add_entry(TreeEntry::new(
+            "run.sh".to_string(),
+            FileMode::Executable,
+            blob,
+        ));
+
+        let data = encode_tree(&tree);
+        assert!(is_tree(&data));
+        assert_eq!(tree_entry_count(&data), 3);
+
+        // "a" is a directory, so it sorts as "a/" after "a.psd"
+        let mut expected = b"100644 a.psd\0".to_vec();
+        expected.extend_from_slice(blob.as_bytes());
+        expected.extend_from_slice(b"40000 a\0");
+        expected.extend_from_slice(dir.as_bytes());
+        expected.extend_from_slice(b"100755 run.sh\0");
+        expected.extend_from_slice(blob.as_bytes());
+        assert_eq!(data, expected);
+
+        assert_eq!(decode_tree(&data).unwrap(), tree);
+    }
+
+    #[test]
+    fn test_native_encodings_are_not_detected() {
+        let commit = crate::format::serialize(&sample_commit()).unwrap();
+        assert!(!is_commit(&commit));
+
+        let mut tree = Tree::new();
+        assert!(!is_tree(&crate::format::serialize(&tree).unwrap()));
+        tree.add_entry(TreeEntry::new(
+            "a".to_string(),
+            FileMode::Regular,
+            Oid::hash(b"a"),
+        ));
+        assert!(!is_tree(&crate::format::serialize(&tree).unwrap()));
+    }
+
+    #[test]
+    fn test_truncated_tree_is_rejected() {
+        let mut tree = Tree::new();
+        tree.add_entry(TreeEntry::new(
+            "a".to_string(),
+            FileMode::Regular,
+            Oid::hash(b"a"),
+        ));
+        let data = encode_tree(&tree);
+        assert!(decode_tree(&data[..data.len() - 1]).is_err());
+    }
+}
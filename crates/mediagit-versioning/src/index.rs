@@ -63,6 +63,9 @@ pub struct Index {
     /// Files marked for deletion (to be removed from tree at commit time)
     #[serde(default)]
     deleted_entries: HashSet<PathBuf>,
+    /// Staged renames, keyed by destination with the original path as value
+    #[serde(default)]
+    renamed_entries: BTreeMap<PathBuf, PathBuf>,
     /// Version of the index format
     version: u32,
 }
@@ -73,6 +76,7 @@ impl Index {
         Self {
             entries: BTreeMap::new(),
             deleted_entries: HashSet::new(),
+            renamed_entries: BTreeMap::new(),
             version: 1,
         }
     }
@@ -113,6 +117,7 @@ impl Index {
 
     /// Remove an entry from the index
     pub fn remove_entry(&mut self, path: &Path) -> Option<IndexEntry> {
+        self.renamed_entries.remove(path);
         self.entries.remove(path)
     }
 
@@ -145,6 +150,7 @@ impl Index {
     pub fn clear(&mut self) {
         self.entries.clear();
         self.deleted_entries.clear();
+        self.renamed_entries.clear();
     }
 
     /// Get all staged file paths
@@ -166,6 +172,7 @@ impl Index {
     pub fn mark_deleted(&mut self, path: PathBuf) {
         // If file was staged for addition, remove it from entries
         self.entries.remove(&path);
+        self.renamed_entries.remove(&path);
         // Add to deleted entries
         self.deleted_entries.insert(path);
     }
@@ -189,6 +196,42 @@ impl Index {
     pub fn has_deletions(&self) -> bool {
         !self.deleted_entries.is_empty()
     }
+
+    // ===== Rename tracking methods =====
+
+    /// Stage a rename of `from` to `entry.path`
+    ///
+    /// The source is marked deleted and the entry staged at its new path.
+    /// Chained renames keep the original source, and renaming a file back to
+    /// where it started cancels the rename.
+    pub fn record_rename(&mut self, from: &Path, entry: IndexEntry) {
+        let to = entry.path.clone();
+        let origin = self
+            .renamed_entries
+            .remove(from)
+            .unwrap_or_else(|| from.to_path_buf());
+
+        self.entries.remove(from);
+        if origin == from {
+            self.deleted_entries.insert(origin.clone());
+        }
+        self.deleted_entries.remove(&to);
+
+        if origin != to {
+            self.renamed_entries.insert(to, origin);
+        }
+        self.add_entry(entry);
+    }
+
+    /// The original path of a file staged as a rename
+    pub fn rename_source(&self, path: &Path) -> Option<&Path> {
+        self.renamed_entries.get(path).map(PathBuf::as_path)
+    }
+
+    /// Get all staged renames as (source, destination) pairs
+    pub fn renamed_paths(&self) -> impl Iterator<Item = (&PathBuf, &PathBuf)> {
+        self.renamed_entries.iter().map(|(to, from)| (from, to))
+    }
 }
 
 impl Default for Index {
@@ -292,4 +335,38 @@ mod tests {
         assert!(paths.contains(&PathBuf::from("file1.txt")));
         assert!(paths.contains(&PathBuf::from("file2.txt")));
     }
+
+    #[test]
+    fn test_index_record_rename() {
+        let mut index = Index::new();
+        let oid = Oid::hash(b"sprite");
+        let entry = |path: &str| IndexEntry::new(PathBuf::from(path), oid, 0o100644, 6, None);
+
+        index.record_rename(Path::new("a.png"), entry("b.png"));
+        assert!(index.is_deleted(Path::new("a.png")));
+        assert!(index.contains(Path::new("b.png")));
+        assert_eq!(
+            index.rename_source(Path::new("b.png")),
+            Some(Path::new("a.png"))
+        );
+
+        // Chained renames keep the original source
+        index.record_rename(Path::new("b.png"), entry("art/c.png"));
+        assert!(!index.contains(Path::new("b.png")));
+        assert!(!index.is_deleted(Path::new("b.png")));
+        let renames: Vec<_> = index.renamed_paths().collect();
+        assert_eq!(
+            renames,
+            vec![(&PathBuf::from("a.png"), &PathBuf::from("art/c.png"))]
+        );
+
+        // Moving back to the original path cancels the rename
+        index.record_rename(Path::new("art/c.png"), entry("a.png"));
+        assert!(!index.is_deleted(Path::new("a.png")));
+        assert_eq!(index.renamed_paths().count(), 0);
+
+        index.record_rename(Path::new("a.png"), entry("d.png"));
+        index.clear();
+        assert_eq!(index.renamed_paths().count(), 0);
+    }
 }
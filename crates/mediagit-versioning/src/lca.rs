@@ -201,6 +201,27 @@ impl LcaFinder {
         Ok(false)
     }
 
+    /// Count commits on each side of two histories
+    ///
+    /// Returns `(ahead, behind)`: the number of commits reachable from
+    /// `local` but not `upstream`, and from `upstream` but not `local`.
+    pub async fn ahead_behind(
+        &self,
+        local: &Oid,
+        upstream: &Oid,
+    ) -> anyhow::Result<(usize, usize)> {
+        if local == upstream {
+            return Ok((0, 0));
+        }
+
+        let local_history = self.get_all_ancestors(local).await?;
+        let upstream_history = self.get_all_ancestors(upstream).await?;
+
+        let ahead = local_history.difference(&upstream_history).count();
+        let behind = upstream_history.difference(&local_history).count();
+        Ok((ahead, behind))
+    }
+
     /// Find all common ancestors between two commits using BFS
     async fn find_common_ancestors(&self, oid1: &Oid, oid2: &Oid) -> anyhow::Result<Vec<Oid>> {
         // BFS from oid1 to mark all ancestors
@@ -454,6 +475,62 @@ mod tests {
         assert_eq!(bases[0], a);
     }
 
+    #[tokio::test]
+    async fn test_ahead_behind() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = Arc::new(ObjectDatabase::new(storage, 100));
+        let lca_finder = LcaFinder::new(odb.clone());
+
+        //   B - C   (local)
+        //  /
+        // A - D     (upstream)
+        let a = create_commit(&odb, "A", vec![]).await.unwrap();
+        let b = create_commit(&odb, "B", vec![a]).await.unwrap();
+        let c = create_commit(&odb, "C", vec![b]).await.unwrap();
+        let d = create_commit(&odb, "D", vec![a]).await.unwrap();
+
+        assert_eq!(lca_finder.ahead_behind(&c, &c).await.unwrap(), (0, 0));
+        assert_eq!(lca_finder.ahead_behind(&c, &a).await.unwrap(), (2, 0));
+        assert_eq!(lca_finder.ahead_behind(&a, &c).await.unwrap(), (0, 2));
+        assert_eq!(lca_finder.ahead_behind(&c, &d).await.unwrap(), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_ahead_behind_after_merge() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = Arc::new(ObjectDatabase::new(storage, 100));
+        let lca_finder = LcaFinder::new(odb.clone());
+
+        //   B - C
+        //  /     \
+        // A - D - M   (local has merged upstream D)
+        let a = create_commit(&odb, "A", vec![]).await.unwrap();
+        let b = create_commit(&odb, "B", vec![a]).await.unwrap();
+        let c = create_commit(&odb, "C", vec![b]).await.unwrap();
+        let d = create_commit(&odb, "D", vec![a]).await.unwrap();
+        let m = create_commit(&odb, "M", vec![c, d]).await.unwrap();
+
+        // Fully merged: nothing on the upstream is missing locally
+        assert_eq!(lca_finder.ahead_behind(&m, &d).await.unwrap(), (3, 0));
+        assert_eq!(lca_finder.ahead_behind(&d, &m).await.unwrap(), (0, 3));
+    }
+
+    #[tokio::test]
+    async fn test_ahead_behind_unrelated_histories() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = Arc::new(ObjectDatabase::new(storage, 100));
+        let lca_finder = LcaFinder::new(odb.clone());
+
+        // A - B   and   X - Y - Z share no commits
+        let a = create_commit(&odb, "A", vec![]).await.unwrap();
+        let b = create_commit(&odb, "B", vec![a]).await.unwrap();
+        let x = create_commit(&odb, "X", vec![]).await.unwrap();
+        let y = create_commit(&odb, "Y", vec![x]).await.unwrap();
+        let z = create_commit(&odb, "Z", vec![y]).await.unwrap();
+
+        assert_eq!(lca_finder.ahead_behind(&b, &z).await.unwrap(), (2, 3));
+    }
+
     #[tokio::test]
     async fn test_is_ancestor_true() {
         let storage = Arc::new(MockBackend::new());
@@ -61,16 +61,19 @@
 //! }
 //! ```
 
+mod attributes;
 mod branch;
+mod bundle;
 mod checkout;
 pub mod chunking;
 mod commit;
 mod config;
 mod conflict;
-mod delta;
 mod diff;
 pub mod format;
 pub mod fsck;
+mod gc_lock;
+mod git_format;
 mod index;
 mod lca;
 mod merge;
@@ -79,17 +82,25 @@ mod object;
 mod odb;
 mod oid;
 mod pack;
+mod patch;
 mod reflog;
 mod refs;
 mod revision;
+mod rollup;
 mod similarity;
 mod streaming_index;
 mod streaming_pack;
+mod thin;
 mod transaction;
 mod tree;
 
+pub use attributes::{
+    convert_lf_to_crlf, looks_binary, normalize_to_lf, CompressionAttributes, EolStyle,
+    MergeAttributes, MergeDriver, TextAttributes, TextSetting, ATTRIBUTES_FILE, BINARY_SNIFF_LEN,
+};
 pub use branch::{BranchInfo, BranchManager, DetachedHead};
-pub use checkout::{CheckoutManager, CheckoutStats};
+pub use bundle::{Bundle, BUNDLE_EXTENSION};
+pub use checkout::{CheckoutManager, CheckoutStats, PrefetchEntry, PrefetchManifest};
 pub use chunking::{
     ChunkId, ChunkManifest, ChunkRef, ChunkStore, ChunkStoreStats, ChunkStrategy, ChunkType,
     CodecHint, ContentChunk, ContentChunker,
@@ -97,28 +108,45 @@ pub use chunking::{
 pub use commit::{Commit, Signature};
 pub use config::{ChunkingStrategyConfig, StorageConfig};
 pub use conflict::{Conflict, ConflictDetector, ConflictSide, ConflictStats, ConflictType};
-pub use delta::{Delta, DeltaDecoder, DeltaEncoder};
-pub use diff::{ModifiedEntry, ThreeWayDiff, TreeDiff, TreeDiffer};
+pub use diff::{
+    ModifiedEntry, RenameDetection, RenameOptions, RenamedEntry, ThreeWayDiff, TreeDiff,
+    TreeDiffer, DEFAULT_RENAME_LIMIT, DEFAULT_RENAME_SIMILARITY,
+};
+pub use format::ObjectFormat;
+pub use gc_lock::{GcLock, GcLockHolder, GC_LOCK_FILE, STALE_LOCK_AGE};
 pub use index::{Index, IndexEntry};
 pub use lca::{LcaFinder, LcaResult};
+pub use mediagit_compression::{Delta, DeltaDecoder, DeltaEncoder};
 pub use merge::{FastForwardInfo, MergeEngine, MergeResult, MergeStrategy};
-pub use metrics::OdbMetrics;
+pub use metrics::{CategoryMetrics, CompressionTotals, OdbMetrics};
 pub use object::ObjectType;
-pub use odb::{ObjectDatabase, RepackStats};
+pub use odb::{
+    infer_object_type, ObjectDatabase, Prefetched, RepackOptions, RepackStats,
+    DEFAULT_REPACK_WINDOW, DEFAULT_REPACK_WINDOW_MEMORY, MAX_OBJECT_SIZE, MIN_DICTIONARY_SAMPLES,
+};
 pub use oid::Oid;
 pub use pack::{PackHeader, PackIndex, PackMetadata, PackObjectEntry, PackReader, PackWriter};
+pub use patch::{
+    binary_diff, merge_text, parse_patch, unified_diff, union_text, ApplyOutcome, FilePatch,
+    MergedText, DEFAULT_CONTEXT_LINES,
+};
 pub use reflog::{Reflog, ReflogEntry};
 pub use refs::{normalize_ref_name, Ref, RefDatabase, RefType};
 pub use revision::resolve_revision;
+pub use rollup::{DroppedBlob, HistoryRollup, RollupOutcome, RollupPlan, RollupPolicy};
 pub use similarity::{ObjectMetadata, SimilarityDetector, SimilarityScore};
 pub use streaming_index::StreamingPackIndex;
 pub use streaming_pack::{StreamingPackReader, StreamingPackWriter};
+pub use thin::{MediaThinner, PathPlan, RefRewrite, ThinOutcome, ThinPlan, ThinRule, ThinVersion};
 pub use transaction::{recover_incomplete_transactions, PackTransaction, RecoveryReport};
-pub use tree::{FileMode, Tree, TreeEntry};
+pub use tree::{
+    FileMode, Tree, TreeEntry, TreeLimits, DEFAULT_MAX_TREE_DEPTH, DEFAULT_MAX_TREE_ENTRIES,
+};
 
 // Re-export fsck module
 pub use fsck::{
-    FsckChecker, FsckIssue, FsckOptions, FsckRepair, FsckReport, IssueCategory, IssueSeverity,
+    CategoryCompressionStats, CompressionStats, ContentIntegrity, FsckChecker, FsckIssue,
+    FsckOptions, FsckRepair, FsckReport, IssueCategory, IssueSeverity, ObjectCompressionInfo,
 };
 
 #[cfg(test)]
@@ -16,8 +16,12 @@
 //! This module orchestrates LCA finding, tree diffing, and conflict detection
 //! to perform complete merge operations with various strategies.
 
-use crate::{Commit, Conflict, ConflictDetector, LcaFinder, ObjectDatabase, Oid, Tree, TreeDiffer};
+use crate::{
+    union_text, Commit, Conflict, ConflictDetector, LcaFinder, MergeAttributes, MergeDriver,
+    ObjectDatabase, ObjectType, Oid, Tree, TreeDiffer, TreeEntry,
+};
 use anyhow::{anyhow, Result};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{debug, instrument, trace};
 
@@ -66,6 +70,9 @@ pub struct MergeResult {
 
     /// Merge strategy used
     pub strategy: MergeStrategy,
+
+    /// Conflicting paths resolved by their `merge` attribute, with the driver used
+    pub auto_resolved: Vec<(String, MergeDriver)>,
 }
 
 impl MergeResult {
@@ -89,6 +96,7 @@ pub struct MergeEngine {
     lca_finder: LcaFinder,
     __differ: TreeDiffer,
     conflict_detector: ConflictDetector,
+    merge_attributes: MergeAttributes,
 }
 
 impl MergeEngine {
@@ -98,10 +106,20 @@ impl MergeEngine {
             lca_finder: LcaFinder::new(Arc::clone(&odb)),
             __differ: TreeDiffer::new(Arc::clone(&odb)),
             conflict_detector: ConflictDetector::new(Arc::clone(&odb)),
+            merge_attributes: MergeAttributes::default(),
             odb,
         }
     }
 
+    /// Resolve conflicts on paths with a `merge` attribute instead of reporting them
+    ///
+    /// Applies to the recursive strategy; `ours` and `theirs` already resolve
+    /// every conflict.
+    pub fn with_merge_attributes(mut self, attributes: MergeAttributes) -> Self {
+        self.merge_attributes = attributes;
+        self
+    }
+
     /// Perform a merge operation between two commits
     ///
     /// This is the main entry point for merge operations. It:
@@ -129,6 +147,7 @@ impl MergeEngine {
                 success: true,
                 fast_forward: None,
                 strategy,
+                auto_resolved: Vec::new(),
             });
         }
 
@@ -182,6 +201,7 @@ impl MergeEngine {
                     is_fast_forward: false, // No actual fast-forward needed
                 }),
                 strategy,
+                auto_resolved: Vec::new(),
             }));
         }
 
@@ -199,6 +219,7 @@ impl MergeEngine {
                     is_fast_forward: true,
                 }),
                 strategy,
+                auto_resolved: Vec::new(),
             }));
         }
 
@@ -225,11 +246,17 @@ impl MergeEngine {
         debug!("Detected {} conflicts", conflicts.len());
 
         // Build merged tree based on strategy
+        let mut auto_resolved = Vec::new();
         let (tree_oid, final_conflicts, success) = match strategy {
             MergeStrategy::Recursive => {
+                let (resolutions, conflicts) = self
+                    .auto_resolve(ours, theirs, conflicts, &mut auto_resolved)
+                    .await?;
                 if conflicts.is_empty() {
-                    // No conflicts - build clean merged tree
-                    let tree = self.build_merged_tree(base, ours, theirs, &[]).await?;
+                    // No conflicts left - build clean merged tree
+                    let tree = self
+                        .build_merged_tree(base, ours, theirs, &resolutions)
+                        .await?;
                     let tree_oid = tree.write(&self.odb).await?;
                     (Some(tree_oid), Vec::new(), true)
                 } else {
@@ -257,16 +284,71 @@ impl MergeEngine {
             success,
             fast_forward: None,
             strategy,
+            auto_resolved,
         })
     }
 
-    /// Build merged tree for clean merge (no conflicts)
+    /// Resolve conflicts on paths with a `merge` attribute
+    ///
+    /// Returns the resolved entry (`None` for a deletion) per resolved path,
+    /// and the conflicts that remain.
+    async fn auto_resolve(
+        &self,
+        ours: &Tree,
+        theirs: &Tree,
+        conflicts: Vec<Conflict>,
+        auto_resolved: &mut Vec<(String, MergeDriver)>,
+    ) -> Result<(HashMap<String, Option<TreeEntry>>, Vec<Conflict>)> {
+        let mut resolutions = HashMap::new();
+        let mut remaining = Vec::new();
+
+        for conflict in conflicts {
+            let Some(driver) = self.merge_attributes.driver_for(&conflict.path) else {
+                remaining.push(conflict);
+                continue;
+            };
+            let ours_entry = ours.entries.get(&conflict.path);
+            let theirs_entry = theirs.entries.get(&conflict.path);
+
+            let entry = match driver {
+                MergeDriver::Ours => ours_entry.cloned(),
+                // Keep a file in place for the regenerate command to rebuild
+                MergeDriver::Regenerate => ours_entry.or(theirs_entry).cloned(),
+                MergeDriver::Union => match (ours_entry, theirs_entry) {
+                    (Some(ours_entry), Some(theirs_entry)) => {
+                        let base_data = match &conflict.base {
+                            Some(base) => self.odb.read(&base.oid).await?,
+                            None => Vec::new(),
+                        };
+                        let merged = union_text(
+                            &base_data,
+                            &self.odb.read(&ours_entry.oid).await?,
+                            &self.odb.read(&theirs_entry.oid).await?,
+                        );
+                        let oid = self.odb.write(ObjectType::Blob, &merged).await?;
+                        Some(TreeEntry::new(conflict.path.clone(), ours_entry.mode, oid))
+                    }
+                    // Modify/delete: keep the side that still has content
+                    (ours_entry, theirs_entry) => ours_entry.or(theirs_entry).cloned(),
+                },
+            };
+
+            debug!("Resolved {} with merge={:?}", conflict.path, driver);
+            auto_resolved.push((conflict.path.clone(), driver));
+            resolutions.insert(conflict.path, entry);
+        }
+
+        Ok((resolutions, remaining))
+    }
+
+    /// Build merged tree for clean merge, taking `resolutions` for paths
+    /// whose conflicts were resolved automatically
     async fn build_merged_tree(
         &self,
         base: &Tree,
         ours: &Tree,
         theirs: &Tree,
-        _conflicts: &[Conflict],
+        resolutions: &HashMap<String, Option<TreeEntry>>,
     ) -> Result<Tree> {
         let mut merged = Tree::new();
 
@@ -283,6 +365,13 @@ impl MergeEngine {
 
             trace!("Merging path: {}", path);
 
+            if let Some(resolved) = resolutions.get(path) {
+                if let Some(entry) = resolved {
+                    merged.add_entry(entry.clone());
+                }
+                continue;
+            }
+
             // Determine which version to use
             let entry = match (base_entry, ours_entry, theirs_entry) {
                 // All three present
@@ -891,4 +980,94 @@ mod tests {
         // file5: they added, we didn't - included
         assert!(merged_tree.entries.contains_key("file5.txt"));
     }
+
+    #[tokio::test]
+    async fn test_merge_attribute_ours_resolves_conflict() {
+        let odb = create_test_odb();
+        let engine = MergeEngine::new(Arc::clone(&odb))
+            .with_merge_attributes(MergeAttributes::parse("package-lock.json merge=ours\n"));
+
+        let base_tree = create_tree(
+            &odb,
+            vec![("package-lock.json", b"base"), ("app.js", b"base")],
+        )
+        .await;
+        let base_commit = create_commit(&odb, base_tree, vec![], "Base").await;
+        let ours_tree = create_tree(
+            &odb,
+            vec![("package-lock.json", b"ours"), ("app.js", b"base")],
+        )
+        .await;
+        let ours_commit = create_commit(&odb, ours_tree, vec![base_commit], "Ours").await;
+        let theirs_tree = create_tree(
+            &odb,
+            vec![("package-lock.json", b"theirs"), ("app.js", b"theirs")],
+        )
+        .await;
+        let theirs_commit = create_commit(&odb, theirs_tree, vec![base_commit], "Theirs").await;
+
+        let result = engine
+            .merge(&ours_commit, &theirs_commit, MergeStrategy::Recursive)
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            result.auto_resolved,
+            vec![("package-lock.json".to_string(), MergeDriver::Ours)]
+        );
+        let merged_tree = Tree::read(&odb, &result.tree_oid.unwrap()).await.unwrap();
+        assert_eq!(
+            merged_tree.entries.get("package-lock.json").unwrap().oid,
+            Oid::hash(b"ours")
+        );
+        assert_eq!(
+            merged_tree.entries.get("app.js").unwrap().oid,
+            Oid::hash(b"theirs")
+        );
+
+        // Without the attribute the same merge conflicts
+        let result = MergeEngine::new(Arc::clone(&odb))
+            .merge(&ours_commit, &theirs_commit, MergeStrategy::Recursive)
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.conflicts[0].path, "package-lock.json");
+    }
+
+    #[tokio::test]
+    async fn test_merge_attribute_union_keeps_both_sides() {
+        let odb = create_test_odb();
+        let engine = MergeEngine::new(Arc::clone(&odb))
+            .with_merge_attributes(MergeAttributes::parse("*.log merge=union\n"));
+
+        let write_blob = |content: &'static [u8]| {
+            let odb = Arc::clone(&odb);
+            async move { odb.write(ObjectType::Blob, content).await.unwrap() }
+        };
+        write_blob(b"start\n").await;
+        write_blob(b"start\nours\n").await;
+        write_blob(b"start\ntheirs\n").await;
+
+        let base_tree = create_tree(&odb, vec![("render.log", b"start\n")]).await;
+        let base_commit = create_commit(&odb, base_tree, vec![], "Base").await;
+        let ours_tree = create_tree(&odb, vec![("render.log", b"start\nours\n")]).await;
+        let ours_commit = create_commit(&odb, ours_tree, vec![base_commit], "Ours").await;
+        let theirs_tree = create_tree(&odb, vec![("render.log", b"start\ntheirs\n")]).await;
+        let theirs_commit = create_commit(&odb, theirs_tree, vec![base_commit], "Theirs").await;
+
+        let result = engine
+            .merge(&ours_commit, &theirs_commit, MergeStrategy::Recursive)
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        let merged_tree = Tree::read(&odb, &result.tree_oid.unwrap()).await.unwrap();
+        let merged_oid = merged_tree.entries.get("render.log").unwrap().oid;
+        assert_eq!(
+            odb.read(&merged_oid).await.unwrap(),
+            b"start\nours\ntheirs\n"
+        );
+    }
 }
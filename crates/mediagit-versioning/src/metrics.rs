@@ -13,7 +13,9 @@
 
 //! Metrics tracking for object database operations
 
+use mediagit_compression::{CompressionAlgorithm, ObjectCategory};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Metrics for object database operations and deduplication efficiency
 ///
@@ -37,6 +39,140 @@ pub struct OdbMetrics {
 
     /// Total bytes written (including duplicates that were deduplicated)
     pub bytes_written: u64,
+
+    /// Loose objects read through a memory map instead of a heap copy
+    #[serde(default)]
+    pub mmap_reads: u64,
+
+    /// Batched prefetches issued (see [`ObjectDatabase::prefetch`](crate::ObjectDatabase::prefetch))
+    #[serde(default)]
+    pub prefetch_batches: u64,
+
+    /// Objects requested across all prefetches
+    #[serde(default)]
+    pub objects_prefetched: u64,
+
+    /// Write counters broken down by object category
+    ///
+    /// Keyed by [`ObjectCategory`] rather than file type so the number of
+    /// entries stays small.
+    #[serde(default)]
+    pub by_category: HashMap<ObjectCategory, CategoryMetrics>,
+
+    /// Compression of newly stored objects and chunks, by the algorithm
+    /// that compressed them ([`CompressionAlgorithm::None`] for data stored
+    /// as-is). Chunks stored as deltas are not counted.
+    #[serde(default)]
+    pub compression_by_algorithm: HashMap<CompressionAlgorithm, CompressionTotals>,
+
+    /// Compression of newly stored objects and chunks, by object category
+    #[serde(default)]
+    pub compression_by_category: HashMap<ObjectCategory, CompressionTotals>,
+}
+
+/// Write and deduplication counters for one object category
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryMetrics {
+    /// Total number of write operations (including duplicates)
+    pub total_writes: u64,
+
+    /// Number of unique objects stored
+    pub unique_objects: u64,
+
+    /// Total bytes written (including duplicates)
+    pub bytes_written: u64,
+
+    /// Bytes actually stored after deduplication
+    pub bytes_stored: u64,
+}
+
+impl CategoryMetrics {
+    /// Record a write of `size` bytes
+    pub fn record_write(&mut self, size: u64, is_new: bool) {
+        self.total_writes += 1;
+        self.bytes_written += size;
+
+        if is_new {
+            self.unique_objects += 1;
+            self.bytes_stored += size;
+        }
+    }
+
+    /// Add another set of counters to this one
+    pub fn merge(&mut self, other: &CategoryMetrics) {
+        self.total_writes += other.total_writes;
+        self.unique_objects += other.unique_objects;
+        self.bytes_written += other.bytes_written;
+        self.bytes_stored += other.bytes_stored;
+    }
+
+    /// Ratio of written bytes saved through deduplication
+    pub fn dedup_ratio(&self) -> f64 {
+        if self.bytes_written == 0 {
+            0.0
+        } else {
+            self.bytes_saved() as f64 / self.bytes_written as f64
+        }
+    }
+
+    /// Bytes saved through deduplication
+    pub fn bytes_saved(&self) -> u64 {
+        self.bytes_written.saturating_sub(self.bytes_stored)
+    }
+}
+
+/// Bytes before and after compression for a group of stored objects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionTotals {
+    /// Objects and chunks compressed
+    pub objects: u64,
+
+    /// Bytes before compression
+    pub bytes_in: u64,
+
+    /// Bytes stored after compression
+    pub bytes_out: u64,
+}
+
+impl CompressionTotals {
+    /// Record `bytes_in` bytes stored as `bytes_out`
+    pub fn record(&mut self, bytes_in: u64, bytes_out: u64) {
+        self.objects += 1;
+        self.bytes_in += bytes_in;
+        self.bytes_out += bytes_out;
+    }
+
+    /// Add another set of totals to this one
+    pub fn merge(&mut self, other: &CompressionTotals) {
+        self.objects += other.objects;
+        self.bytes_in += other.bytes_in;
+        self.bytes_out += other.bytes_out;
+    }
+
+    /// Compression ratio (bytes in per byte stored; higher is better)
+    pub fn ratio(&self) -> f64 {
+        if self.bytes_out == 0 {
+            1.0
+        } else {
+            self.bytes_in as f64 / self.bytes_out as f64
+        }
+    }
+
+    /// Bytes saved compared with storing everything raw
+    ///
+    /// Data that grew when compressed counts as nothing saved.
+    pub fn bytes_saved(&self) -> u64 {
+        self.bytes_in.saturating_sub(self.bytes_out)
+    }
+
+    /// Fraction of the raw size saved
+    pub fn saved_ratio(&self) -> f64 {
+        if self.bytes_in == 0 {
+            0.0
+        } else {
+            self.bytes_saved() as f64 / self.bytes_in as f64
+        }
+    }
 }
 
 impl OdbMetrics {
@@ -105,6 +241,17 @@ impl OdbMetrics {
         self.cache_misses += 1;
     }
 
+    /// Record a loose object read through a memory map
+    pub fn record_mmap_read(&mut self) {
+        self.mmap_reads += 1;
+    }
+
+    /// Record a batched prefetch of `objects` objects
+    pub fn record_prefetch(&mut self, objects: usize) {
+        self.prefetch_batches += 1;
+        self.objects_prefetched += objects as u64;
+    }
+
     /// Record a new object write
     pub fn record_write(&mut self, size: u64, is_new: bool) {
         self.total_writes += 1;
@@ -116,6 +263,74 @@ impl OdbMetrics {
         }
     }
 
+    /// Record a write and attribute it to an object category
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mediagit_compression::{CompressionAlgorithm, ObjectCategory};
+    /// use mediagit_versioning::OdbMetrics;
+    ///
+    /// let mut metrics = OdbMetrics::new();
+    /// metrics.record_typed_write(ObjectCategory::Text, 100, true);
+    /// metrics.record_typed_write(ObjectCategory::Text, 100, false);
+    /// assert_eq!(metrics.category(ObjectCategory::Text).dedup_ratio(), 0.5);
+    /// assert_eq!(metrics.total_writes, 2);
+    /// ```
+    pub fn record_typed_write(&mut self, category: ObjectCategory, size: u64, is_new: bool) {
+        self.record_write(size, is_new);
+        self.by_category
+            .entry(category)
+            .or_default()
+            .record_write(size, is_new);
+    }
+
+    /// Counters for one category (zero if nothing of that category was written)
+    pub fn category(&self, category: ObjectCategory) -> CategoryMetrics {
+        self.by_category.get(&category).copied().unwrap_or_default()
+    }
+
+    /// Record `bytes_in` bytes of `category` stored as `bytes_out` bytes
+    /// compressed with `algorithm`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mediagit_compression::{CompressionAlgorithm, ObjectCategory};
+    /// use mediagit_versioning::OdbMetrics;
+    ///
+    /// let mut metrics = OdbMetrics::new();
+    /// metrics.record_compression(ObjectCategory::Text, CompressionAlgorithm::Brotli, 1000, 200);
+    /// metrics.record_compression(ObjectCategory::Image, CompressionAlgorithm::None, 500, 500);
+    /// assert_eq!(metrics.compression_total().bytes_saved(), 800);
+    /// assert_eq!(metrics.compression_by_algorithm[&CompressionAlgorithm::Brotli].ratio(), 5.0);
+    /// ```
+    pub fn record_compression(
+        &mut self,
+        category: ObjectCategory,
+        algorithm: CompressionAlgorithm,
+        bytes_in: u64,
+        bytes_out: u64,
+    ) {
+        self.compression_by_algorithm
+            .entry(algorithm)
+            .or_default()
+            .record(bytes_in, bytes_out);
+        self.compression_by_category
+            .entry(category)
+            .or_default()
+            .record(bytes_in, bytes_out);
+    }
+
+    /// Compression totals across every algorithm
+    pub fn compression_total(&self) -> CompressionTotals {
+        let mut total = CompressionTotals::default();
+        for totals in self.compression_by_algorithm.values() {
+            total.merge(totals);
+        }
+        total
+    }
+
     /// Record object deletion
     pub fn record_delete(&mut self, size: u64) {
         if self.unique_objects > 0 {
@@ -194,4 +409,62 @@ mod tests {
         assert_eq!(metrics.unique_objects, 0);
         assert_eq!(metrics.bytes_stored, 0);
     }
+
+    #[test]
+    fn test_record_typed_write_per_category() {
+        let mut metrics = OdbMetrics::new();
+        metrics.record_typed_write(ObjectCategory::Image, 1000, true);
+        metrics.record_typed_write(ObjectCategory::Text, 200, true);
+        metrics.record_typed_write(ObjectCategory::Text, 200, false);
+        metrics.record_typed_write(ObjectCategory::Text, 200, false);
+
+        let image = metrics.category(ObjectCategory::Image);
+        assert_eq!(image.total_writes, 1);
+        assert_eq!(image.dedup_ratio(), 0.0);
+
+        let text = metrics.category(ObjectCategory::Text);
+        assert_eq!(text.total_writes, 3);
+        assert_eq!(text.unique_objects, 1);
+        assert_eq!(text.bytes_saved(), 400);
+
+        assert_eq!(metrics.total_writes, 4);
+        assert_eq!(metrics.bytes_written, 1600);
+        assert_eq!(
+            metrics.category(ObjectCategory::Video),
+            CategoryMetrics::default()
+        );
+    }
+
+    #[test]
+    fn test_record_compression() {
+        let mut metrics = OdbMetrics::new();
+        metrics.record_compression(
+            ObjectCategory::Text,
+            CompressionAlgorithm::Brotli,
+            1000,
+            100,
+        );
+        metrics.record_compression(ObjectCategory::Text, CompressionAlgorithm::Zstd, 1000, 400);
+        metrics.record_compression(
+            ObjectCategory::Video,
+            CompressionAlgorithm::None,
+            5000,
+            5001,
+        );
+
+        let text = metrics.compression_by_category[&ObjectCategory::Text];
+        assert_eq!(text.objects, 2);
+        assert_eq!(text.bytes_saved(), 1500);
+        assert_eq!(text.saved_ratio(), 0.75);
+
+        let stored = metrics.compression_by_algorithm[&CompressionAlgorithm::None];
+        assert_eq!(stored.bytes_saved(), 0);
+        assert!(stored.ratio() < 1.0);
+
+        let total = metrics.compression_total();
+        assert_eq!(total.objects, 3);
+        assert_eq!(total.bytes_in, 7000);
+        assert_eq!(total.bytes_out, 5501);
+        assert_eq!(CompressionTotals::default().ratio(), 1.0);
+    }
 }
@@ -15,7 +15,8 @@
 //!
 //! The ODB provides:
 //! - **Content-addressable storage**: Objects are identified by SHA-256 hash of their content
-//! - **Automatic deduplication**: Identical content is stored only once
+//! - **Automatic deduplication**: Identical content is stored only once, whether
+//!   it was written whole or chunked, since an OID hashes the full content either way
 //! - **LRU caching**: Frequently accessed objects are cached in memory
 //! - **Observable metrics**: Track cache performance and deduplication efficiency
 //! - **Delta compression**: Store only differences between similar objects
@@ -31,15 +32,45 @@ pub const MAX_DELTA_DEPTH: u8 = 10;
 /// that may contain extremely large total_size values.
 pub const MAX_OBJECT_SIZE: u64 = 16 * 1024 * 1024 * 1024;
 
+// Reading any object the database accepts must not need a decompression limit
+const _: () = assert!(MAX_OBJECT_SIZE <= mediagit_compression::DEFAULT_MAX_DECOMPRESSED_SIZE);
+
+/// Objects smaller than this (1 MB) are never chunked.
+const MIN_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Storage key prefix for trained compression dictionaries, one per id
+const DICTIONARY_PREFIX: &str = "dictionaries/";
+
+/// Storage key naming the dictionary new objects are compressed with
+const ACTIVE_DICTIONARY_KEY: &str = "dictionaries/active";
+
+/// Fewest sample objects [`ObjectDatabase::train_dictionary`] trains from
+pub const MIN_DICTIONARY_SAMPLES: usize = 8;
+
+/// Most sample objects read when training a dictionary
+const MAX_DICTIONARY_SAMPLES: usize = 4096;
+
+/// Upper bound on the size of a trained dictionary (64 KB)
+const MAX_DICTIONARY_SIZE: usize = 64 * 1024;
+
 use crate::chunking::{ChunkManifest, ChunkRef, ChunkStrategy, ContentChunker};
-use crate::delta::{Delta, DeltaDecoder, DeltaEncoder};
-use crate::{ObjectType, OdbMetrics, Oid};
+use crate::format::ObjectFormat;
+use crate::{CompressionAttributes, ObjectType, OdbMetrics, Oid, TreeLimits};
+use futures::TryStreamExt;
 use mediagit_compression::ObjectType as CompressionObjectType;
 use mediagit_compression::{
-    ChunkCodecHint, CompressionAlgorithm, Compressor, SmartCompressor, TypeAwareCompressor,
-    ZlibCompressor,
+    BrotliSettings, ChunkCodecHint, CompressionAlgorithm, CompressionDictionary, CompressionError,
+    CompressionStrategy, CompressionTier, Compressor, Delta, DeltaBase, DeltaDecoder, DeltaEncoder,
+    ObjectCategory, SmartCompressor, TypeAwareCompressor, ZlibCompressor,
+    MAX_DICTIONARY_OBJECT_SIZE,
+};
+use mediagit_storage::{
+    MmapOrVec, NamespacedBackend, PooledUploadBackend, StorageBackend, UploadPool,
 };
-use mediagit_storage::StorageBackend;
+use moka::future::Cache;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
 
 /// Codec-aware delta acceptance threshold.
 ///
@@ -90,10 +121,87 @@ fn to_chunk_codec_hint(
         },
     }
 }
-use moka::future::Cache;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{debug, info, warn};
+
+/// Category a write is counted under in [`OdbMetrics`].
+///
+/// Trees and commits are always git objects; blobs take the category of
+/// their detected file type.
+fn write_category(obj_type: ObjectType, detected: CompressionObjectType) -> ObjectCategory {
+    match obj_type {
+        ObjectType::Blob => detected.category(),
+        ObjectType::Tree | ObjectType::Commit => ObjectCategory::GitObject,
+    }
+}
+
+/// Count `size` bytes of `category` stored as `stored` in `metrics`
+///
+/// The algorithm is read from the stored data after its type header;
+/// dictionary-compressed data counts as zstd.
+async fn record_compression(
+    metrics: &RwLock<OdbMetrics>,
+    category: ObjectCategory,
+    size: usize,
+    stored: &[u8],
+) {
+    let (_, body) = split_type_header(stored);
+    let algorithm = if CompressionDictionary::id_of(body).is_some() {
+        CompressionAlgorithm::Zstd
+    } else {
+        CompressionAlgorithm::detect(body)
+    };
+    metrics
+        .write()
+        .await
+        .record_compression(category, algorithm, size as u64, stored.len() as u64);
+}
+
+/// Work out an object's type from its content
+///
+/// Anything that is not exactly one encoded commit or tree is a blob. Only
+/// used for objects written before types were recorded, since a blob whose
+/// bytes happen to encode a commit or tree is reported as one.
+pub fn infer_object_type(data: &[u8]) -> ObjectType {
+    if crate::Commit::deserialize(data).is_ok() {
+        ObjectType::Commit
+    } else if !data.is_empty() && crate::Tree::deserialize(data).is_ok() {
+        ObjectType::Tree
+    } else {
+        ObjectType::Blob
+    }
+}
+
+/// Start of the header that stores an object's type before its stored bytes
+const TYPE_HEADER_MAGIC: &[u8; 4] = b"MGOT";
+
+/// Length of the type header: the magic and one type byte
+const TYPE_HEADER_LEN: usize = TYPE_HEADER_MAGIC.len() + 1;
+
+/// Prefix an object's stored bytes with its type
+///
+/// Loose objects and object deltas are stored this way, so the type lives in
+/// the same key as the content and can't be changed on its own.
+fn with_type_header(obj_type: ObjectType, mut stored: Vec<u8>) -> Vec<u8> {
+    let mut header = [0u8; TYPE_HEADER_LEN];
+    header[..TYPE_HEADER_MAGIC.len()].copy_from_slice(TYPE_HEADER_MAGIC);
+    header[TYPE_HEADER_MAGIC.len()] = obj_type.to_u8();
+    stored.splice(0..0, header);
+    stored
+}
+
+/// Split the type header off an object's stored bytes
+///
+/// Objects written before types were recorded have no header; they return
+/// None and the bytes unchanged.
+pub(crate) fn split_type_header(stored: &[u8]) -> (Option<ObjectType>, &[u8]) {
+    let header = stored
+        .strip_prefix(TYPE_HEADER_MAGIC.as_slice())
+        .and_then(<[u8]>::split_first)
+        .and_then(|(&byte, rest)| Some((ObjectType::from_u8(byte)?, rest)));
+    match header {
+        Some((obj_type, rest)) => (Some(obj_type), rest),
+        None => (None, stored),
+    }
+}
 
 /// Object Database with content-addressable storage
 ///
@@ -166,6 +274,21 @@ pub struct ObjectDatabase {
     /// LRU cache for decompressed base chunks used in delta encoding.
     /// Avoids re-reading and re-decompressing the same base chunk across workers.
     base_chunk_cache: Cache<Oid, Arc<Vec<u8>>>,
+
+    /// Compress small text objects with the repository's trained dictionary
+    dictionary_enabled: bool,
+
+    /// Set once the active dictionary has been looked up in storage
+    active_dictionary_loaded: Arc<tokio::sync::OnceCell<()>>,
+
+    /// Per-path compression overrides from `.mediagitattributes`
+    compression_attributes: Arc<CompressionAttributes>,
+
+    /// Bounds on trees read through this database
+    tree_limits: TreeLimits,
+
+    /// Layout for commits and trees written through this database
+    object_format: ObjectFormat,
 }
 
 impl Clone for ObjectDatabase {
@@ -181,6 +304,11 @@ impl Clone for ObjectDatabase {
             delta_enabled: self.delta_enabled,
             similarity_detector: self.similarity_detector.clone(),
             base_chunk_cache: self.base_chunk_cache.clone(),
+            dictionary_enabled: self.dictionary_enabled,
+            active_dictionary_loaded: self.active_dictionary_loaded.clone(),
+            compression_attributes: self.compression_attributes.clone(),
+            tree_limits: self.tree_limits,
+            object_format: self.object_format,
         }
     }
 }
@@ -230,6 +358,11 @@ impl ObjectDatabase {
                 crate::similarity::MAX_SIMILARITY_CANDIDATES,
             ))),
             base_chunk_cache: Cache::new(64),
+            dictionary_enabled: true,
+            active_dictionary_loaded: Arc::default(),
+            compression_attributes: Arc::default(),
+            tree_limits: TreeLimits::default(),
+            object_format: ObjectFormat::default(),
         }
     }
 
@@ -266,6 +399,11 @@ impl ObjectDatabase {
                 crate::similarity::MAX_SIMILARITY_CANDIDATES,
             ))),
             base_chunk_cache: Cache::new(64),
+            dictionary_enabled: true,
+            active_dictionary_loaded: Arc::default(),
+            compression_attributes: Arc::default(),
+            tree_limits: TreeLimits::default(),
+            object_format: ObjectFormat::default(),
         }
     }
 
@@ -291,6 +429,11 @@ impl ObjectDatabase {
                 crate::similarity::MAX_SIMILARITY_CANDIDATES,
             ))),
             base_chunk_cache: Cache::new(64),
+            dictionary_enabled: true,
+            active_dictionary_loaded: Arc::default(),
+            compression_attributes: Arc::default(),
+            tree_limits: TreeLimits::default(),
+            object_format: ObjectFormat::default(),
         }
     }
 
@@ -321,6 +464,11 @@ impl ObjectDatabase {
                 crate::similarity::MAX_SIMILARITY_CANDIDATES,
             ))),
             base_chunk_cache: Cache::new(64),
+            dictionary_enabled: true,
+            active_dictionary_loaded: Arc::default(),
+            compression_attributes: Arc::default(),
+            tree_limits: TreeLimits::default(),
+            object_format: ObjectFormat::default(),
         }
     }
 
@@ -346,12 +494,197 @@ impl ObjectDatabase {
                 crate::similarity::MAX_SIMILARITY_CANDIDATES,
             ))),
             base_chunk_cache: Cache::new(64),
+            dictionary_enabled: true,
+            active_dictionary_loaded: Arc::default(),
+            compression_attributes: Arc::default(),
+            tree_limits: TreeLimits::default(),
+            object_format: ObjectFormat::default(),
+        }
+    }
+
+    /// Store every object under a key namespace such as `repos/gameassets/`
+    ///
+    /// All keys the database builds (loose objects, chunks, manifests, deltas
+    /// and packs) are prefixed, and listings only see keys inside the
+    /// namespace, so several repositories can share one bucket. An empty
+    /// prefix leaves keys unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mediagit_versioning::ObjectDatabase;
+    /// use mediagit_storage::mock::MockBackend;
+    /// use std::sync::Arc;
+    ///
+    /// let bucket: Arc<dyn mediagit_storage::StorageBackend> = Arc::new(MockBackend::new());
+    /// let odb = ObjectDatabase::with_smart_compression(bucket, 1000)
+    ///     .with_namespace("repos/gameassets");
+    /// ```
+    pub fn with_namespace(mut self, prefix: impl AsRef<str>) -> Self {
+        let namespaced = NamespacedBackend::new(self.storage, prefix);
+        if !namespaced.prefix().is_empty() {
+            debug!(prefix = namespaced.prefix(), "Using storage key namespace");
+        }
+        self.storage = Arc::new(namespaced);
+        self
+    }
+
+    /// Run every write through `pool`
+    ///
+    /// The parallel chunk workers of
+    /// [`write_chunked_parallel`](Self::write_chunked_parallel) then wait for
+    /// room in the pool instead of starting a put for every chunk, so
+    /// connections and buffered chunk data stay within the pool's limits.
+    /// Pass [`shared_upload_pool`](mediagit_storage::shared_upload_pool) to
+    /// share them with the rest of the process.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mediagit_versioning::ObjectDatabase;
+    /// use mediagit_storage::{mock::MockBackend, UploadLimits, UploadPool};
+    /// use std::sync::Arc;
+    ///
+    /// let bucket: Arc<dyn mediagit_storage::StorageBackend> = Arc::new(MockBackend::new());
+    /// let odb = ObjectDatabase::with_smart_compression(bucket, 1000)
+    ///     .with_upload_pool(UploadPool::new(UploadLimits::new(8, 64 * 1024 * 1024)));
+    /// ```
+    pub fn with_upload_pool(mut self, pool: UploadPool) -> Self {
+        self.storage = Arc::new(PooledUploadBackend::new(self.storage, pool));
+        self
+    }
+
+    /// Store objects smaller than `min_compress_size` bytes without compression
+    ///
+    /// Applies to the smart-compression write paths; see
+    /// [`SmartCompressor::with_min_compress_size`]. Has no effect on a
+    /// database without smart compression.
+    pub fn with_min_compress_size(mut self, min_compress_size: usize) -> Self {
+        if let Some(smart) = self.smart_compressor.take() {
+            self.smart_compressor = Some(Arc::new(
+                smart
+                    .as_ref()
+                    .clone()
+                    .with_min_compress_size(min_compress_size),
+            ));
+        }
+        self
+    }
+
+    /// Compress new objects for `tier`
+    ///
+    /// Applies to the strategies the smart-compression write paths pick from
+    /// file types and codecs, and to objects compressed while repacking; see
+    /// [`SmartCompressor::with_tier`]. `.mediagitattributes` overrides are
+    /// used as given. Has no effect on a database without smart compression.
+    pub fn with_compression_tier(mut self, tier: CompressionTier) -> Self {
+        if let Some(smart) = self.smart_compressor.take() {
+            self.smart_compressor = Some(Arc::new(smart.as_ref().clone().with_tier(tier)));
+        }
+        self
+    }
+
+    /// Compress new objects that use Brotli with `settings`
+    ///
+    /// Covers `.mediagitattributes` overrides as well as strategies picked
+    /// from file types; see [`SmartCompressor::with_brotli`]. Has no effect on
+    /// a database without smart compression.
+    pub fn with_brotli(mut self, settings: BrotliSettings) -> Self {
+        if let Some(smart) = self.smart_compressor.take() {
+            self.smart_compressor = Some(Arc::new(smart.as_ref().clone().with_brotli(settings)));
+        }
+        self
+    }
+
+    /// Refuse to read objects that decompress to more than
+    /// `max_decompressed_size` bytes
+    ///
+    /// Decompression stops once the limit is passed, so a crafted object
+    /// cannot exhaust memory; see
+    /// [`SmartCompressor::with_max_decompressed_size`]. Has no effect on a
+    /// database without smart compression.
+    ///
+    /// The default admits every object up to [`MAX_OBJECT_SIZE`], so only
+    /// readers of objects from an untrusted source need a lower limit.
+    pub fn with_max_decompressed_size(mut self, max_decompressed_size: u64) -> Self {
+        if let Some(smart) = self.smart_compressor.take() {
+            self.smart_compressor = Some(Arc::new(
+                smart
+                    .as_ref()
+                    .clone()
+                    .with_max_decompressed_size(max_decompressed_size),
+            ));
+        }
+        self
+    }
+
+    /// Enable or disable the repository compression dictionary for new objects
+    ///
+    /// Enabled by default. Once [`train_dictionary`](Self::train_dictionary)
+    /// has stored a dictionary, small text objects written through
+    /// [`write_with_path`](Self::write_with_path) are compressed with it when
+    /// that beats plain compression. Disabling only affects writes; objects
+    /// already compressed with a dictionary stay readable.
+    pub fn with_dictionary_compression(mut self, enabled: bool) -> Self {
+        self.dictionary_enabled = enabled;
+        self
+    }
+
+    /// Compress paths matching `.mediagitattributes` `compression=` rules with
+    /// the strategy they name
+    ///
+    /// An override takes precedence over the strategy picked from the file
+    /// type, the codec of demuxed chunks and the compression dictionary.
+    /// Write paths are matched against the `filename` given to
+    /// [`write_with_path`](Self::write_with_path) and the chunked writers, so
+    /// callers pass paths relative to the repository root. Has no effect on a
+    /// database without smart compression.
+    pub fn with_compression_attributes(mut self, attributes: CompressionAttributes) -> Self {
+        self.compression_attributes = Arc::new(attributes);
+        self
+    }
+
+    /// Bound the trees read through this database
+    ///
+    /// [`Tree::read`](crate::Tree::read) rejects trees with more entries than
+    /// allowed, and checkout and fsck stop descending past the depth limit.
+    pub fn with_tree_limits(mut self, limits: TreeLimits) -> Self {
+        self.tree_limits = limits;
+        self
+    }
+
+    /// Bounds on trees read through this database
+    pub fn tree_limits(&self) -> &TreeLimits {
+        &self.tree_limits
+    }
+
+    /// Write commits and trees in `format`
+    ///
+    /// Objects already stored keep their layout; both are always readable.
+    pub fn with_object_format(mut self, format: ObjectFormat) -> Self {
+        self.object_format = format;
+        self
+    }
+
+    /// Layout for commits and trees written through this database
+    pub fn object_format(&self) -> ObjectFormat {
+        self.object_format
+    }
+
+    /// Compression strategy `.mediagitattributes` sets for `filename`, if any
+    fn compression_override(&self, filename: &str) -> Option<CompressionStrategy> {
+        if filename.is_empty() {
+            return None;
         }
+        self.compression_attributes
+            .strategy_for(std::path::Path::new(filename))
     }
 
     /// Get reference to the underlying storage backend
     ///
     /// Useful for creating transactions or accessing storage directly.
+    /// With [`with_namespace`](Self::with_namespace) this is the namespaced
+    /// view, so keys used through it stay inside the namespace.
     pub fn storage(&self) -> &Arc<dyn StorageBackend> {
         &self.storage
     }
@@ -398,6 +731,7 @@ impl ObjectDatabase {
 
         // Compute OID from UNCOMPRESSED content (Git compatibility)
         let oid = Oid::hash(data);
+        let category = write_category(obj_type, CompressionObjectType::from_magic_bytes(data));
 
         debug!(
             oid = %oid,
@@ -411,13 +745,12 @@ impl ObjectDatabase {
         let key = oid.to_hex();
 
         // Check if object already exists (deduplication)
-        let exists = self.storage.exists(&key).await?;
+        let exists = self.is_stored(&oid, data.len()).await?;
 
         if exists {
             debug!(oid = %oid, "Object already exists (deduplicated)");
             // Update metrics for duplicate write
-            let mut metrics = self.metrics.write().await;
-            metrics.record_write(data.len() as u64, false);
+            self.record_write(category, data.len() as u64, false).await;
         } else {
             // Compress data if enabled
             let storage_data = if self.compression_enabled {
@@ -439,8 +772,10 @@ impl ObjectDatabase {
                 data.to_vec()
             };
 
-            // Store object (compressed or raw)
+            // Store object (compressed or raw) behind its type
+            let storage_data = with_type_header(obj_type, storage_data);
             self.storage.put(&key, &storage_data).await?;
+            record_compression(&self.metrics, category, data.len(), &storage_data).await;
 
             info!(
                 oid = %oid,
@@ -451,8 +786,7 @@ impl ObjectDatabase {
             );
 
             // Update metrics for new write
-            let mut metrics = self.metrics.write().await;
-            metrics.record_write(data.len() as u64, true);
+            self.record_write(category, data.len() as u64, true).await;
         }
 
         // Cache the UNCOMPRESSED object for future reads
@@ -480,6 +814,23 @@ impl ObjectDatabase {
         obj_type: ObjectType,
         data: &[u8],
         filename: &str,
+    ) -> anyhow::Result<Oid> {
+        self.write_with_path_compressed(obj_type, data, filename, None)
+            .await
+    }
+
+    /// [`write_with_path`](Self::write_with_path), storing `compressed` if
+    /// the caller already compressed `data` with
+    /// [`compress_for_path`](Self::compress_for_path)
+    ///
+    /// Without smart compression `data` goes through [`write`](Self::write)
+    /// and is compressed again.
+    async fn write_with_path_compressed(
+        &self,
+        obj_type: ObjectType,
+        data: &[u8],
+        filename: &str,
+        compressed: Option<Vec<u8>>,
     ) -> anyhow::Result<Oid> {
         // If smart compression is not enabled, fall back to standard write
         if self.smart_compressor.is_none() {
@@ -489,12 +840,9 @@ impl ObjectDatabase {
         // Compute OID from UNCOMPRESSED content (Git compatibility)
         let oid = Oid::hash(data);
 
-        // Detect file type for smart compression
-        let compression_type = if !filename.is_empty() {
-            CompressionObjectType::from_path(filename)
-        } else {
-            CompressionObjectType::from_magic_bytes(data)
-        };
+        // Detect file type for smart compression; content overrides a wrong extension
+        let compression_type = CompressionObjectType::detect(filename, data);
+        let category = write_category(obj_type, compression_type);
 
         debug!(
             oid = %oid,
@@ -509,38 +857,30 @@ impl ObjectDatabase {
         let key = oid.to_hex();
 
         // Check if object already exists (deduplication)
-        let exists = self.storage.exists(&key).await?;
+        let exists = self.is_stored(&oid, data.len()).await?;
 
         if exists {
             debug!(oid = %oid, "Object already exists (deduplicated)");
-            let mut metrics = self.metrics.write().await;
-            metrics.record_write(data.len() as u64, false);
+            self.record_write(category, data.len() as u64, false).await;
         } else {
             // Use smart compressor with size-aware strategy
-            let storage_data = if let Some(smart_comp) = &self.smart_compressor {
-                let compressed = smart_comp
-                    .compress_typed_with_size(data, compression_type)
-                    .map_err(|e| anyhow::anyhow!("Smart compression failed: {}", e))?;
-
-                debug!(
-                    oid = %oid,
-                    original_size = data.len(),
-                    compressed_size = compressed.len(),
-                    ratio = compressed.len() as f64 / data.len() as f64,
-                    file_type = ?compression_type,
-                    "Smart compressed object"
-                );
-
-                compressed
-            } else {
-                // Fallback to standard compression
-                self.compressor
-                    .compress(data)
-                    .map_err(|e| anyhow::anyhow!("Compression failed: {}", e))?
+            let storage_data = match compressed {
+                Some(compressed) => compressed,
+                None => self.compress_for_path(data, filename).await?,
             };
+            debug!(
+                oid = %oid,
+                original_size = data.len(),
+                compressed_size = storage_data.len(),
+                ratio = storage_data.len() as f64 / data.len() as f64,
+                file_type = ?compression_type,
+                "Smart compressed object"
+            );
 
-            // Store object
+            // Store object behind its type
+            let storage_data = with_type_header(obj_type, storage_data);
             self.storage.put(&key, &storage_data).await?;
+            record_compression(&self.metrics, category, data.len(), &storage_data).await;
 
             info!(
                 oid = %oid,
@@ -551,8 +891,7 @@ impl ObjectDatabase {
             );
 
             // Update metrics
-            let mut metrics = self.metrics.write().await;
-            metrics.record_write(data.len() as u64, true);
+            self.record_write(category, data.len() as u64, true).await;
         }
 
         // Cache the UNCOMPRESSED object
@@ -561,6 +900,31 @@ impl ObjectDatabase {
         Ok(oid)
     }
 
+    /// `data` compressed as [`write_with_path`](Self::write_with_path)
+    /// stores it in full
+    async fn compress_for_path(&self, data: &[u8], filename: &str) -> anyhow::Result<Vec<u8>> {
+        let Some(smart_comp) = &self.smart_compressor else {
+            if !self.compression_enabled {
+                return Ok(data.to_vec());
+            }
+            return self
+                .compressor
+                .compress(data)
+                .map_err(|e| anyhow::anyhow!("Compression failed: {}", e));
+        };
+
+        let compression_type = CompressionObjectType::detect(filename, data);
+        if let Some(strategy) = self.compression_override(filename) {
+            smart_comp.compress_with_strategy(data, strategy)
+        } else if self.dictionary_enabled {
+            self.load_active_dictionary().await?;
+            smart_comp.compress_with_dictionary(data, compression_type)
+        } else {
+            smart_comp.compress_typed_with_size(data, compression_type)
+        }
+        .map_err(|e| anyhow::anyhow!("Smart compression failed: {}", e))
+    }
+
     /// Try to store a chunk as delta against a similar existing chunk.
     ///
     /// Returns `true` if the chunk was successfully stored as a delta,
@@ -681,7 +1045,6 @@ impl ObjectDatabase {
 
         // Skip chunking for small files (<1MB) to avoid overhead
         // Files 1-10MB benefit from chunking for delta encoding
-        const MIN_CHUNK_SIZE: usize = 1024 * 1024; // 1MB
         if data.len() < MIN_CHUNK_SIZE {
             debug!(
                 size = data.len(),
@@ -697,7 +1060,7 @@ impl ObjectDatabase {
         // - Large videos benefit from chunk-level delta encoding
         // - Enables resumable transfers for large files
         if !filename.is_empty() {
-            let compression_type = CompressionObjectType::from_path(filename);
+            let compression_type = CompressionObjectType::detect(filename, data);
             let should_skip_chunking = matches!(
                 compression_type,
                 // Compressed images: typically small, don't benefit from chunking
@@ -726,6 +1089,7 @@ impl ObjectDatabase {
 
         // Compute OID from original data (git compatibility)
         let oid = Oid::hash(data);
+        let category = write_category(obj_type, CompressionObjectType::from_path(filename));
 
         debug!(
             oid = %oid,
@@ -741,8 +1105,7 @@ impl ObjectDatabase {
         })?;
         if exists {
             debug!(oid = %oid, "Chunked object already exists (deduplicated)");
-            let mut metrics = self.metrics.write().await;
-            metrics.record_write(data.len() as u64, false);
+            self.record_write(category, data.len() as u64, false).await;
             return Ok(oid);
         }
 
@@ -805,11 +1168,17 @@ impl ObjectDatabase {
                         } else {
                             CompressionObjectType::Unknown
                         };
-                        smart_comp
-                            .compress_typed_with_size(&chunk.data, chunk_comp_type)
-                            .map_err(|e| {
-                                anyhow::anyhow!("Failed to compress chunk {}: {}", chunk_key, e)
-                            })?
+                        match self.compression_override(filename) {
+                            Some(strategy) => {
+                                smart_comp.compress_with_strategy(&chunk.data, strategy)
+                            }
+                            None => {
+                                smart_comp.compress_typed_with_size(&chunk.data, chunk_comp_type)
+                            }
+                        }
+                        .map_err(|e| {
+                            anyhow::anyhow!("Failed to compress chunk {}: {}", chunk_key, e)
+                        })?
                     } else {
                         self.compressor.compress(&chunk.data).map_err(|e| {
                             anyhow::anyhow!("Failed to compress chunk {}: {}", chunk_key, e)
@@ -827,6 +1196,8 @@ impl ObjectDatabase {
                                 e
                             )
                         })?;
+                    record_compression(&self.metrics, category, chunk.data.len(), &compressed)
+                        .await;
 
                     debug!(
                         chunk_id = %chunk.id,
@@ -869,8 +1240,7 @@ impl ObjectDatabase {
         );
 
         // Update metrics
-        let mut metrics = self.metrics.write().await;
-        metrics.record_write(data.len() as u64, true);
+        self.record_write(category, data.len() as u64, true).await;
 
         // NOTE: Don't cache full data for chunked objects - individual chunks are
         // already stored and the manifest provides reconstruction. Caching the full
@@ -899,14 +1269,13 @@ impl ObjectDatabase {
             return self.write_with_path(obj_type, data, filename).await;
         }
 
-        const MIN_CHUNK_SIZE: usize = 1024 * 1024; // 1MB
         if data.len() < MIN_CHUNK_SIZE {
             return self.write_with_path(obj_type, data, filename).await;
         }
 
         // Skip chunking for small compressed formats
         if !filename.is_empty() {
-            let compression_type = CompressionObjectType::from_path(filename);
+            let compression_type = CompressionObjectType::detect(filename, data);
             let should_skip = matches!(
                 compression_type,
                 CompressionObjectType::Jpeg
@@ -926,6 +1295,7 @@ impl ObjectDatabase {
 
         // Compute OID from original data
         let oid = Oid::hash(data);
+        let category = write_category(obj_type, CompressionObjectType::from_path(filename));
 
         // Check if object already exists
         let key = oid.to_hex();
@@ -935,8 +1305,7 @@ impl ObjectDatabase {
             .await
             .map_err(|e| anyhow::anyhow!("Failed to check object existence: {}", e))?
         {
-            let mut metrics = self.metrics.write().await;
-            metrics.record_write(data.len() as u64, false);
+            self.record_write(category, data.len() as u64, false).await;
             return Ok(oid);
         }
 
@@ -947,8 +1316,7 @@ impl ObjectDatabase {
             .await
             .unwrap_or(false)
         {
-            let mut metrics = self.metrics.write().await;
-            metrics.record_write(data.len() as u64, false);
+            self.record_write(category, data.len() as u64, false).await;
             return Ok(oid);
         }
 
@@ -970,6 +1338,7 @@ impl ObjectDatabase {
         } else {
             CompressionObjectType::Unknown
         };
+        let comp_override = self.compression_override(filename);
 
         // For small chunk counts, sequential is faster (no channel overhead).
         // Threshold lowered to 2: even two chunks benefit from parallel I/O.
@@ -1001,6 +1370,7 @@ impl ObjectDatabase {
             let similarity_detector = self.similarity_detector.clone();
             let delta_enabled = self.delta_enabled;
             let base_chunk_cache = self.base_chunk_cache.clone();
+            let metrics = Arc::clone(&self.metrics);
 
             let handle = tokio::spawn(async move {
                 let mut results: Vec<(usize, ChunkRef)> = Vec::new();
@@ -1177,9 +1547,13 @@ impl ObjectDatabase {
                     // 3. Full compress + store if not delta
                     if !stored_as_delta {
                         let compressed = if let Some(ref smart) = smart_comp {
-                            smart
-                                .compress_typed_with_size(&chunk.data, comp_type)
-                                .map_err(|e| anyhow::anyhow!("Compress chunk: {}", e))?
+                            match comp_override {
+                                Some(strategy) => {
+                                    smart.compress_with_strategy(&chunk.data, strategy)
+                                }
+                                None => smart.compress_typed_with_size(&chunk.data, comp_type),
+                            }
+                            .map_err(|e| anyhow::anyhow!("Compress chunk: {}", e))?
                         } else {
                             compressor
                                 .compress(&chunk.data)
@@ -1192,6 +1566,7 @@ impl ObjectDatabase {
                                 return Err(anyhow::anyhow!("Store chunk: {}", e));
                             }
                         }
+                        record_compression(&metrics, category, chunk.data.len(), &compressed).await;
 
                         debug!(
                             chunk_id = %chunk.id,
@@ -1248,8 +1623,7 @@ impl ObjectDatabase {
         info!(oid = %oid, chunks = manifest.chunk_count(), "Parallel chunked write complete");
 
         // Update metrics
-        let mut metrics = self.metrics.write().await;
-        metrics.record_write(data.len() as u64, true);
+        self.record_write(category, data.len() as u64, true).await;
 
         // NOTE: Don't cache full data for chunked objects - individual chunks are
         // already stored and the manifest provides reconstruction. Caching the full
@@ -1319,6 +1693,8 @@ impl ObjectDatabase {
             .await?
         {
             debug!("File already exists in storage: {}", file_oid);
+            let category = CompressionObjectType::from_path(filename).category();
+            self.record_write(category, file_size, false).await;
             // Report full file size so progress bar stays accurate
             if let Some(ref cb) = on_progress {
                 cb(file_size);
@@ -1338,6 +1714,7 @@ impl ObjectDatabase {
         } else {
             CompressionObjectType::Unknown
         };
+        let comp_override = self.compression_override(filename);
 
         // --- Parallel pipeline: spawn workers FIRST, then produce chunks ---
         let num_workers = num_cpus::get().clamp(2, 16);
@@ -1356,6 +1733,7 @@ impl ObjectDatabase {
             let compression_enabled = self.compression_enabled;
             let delta_enabled = self.delta_enabled;
             let similarity_detector = self.similarity_detector.clone();
+            let metrics = Arc::clone(&self.metrics);
             let chunks_w = chunks_written.clone();
             let bytes_w = bytes_written.clone();
             let on_progress = on_progress.clone();
@@ -1535,8 +1913,14 @@ impl ObjectDatabase {
                     if !stored_as_delta {
                         let codec_hint = to_chunk_codec_hint(chunk.codec_hint, chunk.chunk_type);
                         let data_to_store = if let Some(ref smart) = smart_comp {
-                            // Try codec-aware compression first
-                            if let Some(result) = smart.compress_by_codec(&chunk.data, codec_hint) {
+                            // A path override wins, then codec-aware compression
+                            if let Some(strategy) = comp_override {
+                                smart
+                                    .compress_with_strategy(&chunk.data, strategy)
+                                    .map_err(|e| anyhow::anyhow!("Compress chunk: {}", e))?
+                            } else if let Some(result) =
+                                smart.compress_by_codec(&chunk.data, codec_hint)
+                            {
                                 result
                                     .map_err(|e| anyhow::anyhow!("Compress chunk (codec): {}", e))?
                             } else {
@@ -1557,6 +1941,13 @@ impl ObjectDatabase {
                             .put(&chunk_key, &data_to_store)
                             .await
                             .map_err(|e| anyhow::anyhow!("Store chunk: {}", e))?;
+                        record_compression(
+                            &metrics,
+                            comp_type.category(),
+                            chunk.data.len(),
+                            &data_to_store,
+                        )
+                        .await;
                     }
 
                     chunks_w.fetch_add(1, Ordering::Relaxed);
@@ -1640,8 +2031,8 @@ impl ObjectDatabase {
         );
 
         // Update metrics
-        let mut metrics = self.metrics.write().await;
-        metrics.record_write(file_size, true);
+        self.record_write(comp_type.category(), file_size, true)
+            .await;
 
         Ok(file_oid)
     }
@@ -1665,7 +2056,8 @@ impl ObjectDatabase {
     /// 1. Generate samples from the new object
     /// 2. Search recent objects for similarity (> 30%)
     /// 3. If similar object found, create delta
-    /// 4. Use delta only if smaller than 80% of original
+    /// 4. Use delta only if smaller than 80% of the object as it would be
+    ///    stored in full, compressed
     /// 5. Fall back to standard write otherwise
     pub async fn write_with_delta(
         &self,
@@ -1715,6 +2107,8 @@ impl ObjectDatabase {
         let similar = detector.find_similar_with_size_ratio(&metadata, threshold, size_ratio);
         drop(detector);
 
+        // The full object, compressed, once a delta has been weighed against it
+        let mut full = None;
         if let Some((base_oid, score)) = similar {
             // CRITICAL: Prevent self-referencing delta (OID == base OID)
             if oid == base_oid {
@@ -1760,33 +2154,32 @@ impl ObjectDatabase {
                             // Fall through to standard write
                         } else {
                             // Create delta
-                            let delta = DeltaEncoder::encode(&base_data, data);
-                            let delta_data = delta.to_bytes();
-
-                            // Only use delta if it's smaller than 80% of original
-                            let delta_ratio = delta_data.len() as f64 / data.len() as f64;
-
-                            if delta_ratio < 0.80 {
+                            let compressed_delta =
+                                self.encode_delta(&base_oid, &base_data, data)?;
+
+                            // Only use delta if it's smaller than 80% of the
+                            // full object compressed
+                            let full_size = full
+                                .insert(self.compress_for_path(data, filename).await?)
+                                .len();
+                            let delta_ratio = compressed_delta
+                                .as_ref()
+                                .map_or(1.0, |delta| delta.len() as f64 / full_size.max(1) as f64);
+
+                            if let Some(compressed_delta) =
+                                compressed_delta.filter(|_| delta_ratio < 0.80)
+                            {
                                 info!(
                                     oid = %oid,
                                     original_size = data.len(),
-                                    delta_size = delta_data.len(),
+                                    delta_size = compressed_delta.len(),
                                     ratio = delta_ratio,
                                     "Delta compression beneficial, storing delta"
                                 );
 
                                 // Store delta
                                 let delta_key = format!("deltas/{}", oid.to_hex());
-                                let compressed_delta =
-                                    if let Some(smart_comp) = &self.smart_compressor {
-                                        smart_comp.compress_typed(
-                                            &delta_data,
-                                            CompressionObjectType::Unknown,
-                                        )?
-                                    } else {
-                                        self.compressor.compress(&delta_data)?
-                                    };
-
+                                let compressed_delta = with_type_header(obj_type, compressed_delta);
                                 self.storage.put(&delta_key, &compressed_delta).await?;
 
                                 // Store delta metadata (base OID reference + chain depth)
@@ -1804,8 +2197,11 @@ impl ObjectDatabase {
                                 );
 
                                 // Update metrics
-                                let mut metrics = self.metrics.write().await;
-                                metrics.record_write(data.len() as u64, true);
+                                let category = write_category(
+                                    obj_type,
+                                    CompressionObjectType::from_path(filename),
+                                );
+                                self.record_write(category, data.len() as u64, true).await;
 
                                 // Cache original data
                                 self.cache.insert(oid, Arc::new(data.to_vec())).await;
@@ -1843,7 +2239,69 @@ impl ObjectDatabase {
         drop(detector);
 
         // Fall back to standard write
-        self.write_with_path(obj_type, data, filename).await
+        self.write_with_path_compressed(obj_type, data, filename, full)
+            .await
+    }
+
+    /// Delta of `data` against the object `base_oid`, as stored under
+    /// `deltas/`
+    ///
+    /// With smart compression this is a compressed delta framed with the id
+    /// of its base (see [`SmartCompressor::compress_delta`]), or `None` when
+    /// it would be no smaller than `data` compressed with Zstd.
+    fn encode_delta(
+        &self,
+        base_oid: &Oid,
+        base_data: &[u8],
+        data: &[u8],
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(smart_comp) = &self.smart_compressor else {
+            let delta = DeltaEncoder::encode(base_data, data);
+            return Ok(Some(self.compressor.compress(&delta.to_bytes())?));
+        };
+
+        let base = DeltaBase {
+            id: base_oid.as_bytes(),
+            data: base_data,
+        };
+        let stored = smart_comp.compress_delta(data, Some(base))?;
+        Ok(mediagit_compression::delta::base_id_of(&stored)
+            .is_some()
+            .then_some(stored))
+    }
+
+    /// Apply a delta stored under `deltas/` to the content of its base
+    /// `base_oid`
+    ///
+    /// Reads both framed deltas and the unframed, compressed deltas written
+    /// before them.
+    fn decode_delta(
+        &self,
+        stored: &[u8],
+        base_oid: &Oid,
+        base_data: Vec<u8>,
+    ) -> anyhow::Result<Vec<u8>> {
+        if let Some(smart_comp) = &self.smart_compressor {
+            if mediagit_compression::delta::base_id_of(stored).is_some() {
+                let data = smart_comp.decompress_with_base(stored, |id| {
+                    if id != base_oid.as_bytes() {
+                        return Err(CompressionError::decompression_failed(format!(
+                            "delta was encoded against a different base than {}",
+                            base_oid
+                        )));
+                    }
+                    Ok(base_data)
+                })?;
+                return Ok(data);
+            }
+        }
+
+        let delta_bytes = match &self.smart_compressor {
+            Some(smart_comp) => smart_comp.decompress_typed(stored)?,
+            None => self.compressor.decompress(stored)?,
+        };
+        let delta = Delta::from_bytes(&delta_bytes)?;
+        Ok(DeltaDecoder::apply(&base_data, &delta)?)
     }
 
     /// Get the delta chain depth for an object
@@ -1995,69 +2453,51 @@ impl ObjectDatabase {
                     // Parse pack file
                     match PackReader::new(pack_data) {
                         Ok(pack_reader) => {
-                            // Try to get object from this pack
-                            match pack_reader.get_object(oid) {
-                                Ok(compressed_data) => {
-                                    debug!(
-                                        oid = %oid,
-                                        pack = pack_key,
-                                        "Found object in pack file"
-                                    );
-
-                                    // Decompress the object data (pack stores compressed data)
-                                    let data = if let Some(smart_comp) = &self.smart_compressor {
-                                        match smart_comp.decompress_typed(&compressed_data) {
-                                            Ok(d) => d,
-                                            Err(_) => {
-                                                // Fallback to standard decompression
-                                                match self.compressor.decompress(&compressed_data) {
-                                                    Ok(d) => d,
-                                                    Err(_) => compressed_data, // Use raw data as last resort
-                                                }
-                                            }
-                                        }
-                                    } else if self.compression_enabled
-                                        || (compressed_data.len() >= 2
-                                            && compressed_data[0] == 0x78)
-                                    {
-                                        match self.compressor.decompress(&compressed_data) {
-                                            Ok(d) => d,
-                                            Err(_) => compressed_data,
-                                        }
-                                    } else {
-                                        compressed_data
-                                    };
-
-                                    // Verify integrity
-                                    let computed_oid = Oid::hash(&data);
-                                    if computed_oid != *oid {
-                                        warn!(
-                                            expected = %oid,
-                                            computed = %computed_oid,
+                            // Deltas apply to the base's content, not its stored bytes
+                            let data = match pack_reader.get_delta(oid) {
+                                Ok(Some((base_oid, delta))) => {
+                                    let base_data = Box::pin(self.read(&base_oid)).await?;
+                                    DeltaDecoder::apply(&base_data, &delta)?
+                                }
+                                Ok(None) => match pack_reader.get_object(oid) {
+                                    Ok(compressed_data) => {
+                                        debug!(
+                                            oid = %oid,
                                             pack = pack_key,
-                                            "Pack object integrity check failed"
+                                            "Found object in pack file"
                                         );
-                                        continue; // Try next pack
+                                        self.decompress_packed(compressed_data).await?
                                     }
+                                    // Object not in this pack, try next one
+                                    Err(_) => continue,
+                                },
+                                Err(_) => continue,
+                            };
+
+                            // Verify integrity
+                            let computed_oid = Oid::hash(&data);
+                            if computed_oid != *oid {
+                                warn!(
+                                    expected = %oid,
+                                    computed = %computed_oid,
+                                    pack = pack_key,
+                                    "Pack object integrity check failed"
+                                );
+                                continue; // Try next pack
+                            }
 
-                                    // Cache the decompressed data
-                                    let arc_data = Arc::new(data.clone());
-                                    self.cache.insert(*oid, arc_data).await;
+                            // Cache the decompressed data
+                            let arc_data = Arc::new(data.clone());
+                            self.cache.insert(*oid, arc_data).await;
 
-                                    info!(
-                                        oid = %oid,
-                                        pack = pack_key,
-                                        size = data.len(),
-                                        "Successfully read object from pack file"
-                                    );
+                            info!(
+                                oid = %oid,
+                                pack = pack_key,
+                                size = data.len(),
+                                "Successfully read object from pack file"
+                            );
 
-                                    return Ok(data);
-                                }
-                                Err(_) => {
-                                    // Object not in this pack, try next one
-                                    continue;
-                                }
-                            }
+                            return Ok(data);
                         }
                         Err(e) => {
                             warn!(
@@ -2251,22 +2691,10 @@ impl ObjectDatabase {
             .await
             .map_err(|e| anyhow::anyhow!("Failed to read delta data for {}: {}", oid, e))?;
 
-        // Decompress delta
-        let delta_bytes = if let Some(smart_comp) = &self.smart_compressor {
-            smart_comp
-                .decompress_typed(&compressed_delta)
-                .map_err(|e| anyhow::anyhow!("Failed to decompress delta: {}", e))?
-        } else {
-            self.compressor
-                .decompress(&compressed_delta)
-                .map_err(|e| anyhow::anyhow!("Failed to decompress delta: {}", e))?
-        };
-
-        // Parse and apply delta
-        let delta = Delta::from_bytes(&delta_bytes)
-            .map_err(|e| anyhow::anyhow!("Failed to parse delta: {}", e))?;
-
-        let reconstructed = DeltaDecoder::apply(&base_data, &delta)
+        let base_size = base_data.len();
+        let (_, delta) = split_type_header(&compressed_delta);
+        let reconstructed = self
+            .decode_delta(delta, &base_oid, base_data)
             .map_err(|e| anyhow::anyhow!("Failed to apply delta: {}", e))?;
 
         // Verify integrity
@@ -2282,8 +2710,8 @@ impl ObjectDatabase {
         info!(
             oid = %oid,
             base_oid = %base_oid,
-            base_size = base_data.len(),
-            delta_size = delta_bytes.len(),
+            base_size,
+            delta_size = compressed_delta.len(),
             result_size = reconstructed.len(),
             "Successfully reconstructed delta-encoded object"
         );
@@ -2368,14 +2796,8 @@ impl ObjectDatabase {
                 // Read and apply delta
                 let delta_key = format!("deltas/{}", oid.to_hex());
                 let compressed_delta = self.storage.get(&delta_key).await?;
-                let delta_bytes = if let Some(smart_comp) = &self.smart_compressor {
-                    smart_comp.decompress_typed(&compressed_delta)?
-                } else {
-                    self.compressor.decompress(&compressed_delta)?
-                };
-
-                let delta = Delta::from_bytes(&delta_bytes)?;
-                let reconstructed = DeltaDecoder::apply(&base_data, &delta)?;
+                let (_, delta) = split_type_header(&compressed_delta);
+                let reconstructed = self.decode_delta(delta, &base_oid, base_data)?;
 
                 // Cache and return
                 self.cache
@@ -2393,15 +2815,17 @@ impl ObjectDatabase {
 
             // Try loose object
             let key = oid.to_hex();
-            if let Ok(storage_data) = self.storage.get(&key).await {
+            if let Ok(stored) = self.storage.get(&key).await {
+                let (_, storage_data) = split_type_header(&stored);
+                self.load_dictionary_for(storage_data).await?;
                 let data = if let Some(smart_comp) = &self.smart_compressor {
                     smart_comp
-                        .decompress_typed(&storage_data)
-                        .unwrap_or_else(|_| storage_data.clone())
+                        .decompress_typed(storage_data)
+                        .unwrap_or_else(|_| storage_data.to_vec())
                 } else {
                     self.compressor
-                        .decompress(&storage_data)
-                        .unwrap_or(storage_data)
+                        .decompress(storage_data)
+                        .unwrap_or_else(|_| storage_data.to_vec())
                 };
 
                 self.cache.insert(oid, Arc::new(data.clone())).await;
@@ -2413,6 +2837,65 @@ impl ObjectDatabase {
         })
     }
 
+    /// Fetch many objects concurrently, delivering each as soon as it arrives
+    ///
+    /// Reads are started in the order of `oids`, at most `concurrency` at a
+    /// time, so the caller decides what is requested first; results arrive in
+    /// completion order. Chunked objects are not loaded into memory but
+    /// reported as [`Prefetched::Chunked`] for the caller to stream with
+    /// [`read_to_file`](Self::read_to_file). Dropping the receiver stops
+    /// further reads.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use mediagit_versioning::{ObjectDatabase, Oid, Prefetched};
+    /// # async fn example(odb: ObjectDatabase, oids: Vec<Oid>) -> anyhow::Result<()> {
+    /// let mut arrivals = odb.prefetch(oids, 16).await;
+    /// while let Some((oid, fetched)) = arrivals.recv().await {
+    ///     if let Prefetched::Data(data) = fetched? {
+    ///         println!("{}: {} bytes", oid, data.len());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn prefetch(
+        &self,
+        oids: Vec<Oid>,
+        concurrency: usize,
+    ) -> tokio::sync::mpsc::Receiver<(Oid, anyhow::Result<Prefetched>)> {
+        let concurrency = concurrency.max(1);
+        self.metrics.write().await.record_prefetch(oids.len());
+        debug!(objects = oids.len(), concurrency, "Prefetching objects");
+
+        let (tx, rx) = tokio::sync::mpsc::channel(concurrency);
+        let odb = self.clone();
+        tokio::spawn(async move {
+            let permits = Arc::new(tokio::sync::Semaphore::new(concurrency));
+            for oid in oids {
+                let Ok(permit) = permits.clone().acquire_owned().await else {
+                    break;
+                };
+                if tx.is_closed() {
+                    break;
+                }
+                let odb = odb.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let fetched = match odb.is_chunked(&oid).await {
+                        Ok(true) => Ok(Prefetched::Chunked),
+                        Ok(false) => odb.read(&oid).await.map(Prefetched::Data),
+                        Err(e) => Err(e),
+                    };
+                    let _ = tx.send((oid, fetched)).await;
+                    drop(permit);
+                });
+            }
+        });
+        rx
+    }
+
     /// Read an object and stream directly to file (constant memory)
     ///
     /// This method writes chunked objects directly to disk without loading
@@ -2585,9 +3068,15 @@ impl ObjectDatabase {
             return self.read_delta(oid).await;
         }
 
-        // Try standard loose object path first
+        // Try standard loose object path first. Backends that can memory-map
+        // large objects do so, leaving only the decompressed copy on the heap.
         let key = oid.to_hex();
-        let storage_data = match self.storage.get(&key).await {
+        let stored = if self.storage.capabilities().mmap {
+            self.storage.get_mapped(&key).await
+        } else {
+            self.storage.get(&key).await.map(MmapOrVec::Vec)
+        };
+        let stored = match stored {
             Ok(data) => data,
             Err(_) => {
                 // Loose object not found - fallback to pack files
@@ -2595,11 +3084,17 @@ impl ObjectDatabase {
                 return self.read_from_packs(oid).await;
             }
         };
+        if matches!(stored, MmapOrVec::Mmap(_)) {
+            debug!(oid = %oid, size = stored.as_ref().len(), "Reading memory-mapped object");
+            self.metrics.write().await.record_mmap_read();
+        }
+        let (_, storage_data) = split_type_header(stored.as_ref());
 
         // Decompress data with smart decompression if available
+        self.load_dictionary_for(storage_data).await?;
         let data = if let Some(smart_comp) = &self.smart_compressor {
             // Use smart compressor for auto-detection of compression type
-            match smart_comp.decompress_typed(&storage_data) {
+            match smart_comp.decompress_typed(storage_data) {
                 Ok(decompressed) => {
                     debug!(
                         oid = %oid,
@@ -2609,6 +3104,9 @@ impl ObjectDatabase {
                     );
                     decompressed
                 }
+                Err(e) if e.is_output_too_large() => {
+                    return Err(anyhow::anyhow!("Object {} is too large: {}", oid, e));
+                }
                 Err(e) => {
                     warn!(
                         oid = %oid,
@@ -2616,15 +3114,15 @@ impl ObjectDatabase {
                         "Smart decompression failed, trying fallback"
                     );
                     // Fallback to standard decompression
-                    match self.compressor.decompress(&storage_data) {
+                    match self.compressor.decompress(storage_data) {
                         Ok(d) => d,
-                        Err(_) => storage_data, // Use raw data as last resort
+                        Err(_) => storage_data.to_vec(), // Use raw data as last resort
                     }
                 }
             }
         } else if self.compression_enabled || (storage_data.len() >= 2 && storage_data[0] == 0x78) {
             // Standard decompression path
-            match self.compressor.decompress(&storage_data) {
+            match self.compressor.decompress(storage_data) {
                 Ok(decompressed) => {
                     debug!(
                         oid = %oid,
@@ -2641,14 +3139,14 @@ impl ObjectDatabase {
                             error = %e,
                             "Decompression failed, using raw data"
                         );
-                        storage_data
+                        storage_data.to_vec()
                     } else {
                         return Err(anyhow::anyhow!("Decompression failed: {}", e));
                     }
                 }
             }
         } else {
-            storage_data
+            storage_data.to_vec()
         };
 
         // Validate decompressed size to prevent OOM from corrupted data
@@ -2730,28 +3228,283 @@ impl ObjectDatabase {
         self.storage.exists(&manifest_key).await
     }
 
-    /// Get the chunk manifest for a chunked object
+    /// Get the type of an object
     ///
-    /// Returns None if the object is not chunked.
-    pub async fn get_chunk_manifest(
-        &self,
-        oid: &Oid,
-    ) -> anyhow::Result<Option<crate::chunking::ChunkManifest>> {
-        let manifest_key = format!("manifests/{}", oid.to_hex());
-
-        if !self.storage.exists(&manifest_key).await? {
-            return Ok(None);
-        }
-
-        let manifest_data = self.storage.get(&manifest_key).await?;
-        let manifest: crate::chunking::ChunkManifest =
-            crate::format::deserialize(&manifest_data)
-                .map_err(|e| anyhow::anyhow!("Failed to deserialize chunk manifest: {}", e))?;
-
-        Ok(Some(manifest))
-    }
-
-    /// Seed the similarity detector with chunks from a previous manifest.
+    /// Reads the type stored with the object (see
+    /// [`recorded_type`](Self::recorded_type)), which for a loose object costs
+    /// one ranged read of its header and no parsing. The type is checked
+    /// against the content whenever the object is read with
+    /// [`read_typed`](Self::read_typed), and by fsck. Objects written before
+    /// types were recorded fall back to parsing the content.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mediagit_versioning::{ObjectDatabase, ObjectType};
+    /// # use mediagit_storage::LocalBackend;
+    /// # use std::sync::Arc;
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// # let storage: Arc<dyn mediagit_storage::StorageBackend> =
+    /// #     Arc::new(LocalBackend::new("/tmp/odb").await?);
+    /// # let odb = ObjectDatabase::new(storage, 100);
+    /// let oid = odb.write(ObjectType::Blob, b"test data").await?;
+    /// assert_eq!(odb.object_type(&oid).await?, ObjectType::Blob);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn object_type(&self, oid: &Oid) -> anyhow::Result<ObjectType> {
+        if let Some(obj_type) = self.recorded_type(oid).await? {
+            return Ok(obj_type);
+        }
+        let data = self.read(oid).await?;
+        Ok(infer_object_type(&data))
+    }
+
+    /// Read an object together with its type
+    ///
+    /// The content is verified against the OID as by [`read`](Self::read),
+    /// and the stored type against the content: an object stored as a
+    /// commit or tree must decode as exactly one, so a damaged header is an
+    /// error rather than a wrong answer. Blobs are arbitrary bytes, so a blob
+    /// header always agrees.
+    pub async fn read_typed(&self, oid: &Oid) -> anyhow::Result<(ObjectType, Vec<u8>)> {
+        let data = self.read(oid).await?;
+        let Some(obj_type) = self.recorded_type(oid).await? else {
+            return Ok((infer_object_type(&data), data));
+        };
+
+        if !self.content_matches(obj_type, &data) {
+            anyhow::bail!(
+                "Object {} is recorded as a {} but its content is not one",
+                oid,
+                obj_type
+            );
+        }
+        Ok((obj_type, data))
+    }
+
+    /// Whether `data` can be an object of type `obj_type`
+    ///
+    /// Commits and trees must decode as exactly one; trees over the entry
+    /// limit are rejected when they are parsed, so they are not checked here.
+    /// Any bytes can be a blob.
+    pub(crate) fn content_matches(&self, obj_type: ObjectType, data: &[u8]) -> bool {
+        match obj_type {
+            ObjectType::Commit => crate::Commit::deserialize(data).is_ok(),
+            ObjectType::Tree => {
+                !self.tree_limits.admits(data)
+                    || crate::Tree::deserialize_with_limits(data, &self.tree_limits).is_ok()
+            }
+            ObjectType::Blob => true,
+        }
+    }
+
+    /// Get the type stored with an object
+    ///
+    /// Loose objects and deltas carry their type in a header in front of
+    /// their stored bytes, of which only the header is read; packs keep it
+    /// in each entry, and a delta in a pack has its base's type. Chunked
+    /// objects are always blobs. Returns None for objects written before
+    /// types were recorded.
+    pub async fn recorded_type(&self, oid: &Oid) -> anyhow::Result<Option<ObjectType>> {
+        self.recorded_type_at_depth(oid, 0).await
+    }
+
+    /// [`recorded_type`](Self::recorded_type), `depth` deltas into a pack's
+    /// delta chain
+    async fn recorded_type_at_depth(
+        &self,
+        oid: &Oid,
+        depth: u8,
+    ) -> anyhow::Result<Option<ObjectType>> {
+        let header_len = TYPE_HEADER_LEN as u64;
+        if let Ok(header) = self.storage.get_range(&oid.to_hex(), 0, header_len).await {
+            return Ok(split_type_header(&header).0);
+        }
+        if self.is_chunked(oid).await? {
+            return Ok(Some(ObjectType::Blob));
+        }
+        let delta_key = format!("deltas/{}", oid.to_hex());
+        if let Ok(header) = self.storage.get_range(&delta_key, 0, header_len).await {
+            return Ok(split_type_header(&header).0);
+        }
+
+        for pack_key in self.list_pack_files().await? {
+            let Ok(reader) = crate::pack::PackReader::new(self.storage.get(&pack_key).await?)
+            else {
+                continue;
+            };
+            match reader.get_delta(oid) {
+                Ok(Some((base_oid, _))) => {
+                    if depth >= MAX_DELTA_DEPTH {
+                        anyhow::bail!("Delta chain too deep (> {}) at {}", MAX_DELTA_DEPTH, oid);
+                    }
+                    return Box::pin(self.recorded_type_at_depth(&base_oid, depth + 1)).await;
+                }
+                Ok(None) => {
+                    if let Ok((obj_type, _)) = reader.get_object_with_type(oid) {
+                        return Ok(Some(obj_type));
+                    }
+                }
+                Err(_) => continue,
+            }
+        }
+        Ok(None)
+    }
+
+    /// Load the active compression dictionary, once per database
+    async fn load_active_dictionary(&self) -> anyhow::Result<()> {
+        let Some(smart_comp) = &self.smart_compressor else {
+            return Ok(());
+        };
+        self.active_dictionary_loaded
+            .get_or_try_init(|| async {
+                if !self.storage.exists(ACTIVE_DICTIONARY_KEY).await? {
+                    return Ok(());
+                }
+                let active = self.storage.get(ACTIVE_DICTIONARY_KEY).await?;
+                let id = std::str::from_utf8(&active)
+                    .ok()
+                    .and_then(|id| u32::from_str_radix(id.trim(), 16).ok())
+                    .ok_or_else(|| anyhow::anyhow!("Invalid active compression dictionary"))?;
+                let dictionary = self.fetch_dictionary(id).await?;
+                smart_comp.add_dictionary(dictionary, true);
+                debug!(dictionary = id, "Loaded active compression dictionary");
+                Ok::<(), anyhow::Error>(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Load the dictionary `data` was compressed with, if it is not loaded yet
+    async fn load_dictionary_for(&self, data: &[u8]) -> anyhow::Result<()> {
+        let (Some(smart_comp), Some(id)) =
+            (&self.smart_compressor, CompressionDictionary::id_of(data))
+        else {
+            return Ok(());
+        };
+        if !smart_comp.has_dictionary(id) {
+            smart_comp.add_dictionary(self.fetch_dictionary(id).await?, false);
+        }
+        Ok(())
+    }
+
+    /// Read dictionary `id` from storage
+    async fn fetch_dictionary(&self, id: u32) -> anyhow::Result<CompressionDictionary> {
+        let key = format!("{}{:08x}", DICTIONARY_PREFIX, id);
+        let data =
+            self.storage.get(&key).await.map_err(|e| {
+                anyhow::anyhow!("Failed to read compression dictionary {}: {}", id, e)
+            })?;
+        let dictionary = CompressionDictionary::from_bytes(data)
+            .map_err(|e| anyhow::anyhow!("Invalid compression dictionary {}: {}", id, e))?;
+        if dictionary.id() != id {
+            anyhow::bail!(
+                "Compression dictionary {} is stored under id {}",
+                dictionary.id(),
+                id
+            );
+        }
+        Ok(dictionary)
+    }
+
+    /// Train a compression dictionary from small text blobs and make it active
+    ///
+    /// Samples blobs among `candidates` that are between the minimum
+    /// compression size and [`MAX_DICTIONARY_OBJECT_SIZE`] and valid UTF-8,
+    /// the kind of object the dictionary is used for. The dictionary is stored
+    /// under `dictionaries/` and compresses new small text objects from then
+    /// on. Previous dictionaries are kept, so objects compressed with them
+    /// remain readable.
+    ///
+    /// Returns None, changing nothing, if fewer than
+    /// [`MIN_DICTIONARY_SAMPLES`] suitable blobs are found.
+    ///
+    /// # Errors
+    ///
+    /// Fails without smart compression, or if training or storage fails.
+    pub async fn train_dictionary(
+        &self,
+        candidates: &[Oid],
+    ) -> anyhow::Result<Option<CompressionDictionary>> {
+        let Some(smart_comp) = &self.smart_compressor else {
+            anyhow::bail!("Compression dictionaries require smart compression");
+        };
+
+        let mut samples = Vec::new();
+        for oid in candidates {
+            if samples.len() >= MAX_DICTIONARY_SAMPLES {
+                break;
+            }
+            if self.is_chunked(oid).await? {
+                continue;
+            }
+            let Ok((ObjectType::Blob, data)) = self.read_typed(oid).await else {
+                continue;
+            };
+            if (smart_comp.min_compress_size()..=MAX_DICTIONARY_OBJECT_SIZE).contains(&data.len())
+                && std::str::from_utf8(&data).is_ok()
+            {
+                samples.push(data);
+            }
+        }
+
+        if samples.len() < MIN_DICTIONARY_SAMPLES {
+            info!(
+                samples = samples.len(),
+                "Too few small text objects to train a compression dictionary"
+            );
+            return Ok(None);
+        }
+
+        // Roughly a tenth of the sampled bytes, as zstd recommends
+        let sample_bytes: usize = samples.iter().map(Vec::len).sum();
+        let max_size = (sample_bytes / 10).clamp(1024, MAX_DICTIONARY_SIZE);
+        let dictionary = CompressionDictionary::train(&samples, max_size)
+            .map_err(|e| anyhow::anyhow!("Failed to train compression dictionary: {}", e))?;
+
+        let key = format!("{}{:08x}", DICTIONARY_PREFIX, dictionary.id());
+        self.storage.put(&key, dictionary.as_bytes()).await?;
+        self.storage
+            .put(
+                ACTIVE_DICTIONARY_KEY,
+                format!("{:08x}", dictionary.id()).as_bytes(),
+            )
+            .await?;
+        smart_comp.add_dictionary(dictionary.clone(), true);
+
+        info!(
+            dictionary = dictionary.id(),
+            samples = samples.len(),
+            size = dictionary.as_bytes().len(),
+            "Trained compression dictionary"
+        );
+        Ok(Some(dictionary))
+    }
+
+    /// Get the chunk manifest for a chunked object
+    ///
+    /// Returns None if the object is not chunked.
+    pub async fn get_chunk_manifest(
+        &self,
+        oid: &Oid,
+    ) -> anyhow::Result<Option<crate::chunking::ChunkManifest>> {
+        let manifest_key = format!("manifests/{}", oid.to_hex());
+
+        if !self.storage.exists(&manifest_key).await? {
+            return Ok(None);
+        }
+
+        let manifest_data = self.storage.get(&manifest_key).await?;
+        let manifest: crate::chunking::ChunkManifest =
+            crate::format::deserialize(&manifest_data)
+                .map_err(|e| anyhow::anyhow!("Failed to deserialize chunk manifest: {}", e))?;
+
+        Ok(Some(manifest))
+    }
+
+    /// Seed the similarity detector with chunks from a previous manifest.
     ///
     /// Call this before `write_chunked()` or `write_chunked_from_file()` when
     /// adding a new version of an existing file. Pre-loading old chunk metadata
@@ -3106,6 +3859,29 @@ impl ObjectDatabase {
         self.metrics.read().await.clone()
     }
 
+    /// Count a write in the metrics under its object category
+    async fn record_write(&self, category: ObjectCategory, size: u64, is_new: bool) {
+        self.metrics
+            .write()
+            .await
+            .record_typed_write(category, size, is_new);
+    }
+
+    /// Whether content of `size` bytes with this OID is already stored
+    ///
+    /// Content large enough to be chunked counts as stored when it exists
+    /// either whole or as a chunk manifest, so writing it whole after it
+    /// was written chunked does not store it twice.
+    async fn is_stored(&self, oid: &Oid, size: usize) -> anyhow::Result<bool> {
+        if self.storage.exists(&oid.to_hex()).await? {
+            return Ok(true);
+        }
+        if size < MIN_CHUNK_SIZE {
+            return Ok(false);
+        }
+        self.is_chunked(oid).await
+    }
+
     /// Invalidate cache entry
     ///
     /// Removes an object from the cache. Useful for testing or
@@ -3137,6 +3913,12 @@ impl ObjectDatabase {
     /// - Batch delta compression for similar objects
     /// - Eliminating per-file overhead
     /// - Optimizing delta chains
+    /// - Dropping whole-file copies of objects that are also stored chunked
+    ///
+    /// A loose object whose chunk manifest and chunks are all present is a
+    /// duplicate left by a change of chunking strategy: reads already go
+    /// through the manifest, so it is not packed, and is deleted when
+    /// `remove_loose` is set.
     ///
     /// # Arguments
     ///
@@ -3170,16 +3952,58 @@ impl ObjectDatabase {
         max_objects: usize,
         remove_loose: bool,
     ) -> anyhow::Result<RepackStats> {
+        self.repack_with(&RepackOptions {
+            max_objects,
+            remove_loose,
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Repack loose objects within the resource limits of `options`
+    ///
+    /// Up to `jobs` objects are read and compressed at once. Each object is
+    /// delta-compressed against the most recently packed objects of the same
+    /// type (the window), which holds at most `window` objects and
+    /// `window_memory` bytes; an object too large for the window is packed
+    /// whole. A small budget therefore costs compression, never memory.
+    pub async fn repack_with(&self, options: &RepackOptions) -> anyhow::Result<RepackStats> {
         use crate::pack::PackWriter;
 
-        info!(max_objects, remove_loose, "Starting repack operation");
+        let max_objects = options.max_objects;
+        let remove_loose = options.remove_loose;
+        let jobs = options.jobs();
+        info!(
+            max_objects,
+            remove_loose,
+            jobs,
+            window = options.window,
+            window_memory = options.window_memory,
+            "Starting repack operation"
+        );
 
         let mut stats = RepackStats::default();
 
         // List all loose objects
-        let loose_objects = self.list_loose_objects().await?;
+        let mut loose_objects = self.list_loose_objects().await?;
         stats.loose_objects_found = loose_objects.len();
 
+        // Whole copies of chunked objects are dropped rather than packed
+        let duplicates = self.chunked_duplicates(&loose_objects).await?;
+        if !duplicates.is_empty() {
+            loose_objects.retain(|oid| !duplicates.contains(oid));
+            info!(
+                count = duplicates.len(),
+                "Found whole-file copies of chunked objects"
+            );
+            if remove_loose {
+                let keys: Vec<String> = duplicates.iter().map(|oid| oid.to_hex()).collect();
+                stats.duplicates_removed = self
+                    .remove_objects(&keys, "Failed to remove duplicate object")
+                    .await;
+            }
+        }
+
         if loose_objects.is_empty() {
             info!("No loose objects to repack");
             return Ok(stats);
@@ -3200,75 +4024,68 @@ impl ObjectDatabase {
         // Create pack writer
         let mut pack_writer = PackWriter::new();
         let mut packed_oids = Vec::new();
+        stats.jobs = jobs;
 
         // Track sizes for statistics
         let mut total_original_size = 0u64;
 
-        // Add objects to pack with delta compression
-        for oid in objects_to_pack {
-            match self.read(oid).await {
-                Ok(data) => {
-                    total_original_size += data.len() as u64;
-
-                    // Try to find similar object for delta encoding
-                    if self.delta_enabled {
-                        let mut metadata = crate::similarity::ObjectMetadata::new(
-                            *oid,
-                            data.len(),
-                            ObjectType::Blob, // Assume blob for now
-                            None,
-                        );
-                        metadata.generate_samples(&data);
-
-                        let detector = self.similarity_detector.read().await;
-                        if let Some((base_oid, score)) = detector
-                            .find_similar(&metadata, crate::similarity::MIN_SIMILARITY_THRESHOLD)
-                        {
-                            drop(detector);
-
-                            // Try to read base and create delta
-                            if let Ok(base_data) = self.read(&base_oid).await {
-                                let delta = DeltaEncoder::encode(&base_data, &data);
-                                let delta_data = delta.to_bytes();
-
-                                // Use delta if beneficial
-                                let delta_ratio = delta_data.len() as f64 / data.len() as f64;
-                                if delta_ratio < 0.80 {
-                                    debug!(
-                                        oid = %oid,
-                                        base = %base_oid,
-                                        similarity = score.score,
-                                        delta_size = delta_data.len(),
-                                        original_size = data.len(),
-                                        "Using delta encoding in pack"
-                                    );
-
-                                    pack_writer.add_delta_object(*oid, base_oid, &delta_data);
-                                    stats.delta_objects += 1;
-                                    packed_oids.push(*oid);
-                                    continue;
-                                }
-                            }
-                        }
-                    }
-
-                    // Add as regular object (no delta or delta not beneficial)
-                    // Compress if enabled
-                    let object_data = if self.compression_enabled {
-                        if let Some(smart_comp) = &self.smart_compressor {
-                            smart_comp.compress_typed(&data, CompressionObjectType::Unknown)?
-                        } else {
-                            self.compressor.compress(&data)?
-                        }
-                    } else {
-                        data.clone()
-                    };
+        // Delta base candidates: recently packed whole objects
+        let mut window: std::collections::VecDeque<(Oid, ObjectType, Arc<Vec<u8>>)> =
+            std::collections::VecDeque::new();
+        let mut window_bytes = 0u64;
+
+        // Objects are read and compressed by up to `jobs` tasks, and added to
+        // the pack in listing order
+        let mut pending = objects_to_pack.iter().copied();
+        let mut in_flight = std::collections::VecDeque::new();
+        loop {
+            while in_flight.len() < jobs {
+                let Some(oid) = pending.next() else { break };
+                let odb = self.clone();
+                in_flight.push_back((
+                    oid,
+                    tokio::spawn(async move { odb.load_for_pack(&oid).await }),
+                ));
+            }
+            stats.peak_jobs = stats.peak_jobs.max(in_flight.len());
+            let Some((oid, handle)) = in_flight.pop_front() else {
+                break;
+            };
 
-                    pack_writer.add_object(*oid, ObjectType::Blob, &object_data);
-                    packed_oids.push(*oid);
-                }
+            let (data, obj_type, object_data) = match handle.await? {
+                Ok(loaded) => loaded,
                 Err(e) => {
                     warn!(oid = %oid, error = %e, "Failed to read object for packing");
+                    continue;
+                }
+            };
+            total_original_size += data.len() as u64;
+
+            // Try to find similar object for delta encoding
+            if let Some((base_oid, delta_data)) = self
+                .find_pack_delta(&oid, obj_type, &data, &window, options)
+                .await
+            {
+                pack_writer.add_delta_object(oid, base_oid, &delta_data);
+                stats.delta_objects += 1;
+                packed_oids.push(oid);
+                continue;
+            }
+
+            // Add as regular object (no delta or delta not beneficial)
+            pack_writer.add_object(oid, obj_type, &object_data);
+            packed_oids.push(oid);
+
+            // Whole objects become delta bases for those that follow
+            let size = data.len() as u64;
+            if options.window > 0 && options.fits_window(size) {
+                window.push_back((oid, obj_type, Arc::new(data)));
+                window_bytes += size;
+                while window.len() > options.window || !options.fits_window(window_bytes) {
+                    match window.pop_front() {
+                        Some((_, _, evicted)) => window_bytes -= evicted.len() as u64,
+                        None => break,
+                    }
                 }
             }
         }
@@ -3302,16 +4119,11 @@ impl ObjectDatabase {
 
         // Remove loose objects if requested
         if remove_loose {
-            let mut removed = 0;
-            for oid in &packed_oids {
-                // Use oid.to_hex() for consistency - LocalBackend handles path sharding
-                let object_key = oid.to_hex();
-                if let Err(e) = self.storage.delete(&object_key).await {
-                    warn!(oid = %oid, error = %e, "Failed to remove loose object");
-                } else {
-                    removed += 1;
-                }
-            }
+            // Use oid.to_hex() for consistency - LocalBackend handles path sharding
+            let keys: Vec<String> = packed_oids.iter().map(|oid| oid.to_hex()).collect();
+            let removed = self
+                .remove_objects(&keys, "Failed to remove loose object")
+                .await;
             stats.loose_objects_removed = removed;
             info!(removed, "Removed loose objects");
         }
@@ -3326,6 +4138,117 @@ impl ObjectDatabase {
         Ok(stats)
     }
 
+    /// Decompress an object stored whole in a pack
+    async fn decompress_packed(&self, compressed_data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        self.load_dictionary_for(&compressed_data).await?;
+        let data = if let Some(smart_comp) = &self.smart_compressor {
+            match smart_comp.decompress_typed(&compressed_data) {
+                Ok(d) => d,
+                Err(e) if e.is_output_too_large() => return Err(e.into()),
+                Err(_) => {
+                    // Fallback to standard decompression
+                    match self.compressor.decompress(&compressed_data) {
+                        Ok(d) => d,
+                        Err(_) => compressed_data, // Use raw data as last resort
+                    }
+                }
+            }
+        } else if self.compression_enabled
+            || (compressed_data.len() >= 2 && compressed_data[0] == 0x78)
+        {
+            match self.compressor.decompress(&compressed_data) {
+                Ok(d) => d,
+                Err(_) => compressed_data,
+            }
+        } else {
+            compressed_data
+        };
+        Ok(data)
+    }
+
+    /// Read an object for packing: its content, type and stored form
+    async fn load_for_pack(&self, oid: &Oid) -> anyhow::Result<(Vec<u8>, ObjectType, Vec<u8>)> {
+        let (obj_type, data) = self.read_typed(oid).await?;
+        let object_data = if self.compression_enabled {
+            if let Some(smart_comp) = &self.smart_compressor {
+                smart_comp.compress_typed(&data, CompressionObjectType::Unknown)?
+            } else {
+                self.compressor.compress(&data)?
+            }
+        } else {
+            data.clone()
+        };
+        Ok((data, obj_type, object_data))
+    }
+
+    /// Find the best delta for an object being packed
+    ///
+    /// Candidates are the similarity detector's suggestion and the window
+    /// entries of the same type and comparable size. A pair whose combined
+    /// size exceeds the window memory is not tried. Returns the base and the
+    /// delta if it is under 80% of the object's size.
+    async fn find_pack_delta(
+        &self,
+        oid: &Oid,
+        obj_type: ObjectType,
+        data: &[u8],
+        window: &std::collections::VecDeque<(Oid, ObjectType, Arc<Vec<u8>>)>,
+        options: &RepackOptions,
+    ) -> Option<(Oid, Vec<u8>)> {
+        let mut best: Option<(Oid, Vec<u8>)> = None;
+        let mut consider = |base_oid: Oid, base: &[u8]| {
+            if !options.fits_window(base.len() as u64 + data.len() as u64) {
+                return;
+            }
+            let delta_data = DeltaEncoder::encode(base, data).to_bytes();
+            let delta_ratio = delta_data.len() as f64 / data.len().max(1) as f64;
+            let smaller = best
+                .as_ref()
+                .is_none_or(|(_, current)| delta_data.len() < current.len());
+            if delta_ratio < 0.80 && smaller {
+                best = Some((base_oid, delta_data));
+            }
+        };
+
+        if self.delta_enabled {
+            let mut metadata =
+                crate::similarity::ObjectMetadata::new(*oid, data.len(), obj_type, None);
+            metadata.generate_samples(data);
+            let similar = self
+                .similarity_detector
+                .read()
+                .await
+                .find_similar(&metadata, crate::similarity::MIN_SIMILARITY_THRESHOLD);
+            // A delta in a pack has its base's type
+            if let Some((base_oid, score)) = similar {
+                if let Ok((base_type, base_data)) = self.read_typed(&base_oid).await {
+                    if base_type == obj_type {
+                        debug!(oid = %oid, base = %base_oid, similarity = score.score, "Trying similar base");
+                        consider(base_oid, &base_data);
+                    }
+                }
+            }
+        }
+
+        for (base_oid, base_type, base) in window.iter().rev() {
+            let comparable = base.len() / 2 <= data.len() && data.len() / 2 <= base.len();
+            if *base_type == obj_type && comparable {
+                consider(*base_oid, base);
+            }
+        }
+
+        if let Some((base_oid, delta_data)) = &best {
+            debug!(
+                oid = %oid,
+                base = %base_oid,
+                delta_size = delta_data.len(),
+                original_size = data.len(),
+                "Using delta encoding in pack"
+            );
+        }
+        best
+    }
+
     /// Resolve an abbreviated OID prefix to a full OID.
     ///
     /// Scans loose objects for keys matching the given hex prefix.
@@ -3381,6 +4304,65 @@ impl ObjectDatabase {
         }
     }
 
+    /// Number of loose (unpacked) objects in the object database
+    ///
+    /// Used to decide when a repository is worth repacking.
+    pub async fn count_loose_objects(&self) -> anyhow::Result<usize> {
+        Ok(self.list_loose_objects().await?.len())
+    }
+
+    /// Delete `keys` in one batch, logging each failure with `message`, and
+    /// return how many were deleted
+    async fn remove_objects(&self, keys: &[String], message: &str) -> usize {
+        match self.storage.delete_many(keys).await {
+            Ok(failures) => {
+                for failure in &failures {
+                    warn!(key = %failure.key, error = %failure.error, "{}", message);
+                }
+                keys.len() - failures.len()
+            }
+            Err(e) => {
+                warn!(error = %e, "{}", message);
+                0
+            }
+        }
+    }
+
+    /// Loose objects among `oids` that are also fully stored as chunks
+    async fn chunked_duplicates(
+        &self,
+        oids: &[Oid],
+    ) -> anyhow::Result<std::collections::HashSet<Oid>> {
+        let manifests: std::collections::HashSet<String> = self
+            .storage
+            .list_objects("manifests/")
+            .await?
+            .into_iter()
+            .filter_map(|key| key.strip_prefix("manifests/").map(str::to_string))
+            .collect();
+
+        let mut duplicates = std::collections::HashSet::new();
+        for oid in oids {
+            if !manifests.contains(&oid.to_hex()) {
+                continue;
+            }
+            let Some(manifest) = self.get_chunk_manifest(oid).await? else {
+                continue;
+            };
+            let mut complete = true;
+            for chunk in &manifest.chunks {
+                if !self.chunk_exists(&chunk.id).await? {
+                    complete = false;
+                    break;
+                }
+            }
+            if complete {
+                duplicates.insert(*oid);
+            }
+        }
+        Ok(duplicates)
+    }
+
     /// List all loose objects in the object database
     ///
     /// Scans the objects/ directory and returns OIDs of all loose objects.
@@ -3390,9 +4372,10 @@ impl ObjectDatabase {
         // List all keys (empty prefix = all loose objects)
         // LocalBackend stores objects with plain hex keys (e.g., "abc123...")
         // not "objects/abc123..." - the "objects/" part is handled internally
-        let keys = self.storage.list_objects("").await?;
+        // Keys are streamed so only the parsed OIDs are held in memory
+        let mut keys = self.storage.list_objects_stream("");
 
-        for key in keys {
+        while let Some(key) = keys.try_next().await? {
             // Skip non-object keys (like "packs/...")
             if key.starts_with("packs/") {
                 continue;
@@ -3413,6 +4396,15 @@ impl ObjectDatabase {
     }
 }
 
+/// An object delivered by [`ObjectDatabase::prefetch`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Prefetched {
+    /// The object's content
+    Data(Vec<u8>),
+    /// A chunked object, left in storage to be streamed with `read_to_file`
+    Chunked,
+}
+
 /// Statistics from a repack operation
 #[derive(Debug, Default, Clone)]
 pub struct RepackStats {
@@ -3428,6 +4420,63 @@ pub struct RepackStats {
     pub bytes_saved: u64,
     /// Number of loose objects removed
     pub loose_objects_removed: usize,
+    /// Number of whole-file copies of chunked objects removed
+    pub duplicates_removed: usize,
+    /// Objects allowed to be read and compressed at once
+    pub jobs: usize,
+    /// Most objects that were actually in flight at once
+    pub peak_jobs: usize,
+}
+
+/// Default number of objects kept as delta bases while repacking
+pub const DEFAULT_REPACK_WINDOW: usize = 10;
+
+/// Default memory budget of the repack delta window (256 MiB)
+pub const DEFAULT_REPACK_WINDOW_MEMORY: u64 = 256 * 1024 * 1024;
+
+/// Resource limits for [`ObjectDatabase::repack_with`]
+///
+/// The counterparts of Git's `pack.threads`, `pack.window` and
+/// `pack.windowMemory`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepackOptions {
+    /// Maximum number of objects to include in each pack (0 = unlimited)
+    pub max_objects: usize,
+    /// Whether to remove loose objects after packing
+    pub remove_loose: bool,
+    /// Objects read and compressed concurrently (0 = one per CPU)
+    pub jobs: usize,
+    /// Recently packed objects kept as delta bases (0 = no window)
+    pub window: usize,
+    /// Bytes the delta window may hold (0 = unlimited)
+    pub window_memory: u64,
+}
+
+impl Default for RepackOptions {
+    fn default() -> Self {
+        Self {
+            max_objects: 0,
+            remove_loose: true,
+            jobs: 0,
+            window: DEFAULT_REPACK_WINDOW,
+            window_memory: DEFAULT_REPACK_WINDOW_MEMORY,
+        }
+    }
+}
+
+impl RepackOptions {
+    /// Concurrency to use, resolving 0 to the number of CPUs
+    pub fn jobs(&self) -> usize {
+        match self.jobs {
+            0 => num_cpus::get(),
+            jobs => jobs,
+        }
+    }
+
+    /// Whether `bytes` of delta candidates fit the window memory
+    fn fits_window(&self, bytes: u64) -> bool {
+        self.window_memory == 0 || bytes <= self.window_memory
+    }
 }
 
 #[cfg(test)]
@@ -3435,6 +4484,12 @@ mod tests {
     use super::*;
     use mediagit_storage::mock::MockBackend;
 
+    /// The bytes stored under `key`, after the type header
+    async fn stored_body(storage: &MockBackend, key: &str) -> Vec<u8> {
+        let stored = storage.get(key).await.unwrap();
+        split_type_header(&stored).1.to_vec()
+    }
+
     #[tokio::test]
     async fn test_write_and_read() {
         let storage = Arc::new(MockBackend::new());
@@ -3470,6 +4525,675 @@ mod tests {
         assert_eq!(metrics.dedup_ratio(), 0.5); // 50% deduplicated
     }
 
+    #[tokio::test]
+    async fn test_min_compress_size_stores_small_objects_raw() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 100)
+            .with_min_compress_size(128);
+
+        let small = b"oid sha256:4d7a2146 size 30".to_vec();
+        let large = "frame_rate = 24\n".repeat(32).into_bytes();
+        let small_oid = odb.write(ObjectType::Blob, &small).await.unwrap();
+        let large_oid = odb.write(ObjectType::Blob, &large).await.unwrap();
+
+        // Below the threshold: Store marker followed by the raw bytes
+        let stored = stored_body(&storage, &small_oid.to_hex()).await;
+        assert_eq!(stored[0], 0x00);
+        assert_eq!(&stored[1..], small.as_slice());
+
+        // Above the threshold: compressed
+        let stored = stored_body(&storage, &large_oid.to_hex()).await;
+        assert!(stored.len() < large.len());
+
+        // Both read back unchanged through a fresh database
+        let reader = ObjectDatabase::with_smart_compression(storage, 100);
+        assert_eq!(reader.read(&small_oid).await.unwrap(), small);
+        assert_eq!(reader.read(&large_oid).await.unwrap(), large);
+    }
+
+    #[tokio::test]
+    async fn test_archival_tier_writes_xz() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 100)
+            .with_compression_tier(CompressionTier::Archival);
+
+        let data = "frame_rate = 24\n".repeat(64).into_bytes();
+        let oid = odb.write(ObjectType::Blob, &data).await.unwrap();
+
+        let stored = stored_body(&storage, &oid.to_hex()).await;
+        assert_eq!(
+            CompressionAlgorithm::detect(&stored),
+            CompressionAlgorithm::Xz
+        );
+
+        let reader = ObjectDatabase::with_smart_compression(storage, 100);
+        assert_eq!(reader.read(&oid).await.unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_brotli_settings_apply_to_writes() {
+        let storage = Arc::new(MockBackend::new());
+        let settings = BrotliSettings {
+            window: 16,
+            quality: Some(2),
+        };
+        let odb =
+            ObjectDatabase::with_smart_compression(storage.clone(), 100).with_brotli(settings);
+
+        let data = "scene 12, take 4: keep\n".repeat(256).into_bytes();
+        let oid = odb
+            .write_with_path(ObjectType::Blob, &data, "notes.txt")
+            .await
+            .unwrap();
+
+        let stored = stored_body(&storage, &oid.to_hex()).await;
+        let expected = SmartCompressor::new()
+            .with_brotli(settings)
+            .compress_typed(&data, CompressionObjectType::Text)
+            .unwrap();
+        assert_eq!(stored, expected);
+
+        let reader = ObjectDatabase::with_smart_compression(storage, 100);
+        assert_eq!(reader.read(&oid).await.unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_max_decompressed_size_rejects_large_objects() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 100);
+        let data = vec![0u8; 256 * 1024];
+        let oid = odb.write(ObjectType::Blob, &data).await.unwrap();
+
+        let limited = ObjectDatabase::with_smart_compression(storage.clone(), 100)
+            .with_max_decompressed_size(64 * 1024);
+        let err = limited.read(&oid).await.unwrap_err();
+        assert!(err.to_string().contains("byte limit"), "{}", err);
+
+        let reader = ObjectDatabase::with_smart_compression(storage, 100)
+            .with_max_decompressed_size(data.len() as u64);
+        assert_eq!(reader.read(&oid).await.unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_delta_objects_are_framed_with_their_base() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 100);
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let v1: Vec<u8> = (0..64 * 1024)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                b'a' + (state % 26) as u8
+            })
+            .collect();
+        let mut v2 = v1.clone();
+        v2[1000..1010].copy_from_slice(b"0123456789");
+        let mut v3 = v2.clone();
+        v3[40_000..40_010].copy_from_slice(b"9876543210");
+
+        let base = odb
+            .write_with_delta(ObjectType::Blob, &v1, "scene.txt")
+            .await
+            .unwrap();
+        let oid2 = odb
+            .write_with_delta(ObjectType::Blob, &v2, "scene.txt")
+            .await
+            .unwrap();
+        let oid3 = odb
+            .write_with_delta(ObjectType::Blob, &v3, "scene.txt")
+            .await
+            .unwrap();
+
+        let stored = stored_body(&storage, &format!("deltas/{}", oid2.to_hex())).await;
+        assert_eq!(
+            mediagit_compression::delta::base_id_of(&stored),
+            Some(&base.as_bytes()[..])
+        );
+        assert!(
+            stored.len() < v2.len() / 10,
+            "delta is {} bytes",
+            stored.len()
+        );
+        let stored = stored_body(&storage, &format!("deltas/{}", oid3.to_hex())).await;
+        assert!(mediagit_compression::delta::base_id_of(&stored).is_some());
+
+        // A fresh database has nothing cached, so it applies the chain
+        let reader = ObjectDatabase::with_smart_compression(storage, 100);
+        assert_eq!(reader.read(&oid3).await.unwrap(), v3);
+        assert_eq!(reader.read(&oid2).await.unwrap(), v2);
+    }
+
+    #[tokio::test]
+    async fn test_unframed_delta_objects_still_read() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 100);
+        let v1 = b"shot 010 frame range 1001-1100\n".repeat(200);
+        let v2 = b"shot 010 frame range 1001-1148\n".repeat(200);
+        let base = odb.write(ObjectType::Blob, &v1).await.unwrap();
+
+        // Deltas were once stored as compressed, unframed delta bytes
+        let oid = Oid::hash(&v2);
+        let delta = DeltaEncoder::encode(&v1, &v2).to_bytes();
+        let stored = SmartCompressor::new()
+            .compress_typed(&delta, CompressionObjectType::Unknown)
+            .unwrap();
+        storage
+            .put(&format!("deltas/{}", oid.to_hex()), &stored)
+            .await
+            .unwrap();
+        storage
+            .put(
+                &format!("deltas/{}.meta", oid.to_hex()),
+                format!("base:{}:depth:1", base.to_hex()).as_bytes(),
+            )
+            .await
+            .unwrap();
+
+        let reader = ObjectDatabase::with_smart_compression(storage, 100);
+        assert_eq!(reader.read(&oid).await.unwrap(), v2);
+    }
+
+    #[tokio::test]
+    async fn test_store_override_keeps_objects_raw() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::with_optimizations(
+            storage.clone(),
+            100,
+            Some(ChunkStrategy::Fixed { size: 256 * 1024 }),
+            false,
+        )
+        .with_compression_attributes(CompressionAttributes::parse(
+            "incompressible_cache/** compression=store\n",
+        ));
+
+        // EXR would normally get Zstd at its best level
+        let frame = "pixel 0.5 0.5 0.5 1.0\n".repeat(64).into_bytes();
+        let cached = odb
+            .write_with_path(
+                ObjectType::Blob,
+                &frame,
+                "incompressible_cache/shot_010.exr",
+            )
+            .await
+            .unwrap();
+        let stored = stored_body(&storage, &cached.to_hex()).await;
+        assert_eq!(stored[0], 0x00);
+        assert_eq!(&stored[1..], frame.as_slice());
+
+        let render = "pixel 0.2 0.2 0.2 1.0\n".repeat(64).into_bytes();
+        let rendered = odb
+            .write_with_path(ObjectType::Blob, &render, "renders/shot_010.exr")
+            .await
+            .unwrap();
+        assert!(stored_body(&storage, &rendered.to_hex()).await.len() < render.len());
+
+        // Chunks of a large object under the override are stored raw too
+        let sequence = "pixel 0.7 0.7 0.7 1.0\n".repeat(64 * 1024).into_bytes();
+        let oid = odb
+            .write_chunked(ObjectType::Blob, &sequence, "incompressible_cache/seq.exr")
+            .await
+            .unwrap();
+        let manifest = odb.get_chunk_manifest(&oid).await.unwrap().unwrap();
+        assert!(manifest.chunks.len() > 1);
+        for chunk in &manifest.chunks {
+            let stored = storage
+                .get(&format!("chunks/{}", chunk.id.to_hex()))
+                .await
+                .unwrap();
+            assert_eq!(stored.len(), chunk.size + 1);
+            assert_eq!(stored[0], 0x00);
+        }
+        assert_eq!(odb.read(&oid).await.unwrap(), sequence);
+    }
+
+    #[tokio::test]
+    async fn test_trained_dictionary_shrinks_small_objects() {
+        fn sidecar(i: usize) -> Vec<u8> {
+            format!(
+                r#"{{"asset": "shot_{:04}.exr", "colorspace": "ACEScg", "frame_range": [{}, {}], "camera": {{"lens": "35mm", "iso": 800}}, "status": "approved"}}"#,
+                i,
+                1001 + i,
+                1100 + i
+            )
+            .into_bytes()
+        }
+        async fn stored_size(storage: &MockBackend, oids: &[Oid]) -> usize {
+            let mut total = 0;
+            for oid in oids {
+                total += storage.get(&oid.to_hex()).await.unwrap().len();
+            }
+            total
+        }
+
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 100);
+        let mut before = Vec::new();
+        for i in 0..200 {
+            let name = format!("shot_{}.json", i);
+            before.push(
+                odb.write_with_path(ObjectType::Blob, &sidecar(i), &name)
+                    .await
+                    .unwrap(),
+            );
+        }
+        let first = odb.train_dictionary(&before).await.unwrap().unwrap();
+
+        // New sidecars come out smaller than generic compression manages
+        let plain_storage = Arc::new(MockBackend::new());
+        let plain = ObjectDatabase::with_smart_compression(plain_storage.clone(), 100)
+            .with_dictionary_compression(false);
+        let mut after = Vec::new();
+        for i in 200..300 {
+            let name = format!("shot_{}.json", i);
+            after.push(
+                odb.write_with_path(ObjectType::Blob, &sidecar(i), &name)
+                    .await
+                    .unwrap(),
+            );
+            plain
+                .write_with_path(ObjectType::Blob, &sidecar(i), &name)
+                .await
+                .unwrap();
+        }
+        let with_dictionary = stored_size(&storage, &after).await;
+        let without_dictionary = stored_size(&plain_storage, &after).await;
+        assert!(
+            with_dictionary * 2 < without_dictionary,
+            "{} bytes with dictionary, {} without",
+            with_dictionary,
+            without_dictionary
+        );
+        let stored = stored_body(&storage, &after[0].to_hex()).await;
+        assert_eq!(CompressionDictionary::id_of(&stored), Some(first.id()));
+
+        // Retraining switches new objects to a new dictionary
+        let second = odb.train_dictionary(&after).await.unwrap().unwrap();
+        assert_ne!(second.id(), first.id());
+        let newest = odb
+            .write_with_path(ObjectType::Blob, &sidecar(500), "shot_500.json")
+            .await
+            .unwrap();
+        let stored = stored_body(&storage, &newest.to_hex()).await;
+        assert_eq!(CompressionDictionary::id_of(&stored), Some(second.id()));
+
+        // A fresh database reads objects from before, under and after the first dictionary
+        let reader = ObjectDatabase::with_smart_compression(storage, 100);
+        for (i, oid) in before.iter().chain(&after).enumerate() {
+            assert_eq!(reader.read(oid).await.unwrap(), sidecar(i));
+        }
+        assert_eq!(reader.read(&newest).await.unwrap(), sidecar(500));
+    }
+
+    #[tokio::test]
+    async fn test_object_type_is_recorded() {
+        use crate::{Commit, FileMode, Signature, Tree, TreeEntry};
+
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 100);
+
+        let blob = odb
+            .write(ObjectType::Blob, b"shot 010 notes")
+            .await
+            .unwrap();
+        let mut tree = Tree::new();
+        tree.add_entry(TreeEntry::new(
+            "notes.txt".to_string(),
+            FileMode::Regular,
+            blob,
+        ));
+        let tree_oid = tree.write(&odb).await.unwrap();
+        let commit = Commit::new(
+            tree_oid,
+            Signature::now("Test".to_string(), "test@example.com".to_string()),
+            Signature::now("Test".to_string(), "test@example.com".to_string()),
+            "Add notes".to_string(),
+        );
+        let commit_oid = commit.write(&odb).await.unwrap();
+
+        // Blobs whose bytes start with a tree encoding, or are empty
+        let mut tree_like = tree.serialize().unwrap();
+        tree_like.extend_from_slice(b"trailing media bytes");
+        let tree_like = odb.write(ObjectType::Blob, &tree_like).await.unwrap();
+        let empty = odb.write(ObjectType::Blob, b"").await.unwrap();
+
+        let reader = ObjectDatabase::with_smart_compression(storage.clone(), 100);
+        for (oid, expected) in [
+            (blob, ObjectType::Blob),
+            (tree_oid, ObjectType::Tree),
+            (commit_oid, ObjectType::Commit),
+            (tree_like, ObjectType::Blob),
+            (empty, ObjectType::Blob),
+        ] {
+            assert_eq!(reader.recorded_type(&oid).await.unwrap(), Some(expected));
+            assert_eq!(reader.object_type(&oid).await.unwrap(), expected);
+            assert_eq!(reader.read_typed(&oid).await.unwrap().0, expected);
+            let stored = storage.get(&oid.to_hex()).await.unwrap();
+            assert_eq!(split_type_header(&stored).0, Some(expected));
+        }
+
+        // A type header changed to another type is caught when the object is read
+        let mut stored = storage.get(&blob.to_hex()).await.unwrap();
+        stored[TYPE_HEADER_LEN - 1] = ObjectType::Tree.to_u8();
+        storage.put(&blob.to_hex(), &stored).await.unwrap();
+        let reader = ObjectDatabase::with_smart_compression(storage.clone(), 100);
+        let err = reader.read_typed(&blob).await.unwrap_err();
+        assert!(err.to_string().contains("recorded as a tree"), "{}", err);
+
+        // Objects from before types were recorded fall back to parsing
+        let stored = storage.get(&tree_oid.to_hex()).await.unwrap();
+        let (_, legacy) = split_type_header(&stored);
+        storage.put(&tree_oid.to_hex(), legacy).await.unwrap();
+        let reader = ObjectDatabase::with_smart_compression(storage.clone(), 100);
+        assert_eq!(
+            reader.read(&tree_oid).await.unwrap(),
+            tree.serialize().unwrap()
+        );
+        assert_eq!(reader.recorded_type(&tree_oid).await.unwrap(), None);
+        assert_eq!(
+            reader.object_type(&tree_oid).await.unwrap(),
+            ObjectType::Tree
+        );
+    }
+
+    #[tokio::test]
+    async fn test_repack_keeps_object_types() {
+        use crate::{Signature, Tree};
+
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 100);
+
+        let tree_oid = Tree::new().write(&odb).await.unwrap();
+        let commit = crate::Commit::new(
+            tree_oid,
+            Signature::now("Test".to_string(), "test@example.com".to_string()),
+            Signature::now("Test".to_string(), "test@example.com".to_string()),
+            "Empty".to_string(),
+        );
+        let commit_oid = commit.write(&odb).await.unwrap();
+
+        odb.repack(0, true).await.unwrap();
+        assert_eq!(odb.count_loose_objects().await.unwrap(), 0);
+
+        let reader = ObjectDatabase::with_smart_compression(storage, 100);
+        assert_eq!(
+            reader.recorded_type(&tree_oid).await.unwrap(),
+            Some(ObjectType::Tree)
+        );
+        assert_eq!(
+            reader.object_type(&tree_oid).await.unwrap(),
+            ObjectType::Tree
+        );
+        assert_eq!(
+            reader.object_type(&commit_oid).await.unwrap(),
+            ObjectType::Commit
+        );
+    }
+
+    #[tokio::test]
+    async fn test_repack_honors_job_limit() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::new(storage.clone(), 100);
+        let mut oids = Vec::new();
+        for i in 0..20 {
+            let content = format!("object number {}", i);
+            oids.push(
+                odb.write(ObjectType::Blob, content.as_bytes())
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let stats = odb
+            .repack_with(&RepackOptions {
+                jobs: 2,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(stats.jobs, 2);
+        assert_eq!(stats.peak_jobs, 2);
+        assert_eq!(stats.objects_packed, 20);
+        assert_eq!(odb.count_loose_objects().await.unwrap(), 0);
+
+        let reader = ObjectDatabase::new(storage, 100);
+        for (i, oid) in oids.iter().enumerate() {
+            let expected = format!("object number {}", i);
+            assert_eq!(reader.read(oid).await.unwrap(), expected.as_bytes());
+        }
+    }
+
+    /// Versions of a 16KB file, each differing from the first in a few bytes
+    async fn write_versions(odb: &ObjectDatabase) -> Vec<(Oid, Vec<u8>)> {
+        let base: Vec<u8> = (0..16 * 1024u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 11) as u8)
+            .collect();
+        let mut versions = Vec::new();
+        for v in 0..5u8 {
+            let mut data = base.clone();
+            data[1000 * v as usize..][..16].fill(v);
+            let oid = odb.write(ObjectType::Blob, &data).await.unwrap();
+            versions.push((oid, data));
+        }
+        versions
+    }
+
+    #[tokio::test]
+    async fn test_repack_window_memory_limits_deltas() {
+        let mut pack_sizes = Vec::new();
+        for window_memory in [DEFAULT_REPACK_WINDOW_MEMORY, 1] {
+            let storage = Arc::new(MockBackend::new());
+            let versions = write_versions(&ObjectDatabase::new(storage.clone(), 100)).await;
+
+            // A fresh database has no similarity history, only the window
+            let odb = ObjectDatabase::new(storage.clone(), 100);
+            let stats = odb
+                .repack_with(&RepackOptions {
+                    window_memory,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            assert_eq!(stats.objects_packed, versions.len());
+            if window_memory == 1 {
+                assert_eq!(stats.delta_objects, 0);
+            } else {
+                assert_eq!(stats.delta_objects, versions.len() - 1);
+            }
+            pack_sizes.push(stats.pack_size);
+
+            let reader = ObjectDatabase::new(storage, 100);
+            for (oid, data) in &versions {
+                assert_eq!(&reader.read(oid).await.unwrap(), data);
+            }
+        }
+        assert!(pack_sizes[1] > pack_sizes[0] * 3);
+    }
+
+    /// 2MB of content that is too large to skip chunking
+    fn chunkable_content() -> Vec<u8> {
+        (0..2 * 1024 * 1024u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_whole_write_of_chunked_content_is_deduplicated() {
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::with_optimizations(
+            storage.clone(),
+            100,
+            Some(ChunkStrategy::Fixed { size: 256 * 1024 }),
+            false,
+        );
+        let data = chunkable_content();
+
+        let oid = odb
+            .write_chunked(ObjectType::Blob, &data, "scene.bin")
+            .await
+            .unwrap();
+        assert_eq!(odb.write(ObjectType::Blob, &data).await.unwrap(), oid);
+
+        assert!(odb.is_chunked(&oid).await.unwrap());
+        assert!(!storage.exists(&oid.to_hex()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_repack_collapses_whole_and_chunked_copies() {
+        let storage = Arc::new(MockBackend::new());
+        let data = chunkable_content();
+
+        // Stored whole, as by a repository that had chunking disabled
+        let whole = ObjectDatabase::with_smart_compression(storage.clone(), 100);
+        let oid = whole.write(ObjectType::Blob, &data).await.unwrap();
+        let whole_copy = storage.get(&oid.to_hex()).await.unwrap();
+
+        // ...and chunked after chunking was turned on
+        storage.delete(&oid.to_hex()).await.unwrap();
+        let chunked = ObjectDatabase::with_optimizations(
+            storage.clone(),
+            100,
+            Some(ChunkStrategy::Fixed { size: 256 * 1024 }),
+            false,
+        );
+        assert_eq!(
+            chunked
+                .write_chunked(ObjectType::Blob, &data, "scene.bin")
+                .await
+                .unwrap(),
+            oid
+        );
+        storage.put(&oid.to_hex(), &whole_copy).await.unwrap();
+        let other = chunked.write(ObjectType::Blob, b"other").await.unwrap();
+
+        let stats = chunked.repack(0, true).await.unwrap();
+        assert_eq!(stats.loose_objects_found, 2);
+        assert_eq!(stats.duplicates_removed, 1);
+        assert_eq!(stats.objects_packed, 1);
+
+        // A single copy remains, and it is the chunked one
+        assert!(!storage.exists(&oid.to_hex()).await.unwrap());
+        assert!(chunked.is_chunked(&oid).await.unwrap());
+        let reader = ObjectDatabase::with_smart_compression(storage, 100);
+        assert_eq!(reader.read(&oid).await.unwrap(), data);
+        assert_eq!(reader.read(&other).await.unwrap(), b"other");
+    }
+
+    #[tokio::test]
+    async fn test_large_local_objects_are_memory_mapped() {
+        use mediagit_storage::LocalBackend;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage: Arc<dyn StorageBackend> =
+            Arc::new(LocalBackend::new(temp_dir.path()).await.unwrap());
+        let odb = ObjectDatabase::with_smart_compression(storage.clone(), 100);
+
+        // Incompressible, so it is stored as-is and stays above the 10MB mmap threshold
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let large: Vec<u8> = (0..11 * 1024 * 1024)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        let small = b"thumbnail".to_vec();
+        let large_oid = odb.write(ObjectType::Blob, &large).await.unwrap();
+        let small_oid = odb.write(ObjectType::Blob, &small).await.unwrap();
+
+        // A fresh database so reads are not served from the cache
+        let reader = ObjectDatabase::with_smart_compression(storage, 100);
+        assert_eq!(reader.read(&small_oid).await.unwrap(), small);
+        assert_eq!(reader.metrics().await.mmap_reads, 0);
+
+        assert_eq!(reader.read(&large_oid).await.unwrap(), large);
+        assert_eq!(reader.metrics().await.mmap_reads, 1);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_by_object_category() {
+        use mediagit_compression::ObjectCategory;
+
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::with_smart_compression(storage, 100);
+
+        // Two revisions of a Photoshop file share nothing
+        odb.write_with_path(ObjectType::Blob, b"psd layers v1", "art/hero.psd")
+            .await
+            .unwrap();
+        odb.write_with_path(ObjectType::Blob, b"psd layers v2", "art/hero.psd")
+            .await
+            .unwrap();
+        // The same JSON config is written three times
+        for _ in 0..3 {
+            odb.write_with_path(ObjectType::Blob, b"{\"fps\": 24}", "config.json")
+                .await
+                .unwrap();
+        }
+        odb.write(ObjectType::Tree, b"tree entries").await.unwrap();
+
+        let metrics = odb.metrics().await;
+        assert_eq!(metrics.by_category.len(), 3);
+
+        let psd = metrics.category(ObjectCategory::CreativeProject);
+        assert_eq!(psd.total_writes, 2);
+        assert_eq!(psd.unique_objects, 2);
+        assert_eq!(psd.bytes_written, 26);
+        assert_eq!(psd.dedup_ratio(), 0.0);
+
+        let json = metrics.category(ObjectCategory::Text);
+        assert_eq!(json.total_writes, 3);
+        assert_eq!(json.unique_objects, 1);
+        assert_eq!(json.bytes_written, 33);
+        assert_eq!(json.bytes_stored, 11);
+        assert!((json.dedup_ratio() - 2.0 / 3.0).abs() < 1e-9);
+
+        assert_eq!(metrics.category(ObjectCategory::GitObject).total_writes, 1);
+
+        // Category counters add up to the global ones
+        assert_eq!(metrics.total_writes, 6);
+        assert_eq!(
+            metrics.bytes_stored,
+            metrics
+                .by_category
+                .values()
+                .map(|c| c.bytes_stored)
+                .sum::<u64>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compression_metrics() {
+        use mediagit_compression::{CompressionAlgorithm, ObjectCategory};
+
+        let storage = Arc::new(MockBackend::new());
+        let odb = ObjectDatabase::with_smart_compression(storage, 100);
+
+        let json = "{\"fps\": 24}\n".repeat(200);
+        // Written twice; the duplicate stores nothing and is not counted
+        for _ in 0..2 {
+            odb.write_with_path(ObjectType::Blob, json.as_bytes(), "config.json")
+                .await
+                .unwrap();
+        }
+
+        let metrics = odb.metrics().await;
+        let text = metrics.compression_by_category[&ObjectCategory::Text];
+        assert_eq!(text.objects, 1);
+        assert_eq!(text.bytes_in, json.len() as u64);
+        assert!(text.bytes_out < text.bytes_in);
+        assert!(text.ratio() > 1.0);
+
+        // Per-algorithm and per-category totals describe the same writes
+        let total = metrics.compression_total();
+        assert_eq!(total, text);
+        assert!(metrics
+            .compression_by_algorithm
+            .keys()
+            .all(|a| *a != CompressionAlgorithm::None));
+    }
+
     #[tokio::test]
     async fn test_cache_hit() {
         let storage = Arc::new(MockBackend::new());
@@ -3502,6 +5226,36 @@ mod tests {
         assert!(!odb.exists(&non_existent).await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_namespaces_isolate_objects_in_shared_backend() {
+        let bucket = Arc::new(MockBackend::new());
+        let game = ObjectDatabase::new(bucket.clone(), 100).with_namespace("repos/gameassets");
+        let web = ObjectDatabase::new(bucket.clone(), 100).with_namespace("repos/website/");
+
+        let game_oid = game.write(ObjectType::Blob, b"sprite sheet").await.unwrap();
+        let web_oid = web.write(ObjectType::Blob, b"landing page").await.unwrap();
+
+        assert!(game.exists(&game_oid).await.unwrap());
+        assert!(!game.exists(&web_oid).await.unwrap());
+        assert!(web.exists(&web_oid).await.unwrap());
+        assert!(!web.exists(&game_oid).await.unwrap());
+        assert!(web.read(&game_oid).await.is_err());
+
+        assert_eq!(game.list_loose_objects().await.unwrap(), vec![game_oid]);
+        assert_eq!(web.list_loose_objects().await.unwrap(), vec![web_oid]);
+        assert!(web
+            .resolve_abbreviated_oid(&game_oid.to_hex()[..8])
+            .await
+            .is_err());
+
+        // Every key in the shared bucket belongs to one of the namespaces
+        let keys = bucket.list_objects("").await.unwrap();
+        assert_eq!(keys.len(), 2);
+        assert!(keys
+            .iter()
+            .all(|k| k.starts_with("repos/gameassets/") || k.starts_with("repos/website/")));
+    }
+
     #[tokio::test]
     async fn test_verify() {
         let storage = Arc::new(MockBackend::new());
@@ -3552,7 +5306,7 @@ mod tests {
 
         // Verify data is compressed in storage
         let key = oid.to_hex();
-        let stored_data = storage.get(&key).await.unwrap();
+        let stored_data = stored_body(&storage, &key).await;
 
         // Stored data should be smaller than original (compressed)
         assert!(stored_data.len() < data.len());
@@ -3575,7 +5329,7 @@ mod tests {
 
         // Verify data is NOT compressed in storage
         let key = oid.to_hex();
-        let stored_data = storage.get(&key).await.unwrap();
+        let stored_data = stored_body(&storage, &key).await;
 
         // Stored data should be same as original (uncompressed)
         assert_eq!(stored_data, data);
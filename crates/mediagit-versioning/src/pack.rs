@@ -31,8 +31,8 @@
 //!   - SHA-256 of pack content
 //! ```
 
-use crate::delta::{Delta, DeltaDecoder};
 use crate::{ObjectType, Oid};
+use mediagit_compression::{Delta, DeltaDecoder};
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
 use std::collections::BTreeMap;
@@ -406,15 +406,16 @@ impl PackWriter {
         let offset = self.data.len() as u64;
 
         // Write delta header with base OID reference
-        self.data.extend_from_slice(b"DELTA");
+        self.data.extend_from_slice(DELTA_MAGIC);
         self.data.extend_from_slice(base_oid.as_bytes());
 
         // Write delta data
         let size = delta_data.len() as u32;
         self.data.extend_from_slice(delta_data);
 
-        // Record entry
-        self.index.insert(oid, offset, size);
+        // Record entry - the index size covers the magic and base OID (37 bytes)
+        let header_size = (DELTA_MAGIC.len() + base_oid.as_bytes().len()) as u32;
+        self.index.insert(oid, offset, size + header_size);
         self.entries.push(PackObjectEntry {
             oid,
             object_type: ObjectType::Blob, // Delta objects are stored as blobs
@@ -552,6 +553,39 @@ impl PackReader {
         Ok(data)
     }
 
+    /// Get the base and delta of a delta-encoded object
+    ///
+    /// Returns `None` for an object stored whole. Lets a caller apply the
+    /// delta to the base's decompressed content rather than its stored bytes.
+    pub fn get_delta(&self, oid: &Oid) -> io::Result<Option<(Oid, Delta)>> {
+        const BASE_OID_SIZE: usize = 32;
+
+        let (offset, total_size) = self
+            .index
+            .lookup(oid)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Object not found in pack"))?;
+        let (offset, total_size) = (offset as usize, total_size as usize);
+        let header_size = DELTA_MAGIC.len() + BASE_OID_SIZE;
+        if offset + total_size > self.data.len()
+            || total_size < header_size
+            || &self.data[offset..offset + DELTA_MAGIC.len()] != DELTA_MAGIC
+        {
+            return Ok(None);
+        }
+
+        let mut base_oid_bytes = [0u8; 32];
+        base_oid_bytes
+            .copy_from_slice(&self.data[offset + DELTA_MAGIC.len()..offset + header_size]);
+        let delta = Delta::from_bytes(&self.data[offset + header_size..offset + total_size])
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Failed to parse delta: {}", e),
+                )
+            })?;
+        Ok(Some((Oid::from(base_oid_bytes), delta)))
+    }
+
     /// Get object data and type by OID
     ///
     /// Handles both regular objects and delta-encoded objects.
@@ -686,6 +720,11 @@ impl PackReader {
         Ok((base_type, reconstructed))
     }
 
+    /// Raw pack data, including its checksum
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
     /// Get the index reference
     pub fn index(&self) -> &PackIndex {
         &self.index
@@ -777,6 +816,23 @@ mod tests {
         assert!(pack_data.len() > 12 + CHECKSUM_SIZE);
     }
 
+    #[test]
+    fn test_pack_reader_delta_object() {
+        let base = b"the quick brown fox jumps over the lazy dog".repeat(20);
+        let mut target = base.clone();
+        target[100..105].copy_from_slice(b"DELTA");
+        let base_oid = Oid::hash(&base);
+        let target_oid = Oid::hash(&target);
+
+        let mut writer = PackWriter::new();
+        writer.add_object(base_oid, ObjectType::Blob, &base);
+        let delta = mediagit_compression::DeltaEncoder::encode(&base, &target);
+        writer.add_delta_object(target_oid, base_oid, &delta.to_bytes());
+
+        let reader = PackReader::new(writer.finalize()).unwrap();
+        assert_eq!(reader.get_object(&target_oid).unwrap(), target);
+    }
+
     #[test]
     fn test_pack_reader_verification() {
         let mut writer = PackWriter::new();
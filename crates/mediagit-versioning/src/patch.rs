@@ -0,0 +1,1160 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Unified patches for text assets
+//!
+//! Produces git-style unified diffs (`diff --git` header, `@@` hunks) that
+//! `patch -p1` and `git apply` accept. Lines are compared with the Myers
+//! O(ND) algorithm; binary content yields a single "Binary files differ" line.
+//!
+//! The same format can be parsed back with [`parse_patch`] and applied with
+//! [`FilePatch::apply`]. When a hunk's context no longer matches, the
+//! preimage recorded on the `index` line can be merged line-by-line with
+//! [`merge_text`] instead.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use mediagit_versioning::unified_diff;
+//!
+//! let patch = unified_diff(
+//!     "notes.txt",
+//!     Some(b"one\ntwo\nthree\n"),
+//!     Some(b"one\n2\nthree\n"),
+//!     3,
+//! );
+//! let text = String::from_utf8(patch).unwrap();
+//! assert!(text.contains("@@ -1,3 +1,3 @@\n one\n-two\n+2\n three\n"));
+//! ```
+
+use crate::attributes::looks_binary;
+use crate::Oid;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Component, Path};
+
+/// Default number of context lines around each hunk
+pub const DEFAULT_CONTEXT_LINES: usize = 3;
+
+/// Mode written in the `new file` / `deleted file` header lines
+const REGULAR_FILE_MODE: &str = "100644";
+
+/// One step of a line-level edit script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditKind {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// An edit with the old/new line positions it applies at
+///
+/// For deletions `new` is the number of new lines emitted so far, and for
+/// insertions `old` is the number of old lines consumed so far.
+#[derive(Debug, Clone, Copy)]
+struct Edit {
+    kind: EditKind,
+    old: usize,
+    new: usize,
+}
+
+/// Generate a unified diff for one file
+///
+/// `old` is None for an added file and `new` is None for a deleted one.
+/// Returns an empty patch when both sides are identical.
+///
+/// # Arguments
+/// * `path` - Repository-relative path, used for the `a/` and `b/` names
+/// * `old` - Content before the change
+/// * `new` - Content after the change
+/// * `context` - Number of unchanged lines to show around each change
+pub fn unified_diff(path: &str, old: Option<&[u8]>, new: Option<&[u8]>, context: usize) -> Vec<u8> {
+    if old == new {
+        return Vec::new();
+    }
+    let (old_id, new_id) = (old.map(Oid::hash), new.map(Oid::hash));
+    let old = old.unwrap_or_default();
+    let new = new.unwrap_or_default();
+    if looks_binary(old) || looks_binary(new) {
+        return binary_diff(path, old_id, new_id);
+    }
+
+    let mut out = file_header(path, old_id, new_id);
+    let (old_name, new_name) = side_names(path, old_id, new_id);
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+    let edits = diff_lines(&old_lines, &new_lines);
+    let hunks = group_hunks(&edits, context);
+
+    // Adding or removing an empty file is described by the header alone
+    if !hunks.is_empty() {
+        out.extend_from_slice(format!("--- {}\n+++ {}\n", old_name, new_name).as_bytes());
+    }
+
+    for hunk in hunks {
+        let edits = &edits[hunk];
+        let old_count = edits.iter().filter(|e| e.kind != EditKind::Insert).count();
+        let new_count = edits.iter().filter(|e| e.kind != EditKind::Delete).count();
+        out.extend_from_slice(
+            format!(
+                "@@ -{} +{} @@\n",
+                hunk_range(edits[0].old, old_count),
+                hunk_range(edits[0].new, new_count)
+            )
+            .as_bytes(),
+        );
+
+        for edit in edits {
+            let (prefix, line) = match edit.kind {
+                EditKind::Equal => (b' ', old_lines[edit.old]),
+                EditKind::Delete => (b'-', old_lines[edit.old]),
+                EditKind::Insert => (b'+', new_lines[edit.new]),
+            };
+            out.push(prefix);
+            out.extend_from_slice(line);
+            if !line.ends_with(b"\n") {
+                out.extend_from_slice(b"\n\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    out
+}
+
+/// Generate the patch for a file that is binary on either side
+///
+/// Takes object IDs rather than content, so large media can be reported as
+/// changed without reading it. Returns an empty patch when both IDs match.
+pub fn binary_diff(path: &str, old: Option<Oid>, new: Option<Oid>) -> Vec<u8> {
+    if old == new {
+        return Vec::new();
+    }
+    let mut out = file_header(path, old, new);
+    let (old_name, new_name) = side_names(path, old, new);
+    out.extend_from_slice(
+        format!("Binary files {} and {} differ\n", old_name, new_name).as_bytes(),
+    );
+    out
+}
+
+/// The `diff --git`, file mode and `index` lines that open a file's patch
+fn file_header(path: &str, old: Option<Oid>, new: Option<Oid>) -> Vec<u8> {
+    let mut out = format!("diff --git a/{0} b/{0}\n", path).into_bytes();
+    match (old, new) {
+        (None, _) => {
+            out.extend_from_slice(format!("new file mode {}\n", REGULAR_FILE_MODE).as_bytes())
+        }
+        (_, None) => {
+            out.extend_from_slice(format!("deleted file mode {}\n", REGULAR_FILE_MODE).as_bytes())
+        }
+        _ => {}
+    }
+    out.extend_from_slice(index_line(old, new).as_bytes());
+    out
+}
+
+/// The `a/` and `b/` names of each side, or `/dev/null` for a missing one
+fn side_names(path: &str, old: Option<Oid>, new: Option<Oid>) -> (String, String) {
+    (
+        old.map_or("/dev/null".to_string(), |_| format!("a/{}", path)),
+        new.map_or("/dev/null".to_string(), |_| format!("b/{}", path)),
+    )
+}
+
+/// The `index <old>..<new>` line naming the blobs on each side
+///
+/// Full object IDs are written so `apply --3way` can find the preimage; a
+/// missing side is all zeros, as in git.
+fn index_line(old: Option<Oid>, new: Option<Oid>) -> String {
+    let id = |side: Option<Oid>| side.map_or("0".repeat(64), |oid| oid.to_hex());
+    match (old, new) {
+        (Some(_), Some(_)) => format!("index {}..{} {}\n", id(old), id(new), REGULAR_FILE_MODE),
+        _ => format!("index {}..{}\n", id(old), id(new)),
+    }
+}
+
+/// Split content into lines, keeping each line's terminator
+fn split_lines(data: &[u8]) -> Vec<&[u8]> {
+    data.split_inclusive(|&b| b == b'\n').collect()
+}
+
+/// Format a hunk range as `start,count` (or just `start` for a single line)
+///
+/// An empty range names the line *before* the change, per the unified format.
+fn hunk_range(position: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", position),
+        1 => format!("{}", position + 1),
+        _ => format!("{},{}", position + 1, count),
+    }
+}
+
+/// Compute the line edit script between `old` and `new`
+fn diff_lines<'a>(old: &[&'a [u8]], new: &[&'a [u8]]) -> Vec<Edit> {
+    // Intern lines so the inner loop compares integers
+    let mut ids: HashMap<&'a [u8], u32> = HashMap::new();
+    let mut intern = |lines: &[&'a [u8]]| -> Vec<u32> {
+        lines
+            .iter()
+            .map(|line| {
+                let next = ids.len() as u32;
+                *ids.entry(line).or_insert(next)
+            })
+            .collect()
+    };
+    let a = intern(old);
+    let b = intern(new);
+    myers(&a, &b)
+}
+
+/// Myers' O(ND) shortest edit script, in linear space
+///
+/// Searches forward from the start and backward from the end at once to find
+/// where an optimal path crosses the middle of the edit graph, then recurses
+/// on either side, so only two frontiers of O(N + M) are held at a time.
+/// Within each run of changes, deletions are listed before insertions.
+fn myers(a: &[u32], b: &[u32]) -> Vec<Edit> {
+    let mut kinds = Vec::with_capacity(a.len().max(b.len()));
+    edit_kinds(a, b, &mut kinds);
+
+    let mut edits = Vec::with_capacity(kinds.len());
+    let (mut old, mut new) = (0, 0);
+    let mut i = 0;
+    while i < kinds.len() {
+        if kinds[i] == EditKind::Equal {
+            edits.push(Edit {
+                kind: EditKind::Equal,
+                old,
+                new,
+            });
+            (old, new, i) = (old + 1, new + 1, i + 1);
+            continue;
+        }
+        let run = kinds[i..]
+            .iter()
+            .take_while(|&&kind| kind != EditKind::Equal);
+        let deleted = run
+            .clone()
+            .filter(|&&kind| kind == EditKind::Delete)
+            .count();
+        let inserted = run.count() - deleted;
+        edits.extend((0..deleted).map(|d| Edit {
+            kind: EditKind::Delete,
+            old: old + d,
+            new,
+        }));
+        edits.extend((0..inserted).map(|n| Edit {
+            kind: EditKind::Insert,
+            old: old + deleted,
+            new: new + n,
+        }));
+        (old, new, i) = (old + deleted, new + inserted, i + deleted + inserted);
+    }
+    edits
+}
+
+/// Append the kinds of the edits that turn `a` into `b`
+fn edit_kinds(a: &[u32], b: &[u32], out: &mut Vec<EditKind>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+
+    // Common prefix and suffix never need the O(ND) search
+    out.extend(std::iter::repeat_n(EditKind::Equal, prefix));
+    match middle_snake(a, b) {
+        Some((x, y)) => {
+            edit_kinds(&a[..x], &b[..y], out);
+            edit_kinds(&a[x..], &b[y..], out);
+        }
+        None => {
+            out.extend(std::iter::repeat_n(EditKind::Delete, a.len()));
+            out.extend(std::iter::repeat_n(EditKind::Insert, b.len()));
+        }
+    }
+    out.extend(std::iter::repeat_n(EditKind::Equal, suffix));
+}
+
+/// A point where an optimal edit path from `a` to `b` crosses the middle
+///
+/// `a` and `b` must differ in their first and last lines. Returns None when
+/// either is empty or no line of one survives into the other.
+fn middle_snake(a: &[u32], b: &[u32]) -> Option<(usize, usize)> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d;
+    let len = 2 * max_d + 2;
+    // Furthest x reached on each diagonal, from the start and from the end
+    let mut forward = vec![-1isize; len as usize];
+    let mut reverse = vec![-1isize; len as usize];
+    forward[offset as usize + 1] = 0;
+    reverse[offset as usize + 1] = 0;
+
+    // With an odd difference the paths meet during a forward step
+    let delta = n - m;
+    let meet_forward = delta % 2 != 0;
+    // Diagonals that ran off the graph are not searched again
+    let (mut forward_start, mut forward_end) = (0, 0);
+    let (mut reverse_start, mut reverse_end) = (0, 0);
+
+    for d in 0..max_d {
+        for k in (-d + forward_start..=d - forward_end).step_by(2) {
+            let at = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && forward[at - 1] < forward[at + 1]) {
+                forward[at + 1]
+            } else {
+                forward[at - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at] = x;
+            if x > n {
+                forward_end += 2;
+            } else if y > m {
+                forward_start += 2;
+            } else if meet_forward {
+                let other = offset + delta - k;
+                if (0..len).contains(&other)
+                    && reverse[other as usize] != -1
+                    && x >= n - reverse[other as usize]
+                {
+                    return Some((x as usize, y as usize));
+                }
+            }
+        }
+
+        for k in (-d + reverse_start..=d - reverse_end).step_by(2) {
+            let at = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && reverse[at - 1] < reverse[at + 1]) {
+                reverse[at + 1]
+            } else {
+                reverse[at - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            reverse[at] = x;
+            if x > n {
+                reverse_end += 2;
+            } else if y > m {
+                reverse_start += 2;
+            } else if !meet_forward {
+                let other = offset + delta - k;
+                if (0..len).contains(&other) && forward[other as usize] != -1 {
+                    let forward_x = forward[other as usize];
+                    if forward_x >= n - x {
+                        let forward_y = offset + forward_x - other;
+                        return Some((forward_x as usize, forward_y as usize));
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Group edits into hunks, merging changes separated by at most `2 * context` lines
+fn group_hunks(edits: &[Edit], context: usize) -> Vec<Range<usize>> {
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.kind != EditKind::Equal)
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut hunks: Vec<Range<usize>> = Vec::new();
+    for &i in &changes {
+        let start = i.saturating_sub(context);
+        let end = (i + 1 + context).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+    hunks
+}
+
+/// One line of a hunk body, including its line terminator
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HunkLine {
+    Context(Vec<u8>),
+    Delete(Vec<u8>),
+    Insert(Vec<u8>),
+}
+
+/// A parsed `@@` hunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Hunk {
+    old_start: usize,
+    old_count: usize,
+    new_start: usize,
+    new_count: usize,
+    lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// Lines the hunk expects to find (context and deletions)
+    fn preimage(&self) -> Vec<&[u8]> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(l) | HunkLine::Delete(l) => Some(l.as_slice()),
+                HunkLine::Insert(_) => None,
+            })
+            .collect()
+    }
+
+    /// Lines the hunk leaves behind (context and insertions)
+    fn postimage(&self) -> Vec<&[u8]> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(l) | HunkLine::Insert(l) => Some(l.as_slice()),
+                HunkLine::Delete(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// The changes a patch makes to one file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilePatch {
+    /// Path before the change; None when the patch creates the file
+    pub old_path: Option<String>,
+
+    /// Path after the change; None when the patch deletes the file
+    pub new_path: Option<String>,
+
+    /// Blob the patch was made against, when the `index` line names one
+    pub old_oid: Option<Oid>,
+
+    /// Mode of the file after the change, when the header names one
+    pub mode: Option<u32>,
+
+    /// Binary patch (only "Binary files differ" or a git binary payload)
+    pub binary: bool,
+
+    hunks: Vec<Hunk>,
+}
+
+/// Result of applying a [`FilePatch`] to some content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApplyOutcome {
+    /// Every hunk applied; holds the patched content
+    Applied(Vec<u8>),
+
+    /// The content already contains the patch's changes
+    AlreadyApplied,
+
+    /// Hunk number `hunk` (1-based) matched neither the old nor the new lines
+    Conflict { hunk: usize },
+}
+
+impl FilePatch {
+    /// Repository-relative path the patch applies to
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Whether the patch creates a new file
+    pub fn is_creation(&self) -> bool {
+        self.old_path.is_none()
+    }
+
+    /// Whether the patch deletes the file
+    pub fn is_deletion(&self) -> bool {
+        self.new_path.is_none()
+    }
+
+    /// Apply the hunks to `content`
+    ///
+    /// Each hunk is matched exactly, first at the line its header names and
+    /// then at increasing distances from it, so patches still apply after
+    /// unrelated lines were added or removed elsewhere in the file.
+    pub fn apply(&self, content: &[u8]) -> ApplyOutcome {
+        match apply_hunks(&self.hunks, content, false) {
+            Ok(patched) => ApplyOutcome::Applied(patched),
+            Err(_) if !self.hunks.is_empty() && apply_hunks(&self.hunks, content, true).is_ok() => {
+                ApplyOutcome::AlreadyApplied
+            }
+            Err(hunk) => ApplyOutcome::Conflict { hunk },
+        }
+    }
+}
+
+/// Apply hunks in order; on failure returns the 1-based number of the failing hunk
+///
+/// With `reverse` the hunks are undone instead, which is how an
+/// already-applied patch is recognized.
+fn apply_hunks(
+    hunks: &[Hunk],
+    content: &[u8],
+    reverse: bool,
+) -> std::result::Result<Vec<u8>, usize> {
+    let lines = split_lines(content);
+    let mut out = Vec::with_capacity(content.len());
+    let mut cursor = 0;
+    let mut drift = 0isize;
+
+    for (n, hunk) in hunks.iter().enumerate() {
+        let (from, to, start, count) = if reverse {
+            (
+                hunk.postimage(),
+                hunk.preimage(),
+                hunk.new_start,
+                hunk.new_count,
+            )
+        } else {
+            (
+                hunk.preimage(),
+                hunk.postimage(),
+                hunk.old_start,
+                hunk.old_count,
+            )
+        };
+
+        // An empty range names the line before the change
+        let named = if count == 0 {
+            start
+        } else {
+            start.saturating_sub(1)
+        };
+        let expected = (named as isize + drift).max(0) as usize;
+        let pos = find_block(&lines, &from, cursor, expected).ok_or(n + 1)?;
+
+        lines[cursor..pos]
+            .iter()
+            .for_each(|l| out.extend_from_slice(l));
+        to.iter().for_each(|l| out.extend_from_slice(l));
+        drift = pos as isize - named as isize;
+        cursor = pos + from.len();
+    }
+
+    lines[cursor..]
+        .iter()
+        .for_each(|l| out.extend_from_slice(l));
+    Ok(out)
+}
+
+/// Find `block` in `lines` at or after `min`, searching outward from `expected`
+fn find_block(lines: &[&[u8]], block: &[&[u8]], min: usize, expected: usize) -> Option<usize> {
+    if lines.len() < min + block.len() {
+        return None;
+    }
+    let last = lines.len() - block.len();
+    let expected = expected.clamp(min, last);
+    let matches = |p: usize| lines[p..p + block.len()] == *block;
+
+    for distance in 0.. {
+        let after = expected + distance;
+        let before = expected.checked_sub(distance).filter(|&p| p >= min);
+        if after > last && before.is_none() {
+            break;
+        }
+        if after <= last && matches(after) {
+            return Some(after);
+        }
+        if let Some(p) = before.filter(|_| distance > 0) {
+            if matches(p) {
+                return Some(p);
+            }
+        }
+    }
+    None
+}
+
+/// Parse a unified diff into per-file patches
+///
+/// Accepts git-style patches (`diff --git` headers, `new file mode`,
+/// `index` lines) as well as plain `diff -u` output. Text before the first
+/// header, such as an email preamble, is ignored. One leading path component
+/// (`a/`, `b/`) is stripped, as with `patch -p1`.
+///
+/// Fails if any path is absolute, contains `..` or `.`, or lies under
+/// `.mediagit`, so a patch can't write outside the working tree.
+pub fn parse_patch(data: &[u8]) -> Result<Vec<FilePatch>> {
+    let lines = split_lines(data);
+    let mut files: Vec<FilePatch> = Vec::new();
+    // Between a `diff --git` line and the file's first hunk
+    let mut in_git_header = false;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = trim_eol(lines[i]);
+
+        if let Some(names) = line.strip_prefix(b"diff --git ") {
+            files.push(parse_git_header(names));
+            in_git_header = true;
+        } else if line.starts_with(b"--- ")
+            && lines
+                .get(i + 1)
+                .is_some_and(|next| next.starts_with(b"+++ "))
+        {
+            if !in_git_header {
+                files.push(FilePatch::default());
+            }
+            let file = files.last_mut().expect("file patch was just pushed");
+            file.old_path = parse_name(&line[4..]);
+            file.new_path = parse_name(&trim_eol(lines[i + 1])[4..]);
+            in_git_header = false;
+            i += 2;
+            continue;
+        } else if line.starts_with(b"@@ ") {
+            let file = files
+                .last_mut()
+                .with_context(|| format!("Hunk without a file header at line {}", i + 1))?;
+            let (hunk, used) = parse_hunk(&lines[i..])
+                .with_context(|| format!("Malformed hunk at line {}", i + 1))?;
+            file.hunks.push(hunk);
+            in_git_header = false;
+            i += used;
+            continue;
+        } else if in_git_header {
+            let file = files.last_mut().expect("inside a git header");
+            if let Some(mode) = line.strip_prefix(b"new file mode ") {
+                file.old_path = None;
+                file.mode = parse_mode(mode);
+            } else if line.starts_with(b"deleted file mode") {
+                file.new_path = None;
+            } else if let Some(mode) = line.strip_prefix(b"new mode ") {
+                file.mode = parse_mode(mode);
+            } else if let Some(ids) = line.strip_prefix(b"index ") {
+                file.old_oid = parse_index_oid(ids);
+                if let Some(mode) = ids.split(|&b| b == b' ').nth(1) {
+                    file.mode = parse_mode(mode);
+                }
+            } else if line.starts_with(b"Binary files ") || line == b"GIT binary patch" {
+                file.binary = true;
+            }
+        }
+        i += 1;
+    }
+
+    for file in &files {
+        let paths = [file.old_path.as_deref(), file.new_path.as_deref()];
+        for path in paths.into_iter().flatten().chain([file.path()]) {
+            check_path(path)?;
+        }
+    }
+    Ok(files)
+}
+
+/// Fail unless `path` is a plain relative path outside `.mediagit`
+fn check_path(path: &str) -> Result<()> {
+    let mut components = Path::new(path).components();
+    let safe = matches!(
+        components.next(),
+        Some(Component::Normal(first)) if !first.eq_ignore_ascii_case(".mediagit")
+    ) && components.all(|c| matches!(c, Component::Normal(_)));
+    if !safe {
+        anyhow::bail!(
+            "Unsafe path '{}' in patch: paths must be relative, without '.' or '..', \
+             and outside .mediagit",
+            path
+        );
+    }
+    Ok(())
+}
+
+/// Parse the `a/<path> b/<path>` part of a `diff --git` line
+fn parse_git_header(names: &[u8]) -> FilePatch {
+    let names = String::from_utf8_lossy(names);
+    let path = match names.rfind(" b/") {
+        Some(split) => strip_component(&names[split + 1..]),
+        None => strip_component(names.split(' ').next().unwrap_or_default()),
+    };
+    FilePatch {
+        old_path: Some(path.clone()),
+        new_path: Some(path),
+        ..Default::default()
+    }
+}
+
+/// Parse a `---`/`+++` file name; `/dev/null` means the file is absent
+fn parse_name(name: &[u8]) -> Option<String> {
+    let name = String::from_utf8_lossy(name);
+    // diff -u appends a tab and timestamp
+    let name = name.split('\t').next().unwrap_or_default().trim_end();
+    (name != "/dev/null").then(|| strip_component(name))
+}
+
+/// Drop the first path component (`a/`, `b/`), like `patch -p1`
+fn strip_component(path: &str) -> String {
+    path.split_once('/')
+        .map_or(path, |(_, rest)| rest)
+        .to_string()
+}
+
+/// Preimage blob from an `index <old>..<new> [mode]` line
+///
+/// Only full object IDs are usable; abbreviated or all-zero IDs give None.
+fn parse_index_oid(ids: &[u8]) -> Option<Oid> {
+    let ids = std::str::from_utf8(ids).ok()?;
+    let (old, _) = ids.split_once("..")?;
+    if old.bytes().all(|b| b == b'0') {
+        return None;
+    }
+    Oid::from_hex(old).ok()
+}
+
+/// Octal file mode from a `new file mode`, `new mode` or `index` line
+fn parse_mode(mode: &[u8]) -> Option<u32> {
+    u32::from_str_radix(std::str::from_utf8(mode).ok()?.trim(), 8).ok()
+}
+
+/// Parse one hunk starting at its `@@` line; returns the hunk and lines consumed
+fn parse_hunk(lines: &[&[u8]]) -> Result<(Hunk, usize)> {
+    let header = std::str::from_utf8(trim_eol(lines[0])).context("Hunk header is not UTF-8")?;
+    let mut fields = header.split_whitespace().skip(1);
+    let (old_start, old_count) = fields
+        .next()
+        .and_then(|f| f.strip_prefix('-'))
+        .and_then(parse_range)
+        .context("Invalid old range in hunk header")?;
+    let (new_start, new_count) = fields
+        .next()
+        .and_then(|f| f.strip_prefix('+'))
+        .and_then(parse_range)
+        .context("Invalid new range in hunk header")?;
+
+    let mut hunk = Hunk {
+        old_start,
+        old_count,
+        new_start,
+        new_count,
+        lines: Vec::new(),
+    };
+    let (mut old_left, mut new_left) = (old_count, new_count);
+    let mut used = 1;
+
+    while old_left > 0 || new_left > 0 {
+        let raw = *lines.get(used).context("Hunk is truncated")?;
+        let line = match raw.first() {
+            Some(b' ') => HunkLine::Context(raw[1..].to_vec()),
+            Some(b'-') => HunkLine::Delete(raw[1..].to_vec()),
+            Some(b'+') => HunkLine::Insert(raw[1..].to_vec()),
+            // Some tools strip the space from empty context lines
+            Some(b'\n') | Some(b'\r') => HunkLine::Context(raw.to_vec()),
+            _ => anyhow::bail!(
+                "Unexpected line in hunk: {:?}",
+                String::from_utf8_lossy(raw)
+            ),
+        };
+
+        let (old_used, new_used) = match line {
+            HunkLine::Context(_) => (1, 1),
+            HunkLine::Delete(_) => (1, 0),
+            HunkLine::Insert(_) => (0, 1),
+        };
+        if old_left < old_used || new_left < new_used {
+            anyhow::bail!("Hunk has more lines than its header declares");
+        }
+        old_left -= old_used;
+        new_left -= new_used;
+        hunk.lines.push(line);
+        used += 1;
+
+        // "\ No newline at end of file" applies to the line before it
+        if lines.get(used).is_some_and(|l| l.starts_with(b"\\")) {
+            if let Some(HunkLine::Context(l) | HunkLine::Delete(l) | HunkLine::Insert(l)) =
+                hunk.lines.last_mut()
+            {
+                if l.ends_with(b"\n") {
+                    l.pop();
+                }
+            }
+            used += 1;
+        }
+    }
+
+    Ok((hunk, used))
+}
+
+/// Parse `start[,count]`; the count defaults to 1
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// Strip a trailing `\n` or `\r\n`
+fn trim_eol(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// Result of a line-based three-way merge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedText {
+    /// Merged content, with conflict markers where both sides changed the same lines
+    pub content: Vec<u8>,
+
+    /// Number of conflicting regions
+    pub conflicts: usize,
+}
+
+/// Merge two edits of the same text against their common base
+///
+/// Regions changed on only one side take that side's lines; regions both
+/// sides changed differently are wrapped in `<<<<<<<`/`=======`/`>>>>>>>`
+/// markers labelled with `ours_label` and `theirs_label`.
+pub fn merge_text(
+    base: &[u8],
+    ours: &[u8],
+    theirs: &[u8],
+    ours_label: &str,
+    theirs_label: &str,
+) -> MergedText {
+    merge_lines(base, ours, theirs, Some((ours_label, theirs_label)))
+}
+
+/// Merge two edits of the same text, keeping both sides where they conflict
+///
+/// Like [`merge_text`], but regions both sides changed get our lines followed
+/// by theirs, with no markers — the `merge=union` resolution for append-only
+/// files such as logs and changelogs.
+pub fn union_text(base: &[u8], ours: &[u8], theirs: &[u8]) -> Vec<u8> {
+    merge_lines(base, ours, theirs, None).content
+}
+
+/// Three-way line merge; conflicting regions get markers labelled with
+/// `labels`, or both sides unmarked if `labels` is `None`
+fn merge_lines(
+    base: &[u8],
+    ours: &[u8],
+    theirs: &[u8],
+    labels: Option<(&str, &str)>,
+) -> MergedText {
+    let base_lines = split_lines(base);
+    let ours_lines = split_lines(ours);
+    let theirs_lines = split_lines(theirs);
+    let to_ours = equal_lines(&diff_lines(&base_lines, &ours_lines), base_lines.len());
+    let to_theirs = equal_lines(&diff_lines(&base_lines, &theirs_lines), base_lines.len());
+
+    let mut content = Vec::with_capacity(ours.len().max(theirs.len()));
+    let mut conflicts = 0;
+    let (mut b, mut o, mut t) = (0, 0, 0);
+
+    loop {
+        // Copy lines all three versions agree on
+        while b < base_lines.len() && to_ours[b] == Some(o) && to_theirs[b] == Some(t) {
+            content.extend_from_slice(base_lines[b]);
+            b += 1;
+            o += 1;
+            t += 1;
+        }
+        if b == base_lines.len() && o == ours_lines.len() && t == theirs_lines.len() {
+            break;
+        }
+
+        // The changed region ends at the next base line both sides kept
+        let (b_end, o_end, t_end) = (b..base_lines.len())
+            .find_map(|i| Some((i, to_ours[i]?, to_theirs[i]?)))
+            .unwrap_or((base_lines.len(), ours_lines.len(), theirs_lines.len()));
+
+        let base_chunk = &base_lines[b..b_end];
+        let ours_chunk = &ours_lines[o..o_end];
+        let theirs_chunk = &theirs_lines[t..t_end];
+
+        if ours_chunk == base_chunk {
+            theirs_chunk
+                .iter()
+                .for_each(|l| content.extend_from_slice(l));
+        } else if theirs_chunk == base_chunk || ours_chunk == theirs_chunk {
+            ours_chunk.iter().for_each(|l| content.extend_from_slice(l));
+        } else if let Some((ours_label, theirs_label)) = labels {
+            conflicts += 1;
+            content.extend_from_slice(format!("<<<<<<< {}\n", ours_label).as_bytes());
+            push_terminated(&mut content, ours_chunk);
+            content.extend_from_slice(b"=======\n");
+            push_terminated(&mut content, theirs_chunk);
+            content.extend_from_slice(format!(">>>>>>> {}\n", theirs_label).as_bytes());
+        } else {
+            push_terminated(&mut content, ours_chunk);
+            theirs_chunk
+                .iter()
+                .for_each(|l| content.extend_from_slice(l));
+        }
+
+        (b, o, t) = (b_end, o_end, t_end);
+    }
+
+    MergedText { content, conflicts }
+}
+
+/// For each old line, the new line it is unchanged as (if any)
+fn equal_lines(edits: &[Edit], old_len: usize) -> Vec<Option<usize>> {
+    let mut map = vec![None; old_len];
+    for edit in edits.iter().filter(|e| e.kind == EditKind::Equal) {
+        map[edit.old] = Some(edit.new);
+    }
+    map
+}
+
+/// Append lines, making sure the last one ends with a newline (before a marker)
+fn push_terminated(out: &mut Vec<u8>, lines: &[&[u8]]) {
+    lines.iter().for_each(|l| out.extend_from_slice(l));
+    if lines.last().is_some_and(|l| !l.ends_with(b"\n")) {
+        out.push(b'\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(old: &str, new: &str, context: usize) -> String {
+        String::from_utf8(unified_diff(
+            "f.txt",
+            Some(old.as_bytes()),
+            Some(new.as_bytes()),
+            context,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_identical_content_has_no_patch() {
+        assert!(unified_diff("f.txt", Some(b"same\n"), Some(b"same\n"), 3).is_empty());
+    }
+
+    #[test]
+    fn test_hunks_split_and_merge_by_context() {
+        let old: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        let new = old
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "line eighteen\n");
+
+        let patch = diff(&old, &new, 3);
+        assert!(patch.contains("@@ -1,5 +1,5 @@\n line 1\n-line 2\n+line two\n"));
+        assert!(patch.contains("@@ -15,6 +15,6 @@\n"));
+        assert_eq!(patch.matches("@@ -").count(), 2);
+
+        // With enough context both changes share one hunk
+        assert_eq!(diff(&old, &new, 8).matches("@@ -").count(), 1);
+    }
+
+    #[test]
+    fn test_added_deleted_and_missing_newline() {
+        let added = String::from_utf8(unified_diff("n.txt", None, Some(b"a\nb"), 3)).unwrap();
+        assert!(added.contains("new file mode 100644\nindex 0000"));
+        assert!(added.contains("\n--- /dev/null\n+++ b/n.txt\n"));
+        assert!(added.ends_with("@@ -0,0 +1,2 @@\n+a\n+b\n\\ No newline at end of file\n"));
+
+        let deleted = String::from_utf8(unified_diff("n.txt", Some(b"a\n"), None, 3)).unwrap();
+        assert!(deleted.contains("--- a/n.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-a\n"));
+    }
+
+    #[test]
+    fn test_edit_scripts_are_shortest() {
+        // Small alphabets force many repeated lines and competing paths
+        let mut seed = 0x2545_f491_u32;
+        let mut next = |bound: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % bound
+        };
+        for _ in 0..500 {
+            let a: Vec<u32> = (0..next(30)).map(|_| next(4)).collect();
+            let b: Vec<u32> = (0..next(30)).map(|_| next(4)).collect();
+            let edits = myers(&a, &b);
+
+            let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+            for i in (0..a.len()).rev() {
+                for j in (0..b.len()).rev() {
+                    lcs[i][j] = if a[i] == b[j] {
+                        lcs[i + 1][j + 1] + 1
+                    } else {
+                        lcs[i + 1][j].max(lcs[i][j + 1])
+                    };
+                }
+            }
+            let changes = edits.iter().filter(|e| e.kind != EditKind::Equal).count();
+            assert_eq!(changes, a.len() + b.len() - 2 * lcs[0][0]);
+
+            let (mut rebuilt_a, mut rebuilt_b) = (Vec::new(), Vec::new());
+            for edit in &edits {
+                match edit.kind {
+                    EditKind::Equal => {
+                        assert_eq!(a[edit.old], b[edit.new]);
+                        rebuilt_a.push(a[edit.old]);
+                        rebuilt_b.push(b[edit.new]);
+                    }
+                    EditKind::Delete => {
+                        assert_eq!(edit.new, rebuilt_b.len());
+                        rebuilt_a.push(a[edit.old]);
+                    }
+                    EditKind::Insert => {
+                        assert_eq!(edit.old, rebuilt_a.len());
+                        rebuilt_b.push(b[edit.new]);
+                    }
+                }
+            }
+            assert_eq!((rebuilt_a, rebuilt_b), (a, b));
+        }
+    }
+
+    #[test]
+    fn test_binary_content() {
+        let patch = unified_diff("img.png", Some(b"\x89PNG\0\x01"), Some(b"\x89PNG\0\x02"), 3);
+        let text = String::from_utf8(patch).unwrap();
+        assert!(text.starts_with("diff --git a/img.png b/img.png\nindex "));
+        assert!(text.ends_with("\nBinary files a/img.png and b/img.png differ\n"));
+    }
+
+    #[test]
+    fn test_parse_and_apply_round_trip() {
+        let old = "alpha\nbeta\ngamma\ndelta\nepsilon\nzeta\neta\ntheta\n";
+        let new = "alpha\nBETA\ngamma\ndelta\nepsilon\nzeta\neta\ntheta\niota";
+        let patch = unified_diff("greek.txt", Some(old.as_bytes()), Some(new.as_bytes()), 1);
+
+        let files = parse_patch(&patch).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path(), "greek.txt");
+        assert_eq!(files[0].old_oid, Some(Oid::hash(old.as_bytes())));
+        assert_eq!(
+            files[0].apply(old.as_bytes()),
+            ApplyOutcome::Applied(new.as_bytes().to_vec())
+        );
+
+        // Lines added above the hunks shift them without breaking the match
+        let shifted = format!("preface\n{}", old);
+        assert_eq!(
+            files[0].apply(shifted.as_bytes()),
+            ApplyOutcome::Applied(format!("preface\n{}", new).into_bytes())
+        );
+    }
+
+    #[test]
+    fn test_apply_detects_already_applied_and_conflicts() {
+        let patch = unified_diff("f.txt", Some(b"a\nb\nc\n"), Some(b"a\nB\nc\n"), 3);
+        let file = &parse_patch(&patch).unwrap()[0];
+
+        assert_eq!(file.apply(b"a\nB\nc\n"), ApplyOutcome::AlreadyApplied);
+        assert_eq!(file.apply(b"a\nX\nc\n"), ApplyOutcome::Conflict { hunk: 1 });
+    }
+
+    #[test]
+    fn test_parse_creation_deletion_and_plain_diffs() {
+        let mut patch = unified_diff("new.txt", None, Some(b"hello\n"), 3);
+        patch.extend(unified_diff("old.txt", Some(b"bye\n"), None, 3));
+        patch.extend_from_slice(
+            b"--- a/plain.txt\t2025-01-01\n+++ b/plain.txt\t2025-01-02\n@@ -1 +1 @@\n-x\n+y\n",
+        );
+
+        let files = parse_patch(&patch).unwrap();
+        assert_eq!(files.len(), 3);
+        assert!(files[0].is_creation() && files[0].old_oid.is_none());
+        assert_eq!(
+            files[0].apply(b""),
+            ApplyOutcome::Applied(b"hello\n".to_vec())
+        );
+        assert!(files[1].is_deletion());
+        assert_eq!(files[1].apply(b"bye\n"), ApplyOutcome::Applied(Vec::new()));
+        assert_eq!(files[2].path(), "plain.txt");
+        assert_eq!(
+            files[2].apply(b"x\n"),
+            ApplyOutcome::Applied(b"y\n".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_parse_reads_file_modes() {
+        let patch = b"diff --git a/run.sh b/run.sh\nold mode 100644\nnew mode 100755\n\
+                      diff --git a/tool b/tool\nnew file mode 100755\nindex 0000000..1111111\n\
+                      diff --git a/a.txt b/a.txt\nindex 1111111..2222222 100644\n";
+        let files = parse_patch(patch).unwrap();
+        assert_eq!(files[0].mode, Some(0o100755));
+        assert_eq!(files[1].mode, Some(0o100755));
+        assert_eq!(files[2].mode, Some(0o100644));
+    }
+
+    #[test]
+    fn test_parse_rejects_unsafe_paths() {
+        for patch in [
+            "diff --git a/../outside.txt b/../outside.txt\n",
+            "--- a/docs/../../outside.txt\n+++ b/docs/../../outside.txt\n@@ -1 +1 @@\n-x\n+y\n",
+            "diff --git a//etc/passwd b//etc/passwd\n",
+            "--- a/notes.txt\n+++ b//tmp/notes.txt\n@@ -1 +1 @@\n-x\n+y\n",
+            "diff --git a/.mediagit/HEAD b/.mediagit/HEAD\n",
+            "--- /dev/null\n+++ b/.MediaGit/refs/heads/main\n@@ -0,0 +1 @@\n+x\n",
+            "diff --git a/./notes.txt b/./notes.txt\n",
+        ] {
+            let err = parse_patch(patch.as_bytes()).unwrap_err();
+            assert!(
+                err.to_string().contains("Unsafe path"),
+                "{}: {}",
+                patch,
+                err
+            );
+        }
+
+        // A .mediagit directory below the root is an ordinary path
+        let files = parse_patch(b"diff --git a/docs/.mediagit b/docs/.mediagit\n").unwrap();
+        assert_eq!(files[0].path(), "docs/.mediagit");
+    }
+
+    #[test]
+    fn test_merge_text() {
+        let base = b"1\n2\n3\n4\n5\n";
+        let clean = merge_text(
+            base,
+            b"one\n2\n3\n4\n5\n",
+            b"1\n2\n3\n4\nfive\n",
+            "ours",
+            "theirs",
+        );
+        assert_eq!(clean.conflicts, 0);
+        assert_eq!(clean.content, b"one\n2\n3\n4\nfive\n");
+
+        let conflicted = merge_text(
+            base,
+            b"1\n2\nours\n4\n5\n",
+            b"1\n2\ntheirs\n4\n5\n",
+            "ours",
+            "theirs",
+        );
+        assert_eq!(conflicted.conflicts, 1);
+        assert_eq!(
+            String::from_utf8(conflicted.content).unwrap(),
+            "1\n2\n<<<<<<< ours\nours\n=======\ntheirs\n>>>>>>> theirs\n4\n5\n"
+        );
+    }
+
+    #[test]
+    fn test_union_text() {
+        // Both sides appended to a log: keep both entries, no markers
+        let merged = union_text(b"start\n", b"start\nours entry\n", b"start\ntheirs entry\n");
+        assert_eq!(merged, b"start\nours entry\ntheirs entry\n");
+
+        // Changes on one side only merge as usual
+        assert_eq!(union_text(b"a\nb\n", b"a\nb\n", b"a\nc\n"), b"a\nc\n");
+    }
+}
@@ -19,11 +19,24 @@
 //! - **Ref namespaces**: heads/, tags/, remotes/ for organization
 //! - **Atomic updates**: Safe ref updates with validation
 //! - **Symbolic references**: Support for HEAD pointing to current branch
+//! - **Packed refs**: Direct refs moved into one `packed-refs` file
+//!
+//! Each ref is a file under the database root. [`RefDatabase::pack_refs`]
+//! moves direct refs into `packed-refs`, one `<hex-oid> <name>` line each, as
+//! Git does. A loose file always takes precedence over the packed entry of
+//! the same name, so writing a packed ref simply creates its file again.
 
 use crate::Oid;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use tracing::debug;
+use tracing::{debug, warn};
+
+/// File under the database root holding packed refs
+const PACKED_REFS_FILE: &str = "packed-refs";
+
+/// First line of a `packed-refs` file
+const PACKED_REFS_HEADER: &str = "# pack-refs";
 
 /// Ref types in the reference database
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -387,13 +400,80 @@ impl RefDatabase {
         self.root.join(ref_name)
     }
 
+    /// Packed refs by name, empty when nothing has been packed
+    async fn read_packed(&self) -> anyhow::Result<BTreeMap<String, Oid>> {
+        use anyhow::Context;
+
+        let path = self.root.join(PACKED_REFS_FILE);
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e).context("Failed to read packed refs"),
+        };
+
+        let mut packed = BTreeMap::new();
+        for line in content.lines() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (hex, name) = line
+                .split_once(' ')
+                .ok_or_else(|| anyhow::anyhow!("Invalid line in packed refs: {}", line))?;
+            let oid = Oid::from_hex(hex)
+                .map_err(|e| anyhow::anyhow!("Invalid OID in packed refs: {}", e))?;
+            packed.insert(name.to_string(), oid);
+        }
+        Ok(packed)
+    }
+
+    /// Replace the packed refs with `packed`
+    async fn write_packed(&self, packed: &BTreeMap<String, Oid>) -> anyhow::Result<()> {
+        let mut content = format!("{}\n", PACKED_REFS_HEADER);
+        for (name, oid) in packed {
+            content.push_str(&format!("{} {}\n", oid.to_hex(), name));
+        }
+        write_atomic(&self.root.join(PACKED_REFS_FILE), content.as_bytes()).await
+    }
+
+    /// Read a ref from its own file, or `None` if it has none
+    async fn read_loose(&self, ref_name: &str) -> anyhow::Result<Option<Ref>> {
+        use anyhow::Context;
+        use tokio::fs;
+
+        let path = self.ref_path(ref_name);
+
+        let data = match fs::read(&path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read reference: {}", ref_name))
+            }
+        };
+        let mut r =
+            Ref::deserialize(&data).with_context(|| format!("Invalid reference: {}", ref_name))?;
+        r.name = ref_name.to_string();
+        Ok(Some(r))
+    }
+
+    /// Names of refs under `refs/{namespace}` that have their own file
+    async fn list_loose(&self, namespace: &str) -> anyhow::Result<Vec<String>> {
+        let dir_path = self.root.join(format!("refs/{}", namespace));
+        if tokio::fs::metadata(&dir_path).await.is_err() {
+            return Ok(Vec::new());
+        }
+
+        let mut refs = Vec::new();
+        let refs_root = self.root.join("refs");
+        collect_refs_recursive(&dir_path, &refs_root, &mut refs).await?;
+        Ok(refs)
+    }
+
     /// Write a reference to the database
     ///
     /// Performs validation and atomically stores the reference.
     /// For symbolic refs, also validates that the target is valid.
     pub async fn write(&self, r: &Ref) -> anyhow::Result<()> {
         use tokio::fs;
-        use tokio::io::AsyncWriteExt;
 
         r.validate()?;
 
@@ -411,14 +491,7 @@ impl RefDatabase {
             fs::create_dir_all(parent).await?;
         }
 
-        // Write atomically using temp file + rename
-        let temp_path = path.with_extension("tmp");
-        let mut file = fs::File::create(&temp_path).await?;
-        file.write_all(&data).await?;
-        file.sync_all().await?;
-        drop(file);
-
-        fs::rename(&temp_path, &path).await?;
+        write_atomic(&path, &data).await?;
 
         debug!(ref_name = %r.name, "Reference written successfully");
         Ok(())
@@ -445,10 +518,26 @@ impl RefDatabase {
                 r.name = ref_name.to_string(); // Set name from the file path
                 Ok(r)
             }
-            Err(_) => {
-                anyhow::bail!("Reference not found: {}", ref_name)
-            }
+            Err(_) => match self.read_packed().await?.get(ref_name) {
+                Some(oid) => Ok(Ref::new_direct(ref_name.to_string(), *oid)),
+                None => anyhow::bail!("Reference not found: {}", ref_name),
+            },
+        }
+    }
+
+    /// Read a reference, or `None` if it does not exist
+    ///
+    /// Unlike [`read`](Self::read), only a missing reference maps to `None`;
+    /// I/O errors and references that fail to parse are returned as errors.
+    pub async fn read_optional(&self, ref_name: &str) -> anyhow::Result<Option<Ref>> {
+        if let Some(r) = self.read_loose(ref_name).await? {
+            return Ok(Some(r));
         }
+        Ok(self
+            .read_packed()
+            .await?
+            .get(ref_name)
+            .map(|oid| Ref::new_direct(ref_name.to_string(), *oid)))
     }
 
     /// Check if a reference exists
@@ -456,7 +545,10 @@ impl RefDatabase {
         use tokio::fs;
 
         let path = self.ref_path(ref_name);
-        Ok(fs::metadata(&path).await.is_ok())
+        if fs::metadata(&path).await.is_ok() {
+            return Ok(true);
+        }
+        Ok(self.read_packed().await?.contains_key(ref_name))
     }
 
     /// Delete a reference
@@ -469,8 +561,20 @@ impl RefDatabase {
 
         debug!(ref_name = %ref_name, "Deleting reference");
 
+        // The packed entry goes first, so an interrupted delete never
+        // leaves the old packed value showing through
+        let mut packed = self.read_packed().await?;
+        let was_packed = packed.remove(ref_name).is_some();
+        if was_packed {
+            self.write_packed(&packed).await?;
+        }
+
         let path = self.ref_path(ref_name);
-        fs::remove_file(&path).await?;
+        match fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && was_packed => {}
+            Err(e) => return Err(e.into()),
+        }
 
         debug!(ref_name = %ref_name, "Reference deleted");
         Ok(())
@@ -486,20 +590,16 @@ impl RefDatabase {
     ///
     /// Vector of reference names in the namespace
     pub async fn list(&self, namespace: &str) -> anyhow::Result<Vec<String>> {
-        use tokio::fs;
-
-        let prefix = format!("refs/{}", namespace);
-        let dir_path = self.root.join(&prefix);
-
         debug!(namespace = %namespace, "Listing references");
 
-        if fs::metadata(&dir_path).await.is_err() {
-            return Ok(Vec::new());
-        }
-
-        let mut refs = Vec::new();
-        let refs_root = self.root.join("refs");
-        collect_refs_recursive(&dir_path, &refs_root, &mut refs).await?;
+        let mut refs = self.list_loose(namespace).await?;
+        let prefix = format!("refs/{}", namespace);
+        refs.extend(self.read_packed().await?.into_keys().filter(|name| {
+            name.strip_prefix(&prefix)
+                .is_some_and(|rest| prefix.ends_with('/') || rest.starts_with('/'))
+        }));
+        refs.sort();
+        refs.dedup();
 
         debug!(
             namespace = %namespace,
@@ -623,6 +723,72 @@ impl RefDatabase {
         );
         Ok(())
     }
+
+    /// Move every direct ref under `refs/` into the `packed-refs` file
+    ///
+    /// A ref's file is removed only after the packed file holding its value
+    /// has been written, and only if it still holds that value, so every ref
+    /// reads the same throughout. Symbolic refs, `HEAD` and refs that fail to
+    /// parse keep their files. Returns the number of refs packed.
+    ///
+    /// Callers should keep other writers of the database out while packing;
+    /// a ref written between the check and the removal of its file would be
+    /// lost.
+    pub async fn pack_refs(&self) -> anyhow::Result<usize> {
+        let mut packed = self.read_packed().await?;
+        let mut loose = Vec::new();
+        for name in self.list_loose("").await? {
+            match self.read_loose(&name).await {
+                Ok(Some(Ref {
+                    ref_type: RefType::Direct,
+                    oid: Some(oid),
+                    ..
+                })) => {
+                    packed.insert(name.clone(), oid);
+                    loose.push((name, oid));
+                }
+                Ok(_) => {}
+                Err(e) => warn!(ref_name = %name, "Not packing reference: {:#}", e),
+            }
+        }
+        if loose.is_empty() {
+            return Ok(0);
+        }
+
+        self.write_packed(&packed).await?;
+        for (name, oid) in &loose {
+            let unchanged = matches!(
+                self.read_loose(name).await,
+                Ok(Some(Ref { oid: Some(current), .. })) if current == *oid
+            );
+            if unchanged {
+                match tokio::fs::remove_file(self.ref_path(name)).await {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+
+        debug!(count = loose.len(), "Packed references");
+        Ok(loose.len())
+    }
+}
+
+/// Write `data` to `path` through a temporary file and a rename, so readers
+/// see either the old or the new content
+async fn write_atomic(path: &Path, data: &[u8]) -> anyhow::Result<()> {
+    use tokio::fs;
+    use tokio::io::AsyncWriteExt;
+
+    let temp_path = path.with_extension("tmp");
+    let mut file = fs::File::create(&temp_path).await?;
+    file.write_all(data).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    fs::rename(&temp_path, path).await?;
+    Ok(())
 }
 
 /// Normalize a ref name to its full path
@@ -859,6 +1025,29 @@ mod tests {
         assert!(refdb.read("refs/heads/main").await.is_err());
     }
 
+    #[tokio::test]
+    async fn test_refdb_read_optional() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let refdb = RefDatabase::new(temp_dir.path());
+
+        assert!(refdb
+            .read_optional("refs/heads/main")
+            .await
+            .unwrap()
+            .is_none());
+
+        let r = Ref::new_direct("refs/heads/main".to_string(), Oid::hash(b"commit"));
+        refdb.write(&r).await.unwrap();
+        assert_eq!(
+            refdb.read_optional("refs/heads/main").await.unwrap(),
+            Some(r)
+        );
+
+        // A ref that exists but cannot be parsed is an error, not "absent"
+        std::fs::write(temp_dir.path().join("refs/heads/broken"), b"\xff garbage").unwrap();
+        assert!(refdb.read_optional("refs/heads/broken").await.is_err());
+    }
+
     #[tokio::test]
     async fn test_refdb_exists() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -953,6 +1142,66 @@ mod tests {
         assert!(tags.iter().any(|t| t == "refs/tags/v2.0.0"));
     }
 
+    #[tokio::test]
+    async fn test_refdb_pack_refs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let refdb = RefDatabase::new(temp_dir.path());
+
+        let main = Oid::hash(b"main");
+        let tag = Oid::hash(b"tag");
+        refdb
+            .write(&Ref::new_direct("refs/heads/main".to_string(), main))
+            .await
+            .unwrap();
+        refdb
+            .write(&Ref::new_direct("refs/tags/v1.0.0".to_string(), tag))
+            .await
+            .unwrap();
+        refdb
+            .write(&Ref::new_symbolic(
+                "HEAD".to_string(),
+                "refs/heads/main".to_string(),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(refdb.pack_refs().await.unwrap(), 2);
+        assert!(!temp_dir.path().join("refs/heads/main").exists());
+        assert!(temp_dir.path().join("HEAD").exists());
+
+        // Packed refs read, list and resolve as before
+        assert_eq!(refdb.resolve("HEAD").await.unwrap(), main);
+        assert!(refdb.exists("refs/tags/v1.0.0").await.unwrap());
+        assert_eq!(
+            refdb.read_optional("refs/tags/v1.0.0").await.unwrap(),
+            Some(Ref::new_direct("refs/tags/v1.0.0".to_string(), tag))
+        );
+        assert_eq!(
+            refdb.list_branches().await.unwrap(),
+            vec!["refs/heads/main"]
+        );
+        assert_eq!(
+            refdb.list("").await.unwrap(),
+            vec!["refs/heads/main", "refs/tags/v1.0.0"]
+        );
+
+        // A new loose value takes precedence over the packed one
+        let next = Oid::hash(b"next");
+        refdb.update("refs/heads/main", next, true).await.unwrap();
+        assert_eq!(refdb.resolve("refs/heads/main").await.unwrap(), next);
+        assert_eq!(
+            refdb.list_branches().await.unwrap(),
+            vec!["refs/heads/main"]
+        );
+
+        // Deleting removes both the loose file and the packed entry
+        refdb.delete("refs/heads/main").await.unwrap();
+        assert!(!refdb.exists("refs/heads/main").await.unwrap());
+        refdb.delete("refs/tags/v1.0.0").await.unwrap();
+        assert!(refdb.list("").await.unwrap().is_empty());
+        assert!(refdb.delete("refs/tags/v1.0.0").await.is_err());
+    }
+
     #[tokio::test]
     async fn test_refdb_circular_reference_detection() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -0,0 +1,411 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Rolling up old history under a retention policy
+//!
+//! Append-only asset repositories rarely need their full history, yet every
+//! old version of every large file stays reachable forever. [`HistoryRollup`]
+//! cuts history off below a [`RollupPolicy`]: the recent commits are kept and
+//! everything older is rolled up, so ordinary garbage collection reclaims the
+//! blobs only the old commits used.
+//!
+//! # Which commits are kept
+//!
+//! Commits are walked from every branch, tag and detached HEAD. A ref's own
+//! commit is always kept. A parent of a kept commit is kept while it is
+//! fewer than `max_depth` parent links from some ref and, if a cutoff is set,
+//! was committed at or after it. Everything reachable only through commits
+//! that fall outside the policy is rolled up.
+//!
+//! # How history is grafted
+//!
+//! Kept commits that lose a parent become boundary commits: they keep their
+//! tree, author, committer and message, but only their kept parents. A
+//! boundary commit left with no parents is a new root, and behaves like the
+//! edge of a shallow clone: logs stop there, and nothing below it is needed
+//! to check out, diff or verify the kept history. Commits above the boundary
+//! get new OIDs, and refs are moved to the rewritten tips.
+
+use crate::thin::{load_commits, local_tips, topological_order};
+use crate::{Commit, FileMode, ObjectDatabase, Oid, RefDatabase, RefRewrite, Tree};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use tracing::debug;
+
+/// How much history a rollup keeps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RollupPolicy {
+    /// Keep commits fewer than this many parent links from a ref (`None`: any depth)
+    pub max_depth: Option<usize>,
+    /// Keep commits committed at or after this time (`None`: any age)
+    pub cutoff: Option<DateTime<Utc>>,
+}
+
+impl RollupPolicy {
+    /// Whether a commit `depth` links below a ref, committed at `time`, is kept
+    fn keeps(&self, depth: usize, time: DateTime<Utc>) -> bool {
+        self.max_depth.is_none_or(|max| depth < max)
+            && self.cutoff.is_none_or(|cutoff| time >= cutoff)
+    }
+}
+
+/// A blob that only rolled-up commits reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroppedBlob {
+    /// The blob
+    pub oid: Oid,
+    /// Logical size in bytes
+    pub size: u64,
+}
+
+/// Everything [`HistoryRollup::apply`] would change
+#[derive(Debug, Clone, Default)]
+pub struct RollupPlan {
+    /// Kept commits that lose at least one parent, oldest first
+    pub boundary: Vec<Oid>,
+    /// Blobs referenced only by rolled-up commits, largest first
+    pub dropped_blobs: Vec<DroppedBlob>,
+    /// Number of commits rolled up
+    pub rolled_up: usize,
+    /// Refs walked, with the commit each points at
+    tips: Vec<(String, Oid)>,
+    /// Kept commits, parents before children
+    kept: Vec<Oid>,
+}
+
+impl RollupPlan {
+    /// Whether applying the plan would change nothing
+    pub fn is_empty(&self) -> bool {
+        self.rolled_up == 0
+    }
+
+    /// Number of commits kept
+    pub fn kept_count(&self) -> usize {
+        self.kept.len()
+    }
+
+    /// Total size of the blobs that become unreachable
+    pub fn dropped_bytes(&self) -> u64 {
+        self.dropped_blobs.iter().map(|b| b.size).sum()
+    }
+}
+
+/// Result of applying a [`RollupPlan`]
+#[derive(Debug, Clone, Default)]
+pub struct RollupOutcome {
+    /// Old commit OID to rewritten commit OID, for commits that changed
+    pub rewritten: HashMap<Oid, Oid>,
+    /// Refs moved to rewritten commits
+    pub refs: Vec<RefRewrite>,
+}
+
+/// Plans and applies history rollups over a repository's local refs
+pub struct HistoryRollup<'a> {
+    odb: &'a ObjectDatabase,
+    refs: &'a RefDatabase,
+    policy: RollupPolicy,
+}
+
+impl<'a> HistoryRollup<'a> {
+    /// Create a rollup keeping the history `policy` allows
+    pub fn new(odb: &'a ObjectDatabase, refs: &'a RefDatabase, policy: RollupPolicy) -> Self {
+        Self { odb, refs, policy }
+    }
+
+    /// Work out which commits are kept and which blobs become unreachable
+    ///
+    /// Reads history but changes nothing.
+    pub async fn plan(&self) -> Result<RollupPlan> {
+        let tips = local_tips(self.refs).await?;
+        let commits = load_commits(self.odb, &tips).await?;
+
+        // Breadth-first, so each commit is first reached at its least depth
+        let mut kept: HashSet<Oid> = HashSet::new();
+        let mut queue: VecDeque<(Oid, usize)> = VecDeque::new();
+        for (_, tip) in &tips {
+            if kept.insert(*tip) {
+                queue.push_back((*tip, 0));
+            }
+        }
+        while let Some((oid, depth)) = queue.pop_front() {
+            for parent in &commits[&oid].parents {
+                if !kept.contains(parent)
+                    && self
+                        .policy
+                        .keeps(depth + 1, commits[parent].committer.timestamp)
+                {
+                    kept.insert(*parent);
+                    queue.push_back((*parent, depth + 1));
+                }
+            }
+        }
+
+        let order: Vec<Oid> = topological_order(&commits)
+            .into_iter()
+            .filter(|oid| kept.contains(oid))
+            .collect();
+        let boundary: Vec<Oid> = order
+            .iter()
+            .filter(|oid| commits[oid].parents.iter().any(|p| !kept.contains(p)))
+            .copied()
+            .collect();
+
+        let mut seen_trees = HashSet::new();
+        let mut kept_blobs = HashSet::new();
+        for oid in &order {
+            self.collect_blobs(commits[oid].tree, &mut seen_trees, &mut kept_blobs)
+                .await?;
+        }
+        let mut old_blobs = HashSet::new();
+        for (oid, commit) in &commits {
+            if !kept.contains(oid) {
+                self.collect_blobs(commit.tree, &mut seen_trees, &mut old_blobs)
+                    .await?;
+            }
+        }
+
+        let mut dropped_blobs = Vec::new();
+        for oid in old_blobs.difference(&kept_blobs) {
+            let size = match self.odb.get_chunk_manifest(oid).await? {
+                Some(manifest) => manifest.total_size,
+                None => self.odb.get_object_size(oid).await? as u64,
+            };
+            dropped_blobs.push(DroppedBlob { oid: *oid, size });
+        }
+        dropped_blobs.sort_by(|a, b| b.size.cmp(&a.size).then(a.oid.cmp(&b.oid)));
+
+        debug!(
+            kept = order.len(),
+            rolled_up = commits.len() - order.len(),
+            boundary = boundary.len(),
+            "Planned history rollup"
+        );
+        Ok(RollupPlan {
+            boundary,
+            dropped_blobs,
+            rolled_up: commits.len() - order.len(),
+            tips,
+            kept: order,
+        })
+    }
+
+    /// Graft the kept history onto its boundary and move refs to it
+    ///
+    /// Rolled-up commits and the dropped blobs are left in the object
+    /// database for garbage collection to prune.
+    pub async fn apply(&self, plan: &RollupPlan) -> Result<RollupOutcome> {
+        let mut outcome = RollupOutcome::default();
+        if plan.is_empty() {
+            return Ok(outcome);
+        }
+        let kept: HashSet<&Oid> = plan.kept.iter().collect();
+
+        for oid in &plan.kept {
+            let commit = Commit::read(self.odb, oid).await?;
+            let parents: Vec<Oid> = commit
+                .parents
+                .iter()
+                .filter(|p| kept.contains(p))
+                .map(|p| *outcome.rewritten.get(p).unwrap_or(p))
+                .collect();
+            if parents == commit.parents {
+                continue;
+            }
+
+            let grafted = Commit { parents, ..commit };
+            let new_oid = grafted.write(self.odb).await?;
+            outcome.rewritten.insert(*oid, new_oid);
+        }
+
+        for (name, old_oid) in &plan.tips {
+            if let Some(new_oid) = outcome.rewritten.get(old_oid) {
+                self.refs
+                    .update(name, *new_oid, true)
+                    .await
+                    .with_context(|| format!("Failed to move {}", name))?;
+                outcome.refs.push(RefRewrite {
+                    name: name.clone(),
+                    old_oid: *old_oid,
+                    new_oid: *new_oid,
+                });
+            }
+        }
+
+        debug!(
+            commits = outcome.rewritten.len(),
+            refs = outcome.refs.len(),
+            "Applied history rollup"
+        );
+        Ok(outcome)
+    }
+
+    /// Add the blobs under `tree_oid` to `blobs`, skipping trees already seen
+    fn collect_blobs<'b>(
+        &'b self,
+        tree_oid: Oid,
+        seen: &'b mut HashSet<Oid>,
+        blobs: &'b mut HashSet<Oid>,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + 'b>> {
+        Box::pin(async move {
+            if !seen.insert(tree_oid) {
+                return Ok(());
+            }
+            let tree = Tree::read(self.odb, &tree_oid).await?;
+            for entry in tree.iter() {
+                if entry.mode == FileMode::Directory {
+                    self.collect_blobs(entry.oid, seen, blobs).await?;
+                } else {
+                    blobs.insert(entry.oid);
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ObjectType, Signature, TreeEntry};
+    use mediagit_storage::mock::MockBackend;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    /// Commit one version of `shot.exr` per entry next to an unchanging
+    /// note, returning the commits and the shot versions, oldest first
+    async fn build_history(
+        odb: &ObjectDatabase,
+        refs: &RefDatabase,
+        versions: usize,
+    ) -> (Vec<Oid>, Vec<Oid>) {
+        let note = odb.write(ObjectType::Blob, b"notes").await.unwrap();
+        let mut commits = Vec::new();
+        let mut blobs = Vec::new();
+        for n in 1..=versions {
+            let blob = odb
+                .write(ObjectType::Blob, format!("shot v{}", n).as_bytes())
+                .await
+                .unwrap();
+            blobs.push(blob);
+
+            let mut root = Tree::new();
+            root.add_entry(TreeEntry::new("notes.txt".into(), FileMode::Regular, note));
+            root.add_entry(TreeEntry::new("shot.exr".into(), FileMode::Regular, blob));
+            let root = root.write(odb).await.unwrap();
+
+            let time = DateTime::from_timestamp(1_700_000_000 + n as i64 * 86_400, 0).unwrap();
+            let sig = Signature::new("Artist".into(), "artist@example.com".into(), time);
+            let mut commit = Commit::new(root, sig.clone(), sig, format!("Shot {}", n));
+            commit.parents.extend(commits.last().copied());
+            commits.push(commit.write(odb).await.unwrap());
+        }
+        refs.update("refs/heads/main", *commits.last().unwrap(), true)
+            .await
+            .unwrap();
+        refs.update_symbolic("HEAD", "refs/heads/main")
+            .await
+            .unwrap();
+        (commits, blobs)
+    }
+
+    /// Commits from HEAD down to the root, newest first
+    async fn history(odb: &ObjectDatabase, refs: &RefDatabase) -> Vec<Commit> {
+        let mut commits = Vec::new();
+        let mut next = Some(refs.resolve("HEAD").await.unwrap());
+        while let Some(oid) = next {
+            let commit = Commit::read(odb, &oid).await.unwrap();
+            next = commit.parents.first().copied();
+            commits.push(commit);
+        }
+        commits
+    }
+
+    #[tokio::test]
+    async fn test_rollup_to_depth_keeps_recent_content() {
+        let dir = TempDir::new().unwrap();
+        let odb = ObjectDatabase::new(Arc::new(MockBackend::new()), 100);
+        let refs = RefDatabase::new(dir.path());
+        let (commits, blobs) = build_history(&odb, &refs, 6).await;
+        let before = history(&odb, &refs).await;
+
+        let policy = RollupPolicy {
+            max_depth: Some(3),
+            cutoff: None,
+        };
+        let rollup = HistoryRollup::new(&odb, &refs, policy);
+        let plan = rollup.plan().await.unwrap();
+
+        // Shots 4-6 are kept; 1-3 go, and with them versions 1-3 of the shot
+        assert_eq!(plan.kept_count(), 3);
+        assert_eq!(plan.rolled_up, 3);
+        assert_eq!(plan.boundary, vec![commits[3]]);
+        let mut dropped: Vec<Oid> = plan.dropped_blobs.iter().map(|b| b.oid).collect();
+        dropped.sort();
+        let mut expected = blobs[..3].to_vec();
+        expected.sort();
+        assert_eq!(dropped, expected);
+        assert_eq!(plan.dropped_bytes(), 3 * "shot v1".len() as u64);
+
+        let outcome = rollup.apply(&plan).await.unwrap();
+        assert_eq!(outcome.rewritten.len(), 3);
+        assert_eq!(outcome.refs.len(), 1);
+        assert_eq!(outcome.refs[0].old_oid, commits[5]);
+
+        // The recent commits are intact, down to a new root
+        let after = history(&odb, &refs).await;
+        assert_eq!(after.len(), 3);
+        for (kept, original) in after.iter().zip(&before) {
+            assert_eq!(kept.tree, original.tree);
+            assert_eq!(kept.message, original.message);
+            assert_eq!(kept.author, original.author);
+        }
+        assert!(after[2].parents.is_empty());
+
+        // Rolling up again changes nothing
+        let replan = rollup.plan().await.unwrap();
+        assert!(replan.is_empty());
+        assert!(replan.dropped_blobs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rollup_by_age_keeps_ref_commits() {
+        let dir = TempDir::new().unwrap();
+        let odb = ObjectDatabase::new(Arc::new(MockBackend::new()), 100);
+        let refs = RefDatabase::new(dir.path());
+        let (commits, _) = build_history(&odb, &refs, 4).await;
+        refs.update("refs/tags/v1", commits[0], true).await.unwrap();
+
+        // Only shot 4 is recent enough, but the tag still holds shot 1
+        let cutoff = Commit::read(&odb, &commits[3])
+            .await
+            .unwrap()
+            .committer
+            .timestamp;
+        let policy = RollupPolicy {
+            max_depth: None,
+            cutoff: Some(cutoff),
+        };
+        let rollup = HistoryRollup::new(&odb, &refs, policy);
+        let plan = rollup.plan().await.unwrap();
+        assert_eq!(plan.kept_count(), 2);
+        assert_eq!(plan.rolled_up, 2);
+        assert_eq!(plan.boundary, vec![commits[3]]);
+
+        rollup.apply(&plan).await.unwrap();
+        assert_eq!(history(&odb, &refs).await.len(), 1);
+        assert_eq!(refs.resolve("refs/tags/v1").await.unwrap(), commits[0]);
+    }
+}
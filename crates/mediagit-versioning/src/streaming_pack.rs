@@ -16,10 +16,11 @@
 //! This module provides streaming pack reader/writer that process objects
 //! incrementally without loading entire packs into memory.
 
-use crate::pack::PackHeader;
+use crate::pack::{PackHeader, PackIndex};
 use crate::streaming_index::StreamingPackIndex;
 use crate::{ObjectType, Oid};
 use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::io;
 use std::path::Path;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
@@ -31,13 +32,25 @@ const DELTA_MAGIC: &[u8; 5] = b"DELTA";
 /// Prevents OOM from corrupted or malicious pack data advertising huge sizes.
 const MAX_PACK_OBJECT_SIZE: usize = 2 * 1024 * 1024 * 1024;
 
+/// Size of the SHA-256 checksum that ends every pack
+const CHECKSUM_SIZE: usize = 32;
+
+/// Size of one serialized index entry: OID + offset + size
+const INDEX_ENTRY_SIZE: usize = 32 + 8 + 4;
+
 /// Streaming pack reader that processes objects incrementally
+///
+/// Every byte read is hashed, and the OID and offset of each object are
+/// remembered, so that [`verify_checksum`](Self::verify_checksum) can check the
+/// pack's trailing index and checksum once all objects have been read.
 pub struct StreamingPackReader<R: AsyncRead + Unpin> {
     reader: R,
     header: Option<PackHeader>,
     objects_processed: u32,
     expected_count: u32,
     hasher: Sha256,
+    offset: u64,
+    objects_seen: HashSet<(Oid, u64)>,
 }
 
 impl<R: AsyncRead + Unpin> StreamingPackReader<R> {
@@ -62,6 +75,8 @@ impl<R: AsyncRead + Unpin> StreamingPackReader<R> {
             objects_processed: 0,
             expected_count: header.object_count,
             hasher,
+            offset: header_buf.len() as u64,
+            objects_seen: HashSet::new(),
         })
     }
 
@@ -90,6 +105,8 @@ impl<R: AsyncRead + Unpin> StreamingPackReader<R> {
     }
 
     async fn read_object_internal(&mut self) -> io::Result<(Oid, ObjectType, Vec<u8>)> {
+        let entry_offset = self.offset;
+
         // Read object header: type (1 byte) + size (4 bytes)
         let mut header_buf = [0u8; 5];
         self.reader.read_exact(&mut header_buf).await?;
@@ -121,6 +138,7 @@ impl<R: AsyncRead + Unpin> StreamingPackReader<R> {
         obj_data.resize(size, 0);
         self.reader.read_exact(&mut obj_data).await?;
         self.hasher.update(&obj_data);
+        self.offset += (header_buf.len() + size) as u64;
 
         // Check for delta encoding
         if obj_data.len() >= 5 && &obj_data[0..5] == DELTA_MAGIC {
@@ -162,19 +180,87 @@ impl<R: AsyncRead + Unpin> StreamingPackReader<R> {
 
         // Calculate OID
         let oid = Oid::hash(&obj_data);
+        self.objects_seen.insert((oid, entry_offset));
 
         Ok((oid, obj_type, obj_data))
     }
 
-    /// Verify pack checksum after reading all objects
-    pub fn verify_checksum(&mut self) -> io::Result<()> {
-        // In a complete implementation, we would:
-        // 1. Read the final checksum bytes from stream
-        // 2. Compare with accumulated hasher result
-        // For now, return Ok (will be implemented in Epic 1.1.5)
+    /// Verify the pack trailer after reading all objects
+    ///
+    /// Reads the index, index offset and checksum that follow the objects and
+    /// checks that:
+    /// - the checksum matches the SHA-256 of everything before it,
+    /// - the index starts where the objects end, and
+    /// - the index lists exactly the objects read, each under the OID computed
+    ///   from its content and at the offset it was read from.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidData` if any check fails, and `InvalidInput` if called
+    /// before every object has been read.
+    pub async fn verify_checksum(&mut self) -> io::Result<()> {
+        if self.objects_processed < self.expected_count {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Pack verification needs all objects read ({} of {})",
+                    self.objects_processed, self.expected_count
+                ),
+            ));
+        }
+
+        // The index is sized by the header count; reading one byte past the
+        // longest valid trailer is enough to detect trailing garbage.
+        let max_trailer = 4 + self.expected_count as usize * INDEX_ENTRY_SIZE + 4 + CHECKSUM_SIZE;
+        let mut trailer = Vec::new();
+        (&mut self.reader)
+            .take(max_trailer as u64 + 1)
+            .read_to_end(&mut trailer)
+            .await?;
+        if trailer.len() > max_trailer {
+            return Err(invalid_pack("unexpected data after pack index"));
+        }
+        if trailer.len() < 4 + 4 + CHECKSUM_SIZE {
+            return Err(invalid_pack("pack is truncated"));
+        }
+
+        let checksum_offset = trailer.len() - CHECKSUM_SIZE;
+        let mut hasher = self.hasher.clone();
+        hasher.update(&trailer[..checksum_offset]);
+        if hasher.finalize()[..] != trailer[checksum_offset..] {
+            return Err(invalid_pack("pack checksum verification failed"));
+        }
+
+        let index_offset_pos = checksum_offset - 4;
+        let mut index_offset = [0u8; 4];
+        index_offset.copy_from_slice(&trailer[index_offset_pos..checksum_offset]);
+        if u64::from(u32::from_le_bytes(index_offset)) != self.offset {
+            return Err(invalid_pack("pack index offset does not match object data"));
+        }
+
+        let index = PackIndex::from_bytes(&trailer[..index_offset_pos])?;
+        for (oid, (offset, _)) in index.iter() {
+            if !self.objects_seen.contains(&(*oid, *offset)) {
+                return Err(invalid_pack(&format!(
+                    "pack index entry {} does not match the object at offset {}",
+                    oid, offset
+                )));
+            }
+        }
+        if let Some((oid, _)) = self
+            .objects_seen
+            .iter()
+            .find(|(oid, _)| index.lookup(oid).is_none())
+        {
+            return Err(invalid_pack(&format!(
+                "object {} is missing from the pack index",
+                oid
+            )));
+        }
+
         debug!(
             objects_read = self.objects_processed,
-            "Pack checksum verification (placeholder)"
+            "Pack checksum and index verified"
         );
         Ok(())
     }
@@ -190,6 +276,10 @@ impl<R: AsyncRead + Unpin> StreamingPackReader<R> {
     }
 }
 
+fn invalid_pack(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
 /// Streaming pack writer that generates pack data incrementally
 ///
 /// Uses `StreamingPackIndex` for O(1) memory regardless of object count.
@@ -414,5 +504,61 @@ mod tests {
         assert_eq!(read_type, ObjectType::Blob);
         assert_eq!(read_data, test_data);
         assert!(reader.next_object().await.is_none());
+        reader.verify_checksum().await.unwrap();
+    }
+
+    fn test_pack() -> Vec<u8> {
+        let mut writer = crate::PackWriter::new();
+        for data in [&b"first object"[..], b"second object", b"first object"] {
+            writer.add_object(Oid::hash(data), ObjectType::Blob, data);
+        }
+        writer.finalize()
+    }
+
+    async fn read_and_verify(pack: &[u8]) -> io::Result<u32> {
+        let mut reader = StreamingPackReader::new(pack).await?;
+        while let Some(object) = reader.next_object().await {
+            object?;
+        }
+        reader.verify_checksum().await?;
+        Ok(reader.objects_processed())
+    }
+
+    #[tokio::test]
+    async fn test_verify_checksum_accepts_valid_pack() {
+        assert_eq!(read_and_verify(&test_pack()).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_verify_checksum_rejects_flipped_bit() {
+        let pack = test_pack();
+        // One bit flipped anywhere in the pack must be caught
+        for pos in [14, 20, pack.len() - 40, pack.len() - 1] {
+            let mut corrupt = pack.clone();
+            corrupt[pos] ^= 0x01;
+            assert!(read_and_verify(&corrupt).await.is_err(), "byte {}", pos);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_checksum_rejects_mismatched_index() {
+        // A pack whose index claims a different OID, re-checksummed so only
+        // the index check can catch it
+        let mut writer = crate::PackWriter::new();
+        writer.add_object(Oid::hash(b"claimed"), ObjectType::Blob, b"actual");
+        let mut pack = writer.finalize();
+        pack.truncate(pack.len() - CHECKSUM_SIZE);
+        let checksum = Sha256::digest(&pack);
+        pack.extend_from_slice(&checksum);
+
+        let err = read_and_verify(&pack).await.unwrap_err();
+        assert!(err.to_string().contains("pack index entry"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_verify_checksum_rejects_truncated_pack() {
+        let pack = test_pack();
+        let err = read_and_verify(&pack[..pack.len() - 10]).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
     }
 }
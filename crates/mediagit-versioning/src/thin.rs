@@ -0,0 +1,602 @@
+// MediaGit - Git for Media Files
+// Copyright (C) 2025 MediaGit Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+
+//! Thinning of intermediate media versions from history
+//!
+//! Iterative work on large files (render checkpoints, simulation caches)
+//! leaves every intermediate version reachable forever. [`MediaThinner`]
+//! rewrites history so that, for paths matching a [`ThinRule`], only the
+//! designated versions stay reachable; ordinary garbage collection then
+//! reclaims the rest.
+//!
+//! # Which versions are kept
+//!
+//! The distinct blobs a path has held are numbered from 1 in the order they
+//! first appear in history (parents before children, ties broken by commit
+//! time). Version `i` of `n` is kept when:
+//!
+//! 1. `i` is a multiple of `keep_every`,
+//! 2. `i` is one of the last `keep_last` versions, or
+//! 3. a branch, tag or detached HEAD still points at a tree holding it.
+//!
+//! When several rules match a path the last one wins, as in
+//! `.mediagitattributes`. Numbering starts afresh on every run, so applying
+//! the same rules again thins the surviving versions further.
+//!
+//! # How history is rewritten
+//!
+//! Every commit is kept with its author, committer and message. In each
+//! tree, a dropped version is replaced by the nearest earlier kept version
+//! of the same path, or the nearest later one if none came before. A path
+//! with no kept version at all is removed from the tree. Commits whose trees
+//! or parents changed get new OIDs, and refs are moved to the rewritten
+//! tips. Ref tips keep their exact content, so working trees are unaffected.
+
+use crate::attributes::AttributePattern;
+use crate::{Commit, FileMode, ObjectDatabase, Oid, RefDatabase, RefType, Tree, TreeEntry};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use tracing::debug;
+
+/// Which versions of matching paths to keep
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThinRule {
+    /// `.mediagitattributes`-style glob selecting the paths the rule covers
+    pub pattern: String,
+    /// Keep every Nth version (0 keeps none on this basis)
+    pub keep_every: usize,
+    /// Keep the most recent N versions
+    pub keep_last: usize,
+}
+
+impl ThinRule {
+    /// Whether version `number` (1-based) of `count` is kept by this rule
+    fn keeps(&self, number: usize, count: usize) -> bool {
+        (self.keep_every > 0 && number.is_multiple_of(self.keep_every))
+            || number + self.keep_last > count
+    }
+}
+
+/// One historical version of a path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThinVersion {
+    /// Blob holding this version
+    pub oid: Oid,
+    /// Whether the version survives thinning
+    pub keep: bool,
+}
+
+/// The versions of one path and which of them are kept
+#[derive(Debug, Clone)]
+pub struct PathPlan {
+    /// Repository-relative path with `/` separators
+    pub path: String,
+    /// Distinct versions in order of first appearance
+    pub versions: Vec<ThinVersion>,
+}
+
+impl PathPlan {
+    /// Number of versions that survive
+    pub fn kept_count(&self) -> usize {
+        self.versions.iter().filter(|v| v.keep).count()
+    }
+
+    /// Number of versions that are dropped
+    pub fn dropped_count(&self) -> usize {
+        self.versions.len() - self.kept_count()
+    }
+
+    /// What a dropped version is replaced with, or `None` to remove the entry
+    ///
+    /// Versions that are kept, or unknown to the plan, map to themselves.
+    pub fn replacement(&self, oid: &Oid) -> Option<Oid> {
+        let Some(index) = self.versions.iter().position(|v| &v.oid == oid) else {
+            return Some(*oid);
+        };
+        if self.versions[index].keep {
+            return Some(*oid);
+        }
+        self.versions[..index]
+            .iter()
+            .rev()
+            .chain(&self.versions[index + 1..])
+            .find(|v| v.keep)
+            .map(|v| v.oid)
+    }
+}
+
+/// Everything [`MediaThinner::apply`] would change
+#[derive(Debug, Clone, Default)]
+pub struct ThinPlan {
+    /// Paths matched by a rule, in path order
+    pub paths: Vec<PathPlan>,
+    /// Refs walked, with the commit each points at
+    tips: Vec<(String, Oid)>,
+    /// Every reachable commit, parents before children
+    commits: Vec<Oid>,
+}
+
+impl ThinPlan {
+    /// Whether applying the plan would drop nothing
+    pub fn is_empty(&self) -> bool {
+        self.dropped_count() == 0
+    }
+
+    /// Total number of versions dropped across all paths
+    pub fn dropped_count(&self) -> usize {
+        self.paths.iter().map(PathPlan::dropped_count).sum()
+    }
+
+    /// Blobs no longer referenced at their path once the plan is applied
+    pub fn dropped_oids(&self) -> Vec<Oid> {
+        self.paths
+            .iter()
+            .flat_map(|p| p.versions.iter().filter(|v| !v.keep).map(|v| v.oid))
+            .collect()
+    }
+
+    /// Number of reachable commits the plan covers
+    pub fn commit_count(&self) -> usize {
+        self.commits.len()
+    }
+}
+
+/// A ref moved by [`MediaThinner::apply`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefRewrite {
+    /// Full ref name (`refs/heads/main`, `refs/tags/v1`, or `HEAD` when detached)
+    pub name: String,
+    /// Commit the ref pointed at before thinning
+    pub old_oid: Oid,
+    /// Rewritten commit the ref points at now
+    pub new_oid: Oid,
+}
+
+/// Result of applying a [`ThinPlan`]
+#[derive(Debug, Clone, Default)]
+pub struct ThinOutcome {
+    /// Old commit OID to rewritten commit OID, for commits that changed
+    pub rewritten: HashMap<Oid, Oid>,
+    /// Refs moved to rewritten commits
+    pub refs: Vec<RefRewrite>,
+}
+
+/// Files under a tree, relative to its prefix, paired with their blobs
+type TreeFiles = Vec<(String, Oid)>;
+
+/// Plans and applies media thinning over a repository's local refs
+pub struct MediaThinner<'a> {
+    odb: &'a ObjectDatabase,
+    refs: &'a RefDatabase,
+    /// Rules with their compiled patterns
+    rules: Vec<(AttributePattern, ThinRule)>,
+}
+
+impl<'a> MediaThinner<'a> {
+    /// Create a thinner applying `rules` to history reachable from `refs`
+    pub fn new(odb: &'a ObjectDatabase, refs: &'a RefDatabase, rules: Vec<ThinRule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .map(|rule| (AttributePattern::new(&rule.pattern), rule))
+            .collect();
+        Self { odb, refs, rules }
+    }
+
+    /// The last rule matching `path`
+    fn rule_for(&self, path: &str) -> Option<&ThinRule> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches(path))
+            .map(|(_, rule)| rule)
+    }
+
+    /// Work out which versions of each matching path are kept
+    ///
+    /// Reads history but changes nothing.
+    pub async fn plan(&self) -> Result<ThinPlan> {
+        let tips = local_tips(self.refs).await?;
+        let commits = load_commits(self.odb, &tips).await?;
+        let order = topological_order(&commits);
+
+        let mut files_memo = HashMap::new();
+        let mut first_seen: HashMap<String, Vec<Oid>> = HashMap::new();
+        for oid in &order {
+            let files = self
+                .matching_files(commits[oid].tree, String::new(), &mut files_memo)
+                .await?;
+            for (path, blob) in files {
+                let versions = first_seen.entry(path).or_default();
+                if !versions.contains(&blob) {
+                    versions.push(blob);
+                }
+            }
+        }
+
+        // Content a ref still points at is never dropped
+        let mut at_tips = HashSet::new();
+        for (_, tip) in &tips {
+            let files = self
+                .matching_files(commits[tip].tree, String::new(), &mut files_memo)
+                .await?;
+            at_tips.extend(files);
+        }
+
+        let mut paths: Vec<PathPlan> = first_seen
+            .into_iter()
+            .map(|(path, blobs)| {
+                let rule = self.rule_for(&path).expect("path was matched by a rule");
+                let count = blobs.len();
+                let versions = blobs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, oid)| ThinVersion {
+                        keep: rule.keeps(index + 1, count)
+                            || at_tips.contains(&(path.clone(), oid)),
+                        oid,
+                    })
+                    .collect();
+                PathPlan { path, versions }
+            })
+            .collect();
+        paths.sort_by(|a, b| a.path.cmp(&b.path));
+
+        debug!(
+            paths = paths.len(),
+            commits = order.len(),
+            "Planned media thinning"
+        );
+        Ok(ThinPlan {
+            paths,
+            tips,
+            commits: order,
+        })
+    }
+
+    /// Rewrite history according to `plan` and move refs to the new commits
+    ///
+    /// Old commits and the dropped blobs are left in the object database
+    /// for garbage collection to prune.
+    pub async fn apply(&self, plan: &ThinPlan) -> Result<ThinOutcome> {
+        let paths: HashMap<&str, &PathPlan> = plan
+            .paths
+            .iter()
+            .filter(|p| p.dropped_count() > 0)
+            .map(|p| (p.path.as_str(), p))
+            .collect();
+        let mut outcome = ThinOutcome::default();
+        if paths.is_empty() {
+            return Ok(outcome);
+        }
+
+        let mut tree_memo = HashMap::new();
+        for oid in &plan.commits {
+            let commit = Commit::read(self.odb, oid).await?;
+            let tree = match self
+                .rewrite_tree(commit.tree, String::new(), &paths, &mut tree_memo)
+                .await?
+            {
+                Some(tree) => tree,
+                None => Tree::new().write(self.odb).await?,
+            };
+            let parents: Vec<Oid> = commit
+                .parents
+                .iter()
+                .map(|p| *outcome.rewritten.get(p).unwrap_or(p))
+                .collect();
+            if tree == commit.tree && parents == commit.parents {
+                continue;
+            }
+
+            let rewritten = Commit {
+                tree,
+                parents,
+                ..commit
+            };
+            let new_oid = rewritten.write(self.odb).await?;
+            outcome.rewritten.insert(*oid, new_oid);
+        }
+
+        for (name, old_oid) in &plan.tips {
+            if let Some(new_oid) = outcome.rewritten.get(old_oid) {
+                self.refs
+                    .update(name, *new_oid, true)
+                    .await
+                    .with_context(|| format!("Failed to move {}", name))?;
+                outcome.refs.push(RefRewrite {
+                    name: name.clone(),
+                    old_oid: *old_oid,
+                    new_oid: *new_oid,
+                });
+            }
+        }
+
+        debug!(
+            commits = outcome.rewritten.len(),
+            refs = outcome.refs.len(),
+            "Applied media thinning"
+        );
+        Ok(outcome)
+    }
+
+    /// Files under `tree_oid` matched by some rule
+    fn matching_files<'b>(
+        &'b self,
+        tree_oid: Oid,
+        prefix: String,
+        memo: &'b mut HashMap<(String, Oid), TreeFiles>,
+    ) -> Pin<Box<dyn Future<Output = Result<TreeFiles>> + 'b>> {
+        Box::pin(async move {
+            if let Some(files) = memo.get(&(prefix.clone(), tree_oid)) {
+                return Ok(files.clone());
+            }
+            self.odb
+                .tree_limits()
+                .check_depth(prefix.matches('/').count())
+                .with_context(|| format!("Cannot walk {}", prefix))?;
+
+            let tree = Tree::read(self.odb, &tree_oid).await?;
+            let mut files = Vec::new();
+            for entry in tree.iter() {
+                let path = format!("{}{}", prefix, entry.name);
+                if entry.mode == FileMode::Directory {
+                    let nested = self
+                        .matching_files(entry.oid, format!("{}/", path), memo)
+                        .await?;
+                    files.extend(nested);
+                } else if self.rule_for(&path).is_some() {
+                    files.push((path, entry.oid));
+                }
+            }
+            memo.insert((prefix, tree_oid), files.clone());
+            Ok(files)
+        })
+    }
+
+    /// Rewrite `tree_oid` with dropped versions replaced
+    ///
+    /// Returns `None` if every entry was removed.
+    fn rewrite_tree<'b>(
+        &'b self,
+        tree_oid: Oid,
+        prefix: String,
+        paths: &'b HashMap<&str, &PathPlan>,
+        memo: &'b mut HashMap<(String, Oid), Option<Oid>>,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<Oid>>> + 'b>> {
+        Box::pin(async move {
+            if let Some(rewritten) = memo.get(&(prefix.clone(), tree_oid)) {
+                return Ok(*rewritten);
+            }
+
+            let tree = Tree::read(self.odb, &tree_oid).await?;
+            let mut rewritten = Tree::new();
+            let mut changed = false;
+            for entry in tree.iter() {
+                let path = format!("{}{}", prefix, entry.name);
+                let oid = if entry.mode == FileMode::Directory {
+                    self.rewrite_tree(entry.oid, format!("{}/", path), paths, memo)
+                        .await?
+                } else {
+                    match paths.get(path.as_str()) {
+                        Some(plan) => plan.replacement(&entry.oid),
+                        None => Some(entry.oid),
+                    }
+                };
+                match oid {
+                    Some(oid) => {
+                        changed |= oid != entry.oid;
+                        rewritten.add_entry(TreeEntry::new(entry.name.clone(), entry.mode, oid));
+                    }
+                    None => changed = true,
+                }
+            }
+
+            let result = if !changed {
+                Some(tree_oid)
+            } else if rewritten.is_empty() {
+                None
+            } else {
+                Some(rewritten.write(self.odb).await?)
+            };
+            memo.insert((prefix, tree_oid), result);
+            Ok(result)
+        })
+    }
+}
+
+/// Local branches, tags and a detached HEAD, with their commits
+pub(crate) async fn local_tips(refs: &RefDatabase) -> Result<Vec<(String, Oid)>> {
+    let mut tips = Vec::new();
+    for namespace in ["heads", "tags"] {
+        for name in refs.list(namespace).await? {
+            let oid = refs.resolve(&name).await?;
+            tips.push((name, oid));
+        }
+    }
+    if let Ok(head) = refs.read("HEAD").await {
+        if head.ref_type == RefType::Direct {
+            if let Some(oid) = head.oid {
+                tips.push(("HEAD".to_string(), oid));
+            }
+        }
+    }
+    Ok(tips)
+}
+
+/// Every commit reachable from `tips`
+pub(crate) async fn load_commits(
+    odb: &ObjectDatabase,
+    tips: &[(String, Oid)],
+) -> Result<HashMap<Oid, Commit>> {
+    let mut commits = HashMap::new();
+    let mut pending: Vec<Oid> = tips.iter().map(|(_, oid)| *oid).collect();
+    while let Some(oid) = pending.pop() {
+        if commits.contains_key(&oid) {
+            continue;
+        }
+        let commit = Commit::read(odb, &oid)
+            .await
+            .with_context(|| format!("Failed to read commit {}", oid))?;
+        pending.extend(commit.parents.iter().copied());
+        commits.insert(oid, commit);
+    }
+    Ok(commits)
+}
+
+/// Order commits so parents come before children, oldest first among peers
+pub(crate) fn topological_order(commits: &HashMap<Oid, Commit>) -> Vec<Oid> {
+    let mut waiting: HashMap<Oid, usize> = HashMap::new();
+    let mut children: HashMap<Oid, Vec<Oid>> = HashMap::new();
+    for (oid, commit) in commits {
+        waiting.insert(*oid, commit.parents.len());
+        for parent in &commit.parents {
+            children.entry(*parent).or_default().push(*oid);
+        }
+    }
+
+    let time = |oid: &Oid| -> DateTime<Utc> { commits[oid].committer.timestamp };
+    let mut ready: BinaryHeap<Reverse<(DateTime<Utc>, Oid)>> = waiting
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(oid, _)| Reverse((time(oid), *oid)))
+        .collect();
+
+    let mut order = Vec::with_capacity(commits.len());
+    while let Some(Reverse((_, oid))) = ready.pop() {
+        order.push(oid);
+        for child in children.get(&oid).into_iter().flatten() {
+            let count = waiting.get_mut(child).expect("child is a loaded commit");
+            *count -= 1;
+            if *count == 0 {
+                ready.push(Reverse((time(child), *child)));
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ObjectType, Signature};
+    use mediagit_storage::mock::MockBackend;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    /// Commit one version of `shot.exr` per entry, plus an untouched note
+    async fn build_history(odb: &ObjectDatabase, refs: &RefDatabase, frames: usize) -> Vec<Oid> {
+        let note = odb.write(ObjectType::Blob, b"notes").await.unwrap();
+        let mut blobs = Vec::new();
+        let mut parent = None;
+        for n in 1..=frames {
+            let blob = odb
+                .write(ObjectType::Blob, format!("frame v{}", n).as_bytes())
+                .await
+                .unwrap();
+            blobs.push(blob);
+
+            let mut renders = Tree::new();
+            renders.add_entry(TreeEntry::new("shot.exr".into(), FileMode::Regular, blob));
+            let renders = renders.write(odb).await.unwrap();
+            let mut root = Tree::new();
+            root.add_entry(TreeEntry::new("notes.txt".into(), FileMode::Regular, note));
+            root.add_entry(TreeEntry::new(
+                "renders".into(),
+                FileMode::Directory,
+                renders,
+            ));
+            let root = root.write(odb).await.unwrap();
+
+            let time = DateTime::from_timestamp(1_700_000_000 + n as i64, 0).unwrap();
+            let sig = Signature::new("Artist".into(), "artist@example.com".into(), time);
+            let mut commit = Commit::new(root, sig.clone(), sig, format!("Render {}", n));
+            commit.parents.extend(parent);
+            parent = Some(commit.write(odb).await.unwrap());
+        }
+        refs.update("refs/heads/main", parent.unwrap(), true)
+            .await
+            .unwrap();
+        refs.update_symbolic("HEAD", "refs/heads/main")
+            .await
+            .unwrap();
+        blobs
+    }
+
+    #[tokio::test]
+    async fn test_keep_every_nth_and_last() {
+        let dir = TempDir::new().unwrap();
+        let odb = ObjectDatabase::new(Arc::new(MockBackend::new()), 100);
+        let refs = RefDatabase::new(dir.path());
+        let blobs = build_history(&odb, &refs, 7).await;
+        let old_head = refs.resolve("HEAD").await.unwrap();
+
+        let rules = vec![ThinRule {
+            pattern: "renders/*.exr".into(),
+            keep_every: 3,
+            keep_last: 2,
+        }];
+        let thinner = MediaThinner::new(&odb, &refs, rules);
+        let plan = thinner.plan().await.unwrap();
+
+        // Versions 3 and 6 by keep_every, 6 and 7 by keep_last
+        assert_eq!(plan.paths.len(), 1);
+        assert_eq!(plan.paths[0].path, "renders/shot.exr");
+        let kept: Vec<Oid> = plan.paths[0]
+            .versions
+            .iter()
+            .filter(|v| v.keep)
+            .map(|v| v.oid)
+            .collect();
+        assert_eq!(kept, vec![blobs[2], blobs[5], blobs[6]]);
+        assert_eq!(plan.dropped_count(), 4);
+        assert_eq!(plan.commit_count(), 7);
+
+        let outcome = thinner.apply(&plan).await.unwrap();
+        assert_eq!(outcome.refs.len(), 1);
+        assert_eq!(outcome.refs[0].name, "refs/heads/main");
+        assert_eq!(outcome.refs[0].old_oid, old_head);
+
+        // Every commit survives; each now holds the nearest kept version
+        let mut seen = Vec::new();
+        let mut next = Some(refs.resolve("HEAD").await.unwrap());
+        while let Some(oid) = next {
+            let commit = Commit::read(&odb, &oid).await.unwrap();
+            let root = Tree::read(&odb, &commit.tree).await.unwrap();
+            let renders = root.get_entry("renders").unwrap().oid;
+            let renders = Tree::read(&odb, &renders).await.unwrap();
+            seen.push((
+                commit.message.clone(),
+                renders.get_entry("shot.exr").unwrap().oid,
+            ));
+            assert!(root.has_entry("notes.txt"));
+            next = commit.parents.first().copied();
+        }
+        seen.reverse();
+        let expected = [0, 1, 2, 3, 4, 5, 6].map(|n| blobs[[2, 2, 2, 2, 2, 5, 6][n]]);
+        assert_eq!(
+            seen.iter().map(|(_, oid)| *oid).collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(seen[0].0, "Render 1");
+        assert_eq!(seen[6].0, "Render 7");
+
+        // Only the kept versions remain in history
+        let replan = thinner.plan().await.unwrap();
+        let remaining: Vec<Oid> = replan.paths[0].versions.iter().map(|v| v.oid).collect();
+        assert_eq!(remaining, vec![blobs[2], blobs[5], blobs[6]]);
+    }
+}
@@ -15,7 +15,13 @@
 //!
 //! A Tree object contains references to files (blobs) and subdirectories (other trees).
 //! Trees are Git-compatible and provide the structure for snapshots in commits.
+//!
+//! Trees arrive from other repositories in packs and bundles, so parsing and
+//! walking them is bounded by [`TreeLimits`]: a corrupt or hostile tree with
+//! millions of entries or absurd nesting fails with an error instead of
+//! exhausting memory.
 
+use crate::format::ObjectFormat;
 use crate::{ObjectType, Oid};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -118,6 +124,69 @@ impl TreeEntry {
     }
 }
 
+/// Default for [`TreeLimits::max_depth`]
+pub const DEFAULT_MAX_TREE_DEPTH: usize = 1024;
+
+/// Default for [`TreeLimits::max_entries`]
+pub const DEFAULT_MAX_TREE_ENTRIES: usize = 1_000_000;
+
+/// Bounds on the trees a repository will parse and walk
+///
+/// The defaults are far beyond any real project; lower them to reject
+/// suspicious input sooner, or raise them for unusual layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeLimits {
+    /// Deepest directory nesting a walk descends into, counting the root tree as 0
+    pub max_depth: usize,
+    /// Most entries a single tree may hold
+    pub max_entries: usize,
+}
+
+impl Default for TreeLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_TREE_DEPTH,
+            max_entries: DEFAULT_MAX_TREE_ENTRIES,
+        }
+    }
+}
+
+impl TreeLimits {
+    /// Fail if a tree `depth` levels below the root may not be walked
+    pub fn check_depth(&self, depth: usize) -> anyhow::Result<()> {
+        if depth > self.max_depth {
+            anyhow::bail!(
+                "Tree is nested {} levels deep, more than the limit of {}",
+                depth,
+                self.max_depth
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns true if a serialized tree declares no more entries than allowed
+    pub fn admits(&self, data: &[u8]) -> bool {
+        self.check_entries(data).is_ok()
+    }
+
+    /// Fail if a serialized tree declares more entries than allowed
+    fn check_entries(&self, data: &[u8]) -> anyhow::Result<()> {
+        let count = if crate::git_format::is_tree(data) {
+            Some(crate::git_format::tree_entry_count(data))
+        } else {
+            encoded_entry_count(data)
+        };
+        match count {
+            Some(entries) if entries > self.max_entries as u64 => anyhow::bail!(
+                "Tree has {} entries, more than the limit of {}",
+                entries,
+                self.max_entries
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
 /// Tree object representing a directory snapshot
 ///
 /// Trees contain entries sorted by name, providing a canonical structure
@@ -210,18 +279,41 @@ impl Tree {
 
     /// Serialize tree to bytes
     pub fn serialize(&self) -> anyhow::Result<Vec<u8>> {
-        crate::format::serialize(self)
-            .map_err(|e| anyhow::anyhow!("Tree serialization failed: {}", e))
+        self.serialize_as(ObjectFormat::Native)
     }
 
-    /// Deserialize tree from bytes
+    /// Serialize tree to bytes in the given object format
+    pub fn serialize_as(&self, format: ObjectFormat) -> anyhow::Result<Vec<u8>> {
+        match format {
+            ObjectFormat::Native => crate::format::serialize(self)
+                .map_err(|e| anyhow::anyhow!("Tree serialization failed: {}", e)),
+            ObjectFormat::Git => Ok(crate::git_format::encode_tree(self)),
+        }
+    }
+
+    /// Deserialize tree from bytes, within the default [`TreeLimits`]
     pub fn deserialize(data: &[u8]) -> anyhow::Result<Self> {
-        crate::format::deserialize(data)
-            .map_err(|e| anyhow::anyhow!("Tree deserialization failed: {}", e))
+        Self::deserialize_with_limits(data, &TreeLimits::default())
+    }
+
+    /// Deserialize tree from bytes in either object format, rejecting trees
+    /// with more entries than `limits` allows
+    ///
+    /// The entry count is checked before any entry is decoded.
+    pub fn deserialize_with_limits(data: &[u8], limits: &TreeLimits) -> anyhow::Result<Self> {
+        limits.check_entries(data)?;
+        if crate::git_format::is_tree(data) {
+            crate::git_format::decode_tree(data)
+        } else {
+            crate::format::deserialize_exact(data)
+        }
+        .map_err(|e| anyhow::anyhow!("Tree deserialization failed: {}", e))
     }
 
     /// Write tree to object database and return its OID
     ///
+    /// The tree is encoded in the database's [`ObjectFormat`].
+    ///
     /// # Arguments
     ///
     /// * `odb` - Object database instance
@@ -230,12 +322,14 @@ impl Tree {
     ///
     /// The OID of the written tree
     pub async fn write(&self, odb: &crate::ObjectDatabase) -> anyhow::Result<Oid> {
-        let data = self.serialize()?;
+        let data = self.serialize_as(odb.object_format())?;
         odb.write(ObjectType::Tree, &data).await
     }
 
     /// Read tree from object database by OID
     ///
+    /// The tree must be within the database's [`TreeLimits`].
+    ///
     /// # Arguments
     ///
     /// * `odb` - Object database instance
@@ -246,7 +340,7 @@ impl Tree {
     /// The deserialized tree object
     pub async fn read(odb: &crate::ObjectDatabase, oid: &Oid) -> anyhow::Result<Self> {
         let data = odb.read(oid).await?;
-        Self::deserialize(&data)
+        Self::deserialize_with_limits(&data, odb.tree_limits())
     }
 
     /// Count files (blobs) in tree (not recursively)
@@ -266,6 +360,22 @@ impl Default for Tree {
     }
 }
 
+/// Number of entries a serialized tree declares
+///
+/// A tree is encoded as its entry map, which starts with the entry count as
+/// an unsigned LEB128 varint. Returns `None` if the prefix is malformed; the
+/// full decode then reports the error.
+fn encoded_entry_count(data: &[u8]) -> Option<u64> {
+    let mut count = 0u64;
+    for (i, byte) in data.iter().take(10).enumerate() {
+        count |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some(count);
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -466,4 +576,39 @@ mod tests {
         assert_eq!(loaded.dir_count(), 1);
         assert_eq!(loaded.len(), 4);
     }
+
+    #[tokio::test]
+    async fn test_tree_limits() {
+        use mediagit_storage::mock::MockBackend;
+        use std::sync::Arc;
+
+        let mut tree = Tree::new();
+        for i in 0..5 {
+            tree.add_entry(TreeEntry::new(
+                format!("frame_{:04}.exr", i),
+                FileMode::Regular,
+                Oid::hash(format!("frame {}", i).as_bytes()),
+            ));
+        }
+        let data = tree.serialize().unwrap();
+
+        let limits = TreeLimits {
+            max_depth: 3,
+            max_entries: 4,
+        };
+        assert!(!limits.admits(&data));
+        let err = Tree::deserialize_with_limits(&data, &limits).unwrap_err();
+        assert!(err.to_string().contains("more than the limit of 4"));
+        assert!(limits.check_depth(3).is_ok());
+        assert!(limits.check_depth(4).is_err());
+
+        // Reads through the database use its limits
+        let storage = Arc::new(MockBackend::new());
+        let odb = crate::ObjectDatabase::new(storage, 100);
+        let tree_oid = tree.write(&odb).await.unwrap();
+        assert_eq!(Tree::read(&odb, &tree_oid).await.unwrap(), tree);
+
+        let odb = odb.with_tree_limits(limits);
+        assert!(Tree::read(&odb, &tree_oid).await.is_err());
+    }
 }
@@ -13,10 +13,11 @@
 
 //! Integration tests for FSCK (File System Check) functionality
 
+use mediagit_compression::{CompressionAlgorithm, ObjectCategory};
 use mediagit_storage::{LocalBackend, StorageBackend};
 use mediagit_versioning::{
-    Commit, FsckChecker, FsckOptions, FsckRepair, IssueCategory, IssueSeverity, ObjectDatabase,
-    ObjectType, Oid, Ref, Signature,
+    ChunkStrategy, Commit, ContentIntegrity, FileMode, FsckChecker, FsckOptions, FsckRepair,
+    IssueCategory, IssueSeverity, ObjectDatabase, ObjectType, Oid, Ref, Signature, Tree, TreeEntry,
 };
 use std::sync::Arc;
 use tempfile::TempDir;
@@ -353,3 +354,282 @@ async fn test_fsck_max_objects_limit() {
     // Should only check 5 objects
     assert_eq!(report.objects_checked, 5);
 }
+
+#[tokio::test]
+async fn test_fsck_connectivity_only_skips_content_hashing() {
+    let (_temp_dir, storage, odb) = setup_test_repo().await;
+
+    let corrupted = odb.write(ObjectType::Blob, b"texture v1").await.unwrap();
+    let missing = odb.write(ObjectType::Blob, b"texture v2").await.unwrap();
+
+    let mut tree = Tree::new();
+    tree.add_entry(TreeEntry::new(
+        "corrupted.png".to_string(),
+        FileMode::Regular,
+        corrupted,
+    ));
+    tree.add_entry(TreeEntry::new(
+        "missing.png".to_string(),
+        FileMode::Regular,
+        missing,
+    ));
+    let tree_oid = tree.write(&odb).await.unwrap();
+
+    let commit = Commit::new(
+        tree_oid,
+        Signature::now("Test".to_string(), "test@example.com".to_string()),
+        Signature::now("Test".to_string(), "test@example.com".to_string()),
+        "Add textures".to_string(),
+    );
+    let commit_oid = commit.write(&odb).await.unwrap();
+
+    let main_ref = Ref::new_direct("refs/heads/main".to_string(), commit_oid);
+    let ref_data = mediagit_versioning::format::serialize(&main_ref).unwrap();
+    storage.put("refs/heads/main", &ref_data).await.unwrap();
+
+    // One blob's content is corrupted in place, the other is gone entirely
+    storage
+        .put(&corrupted.to_hex(), b"corrupted data")
+        .await
+        .unwrap();
+    storage.delete(&missing.to_hex()).await.unwrap();
+
+    let checker = FsckChecker::new(storage);
+    let report = checker
+        .check(FsckOptions::connectivity_only())
+        .await
+        .unwrap();
+
+    assert_eq!(report.content_integrity, ContentIntegrity::NotChecked);
+    assert_eq!(report.missing_objects, 1);
+    assert!(report
+        .issues
+        .iter()
+        .any(|i| i.category == IssueCategory::MissingObject && i.oid == Some(missing)));
+    assert_eq!(report.corrupted_objects, 0);
+    assert!(!report.issues.iter().any(|i| i.oid == Some(corrupted)));
+
+    // A content check does catch the corrupted blob
+    let report = checker.check(FsckOptions::quick()).await.unwrap();
+    assert_eq!(report.content_integrity, ContentIntegrity::Verified);
+    assert!(report
+        .issues
+        .iter()
+        .any(|i| i.category == IssueCategory::ChecksumMismatch && i.oid == Some(corrupted)));
+}
+
+#[tokio::test]
+async fn test_fsck_connectivity_only_checks_chunks_of_chunked_blobs() {
+    let (_temp_dir, storage, _) = setup_test_repo().await;
+    let odb = ObjectDatabase::with_optimizations(
+        storage.clone(),
+        100,
+        Some(ChunkStrategy::Fixed { size: 256 * 1024 }),
+        false,
+    );
+
+    let frames: Vec<u8> = (0..1024 * 1024u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect();
+    let clip = odb
+        .write_chunked(ObjectType::Blob, &frames, "clip.exr")
+        .await
+        .unwrap();
+    let manifest = odb.get_chunk_manifest(&clip).await.unwrap().unwrap();
+    assert!(manifest.chunks.len() > 1);
+
+    let mut tree = Tree::new();
+    tree.add_entry(TreeEntry::new(
+        "clip.exr".to_string(),
+        FileMode::Regular,
+        clip,
+    ));
+    let tree_oid = tree.write(&odb).await.unwrap();
+    let commit = Commit::new(
+        tree_oid,
+        Signature::now("Test".to_string(), "test@example.com".to_string()),
+        Signature::now("Test".to_string(), "test@example.com".to_string()),
+        "Add clip".to_string(),
+    );
+    let commit_oid = commit.write(&odb).await.unwrap();
+    let main_ref = Ref::new_direct("refs/heads/main".to_string(), commit_oid);
+    let ref_data = mediagit_versioning::format::serialize(&main_ref).unwrap();
+    storage.put("refs/heads/main", &ref_data).await.unwrap();
+
+    let checker = FsckChecker::new(storage.clone());
+    let report = checker
+        .check(FsckOptions::connectivity_only())
+        .await
+        .unwrap();
+    assert_eq!(report.missing_objects, 0);
+
+    // The manifest survives, but one of the chunks it lists is gone
+    let last = manifest.chunks.last().unwrap().id;
+    storage
+        .delete(&format!("chunks/{}", last.to_hex()))
+        .await
+        .unwrap();
+    let report = checker
+        .check(FsckOptions::connectivity_only())
+        .await
+        .unwrap();
+    assert_eq!(report.missing_objects, 1);
+    assert!(report
+        .issues
+        .iter()
+        .any(|i| i.category == IssueCategory::MissingObject && i.oid == Some(clip)));
+}
+
+#[tokio::test]
+async fn test_fsck_detects_object_type_mismatch() {
+    let (_temp_dir, storage, odb) = setup_test_repo().await;
+
+    let texture = odb.write(ObjectType::Blob, b"texture v1").await.unwrap();
+    let mut tree = Tree::new();
+    tree.add_entry(TreeEntry::new(
+        "texture.png".to_string(),
+        FileMode::Regular,
+        texture,
+    ));
+    let tree_oid = tree.write(&odb).await.unwrap();
+    let commit = Commit::new(
+        tree_oid,
+        Signature::now("Test".to_string(), "test@example.com".to_string()),
+        Signature::now("Test".to_string(), "test@example.com".to_string()),
+        "Add texture".to_string(),
+    );
+    let commit_oid = commit.write(&odb).await.unwrap();
+    let main_ref = Ref::new_direct("refs/heads/main".to_string(), commit_oid);
+    let ref_data = mediagit_versioning::format::serialize(&main_ref).unwrap();
+    storage.put("refs/heads/main", &ref_data).await.unwrap();
+
+    let checker = FsckChecker::new(storage.clone());
+    let report = checker
+        .check(FsckOptions::connectivity_only())
+        .await
+        .unwrap();
+    assert!(!report
+        .issues
+        .iter()
+        .any(|i| i.category == IssueCategory::TypeMismatch));
+
+    // A directory entry pointing at the blob
+    let mut tree = Tree::new();
+    tree.add_entry(TreeEntry::new(
+        "textures".to_string(),
+        FileMode::Directory,
+        texture,
+    ));
+    let bad_tree = tree.write(&odb).await.unwrap();
+    let commit = Commit::new(
+        bad_tree,
+        Signature::now("Test".to_string(), "test@example.com".to_string()),
+        Signature::now("Test".to_string(), "test@example.com".to_string()),
+        "Nest texture".to_string(),
+    );
+    let commit_oid = commit.write(&odb).await.unwrap();
+    let main_ref = Ref::new_direct("refs/heads/main".to_string(), commit_oid);
+    let ref_data = mediagit_versioning::format::serialize(&main_ref).unwrap();
+    storage.put("refs/heads/main", &ref_data).await.unwrap();
+
+    // The blob is recorded as one, so the walk notices
+    let report = checker
+        .check(FsckOptions::connectivity_only())
+        .await
+        .unwrap();
+    let mismatches: Vec<_> = report
+        .issues
+        .iter()
+        .filter(|i| i.category == IssueCategory::TypeMismatch)
+        .collect();
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0].oid, Some(texture));
+    assert!(!report
+        .issues
+        .iter()
+        .any(|i| i.category == IssueCategory::InvalidFormat));
+
+    // The type byte after the blob's 4-byte header magic is rewritten to
+    // claim it is a tree; its content does not decode as one
+    let mut stored = storage.get(&texture.to_hex()).await.unwrap();
+    stored[4] = ObjectType::Tree.to_u8();
+    storage.put(&texture.to_hex(), &stored).await.unwrap();
+    let report = checker.check(FsckOptions::quick()).await.unwrap();
+    assert!(report
+        .issues
+        .iter()
+        .any(|i| i.category == IssueCategory::TypeMismatch
+            && i.oid == Some(texture)
+            && i.message.contains("recorded as a tree")));
+}
+
+#[tokio::test]
+async fn test_fsck_compression_stats_by_category() {
+    let (_temp_dir, storage, _odb) = setup_test_repo().await;
+    let odb = ObjectDatabase::with_smart_compression(storage.clone(), 100);
+
+    // PNG is stored as-is, BMP and PDF go through zstd, text through brotli
+    let mut png = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+    png.extend((0..4096u32).map(|i| (i * 7919 % 251) as u8));
+    let mut bmp = b"BM".to_vec();
+    bmp.extend(std::iter::repeat_n(0x40, 8192));
+    let mut pdf = b"%PDF-1.7\n".to_vec();
+    pdf.extend(b"stream endstream ".repeat(256));
+    let text = b"shot 010 frame range 1001-1096\n".repeat(128);
+
+    let png_oid = odb
+        .write_with_path(ObjectType::Blob, &png, "hero.png")
+        .await
+        .unwrap();
+    let bmp_oid = odb
+        .write_with_path(ObjectType::Blob, &bmp, "mask.bmp")
+        .await
+        .unwrap();
+    odb.write_with_path(ObjectType::Blob, &pdf, "brief.pdf")
+        .await
+        .unwrap();
+    odb.write_with_path(ObjectType::Blob, &text, "shots.txt")
+        .await
+        .unwrap();
+
+    let checker = FsckChecker::new(storage);
+    let report = checker
+        .check(FsckOptions::quick().with_compression_stats(true))
+        .await
+        .unwrap();
+
+    assert!(!report.has_errors());
+    let stats = report.compression_stats.expect("stats requested");
+    assert_eq!(stats.objects.len(), 4);
+
+    let png_info = stats.objects.iter().find(|o| o.oid == png_oid).unwrap();
+    assert_eq!(png_info.algorithm, CompressionAlgorithm::None);
+    assert_eq!(png_info.logical_size, png.len() as u64);
+    let bmp_info = stats.objects.iter().find(|o| o.oid == bmp_oid).unwrap();
+    assert_eq!(bmp_info.algorithm, CompressionAlgorithm::Zstd);
+    assert!(bmp_info.stored_size < bmp_info.logical_size);
+
+    let images = &stats.by_category[&ObjectCategory::Image];
+    assert_eq!(images.objects, 2);
+    assert_eq!(images.algorithms[&CompressionAlgorithm::None], 1);
+    assert_eq!(images.algorithms[&CompressionAlgorithm::Zstd], 1);
+    assert_eq!(images.logical_bytes, (png.len() + bmp.len()) as u64);
+
+    let documents = &stats.by_category[&ObjectCategory::Document];
+    assert_eq!(documents.objects, 1);
+    assert_eq!(documents.algorithms[&CompressionAlgorithm::Zstd], 1);
+
+    let texts = &stats.by_category[&ObjectCategory::Text];
+    assert_eq!(texts.objects, 1);
+    assert_eq!(texts.algorithms[&CompressionAlgorithm::Brotli], 1);
+    assert!(texts.ratio() < 0.5);
+
+    assert_eq!(
+        stats.total_logical_bytes(),
+        (png.len() + bmp.len() + pdf.len() + text.len()) as u64
+    );
+
+    // Not collected unless asked for
+    let report = checker.check(FsckOptions::quick()).await.unwrap();
+    assert!(report.compression_stats.is_none());
+}
@@ -15,7 +15,7 @@
 
 use mediagit_storage::mock::MockBackend;
 use mediagit_storage::StorageBackend;
-use mediagit_versioning::{ObjectDatabase, ObjectType};
+use mediagit_versioning::{ObjectDatabase, ObjectType, Oid};
 use std::sync::Arc;
 
 #[tokio::test]
@@ -43,11 +43,11 @@ async fn test_zlib_compression_enabled() {
         data.len()
     );
 
-    // Stored data should have zlib header (0x78)
+    // After the 5-byte type header, stored data should have zlib header (0x78)
     assert_eq!(
-        stored_data[0], 0x78,
+        stored_data[5], 0x78,
         "Expected zlib header 0x78, got 0x{:02x}",
-        stored_data[0]
+        stored_data[5]
     );
 }
 
@@ -55,18 +55,10 @@ async fn test_zlib_compression_enabled() {
 async fn test_backward_compatibility_with_uncompressed_data() {
     let storage = Arc::new(MockBackend::new());
 
-    // First, write uncompressed data (simulating old version)
-    let odb_old = ObjectDatabase::without_compression(storage.clone(), 100);
+    // First, store uncompressed data without a type header (simulating old version)
     let data = b"old uncompressed data";
-    let oid = odb_old.write(ObjectType::Blob, data).await.unwrap();
-
-    // Verify it's stored uncompressed
-    let key = oid.to_hex();
-    let stored_data = storage.get(&key).await.unwrap();
-    assert_eq!(
-        stored_data, data,
-        "Old version should store data uncompressed"
-    );
+    let oid = Oid::hash(data);
+    storage.put(&oid.to_hex(), data).await.unwrap();
 
     // Now read with compression-enabled ODB (simulating new version)
     let odb_new = ObjectDatabase::new(storage, 100);